@@ -0,0 +1,294 @@
+/*!
+ * CLI Commands
+ *
+ * Each command returns its result as JSON plus a human-readable rendering;
+ * `--json` selects which one is printed.
+ */
+
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{bail, Context, Result};
+use serde_json::{json, Value};
+
+use rag_core::modules::kb::KbCreateConfig;
+use rag_core::modules::pipeline::{
+    pipeline_templates, AnnotateStepExecutor, EmbedStepExecutor, EvalStepExecutor, ExtractEntitiesStepExecutor, FetchStepExecutor, NormalizeStepExecutor, ParseStepExecutor,
+    PipelineRunner, PipelineSpec, RedactStepExecutor, SourceSandbox, StepData, SummarizeStepExecutor, TesseractOcrEngine, WhisperTranscriber,
+};
+use rag_core::KbService;
+
+use crate::context::CliContext;
+
+/// Default chunking for KBs created from the CLI (matches the desktop defaults)
+const DEFAULT_CHUNK_SIZE: usize = 512;
+const DEFAULT_CHUNK_OVERLAP: usize = 50;
+
+/// Command result in both output formats
+pub struct CommandOutput {
+    pub json: Value,
+    pub text: String,
+}
+
+impl CommandOutput {
+    pub fn print(&self, as_json: bool) -> Result<()> {
+        if as_json {
+            println!("{}", serde_json::to_string_pretty(&self.json)?);
+        } else {
+            println!("{}", self.text);
+        }
+        Ok(())
+    }
+}
+
+pub async fn kb_create(
+    ctx: &CliContext,
+    name: &str,
+    description: Option<String>,
+    embedder_model: String,
+    sources: Vec<String>,
+) -> Result<CommandOutput> {
+    let config = KbCreateConfig {
+        description,
+        embedder_model,
+        chunk_size: DEFAULT_CHUNK_SIZE,
+        chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+    };
+    let kb_id = ctx.kb_service.create_collection(name, config).await?;
+
+    let documents = if sources.is_empty() {
+        Vec::new()
+    } else {
+        ctx.kb_service.add_documents(&kb_id, sources).await?
+    };
+
+    Ok(CommandOutput {
+        text: format!("Created knowledge base {} ({}) with {} documents", kb_id, name, documents.len()),
+        json: json!({ "kb_id": kb_id, "name": name, "documents": documents }),
+    })
+}
+
+pub async fn kb_list(ctx: &CliContext) -> Result<CommandOutput> {
+    let mut collections = ctx.kb_service.list_collections(None).await?;
+    collections.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let text = if collections.is_empty() {
+        "No knowledge bases".to_string()
+    } else {
+        collections.iter()
+            .map(|kb| format!("{}\t{}\tv{}\t{}", kb.id, kb.name, kb.version, kb.status))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Ok(CommandOutput { json: serde_json::to_value(&collections)?, text })
+}
+
+pub async fn kb_search(ctx: &CliContext, kb_id: &str, query: &str, top_k: usize) -> Result<CommandOutput> {
+    let results = ctx.kb_service.hybrid_search(kb_id, query, top_k, None, None).await?;
+
+    let text = if results.is_empty() {
+        "No results".to_string()
+    } else {
+        results.iter().enumerate()
+            .map(|(i, r)| format!("[{}] {:.3} {} ({})\n    {}", i + 1, r.score, r.citation.title, r.citation.source_path, r.snippet))
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    Ok(CommandOutput { json: serde_json::to_value(&results)?, text })
+}
+
+pub async fn kb_export(ctx: &CliContext, kb_id: &str, output: &Path) -> Result<CommandOutput> {
+    let data = ctx.kb_service.export_kb(kb_id).await?;
+    tokio::fs::write(output, &data).await
+        .with_context(|| format!("Failed to write {}", output.display()))?;
+
+    Ok(CommandOutput {
+        text: format!("Exported {} to {} ({} bytes)", kb_id, output.display(), data.len()),
+        json: json!({ "kb_id": kb_id, "path": output, "size_bytes": data.len() }),
+    })
+}
+
+pub async fn kb_check(ctx: &CliContext, kb_id: &str, repair: bool) -> Result<CommandOutput> {
+    let report = ctx.kb_service.check_consistency(kb_id, repair).await?;
+
+    let text = if report.is_consistent() {
+        format!("{} is consistent", kb_id)
+    } else {
+        format!(
+            "{} {}: {} staged writes, orphan {:?}, missing {:?}, stale {:?}",
+            kb_id,
+            if report.repaired { "repaired" } else { "is inconsistent" },
+            report.staged_writes.len(),
+            report.orphan_documents,
+            report.missing_documents,
+            report.stale_documents,
+        )
+    };
+    Ok(CommandOutput { json: serde_json::to_value(&report)?, text })
+}
+
+pub async fn kb_rebuild_bm25(ctx: &CliContext, kb_id: &str) -> Result<CommandOutput> {
+    let report = ctx.kb_service.rebuild_bm25_index(kb_id, &mut |_| {}).await?;
+    Ok(CommandOutput {
+        text: format!("Rebuilt BM25 index of {}: {} chunks ({} dropped)", kb_id, report.chunks, report.removed),
+        json: serde_json::to_value(&report)?,
+    })
+}
+
+pub async fn kb_optimize(ctx: &CliContext, kb_id: &str) -> Result<CommandOutput> {
+    let report = ctx.kb_service.optimize_index(kb_id, &mut |_| {}).await?;
+    Ok(CommandOutput {
+        text: format!(
+            "Optimized {}: {} chunks ({} dropped), BM25 index {} -> {} bytes",
+            kb_id, report.chunks, report.removed, report.size_before, report.size_after
+        ),
+        json: serde_json::to_value(&report)?,
+    })
+}
+
+/// Run a pipeline spec; when it targets a KB, index the output and record a new version
+pub async fn pipeline_run(ctx: &CliContext, spec_path: &Path, sources: Vec<String>) -> Result<CommandOutput> {
+    let spec_json = tokio::fs::read_to_string(spec_path).await
+        .with_context(|| format!("Failed to read pipeline spec {}", spec_path.display()))?;
+    let spec: PipelineSpec = serde_json::from_str(&spec_json)
+        .with_context(|| format!("Invalid pipeline spec {}", spec_path.display()))?;
+
+    // Sources must lie under the spec's source roots, like files its parse steps read
+    let sandbox = SourceSandbox::new(&spec.source_roots);
+    let mut documents = Vec::with_capacity(sources.len());
+    for source in &sources {
+        documents.push(ctx.kb_service.load_source(&sandbox, source, None).await?);
+    }
+
+    let mut runner = PipelineRunner::new();
+    // The CLI has no vault; fetch steps take tokens from `tokenEnv`
+    runner.register(Arc::new(FetchStepExecutor::new().with_cursor_store(ctx.fetch_cursors.clone())));
+    let mut parse = ParseStepExecutor::new();
+    let ocr = TesseractOcrEngine::default();
+    if ocr.is_available() {
+        parse = parse.with_ocr(Arc::new(ocr));
+    }
+    let whisper = WhisperTranscriber::default();
+    if whisper.is_available() {
+        parse = parse.with_transcriber(Arc::new(whisper));
+    }
+    runner.register(Arc::new(parse));
+    runner.register(Arc::new(NormalizeStepExecutor::new()));
+    runner.register(Arc::new(RedactStepExecutor::new()));
+    runner.register(Arc::new(ExtractEntitiesStepExecutor::new(ctx.graph_service.clone())));
+    runner.register(Arc::new(AnnotateStepExecutor::new()));
+    runner.register(Arc::new(EmbedStepExecutor::new()));
+    runner.register(Arc::new(EvalStepExecutor::new(ctx.vector_service.clone())));
+    runner.register(Arc::new(SummarizeStepExecutor::new(ctx.vector_service.clone())));
+
+    let run_id = format!("run_{}", uuid::Uuid::new_v4().simple());
+    let output = runner.run(&spec, &run_id, StepData { documents, chunks: Vec::new() }).await;
+    if let Some(error) = &output.error {
+        bail!("Pipeline run {} failed: {}", run_id, error);
+    }
+
+    let version = match &spec.kb_id {
+        Some(kb_id) => {
+            ctx.kb_service.ingest_documents(kb_id, &output.data.documents).await?;
+            ctx.kb_service.create_version_from_run(kb_id, &spec, &run_id, &output).await?
+        }
+        None => None,
+    };
+    // Output is stored; the next run's fetch steps resume from here
+    ctx.fetch_cursors.commit_run(&run_id).await?;
+
+    let mut text = format!("Pipeline {} run {} completed ({} steps, {} documents)",
+        spec.id, run_id, output.report.steps.len(), output.data.documents.len());
+    if let Some(version) = &version {
+        text.push_str(&format!("\nCreated {} version {}", version.kb_id, version.version));
+    }
+    Ok(CommandOutput {
+        json: json!({
+            "run_id": run_id,
+            "pipeline_id": spec.id,
+            "report": output.report,
+            "version": version,
+        }),
+        text,
+    })
+}
+
+/// Bundled pipeline templates; `--json` prints their specs
+pub async fn pipeline_templates_list() -> Result<CommandOutput> {
+    let templates = pipeline_templates();
+    let text = templates.iter()
+        .map(|t| format!("{}\t{}\n  {}", t.id, t.name, t.description))
+        .collect::<Vec<_>>()
+        .join("\n");
+    Ok(CommandOutput { json: serde_json::to_value(&templates)?, text })
+}
+
+pub async fn eval_run(ctx: &CliContext, kb_id: &str, version: Option<i32>) -> Result<CommandOutput> {
+    let version = match version {
+        Some(version) => version,
+        None => ctx.kb_service.get_active_version(kb_id).await?
+            .map(|v| v.version)
+            .with_context(|| format!("KB {} has no active version; pass --version", kb_id))?,
+    };
+    let run = ctx.eval_service.run_regression(kb_id, version).await?;
+
+    let text = format!(
+        "{} v{}: {} (recall@{} {:.3}, MRR {:.3}, nDCG {:.3} over {} queries)",
+        kb_id, version,
+        if run.passed { "PASSED" } else { "FAILED" },
+        run.top_k, run.metrics.recall_at_k, run.metrics.mrr, run.metrics.ndcg_at_k, run.metrics.query_count,
+    );
+    Ok(CommandOutput { json: serde_json::to_value(&run)?, text })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_kb_survives_between_invocations() {
+        let temp_dir = TempDir::new().unwrap();
+        let doc = temp_dir.path().join("install.md");
+        std::fs::write(&doc, "Run the installer and restart the service.").unwrap();
+
+        let ctx = CliContext::open(temp_dir.path()).await.unwrap();
+        let created = kb_create(&ctx, "Docs", None, "test-model".to_string(), vec![doc.to_string_lossy().to_string()])
+            .await.unwrap();
+        let kb_id = created.json["kb_id"].as_str().unwrap().to_string();
+        assert_eq!(created.json["documents"].as_array().unwrap().len(), 1);
+        drop(ctx);
+
+        // A second invocation sees the KB and can export it
+        let ctx = CliContext::open(temp_dir.path()).await.unwrap();
+        let listed = kb_list(&ctx).await.unwrap();
+        assert_eq!(listed.json[0]["id"], kb_id.as_str());
+        assert!(listed.text.contains("Docs"));
+
+        let found = kb_search(&ctx, &kb_id, "installer", 3).await.unwrap();
+        assert!(found.json.is_array());
+
+        let pack = temp_dir.path().join("docs.ragkb");
+        kb_export(&ctx, &kb_id, &pack).await.unwrap();
+        assert!(pack.exists());
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_run_without_kb() {
+        let temp_dir = TempDir::new().unwrap();
+        let doc = temp_dir.path().join("notes.txt");
+        std::fs::write(&doc, "Some   notes\r\nwith  whitespace").unwrap();
+        let spec = temp_dir.path().join("pipeline.json");
+        let mut spec_json = json!({"id": "p1", "name": "Normalize", "kb_id": null, "steps": [{"step_type": "normalize"}]});
+        std::fs::write(&spec, spec_json.to_string()).unwrap();
+
+        let ctx = CliContext::open(temp_dir.path()).await.unwrap();
+        // No source roots declared, so nothing can be read
+        assert!(pipeline_run(&ctx, &spec, vec![doc.to_string_lossy().to_string()]).await.is_err());
+
+        spec_json["source_roots"] = json!([temp_dir.path()]);
+        std::fs::write(&spec, spec_json.to_string()).unwrap();
+        let output = pipeline_run(&ctx, &spec, vec![doc.to_string_lossy().to_string()]).await.unwrap();
+        assert_eq!(output.json["report"]["steps"].as_array().unwrap().len(), 1);
+        assert!(output.json["version"].is_null());
+    }
+}
@@ -0,0 +1,56 @@
+/*!
+ * CLI Service Context
+ *
+ * Opens the same services the desktop Manager composes, rooted at the CLI's
+ * data directory.
+ */
+
+use std::path::Path;
+use std::sync::Arc;
+use anyhow::{Context, Result};
+use tracing::debug;
+
+use rag_core::modules::eval::{EvalConfig, EvalService};
+use rag_core::modules::graph::GraphService;
+use rag_core::modules::pipeline::FetchCursorStore;
+use rag_core::{KbConfig, KbServiceImpl, SqlConfig, SqlService, StateManager, VectorDbConfig, VectorDbService};
+
+/// Services shared by all commands
+pub struct CliContext {
+    pub kb_service: Arc<KbServiceImpl>,
+    pub vector_service: Arc<VectorDbService>,
+    pub graph_service: Arc<GraphService>,
+    pub eval_service: EvalService,
+    pub fetch_cursors: Arc<FetchCursorStore>,
+}
+
+impl CliContext {
+    /// Open databases under `data_dir` (created if missing) and load persisted KBs
+    pub async fn open(data_dir: &Path) -> Result<Self> {
+        tokio::fs::create_dir_all(data_dir).await
+            .with_context(|| format!("Failed to create data directory {}", data_dir.display()))?;
+
+        let sql_service = Arc::new(SqlService::new(SqlConfig::new_mvp(data_dir.join("rag_studio.db"))).await?);
+        sql_service.run_migrations().await?;
+
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig {
+            data_dir: data_dir.join("data").join("vector_db"),
+            ..Default::default()
+        }).await?.with_sql_service(sql_service.clone()));
+
+        let graph_service = Arc::new(GraphService::new(sql_service.clone()));
+        let kb_service = Arc::new(KbServiceImpl::new(
+            sql_service.clone(),
+            vector_service.clone(),
+            Arc::new(StateManager::new()),
+            KbConfig::mvp(),
+        ).with_graph_service(graph_service.clone()));
+        let loaded = kb_service.load_collections().await?;
+        debug!("Loaded {} knowledge bases from {}", loaded, data_dir.display());
+
+        let fetch_cursors = Arc::new(FetchCursorStore::new(sql_service.clone()));
+        let eval_service = EvalService::new(sql_service, kb_service.clone(), EvalConfig::default());
+
+        Ok(Self { kb_service, vector_service, graph_service, eval_service, fetch_cursors })
+    }
+}
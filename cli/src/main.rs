@@ -0,0 +1,184 @@
+/*!
+ * RAG CLI - Headless command-line interface for RAG Studio
+ *
+ * Runs KB, pipeline and evaluation operations against the same databases as
+ * the desktop app without launching the Tauri UI (CI ingestion jobs, scripted
+ * workflows on servers).
+ * MVP: operates on the data directory directly; the desktop app picks up the
+ * changes on its next start.
+ */
+
+use std::path::PathBuf;
+use clap::{Parser, Subcommand};
+use anyhow::Result;
+
+mod commands;
+mod context;
+
+use commands::CommandOutput;
+use context::CliContext;
+
+/// RAG Studio CLI arguments
+#[derive(Parser, Debug)]
+#[command(author, version, about, long_about = None)]
+struct Args {
+    /// Data directory holding rag_studio.db and data/vector_db
+    #[arg(long, global = true, default_value = ".")]
+    data_dir: PathBuf,
+
+    /// Print results as JSON
+    #[arg(long, global = true)]
+    json: bool,
+
+    /// Enable debug logging
+    #[arg(short, long, global = true)]
+    debug: bool,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Knowledge base operations
+    Kb {
+        #[command(subcommand)]
+        command: KbCommand,
+    },
+    /// Pipeline operations
+    Pipeline {
+        #[command(subcommand)]
+        command: PipelineCommand,
+    },
+    /// Evaluation operations
+    Eval {
+        #[command(subcommand)]
+        command: EvalCommand,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum KbCommand {
+    /// Create a knowledge base, optionally ingesting local files
+    Create {
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        description: Option<String>,
+        #[arg(long, default_value = "sentence-transformers/all-MiniLM-L6-v2")]
+        embedder_model: String,
+        /// Local file to ingest (repeatable)
+        #[arg(long = "source")]
+        sources: Vec<String>,
+    },
+    /// List knowledge bases
+    List,
+    /// Search a knowledge base
+    Search {
+        kb_id: String,
+        query: String,
+        #[arg(long, default_value_t = 5)]
+        top_k: usize,
+    },
+    /// Export a knowledge base to a .ragkb archive
+    Export {
+        kb_id: String,
+        #[arg(short, long)]
+        output: PathBuf,
+    },
+    /// Check that SQL metadata and the vector store agree
+    Check {
+        kb_id: String,
+        /// Purge diverged documents and settle interrupted writes
+        #[arg(long)]
+        repair: bool,
+    },
+    /// Rebuild the BM25 index from stored chunks
+    RebuildBm25 {
+        kb_id: String,
+    },
+    /// Compact the KB's indexes (BM25, FTS5 segments, LanceDB)
+    Optimize {
+        kb_id: String,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum PipelineCommand {
+    /// Run a pipeline spec (JSON) over local files
+    Run {
+        #[arg(long)]
+        spec: PathBuf,
+        /// Local file under the spec's `source_roots` to feed into the pipeline (repeatable)
+        #[arg(long = "source")]
+        sources: Vec<String>,
+    },
+    /// List bundled pipeline templates (Confluence, Notion, ...)
+    Templates,
+}
+
+#[derive(Subcommand, Debug)]
+enum EvalCommand {
+    /// Run the golden-set regression for a KB version
+    Run {
+        kb_id: String,
+        /// Version to evaluate (active version when omitted)
+        #[arg(long)]
+        version: Option<i32>,
+    },
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+
+    // Logs go to stderr so stdout stays scriptable
+    let level = if args.debug { "debug" } else { "warn" };
+    tracing_subscriber::fmt()
+        .with_env_filter(format!("rag_cli={level},rag_core={level}"))
+        .with_writer(std::io::stderr)
+        .init();
+
+    let ctx = CliContext::open(&args.data_dir).await?;
+    let output = run(&ctx, args.command).await?;
+    output.print(args.json)?;
+    Ok(())
+}
+
+async fn run(ctx: &CliContext, command: Command) -> Result<CommandOutput> {
+    match command {
+        Command::Kb { command } => match command {
+            KbCommand::Create { name, description, embedder_model, sources } => {
+                commands::kb_create(ctx, &name, description, embedder_model, sources).await
+            }
+            KbCommand::List => commands::kb_list(ctx).await,
+            KbCommand::Search { kb_id, query, top_k } => commands::kb_search(ctx, &kb_id, &query, top_k).await,
+            KbCommand::Export { kb_id, output } => commands::kb_export(ctx, &kb_id, &output).await,
+            KbCommand::Check { kb_id, repair } => commands::kb_check(ctx, &kb_id, repair).await,
+            KbCommand::RebuildBm25 { kb_id } => commands::kb_rebuild_bm25(ctx, &kb_id).await,
+            KbCommand::Optimize { kb_id } => commands::kb_optimize(ctx, &kb_id).await,
+        },
+        Command::Pipeline { command } => match command {
+            PipelineCommand::Run { spec, sources } => commands::pipeline_run(ctx, &spec, sources).await,
+            PipelineCommand::Templates => commands::pipeline_templates_list().await,
+        },
+        Command::Eval { command } => match command {
+            EvalCommand::Run { kb_id, version } => commands::eval_run(ctx, &kb_id, version).await,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_global_flags_after_subcommand() {
+        let args = Args::try_parse_from([
+            "rag-cli", "kb", "search", "kb_1", "install steps", "--top-k", "3", "--json", "--data-dir", "/tmp/rag",
+        ]).unwrap();
+        assert!(args.json);
+        assert_eq!(args.data_dir, PathBuf::from("/tmp/rag"));
+        assert!(matches!(args.command, Command::Kb { command: KbCommand::Search { top_k: 3, .. } }));
+    }
+}
@@ -0,0 +1,9 @@
+-- Rollback golden-dataset regression evaluation
+
+DROP INDEX IF EXISTS idx_eval_runs_kb_version;
+DROP INDEX IF EXISTS idx_eval_golden_kb_id;
+
+DROP TABLE IF EXISTS eval_runs;
+DROP TABLE IF EXISTS eval_golden_queries;
+
+DELETE FROM schema_migrations WHERE version = 2;
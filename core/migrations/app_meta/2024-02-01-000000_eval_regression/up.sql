@@ -0,0 +1,30 @@
+-- Golden-dataset regression evaluation
+-- KBs are tracked in application state for MVP, so kb_id is not a foreign key yet
+
+-- Golden question -> relevant chunk mappings per KB
+CREATE TABLE eval_golden_queries (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    question TEXT NOT NULL,
+    relevant_chunk_ids JSON NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+-- Evaluation runs per KB version with deltas against the previous version
+CREATE TABLE eval_runs (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    kb_version INTEGER NOT NULL,
+    baseline_version INTEGER,
+    top_k INTEGER NOT NULL,
+    query_count INTEGER NOT NULL,
+    metrics JSON NOT NULL,
+    deltas JSON,
+    passed BOOLEAN NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_eval_golden_kb_id ON eval_golden_queries(kb_id);
+CREATE INDEX idx_eval_runs_kb_version ON eval_runs(kb_id, kb_version);
+
+INSERT INTO schema_migrations (version, description) VALUES (2, 'Golden-dataset regression evaluation');
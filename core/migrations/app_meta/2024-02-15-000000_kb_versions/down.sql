@@ -0,0 +1,8 @@
+-- Rollback immutable KB versions
+
+DROP INDEX IF EXISTS idx_kb_versions_status;
+DROP INDEX IF EXISTS idx_kb_versions_kb_id;
+
+DROP TABLE IF EXISTS kb_versions;
+
+DELETE FROM schema_migrations WHERE version = 3;
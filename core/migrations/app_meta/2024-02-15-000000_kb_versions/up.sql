@@ -0,0 +1,19 @@
+-- Immutable KB versions created by successful pipeline runs
+CREATE TABLE kb_versions (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    version INTEGER NOT NULL,
+    generation_id BIGINT NOT NULL,
+    pipeline_run_id TEXT,
+    status TEXT NOT NULL CHECK (status IN ('ready', 'active', 'archived')),
+    manifest JSON NOT NULL,
+    stats JSON NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    activated_at DATETIME,
+    UNIQUE(kb_id, version)
+);
+
+CREATE INDEX idx_kb_versions_kb_id ON kb_versions(kb_id);
+CREATE INDEX idx_kb_versions_status ON kb_versions(kb_id, status);
+
+INSERT INTO schema_migrations (version, description) VALUES (3, 'Immutable KB versions');
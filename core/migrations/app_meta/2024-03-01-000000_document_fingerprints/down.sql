@@ -0,0 +1,7 @@
+-- Rollback document fingerprints
+
+DROP INDEX IF EXISTS idx_document_fingerprints_hash;
+
+DROP TABLE IF EXISTS document_fingerprints;
+
+DELETE FROM schema_migrations WHERE version = 4;
@@ -0,0 +1,14 @@
+-- Per-document content fingerprints for incremental reindexing
+CREATE TABLE document_fingerprints (
+    kb_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    indexed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kb_id, source_path)
+);
+
+CREATE INDEX idx_document_fingerprints_hash ON document_fingerprints(content_hash);
+
+INSERT INTO schema_migrations (version, description) VALUES (4, 'Document fingerprints for incremental reindex');
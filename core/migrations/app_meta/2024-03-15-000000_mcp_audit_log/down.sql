@@ -0,0 +1,10 @@
+-- Rollback MCP audit log
+
+DROP TRIGGER IF EXISTS mcp_audit_log_no_delete;
+DROP TRIGGER IF EXISTS mcp_audit_log_no_update;
+DROP INDEX IF EXISTS idx_mcp_audit_log_tool;
+DROP INDEX IF EXISTS idx_mcp_audit_log_created_at;
+
+DROP TABLE IF EXISTS mcp_audit_log;
+
+DELETE FROM schema_migrations WHERE version = 5;
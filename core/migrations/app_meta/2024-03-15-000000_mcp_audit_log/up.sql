@@ -0,0 +1,28 @@
+-- Append-only audit log of MCP tool invocations
+CREATE TABLE mcp_audit_log (
+    id TEXT PRIMARY KEY,
+    tool_name TEXT NOT NULL,
+    arguments JSON NOT NULL,          -- Sanitized arguments
+    caller TEXT NOT NULL,             -- MCP client/session
+    latency_ms INTEGER NOT NULL,
+    result_bytes INTEGER NOT NULL,
+    success BOOLEAN NOT NULL,
+    error TEXT,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_mcp_audit_log_created_at ON mcp_audit_log(created_at);
+CREATE INDEX idx_mcp_audit_log_tool ON mcp_audit_log(tool_name, created_at);
+
+-- Entries can never be changed or removed
+CREATE TRIGGER mcp_audit_log_no_update BEFORE UPDATE ON mcp_audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'mcp_audit_log is append-only');
+END;
+
+CREATE TRIGGER mcp_audit_log_no_delete BEFORE DELETE ON mcp_audit_log
+BEGIN
+    SELECT RAISE(ABORT, 'mcp_audit_log is append-only');
+END;
+
+INSERT INTO schema_migrations (version, description) VALUES (5, 'MCP tool invocation audit log');
@@ -0,0 +1,9 @@
+-- Rollback conversation memory
+
+DROP INDEX IF EXISTS idx_conversation_summaries_expires_at;
+DROP INDEX IF EXISTS idx_conversation_messages_expires_at;
+
+DROP TABLE IF EXISTS conversation_summaries;
+DROP TABLE IF EXISTS conversation_messages;
+
+DELETE FROM schema_migrations WHERE version = 6;
@@ -0,0 +1,27 @@
+-- Per-session conversation memory for conversational RAG (expires after a TTL)
+CREATE TABLE conversation_messages (
+    id TEXT PRIMARY KEY,
+    session_id TEXT NOT NULL,
+    seq INTEGER NOT NULL,             -- Order within the session
+    role TEXT NOT NULL,               -- user | assistant | system
+    content TEXT NOT NULL,
+    context_summary TEXT,             -- Sources retrieved for this turn
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL,
+    UNIQUE(session_id, seq)
+);
+
+CREATE INDEX idx_conversation_messages_expires_at ON conversation_messages(expires_at);
+
+-- Rolling summary of messages folded out of the verbatim history
+CREATE TABLE conversation_summaries (
+    session_id TEXT PRIMARY KEY,
+    summary TEXT NOT NULL,
+    summarized_messages INTEGER NOT NULL,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    expires_at DATETIME NOT NULL
+);
+
+CREATE INDEX idx_conversation_summaries_expires_at ON conversation_summaries(expires_at);
+
+INSERT INTO schema_migrations (version, description) VALUES (6, 'Conversation memory');
@@ -0,0 +1,5 @@
+-- Rollback FTS5 chunk index
+
+DROP TABLE IF EXISTS chunks_fts;
+
+DELETE FROM schema_migrations WHERE version = 7;
@@ -0,0 +1,13 @@
+-- FTS5 lexical index over chunk text, the fallback BM25 backend used while a
+-- KB's BM25 index is missing or being rebuilt. Moves to the per-KB content
+-- database in split mode, like the other content tables.
+CREATE VIRTUAL TABLE chunks_fts USING fts5(
+    chunk_id UNINDEXED,
+    document_id UNINDEXED,
+    kb_id UNINDEXED,
+    content,
+    metadata UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
+
+INSERT INTO schema_migrations (version, description) VALUES (7, 'FTS5 chunk index');
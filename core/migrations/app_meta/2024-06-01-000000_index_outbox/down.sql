@@ -0,0 +1,7 @@
+-- Rollback index write outbox
+
+DROP INDEX IF EXISTS idx_index_outbox_status;
+DROP INDEX IF EXISTS idx_index_outbox_document;
+DROP TABLE IF EXISTS index_outbox;
+
+DELETE FROM schema_migrations WHERE version = 8;
@@ -0,0 +1,20 @@
+-- Write-ahead outbox for document indexing: a write is staged here before its
+-- vectors are written and committed in the same transaction as the document's
+-- fingerprint, so interrupted writes can be found and repaired.
+-- Moves to the per-KB content database in split mode.
+CREATE TABLE index_outbox (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('upsert', 'delete')),
+    generation BIGINT NOT NULL,       -- Write generation stamped on the staged vectors
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK (status IN ('staged', 'committed', 'rolled_back')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    committed_at DATETIME
+);
+
+CREATE INDEX idx_index_outbox_document ON index_outbox(kb_id, document_id);
+CREATE INDEX idx_index_outbox_status ON index_outbox(kb_id, status);
+
+INSERT INTO schema_migrations (version, description) VALUES (8, 'Index write outbox');
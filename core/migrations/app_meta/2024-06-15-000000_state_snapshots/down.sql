@@ -0,0 +1,5 @@
+-- Rollback app state snapshots
+
+DROP TABLE IF EXISTS state_snapshots;
+
+DELETE FROM schema_migrations WHERE version = 9;
@@ -0,0 +1,12 @@
+-- Versioned snapshots of persistent app state (tools, settings, workspaces,
+-- prompts, flows), written after mutations and rehydrated on startup.
+-- Only the newest snapshots are kept.
+CREATE TABLE state_snapshots (
+    version INTEGER PRIMARY KEY AUTOINCREMENT,
+    revision BIGINT NOT NULL,         -- StateManager revision the snapshot was taken at
+    format INTEGER NOT NULL,          -- Snapshot payload format
+    state TEXT NOT NULL,              -- JSON PersistedState
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+INSERT INTO schema_migrations (version, description) VALUES (9, 'App state snapshots');
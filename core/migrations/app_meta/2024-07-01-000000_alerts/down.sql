@@ -0,0 +1,5 @@
+-- Rollback alert inbox
+
+DROP TABLE IF EXISTS alerts;
+
+DELETE FROM schema_migrations WHERE version = 10;
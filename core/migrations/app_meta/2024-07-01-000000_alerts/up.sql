@@ -0,0 +1,19 @@
+-- In-app alert inbox (pipeline failures, disk quota, model downloads,
+-- worker crashes, eval regressions). Desktop notifications are sent
+-- alongside; the inbox is what survives a restart.
+CREATE TABLE alerts (
+    id TEXT PRIMARY KEY,
+    category TEXT NOT NULL,
+    severity TEXT NOT NULL,           -- info | warning | critical
+    title TEXT NOT NULL,
+    message TEXT NOT NULL,
+    source TEXT,                      -- What raised it, e.g. a KB or model id
+    metadata JSON NOT NULL,
+    read BOOLEAN NOT NULL DEFAULT FALSE,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_alerts_created_at ON alerts(created_at);
+CREATE INDEX idx_alerts_unread ON alerts(read, created_at);
+
+INSERT INTO schema_migrations (version, description) VALUES (10, 'Alert inbox');
@@ -0,0 +1,9 @@
+-- Rollback knowledge graph
+
+DROP INDEX IF EXISTS idx_graph_relations_target;
+DROP INDEX IF EXISTS idx_graph_relations_source;
+DROP INDEX IF EXISTS idx_graph_entities_entity;
+DROP TABLE IF EXISTS graph_relations;
+DROP TABLE IF EXISTS graph_entities;
+
+DELETE FROM schema_migrations WHERE version = 11;
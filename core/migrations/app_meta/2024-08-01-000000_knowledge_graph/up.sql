@@ -0,0 +1,29 @@
+-- Knowledge graph extracted by the extract_entities pipeline step: entities
+-- and co-occurrence relations per document, so re-extracting or removing a
+-- document replaces only its rows. KB-wide counts are sums over documents.
+-- Moves to the per-KB content database in split mode.
+CREATE TABLE graph_entities (
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    entity_id TEXT NOT NULL,          -- Normalized name (lowercase, single spaces)
+    name TEXT NOT NULL,               -- Most frequent surface form in the document
+    entity_type TEXT NOT NULL,
+    mentions INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (kb_id, document_id, entity_id)
+);
+
+CREATE TABLE graph_relations (
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    source_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    relation TEXT NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 1,  -- Sentences the relation was seen in
+    PRIMARY KEY (kb_id, document_id, source_id, target_id, relation)
+);
+
+CREATE INDEX idx_graph_entities_entity ON graph_entities(kb_id, entity_id);
+CREATE INDEX idx_graph_relations_source ON graph_relations(kb_id, source_id);
+CREATE INDEX idx_graph_relations_target ON graph_relations(kb_id, target_id);
+
+INSERT INTO schema_migrations (version, description) VALUES (11, 'Knowledge graph');
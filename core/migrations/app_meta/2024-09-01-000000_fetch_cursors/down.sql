@@ -0,0 +1,6 @@
+-- Rollback fetch cursors
+
+DROP INDEX IF EXISTS idx_fetch_cursors_pending_run;
+DROP TABLE IF EXISTS fetch_cursors;
+
+DELETE FROM schema_migrations WHERE version = 12;
@@ -0,0 +1,17 @@
+-- Incremental sync cursors of pipeline fetch steps (updated-since timestamps
+-- from Confluence, Notion, ...). A run records its cursor as pending; it
+-- becomes the one the next run starts from only once the run has been
+-- ingested, so a failed run fetches the same changes again.
+CREATE TABLE fetch_cursors (
+    pipeline_id TEXT NOT NULL,
+    cursor_key TEXT NOT NULL,         -- Source type plus a hash of the step config
+    cursor TEXT,                      -- Committed; NULL until a run completes
+    pending_cursor TEXT,
+    pending_run_id TEXT,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (pipeline_id, cursor_key)
+);
+
+CREATE INDEX idx_fetch_cursors_pending_run ON fetch_cursors(pending_run_id);
+
+INSERT INTO schema_migrations (version, description) VALUES (12, 'Fetch cursors');
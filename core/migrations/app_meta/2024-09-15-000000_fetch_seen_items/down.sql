@@ -0,0 +1,6 @@
+-- Rollback fetch seen items
+
+DROP INDEX IF EXISTS idx_fetch_seen_items_pending_run;
+DROP TABLE IF EXISTS fetch_seen_items;
+
+DELETE FROM schema_migrations WHERE version = 13;
@@ -0,0 +1,18 @@
+-- Items a fetch step has already ingested, for sources without a reliable
+-- updated-since query (feed GUIDs, sitemap URLs). `version` is what changes
+-- when the item does (its lastmod or a content hash); like cursors, a run's
+-- versions are pending until the run has been ingested.
+CREATE TABLE fetch_seen_items (
+    pipeline_id TEXT NOT NULL,
+    cursor_key TEXT NOT NULL,         -- As in fetch_cursors
+    item_key TEXT NOT NULL,           -- GUID, entry id or URL
+    version TEXT,                     -- Committed; NULL until a run completes
+    pending_version TEXT,
+    pending_run_id TEXT,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (pipeline_id, cursor_key, item_key)
+);
+
+CREATE INDEX idx_fetch_seen_items_pending_run ON fetch_seen_items(pending_run_id);
+
+INSERT INTO schema_migrations (version, description) VALUES (13, 'Fetch seen items');
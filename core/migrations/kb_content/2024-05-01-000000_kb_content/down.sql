@@ -0,0 +1,10 @@
+-- Rollback per-KB content database
+
+DROP INDEX IF EXISTS idx_document_fingerprints_hash;
+DROP INDEX IF EXISTS idx_chunks_hash;
+DROP INDEX IF EXISTS idx_chunks_document_id;
+DROP INDEX IF EXISTS idx_documents_hash;
+
+DROP TABLE IF EXISTS document_fingerprints;
+DROP TABLE IF EXISTS document_chunks;
+DROP TABLE IF EXISTS documents;
@@ -0,0 +1,47 @@
+-- Per-KB content database (split layout): documents, chunks and fingerprints
+-- of one knowledge base. Column order matches app_meta so the migration from
+-- the single-file layout can copy rows with SELECT *. No foreign key to
+-- knowledge_bases, which lives in app_meta.db.
+
+CREATE TABLE documents (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    title TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    license_info TEXT,
+    metadata JSON,
+    chunk_count INTEGER DEFAULT 0,
+    size_bytes INTEGER DEFAULT 0,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE TABLE document_chunks (
+    id TEXT PRIMARY KEY,
+    document_id TEXT NOT NULL,
+    kb_id TEXT NOT NULL,
+    chunk_index INTEGER NOT NULL,
+    content TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    token_count INTEGER,
+    metadata JSON,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    FOREIGN KEY (document_id) REFERENCES documents(id) ON DELETE CASCADE,
+    UNIQUE(document_id, chunk_index)
+);
+
+CREATE TABLE document_fingerprints (
+    kb_id TEXT NOT NULL,
+    source_path TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    content_hash TEXT NOT NULL,
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    indexed_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    PRIMARY KEY (kb_id, source_path)
+);
+
+CREATE INDEX idx_documents_hash ON documents(content_hash);
+CREATE INDEX idx_chunks_document_id ON document_chunks(document_id);
+CREATE INDEX idx_chunks_hash ON document_chunks(content_hash);
+CREATE INDEX idx_document_fingerprints_hash ON document_fingerprints(content_hash);
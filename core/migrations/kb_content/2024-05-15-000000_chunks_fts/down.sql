@@ -0,0 +1,3 @@
+-- Rollback FTS5 chunk index
+
+DROP TABLE IF EXISTS chunks_fts;
@@ -0,0 +1,10 @@
+-- FTS5 lexical index over the KB's chunk text (fallback BM25 backend).
+-- Column order matches app_meta so rows copy over with SELECT *.
+CREATE VIRTUAL TABLE chunks_fts USING fts5(
+    chunk_id UNINDEXED,
+    document_id UNINDEXED,
+    kb_id UNINDEXED,
+    content,
+    metadata UNINDEXED,
+    tokenize = 'unicode61 remove_diacritics 2'
+);
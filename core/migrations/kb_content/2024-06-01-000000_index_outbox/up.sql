@@ -0,0 +1,16 @@
+-- Write-ahead outbox for document indexing (see app_meta migration).
+-- Column order matches app_meta so rows copy over with SELECT *.
+CREATE TABLE index_outbox (
+    id TEXT PRIMARY KEY,
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    operation TEXT NOT NULL CHECK (operation IN ('upsert', 'delete')),
+    generation BIGINT NOT NULL,       -- Write generation stamped on the staged vectors
+    chunk_count INTEGER NOT NULL DEFAULT 0,
+    status TEXT NOT NULL CHECK (status IN ('staged', 'committed', 'rolled_back')),
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    committed_at DATETIME
+);
+
+CREATE INDEX idx_index_outbox_document ON index_outbox(kb_id, document_id);
+CREATE INDEX idx_index_outbox_status ON index_outbox(kb_id, status);
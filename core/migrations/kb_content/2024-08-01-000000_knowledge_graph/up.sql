@@ -0,0 +1,25 @@
+-- Knowledge graph entities and relations (see app_meta migration).
+-- Column order matches app_meta so rows copy over with SELECT *.
+CREATE TABLE graph_entities (
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    name TEXT NOT NULL,
+    entity_type TEXT NOT NULL,
+    mentions INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (kb_id, document_id, entity_id)
+);
+
+CREATE TABLE graph_relations (
+    kb_id TEXT NOT NULL,
+    document_id TEXT NOT NULL,
+    source_id TEXT NOT NULL,
+    target_id TEXT NOT NULL,
+    relation TEXT NOT NULL,
+    weight INTEGER NOT NULL DEFAULT 1,
+    PRIMARY KEY (kb_id, document_id, source_id, target_id, relation)
+);
+
+CREATE INDEX idx_graph_entities_entity ON graph_entities(kb_id, entity_id);
+CREATE INDEX idx_graph_relations_source ON graph_relations(kb_id, source_id);
+CREATE INDEX idx_graph_relations_target ON graph_relations(kb_id, target_id);
@@ -24,15 +24,34 @@ pub mod utils;
 pub use modules::kb::{KbService, KbServiceImpl, KbError, KbConfig, DocumentInfo, KbStats, KbInfo};
 
 // Re-export commonly used infrastructure services
-pub use services::sql::{SqlService, SqlConfig, SqlError};
+pub use services::sql::{SqlService, SqlConfig, SqlError, KbDatabaseHealth, SplitMigrationReport};
+pub use services::storage::{StorageService, StorageConfig, StorageError, PackManifest, PackVerification};
+pub use services::pack_signing::{PackSigner, PackSignature, TrustedSigner, SigningError};
+pub use services::generation::{GenerationService, GenerationConfig, GenerationParams, GenerationError};
+pub use services::llm::{LlmService, LlmProvider, LlmProviderConfig, LlmError};
+pub use services::cache::{CacheService, CacheConfig, CacheStats, CacheError};
+pub use services::model::{ModelService, ModelConfig, ModelManifest, ModelError, DownloadProgress, ModelKind, KbModelProfile, ModelRecommendation, ModelStorageStats, ModelCleanupReport};
+pub use services::logging::{LoggingService, LoggingConfig, LoggingError, LogEntry, LogFilter, LogRange, LogLevel, LogSubsystem};
+pub use services::metrics::{MetricsService, MetricsSnapshot, MetricsSummary};
+pub use services::telemetry::{TelemetryService, TelemetryConfig, TelemetryError, TraceContext};
+pub use services::settings::{SettingsService, Settings, SettingsChange, SettingsError, AlertSettings, PathSettings, QuotaSettings, MaintenanceSettings};
+pub use services::secrets::{SecretsService, SecretsConfig, SecretsError, SecretInfo};
+pub use services::alerts::{AlertService, Alert, AlertCategory, AlertSeverity, AlertFilter, AlertError};
+pub use services::health::{HealthMonitor, HealthReport, HealthDelta, HealthState, SubsystemHealth, ProbeResult};
+pub use services::projects::{ProjectRegistry, ProjectInfo, ProjectError};
+pub use services::backup::{ArchiveManifest, ArchiveSection, ArchiveSource, BackupError, BackupProgress};
+pub use services::encryption::{ContentCipher, EncryptionError};
+pub use services::jobs::{JobService, JobConfig, JobContext, JobKind, JobStatus, JobRecord, JobError};
+pub use services::quota::{QuotaService, QuotaLimits, QuotaScope, QuotaUsage, QuotaCheck, QuotaError};
+pub use services::network_policy::{NetworkPolicy, NetworkFeature, NetworkPolicyStatus, PolicyError};
 pub use services::vector::{
-    VectorDbService, VectorDbConfig, VectorDbError,
-    HybridConfig, GenerationManager
+    VectorDbService, VectorDbConfig, VectorDbError, LexicalBackend,
+    HybridConfig, GenerationManager, CollectionStats, GenerationStats, IndexMaintenanceProgress, IndexMaintenanceReport
 };
 pub use schemas::{VectorSchema, SearchResult, SearchQuery, SearchType, CitationInfo};
 
 // Re-export state management
-pub use state::{AppState, StateManager};
+pub use state::{AppState, StateManager, StateStore, PersistedState};
 
 // Re-export shared types
 pub use models::common::*;
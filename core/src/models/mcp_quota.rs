@@ -0,0 +1,110 @@
+/*!
+ * MCP Quota Metrics
+ *
+ * Snapshot of per-client rate-limit usage written by the MCP subprocess so the
+ * Manager can show it on the dashboard. Same handoff as the tool and prompt
+ * catalogs: a small JSON file written atomically.
+ */
+
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CoreResult;
+
+/// Default location of the snapshot written by the MCP subprocess
+pub const DEFAULT_QUOTA_METRICS_PATH: &str = "./mcp_quota.json";
+
+/// Configured limits (same for every client in the MVP)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QuotaLimits {
+    pub calls_per_minute: u32,
+    pub max_concurrent_calls: u32,
+    pub max_result_bytes: usize,
+}
+
+impl Default for QuotaLimits {
+    fn default() -> Self {
+        Self {
+            calls_per_minute: 120,
+            max_concurrent_calls: 4,
+            max_result_bytes: 1_000_000,
+        }
+    }
+}
+
+/// Usage for one client/session
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ClientQuotaUsage {
+    pub client_id: String,
+    pub calls_allowed: u64,
+    pub calls_rejected: u64,
+    pub results_truncated: u64,         // Rejected for exceeding max_result_bytes
+    pub in_flight: u32,
+    pub tokens_remaining: f64,          // Calls available right now
+    pub last_call_at: Option<DateTime<Utc>>,
+}
+
+/// Snapshot file contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QuotaMetrics {
+    pub limits: QuotaLimits,
+    pub clients: Vec<ClientQuotaUsage>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl QuotaMetrics {
+    /// Load a snapshot; a missing file means the server hasn't served any calls
+    pub fn load(path: &Path) -> CoreResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write atomically (temp file + rename) so readers never see partial JSON
+    pub fn save(&self, path: &Path) -> CoreResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Total rejected calls across clients
+    pub fn total_rejected(&self) -> u64 {
+        self.clients.iter().map(|c| c.calls_rejected).sum()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_metrics_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("quota.json");
+        assert!(QuotaMetrics::load(&path).unwrap().clients.is_empty());
+
+        let metrics = QuotaMetrics {
+            limits: QuotaLimits::default(),
+            clients: vec![ClientQuotaUsage {
+                client_id: "claude-desktop".to_string(),
+                calls_allowed: 10,
+                calls_rejected: 2,
+                ..Default::default()
+            }],
+            updated_at: Some(Utc::now()),
+        };
+        metrics.save(&path).unwrap();
+
+        let loaded = QuotaMetrics::load(&path).unwrap();
+        assert_eq!(loaded.clients, metrics.clients);
+        assert_eq!(loaded.total_rejected(), 2);
+    }
+}
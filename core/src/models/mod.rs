@@ -5,6 +5,17 @@
  */
 
 pub mod common;
+pub mod mcp_quota;
+pub mod outbound_rpc;
+pub mod prompt;
+pub mod tool_catalog;
+pub mod tool_pack;
+pub mod tool_template;
 
 // Re-export common types
-pub use common::*;
\ No newline at end of file
+pub use common::*;
+pub use prompt::{PromptTemplate, PromptArgument, PromptCatalog};
+pub use tool_catalog::{DynamicToolSpec, ToolCatalog};
+pub use tool_pack::{DependencyResolution, SetupTask, ToolDependencies};
+pub use tool_template::ToolTemplate;
+pub use mcp_quota::{QuotaLimits, QuotaMetrics};
\ No newline at end of file
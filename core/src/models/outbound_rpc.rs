@@ -0,0 +1,128 @@
+/*!
+ * Outbound RPC Wire Types
+ *
+ * Requests from the MCP subprocess to the Manager's outbound RPC server.
+ * One JSON body per call (`{"method", "params"}`) posted to `RPC_PATH`,
+ * authenticated with a bearer token. The Manager takes the token from
+ * `RPC_TOKEN_ENV` when set, otherwise from `RPC_TOKEN_FILE` in its project
+ * directory (created on first start). The subprocess reads the same variable,
+ * or the file passed as `--rpc-token-file`.
+ *
+ * The optional `traceparent` (W3C trace context) makes the Manager's handling
+ * a child of the calling MCP request in distributed traces.
+ *
+ * Methods: `kb.*` (see `modules::kb::rpc`) and `llm.generate`
+ * (`{"prompt", "max_tokens", "temperature", "tool_id"?, "flow_id"?}` ->
+ * `{"text", "model", "provider"}`; the tool/flow selects the LLM provider).
+ */
+
+use std::io::Write;
+use std::path::Path;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Address the Manager listens on (loopback only)
+pub const DEFAULT_RPC_ADDR: &str = "127.0.0.1:3000";
+
+/// HTTP path of the RPC endpoint
+pub const RPC_PATH: &str = "/rpc";
+
+/// Environment variable carrying the bearer token
+pub const RPC_TOKEN_ENV: &str = "RAG_STUDIO_RPC_TOKEN";
+
+/// Token file in the Manager's project directory, used when `RPC_TOKEN_ENV` is unset
+pub const RPC_TOKEN_FILE: &str = "rpc_token";
+
+/// RPC request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcRequest {
+    pub method: String,
+    #[serde(default)]
+    pub params: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub traceparent: Option<String>,
+}
+
+/// RPC error body
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RpcErrorBody {
+    pub code: i32,
+    pub message: String,
+}
+
+/// RPC response: exactly one of `result` / `error`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RpcResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub result: Option<Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<RpcErrorBody>,
+}
+
+impl RpcResponse {
+    pub fn success(result: Value) -> Self {
+        Self { result: Some(result), error: None }
+    }
+
+    pub fn error(code: i32, message: &str) -> Self {
+        Self { result: None, error: Some(RpcErrorBody { code, message: message.to_string() }) }
+    }
+}
+
+/// Read the token stored at `path`, or write a new random one there (owner-only on Unix)
+pub fn load_or_create_token(path: &Path) -> std::io::Result<String> {
+    match std::fs::read_to_string(path) {
+        Ok(token) if !token.trim().is_empty() => return Ok(token.trim().to_string()),
+        Ok(_) => {}
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => return Err(e),
+    }
+
+    let token = uuid::Uuid::new_v4().simple().to_string();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)?.write_all(token.as_bytes())?;
+    Ok(token)
+}
+
+/// Compare a presented bearer token without exiting at the first differing byte
+pub fn token_matches(expected: &str, presented: &str) -> bool {
+    expected.len() == presented.len()
+        && expected.bytes().zip(presented.bytes()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_file_is_reused() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join(RPC_TOKEN_FILE);
+
+        let token = load_or_create_token(&path).unwrap();
+        assert_eq!(token.len(), 32);
+        assert_eq!(load_or_create_token(&path).unwrap(), token);
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o600);
+        }
+    }
+
+    #[test]
+    fn test_token_matches() {
+        assert!(token_matches("secret-token", "secret-token"));
+        assert!(!token_matches("secret-token", "secret-tokem"));
+        assert!(!token_matches("secret-token", "secret"));
+        assert!(!token_matches("secret-token", ""));
+    }
+}
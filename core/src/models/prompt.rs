@@ -0,0 +1,275 @@
+/*!
+ * Prompt Templates
+ *
+ * User-authored prompt templates exposed to agents through MCP `prompts/list`
+ * and `prompts/get`. Templates use `{{argument}}` placeholders; `{{context}}`
+ * is filled with retrieved KB passages by the MCP server.
+ */
+
+use std::collections::HashMap;
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CoreError, CoreResult};
+
+/// Reserved placeholder filled with retrieved passages and citations
+pub const CONTEXT_PLACEHOLDER: &str = "context";
+
+/// Default location of the catalog shared with the MCP subprocess, relative to the project root
+pub const DEFAULT_PROMPTS_PATH: &str = "./mcp_prompts.json";
+
+/// Declared prompt argument (MCP standard)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PromptArgument {
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(default)]
+    pub required: bool,
+}
+
+impl PromptArgument {
+    pub fn required(name: &str, description: &str) -> Self {
+        Self { name: name.to_string(), description: Some(description.to_string()), required: true }
+    }
+
+    pub fn optional(name: &str, description: &str) -> Self {
+        Self { name: name.to_string(), description: Some(description.to_string()), required: false }
+    }
+}
+
+/// Prompt template
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTemplate {
+    pub id: String,
+    pub name: String,                           // MCP prompt name, e.g. "answer-with-citations"
+    pub description: Option<String>,
+    pub template: String,
+    pub arguments: Vec<PromptArgument>,
+    #[serde(default)]
+    pub arguments_schema: Option<serde_json::Value>,   // Overrides the schema derived from `arguments`
+    #[serde(default)]
+    pub kb_id: Option<String>,                  // Default KB for `{{context}}`
+    pub enabled: bool,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl PromptTemplate {
+    pub fn new(name: &str, description: &str, template: &str, arguments: Vec<PromptArgument>) -> Self {
+        let now = Utc::now();
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            name: name.to_string(),
+            description: Some(description.to_string()),
+            template: template.to_string(),
+            arguments,
+            arguments_schema: None,
+            kb_id: None,
+            enabled: true,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    /// Placeholder names in template order (deduplicated)
+    pub fn placeholders(&self) -> Vec<String> {
+        let mut names: Vec<String> = Vec::new();
+        let mut rest = self.template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start + 2..].find("}}") else { break };
+            let name = rest[start + 2..start + 2 + end].trim().to_string();
+            if !names.contains(&name) {
+                names.push(name);
+            }
+            rest = &rest[start + 2 + end + 2..];
+        }
+        names
+    }
+
+    /// True when the template asks for retrieved KB context
+    pub fn uses_context(&self) -> bool {
+        self.placeholders().iter().any(|p| p == CONTEXT_PLACEHOLDER)
+    }
+
+    /// Check name and that every placeholder is declared (or reserved)
+    pub fn validate(&self) -> CoreResult<()> {
+        if self.name.is_empty() || self.name.len() > 64
+            || !self.name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(CoreError::Validation(format!("Invalid prompt name: '{}'", self.name)));
+        }
+        if self.template.trim().is_empty() {
+            return Err(CoreError::Validation("Prompt template cannot be empty".to_string()));
+        }
+
+        for placeholder in self.placeholders() {
+            let declared = self.arguments.iter().any(|a| a.name == placeholder);
+            if !declared && placeholder != CONTEXT_PLACEHOLDER {
+                return Err(CoreError::Validation(format!(
+                    "Placeholder '{{{{{}}}}}' is not a declared argument", placeholder
+                )));
+            }
+        }
+
+        if let Some(schema) = &self.arguments_schema {
+            if !schema.is_object() {
+                return Err(CoreError::Validation("arguments_schema must be a JSON object".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    /// JSON schema for the arguments (explicit schema or derived string properties)
+    pub fn schema(&self) -> serde_json::Value {
+        if let Some(schema) = &self.arguments_schema {
+            return schema.clone();
+        }
+
+        let properties: serde_json::Map<String, serde_json::Value> = self.arguments.iter()
+            .map(|a| (a.name.clone(), serde_json::json!({
+                "type": "string",
+                "description": a.description.clone().unwrap_or_default(),
+            })))
+            .collect();
+        let required: Vec<&str> = self.arguments.iter()
+            .filter(|a| a.required)
+            .map(|a| a.name.as_str())
+            .collect();
+
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+            "additionalProperties": false,
+        })
+    }
+
+    /// Substitute arguments; missing optional arguments render as empty
+    pub fn render(&self, args: &HashMap<String, String>) -> CoreResult<String> {
+        if let Some(missing) = self.arguments.iter().find(|a| a.required && !args.contains_key(&a.name)) {
+            return Err(CoreError::Validation(format!("Missing required argument: {}", missing.name)));
+        }
+
+        let mut rendered = self.template.clone();
+        for placeholder in self.placeholders() {
+            let value = args.get(&placeholder).map(String::as_str).unwrap_or("");
+            rendered = rendered.replace(&format!("{{{{{}}}}}", placeholder), value)
+                .replace(&format!("{{{{ {} }}}}", placeholder), value);
+        }
+        Ok(rendered)
+    }
+}
+
+/// Built-in templates seeded into a new catalog
+pub fn builtin_prompts() -> Vec<PromptTemplate> {
+    vec![
+        PromptTemplate::new(
+            "answer-with-citations",
+            "Answer a question using only retrieved knowledge base passages, citing each claim",
+            "Answer the question using only the passages below. Cite every claim with the \
+             passage's [n] marker and say so if the passages do not contain the answer.\n\n\
+             Passages:\n{{context}}\n\nQuestion: {{question}}",
+            vec![
+                PromptArgument::required("question", "Question to answer"),
+                PromptArgument::optional("collection", "Knowledge base to retrieve passages from"),
+            ],
+        ),
+        PromptTemplate::new(
+            "summarize-doc",
+            "Summarize a document for a given audience",
+            "Summarize the following document{{audience}} in a few short paragraphs, \
+             keeping key terms and any version-specific details.\n\n{{document}}",
+            vec![
+                PromptArgument::required("document", "Document text to summarize"),
+                PromptArgument::optional("audience", "Optional audience, e.g. ' for new engineers'"),
+            ],
+        ),
+    ]
+}
+
+/// Prompt catalog file shared between the Manager and the MCP subprocess
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptCatalog {
+    pub prompts: Vec<PromptTemplate>,
+}
+
+impl PromptCatalog {
+    /// Load a catalog, seeding built-ins when the file doesn't exist yet
+    pub fn load(path: &Path) -> CoreResult<Self> {
+        if !path.exists() {
+            return Ok(Self { prompts: builtin_prompts() });
+        }
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write atomically (temp file + rename) so readers never see partial JSON
+    pub fn save(&self, path: &Path) -> CoreResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+
+    /// Enabled prompt by MCP name
+    pub fn get(&self, name: &str) -> Option<&PromptTemplate> {
+        self.prompts.iter().find(|p| p.enabled && p.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_builtins_are_valid() {
+        for prompt in builtin_prompts() {
+            prompt.validate().unwrap();
+        }
+        let answer = &builtin_prompts()[0];
+        assert!(answer.uses_context());
+        assert_eq!(answer.schema()["required"], serde_json::json!(["question"]));
+    }
+
+    #[test]
+    fn test_render_and_validation() {
+        let prompt = PromptTemplate::new(
+            "greet",
+            "Greeting",
+            "Hello {{name}}, welcome to {{ place }}. Bye {{name}}.",
+            vec![
+                PromptArgument::required("name", "Name"),
+                PromptArgument::optional("place", "Place"),
+            ],
+        );
+        prompt.validate().unwrap();
+
+        let args = HashMap::from([("name".to_string(), "Ada".to_string())]);
+        assert_eq!(prompt.render(&args).unwrap(), "Hello Ada, welcome to . Bye Ada.");
+        assert!(prompt.render(&HashMap::new()).is_err());
+
+        let undeclared = PromptTemplate::new("bad", "Bad", "Use {{missing}}", vec![]);
+        assert!(matches!(undeclared.validate(), Err(CoreError::Validation(_))));
+        let bad_name = PromptTemplate::new("bad name!", "Bad", "text", vec![]);
+        assert!(bad_name.validate().is_err());
+    }
+
+    #[test]
+    fn test_catalog_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("prompts.json");
+
+        let catalog = PromptCatalog::load(&path).unwrap();
+        assert_eq!(catalog.prompts.len(), 2);
+        catalog.save(&path).unwrap();
+
+        let loaded = PromptCatalog::load(&path).unwrap();
+        assert!(loaded.get("summarize-doc").is_some());
+        assert!(loaded.get("unknown").is_none());
+    }
+}
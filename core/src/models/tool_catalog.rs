@@ -0,0 +1,171 @@
+/*!
+ * Dynamic Tool Catalog
+ *
+ * Control file through which the Manager tells the MCP subprocess which
+ * user-defined tools to expose. Each enabled `ToolState` with a KB binding
+ * becomes one MCP search tool; the server polls the file for changes.
+ */
+
+use std::path::Path;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::CoreResult;
+use crate::state::ToolState;
+
+/// Default location of the catalog shared with the MCP subprocess, relative to the project root
+pub const DEFAULT_TOOLS_PATH: &str = "./mcp_tools.json";
+
+/// Tool type for KB-bound search tools
+pub const KB_SEARCH_TOOL_TYPE: &str = "kb_search";
+
+/// Scope a KB search tool needs when it declares none
+pub const DEFAULT_TOOL_SCOPE: &str = "kb.read";
+
+const DEFAULT_TOP_K: u32 = 10;
+
+/// MCP tool backed by a KB search
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DynamicToolSpec {
+    pub tool_id: String,
+    pub name: String,                   // MCP tool name (sanitized)
+    pub description: String,
+    pub kb_id: String,
+    pub top_k: u32,
+    pub top_n: Option<u32>,             // Results kept after reranking
+    #[serde(default)]
+    pub scopes: Vec<String>,            // Enforced by the MCP server's capability policy
+}
+
+impl DynamicToolSpec {
+    /// Spec for an enabled KB search tool (None for disabled or unbound tools)
+    pub fn from_tool_state(tool: &ToolState) -> Option<Self> {
+        if !tool.enabled || tool.tool_type != KB_SEARCH_TOOL_TYPE {
+            return None;
+        }
+        let kb_id = tool.config.get("kb_id").and_then(|v| v.as_str())?;
+        let name = mcp_tool_name(&tool.name)?;
+
+        Some(Self {
+            tool_id: tool.id.clone(),
+            name,
+            description: tool.config.get("description")
+                .and_then(|v| v.as_str())
+                .map(String::from)
+                .unwrap_or_else(|| format!("Search the {} knowledge base", kb_id)),
+            kb_id: kb_id.to_string(),
+            top_k: tool.config.get("top_k").and_then(|v| v.as_u64()).map(|v| v as u32).unwrap_or(DEFAULT_TOP_K),
+            top_n: tool.config.get("top_n").and_then(|v| v.as_u64()).map(|v| v as u32),
+            scopes: if tool.permissions.is_empty() {
+                vec![DEFAULT_TOOL_SCOPE.to_string()]
+            } else {
+                tool.permissions.clone()
+            },
+        })
+    }
+}
+
+/// Lowercase, `_`-separated tool name; None if nothing usable remains
+pub fn mcp_tool_name(name: &str) -> Option<String> {
+    let sanitized: String = name.trim().to_lowercase().chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect::<String>()
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("_");
+
+    if sanitized.is_empty() {
+        None
+    } else {
+        Some(sanitized.chars().take(64).collect())
+    }
+}
+
+/// Catalog file contents
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ToolCatalog {
+    pub tools: Vec<DynamicToolSpec>,
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl ToolCatalog {
+    /// Build from tool state, skipping disabled tools and name collisions
+    pub fn from_tools<'a>(tools: impl IntoIterator<Item = &'a ToolState>) -> Self {
+        let mut specs: Vec<DynamicToolSpec> = tools.into_iter()
+            .filter_map(DynamicToolSpec::from_tool_state)
+            .collect();
+        specs.sort_by(|a, b| a.name.cmp(&b.name).then(a.tool_id.cmp(&b.tool_id)));
+        specs.dedup_by(|a, b| a.name == b.name);
+
+        Self {
+            tools: specs,
+            updated_at: Some(Utc::now()),
+        }
+    }
+
+    /// Load a catalog; a missing file means no dynamic tools
+    pub fn load(path: &Path) -> CoreResult<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let json = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&json)?)
+    }
+
+    /// Write atomically (temp file + rename) so the MCP server never reads partial JSON
+    pub fn save(&self, path: &Path) -> CoreResult<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?)?;
+        std::fs::rename(&tmp, path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tool(id: &str, name: &str, enabled: bool, config: serde_json::Value) -> ToolState {
+        ToolState {
+            id: id.to_string(),
+            name: name.to_string(),
+            tool_type: KB_SEARCH_TOOL_TYPE.to_string(),
+            enabled,
+            last_used: None,
+            usage_count: 0,
+            config,
+            schema: serde_json::json!({}),
+            permissions: Vec::new(),
+            usage: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_tool_names() {
+        assert_eq!(mcp_tool_name("Search  Product Docs!").as_deref(), Some("search_product_docs"));
+        assert_eq!(mcp_tool_name("kb.search").as_deref(), Some("kb_search"));
+        assert!(mcp_tool_name("  --  ").is_none());
+    }
+
+    #[test]
+    fn test_catalog_from_tools() {
+        let tools = vec![
+            tool("t1", "Docs Search", true, serde_json::json!({"kb_id": "kb_1", "top_k": 5, "top_n": 3})),
+            tool("t2", "Disabled", false, serde_json::json!({"kb_id": "kb_1"})),
+            tool("t3", "Unbound", true, serde_json::json!({})),
+            tool("t4", "docs-search", true, serde_json::json!({"kb_id": "kb_2"})),
+        ];
+
+        let catalog = ToolCatalog::from_tools(&tools);
+        assert_eq!(catalog.tools.len(), 1);
+        let spec = &catalog.tools[0];
+        assert_eq!(spec.name, "docs_search");
+        assert_eq!(spec.tool_id, "t1");
+        assert_eq!((spec.top_k, spec.top_n), (5, Some(3)));
+        assert_eq!(spec.scopes, vec![DEFAULT_TOOL_SCOPE.to_string()]);
+    }
+}
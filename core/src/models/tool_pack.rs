@@ -0,0 +1,264 @@
+/*!
+ * Tool Packs
+ *
+ * Layout of tool packs (`pack_type` "tool", `.ragpack`): one or more tool
+ * definitions, the dependencies they need on the importing machine, and
+ * optionally tool templates and the exported archives of the KBs they search. KB archives are
+ * split into hex-encoded parts so no single pack file gets huge. Importing
+ * resolves each
+ * dependency (bundled KB import, model download) and reports whatever the
+ * user still has to set up as `SetupTask`s.
+ */
+
+use std::collections::BTreeMap;
+use serde::{Deserialize, Serialize};
+
+use crate::modules::pipeline::{ETLStepType, PipelineResources, PipelineSpec, PipelineStepConfig, ResourceLimitAction};
+use crate::services::llm::LlmProviderConfig;
+use crate::services::network_policy::NetworkFeature;
+use crate::services::secrets::{from_hex, to_hex};
+use crate::state::{AppState, ToolState};
+
+/// Pack type of tool packs
+pub const TOOL_PACK_TYPE: &str = "tool";
+
+/// Tool definitions inside a tool pack
+pub const TOOLS_FILE: &str = "tools.json";
+
+/// Dependency listing inside a tool pack
+pub const DEPENDENCIES_FILE: &str = "dependencies.json";
+
+/// Optional user-defined tool templates inside a tool pack
+pub const TEMPLATES_FILE: &str = "templates.json";
+
+/// Raw bytes per bundled KB part
+pub const KB_PART_BYTES: usize = 4 * 1024 * 1024;
+
+/// KB the tool searches
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KbDependency {
+    pub id: String,
+    pub name: String,
+    pub version: Option<i32>,
+    pub embedder_model: Option<String>,
+}
+
+/// External service the tool calls
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ServiceDependency {
+    pub provider: String,               // e.g. "openai_compatible:gpt-4o"
+    pub remote: bool,
+    pub api_key_secret: Option<String>,
+}
+
+/// Everything a tool needs besides its own definition
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolDependencies {
+    pub kbs: Vec<KbDependency>,
+    pub models: Vec<String>,            // Embedding models of the KBs
+    pub services: Vec<ServiceDependency>,
+}
+
+impl ToolDependencies {
+    /// Dependencies of `tool`, filled in from what `state` knows about its KB
+    pub fn for_tool(tool: &ToolState, state: &AppState) -> Self {
+        let mut dependencies = Self::default();
+
+        if let Some(kb_id) = tool.config.get("kb_id").and_then(|v| v.as_str()) {
+            let kb = state.knowledge_bases.get(kb_id);
+            let embedder_model = kb.map(|kb| kb.embedder_model.clone()).filter(|m| !m.is_empty());
+            dependencies.models.extend(embedder_model.clone());
+            dependencies.kbs.push(KbDependency {
+                id: kb_id.to_string(),
+                name: kb.map_or_else(|| kb_id.to_string(), |kb| kb.name.clone()),
+                version: kb.map(|kb| kb.version),
+                embedder_model,
+            });
+        }
+
+        if let Ok(llm) = LlmProviderConfig::from_config(&tool.config) {
+            if llm != LlmProviderConfig::Local {
+                let api_key_secret = match &llm {
+                    LlmProviderConfig::OpenaiCompatible { api_key_secret, .. } => api_key_secret.clone(),
+                    _ => None,
+                };
+                dependencies.services.push(ServiceDependency {
+                    provider: llm.name(),
+                    remote: llm.is_remote(),
+                    api_key_secret,
+                });
+            }
+        }
+        dependencies
+    }
+
+    /// Add `other`'s dependencies, skipping ones already listed
+    pub fn merge(&mut self, other: ToolDependencies) {
+        for kb in other.kbs {
+            if !self.kbs.iter().any(|k| k.id == kb.id) {
+                self.kbs.push(kb);
+            }
+        }
+        for model in other.models {
+            if !self.models.contains(&model) {
+                self.models.push(model);
+            }
+        }
+        for service in other.services {
+            if !self.services.contains(&service) {
+                self.services.push(service);
+            }
+        }
+    }
+}
+
+/// Something the user has to do before an imported tool works
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SetupTask {
+    /// KB not on this machine and not bundled; build it with the suggested pipeline
+    BuildKb { kb: KbDependency, pipeline: PipelineSpec },
+    /// Model download failed (e.g. air-gapped); import it from an archive instead
+    InstallModel { model_id: String, reason: String },
+    /// The provider's API key is not in the vault
+    ConfigureSecret { provider: String, secret: String },
+    /// The provider is remote but the network policy blocks it
+    AllowNetwork { provider: String, feature: NetworkFeature },
+}
+
+/// Outcome of resolving a tool's dependencies
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DependencyResolution {
+    pub installed: Vec<String>,         // e.g. "kb:kb_docs", "model:bge-small"
+    pub tasks: Vec<SetupTask>,          // Left for the user
+}
+
+impl DependencyResolution {
+    pub fn is_complete(&self) -> bool {
+        self.tasks.is_empty()
+    }
+}
+
+fn bundled_kb_prefix(kb_id: &str) -> String {
+    format!("kbs/{}/", kb_id)
+}
+
+/// Add a KB archive (`.ragkb` bytes) to pack files as numbered parts
+pub fn bundle_kb(files: &mut BTreeMap<String, String>, kb_id: &str, archive: &[u8]) {
+    let prefix = bundled_kb_prefix(kb_id);
+    for (index, part) in archive.chunks(KB_PART_BYTES).enumerate() {
+        files.insert(format!("{}part-{:05}", prefix, index), to_hex(part));
+    }
+}
+
+/// Reassembled archive of `kb_id`, if the pack carries one
+pub fn bundled_kb(files: &BTreeMap<String, String>, kb_id: &str) -> Option<Vec<u8>> {
+    let prefix = bundled_kb_prefix(kb_id);
+    // BTreeMap order is part order thanks to the zero-padded index
+    let mut archive = Vec::new();
+    for (_, part) in files.range(prefix.clone()..).take_while(|(path, _)| path.starts_with(&prefix)) {
+        archive.extend(from_hex(part).ok()?);
+    }
+    (!archive.is_empty()).then_some(archive)
+}
+
+/// Starting point for rebuilding a missing KB: parse local files, chunk,
+/// embed with the KB's model and index
+pub fn kb_pipeline_template(kb: &KbDependency) -> PipelineSpec {
+    let step = |step_type: ETLStepType, config: serde_json::Value| PipelineStepConfig {
+        step_type,
+        config,
+        resources: None,
+        on_limit: ResourceLimitAction::default(),
+    };
+    PipelineSpec {
+        id: format!("pipeline_{}", kb.id),
+        name: format!("Build {}", kb.name),
+        kb_id: Some(kb.id.clone()),
+        steps: vec![
+            step(ETLStepType::Parse, serde_json::json!({ "sources": ["."] })),
+            step(ETLStepType::Chunk, serde_json::json!({})),
+            step(ETLStepType::Embed, serde_json::json!({ "model": kb.embedder_model })),
+            step(ETLStepType::Index, serde_json::json!({})),
+        ],
+        resources: PipelineResources::default(),
+        source_roots: Vec::new(),       // The user picks the document folder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::state::{KnowledgeBaseState, KnowledgeBaseStatus};
+
+    fn tool(config: serde_json::Value) -> ToolState {
+        ToolState {
+            id: "tool_1".to_string(),
+            name: "Search docs".to_string(),
+            tool_type: "kb_search".to_string(),
+            enabled: true,
+            last_used: None,
+            usage_count: 0,
+            config,
+            schema: serde_json::json!({}),
+            permissions: Vec::new(),
+            usage: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_dependencies_for_tool() {
+        let mut state = AppState::default();
+        state.knowledge_bases.insert("kb_docs".to_string(), KnowledgeBaseState {
+            id: "kb_docs".to_string(),
+            name: "Docs".to_string(),
+            version: 3,
+            status: KnowledgeBaseStatus::Active,
+            embedder_model: "bge-small".to_string(),
+            health_score: 1.0,
+            document_count: 0,
+            chunk_count: 0,
+            last_updated: chrono::Utc::now(),
+            metadata: serde_json::json!({}),
+        });
+
+        let dependencies = ToolDependencies::for_tool(&tool(serde_json::json!({
+            "kb_id": "kb_docs",
+            "llm": { "provider": "openai_compatible", "base_url": "https://api.openai.com/v1", "model": "gpt-4o", "api_key_secret": "tool_1.llm_api_key" },
+        })), &state);
+        assert_eq!(dependencies.kbs[0].version, Some(3));
+        assert_eq!(dependencies.models, vec!["bge-small"]);
+        assert_eq!(dependencies.services, vec![ServiceDependency {
+            provider: "openai_compatible:gpt-4o".to_string(),
+            remote: true,
+            api_key_secret: Some("tool_1.llm_api_key".to_string()),
+        }]);
+
+        // Unknown KB, local provider
+        let dependencies = ToolDependencies::for_tool(&tool(serde_json::json!({ "kb_id": "kb_other" })), &state);
+        assert_eq!(dependencies.kbs[0].name, "kb_other");
+        assert!(dependencies.models.is_empty() && dependencies.services.is_empty());
+    }
+
+    #[test]
+    fn test_bundled_kb_roundtrip() {
+        let archive: Vec<u8> = (0..KB_PART_BYTES * 2 + 10).map(|i| (i % 251) as u8).collect();
+        let mut files = BTreeMap::from([(TOOLS_FILE.to_string(), "[]".to_string())]);
+        bundle_kb(&mut files, "kb_docs", &archive);
+        bundle_kb(&mut files, "kb_docs_v2", &[7]);
+
+        assert_eq!(files.keys().filter(|k| k.starts_with("kbs/kb_docs/")).count(), 3);
+        assert_eq!(bundled_kb(&files, "kb_docs"), Some(archive));
+        assert_eq!(bundled_kb(&files, "kb_docs_v2"), Some(vec![7]));
+        assert_eq!(bundled_kb(&files, "kb_other"), None);
+    }
+
+    #[test]
+    fn test_merge_dependencies() {
+        let kb = |id: &str| KbDependency { id: id.to_string(), name: id.to_string(), version: None, embedder_model: None };
+        let mut dependencies = ToolDependencies { kbs: vec![kb("kb_a")], models: vec!["bge-small".to_string()], services: Vec::new() };
+        dependencies.merge(ToolDependencies { kbs: vec![kb("kb_a"), kb("kb_b")], models: vec!["bge-small".to_string()], services: Vec::new() });
+        assert_eq!(dependencies.kbs.len(), 2);
+        assert_eq!(dependencies.models.len(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+/*!
+ * Tool Templates
+ *
+ * Starting points for new tools: a tool type, a config without a KB binding,
+ * and the permissions it needs. Built-in templates ship with the app; users
+ * save their own from existing tools, which live in core state (and so in
+ * the SQL state snapshots) and can travel in tool packs.
+ */
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::errors::{CoreError, CoreResult};
+use crate::models::tool_catalog::{DEFAULT_TOOL_SCOPE, KB_SEARCH_TOOL_TYPE};
+use crate::state::ToolState;
+
+/// Config keys bound to one machine's data, dropped when saving a template
+const INSTANCE_CONFIG_KEYS: &[&str] = &["kb_id"];
+
+/// Tool template
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub tool_type: String,
+    pub config: serde_json::Value,      // Tool config minus the KB binding
+    #[serde(default)]
+    pub permissions: Vec<String>,
+    #[serde(default)]
+    pub builtin: bool,                  // Shipped with the app; read-only
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl ToolTemplate {
+    /// Template from an existing tool, keeping its settings but not its KB
+    pub fn from_tool(tool: &ToolState, name: &str, description: Option<String>) -> Self {
+        let mut config = tool.config.clone();
+        if let Some(config) = config.as_object_mut() {
+            for key in INSTANCE_CONFIG_KEYS {
+                config.remove(*key);
+            }
+        }
+        let now = Utc::now();
+        Self {
+            id: format!("tpl_{}", uuid::Uuid::new_v4().simple()),
+            name: name.to_string(),
+            description,
+            tool_type: tool.tool_type.clone(),
+            config,
+            permissions: tool.permissions.clone(),
+            builtin: false,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn validate(&self) -> CoreResult<()> {
+        if self.name.trim().is_empty() {
+            return Err(CoreError::Validation("Template name is required".to_string()));
+        }
+        if !self.config.is_object() {
+            return Err(CoreError::Validation(format!("Template {} config must be an object", self.name)));
+        }
+        Ok(())
+    }
+}
+
+/// Templates shipped with the app
+pub fn builtin_templates() -> Vec<ToolTemplate> {
+    let created_at = DateTime::<Utc>::UNIX_EPOCH;
+    let template = |id: &str, name: &str, description: &str, config: serde_json::Value| ToolTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: Some(description.to_string()),
+        tool_type: KB_SEARCH_TOOL_TYPE.to_string(),
+        config,
+        permissions: vec![DEFAULT_TOOL_SCOPE.to_string()],
+        builtin: true,
+        created_at,
+        updated_at: created_at,
+    };
+
+    vec![
+        template(
+            "builtin_kb_search",
+            "Knowledge base search",
+            "Hybrid search returning the top passages with citations",
+            serde_json::json!({ "top_k": 10 }),
+        ),
+        template(
+            "builtin_kb_search_reranked",
+            "Reranked search",
+            "Retrieve broadly, rerank and keep the best few passages",
+            serde_json::json!({ "top_k": 50, "top_n": 5 }),
+        ),
+        template(
+            "builtin_kb_answer_local",
+            "Answer with local LLM",
+            "Answer questions from the knowledge base with the bundled local model",
+            serde_json::json!({ "top_k": 8, "llm": { "provider": "local" } }),
+        ),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_template_from_tool() {
+        let tool = ToolState {
+            id: "tool_1".to_string(),
+            name: "Search docs".to_string(),
+            tool_type: KB_SEARCH_TOOL_TYPE.to_string(),
+            enabled: true,
+            last_used: None,
+            usage_count: 42,
+            config: serde_json::json!({ "kb_id": "kb_docs", "top_k": 20, "top_n": 5 }),
+            schema: serde_json::json!({}),
+            permissions: vec!["kb.read".to_string()],
+            usage: Default::default(),
+        };
+
+        let template = ToolTemplate::from_tool(&tool, "Reranked docs search", None);
+        assert_eq!(template.config, serde_json::json!({ "top_k": 20, "top_n": 5 }));
+        assert!(!template.builtin);
+        assert!(template.validate().is_ok());
+
+        assert!(ToolTemplate { name: " ".to_string(), ..template }.validate().is_err());
+        assert!(builtin_templates().iter().all(|t| t.builtin && t.validate().is_ok()));
+    }
+}
@@ -0,0 +1,17 @@
+/*!
+ * Audit Domain Errors
+ *
+ * Domain-specific error types for the MCP audit log.
+ */
+
+use crate::services::sql::SqlError;
+
+/// Audit Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum AuditError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
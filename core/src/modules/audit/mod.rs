@@ -0,0 +1,16 @@
+/*!
+ * Audit Domain Module
+ *
+ * Append-only log of MCP tool invocations: who called which tool, with what
+ * (sanitized) arguments, how long it took and how it ended.
+ */
+
+pub mod service;
+pub mod models;
+pub mod schema;
+pub mod errors;
+
+// Re-export public types
+pub use service::AuditService;
+pub use models::*;
+pub use errors::AuditError;
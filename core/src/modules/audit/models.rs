@@ -0,0 +1,276 @@
+/*!
+ * Audit Domain Models
+ *
+ * Audit entries, query filters, argument sanitization and the per-tool
+ * usage analytics derived from them.
+ */
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use chrono::{DateTime, Duration, Utc};
+
+use crate::modules::pipeline::steps::redact_pii;
+use crate::state::{QueryCount, ToolUsage};
+
+/// Argument keys whose values are never stored
+const SENSITIVE_KEYS: &[&str] = &["password", "secret", "token", "api_key", "apikey", "authorization", "credential"];
+
+/// Longest string argument kept verbatim
+const MAX_ARGUMENT_CHARS: usize = 256;
+
+/// Queries listed in a tool's usage summary
+const TOP_QUERIES_LIMIT: usize = 10;
+
+/// One MCP tool invocation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpAuditEntry {
+    pub id: String,
+    pub tool_name: String,
+    pub arguments: Value,           // Sanitized (see `sanitize_arguments`)
+    pub caller: String,             // MCP client/session
+    pub latency_ms: u64,
+    pub result_bytes: u64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl McpAuditEntry {
+    /// Entry for a finished call; arguments are sanitized here
+    pub fn new(tool_name: &str, arguments: &Value, caller: &str) -> Self {
+        Self {
+            id: format!("audit_{}", uuid::Uuid::new_v4().simple()),
+            tool_name: tool_name.to_string(),
+            arguments: sanitize_arguments(arguments),
+            caller: caller.to_string(),
+            latency_ms: 0,
+            result_bytes: 0,
+            success: true,
+            error: None,
+            created_at: Utc::now(),
+        }
+    }
+}
+
+/// Mask sensitive keys and truncate long strings
+pub fn sanitize_arguments(value: &Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(map.iter()
+            .map(|(key, v)| {
+                let lower = key.to_lowercase();
+                if SENSITIVE_KEYS.iter().any(|s| lower.contains(s)) {
+                    (key.clone(), Value::String("[REDACTED]".to_string()))
+                } else {
+                    (key.clone(), sanitize_arguments(v))
+                }
+            })
+            .collect()),
+        Value::Array(items) => Value::Array(items.iter().map(sanitize_arguments).collect()),
+        Value::String(s) if s.chars().count() > MAX_ARGUMENT_CHARS => {
+            let truncated: String = s.chars().take(MAX_ARGUMENT_CHARS).collect();
+            Value::String(format!("{}… ({} chars)", truncated, s.chars().count()))
+        }
+        other => other.clone(),
+    }
+}
+
+/// Audit log query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AuditLogFilter {
+    pub tool_name: Option<String>,
+    pub caller: Option<String>,
+    pub success: Option<bool>,
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,       // Newest first; defaults to DEFAULT_AUDIT_LIMIT
+}
+
+/// Window shown by the Tools dashboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AnalyticsRange {
+    #[serde(rename = "1h")]
+    LastHour,
+    #[serde(rename = "24h")]
+    LastDay,
+    #[serde(rename = "7d")]
+    LastWeek,
+    #[serde(rename = "30d")]
+    LastMonth,
+}
+
+impl AnalyticsRange {
+    pub fn duration(&self) -> Duration {
+        match self {
+            AnalyticsRange::LastHour => Duration::hours(1),
+            AnalyticsRange::LastDay => Duration::days(1),
+            AnalyticsRange::LastWeek => Duration::days(7),
+            AnalyticsRange::LastMonth => Duration::days(30),
+        }
+    }
+
+    /// Width of one time-series bucket
+    pub fn bucket(&self) -> Duration {
+        match self {
+            AnalyticsRange::LastHour => Duration::minutes(5),
+            AnalyticsRange::LastDay => Duration::hours(1),
+            AnalyticsRange::LastWeek => Duration::hours(6),
+            AnalyticsRange::LastMonth => Duration::days(1),
+        }
+    }
+}
+
+/// Calls to a tool within one time bucket
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageBucket {
+    pub start: DateTime<Utc>,
+    pub calls: u64,
+    pub errors: u64,
+    pub p50_latency_ms: u64,
+    pub p95_latency_ms: u64,
+}
+
+/// Usage summary and time series for one tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolAnalytics {
+    pub tool_name: String,
+    pub range: AnalyticsRange,
+    pub since: DateTime<Utc>,
+    pub until: DateTime<Utc>,
+    pub summary: ToolUsage,
+    pub buckets: Vec<UsageBucket>,      // Oldest first, empty buckets included
+}
+
+/// Nearest-rank percentile of sorted values (0 when empty)
+fn percentile(sorted: &[u64], p: f64) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = ((p / 100.0) * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+/// Fold a tool's calls into a usage summary
+pub fn summarize_usage(entries: &[McpAuditEntry]) -> ToolUsage {
+    let mut latencies: Vec<u64> = entries.iter().map(|e| e.latency_ms).collect();
+    latencies.sort_unstable();
+    let errors = entries.iter().filter(|e| !e.success).count() as u64;
+
+    let mut queries: HashMap<&str, u64> = HashMap::new();
+    for entry in entries {
+        if let Some(query) = entry.arguments.get("query").and_then(|q| q.as_str()) {
+            *queries.entry(query.trim()).or_default() += 1;
+        }
+    }
+    let mut top_queries: Vec<(&str, u64)> = queries.into_iter().filter(|(q, _)| !q.is_empty()).collect();
+    top_queries.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    ToolUsage {
+        calls: entries.len() as u64,
+        errors,
+        error_rate: if entries.is_empty() { 0.0 } else { errors as f64 / entries.len() as f64 },
+        p50_latency_ms: percentile(&latencies, 50.0),
+        p95_latency_ms: percentile(&latencies, 95.0),
+        p99_latency_ms: percentile(&latencies, 99.0),
+        last_used: entries.iter().map(|e| e.created_at).max(),
+        top_queries: top_queries.into_iter().take(TOP_QUERIES_LIMIT)
+            .map(|(query, count)| QueryCount { query: redact_pii(query), count })
+            .collect(),
+    }
+}
+
+/// Split calls in `[since, until)` into fixed-width buckets
+pub fn usage_buckets(entries: &[McpAuditEntry], since: DateTime<Utc>, until: DateTime<Utc>, width: Duration) -> Vec<UsageBucket> {
+    let mut buckets = Vec::new();
+    let mut start = since;
+    while start < until {
+        let end = start + width;
+        let mut latencies: Vec<u64> = Vec::new();
+        let mut errors = 0;
+        for entry in entries.iter().filter(|e| e.created_at >= start && e.created_at < end) {
+            latencies.push(entry.latency_ms);
+            errors += u64::from(!entry.success);
+        }
+        latencies.sort_unstable();
+        buckets.push(UsageBucket {
+            start,
+            calls: latencies.len() as u64,
+            errors,
+            p50_latency_ms: percentile(&latencies, 50.0),
+            p95_latency_ms: percentile(&latencies, 95.0),
+        });
+        start = end;
+    }
+    buckets
+}
+
+/// Entries returned when the filter sets no limit
+pub const DEFAULT_AUDIT_LIMIT: usize = 500;
+
+/// Export format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditExportFormat {
+    Json,
+    Csv,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sanitize_arguments() {
+        let long = "x".repeat(300);
+        let sanitized = sanitize_arguments(&json!({
+            "query": "install guide",
+            "api_key": "sk-123",
+            "nested": {"Auth_Token": "abc", "text": long},
+            "ids": ["a", "b"]
+        }));
+
+        assert_eq!(sanitized["query"], "install guide");
+        assert_eq!(sanitized["api_key"], "[REDACTED]");
+        assert_eq!(sanitized["nested"]["Auth_Token"], "[REDACTED]");
+        assert!(sanitized["nested"]["text"].as_str().unwrap().ends_with("(300 chars)"));
+        assert_eq!(sanitized["ids"], json!(["a", "b"]));
+    }
+
+    fn call(query: &str, latency_ms: u64, success: bool, minutes_ago: i64) -> McpAuditEntry {
+        let mut entry = McpAuditEntry::new("kb.search_docs", &json!({"query": query}), "agent");
+        entry.latency_ms = latency_ms;
+        entry.success = success;
+        entry.created_at = Utc::now() - Duration::minutes(minutes_ago);
+        entry
+    }
+
+    #[test]
+    fn test_summarize_usage() {
+        let mut entries: Vec<McpAuditEntry> = (1..=100).map(|ms| call("install guide", ms, true, 1)).collect();
+        entries.push(call("contact jane@example.com", 500, false, 0));
+
+        let usage = summarize_usage(&entries);
+        assert_eq!((usage.calls, usage.errors), (101, 1));
+        assert!((usage.error_rate - 1.0 / 101.0).abs() < 1e-9);
+        assert_eq!((usage.p50_latency_ms, usage.p95_latency_ms, usage.p99_latency_ms), (51, 96, 100));
+        assert_eq!(usage.last_used, Some(entries[100].created_at));
+        assert_eq!(usage.top_queries[0], QueryCount { query: "install guide".to_string(), count: 100 });
+        assert_eq!(usage.top_queries[1].query, "contact [REDACTED:EMAIL]");
+
+        assert_eq!(summarize_usage(&[]), ToolUsage::default());
+    }
+
+    #[test]
+    fn test_usage_buckets() {
+        let until = Utc::now();
+        let entries = vec![call("a", 10, true, 2), call("b", 20, false, 3), call("c", 30, true, 50)];
+        let range = AnalyticsRange::LastHour;
+
+        let buckets = usage_buckets(&entries, until - range.duration(), until, range.bucket());
+        assert_eq!(buckets.len(), 12);
+        assert_eq!(buckets.iter().map(|b| b.calls).sum::<u64>(), 3);
+        let last = buckets.last().unwrap();
+        assert_eq!((last.calls, last.errors, last.p95_latency_ms), (2, 1, 20));
+    }
+}
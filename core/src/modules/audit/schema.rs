@@ -0,0 +1,58 @@
+/*!
+ * Audit Domain Schema
+ *
+ * Diesel row type for the mcp_audit_log table.
+ */
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::mcp_audit_log;
+use super::errors::AuditError;
+use super::models::McpAuditEntry;
+
+/// Row in mcp_audit_log
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = mcp_audit_log)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct McpAuditRow {
+    pub id: String,
+    pub tool_name: String,
+    pub arguments: String,            // JSON, sanitized
+    pub caller: String,
+    pub latency_ms: i64,
+    pub result_bytes: i64,
+    pub success: bool,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl McpAuditRow {
+    pub fn from_model(entry: &McpAuditEntry) -> Result<Self, AuditError> {
+        Ok(Self {
+            id: entry.id.clone(),
+            tool_name: entry.tool_name.clone(),
+            arguments: serde_json::to_string(&entry.arguments)?,
+            caller: entry.caller.clone(),
+            latency_ms: entry.latency_ms as i64,
+            result_bytes: entry.result_bytes as i64,
+            success: entry.success,
+            error: entry.error.clone(),
+            created_at: entry.created_at.naive_utc(),
+        })
+    }
+
+    pub fn into_model(self) -> Result<McpAuditEntry, AuditError> {
+        Ok(McpAuditEntry {
+            id: self.id,
+            tool_name: self.tool_name,
+            arguments: serde_json::from_str(&self.arguments)?,
+            caller: self.caller,
+            latency_ms: self.latency_ms.max(0) as u64,
+            result_bytes: self.result_bytes.max(0) as u64,
+            success: self.success,
+            error: self.error,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+        })
+    }
+}
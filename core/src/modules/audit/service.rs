@@ -0,0 +1,235 @@
+/*!
+ * Audit Domain Service
+ *
+ * Appends MCP tool invocations to `mcp_audit_log` and serves filtered reads
+ * and exports. The table rejects updates and deletes (see migration).
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use super::errors::AuditError;
+use super::models::*;
+use super::schema::McpAuditRow;
+use crate::state::ToolUsage;
+use crate::schemas::schema::mcp_audit_log;
+use crate::services::sql::SqlService;
+
+/// MCP audit log service
+pub struct AuditService {
+    sql_service: Arc<SqlService>,
+}
+
+impl AuditService {
+    pub fn new(sql_service: Arc<SqlService>) -> Self {
+        Self { sql_service }
+    }
+
+    /// Append an entry
+    pub async fn record(&self, entry: &McpAuditEntry) -> Result<(), AuditError> {
+        let row = McpAuditRow::from_model(entry)?;
+        self.sql_service.with_app_transaction(|conn| {
+            diesel::insert_into(mcp_audit_log::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// Entries matching the filter, newest first
+    pub async fn query(&self, filter: &AuditLogFilter) -> Result<Vec<McpAuditEntry>, AuditError> {
+        let filter = filter.clone();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            let mut query = mcp_audit_log::table.into_boxed();
+            if let Some(tool_name) = filter.tool_name {
+                query = query.filter(mcp_audit_log::tool_name.eq(tool_name));
+            }
+            if let Some(caller) = filter.caller {
+                query = query.filter(mcp_audit_log::caller.eq(caller));
+            }
+            if let Some(success) = filter.success {
+                query = query.filter(mcp_audit_log::success.eq(success));
+            }
+            if let Some(since) = filter.since {
+                query = query.filter(mcp_audit_log::created_at.ge(since.naive_utc()));
+            }
+            if let Some(until) = filter.until {
+                query = query.filter(mcp_audit_log::created_at.lt(until.naive_utc()));
+            }
+
+            Ok(query
+                .order(mcp_audit_log::created_at.desc())
+                .limit(filter.limit.unwrap_or(DEFAULT_AUDIT_LIMIT) as i64)
+                .select(McpAuditRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(McpAuditRow::into_model).collect()
+    }
+
+    /// Every call since a point in time (optionally for one tool), oldest first
+    async fn calls_since(&self, tool_name: Option<&str>, since: DateTime<Utc>) -> Result<Vec<McpAuditEntry>, AuditError> {
+        let tool_name = tool_name.map(str::to_string);
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            let mut query = mcp_audit_log::table
+                .filter(mcp_audit_log::created_at.ge(since.naive_utc()))
+                .into_boxed();
+            if let Some(tool_name) = tool_name {
+                query = query.filter(mcp_audit_log::tool_name.eq(tool_name));
+            }
+            Ok(query
+                .order(mcp_audit_log::created_at.asc())
+                .select(McpAuditRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(McpAuditRow::into_model).collect()
+    }
+
+    /// Usage summary per MCP tool name for calls since `since`
+    pub async fn tool_usage(&self, since: DateTime<Utc>) -> Result<HashMap<String, ToolUsage>, AuditError> {
+        let mut by_tool: HashMap<String, Vec<McpAuditEntry>> = HashMap::new();
+        for entry in self.calls_since(None, since).await? {
+            by_tool.entry(entry.tool_name.clone()).or_default().push(entry);
+        }
+        Ok(by_tool.into_iter().map(|(tool_name, entries)| (tool_name, summarize_usage(&entries))).collect())
+    }
+
+    /// Summary and time-series buckets for one MCP tool over `range`
+    pub async fn tool_analytics(&self, tool_name: &str, range: AnalyticsRange) -> Result<ToolAnalytics, AuditError> {
+        let until = Utc::now();
+        let since = until - range.duration();
+        let entries = self.calls_since(Some(tool_name), since).await?;
+
+        Ok(ToolAnalytics {
+            tool_name: tool_name.to_string(),
+            range,
+            since,
+            until,
+            summary: summarize_usage(&entries),
+            buckets: usage_buckets(&entries, since, until, range.bucket()),
+        })
+    }
+
+    /// Export matching entries as JSON or CSV
+    pub async fn export(&self, filter: &AuditLogFilter, format: AuditExportFormat) -> Result<String, AuditError> {
+        let entries = self.query(filter).await?;
+        match format {
+            AuditExportFormat::Json => Ok(serde_json::to_string_pretty(&entries)?),
+            AuditExportFormat::Csv => {
+                let mut csv = String::from("id,created_at,tool_name,caller,success,latency_ms,result_bytes,error,arguments\n");
+                for entry in &entries {
+                    let fields = [
+                        entry.id.clone(),
+                        entry.created_at.to_rfc3339(),
+                        entry.tool_name.clone(),
+                        entry.caller.clone(),
+                        entry.success.to_string(),
+                        entry.latency_ms.to_string(),
+                        entry.result_bytes.to_string(),
+                        entry.error.clone().unwrap_or_default(),
+                        entry.arguments.to_string(),
+                    ];
+                    let line: Vec<String> = fields.iter().map(|f| csv_field(f)).collect();
+                    csv.push_str(&line.join(","));
+                    csv.push('\n');
+                }
+                Ok(csv)
+            }
+        }
+    }
+}
+
+/// Quote a CSV field when needed
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use crate::services::sql::SqlConfig;
+
+    async fn service(temp_dir: &TempDir) -> AuditService {
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        AuditService::new(Arc::new(sql_service))
+    }
+
+    #[tokio::test]
+    async fn test_record_and_query() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = service(&temp_dir).await;
+
+        let mut ok = McpAuditEntry::new("kb.hybrid_search", &json!({"query": "install, upgrade"}), "agent-a");
+        ok.latency_ms = 12;
+        ok.result_bytes = 2048;
+        service.record(&ok).await.unwrap();
+
+        let mut failed = McpAuditEntry::new("kb.stats", &json!({}), "agent-b");
+        failed.success = false;
+        failed.error = Some("Rate limit exceeded".to_string());
+        service.record(&failed).await.unwrap();
+
+        assert_eq!(service.query(&AuditLogFilter::default()).await.unwrap().len(), 2);
+
+        let filter = AuditLogFilter { success: Some(false), ..Default::default() };
+        let entries = service.query(&filter).await.unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].caller, "agent-b");
+
+        let filter = AuditLogFilter { tool_name: Some("kb.hybrid_search".to_string()), ..Default::default() };
+        let csv = service.export(&filter, AuditExportFormat::Csv).await.unwrap();
+        let lines: Vec<&str> = csv.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].contains("\"{\"\"query\"\":\"\"install, upgrade\"\"}\""));
+    }
+
+    #[tokio::test]
+    async fn test_tool_usage() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = service(&temp_dir).await;
+        for (latency_ms, success) in [(10, true), (30, false)] {
+            let mut entry = McpAuditEntry::new("kb.search_docs", &json!({}), "agent");
+            entry.latency_ms = latency_ms;
+            entry.success = success;
+            service.record(&entry).await.unwrap();
+        }
+        service.record(&McpAuditEntry::new("kb.stats", &json!({}), "agent")).await.unwrap();
+
+        let usage = service.tool_usage(Utc::now() - chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(usage.len(), 2);
+        let search = &usage["kb.search_docs"];
+        assert_eq!((search.calls, search.errors, search.p95_latency_ms), (2, 1, 30));
+        assert!(search.last_used.is_some());
+
+        assert!(service.tool_usage(Utc::now() + chrono::Duration::hours(1)).await.unwrap().is_empty());
+
+        let analytics = service.tool_analytics("kb.search_docs", AnalyticsRange::LastDay).await.unwrap();
+        assert_eq!(analytics.summary.calls, 2);
+        assert_eq!(analytics.buckets.len(), 24);
+        assert_eq!(analytics.buckets.last().unwrap().calls, 2);
+    }
+
+    #[tokio::test]
+    async fn test_log_is_append_only() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = service(&temp_dir).await;
+        service.record(&McpAuditEntry::new("kb.stats", &json!({}), "agent")).await.unwrap();
+
+        let deleted = service.sql_service.with_app_transaction(|conn| {
+            Ok(diesel::delete(mcp_audit_log::table).execute(conn)?)
+        }).await;
+        assert!(deleted.is_err());
+        assert_eq!(service.query(&AuditLogFilter::default()).await.unwrap().len(), 1);
+    }
+}
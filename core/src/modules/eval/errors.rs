@@ -0,0 +1,34 @@
+/*!
+ * Evaluation Domain Errors
+ *
+ * Domain-specific error types for golden-dataset evaluation.
+ */
+
+use crate::modules::kb::KbError;
+use crate::services::sql::SqlError;
+use crate::services::vector::VectorDbError;
+
+/// Evaluation Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum EvalError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Vector database error: {0}")]
+    VectorError(#[from] VectorDbError),
+
+    #[error("Knowledge base error: {0}")]
+    KbError(#[from] KbError),
+
+    #[error("No golden queries registered for KB: {0}")]
+    NoGoldenQueries(String),
+
+    #[error("Golden query not found: {0}")]
+    GoldenQueryNotFound(String),
+
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
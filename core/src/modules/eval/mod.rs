@@ -0,0 +1,16 @@
+/*!
+ * Evaluation Domain Module
+ *
+ * Golden-dataset regression evaluation: golden question → chunk mappings per
+ * KB and metric deltas between KB versions, persisted in SQL.
+ */
+
+pub mod service;
+pub mod models;
+pub mod schema;
+pub mod errors;
+
+// Re-export public types
+pub use service::EvalService;
+pub use models::*;
+pub use errors::EvalError;
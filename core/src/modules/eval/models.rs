@@ -0,0 +1,93 @@
+/*!
+ * Evaluation Domain Models
+ *
+ * Data structures for golden queries, evaluation runs and metric deltas.
+ */
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+pub use crate::modules::pipeline::steps::eval::RetrievalMetrics;
+
+/// Evaluation configuration
+#[derive(Debug, Clone)]
+pub struct EvalConfig {
+    pub top_k: usize,
+    pub max_regression: f64,    // Allowed drop per metric before a run fails
+}
+
+impl Default for EvalConfig {
+    fn default() -> Self {
+        Self {
+            top_k: 5,
+            max_regression: 0.05,
+        }
+    }
+}
+
+/// Golden question with the chunks that should answer it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GoldenQuery {
+    pub id: String,
+    pub kb_id: String,
+    pub question: String,
+    pub relevant_chunk_ids: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Metric changes relative to the baseline version (positive = better)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricDeltas {
+    pub recall_at_k: f64,
+    pub mrr: f64,
+    pub ndcg_at_k: f64,
+}
+
+impl MetricDeltas {
+    pub fn between(current: &RetrievalMetrics, baseline: &RetrievalMetrics) -> Self {
+        Self {
+            recall_at_k: current.recall_at_k - baseline.recall_at_k,
+            mrr: current.mrr - baseline.mrr,
+            ndcg_at_k: current.ndcg_at_k - baseline.ndcg_at_k,
+        }
+    }
+
+    /// Largest drop across metrics (0.0 if nothing regressed)
+    pub fn worst_regression(&self) -> f64 {
+        [self.recall_at_k, self.mrr, self.ndcg_at_k]
+            .into_iter()
+            .fold(0.0_f64, |worst, delta| worst.max(-delta))
+    }
+}
+
+/// Evaluation run for a KB version
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalRun {
+    pub id: String,
+    pub kb_id: String,
+    pub kb_version: i32,
+    pub baseline_version: Option<i32>,
+    pub top_k: usize,
+    pub metrics: RetrievalMetrics,
+    pub deltas: Option<MetricDeltas>,
+    pub passed: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_metric_deltas() {
+        let baseline = RetrievalMetrics { recall_at_k: 0.8, mrr: 0.6, ndcg_at_k: 0.7, query_count: 10 };
+        let current = RetrievalMetrics { recall_at_k: 0.9, mrr: 0.5, ndcg_at_k: 0.7, query_count: 10 };
+
+        let deltas = MetricDeltas::between(&current, &baseline);
+        assert!((deltas.recall_at_k - 0.1).abs() < 1e-9);
+        assert!((deltas.worst_regression() - 0.1).abs() < 1e-9);
+
+        let improved = MetricDeltas::between(&baseline, &baseline);
+        assert_eq!(improved.worst_regression(), 0.0);
+    }
+}
@@ -0,0 +1,94 @@
+/*!
+ * Evaluation Domain Schema
+ *
+ * Diesel row types for the eval_golden_queries and eval_runs tables.
+ */
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::{eval_golden_queries, eval_runs};
+use super::errors::EvalError;
+use super::models::{EvalRun, GoldenQuery};
+
+/// Row in eval_golden_queries
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = eval_golden_queries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct GoldenQueryRow {
+    pub id: String,
+    pub kb_id: String,
+    pub question: String,
+    pub relevant_chunk_ids: String,   // JSON array
+    pub created_at: NaiveDateTime,
+}
+
+impl GoldenQueryRow {
+    pub fn from_model(query: &GoldenQuery) -> Result<Self, EvalError> {
+        Ok(Self {
+            id: query.id.clone(),
+            kb_id: query.kb_id.clone(),
+            question: query.question.clone(),
+            relevant_chunk_ids: serde_json::to_string(&query.relevant_chunk_ids)?,
+            created_at: query.created_at.naive_utc(),
+        })
+    }
+
+    pub fn into_model(self) -> Result<GoldenQuery, EvalError> {
+        Ok(GoldenQuery {
+            id: self.id,
+            kb_id: self.kb_id,
+            question: self.question,
+            relevant_chunk_ids: serde_json::from_str(&self.relevant_chunk_ids)?,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+        })
+    }
+}
+
+/// Row in eval_runs
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = eval_runs)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct EvalRunRow {
+    pub id: String,
+    pub kb_id: String,
+    pub kb_version: i32,
+    pub baseline_version: Option<i32>,
+    pub top_k: i32,
+    pub query_count: i32,
+    pub metrics: String,              // JSON RetrievalMetrics
+    pub deltas: Option<String>,       // JSON MetricDeltas
+    pub passed: bool,
+    pub created_at: NaiveDateTime,
+}
+
+impl EvalRunRow {
+    pub fn from_model(run: &EvalRun) -> Result<Self, EvalError> {
+        Ok(Self {
+            id: run.id.clone(),
+            kb_id: run.kb_id.clone(),
+            kb_version: run.kb_version,
+            baseline_version: run.baseline_version,
+            top_k: run.top_k as i32,
+            query_count: run.metrics.query_count as i32,
+            metrics: serde_json::to_string(&run.metrics)?,
+            deltas: run.deltas.as_ref().map(serde_json::to_string).transpose()?,
+            passed: run.passed,
+            created_at: run.created_at.naive_utc(),
+        })
+    }
+
+    pub fn into_model(self) -> Result<EvalRun, EvalError> {
+        Ok(EvalRun {
+            id: self.id,
+            kb_id: self.kb_id,
+            kb_version: self.kb_version,
+            baseline_version: self.baseline_version,
+            top_k: self.top_k.max(0) as usize,
+            metrics: serde_json::from_str(&self.metrics)?,
+            deltas: self.deltas.as_deref().map(serde_json::from_str).transpose()?,
+            passed: self.passed,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+        })
+    }
+}
@@ -0,0 +1,295 @@
+/*!
+ * Evaluation Domain Service
+ *
+ * Runs registered golden queries against a KB version's index and compares
+ * the resulting retrieval metrics with the previous version so reindexes can
+ * be gated before the new version goes live.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use chrono::Utc;
+use diesel::prelude::*;
+use tracing::{info, warn};
+
+use super::errors::EvalError;
+use super::models::*;
+use super::schema::{EvalRunRow, GoldenQueryRow};
+use crate::modules::kb::KbService;
+use crate::modules::pipeline::steps::eval::{aggregate_scores, score_query, QueryScore};
+use crate::schemas::schema::{eval_golden_queries, eval_runs};
+use crate::services::sql::SqlService;
+
+/// Golden-dataset evaluation service
+pub struct EvalService {
+    sql_service: Arc<SqlService>,
+    kb_service: Arc<dyn KbService>,
+    config: EvalConfig,
+}
+
+impl EvalService {
+    pub fn new(
+        sql_service: Arc<SqlService>,
+        kb_service: Arc<dyn KbService>,
+        config: EvalConfig,
+    ) -> Self {
+        Self {
+            sql_service,
+            kb_service,
+            config,
+        }
+    }
+
+    /// Register a golden question for a KB
+    pub async fn add_golden_query(
+        &self,
+        kb_id: &str,
+        question: &str,
+        relevant_chunk_ids: Vec<String>,
+    ) -> Result<GoldenQuery, EvalError> {
+        if question.trim().is_empty() {
+            return Err(EvalError::ValidationError("Question cannot be empty".to_string()));
+        }
+        if relevant_chunk_ids.is_empty() {
+            return Err(EvalError::ValidationError("At least one relevant chunk is required".to_string()));
+        }
+
+        let query = GoldenQuery {
+            id: format!("gq_{}", uuid::Uuid::new_v4().simple()),
+            kb_id: kb_id.to_string(),
+            question: question.trim().to_string(),
+            relevant_chunk_ids,
+            created_at: Utc::now(),
+        };
+        let row = GoldenQueryRow::from_model(&query)?;
+
+        self.sql_service.with_app_transaction(|conn| {
+            diesel::insert_into(eval_golden_queries::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        Ok(query)
+    }
+
+    /// List golden questions for a KB
+    pub async fn list_golden_queries(&self, kb_id: &str) -> Result<Vec<GoldenQuery>, EvalError> {
+        let kb_id = kb_id.to_string();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            Ok(eval_golden_queries::table
+                .filter(eval_golden_queries::kb_id.eq(kb_id))
+                .order(eval_golden_queries::created_at.asc())
+                .select(GoldenQueryRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(GoldenQueryRow::into_model).collect()
+    }
+
+    /// Remove a golden question
+    pub async fn remove_golden_query(&self, query_id: &str) -> Result<(), EvalError> {
+        let id = query_id.to_string();
+        let deleted = self.sql_service.with_app_transaction(move |conn| {
+            Ok(diesel::delete(eval_golden_queries::table.filter(eval_golden_queries::id.eq(id)))
+                .execute(conn)?)
+        }).await?;
+
+        if deleted == 0 {
+            return Err(EvalError::GoldenQueryNotFound(query_id.to_string()));
+        }
+        Ok(())
+    }
+
+    /// Evaluation history for a KB, newest version first
+    pub async fn list_runs(&self, kb_id: &str) -> Result<Vec<EvalRun>, EvalError> {
+        let kb_id = kb_id.to_string();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            Ok(eval_runs::table
+                .filter(eval_runs::kb_id.eq(kb_id))
+                .order((eval_runs::kb_version.desc(), eval_runs::created_at.desc()))
+                .select(EvalRunRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(EvalRunRow::into_model).collect()
+    }
+
+    /// Latest run for the closest version before `version`
+    async fn baseline_run(&self, kb_id: &str, version: i32) -> Result<Option<EvalRun>, EvalError> {
+        let kb_id = kb_id.to_string();
+        let row = self.sql_service.with_app_transaction(move |conn| {
+            Ok(eval_runs::table
+                .filter(eval_runs::kb_id.eq(kb_id))
+                .filter(eval_runs::kb_version.lt(version))
+                .order((eval_runs::kb_version.desc(), eval_runs::created_at.desc()))
+                .select(EvalRunRow::as_select())
+                .first(conn)
+                .optional()?)
+        }).await?;
+
+        row.map(EvalRunRow::into_model).transpose()
+    }
+
+    /// Evaluate a KB version against its golden set and persist metric deltas
+    ///
+    /// Queries search the version's own index, so a candidate can be judged
+    /// before it is activated.
+    pub async fn run_regression(&self, kb_id: &str, version: i32) -> Result<EvalRun, EvalError> {
+        let golden = self.list_golden_queries(kb_id).await?;
+        if golden.is_empty() {
+            return Err(EvalError::NoGoldenQueries(kb_id.to_string()));
+        }
+
+        let mut scores = Vec::with_capacity(golden.len());
+        for query in &golden {
+            let results = self.kb_service
+                .hybrid_search_version(kb_id, version, &query.question, self.config.top_k, None)
+                .await?;
+            let ranked_ids: Vec<Vec<String>> = results.into_iter().map(|r| vec![r.chunk_id]).collect();
+            let relevant: HashSet<String> = query.relevant_chunk_ids.iter().cloned().collect();
+
+            let (recall_at_k, reciprocal_rank, ndcg_at_k) = score_query(&ranked_ids, &relevant, self.config.top_k);
+            scores.push(QueryScore {
+                query: query.question.clone(),
+                recall_at_k,
+                reciprocal_rank,
+                ndcg_at_k,
+            });
+        }
+
+        let metrics = aggregate_scores(&scores);
+        let baseline = self.baseline_run(kb_id, version).await?;
+        let deltas = baseline.as_ref().map(|b| MetricDeltas::between(&metrics, &b.metrics));
+        let passed = deltas
+            .as_ref()
+            .map(|d| d.worst_regression() <= self.config.max_regression)
+            .unwrap_or(true);
+
+        let run = EvalRun {
+            id: format!("eval_{}", uuid::Uuid::new_v4().simple()),
+            kb_id: kb_id.to_string(),
+            kb_version: version,
+            baseline_version: baseline.map(|b| b.kb_version),
+            top_k: self.config.top_k,
+            metrics,
+            deltas,
+            passed,
+            created_at: Utc::now(),
+        };
+
+        let row = EvalRunRow::from_model(&run)?;
+        self.sql_service.with_app_transaction(|conn| {
+            diesel::insert_into(eval_runs::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        if run.passed {
+            info!("Eval regression for KB {} v{} passed (ndcg@{}={:.3})", kb_id, version, run.top_k, run.metrics.ndcg_at_k);
+        } else {
+            warn!("Eval regression for KB {} v{} failed against v{:?}", kb_id, version, run.baseline_version);
+        }
+
+        Ok(run)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::modules::kb::{KbCreateConfig, KbServiceImpl, KbVersion};
+    use crate::modules::pipeline::{PipelineDocument, PipelineRunOutput, PipelineRunReport, PipelineSpec, StepData};
+    use crate::services::sql::SqlConfig;
+    use crate::services::vector::{VectorDbConfig, VectorDbService};
+    use crate::state::StateManager;
+
+    struct Fixture {
+        _temp_dir: TempDir,
+        sql: Arc<SqlService>,
+        kb_service: Arc<KbServiceImpl>,
+        kb_id: String,
+    }
+
+    async fn fixture() -> Fixture {
+        let temp_dir = TempDir::new().unwrap();
+        let sql = Arc::new(SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let kb_service = Arc::new(KbServiceImpl::new_mvp(sql.clone(), vector_service, Arc::new(StateManager::new())));
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = kb_service.create_collection("Docs", config).await.unwrap();
+        Fixture { _temp_dir: temp_dir, sql, kb_service, kb_id }
+    }
+
+    /// New KB version holding one chunk per (document id, content)
+    async fn build_version(fixture: &Fixture, documents: &[(&str, &str)]) -> KbVersion {
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Ingest".to_string(),
+            kb_id: Some(fixture.kb_id.clone()),
+            steps: Vec::new(),
+            resources: Default::default(),
+            source_roots: Vec::new(),
+        };
+        let output = PipelineRunOutput {
+            data: StepData {
+                documents: documents.iter().map(|(id, content)| PipelineDocument {
+                    id: id.to_string(),
+                    title: id.to_string(),
+                    source_path: format!("/docs/{}.md", id),
+                    content: content.to_string(),
+                    content_hash: String::new(),
+                    license_info: None,
+                    metadata: serde_json::json!({}),
+                }).collect(),
+                chunks: Vec::new(),
+            },
+            report: PipelineRunReport::default(),
+            error: None,
+        };
+        fixture.kb_service.create_version_from_run(&fixture.kb_id, &spec, "run_1", &output).await.unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_regression_requires_golden_queries() {
+        let fixture = fixture().await;
+        let service = EvalService::new(fixture.sql.clone(), fixture.kb_service.clone(), EvalConfig::default());
+
+        let result = service.run_regression(&fixture.kb_id, 1).await;
+        assert!(matches!(result, Err(EvalError::NoGoldenQueries(_))));
+    }
+
+    #[tokio::test]
+    async fn test_regression_between_versions() {
+        let fixture = fixture().await;
+        let service = EvalService::new(fixture.sql.clone(), fixture.kb_service.clone(), EvalConfig::default());
+        service.add_golden_query(&fixture.kb_id, "nightly backups", vec!["backups_0".to_string()]).await.unwrap();
+
+        let v1 = build_version(&fixture, &[("backups", "Nightly backups go to the vault"), ("install", "Run the installer")]).await;
+        let first = service.run_regression(&fixture.kb_id, v1.version).await.unwrap();
+        assert!(first.passed);
+        assert!(first.deltas.is_none());
+        assert_eq!(first.metrics.mrr, 1.0);
+
+        // The candidate is evaluated on its own index, without being activated
+        let v2 = build_version(&fixture, &[("backups", "Copies are kept offsite"), ("install", "Run the installer")]).await;
+        let second = service.run_regression(&fixture.kb_id, v2.version).await.unwrap();
+        assert!(fixture.kb_service.get_active_version(&fixture.kb_id).await.unwrap().is_none());
+
+        assert!(!second.passed);
+        assert_eq!(second.baseline_version, Some(1));
+        assert_eq!(second.deltas.unwrap().mrr, -1.0);
+        assert_eq!(service.list_runs(&fixture.kb_id).await.unwrap().len(), 2);
+
+        let missing = service.run_regression(&fixture.kb_id, 9).await;
+        assert!(matches!(missing, Err(EvalError::KbError(_))));
+    }
+}
@@ -0,0 +1,40 @@
+/*!
+ * Flow Domain Errors
+ *
+ * Domain-specific error types for flow definitions and runs.
+ */
+
+use crate::modules::kb::KbError;
+use crate::services::llm::LlmError;
+use crate::services::sql::SqlError;
+
+/// Flow Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum FlowError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Knowledge base error: {0}")]
+    KbError(#[from] KbError),
+
+    #[error("LLM error: {0}")]
+    LlmError(#[from] LlmError),
+
+    #[error("Flow not found: {0}")]
+    FlowNotFound(String),
+
+    #[error("Invalid flow: {0}")]
+    InvalidSpec(String),
+
+    #[error("Invalid config for {node} node: {message}")]
+    InvalidNodeConfig { node: String, message: String },
+
+    #[error("Flow run failed: {0}")]
+    RunFailed(String),
+
+    #[error("State error: {0}")]
+    StateError(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+}
@@ -0,0 +1,193 @@
+/*!
+ * Flow Execution
+ *
+ * NodeExecutor trait for individual flow nodes and a FlowExecutor that runs
+ * a flow spec node by node (same shape as the pipeline runner), collecting
+ * `FlowNodeMetrics` for each node.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use tracing::{info, warn};
+
+use super::errors::FlowError;
+use super::models::*;
+use crate::services::llm::LlmProvider;
+
+/// Execution context handed to each node
+pub struct NodeContext {
+    pub run_id: String,
+    pub flow_id: String,
+    pub node: FlowNodeConfig,
+    llm: Option<Arc<dyn LlmProvider>>,
+}
+
+impl NodeContext {
+    pub fn new(run_id: &str, spec: &FlowSpec, node: FlowNodeConfig, llm: Option<Arc<dyn LlmProvider>>) -> Self {
+        Self {
+            run_id: run_id.to_string(),
+            flow_id: spec.id.clone(),
+            node,
+            llm,
+        }
+    }
+
+    /// Node-specific configuration
+    pub fn config(&self) -> &serde_json::Value {
+        &self.node.config
+    }
+
+    /// Typed node configuration (missing config means defaults)
+    pub fn parse_config<T: serde::de::DeserializeOwned + Default>(&self) -> Result<T, FlowError> {
+        if self.node.config.is_null() {
+            return Ok(T::default());
+        }
+        serde_json::from_value(self.node.config.clone()).map_err(|e| FlowError::InvalidNodeConfig {
+            node: self.node.node_type.as_str().to_string(),
+            message: e.to_string(),
+        })
+    }
+
+    /// The flow's LLM provider
+    pub fn llm(&self) -> Result<&Arc<dyn LlmProvider>, FlowError> {
+        self.llm.as_ref().ok_or_else(|| FlowError::InvalidNodeConfig {
+            node: self.node.node_type.as_str().to_string(),
+            message: "No LLM provider available for this flow".to_string(),
+        })
+    }
+}
+
+/// Executor for a single flow node type
+#[async_trait]
+pub trait NodeExecutor: Send + Sync {
+    fn node_type(&self) -> FlowNodeType;
+
+    async fn execute(&self, ctx: &NodeContext, data: FlowData) -> Result<NodeOutcome, FlowError>;
+}
+
+/// Sequential flow executor
+pub struct FlowExecutor {
+    executors: HashMap<FlowNodeType, Arc<dyn NodeExecutor>>,
+}
+
+impl FlowExecutor {
+    pub fn new() -> Self {
+        Self {
+            executors: HashMap::new(),
+        }
+    }
+
+    /// Register an executor for its node type
+    pub fn register(&mut self, executor: Arc<dyn NodeExecutor>) {
+        self.executors.insert(executor.node_type(), executor);
+    }
+
+    /// Run all nodes of a flow, stopping at the first failure
+    pub async fn run(
+        &self,
+        spec: &FlowSpec,
+        run_id: &str,
+        query: &str,
+        llm: Option<Arc<dyn LlmProvider>>,
+    ) -> FlowRunOutput {
+        info!("Starting flow run {} for flow {}", run_id, spec.id);
+
+        let mut data = FlowData::new(query);
+        let mut nodes = Vec::with_capacity(spec.nodes.len());
+
+        for node in &spec.nodes {
+            let started_at = Utc::now();
+            let ctx = NodeContext::new(run_id, spec, node.clone(), llm.clone());
+            let result = match self.executors.get(&node.node_type) {
+                Some(executor) => executor.execute(&ctx, data.clone()).await,
+                None => Err(FlowError::InvalidSpec(format!("No executor registered for node: {}", node.node_type.as_str()))),
+            };
+
+            let (items_processed, details) = match &result {
+                Ok(outcome) => (outcome.items_processed, outcome.details.clone()),
+                Err(_) => (0, serde_json::json!({})),
+            };
+            nodes.push(FlowNodeMetrics {
+                node_type: node.node_type,
+                started_at,
+                duration_ms: (Utc::now() - started_at).num_milliseconds().max(0) as u64,
+                items_processed,
+                success: result.is_ok(),
+                error: result.as_ref().err().map(|e| e.to_string()),
+                details,
+            });
+
+            match result {
+                Ok(outcome) => data = outcome.data,
+                Err(e) => {
+                    warn!("Flow run {} failed at node {}: {}", run_id, node.node_type.as_str(), e);
+                    return FlowRunOutput {
+                        run_id: run_id.to_string(),
+                        flow_id: spec.id.clone(),
+                        data,
+                        nodes,
+                        error: Some(e.to_string()),
+                    };
+                }
+            }
+        }
+
+        info!("Flow run {} completed ({} nodes)", run_id, nodes.len());
+        FlowRunOutput {
+            run_id: run_id.to_string(),
+            flow_id: spec.id.clone(),
+            data,
+            nodes,
+            error: None,
+        }
+    }
+}
+
+impl Default for FlowExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    struct UppercaseRewrite;
+
+    #[async_trait]
+    impl NodeExecutor for UppercaseRewrite {
+        fn node_type(&self) -> FlowNodeType {
+            FlowNodeType::QueryRewrite
+        }
+
+        async fn execute(&self, _ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+            data.queries = vec![data.query.to_uppercase()];
+            Ok(NodeOutcome { data, items_processed: 1, details: json!({}) })
+        }
+    }
+
+    fn spec() -> FlowSpec {
+        serde_json::from_value(json!({
+            "id": "flow_1",
+            "name": "Test",
+            "nodes": [{ "node_type": "query_rewrite" }, { "node_type": "retrieve" }],
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_run_stops_at_unregistered_node() {
+        let mut executor = FlowExecutor::new();
+        executor.register(Arc::new(UppercaseRewrite));
+
+        let output = executor.run(&spec(), "run_1", "how to install", None).await;
+        assert!(!output.is_success());
+        assert_eq!(output.data.queries, vec!["HOW TO INSTALL"]);
+        assert_eq!(output.nodes.len(), 2);
+        assert!(output.nodes[0].success);
+        assert!(output.nodes[1].error.as_deref().unwrap().contains("retrieve"));
+    }
+}
@@ -0,0 +1,20 @@
+/*!
+ * Flow Domain Module
+ *
+ * Declarative multi-step query flows (query rewrite → retrieve over N KBs →
+ * fusion → rerank → generate), executed node by node like pipeline steps.
+ */
+
+pub mod models;
+pub mod errors;
+pub mod executor;
+pub mod nodes;
+pub mod schema;
+pub mod service;
+
+// Re-export public types
+pub use models::*;
+pub use errors::FlowError;
+pub use executor::{FlowExecutor, NodeContext, NodeExecutor};
+pub use nodes::{FusionNodeExecutor, GenerateNodeExecutor, QueryRewriteNodeExecutor, RerankNodeExecutor, RetrieveNodeExecutor};
+pub use service::FlowService;
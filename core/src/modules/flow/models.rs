@@ -0,0 +1,269 @@
+/*!
+ * Flow Domain Models
+ *
+ * Flow definitions (stored as JSON in `flows.definition`), the data passed
+ * between nodes and per-node run metrics.
+ */
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use super::errors::FlowError;
+use crate::modules::eval::{MetricDeltas, RetrievalMetrics};
+use crate::modules::kb::GroundedAnswer;
+use crate::schemas::SearchResult;
+use crate::services::llm::LlmProviderConfig;
+
+/// Flow node types
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlowNodeType {
+    QueryRewrite,
+    Retrieve,
+    Fusion,
+    Rerank,
+    Generate,
+}
+
+impl FlowNodeType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlowNodeType::QueryRewrite => "query_rewrite",
+            FlowNodeType::Retrieve => "retrieve",
+            FlowNodeType::Fusion => "fusion",
+            FlowNodeType::Rerank => "rerank",
+            FlowNodeType::Generate => "generate",
+        }
+    }
+
+    /// Nodes that call the flow's LLM provider
+    pub fn uses_llm(&self) -> bool {
+        matches!(self, FlowNodeType::QueryRewrite | FlowNodeType::Generate)
+    }
+}
+
+/// Query transformation performed by a query_rewrite node (`mode` in its config)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryTransform {
+    #[default]
+    Rewrite,        // One standalone search query
+    Expand,         // Question plus keywords/synonyms
+    Hyde,           // Hypothetical answer passage used as the query
+    MultiQuery,     // Several alternative queries, merged by a fusion node
+}
+
+impl QueryTransform {
+    /// Whether the original question is searched alongside the output by default
+    pub fn keeps_original(&self) -> bool {
+        matches!(self, QueryTransform::Hyde | QueryTransform::MultiQuery)
+    }
+}
+
+/// Node configuration within a flow definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowNodeConfig {
+    pub node_type: FlowNodeType,
+    #[serde(default)]
+    pub config: serde_json::Value,      // Node-specific, see nodes.rs
+}
+
+/// Flow definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowSpec {
+    #[serde(default)]
+    pub id: String,                     // Assigned on save when empty
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub nodes: Vec<FlowNodeConfig>,     // Executed in order
+    #[serde(default)]
+    pub llm: Option<LlmProviderConfig>, // Provider for rewrite/generate nodes; local when unset
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl FlowSpec {
+    /// Structural checks; node configs are validated when the node runs
+    pub fn validate(&self) -> Result<(), FlowError> {
+        if self.name.trim().is_empty() {
+            return Err(FlowError::InvalidSpec("Flow name cannot be empty".to_string()));
+        }
+        let Some(first_retrieve) = self.nodes.iter().position(|n| n.node_type == FlowNodeType::Retrieve) else {
+            return Err(FlowError::InvalidSpec("Flow needs at least one retrieve node".to_string()));
+        };
+        // Everything downstream works on retrieved results
+        if let Some(node) = self.nodes[..first_retrieve].iter().find(|n| n.node_type != FlowNodeType::QueryRewrite) {
+            return Err(FlowError::InvalidSpec(format!("{} node must come after retrieve", node.node_type.as_str())));
+        }
+        if let Some(position) = self.nodes.iter().position(|n| n.node_type == FlowNodeType::Generate) {
+            if position != self.nodes.len() - 1 {
+                return Err(FlowError::InvalidSpec("generate must be the last node".to_string()));
+            }
+        }
+        Ok(())
+    }
+
+    pub fn uses_llm(&self) -> bool {
+        self.nodes.iter().any(|n| n.node_type.uses_llm())
+    }
+
+    /// Transforms applied by the query_rewrite nodes, in order
+    pub fn query_transforms(&self) -> Vec<QueryTransform> {
+        self.nodes.iter()
+            .filter(|n| n.node_type == FlowNodeType::QueryRewrite)
+            .map(|n| n.config.get("mode")
+                .and_then(|mode| serde_json::from_value(mode.clone()).ok())
+                .unwrap_or_default())
+            .collect()
+    }
+
+    /// The flow without generation, used to score retrieval
+    pub fn retrieval_only(&self) -> FlowSpec {
+        let mut spec = self.clone();
+        spec.nodes.retain(|n| n.node_type != FlowNodeType::Generate);
+        spec
+    }
+
+    /// The retrieval flow without query transforms, the baseline for eval
+    pub fn without_query_transforms(&self) -> FlowSpec {
+        let mut spec = self.retrieval_only();
+        spec.nodes.retain(|n| n.node_type != FlowNodeType::QueryRewrite);
+        spec
+    }
+}
+
+/// Data flowing between nodes
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct FlowData {
+    pub query: String,                      // Original question, used for generation
+    pub queries: Vec<String>,               // Retrieval queries (rewrite output, else the question)
+    pub result_sets: Vec<Vec<SearchResult>>, // One ranked list per query × KB, input to fusion
+    pub results: Vec<SearchResult>,         // Current candidates
+    pub answer: Option<GroundedAnswer>,
+}
+
+impl FlowData {
+    pub fn new(query: &str) -> Self {
+        Self {
+            query: query.to_string(),
+            queries: vec![query.to_string()],
+            ..Default::default()
+        }
+    }
+}
+
+/// Result of a single node execution
+#[derive(Debug, Clone)]
+pub struct NodeOutcome {
+    pub data: FlowData,
+    pub items_processed: usize,
+    pub details: serde_json::Value,
+}
+
+/// Per-node run metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowNodeMetrics {
+    pub node_type: FlowNodeType,
+    pub started_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub items_processed: usize,
+    pub success: bool,
+    pub error: Option<String>,
+    pub details: serde_json::Value,
+}
+
+/// Output of a flow run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowRunOutput {
+    pub run_id: String,
+    pub flow_id: String,
+    pub data: FlowData,
+    pub nodes: Vec<FlowNodeMetrics>,
+    pub error: Option<String>,
+}
+
+impl FlowRunOutput {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Scores for one golden question run through a flow
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowQueryScore {
+    pub question: String,
+    pub queries: Vec<String>,       // Retrieval queries after transformation
+    pub recall_at_k: f64,
+    pub reciprocal_rank: f64,
+    pub ndcg_at_k: f64,
+}
+
+/// Retrieval quality of a flow's query transforms on a golden set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FlowEvalReport {
+    pub flow_id: String,
+    pub kb_id: String,
+    pub k: usize,
+    pub transforms: Vec<QueryTransform>,
+    pub metrics: RetrievalMetrics,      // With the flow's query transforms
+    pub baseline: RetrievalMetrics,     // Same flow, original question only
+    pub deltas: MetricDeltas,           // metrics - baseline (positive = transforms help)
+    pub queries: Vec<FlowQueryScore>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn spec(nodes: &[&str]) -> FlowSpec {
+        serde_json::from_value(json!({
+            "name": "Support answers",
+            "nodes": nodes.iter().map(|n| json!({ "node_type": n })).collect::<Vec<_>>(),
+        })).unwrap()
+    }
+
+    #[test]
+    fn test_validate_node_order() {
+        assert!(spec(&["query_rewrite", "retrieve", "fusion", "rerank", "generate"]).validate().is_ok());
+        assert!(spec(&["retrieve", "retrieve", "fusion"]).validate().is_ok());
+
+        assert!(matches!(spec(&["fusion", "generate"]).validate(), Err(FlowError::InvalidSpec(_))));
+        assert!(matches!(spec(&["rerank", "retrieve"]).validate(), Err(FlowError::InvalidSpec(_))));
+        assert!(matches!(spec(&["retrieve", "generate", "rerank"]).validate(), Err(FlowError::InvalidSpec(_))));
+    }
+
+    #[test]
+    fn test_spec_defaults() {
+        let spec = spec(&["retrieve"]);
+        assert!(spec.enabled);
+        assert!(spec.id.is_empty());
+        assert!(spec.llm.is_none());
+        assert!(!spec.uses_llm());
+    }
+
+    #[test]
+    fn test_query_transforms_and_eval_variants() {
+        let spec: FlowSpec = serde_json::from_value(json!({
+            "name": "Multi",
+            "nodes": [
+                { "node_type": "query_rewrite", "config": { "mode": "multi_query", "num_queries": 2 } },
+                { "node_type": "query_rewrite" },
+                { "node_type": "retrieve" },
+                { "node_type": "fusion" },
+                { "node_type": "generate" },
+            ],
+        })).unwrap();
+
+        assert_eq!(spec.query_transforms(), vec![QueryTransform::MultiQuery, QueryTransform::Rewrite]);
+        assert_eq!(spec.retrieval_only().nodes.len(), 4);
+        let baseline = spec.without_query_transforms();
+        assert_eq!(baseline.nodes.len(), 2);
+        assert!(!baseline.uses_llm());
+    }
+}
@@ -0,0 +1,538 @@
+/*!
+ * Flow Node Executors
+ *
+ * Built-in nodes: query transformation (rewrite, keyword expansion, HyDE,
+ * multi-query), retrieval over one or more KBs, RRF fusion, rerank (with
+ * optional MMR diversification) and grounded generation with citations.
+ * MVP: rerank scores lexical overlap with the question; upgrade path to a
+ * cross-encoder model once the embedding worker serves one.
+ */
+
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use async_trait::async_trait;
+use futures::future::try_join_all;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::errors::FlowError;
+use super::executor::{NodeContext, NodeExecutor};
+use super::models::*;
+use crate::modules::graph::DEFAULT_EXPANSION_TERMS;
+use crate::modules::kb::answer::{build_grounded_prompt, cite_answer, NO_SOURCES_ANSWER};
+use crate::modules::kb::mmr::terms;
+use crate::modules::kb::{pack_context, ContextBudget, ContextExpansion, EstimatingTokenizer, SearchMode};
+use crate::modules::kb::{mmr_rerank, reciprocal_rank_fusion, KbService, DEFAULT_RRF_K};
+use crate::schemas::SearchResult;
+use crate::services::generation::GenerationParams;
+
+/// Transforms the question into retrieval queries (rewrite, keyword
+/// expansion, HyDE or multi-query)
+pub struct QueryRewriteNodeExecutor;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct QueryRewriteConfig {
+    mode: QueryTransform,
+    instructions: Option<String>,   // Replaces the default instructions for the mode
+    num_queries: usize,             // multi_query: alternative queries to generate
+    keep_original: Option<bool>,    // Also search the original question (default: hyde, multi_query)
+    synonyms: HashMap<String, Vec<String>>, // expand: static term expansions, skips the LLM when set
+}
+
+impl Default for QueryRewriteConfig {
+    fn default() -> Self {
+        Self {
+            mode: QueryTransform::Rewrite,
+            instructions: None,
+            num_queries: 3,
+            keep_original: None,
+            synonyms: HashMap::new(),
+        }
+    }
+}
+
+const DEFAULT_REWRITE_INSTRUCTIONS: &str =
+    "Rewrite the question below as a concise, self-contained search query. Reply with the query only.";
+const DEFAULT_EXPAND_INSTRUCTIONS: &str =
+    "List search keywords and synonyms for the question below, separated by commas. Reply with the keywords only.";
+const DEFAULT_HYDE_INSTRUCTIONS: &str =
+    "Write a short passage from a documentation page that answers the question below. Reply with the passage only.";
+const DEFAULT_MULTI_QUERY_INSTRUCTIONS: &str =
+    "Write different search queries that would find information to answer the question below, one per line. Reply with the queries only.";
+
+/// First non-empty line without surrounding quotes
+fn first_line(text: &str) -> Option<String> {
+    text.lines()
+        .map(|line| line.trim().trim_matches('"').trim())
+        .find(|line| !line.is_empty())
+        .map(str::to_string)
+}
+
+/// One query per line, without list markers, numbering or quotes
+fn query_lines(text: &str) -> Vec<String> {
+    text.lines()
+        .map(|line| {
+            let line = line.trim();
+            let line = line.strip_prefix(['-', '*']).unwrap_or_else(|| {
+                // "1." / "2)" numbering, but not queries that start with a number
+                let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+                match line[digits..].strip_prefix(['.', ')']) {
+                    Some(rest) if digits > 0 => rest,
+                    _ => line,
+                }
+            });
+            line.trim().trim_matches('"').trim().to_string()
+        })
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+/// Question followed by keywords it does not already contain
+fn expand_query(query: &str, keywords: impl IntoIterator<Item = String>) -> String {
+    let mut seen = terms(query);
+    let mut expanded = query.to_string();
+    for keyword in keywords {
+        let keyword = keyword.trim().trim_matches('"').trim().to_string();
+        if !keyword.is_empty() && seen.insert(keyword.to_lowercase()) {
+            expanded.push(' ');
+            expanded.push_str(&keyword);
+        }
+    }
+    expanded
+}
+
+impl QueryRewriteNodeExecutor {
+    async fn complete(ctx: &NodeContext, instructions: &str, query: &str, answer_label: &str, max_tokens: u32) -> Result<String, FlowError> {
+        let prompt = format!("{}\n\nQuestion: {}\n{}:", instructions, query, answer_label);
+        let params = GenerationParams { max_tokens, temperature: 0.0, ..Default::default() };
+        Ok(ctx.llm()?.generate(&prompt, &params).await?.text)
+    }
+}
+
+#[async_trait]
+impl NodeExecutor for QueryRewriteNodeExecutor {
+    fn node_type(&self) -> FlowNodeType {
+        FlowNodeType::QueryRewrite
+    }
+
+    async fn execute(&self, ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+        let config: QueryRewriteConfig = ctx.parse_config()?;
+        let instructions = config.instructions.as_deref();
+        let query = data.query.clone();
+
+        // Keep the original question whenever the model returns nothing usable
+        let mut queries = match config.mode {
+            QueryTransform::Rewrite => {
+                let text = Self::complete(ctx, instructions.unwrap_or(DEFAULT_REWRITE_INSTRUCTIONS), &query, "Search query", 64).await?;
+                vec![first_line(&text).unwrap_or_else(|| query.clone())]
+            }
+            QueryTransform::Expand => {
+                let keywords: Vec<String> = if config.synonyms.is_empty() {
+                    let text = Self::complete(ctx, instructions.unwrap_or(DEFAULT_EXPAND_INSTRUCTIONS), &query, "Keywords", 64).await?;
+                    text.split([',', '\n']).map(str::to_string).collect()
+                } else {
+                    let query_terms = terms(&query);
+                    config.synonyms.iter()
+                        .filter(|(term, _)| query_terms.contains(&term.to_lowercase()))
+                        .flat_map(|(_, alternatives)| alternatives.iter().cloned())
+                        .collect()
+                };
+                vec![expand_query(&query, keywords)]
+            }
+            QueryTransform::Hyde => {
+                // MVP: the hypothetical passage is searched as text; it is embedded
+                // once the hybrid search runs on real query vectors
+                let text = Self::complete(ctx, instructions.unwrap_or(DEFAULT_HYDE_INSTRUCTIONS), &query, "Passage", 256).await?;
+                let passage = text.trim();
+                vec![if passage.is_empty() { query.clone() } else { passage.to_string() }]
+            }
+            QueryTransform::MultiQuery => {
+                let instructions = instructions.unwrap_or(DEFAULT_MULTI_QUERY_INSTRUCTIONS);
+                let text = Self::complete(ctx, &format!("{} Write {} queries.", instructions, config.num_queries), &query, "Queries", 256).await?;
+                let mut generated = query_lines(&text);
+                generated.truncate(config.num_queries);
+                generated
+            }
+        };
+
+        if config.keep_original.unwrap_or(config.mode.keeps_original()) {
+            queries.insert(0, query.clone());
+        }
+        // Duplicates would only double-count lists in fusion
+        let mut seen = HashSet::new();
+        queries.retain(|q| seen.insert(q.to_lowercase()));
+        if queries.is_empty() {
+            queries.push(query);
+        }
+
+        data.queries = queries;
+        Ok(NodeOutcome {
+            items_processed: data.queries.len(),
+            details: json!({ "mode": config.mode, "queries": data.queries }),
+            data,
+        })
+    }
+}
+
+/// Hybrid search for every query over every configured KB (concurrently)
+pub struct RetrieveNodeExecutor {
+    kb_service: Arc<dyn KbService>,
+}
+
+impl RetrieveNodeExecutor {
+    pub fn new(kb_service: Arc<dyn KbService>) -> Self {
+        Self { kb_service }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct RetrieveConfig {
+    kb_ids: Vec<String>,
+    top_k: usize,
+    expand: Option<ContextExpansion>,   // Return each hit's neighbourhood instead of the chunk alone
+    mode: SearchMode,                   // Hierarchical walks the summary tree first
+    graph_expand: bool,                 // Append related entities from the KB's knowledge graph
+}
+
+impl Default for RetrieveConfig {
+    fn default() -> Self {
+        Self { kb_ids: Vec::new(), top_k: 10, expand: None, mode: SearchMode::Hybrid, graph_expand: false }
+    }
+}
+
+#[async_trait]
+impl NodeExecutor for RetrieveNodeExecutor {
+    fn node_type(&self) -> FlowNodeType {
+        FlowNodeType::Retrieve
+    }
+
+    async fn execute(&self, ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+        let config: RetrieveConfig = ctx.parse_config()?;
+        if config.kb_ids.is_empty() {
+            return Err(FlowError::InvalidNodeConfig {
+                node: FlowNodeType::Retrieve.as_str().to_string(),
+                message: "kb_ids must list at least one knowledge base".to_string(),
+            });
+        }
+
+        let searches = data.queries.iter().flat_map(|query| {
+            config.kb_ids.iter().map(move |kb_id| async move {
+                let query = match config.graph_expand {
+                    true => self.kb_service.expand_query_with_graph(kb_id, query, DEFAULT_EXPANSION_TERMS).await?.expanded_query,
+                    false => query.clone(),
+                };
+                match config.mode {
+                    SearchMode::Hybrid => self.kb_service.hybrid_search(kb_id, &query, config.top_k, None, None).await,
+                    SearchMode::Hierarchical => self.kb_service.hierarchical_search(kb_id, &query, config.top_k).await,
+                }
+            })
+        });
+        let mut result_sets: Vec<Vec<SearchResult>> = try_join_all(searches).await?;
+        if let Some(expansion) = config.expand {
+            let expansions = result_sets.into_iter().map(|results| self.kb_service.expand_results(results, expansion));
+            result_sets = try_join_all(expansions).await?;
+        }
+
+        // Without a fusion node, downstream nodes see the lists concatenated
+        data.results = result_sets.iter().flatten().cloned().collect();
+        data.result_sets.extend(result_sets);
+        let retrieved = data.results.len();
+        Ok(NodeOutcome {
+            data,
+            items_processed: retrieved,
+            details: json!({ "kb_ids": config.kb_ids, "top_k": config.top_k, "expand": config.expand, "mode": config.mode, "graph_expand": config.graph_expand }),
+        })
+    }
+}
+
+/// Merges all retrieved lists with Reciprocal Rank Fusion
+pub struct FusionNodeExecutor;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct FusionConfig {
+    k: f32,
+    top_k: usize,
+}
+
+impl Default for FusionConfig {
+    fn default() -> Self {
+        Self { k: DEFAULT_RRF_K, top_k: 10 }
+    }
+}
+
+#[async_trait]
+impl NodeExecutor for FusionNodeExecutor {
+    fn node_type(&self) -> FlowNodeType {
+        FlowNodeType::Fusion
+    }
+
+    async fn execute(&self, ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+        let config: FusionConfig = ctx.parse_config()?;
+        let lists = data.result_sets.len();
+        data.results = reciprocal_rank_fusion(&data.result_sets, config.k, config.top_k);
+
+        let fused = data.results.len();
+        Ok(NodeOutcome { data, items_processed: fused, details: json!({ "lists": lists, "k": config.k }) })
+    }
+}
+
+/// Reorders candidates by how many question terms they contain
+pub struct RerankNodeExecutor;
+
+#[derive(Debug, Deserialize)]
+#[serde(default)]
+struct RerankConfig {
+    top_n: usize,
+    mmr_lambda: Option<f32>,    // Diversify the final selection with MMR (0.0-1.0)
+}
+
+impl Default for RerankConfig {
+    fn default() -> Self {
+        Self { top_n: 5, mmr_lambda: None }
+    }
+}
+
+/// Fraction of query terms present in the content
+fn term_overlap(query_terms: &HashSet<String>, content: &str) -> f32 {
+    if query_terms.is_empty() {
+        return 0.0;
+    }
+    let content_terms = terms(content);
+    query_terms.intersection(&content_terms).count() as f32 / query_terms.len() as f32
+}
+
+#[async_trait]
+impl NodeExecutor for RerankNodeExecutor {
+    fn node_type(&self) -> FlowNodeType {
+        FlowNodeType::Rerank
+    }
+
+    async fn execute(&self, ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+        let config: RerankConfig = ctx.parse_config()?;
+        if config.mmr_lambda.is_some_and(|lambda| !(0.0..=1.0).contains(&lambda)) {
+            return Err(FlowError::InvalidNodeConfig {
+                node: FlowNodeType::Rerank.as_str().to_string(),
+                message: "mmr_lambda must be between 0.0 and 1.0".to_string(),
+            });
+        }
+        let query_terms = terms(&data.query);
+
+        let candidates = data.results.len();
+        for result in &mut data.results {
+            result.score = term_overlap(&query_terms, &result.content);
+        }
+        // Stable sort keeps the retrieval order among equal scores
+        data.results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+        match config.mmr_lambda {
+            Some(lambda) => data.results = mmr_rerank(std::mem::take(&mut data.results), lambda, config.top_n),
+            None => data.results.truncate(config.top_n),
+        }
+
+        Ok(NodeOutcome {
+            data,
+            items_processed: candidates,
+            details: json!({ "top_n": config.top_n, "mmr_lambda": config.mmr_lambda }),
+        })
+    }
+}
+
+/// Generates a cited answer from the final candidates
+pub struct GenerateNodeExecutor;
+
+#[derive(Debug, Default, Deserialize)]
+struct GenerateConfig {
+    #[serde(flatten)]
+    params: GenerationParams,
+    #[serde(default)]
+    context_tokens: Option<usize>,  // Budget for packed sources
+}
+
+#[async_trait]
+impl NodeExecutor for GenerateNodeExecutor {
+    fn node_type(&self) -> FlowNodeType {
+        FlowNodeType::Generate
+    }
+
+    async fn execute(&self, ctx: &NodeContext, mut data: FlowData) -> Result<NodeOutcome, FlowError> {
+        let config: GenerateConfig = ctx.parse_config()?;
+
+        // Never let the model answer without sources
+        let (answer, model, packed_tokens) = if data.results.is_empty() {
+            (cite_answer(NO_SOURCES_ANSWER, &[]), None, 0)
+        } else {
+            let budget = config.context_tokens.map(ContextBudget::new).unwrap_or_default();
+            let packed = pack_context(&data.results, &budget, &EstimatingTokenizer);
+            let prompt = build_grounded_prompt(&data.query, &packed.sources);
+            let output = ctx.llm()?.generate(&prompt, &config.params).await?;
+            (cite_answer(&output.text, &packed.sources), Some(output.model), packed.tokens)
+        };
+
+        let citations = answer.citations.len();
+        data.answer = Some(answer);
+        Ok(NodeOutcome {
+            data,
+            items_processed: citations,
+            details: json!({ "model": model, "citations": citations, "context_tokens": packed_tokens }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::CitationInfo;
+    use crate::services::generation::GenerationOutput;
+    use crate::services::llm::{LlmError, LlmProvider};
+
+    /// Provider that echoes a canned reply
+    struct CannedLlm(&'static str);
+
+    #[async_trait]
+    impl LlmProvider for CannedLlm {
+        fn name(&self) -> String {
+            "canned".to_string()
+        }
+
+        fn is_remote(&self) -> bool {
+            false
+        }
+
+        async fn generate(&self, _prompt: &str, _params: &GenerationParams) -> Result<GenerationOutput, LlmError> {
+            Ok(GenerationOutput { text: self.0.to_string(), model: "canned".to_string(), duration_ms: 0 })
+        }
+    }
+
+    fn ctx(node_type: FlowNodeType, config: serde_json::Value, llm: Option<&'static str>) -> NodeContext {
+        let spec: FlowSpec = serde_json::from_value(json!({ "id": "flow_1", "name": "Test", "nodes": [] })).unwrap();
+        let llm = llm.map(|reply| Arc::new(CannedLlm(reply)) as Arc<dyn LlmProvider>);
+        NodeContext::new("run_1", &spec, FlowNodeConfig { node_type, config }, llm)
+    }
+
+    fn result(chunk_id: &str, content: &str) -> SearchResult {
+        SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: format!("doc_{}", chunk_id),
+            kb_id: "kb_1".to_string(),
+            score: 1.0,
+            content: content.to_string(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: json!({}),
+            citation: CitationInfo {
+                title: chunk_id.to_string(),
+                source_path: format!("/docs/{}.md", chunk_id),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        }
+    }
+
+    #[tokio::test]
+    async fn test_query_rewrite_uses_first_line() {
+        let node = ctx(FlowNodeType::QueryRewrite, serde_json::Value::Null, Some("\n\"install on linux\"\nextra"));
+        let outcome = QueryRewriteNodeExecutor.execute(&node, FlowData::new("how do I get it running?")).await.unwrap();
+        assert_eq!(outcome.data.queries, vec!["install on linux"]);
+        assert_eq!(outcome.data.query, "how do I get it running?");
+
+        // Rewrite needs a provider
+        let node = ctx(FlowNodeType::QueryRewrite, serde_json::Value::Null, None);
+        assert!(QueryRewriteNodeExecutor.execute(&node, FlowData::new("q")).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_keyword_expansion() {
+        // Static synonyms need no provider
+        let config = json!({ "mode": "expand", "synonyms": { "install": ["setup", "Install"], "delete": ["remove"] } });
+        let node = ctx(FlowNodeType::QueryRewrite, config, None);
+        let outcome = QueryRewriteNodeExecutor.execute(&node, FlowData::new("install the server")).await.unwrap();
+        assert_eq!(outcome.data.queries, vec!["install the server setup"]);
+        assert_eq!(outcome.details["mode"], "expand");
+
+        let node = ctx(FlowNodeType::QueryRewrite, json!({ "mode": "expand" }), Some("setup, server,\ndeployment"));
+        let outcome = QueryRewriteNodeExecutor.execute(&node, FlowData::new("install the server")).await.unwrap();
+        assert_eq!(outcome.data.queries, vec!["install the server setup deployment"]);
+    }
+
+    #[tokio::test]
+    async fn test_multi_query_and_hyde() {
+        let reply = "1. install server linux\n2) \"server setup guide\"\n- install server linux\n3. 2024 requirements";
+        let node = ctx(FlowNodeType::QueryRewrite, json!({ "mode": "multi_query", "num_queries": 4 }), Some(reply));
+        let outcome = QueryRewriteNodeExecutor.execute(&node, FlowData::new("how do I install?")).await.unwrap();
+        // Original first, list markers stripped, duplicates dropped
+        assert_eq!(outcome.data.queries, vec!["how do I install?", "install server linux", "server setup guide", "2024 requirements"]);
+        assert_eq!(outcome.items_processed, 4);
+
+        let node = ctx(FlowNodeType::QueryRewrite, json!({ "mode": "hyde", "keep_original": false }), Some("  Run setup.exe and follow the wizard.  "));
+        let outcome = QueryRewriteNodeExecutor.execute(&node, FlowData::new("how do I install?")).await.unwrap();
+        assert_eq!(outcome.data.queries, vec!["Run setup.exe and follow the wizard."]);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_by_term_overlap() {
+        let mut data = FlowData::new("install the server");
+        data.results = vec![
+            result("c1", "Release notes"),
+            result("c2", "Install the client"),
+            result("c3", "To install the server, run setup"),
+        ];
+
+        let node = ctx(FlowNodeType::Rerank, json!({ "top_n": 2 }), None);
+        let outcome = RerankNodeExecutor.execute(&node, data).await.unwrap();
+        let ids: Vec<&str> = outcome.data.results.iter().map(|r| r.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["c3", "c2"]);
+        assert_eq!(outcome.items_processed, 3);
+    }
+
+    #[tokio::test]
+    async fn test_rerank_with_mmr() {
+        let mut data = FlowData::new("install the server");
+        data.results = vec![
+            result("c1", "Install the server with setup"),
+            result("c2", "Install the server with setup on Windows"),
+            result("c3", "The server needs firewall ports to install"),
+        ];
+        // All three cover every query term; MMR prefers the less redundant chunk
+        let node = ctx(FlowNodeType::Rerank, json!({ "top_n": 2, "mmr_lambda": 0.3 }), None);
+        let outcome = RerankNodeExecutor.execute(&node, data).await.unwrap();
+        let ids: Vec<&str> = outcome.data.results.iter().map(|r| r.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["c1", "c3"]);
+
+        let node = ctx(FlowNodeType::Rerank, json!({ "mmr_lambda": 1.5 }), None);
+        assert!(matches!(
+            RerankNodeExecutor.execute(&node, FlowData::new("q")).await,
+            Err(FlowError::InvalidNodeConfig { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_generate_cites_results() {
+        let mut data = FlowData::new("How do I install?");
+        data.results = vec![result("c1", "Run setup"), result("c2", "Restart")];
+
+        let node = ctx(FlowNodeType::Generate, json!({ "max_tokens": 128 }), Some("Run setup [1], then restart [2]."));
+        let outcome = GenerateNodeExecutor.execute(&node, data).await.unwrap();
+        let answer = outcome.data.answer.unwrap();
+        assert_eq!(answer.citations.len(), 2);
+        assert_eq!(answer.citations[1].chunk_id, "c2");
+
+        // No sources: no generation, no provider needed
+        let node = ctx(FlowNodeType::Generate, serde_json::Value::Null, None);
+        let outcome = GenerateNodeExecutor.execute(&node, FlowData::new("q")).await.unwrap();
+        assert_eq!(outcome.data.answer.unwrap().answer, NO_SOURCES_ANSWER);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_node_config() {
+        let node = ctx(FlowNodeType::Fusion, json!({ "top_k": "ten" }), None);
+        assert!(matches!(
+            FusionNodeExecutor.execute(&node, FlowData::new("q")).await,
+            Err(FlowError::InvalidNodeConfig { .. })
+        ));
+    }
+}
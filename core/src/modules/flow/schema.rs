@@ -0,0 +1,45 @@
+/*!
+ * Flow Domain Schema
+ *
+ * Diesel row type for the flows table.
+ */
+
+use chrono::{NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::flows;
+use super::errors::FlowError;
+use super::models::FlowSpec;
+
+/// Row in flows
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = flows)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct FlowRow {
+    pub id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub definition: String,           // JSON FlowSpec
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+}
+
+impl FlowRow {
+    pub fn from_model(spec: &FlowSpec) -> Result<Self, FlowError> {
+        let now = Utc::now().naive_utc();
+        Ok(Self {
+            id: spec.id.clone(),
+            name: spec.name.clone(),
+            description: spec.description.clone(),
+            definition: serde_json::to_string(spec)?,
+            enabled: spec.enabled,
+            created_at: now,
+            updated_at: now,
+        })
+    }
+
+    pub fn into_model(self) -> Result<FlowSpec, FlowError> {
+        Ok(serde_json::from_str(&self.definition)?)
+    }
+}
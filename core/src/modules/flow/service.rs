@@ -0,0 +1,445 @@
+/*!
+ * Flow Service Implementation
+ *
+ * Stores flow definitions (SQL `flows` table, mirrored in app state so the
+ * outbound RPC server can resolve a flow's LLM provider) and runs them with
+ * the built-in node executors.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use chrono::Utc;
+use diesel::prelude::*;
+use tracing::info;
+
+use super::errors::FlowError;
+use super::executor::FlowExecutor;
+use super::models::*;
+use super::nodes::*;
+use super::schema::FlowRow;
+use crate::modules::eval::{GoldenQuery, MetricDeltas};
+use crate::modules::kb::KbService;
+use crate::modules::pipeline::steps::eval::{aggregate_scores, score_query, QueryScore};
+use crate::schemas::schema::flows;
+use crate::services::llm::LlmService;
+use crate::services::sql::SqlService;
+use crate::state::{FlowState, StateDelta, StateManager};
+
+/// Flow definitions and runs
+pub struct FlowService {
+    sql_service: Arc<SqlService>,
+    state_manager: Arc<StateManager>,
+    kb_service: Arc<dyn KbService>,
+    llm_service: Arc<LlmService>,
+}
+
+impl FlowService {
+    pub fn new(
+        sql_service: Arc<SqlService>,
+        state_manager: Arc<StateManager>,
+        kb_service: Arc<dyn KbService>,
+        llm_service: Arc<LlmService>,
+    ) -> Self {
+        Self {
+            sql_service,
+            state_manager,
+            kb_service,
+            llm_service,
+        }
+    }
+
+    /// Load persisted flows into the state manager; returns how many were added
+    pub async fn load_flows(&self) -> Result<usize, FlowError> {
+        let rows = self.sql_service.with_app_transaction(|conn| {
+            Ok(flows::table
+                .select(FlowRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        let mut loaded = 0;
+        for row in rows {
+            if self.state_manager.read_state().flows.contains_key(&row.id) {
+                continue;
+            }
+            let spec = row.into_model()?;
+            self.upsert_state(&spec, None, 0)?;
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
+    /// Create (empty id) or update a flow
+    pub async fn save_flow(&self, mut spec: FlowSpec) -> Result<FlowSpec, FlowError> {
+        spec.validate()?;
+        let existing = if spec.id.is_empty() {
+            spec.id = format!("flow_{}", uuid::Uuid::new_v4().simple());
+            None
+        } else {
+            self.state_manager.read_state().flows.get(&spec.id).cloned()
+        };
+
+        let name_taken = self.state_manager.read_state().flows.values()
+            .any(|f| f.name == spec.name && f.id != spec.id);
+        if name_taken {
+            return Err(FlowError::InvalidSpec(format!("A flow named '{}' already exists", spec.name)));
+        }
+
+        let row = FlowRow::from_model(&spec)?;
+        let update = existing.is_some();
+        self.sql_service.with_app_transaction(move |conn| {
+            if update {
+                diesel::update(flows::table.find(&row.id))
+                    .set((
+                        flows::name.eq(&row.name),
+                        flows::description.eq(&row.description),
+                        flows::definition.eq(&row.definition),
+                        flows::enabled.eq(row.enabled),
+                        flows::updated_at.eq(row.updated_at),
+                    ))
+                    .execute(conn)?;
+            } else {
+                diesel::insert_into(flows::table)
+                    .values(&row)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).await?;
+
+        let (last_executed, execution_count) = existing
+            .map(|f| (f.last_executed, f.execution_count))
+            .unwrap_or((None, 0));
+        self.upsert_state(&spec, last_executed, execution_count)?;
+
+        info!("Saved flow {} ({}) with {} nodes", spec.name, spec.id, spec.nodes.len());
+        Ok(spec)
+    }
+
+    /// All flows, by name
+    pub fn list_flows(&self) -> Result<Vec<FlowSpec>, FlowError> {
+        let mut specs = self.state_manager.read_state().flows.values()
+            .map(|f| serde_json::from_value::<FlowSpec>(f.definition.clone()))
+            .collect::<Result<Vec<_>, _>>()?;
+        specs.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(specs)
+    }
+
+    pub fn get_flow(&self, flow_id: &str) -> Result<FlowSpec, FlowError> {
+        let flow = self.state_manager.read_state().flows.get(flow_id).cloned()
+            .ok_or_else(|| FlowError::FlowNotFound(flow_id.to_string()))?;
+        Ok(serde_json::from_value(flow.definition)?)
+    }
+
+    pub async fn delete_flow(&self, flow_id: &str) -> Result<(), FlowError> {
+        self.get_flow(flow_id)?;
+
+        let id = flow_id.to_string();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::delete(flows::table.find(id)).execute(conn)?;
+            Ok(())
+        }).await?;
+
+        self.state_manager
+            .mutate(StateDelta::FlowRemove { id: flow_id.to_string() })
+            .map_err(FlowError::StateError)
+    }
+
+    /// Run a saved flow and record the execution
+    pub async fn run_flow(&self, flow_id: &str, query: &str) -> Result<FlowRunOutput, FlowError> {
+        let spec = self.get_flow(flow_id)?;
+        if !spec.enabled {
+            return Err(FlowError::InvalidSpec(format!("Flow is disabled: {}", flow_id)));
+        }
+
+        let output = self.execute(&spec, query).await?;
+
+        let flow = self.state_manager.read_state().flows.get(flow_id).cloned();
+        if let Some(flow) = flow {
+            self.upsert_state(&spec, Some(Utc::now()), flow.execution_count + 1)?;
+        }
+        Ok(output)
+    }
+
+    /// Run an unsaved flow definition (editor "test" button); nothing is recorded
+    pub async fn test_flow(&self, spec: &FlowSpec, query: &str) -> Result<FlowRunOutput, FlowError> {
+        self.execute(spec, query).await
+    }
+
+    /// Score a flow's retrieval on a golden set, with and without its query
+    /// transforms, so each operator's effect shows up as metric deltas
+    pub async fn evaluate_flow(
+        &self,
+        spec: &FlowSpec,
+        kb_id: &str,
+        golden: &[GoldenQuery],
+        k: usize,
+    ) -> Result<FlowEvalReport, FlowError> {
+        if golden.is_empty() {
+            return Err(FlowError::InvalidSpec(format!("No golden queries for KB: {}", kb_id)));
+        }
+        let transformed = spec.retrieval_only();
+        let baseline = spec.without_query_transforms();
+
+        let mut queries = Vec::with_capacity(golden.len());
+        let mut baseline_scores = Vec::with_capacity(golden.len());
+        for query in golden {
+            let relevant: HashSet<String> = query.relevant_chunk_ids.iter().cloned().collect();
+
+            let output = self.execute(&transformed, &query.question).await?;
+            let retrieval_queries = output.data.queries.clone();
+            let (recall_at_k, reciprocal_rank, ndcg_at_k) = Self::score_output(output, &relevant, k)?;
+            queries.push(FlowQueryScore {
+                question: query.question.clone(),
+                queries: retrieval_queries,
+                recall_at_k,
+                reciprocal_rank,
+                ndcg_at_k,
+            });
+
+            let output = self.execute(&baseline, &query.question).await?;
+            let (recall_at_k, reciprocal_rank, ndcg_at_k) = Self::score_output(output, &relevant, k)?;
+            baseline_scores.push(QueryScore { query: query.question.clone(), recall_at_k, reciprocal_rank, ndcg_at_k });
+        }
+
+        let scores: Vec<QueryScore> = queries.iter().map(|q| QueryScore {
+            query: q.question.clone(),
+            recall_at_k: q.recall_at_k,
+            reciprocal_rank: q.reciprocal_rank,
+            ndcg_at_k: q.ndcg_at_k,
+        }).collect();
+        let metrics = aggregate_scores(&scores);
+        let baseline = aggregate_scores(&baseline_scores);
+        info!("Flow {} eval on KB {}: ndcg@{} {:.3} (baseline {:.3})", spec.id, kb_id, k, metrics.ndcg_at_k, baseline.ndcg_at_k);
+
+        Ok(FlowEvalReport {
+            flow_id: spec.id.clone(),
+            kb_id: kb_id.to_string(),
+            k,
+            transforms: spec.query_transforms(),
+            deltas: MetricDeltas::between(&metrics, &baseline),
+            metrics,
+            baseline,
+            queries,
+        })
+    }
+
+    fn score_output(output: FlowRunOutput, relevant: &HashSet<String>, k: usize) -> Result<(f64, f64, f64), FlowError> {
+        if let Some(error) = output.error {
+            return Err(FlowError::RunFailed(error));
+        }
+        let ranked_ids: Vec<Vec<String>> = output.data.results.into_iter().map(|r| vec![r.chunk_id]).collect();
+        Ok(score_query(&ranked_ids, relevant, k))
+    }
+
+    async fn execute(&self, spec: &FlowSpec, query: &str) -> Result<FlowRunOutput, FlowError> {
+        spec.validate()?;
+        if query.trim().is_empty() {
+            return Err(FlowError::InvalidSpec("Query cannot be empty".to_string()));
+        }
+
+        // Resolve the provider up front so network policy violations fail before any work
+        let llm = if spec.uses_llm() {
+            Some(self.llm_service.provider(&spec.llm.clone().unwrap_or_default())?)
+        } else {
+            None
+        };
+
+        let run_id = format!("flowrun_{}", uuid::Uuid::new_v4().simple());
+        Ok(self.executor().run(spec, &run_id, query, llm).await)
+    }
+
+    /// Executor with all built-in nodes registered
+    fn executor(&self) -> FlowExecutor {
+        let mut executor = FlowExecutor::new();
+        executor.register(Arc::new(QueryRewriteNodeExecutor));
+        executor.register(Arc::new(RetrieveNodeExecutor::new(self.kb_service.clone())));
+        executor.register(Arc::new(FusionNodeExecutor));
+        executor.register(Arc::new(RerankNodeExecutor));
+        executor.register(Arc::new(GenerateNodeExecutor));
+        executor
+    }
+
+    fn upsert_state(
+        &self,
+        spec: &FlowSpec,
+        last_executed: Option<chrono::DateTime<Utc>>,
+        execution_count: u64,
+    ) -> Result<(), FlowError> {
+        let flow = FlowState {
+            id: spec.id.clone(),
+            name: spec.name.clone(),
+            enabled: spec.enabled,
+            definition: serde_json::to_value(spec)?,
+            last_executed,
+            execution_count,
+        };
+        self.state_manager
+            .mutate(StateDelta::FlowUpsert { flow })
+            .map_err(FlowError::StateError)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use tempfile::TempDir;
+    use crate::modules::kb::{KbCreateConfig, KbServiceImpl};
+    use crate::services::generation::{GenerationConfig, GenerationService};
+    use crate::services::llm::{LlmError, LlmProviderConfig};
+    use crate::services::network_policy::NetworkPolicy;
+    use crate::services::sql::SqlConfig;
+    use crate::services::vector::{VectorDbConfig, VectorDbService};
+
+    struct Fixture {
+        _temp_dir: TempDir,
+        sql_service: Arc<SqlService>,
+        kb_service: Arc<KbServiceImpl>,
+        llm_service: Arc<LlmService>,
+    }
+
+    async fn fixture() -> Fixture {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let kb_service = Arc::new(KbServiceImpl::new_mvp(sql_service.clone(), vector_service, Arc::new(StateManager::new())));
+        let generation = Arc::new(GenerationService::new(GenerationConfig { model_path: None, ..Default::default() }));
+        Fixture { _temp_dir: temp_dir, sql_service, kb_service, llm_service: Arc::new(LlmService::new(generation)) }
+    }
+
+    fn service(fixture: &Fixture) -> FlowService {
+        FlowService::new(
+            fixture.sql_service.clone(),
+            Arc::new(StateManager::new()),
+            fixture.kb_service.clone(),
+            fixture.llm_service.clone(),
+        )
+    }
+
+    fn spec(name: &str, kb_ids: &[&str]) -> FlowSpec {
+        serde_json::from_value(json!({
+            "name": name,
+            "nodes": [
+                { "node_type": "retrieve", "config": { "kb_ids": kb_ids, "top_k": 3 } },
+                { "node_type": "fusion", "config": { "top_k": 4 } },
+                { "node_type": "rerank", "config": { "top_n": 2 } },
+            ],
+        })).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_save_load_delete() {
+        let fixture = fixture().await;
+        let flows = service(&fixture);
+
+        let saved = flows.save_flow(spec("Support", &["kb_1"])).await.unwrap();
+        assert!(saved.id.starts_with("flow_"));
+        assert!(matches!(flows.save_flow(spec("Support", &["kb_2"])).await, Err(FlowError::InvalidSpec(_))));
+
+        // Updating keeps the id
+        let mut updated = saved.clone();
+        updated.description = Some("Tier 1".to_string());
+        flows.save_flow(updated).await.unwrap();
+
+        let reloaded = service(&fixture);
+        assert_eq!(reloaded.load_flows().await.unwrap(), 1);
+        let flow = reloaded.get_flow(&saved.id).unwrap();
+        assert_eq!(flow.description.as_deref(), Some("Tier 1"));
+        assert_eq!(flow.nodes.len(), 3);
+
+        reloaded.delete_flow(&saved.id).await.unwrap();
+        assert!(matches!(reloaded.get_flow(&saved.id), Err(FlowError::FlowNotFound(_))));
+        assert_eq!(service(&fixture).load_flows().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_run_flow_over_two_kbs() {
+        let fixture = fixture().await;
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "test-model".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_a = fixture.kb_service.create_collection("A", config.clone()).await.unwrap();
+        let kb_b = fixture.kb_service.create_collection("B", config).await.unwrap();
+
+        let flows = service(&fixture);
+        let saved = flows.save_flow(spec("Federated", &[&kb_a, &kb_b])).await.unwrap();
+        let output = flows.run_flow(&saved.id, "install guide").await.unwrap();
+
+        assert!(output.is_success(), "{:?}", output.error);
+        assert_eq!(output.data.result_sets.len(), 2);
+        assert!(output.data.results.len() <= 2);
+        assert_eq!(output.nodes.len(), 3);
+        assert_eq!(flows.state_manager.read_state().flows[&saved.id].execution_count, 1);
+
+        // Node failures are reported in the output, not as errors
+        let output = flows.test_flow(&spec("Missing", &["kb_missing"]), "install").await.unwrap();
+        assert!(!output.is_success());
+        assert!(!output.nodes[0].success);
+    }
+
+    #[tokio::test]
+    async fn test_evaluate_flow_reports_transform_deltas() {
+        let fixture = fixture().await;
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "test-model".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = fixture.kb_service.create_collection("Docs", config).await.unwrap();
+        let flows = service(&fixture);
+
+        let mut expanded = spec("Expanded", &[&kb_id]);
+        expanded.nodes.insert(0, FlowNodeConfig {
+            node_type: FlowNodeType::QueryRewrite,
+            config: json!({ "mode": "expand", "synonyms": { "install": ["setup"] } }),
+        });
+        let golden = vec![GoldenQuery {
+            id: "gq_1".to_string(),
+            kb_id: kb_id.clone(),
+            question: "install guide".to_string(),
+            relevant_chunk_ids: vec!["chunk_1".to_string()],
+            created_at: Utc::now(),
+        }];
+
+        let report = flows.evaluate_flow(&expanded, &kb_id, &golden, 5).await.unwrap();
+        assert_eq!(report.transforms, vec![QueryTransform::Expand]);
+        assert_eq!(report.queries[0].queries, vec!["install guide setup"]);
+        assert!((report.deltas.ndcg_at_k - (report.metrics.ndcg_at_k - report.baseline.ndcg_at_k)).abs() < 1e-9);
+
+        assert!(matches!(
+            flows.evaluate_flow(&expanded, &kb_id, &[], 5).await,
+            Err(FlowError::InvalidSpec(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_air_gapped_rejects_remote_provider() {
+        let fixture = fixture().await;
+        let generation = Arc::new(GenerationService::new(GenerationConfig { model_path: None, ..Default::default() }));
+        let llm_service = LlmService::new(generation).with_network_policy(Arc::new(NetworkPolicy::new(true)));
+        let flows = FlowService::new(
+            fixture.sql_service.clone(),
+            Arc::new(StateManager::new()),
+            fixture.kb_service.clone(),
+            Arc::new(llm_service),
+        );
+
+        let mut remote = spec("Remote", &["kb_1"]);
+        remote.nodes.push(FlowNodeConfig { node_type: FlowNodeType::Generate, config: json!({}) });
+        remote.llm = Some(LlmProviderConfig::OpenaiCompatible {
+            base_url: "https://api.example.com/v1".to_string(),
+            model: "gpt".to_string(),
+            api_key: None,
+            api_key_secret: None,
+        });
+
+        assert!(matches!(
+            flows.test_flow(&remote, "question").await,
+            Err(FlowError::LlmError(LlmError::Policy(_)))
+        ));
+    }
+}
@@ -0,0 +1,17 @@
+/*!
+ * Knowledge Graph Domain Errors
+ *
+ * Domain-specific error types for the knowledge graph.
+ */
+
+use crate::services::sql::SqlError;
+
+/// Knowledge Graph Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum GraphError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+}
@@ -0,0 +1,256 @@
+/*!
+ * Rule-Based Entity Extraction
+ *
+ * Finds entities without a model: runs of capitalized words ("Lance DB",
+ * "Acme Corp") and acronyms ("MCP"), plus any spans an NER backend detected.
+ * A capitalized word opening a sentence only counts if it also appears
+ * capitalized mid-sentence, and leading function words ("The", "When") are
+ * dropped. Entities mentioned in the same sentence are related (`co_occurs`).
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ops::Range;
+
+use super::models::*;
+use crate::utils::snippet::sentences;
+
+/// Capitalized words that start phrases without naming anything
+const LEADING_STOPWORDS: &[&str] = &[
+    "a", "after", "all", "also", "an", "and", "are", "as", "at", "be", "before", "but", "by", "each",
+    "every", "for", "from", "he", "her", "here", "his", "however", "i", "if", "in", "is", "it", "its",
+    "no", "not", "note", "of", "on", "or", "our", "she", "some", "that", "the", "their", "then",
+    "there", "these", "they", "this", "those", "to", "was", "we", "when", "while", "with", "you", "your",
+];
+
+/// Longest run of capitalized words taken as one entity
+const MAX_ENTITY_WORDS: usize = 5;
+
+/// Extraction limits
+#[derive(Debug, Clone)]
+pub struct ExtractOptions {
+    pub min_mentions: u32,          // Entities mentioned less often are dropped
+    pub max_entities: usize,        // Most mentioned entities kept per document
+    pub max_sentence_entities: usize,   // Caps relations per sentence (n² pairs)
+}
+
+impl Default for ExtractOptions {
+    fn default() -> Self {
+        Self {
+            min_mentions: 1,
+            max_entities: 200,
+            max_sentence_entities: 8,
+        }
+    }
+}
+
+/// Entity span detected by an NER backend (byte offsets into the text)
+#[derive(Debug, Clone, PartialEq)]
+pub struct EntitySpan {
+    pub entity_type: String,
+    pub start: usize,
+    pub end: usize,
+}
+
+struct Mention {
+    id: String,
+    span: Range<usize>,
+    surface: String,
+    entity_type: String,
+    sentence: usize,
+    opening: bool,                  // Single capitalized word opening its sentence
+    detected: bool,                 // From NER; its type wins
+}
+
+/// Mentions of one entity across the document
+struct Tally<'a> {
+    count: u32,
+    surfaces: HashMap<&'a str, u32>,
+    entity_type: &'a str,
+    detected: bool,
+}
+
+/// Entities and same-sentence relations of `text`
+pub fn extract_graph(text: &str, detected: &[EntitySpan], options: &ExtractOptions) -> DocumentGraph {
+    let sentences = sentences(text);
+    let mut mentions = Vec::new();
+    for (index, sentence) in sentences.iter().enumerate() {
+        let words = words(text, sentence.clone());
+        let mut i = 0;
+        while i < words.len() {
+            if !is_capitalized(&text[words[i].clone()]) {
+                i += 1;
+                continue;
+            }
+            // Capitalized words separated by single spaces only
+            let mut end = i + 1;
+            while end < words.len() && end - i < MAX_ENTITY_WORDS
+                && is_capitalized(&text[words[end].clone()])
+                && text[words[end - 1].end..words[end].start] == *" "
+            {
+                end += 1;
+            }
+            let first = (i..end).find(|&w| !LEADING_STOPWORDS.contains(&text[words[w].clone()].to_lowercase().as_str()));
+            if let Some(first) = first {
+                let surface = &text[words[first].start..words[end - 1].end];
+                let acronym = end - first == 1 && is_acronym(surface);
+                mentions.push(Mention {
+                    id: entity_id(surface),
+                    span: words[first].start..words[end - 1].end,
+                    surface: surface.to_string(),
+                    entity_type: if acronym { ENTITY_ACRONYM } else { ENTITY_PROPER_NOUN }.to_string(),
+                    sentence: index,
+                    opening: first == 0 && end == 1 && !acronym,
+                    detected: false,
+                });
+            }
+            i = end;
+        }
+    }
+    for span in detected {
+        let valid = span.start < span.end && span.end <= text.len()
+            && text.is_char_boundary(span.start) && text.is_char_boundary(span.end);
+        let surface = if valid { text[span.start..span.end].trim() } else { "" };
+        let id = entity_id(surface);
+        if id.is_empty() {
+            continue;
+        }
+        mentions.push(Mention {
+            id,
+            span: span.start..span.end,
+            surface: surface.to_string(),
+            entity_type: span.entity_type.clone(),
+            sentence: sentences.iter().position(|s| s.end > span.start).unwrap_or(sentences.len()),
+            opening: false,
+            detected: true,
+        });
+    }
+
+    // Sentence openers only count when the word is also capitalized elsewhere
+    let confirmed: HashSet<String> = mentions.iter().filter(|m| !m.opening).map(|m| m.id.clone()).collect();
+    mentions.retain(|m| !m.opening || confirmed.contains(&m.id));
+    // NER spans replace the capitalized runs they overlap
+    let detected_spans: Vec<Range<usize>> = mentions.iter().filter(|m| m.detected).map(|m| m.span.clone()).collect();
+    mentions.retain(|m| m.detected || !detected_spans.iter().any(|d| d.start < m.span.end && m.span.start < d.end));
+
+    let mut entities: BTreeMap<&str, Tally> = BTreeMap::new();
+    for mention in &mentions {
+        let tally = entities.entry(&mention.id).or_insert_with(|| Tally {
+            count: 0,
+            surfaces: HashMap::new(),
+            entity_type: &mention.entity_type,
+            detected: mention.detected,
+        });
+        tally.count += 1;
+        *tally.surfaces.entry(&mention.surface).or_default() += 1;
+        if mention.detected && !tally.detected {
+            tally.entity_type = &mention.entity_type;
+            tally.detected = true;
+        }
+    }
+    let mut kept: Vec<EntityMention> = entities.into_iter()
+        .filter(|(_, tally)| tally.count >= options.min_mentions)
+        .map(|(id, tally)| {
+            let name = tally.surfaces.into_iter().max_by(|a, b| a.1.cmp(&b.1).then(b.0.cmp(a.0))).map_or(id, |(s, _)| s);
+            EntityMention { entity_id: id.to_string(), name: name.to_string(), entity_type: tally.entity_type.to_string(), mentions: tally.count }
+        })
+        .collect();
+    kept.sort_by(|a, b| b.mentions.cmp(&a.mentions).then(a.entity_id.cmp(&b.entity_id)));
+    kept.truncate(options.max_entities);
+
+    let kept_ids: HashSet<&str> = kept.iter().map(|e| e.entity_id.as_str()).collect();
+    let mut per_sentence: BTreeMap<usize, Vec<&str>> = BTreeMap::new();
+    for mention in mentions.iter().filter(|m| kept_ids.contains(m.id.as_str())) {
+        let ids = per_sentence.entry(mention.sentence).or_default();
+        if !ids.contains(&mention.id.as_str()) && ids.len() < options.max_sentence_entities {
+            ids.push(&mention.id);
+        }
+    }
+    let mut pairs: BTreeMap<(&str, &str), u32> = BTreeMap::new();
+    for ids in per_sentence.values() {
+        for (i, a) in ids.iter().enumerate() {
+            for b in &ids[i + 1..] {
+                *pairs.entry(if a < b { (*a, *b) } else { (*b, *a) }).or_default() += 1;
+            }
+        }
+    }
+    let relations = pairs.into_iter()
+        .map(|((source, target), weight)| RelationMention {
+            source_id: source.to_string(),
+            target_id: target.to_string(),
+            relation: RELATION_CO_OCCURS.to_string(),
+            weight,
+        })
+        .collect();
+
+    DocumentGraph { entities: kept, relations }
+}
+
+/// Byte ranges of words (letters and digits) within `range`
+fn words(text: &str, range: Range<usize>) -> Vec<Range<usize>> {
+    let mut words = Vec::new();
+    let mut start: Option<usize> = None;
+    for (i, c) in text[range.clone()].char_indices().chain([(range.len(), ' ')]) {
+        match (c.is_alphanumeric(), start) {
+            (true, None) => start = Some(i),
+            (false, Some(s)) => {
+                words.push(range.start + s..range.start + i);
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    words
+}
+
+fn is_capitalized(word: &str) -> bool {
+    word.chars().next().is_some_and(char::is_uppercase)
+}
+
+/// Two or more capitals, nothing lowercase ("MCP", "S3" is too short to tell)
+fn is_acronym(word: &str) -> bool {
+    word.chars().filter(|c| c.is_uppercase()).count() >= 2 && !word.chars().any(char::is_lowercase)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ids(graph: &DocumentGraph) -> Vec<&str> {
+        graph.entities.iter().map(|e| e.entity_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_extract_entities_and_relations() {
+        let text = "The Manager starts the MCP server. Backups go to Acme Storage nightly.\n\
+                    Acme Storage keeps them for Lance DB and the Manager. When done, logs rotate.";
+        let graph = extract_graph(text, &[], &ExtractOptions::default());
+        assert_eq!(ids(&graph), vec!["acme storage", "manager", "lance db", "mcp"]);
+        assert_eq!(graph.entities[3].entity_type, ENTITY_ACRONYM);
+        assert_eq!(graph.entities[0].mentions, 2);
+        // "Backups" and "When" open their sentences and appear nowhere else
+        let manager_storage = graph.relations.iter().find(|r| r.source_id == "acme storage" && r.target_id == "manager").unwrap();
+        assert_eq!(manager_storage.weight, 1);
+        assert!(graph.relations.iter().any(|r| r.source_id == "manager" && r.target_id == "mcp"));
+        assert_eq!(graph.relations.len(), 4);
+
+        let graph = extract_graph(text, &[], &ExtractOptions { min_mentions: 2, ..Default::default() });
+        assert_eq!(ids(&graph), vec!["acme storage", "manager"]);
+        assert_eq!(graph.relations.len(), 1);
+    }
+
+    #[test]
+    fn test_detected_spans_set_type() {
+        let text = "Contact Jane Doe about the rollout. jane doe approved it.";
+        let detected = vec![
+            EntitySpan { entity_type: "person".to_string(), start: 8, end: 16 },
+            EntitySpan { entity_type: "person".to_string(), start: 36, end: 44 },
+            EntitySpan { entity_type: "person".to_string(), start: 50, end: 500 },
+        ];
+        let graph = extract_graph(text, &detected, &ExtractOptions::default());
+        assert_eq!(graph.entities.len(), 1);
+        assert_eq!(graph.entities[0].entity_id, "jane doe");
+        assert_eq!(graph.entities[0].name, "Jane Doe");
+        assert_eq!(graph.entities[0].entity_type, "person");
+        assert_eq!(graph.entities[0].mentions, 2);
+    }
+}
@@ -0,0 +1,19 @@
+/*!
+ * Knowledge Graph Domain Module
+ *
+ * Entities and relations extracted from KB documents by the extract_entities
+ * pipeline step, stored per document in the KB's content database, and the
+ * query expansion that adds entities related to those a query mentions.
+ */
+
+pub mod service;
+pub mod models;
+pub mod schema;
+pub mod errors;
+pub mod extract;
+
+// Re-export public types
+pub use service::GraphService;
+pub use models::*;
+pub use errors::GraphError;
+pub use extract::{extract_graph, EntitySpan, ExtractOptions};
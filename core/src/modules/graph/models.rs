@@ -0,0 +1,71 @@
+/*!
+ * Knowledge Graph Models
+ *
+ * Per-document entities and relations, and the query expansion built from them.
+ */
+
+use serde::{Deserialize, Serialize};
+
+/// Relation between entities mentioned in the same sentence
+pub const RELATION_CO_OCCURS: &str = "co_occurs";
+
+/// Related entities appended to a query by default
+pub const DEFAULT_EXPANSION_TERMS: usize = 5;
+
+/// Entity types assigned by the rule-based extractor (NER supplies its own)
+pub const ENTITY_PROPER_NOUN: &str = "proper_noun";
+pub const ENTITY_ACRONYM: &str = "acronym";
+
+/// Key of an entity within a KB: lowercased words joined by single spaces
+pub fn entity_id(name: &str) -> String {
+    name.split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Entity as found in one document
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EntityMention {
+    pub entity_id: String,
+    pub name: String,               // Most frequent surface form
+    pub entity_type: String,
+    pub mentions: u32,
+}
+
+/// Relation as found in one document (`source_id` < `target_id` for symmetric relations)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelationMention {
+    pub source_id: String,
+    pub target_id: String,
+    pub relation: String,
+    pub weight: u32,
+}
+
+/// Graph extracted from one document
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DocumentGraph {
+    pub entities: Vec<EntityMention>,
+    pub relations: Vec<RelationMention>,
+}
+
+/// Entity linked to one of the query's entities, weights summed over the KB
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RelatedEntity {
+    pub entity_id: String,
+    pub name: String,
+    pub entity_type: String,
+    pub relation: String,
+    pub weight: u32,
+    pub via: String,                // Query entity it is linked to
+}
+
+/// Query with the names of related entities appended
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryExpansion {
+    pub query: String,
+    pub expanded_query: String,     // Equal to `query` when nothing matched
+    pub matched: Vec<String>,       // Entity ids mentioned by the query
+    pub related: Vec<RelatedEntity>,
+}
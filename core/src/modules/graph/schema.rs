@@ -0,0 +1,62 @@
+/*!
+ * Knowledge Graph Schema
+ *
+ * Diesel row types for the graph_entities and graph_relations tables.
+ */
+
+use diesel::prelude::*;
+
+use crate::schemas::schema::{graph_entities, graph_relations};
+use super::models::{EntityMention, RelationMention};
+
+/// Row in graph_entities
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = graph_entities)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct GraphEntityRow {
+    pub kb_id: String,
+    pub document_id: String,
+    pub entity_id: String,
+    pub name: String,
+    pub entity_type: String,
+    pub mentions: i32,
+}
+
+impl GraphEntityRow {
+    pub fn from_model(kb_id: &str, document_id: &str, entity: &EntityMention) -> Self {
+        Self {
+            kb_id: kb_id.to_string(),
+            document_id: document_id.to_string(),
+            entity_id: entity.entity_id.clone(),
+            name: entity.name.clone(),
+            entity_type: entity.entity_type.clone(),
+            mentions: entity.mentions as i32,
+        }
+    }
+}
+
+/// Row in graph_relations
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = graph_relations)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct GraphRelationRow {
+    pub kb_id: String,
+    pub document_id: String,
+    pub source_id: String,
+    pub target_id: String,
+    pub relation: String,
+    pub weight: i32,
+}
+
+impl GraphRelationRow {
+    pub fn from_model(kb_id: &str, document_id: &str, relation: &RelationMention) -> Self {
+        Self {
+            kb_id: kb_id.to_string(),
+            document_id: document_id.to_string(),
+            source_id: relation.source_id.clone(),
+            target_id: relation.target_id.clone(),
+            relation: relation.relation.clone(),
+            weight: relation.weight as i32,
+        }
+    }
+}
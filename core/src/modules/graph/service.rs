@@ -0,0 +1,241 @@
+/*!
+ * Knowledge Graph Service
+ *
+ * Stores each document's extracted graph in the KB's content database and
+ * expands search queries with entities related to the ones they mention.
+ * Relation weights are summed over documents at query time, so replacing or
+ * removing one document never needs a KB-wide rebuild.
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use diesel::prelude::*;
+use tracing::debug;
+
+use super::errors::GraphError;
+use super::models::*;
+use super::schema::{GraphEntityRow, GraphRelationRow};
+use crate::schemas::schema::{graph_entities, graph_relations};
+use crate::services::sql::{SqlError, SqlService};
+
+/// Longest entity name matched in a query, in words
+const MAX_QUERY_NGRAM: usize = 4;
+
+/// Knowledge graph service
+pub struct GraphService {
+    sql_service: Arc<SqlService>,
+}
+
+impl GraphService {
+    pub fn new(sql_service: Arc<SqlService>) -> Self {
+        Self { sql_service }
+    }
+
+    /// Replace a document's entities and relations with `graph`
+    pub async fn replace_document(&self, kb_id: &str, document_id: &str, graph: &DocumentGraph) -> Result<(), GraphError> {
+        let entities: Vec<GraphEntityRow> = graph.entities.iter()
+            .map(|entity| GraphEntityRow::from_model(kb_id, document_id, entity))
+            .collect();
+        let relations: Vec<GraphRelationRow> = graph.relations.iter()
+            .map(|relation| GraphRelationRow::from_model(kb_id, document_id, relation))
+            .collect();
+
+        let (kb, doc) = (kb_id.to_string(), document_id.to_string());
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            delete_document_rows(conn, &kb, &doc)?;
+            diesel::insert_into(graph_entities::table).values(&entities).execute(conn)?;
+            diesel::insert_into(graph_relations::table).values(&relations).execute(conn)?;
+            Ok(())
+        }).await?;
+
+        debug!(
+            "Stored graph of document {} in KB {}: {} entities, {} relations",
+            document_id, kb_id, graph.entities.len(), graph.relations.len()
+        );
+        Ok(())
+    }
+
+    /// Drop a document's graph; returns the rows removed
+    pub async fn remove_document(&self, kb_id: &str, document_id: &str) -> Result<usize, GraphError> {
+        let (kb, doc) = (kb_id.to_string(), document_id.to_string());
+        Ok(self.sql_service.with_kb_transaction(kb_id, move |conn| delete_document_rows(conn, &kb, &doc)).await?)
+    }
+
+    /// Drop every graph row of a KB; returns the rows removed
+    pub async fn remove_kb(&self, kb_id: &str) -> Result<usize, GraphError> {
+        let kb = kb_id.to_string();
+        Ok(self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            let entities = diesel::delete(graph_entities::table.filter(graph_entities::kb_id.eq(&kb)))
+                .execute(conn)?;
+            let relations = diesel::delete(graph_relations::table.filter(graph_relations::kb_id.eq(&kb)))
+                .execute(conn)?;
+            Ok(entities + relations)
+        }).await?)
+    }
+
+    /// Entities linked to any of `entity_ids`, strongest first
+    pub async fn related_entities(&self, kb_id: &str, entity_ids: &[String], limit: usize) -> Result<Vec<RelatedEntity>, GraphError> {
+        if entity_ids.is_empty() || limit == 0 {
+            return Ok(Vec::new());
+        }
+
+        let (kb, ids) = (kb_id.to_string(), entity_ids.to_vec());
+        let relations = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(graph_relations::table
+                .filter(graph_relations::kb_id.eq(&kb))
+                .filter(graph_relations::source_id.eq_any(&ids).or(graph_relations::target_id.eq_any(&ids)))
+                .select(GraphRelationRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        // (other, relation) → (summed weight, query entity)
+        let query_ids: HashSet<&str> = entity_ids.iter().map(String::as_str).collect();
+        let mut linked: BTreeMap<(String, String), (u32, String)> = BTreeMap::new();
+        for row in relations {
+            let (via, other) = if query_ids.contains(row.source_id.as_str()) {
+                (row.source_id, row.target_id)
+            } else {
+                (row.target_id, row.source_id)
+            };
+            if query_ids.contains(other.as_str()) {
+                continue;
+            }
+            let entry = linked.entry((other, row.relation)).or_insert((0, via));
+            entry.0 += row.weight.max(0) as u32;
+        }
+        let mut ranked: Vec<((String, String), (u32, String))> = linked.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.0.cmp(&a.1.0).then(a.0.cmp(&b.0)));
+        ranked.truncate(limit);
+
+        let names = self.entity_names(kb_id, ranked.iter().map(|((id, _), _)| id.clone()).collect()).await?;
+        Ok(ranked.into_iter()
+            .map(|((entity_id, relation), (weight, via))| {
+                let (name, entity_type) = names.get(&entity_id).cloned().unwrap_or_else(|| (entity_id.clone(), String::new()));
+                RelatedEntity { entity_id, name, entity_type, relation, weight, via }
+            })
+            .collect())
+    }
+
+    /// Append the names of up to `max_terms` entities related to those `query` mentions
+    pub async fn expand_query(&self, kb_id: &str, query: &str, max_terms: usize) -> Result<QueryExpansion, GraphError> {
+        if query.trim().is_empty() {
+            return Err(GraphError::InvalidInput("Query cannot be empty".to_string()));
+        }
+
+        // Entity ids are normalized word sequences, so query n-grams match them directly
+        let words: Vec<String> = entity_id(query).split(' ').map(str::to_string).collect();
+        let mut candidates: Vec<String> = (1..=MAX_QUERY_NGRAM)
+            .flat_map(|n| words.windows(n).map(|window| window.join(" ")).collect::<Vec<_>>())
+            .filter(|candidate| !candidate.is_empty())
+            .collect();
+        candidates.sort();
+        candidates.dedup();
+
+        let kb = kb_id.to_string();
+        let matched: Vec<String> = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(graph_entities::table
+                .filter(graph_entities::kb_id.eq(&kb))
+                .filter(graph_entities::entity_id.eq_any(&candidates))
+                .select(graph_entities::entity_id)
+                .distinct()
+                .order(graph_entities::entity_id)
+                .load(conn)?)
+        }).await?;
+
+        // Entities already in the query add nothing
+        let query_id = format!(" {} ", words.join(" "));
+        let related: Vec<RelatedEntity> = self.related_entities(kb_id, &matched, max_terms * 2).await?
+            .into_iter()
+            .filter(|entity| !query_id.contains(&format!(" {} ", entity.entity_id)))
+            .take(max_terms)
+            .collect();
+
+        let mut expanded_query = query.trim().to_string();
+        for entity in &related {
+            expanded_query.push(' ');
+            expanded_query.push_str(&entity.name);
+        }
+        debug!("Expanded query for KB {} with {} related entities", kb_id, related.len());
+        Ok(QueryExpansion { query: query.to_string(), expanded_query, matched, related })
+    }
+
+    /// Display name and type per entity: the form used by the most mentions across documents
+    async fn entity_names(&self, kb_id: &str, entity_ids: Vec<String>) -> Result<HashMap<String, (String, String)>, GraphError> {
+        let kb = kb_id.to_string();
+        let rows = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(graph_entities::table
+                .filter(graph_entities::kb_id.eq(&kb))
+                .filter(graph_entities::entity_id.eq_any(&entity_ids))
+                .select(GraphEntityRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        let mut best: HashMap<String, GraphEntityRow> = HashMap::new();
+        for row in rows {
+            match best.get(&row.entity_id) {
+                Some(kept) if kept.mentions >= row.mentions => {}
+                _ => {
+                    best.insert(row.entity_id.clone(), row);
+                }
+            }
+        }
+        Ok(best.into_iter().map(|(id, row)| (id, (row.name, row.entity_type))).collect())
+    }
+}
+
+fn delete_document_rows(conn: &mut SqliteConnection, kb_id: &str, document_id: &str) -> Result<usize, SqlError> {
+    let entities = diesel::delete(graph_entities::table
+        .filter(graph_entities::kb_id.eq(kb_id))
+        .filter(graph_entities::document_id.eq(document_id)))
+        .execute(conn)?;
+    let relations = diesel::delete(graph_relations::table
+        .filter(graph_relations::kb_id.eq(kb_id))
+        .filter(graph_relations::document_id.eq(document_id)))
+        .execute(conn)?;
+    Ok(entities + relations)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::modules::graph::{extract_graph, ExtractOptions};
+    use crate::services::sql::SqlConfig;
+
+    #[tokio::test]
+    async fn test_graph_storage_and_query_expansion() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let graph_service = GraphService::new(Arc::new(sql_service));
+
+        let options = ExtractOptions::default();
+        let first = extract_graph("The Manager talks to Lance DB. The Manager also starts the MCP server.", &[], &options);
+        let second = extract_graph("Lance DB stores vectors for the Manager and the Tantivy index.", &[], &options);
+        graph_service.replace_document("kb_1", "doc_1", &first).await.unwrap();
+        graph_service.replace_document("kb_1", "doc_2", &second).await.unwrap();
+        // Re-extracting a document replaces its rows
+        graph_service.replace_document("kb_1", "doc_2", &second).await.unwrap();
+
+        let expansion = graph_service.expand_query("kb_1", "How does lance db work?", 5).await.unwrap();
+        assert_eq!(expansion.matched, vec!["lance db"]);
+        let related: Vec<(&str, u32)> = expansion.related.iter().map(|e| (e.name.as_str(), e.weight)).collect();
+        assert_eq!(related, vec![("Manager", 2), ("Tantivy", 1)]);
+        assert_eq!(expansion.expanded_query, "How does lance db work? Manager Tantivy");
+        assert!(expansion.related.iter().all(|e| e.via == "lance db" && e.relation == RELATION_CO_OCCURS));
+
+        // Related entities the query already names are skipped
+        let expansion = graph_service.expand_query("kb_1", "lance db and the manager", 5).await.unwrap();
+        assert_eq!(expansion.matched, vec!["lance db", "manager"]);
+        let names: Vec<&str> = expansion.related.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Tantivy", "MCP"]);
+
+        graph_service.remove_document("kb_1", "doc_2").await.unwrap();
+        let expansion = graph_service.expand_query("kb_1", "lance db", 5).await.unwrap();
+        let names: Vec<&str> = expansion.related.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, vec!["Manager"]);
+        let expansion = graph_service.expand_query("kb_1", "unrelated words", 5).await.unwrap();
+        assert!(expansion.matched.is_empty());
+        assert_eq!(expansion.expanded_query, "unrelated words");
+    }
+}
@@ -0,0 +1,368 @@
+/*!
+ * Citation-Grounded Answers
+ *
+ * Builds the generation prompt for `rag.answer` from retrieved chunks and
+ * maps the `[n]` markers in the model's answer back to the sources' citations.
+ * `answer_question` runs the whole pipeline in-process (retrieve, pack,
+ * generate, cite) for callers that hold the services directly. Retrieved
+ * chunks are packed into the context budget first (see `context`), and `[n]`
+ * markers refer to the packed sources.
+ *
+ * Citations are verified after generation: every marker must name a retrieved
+ * chunk, and text the answer quotes must appear in a chunk its sentence cites
+ * (exactly, or for most of its words). Failures are flagged rather than
+ * removed, and the share of sentences that pass becomes the answer's
+ * groundedness score.
+ */
+
+use std::sync::OnceLock;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::context::{pack_context, ContextBudget, EstimatingTokenizer};
+use super::errors::KbError;
+use super::service::KbService;
+use crate::schemas::{CitationInfo, SearchResult};
+use crate::services::generation::GenerationParams;
+use crate::services::llm::{LlmError, LlmProviderConfig, LlmService};
+
+/// Answer returned when retrieval finds nothing to ground on
+pub const NO_SOURCES_ANSWER: &str = "I couldn't find anything in the knowledge base to answer this question.";
+
+/// Share of a quote's words a chunk must contain for a fuzzy match
+const FUZZY_QUOTE_THRESHOLD: f64 = 0.8;
+
+/// Shortest quoted span that is checked (shorter ones are usually terms, not quotes)
+const MIN_QUOTE_CHARS: usize = 8;
+
+/// Citation for one marker used in the answer
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnswerCitation {
+    pub marker: usize,              // `[n]` in the answer, 1-based
+    pub chunk_id: String,
+    pub document_id: String,
+    pub citation: CitationInfo,
+    #[serde(default)]
+    pub verified: bool,             // False if any use of the marker was flagged
+}
+
+/// Why a citation in the answer couldn't be verified
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CitationIssue {
+    UnknownSource,                  // The marker doesn't name a retrieved chunk
+    QuoteNotFound,                  // A quote in the sentence isn't in any chunk it cites
+}
+
+/// Citation flagged during verification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UnverifiedCitation {
+    pub marker: usize,
+    pub issue: CitationIssue,
+    pub sentence: String,
+    pub quote: Option<String>,
+}
+
+/// Generated answer with its resolved citations
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GroundedAnswer {
+    pub answer: String,
+    pub citations: Vec<AnswerCitation>,   // In order of first use
+    pub sources_considered: usize,
+    #[serde(default)]
+    pub unverified: Vec<UnverifiedCitation>,
+    #[serde(default)]
+    pub groundedness: f64,                // Share of sentences with verified citations, 0..=1
+}
+
+/// Answer pipeline errors
+#[derive(Debug, Error)]
+pub enum AnswerError {
+    #[error(transparent)]
+    Kb(#[from] KbError),
+
+    #[error(transparent)]
+    Llm(#[from] LlmError),
+}
+
+/// Question to answer from one collection
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AnswerRequest {
+    pub collection: String,
+    pub question: String,
+    pub top_k: usize,
+    #[serde(default)]
+    pub params: GenerationParams,
+    #[serde(default)]
+    pub context_tokens: Option<usize>,  // Budget for packed sources, default `DEFAULT_CONTEXT_TOKENS`
+}
+
+/// Retrieve, generate a grounded answer and resolve its citations
+pub async fn answer_question(
+    kb_service: &dyn KbService,
+    llm_service: &LlmService,
+    provider: &LlmProviderConfig,
+    request: &AnswerRequest,
+) -> Result<GroundedAnswer, AnswerError> {
+    let sources = kb_service.hybrid_search(&request.collection, &request.question, request.top_k, None, None).await?;
+    // Never let the model answer without sources
+    if sources.is_empty() {
+        return Ok(cite_answer(NO_SOURCES_ANSWER, &sources));
+    }
+
+    let budget = request.context_tokens.map(ContextBudget::new).unwrap_or_default();
+    let packed = pack_context(&sources, &budget, &EstimatingTokenizer);
+    let prompt = build_grounded_prompt(&request.question, &packed.sources);
+    let output = llm_service.generate(provider, &prompt, &request.params).await?;
+    Ok(cite_answer(&output.text, &packed.sources))
+}
+
+/// Prompt asking the model to answer only from the numbered sources; pack the
+/// sources first, their content is included as is
+pub fn build_grounded_prompt(question: &str, sources: &[SearchResult]) -> String {
+    let mut prompt = String::from(
+        "Answer the question using only the sources below. \
+         Cite every claim with the source number in square brackets, e.g. [1] or [2][3]. \
+         If the sources do not contain the answer, say so.\n\nSources:\n",
+    );
+    for (index, source) in sources.iter().enumerate() {
+        prompt.push_str(&format!("[{}] {} ({})\n{}\n\n", index + 1, source.citation.title, source.citation.source_path, source.content));
+    }
+    prompt.push_str(&format!("Question: {}\nAnswer:", question));
+    prompt
+}
+
+/// Resolve `[n]` markers against the sources and verify them; unknown markers
+/// are dropped from the citation list and flagged
+pub fn cite_answer(answer: &str, sources: &[SearchResult]) -> GroundedAnswer {
+    let mut citations: Vec<AnswerCitation> = Vec::new();
+    let mut unverified: Vec<UnverifiedCitation> = Vec::new();
+    let mut grounded = 0;
+
+    let sentences = split_sentences(answer);
+    for sentence in &sentences {
+        let known: Vec<usize> = sentence.markers.iter().copied()
+            .filter(|&number| number >= 1 && number <= sources.len())
+            .collect();
+        for &number in sentence.markers.iter().filter(|number| !known.contains(number)) {
+            unverified.push(UnverifiedCitation {
+                marker: number,
+                issue: CitationIssue::UnknownSource,
+                sentence: sentence.text.clone(),
+                quote: None,
+            });
+        }
+        for &number in &known {
+            if citations.iter().any(|c| c.marker == number) {
+                continue;
+            }
+            let source = &sources[number - 1];
+            citations.push(AnswerCitation {
+                marker: number,
+                chunk_id: source.chunk_id.clone(),
+                document_id: source.document_id.clone(),
+                citation: source.citation.clone(),
+                verified: true,
+            });
+        }
+
+        // A quote may come from any source its sentence cites
+        let mut quotes_found = true;
+        for quote in &sentence.quotes {
+            if known.iter().any(|&number| quote_in_source(quote, &sources[number - 1].content)) {
+                continue;
+            }
+            quotes_found = false;
+            for &number in &known {
+                unverified.push(UnverifiedCitation {
+                    marker: number,
+                    issue: CitationIssue::QuoteNotFound,
+                    sentence: sentence.text.clone(),
+                    quote: Some(quote.clone()),
+                });
+            }
+        }
+        if !known.is_empty() && quotes_found {
+            grounded += 1;
+        }
+    }
+
+    for citation in &mut citations {
+        citation.verified = !unverified.iter().any(|u| u.marker == citation.marker);
+    }
+    if !unverified.is_empty() {
+        tracing::debug!("{} citations in the answer could not be verified", unverified.len());
+    }
+
+    GroundedAnswer {
+        answer: answer.trim().to_string(),
+        citations,
+        sources_considered: sources.len(),
+        unverified,
+        groundedness: if sentences.is_empty() { 0.0 } else { grounded as f64 / sentences.len() as f64 },
+    }
+}
+
+/// Sentence of an answer with the markers and quotes it contains
+struct Sentence {
+    text: String,
+    markers: Vec<usize>,
+    quotes: Vec<String>,
+}
+
+/// Split an answer into sentences. Markers written after the full stop
+/// (`... installer. [2]`) belong to the sentence before them.
+fn split_sentences(answer: &str) -> Vec<Sentence> {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    static LEADING_MARKERS: OnceLock<Regex> = OnceLock::new();
+    static QUOTE: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| Regex::new(r"\[(\d{1,3})\]").unwrap());
+    let leading_markers = LEADING_MARKERS.get_or_init(|| Regex::new(r"^(\s*\[\d{1,3}\])+").unwrap());
+    let quote = QUOTE.get_or_init(|| Regex::new(r#"["“]([^"“”]+)["”]"#).unwrap());
+
+    let mut pieces: Vec<String> = Vec::new();
+    let mut current = String::new();
+    let mut chars = answer.chars().peekable();
+    while let Some(c) = chars.next() {
+        current.push(c);
+        let boundary = c == '\n' || (matches!(c, '.' | '!' | '?') && chars.peek().is_none_or(|next| next.is_whitespace()));
+        if boundary {
+            pieces.push(std::mem::take(&mut current));
+        }
+    }
+    pieces.push(current);
+
+    let mut texts: Vec<String> = Vec::new();
+    for piece in pieces {
+        let mut piece = piece.trim();
+        if let (Some(previous), Some(markers)) = (texts.last_mut(), leading_markers.find(piece)) {
+            previous.push(' ');
+            previous.push_str(markers.as_str().trim());
+            piece = piece[markers.end()..].trim();
+        }
+        // Sentences need some words; lone markers and punctuation don't count
+        if piece.chars().any(char::is_alphanumeric) && leading_markers.find(piece).is_none_or(|m| m.end() != piece.len()) {
+            texts.push(piece.to_string());
+        }
+    }
+
+    texts.into_iter()
+        .map(|text| Sentence {
+            markers: marker.captures_iter(&text).filter_map(|c| c[1].parse().ok()).collect(),
+            quotes: quote.captures_iter(&text)
+                .map(|c| c[1].trim().to_string())
+                .filter(|q| q.chars().count() >= MIN_QUOTE_CHARS)
+                .collect(),
+            text,
+        })
+        .collect()
+}
+
+/// Whether a quote appears in a source: verbatim after normalizing case and
+/// whitespace, or with most of its words present
+fn quote_in_source(quote: &str, content: &str) -> bool {
+    let quote = normalize(quote);
+    let content = normalize(content);
+    if content.contains(&quote) {
+        return true;
+    }
+    let words: Vec<&str> = quote.split(' ').filter(|w| !w.is_empty()).collect();
+    if words.is_empty() {
+        return false;
+    }
+    let content_words: std::collections::HashSet<&str> = content.split(' ').collect();
+    let found = words.iter().filter(|w| content_words.contains(*w)).count();
+    found as f64 / words.len() as f64 >= FUZZY_QUOTE_THRESHOLD
+}
+
+/// Lowercase, punctuation dropped, whitespace collapsed
+fn normalize(text: &str) -> String {
+    text.to_lowercase()
+        .chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn source(chunk_id: &str, title: &str) -> SearchResult {
+        SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: format!("doc_{}", chunk_id),
+            kb_id: "kb_1".to_string(),
+            score: 0.9,
+            content: format!("Content of {}", title),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: json!({}),
+            citation: CitationInfo {
+                title: title.to_string(),
+                source_path: format!("/docs/{}.md", title),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_grounded_prompt_numbers_sources() {
+        let prompt = build_grounded_prompt("How do I install?", &[source("c1", "install"), source("c2", "upgrade")]);
+        assert!(prompt.contains("[1] install (/docs/install.md)\nContent of install"));
+        assert!(prompt.contains("[2] upgrade"));
+        assert!(prompt.ends_with("Question: How do I install?\nAnswer:"));
+    }
+
+    #[test]
+    fn test_cite_answer() {
+        let sources = [source("c1", "install"), source("c2", "upgrade")];
+        let answer = cite_answer(" Run the installer [2][1]. Then upgrade [2]. See also [7]. \n", &sources);
+
+        assert_eq!(answer.answer, "Run the installer [2][1]. Then upgrade [2]. See also [7].");
+        let markers: Vec<usize> = answer.citations.iter().map(|c| c.marker).collect();
+        assert_eq!(markers, vec![2, 1]);
+        assert_eq!(answer.citations[0].chunk_id, "c2");
+        assert_eq!(answer.citations[0].citation.title, "upgrade");
+        assert_eq!(answer.sources_considered, 2);
+        // The last sentence cites nothing retrieved
+        assert_eq!(answer.unverified.len(), 1);
+        assert_eq!(answer.unverified[0].issue, CitationIssue::UnknownSource);
+        assert!((answer.groundedness - 2.0 / 3.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_quotes_verified_against_cited_sources() {
+        let mut install = source("c1", "install");
+        install.content = "To install, run the setup wizard and accept the license.".to_string();
+        let sources = [install, source("c2", "upgrade")];
+
+        // Exact (modulo case and punctuation), fuzzy, and trailing markers
+        let answer = cite_answer(
+            "The docs say to \"Run the setup wizard\" [1]. It also says to \"run the wizard and accept the license\". [1]",
+            &sources,
+        );
+        assert!(answer.unverified.is_empty());
+        assert!(answer.citations[0].verified);
+        assert_eq!(answer.groundedness, 1.0);
+
+        // Quoting a source that isn't cited flags the citation
+        let answer = cite_answer("Upgrading is \"run the setup wizard twice\" [2]. Then restart [1].", &sources);
+        assert_eq!(answer.unverified.len(), 1);
+        assert_eq!(answer.unverified[0].marker, 2);
+        assert_eq!(answer.unverified[0].issue, CitationIssue::QuoteNotFound);
+        assert!(!answer.citations[0].verified);
+        assert!(answer.citations[1].verified);
+        assert_eq!(answer.groundedness, 0.5);
+
+        assert_eq!(cite_answer(NO_SOURCES_ANSWER, &[]).groundedness, 0.0);
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+ * Context Window Packing
+ *
+ * Fits retrieved chunks into the LLM's context budget before a grounded
+ * prompt is built:
+ * - duplicate chunks (same ID, same text, or text contained in a better
+ *   ranked chunk) are dropped
+ * - adjacent chunks of one document are merged, without repeating their overlap
+ * - groups are taken by relevance until the token budget is spent; the first
+ *   group that doesn't fit is truncated if enough room is left
+ * - the result is ordered by relevance of each document, then by position
+ *   within the document
+ *
+ * Callers number the packed sources for citations, so `[n]` markers resolve
+ * against the packed list. Merged sources carry their chunk IDs in
+ * `metadata.merged_chunk_ids`.
+ */
+
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+use crate::schemas::SearchResult;
+
+/// Context budget when the caller doesn't set one
+pub const DEFAULT_CONTEXT_TOKENS: usize = 3000;
+
+/// Prompt tokens per source besides its text (number, title, path)
+const SOURCE_OVERHEAD_TOKENS: usize = 24;
+
+/// Smallest truncated source worth including
+const MIN_TRUNCATED_TOKENS: usize = 64;
+
+/// Shortest text overlap treated as two chunks being adjacent
+const MIN_OVERLAP_CHARS: usize = 16;
+
+/// Longest overlap looked for between two chunks
+const MAX_OVERLAP_CHARS: usize = 512;
+
+/// Counts tokens the way the target model will
+pub trait Tokenizer: Send + Sync {
+    fn count_tokens(&self, text: &str) -> usize;
+
+    /// Longest prefix of `text` within `max_tokens`, cut at a word boundary when possible
+    fn truncate(&self, text: &str, max_tokens: usize) -> String {
+        let boundaries: Vec<usize> = text.char_indices().map(|(i, _)| i).chain([text.len()]).collect();
+        // Binary search on the number of characters kept
+        let (mut low, mut high) = (0, boundaries.len() - 1);
+        while low < high {
+            let mid = (low + high).div_ceil(2);
+            if self.count_tokens(&text[..boundaries[mid]]) <= max_tokens {
+                low = mid;
+            } else {
+                high = mid - 1;
+            }
+        }
+        let prefix = &text[..boundaries[low]];
+        // Don't end in the middle of a word
+        let mid_word = !prefix.ends_with(char::is_whitespace)
+            && text[prefix.len()..].starts_with(|c: char| !c.is_whitespace());
+        match prefix.rfind(char::is_whitespace) {
+            Some(end) if mid_word => prefix[..end].trim_end().to_string(),
+            _ => prefix.trim_end().to_string(),
+        }
+    }
+}
+
+/// Model-agnostic estimate: about four characters per token for words, one
+/// token per punctuation mark. MVP: upgrade path to the served model's tokenizer.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EstimatingTokenizer;
+
+impl Tokenizer for EstimatingTokenizer {
+    fn count_tokens(&self, text: &str) -> usize {
+        let mut tokens = 0;
+        let mut word: usize = 0;
+        for c in text.chars() {
+            if c.is_alphanumeric() {
+                word += 1;
+                continue;
+            }
+            tokens += word.div_ceil(4);
+            word = 0;
+            if !c.is_whitespace() {
+                tokens += 1;
+            }
+        }
+        tokens + word.div_ceil(4)
+    }
+}
+
+/// How much context the packed sources may take
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ContextBudget {
+    pub max_tokens: usize,
+    pub source_overhead_tokens: usize,
+}
+
+impl ContextBudget {
+    pub fn new(max_tokens: usize) -> Self {
+        Self { max_tokens, ..Default::default() }
+    }
+}
+
+impl Default for ContextBudget {
+    fn default() -> Self {
+        Self {
+            max_tokens: DEFAULT_CONTEXT_TOKENS,
+            source_overhead_tokens: SOURCE_OVERHEAD_TOKENS,
+        }
+    }
+}
+
+/// Sources that fit the budget, ready to number in a prompt
+#[derive(Debug, Clone, Serialize)]
+pub struct PackedContext {
+    pub sources: Vec<SearchResult>,
+    pub tokens: usize,
+    pub duplicates_dropped: usize,
+    pub chunks_merged: usize,
+    pub chunks_left_out: usize,     // Didn't fit the budget
+    pub truncated: bool,
+}
+
+/// Chunks of one document merged into a single source
+struct Group {
+    rank: usize,                    // Best rank among the members
+    document_rank: usize,           // Best rank of the document
+    position: Option<u64>,          // First chunk's index
+    last_position: Option<u64>,
+    chunk_ids: Vec<String>,
+    result: SearchResult,
+}
+
+/// Pack ranked results (best first) into the budget
+pub fn pack_context(results: &[SearchResult], budget: &ContextBudget, tokenizer: &dyn Tokenizer) -> PackedContext {
+    // Dedupe in rank order so the better ranked copy survives
+    let mut kept: Vec<(usize, &SearchResult)> = Vec::new();
+    let mut seen_ids = HashSet::new();
+    let mut duplicates_dropped = 0;
+    for (rank, result) in results.iter().enumerate() {
+        let text = normalize(&result.content);
+        let duplicate = !seen_ids.insert(result.chunk_id.as_str())
+            || text.is_empty()
+            || kept.iter().any(|(_, other)| normalize(&other.content).contains(&text));
+        if duplicate {
+            duplicates_dropped += 1;
+        } else {
+            kept.push((rank, result));
+        }
+    }
+
+    // Merge neighbours within each document
+    let mut document_ranks: HashMap<&str, usize> = HashMap::new();
+    let mut by_document: HashMap<&str, Vec<(usize, &SearchResult)>> = HashMap::new();
+    for &(rank, result) in &kept {
+        document_ranks.entry(result.document_id.as_str()).or_insert(rank);
+        by_document.entry(result.document_id.as_str()).or_default().push((rank, result));
+    }
+    let mut groups: Vec<Group> = Vec::new();
+    let mut chunks_merged = 0;
+    for (document_id, mut chunks) in by_document {
+        // Chunks without a position keep their rank order
+        chunks.sort_by_key(|(rank, result)| (chunk_index(result).unwrap_or(u64::MAX), *rank));
+        let document_rank = document_ranks[document_id];
+        let mut current: Option<Group> = None;
+        for (rank, result) in chunks {
+            if let Some(group) = current.as_mut() {
+                let consecutive = matches!((group.last_position, chunk_index(result)), (Some(a), Some(b)) if b == a + 1);
+                let overlap = overlap_len(&group.result.content, &result.content);
+                if consecutive || overlap >= MIN_OVERLAP_CHARS {
+                    group.result.content.push_str(&result.content[overlap..]);
+                    group.result.score = group.result.score.max(result.score);
+                    group.rank = group.rank.min(rank);
+                    group.last_position = chunk_index(result).or(group.last_position);
+                    group.chunk_ids.push(result.chunk_id.clone());
+                    chunks_merged += 1;
+                    continue;
+                }
+            }
+            groups.extend(current.take());
+            current = Some(Group {
+                rank,
+                document_rank,
+                position: chunk_index(result),
+                last_position: chunk_index(result),
+                chunk_ids: vec![result.chunk_id.clone()],
+                result: result.clone(),
+            });
+        }
+        groups.extend(current);
+    }
+
+    // Spend the budget by relevance
+    groups.sort_by_key(|group| group.rank);
+    let mut selected: Vec<Group> = Vec::new();
+    let mut tokens = 0;
+    let mut chunks_left_out = 0;
+    let mut truncated = false;
+    for mut group in groups {
+        let remaining = budget.max_tokens.saturating_sub(tokens + budget.source_overhead_tokens);
+        let cost = tokenizer.count_tokens(&group.result.content);
+        if cost <= remaining {
+            tokens += cost + budget.source_overhead_tokens;
+            selected.push(group);
+        } else if !truncated && remaining >= MIN_TRUNCATED_TOKENS {
+            group.result.content = tokenizer.truncate(&group.result.content, remaining);
+            tokens += tokenizer.count_tokens(&group.result.content) + budget.source_overhead_tokens;
+            truncated = true;
+            selected.push(group);
+        } else {
+            chunks_left_out += group.chunk_ids.len();
+        }
+    }
+
+    // Most relevant document first, each document read top to bottom
+    selected.sort_by_key(|group| (group.document_rank, group.position.unwrap_or(u64::MAX), group.rank));
+    let sources = selected.into_iter()
+        .map(|group| {
+            let mut result = group.result;
+            if group.chunk_ids.len() > 1 {
+                if let Some(metadata) = result.metadata.as_object_mut() {
+                    metadata.insert("merged_chunk_ids".to_string(), Value::from(group.chunk_ids));
+                } else {
+                    result.metadata = serde_json::json!({ "merged_chunk_ids": group.chunk_ids });
+                }
+            }
+            result
+        })
+        .collect();
+
+    PackedContext { sources, tokens, duplicates_dropped, chunks_merged, chunks_left_out, truncated }
+}
+
+/// Position of a chunk in its document (set when it was indexed)
+fn chunk_index(result: &SearchResult) -> Option<u64> {
+    result.metadata.get("chunk_index").and_then(Value::as_u64)
+}
+
+/// Bytes at the start of `next` that repeat the end of `previous`
+fn overlap_len(previous: &str, next: &str) -> usize {
+    let limit = next.char_indices()
+        .map(|(i, _)| i)
+        .chain([next.len()])
+        .take_while(|&i| i <= MAX_OVERLAP_CHARS.min(previous.len()))
+        .collect::<Vec<_>>();
+    limit.into_iter()
+        .rev()
+        .find(|&i| i > 0 && previous.ends_with(&next[..i]))
+        .unwrap_or(0)
+}
+
+/// Lowercase with whitespace collapsed, for duplicate detection
+fn normalize(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ").to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use crate::schemas::CitationInfo;
+
+    fn chunk(chunk_id: &str, document_id: &str, index: Option<u64>, content: &str) -> SearchResult {
+        SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: document_id.to_string(),
+            kb_id: "kb_1".to_string(),
+            score: 0.5,
+            content: content.to_string(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: index.map(|i| json!({ "chunk_index": i })).unwrap_or(json!({})),
+            citation: CitationInfo {
+                title: document_id.to_string(),
+                source_path: format!("/docs/{}.md", document_id),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_estimating_tokenizer() {
+        let tokenizer = EstimatingTokenizer;
+        assert_eq!(tokenizer.count_tokens(""), 0);
+        assert_eq!(tokenizer.count_tokens("Run setup."), 4);
+        assert_eq!(tokenizer.truncate("one two three four", 2), "one two");
+        assert_eq!(tokenizer.truncate("internationalization", 2), "internat");
+        assert_eq!(tokenizer.truncate("short", 10), "short");
+    }
+
+    #[test]
+    fn test_pack_dedupes_merges_and_orders() {
+        let results = vec![
+            chunk("b_1", "b", Some(1), "Then restart the service so the new settings apply to every node."),
+            chunk("a_0", "a", Some(0), "Install the package with the setup wizard on each machine"),
+            chunk("b_0", "b", Some(0), "Edit the config file and set the port. Then restart the service"),
+            chunk("b_1", "b", Some(1), "Then restart the service so the new settings apply to every node."),
+            chunk("a_dup", "c", None, "install the  package with the setup wizard"),
+        ];
+        let packed = pack_context(&results, &ContextBudget::default(), &EstimatingTokenizer);
+
+        assert_eq!(packed.duplicates_dropped, 2);
+        assert_eq!(packed.chunks_merged, 1);
+        let ids: Vec<&str> = packed.sources.iter().map(|s| s.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["b_0", "a_0"]);
+        // The overlap is not repeated
+        assert_eq!(
+            packed.sources[0].content,
+            "Edit the config file and set the port. Then restart the service so the new settings apply to every node.",
+        );
+        assert_eq!(packed.sources[0].metadata["merged_chunk_ids"], json!(["b_0", "b_1"]));
+    }
+
+    #[test]
+    fn test_pack_respects_budget() {
+        let long = "word ".repeat(400);
+        let results = vec![
+            chunk("a_0", "a", None, &long),
+            chunk("b_0", "b", None, &long.replace("word", "text")),
+            chunk("c_0", "c", None, "A short closing note."),
+        ];
+        let tokenizer = EstimatingTokenizer;
+        let budget = ContextBudget::new(600);
+        let packed = pack_context(&results, &budget, &tokenizer);
+
+        assert!(packed.tokens <= budget.max_tokens);
+        assert!(packed.truncated);
+        let ids: Vec<&str> = packed.sources.iter().map(|s| s.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["a_0", "b_0"]);
+        assert!(tokenizer.count_tokens(&packed.sources[1].content) < 400);
+        assert_eq!(packed.chunks_left_out, 1);
+
+        // Too little room left to be worth truncating
+        let packed = pack_context(&results, &ContextBudget::new(480), &tokenizer);
+        let ids: Vec<&str> = packed.sources.iter().map(|s| s.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["a_0", "c_0"]);
+        assert!(!packed.truncated);
+    }
+}
@@ -4,7 +4,10 @@
  * Domain-specific error types for Knowledge Base operations.
  */
 
+use crate::modules::graph::GraphError;
+use crate::services::quota::QuotaError;
 use crate::services::sql::SqlError;
+use crate::services::storage::StorageError;
 use crate::services::vector::VectorDbError;
 
 /// Knowledge Base Domain Error Types
@@ -16,9 +19,24 @@ pub enum KbError {
     #[error("Vector database error: {0}")]
     VectorError(#[from] VectorDbError),
 
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+
+    #[error(transparent)]
+    QuotaError(#[from] QuotaError),
+
+    #[error("Knowledge graph error: {0}")]
+    GraphError(#[from] GraphError),
+
     #[error("KB not found: {0}")]
     KbNotFound(String),
 
+    #[error("Document not found: {0}")]
+    DocumentNotFound(String),
+
+    #[error("KB version not found: {0}")]
+    VersionNotFound(String),
+
     #[error("Invalid search query: {0}")]
     InvalidQuery(String),
 
@@ -33,4 +51,7 @@ pub enum KbError {
 
     #[error("State error: {0}")]
     StateError(String),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
 }
\ No newline at end of file
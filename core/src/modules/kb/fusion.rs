@@ -0,0 +1,120 @@
+/*!
+ * Result Fusion
+ *
+ * Reciprocal Rank Fusion (RRF) for merging ranked result lists from several
+ * queries or knowledge bases. Only ranks are used, so lists with scores on
+ * different scales (BM25, cosine, other KBs) merge fairly.
+ */
+
+use std::collections::HashMap;
+
+use crate::schemas::SearchResult;
+
+/// Standard RRF damping constant
+pub const DEFAULT_RRF_K: f32 = 60.0;
+
+/// Merge ranked lists with RRF; results present in several lists are kept once (by KB + chunk)
+pub fn reciprocal_rank_fusion(result_sets: &[Vec<SearchResult>], k: f32, top_k: usize) -> Vec<SearchResult> {
+    let mut fused: HashMap<(String, String), (f32, SearchResult)> = HashMap::new();
+    let mut first_seen: Vec<(String, String)> = Vec::new();
+
+    for results in result_sets {
+        for (rank, result) in results.iter().enumerate() {
+            let key = (result.kb_id.clone(), result.chunk_id.clone());
+            let contribution = 1.0 / (k + rank as f32 + 1.0);
+            match fused.get_mut(&key) {
+                Some((score, _)) => *score += contribution,
+                None => {
+                    first_seen.push(key.clone());
+                    fused.insert(key, (contribution, result.clone()));
+                }
+            }
+        }
+    }
+
+    // Stable order for ties: first appearance wins
+    let mut merged: Vec<SearchResult> = first_seen.into_iter()
+        .filter_map(|key| fused.remove(&key))
+        .map(|(score, mut result)| {
+            result.score = score;
+            result
+        })
+        .collect();
+    merged.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    merged.truncate(top_k);
+    merged
+}
+
+/// Min-max normalize scores to [0, 1] in place (all-equal scores become 1.0)
+pub fn normalize_scores(results: &mut [SearchResult]) {
+    let (min, max) = results.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), r| (min.min(r.score), max.max(r.score)));
+    let range = max - min;
+    for result in results {
+        result.score = if range > f32::EPSILON { (result.score - min) / range } else { 1.0 };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::CitationInfo;
+    use serde_json::json;
+
+    fn result(kb_id: &str, chunk_id: &str, score: f32) -> SearchResult {
+        SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: format!("doc_{}", chunk_id),
+            kb_id: kb_id.to_string(),
+            score,
+            content: String::new(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: json!({}),
+            citation: CitationInfo {
+                title: chunk_id.to_string(),
+                source_path: String::new(),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        }
+    }
+
+    #[test]
+    fn test_rrf_rewards_agreement_across_lists() {
+        let bm25 = vec![result("kb_a", "c1", 12.0), result("kb_a", "c2", 9.0), result("kb_a", "c3", 1.0)];
+        let other = vec![result("kb_b", "c1", 0.9), result("kb_a", "c2", 0.8), result("kb_a", "c4", 0.7)];
+
+        let fused = reciprocal_rank_fusion(&[bm25, other], DEFAULT_RRF_K, 10);
+        let ids: Vec<(&str, &str)> = fused.iter().map(|r| (r.kb_id.as_str(), r.chunk_id.as_str())).collect();
+
+        // c2 is ranked 2nd in both lists, beating the chunks that top only one list
+        assert_eq!(ids[0], ("kb_a", "c2"));
+        // Same chunk id in another KB is a different result
+        assert_eq!(fused.len(), 5);
+        assert!(ids.contains(&("kb_b", "c1")));
+    }
+
+    #[test]
+    fn test_normalize_scores() {
+        let mut results = vec![result("kb_a", "c1", 12.0), result("kb_a", "c2", 7.0), result("kb_a", "c3", 2.0)];
+        normalize_scores(&mut results);
+        let scores: Vec<f32> = results.iter().map(|r| r.score).collect();
+        assert_eq!(scores, vec![1.0, 0.5, 0.0]);
+
+        let mut single = vec![result("kb_a", "c1", 0.3)];
+        normalize_scores(&mut single);
+        assert_eq!(single[0].score, 1.0);
+    }
+
+    #[test]
+    fn test_rrf_truncates() {
+        let results = vec![result("kb_a", "c1", 1.0), result("kb_a", "c2", 0.5)];
+        let fused = reciprocal_rank_fusion(&[results], DEFAULT_RRF_K, 1);
+        assert_eq!(fused.len(), 1);
+        assert_eq!(fused[0].chunk_id, "c1");
+        assert!((fused[0].score - 1.0 / 61.0).abs() < 1e-6);
+    }
+}
@@ -0,0 +1,128 @@
+/*!
+ * Maximal Marginal Relevance
+ *
+ * Re-ranks candidates to balance relevance against redundancy so answer
+ * context isn't filled with near-duplicate chunks of one document.
+ * MVP: similarity is term-set Jaccard, with a floor for chunks of the same
+ * document; upgrade path to embedding cosine once results carry vectors.
+ */
+
+use std::collections::HashSet;
+
+use crate::schemas::SearchResult;
+
+/// Default trade-off: mostly relevance, some diversity
+pub const DEFAULT_MMR_LAMBDA: f32 = 0.7;
+
+/// Minimum similarity between two chunks of the same document
+const SAME_DOCUMENT_SIMILARITY: f32 = 0.5;
+
+/// Lowercased terms longer than two characters
+pub fn terms(text: &str) -> HashSet<String> {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|t| t.chars().count() > 2)
+        .map(str::to_lowercase)
+        .collect()
+}
+
+fn jaccard(a: &HashSet<String>, b: &HashSet<String>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+/// Select `top_n` candidates by MMR; `lambda` = 1.0 keeps the relevance order,
+/// lower values favour results unlike those already selected. Scores are unchanged.
+pub fn mmr_rerank(candidates: Vec<SearchResult>, lambda: f32, top_n: usize) -> Vec<SearchResult> {
+    let lambda = lambda.clamp(0.0, 1.0);
+
+    // Relevance on a common [0, 1] scale
+    let (min, max) = candidates.iter().fold((f32::INFINITY, f32::NEG_INFINITY), |(min, max), r| (min.min(r.score), max.max(r.score)));
+    let range = max - min;
+    let relevance: Vec<f32> = candidates.iter()
+        .map(|r| if range > f32::EPSILON { (r.score - min) / range } else { 1.0 })
+        .collect();
+    let term_sets: Vec<HashSet<String>> = candidates.iter()
+        .map(|r| terms(if r.content.is_empty() { &r.snippet } else { &r.content }))
+        .collect();
+    let similarity = |i: usize, j: usize| {
+        let same_document = candidates[i].kb_id == candidates[j].kb_id && candidates[i].document_id == candidates[j].document_id;
+        let floor = if same_document { SAME_DOCUMENT_SIMILARITY } else { 0.0 };
+        jaccard(&term_sets[i], &term_sets[j]).max(floor)
+    };
+
+    let mut selected: Vec<usize> = Vec::with_capacity(top_n.min(candidates.len()));
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    while selected.len() < top_n && !remaining.is_empty() {
+        let mut best: Option<(usize, f32)> = None;
+        for (position, &i) in remaining.iter().enumerate() {
+            let redundancy = selected.iter().map(|&j| similarity(i, j)).fold(0.0_f32, f32::max);
+            let score = lambda * relevance[i] - (1.0 - lambda) * redundancy;
+            // Strict comparison keeps the earlier candidate on ties
+            if best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((position, score));
+            }
+        }
+        let (position, _) = best.expect("remaining is not empty");
+        selected.push(remaining.remove(position));
+    }
+
+    let mut slots: Vec<Option<SearchResult>> = candidates.into_iter().map(Some).collect();
+    selected.into_iter().filter_map(|i| slots[i].take()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::schemas::CitationInfo;
+    use serde_json::json;
+
+    fn result(chunk_id: &str, document_id: &str, score: f32, content: &str) -> SearchResult {
+        SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: document_id.to_string(),
+            kb_id: "kb_1".to_string(),
+            score,
+            content: content.to_string(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: json!({}),
+            citation: CitationInfo {
+                title: chunk_id.to_string(),
+                source_path: String::new(),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        }
+    }
+
+    fn candidates() -> Vec<SearchResult> {
+        vec![
+            result("c1", "doc_a", 0.95, "Install the server with the setup wizard"),
+            result("c2", "doc_a", 0.94, "Install the server with the setup wizard on Windows"),
+            result("c3", "doc_b", 0.80, "Firewall ports required by the server"),
+            result("c4", "doc_a", 0.70, "Uninstalling removes configuration"),
+        ]
+    }
+
+    #[test]
+    fn test_mmr_demotes_near_duplicates() {
+        let ranked = mmr_rerank(candidates(), 0.5, 3);
+        let ids: Vec<&str> = ranked.iter().map(|r| r.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["c1", "c3", "c2"]);
+        assert_eq!(ranked[0].score, 0.95);
+    }
+
+    #[test]
+    fn test_lambda_one_keeps_relevance_order() {
+        let ranked = mmr_rerank(candidates(), 1.0, 10);
+        let ids: Vec<&str> = ranked.iter().map(|r| r.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["c1", "c2", "c3", "c4"]);
+        assert!(mmr_rerank(Vec::new(), 0.5, 3).is_empty());
+    }
+}
@@ -8,9 +8,19 @@ pub mod service;
 pub mod models;
 pub mod schema;
 pub mod errors;
+pub mod rpc;
+pub mod answer;
+pub mod context;
+pub mod fusion;
+pub mod mmr;
 
 // Re-export public types
 pub use service::{KbService, KbServiceImpl};
 pub use models::*;
 pub use schema::*;
-pub use errors::KbError;
\ No newline at end of file
+pub use errors::KbError;
+pub use rpc::KbRpcHandler;
+pub use answer::{answer_question, AnswerCitation, AnswerError, AnswerRequest, CitationIssue, GroundedAnswer, UnverifiedCitation};
+pub use context::{pack_context, ContextBudget, EstimatingTokenizer, PackedContext, Tokenizer, DEFAULT_CONTEXT_TOKENS};
+pub use fusion::{normalize_scores, reciprocal_rank_fusion, DEFAULT_RRF_K};
+pub use mmr::{mmr_rerank, DEFAULT_MMR_LAMBDA};
@@ -4,7 +4,12 @@
  * Data structures specific to the Knowledge Base domain.
  */
 
+use std::collections::{HashMap, HashSet};
 use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+use crate::modules::pipeline::PipelineDocument;
+use crate::schemas::CitationInfo;
 
 /// Knowledge Base Configuration
 #[derive(Debug, Clone)]
@@ -15,6 +20,7 @@ pub struct KbConfig {
     pub cache_enabled: bool,            // MVP: Basic caching
     pub max_results: usize,             // Default: 100
     pub citation_required: bool,        // MVP: Always true
+    pub mmr_lambda: Option<f32>,        // MMR diversification of results (None = off)
 }
 
 impl Default for KbConfig {
@@ -26,6 +32,7 @@ impl Default for KbConfig {
             cache_enabled: true,
             max_results: 100,
             citation_required: true,    // MVP: "No citation → no answer"
+            mmr_lambda: None,
         }
     }
 }
@@ -40,6 +47,7 @@ impl KbConfig {
             cache_enabled: true,
             max_results: 50,           // Conservative for MVP
             citation_required: true,
+            mmr_lambda: None,
         }
     }
 
@@ -52,6 +60,7 @@ impl KbConfig {
             cache_enabled: true,
             max_results: 100,
             citation_required: true,
+            mmr_lambda: None,
         }
     }
 
@@ -64,6 +73,7 @@ impl KbConfig {
             cache_enabled: false,
             max_results: 10,
             citation_required: true,
+            mmr_lambda: None,
         }
     }
 }
@@ -87,16 +97,50 @@ pub struct DocumentInfo {
     pub size_bytes: i64,
 }
 
+/// Chunk of a document, located by character offsets into its text
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentChunk {
+    pub chunk_id: String,
+    pub start: usize,
+    pub end: usize,
+    pub citation: CitationInfo,
+}
+
+/// Full text of an indexed document, reassembled from its chunks
+#[derive(Debug, Clone, Serialize)]
+pub struct DocumentContent {
+    pub kb_id: String,
+    #[serde(flatten)]
+    pub document: DocumentInfo,
+    pub content: String,
+    pub citation: CitationInfo,
+    pub chunks: Vec<DocumentChunk>,
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct KbStats {
     pub collection_name: Option<String>,
     pub version: i32,
     pub document_count: usize,
     pub chunk_count: usize,
-    pub size_bytes: u64,
+    pub vector_count: usize,            // Chunks with an embedding
+    pub dimension: Option<usize>,       // None until a vector is stored, or across KBs
+    pub size_bytes: u64,                // Vector store files on disk
     pub health_score: f64,
     pub embedder_version: String,
     pub last_updated: chrono::DateTime<chrono::Utc>,
+    pub generations: Vec<KbGenerationStats>,    // Per version, newest first
+}
+
+/// Vector store size of one KB version's generation
+#[derive(Debug, Clone, Serialize)]
+pub struct KbGenerationStats {
+    pub version: i32,
+    pub generation_id: u64,
+    pub status: KbVersionStatus,
+    pub chunk_count: usize,
+    pub vector_count: usize,
+    pub size_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -118,6 +162,386 @@ pub struct KbStateInfo {
     pub status: String,
     pub health_score: f64,
     pub version: i32,
+    pub embedder_model: String,
+}
+
+/// Context fetched around each search hit ("small-to-big" retrieval): the
+/// index stays fine-grained, the answer gets the surrounding text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum ContextExpansion {
+    /// The hit plus `window` chunks on each side
+    Adjacent {
+        #[serde(default = "default_expansion_window")]
+        window: usize,
+    },
+    /// The heading-delimited section containing the hit, at most `max_chunks` chunks
+    Parent {
+        #[serde(default = "default_parent_chunks")]
+        max_chunks: usize,
+    },
+}
+
+fn default_expansion_window() -> usize {
+    1
+}
+
+fn default_parent_chunks() -> usize {
+    8
+}
+
+/// How a search walks the KB
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchMode {
+    /// Chunks ranked by vector + BM25 scores
+    #[default]
+    Hybrid,
+    /// Summary tree first (documents, then sections), then the chunks under the
+    /// best sections; falls back to hybrid without a summary index
+    Hierarchical,
+}
+
+/// Lifecycle of an immutable KB version
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum KbVersionStatus {
+    Ready,
+    Active,
+    Archived,
+}
+
+impl KbVersionStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KbVersionStatus::Ready => "ready",
+            KbVersionStatus::Active => "active",
+            KbVersionStatus::Archived => "archived",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "ready" => Some(KbVersionStatus::Ready),
+            "active" => Some(KbVersionStatus::Active),
+            "archived" => Some(KbVersionStatus::Archived),
+            _ => None,
+        }
+    }
+}
+
+/// Document entry in a version manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct VersionDocument {
+    pub id: String,
+    pub title: String,
+    pub source_path: String,
+    pub content_hash: String,
+    pub chunk_count: usize,
+}
+
+/// What went into a KB version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KbVersionManifest {
+    pub pipeline_id: Option<String>,
+    pub pipeline_run_id: Option<String>,
+    pub documents: Vec<VersionDocument>,
+    pub metadata: serde_json::Value,
+}
+
+/// Size of a KB version
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KbVersionStats {
+    pub document_count: usize,
+    pub chunk_count: usize,
+    pub size_bytes: u64,
+}
+
+/// Immutable KB version (generation + manifest + stats)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbVersion {
+    pub id: String,
+    pub kb_id: String,
+    pub version: i32,
+    pub generation_id: u64,
+    pub pipeline_run_id: Option<String>,
+    pub status: KbVersionStatus,
+    pub manifest: KbVersionManifest,
+    pub stats: KbVersionStats,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+}
+
+impl KbVersion {
+    /// Vector collection holding this version's index
+    pub fn collection_id(&self) -> String {
+        format!("{}_{}", self.kb_id, self.generation_id)
+    }
+}
+
+/// Version as listed next to its KB (manifest left out)
+#[derive(Debug, Clone, Serialize)]
+pub struct KbVersionSummary {
+    pub version: i32,
+    pub status: KbVersionStatus,
+    pub pipeline_run_id: Option<String>,
+    pub stats: KbVersionStats,
+    pub created_at: DateTime<Utc>,
+    pub activated_at: Option<DateTime<Utc>>,
+}
+
+impl From<&KbVersion> for KbVersionSummary {
+    fn from(version: &KbVersion) -> Self {
+        Self {
+            version: version.version,
+            status: version.status,
+            pipeline_run_id: version.pipeline_run_id.clone(),
+            stats: version.stats.clone(),
+            created_at: version.created_at,
+            activated_at: version.activated_at,
+        }
+    }
+}
+
+/// KB with its versions, newest first
+#[derive(Debug, Clone, Serialize)]
+pub struct KbListing {
+    #[serde(flatten)]
+    pub info: KbInfo,
+    pub versions: Vec<KbVersionSummary>,
+}
+
+/// Document whose content changed between two versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentChange {
+    pub source_path: String,
+    pub title: String,
+    pub old_content_hash: String,
+    pub new_content_hash: String,
+    pub old_chunk_count: usize,
+    pub new_chunk_count: usize,
+}
+
+/// Document-level diff between two KB versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KbVersionDiff {
+    pub kb_id: String,
+    pub from_version: i32,
+    pub to_version: i32,
+    pub added: Vec<VersionDocument>,
+    pub removed: Vec<VersionDocument>,
+    pub changed: Vec<DocumentChange>,
+    pub unchanged_count: usize,
+    pub from_chunk_count: usize,
+    pub to_chunk_count: usize,
+}
+
+impl KbVersionDiff {
+    /// Compare manifests, matching documents by source path (IDs change across reindexes)
+    pub fn between(from: &KbVersion, to: &KbVersion) -> Self {
+        let old_docs: HashMap<&str, &VersionDocument> = from.manifest.documents.iter()
+            .map(|d| (d.source_path.as_str(), d))
+            .collect();
+        let new_paths: HashSet<&str> = to.manifest.documents.iter()
+            .map(|d| d.source_path.as_str())
+            .collect();
+
+        let mut added = Vec::new();
+        let mut changed = Vec::new();
+        let mut unchanged_count = 0;
+
+        for doc in &to.manifest.documents {
+            match old_docs.get(doc.source_path.as_str()) {
+                None => added.push(doc.clone()),
+                Some(old) if old.content_hash != doc.content_hash || old.chunk_count != doc.chunk_count => {
+                    changed.push(DocumentChange {
+                        source_path: doc.source_path.clone(),
+                        title: doc.title.clone(),
+                        old_content_hash: old.content_hash.clone(),
+                        new_content_hash: doc.content_hash.clone(),
+                        old_chunk_count: old.chunk_count,
+                        new_chunk_count: doc.chunk_count,
+                    });
+                }
+                Some(_) => unchanged_count += 1,
+            }
+        }
+
+        let removed = from.manifest.documents.iter()
+            .filter(|d| !new_paths.contains(d.source_path.as_str()))
+            .cloned()
+            .collect();
+
+        Self {
+            kb_id: to.kb_id.clone(),
+            from_version: from.version,
+            to_version: to.version,
+            added,
+            removed,
+            changed,
+            unchanged_count,
+            from_chunk_count: from.stats.chunk_count,
+            to_chunk_count: to.stats.chunk_count,
+        }
+    }
+}
+
+/// Content fingerprint of an indexed document
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentFingerprint {
+    pub kb_id: String,
+    pub source_path: String,
+    pub document_id: String,
+    pub content_hash: String,
+    pub chunk_count: usize,
+    pub indexed_at: DateTime<Utc>,
+}
+
+impl DocumentFingerprint {
+    /// SHA-256 of document content (hex)
+    pub fn hash_content(content: &str) -> String {
+        ring::digest::digest(&ring::digest::SHA256, content.as_bytes())
+            .as_ref()
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+
+    /// Hash for a pipeline document, computing it when the parser didn't
+    pub fn hash_for(doc: &PipelineDocument) -> String {
+        if doc.content_hash.is_empty() {
+            Self::hash_content(&doc.content)
+        } else {
+            doc.content_hash.clone()
+        }
+    }
+}
+
+/// Documents to process in an incremental reindex
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ReindexPlan {
+    pub kb_id: String,
+    pub changed: Vec<PipelineDocument>,        // New or modified, needs chunk + embed
+    pub unchanged: Vec<DocumentFingerprint>,   // Reused from the previous index
+    pub removed: Vec<DocumentFingerprint>,     // No longer in the source set
+}
+
+impl ReindexPlan {
+    /// Compare incoming documents against stored fingerprints (matched by source path)
+    pub fn build(kb_id: &str, documents: Vec<PipelineDocument>, fingerprints: Vec<DocumentFingerprint>) -> Self {
+        let mut previous: HashMap<String, DocumentFingerprint> = fingerprints.into_iter()
+            .map(|f| (f.source_path.clone(), f))
+            .collect();
+
+        let mut plan = ReindexPlan {
+            kb_id: kb_id.to_string(),
+            ..Default::default()
+        };
+
+        for doc in documents {
+            match previous.remove(&doc.source_path) {
+                Some(fingerprint) if fingerprint.content_hash == DocumentFingerprint::hash_for(&doc) => {
+                    plan.unchanged.push(fingerprint);
+                }
+                _ => plan.changed.push(doc),
+            }
+        }
+
+        plan.removed = previous.into_values().collect();
+        plan
+    }
+
+    /// True when nothing needs reindexing
+    pub fn is_noop(&self) -> bool {
+        self.changed.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Kind of staged index write
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxOperation {
+    Upsert,
+    Delete,
+}
+
+impl OutboxOperation {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxOperation::Upsert => "upsert",
+            OutboxOperation::Delete => "delete",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "upsert" => Some(OutboxOperation::Upsert),
+            "delete" => Some(OutboxOperation::Delete),
+            _ => None,
+        }
+    }
+}
+
+/// Outbox entry lifecycle: staged before vectors are written, committed with the fingerprint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OutboxStatus {
+    Staged,
+    Committed,
+    RolledBack,
+}
+
+impl OutboxStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OutboxStatus::Staged => "staged",
+            OutboxStatus::Committed => "committed",
+            OutboxStatus::RolledBack => "rolled_back",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "staged" => Some(OutboxStatus::Staged),
+            "committed" => Some(OutboxStatus::Committed),
+            "rolled_back" => Some(OutboxStatus::RolledBack),
+            _ => None,
+        }
+    }
+}
+
+/// Index write tracked across the SQL and vector stores
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexOutboxEntry {
+    pub id: String,
+    pub kb_id: String,
+    pub document_id: String,
+    pub operation: OutboxOperation,
+    pub generation: u64,           // Stamped into chunk metadata as "generation"
+    pub chunk_count: usize,
+    pub status: OutboxStatus,
+    pub created_at: DateTime<Utc>,
+    pub committed_at: Option<DateTime<Utc>>,
+}
+
+/// Divergence between a KB's SQL metadata and its vector store
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub kb_id: String,
+    pub staged_writes: Vec<IndexOutboxEntry>,   // Interrupted before commit
+    pub orphan_documents: Vec<String>,          // Chunks without a fingerprint
+    pub missing_documents: Vec<String>,         // Fingerprint without chunks
+    pub stale_documents: Vec<String>,           // Chunks from a generation that never committed
+    pub repaired: bool,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.staged_writes.is_empty()
+            && self.orphan_documents.is_empty()
+            && self.missing_documents.is_empty()
+            && self.stale_documents.is_empty()
+    }
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -161,4 +585,87 @@ mod tests {
         assert_eq!(config.chunk_size, 512);
         assert_eq!(config.chunk_overlap, 50);
     }
-}
\ No newline at end of file
+
+    fn version_with(version: i32, docs: &[(&str, &str, usize)]) -> KbVersion {
+        let documents: Vec<VersionDocument> = docs.iter().map(|(path, hash, chunks)| VersionDocument {
+            id: format!("doc_{}_{}", version, path),
+            title: path.to_string(),
+            source_path: path.to_string(),
+            content_hash: hash.to_string(),
+            chunk_count: *chunks,
+        }).collect();
+
+        KbVersion {
+            id: format!("kbv_{}", version),
+            kb_id: "kb_1".to_string(),
+            version,
+            generation_id: version as u64,
+            pipeline_run_id: None,
+            status: KbVersionStatus::Ready,
+            stats: KbVersionStats {
+                document_count: documents.len(),
+                chunk_count: documents.iter().map(|d| d.chunk_count).sum(),
+                size_bytes: 0,
+            },
+            manifest: KbVersionManifest {
+                documents,
+                ..Default::default()
+            },
+            created_at: Utc::now(),
+            activated_at: None,
+        }
+    }
+
+    #[test]
+    fn test_version_diff() {
+        let v1 = version_with(1, &[("a.md", "h1", 2), ("b.md", "h2", 3), ("c.md", "h3", 1)]);
+        let v2 = version_with(2, &[("a.md", "h1", 2), ("b.md", "h2b", 4), ("d.md", "h4", 5)]);
+
+        let diff = KbVersionDiff::between(&v1, &v2);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].source_path, "d.md");
+        assert_eq!(diff.removed[0].source_path, "c.md");
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].new_chunk_count, 4);
+        assert_eq!(diff.unchanged_count, 1);
+        assert_eq!((diff.from_chunk_count, diff.to_chunk_count), (6, 11));
+    }
+
+    fn pipeline_doc(path: &str, content: &str) -> PipelineDocument {
+        PipelineDocument {
+            id: format!("doc_{}", path),
+            title: path.to_string(),
+            source_path: path.to_string(),
+            content: content.to_string(),
+            content_hash: String::new(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    fn fingerprint(path: &str, content: &str) -> DocumentFingerprint {
+        DocumentFingerprint {
+            kb_id: "kb_1".to_string(),
+            source_path: path.to_string(),
+            document_id: format!("doc_{}", path),
+            content_hash: DocumentFingerprint::hash_content(content),
+            chunk_count: 1,
+            indexed_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_reindex_plan() {
+        let plan = ReindexPlan::build(
+            "kb_1",
+            vec![pipeline_doc("a.md", "same"), pipeline_doc("b.md", "edited"), pipeline_doc("c.md", "new")],
+            vec![fingerprint("a.md", "same"), fingerprint("b.md", "original"), fingerprint("d.md", "gone")],
+        );
+
+        let changed: Vec<&str> = plan.changed.iter().map(|d| d.source_path.as_str()).collect();
+        assert_eq!(changed, vec!["b.md", "c.md"]);
+        assert_eq!(plan.unchanged.len(), 1);
+        assert_eq!(plan.removed[0].source_path, "d.md");
+        assert!(!plan.is_noop());
+    }
+}
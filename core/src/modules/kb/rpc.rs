@@ -0,0 +1,324 @@
+/*!
+ * Knowledge Base RPC Dispatch
+ *
+ * Maps outbound RPC requests from the MCP subprocess (`kb.*` methods, see
+ * `models::outbound_rpc`) onto `KbService`. Transport-free so the Manager's
+ * HTTP server stays a thin shell and dispatch is testable on its own.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::errors::KbError;
+use super::models::{ContextExpansion, HealthStatus, KbListing, KbVersionSummary, SearchMode};
+use super::service::KbService;
+use crate::modules::graph::DEFAULT_EXPANSION_TERMS;
+use crate::models::outbound_rpc::{RpcRequest, RpcResponse};
+
+/// Default result count when the caller doesn't pass `top_k`
+const DEFAULT_TOP_K: usize = 10;
+
+/// RPC dispatch errors
+#[derive(Debug, thiserror::Error)]
+pub enum RpcError {
+    #[error("Method not found: {0}")]
+    MethodNotFound(String),
+
+    #[error("Invalid params: {0}")]
+    InvalidParams(String),
+
+    #[error(transparent)]
+    Kb(#[from] KbError),
+}
+
+impl RpcError {
+    /// JSON-RPC style error code
+    pub fn code(&self) -> i32 {
+        match self {
+            RpcError::MethodNotFound(_) => -32601,
+            RpcError::InvalidParams(_) => -32602,
+            RpcError::Kb(KbError::KbNotFound(_) | KbError::DocumentNotFound(_) | KbError::VersionNotFound(_)) => -32002,
+            RpcError::Kb(KbError::InvalidQuery(_) | KbError::ValidationError(_)) => -32602,
+            RpcError::Kb(_) => -32603,
+        }
+    }
+}
+
+/// Dispatches `kb.*` RPC methods to the KB service
+pub struct KbRpcHandler {
+    kb_service: Arc<dyn KbService>,
+}
+
+impl KbRpcHandler {
+    pub fn new(kb_service: Arc<dyn KbService>) -> Self {
+        Self { kb_service }
+    }
+
+    /// Handle a request, folding errors into the response body
+    pub async fn handle(&self, request: RpcRequest) -> RpcResponse {
+        match self.dispatch(&request.method, &request.params).await {
+            Ok(result) => RpcResponse::success(result),
+            Err(e) => {
+                debug!("RPC {} failed: {}", request.method, e);
+                RpcResponse::error(e.code(), &e.to_string())
+            }
+        }
+    }
+
+    async fn dispatch(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "kb.hybrid_search" => {
+                let collection = required_str(params, "collection")?;
+                let query = required_str(params, "query")?;
+                let top_k = optional_usize(params, "top_k").unwrap_or(DEFAULT_TOP_K);
+                let filters = optional_map(params, "filters")?;
+                let cache_ttl = params.get("cache_ttl").and_then(|v| v.as_u64());
+
+                let expand = optional_expansion(params)?;
+                let mode: SearchMode = match params.get("mode") {
+                    None | Some(Value::Null) => SearchMode::default(),
+                    Some(value) => serde_json::from_value(value.clone())
+                        .map_err(|e| RpcError::InvalidParams(format!("mode: {}", e)))?,
+                };
+
+                // Graph expansion rewrites the query before either search mode runs
+                let graph = match params.get("graph_expand").and_then(|v| v.as_bool()).unwrap_or(false) {
+                    true => Some(self.kb_service.expand_query_with_graph(collection, query, DEFAULT_EXPANSION_TERMS).await?),
+                    false => None,
+                };
+                let query = graph.as_ref().map_or(query, |g| g.expanded_query.as_str());
+
+                let mut results = match mode {
+                    SearchMode::Hybrid => self.kb_service.hybrid_search(collection, query, top_k, filters, cache_ttl).await?,
+                    SearchMode::Hierarchical => self.kb_service.hierarchical_search(collection, query, top_k).await?,
+                };
+                if let Some(top_n) = optional_usize(params, "top_n") {
+                    results.truncate(top_n);
+                }
+                if let Some(expansion) = expand {
+                    results = self.kb_service.expand_results(results, expansion).await?;
+                }
+                match graph {
+                    Some(graph) => Ok(json!({ "results": results, "graph": graph })),
+                    None => Ok(json!({ "results": results })),
+                }
+            }
+            "kb.search_multiple" => {
+                let collections: Vec<String> = params.get("collections")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .ok_or_else(|| RpcError::InvalidParams("collections must be an array of strings".to_string()))?;
+                let query = required_str(params, "query")?;
+                let top_k = optional_usize(params, "top_k").unwrap_or(DEFAULT_TOP_K);
+                let filters = optional_map(params, "filters")?;
+
+                let expand = optional_expansion(params)?;
+
+                let mut results = self.kb_service.search_multiple(&collections, query, top_k, filters).await?;
+                if let Some(expansion) = expand {
+                    results = self.kb_service.expand_results(results, expansion).await?;
+                }
+                Ok(json!({ "results": results }))
+            }
+            "kb.get_document" => {
+                let doc_id = required_str(params, "doc_id")?;
+                let range = params.get("range")
+                    .and_then(|r| Some((r.get("start")?.as_u64()? as usize, r.get("end")?.as_u64()? as usize)));
+                // With a collection the full text and citations come back, not just metadata
+                match params.get("collection").and_then(|v| v.as_str()) {
+                    Some(collection) => {
+                        let document = self.kb_service.get_document_content(collection, doc_id, range).await?;
+                        Ok(serde_json::to_value(document).map_err(KbError::from)?)
+                    }
+                    None => {
+                        let document = self.kb_service.get_document(doc_id, range).await?;
+                        Ok(serde_json::to_value(document).map_err(KbError::from)?)
+                    }
+                }
+            }
+            "kb.get_chunk" => {
+                let collection = required_str(params, "collection")?;
+                let doc_id = required_str(params, "doc_id")?;
+                let chunk_id = required_str(params, "chunk_id")?;
+
+                let document = self.kb_service.get_document_content(collection, doc_id, None).await?;
+                let chunk = document.chunks.iter()
+                    .find(|chunk| chunk.chunk_id == chunk_id)
+                    .ok_or_else(|| KbError::DocumentNotFound(format!("{}/{}", doc_id, chunk_id)))?;
+                // Offsets are in characters, not bytes
+                let content: String = document.content.chars()
+                    .skip(chunk.start)
+                    .take(chunk.end - chunk.start)
+                    .collect();
+                Ok(json!({
+                    "kb_id": document.kb_id,
+                    "doc_id": doc_id,
+                    "chunk_id": chunk.chunk_id,
+                    "content": content,
+                    "start": chunk.start,
+                    "end": chunk.end,
+                    "citation": chunk.citation,
+                }))
+            }
+            "kb.resolve_citations" => {
+                let chunk_ids: Vec<String> = params.get("chunk_ids")
+                    .and_then(|v| serde_json::from_value(v.clone()).ok())
+                    .ok_or_else(|| RpcError::InvalidParams("chunk_ids must be an array of strings".to_string()))?;
+                let citations = self.kb_service.resolve_citations(chunk_ids).await?;
+                Ok(json!({ "citations": citations }))
+            }
+            "kb.stats" => {
+                let collection = params.get("collection").and_then(|v| v.as_str()).map(String::from);
+                let version = params.get("version").and_then(|v| v.as_i64()).map(|v| v as i32);
+                let stats = self.kb_service.get_stats(collection, version).await?;
+                // A failing probe is reported, not raised: the stats are still useful
+                let health = self.kb_service.health_check().await.unwrap_or(HealthStatus::Unhealthy);
+                let mut result = serde_json::to_value(stats).map_err(KbError::from)?;
+                result["health"] = serde_json::to_value(health).map_err(KbError::from)?;
+                Ok(result)
+            }
+            "kb.list" => {
+                let filters = optional_map(params, "filters")?;
+                let mut listings = Vec::new();
+                for info in self.kb_service.list_collections(filters).await? {
+                    let versions = self.kb_service.list_versions(&info.id).await?;
+                    listings.push(KbListing {
+                        versions: versions.iter().map(KbVersionSummary::from).collect(),
+                        info,
+                    });
+                }
+                Ok(json!({ "knowledge_bases": listings }))
+            }
+            "kb.list_collections" => {
+                let filters = optional_map(params, "filters")?;
+                let collections = self.kb_service.list_collections(filters).await?;
+                Ok(json!({ "collections": collections }))
+            }
+            "kb.list_documents" => {
+                let collection = required_str(params, "collection")?;
+                let documents = self.kb_service.list_documents(collection).await?;
+                Ok(json!({ "documents": documents }))
+            }
+            _ => Err(RpcError::MethodNotFound(method.to_string())),
+        }
+    }
+}
+
+fn required_str<'a>(params: &'a Value, field: &str) -> Result<&'a str, RpcError> {
+    params.get(field)
+        .and_then(|v| v.as_str())
+        .ok_or_else(|| RpcError::InvalidParams(format!("Missing required field: {}", field)))
+}
+
+fn optional_usize(params: &Value, field: &str) -> Option<usize> {
+    params.get(field).and_then(|v| v.as_u64()).map(|v| v as usize)
+}
+
+fn optional_map(params: &Value, field: &str) -> Result<Option<HashMap<String, Value>>, RpcError> {
+    match params.get(field) {
+        None | Some(Value::Null) => Ok(None),
+        Some(Value::Object(map)) => Ok(Some(map.clone().into_iter().collect())),
+        Some(_) => Err(RpcError::InvalidParams(format!("{} must be an object", field))),
+    }
+}
+
+fn optional_expansion(params: &Value) -> Result<Option<ContextExpansion>, RpcError> {
+    match params.get("expand") {
+        None | Some(Value::Null) => Ok(None),
+        Some(value) => serde_json::from_value(value.clone())
+            .map(Some)
+            .map_err(|e| RpcError::InvalidParams(format!("expand: {}", e))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::modules::kb::{KbCreateConfig, KbServiceImpl};
+    use crate::services::sql::{SqlConfig, SqlService};
+    use crate::services::vector::{VectorDbConfig, VectorDbService};
+    use crate::state::StateManager;
+
+    async fn handler(temp_dir: &TempDir) -> KbRpcHandler {
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+        KbRpcHandler::new(Arc::new(kb_service))
+    }
+
+    fn request(method: &str, params: Value) -> RpcRequest {
+        RpcRequest { method: method.to_string(), params, traceparent: None }
+    }
+
+    #[tokio::test]
+    async fn test_dispatch() {
+        let temp_dir = TempDir::new().unwrap();
+        let handler = handler(&temp_dir).await;
+
+        let response = handler.handle(request("kb.list_collections", json!({}))).await;
+        assert_eq!(response.result.unwrap(), json!({ "collections": [] }));
+
+        let response = handler.handle(request("kb.list", json!({}))).await;
+        assert_eq!(response.result.unwrap(), json!({ "knowledge_bases": [] }));
+
+        // Unknown chunks have nothing to cite
+        let response = handler.handle(request("kb.resolve_citations", json!({ "chunk_ids": ["c1"] }))).await;
+        assert_eq!(response.result.unwrap()["citations"].as_array().unwrap().len(), 0);
+
+        // Errors map to JSON-RPC codes
+        let response = handler.handle(request("kb.stats", json!({ "collection": "missing" }))).await;
+        assert_eq!(response.error.unwrap().code, -32002);
+        let response = handler.handle(request("kb.get_document", json!({ "collection": "missing", "doc_id": "d1" }))).await;
+        assert_eq!(response.error.unwrap().code, -32002);
+        let response = handler.handle(request("kb.hybrid_search", json!({ "collection": "kb_1" }))).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+        let response = handler.handle(request("kb.search_multiple", json!({ "collections": "kb_1", "query": "q" }))).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+        let response = handler.handle(request("kb.hybrid_search", json!({ "collection": "kb_1", "query": "q", "expand": { "mode": "nearby" } }))).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+        let response = handler.handle(request("kb.hybrid_search", json!({ "collection": "kb_1", "query": "q", "mode": "tree" }))).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+        let response = handler.handle(request("kb.search_multiple", json!({ "collections": ["missing"], "query": "q" }))).await;
+        assert_eq!(response.error.unwrap().code, -32002);
+        let response = handler.handle(request("kb.drop_everything", json!({}))).await;
+        assert_eq!(response.error.unwrap().code, -32601);
+    }
+
+    #[tokio::test]
+    async fn test_get_chunk() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let kb_service = Arc::new(KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new())));
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+        let path = temp_dir.path().join("guide.md");
+        std::fs::write(&path, "install the studio").unwrap();
+        let added = kb_service.add_documents(&kb_id, vec![path.to_str().unwrap().to_string()]).await.unwrap();
+        let document = kb_service.get_document_content(&kb_id, &added[0].id, None).await.unwrap();
+        let handler = KbRpcHandler::new(kb_service);
+
+        let chunk_id = &document.chunks[0].chunk_id;
+        let response = handler.handle(request("kb.get_chunk", json!({
+            "collection": kb_id, "doc_id": added[0].id, "chunk_id": chunk_id
+        }))).await;
+        let chunk = response.result.unwrap();
+        assert_eq!(chunk["content"], "install the studio");
+        assert_eq!(chunk["chunk_id"], json!(chunk_id));
+
+        let response = handler.handle(request("kb.get_chunk", json!({
+            "collection": kb_id, "doc_id": added[0].id, "chunk_id": "chunk_missing"
+        }))).await;
+        assert_eq!(response.error.unwrap().code, -32002);
+        let response = handler.handle(request("kb.get_chunk", json!({ "collection": kb_id }))).await;
+        assert_eq!(response.error.unwrap().code, -32602);
+    }
+}
@@ -7,6 +7,16 @@
 // Note: This module would contain KB-specific database schema definitions
 // For now, we re-export from the shared schema module to maintain compatibility
 
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::{document_fingerprints, index_outbox, kb_versions, knowledge_bases};
+use crate::state::{KnowledgeBaseState, KnowledgeBaseStatus};
+use super::errors::KbError;
+use super::models::{
+    DocumentFingerprint, IndexOutboxEntry, KbCreateConfig, KbVersion, KbVersionStatus, OutboxOperation, OutboxStatus,
+};
+
 pub use crate::schemas::{
     SearchResult,
     CitationInfo,
@@ -23,4 +33,217 @@ pub use crate::schemas::{
 //     pub status: String,
 //     pub version: i32,
 //     pub health_score: f64,
-// }
\ No newline at end of file
+// }
+
+/// Row in knowledge_bases (KB metadata that outlives the in-memory state)
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = knowledge_bases)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct KnowledgeBaseRow {
+    pub id: String,
+    pub name: String,
+    pub version: i32,
+    pub status: String,
+    pub embedder_model: String,
+    pub chunk_size: i32,
+    pub chunk_overlap: i32,
+    pub description: Option<String>,
+    pub metadata: Option<String>,     // JSON
+    pub health_score: f64,
+    pub created_at: NaiveDateTime,
+    pub updated_at: NaiveDateTime,
+    pub pinned_version: Option<i32>,
+}
+
+impl KnowledgeBaseRow {
+    pub fn from_state(kb: &KnowledgeBaseState, config: &KbCreateConfig) -> Result<Self, KbError> {
+        let now = Utc::now().naive_utc();
+        Ok(Self {
+            id: kb.id.clone(),
+            name: kb.name.clone(),
+            version: kb.version,
+            status: match kb.status {
+                KnowledgeBaseStatus::Active => "active",
+                KnowledgeBaseStatus::Inactive => "inactive",
+                KnowledgeBaseStatus::Building => "building",
+                KnowledgeBaseStatus::Error(_) => "error",
+            }.to_string(),
+            embedder_model: kb.embedder_model.clone(),
+            chunk_size: config.chunk_size as i32,
+            chunk_overlap: config.chunk_overlap as i32,
+            description: config.description.clone(),
+            metadata: Some(serde_json::to_string(&kb.metadata)?),
+            health_score: kb.health_score,
+            created_at: now,
+            updated_at: now,
+            pinned_version: None,
+        })
+    }
+
+    /// State entry; document/chunk counts are filled in from fingerprints afterwards
+    pub fn into_state(self) -> Result<KnowledgeBaseState, KbError> {
+        let status = match self.status.as_str() {
+            "active" => KnowledgeBaseStatus::Active,
+            "inactive" => KnowledgeBaseStatus::Inactive,
+            "building" => KnowledgeBaseStatus::Building,
+            _ => KnowledgeBaseStatus::Error("Restored in error state".to_string()),
+        };
+        let metadata = match self.metadata {
+            Some(json) => serde_json::from_str(&json)?,
+            None => serde_json::json!({}),
+        };
+
+        Ok(KnowledgeBaseState {
+            id: self.id,
+            name: self.name,
+            version: self.version,
+            status,
+            embedder_model: self.embedder_model,
+            health_score: self.health_score,
+            document_count: 0,
+            chunk_count: 0,
+            last_updated: DateTime::<Utc>::from_naive_utc_and_offset(self.updated_at, Utc),
+            metadata,
+        })
+    }
+}
+
+/// Row in kb_versions
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = kb_versions)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct KbVersionRow {
+    pub id: String,
+    pub kb_id: String,
+    pub version: i32,
+    pub generation_id: i64,
+    pub pipeline_run_id: Option<String>,
+    pub status: String,
+    pub manifest: String,             // JSON KbVersionManifest
+    pub stats: String,                // JSON KbVersionStats
+    pub created_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+}
+
+impl KbVersionRow {
+    pub fn from_model(version: &KbVersion) -> Result<Self, KbError> {
+        Ok(Self {
+            id: version.id.clone(),
+            kb_id: version.kb_id.clone(),
+            version: version.version,
+            generation_id: version.generation_id as i64,
+            pipeline_run_id: version.pipeline_run_id.clone(),
+            status: version.status.as_str().to_string(),
+            manifest: serde_json::to_string(&version.manifest)?,
+            stats: serde_json::to_string(&version.stats)?,
+            created_at: version.created_at.naive_utc(),
+            activated_at: version.activated_at.map(|t| t.naive_utc()),
+        })
+    }
+
+    pub fn into_model(self) -> Result<KbVersion, KbError> {
+        let status = KbVersionStatus::parse(&self.status)
+            .ok_or_else(|| KbError::StateError(format!("Unknown KB version status: {}", self.status)))?;
+
+        Ok(KbVersion {
+            id: self.id,
+            kb_id: self.kb_id,
+            version: self.version,
+            generation_id: self.generation_id as u64,
+            pipeline_run_id: self.pipeline_run_id,
+            status,
+            manifest: serde_json::from_str(&self.manifest)?,
+            stats: serde_json::from_str(&self.stats)?,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+            activated_at: self.activated_at.map(|t| DateTime::<Utc>::from_naive_utc_and_offset(t, Utc)),
+        })
+    }
+}
+
+/// Row in document_fingerprints
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = document_fingerprints)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct DocumentFingerprintRow {
+    pub kb_id: String,
+    pub source_path: String,
+    pub document_id: String,
+    pub content_hash: String,
+    pub chunk_count: i32,
+    pub indexed_at: NaiveDateTime,
+}
+
+impl DocumentFingerprintRow {
+    pub fn from_model(fingerprint: &DocumentFingerprint) -> Self {
+        Self {
+            kb_id: fingerprint.kb_id.clone(),
+            source_path: fingerprint.source_path.clone(),
+            document_id: fingerprint.document_id.clone(),
+            content_hash: fingerprint.content_hash.clone(),
+            chunk_count: fingerprint.chunk_count as i32,
+            indexed_at: fingerprint.indexed_at.naive_utc(),
+        }
+    }
+
+    pub fn into_model(self) -> DocumentFingerprint {
+        DocumentFingerprint {
+            kb_id: self.kb_id,
+            source_path: self.source_path,
+            document_id: self.document_id,
+            content_hash: self.content_hash,
+            chunk_count: self.chunk_count.max(0) as usize,
+            indexed_at: DateTime::<Utc>::from_naive_utc_and_offset(self.indexed_at, Utc),
+        }
+    }
+}
+
+/// Row in index_outbox
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = index_outbox)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct IndexOutboxRow {
+    pub id: String,
+    pub kb_id: String,
+    pub document_id: String,
+    pub operation: String,
+    pub generation: i64,
+    pub chunk_count: i32,
+    pub status: String,
+    pub created_at: NaiveDateTime,
+    pub committed_at: Option<NaiveDateTime>,
+}
+
+impl IndexOutboxRow {
+    pub fn from_model(entry: &IndexOutboxEntry) -> Self {
+        Self {
+            id: entry.id.clone(),
+            kb_id: entry.kb_id.clone(),
+            document_id: entry.document_id.clone(),
+            operation: entry.operation.as_str().to_string(),
+            generation: entry.generation as i64,
+            chunk_count: entry.chunk_count as i32,
+            status: entry.status.as_str().to_string(),
+            created_at: entry.created_at.naive_utc(),
+            committed_at: entry.committed_at.map(|t| t.naive_utc()),
+        }
+    }
+
+    pub fn into_model(self) -> Result<IndexOutboxEntry, KbError> {
+        let operation = OutboxOperation::parse(&self.operation)
+            .ok_or_else(|| KbError::StateError(format!("Unknown outbox operation: {}", self.operation)))?;
+        let status = OutboxStatus::parse(&self.status)
+            .ok_or_else(|| KbError::StateError(format!("Unknown outbox status: {}", self.status)))?;
+
+        Ok(IndexOutboxEntry {
+            id: self.id,
+            kb_id: self.kb_id,
+            document_id: self.document_id,
+            operation,
+            generation: self.generation.max(0) as u64,
+            chunk_count: self.chunk_count.max(0) as usize,
+            status,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+            committed_at: self.committed_at.map(|t| DateTime::<Utc>::from_naive_utc_and_offset(t, Utc)),
+        })
+    }
+}
@@ -4,24 +4,59 @@
  * Core business logic for KB operations following the CORE_DESIGN.md architecture:
  * - Hybrid Search: Vector (LanceDB) + BM25 (tantivy) with reranking
  * - Versioning: Atomic promotion with generation management
+ * - Write path: document writes are staged in an outbox, applied to the vector
+ *   store and committed together with the document's fingerprint
  * - Citations: Mandatory citation enrichment
  * - State Integration: Arc<RwLock<AppState>> for MVP
  */
 
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
 use async_trait::async_trait;
+use chrono::Utc;
+use diesel::prelude::*;
+use futures::future::try_join_all;
 use tracing;
 
 // Domain imports
 use super::models::*;
 use super::schema::*;
 use super::errors::KbError;
+use super::fusion::{normalize_scores, reciprocal_rank_fusion, DEFAULT_RRF_K};
+use super::mmr::{mmr_rerank, terms};
 
 // Infrastructure service imports
-use crate::services::sql::SqlService;
-use crate::services::vector::{VectorDbService, VectorDbServiceTrait, HealthStatus as VectorHealthStatus};
-use crate::state::StateManager;
+use crate::modules::graph::{GraphService, QueryExpansion};
+use crate::modules::pipeline::{summary_index_id, PipelineChunk, PipelineDocument, PipelineRunOutput, PipelineRunner, PipelineSpec, SourceSandbox, StepData};
+use crate::modules::pipeline::steps::annotate::{AnnotationTarget, AnnotatorSet};
+use crate::modules::pipeline::steps::normalize::detect_language;
+use crate::schemas::schema::{document_fingerprints, index_outbox, kb_versions, knowledge_bases};
+use crate::schemas::TimeRange;
+use crate::services::cache::{kb_tag, CacheService};
+use crate::services::metrics::MetricsService;
+use crate::services::model::EmbeddingBackend;
+use crate::services::quota::{estimate_ingest_bytes, QuotaCheck, QuotaService};
+use crate::services::sql::{SqlError, SqlService};
+use crate::services::storage::{sha256_hex, PackManifest, StorageConfig, StorageService};
+use crate::services::vector::{
+    CollectionStats, IndexMaintenanceProgress, IndexMaintenanceReport, VectorDbError, VectorDbService, VectorDbServiceTrait, VectorDocument,
+    HealthStatus as VectorHealthStatus,
+};
+use crate::state::{KnowledgeBaseState, StateDelta, StateManager};
+use crate::utils::snippet::highlight_result;
+
+/// Chunking for documents added outside a pipeline run (matches KbCreateConfig defaults)
+const DEFAULT_CHUNK_SIZE: usize = 512;
+const DEFAULT_CHUNK_OVERLAP: usize = 50;
+
+/// Most KBs a single federated search may fan out to
+const MAX_FEDERATED_KBS: usize = 16;
+
+/// Candidate pool per requested result when MMR diversification is on
+const MMR_CANDIDATE_FACTOR: usize = 3;
+
+/// Documents a hierarchical search drills into
+const HIERARCHY_DOCUMENTS: usize = 3;
 
 /// Knowledge Base Service trait for dependency injection
 #[async_trait]
@@ -36,6 +71,36 @@ pub trait KbService: Send + Sync {
         cache_ttl: Option<u64>,
     ) -> Result<Vec<SearchResult>, KbError>;
 
+    /// Federated hybrid search over several KBs, merged with RRF; each result's
+    /// metadata names its source KB (`source_kb`) and per-KB normalized score
+    async fn search_multiple(
+        &self,
+        kb_ids: &[String],
+        query: &str,
+        top_k: usize,
+        filters: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Vec<SearchResult>, KbError>;
+
+    /// Summary-first search over the tree built by the Summarize step: documents
+    /// and sections are ranked by their summaries, then the chunks under the
+    /// matching sections. The best document summary leads the results. Falls back
+    /// to `hybrid_search` when the KB has no summaries or none match.
+    async fn hierarchical_search(
+        &self,
+        collection: &str,
+        query: &str,
+        top_k: usize,
+    ) -> Result<Vec<SearchResult>, KbError>;
+
+    /// Append entities related, in the KB's knowledge graph, to those the query
+    /// mentions; the query comes back unchanged when the graph has no match
+    async fn expand_query_with_graph(
+        &self,
+        collection: &str,
+        query: &str,
+        max_terms: usize,
+    ) -> Result<QueryExpansion, KbError>;
+
     /// Get document by ID with optional range
     async fn get_document(
         &self,
@@ -43,7 +108,25 @@ pub trait KbService: Send + Sync {
         range: Option<(usize, usize)>,
     ) -> Result<DocumentInfo, KbError>;
 
-    /// Resolve citations for chunk IDs
+    /// Full text of a KB document with citations for it and each of its chunks;
+    /// `range` selects characters of the text
+    async fn get_document_content(
+        &self,
+        kb_id: &str,
+        doc_id: &str,
+        range: Option<(usize, usize)>,
+    ) -> Result<DocumentContent, KbError>;
+
+    /// Replace each hit's content with its neighbourhood in the document, using
+    /// the chunk positions recorded at indexing time. Hits keep their ID, score and
+    /// citation; overlapping neighbourhoods are merged into the better ranked hit.
+    async fn expand_results(
+        &self,
+        results: Vec<SearchResult>,
+        expansion: ContextExpansion,
+    ) -> Result<Vec<SearchResult>, KbError>;
+
+    /// Resolve citations for chunk IDs, in the order given; unknown IDs are left out
     async fn resolve_citations(
         &self,
         chunk_ids: Vec<String>,
@@ -62,6 +145,9 @@ pub trait KbService: Send + Sync {
         filters: Option<HashMap<String, serde_json::Value>>,
     ) -> Result<Vec<KbInfo>, KbError>;
 
+    /// List indexed documents of a knowledge base
+    async fn list_documents(&self, kb_id: &str) -> Result<Vec<DocumentInfo>, KbError>;
+
     /// Create a new collection
     async fn create_collection(
         &self,
@@ -71,6 +157,76 @@ pub trait KbService: Send + Sync {
 
     /// Health check
     async fn health_check(&self) -> Result<HealthStatus, KbError>;
+
+    /// Hybrid search against a specific immutable version
+    async fn hybrid_search_version(
+        &self,
+        collection: &str,
+        version: i32,
+        query: &str,
+        top_k: usize,
+        filters: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Vec<SearchResult>, KbError>;
+
+    /// Record an immutable version for a successful pipeline run (None if the run failed)
+    async fn create_version_from_run(
+        &self,
+        kb_id: &str,
+        spec: &PipelineSpec,
+        run_id: &str,
+        output: &PipelineRunOutput,
+    ) -> Result<Option<KbVersion>, KbError>;
+
+    /// List versions of a KB, newest first
+    async fn list_versions(&self, kb_id: &str) -> Result<Vec<KbVersion>, KbError>;
+
+    /// Make a version the active one for search
+    async fn activate_version(&self, kb_id: &str, version: i32) -> Result<KbVersion, KbError>;
+
+    /// Delete a non-active version and its index
+    async fn delete_version(&self, kb_id: &str, version: i32) -> Result<(), KbError>;
+
+    /// Document-level changes between two versions of a KB
+    async fn diff_versions(&self, kb_id: &str, from_version: i32, to_version: i32) -> Result<KbVersionDiff, KbError>;
+
+    /// Split incoming documents into changed/unchanged/removed using stored fingerprints
+    async fn plan_reindex(&self, kb_id: &str, documents: Vec<PipelineDocument>) -> Result<ReindexPlan, KbError>;
+
+    /// Store fingerprints for documents indexed by an incremental run
+    async fn record_fingerprints(&self, kb_id: &str, plan: &ReindexPlan, data: &StepData) -> Result<(), KbError>;
+
+    /// Forget all fingerprints so the next reindex processes every document
+    async fn clear_fingerprints(&self, kb_id: &str) -> Result<(), KbError>;
+
+    /// Add documents from local paths to an existing KB
+    async fn add_documents(&self, kb_id: &str, sources: Vec<String>) -> Result<Vec<DocumentInfo>, KbError>;
+
+    /// Remove a document and its chunks from a KB
+    async fn remove_document(&self, kb_id: &str, doc_id: &str) -> Result<(), KbError>;
+
+    /// Re-read a document from its source and reindex it if the content changed
+    async fn update_document(&self, kb_id: &str, doc_id: &str) -> Result<DocumentInfo, KbError>;
+
+    /// Compare fingerprints, outbox and vector store; with `repair`, purge diverged documents
+    /// (re-indexed by the next run) and settle interrupted writes. Run while no indexing is active.
+    async fn check_consistency(&self, kb_id: &str, repair: bool) -> Result<ConsistencyReport, KbError>;
+
+    /// Delete a KB with its versions, fingerprints, graph and index data
+    async fn delete_collection(&self, kb_id: &str) -> Result<(), KbError>;
+
+    /// Package a KB (metadata, versions, fingerprints, chunks) into a portable .ragkb archive
+    async fn export_kb(&self, kb_id: &str) -> Result<Vec<u8>, KbError>;
+
+    /// Import a .ragkb archive, returning the imported KB ID. Unsigned archives
+    /// are refused unless `allow_unsigned` (the user confirmed them).
+    async fn import_kb(&self, data: &[u8], allow_unsigned: bool) -> Result<String, KbError>;
+
+    /// Resolve the version a query should use: workspace pin first, then the active version
+    async fn resolve_version(
+        &self,
+        collection: &str,
+        workspace_id: Option<&str>,
+    ) -> Result<Option<KbVersion>, KbError>;
 }
 
 /// KB Service implementation with dependency injection
@@ -78,6 +234,13 @@ pub struct KbServiceImpl {
     sql_service: Arc<SqlService>,
     vector_service: Arc<VectorDbService>,
     state_manager: Arc<StateManager>,
+    storage_service: Arc<StorageService>,
+    graph_service: Arc<GraphService>,         // Entities from the extract_entities step
+    cache_service: Option<Arc<CacheService>>,  // Search result cache, invalidated on KB changes
+    query_embedder: Option<Arc<dyn EmbeddingBackend>>,  // Embeds queries for the semantic search cache
+    metrics_service: Option<Arc<MetricsService>>,
+    quota_service: Option<Arc<QuotaService>>,  // Refuses ingests that would exceed a disk quota
+    pipeline_runner: Option<Arc<PipelineRunner>>,  // Replays a KB's stored pipeline on reindex
     config: KbConfig,
 }
 
@@ -90,13 +253,62 @@ impl KbServiceImpl {
         config: KbConfig,
     ) -> Self {
         Self {
+            graph_service: Arc::new(GraphService::new(sql_service.clone())),
             sql_service,
             vector_service,
             state_manager,
+            storage_service: Arc::new(StorageService::new(StorageConfig::default())),
+            cache_service: None,
+            query_embedder: None,
+            metrics_service: None,
+            quota_service: None,
+            pipeline_runner: None,
             config,
         }
     }
 
+    /// Use a shared storage service for KB packs
+    pub fn with_storage_service(mut self, storage_service: Arc<StorageService>) -> Self {
+        self.storage_service = storage_service;
+        self
+    }
+
+    /// Use a shared knowledge graph service
+    pub fn with_graph_service(mut self, graph_service: Arc<GraphService>) -> Self {
+        self.graph_service = graph_service;
+        self
+    }
+
+    /// Cache search results (when callers pass a cache TTL) in a shared cache
+    pub fn with_cache_service(mut self, cache_service: Arc<CacheService>) -> Self {
+        self.cache_service = Some(cache_service);
+        self
+    }
+
+    /// Serve cached results for near-duplicate queries, embedded with the KB's embedder
+    pub fn with_query_embedder(mut self, query_embedder: Arc<dyn EmbeddingBackend>) -> Self {
+        self.query_embedder = Some(query_embedder);
+        self
+    }
+
+    /// Record search latency and search cache hits
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    /// Check disk quotas before documents are ingested
+    pub fn with_quota_service(mut self, quota_service: Arc<QuotaService>) -> Self {
+        self.quota_service = Some(quota_service);
+        self
+    }
+
+    /// Run the KB's stored pipeline when its sources are reindexed
+    pub fn with_pipeline_runner(mut self, pipeline_runner: Arc<PipelineRunner>) -> Self {
+        self.pipeline_runner = Some(pipeline_runner);
+        self
+    }
+
     /// MVP constructor with basic services
     pub fn new_mvp(
         sql_service: Arc<SqlService>,
@@ -111,6 +323,30 @@ impl KbServiceImpl {
         )
     }
 
+    /// Load persisted KBs into the state manager; returns how many were added
+    pub async fn load_collections(&self) -> Result<usize, KbError> {
+        let rows = self.sql_service.with_app_transaction(|conn| {
+            Ok(knowledge_bases::table
+                .select(KnowledgeBaseRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        let mut loaded = 0;
+        for row in rows {
+            if self.state_manager.read_state().knowledge_bases.contains_key(&row.id) {
+                continue;
+            }
+            let kb = row.into_state()?;
+            let kb_id = kb.id.clone();
+            self.state_manager
+                .mutate(StateDelta::KnowledgeBaseAdd { kb })
+                .map_err(KbError::StateError)?;
+            self.refresh_document_counts(&kb_id).await?;
+            loaded += 1;
+        }
+        Ok(loaded)
+    }
+
     /// Validate search query
     fn validate_query(&self, query: &str, top_k: usize) -> Result<(), KbError> {
         if query.trim().is_empty() {
@@ -138,57 +374,643 @@ impl KbServiceImpl {
                 status: format!("{:?}", kb.status),
                 health_score: kb.health_score,
                 version: kb.version,
+                embedder_model: kb.embedder_model.clone(),
             })
         } else {
             Err(KbError::KbNotFound(collection.to_string()))
         }
     }
 
-    /// Enrich results with citations (mandatory for MVP)
+    /// Enrich results with citations (mandatory for MVP), cited at the
+    /// version `index_id` was built for
     async fn enrich_with_citations(
         &self,
+        index_id: &str,
         mut results: Vec<SearchResult>,
     ) -> Result<Vec<SearchResult>, KbError> {
         if !self.config.enable_citations {
             return Ok(results);
         }
 
+        let mut fingerprints: HashMap<(String, String), Option<DocumentFingerprint>> = HashMap::new();
+        let mut versions: HashMap<String, i32> = HashMap::new();
         for result in &mut results {
-            // Get document metadata for citation
-            let doc_info = self.get_document_info(&result.document_id).await?;
-
-            result.citation = CitationInfo {
-                title: doc_info.title,
-                source_path: doc_info.source_path,
-                license: doc_info.license_info,
-                version: Some(doc_info.version.to_string()),
-                anchor: Some(format!("chunk_{}", result.chunk_id)),
-                page_number: None, // TODO: Extract from metadata
-            };
+            let key = (result.kb_id.clone(), result.document_id.clone());
+            if !fingerprints.contains_key(&key) {
+                let fingerprint = self.find_fingerprint(&result.kb_id, &result.document_id).await?;
+                fingerprints.insert(key.clone(), fingerprint);
+            }
+            if !versions.contains_key(&result.kb_id) {
+                versions.insert(result.kb_id.clone(), self.index_version(&result.kb_id, index_id).await?);
+            }
+            result.citation = chunk_citation(&result.chunk_id, &result.metadata, fingerprints[&key].as_ref(), versions[&result.kb_id]);
         }
 
         Ok(results)
     }
 
-    /// Get document info for citation
-    async fn get_document_info(&self, document_id: &str) -> Result<DocumentInfo, KbError> {
-        // This is a placeholder - would query SQL service for document metadata
+    /// Fingerprint of a document, None when the KB or the document is unknown
+    async fn find_fingerprint(&self, kb_id: &str, doc_id: &str) -> Result<Option<DocumentFingerprint>, KbError> {
+        if self.get_kb_state(kb_id).is_err() {
+            return Ok(None);
+        }
+        match self.get_fingerprint(kb_id, doc_id).await {
+            Ok(fingerprint) => Ok(Some(fingerprint)),
+            Err(KbError::DocumentNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Version an index was built for; the KB's own index is at its current version
+    async fn index_version(&self, kb_id: &str, index_id: &str) -> Result<i32, KbError> {
+        let versions = self.list_versions(kb_id).await?;
+        Ok(match versions.iter().find(|v| v.collection_id() == index_id) {
+            Some(version) => version.version,
+            None => self.state_manager.read_state().knowledge_bases.get(kb_id).map_or(1, |kb| kb.version),
+        })
+    }
+
+    /// Load a single KB version row
+    async fn get_version(&self, kb_id: &str, version: i32) -> Result<KbVersion, KbError> {
+        let id = kb_id.to_string();
+        let row = self.sql_service.with_app_transaction(move |conn| {
+            Ok(kb_versions::table
+                .filter(kb_versions::kb_id.eq(id))
+                .filter(kb_versions::version.eq(version))
+                .select(KbVersionRow::as_select())
+                .first(conn)
+                .optional()?)
+        }).await?;
+
+        row.ok_or_else(|| KbError::VersionNotFound(format!("{} v{}", kb_id, version)))?
+            .into_model()
+    }
+
+    /// Active version of a KB, if any version has been activated
+    pub async fn get_active_version(&self, kb_id: &str) -> Result<Option<KbVersion>, KbError> {
+        let id = kb_id.to_string();
+        let row = self.sql_service.with_app_transaction(move |conn| {
+            Ok(kb_versions::table
+                .filter(kb_versions::kb_id.eq(id))
+                .filter(kb_versions::status.eq(KbVersionStatus::Active.as_str()))
+                .select(KbVersionRow::as_select())
+                .first(conn)
+                .optional()?)
+        }).await?;
+
+        row.map(KbVersionRow::into_model).transpose()
+    }
+
+    /// Apply a change to the KB's entry in app state
+    fn update_kb_state(&self, kb_id: &str, apply: impl FnOnce(&mut KnowledgeBaseState)) -> Result<(), KbError> {
+        let kb = self.state_manager.read_state().knowledge_bases.get(kb_id).cloned();
+
+        // MVP: KBs created outside the state manager have nothing to update
+        if let Some(mut kb) = kb {
+            apply(&mut kb);
+            kb.last_updated = Utc::now();
+            self.state_manager
+                .mutate(StateDelta::KnowledgeBaseUpdate {
+                    id: kb_id.to_string(),
+                    updates: serde_json::to_value(&kb)?,
+                })
+                .map_err(KbError::StateError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Fingerprint of an indexed document, looked up by document ID
+    async fn get_fingerprint(&self, kb_id: &str, doc_id: &str) -> Result<DocumentFingerprint, KbError> {
+        let id = kb_id.to_string();
+        let document_id = doc_id.to_string();
+        let row = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(id))
+                .filter(document_fingerprints::document_id.eq(document_id))
+                .select(DocumentFingerprintRow::as_select())
+                .first(conn)
+                .optional()?)
+        }).await?;
+
+        row.map(DocumentFingerprintRow::into_model)
+            .ok_or_else(|| KbError::DocumentNotFound(doc_id.to_string()))
+    }
+
+    /// Read a local source into a pipeline document; the path must resolve inside `sandbox`
+    pub async fn load_source(&self, sandbox: &SourceSandbox, source: &str, doc_id: Option<&str>) -> Result<PipelineDocument, KbError> {
+        if is_remote_source(source) {
+            // MVP: remote sources go through a pipeline Fetch step
+            return Err(KbError::ValidationError(format!("Remote sources must be ingested via a pipeline: {}", source)));
+        }
+
+        let path = sandbox.resolve(source)
+            .map_err(|e| KbError::ValidationError(e.to_string()))?;
+        let content = tokio::fs::read_to_string(&path).await
+            .map_err(|e| KbError::ValidationError(format!("Failed to read {}: {}", source, e)))?;
+
+        Ok(PipelineDocument {
+            id: doc_id.map(str::to_string).unwrap_or_else(|| format!("doc_{}", uuid::Uuid::new_v4().simple())),
+            title: path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_else(|| source.to_string()),
+            source_path: path.to_string_lossy().to_string(),
+            content_hash: DocumentFingerprint::hash_content(&content),
+            content,
+            license_info: None,
+            metadata: serde_json::json!({}),
+        })
+    }
+
+    /// Read a file the user named directly (`add_documents`). Its own directory is
+    /// the sandbox, so a symlink can't redirect the read elsewhere; stored source
+    /// paths go through `kb_sandbox` instead.
+    async fn load_named_source(&self, source: &str, doc_id: Option<&str>) -> Result<PipelineDocument, KbError> {
+        if is_remote_source(source) {
+            return self.load_source(&SourceSandbox::default(), source, doc_id).await;
+        }
+        let path = std::path::absolute(source.strip_prefix("file://").unwrap_or(source))
+            .map_err(|e| KbError::ValidationError(format!("Invalid source {}: {}", source, e)))?;
+        let root = path.parent().map(|dir| dir.to_string_lossy().to_string()).unwrap_or_default();
+        self.load_source(&SourceSandbox::new(&[root]), &path.to_string_lossy(), doc_id).await
+    }
+
+    /// Sandbox over the source roots declared by the pipelines that build a KB
+    /// (those its refresh schedules replay). Stored source paths, imported ones
+    /// included, are only re-read through it; a KB without a pipeline has no roots.
+    fn kb_sandbox(&self, kb_id: &str) -> SourceSandbox {
+        let state = self.state_manager.read_state();
+        let roots: Vec<String> = state.schedules.values()
+            .filter(|schedule| schedule.kb_id.as_deref() == Some(kb_id))
+            .filter_map(|schedule| schedule.pipeline.as_ref())
+            .flat_map(|spec| spec.source_roots.iter().cloned())
+            .collect();
+        SourceSandbox::new(&roots)
+    }
+
+    /// Upsert a document's chunks and record its fingerprint. The write is staged
+    /// in the outbox first; `replace` drops the document's old chunks.
+    async fn index_document(&self, kb_id: &str, doc: &PipelineDocument, chunks: Vec<String>, replace: bool) -> Result<DocumentInfo, KbError> {
+        let now = Utc::now();
+        let chunk_count = chunks.len();
+        let entry = self.stage_write(kb_id, &doc.id, OutboxOperation::Upsert, chunk_count).await?;
+        let vectors = document_vectors(kb_id, doc, chunks, Some(entry.generation), now.timestamp());
+
+        let index_id = self.maintained_index(kb_id).await?;
+        if replace {
+            self.vector_service.delete_document(&index_id, &doc.id).await?;
+        }
+        // A failure from here on leaves the entry staged for check_consistency to repair
+        self.vector_service.upsert_vectors(&index_id, vectors).await?;
+
+        let row = DocumentFingerprintRow::from_model(&DocumentFingerprint {
+            kb_id: kb_id.to_string(),
+            source_path: doc.source_path.clone(),
+            document_id: doc.id.clone(),
+            content_hash: DocumentFingerprint::hash_for(doc),
+            chunk_count,
+            indexed_at: now,
+        });
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::replace_into(document_fingerprints::table)
+                .values(&row)
+                .execute(conn)?;
+            commit_outbox_entry(conn, &entry)
+        }).await?;
+
         Ok(DocumentInfo {
-            id: document_id.to_string(),
-            title: format!("Document {}", document_id),
-            source_path: format!("/documents/{}", document_id),
-            license_info: Some("MIT".to_string()),
-            version: 1,
-            chunk_count: 10,
-            size_bytes: 1024,
+            id: doc.id.clone(),
+            title: doc.title.clone(),
+            source_path: doc.source_path.clone(),
+            license_info: doc.license_info.clone(),
+            version: self.get_kb_state(kb_id)?.version,
+            chunk_count: chunk_count as i32,
+            size_bytes: doc.content.len() as i64,
+        })
+    }
+
+    /// Index already-loaded documents (e.g. pipeline output) into a KB
+    pub async fn ingest_documents(&self, kb_id: &str, documents: &[PipelineDocument]) -> Result<Vec<DocumentInfo>, KbError> {
+        self.get_kb_state(kb_id)?;
+        self.check_ingest_quota(kb_id, documents).await?;
+
+        let mut added = Vec::with_capacity(documents.len());
+        for doc in documents {
+            let chunks = chunk_text(&doc.content, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
+            added.push(self.index_document(kb_id, doc, chunks, false).await?);
+        }
+
+        self.refresh_document_counts(kb_id).await?;
+        self.invalidate_cache(kb_id);
+        tracing::info!("Added {} documents to KB {}", added.len(), kb_id);
+        Ok(added)
+    }
+
+    /// The stored pipeline (and its input sources) that builds a KB: the one its
+    /// refresh schedule replays, the first by schedule id when there are several
+    fn kb_pipeline(&self, kb_id: &str) -> Option<(PipelineSpec, Vec<String>)> {
+        let state = self.state_manager.read_state();
+        state.schedules.values()
+            .filter(|schedule| schedule.kb_id.as_deref() == Some(kb_id) && schedule.pipeline.is_some())
+            .min_by(|a, b| a.id.cmp(&b.id))
+            .and_then(|schedule| Some((schedule.pipeline.clone()?, schedule.sources.clone())))
+    }
+
+    /// Re-run the KB's stored pipeline and reindex only the documents whose source
+    /// fingerprint changed, so parsing, normalization and redaction match the build.
+    /// Documents the run didn't produce (remote, outside the source roots, skipped)
+    /// stay indexed unless their local file is gone. `full` ignores fingerprints.
+    pub async fn reindex_sources(&self, kb_id: &str, full: bool) -> Result<ReindexPlan, KbError> {
+        self.get_kb_state(kb_id)?;
+        let runner = self.pipeline_runner.as_ref()
+            .ok_or_else(|| KbError::ValidationError("No pipeline runner configured for reindexing".to_string()))?;
+        let (spec, sources) = self.kb_pipeline(kb_id)
+            .ok_or_else(|| KbError::ValidationError(format!("KB {} has no stored pipeline to reindex with", kb_id)))?;
+
+        let sandbox = SourceSandbox::new(&spec.source_roots);
+        let mut inputs = Vec::with_capacity(sources.len());
+        for source in &sources {
+            inputs.push(self.load_source(&sandbox, source, None).await?);
+        }
+        let run_id = format!("reindex_{}", uuid::Uuid::new_v4().simple());
+        let output = runner.run(&spec, &run_id, StepData { documents: inputs, chunks: Vec::new() }).await;
+        if let Some(error) = &output.error {
+            return Err(KbError::ValidationError(format!("Pipeline failed: {}", error)));
+        }
+
+        // A reindexed source keeps its document id, so its old chunks are replaced
+        let fingerprints = self.list_fingerprints(kb_id).await?;
+        let document_ids: HashMap<&str, &str> = fingerprints.iter()
+            .map(|f| (f.source_path.as_str(), f.document_id.as_str()))
+            .collect();
+        let documents: Vec<PipelineDocument> = output.data.documents.into_iter().map(|mut doc| {
+            if let Some(id) = document_ids.get(doc.source_path.as_str()) {
+                doc.id = id.to_string();
+            }
+            doc
+        }).collect();
+
+        let mut plan = self.plan_reindex(kb_id, documents.clone()).await?;
+        if full {
+            plan.changed = documents;
+            plan.unchanged.clear();
+        }
+        let (removed, kept) = std::mem::take(&mut plan.removed).into_iter()
+            .partition(|f| is_deleted_source(&f.source_path));
+        plan.removed = removed;
+        plan.unchanged.extend::<Vec<_>>(kept);
+        self.check_ingest_quota(kb_id, &plan.changed).await?;
+
+        let mut data = StepData::default();
+        for doc in &plan.changed {
+            let chunks = chunk_text(&doc.content, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
+            data.chunks.extend(chunks.iter().enumerate().map(|(index, content)| PipelineChunk {
+                id: format!("{}_{}", doc.id, index),
+                document_id: doc.id.clone(),
+                chunk_index: index as i32,
+                content: content.clone(),
+                content_hash: DocumentFingerprint::hash_content(content),
+                metadata: serde_json::json!({}),
+                embedding: Vec::new(),
+            }));
+            self.index_document(kb_id, doc, chunks, true).await?;
+            data.documents.push(doc.clone());
+        }
+        for fingerprint in &plan.removed {
+            self.remove_document(kb_id, &fingerprint.document_id).await?;
+        }
+        self.record_fingerprints(kb_id, &plan, &data).await?;
+
+        self.refresh_document_counts(kb_id).await?;
+        self.invalidate_cache(kb_id);
+        tracing::info!(
+            "Reindexed KB {}: {} changed, {} unchanged, {} removed",
+            kb_id, plan.changed.len(), plan.unchanged.len(), plan.removed.len()
+        );
+        Ok(plan)
+    }
+
+    /// Rebuild the BM25 index of the KB's active version from its stored chunks
+    pub async fn rebuild_bm25_index(
+        &self,
+        kb_id: &str,
+        progress: &mut (dyn FnMut(IndexMaintenanceProgress) + Send),
+    ) -> Result<IndexMaintenanceReport, KbError> {
+        self.get_kb_state(kb_id)?;
+        let index_id = self.maintained_index(kb_id).await?;
+        let mut report = self.vector_service.rebuild_bm25_index(&index_id, progress).await?;
+        report.kb_id = kb_id.to_string();
+        self.invalidate_cache(kb_id);
+        Ok(report)
+    }
+
+    /// Compact the indexes of the KB's active version (BM25, FTS5, LanceDB)
+    pub async fn optimize_index(
+        &self,
+        kb_id: &str,
+        progress: &mut (dyn FnMut(IndexMaintenanceProgress) + Send),
+    ) -> Result<IndexMaintenanceReport, KbError> {
+        self.get_kb_state(kb_id)?;
+        let index_id = self.maintained_index(kb_id).await?;
+        let mut report = self.vector_service.optimize_index(&index_id, progress).await?;
+        report.kb_id = kb_id.to_string();
+        if report.removed > 0 {
+            self.invalidate_cache(kb_id);
+        }
+        Ok(report)
+    }
+
+    /// Index documents are written to and searches read: the active version's,
+    /// or the KB's own before any version is activated
+    async fn maintained_index(&self, kb_id: &str) -> Result<String, KbError> {
+        Ok(match self.get_active_version(kb_id).await? {
+            Some(version) => version.collection_id(),
+            None => kb_id.to_string(),
+        })
+    }
+
+    /// Index queries read: the pinned or active version's, or the KB's own
+    /// before any version is activated
+    async fn read_index(&self, kb_id: &str) -> Result<String, KbError> {
+        Ok(match self.resolve_version(kb_id, None).await? {
+            Some(version) => version.collection_id(),
+            None => kb_id.to_string(),
+        })
+    }
+
+    /// Stats of the version a query would use (or `version`), measured from
+    /// the vector store, with every version's generation alongside
+    async fn collection_stats(&self, kb_id: &str, version: Option<i32>) -> Result<KbStats, KbError> {
+        let (kb_state, pinned) = {
+            let state = self.state_manager.read_state();
+            let kb_state = state.knowledge_bases.get(kb_id).cloned()
+                .ok_or_else(|| KbError::KbNotFound(kb_id.to_string()))?;
+            (kb_state, state.pinned_version(None, kb_id))
+        };
+        let versions = self.list_versions(kb_id).await?;
+        let selected = match version.or(pinned) {
+            Some(number) => Some(versions.iter().find(|v| v.version == number)
+                .ok_or_else(|| KbError::VersionNotFound(format!("{} v{}", kb_id, number)))?),
+            None => versions.iter().find(|v| v.status == KbVersionStatus::Active),
+        };
+        let stats = self.index_stats(&selected.map_or_else(|| kb_id.to_string(), KbVersion::collection_id)).await?;
+
+        let mut generations = Vec::with_capacity(versions.len());
+        for kb_version in &versions {
+            let generation = self.index_stats(&kb_version.collection_id()).await?;
+            generations.push(KbGenerationStats {
+                version: kb_version.version,
+                generation_id: kb_version.generation_id,
+                status: kb_version.status,
+                chunk_count: generation.chunk_count as usize,
+                vector_count: generation.vector_count as usize,
+                size_bytes: generation.size_bytes,
+            });
+        }
+
+        Ok(KbStats {
+            collection_name: Some(kb_id.to_string()),
+            version: version.or(pinned).unwrap_or(kb_state.version),
+            document_count: stats.document_count as usize,
+            chunk_count: stats.chunk_count as usize,
+            vector_count: stats.vector_count as usize,
+            dimension: stats.dimension,
+            size_bytes: stats.size_bytes,
+            health_score: kb_state.health_score,
+            embedder_version: kb_state.embedder_model,
+            last_updated: stats.last_updated.unwrap_or(kb_state.last_updated),
+            generations,
+        })
+    }
+
+    /// Vector store stats of an index; empty for one nothing was written to yet
+    async fn index_stats(&self, index_id: &str) -> Result<CollectionStats, KbError> {
+        match self.vector_service.get_collection_stats(index_id).await {
+            Ok(stats) => Ok(stats),
+            Err(VectorDbError::CollectionNotFound(_)) => Ok(CollectionStats::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Pre-flight disk quota check for ingesting `documents` into a KB; fails
+    /// with `KbError::QuotaError` before anything is written. None when no
+    /// quotas are configured.
+    pub async fn check_ingest_quota(&self, kb_id: &str, documents: &[PipelineDocument]) -> Result<Option<QuotaCheck>, KbError> {
+        let Some(quota_service) = &self.quota_service else {
+            return Ok(None);
+        };
+        let content_bytes: usize = documents.iter().map(|doc| doc.content.len()).sum();
+        let chunks = content_bytes.div_ceil(DEFAULT_CHUNK_SIZE - DEFAULT_CHUNK_OVERLAP);
+        let requested = estimate_ingest_bytes(content_bytes as u64, chunks as u64);
+        Ok(Some(quota_service.check(Some(kb_id), requested).await?))
+    }
+
+    /// Recompute document/chunk counts in app state from stored fingerprints
+    async fn refresh_document_counts(&self, kb_id: &str) -> Result<(), KbError> {
+        let id = kb_id.to_string();
+        let chunk_counts: Vec<i32> = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(id))
+                .select(document_fingerprints::chunk_count)
+                .load(conn)?)
+        }).await?;
+
+        self.update_kb_state(kb_id, |kb| {
+            kb.document_count = chunk_counts.len();
+            kb.chunk_count = chunk_counts.iter().map(|c| *c as usize).sum();
         })
     }
 
+    /// Drop cached results for a KB after its contents or active version change
+    fn invalidate_cache(&self, kb_id: &str) {
+        if let Some(cache) = &self.cache_service {
+            if let Err(e) = cache.invalidate_kb(kb_id) {
+                tracing::warn!("Failed to invalidate cache for KB {}: {}", kb_id, e);
+            }
+        }
+    }
+
+    /// Keep a version's document content in the blob store, plus the run's
+    /// artifacts (documents and report) under the run. Unchanged documents
+    /// are stored once however many versions and runs include them.
+    fn store_version_blobs(&self, kb_version: &KbVersion, output: &PipelineRunOutput) -> Result<(), KbError> {
+        let documents: BTreeMap<String, Vec<u8>> = output.data.documents.iter()
+            .map(|doc| (format!("documents/{}", doc.source_path), doc.content.as_bytes().to_vec()))
+            .collect();
+        let metadata = serde_json::json!({ "kb_id": kb_version.kb_id, "version": kb_version.version });
+
+        if let Some(run_id) = &kb_version.pipeline_run_id {
+            let mut artifacts = documents.clone();
+            artifacts.insert("report.json".to_string(), serde_json::to_vec_pretty(&output.report)?);
+            self.storage_service.store_blobs(&run_blob_manifest(run_id), artifacts, metadata.clone())?;
+        }
+        self.storage_service.store_blobs(&version_blob_manifest(&kb_version.kb_id, kb_version.version), documents, metadata)?;
+        Ok(())
+    }
+
+    /// All fingerprints stored for a KB
+    async fn list_fingerprints(&self, kb_id: &str) -> Result<Vec<DocumentFingerprint>, KbError> {
+        let id = kb_id.to_string();
+        let rows = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(id))
+                .select(DocumentFingerprintRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        Ok(rows.into_iter().map(DocumentFingerprintRow::into_model).collect())
+    }
+
+    /// Record a write in the outbox before the vector store is touched
+    async fn stage_write(
+        &self,
+        kb_id: &str,
+        document_id: &str,
+        operation: OutboxOperation,
+        chunk_count: usize,
+    ) -> Result<IndexOutboxEntry, KbError> {
+        let id = kb_id.to_string();
+        let document_id = document_id.to_string();
+        let entry = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            let latest: Option<i64> = index_outbox::table
+                .filter(index_outbox::kb_id.eq(&id))
+                .select(diesel::dsl::max(index_outbox::generation))
+                .first(conn)?;
+            let entry = IndexOutboxEntry {
+                id: format!("obx_{}", uuid::Uuid::new_v4().simple()),
+                kb_id: id,
+                document_id,
+                operation,
+                generation: latest.unwrap_or(0).max(0) as u64 + 1,
+                chunk_count,
+                status: OutboxStatus::Staged,
+                created_at: Utc::now(),
+                committed_at: None,
+            };
+            diesel::insert_into(index_outbox::table)
+                .values(&IndexOutboxRow::from_model(&entry))
+                .execute(conn)?;
+            Ok(entry)
+        }).await?;
+
+        Ok(entry)
+    }
+
+    /// All outbox entries of a KB, oldest first
+    async fn list_outbox(&self, kb_id: &str) -> Result<Vec<IndexOutboxEntry>, KbError> {
+        let id = kb_id.to_string();
+        let rows = self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            Ok(index_outbox::table
+                .filter(index_outbox::kb_id.eq(id))
+                .order(index_outbox::generation.asc())
+                .select(IndexOutboxRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(IndexOutboxRow::into_model).collect()
+    }
+
+    /// Purge the index state of diverged documents and settle staged writes:
+    /// interrupted deletes are completed, interrupted upserts rolled back
+    async fn repair_index(&self, kb_id: &str, report: &ConsistencyReport) -> Result<(), KbError> {
+        let purge: BTreeSet<String> = report.orphan_documents.iter()
+            .chain(&report.missing_documents)
+            .chain(&report.stale_documents)
+            .chain(report.staged_writes.iter().map(|e| &e.document_id))
+            .cloned()
+            .collect();
+
+        let index_id = self.maintained_index(kb_id).await?;
+        for doc_id in &purge {
+            match self.vector_service.delete_document(&index_id, doc_id).await {
+                Ok(()) | Err(VectorDbError::CollectionNotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+
+        let id = kb_id.to_string();
+        let staged = report.staged_writes.clone();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::delete(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(&id))
+                .filter(document_fingerprints::document_id.eq_any(&purge)))
+                .execute(conn)?;
+            for entry in &staged {
+                let status = match entry.operation {
+                    OutboxOperation::Delete => OutboxStatus::Committed,
+                    OutboxOperation::Upsert => OutboxStatus::RolledBack,
+                };
+                diesel::update(index_outbox::table.filter(index_outbox::id.eq(&entry.id)))
+                    .set((
+                        index_outbox::status.eq(status.as_str()),
+                        index_outbox::committed_at.eq(Some(Utc::now().naive_utc())),
+                    ))
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).await?;
+
+        self.refresh_document_counts(kb_id).await?;
+        self.invalidate_cache(kb_id);
+        Ok(())
+    }
+
+    /// Shared search path for active and version-scoped search
+    async fn search_index(
+        &self,
+        index_id: &str,
+        query: &str,
+        top_k: usize,
+        filters: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Vec<SearchResult>, KbError> {
+        if !self.config.hybrid_search_enabled {
+            // MVP: Simple vector search fallback
+            return self.vector_only_search(index_id, query, top_k, filters).await;
+        }
+
+        // MMR picks top_k from a wider candidate pool
+        let candidates = match self.config.mmr_lambda {
+            Some(_) => top_k * MMR_CANDIDATE_FACTOR,
+            None => top_k,
+        };
+
+        // MVP: Sequential search (upgrade path: parallel with tokio::join!)
+        let vector_results = self.vector_search(index_id, query, candidates * 2, &filters).await?;
+        let bm25_results = self.bm25_search(index_id, query, candidates * 2, &filters).await?;
+        // Before merging, which replaces result metadata
+        let vector_results = apply_query_language(apply_metadata_filters(vector_results, &filters), query, &filters);
+        let bm25_results = apply_query_language(apply_metadata_filters(bm25_results, &filters), query, &filters);
+
+        // Merge results with simple scoring (MVP)
+        let mut merged_results = self.merge_search_results(vector_results, bm25_results, candidates)?;
+        if let Some(lambda) = self.config.mmr_lambda {
+            merged_results = mmr_rerank(merged_results, lambda, top_k);
+        }
+
+        for result in &mut merged_results {
+            highlight_result(result, query);
+        }
+
+        // Mandatory citation enrichment
+        let enriched_results = self.enrich_with_citations(index_id, merged_results).await?;
+
+        // Validate all results have citations (MVP requirement)
+        if self.config.citation_required {
+            for result in &enriched_results {
+                if result.citation.title.is_empty() {
+                    return Err(KbError::CitationError(
+                        format!("Missing citation for chunk: {}", result.chunk_id)
+                    ));
+                }
+            }
+        }
+
+        Ok(enriched_results)
+    }
+
     // Helper search methods
     async fn vector_only_search(
         &self,
         _collection: &str,
-        _query: &str,
+        query: &str,
         top_k: usize,
         _filters: Option<HashMap<String, serde_json::Value>>,
     ) -> Result<Vec<SearchResult>, KbError> {
@@ -207,7 +1029,8 @@ impl KbServiceImpl {
                 kb_id: doc.kb_id,
                 score: 1.0 - (i as f32 * 0.1), // Simple scoring
                 content: doc.content.clone(),
-                snippet: doc.content[..std::cmp::min(200, doc.content.len())].to_string(),
+                snippet: String::new(),
+                highlights: Vec::new(),
                 metadata: doc.metadata,
                 citation: CitationInfo {
                     title: "".to_string(),
@@ -216,9 +1039,13 @@ impl KbServiceImpl {
                     version: None,
                     anchor: None,
                     page_number: None,
+                    time_range: None,
                 },
             });
         }
+        for result in &mut results {
+            highlight_result(result, query);
+        }
 
         Ok(results)
     }
@@ -227,62 +1054,26 @@ impl KbServiceImpl {
         &self,
         _collection: &str,
         _query: &str,
-        top_k: usize,
+        _top_k: usize,
         _filters: &Option<HashMap<String, serde_json::Value>>,
     ) -> Result<Vec<SearchResult>, KbError> {
-        // MVP: Placeholder vector search
-        let mut results = Vec::new();
-        for i in 0..std::cmp::min(top_k, 5) {
-            results.push(SearchResult {
-                chunk_id: format!("chunk_vector_{}", i),
-                document_id: format!("doc_{}", i),
-                kb_id: "test_kb".to_string(),
-                score: 0.9 - (i as f32 * 0.1),
-                content: format!("Vector search result {}", i),
-                snippet: format!("Vector snippet {}", i),
-                metadata: serde_json::json!({"source": "vector"}),
-                citation: CitationInfo {
-                    title: "".to_string(),
-                    source_path: "".to_string(),
-                    license: None,
-                    version: None,
-                    anchor: None,
-                    page_number: None,
-                },
-            });
-        }
-        Ok(results)
+        // MVP: queries are not embedded yet, so the lexical leg answers alone
+        Ok(Vec::new())
     }
 
     async fn bm25_search(
         &self,
-        _collection: &str,
-        _query: &str,
+        collection: &str,
+        query: &str,
         top_k: usize,
         _filters: &Option<HashMap<String, serde_json::Value>>,
     ) -> Result<Vec<SearchResult>, KbError> {
-        // MVP: Placeholder BM25 search
-        let mut results = Vec::new();
-        for i in 0..std::cmp::min(top_k, 5) {
-            results.push(SearchResult {
-                chunk_id: format!("chunk_bm25_{}", i),
-                document_id: format!("doc_{}", i),
-                kb_id: "test_kb".to_string(),
-                score: 0.8 - (i as f32 * 0.1),
-                content: format!("BM25 search result {}", i),
-                snippet: format!("BM25 snippet {}", i),
-                metadata: serde_json::json!({"source": "bm25"}),
-                citation: CitationInfo {
-                    title: "".to_string(),
-                    source_path: "".to_string(),
-                    license: None,
-                    version: None,
-                    anchor: None,
-                    page_number: None,
-                },
-            });
+        // Metadata filters are applied by the caller, after both legs
+        match self.vector_service.bm25_search(collection, query, top_k, None).await {
+            Ok(results) => Ok(results),
+            Err(VectorDbError::CollectionNotFound(_)) => Ok(Vec::new()),  // Nothing indexed yet
+            Err(e) => Err(e.into()),
         }
-        Ok(results)
     }
 
     fn merge_search_results(
@@ -333,40 +1124,81 @@ impl KbService for KbServiceImpl {
         query: &str,
         top_k: usize,
         filters: Option<HashMap<String, serde_json::Value>>,
-        _cache_ttl: Option<u64>,
+        cache_ttl: Option<u64>,
     ) -> Result<Vec<SearchResult>, KbError> {
         self.validate_query(query, top_k)?;
+        let started = std::time::Instant::now();
 
         // Check KB exists and get state
-        let _kb_state = self.get_kb_state(collection)?;
+        let kb_state = self.get_kb_state(collection)?;
 
         tracing::info!(
             "Starting hybrid search for collection: {}, query: {}, top_k: {}",
             collection, query, top_k
         );
 
-        if !self.config.hybrid_search_enabled {
-            // MVP: Simple vector search fallback
-            return self.vector_only_search(collection, query, top_k, filters).await;
+        // Search the pinned or active version's index once versions exist
+        let index_id = self.read_index(collection).await?;
+
+        // Cached only when the caller asks for it (cache_ttl in seconds)
+        let cache = self.cache_service.as_ref()
+            .zip(cache_ttl.filter(|ttl| *ttl > 0))
+            .map(|(cache, ttl)| (cache, search_cache_key(&index_id, query, top_k, filters.as_ref()), ttl));
+        if let Some((cache, key, _)) = &cache {
+            match cache.get::<Vec<SearchResult>>(key) {
+                Ok(Some(results)) => {
+                    tracing::debug!("Hybrid search served from cache: {}", key);
+                    if let Some(metrics) = &self.metrics_service {
+                        metrics.record_cache_lookup(true);
+                        metrics.record_search(started.elapsed());
+                    }
+                    return Ok(results);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Cache lookup failed for {}: {}", key, e),
+            }
         }
 
-        // MVP: Sequential search (upgrade path: parallel with tokio::join!)
-        let vector_results = self.vector_search(collection, query, top_k * 2, &filters).await?;
-        let bm25_results = self.bm25_search(collection, query, top_k * 2, &filters).await?;
-
-        // Merge results with simple scoring (MVP)
-        let merged_results = self.merge_search_results(vector_results, bm25_results, top_k)?;
+        // Near-duplicate queries: the semantic tier matches on the query's embedding
+        let scope = semantic_cache_scope(&index_id, top_k, filters.as_ref());
+        let query_embedding = match (&cache, &self.query_embedder) {
+            (Some(_), Some(embedder)) => match embedder.embed(&kb_state.embedder_model, &[query.to_string()]).await {
+                Ok(mut vectors) => vectors.pop(),
+                Err(e) => {
+                    tracing::warn!("Failed to embed query for the semantic cache: {}", e);
+                    None
+                }
+            },
+            _ => None,
+        };
+        if let Some(((cache, _, _), embedding)) = cache.as_ref().zip(query_embedding.as_ref()) {
+            match cache.get_similar::<Vec<SearchResult>>(&scope, embedding) {
+                Ok(Some(results)) => {
+                    tracing::debug!("Hybrid search served from the semantic cache: {}", scope);
+                    if let Some(metrics) = &self.metrics_service {
+                        metrics.record_cache_lookup(true);
+                        metrics.record_search(started.elapsed());
+                    }
+                    return Ok(results);
+                }
+                Ok(None) => {}
+                Err(e) => tracing::warn!("Semantic cache lookup failed for {}: {}", scope, e),
+            }
+        }
+        if let Some(metrics) = self.metrics_service.as_ref().filter(|_| cache.is_some()) {
+            metrics.record_cache_lookup(false);
+        }
 
-        // Mandatory citation enrichment
-        let enriched_results = self.enrich_with_citations(merged_results).await?;
+        let enriched_results = self.search_index(&index_id, query, top_k, filters).await?;
 
-        // Validate all results have citations (MVP requirement)
-        if self.config.citation_required {
-            for result in &enriched_results {
-                if result.citation.title.is_empty() {
-                    return Err(KbError::CitationError(
-                        format!("Missing citation for chunk: {}", result.chunk_id)
-                    ));
+        if let Some((cache, key, ttl)) = &cache {
+            let ttl = Some(std::time::Duration::from_secs(*ttl));
+            if let Err(e) = cache.set(key, &enriched_results, ttl, &[kb_tag(collection)]) {
+                tracing::warn!("Failed to cache search results for {}: {}", key, e);
+            }
+            if let Some(embedding) = &query_embedding {
+                if let Err(e) = cache.set_similar(&scope, embedding, &enriched_results, ttl, &[kb_tag(collection)]) {
+                    tracing::warn!("Failed to cache search results for {}: {}", scope, e);
                 }
             }
         }
@@ -375,10 +1207,202 @@ impl KbService for KbServiceImpl {
             "Hybrid search completed: {} results with citations",
             enriched_results.len()
         );
+        if let Some(metrics) = &self.metrics_service {
+            metrics.record_search(started.elapsed());
+        }
 
         Ok(enriched_results)
     }
 
+    async fn search_multiple(
+        &self,
+        kb_ids: &[String],
+        query: &str,
+        top_k: usize,
+        filters: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Vec<SearchResult>, KbError> {
+        self.validate_query(query, top_k)?;
+
+        let mut unique: Vec<&String> = Vec::with_capacity(kb_ids.len());
+        for kb_id in kb_ids {
+            if !unique.contains(&kb_id) {
+                unique.push(kb_id);
+            }
+        }
+        if unique.is_empty() || unique.len() > MAX_FEDERATED_KBS {
+            return Err(KbError::InvalidQuery(format!("Federated search needs 1 to {} knowledge bases", MAX_FEDERATED_KBS)));
+        }
+        // Fail before searching if any KB is unknown
+        let sources: Vec<KbStateInfo> = unique.iter().map(|kb_id| self.get_kb_state(kb_id)).collect::<Result<_, _>>()?;
+
+        let searches = sources.iter().map(|kb| self.hybrid_search(&kb.id, query, top_k, filters.clone(), None));
+        let mut result_sets = try_join_all(searches).await?;
+
+        // Scores from different indexes are not comparable; keep the per-KB
+        // normalized score for display and fuse on rank only
+        for (kb, results) in sources.iter().zip(result_sets.iter_mut()) {
+            normalize_scores(results);
+            for result in results.iter_mut() {
+                result.kb_id = kb.id.clone();
+                if !result.metadata.is_object() {
+                    result.metadata = serde_json::json!({});
+                }
+                result.metadata["source_kb"] = serde_json::json!({ "id": kb.id, "name": kb.name });
+                result.metadata["source_score"] = serde_json::json!(result.score);
+            }
+        }
+
+        // Fused scores relative to the best match
+        let mut merged = reciprocal_rank_fusion(&result_sets, DEFAULT_RRF_K, top_k);
+        if let Some(best) = merged.first().map(|r| r.score) {
+            merged.iter_mut().for_each(|r| r.score /= best);
+        }
+
+        tracing::info!("Federated search over {} KBs returned {} results", sources.len(), merged.len());
+        Ok(merged)
+    }
+
+    async fn hierarchical_search(
+        &self,
+        collection: &str,
+        query: &str,
+        top_k: usize,
+    ) -> Result<Vec<SearchResult>, KbError> {
+        self.validate_query(query, top_k)?;
+        let version = self.get_kb_state(collection)?.version;
+
+        let nodes = match self.vector_service.export_chunks(&summary_index_id(collection)).await {
+            Ok(nodes) => nodes,
+            Err(VectorDbError::CollectionNotFound(_)) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let query_terms = terms(query);
+        let coverage = |text: &str| match query_terms.len() {
+            0 => 0.0,
+            n => terms(text).intersection(&query_terms).count() as f32 / n as f32,
+        };
+        let matched: Vec<(&VectorDocument, f32)> = nodes.iter()
+            .map(|node| (node, coverage(&node.content)))
+            .filter(|(_, score)| *score > 0.0)
+            .collect();
+
+        // Level 1: documents ranked by their best matching summary
+        let mut best: HashMap<&str, f32> = HashMap::new();
+        for (node, score) in &matched {
+            let entry = best.entry(node.document_id.as_str()).or_default();
+            *entry = entry.max(*score);
+        }
+        let mut documents: Vec<(&str, f32)> = best.into_iter().collect();
+        documents.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+        documents.truncate(HIERARCHY_DOCUMENTS);
+        if documents.is_empty() {
+            tracing::debug!("No summaries of KB {} match, falling back to hybrid search", collection);
+            return self.hybrid_search(collection, query, top_k, None, None).await;
+        }
+
+        // Level 2: matching sections of those documents, the whole document when none match
+        let span = |node: &VectorDocument, key: &str| node.metadata.get(key).and_then(|v| v.as_u64()).unwrap_or(0) as usize;
+        let mut spans: HashMap<&str, Vec<(usize, usize, f32, &str)>> = HashMap::new();
+        for (doc_id, score) in &documents {
+            let sections: Vec<(usize, usize, f32, &str)> = matched.iter()
+                .filter(|(node, _)| node.document_id == *doc_id && node.metadata["summary_level"] == "section")
+                .map(|(node, score)| (span(node, "start"), span(node, "end"), *score, node.chunk_id.as_str()))
+                .collect();
+            let whole = (0, usize::MAX, *score, "");
+            spans.insert(doc_id, if sections.is_empty() { vec![whole] } else { sections });
+        }
+
+        // Level 3: chunks whose text falls in a selected span
+        let index_id = match self.resolve_version(collection, None).await? {
+            Some(version) => version.collection_id(),
+            None => collection.to_string(),
+        };
+        let chunks = match self.vector_service.export_chunks(&index_id).await {
+            Ok(chunks) => chunks,
+            Err(VectorDbError::CollectionNotFound(_)) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        let step = DEFAULT_CHUNK_SIZE - DEFAULT_CHUNK_OVERLAP;
+        let mut drilled: Vec<(VectorDocument, f32, usize, &str)> = Vec::new();
+        for chunk in chunks {
+            let Some(doc_spans) = spans.get(chunk.document_id.as_str()) else {
+                continue;
+            };
+            let index = chunk.metadata.get("chunk_index").and_then(|i| i.as_u64()).unwrap_or(0) as usize;
+            let (start, end) = (index * step, index * step + DEFAULT_CHUNK_SIZE);
+            let parent = doc_spans.iter()
+                .filter(|(span_start, span_end, _, _)| *span_start < end && start < *span_end)
+                .max_by(|a, b| a.2.total_cmp(&b.2));
+            if let Some((_, _, parent_score, parent_id)) = parent {
+                let score = (parent_score + coverage(&chunk.content)) / 2.0;
+                drilled.push((chunk, score, index, parent_id));
+            }
+        }
+        let rank = |doc_id: &str| documents.iter().position(|(id, _)| *id == doc_id).unwrap_or(usize::MAX);
+        drilled.sort_by(|a, b| {
+            b.1.total_cmp(&a.1)
+                .then(rank(&a.0.document_id).cmp(&rank(&b.0.document_id)))
+                .then(a.2.cmp(&b.2))
+        });
+
+        let to_result = |doc: VectorDocument, score: f32| {
+            let text = |key: &str| doc.metadata.get(key).and_then(|v| v.as_str()).unwrap_or_default().to_string();
+            let citation = CitationInfo {
+                title: text("title"),
+                source_path: text("source_path"),
+                license: None,
+                version: Some(version.to_string()),
+                anchor: Some(format!("chunk_{}", doc.chunk_id)),
+                page_number: None,
+                time_range: TimeRange::from_metadata(&doc.metadata),
+            };
+            let mut result = SearchResult {
+                chunk_id: doc.chunk_id,
+                document_id: doc.document_id,
+                kb_id: collection.to_string(),
+                score,
+                content: doc.content,
+                snippet: String::new(),
+                highlights: Vec::new(),
+                metadata: doc.metadata,
+                citation,
+            };
+            highlight_result(&mut result, query);
+            result
+        };
+
+        let mut results = Vec::with_capacity(top_k);
+        let (top_document, top_score) = documents[0];
+        if let Some(summary) = nodes.iter().find(|n| n.document_id == top_document && n.metadata["summary_level"] == "document") {
+            results.push(to_result(summary.clone(), top_score));
+        }
+        for (chunk, score, _, parent_id) in drilled {
+            let mut result = to_result(chunk, score);
+            if let (false, Some(metadata)) = (parent_id.is_empty(), result.metadata.as_object_mut()) {
+                metadata.insert("summary_id".to_string(), serde_json::json!(parent_id));
+            }
+            results.push(result);
+        }
+        results.truncate(top_k);
+
+        tracing::info!(
+            "Hierarchical search over {} documents of KB {} returned {} results",
+            documents.len(), collection, results.len()
+        );
+        Ok(results)
+    }
+
+    async fn expand_query_with_graph(
+        &self,
+        collection: &str,
+        query: &str,
+        max_terms: usize,
+    ) -> Result<QueryExpansion, KbError> {
+        self.validate_query(query, 1)?;
+        self.get_kb_state(collection)?;
+        Ok(self.graph_service.expand_query(collection, query, max_terms).await?)
+    }
+
     async fn get_document(
         &self,
         doc_id: &str,
@@ -386,29 +1410,188 @@ impl KbService for KbServiceImpl {
     ) -> Result<DocumentInfo, KbError> {
         tracing::debug!("Getting document: {} with range: {:?}", doc_id, range);
 
-        // MVP: Simple document retrieval
-        self.get_document_info(doc_id).await
+        // Document IDs are unique across KBs, so the KB holding it answers
+        let kb_ids: Vec<String> = self.state_manager.read_state().knowledge_bases.keys().cloned().collect();
+        for kb_id in kb_ids {
+            if let Some(fingerprint) = self.find_fingerprint(&kb_id, doc_id).await? {
+                return Ok(document_info(fingerprint, self.get_kb_state(&kb_id)?.version));
+            }
+        }
+        Err(KbError::DocumentNotFound(doc_id.to_string()))
+    }
+
+    async fn expand_results(
+        &self,
+        results: Vec<SearchResult>,
+        expansion: ContextExpansion,
+    ) -> Result<Vec<SearchResult>, KbError> {
+        // Chunks of every document hit, in document order
+        let mut documents: HashMap<(String, String), Vec<VectorDocument>> = HashMap::new();
+        let hit_documents: HashSet<(&str, &str)> = results.iter().map(|r| (r.kb_id.as_str(), r.document_id.as_str())).collect();
+        let kb_ids: BTreeSet<&str> = hit_documents.iter().map(|(kb_id, _)| *kb_id).collect();
+        for kb_id in kb_ids {
+            let chunks = match self.vector_service.export_chunks(&self.read_index(kb_id).await?).await {
+                Ok(chunks) => chunks,
+                Err(VectorDbError::CollectionNotFound(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            for chunk in chunks.into_iter().filter(|c| hit_documents.contains(&(kb_id, c.document_id.as_str()))) {
+                documents.entry((kb_id.to_string(), chunk.document_id.clone())).or_default().push(chunk);
+            }
+        }
+        for chunks in documents.values_mut() {
+            chunks.sort_by_key(|chunk| chunk.metadata.get("chunk_index").and_then(|i| i.as_u64()).unwrap_or(u64::MAX));
+        }
+
+        // In rank order, so a neighbourhood overlapping a better hit's joins it
+        let mut expanded: Vec<(SearchResult, Option<std::ops::Range<usize>>)> = Vec::with_capacity(results.len());
+        for result in results {
+            let key = (result.kb_id.clone(), result.document_id.clone());
+            let hit = documents.get(&key).and_then(|chunks| {
+                chunks.iter().position(|c| c.chunk_id == result.chunk_id).map(|hit| neighbourhood(chunks, hit, expansion))
+            });
+            let Some(range) = hit else {
+                expanded.push((result, None));
+                continue;
+            };
+            let better = expanded.iter_mut().find(|(kept, kept_range)| {
+                kept.kb_id == result.kb_id && kept.document_id == result.document_id
+                    && kept_range.as_ref().is_some_and(|r| r.start <= range.end && range.start <= r.end)
+            });
+            match better {
+                Some((_, Some(kept_range))) => *kept_range = kept_range.start.min(range.start)..kept_range.end.max(range.end),
+                _ => expanded.push((result, Some(range))),
+            }
+        }
+
+        Ok(expanded.into_iter()
+            .map(|(mut result, range)| {
+                if let Some(range) = range {
+                    let chunks = &documents[&(result.kb_id.clone(), result.document_id.clone())][range];
+                    let texts: Vec<&str> = chunks.iter().map(|c| c.content.as_str()).collect();
+                    result.content = join_chunks(&texts, DEFAULT_CHUNK_OVERLAP).0;
+                    let chunk_ids: Vec<&str> = chunks.iter().map(|c| c.chunk_id.as_str()).collect();
+                    if let Some(metadata) = result.metadata.as_object_mut() {
+                        metadata.insert("expanded_chunk_ids".to_string(), serde_json::json!(chunk_ids));
+                    } else {
+                        result.metadata = serde_json::json!({ "expanded_chunk_ids": chunk_ids });
+                    }
+                }
+                result
+            })
+            .collect())
+    }
+
+    async fn get_document_content(
+        &self,
+        kb_id: &str,
+        doc_id: &str,
+        range: Option<(usize, usize)>,
+    ) -> Result<DocumentContent, KbError> {
+        let kb_version = self.get_kb_state(kb_id)?.version;
+        let fingerprint = self.get_fingerprint(kb_id, doc_id).await?;
+        // The version searches read, so content matches the hits it is opened from
+        let (version, index_id) = match self.resolve_version(kb_id, None).await? {
+            Some(version) => (version.version, version.collection_id()),
+            None => (kb_version, kb_id.to_string()),
+        };
+        let mut chunks: Vec<VectorDocument> = match self.vector_service.export_chunks(&index_id).await {
+            Ok(chunks) => chunks.into_iter().filter(|chunk| chunk.document_id == doc_id).collect(),
+            Err(VectorDbError::CollectionNotFound(_)) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+        if chunks.is_empty() && fingerprint.chunk_count > 0 {
+            return Err(KbError::DocumentNotFound(doc_id.to_string()));
+        }
+        chunks.sort_by_key(|chunk| chunk.metadata.get("chunk_index").and_then(|i| i.as_u64()).unwrap_or(u64::MAX));
+
+        let title = chunks.first()
+            .and_then(|chunk| chunk.metadata.get("title"))
+            .and_then(|title| title.as_str())
+            .map(str::to_string)
+            .unwrap_or_else(|| document_title(&fingerprint.source_path));
+        let citation = |anchor: Option<String>, time_range: Option<TimeRange>| CitationInfo {
+            title: title.clone(),
+            source_path: fingerprint.source_path.clone(),
+            license: None,
+            version: Some(version.to_string()),
+            anchor,
+            page_number: None,
+            time_range,
+        };
+
+        let texts: Vec<&str> = chunks.iter().map(|chunk| chunk.content.as_str()).collect();
+        let (content, offsets) = join_chunks(&texts, DEFAULT_CHUNK_OVERLAP);
+        let length = content.chars().count();
+        let (start, end) = match range {
+            Some((start, end)) if start > end => {
+                return Err(KbError::ValidationError(format!("Invalid range: {}..{}", start, end)));
+            }
+            Some((start, end)) => (start.min(length), end.min(length)),
+            None => (0, length),
+        };
+
+        let document_chunks = chunks.iter()
+            .zip(offsets)
+            .filter(|(_, (chunk_start, chunk_end))| *chunk_start < end && *chunk_end > start)
+            .map(|(chunk, (chunk_start, chunk_end))| DocumentChunk {
+                chunk_id: chunk.chunk_id.clone(),
+                start: chunk_start,
+                end: chunk_end,
+                citation: citation(Some(format!("chunk_{}", chunk.chunk_id)), TimeRange::from_metadata(&chunk.metadata)),
+            })
+            .collect();
+
+        Ok(DocumentContent {
+            kb_id: kb_id.to_string(),
+            document: DocumentInfo {
+                id: doc_id.to_string(),
+                title: title.clone(),
+                source_path: fingerprint.source_path.clone(),
+                license_info: None,
+                version,
+                chunk_count: fingerprint.chunk_count as i32,
+                size_bytes: content.len() as i64,
+            },
+            content: content.chars().skip(start).take(end - start).collect(),
+            citation: citation(None, None),
+            chunks: document_chunks,
+        })
     }
 
     async fn resolve_citations(
         &self,
         chunk_ids: Vec<String>,
     ) -> Result<Vec<CitationInfo>, KbError> {
-        let mut citations = Vec::new();
-
-        for chunk_id in chunk_ids {
-            // MVP: Simple citation resolution
-            citations.push(CitationInfo {
-                title: format!("Document for chunk {}", chunk_id),
-                source_path: format!("/chunks/{}", chunk_id),
-                license: Some("MIT".to_string()),
-                version: Some("1".to_string()),
-                anchor: Some(format!("chunk_{}", chunk_id)),
-                page_number: None,
-            });
+        let wanted: HashSet<&str> = chunk_ids.iter().map(String::as_str).collect();
+        let mut kb_ids: Vec<String> = self.state_manager.read_state().knowledge_bases.keys().cloned().collect();
+        kb_ids.sort();
+
+        // Chunks are cited from the index searches read, like their search hits
+        let mut citations: HashMap<String, CitationInfo> = HashMap::new();
+        for kb_id in kb_ids {
+            if citations.len() == wanted.len() {
+                break;
+            }
+            let index_id = self.read_index(&kb_id).await?;
+            let chunks = match self.vector_service.export_chunks(&index_id).await {
+                Ok(chunks) => chunks,
+                Err(VectorDbError::CollectionNotFound(_)) => continue,
+                Err(e) => return Err(e.into()),
+            };
+            let version = self.index_version(&kb_id, &index_id).await?;
+            let mut fingerprints: HashMap<String, Option<DocumentFingerprint>> = HashMap::new();
+            for chunk in chunks.iter().filter(|c| wanted.contains(c.chunk_id.as_str())) {
+                if !fingerprints.contains_key(&chunk.document_id) {
+                    let fingerprint = self.find_fingerprint(&kb_id, &chunk.document_id).await?;
+                    fingerprints.insert(chunk.document_id.clone(), fingerprint);
+                }
+                let citation = chunk_citation(&chunk.chunk_id, &chunk.metadata, fingerprints[&chunk.document_id].as_ref(), version);
+                citations.entry(chunk.chunk_id.clone()).or_insert(citation);
+            }
         }
 
-        Ok(citations)
+        Ok(chunk_ids.iter().filter_map(|id| citations.get(id).cloned()).collect())
     }
 
     async fn get_stats(
@@ -416,34 +1599,37 @@ impl KbService for KbServiceImpl {
         collection: Option<String>,
         version: Option<i32>,
     ) -> Result<KbStats, KbError> {
-        match collection {
-            Some(collection_name) => {
-                let kb_state = self.get_kb_state(&collection_name)?;
-                Ok(KbStats {
-                    collection_name: Some(collection_name),
-                    version: version.unwrap_or(kb_state.version),
-                    document_count: 100, // TODO: Query from SQL
-                    chunk_count: 1000,   // TODO: Query from SQL
-                    size_bytes: 1024000, // TODO: Query from SQL
-                    health_score: kb_state.health_score,
-                    embedder_version: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
-                    last_updated: chrono::Utc::now(),
-                })
-            }
-            None => {
-                // Global stats
-                Ok(KbStats {
-                    collection_name: None,
-                    version: 1,
-                    document_count: 500,
-                    chunk_count: 5000,
-                    size_bytes: 5120000,
-                    health_score: 0.95,
-                    embedder_version: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
-                    last_updated: chrono::Utc::now(),
-                })
+        if let Some(collection_name) = collection {
+            return self.collection_stats(&collection_name, version).await;
+        }
+
+        // Global stats: every KB's searched version; a KB that can't be measured is left out
+        let kb_ids: Vec<String> = self.state_manager.read_state().knowledge_bases.keys().cloned().collect();
+        let mut measured = Vec::with_capacity(kb_ids.len());
+        for kb_id in &kb_ids {
+            match self.collection_stats(kb_id, None).await {
+                Ok(stats) => measured.push(stats),
+                Err(e) => tracing::warn!("Leaving KB {} out of the stats: {}", kb_id, e),
             }
         }
+        let embedders: BTreeSet<&str> = measured.iter().map(|stats| stats.embedder_version.as_str()).collect();
+        Ok(KbStats {
+            collection_name: None,
+            version: 1,
+            document_count: measured.iter().map(|stats| stats.document_count).sum(),
+            chunk_count: measured.iter().map(|stats| stats.chunk_count).sum(),
+            vector_count: measured.iter().map(|stats| stats.vector_count).sum(),
+            dimension: None,
+            size_bytes: measured.iter().map(|stats| stats.size_bytes).sum(),
+            health_score: if measured.is_empty() {
+                1.0
+            } else {
+                measured.iter().map(|stats| stats.health_score).sum::<f64>() / measured.len() as f64
+            },
+            embedder_version: embedders.into_iter().collect::<Vec<_>>().join(", "),
+            last_updated: measured.iter().map(|stats| stats.last_updated).max().unwrap_or_else(Utc::now),
+            generations: Vec::new(),
+        })
     }
 
     async fn list_collections(
@@ -454,14 +1640,16 @@ impl KbService for KbServiceImpl {
 
         let mut kbs = Vec::new();
         for (id, kb) in &state.knowledge_bases {
+            // Pinned in the active workspace, reported like collection_stats does
+            let pinned = state.pinned_version(None, id);
             kbs.push(KbInfo {
                 id: id.clone(),
                 name: kb.name.clone(),
-                version: kb.version,
+                version: pinned.unwrap_or(kb.version),
                 status: format!("{:?}", kb.status),
-                description: None, // TODO: Add description field to state
+                description: kb.metadata.get("description").and_then(|d| d.as_str()).map(str::to_string),
                 health_score: kb.health_score,
-                pinned: false, // TODO: Add pinned_version field to state
+                pinned: pinned.is_some(),
                 flows: Vec::new(), // TODO: Get associated flows
             });
         }
@@ -469,6 +1657,16 @@ impl KbService for KbServiceImpl {
         Ok(kbs)
     }
 
+    async fn list_documents(&self, kb_id: &str) -> Result<Vec<DocumentInfo>, KbError> {
+        let version = self.get_kb_state(kb_id)?.version;
+        let mut documents: Vec<DocumentInfo> = self.list_fingerprints(kb_id).await?
+            .into_iter()
+            .map(|fp| document_info(fp, version))
+            .collect();
+        documents.sort_by(|a, b| a.source_path.cmp(&b.source_path));
+        Ok(documents)
+    }
+
     async fn create_collection(
         &self,
         name: &str,
@@ -476,35 +1674,939 @@ impl KbService for KbServiceImpl {
     ) -> Result<String, KbError> {
         tracing::info!("Creating collection: {} with config: {:?}", name, config);
 
-        // MVP: Basic collection creation
+        if name.trim().is_empty() {
+            return Err(KbError::ValidationError("Collection name cannot be empty".to_string()));
+        }
         let kb_id = format!("kb_{}", uuid::Uuid::new_v4().to_string().replace('-', "")[..8].to_lowercase());
+        let kb = KnowledgeBaseState {
+            id: kb_id.clone(),
+            name: name.to_string(),
+            version: 1,
+            status: crate::state::KnowledgeBaseStatus::Active,
+            embedder_model: config.embedder_model.clone(),
+            health_score: 1.0,
+            document_count: 0,
+            chunk_count: 0,
+            last_updated: Utc::now(),
+            metadata: serde_json::json!({ "description": config.description }),
+        };
+
+        // Persist first so the KB survives restarts (and headless tools see it)
+        let row = KnowledgeBaseRow::from_state(&kb, &config)?;
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::insert_into(knowledge_bases::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        // TODO: Create vector collection
+        self.state_manager
+            .mutate(StateDelta::KnowledgeBaseAdd { kb })
+            .map_err(KbError::StateError)?;
+
+        Ok(kb_id)
+    }
+
+    async fn delete_collection(&self, kb_id: &str) -> Result<(), KbError> {
+        self.get_kb_state(kb_id)?;
+        let versions = self.list_versions(kb_id).await?;
+
+        // Index data first: if this fails the KB is still listed and the delete can be retried
+        let mut index_ids: Vec<String> = versions.iter().map(KbVersion::collection_id).collect();
+        index_ids.push(kb_id.to_string());
+        index_ids.push(summary_index_id(kb_id));
+        for index_id in &index_ids {
+            self.vector_service.delete_collection(index_id).await?;
+        }
+        if self.storage_service.blob_store_enabled() {
+            for version in &versions {
+                self.storage_service.delete_manifest(&version_blob_manifest(kb_id, version.version))?;
+            }
+        }
+        self.graph_service.remove_kb(kb_id).await?;
+
+        let id = kb_id.to_string();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::delete(document_fingerprints::table.filter(document_fingerprints::kb_id.eq(&id)))
+                .execute(conn)?;
+            diesel::delete(index_outbox::table.filter(index_outbox::kb_id.eq(&id)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+        self.sql_service.remove_kb_database(kb_id).await?;
+        let id = kb_id.to_string();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::delete(kb_versions::table.filter(kb_versions::kb_id.eq(&id)))
+                .execute(conn)?;
+            diesel::delete(knowledge_bases::table.filter(knowledge_bases::id.eq(&id)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        self.state_manager
+            .mutate(StateDelta::KnowledgeBaseRemove { id: kb_id.to_string() })
+            .map_err(KbError::StateError)?;
+        self.invalidate_cache(kb_id);
+        tracing::info!("Deleted KB {} ({} versions)", kb_id, versions.len());
+        Ok(())
+    }
+
+    async fn health_check(&self) -> Result<HealthStatus, KbError> {
+        // Check services health
+        let sql_health = self.sql_service.health_check().await
+            .map_err(|e| KbError::StateError(format!("SQL health check failed: {}", e)))?;
+
+        let vector_health = self.vector_service.health_check().await
+            .map_err(|e| KbError::StateError(format!("Vector health check failed: {}", e)))?;
+
+        // Check health based on metrics
+        let is_sql_healthy = sql_health.app_db_size > 0;
+        let is_vector_healthy = matches!(vector_health, VectorHealthStatus::Healthy);
+
+        match (is_sql_healthy, is_vector_healthy) {
+            (true, true) => Ok(HealthStatus::Healthy),
+            _ => Ok(HealthStatus::Degraded),
+        }
+    }
+
+    async fn hybrid_search_version(
+        &self,
+        collection: &str,
+        version: i32,
+        query: &str,
+        top_k: usize,
+        filters: Option<HashMap<String, serde_json::Value>>,
+    ) -> Result<Vec<SearchResult>, KbError> {
+        self.validate_query(query, top_k)?;
+
+        let kb_version = self.get_version(collection, version).await?;
+        tracing::info!("Version-scoped search for {} v{}: {}", collection, version, query);
+
+        self.search_index(&kb_version.collection_id(), query, top_k, filters).await
+    }
+
+    async fn create_version_from_run(
+        &self,
+        kb_id: &str,
+        spec: &PipelineSpec,
+        run_id: &str,
+        output: &PipelineRunOutput,
+    ) -> Result<Option<KbVersion>, KbError> {
+        if !output.is_success() {
+            tracing::info!("Pipeline run {} failed, no version created for KB {}", run_id, kb_id);
+            return Ok(None);
+        }
+
+        let generation_id = self.vector_service.create_generation(kb_id).await?;
+
+        // The version gets its own index, chunked the way documents added to the KB are
+        let now = Utc::now().timestamp();
+        let mut documents = Vec::with_capacity(output.data.documents.len());
+        let mut vectors = Vec::new();
+        for doc in &output.data.documents {
+            let chunks = document_vectors(kb_id, doc, chunk_text(&doc.content, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP), None, now);
+            documents.push(VersionDocument {
+                id: doc.id.clone(),
+                title: doc.title.clone(),
+                source_path: doc.source_path.clone(),
+                content_hash: DocumentFingerprint::hash_for(doc),
+                chunk_count: chunks.len(),
+            });
+            vectors.extend(chunks);
+        }
+
+        let stats = KbVersionStats {
+            document_count: documents.len(),
+            chunk_count: vectors.len(),
+            size_bytes: vectors.iter().map(|v| v.content.len() as u64).sum(),
+        };
+
+        let manifest = KbVersionManifest {
+            pipeline_id: Some(spec.id.clone()),
+            pipeline_run_id: Some(run_id.to_string()),
+            documents,
+            metadata: serde_json::to_value(&output.report)?,
+        };
+
+        let id = kb_id.to_string();
+        let latest: Option<i32> = self.sql_service.with_app_transaction(move |conn| {
+            Ok(kb_versions::table
+                .filter(kb_versions::kb_id.eq(id))
+                .select(diesel::dsl::max(kb_versions::version))
+                .first(conn)?)
+        }).await?;
+
+        let kb_version = KbVersion {
+            id: format!("kbv_{}", uuid::Uuid::new_v4().simple()),
+            kb_id: kb_id.to_string(),
+            version: latest.unwrap_or(0) + 1,
+            generation_id,
+            pipeline_run_id: Some(run_id.to_string()),
+            status: KbVersionStatus::Ready,
+            manifest,
+            stats,
+            created_at: Utc::now(),
+            activated_at: None,
+        };
+
+        let collection_id = kb_version.collection_id();
+        if let Err(e) = self.vector_service.upsert_vectors(&collection_id, vectors).await {
+            if let Err(cleanup) = self.vector_service.delete_collection(&collection_id).await {
+                tracing::warn!("Failed to drop partial index {}: {}", collection_id, cleanup);
+            }
+            return Err(e.into());
+        }
+        self.vector_service.generation_manager().mark_generation_ready(kb_id, generation_id).await?;
+
+        let row = KbVersionRow::from_model(&kb_version)?;
+        self.sql_service.with_app_transaction(|conn| {
+            diesel::insert_into(kb_versions::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        if self.storage_service.blob_store_enabled() {
+            self.store_version_blobs(&kb_version, output)?;
+        }
+
+        self.invalidate_cache(kb_id);
+        tracing::info!("Created KB {} version {} (generation {})", kb_id, kb_version.version, generation_id);
+        Ok(Some(kb_version))
+    }
+
+    async fn list_versions(&self, kb_id: &str) -> Result<Vec<KbVersion>, KbError> {
+        let id = kb_id.to_string();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            Ok(kb_versions::table
+                .filter(kb_versions::kb_id.eq(id))
+                .order(kb_versions::version.desc())
+                .select(KbVersionRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(KbVersionRow::into_model).collect()
+    }
+
+    async fn activate_version(&self, kb_id: &str, version: i32) -> Result<KbVersion, KbError> {
+        let mut kb_version = self.get_version(kb_id, version).await?;
+
+        // Generations are tracked in memory, so only promote ones this session knows about
+        let known = self.vector_service.generation_manager()
+            .get_generations(kb_id).await
+            .iter()
+            .any(|g| g.id == kb_version.generation_id);
+        if known {
+            self.vector_service.promote_generation(kb_id, kb_version.generation_id).await?;
+        }
+
+        let activated_at = Utc::now();
+        let id = kb_id.to_string();
+        let version_id = kb_version.id.clone();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::update(kb_versions::table
+                .filter(kb_versions::kb_id.eq(&id))
+                .filter(kb_versions::status.eq(KbVersionStatus::Active.as_str())))
+                .set(kb_versions::status.eq(KbVersionStatus::Archived.as_str()))
+                .execute(conn)?;
+            diesel::update(kb_versions::table.filter(kb_versions::id.eq(&version_id)))
+                .set((
+                    kb_versions::status.eq(KbVersionStatus::Active.as_str()),
+                    kb_versions::activated_at.eq(Some(activated_at.naive_utc())),
+                ))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        // The KB's documents are now the version's; later edits apply to its index
+        let rows: Vec<DocumentFingerprintRow> = kb_version.manifest.documents.iter().map(|doc| {
+            DocumentFingerprintRow::from_model(&DocumentFingerprint {
+                kb_id: kb_id.to_string(),
+                source_path: doc.source_path.clone(),
+                document_id: doc.id.clone(),
+                content_hash: doc.content_hash.clone(),
+                chunk_count: doc.chunk_count,
+                indexed_at: kb_version.created_at,
+            })
+        }).collect();
+        let id = kb_id.to_string();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::delete(document_fingerprints::table.filter(document_fingerprints::kb_id.eq(id)))
+                .execute(conn)?;
+            for row in &rows {
+                diesel::replace_into(document_fingerprints::table)
+                    .values(row)
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).await?;
+        self.refresh_document_counts(kb_id).await?;
+
+        self.update_kb_state(kb_id, |kb| kb.version = version)?;
+        self.invalidate_cache(kb_id);
+
+        kb_version.status = KbVersionStatus::Active;
+        kb_version.activated_at = Some(activated_at);
+        tracing::info!("Activated KB {} version {}", kb_id, version);
+        Ok(kb_version)
+    }
+
+    async fn delete_version(&self, kb_id: &str, version: i32) -> Result<(), KbError> {
+        let kb_version = self.get_version(kb_id, version).await?;
+        if kb_version.status == KbVersionStatus::Active {
+            return Err(KbError::ValidationError(
+                format!("Cannot delete active version {} of KB {}", version, kb_id)
+            ));
+        }
+
+        let version_id = kb_version.id.clone();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::delete(kb_versions::table.filter(kb_versions::id.eq(version_id)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        self.vector_service.delete_collection(&kb_version.collection_id()).await?;
+        if self.storage_service.blob_store_enabled() {
+            // Content shared with other versions stays; the rest goes at the next GC
+            self.storage_service.delete_manifest(&version_blob_manifest(kb_id, version))?;
+        }
+        self.invalidate_cache(kb_id);
+
+        tracing::info!("Deleted KB {} version {}", kb_id, version);
+        Ok(())
+    }
+
+    async fn diff_versions(&self, kb_id: &str, from_version: i32, to_version: i32) -> Result<KbVersionDiff, KbError> {
+        let from = self.get_version(kb_id, from_version).await?;
+        let to = self.get_version(kb_id, to_version).await?;
+
+        Ok(KbVersionDiff::between(&from, &to))
+    }
+
+    async fn plan_reindex(&self, kb_id: &str, documents: Vec<PipelineDocument>) -> Result<ReindexPlan, KbError> {
+        let fingerprints = self.list_fingerprints(kb_id).await?;
+        let plan = ReindexPlan::build(kb_id, documents, fingerprints);
+
+        tracing::info!(
+            "Reindex plan for KB {}: {} changed, {} unchanged, {} removed",
+            kb_id, plan.changed.len(), plan.unchanged.len(), plan.removed.len()
+        );
+        Ok(plan)
+    }
+
+    async fn record_fingerprints(&self, kb_id: &str, plan: &ReindexPlan, data: &StepData) -> Result<(), KbError> {
+        let indexed_at = Utc::now();
+        let rows: Vec<DocumentFingerprintRow> = data.documents.iter().map(|doc| {
+            DocumentFingerprintRow::from_model(&DocumentFingerprint {
+                kb_id: kb_id.to_string(),
+                source_path: doc.source_path.clone(),
+                document_id: doc.id.clone(),
+                content_hash: DocumentFingerprint::hash_for(doc),
+                chunk_count: data.chunks.iter().filter(|c| c.document_id == doc.id).count(),
+                indexed_at,
+            })
+        }).collect();
+
+        let id = kb_id.to_string();
+        let removed: Vec<String> = plan.removed.iter().map(|f| f.source_path.clone()).collect();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            for row in &rows {
+                diesel::replace_into(document_fingerprints::table)
+                    .values(row)
+                    .execute(conn)?;
+            }
+            diesel::delete(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(&id))
+                .filter(document_fingerprints::source_path.eq_any(&removed)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        Ok(())
+    }
+
+    async fn clear_fingerprints(&self, kb_id: &str) -> Result<(), KbError> {
+        let id = kb_id.to_string();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::delete(document_fingerprints::table.filter(document_fingerprints::kb_id.eq(id)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        Ok(())
+    }
+
+    async fn add_documents(&self, kb_id: &str, sources: Vec<String>) -> Result<Vec<DocumentInfo>, KbError> {
+        self.get_kb_state(kb_id)?;
+        if sources.is_empty() {
+            return Err(KbError::ValidationError("No document sources given".to_string()));
+        }
+
+        let mut documents = Vec::with_capacity(sources.len());
+        for source in &sources {
+            documents.push(self.load_named_source(source, None).await?);
+        }
+        self.ingest_documents(kb_id, &documents).await
+    }
+
+    async fn remove_document(&self, kb_id: &str, doc_id: &str) -> Result<(), KbError> {
+        let fingerprint = self.get_fingerprint(kb_id, doc_id).await?;
+        let entry = self.stage_write(kb_id, doc_id, OutboxOperation::Delete, 0).await?;
+
+        self.vector_service.delete_document(&self.maintained_index(kb_id).await?, doc_id).await?;
+        // Summary tree, when a pipeline with a Summarize step built one
+        match self.vector_service.delete_document(&summary_index_id(kb_id), doc_id).await {
+            Ok(()) | Err(VectorDbError::CollectionNotFound(_)) => {}
+            Err(e) => return Err(e.into()),
+        }
+        self.graph_service.remove_document(kb_id, doc_id).await?;
+
+        let id = kb_id.to_string();
+        self.sql_service.with_kb_transaction(kb_id, move |conn| {
+            diesel::delete(document_fingerprints::table
+                .filter(document_fingerprints::kb_id.eq(id))
+                .filter(document_fingerprints::source_path.eq(fingerprint.source_path)))
+                .execute(conn)?;
+            commit_outbox_entry(conn, &entry)
+        }).await?;
+
+        self.refresh_document_counts(kb_id).await?;
+        self.invalidate_cache(kb_id);
+        tracing::info!("Removed document {} from KB {}", doc_id, kb_id);
+        Ok(())
+    }
+
+    async fn update_document(&self, kb_id: &str, doc_id: &str) -> Result<DocumentInfo, KbError> {
+        let fingerprint = self.get_fingerprint(kb_id, doc_id).await?;
+        let doc = self.load_source(&self.kb_sandbox(kb_id), &fingerprint.source_path, Some(doc_id)).await?;
+
+        if doc.content_hash == fingerprint.content_hash {
+            tracing::debug!("Document {} unchanged, skipping reindex", doc_id);
+            return Ok(DocumentInfo {
+                id: doc.id,
+                title: doc.title,
+                source_path: doc.source_path,
+                license_info: doc.license_info,
+                version: self.get_kb_state(kb_id)?.version,
+                chunk_count: fingerprint.chunk_count as i32,
+                size_bytes: doc.content.len() as i64,
+            });
+        }
+
+        self.check_ingest_quota(kb_id, std::slice::from_ref(&doc)).await?;
+        // Drop old chunks first so a shorter document doesn't leave stale ones behind
+        let chunks = chunk_text(&doc.content, DEFAULT_CHUNK_SIZE, DEFAULT_CHUNK_OVERLAP);
+        let info = self.index_document(kb_id, &doc, chunks, true).await?;
+
+        self.refresh_document_counts(kb_id).await?;
+        self.invalidate_cache(kb_id);
+        tracing::info!("Updated document {} in KB {}", doc_id, kb_id);
+        Ok(info)
+    }
+
+    async fn check_consistency(&self, kb_id: &str, repair: bool) -> Result<ConsistencyReport, KbError> {
+        self.get_kb_state(kb_id)?;
+        let fingerprints = self.list_fingerprints(kb_id).await?;
+        let outbox = self.list_outbox(kb_id).await?;
+        let chunks = match self.vector_service.export_chunks(&self.read_index(kb_id).await?).await {
+            Ok(chunks) => chunks,
+            Err(VectorDbError::CollectionNotFound(_)) => Vec::new(),
+            Err(e) => return Err(e.into()),
+        };
+
+        // Generation of each document's last committed upsert
+        let mut committed: HashMap<&str, u64> = HashMap::new();
+        for entry in outbox.iter().filter(|e| e.status == OutboxStatus::Committed && e.operation == OutboxOperation::Upsert) {
+            let generation = committed.entry(entry.document_id.as_str()).or_default();
+            *generation = (*generation).max(entry.generation);
+        }
+
+        let mut chunk_generations: BTreeMap<&str, Vec<Option<u64>>> = BTreeMap::new();
+        for chunk in &chunks {
+            chunk_generations.entry(chunk.document_id.as_str())
+                .or_default()
+                .push(chunk.metadata.get("generation").and_then(|g| g.as_u64()));
+        }
+        let fingerprinted: HashSet<&str> = fingerprints.iter().map(|f| f.document_id.as_str()).collect();
+
+        let mut report = ConsistencyReport {
+            kb_id: kb_id.to_string(),
+            staged_writes: outbox.iter().filter(|e| e.status == OutboxStatus::Staged).cloned().collect(),
+            ..Default::default()
+        };
+        for (doc_id, generations) in &chunk_generations {
+            if !fingerprinted.contains(doc_id) {
+                report.orphan_documents.push(doc_id.to_string());
+            } else if let Some(generation) = committed.get(doc_id) {
+                // Chunks without a generation predate the outbox (or came from a pack import)
+                if generations.iter().flatten().any(|g| g != generation) {
+                    report.stale_documents.push(doc_id.to_string());
+                }
+            }
+        }
+        report.missing_documents = fingerprints.iter()
+            .filter(|f| f.chunk_count > 0 && !chunk_generations.contains_key(f.document_id.as_str()))
+            .map(|f| f.document_id.clone())
+            .collect();
+
+        if !report.is_consistent() {
+            tracing::warn!(
+                "KB {} is inconsistent: {} staged writes, {} orphan, {} missing, {} stale documents",
+                kb_id, report.staged_writes.len(), report.orphan_documents.len(),
+                report.missing_documents.len(), report.stale_documents.len()
+            );
+            if repair {
+                self.repair_index(kb_id, &report).await?;
+                report.repaired = true;
+                tracing::info!("Repaired KB {}", kb_id);
+            }
+        }
+        Ok(report)
+    }
+
+    async fn export_kb(&self, kb_id: &str) -> Result<Vec<u8>, KbError> {
+        let kb = self.state_manager.read_state().knowledge_bases.get(kb_id).cloned()
+            .ok_or_else(|| KbError::KbNotFound(kb_id.to_string()))?;
+
+        let versions = self.list_versions(kb_id).await?;
+        let fingerprints = self.list_fingerprints(kb_id).await?;
+        let collection = match self.get_active_version(kb_id).await? {
+            Some(version) => version.collection_id(),
+            None => kb_id.to_string(),
+        };
+        let chunks = match self.vector_service.export_chunks(&collection).await {
+            Ok(chunks) => chunks,
+            Err(VectorDbError::CollectionNotFound(_)) => Vec::new(),  // Nothing indexed yet
+            Err(e) => return Err(e.into()),
+        };
+
+        let mut manifest = PackManifest::new("kb", kb_id, &kb.name, &kb.version.to_string());
+        manifest.metadata = serde_json::json!({
+            "embedder_model": kb.embedder_model,
+            "document_count": fingerprints.len(),
+            "chunk_count": chunks.len(),
+            "versions": versions.len(),
+        });
+
+        let files = BTreeMap::from([
+            ("kb.json".to_string(), serde_json::to_string(&kb)?),
+            ("versions.json".to_string(), serde_json::to_string(&versions)?),
+            ("fingerprints.json".to_string(), serde_json::to_string(&fingerprints)?),
+            ("chunks.json".to_string(), serde_json::to_string(&chunks)?),
+        ]);
+
+        let (manifest, bytes) = self.storage_service.write_pack(manifest, files)?;
+        tracing::info!("Exported KB {} ({} chunks, checksum {})", kb_id, chunks.len(), manifest.checksum);
+        Ok(bytes)
+    }
+
+    async fn import_kb(&self, data: &[u8], allow_unsigned: bool) -> Result<String, KbError> {
+        let (manifest, files) = self.storage_service.read_pack_with(data, allow_unsigned)?;
+        if manifest.pack_type != "kb" {
+            return Err(KbError::ValidationError(format!("Expected a KB pack, got: {}", manifest.pack_type)));
+        }
+
+        let kb: KnowledgeBaseState = pack_file(&files, "kb.json")?;
+        let versions: Vec<KbVersion> = pack_file(&files, "versions.json")?;
+        let fingerprints: Vec<DocumentFingerprint> = pack_file(&files, "fingerprints.json")?;
+        let chunks: Vec<VectorDocument> = pack_file(&files, "chunks.json")?;
+
+        // The id names index directories and databases, and every row must
+        // belong to it, so a pack can't reach into another KB
+        validate_kb_id(&kb.id)?;
+        if let Some(v) = versions.iter().find(|v| v.kb_id != kb.id) {
+            return Err(KbError::ValidationError(format!("Pack version {} belongs to KB {}, not {}", v.version, v.kb_id, kb.id)));
+        }
+        if let Some(f) = fingerprints.iter().find(|f| f.kb_id != kb.id) {
+            return Err(KbError::ValidationError(format!("Pack fingerprint {} belongs to KB {}, not {}", f.source_path, f.kb_id, kb.id)));
+        }
+
+        if self.state_manager.read_state().knowledge_bases.contains_key(&kb.id) {
+            return Err(KbError::ValidationError(format!("KB already exists: {}", kb.id)));
+        }
+
+        // The KB row makes the import survive restarts, like a created KB
+        let kb_row = KnowledgeBaseRow::from_state(&kb, &KbCreateConfig {
+            description: kb.metadata.get("description").and_then(|v| v.as_str()).map(str::to_string),
+            embedder_model: kb.embedder_model.clone(),
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            chunk_overlap: DEFAULT_CHUNK_OVERLAP,
+        })?;
+        let version_rows = versions.iter().map(KbVersionRow::from_model).collect::<Result<Vec<_>, _>>()?;
+        let fingerprint_rows: Vec<DocumentFingerprintRow> = fingerprints.iter().map(DocumentFingerprintRow::from_model).collect();
+        // Fingerprints go to the KB's content database (a separate file in split mode)
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::insert_into(knowledge_bases::table)
+                .values(&kb_row)
+                .execute(conn)?;
+            diesel::insert_into(kb_versions::table)
+                .values(&version_rows)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+        self.sql_service.with_kb_transaction(&kb.id, move |conn| {
+            diesel::replace_into(document_fingerprints::table)
+                .values(&fingerprint_rows)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        // Search resolves to the active version's collection, so restore chunks there
+        let collection = versions.iter()
+            .find(|v| v.status == KbVersionStatus::Active)
+            .map(KbVersion::collection_id)
+            .unwrap_or_else(|| kb.id.clone());
+        self.vector_service.import_chunks(&collection, chunks).await?;
+
+        let kb_id = kb.id.clone();
+        self.state_manager
+            .mutate(StateDelta::KnowledgeBaseAdd { kb })
+            .map_err(KbError::StateError)?;
+
+        tracing::info!("Imported KB {} from pack {} ({} versions)", kb_id, manifest.checksum, versions.len());
+        Ok(kb_id)
+    }
+
+    async fn resolve_version(
+        &self,
+        collection: &str,
+        workspace_id: Option<&str>,
+    ) -> Result<Option<KbVersion>, KbError> {
+        let pinned = self.state_manager.read_state().pinned_version(workspace_id, collection);
+
+        match pinned {
+            Some(version) => {
+                tracing::debug!("Using pinned version {} of KB {}", version, collection);
+                self.get_version(collection, version).await.map(Some)
+            }
+            None => self.get_active_version(collection).await,
+        }
+    }
+}
+
+/// Listing entry of an indexed document, titled by its file name
+fn document_info(fingerprint: DocumentFingerprint, version: i32) -> DocumentInfo {
+    DocumentInfo {
+        title: document_title(&fingerprint.source_path),
+        id: fingerprint.document_id,
+        source_path: fingerprint.source_path,
+        license_info: None,
+        version,
+        chunk_count: fingerprint.chunk_count as i32,
+        size_bytes: 0, // MVP: fingerprints don't record size
+    }
+}
+
+/// File name of a source path, or the whole path when it has none
+fn document_title(source_path: &str) -> String {
+    std::path::Path::new(source_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_else(|| source_path.to_string())
+}
+
+/// Citation of a chunk: source from the document's fingerprint (the chunk
+/// metadata without one), title from the parser when it recorded one
+fn chunk_citation(chunk_id: &str, metadata: &serde_json::Value, fingerprint: Option<&DocumentFingerprint>, version: i32) -> CitationInfo {
+    let text = |key: &str| metadata.get(key).and_then(|v| v.as_str()).filter(|v| !v.is_empty()).map(str::to_string);
+    let source_path = fingerprint.map(|fp| fp.source_path.clone()).or_else(|| text("source_path")).unwrap_or_default();
+    CitationInfo {
+        title: text("title").unwrap_or_else(|| document_title(&source_path)),
+        source_path,
+        license: None,
+        version: Some(version.to_string()),
+        anchor: Some(format!("chunk_{}", chunk_id)),
+        page_number: None,
+        time_range: TimeRange::from_metadata(metadata),
+    }
+}
+
+/// Mark a staged write committed and drop older entries for the same document
+fn commit_outbox_entry(conn: &mut SqliteConnection, entry: &IndexOutboxEntry) -> Result<(), SqlError> {
+    diesel::update(index_outbox::table.filter(index_outbox::id.eq(&entry.id)))
+        .set((
+            index_outbox::status.eq(OutboxStatus::Committed.as_str()),
+            index_outbox::committed_at.eq(Some(Utc::now().naive_utc())),
+        ))
+        .execute(conn)?;
+    diesel::delete(index_outbox::table
+        .filter(index_outbox::kb_id.eq(&entry.kb_id))
+        .filter(index_outbox::document_id.eq(&entry.document_id))
+        .filter(index_outbox::generation.lt(entry.generation as i64)))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// URL sources, which only a pipeline fetch step can read
+fn is_remote_source(source: &str) -> bool {
+    source.starts_with("http://") || source.starts_with("https://")
+}
+
+/// Cache key for a search; filters are sorted so equal requests share a key
+/// A local source whose file no longer exists (`#fragment` sub-documents
+/// such as mbox messages and table rows go with their file)
+fn is_deleted_source(source: &str) -> bool {
+    if is_remote_source(source) {
+        return false;
+    }
+    let path = source.strip_prefix("file://").unwrap_or(source);
+    !std::path::Path::new(path.split('#').next().unwrap_or(path)).exists()
+}
+
+fn search_cache_key(index_id: &str, query: &str, top_k: usize, filters: Option<&HashMap<String, serde_json::Value>>) -> String {
+    let filters: Option<BTreeMap<&String, &serde_json::Value>> = filters.map(|f| f.iter().collect());
+    let request = serde_json::json!({ "query": query, "top_k": top_k, "filters": filters });
+    format!("search:{}:{}", index_id, sha256_hex(request.to_string().as_bytes()))
+}
+
+/// Semantic cache scope of a search: everything in the request but the query
+fn semantic_cache_scope(index_id: &str, top_k: usize, filters: Option<&HashMap<String, serde_json::Value>>) -> String {
+    let filters: Option<BTreeMap<&String, &serde_json::Value>> = filters.map(|f| f.iter().collect());
+    let request = serde_json::json!({ "top_k": top_k, "filters": filters });
+    format!("search:{}:{}", index_id, sha256_hex(request.to_string().as_bytes()))
+}
+
+/// Blob manifest holding a KB version's documents
+fn version_blob_manifest(kb_id: &str, version: i32) -> String {
+    format!("kb/{}/v{}", kb_id, version)
+}
+
+/// Blob manifest holding a pipeline run's artifacts
+fn run_blob_manifest(run_id: &str) -> String {
+    format!("pipeline_runs/{}", run_id)
+}
+
+/// Deserialize a named file from a KB pack
+/// KB ids become directory and database names, so they follow the
+/// `kb_<alphanumeric>` shape `create_collection` gives them
+fn validate_kb_id(kb_id: &str) -> Result<(), KbError> {
+    let valid = kb_id.len() <= 64
+        && kb_id.strip_prefix("kb_").is_some_and(|rest| {
+            !rest.is_empty() && rest.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(KbError::ValidationError(format!("Invalid KB id: {}", kb_id)))
+    }
+}
+
+fn pack_file<T: serde::de::DeserializeOwned>(files: &BTreeMap<String, String>, name: &str) -> Result<T, KbError> {
+    let content = files.get(name)
+        .ok_or_else(|| KbError::ValidationError(format!("KB pack is missing {}", name)))?;
+    Ok(serde_json::from_str(content)?)
+}
+
+/// A document's chunks as stored in the vector store, with the metadata
+/// search, citations and filters read back. `generation` is the outbox
+/// write the chunks belong to (None for version indexes, built outside it).
+fn document_vectors(kb_id: &str, doc: &PipelineDocument, chunks: Vec<String>, generation: Option<u64>, timestamp: i64) -> Vec<VectorSchema> {
+
+    // Annotators an annotate step chose for this document, and each chunk's byte offset for them
+    let annotators = doc.metadata.get("annotate").and_then(|config| match AnnotatorSet::from_value(config) {
+        Ok(annotators) => Some(annotators),
+        Err(e) => {
+            tracing::warn!("Ignoring annotators of document {}: {}", doc.id, e);
+            None
+        }
+    });
+    let chunk_offsets: Vec<usize> = match &annotators {
+        Some(_) => doc.content.char_indices().map(|(i, _)| i).step_by(DEFAULT_CHUNK_SIZE - DEFAULT_CHUNK_OVERLAP).collect(),
+        None => Vec::new(),
+    };
+    let lang = doc.metadata.get("lang").and_then(|l| l.as_str());
+
+    chunks
+        .into_iter()
+        .enumerate()
+        .map(|(index, content)| {
+            let mut metadata = serde_json::json!({
+                "title": doc.title,
+                "source_path": doc.source_path,
+                "chunk_index": index,
+            });
+            if let Some(generation) = generation {
+                metadata["generation"] = generation.into();
+            }
+            // Transcript chunks cite the part of the recording they cover
+            let start = index * (DEFAULT_CHUNK_SIZE - DEFAULT_CHUNK_OVERLAP);
+            if let Some(range) = transcript_time_range(&doc.metadata, start, start + content.chars().count()) {
+                metadata["start_ms"] = range.start_ms.into();
+                metadata["end_ms"] = range.end_ms.into();
+            }
+            // Language and embedding model chosen by the normalize and embed steps
+            for key in ["lang", "embedding_model"] {
+                if let Some(value) = doc.metadata.get(key) {
+                    metadata[key] = value.clone();
+                }
+            }
+            if let Some(annotators) = &annotators {
+                let target = AnnotationTarget { text: &content, document: Some(&doc.content), offset: chunk_offsets.get(index).copied(), lang };
+                for (key, value) in annotators.annotate(&target) {
+                    metadata[key] = value;
+                }
+            }
+            // Notebook chunks say which kind of cell they come from
+            if let Some(notebook) = doc.metadata.get("notebook") {
+                metadata["cell_type"] = notebook["cell_type"].clone();
+                metadata["execution_count"] = notebook["execution_count"].clone();
+            }
+            VectorSchema {
+                chunk_id: format!("{}_{}", doc.id, index),
+                document_id: doc.id.clone(),
+                kb_id: kb_id.to_string(),
+                content,
+                embedding: Vec::new(),  // MVP: filled in by the Embed step, BM25 works immediately
+                metadata,
+                created_at: timestamp,
+                updated_at: timestamp,
+            }
+        })
+        .collect()
+}
 
-        // TODO: Create in SQL database
-        // TODO: Create vector collection
-        // TODO: Update state
+/// Split text into overlapping windows of `chunk_size` characters
+fn chunk_text(content: &str, chunk_size: usize, overlap: usize) -> Vec<String> {
+    let chars: Vec<char> = content.chars().collect();
+    if chars.is_empty() {
+        return Vec::new();
+    }
 
-        Ok(kb_id)
+    let step = chunk_size.saturating_sub(overlap).max(1);
+    let mut chunks = Vec::new();
+    let mut start = 0;
+    loop {
+        let end = (start + chunk_size).min(chars.len());
+        chunks.push(chars[start..end].iter().collect());
+        if end == chars.len() {
+            break;
+        }
+        start += step;
     }
+    chunks
+}
 
-    async fn health_check(&self) -> Result<HealthStatus, KbError> {
-        // Check services health
-        let sql_health = self.sql_service.health_check().await
-            .map_err(|e| KbError::StateError(format!("SQL health check failed: {}", e)))?;
+/// Recording span of the transcript segments overlapping characters `start..end`
+/// (segments are listed in the document's `transcript` metadata by the parse step)
+fn transcript_time_range(metadata: &serde_json::Value, start: usize, end: usize) -> Option<TimeRange> {
+    metadata.get("transcript")?.get("segments")?.as_array()?
+        .iter()
+        .filter_map(|segment| {
+            let (segment_start, segment_end) = (segment.get("start")?.as_u64()? as usize, segment.get("end")?.as_u64()? as usize);
+            (segment_start < end && start < segment_end).then(|| TimeRange::from_metadata(segment)).flatten()
+        })
+        .reduce(|a, b| TimeRange { start_ms: a.start_ms.min(b.start_ms), end_ms: a.end_ms.max(b.end_ms) })
+}
 
-        let vector_health = self.vector_service.health_check().await
-            .map_err(|e| KbError::StateError(format!("Vector health check failed: {}", e)))?;
+/// Filter keys with a meaning of their own (see `apply_query_language`)
+const LANGUAGE_FILTERS: &[&str] = &["lang", "lang_boost"];
+
+/// Results whose chunk metadata matches every other filter: equal to the value,
+/// or containing it when the field is a list (annotate step fields); a list of
+/// values matches any of them
+fn apply_metadata_filters(mut results: Vec<SearchResult>, filters: &Option<HashMap<String, serde_json::Value>>) -> Vec<SearchResult> {
+    let Some(filters) = filters else {
+        return results;
+    };
+    let matches = |field: &serde_json::Value, wanted: &serde_json::Value| match field {
+        serde_json::Value::Array(values) => values.contains(wanted),
+        value => value == wanted,
+    };
+    results.retain(|result| {
+        filters.iter()
+            .filter(|(key, _)| !LANGUAGE_FILTERS.contains(&key.as_str()))
+            .all(|(key, wanted)| {
+                let field = result.metadata.get(key).unwrap_or(&serde_json::Value::Null);
+                match wanted {
+                    serde_json::Value::Array(options) => options.iter().any(|option| matches(field, option)),
+                    wanted => matches(field, wanted),
+                }
+            })
+    });
+    results
+}
 
-        // Check health based on metrics
-        let is_sql_healthy = sql_health.app_db_size > 0;
-        let is_vector_healthy = matches!(vector_health, VectorHealthStatus::Healthy);
+/// Results narrowed or re-ranked by language. The `lang` filter keeps results in
+/// that language (`"auto"`: the query's), plus results of unknown language;
+/// `lang_boost` raises the score of results in the query's language by that factor
+fn apply_query_language(mut results: Vec<SearchResult>, query: &str, filters: &Option<HashMap<String, serde_json::Value>>) -> Vec<SearchResult> {
+    let Some(filters) = filters else {
+        return results;
+    };
+    let query_lang = || detect_language(query);
+    let result_lang = |result: &SearchResult| result.metadata.get("lang").and_then(|l| l.as_str()).map(str::to_string);
+    if let Some(lang) = filters.get("lang").and_then(|l| l.as_str()) {
+        let lang = if lang == "auto" { query_lang() } else { Some(lang) };
+        if let Some(lang) = lang {
+            results.retain(|result| result_lang(result).is_none_or(|l| l == lang));
+        }
+    }
+    let boost = filters.get("lang_boost").and_then(|b| b.as_f64());
+    if let Some((boost, lang)) = boost.and_then(|boost| Some((boost, query_lang()?))) {
+        for result in &mut results {
+            if result_lang(result).as_deref() == Some(lang) {
+                result.score *= 1.0 + boost as f32;
+            }
+        }
+        results.sort_by(|a, b| b.score.partial_cmp(&a.score).unwrap_or(std::cmp::Ordering::Equal));
+    }
+    results
+}
 
-        match (is_sql_healthy, is_vector_healthy) {
-            (true, true) => Ok(HealthStatus::Healthy),
-            _ => Ok(HealthStatus::Degraded),
+/// Chunks to return for the hit at `hit` (indexes into the document's sorted chunks)
+fn neighbourhood(chunks: &[VectorDocument], hit: usize, expansion: ContextExpansion) -> std::ops::Range<usize> {
+    match expansion {
+        ContextExpansion::Adjacent { window } => hit.saturating_sub(window)..(hit + window + 1).min(chunks.len()),
+        ContextExpansion::Parent { max_chunks } => {
+            // Sections start at chunks holding a Markdown heading; without headings the document is the parent
+            let heading = |chunk: &VectorDocument| chunk.content.lines().any(is_heading);
+            let start = (0..=hit).rev().find(|&i| heading(&chunks[i])).unwrap_or(0);
+            let end = (hit + 1..chunks.len()).find(|&i| heading(&chunks[i])).unwrap_or(chunks.len());
+            // Long sections are cut to a window centred on the hit
+            let max_chunks = max_chunks.max(1);
+            if end - start <= max_chunks {
+                return start..end;
+            }
+            let first = hit.saturating_sub(max_chunks / 2).max(start);
+            let last = (first + max_chunks).min(end);
+            last - max_chunks..last
         }
     }
 }
 
+/// ATX Markdown heading (`# Title` to `###### Title`)
+fn is_heading(line: &str) -> bool {
+    let line = line.trim_start();
+    let level = line.chars().take_while(|c| *c == '#').count();
+    (1..=6).contains(&level) && line[level..].starts_with(' ')
+}
+
+/// Reassemble text split by `chunk_text`, dropping the overlap each chunk repeats
+/// from the previous one. Returns the text and each chunk's character range.
+fn join_chunks(chunks: &[&str], overlap: usize) -> (String, Vec<(usize, usize)>) {
+    let mut content = String::new();
+    let mut offsets = Vec::with_capacity(chunks.len());
+    let mut length = 0;
+    let mut previous: Option<&str> = None;
+    for chunk in chunks {
+        // Chunks that don't repeat the previous tail (e.g. split with another overlap) are appended whole
+        let repeated = previous.is_some_and(|previous| {
+            let chars: Vec<char> = previous.chars().collect();
+            chars.len() >= overlap && chunk.starts_with(&chars[chars.len() - overlap..].iter().collect::<String>())
+        });
+        let skip = if repeated { overlap } else { 0 };
+        let start = length - skip;
+        let chars = chunk.chars().count();
+        content.extend(chunk.chars().skip(skip));
+        length = start + chars;
+        offsets.push((start, length));
+        previous = Some(chunk);
+    }
+    (content, offsets)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -581,6 +2683,7 @@ mod tests {
                 score: 0.9,
                 content: "content".to_string(),
                 snippet: "snippet".to_string(),
+                highlights: Vec::new(),
                 metadata: serde_json::json!({}),
                 citation: CitationInfo {
                     title: "".to_string(),
@@ -589,6 +2692,7 @@ mod tests {
                     version: None,
                     anchor: None,
                     page_number: None,
+                    time_range: None,
                 },
             }
         ];
@@ -601,6 +2705,7 @@ mod tests {
                 score: 0.8,
                 content: "content".to_string(),
                 snippet: "snippet".to_string(),
+                highlights: Vec::new(),
                 metadata: serde_json::json!({}),
                 citation: CitationInfo {
                     title: "".to_string(),
@@ -609,6 +2714,7 @@ mod tests {
                     version: None,
                     anchor: None,
                     page_number: None,
+                    time_range: None,
                 },
             }
         ];
@@ -619,4 +2725,1149 @@ mod tests {
         // Check combined score: 0.9 * 0.6 + 0.8 * 0.4 = 0.54 + 0.32 = 0.86
         assert!((merged[0].score - 0.86).abs() < 0.01);
     }
-}
\ No newline at end of file
+
+    fn successful_run() -> PipelineRunOutput {
+        use crate::modules::pipeline::models::{PipelineChunk, PipelineDocument, PipelineRunReport, StepData};
+
+        PipelineRunOutput {
+            data: StepData {
+                documents: vec![PipelineDocument {
+                    id: "doc_1".to_string(),
+                    title: "Guide".to_string(),
+                    source_path: "/docs/guide.md".to_string(),
+                    content: "hello world".to_string(),
+                    content_hash: "h1".to_string(),
+                    license_info: None,
+                    metadata: serde_json::json!({}),
+                }],
+                chunks: vec![PipelineChunk {
+                    id: "chunk_1".to_string(),
+                    document_id: "doc_1".to_string(),
+                    chunk_index: 0,
+                    content: "hello world".to_string(),
+                    content_hash: "c1".to_string(),
+                    metadata: serde_json::json!({}),
+                    embedding: Vec::new(),
+                }],
+            },
+            report: PipelineRunReport::default(),
+            error: None,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_version_lifecycle() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = kb_service.create_collection("Docs", config).await.unwrap();
+        let kb_id = kb_id.as_str();
+
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Ingest".to_string(),
+            kb_id: Some(kb_id.to_string()),
+            steps: vec![],
+            resources: Default::default(),
+            source_roots: Vec::new(),
+        };
+
+        let mut failed = successful_run();
+        failed.error = Some("parse failed".to_string());
+        assert!(kb_service.create_version_from_run(kb_id, &spec, "run_0", &failed).await.unwrap().is_none());
+
+        let v1 = kb_service.create_version_from_run(kb_id, &spec, "run_1", &successful_run()).await.unwrap().unwrap();
+        let v2 = kb_service.create_version_from_run(kb_id, &spec, "run_2", &successful_run()).await.unwrap().unwrap();
+        assert_eq!((v1.version, v2.version), (1, 2));
+        assert_eq!(v1.stats.chunk_count, 1);
+        assert_eq!(v1.manifest.documents[0].chunk_count, 1);
+        assert_ne!(v1.collection_id(), v2.collection_id());
+
+        // Each version is searchable on its own before it goes live
+        let hits = kb_service.hybrid_search_version(kb_id, 1, "hello", 5, None).await.unwrap();
+        assert_eq!(hits[0].chunk_id, "doc_1_0");
+
+        kb_service.activate_version(kb_id, 1).await.unwrap();
+        let active = kb_service.activate_version(kb_id, 2).await.unwrap();
+        assert_eq!(active.status, KbVersionStatus::Active);
+        assert_eq!(kb_service.get_active_version(kb_id).await.unwrap().unwrap().version, 2);
+
+        // Searches and document reads now go to the active version's index
+        let hits = kb_service.hybrid_search(kb_id, "hello", 5, None, None).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document_id, "doc_1");
+        assert_eq!(kb_service.list_documents(kb_id).await.unwrap().len(), 1);
+        assert_eq!(kb_service.get_document_content(kb_id, "doc_1", None).await.unwrap().content, "hello world");
+
+        // The active version cannot be deleted
+        assert!(kb_service.delete_version(kb_id, 2).await.is_err());
+
+        let versions = kb_service.list_versions(kb_id).await.unwrap();
+        assert_eq!(versions[1].status, KbVersionStatus::Archived);
+        kb_service.delete_version(kb_id, 1).await.unwrap();
+        assert_eq!(kb_service.list_versions(kb_id).await.unwrap().len(), 1);
+        assert_eq!(kb_service.hybrid_search(kb_id, "hello", 5, None, None).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_resolve_version_prefers_workspace_pin() {
+        use crate::state::{KnowledgeBaseStatus, WorkspaceState};
+
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let state_manager = Arc::new(StateManager::new());
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, state_manager.clone());
+
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Ingest".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![],
+            resources: Default::default(),
+            source_roots: Vec::new(),
+        };
+        kb_service.create_version_from_run("kb_1", &spec, "run_1", &successful_run()).await.unwrap();
+        kb_service.create_version_from_run("kb_1", &spec, "run_2", &successful_run()).await.unwrap();
+        kb_service.activate_version("kb_1", 2).await.unwrap();
+
+        state_manager.mutate(StateDelta::WorkspaceAdd {
+            workspace: WorkspaceState {
+                id: "ws_1".to_string(),
+                name: "Stable".to_string(),
+                pinned_versions: HashMap::from([("kb_1".to_string(), 1)]),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+        }).unwrap();
+
+        let latest = kb_service.resolve_version("kb_1", None).await.unwrap().unwrap();
+        assert_eq!(latest.version, 2);
+
+        let pinned = kb_service.resolve_version("kb_1", Some("ws_1")).await.unwrap().unwrap();
+        assert_eq!(pinned.version, 1);
+
+        state_manager.mutate(StateDelta::WorkspaceActivate { id: Some("ws_1".to_string()) }).unwrap();
+        assert_eq!(kb_service.resolve_version("kb_1", None).await.unwrap().unwrap().version, 1);
+        assert_eq!(kb_service.read_index("kb_1").await.unwrap(), pinned.collection_id());
+
+        // Listings report the active workspace's pin
+        state_manager.mutate(StateDelta::KnowledgeBaseAdd {
+            kb: KnowledgeBaseState {
+                id: "kb_1".to_string(),
+                name: "Docs".to_string(),
+                version: 2,
+                status: KnowledgeBaseStatus::Active,
+                embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+                health_score: 1.0,
+                document_count: 0,
+                chunk_count: 0,
+                last_updated: Utc::now(),
+                metadata: serde_json::json!({}),
+            },
+        }).unwrap();
+        let collections = kb_service.list_collections(None).await.unwrap();
+        assert!(collections[0].pinned);
+        assert_eq!(collections[0].version, 1);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_reindex_fingerprints() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+
+        let first = successful_run().data;
+        let plan = kb_service.plan_reindex("kb_1", first.documents.clone()).await.unwrap();
+        assert_eq!(plan.changed.len(), 1);
+        kb_service.record_fingerprints("kb_1", &plan, &first).await.unwrap();
+
+        // Same content is skipped on the next run
+        let plan = kb_service.plan_reindex("kb_1", first.documents.clone()).await.unwrap();
+        assert!(plan.is_noop());
+        assert_eq!(plan.unchanged[0].chunk_count, 1);
+
+        let mut edited = first.documents.clone();
+        edited[0].content_hash = "h2".to_string();
+        assert_eq!(kb_service.plan_reindex("kb_1", edited).await.unwrap().changed.len(), 1);
+
+        kb_service.clear_fingerprints("kb_1").await.unwrap();
+        assert_eq!(kb_service.plan_reindex("kb_1", first.documents).await.unwrap().changed.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_reindex_sources_skips_unchanged() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let mut runner = PipelineRunner::new();
+        runner.register(Arc::new(crate::modules::pipeline::ParseStepExecutor::new()));
+        runner.register(Arc::new(crate::modules::pipeline::RedactStepExecutor::new()));
+        let state_manager = Arc::new(StateManager::new());
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, state_manager.clone())
+            .with_pipeline_runner(Arc::new(runner));
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+        assert!(matches!(kb_service.reindex_sources(&kb_id, false).await, Err(KbError::ValidationError(_))));
+
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir(&docs).unwrap();
+        state_manager.mutate(StateDelta::ScheduleUpsert { schedule: Box::new(kb_schedule(&kb_id, &docs)) }).unwrap();
+
+        // Added by name from outside the pipeline's roots: indexed, but never re-read
+        let elsewhere = TempDir::new().unwrap();
+        let outside = elsewhere.path().join("outside.md");
+        std::fs::write(&outside, "outside notes").unwrap();
+        kb_service.add_documents(&kb_id, vec![outside.to_str().unwrap().to_string()]).await.unwrap();
+        std::fs::write(&outside, "outside notes, edited").unwrap();
+
+        let guide = docs.join("guide.md");
+        let notes = docs.join("notes.md");
+        std::fs::write(&guide, "install guide").unwrap();
+        std::fs::write(&notes, "release notes from ops@example.com").unwrap();
+
+        // New sources go through the pipeline, redaction included
+        let first = kb_service.reindex_sources(&kb_id, false).await.unwrap();
+        assert_eq!(first.changed.len(), 2);
+        assert_eq!(first.unchanged.len(), 1);
+        let hits = kb_service.hybrid_search(&kb_id, "release", 5, None, None).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert!(!hits[0].content.contains("ops@example.com"));
+
+        // Fingerprints hash the source, not the redacted text, so nothing changed
+        let second = kb_service.reindex_sources(&kb_id, false).await.unwrap();
+        assert!(second.is_noop());
+        assert_eq!(second.unchanged.len(), 3);
+
+        std::fs::write(&notes, "release notes, edited").unwrap();
+        let third = kb_service.reindex_sources(&kb_id, false).await.unwrap();
+        assert_eq!(third.changed.len(), 1);
+        assert_eq!(third.changed[0].title, "notes.md");
+        assert_eq!(third.unchanged.len(), 2);
+        let hits = kb_service.hybrid_search(&kb_id, "edited", 5, None, None).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].document_id, third.changed[0].id);
+        assert_eq!(kb_service.list_documents(&kb_id).await.unwrap().len(), 3);
+
+        std::fs::remove_file(&guide).unwrap();
+        let removed = kb_service.reindex_sources(&kb_id, false).await.unwrap();
+        assert_eq!(removed.removed.len(), 1);
+        assert_eq!(kb_service.list_documents(&kb_id).await.unwrap().len(), 2);
+
+        let full = kb_service.reindex_sources(&kb_id, true).await.unwrap();
+        assert_eq!(full.changed.len(), 1);
+        assert_eq!(full.unchanged.len(), 1);
+    }
+
+    /// Refresh schedule whose pipeline parses and redacts the files under `root`
+    fn kb_schedule(kb_id: &str, root: &std::path::Path) -> crate::state::ScheduleState {
+        use crate::modules::pipeline::{ETLStepType, PipelineStepConfig};
+        let step = |step_type, config| PipelineStepConfig { step_type, config, resources: None, on_limit: Default::default() };
+        crate::state::ScheduleState {
+            id: "sched_1".to_string(),
+            name: "Nightly".to_string(),
+            cron_expression: "0 2 * * *".to_string(),
+            pipeline_id: "pipeline_1".to_string(),
+            enabled: true,
+            last_run: None,
+            next_run: None,
+            run_count: 0,
+            kb_id: Some(kb_id.to_string()),
+            tool_id: None,
+            pipeline: Some(PipelineSpec {
+                id: "pipeline_1".to_string(),
+                name: "Ingest".to_string(),
+                kb_id: Some(kb_id.to_string()),
+                steps: vec![
+                    step(ETLStepType::Parse, serde_json::json!({ "sources": ["."] })),
+                    step(ETLStepType::Redact, serde_json::json!({})),
+                ],
+                resources: Default::default(),
+                source_roots: vec![root.to_string_lossy().to_string()],
+            }),
+            sources: Vec::new(),
+            last_outcome: None,
+        }
+    }
+
+    #[test]
+    fn test_chunk_text_overlap() {
+        let chunks = chunk_text("abcdefghij", 4, 1);
+        assert_eq!(chunks, vec!["abcd", "defg", "ghij"]);
+        assert!(chunk_text("", 4, 1).is_empty());
+        assert_eq!(chunk_text("héllo", 10, 2), vec!["héllo"]);
+    }
+
+    #[test]
+    fn test_query_language_filter_and_boost() {
+        let result = |chunk_id: &str, score: f32, lang: Option<&str>| SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: "doc_1".to_string(),
+            kb_id: "kb_1".to_string(),
+            score,
+            content: String::new(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: lang.map_or_else(|| serde_json::json!({}), |l| serde_json::json!({ "lang": l })),
+            citation: CitationInfo {
+                title: String::new(),
+                source_path: String::new(),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        };
+        let results = || vec![result("en", 0.9, Some("en")), result("de", 0.8, Some("de")), result("code", 0.7, None)];
+        let ids = |results: Vec<SearchResult>| results.into_iter().map(|r| r.chunk_id).collect::<Vec<_>>();
+        let query = "Wie kann ich mein Passwort zurücksetzen?";
+        let filters = |value: serde_json::Value| Some(serde_json::from_value::<HashMap<String, serde_json::Value>>(value).unwrap());
+
+        assert_eq!(ids(apply_query_language(results(), query, &None)), vec!["en", "de", "code"]);
+        assert_eq!(ids(apply_query_language(results(), query, &filters(serde_json::json!({"lang": "auto"})))), vec!["de", "code"]);
+        assert_eq!(ids(apply_query_language(results(), query, &filters(serde_json::json!({"lang": "en"})))), vec!["en", "code"]);
+        let boosted = apply_query_language(results(), query, &filters(serde_json::json!({"lang_boost": 0.5})));
+        assert_eq!(boosted[0].chunk_id, "de");
+        assert!((boosted[0].score - 1.2).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_join_chunks_inverts_chunk_text() {
+        let text = "abcdefghij";
+        let chunks = chunk_text(text, 4, 1);
+        let texts: Vec<&str> = chunks.iter().map(String::as_str).collect();
+        let (content, offsets) = join_chunks(&texts, 1);
+        assert_eq!(content, text);
+        assert_eq!(offsets, vec![(0, 4), (3, 7), (6, 10)]);
+        assert_eq!(join_chunks(&["ab", "cd"], 1).0, "abcd");
+        assert!(join_chunks(&[], 1).0.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_document_crud_validation() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+
+        let result = kb_service.add_documents("missing_kb", vec!["/tmp/a.md".to_string()]).await;
+        assert!(matches!(result, Err(KbError::KbNotFound(_))));
+
+        let sandbox = SourceSandbox::new(&[temp_dir.path().to_string_lossy().to_string()]);
+        let result = kb_service.load_source(&sandbox, "https://example.com/page", None).await;
+        assert!(matches!(result, Err(KbError::ValidationError(_))));
+
+        let path = temp_dir.path().join("notes.md");
+        std::fs::write(&path, "release notes").unwrap();
+        let doc = kb_service.load_source(&sandbox, path.to_str().unwrap(), Some("doc_1")).await.unwrap();
+        assert_eq!(doc.id, "doc_1");
+        assert_eq!(doc.title, "notes.md");
+        assert_eq!(doc.content_hash, DocumentFingerprint::hash_content("release notes"));
+
+        // Paths outside the sandbox are refused, including through symlinks inside it
+        let outside = TempDir::new().unwrap();
+        let secret = outside.path().join("secret.txt");
+        std::fs::write(&secret, "secret").unwrap();
+        let result = kb_service.load_source(&sandbox, secret.to_str().unwrap(), None).await;
+        assert!(matches!(result, Err(KbError::ValidationError(_))));
+        #[cfg(unix)]
+        {
+            std::os::unix::fs::symlink(&secret, temp_dir.path().join("link.md")).unwrap();
+            let result = kb_service.load_source(&sandbox, "link.md", None).await;
+            assert!(matches!(result, Err(KbError::ValidationError(_))));
+            let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+                description: None,
+                embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+                chunk_size: 512,
+                chunk_overlap: 50,
+            }).await.unwrap();
+            let link = temp_dir.path().join("link.md").to_string_lossy().to_string();
+            let result = kb_service.add_documents(&kb_id, vec![link]).await;
+            assert!(matches!(result, Err(KbError::ValidationError(_))));
+        }
+
+        let result = kb_service.remove_document("kb_1", "doc_unknown").await;
+        assert!(matches!(result, Err(KbError::DocumentNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_get_document_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+
+        // Long enough to span several overlapping chunks
+        let text: String = (0..200).map(|i| format!("line {} ", i)).collect();
+        let path = temp_dir.path().join("guide.md");
+        std::fs::write(&path, &text).unwrap();
+        let added = kb_service.add_documents(&kb_id, vec![path.to_str().unwrap().to_string()]).await.unwrap();
+        let doc_id = &added[0].id;
+
+        let document = kb_service.get_document_content(&kb_id, doc_id, None).await.unwrap();
+        assert_eq!(document.content, text);
+        assert_eq!(document.document.title, "guide.md");
+        assert!(document.chunks.len() > 1);
+        assert_eq!(document.chunks.last().unwrap().end, text.chars().count());
+        assert_eq!(document.chunks[0].citation.anchor.as_deref(), Some(format!("chunk_{}", document.chunks[0].chunk_id).as_str()));
+
+        // Search hits and resolved chunks cite the document's real source
+        let hits = kb_service.hybrid_search(&kb_id, "line", 3, None, None).await.unwrap();
+        assert!(!hits.is_empty());
+        for hit in &hits {
+            assert_eq!(hit.citation.title, "guide.md");
+            assert_eq!(hit.citation.source_path, added[0].source_path);
+            assert_eq!(hit.citation.license, None);
+        }
+        let citations = kb_service.resolve_citations(vec![hits[0].chunk_id.clone(), "doc_unknown_0".to_string()]).await.unwrap();
+        assert_eq!(citations, vec![hits[0].citation.clone()]);
+        let info = kb_service.get_document(doc_id, None).await.unwrap();
+        assert_eq!((info.title.as_str(), info.chunk_count), ("guide.md", document.chunks.len() as i32));
+
+        let range = kb_service.get_document_content(&kb_id, doc_id, Some((0, 10))).await.unwrap();
+        assert_eq!(range.content, &text[..10]);
+        assert_eq!(range.chunks.len(), 1);
+
+        let result = kb_service.get_document_content(&kb_id, "doc_unknown", None).await;
+        assert!(matches!(result, Err(KbError::DocumentNotFound(_))));
+        let result = kb_service.get_document_content(&kb_id, doc_id, Some((10, 5))).await;
+        assert!(matches!(result, Err(KbError::ValidationError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_expand_results() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+
+        // The Beta heading falls in the second chunk
+        let text = format!("# Alpha\n{}\n# Beta\n{}", "alpha line. ".repeat(60), "beta line. ".repeat(150));
+        let path = temp_dir.path().join("guide.md");
+        std::fs::write(&path, &text).unwrap();
+        let added = kb_service.add_documents(&kb_id, vec![path.to_str().unwrap().to_string()]).await.unwrap();
+        let document = kb_service.get_document_content(&kb_id, &added[0].id, None).await.unwrap();
+        let chunk_ids: Vec<String> = document.chunks.iter().map(|c| c.chunk_id.clone()).collect();
+        assert!(chunk_ids.len() >= 5);
+
+        let hit = |chunk_id: &str| SearchResult {
+            chunk_id: chunk_id.to_string(),
+            document_id: added[0].id.clone(),
+            kb_id: kb_id.clone(),
+            score: 1.0,
+            content: String::new(),
+            snippet: String::new(),
+            highlights: Vec::new(),
+            metadata: serde_json::json!({}),
+            citation: CitationInfo {
+                title: "guide.md".to_string(),
+                source_path: path.to_string_lossy().to_string(),
+                license: None,
+                version: None,
+                anchor: None,
+                page_number: None,
+                time_range: None,
+            },
+        };
+        let expanded_ids = |result: &SearchResult| result.metadata["expanded_chunk_ids"].as_array().unwrap().len();
+
+        let results = kb_service.expand_results(vec![hit(&chunk_ids[2])], ContextExpansion::Adjacent { window: 1 }).await.unwrap();
+        assert_eq!(results[0].chunk_id, chunk_ids[2]);
+        assert_eq!(results[0].metadata["expanded_chunk_ids"], serde_json::json!(chunk_ids[1..4]));
+        assert!(text.contains(&results[0].content));
+
+        // Overlapping neighbourhoods join the better ranked hit
+        let results = kb_service.expand_results(
+            vec![hit(&chunk_ids[2]), hit(&chunk_ids[3]), hit("chunk_unknown")],
+            ContextExpansion::Adjacent { window: 1 },
+        ).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(expanded_ids(&results[0]), 4);
+        assert_eq!(results[1].chunk_id, "chunk_unknown");
+        assert!(results[1].content.is_empty());
+
+        let last = chunk_ids.last().unwrap();
+        let results = kb_service.expand_results(vec![hit(last)], ContextExpansion::Parent { max_chunks: 8 }).await.unwrap();
+        assert!(results[0].content.contains("# Beta"));
+        assert!(!results[0].content.contains("# Alpha"));
+        assert_eq!(expanded_ids(&results[0]), chunk_ids.len() - 1);
+        let results = kb_service.expand_results(vec![hit(last)], ContextExpansion::Parent { max_chunks: 2 }).await.unwrap();
+        assert_eq!(expanded_ids(&results[0]), 2);
+    }
+
+    #[tokio::test]
+    async fn test_hierarchical_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let kb_service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service.clone(), Arc::new(StateManager::new()));
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+
+        let install = format!("# Install\n{}\n", "Run the setup script. ".repeat(40));
+        let text = format!("{}# Backups\n{}", install, "Copies go to the vault every night. ".repeat(40));
+        let path = temp_dir.path().join("guide.md");
+        std::fs::write(&path, &text).unwrap();
+        let added = kb_service.add_documents(&kb_id, vec![path.to_str().unwrap().to_string()]).await.unwrap();
+        let doc_id = added[0].id.clone();
+
+        // Without a summary index the hybrid search answers
+        assert!(kb_service.hierarchical_search(&kb_id, "nightly backups", 5).await.is_ok());
+
+        let split = install.chars().count();
+        let node = |id: &str, level: &str, content: &str, start: usize, end: usize| VectorSchema {
+            chunk_id: format!("{}_{}", doc_id, id),
+            document_id: doc_id.clone(),
+            kb_id: kb_id.clone(),
+            content: content.to_string(),
+            embedding: Vec::new(),
+            metadata: serde_json::json!({ "summary_level": level, "title": "guide.md", "start": start, "end": end }),
+            created_at: 0,
+            updated_at: 0,
+        };
+        vector_service.upsert_vectors(&summary_index_id(&kb_id), vec![
+            node("section_0", "section", "How to install the server.", 0, split),
+            node("section_1", "section", "Nightly backups are copied to the vault.", split, text.chars().count()),
+            node("document", "document", "Server guide: install and backups.", 0, text.chars().count()),
+        ]).await.unwrap();
+
+        let results = kb_service.hierarchical_search(&kb_id, "nightly backups vault", 10).await.unwrap();
+        assert_eq!(results[0].chunk_id, format!("{}_document", doc_id));
+        assert_eq!(results[0].metadata["summary_level"], "document");
+        let step = DEFAULT_CHUNK_SIZE - DEFAULT_CHUNK_OVERLAP;
+        assert!(results.len() > 1);
+        for chunk in &results[1..] {
+            // Only chunks reaching into the Backups section
+            let index = chunk.metadata["chunk_index"].as_u64().unwrap() as usize;
+            assert!(index * step + DEFAULT_CHUNK_SIZE > split);
+            assert_eq!(chunk.metadata["summary_id"], format!("{}_section_1", doc_id));
+        }
+        assert_eq!(kb_service.hierarchical_search(&kb_id, "nightly backups vault", 2).await.unwrap().len(), 2);
+
+        // Removing the document drops its summaries too
+        kb_service.remove_document(&kb_id, &doc_id).await.unwrap();
+        assert!(vector_service.export_chunks(&summary_index_id(&kb_id)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_export_import_roundtrip() {
+        use crate::state::{KnowledgeBaseState, KnowledgeBaseStatus};
+
+        let source_dir = TempDir::new().unwrap();
+        let source_sql = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(source_dir.path())
+        ).await.unwrap();
+        source_sql.run_migrations().await.unwrap();
+        let source_state = Arc::new(StateManager::new());
+        source_state.mutate(StateDelta::KnowledgeBaseAdd {
+            kb: KnowledgeBaseState {
+                id: "kb_1".to_string(),
+                name: "Docs".to_string(),
+                version: 1,
+                status: KnowledgeBaseStatus::Active,
+                embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+                health_score: 1.0,
+                document_count: 1,
+                chunk_count: 1,
+                last_updated: Utc::now(),
+                metadata: serde_json::json!({}),
+            },
+        }).unwrap();
+        let source = KbServiceImpl::new_mvp(
+            Arc::new(source_sql),
+            Arc::new(crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(source_dir.path())
+            ).await.unwrap()),
+            source_state,
+        );
+
+        let data = successful_run().data;
+        let plan = source.plan_reindex("kb_1", data.documents.clone()).await.unwrap();
+        source.record_fingerprints("kb_1", &plan, &data).await.unwrap();
+
+        let pack = source.export_kb("kb_1").await.unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target_sql = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(target_dir.path())
+        ).await.unwrap();
+        target_sql.run_migrations().await.unwrap();
+        let target_sql = Arc::new(target_sql);
+        let target_vectors = Arc::new(crate::services::vector::VectorDbService::new(
+            crate::services::vector::VectorDbConfig::test_config(target_dir.path())
+        ).await.unwrap());
+        let target = KbServiceImpl::new_mvp(target_sql.clone(), target_vectors.clone(), Arc::new(StateManager::new()));
+
+        // The source has no signer, so the pack imports only once confirmed
+        assert!(matches!(target.import_kb(&pack, false).await, Err(KbError::StorageError(_))));
+        assert_eq!(target.import_kb(&pack, true).await.unwrap(), "kb_1");
+        assert_eq!(target.list_fingerprints("kb_1").await.unwrap().len(), 1);
+        assert!(target.get_kb_state("kb_1").is_ok());
+        let restarted = KbServiceImpl::new_mvp(target_sql, target_vectors, Arc::new(StateManager::new()));
+        assert_eq!(restarted.load_collections().await.unwrap(), 1);
+
+        // Importing twice is rejected
+        assert!(matches!(target.import_kb(&pack, true).await, Err(KbError::ValidationError(_))));
+
+        // Rows for another KB and path-like ids are refused
+        let repack = |edit: &dyn Fn(&mut BTreeMap<String, String>)| {
+            let (manifest, mut files) = source.storage_service.read_pack_with(&pack, true).unwrap();
+            edit(&mut files);
+            let manifest = PackManifest::new("kb", &manifest.id, &manifest.name, &manifest.version);
+            source.storage_service.write_pack(manifest, files).unwrap().1
+        };
+        let rename = |files: &mut BTreeMap<String, String>, name: &str, id: &str| {
+            let mut value: serde_json::Value = serde_json::from_str(&files[name]).unwrap();
+            match &mut value {
+                serde_json::Value::Array(rows) => rows.iter_mut().for_each(|row| row["kb_id"] = id.into()),
+                kb => kb["id"] = id.into(),
+            }
+            files.insert(name.to_string(), value.to_string());
+        };
+        let foreign = repack(&|files| {
+            rename(files, "kb.json", "kb_2");
+            rename(files, "versions.json", "kb_2");
+        });
+        assert!(matches!(target.import_kb(&foreign, true).await, Err(KbError::ValidationError(_))));
+        let traversal = repack(&|files| {
+            for name in ["kb.json", "versions.json", "fingerprints.json"] {
+                rename(files, name, "../kb_2");
+            }
+        });
+        assert!(matches!(target.import_kb(&traversal, true).await, Err(KbError::ValidationError(_))));
+        assert!(target.get_kb_state("kb_2").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_collections_persist_across_restarts() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(
+            crate::services::sql::SqlService::new(
+                crate::services::sql::SqlConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+
+        let config = KbCreateConfig {
+            description: Some("Product docs".to_string()),
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let first = KbServiceImpl::new_mvp(sql_service.clone(), vector_service.clone(), Arc::new(StateManager::new()));
+        let kb_id = first.create_collection("Docs", config.clone()).await.unwrap();
+        assert!(first.get_kb_state(&kb_id).is_ok());
+        assert!(matches!(first.create_collection(" ", config).await, Err(KbError::ValidationError(_))));
+
+        // A fresh state manager (new process) sees the KB after loading
+        let second = KbServiceImpl::new_mvp(sql_service.clone(), vector_service.clone(), Arc::new(StateManager::new()));
+        assert_eq!(second.load_collections().await.unwrap(), 1);
+        let collections = second.list_collections(None).await.unwrap();
+        assert_eq!(collections.len(), 1);
+        assert_eq!(collections[0].name, "Docs");
+        assert_eq!(collections[0].description.as_deref(), Some("Product docs"));
+        assert_eq!(second.load_collections().await.unwrap(), 0);
+
+        // Deleted KBs stay deleted, index files included
+        let doc = temp_dir.path().join("guide.md");
+        std::fs::write(&doc, "install guide").unwrap();
+        second.add_documents(&kb_id, vec![doc.to_str().unwrap().to_string()]).await.unwrap();
+        assert!(temp_dir.path().join(format!("{}_bm25", kb_id)).exists());
+        second.delete_collection(&kb_id).await.unwrap();
+        assert!(second.get_kb_state(&kb_id).is_err());
+        assert!(second.list_fingerprints(&kb_id).await.unwrap().is_empty());
+        assert!(!temp_dir.path().join(format!("{}_bm25", kb_id)).exists());
+        let third = KbServiceImpl::new_mvp(sql_service, vector_service, Arc::new(StateManager::new()));
+        assert_eq!(third.load_collections().await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_search_multiple_tags_source_kb() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(
+            crate::services::sql::SqlService::new(
+                crate::services::sql::SqlConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let service = KbServiceImpl::new_mvp(sql_service, vector_service, Arc::new(StateManager::new()));
+
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_a = service.create_collection("Product", config.clone()).await.unwrap();
+        let kb_b = service.create_collection("Support", config).await.unwrap();
+
+        let results = service.search_multiple(&[kb_a.clone(), kb_b.clone(), kb_a.clone()], "install", 5, None).await.unwrap();
+        assert!(results.len() <= 5);
+        for result in &results {
+            let source = &result.metadata["source_kb"];
+            assert_eq!(source["id"], result.kb_id.as_str());
+            assert!(source["name"] == "Product" || source["name"] == "Support");
+            assert!(result.score > 0.0 && result.score <= 1.0);
+        }
+
+        assert!(matches!(service.search_multiple(&[], "install", 5, None).await, Err(KbError::InvalidQuery(_))));
+        assert!(matches!(
+            service.search_multiple(&[kb_a, "kb_missing".to_string()], "install", 5, None).await,
+            Err(KbError::KbNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_consistency_check_repairs_diverged_documents() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(
+            crate::services::sql::SqlService::new(
+                crate::services::sql::SqlConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let service = KbServiceImpl::new_mvp(sql_service, vector_service.clone(), Arc::new(StateManager::new()));
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = service.create_collection("Docs", config).await.unwrap();
+
+        let doc = |id: &str| PipelineDocument {
+            id: id.to_string(),
+            title: id.to_string(),
+            source_path: format!("/docs/{}.md", id),
+            content: format!("Contents of {}", id),
+            content_hash: format!("h_{}", id),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        };
+        service.ingest_documents(&kb_id, &[doc("doc_1"), doc("doc_2"), doc("doc_4")]).await.unwrap();
+        service.remove_document(&kb_id, "doc_4").await.unwrap();
+        assert!(service.check_consistency(&kb_id, false).await.unwrap().is_consistent());
+
+        let outbox = service.list_outbox(&kb_id).await.unwrap();
+        assert_eq!(outbox.len(), 3);
+        assert!(outbox.iter().all(|e| e.status == OutboxStatus::Committed));
+        assert_eq!(outbox.last().unwrap().operation, OutboxOperation::Delete);
+
+        let chunk = |doc_id: &str, generation: u64| VectorSchema {
+            chunk_id: format!("{}_9", doc_id),
+            document_id: doc_id.to_string(),
+            kb_id: kb_id.clone(),
+            content: "leftover".to_string(),
+            embedding: Vec::new(),
+            metadata: serde_json::json!({ "generation": generation }),
+            created_at: 0,
+            updated_at: 0,
+        };
+        // Crash after the vectors were written but before the commit
+        let staged = service.stage_write(&kb_id, "doc_3", OutboxOperation::Upsert, 1).await.unwrap();
+        vector_service.upsert_vectors(&kb_id, vec![chunk("doc_3", staged.generation)]).await.unwrap();
+        // Chunks of doc_1 from a generation that never committed, doc_2's chunks lost
+        vector_service.upsert_vectors(&kb_id, vec![chunk("doc_1", staged.generation)]).await.unwrap();
+        vector_service.delete_document(&kb_id, "doc_2").await.unwrap();
+
+        let report = service.check_consistency(&kb_id, false).await.unwrap();
+        assert!(!report.repaired);
+        assert_eq!(report.staged_writes.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(), vec![staged.id.as_str()]);
+        assert_eq!(report.orphan_documents, vec!["doc_3"]);
+        assert_eq!(report.missing_documents, vec!["doc_2"]);
+        assert_eq!(report.stale_documents, vec!["doc_1"]);
+
+        let report = service.check_consistency(&kb_id, true).await.unwrap();
+        assert!(report.repaired);
+        assert!(service.check_consistency(&kb_id, false).await.unwrap().is_consistent());
+        assert!(vector_service.export_chunks(&kb_id).await.unwrap().is_empty());
+        assert!(service.list_fingerprints(&kb_id).await.unwrap().is_empty());
+
+        let outbox = service.list_outbox(&kb_id).await.unwrap();
+        let rolled_back = outbox.iter().find(|e| e.id == staged.id).unwrap();
+        assert_eq!(rolled_back.status, OutboxStatus::RolledBack);
+
+        // Repaired documents are indexed again by the next run
+        service.ingest_documents(&kb_id, &[doc("doc_1")]).await.unwrap();
+        assert!(service.check_consistency(&kb_id, false).await.unwrap().is_consistent());
+    }
+
+    #[tokio::test]
+    async fn test_search_cache_invalidated_on_ingest() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(
+            crate::services::sql::SqlService::new(
+                crate::services::sql::SqlConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let cache = Arc::new(CacheService::new(crate::services::cache::CacheConfig::test_config(temp_dir.path())).unwrap());
+        let service = KbServiceImpl::new_mvp(sql_service, vector_service, Arc::new(StateManager::new()))
+            .with_cache_service(cache.clone());
+        let metrics = Arc::new(MetricsService::new());
+        let service = service.with_metrics_service(metrics.clone());
+
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = service.create_collection("Docs", config).await.unwrap();
+
+        // No TTL: the cache is bypassed
+        service.hybrid_search(&kb_id, "install", 5, None, None).await.unwrap();
+        assert_eq!(cache.stats().unwrap().miss_count, 0);
+
+        service.hybrid_search(&kb_id, "install", 5, None, Some(60)).await.unwrap();
+        service.hybrid_search(&kb_id, "install", 5, None, Some(60)).await.unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.miss_count, stats.hit_count, stats.disk_entries), (1, 1, 1));
+
+        let doc = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "Guide".to_string(),
+            source_path: "/docs/guide.md".to_string(),
+            content: "Install the server".to_string(),
+            content_hash: "h1".to_string(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        };
+        service.ingest_documents(&kb_id, &[doc]).await.unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.disk_entries, stats.invalidation_count), (0, 1));
+
+        service.hybrid_search(&kb_id, "install", 5, None, Some(60)).await.unwrap();
+        assert_eq!(cache.stats().unwrap().miss_count, 2);
+
+        let summary = metrics.snapshot().summary;
+        assert_eq!(summary.searches, 4);
+        assert!((summary.search_cache_hit_rate.unwrap() - 1.0 / 3.0).abs() < 1e-9);
+    }
+
+    /// Embeds every query mentioning "install" alike, anything else elsewhere
+    struct TopicEmbedder;
+
+    #[async_trait]
+    impl EmbeddingBackend for TopicEmbedder {
+        async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, crate::services::model::ModelError> {
+            assert_eq!(model_id, "sentence-transformers/all-MiniLM-L6-v2");
+            Ok(texts.iter().map(|text| if text.contains("install") { vec![1.0, 0.0] } else { vec![0.0, 1.0] }).collect())
+        }
+
+        async fn rerank(&self, _model_id: &str, _query: &str, documents: &[String]) -> Result<Vec<f32>, crate::services::model::ModelError> {
+            Ok(vec![0.0; documents.len()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_semantic_search_cache() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(
+            crate::services::sql::SqlService::new(
+                crate::services::sql::SqlConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let cache = Arc::new(CacheService::new(crate::services::cache::CacheConfig::test_config(temp_dir.path())).unwrap());
+        let service = KbServiceImpl::new_mvp(sql_service, vector_service, Arc::new(StateManager::new()))
+            .with_cache_service(cache.clone())
+            .with_query_embedder(Arc::new(TopicEmbedder));
+        let config = KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = service.create_collection("Docs", config).await.unwrap();
+
+        // A reworded query is served from the semantic tier; another topic or top_k is not
+        service.hybrid_search(&kb_id, "how to install", 5, None, Some(60)).await.unwrap();
+        service.hybrid_search(&kb_id, "install steps", 5, None, Some(60)).await.unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.hit_count, stats.semantic_entries), (1, 1));
+        service.hybrid_search(&kb_id, "installing the server", 5, None, Some(60)).await.unwrap();
+        service.hybrid_search(&kb_id, "remove data", 5, None, Some(60)).await.unwrap();
+        service.hybrid_search(&kb_id, "install steps", 3, None, Some(60)).await.unwrap();
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.hit_count, stats.semantic_entries), (2, 3));
+
+        // Without a TTL the cache is bypassed entirely
+        service.hybrid_search(&kb_id, "install guide", 5, None, None).await.unwrap();
+        assert_eq!(cache.stats().unwrap().hit_count, 2);
+
+        let doc = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "Guide".to_string(),
+            source_path: "/docs/guide.md".to_string(),
+            content: "Install the server".to_string(),
+            content_hash: "h1".to_string(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        };
+        service.ingest_documents(&kb_id, &[doc]).await.unwrap();
+        assert_eq!(cache.stats().unwrap().semantic_entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_transcript_chunks_carry_time_ranges() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service.clone(), Arc::new(StateManager::new()));
+        let kb_id = service.create_collection("Meetings", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+
+        // 30 segments of 40 characters, 5 seconds each, one per line
+        let lines: Vec<String> = (0..30).map(|i| format!("{:<40}", format!("Segment {:02} on the deploy plan.", i))).collect();
+        let segments: Vec<serde_json::Value> = (0..30)
+            .map(|i| serde_json::json!({ "start": i * 41, "end": i * 41 + 40, "start_ms": i * 5000, "end_ms": (i + 1) * 5000 }))
+            .collect();
+        let content = lines.join("\n");
+        let doc = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "standup.mp4".to_string(),
+            source_path: "/recordings/standup.mp4".to_string(),
+            content_hash: DocumentFingerprint::hash_content(&content),
+            content,
+            license_info: None,
+            metadata: serde_json::json!({ "transcript": { "segments": segments } }),
+        };
+        service.ingest_documents(&kb_id, &[doc]).await.unwrap();
+
+        // Chunks cover characters 0..512, 462..974 and 924..1229
+        let document = service.get_document_content(&kb_id, "doc_1", None).await.unwrap();
+        let ranges: Vec<Option<TimeRange>> = document.chunks.iter().map(|chunk| chunk.citation.time_range).collect();
+        assert_eq!(ranges, vec![
+            Some(TimeRange { start_ms: 0, end_ms: 65_000 }),
+            Some(TimeRange { start_ms: 55_000, end_ms: 120_000 }),
+            Some(TimeRange { start_ms: 110_000, end_ms: 150_000 }),
+        ]);
+        assert_eq!(document.citation.time_range, None);
+
+        let results = vector_service.bm25_search(&kb_id, "deploy plan", 5, None).await.unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results.iter().all(|result| result.citation.time_range.is_some()));
+    }
+
+    #[tokio::test]
+    async fn test_annotated_chunks_are_filterable() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service.clone(), Arc::new(StateManager::new()));
+        let kb_id = service.create_collection("Runbooks", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+
+        // Two chunks: the second starts inside "## Rollback"
+        let content = format!("# Deploy\n\n{}\n\n## Rollback\n\n{}", "Ship the release to staging first. ".repeat(12), "Revert with the runbook steps. ".repeat(8) + "Tracked in OPS-12.");
+        let doc = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "deploy.md".to_string(),
+            source_path: "/runbooks/deploy.md".to_string(),
+            content_hash: DocumentFingerprint::hash_content(&content),
+            content,
+            license_info: None,
+            metadata: serde_json::json!({
+                "lang": "en",
+                "annotate": { "annotators": ["heading_path"], "taggers": [{ "field": "tickets", "pattern": "OPS-\\d+" }] },
+            }),
+        };
+        service.ingest_documents(&kb_id, &[doc]).await.unwrap();
+
+        // Both chunks contain "st"
+        let results = vector_service.bm25_search(&kb_id, "st", 5, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let rollback = results.iter().find(|r| r.metadata["chunk_index"] == 1).unwrap();
+        assert_eq!(rollback.metadata["heading_path"], serde_json::json!(["Deploy", "Rollback"]));
+        assert_eq!(rollback.metadata["tickets"], serde_json::json!(["OPS-12"]));
+        assert_eq!(rollback.metadata["lang"], "en");
+
+        let filters = |value: serde_json::Value| Some(serde_json::from_value::<HashMap<String, serde_json::Value>>(value).unwrap());
+        let kept = apply_metadata_filters(results.clone(), &filters(serde_json::json!({ "tickets": "OPS-12", "lang": "de" })));
+        assert!(kept.iter().all(|r| r.metadata["chunk_index"] == 1) && !kept.is_empty());
+        let kept = apply_metadata_filters(results.clone(), &filters(serde_json::json!({ "heading_path": ["Staging", "Deploy"] })));
+        assert_eq!(kept.len(), results.len());
+        assert!(apply_metadata_filters(results, &filters(serde_json::json!({ "tickets": "OPS-99" }))).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_stats_measured_from_vector_store() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = crate::services::sql::SqlService::new(
+            crate::services::sql::SqlConfig::test_config(temp_dir.path())
+        ).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(
+            crate::services::vector::VectorDbService::new(
+                crate::services::vector::VectorDbConfig::test_config(temp_dir.path())
+            ).await.unwrap()
+        );
+        let service = KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new()));
+        let config = || KbCreateConfig {
+            description: None,
+            embedder_model: "BAAI/bge-small-en-v1.5".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        };
+        let kb_id = service.create_collection("Guides", config()).await.unwrap();
+        let empty_id = service.create_collection("Empty", config()).await.unwrap();
+
+        let content = "Install the agent before enabling sync. ".repeat(20);
+        let doc = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "install.md".to_string(),
+            source_path: "/guides/install.md".to_string(),
+            content_hash: DocumentFingerprint::hash_content(&content),
+            content,
+            license_info: None,
+            metadata: serde_json::json!({}),
+        };
+        service.ingest_documents(&kb_id, &[doc]).await.unwrap();
+
+        let stats = service.get_stats(Some(kb_id.clone()), None).await.unwrap();
+        assert_eq!((stats.document_count, stats.chunk_count, stats.vector_count), (1, 2, 0));
+        assert!(stats.size_bytes > 0);
+        assert_eq!(stats.embedder_version, "BAAI/bge-small-en-v1.5");
+
+        // A KB nothing was indexed into yet reports zeros rather than failing
+        let empty = service.get_stats(Some(empty_id), None).await.unwrap();
+        assert_eq!((empty.chunk_count, empty.size_bytes), (0, 0));
+
+        let global = service.get_stats(None, None).await.unwrap();
+        assert_eq!((global.document_count, global.chunk_count, global.size_bytes), (1, 2, stats.size_bytes));
+        assert!(matches!(service.get_stats(Some(kb_id), Some(7)).await, Err(KbError::VersionNotFound(_))));
+    }
+}
@@ -0,0 +1,28 @@
+/*!
+ * Conversation Memory Domain Errors
+ *
+ * Domain-specific error types for conversation memory.
+ */
+
+use crate::modules::kb::KbError;
+use crate::services::llm::LlmError;
+use crate::services::sql::SqlError;
+
+/// Memory Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum MemoryError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Knowledge base error: {0}")]
+    KbError(#[from] KbError),
+
+    #[error("LLM error: {0}")]
+    LlmError(#[from] LlmError),
+
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+
+    #[error("Corrupt memory record: {0}")]
+    CorruptRecord(String),
+}
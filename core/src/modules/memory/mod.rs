@@ -0,0 +1,17 @@
+/*!
+ * Conversation Memory Domain Module
+ *
+ * Per-session message history and retrieved-context summaries for
+ * conversational RAG. Sessions expire after a sliding TTL; older turns are
+ * folded into a rolling summary that is injected into answer prompts.
+ */
+
+pub mod service;
+pub mod models;
+pub mod schema;
+pub mod errors;
+
+// Re-export public types
+pub use service::MemoryService;
+pub use models::*;
+pub use errors::MemoryError;
@@ -0,0 +1,198 @@
+/*!
+ * Conversation Memory Models
+ *
+ * Messages, rolling summaries and the memory block injected into prompts.
+ */
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::schemas::SearchResult;
+
+/// Longest message text rendered into a prompt
+const MAX_PROMPT_MESSAGE_CHARS: usize = 1000;
+
+/// Sources listed per context summary
+const MAX_CONTEXT_SOURCES: usize = 5;
+
+/// Memory configuration
+#[derive(Debug, Clone)]
+pub struct MemoryConfig {
+    pub ttl_secs: i64,              // Sliding: refreshed on every append
+    pub recent_messages: usize,     // Kept verbatim; older turns are summarized
+    pub summary_max_tokens: u32,
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        Self {
+            ttl_secs: 24 * 60 * 60,
+            recent_messages: 8,
+            summary_max_tokens: 256,
+        }
+    }
+}
+
+/// Message author
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MessageRole {
+    User,
+    Assistant,
+    System,
+}
+
+impl MessageRole {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MessageRole::User => "user",
+            MessageRole::Assistant => "assistant",
+            MessageRole::System => "system",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "user" => Some(MessageRole::User),
+            "assistant" => Some(MessageRole::Assistant),
+            "system" => Some(MessageRole::System),
+            _ => None,
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            MessageRole::User => "User",
+            MessageRole::Assistant => "Assistant",
+            MessageRole::System => "System",
+        }
+    }
+}
+
+/// One turn in a session
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConversationMessage {
+    pub id: String,
+    pub session_id: String,
+    pub seq: i64,
+    pub role: MessageRole,
+    pub content: String,
+    pub context_summary: Option<String>,    // Sources retrieved for this turn
+    pub created_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Rolling summary of turns no longer kept verbatim
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub session_id: String,
+    pub summary: String,
+    pub summarized_messages: u64,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Memory for a session: summary of older turns plus the recent history
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ConversationMemory {
+    pub session_id: String,
+    pub summary: Option<String>,
+    pub messages: Vec<ConversationMessage>,
+}
+
+impl ConversationMemory {
+    pub fn is_empty(&self) -> bool {
+        self.summary.is_none() && self.messages.is_empty()
+    }
+
+    /// Memory as prompt text (empty when there is nothing to remember)
+    pub fn render(&self) -> String {
+        let mut text = String::new();
+        if let Some(summary) = &self.summary {
+            text.push_str(&format!("Conversation summary:\n{}\n\n", summary));
+        }
+        if !self.messages.is_empty() {
+            text.push_str("Recent conversation:\n");
+            for message in &self.messages {
+                let content: String = message.content.chars().take(MAX_PROMPT_MESSAGE_CHARS).collect();
+                text.push_str(&format!("{}: {}\n", message.role.label(), content));
+                if let Some(context) = &message.context_summary {
+                    text.push_str(&format!("  (sources: {})\n", context));
+                }
+            }
+        }
+        text
+    }
+
+    /// Prepend the memory to an answer prompt; the sources stay the only citable material
+    pub fn inject(&self, prompt: &str) -> String {
+        if self.is_empty() {
+            return prompt.to_string();
+        }
+        format!(
+            "Use the conversation below only to understand follow-up questions; cite only the numbered sources.\n\n{}\n{}",
+            self.render(),
+            prompt
+        )
+    }
+}
+
+/// Short description of the sources retrieved for a turn
+pub fn summarize_context(results: &[SearchResult]) -> Option<String> {
+    if results.is_empty() {
+        return None;
+    }
+    let mut sources: Vec<String> = results.iter()
+        .take(MAX_CONTEXT_SOURCES)
+        .map(|r| format!("{} ({})", r.citation.title, r.chunk_id))
+        .collect();
+    if results.len() > MAX_CONTEXT_SOURCES {
+        sources.push(format!("+{} more", results.len() - MAX_CONTEXT_SOURCES));
+    }
+    Some(sources.join("; "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn message(role: MessageRole, content: &str, context: Option<&str>) -> ConversationMessage {
+        ConversationMessage {
+            id: "msg_1".to_string(),
+            session_id: "s1".to_string(),
+            seq: 1,
+            role,
+            content: content.to_string(),
+            context_summary: context.map(str::to_string),
+            created_at: Utc::now(),
+            expires_at: Utc::now(),
+        }
+    }
+
+    #[test]
+    fn test_inject_memory() {
+        let empty = ConversationMemory::default();
+        assert_eq!(empty.inject("Question: q"), "Question: q");
+
+        let memory = ConversationMemory {
+            session_id: "s1".to_string(),
+            summary: Some("User is installing on Linux.".to_string()),
+            messages: vec![
+                message(MessageRole::User, "Which port does it use?", None),
+                message(MessageRole::Assistant, "Port 3000 [1].", Some("Config guide (c1)")),
+            ],
+        };
+        let prompt = memory.inject("Question: can I change it?");
+        assert!(prompt.contains("Conversation summary:\nUser is installing on Linux."));
+        assert!(prompt.contains("User: Which port does it use?\nAssistant: Port 3000 [1].\n  (sources: Config guide (c1))"));
+        assert!(prompt.ends_with("Question: can I change it?"));
+    }
+
+    #[test]
+    fn test_role_round_trip() {
+        for role in [MessageRole::User, MessageRole::Assistant, MessageRole::System] {
+            assert_eq!(MessageRole::parse(role.as_str()), Some(role));
+        }
+        assert_eq!(MessageRole::parse("tool"), None);
+    }
+}
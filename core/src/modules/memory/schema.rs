@@ -0,0 +1,91 @@
+/*!
+ * Conversation Memory Schema
+ *
+ * Diesel row types for the conversation_messages and conversation_summaries tables.
+ */
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::{conversation_messages, conversation_summaries};
+use super::errors::MemoryError;
+use super::models::{ConversationMessage, MessageRole, SessionSummary};
+
+/// Row in conversation_messages
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = conversation_messages)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct ConversationMessageRow {
+    pub id: String,
+    pub session_id: String,
+    pub seq: i64,
+    pub role: String,
+    pub content: String,
+    pub context_summary: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl ConversationMessageRow {
+    pub fn from_model(message: &ConversationMessage) -> Self {
+        Self {
+            id: message.id.clone(),
+            session_id: message.session_id.clone(),
+            seq: message.seq,
+            role: message.role.as_str().to_string(),
+            content: message.content.clone(),
+            context_summary: message.context_summary.clone(),
+            created_at: message.created_at.naive_utc(),
+            expires_at: message.expires_at.naive_utc(),
+        }
+    }
+
+    pub fn into_model(self) -> Result<ConversationMessage, MemoryError> {
+        let role = MessageRole::parse(&self.role)
+            .ok_or_else(|| MemoryError::CorruptRecord(format!("unknown role '{}' in message {}", self.role, self.id)))?;
+        Ok(ConversationMessage {
+            id: self.id,
+            session_id: self.session_id,
+            seq: self.seq,
+            role,
+            content: self.content,
+            context_summary: self.context_summary,
+            created_at: DateTime::<Utc>::from_naive_utc_and_offset(self.created_at, Utc),
+            expires_at: DateTime::<Utc>::from_naive_utc_and_offset(self.expires_at, Utc),
+        })
+    }
+}
+
+/// Row in conversation_summaries
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = conversation_summaries)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+pub struct SessionSummaryRow {
+    pub session_id: String,
+    pub summary: String,
+    pub summarized_messages: i64,
+    pub updated_at: NaiveDateTime,
+    pub expires_at: NaiveDateTime,
+}
+
+impl SessionSummaryRow {
+    pub fn from_model(summary: &SessionSummary) -> Self {
+        Self {
+            session_id: summary.session_id.clone(),
+            summary: summary.summary.clone(),
+            summarized_messages: summary.summarized_messages as i64,
+            updated_at: summary.updated_at.naive_utc(),
+            expires_at: summary.expires_at.naive_utc(),
+        }
+    }
+
+    pub fn into_model(self) -> SessionSummary {
+        SessionSummary {
+            session_id: self.session_id,
+            summary: self.summary,
+            summarized_messages: self.summarized_messages.max(0) as u64,
+            updated_at: DateTime::<Utc>::from_naive_utc_and_offset(self.updated_at, Utc),
+            expires_at: DateTime::<Utc>::from_naive_utc_and_offset(self.expires_at, Utc),
+        }
+    }
+}
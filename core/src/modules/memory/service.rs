@@ -0,0 +1,382 @@
+/*!
+ * Conversation Memory Service
+ *
+ * Stores per-session messages in SQLite with a sliding TTL, folds turns older
+ * than the recent window into a rolling summary (LLM when available, else
+ * extractive) and answers follow-up questions with the memory injected into
+ * the grounded prompt.
+ */
+
+use std::sync::Arc;
+use chrono::{Duration, Utc};
+use diesel::prelude::*;
+use tracing::{info, warn};
+
+use super::errors::MemoryError;
+use super::models::*;
+use super::schema::{ConversationMessageRow, SessionSummaryRow};
+use crate::modules::kb::answer::{build_grounded_prompt, cite_answer, NO_SOURCES_ANSWER};
+use crate::modules::kb::context::{pack_context, ContextBudget, EstimatingTokenizer};
+use crate::modules::kb::{AnswerRequest, GroundedAnswer, KbService};
+use crate::schemas::schema::{conversation_messages, conversation_summaries};
+use crate::schemas::SearchResult;
+use crate::services::generation::GenerationParams;
+use crate::services::llm::{LlmProvider, LlmProviderConfig, LlmService};
+use crate::services::sql::SqlService;
+
+/// Longest extractive summary kept (oldest text is dropped first)
+const MAX_SUMMARY_CHARS: usize = 2000;
+
+/// Per-message text kept in an extractive summary
+const EXTRACTIVE_MESSAGE_CHARS: usize = 200;
+
+const SUMMARY_INSTRUCTIONS: &str =
+    "Summarize the conversation below in a few sentences for later reference. Keep names, versions, settings and decisions. Reply with the summary only.";
+
+/// Conversation memory service
+pub struct MemoryService {
+    sql_service: Arc<SqlService>,
+    config: MemoryConfig,
+}
+
+impl MemoryService {
+    pub fn new(sql_service: Arc<SqlService>) -> Self {
+        Self::with_config(sql_service, MemoryConfig::default())
+    }
+
+    pub fn with_config(sql_service: Arc<SqlService>, config: MemoryConfig) -> Self {
+        Self { sql_service, config }
+    }
+
+    /// Append a turn and refresh the session's TTL
+    pub async fn append(
+        &self,
+        session_id: &str,
+        role: MessageRole,
+        content: &str,
+        context: &[SearchResult],
+    ) -> Result<ConversationMessage, MemoryError> {
+        if session_id.trim().is_empty() {
+            return Err(MemoryError::InvalidInput("Session id cannot be empty".to_string()));
+        }
+        if content.trim().is_empty() {
+            return Err(MemoryError::InvalidInput("Message cannot be empty".to_string()));
+        }
+
+        let now = Utc::now();
+        let mut message = ConversationMessage {
+            id: format!("msg_{}", uuid::Uuid::new_v4().simple()),
+            session_id: session_id.to_string(),
+            seq: 0,
+            role,
+            content: content.to_string(),
+            context_summary: summarize_context(context),
+            created_at: now,
+            expires_at: now + Duration::seconds(self.config.ttl_secs),
+        };
+
+        let mut row = ConversationMessageRow::from_model(&message);
+        let seq = self.sql_service.with_app_transaction(move |conn| {
+            let last: Option<i64> = conversation_messages::table
+                .filter(conversation_messages::session_id.eq(&row.session_id))
+                .select(diesel::dsl::max(conversation_messages::seq))
+                .first(conn)?;
+            row.seq = last.unwrap_or(0) + 1;
+
+            diesel::insert_into(conversation_messages::table)
+                .values(&row)
+                .execute(conn)?;
+            // Sliding TTL: the whole session lives as long as its newest turn
+            diesel::update(conversation_messages::table.filter(conversation_messages::session_id.eq(&row.session_id)))
+                .set(conversation_messages::expires_at.eq(row.expires_at))
+                .execute(conn)?;
+            diesel::update(conversation_summaries::table.filter(conversation_summaries::session_id.eq(&row.session_id)))
+                .set(conversation_summaries::expires_at.eq(row.expires_at))
+                .execute(conn)?;
+            Ok(row.seq)
+        }).await?;
+
+        message.seq = seq;
+        Ok(message)
+    }
+
+    /// Unexpired messages of a session, oldest first
+    pub async fn history(&self, session_id: &str) -> Result<Vec<ConversationMessage>, MemoryError> {
+        let session_id = session_id.to_string();
+        let now = Utc::now().naive_utc();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            Ok(conversation_messages::table
+                .filter(conversation_messages::session_id.eq(session_id))
+                .filter(conversation_messages::expires_at.gt(now))
+                .order(conversation_messages::seq.asc())
+                .select(ConversationMessageRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(ConversationMessageRow::into_model).collect()
+    }
+
+    /// Rolling summary of a session, if any
+    pub async fn get_summary(&self, session_id: &str) -> Result<Option<SessionSummary>, MemoryError> {
+        let session_id = session_id.to_string();
+        let now = Utc::now().naive_utc();
+        let row = self.sql_service.with_app_transaction(move |conn| {
+            Ok(conversation_summaries::table
+                .filter(conversation_summaries::session_id.eq(session_id))
+                .filter(conversation_summaries::expires_at.gt(now))
+                .select(SessionSummaryRow::as_select())
+                .first(conn)
+                .optional()?)
+        }).await?;
+
+        Ok(row.map(SessionSummaryRow::into_model))
+    }
+
+    /// Summary plus the most recent turns, ready for prompt injection
+    pub async fn memory(&self, session_id: &str) -> Result<ConversationMemory, MemoryError> {
+        let mut messages = self.history(session_id).await?;
+        let skip = messages.len().saturating_sub(self.config.recent_messages);
+        messages.drain(..skip);
+
+        Ok(ConversationMemory {
+            session_id: session_id.to_string(),
+            summary: self.get_summary(session_id).await?.map(|s| s.summary),
+            messages,
+        })
+    }
+
+    /// Fold turns older than the recent window into the session summary
+    ///
+    /// Uses the LLM when given one, otherwise (or if it returns nothing) an
+    /// extractive summary. Returns the current summary.
+    pub async fn summarize(
+        &self,
+        session_id: &str,
+        llm: Option<&dyn LlmProvider>,
+    ) -> Result<Option<SessionSummary>, MemoryError> {
+        let messages = self.history(session_id).await?;
+        let existing = self.get_summary(session_id).await?;
+        if messages.len() <= self.config.recent_messages {
+            return Ok(existing);
+        }
+        let folded = &messages[..messages.len() - self.config.recent_messages];
+
+        let transcript: String = folded.iter()
+            .map(|m| format!("{}: {}", m.role.as_str(), m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let generated = match llm {
+            Some(llm) => {
+                let mut prompt = String::from(SUMMARY_INSTRUCTIONS);
+                if let Some(existing) = &existing {
+                    prompt.push_str(&format!("\n\nEarlier summary:\n{}", existing.summary));
+                }
+                prompt.push_str(&format!("\n\nConversation:\n{}\n\nSummary:", transcript));
+                let params = GenerationParams { max_tokens: self.config.summary_max_tokens, temperature: 0.0, ..Default::default() };
+                llm.generate(&prompt, &params).await?.text.trim().to_string()
+            }
+            None => String::new(),
+        };
+        let summary = if generated.is_empty() {
+            extractive_summary(existing.as_ref().map(|s| s.summary.as_str()), folded)
+        } else {
+            generated
+        };
+
+        let now = Utc::now();
+        let summary = SessionSummary {
+            session_id: session_id.to_string(),
+            summary,
+            summarized_messages: existing.as_ref().map(|s| s.summarized_messages).unwrap_or(0) + folded.len() as u64,
+            updated_at: now,
+            expires_at: messages.last().map(|m| m.expires_at).unwrap_or(now),
+        };
+        let row = SessionSummaryRow::from_model(&summary);
+        let folded_ids: Vec<String> = folded.iter().map(|m| m.id.clone()).collect();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::replace_into(conversation_summaries::table)
+                .values(&row)
+                .execute(conn)?;
+            diesel::delete(conversation_messages::table.filter(conversation_messages::id.eq_any(folded_ids)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        info!("Summarized {} messages of session {}", folded.len(), session_id);
+        Ok(Some(summary))
+    }
+
+    /// Answer a follow-up question with the session's memory in the prompt,
+    /// then record both turns
+    ///
+    /// MVP: retrieval uses the question as asked; use a flow with a
+    /// query_rewrite node to condense follow-ups into standalone queries.
+    pub async fn answer(
+        &self,
+        session_id: &str,
+        kb_service: &dyn KbService,
+        llm_service: &LlmService,
+        provider: &LlmProviderConfig,
+        request: &AnswerRequest,
+    ) -> Result<GroundedAnswer, MemoryError> {
+        // Resolve the provider up front so network policy violations fail before any work
+        let llm = llm_service.provider(provider)?;
+        let memory = self.memory(session_id).await?;
+
+        let retrieved = kb_service.hybrid_search(&request.collection, &request.question, request.top_k, None, None).await?;
+        let budget = request.context_tokens.map(ContextBudget::new).unwrap_or_default();
+        let sources = pack_context(&retrieved, &budget, &EstimatingTokenizer).sources;
+        // Never let the model answer without sources
+        let answer = if sources.is_empty() {
+            cite_answer(NO_SOURCES_ANSWER, &sources)
+        } else {
+            let prompt = memory.inject(&build_grounded_prompt(&request.question, &sources));
+            let output = llm.generate(&prompt, &request.params).await?;
+            cite_answer(&output.text, &sources)
+        };
+
+        self.append(session_id, MessageRole::User, &request.question, &sources).await?;
+        self.append(session_id, MessageRole::Assistant, &answer.answer, &[]).await?;
+
+        // A failed summary only costs prompt space; keep the answer
+        if let Err(e) = self.summarize(session_id, Some(llm.as_ref())).await {
+            warn!("Failed to summarize session {}: {}", session_id, e);
+        }
+        Ok(answer)
+    }
+
+    /// Forget a session
+    pub async fn clear_session(&self, session_id: &str) -> Result<(), MemoryError> {
+        let session_id = session_id.to_string();
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::delete(conversation_messages::table.filter(conversation_messages::session_id.eq(&session_id)))
+                .execute(conn)?;
+            diesel::delete(conversation_summaries::table.filter(conversation_summaries::session_id.eq(&session_id)))
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+        Ok(())
+    }
+
+    /// Delete expired messages and summaries; returns the rows removed
+    pub async fn purge_expired(&self) -> Result<usize, MemoryError> {
+        let now = Utc::now().naive_utc();
+        let removed = self.sql_service.with_app_transaction(move |conn| {
+            let messages = diesel::delete(conversation_messages::table.filter(conversation_messages::expires_at.le(now)))
+                .execute(conn)?;
+            let summaries = diesel::delete(conversation_summaries::table.filter(conversation_summaries::expires_at.le(now)))
+                .execute(conn)?;
+            Ok(messages + summaries)
+        }).await?;
+
+        if removed > 0 {
+            info!("Purged {} expired conversation memory rows", removed);
+        }
+        Ok(removed)
+    }
+}
+
+/// Earlier summary plus one truncated line per folded turn, newest text kept
+fn extractive_summary(existing: Option<&str>, folded: &[ConversationMessage]) -> String {
+    let mut lines: Vec<String> = existing.map(|s| vec![s.to_string()]).unwrap_or_default();
+    lines.extend(folded.iter().map(|m| {
+        let content: String = m.content.chars().take(EXTRACTIVE_MESSAGE_CHARS).collect();
+        format!("{}: {}", m.role.as_str(), content)
+    }));
+
+    let summary = lines.join("\n");
+    let excess = summary.chars().count().saturating_sub(MAX_SUMMARY_CHARS);
+    summary.chars().skip(excess).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use async_trait::async_trait;
+    use tempfile::TempDir;
+    use crate::services::generation::GenerationOutput;
+    use crate::services::llm::LlmError;
+    use crate::services::sql::SqlConfig;
+
+    /// Provider that echoes a canned reply
+    struct CannedLlm(&'static str);
+
+    #[async_trait]
+    impl LlmProvider for CannedLlm {
+        fn name(&self) -> String {
+            "canned".to_string()
+        }
+
+        fn is_remote(&self) -> bool {
+            false
+        }
+
+        async fn generate(&self, _prompt: &str, _params: &GenerationParams) -> Result<GenerationOutput, LlmError> {
+            Ok(GenerationOutput { text: self.0.to_string(), model: "canned".to_string(), duration_ms: 0 })
+        }
+    }
+
+    async fn service(temp_dir: &TempDir, config: MemoryConfig) -> MemoryService {
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        MemoryService::with_config(Arc::new(sql_service), config)
+    }
+
+    #[tokio::test]
+    async fn test_append_and_history() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = service(&temp_dir, MemoryConfig::default()).await;
+
+        memory.append("s1", MessageRole::User, "Which port?", &[]).await.unwrap();
+        let reply = memory.append("s1", MessageRole::Assistant, "Port 3000 [1].", &[]).await.unwrap();
+        memory.append("s2", MessageRole::User, "Other session", &[]).await.unwrap();
+        assert_eq!(reply.seq, 2);
+
+        let history = memory.history("s1").await.unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].role, MessageRole::User);
+        assert_eq!(history[1].content, "Port 3000 [1].");
+
+        assert!(matches!(memory.append("s1", MessageRole::User, " ", &[]).await, Err(MemoryError::InvalidInput(_))));
+
+        memory.clear_session("s1").await.unwrap();
+        assert!(memory.history("s1").await.unwrap().is_empty());
+        assert_eq!(memory.history("s2").await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_folds_old_turns() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = MemoryConfig { recent_messages: 2, ..Default::default() };
+        let memory = service(&temp_dir, config).await;
+
+        for content in ["one", "two", "three"] {
+            memory.append("s1", MessageRole::User, content, &[]).await.unwrap();
+        }
+        // Extractive without a provider
+        let summary = memory.summarize("s1", None).await.unwrap().unwrap();
+        assert_eq!(summary.summary, "user: one");
+        assert_eq!(summary.summarized_messages, 1);
+
+        memory.append("s1", MessageRole::User, "four", &[]).await.unwrap();
+        let llm = CannedLlm("User counted to three.");
+        let summary = memory.summarize("s1", Some(&llm)).await.unwrap().unwrap();
+        assert_eq!(summary.summary, "User counted to three.");
+        assert_eq!(summary.summarized_messages, 2);
+
+        let state = memory.memory("s1").await.unwrap();
+        assert_eq!(state.summary.as_deref(), Some("User counted to three."));
+        let contents: Vec<&str> = state.messages.iter().map(|m| m.content.as_str()).collect();
+        assert_eq!(contents, vec!["three", "four"]);
+    }
+
+    #[tokio::test]
+    async fn test_expired_sessions_are_purged() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = service(&temp_dir, MemoryConfig { ttl_secs: -1, ..Default::default() }).await;
+
+        memory.append("s1", MessageRole::User, "gone", &[]).await.unwrap();
+        assert!(memory.history("s1").await.unwrap().is_empty());
+        assert_eq!(memory.purge_expired().await.unwrap(), 1);
+    }
+}
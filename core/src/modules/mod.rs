@@ -6,10 +6,23 @@
  */
 
 pub mod kb;
+pub mod pipeline;
+pub mod eval;
+pub mod audit;
+pub mod flow;
+pub mod memory;
+pub mod schedule;
+pub mod graph;
 
 // Future domain modules:
 // pub mod auth;
-// pub mod flow;
 
 // Re-export commonly used domain types
-pub use kb::{KbService, KbServiceImpl, KbError, KbConfig, DocumentInfo, KbStats, KbInfo};
\ No newline at end of file
+pub use kb::{KbService, KbServiceImpl, KbError, KbConfig, DocumentInfo, KbStats, KbInfo};
+pub use pipeline::{PipelineRunner, PipelineSpec, PipelineError, StepExecutor};
+pub use eval::{EvalService, EvalError, EvalRun, GoldenQuery};
+pub use audit::{AuditService, AuditError, McpAuditEntry};
+pub use flow::{FlowService, FlowError, FlowSpec, FlowRunOutput};
+pub use memory::{MemoryService, MemoryError, ConversationMemory};
+pub use schedule::{RefreshService, ScheduleError, RefreshOutcome};
+pub use graph::{GraphService, GraphError, QueryExpansion};
\ No newline at end of file
@@ -0,0 +1,56 @@
+/*!
+ * Pipeline Domain Errors
+ *
+ * Domain-specific error types for pipeline execution.
+ */
+
+use crate::modules::graph::GraphError;
+use crate::services::network_policy::PolicyError;
+use crate::services::vector::VectorDbError;
+
+/// Pipeline Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum PipelineError {
+    #[error("Step execution failed: {0}")]
+    StepFailed(String),
+
+    #[error("Resource limit exceeded: {0}")]
+    ResourceLimitExceeded(String),
+
+    #[error("Invalid step configuration: {0}")]
+    InvalidConfig(String),
+
+    #[error("Quality gate failed: {0}")]
+    QualityGateFailed(String, serde_json::Value),   // message, evaluation report
+
+    #[error("Vector database error: {0}")]
+    VectorError(#[from] VectorDbError),
+
+    #[error("Knowledge graph error: {0}")]
+    GraphError(#[from] GraphError),
+
+    #[error("Path outside the pipeline's source roots: {0}")]
+    PathNotAllowed(String),
+
+    #[error("Pipeline cancelled: {0}")]
+    Cancelled(String),
+
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+}
+
+impl PipelineError {
+    /// Step report attached to the error, if any
+    pub fn details(&self) -> Option<&serde_json::Value> {
+        match self {
+            PipelineError::QualityGateFailed(_, report) => Some(report),
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,350 @@
+/*!
+ * Pipeline Step Execution
+ *
+ * StepExecutor trait for individual ETL steps and a runner that executes a
+ * pipeline spec step by step, collecting `StepRunMetrics` for each step.
+ * Fetch steps must pass the network policy (`web_fetch`) before they run.
+ */
+
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Instant;
+use async_trait::async_trait;
+use chrono::Utc;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use super::errors::PipelineError;
+use super::models::*;
+use super::resources::ResourceGuard;
+use super::sandbox::SourceSandbox;
+use crate::services::metrics::MetricsService;
+use crate::services::network_policy::{NetworkFeature, NetworkPolicy};
+
+/// Execution context handed to each step
+pub struct StepContext {
+    pub run_id: String,
+    pub pipeline_id: String,
+    pub kb_id: Option<String>,
+    pub step: PipelineStepConfig,
+    pub resources: PipelineResources,
+    downgrade: Arc<AtomicBool>,
+    sandbox: SourceSandbox,
+}
+
+impl StepContext {
+    pub fn new(
+        run_id: &str,
+        spec: &PipelineSpec,
+        step: PipelineStepConfig,
+        downgrade: Arc<AtomicBool>,
+    ) -> Self {
+        let resources = spec.resources_for(&step);
+        Self {
+            run_id: run_id.to_string(),
+            pipeline_id: spec.id.clone(),
+            kb_id: spec.kb_id.clone(),
+            step,
+            resources,
+            downgrade,
+            sandbox: SourceSandbox::new(&spec.source_roots),
+        }
+    }
+
+    /// Step-specific configuration
+    pub fn config(&self) -> &serde_json::Value {
+        &self.step.config
+    }
+
+    /// Paths this run may read; filesystem access must go through it
+    pub fn sandbox(&self) -> &SourceSandbox {
+        &self.sandbox
+    }
+
+    /// True once the resource guard has asked the step to reduce memory usage
+    pub fn downgrade_requested(&self) -> bool {
+        self.downgrade.load(Ordering::SeqCst)
+    }
+}
+
+/// Executor for a single ETL step type
+#[async_trait]
+pub trait StepExecutor: Send + Sync {
+    fn step_type(&self) -> ETLStepType;
+
+    /// Whether the step reacts to `StepContext::downgrade_requested`; steps
+    /// that cannot shrink their working set are aborted at their budget
+    fn supports_downgrade(&self) -> bool {
+        false
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError>;
+}
+
+/// Output of a full pipeline run
+#[derive(Debug, Clone)]
+pub struct PipelineRunOutput {
+    pub data: StepData,
+    pub report: PipelineRunReport,
+    pub error: Option<String>,
+}
+
+impl PipelineRunOutput {
+    pub fn is_success(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Sequential pipeline runner (MVP: one step at a time)
+pub struct PipelineRunner {
+    executors: HashMap<ETLStepType, Arc<dyn StepExecutor>>,
+    metrics_service: Option<Arc<MetricsService>>,
+    network_policy: Option<Arc<NetworkPolicy>>,
+    cancelled: watch::Sender<bool>,     // Set once on shutdown; runs stop at the current step
+}
+
+impl PipelineRunner {
+    pub fn new() -> Self {
+        Self {
+            executors: HashMap::new(),
+            metrics_service: None,
+            network_policy: None,
+            cancelled: watch::channel(false).0,
+        }
+    }
+
+    /// Stop every run in flight and refuse new ones (used on shutdown)
+    pub fn cancel_all(&self) {
+        self.cancelled.send_replace(true);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+
+    /// Refuse fetch steps while the policy blocks web fetches
+    pub fn with_network_policy(mut self, policy: Arc<NetworkPolicy>) -> Self {
+        self.network_policy = Some(policy);
+        self
+    }
+
+    /// Record run durations and failures
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    /// Register an executor for its step type
+    pub fn register(&mut self, executor: Arc<dyn StepExecutor>) {
+        self.executors.insert(executor.step_type(), executor);
+    }
+
+    /// Run a single step under its resource budget
+    pub async fn run_step(
+        &self,
+        spec: &PipelineSpec,
+        step: &PipelineStepConfig,
+        run_id: &str,
+        data: StepData,
+    ) -> (Result<StepOutcome, PipelineError>, StepRunMetrics) {
+        let started_at = Utc::now();
+        let resources = spec.resources_for(step);
+        let executor = self.executors.get(&step.step_type);
+        let action = match executor {
+            Some(executor) if executor.supports_downgrade() => step.on_limit,
+            _ => ResourceLimitAction::Abort,
+        };
+        let guard = ResourceGuard::new(&resources, action);
+        let ctx = StepContext::new(run_id, spec, step.clone(), guard.downgrade_flag());
+
+        // Fetch steps leave the machine
+        let blocked = match (&self.network_policy, step.step_type) {
+            (Some(policy), ETLStepType::Fetch) => {
+                let target = step.config.get("url").and_then(|v| v.as_str()).unwrap_or(&spec.id);
+                policy.check(NetworkFeature::WebFetch, target).err()
+            }
+            _ => None,
+        };
+
+        let (result, enforcement) = match (blocked, executor) {
+            (Some(e), _) => guard.run(async { Err(PipelineError::from(e)) }).await,
+            (None, Some(executor)) => guard.run(executor.execute(&ctx, data)).await,
+            (None, None) => guard.run(async {
+                Err(PipelineError::InvalidConfig(format!(
+                    "No executor registered for step: {}",
+                    step.step_type.as_str()
+                )))
+            }).await,
+        };
+
+        let completed_at = Utc::now();
+        let (items_processed, details) = match &result {
+            Ok(outcome) => (outcome.items_processed, outcome.details.clone()),
+            Err(e) => (0, e.details().cloned().unwrap_or_else(|| serde_json::json!({}))),
+        };
+
+        let metrics = StepRunMetrics {
+            step_type: step.step_type,
+            started_at,
+            completed_at,
+            duration_ms: (completed_at - started_at).num_milliseconds().max(0) as u64,
+            items_processed,
+            success: result.is_ok(),
+            error: result.as_ref().err().map(|e| e.to_string()),
+            resource_enforcement: enforcement,
+            details,
+        };
+
+        (result, metrics)
+    }
+
+    /// Run all steps of a pipeline, stopping at the first failure
+    pub async fn run(&self, spec: &PipelineSpec, run_id: &str, data: StepData) -> PipelineRunOutput {
+        info!("Starting pipeline run {} for pipeline {}", run_id, spec.id);
+
+        let started = Instant::now();
+        let mut report = PipelineRunReport::default();
+        let mut data = data;
+
+        let mut cancelled = self.cancelled.subscribe();
+        for step in &spec.steps {
+            let step_run = self.run_step(spec, step, run_id, data.clone());
+            let (result, metrics) = tokio::select! {
+                biased;
+                _ = cancelled.wait_for(|cancelled| *cancelled) => {
+                    warn!("Pipeline run {} cancelled at step {}", run_id, step.step_type.as_str());
+                    if let Some(metrics) = &self.metrics_service {
+                        metrics.record_pipeline_run(started.elapsed(), false);
+                    }
+                    return PipelineRunOutput {
+                        data,
+                        report,
+                        error: Some(PipelineError::Cancelled(run_id.to_string()).to_string()),
+                    };
+                }
+                done = step_run => done,
+            };
+            report.steps.push(metrics);
+
+            match result {
+                Ok(outcome) => {
+                    *report.counters.entry(format!("{}_items", step.step_type.as_str())).or_insert(0) +=
+                        outcome.items_processed as u64;
+                    data = outcome.data;
+                }
+                Err(e) => {
+                    warn!("Pipeline run {} failed at step {}: {}", run_id, step.step_type.as_str(), e);
+                    if let Some(metrics) = &self.metrics_service {
+                        metrics.record_pipeline_run(started.elapsed(), false);
+                    }
+                    return PipelineRunOutput {
+                        data,
+                        report,
+                        error: Some(e.to_string()),
+                    };
+                }
+            }
+        }
+
+        info!("Pipeline run {} completed ({} steps)", run_id, report.steps.len());
+        if let Some(metrics) = &self.metrics_service {
+            metrics.record_pipeline_run(started.elapsed(), true);
+        }
+        PipelineRunOutput {
+            data,
+            report,
+            error: None,
+        }
+    }
+}
+
+impl Default for PipelineRunner {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct CountingStep;
+
+    #[async_trait]
+    impl StepExecutor for CountingStep {
+        fn step_type(&self) -> ETLStepType {
+            ETLStepType::Normalize
+        }
+
+        async fn execute(&self, _ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+            let count = data.documents.len();
+            Ok(StepOutcome {
+                data,
+                items_processed: count,
+                details: serde_json::json!({"seen": count}),
+            })
+        }
+    }
+
+    fn spec_with(steps: Vec<ETLStepType>) -> PipelineSpec {
+        PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Test".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: steps.into_iter().map(|step_type| PipelineStepConfig {
+                step_type,
+                config: serde_json::json!({}),
+                resources: None,
+                on_limit: ResourceLimitAction::Abort,
+            }).collect(),
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_records_step_metrics() {
+        let mut runner = PipelineRunner::new();
+        runner.register(Arc::new(CountingStep));
+
+        let output = runner.run(&spec_with(vec![ETLStepType::Normalize]), "run_1", StepData::default()).await;
+
+        assert!(output.is_success());
+        assert_eq!(output.report.steps.len(), 1);
+        assert!(output.report.steps[0].success);
+        assert_eq!(output.report.steps[0].resource_enforcement.outcome, EnforcementOutcome::Unbounded);
+    }
+
+    #[tokio::test]
+    async fn test_missing_executor_fails_run() {
+        let runner = PipelineRunner::new();
+        let output = runner.run(&spec_with(vec![ETLStepType::Embed]), "run_2", StepData::default()).await;
+
+        assert!(!output.is_success());
+        assert_eq!(output.report.steps.len(), 1);
+        assert!(!output.report.steps[0].success);
+        assert!(output.error.unwrap().contains("No executor registered"));
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_runner_stops_runs() {
+        let mut runner = PipelineRunner::new();
+        runner.register(Arc::new(CountingStep));
+        runner.cancel_all();
+
+        let output = runner.run(&spec_with(vec![ETLStepType::Normalize]), "run_3", StepData::default()).await;
+
+        assert!(output.report.steps.is_empty());
+        assert!(output.error.unwrap().contains("cancelled"));
+    }
+
+    #[tokio::test]
+    async fn test_fetch_blocked_when_air_gapped() {
+        let runner = PipelineRunner::new().with_network_policy(Arc::new(NetworkPolicy::new(true)));
+        let output = runner.run(&spec_with(vec![ETLStepType::Fetch]), "run_3", StepData::default()).await;
+
+        assert!(!output.is_success());
+        assert!(output.error.unwrap().contains("web_fetch is blocked"));
+    }
+}
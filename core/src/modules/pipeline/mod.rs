@@ -0,0 +1,24 @@
+/*!
+ * Pipeline Domain Module
+ *
+ * ETL pipeline step execution (fetch → parse → normalize → chunk → annotate →
+ * redact → extract_entities → embed → index → eval → summarize → pack) with
+ * per-step resource enforcement.
+ */
+
+pub mod models;
+pub mod errors;
+pub mod executor;
+pub mod resources;
+pub mod sandbox;
+pub mod steps;
+pub mod templates;
+
+// Re-export public types
+pub use models::*;
+pub use errors::PipelineError;
+pub use executor::{StepExecutor, StepContext, PipelineRunner, PipelineRunOutput};
+pub use resources::ResourceGuard;
+pub use sandbox::SourceSandbox;
+pub use templates::{pipeline_template, pipeline_templates, PipelineTemplate};
+pub use steps::{summary_index_id, AnnotateStepExecutor, EmbedStepExecutor, EvalStepExecutor, ExtractEntitiesStepExecutor, FetchCursorStore, FetchStepExecutor, NormalizeStepExecutor, ParseStepExecutor, RedactStepExecutor, SummarizeStepExecutor, TesseractOcrEngine, WhisperTranscriber};
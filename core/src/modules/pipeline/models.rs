@@ -0,0 +1,241 @@
+/*!
+ * Pipeline Domain Models
+ *
+ * Data structures for pipeline definitions, step data, and run metrics.
+ */
+
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+
+/// ETL step types (FR-3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ETLStepType {
+    Fetch,
+    Parse,
+    Normalize,
+    Chunk,
+    Annotate,
+    Redact,
+    ExtractEntities,
+    Embed,
+    Index,
+    Eval,
+    Summarize,
+    Pack,
+}
+
+impl ETLStepType {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ETLStepType::Fetch => "fetch",
+            ETLStepType::Parse => "parse",
+            ETLStepType::Normalize => "normalize",
+            ETLStepType::Chunk => "chunk",
+            ETLStepType::Annotate => "annotate",
+            ETLStepType::Redact => "redact",
+            ETLStepType::ExtractEntities => "extract_entities",
+            ETLStepType::Embed => "embed",
+            ETLStepType::Index => "index",
+            ETLStepType::Eval => "eval",
+            ETLStepType::Summarize => "summarize",
+            ETLStepType::Pack => "pack",
+        }
+    }
+}
+
+/// Declared resource budget for a pipeline or a single step
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineResources {
+    pub cpu_cores: Option<f32>,      // Budget for average cores used during the step
+    pub memory_mb: Option<u64>,      // Budget for memory growth during the step
+    pub disk_mb: Option<u64>,        // Budget for bytes written during the step
+}
+
+/// What to do when a step exceeds its resource budget
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ResourceLimitAction {
+    /// Fail the step immediately
+    Abort,
+    /// Ask the step to reduce its working set, abort if it keeps growing
+    #[default]
+    Downgrade,
+}
+
+/// Step configuration within a pipeline definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineStepConfig {
+    pub step_type: ETLStepType,
+    #[serde(default)]
+    pub config: serde_json::Value,
+    #[serde(default)]
+    pub resources: Option<PipelineResources>,
+    #[serde(default)]
+    pub on_limit: ResourceLimitAction,
+}
+
+/// Pipeline definition
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineSpec {
+    pub id: String,
+    pub name: String,
+    pub kb_id: Option<String>,
+    pub steps: Vec<PipelineStepConfig>,
+    #[serde(default)]
+    pub resources: PipelineResources,
+    #[serde(default)]
+    pub source_roots: Vec<String>,      // Directories fetch/parse steps may read
+}
+
+impl PipelineSpec {
+    /// Resolve effective resources for a step (step overrides pipeline defaults)
+    pub fn resources_for(&self, step: &PipelineStepConfig) -> PipelineResources {
+        let step_resources = step.resources.clone().unwrap_or_default();
+        PipelineResources {
+            cpu_cores: step_resources.cpu_cores.or(self.resources.cpu_cores),
+            memory_mb: step_resources.memory_mb.or(self.resources.memory_mb),
+            disk_mb: step_resources.disk_mb.or(self.resources.disk_mb),
+        }
+    }
+}
+
+/// Document flowing through the pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineDocument {
+    pub id: String,
+    pub title: String,
+    pub source_path: String,
+    pub content: String,
+    pub content_hash: String,
+    pub license_info: Option<String>,
+    pub metadata: serde_json::Value,
+}
+
+/// Chunk flowing through the pipeline
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineChunk {
+    pub id: String,
+    pub document_id: String,
+    pub chunk_index: i32,
+    pub content: String,
+    pub content_hash: String,
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub embedding: Vec<f32>,            // Set by the embed step
+}
+
+/// Data handed from one step to the next
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct StepData {
+    pub documents: Vec<PipelineDocument>,
+    pub chunks: Vec<PipelineChunk>,
+}
+
+/// Result of a single step execution
+#[derive(Debug, Clone)]
+pub struct StepOutcome {
+    pub data: StepData,
+    pub items_processed: usize,
+    pub details: serde_json::Value,   // Step-specific report
+}
+
+/// Outcome of resource enforcement for a step
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EnforcementOutcome {
+    /// No budget declared for the step
+    Unbounded,
+    /// Resource sampling not available on this platform
+    Unsupported,
+    WithinBudget,
+    Downgraded,
+    Aborted,
+}
+
+/// Resource enforcement report attached to step metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceEnforcement {
+    pub memory_limit_mb: Option<u64>,
+    pub peak_memory_mb: Option<u64>,
+    #[serde(default)]
+    pub cpu_limit_cores: Option<f32>,
+    #[serde(default)]
+    pub peak_cpu_cores: Option<f32>,    // Average since the step started
+    #[serde(default)]
+    pub disk_limit_mb: Option<u64>,
+    #[serde(default)]
+    pub disk_written_mb: Option<u64>,
+    pub samples: u32,
+    pub outcome: EnforcementOutcome,
+}
+
+/// Per-step run metrics stored with the pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StepRunMetrics {
+    pub step_type: ETLStepType,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+    pub duration_ms: u64,
+    pub items_processed: usize,
+    pub success: bool,
+    pub error: Option<String>,
+    pub resource_enforcement: ResourceEnforcement,
+    pub details: serde_json::Value,
+}
+
+/// Aggregated run report (stored in `pipeline_runs.metrics`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PipelineRunReport {
+    pub steps: Vec<StepRunMetrics>,
+    pub counters: HashMap<String, u64>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_step_resources_override_pipeline_defaults() {
+        let spec = PipelineSpec {
+            id: "p1".to_string(),
+            name: "Test".to_string(),
+            kb_id: None,
+            steps: vec![],
+            resources: PipelineResources {
+                cpu_cores: Some(2.0),
+                memory_mb: Some(512),
+                disk_mb: Some(1024),
+            },
+            source_roots: Vec::new(),
+        };
+
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Embed,
+            config: serde_json::json!({}),
+            resources: Some(PipelineResources {
+                cpu_cores: None,
+                memory_mb: Some(2048),
+                disk_mb: None,
+            }),
+            on_limit: ResourceLimitAction::Abort,
+        };
+
+        let resolved = spec.resources_for(&step);
+        assert_eq!(resolved.memory_mb, Some(2048));
+        assert_eq!(resolved.cpu_cores, Some(2.0));
+        assert_eq!(resolved.disk_mb, Some(1024));
+    }
+
+    #[test]
+    fn test_step_config_defaults() {
+        let step: PipelineStepConfig = serde_json::from_value(serde_json::json!({
+            "step_type": "normalize"
+        })).unwrap();
+
+        assert_eq!(step.step_type, ETLStepType::Normalize);
+        assert_eq!(step.on_limit, ResourceLimitAction::Downgrade);
+        assert!(step.resources.is_none());
+    }
+}
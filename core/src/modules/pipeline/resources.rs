@@ -0,0 +1,344 @@
+/*!
+ * Pipeline Resource Enforcement
+ *
+ * Samples process memory, CPU time and disk writes while a step runs and
+ * enforces the declared `PipelineResources` budget by downgrading or
+ * aborting the step.
+ */
+
+use std::future::Future;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tracing::{debug, warn};
+
+use super::errors::PipelineError;
+use super::models::{EnforcementOutcome, PipelineResources, ResourceEnforcement, ResourceLimitAction};
+
+/// A downgraded step is aborted once it grows past this multiple of its budget
+const DOWNGRADE_HARD_LIMIT_FACTOR: f64 = 1.5;
+
+/// Default sampling interval
+const DEFAULT_SAMPLE_INTERVAL: Duration = Duration::from_millis(250);
+
+/// CPU usage is averaged over the step and only judged after this many samples
+const CPU_WARMUP_SAMPLES: u32 = 4;
+
+/// Clock ticks per second in /proc/self/stat (USER_HZ is fixed at 100 on Linux)
+#[cfg(target_os = "linux")]
+const CLOCK_TICKS_PER_SECOND: f64 = 100.0;
+
+const MB: u64 = 1024 * 1024;
+
+/// Process-wide counters read on every sample (None where unsupported)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceSample {
+    pub rss_bytes: Option<u64>,
+    pub cpu_seconds: Option<f64>,       // User + system time
+    pub written_bytes: Option<u64>,     // Bytes sent to storage
+}
+
+/// Source of resource samples; replaced in tests
+pub type ResourceProbe = Arc<dyn Fn() -> ResourceSample + Send + Sync>;
+
+/// Read the current process counters
+pub fn read_process_sample() -> ResourceSample {
+    #[cfg(target_os = "linux")]
+    {
+        let proc_field = |file: &str, key: &str| -> Option<u64> {
+            std::fs::read_to_string(file).ok()?
+                .lines()
+                .find(|line| line.starts_with(key))
+                .and_then(|line| line.split_whitespace().nth(1))
+                .and_then(|value| value.parse().ok())
+        };
+        // utime and stime are fields 14 and 15; the command name before them may contain spaces
+        let cpu_seconds = std::fs::read_to_string("/proc/self/stat").ok().and_then(|stat| {
+            let mut fields = stat.rsplit_once(')')?.1.split_whitespace().skip(11);
+            let utime: u64 = fields.next()?.parse().ok()?;
+            let stime: u64 = fields.next()?.parse().ok()?;
+            Some((utime + stime) as f64 / CLOCK_TICKS_PER_SECOND)
+        });
+        ResourceSample {
+            rss_bytes: proc_field("/proc/self/status", "VmRSS:").map(|kb| kb * 1024),
+            cpu_seconds,
+            written_bytes: proc_field("/proc/self/io", "write_bytes:"),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    {
+        // MVP: sampling only on Linux, other platforms report Unsupported
+        ResourceSample::default()
+    }
+}
+
+/// Resource guard for a single step execution
+pub struct ResourceGuard {
+    memory_limit_mb: Option<u64>,
+    cpu_limit_cores: Option<f32>,
+    disk_limit_mb: Option<u64>,
+    action: ResourceLimitAction,
+    sample_interval: Duration,
+    probe: ResourceProbe,
+    downgrade: Arc<AtomicBool>,
+}
+
+impl ResourceGuard {
+    pub fn new(resources: &PipelineResources, action: ResourceLimitAction) -> Self {
+        Self {
+            memory_limit_mb: resources.memory_mb,
+            cpu_limit_cores: resources.cpu_cores,
+            disk_limit_mb: resources.disk_mb,
+            action,
+            sample_interval: DEFAULT_SAMPLE_INTERVAL,
+            probe: Arc::new(read_process_sample),
+            downgrade: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn with_sample_interval(mut self, interval: Duration) -> Self {
+        self.sample_interval = interval;
+        self
+    }
+
+    /// Read counters from `probe` instead of the process
+    pub fn with_probe(mut self, probe: ResourceProbe) -> Self {
+        self.probe = probe;
+        self
+    }
+
+    /// Flag shared with the step so it can shrink its working set
+    pub fn downgrade_flag(&self) -> Arc<AtomicBool> {
+        self.downgrade.clone()
+    }
+
+    /// Run a step future under the resource budget
+    ///
+    /// Budgets apply to growth over the counters observed when the step
+    /// starts, since the process is shared with the UI and other services:
+    /// memory is RSS growth, CPU the average cores used since the start and
+    /// disk the bytes written.
+    pub async fn run<F, T>(&self, fut: F) -> (Result<T, PipelineError>, ResourceEnforcement)
+    where
+        F: Future<Output = Result<T, PipelineError>>,
+    {
+        let mut enforcement = ResourceEnforcement {
+            memory_limit_mb: self.memory_limit_mb,
+            peak_memory_mb: None,
+            cpu_limit_cores: self.cpu_limit_cores,
+            peak_cpu_cores: None,
+            disk_limit_mb: self.disk_limit_mb,
+            disk_written_mb: None,
+            samples: 0,
+            outcome: EnforcementOutcome::WithinBudget,
+        };
+        if self.memory_limit_mb.is_none() && self.cpu_limit_cores.is_none() && self.disk_limit_mb.is_none() {
+            enforcement.outcome = EnforcementOutcome::Unbounded;
+            return (fut.await, enforcement);
+        }
+
+        // Limits whose counter cannot be read on this platform are dropped
+        let baseline = (self.probe)();
+        let memory = self.memory_limit_mb.zip(baseline.rss_bytes);
+        let cpu = self.cpu_limit_cores.zip(baseline.cpu_seconds);
+        let disk = self.disk_limit_mb.zip(baseline.written_bytes);
+        if memory.is_none() && cpu.is_none() && disk.is_none() {
+            enforcement.outcome = EnforcementOutcome::Unsupported;
+            return (fut.await, enforcement);
+        }
+        if (self.memory_limit_mb.is_some() && memory.is_none())
+            || (self.cpu_limit_cores.is_some() && cpu.is_none())
+            || (self.disk_limit_mb.is_some() && disk.is_none())
+        {
+            warn!("Some resource limits cannot be sampled on this platform and are not enforced");
+        }
+
+        let started = Instant::now();
+        let mut interval = tokio::time::interval(self.sample_interval);
+        tokio::pin!(fut);
+
+        let result = loop {
+            tokio::select! {
+                result = &mut fut => break result,
+                _ = interval.tick() => {
+                    let sample = (self.probe)();
+                    enforcement.samples += 1;
+
+                    // Worst usage as a fraction of its budget, with a description
+                    let mut breach: Option<(f64, String)> = None;
+                    let mut check = |usage: f64, limit: f64, describe: &dyn Fn() -> String| {
+                        let ratio = usage / limit.max(f64::MIN_POSITIVE);
+                        if ratio > 1.0 && breach.as_ref().is_none_or(|(worst, _)| ratio > *worst) {
+                            breach = Some((ratio, describe()));
+                        }
+                    };
+
+                    if let Some((limit_mb, base)) = memory {
+                        let growth = sample.rss_bytes.map_or(0, |rss| rss.saturating_sub(base));
+                        let peak = enforcement.peak_memory_mb.unwrap_or(0).max(growth / MB);
+                        enforcement.peak_memory_mb = Some(peak);
+                        check(growth as f64, (limit_mb * MB) as f64, &|| {
+                            format!("memory grew by {} MB (budget: {} MB)", growth / MB, limit_mb)
+                        });
+                    }
+                    if let Some((limit_cores, base)) = cpu {
+                        let elapsed = started.elapsed().as_secs_f64();
+                        let used = sample.cpu_seconds.map_or(0.0, |s| (s - base).max(0.0));
+                        if enforcement.samples >= CPU_WARMUP_SAMPLES && elapsed > 0.0 {
+                            let cores = (used / elapsed) as f32;
+                            enforcement.peak_cpu_cores = Some(enforcement.peak_cpu_cores.unwrap_or(0.0).max(cores));
+                            check(cores as f64, limit_cores as f64, &|| {
+                                format!("used {:.1} CPU cores (budget: {} cores)", cores, limit_cores)
+                            });
+                        }
+                    }
+                    if let Some((limit_mb, base)) = disk {
+                        let written = sample.written_bytes.map_or(0, |w| w.saturating_sub(base));
+                        enforcement.disk_written_mb = Some(written / MB);
+                        check(written as f64, (limit_mb * MB) as f64, &|| {
+                            format!("wrote {} MB to disk (budget: {} MB)", written / MB, limit_mb)
+                        });
+                    }
+
+                    let Some((ratio, message)) = breach else {
+                        continue;
+                    };
+
+                    let abort = match self.action {
+                        ResourceLimitAction::Abort => true,
+                        ResourceLimitAction::Downgrade if enforcement.outcome != EnforcementOutcome::Downgraded => {
+                            warn!("Step exceeded its budget ({}), requesting downgrade", message);
+                            self.downgrade.store(true, Ordering::SeqCst);
+                            enforcement.outcome = EnforcementOutcome::Downgraded;
+                            false
+                        }
+                        ResourceLimitAction::Downgrade => ratio > DOWNGRADE_HARD_LIMIT_FACTOR,
+                    };
+
+                    if abort {
+                        enforcement.outcome = EnforcementOutcome::Aborted;
+                        break Err(PipelineError::ResourceLimitExceeded(message));
+                    }
+                }
+            }
+        };
+
+        debug!("Resource guard finished after {} samples, outcome: {:?}", enforcement.samples, enforcement.outcome);
+        (result, enforcement)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicU64;
+
+    /// Probe whose counters grow by a fixed step on every read
+    fn growing_probe(rss_step: u64, cpu_step: f64, written_step: u64) -> ResourceProbe {
+        let reads = Arc::new(AtomicU64::new(0));
+        Arc::new(move || {
+            let n = reads.fetch_add(1, Ordering::SeqCst);
+            ResourceSample {
+                rss_bytes: Some(100 * MB + n * rss_step),
+                cpu_seconds: Some(n as f64 * cpu_step),
+                written_bytes: Some(n * written_step),
+            }
+        })
+    }
+
+    fn resources(memory_mb: Option<u64>, cpu_cores: Option<f32>, disk_mb: Option<u64>) -> PipelineResources {
+        PipelineResources { cpu_cores, memory_mb, disk_mb }
+    }
+
+    async fn slow_step() -> Result<(), PipelineError> {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_unbounded_step() {
+        let guard = ResourceGuard::new(&PipelineResources::default(), ResourceLimitAction::Abort);
+        let (result, enforcement) = guard.run(async { Ok::<_, PipelineError>(42) }).await;
+
+        assert_eq!(result.unwrap(), 42);
+        assert_eq!(enforcement.outcome, EnforcementOutcome::Unbounded);
+    }
+
+    #[tokio::test]
+    async fn test_abort_on_memory_budget() {
+        let guard = ResourceGuard::new(&resources(Some(8), None, None), ResourceLimitAction::Abort)
+            .with_sample_interval(Duration::from_millis(5))
+            .with_probe(growing_probe(4 * MB, 0.0, 0));
+
+        let (result, enforcement) = guard.run(slow_step()).await;
+
+        assert!(matches!(result, Err(PipelineError::ResourceLimitExceeded(m)) if m.contains("memory")));
+        assert_eq!(enforcement.outcome, EnforcementOutcome::Aborted);
+        assert_eq!(enforcement.peak_memory_mb, Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_downgrade_then_abort() {
+        let guard = ResourceGuard::new(&resources(Some(8), None, None), ResourceLimitAction::Downgrade)
+            .with_sample_interval(Duration::from_millis(5))
+            .with_probe(growing_probe(2 * MB, 0.0, 0));
+        let flag = guard.downgrade_flag();
+
+        let (result, enforcement) = guard.run(slow_step()).await;
+
+        // Downgraded past 8 MB, aborted past 12 MB
+        assert!(flag.load(Ordering::SeqCst));
+        assert!(result.is_err());
+        assert_eq!(enforcement.outcome, EnforcementOutcome::Aborted);
+        assert_eq!(enforcement.peak_memory_mb, Some(14));
+    }
+
+    #[tokio::test]
+    async fn test_cpu_and_disk_budgets() {
+        // Far more CPU seconds per read than wall time can cover
+        let guard = ResourceGuard::new(&resources(None, Some(2.0), None), ResourceLimitAction::Abort)
+            .with_sample_interval(Duration::from_millis(5))
+            .with_probe(growing_probe(0, 1.0, 0));
+        let (result, enforcement) = guard.run(slow_step()).await;
+        assert!(matches!(result, Err(PipelineError::ResourceLimitExceeded(m)) if m.contains("CPU")));
+        assert!(enforcement.samples >= CPU_WARMUP_SAMPLES);
+
+        let guard = ResourceGuard::new(&resources(None, None, Some(10)), ResourceLimitAction::Abort)
+            .with_sample_interval(Duration::from_millis(5))
+            .with_probe(growing_probe(0, 0.0, 3 * MB));
+        let (result, enforcement) = guard.run(slow_step()).await;
+        assert!(matches!(result, Err(PipelineError::ResourceLimitExceeded(m)) if m.contains("disk")));
+        assert_eq!(enforcement.disk_written_mb, Some(12));
+    }
+
+    #[tokio::test]
+    async fn test_within_budget() {
+        let guard = ResourceGuard::new(&resources(Some(4096), Some(64.0), Some(4096)), ResourceLimitAction::Downgrade)
+            .with_sample_interval(Duration::from_millis(5))
+            .with_probe(Arc::new(|| ResourceSample {
+                rss_bytes: Some(100 * MB),
+                cpu_seconds: Some(1.0),
+                written_bytes: Some(0),
+            }));
+
+        let (result, enforcement) = guard.run(async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            Ok::<_, PipelineError>(())
+        }).await;
+
+        assert!(result.is_ok());
+        assert_eq!(enforcement.outcome, EnforcementOutcome::WithinBudget);
+        assert!(!guard.downgrade_flag().load(Ordering::SeqCst));
+    }
+
+    #[tokio::test]
+    async fn test_unreadable_counters() {
+        let guard = ResourceGuard::new(&resources(Some(8), None, None), ResourceLimitAction::Abort)
+            .with_probe(Arc::new(ResourceSample::default));
+        let (result, enforcement) = guard.run(async { Ok::<_, PipelineError>(()) }).await;
+
+        assert!(result.is_ok());
+        assert_eq!(enforcement.outcome, EnforcementOutcome::Unsupported);
+    }
+}
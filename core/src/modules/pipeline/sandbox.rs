@@ -0,0 +1,104 @@
+/*!
+ * Pipeline Source Sandbox
+ *
+ * Pipelines declare the directories they may read (`source_roots`). Steps
+ * that touch the filesystem resolve every path through `SourceSandbox`,
+ * which canonicalizes it (following symlinks) and rejects anything outside
+ * the declared roots, so an imported pipeline can't read arbitrary files.
+ */
+
+use std::path::{Path, PathBuf};
+use tracing::warn;
+
+use super::errors::PipelineError;
+
+/// Canonicalized whitelist of source directories
+#[derive(Debug, Clone, Default)]
+pub struct SourceSandbox {
+    roots: Vec<PathBuf>,
+}
+
+impl SourceSandbox {
+    /// Roots that don't exist are dropped (nothing under them can be read),
+    /// as is the filesystem root, which would whitelist everything
+    pub fn new(roots: &[String]) -> Self {
+        let roots = roots.iter().filter_map(|root| match Path::new(root).canonicalize() {
+            Ok(path) if path.parent().is_none() => {
+                warn!("Ignoring pipeline source root {}: whole filesystem", root);
+                None
+            }
+            Ok(path) => Some(path),
+            Err(e) => {
+                warn!("Ignoring pipeline source root {}: {}", root, e);
+                None
+            }
+        }).collect();
+        Self { roots }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// Canonical path of `source` if it lies under a declared root. Relative
+    /// sources are taken relative to the first root.
+    pub fn resolve(&self, source: &str) -> Result<PathBuf, PipelineError> {
+        let path = Path::new(source.strip_prefix("file://").unwrap_or(source));
+        let Some(first_root) = self.roots.first() else {
+            return Err(PipelineError::PathNotAllowed(format!("{} (pipeline declares no source roots)", source)));
+        };
+        let path = if path.is_relative() { first_root.join(path) } else { path.to_path_buf() };
+
+        // Canonicalizing resolves `..` and symlinks, so escapes show up as foreign prefixes
+        let canonical = path.canonicalize()
+            .map_err(|e| PipelineError::StepFailed(format!("Failed to resolve {}: {}", source, e)))?;
+        if self.roots.iter().any(|root| canonical.starts_with(root)) {
+            Ok(canonical)
+        } else {
+            Err(PipelineError::PathNotAllowed(source.to_string()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_resolve_within_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(docs.join("guides")).unwrap();
+        std::fs::write(docs.join("guides/install.md"), "install").unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "secret").unwrap();
+
+        let sandbox = SourceSandbox::new(&[docs.to_string_lossy().to_string(), "/does/not/exist".to_string(), "/".to_string()]);
+        assert_eq!(sandbox.roots().len(), 1);
+
+        assert!(sandbox.resolve("guides/install.md").is_ok());
+        assert!(sandbox.resolve(&format!("file://{}", docs.join("guides/install.md").display())).is_ok());
+        assert!(matches!(sandbox.resolve("../secret.txt"), Err(PipelineError::PathNotAllowed(_))));
+        assert!(matches!(
+            sandbox.resolve(&temp_dir.path().join("secret.txt").to_string_lossy()),
+            Err(PipelineError::PathNotAllowed(_))
+        ));
+
+        assert!(matches!(SourceSandbox::default().resolve("guides/install.md"), Err(PipelineError::PathNotAllowed(_))));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_escape_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "secret").unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("secret.txt"), docs.join("notes.md")).unwrap();
+        std::os::unix::fs::symlink(temp_dir.path(), docs.join("parent")).unwrap();
+
+        let sandbox = SourceSandbox::new(&[docs.to_string_lossy().to_string()]);
+        assert!(matches!(sandbox.resolve("notes.md"), Err(PipelineError::PathNotAllowed(_))));
+        assert!(matches!(sandbox.resolve("parent/secret.txt"), Err(PipelineError::PathNotAllowed(_))));
+    }
+}
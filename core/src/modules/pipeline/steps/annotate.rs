@@ -0,0 +1,606 @@
+/*!
+ * Annotate Step
+ *
+ * Adds metadata fields to chunks from a registry of annotators picked by name
+ * in the step config: `keywords`, `heading_path` (the Markdown headings a
+ * chunk sits under), `reading_level` (Flesch-Kincaid grade, English only) and
+ * `code_language` (languages of fenced code blocks), plus regex taggers that
+ * put matches (or a fixed tag) in a field of their own.
+ *
+ * The KB chunks documents again when indexing, so the step also leaves its
+ * config in each document's `annotate` metadata; `index_document` runs the
+ * same annotators on every chunk it cuts, and the fields become filterable
+ * search metadata.
+ */
+
+use std::collections::HashMap;
+use async_trait::async_trait;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+
+const DEFAULT_MAX_KEYWORDS: usize = 5;
+
+/// Values a regex tagger keeps per item
+const MAX_TAGGER_MATCHES: usize = 20;
+
+/// Words needed for a reading level
+const MIN_READING_WORDS: usize = 30;
+
+/// Built-in annotators, by the name the step config uses
+pub const ANNOTATORS: &[&str] = &["keywords", "heading_path", "reading_level", "code_language"];
+
+/// Chunk metadata the KB sets itself, which taggers may not overwrite
+const RESERVED_FIELDS: &[&str] = &[
+    "title", "source_path", "chunk_index", "generation", "start_ms", "end_ms", "lang", "embedding_model",
+    "cell_type", "execution_count", "keywords", "heading_path", "reading_grade", "reading_level", "code_languages",
+];
+
+const STOPWORDS: &[&str] = &[
+    "about", "above", "after", "again", "against", "all", "also", "and", "any", "are", "because", "been", "before",
+    "being", "below", "between", "both", "but", "can", "could", "did", "does", "doing", "down", "during", "each",
+    "few", "for", "from", "further", "had", "has", "have", "having", "her", "here", "hers", "him", "his", "how",
+    "into", "its", "itself", "just", "more", "most", "must", "not", "now", "off", "once", "only", "other", "our",
+    "out", "over", "own", "same", "she", "should", "some", "such", "than", "that", "the", "their", "them", "then",
+    "there", "these", "they", "this", "those", "through", "too", "under", "until", "use", "used", "using", "very",
+    "was", "were", "what", "when", "where", "which", "while", "who", "whom", "why", "will", "with", "would", "you",
+    "your", "yours",
+];
+
+/// Annotate step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnotateStepConfig {
+    #[serde(default = "default_annotators")]
+    pub annotators: Vec<String>,        // Names from `ANNOTATORS`
+    #[serde(default = "default_max_keywords")]
+    pub max_keywords: usize,
+    #[serde(default)]
+    pub taggers: Vec<RegexTagger>,
+}
+
+fn default_annotators() -> Vec<String> {
+    ANNOTATORS.iter().map(|a| a.to_string()).collect()
+}
+
+fn default_max_keywords() -> usize { DEFAULT_MAX_KEYWORDS }
+
+impl Default for AnnotateStepConfig {
+    fn default() -> Self {
+        Self { annotators: default_annotators(), max_keywords: DEFAULT_MAX_KEYWORDS, taggers: Vec::new() }
+    }
+}
+
+/// Regex whose matches (or `value`, when set) are listed in `field`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RegexTagger {
+    pub field: String,
+    pub pattern: String,
+    #[serde(default)]
+    pub value: Option<String>,
+}
+
+/// Text to annotate and where it sits
+#[derive(Debug, Clone, Copy)]
+pub struct AnnotationTarget<'a> {
+    pub text: &'a str,
+    pub document: Option<&'a str>,      // Full document content
+    pub offset: Option<usize>,          // Byte offset of `text` in `document`
+    pub lang: Option<&'a str>,
+}
+
+type Fields = serde_json::Map<String, serde_json::Value>;
+
+/// Adds metadata fields for one chunk
+pub trait Annotator: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields);
+}
+
+/// Annotators selected by a step config
+pub struct AnnotatorSet {
+    annotators: Vec<Box<dyn Annotator>>,
+}
+
+impl AnnotatorSet {
+    pub fn from_config(config: &AnnotateStepConfig) -> Result<Self, String> {
+        let mut annotators: Vec<Box<dyn Annotator>> = Vec::new();
+        for name in &config.annotators {
+            annotators.push(match name.as_str() {
+                "keywords" => Box::new(KeywordAnnotator { max_keywords: config.max_keywords }),
+                "heading_path" => Box::new(HeadingPathAnnotator),
+                "reading_level" => Box::new(ReadingLevelAnnotator),
+                "code_language" => Box::new(CodeLanguageAnnotator),
+                other => return Err(format!("unknown annotator '{}' (available: {})", other, ANNOTATORS.join(", "))),
+            });
+        }
+        if !config.taggers.is_empty() {
+            annotators.push(Box::new(RegexAnnotator::new(&config.taggers)?));
+        }
+        Ok(Self { annotators })
+    }
+
+    /// Annotators of a config stored in document metadata
+    pub fn from_value(config: &serde_json::Value) -> Result<Self, String> {
+        let config: AnnotateStepConfig = serde_json::from_value(config.clone()).map_err(|e| e.to_string())?;
+        Self::from_config(&config)
+    }
+
+    pub fn annotate(&self, target: &AnnotationTarget<'_>) -> Fields {
+        let mut fields = Fields::new();
+        for annotator in &self.annotators {
+            annotator.annotate(target, &mut fields);
+        }
+        fields
+    }
+}
+
+/// Most frequent words outside a stopword list
+struct KeywordAnnotator {
+    max_keywords: usize,
+}
+
+impl Annotator for KeywordAnnotator {
+    fn name(&self) -> &str { "keywords" }
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields) {
+        // (count, first position) per word
+        let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+        let words = target.text.split(|c: char| !c.is_alphanumeric() && c != '_')
+            .filter(|w| w.chars().count() > 2 && w.chars().any(char::is_alphabetic))
+            .map(str::to_lowercase)
+            .filter(|w| !STOPWORDS.contains(&w.as_str()));
+        for (position, word) in words.enumerate() {
+            counts.entry(word).or_insert((0, position)).0 += 1;
+        }
+        let mut ranked: Vec<(String, (usize, usize))> = counts.into_iter().collect();
+        ranked.sort_by(|(_, (ca, pa)), (_, (cb, pb))| cb.cmp(ca).then(pa.cmp(pb)));
+        let keywords: Vec<String> = ranked.into_iter().take(self.max_keywords).map(|(word, _)| word).collect();
+        if !keywords.is_empty() {
+            fields.insert("keywords".to_string(), keywords.into());
+        }
+    }
+}
+
+/// Markdown heading: (level, text)
+fn heading(line: &str) -> Option<(usize, &str)> {
+    let line = line.trim_start();
+    let level = line.chars().take_while(|c| *c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then(|| (level, line[level..].trim()))
+}
+
+/// Headings in effect where the chunk's content starts
+struct HeadingPathAnnotator;
+
+impl Annotator for HeadingPathAnnotator {
+    fn name(&self) -> &str { "heading_path" }
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields) {
+        // Headings opening the chunk belong to its path
+        let mut lead = 0;
+        for line in target.text.split_inclusive('\n') {
+            if !line.trim().is_empty() && heading(line).is_none() {
+                break;
+            }
+            lead += line.len();
+        }
+        let scanned = match target.document.zip(target.offset) {
+            Some((document, offset)) => &document[..(offset + lead).min(document.len())],
+            None => &target.text[..lead],
+        };
+
+        let mut path: Vec<(usize, &str)> = Vec::new();
+        let mut fenced = false;
+        for line in scanned.lines() {
+            if line.trim_start().starts_with("```") {
+                fenced = !fenced;
+            }
+            if let Some((level, text)) = heading(line).filter(|_| !fenced) {
+                path.retain(|(l, _)| *l < level);
+                path.push((level, text));
+            }
+        }
+        if !path.is_empty() {
+            fields.insert("heading_path".to_string(), path.into_iter().map(|(_, text)| text).collect::<Vec<_>>().into());
+        }
+    }
+}
+
+/// Flesch-Kincaid grade of English prose
+struct ReadingLevelAnnotator;
+
+/// Vowel groups, less a silent final `e`; at least one
+fn syllables(word: &str) -> usize {
+    let word = word.to_lowercase();
+    let mut count = 0;
+    let mut previous_vowel = false;
+    for c in word.chars() {
+        let vowel = "aeiouy".contains(c);
+        if vowel && !previous_vowel {
+            count += 1;
+        }
+        previous_vowel = vowel;
+    }
+    if word.ends_with('e') && !word.ends_with("le") && count > 1 {
+        count -= 1;
+    }
+    count.max(1)
+}
+
+impl Annotator for ReadingLevelAnnotator {
+    fn name(&self) -> &str { "reading_level" }
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields) {
+        if target.lang.is_some_and(|lang| lang != "en") {
+            return;
+        }
+        // Prose only: code blocks would skew the counts
+        let mut fenced = false;
+        let prose: Vec<&str> = target.text.lines()
+            .filter(|line| {
+                if line.trim_start().starts_with("```") {
+                    fenced = !fenced;
+                    return false;
+                }
+                !fenced && heading(line).is_none()
+            })
+            .collect();
+        let prose = prose.join(" ");
+        let words: Vec<&str> = prose.split_whitespace()
+            .map(|w| w.trim_matches(|c: char| !c.is_alphabetic()))
+            .filter(|w| !w.is_empty())
+            .collect();
+        if words.len() < MIN_READING_WORDS {
+            return;
+        }
+        let sentences = prose.split(['.', '!', '?']).filter(|s| s.chars().any(char::is_alphabetic)).count().max(1);
+        let syllable_count: usize = words.iter().map(|w| syllables(w)).sum();
+        let grade = 0.39 * words.len() as f64 / sentences as f64 + 11.8 * syllable_count as f64 / words.len() as f64 - 15.59;
+        let grade = (grade.max(0.0) * 10.0).round() / 10.0;
+        let level = match grade {
+            g if g <= 6.0 => "easy",
+            g if g <= 12.0 => "standard",
+            _ => "difficult",
+        };
+        fields.insert("reading_grade".to_string(), grade.into());
+        fields.insert("reading_level".to_string(), level.into());
+    }
+}
+
+/// Languages of fenced code blocks, from the info string or the code itself
+struct CodeLanguageAnnotator;
+
+/// Canonical name of a fence info string
+fn fence_language(info: &str) -> Option<&'static str> {
+    let name = info.split(|c: char| c.is_whitespace() || c == '{' || c == ',').next()?.to_lowercase();
+    Some(match name.as_str() {
+        "py" | "python" | "python3" | "ipython" => "python",
+        "js" | "javascript" | "jsx" | "node" => "javascript",
+        "ts" | "typescript" | "tsx" => "typescript",
+        "rs" | "rust" => "rust",
+        "go" | "golang" => "go",
+        "java" => "java",
+        "kt" | "kotlin" => "kotlin",
+        "c" | "h" => "c",
+        "cpp" | "c++" | "cc" | "hpp" => "cpp",
+        "cs" | "csharp" | "c#" => "csharp",
+        "rb" | "ruby" => "ruby",
+        "php" => "php",
+        "swift" => "swift",
+        "sh" | "bash" | "shell" | "zsh" | "console" | "shell-session" => "shell",
+        "ps1" | "powershell" => "powershell",
+        "sql" | "postgresql" | "mysql" | "sqlite" => "sql",
+        "json" | "jsonc" => "json",
+        "yaml" | "yml" => "yaml",
+        "toml" => "toml",
+        "xml" => "xml",
+        "html" | "htm" => "html",
+        "css" | "scss" => "css",
+        "dockerfile" | "docker" => "dockerfile",
+        _ => return None,
+    })
+}
+
+/// Guess for an unlabeled code block
+fn guess_language(code: &str) -> Option<&'static str> {
+    let has = |needles: &[&str]| needles.iter().any(|n| code.contains(n));
+    let lower = code.to_lowercase();
+    let trimmed = code.trim_start();
+    if (trimmed.starts_with('{') || trimmed.starts_with('[')) && serde_json::from_str::<serde_json::Value>(code).is_ok() {
+        return Some("json");
+    }
+    if has(&["fn ", "let mut ", "impl ", "pub struct ", "::new("]) && has(&["fn ", "=>", ";"]) {
+        Some("rust")
+    } else if has(&["def ", "import ", "elif ", "self."]) && !code.contains(';') {
+        Some("python")
+    } else if has(&["func ", "package main", ":= "]) {
+        Some("go")
+    } else if has(&["public class ", "public static void", "System.out."]) {
+        Some("java")
+    } else if has(&["#include"]) {
+        Some("cpp")
+    } else if has(&["interface ", ": string", ": number"]) && has(&["const ", "let ", "function ", "export "]) {
+        Some("typescript")
+    } else if has(&["function ", "const ", "=> {", "console.log", "require("]) {
+        Some("javascript")
+    } else if (lower.contains("select ") && lower.contains(" from ")) || lower.contains("insert into") || lower.contains("create table") {
+        Some("sql")
+    } else if trimmed.starts_with("$ ") || trimmed.starts_with("#!/") || has(&["sudo ", "apt-get ", "pip install", "npm install", "cargo ", "export PATH"]) {
+        Some("shell")
+    } else if trimmed.starts_with('<') {
+        Some("html")
+    } else {
+        None
+    }
+}
+
+impl Annotator for CodeLanguageAnnotator {
+    fn name(&self) -> &str { "code_language" }
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields) {
+        let mut languages: Vec<&str> = Vec::new();
+        let mut block: Option<(Option<&str>, Vec<&str>)> = None;
+        for line in target.text.lines() {
+            let fence = line.trim_start().strip_prefix("```");
+            match (fence, block.take()) {
+                (Some(info), None) => block = Some((fence_language(info.trim()), Vec::new())),
+                (Some(_), Some((language, code))) => {
+                    if let Some(language) = language.or_else(|| guess_language(&code.join("\n"))) {
+                        languages.push(language);
+                    }
+                }
+                (None, Some((language, mut code))) => {
+                    code.push(line);
+                    block = Some((language, code));
+                }
+                (None, None) => {}
+            }
+        }
+        // A block cut off at the chunk's end still counts
+        if let Some((language, code)) = block {
+            if let Some(language) = language.or_else(|| guess_language(&code.join("\n"))) {
+                languages.push(language);
+            }
+        }
+        languages.sort_unstable();
+        languages.dedup();
+        if !languages.is_empty() {
+            fields.insert("code_languages".to_string(), languages.into());
+        }
+    }
+}
+
+/// Config-defined regex taggers
+struct RegexAnnotator {
+    taggers: Vec<(String, Regex, Option<String>)>,
+}
+
+impl RegexAnnotator {
+    fn new(taggers: &[RegexTagger]) -> Result<Self, String> {
+        let mut compiled = Vec::with_capacity(taggers.len());
+        for tagger in taggers {
+            if tagger.field.is_empty() || RESERVED_FIELDS.contains(&tagger.field.as_str()) {
+                return Err(format!("tagger field '{}' is reserved", tagger.field));
+            }
+            let regex = Regex::new(&tagger.pattern).map_err(|e| format!("tagger {}: {}", tagger.field, e))?;
+            compiled.push((tagger.field.clone(), regex, tagger.value.clone()));
+        }
+        Ok(Self { taggers: compiled })
+    }
+}
+
+impl Annotator for RegexAnnotator {
+    fn name(&self) -> &str { "regex" }
+
+    fn annotate(&self, target: &AnnotationTarget<'_>, fields: &mut Fields) {
+        for (field, regex, value) in &self.taggers {
+            let found: Vec<String> = match value {
+                Some(value) => regex.is_match(target.text).then(|| value.clone()).into_iter().collect(),
+                None => regex.find_iter(target.text).map(|m| m.as_str().to_string()).collect(),
+            };
+            if found.is_empty() {
+                continue;
+            }
+            // Taggers sharing a field add to one list
+            let entry = fields.entry(field.clone()).or_insert_with(|| serde_json::Value::Array(Vec::new()));
+            if let Some(values) = entry.as_array_mut() {
+                for item in found {
+                    if values.len() < MAX_TAGGER_MATCHES && !values.iter().any(|v| v == item.as_str()) {
+                        values.push(item.into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Annotate step executor
+pub struct AnnotateStepExecutor;
+
+impl AnnotateStepExecutor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for AnnotateStepExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl StepExecutor for AnnotateStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Annotate
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: AnnotateStepConfig = if ctx.config().is_null() {
+            AnnotateStepConfig::default()
+        } else {
+            serde_json::from_value(ctx.config().clone())
+                .map_err(|e| PipelineError::InvalidConfig(format!("annotate: {}", e)))?
+        };
+        let annotators = AnnotatorSet::from_config(&config)
+            .map_err(|e| PipelineError::InvalidConfig(format!("annotate: {}", e)))?;
+
+        let StepData { mut documents, mut chunks } = data;
+        let stored = serde_json::to_value(&config)?;
+        for doc in &mut documents {
+            if let Some(metadata) = doc.metadata.as_object_mut() {
+                metadata.insert("annotate".to_string(), stored.clone());
+            }
+        }
+
+        let by_id: HashMap<&str, &PipelineDocument> = documents.iter().map(|d| (d.id.as_str(), d)).collect();
+        let mut fields_added = 0;
+        for chunk in &mut chunks {
+            let document = by_id.get(chunk.document_id.as_str());
+            let lang = chunk.metadata.get("lang").or_else(|| document.and_then(|d| d.metadata.get("lang"))).and_then(|l| l.as_str());
+            let target = AnnotationTarget {
+                text: &chunk.content,
+                document: document.map(|d| d.content.as_str()),
+                offset: document.and_then(|d| d.content.find(chunk.content.as_str())),
+                lang,
+            };
+            let fields = annotators.annotate(&target);
+            fields_added += fields.len();
+            if let Some(metadata) = chunk.metadata.as_object_mut() {
+                metadata.extend(fields);
+            }
+        }
+
+        info!(
+            "Annotate for pipeline {}: {} fields on {} chunks ({} annotators), {} documents marked for index-time annotation",
+            ctx.pipeline_id, fields_added, chunks.len(), annotators.annotators.len(), documents.len()
+        );
+        Ok(StepOutcome {
+            items_processed: chunks.len() + documents.len(),
+            details: serde_json::json!({
+                "annotators": annotators.annotators.iter().map(|a| a.name().to_string()).collect::<Vec<_>>(),
+                "chunks": chunks.len(),
+                "documents": documents.len(),
+                "fields": fields_added,
+            }),
+            data: StepData { documents, chunks },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    const GUIDE: &str = "# Setup\n\nIntro.\n\n## Install\n\nRun the installer, then restart the indexer so the indexer picks up the new index settings. See OPS-42 and OPS-7.\n\n```\npip install ragkit\n```\n\n## Configure\n\n```yaml\nport: 8080\n```\n";
+
+    fn target<'a>(text: &'a str, document: Option<&'a str>) -> AnnotationTarget<'a> {
+        AnnotationTarget { text, document, offset: document.and_then(|d| d.find(text)), lang: None }
+    }
+
+    #[test]
+    fn test_builtin_annotators() {
+        let annotators = AnnotatorSet::from_config(&AnnotateStepConfig {
+            max_keywords: 2,
+            taggers: vec![
+                RegexTagger { field: "tickets".to_string(), pattern: r"OPS-\d+".to_string(), value: None },
+                RegexTagger { field: "topics".to_string(), pattern: "(?i)install".to_string(), value: Some("installation".to_string()) },
+            ],
+            ..AnnotateStepConfig::default()
+        }).unwrap();
+
+        let install = &GUIDE[GUIDE.find("Run the").unwrap()..GUIDE.find("## Configure").unwrap()];
+        let fields = annotators.annotate(&target(install, Some(GUIDE)));
+        assert_eq!(fields["heading_path"], serde_json::json!(["Setup", "Install"]));
+        assert_eq!(fields["keywords"], serde_json::json!(["indexer", "ops"]));
+        assert_eq!(fields["code_languages"], serde_json::json!(["shell"]));
+        assert_eq!(fields["tickets"], serde_json::json!(["OPS-42", "OPS-7"]));
+        assert_eq!(fields["topics"], serde_json::json!(["installation"]));
+        // Too few words for a reading level
+        assert!(!fields.contains_key("reading_level"));
+
+        // A chunk opening with a heading sits under it; siblings replace each other
+        let configure = &GUIDE[GUIDE.find("## Configure").unwrap()..];
+        let fields = annotators.annotate(&target(configure, Some(GUIDE)));
+        assert_eq!(fields["heading_path"], serde_json::json!(["Setup", "Configure"]));
+        assert_eq!(fields["code_languages"], serde_json::json!(["yaml"]));
+
+        let prose = "The cat sat on the mat. It was warm. The dog ran to the park and back. We had fun in the sun all day. Then we went home to eat.";
+        let fields = annotators.annotate(&target(prose, None));
+        assert_eq!(fields["reading_level"], "easy");
+        let german = AnnotationTarget { lang: Some("de"), ..target(prose, None) };
+        assert!(!annotators.annotate(&german).contains_key("reading_level"));
+    }
+
+    #[test]
+    fn test_invalid_annotator_config() {
+        let unknown = AnnotateStepConfig { annotators: vec!["sentiment".to_string()], ..AnnotateStepConfig::default() };
+        assert!(AnnotatorSet::from_config(&unknown).err().unwrap().contains("unknown annotator 'sentiment'"));
+        let reserved = AnnotateStepConfig {
+            taggers: vec![RegexTagger { field: "title".to_string(), pattern: "x".to_string(), value: None }],
+            ..AnnotateStepConfig::default()
+        };
+        assert!(AnnotatorSet::from_config(&reserved).is_err());
+        let bad_regex = AnnotateStepConfig {
+            taggers: vec![RegexTagger { field: "ids".to_string(), pattern: "(".to_string(), value: None }],
+            ..AnnotateStepConfig::default()
+        };
+        assert!(AnnotatorSet::from_config(&bad_regex).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_annotate_step() {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Annotate,
+            config: serde_json::json!({ "annotators": ["heading_path"], "taggers": [{ "field": "tickets", "pattern": "OPS-\\d+" }] }),
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Annotate".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        let ctx = StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)));
+        let data = StepData {
+            documents: vec![PipelineDocument {
+                id: "d1".to_string(),
+                title: "guide.md".to_string(),
+                source_path: "/docs/guide.md".to_string(),
+                content: GUIDE.to_string(),
+                content_hash: String::new(),
+                license_info: None,
+                metadata: serde_json::json!({}),
+            }],
+            chunks: vec![PipelineChunk {
+                id: "c1".to_string(),
+                document_id: "d1".to_string(),
+                chunk_index: 1,
+                content: "Run the installer, then restart the indexer".to_string(),
+                content_hash: String::new(),
+                metadata: serde_json::json!({}),
+                embedding: Vec::new(),
+            }],
+        };
+
+        let outcome = AnnotateStepExecutor::new().execute(&ctx, data).await.unwrap();
+        assert_eq!(outcome.data.chunks[0].metadata["heading_path"], serde_json::json!(["Setup", "Install"]));
+        assert!(outcome.data.chunks[0].metadata.get("tickets").is_none());
+        assert_eq!(outcome.details["annotators"], serde_json::json!(["heading_path", "regex"]));
+        // The KB rebuilds the same annotators from the document
+        let stored = &outcome.data.documents[0].metadata["annotate"];
+        assert_eq!(stored["taggers"][0]["field"], "tickets");
+        assert!(AnnotatorSet::from_value(stored).is_ok());
+    }
+}
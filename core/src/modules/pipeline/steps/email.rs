@@ -0,0 +1,452 @@
+/*!
+ * Email Parsing
+ *
+ * Reads `.eml` messages and `.mbox` archives for the parse step: MIME parts
+ * are decoded (base64, quoted-printable, RFC 2047 headers, legacy charsets),
+ * the plain-text body is preferred over HTML, and quoted replies, forwarded
+ * headers and signatures are stripped so each message indexes only what its
+ * sender wrote. Attachments are returned with their bytes so the parse step
+ * can read them like any other file and link them to their message.
+ */
+
+use std::path::Path;
+use std::sync::OnceLock;
+use base64::Engine;
+use chrono::DateTime;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+/// Extensions of email files (read when walking a directory)
+pub const EMAIL_EXTENSIONS: &[&str] = &["eml", "mbox"];
+
+/// Attachment of a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct EmailAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub data: Vec<u8>,
+}
+
+/// Parsed message; `body` has quotes and signature stripped
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct EmailMessage {
+    pub headers: EmailHeaders,
+    pub body: String,
+    pub attachments: Vec<EmailAttachment>,
+}
+
+/// Message headers kept as document metadata
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EmailHeaders {
+    pub message_id: Option<String>,
+    pub subject: Option<String>,
+    pub from: Option<String>,
+    pub to: Vec<String>,
+    pub cc: Vec<String>,
+    pub date: Option<String>,       // RFC 3339 when the header parses, else as sent
+    pub in_reply_to: Option<String>,
+}
+
+/// True if `path` has an email extension
+pub fn is_email_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| EMAIL_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Raw messages of an mbox archive (split at `From ` separator lines, `>From ` unescaped)
+pub fn split_mbox(archive: &[u8]) -> Vec<Vec<u8>> {
+    let mut messages = Vec::new();
+    let mut current: Option<Vec<u8>> = None;
+    for line in archive.split_inclusive(|b| *b == b'\n') {
+        if line.starts_with(b"From ") {
+            messages.extend(current.take());
+            current = Some(Vec::new());
+            continue;
+        }
+        // mboxrd quoting: `>From `, `>>From `, ... lose one `>`
+        let quoted_from = line.starts_with(b">")
+            && line.iter().position(|b| *b != b'>').is_some_and(|i| line[i..].starts_with(b"From "));
+        current.get_or_insert_with(Vec::new).extend_from_slice(if quoted_from { &line[1..] } else { line });
+    }
+    messages.extend(current);
+    messages.retain(|message| message.iter().any(|b| !b.is_ascii_whitespace()));
+    messages
+}
+
+/// Parse one RFC 5322 message
+pub fn parse_message(raw: &[u8]) -> EmailMessage {
+    let part = MimePart::parse(raw);
+    let header = |name: &str| part.header(name).map(|value| decode_words(&value)).filter(|v| !v.is_empty());
+    let headers = EmailHeaders {
+        message_id: header("message-id").map(|id| id.trim_matches(|c| c == '<' || c == '>').to_string()),
+        subject: header("subject"),
+        from: header("from"),
+        to: header("to").map(|v| split_addresses(&v)).unwrap_or_default(),
+        cc: header("cc").map(|v| split_addresses(&v)).unwrap_or_default(),
+        date: header("date").map(|date| DateTime::parse_from_rfc2822(&date).map(|d| d.to_rfc3339()).unwrap_or(date)),
+        in_reply_to: header("in-reply-to").map(|id| id.trim_matches(|c| c == '<' || c == '>').to_string()),
+    };
+
+    let mut content = Content::default();
+    collect_parts(&part, &mut content);
+    let body = if content.plain.is_empty() { content.html.join("\n\n") } else { content.plain.join("\n\n") };
+    EmailMessage { headers, body: strip_quotes_and_signature(&body), attachments: content.attachments }
+}
+
+/// What a reader would see of a body: quoted lines, reply/forward headers and the signature removed
+pub fn strip_quotes_and_signature(body: &str) -> String {
+    static REPLY_HEADER: OnceLock<Regex> = OnceLock::new();
+    let reply_header = REPLY_HEADER.get_or_init(|| Regex::new(
+        r"(?i)^(on .{4,200}wrote:\s*$|-{2,}\s*(original message|forwarded message)\s*-{2,})"
+    ).expect("valid reply header pattern"));
+
+    let lines: Vec<&str> = body.lines().collect();
+    let mut kept: Vec<&str> = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        let trimmed = line.trim_end();
+        // Outlook quotes start with a From:/Sent: block
+        let outlook = trimmed.starts_with("From:")
+            && lines[i + 1..].iter().take(3).any(|next| next.starts_with("Sent:") || next.starts_with("Date:"));
+        if reply_header.is_match(trimmed.trim_start()) || outlook || trimmed == "--" || *line == "-- " {
+            break;
+        }
+        if !trimmed.trim_start().starts_with('>') {
+            kept.push(trimmed);
+        }
+    }
+    while kept.last().is_some_and(|line| line.trim().is_empty() || line.starts_with("Sent from my ")) {
+        kept.pop();
+    }
+
+    // Collapse runs of blank lines left by removed quotes
+    let mut text = String::new();
+    let mut blank = false;
+    for line in kept.iter().skip_while(|line| line.trim().is_empty()) {
+        if line.trim().is_empty() {
+            blank = true;
+            continue;
+        }
+        if !text.is_empty() {
+            text.push_str(if blank { "\n\n" } else { "\n" });
+        }
+        text.push_str(line);
+        blank = false;
+    }
+    text
+}
+
+/// Text and attachments gathered from a MIME tree
+#[derive(Default)]
+struct Content {
+    plain: Vec<String>,
+    html: Vec<String>,
+    attachments: Vec<EmailAttachment>,
+}
+
+fn collect_parts(part: &MimePart, content: &mut Content) {
+    let content_type = part.content_type();
+    if let Some(boundary) = content_type.starts_with("multipart/").then(|| part.param("content-type", "boundary")).flatten() {
+        for child in split_multipart(&part.body, &boundary) {
+            collect_parts(&MimePart::parse(child), content);
+        }
+        return;
+    }
+
+    let disposition = part.header("content-disposition").unwrap_or_default().to_lowercase();
+    let filename = part.param("content-disposition", "filename").or_else(|| part.param("content-type", "name"));
+    let attached = disposition.starts_with("attachment") || filename.is_some();
+    match content_type.as_str() {
+        "text/plain" if !attached => content.plain.push(part.text()),
+        "text/html" if !attached => content.html.push(html_to_text(&part.text())),
+        _ if attached || content_type == "message/rfc822" => {
+            let default_name = if content_type == "message/rfc822" { "message.eml" } else { "attachment" };
+            content.attachments.push(EmailAttachment {
+                filename: filename.map(|name| decode_words(&name)).unwrap_or_else(|| default_name.to_string()),
+                content_type,
+                data: part.decoded_body(),
+            });
+        }
+        // Inline parts without a name (e.g. embedded images) carry no text
+        _ => {}
+    }
+}
+
+/// Header block and body of a message or MIME part
+struct MimePart {
+    headers: Vec<(String, String)>,     // Lowercased name, unfolded value
+    body: Vec<u8>,
+}
+
+impl MimePart {
+    fn parse(raw: &[u8]) -> Self {
+        let (head, body) = match find_blank_line(raw) {
+            Some((head_end, body_start)) => (&raw[..head_end], &raw[body_start..]),
+            None => (raw, &raw[raw.len()..]),
+        };
+
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in String::from_utf8_lossy(head).lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+        Self { headers, body: body.to_vec() }
+    }
+
+    fn header(&self, name: &str) -> Option<String> {
+        self.headers.iter().find(|(n, _)| n == name).map(|(_, v)| v.clone())
+    }
+
+    /// Lowercased MIME type, `text/plain` when absent
+    fn content_type(&self) -> String {
+        self.header("content-type")
+            .and_then(|v| v.split(';').next().map(|t| t.trim().to_lowercase()))
+            .filter(|t| !t.is_empty())
+            .unwrap_or_else(|| "text/plain".to_string())
+    }
+
+    /// Parameter of a structured header, e.g. the `boundary` of `content-type`
+    fn param(&self, header: &str, param: &str) -> Option<String> {
+        let value = self.header(header)?;
+        value.split(';').skip(1).find_map(|pair| {
+            let (name, value) = pair.split_once('=')?;
+            // RFC 2231 `filename*=utf-8''name` is read without its charset prefix
+            let name = name.trim().to_lowercase();
+            (name == param || name == format!("{}*", param)).then(|| {
+                let value = value.trim().trim_matches('"');
+                value.rsplit_once("''").map(|(_, v)| v).unwrap_or(value).to_string()
+            })
+        })
+    }
+
+    /// Body bytes with the transfer encoding undone
+    fn decoded_body(&self) -> Vec<u8> {
+        match self.header("content-transfer-encoding").map(|e| e.to_lowercase()).as_deref() {
+            Some("base64") => {
+                let compact: Vec<u8> = self.body.iter().copied().filter(|b| !b.is_ascii_whitespace()).collect();
+                base64::engine::general_purpose::STANDARD.decode(&compact).unwrap_or_default()
+            }
+            Some("quoted-printable") => decode_quoted_printable(&self.body, false),
+            _ => self.body.clone(),
+        }
+    }
+
+    /// Decoded body as text, in the part's charset, with LF line endings
+    fn text(&self) -> String {
+        decode_charset(&self.decoded_body(), self.param("content-type", "charset").as_deref()).replace("\r\n", "\n")
+    }
+}
+
+/// End of the header block and start of the body
+fn find_blank_line(raw: &[u8]) -> Option<(usize, usize)> {
+    if raw.starts_with(b"\n") || raw.starts_with(b"\r\n") {
+        return Some((0, if raw[0] == b'\r' { 2 } else { 1 }));
+    }
+    raw.windows(2).enumerate().find_map(|(i, pair)| match pair {
+        b"\n\n" => Some((i + 1, i + 2)),
+        b"\n\r" if raw.get(i + 2) == Some(&b'\n') => Some((i + 1, i + 3)),
+        _ => None,
+    })
+}
+
+/// Child parts between `--boundary` delimiter lines
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|b| *b == b'\n') {
+        let text = line.trim_ascii_end();
+        if text.starts_with(delimiter.as_bytes()) {
+            if let Some(start) = start {
+                parts.push(&body[start..offset]);
+            }
+            if text == format!("{}--", delimiter).as_bytes() {
+                return parts;
+            }
+            start = Some(offset + line.len());
+        }
+        offset += line.len();
+    }
+    parts.extend(start.map(|start| &body[start..]));
+    parts
+}
+
+/// Decode quoted-printable; `header` also maps `_` to a space (RFC 2047 Q encoding)
+fn decode_quoted_printable(input: &[u8], header: bool) -> Vec<u8> {
+    let mut output = Vec::with_capacity(input.len());
+    let mut i = 0;
+    while i < input.len() {
+        match input[i] {
+            b'=' if input[i + 1..].starts_with(b"\r\n") => i += 3,
+            b'=' if input[i + 1..].starts_with(b"\n") => i += 2,
+            b'=' if i + 2 < input.len() => {
+                match std::str::from_utf8(&input[i + 1..i + 3]).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                    Some(byte) => {
+                        output.push(byte);
+                        i += 3;
+                    }
+                    None => {
+                        output.push(b'=');
+                        i += 1;
+                    }
+                }
+            }
+            b'_' if header => {
+                output.push(b' ');
+                i += 1;
+            }
+            byte => {
+                output.push(byte);
+                i += 1;
+            }
+        }
+    }
+    output
+}
+
+/// Bytes in a named charset as text (UTF-8 when unknown)
+fn decode_charset(bytes: &[u8], charset: Option<&str>) -> String {
+    let encoding = charset
+        .and_then(|label| encoding_rs::Encoding::for_label(label.trim().as_bytes()))
+        .unwrap_or(encoding_rs::UTF_8);
+    encoding.decode(bytes).0.into_owned()
+}
+
+/// Decode RFC 2047 encoded words (`=?utf-8?B?...?=`) in a header value
+fn decode_words(value: &str) -> String {
+    static ENCODED_WORD: OnceLock<Regex> = OnceLock::new();
+    static BETWEEN_WORDS: OnceLock<Regex> = OnceLock::new();
+    let encoded_word = ENCODED_WORD.get_or_init(|| Regex::new(r"=\?([^?\s]+)\?([BbQq])\?([^?\s]*)\?=")
+        .expect("valid encoded word pattern"));
+    let between_words = BETWEEN_WORDS.get_or_init(|| Regex::new(r"\?=\s+=\?").expect("valid pattern"));
+
+    // Whitespace between adjacent encoded words is not part of the text
+    let value = between_words.replace_all(value, "?==?");
+    encoded_word.replace_all(&value, |caps: &regex::Captures| {
+        let bytes = match &caps[2] {
+            "B" | "b" => base64::engine::general_purpose::STANDARD.decode(&caps[3]).unwrap_or_default(),
+            _ => decode_quoted_printable(caps[3].as_bytes(), true),
+        };
+        decode_charset(&bytes, Some(&caps[1]))
+    }).trim().to_string()
+}
+
+/// Addresses in a list header, splitting at commas outside quoted names
+fn split_addresses(value: &str) -> Vec<String> {
+    let mut addresses = Vec::new();
+    let (mut current, mut quoted) = (String::new(), false);
+    for c in value.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                current.push(c);
+            }
+            ',' if !quoted => addresses.push(std::mem::take(&mut current)),
+            _ => current.push(c),
+        }
+    }
+    addresses.push(current);
+    addresses.into_iter().map(|a| a.trim().to_string()).filter(|a| !a.is_empty()).collect()
+}
+
+/// Readable text of an HTML body
+pub(super) fn html_to_text(html: &str) -> String {
+    static HIDDEN: OnceLock<Regex> = OnceLock::new();
+    static BREAKS: OnceLock<Regex> = OnceLock::new();
+    static TAGS: OnceLock<Regex> = OnceLock::new();
+    let hidden = HIDDEN.get_or_init(|| Regex::new(r"(?is)<(script|style|head)\b.*?</(script|style|head)>").expect("valid pattern"));
+    let breaks = BREAKS.get_or_init(|| Regex::new(r"(?i)<br\s*/?>|</(p|div|li|tr|h[1-6])>").expect("valid pattern"));
+    let tags = TAGS.get_or_init(|| Regex::new(r"(?s)<[^>]*>").expect("valid pattern"));
+
+    let text = hidden.replace_all(html, "");
+    let text = breaks.replace_all(&text, "\n");
+    let text = tags.replace_all(&text, "");
+    let text = text.replace("&nbsp;", " ").replace("&lt;", "<").replace("&gt;", ">")
+        .replace("&quot;", "\"").replace("&#39;", "'").replace("&amp;", "&");
+    text.lines().map(str::trim).collect::<Vec<_>>().join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MULTIPART: &str = "From: =?utf-8?Q?Ren=C3=A9e_Support?= <support@example.com>\r\n\
+        To: \"Doe, Jane\" <jane@example.com>, ops@example.com\r\n\
+        Subject: =?utf-8?B?UmU6IFZQTg==?= =?utf-8?B?IGFjY2Vzcw==?=\r\n\
+        Date: Tue, 4 Jun 2024 09:30:00 +0200\r\n\
+        Message-ID: <abc123@example.com>\r\n\
+        Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+        \r\n\
+        preamble\r\n\
+        --outer\r\n\
+        Content-Type: multipart/alternative; boundary=inner\r\n\
+        \r\n\
+        --inner\r\n\
+        Content-Type: text/plain; charset=iso-8859-1\r\n\
+        Content-Transfer-Encoding: quoted-printable\r\n\
+        \r\n\
+        Reset the token in the portal, then reconnect. Caf=E9 Wi-Fi blocks the p=\r\n\
+        ort.\r\n\
+        \r\n\
+        On Mon, 3 Jun 2024 Jane wrote:\r\n\
+        > The VPN keeps dropping.\r\n\
+        --inner\r\n\
+        Content-Type: text/html\r\n\
+        \r\n\
+        <p>HTML version</p>\r\n\
+        --inner--\r\n\
+        --outer\r\n\
+        Content-Type: text/plain; name=\"vpn.log\"\r\n\
+        Content-Disposition: attachment; filename=\"vpn.log\"\r\n\
+        Content-Transfer-Encoding: base64\r\n\
+        \r\n\
+        dHVubmVsIGRv\r\n\
+        d24=\r\n\
+        --outer--\r\n";
+
+    #[test]
+    fn test_parse_multipart_message() {
+        let message = parse_message(MULTIPART.as_bytes());
+        assert_eq!(message.headers.subject.as_deref(), Some("Re: VPN access"));
+        assert_eq!(message.headers.from.as_deref(), Some("Renée Support <support@example.com>"));
+        assert_eq!(message.headers.to, vec!["\"Doe, Jane\" <jane@example.com>", "ops@example.com"]);
+        assert_eq!(message.headers.date.as_deref(), Some("2024-06-04T09:30:00+02:00"));
+        assert_eq!(message.headers.message_id.as_deref(), Some("abc123@example.com"));
+        assert_eq!(message.body, "Reset the token in the portal, then reconnect. Café Wi-Fi blocks the port.");
+        assert_eq!(message.attachments, vec![EmailAttachment {
+            filename: "vpn.log".to_string(),
+            content_type: "text/plain".to_string(),
+            data: b"tunnel down".to_vec(),
+        }]);
+    }
+
+    #[test]
+    fn test_strip_quotes_and_signature() {
+        let body = "Thanks, that fixed it.\n\nOne more thing: the logs rotate daily.\n\n-- \nJane Doe\nSupport Lead";
+        assert_eq!(strip_quotes_and_signature(body), "Thanks, that fixed it.\n\nOne more thing: the logs rotate daily.");
+        let outlook = "Approved.\n\nSent from my iPhone\n\nFrom: Ops\nSent: Monday\nSubject: Change request";
+        assert_eq!(strip_quotes_and_signature(outlook), "Approved.");
+        let inline = "> Can we ship?\nYes, after QA.\n> And docs?\nDocs are done.";
+        assert_eq!(strip_quotes_and_signature(inline), "Yes, after QA.\nDocs are done.");
+    }
+
+    #[test]
+    fn test_split_mbox() {
+        let archive = b"From alice@example.com Mon Jun  3 10:00:00 2024\nSubject: One\n\nFirst\n>From the start\n\n\
+                        From bob@example.com Mon Jun  3 11:00:00 2024\nSubject: Two\n\nSecond\n";
+        let messages = split_mbox(archive);
+        assert_eq!(messages.len(), 2);
+        let first = parse_message(&messages[0]);
+        assert_eq!(first.headers.subject.as_deref(), Some("One"));
+        assert_eq!(first.body, "First\nFrom the start");
+        let html = parse_message(b"Content-Type: text/html\n\n<html><head><title>x</title></head><body>Hi&nbsp;there<br>Bye</body></html>");
+        assert_eq!(html.body, "Hi there\nBye");
+    }
+}
@@ -0,0 +1,309 @@
+/*!
+ * Embed Step
+ *
+ * Picks an embedding model per document and chunk from its `lang` metadata
+ * (set by the normalize step): an explicit per-language model first, then
+ * the step's model when the registry lists it for that language, then the
+ * multilingual fallback. The choice is kept as `embedding_model` metadata,
+ * which the KB copies onto indexed chunks. With an `EmbeddingBackend` (the
+ * desktop app's ONNX backend when built with the `onnx` feature) chunks are
+ * embedded in batches per model; without one (the CLI, default builds) only
+ * the routing is recorded.
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::services::model::{registry_model, EmbeddingBackend};
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+
+const DEFAULT_BATCH_SIZE: usize = 32;
+
+/// Embed step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EmbedStepConfig {
+    pub model: String,                  // The KB's embedder, used unless routing picks another
+    #[serde(default)]
+    pub language_models: HashMap<String, String>,   // ISO 639-1 code -> model
+    #[serde(default)]
+    pub multilingual_model: Option<String>,         // For languages `model` does not cover
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+}
+
+fn default_batch_size() -> usize { DEFAULT_BATCH_SIZE }
+
+/// Embed report stored with the pipeline run
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct EmbedReport {
+    pub models: BTreeMap<String, usize>,    // Chunks (or documents without chunks) per model
+    pub routed: usize,                  // Items sent to a model other than `model`
+    pub unsupported: usize,             // Items in a language no configured model covers
+    pub embedded: usize,                // Chunks given a vector
+    pub downgraded: bool,               // Smaller batches under memory pressure
+}
+
+impl EmbedStepConfig {
+    /// Model for an item in `lang`, and whether a configured model covers it
+    pub fn route(&self, lang: Option<&str>) -> (&str, bool) {
+        let Some(lang) = lang else {
+            return (&self.model, true);
+        };
+        if let Some(model) = self.language_models.get(lang) {
+            return (model, true);
+        }
+        // Models missing from the registry are trusted with every language
+        if registry_model(&self.model).is_none_or(|m| m.supports_language(lang)) {
+            return (&self.model, true);
+        }
+        match &self.multilingual_model {
+            Some(model) => (model, true),
+            None => (&self.model, false),
+        }
+    }
+
+    /// Every routed model must produce vectors of the same size, or they cannot share an index
+    fn validate(&self) -> Result<(), PipelineError> {
+        if self.model.is_empty() {
+            return Err(PipelineError::InvalidConfig("embed: model is required".to_string()));
+        }
+        if self.batch_size == 0 {
+            return Err(PipelineError::InvalidConfig("embed: batchSize must be greater than 0".to_string()));
+        }
+        let dimension = registry_model(&self.model).and_then(|m| m.dimension);
+        for model in self.language_models.values().chain(&self.multilingual_model) {
+            let other = registry_model(model).and_then(|m| m.dimension);
+            if let Some((a, b)) = dimension.zip(other).filter(|(a, b)| a != b) {
+                return Err(PipelineError::InvalidConfig(format!(
+                    "embed: {} produces {}-dimensional vectors but {} produces {}", model, b, self.model, a
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Embed step executor
+pub struct EmbedStepExecutor {
+    backend: Option<Arc<dyn EmbeddingBackend>>,
+}
+
+impl EmbedStepExecutor {
+    pub fn new() -> Self {
+        Self { backend: None }
+    }
+
+    /// Embed chunks through an embedding backend
+    pub fn with_backend(mut self, backend: Arc<dyn EmbeddingBackend>) -> Self {
+        self.backend = Some(backend);
+        self
+    }
+}
+
+impl Default for EmbedStepExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn set_model(metadata: &mut serde_json::Value, model: &str) {
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.insert("embedding_model".to_string(), model.into());
+    }
+}
+
+#[async_trait]
+impl StepExecutor for EmbedStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Embed
+    }
+
+    fn supports_downgrade(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: EmbedStepConfig = serde_json::from_value(ctx.config().clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("embed: {}", e)))?;
+        config.validate()?;
+
+        let StepData { mut documents, mut chunks } = data;
+        let mut report = EmbedReport::default();
+        let chunked: HashSet<String> = chunks.iter().map(|c| c.document_id.clone()).collect();
+        let document_languages: HashMap<String, Option<String>> = documents.iter()
+            .map(|d| (d.id.clone(), d.metadata.get("lang").and_then(|l| l.as_str()).map(str::to_string)))
+            .collect();
+
+        // Documents are routed too: the KB chunks them again at index time
+        for doc in &mut documents {
+            let (model, covered) = config.route(doc.metadata.get("lang").and_then(|l| l.as_str()));
+            let model = model.to_string();
+            if !chunked.contains(&doc.id) {
+                *report.models.entry(model.clone()).or_default() += 1;
+                report.routed += usize::from(model != config.model);
+                report.unsupported += usize::from(!covered);
+            }
+            set_model(&mut doc.metadata, &model);
+        }
+
+        let mut batches: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+        for (i, chunk) in chunks.iter_mut().enumerate() {
+            let lang = chunk.metadata.get("lang").and_then(|l| l.as_str()).map(str::to_string)
+                .or_else(|| document_languages.get(&chunk.document_id).cloned().flatten());
+            let (model, covered) = config.route(lang.as_deref());
+            let model = model.to_string();
+            *report.models.entry(model.clone()).or_default() += 1;
+            report.routed += usize::from(model != config.model);
+            report.unsupported += usize::from(!covered);
+            set_model(&mut chunk.metadata, &model);
+            batches.entry(model).or_default().push(i);
+        }
+        if report.unsupported > 0 {
+            warn!("{} items are in languages {} does not cover and no multilingualModel is set", report.unsupported, config.model);
+        }
+
+        if let Some(backend) = &self.backend {
+            for (model, indices) in &batches {
+                report.downgraded |= ctx.downgrade_requested();
+                let batch_size = if report.downgraded { config.batch_size.div_ceil(4) } else { config.batch_size };
+                for batch in indices.chunks(batch_size) {
+                    let texts: Vec<String> = batch.iter().map(|&i| chunks[i].content.clone()).collect();
+                    let vectors = backend.embed(model, &texts).await
+                        .map_err(|e| PipelineError::StepFailed(format!("embed: {}: {}", model, e)))?;
+                    if vectors.len() != batch.len() {
+                        return Err(PipelineError::StepFailed(format!(
+                            "embed: {} returned {} vectors for {} chunks", model, vectors.len(), batch.len()
+                        )));
+                    }
+                    for (&i, vector) in batch.iter().zip(vectors) {
+                        chunks[i].embedding = vector;
+                    }
+                    report.embedded += batch.len();
+                }
+            }
+        }
+
+        info!(
+            "Embed for pipeline {}: {} chunks across {} models ({} routed, {} embedded)",
+            ctx.pipeline_id, chunks.len(), report.models.len(), report.routed, report.embedded
+        );
+        Ok(StepOutcome {
+            items_processed: chunks.len(),
+            details: serde_json::to_value(&report)?,
+            data: StepData { documents, chunks },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use crate::services::model::ModelError;
+
+    /// Vector of [text length, model name length]
+    struct LengthBackend;
+
+    #[async_trait]
+    impl EmbeddingBackend for LengthBackend {
+        async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError> {
+            Ok(texts.iter().map(|t| vec![t.len() as f32, model_id.len() as f32]).collect())
+        }
+
+        async fn rerank(&self, _model_id: &str, _query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError> {
+            Ok(vec![0.0; documents.len()])
+        }
+    }
+
+    fn doc(id: &str, lang: Option<&str>) -> PipelineDocument {
+        PipelineDocument {
+            id: id.to_string(),
+            title: id.to_string(),
+            source_path: format!("/docs/{}.md", id),
+            content: String::new(),
+            content_hash: String::new(),
+            license_info: None,
+            metadata: lang.map_or_else(|| serde_json::json!({}), |l| serde_json::json!({ "lang": l })),
+        }
+    }
+
+    fn chunk(id: &str, document_id: &str, lang: Option<&str>) -> PipelineChunk {
+        PipelineChunk {
+            id: id.to_string(),
+            document_id: document_id.to_string(),
+            chunk_index: 0,
+            content: format!("content of {}", id),
+            content_hash: String::new(),
+            metadata: lang.map_or_else(|| serde_json::json!({}), |l| serde_json::json!({ "lang": l })),
+            embedding: Vec::new(),
+        }
+    }
+
+    fn embed_context(config: serde_json::Value) -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Embed,
+            config,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Embed".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    const ENGLISH: &str = "sentence-transformers/all-MiniLM-L6-v2";
+    const MULTILINGUAL: &str = "intfloat/multilingual-e5-small";
+
+    #[tokio::test]
+    async fn test_chunks_routed_by_language() {
+        let data = StepData {
+            documents: vec![doc("d1", Some("en")), doc("d2", Some("de")), doc("d3", Some("fr"))],
+            chunks: vec![chunk("c1", "d1", Some("en")), chunk("c2", "d2", Some("de")), chunk("c3", "d2", None), chunk("c4", "d2", Some("ja"))],
+        };
+        let config = serde_json::json!({ "model": ENGLISH, "multilingualModel": MULTILINGUAL, "languageModels": { "ja": "custom/ja-embedder" } });
+        let outcome = EmbedStepExecutor::new().with_backend(Arc::new(LengthBackend))
+            .execute(&embed_context(config), data)
+            .await
+            .unwrap();
+
+        let models: Vec<&str> = outcome.data.chunks.iter().map(|c| c.metadata["embedding_model"].as_str().unwrap()).collect();
+        // c3 has no language of its own and takes its document's
+        assert_eq!(models, vec![ENGLISH, MULTILINGUAL, MULTILINGUAL, "custom/ja-embedder"]);
+        assert_eq!(outcome.data.chunks[1].embedding, vec![13.0, MULTILINGUAL.len() as f32]);
+        // d3 has no chunks; it is counted as one item
+        assert_eq!(outcome.data.documents[2].metadata["embedding_model"], MULTILINGUAL);
+        assert_eq!(outcome.details["models"][MULTILINGUAL], 3);
+        assert_eq!(outcome.details["routed"], 4);
+        assert_eq!(outcome.details["embedded"], 4);
+    }
+
+    #[tokio::test]
+    async fn test_routing_without_backend_or_fallback() {
+        let data = StepData { documents: vec![doc("d1", Some("de"))], chunks: vec![chunk("c1", "d1", None)] };
+        let outcome = EmbedStepExecutor::new()
+            .execute(&embed_context(serde_json::json!({ "model": ENGLISH })), data)
+            .await
+            .unwrap();
+        assert_eq!(outcome.data.chunks[0].metadata["embedding_model"], ENGLISH);
+        assert!(outcome.data.chunks[0].embedding.is_empty());
+        assert_eq!(outcome.details["unsupported"], 1);
+        assert_eq!(outcome.details["embedded"], 0);
+
+        // bge-m3 vectors (1024) cannot share an index with MiniLM's (384)
+        let mismatched = serde_json::json!({ "model": ENGLISH, "multilingualModel": "BAAI/bge-m3" });
+        let result = EmbedStepExecutor::new().execute(&embed_context(mismatched), StepData::default()).await;
+        assert!(matches!(result, Err(PipelineError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,467 @@
+/*!
+ * Eval Step
+ *
+ * Measures retrieval quality of the freshly built index with recall@k, MRR and
+ * nDCG@k over a small query set, failing the run below `qualityThreshold`.
+ */
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use crate::services::vector::{VectorDbError, VectorDbServiceTrait};
+use crate::schemas::SearchResult;
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+
+const DEFAULT_K: usize = 5;
+const DEFAULT_SAMPLE_SIZE: usize = 20;
+const MIN_QUERY_WORDS: usize = 3;
+const MAX_QUERY_WORDS: usize = 8;
+
+/// A single evaluation query with its relevant chunk or document ids
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalQuery {
+    pub query: String,
+    pub relevant_ids: Vec<String>,
+}
+
+/// Metric used for the quality gate
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum EvalMetric {
+    Recall,
+    Mrr,
+    Ndcg,
+}
+
+/// Eval step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EvalStepConfig {
+    #[serde(default = "default_k")]
+    pub k: usize,
+    pub quality_threshold: Option<f64>,
+    #[serde(default = "default_metric")]
+    pub metric: EvalMetric,
+    #[serde(default)]
+    pub queries: Vec<EvalQuery>,
+    pub queries_path: Option<String>,
+    #[serde(default = "default_sample_size")]
+    pub sample_size: usize,
+}
+
+fn default_k() -> usize { DEFAULT_K }
+fn default_metric() -> EvalMetric { EvalMetric::Ndcg }
+fn default_sample_size() -> usize { DEFAULT_SAMPLE_SIZE }
+
+impl Default for EvalStepConfig {
+    fn default() -> Self {
+        Self {
+            k: DEFAULT_K,
+            quality_threshold: None,
+            metric: EvalMetric::Ndcg,
+            queries: Vec::new(),
+            queries_path: None,
+            sample_size: DEFAULT_SAMPLE_SIZE,
+        }
+    }
+}
+
+/// Aggregated retrieval metrics (averaged over queries)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RetrievalMetrics {
+    pub recall_at_k: f64,
+    pub mrr: f64,
+    pub ndcg_at_k: f64,
+    pub query_count: usize,
+}
+
+impl RetrievalMetrics {
+    pub fn get(&self, metric: EvalMetric) -> f64 {
+        match metric {
+            EvalMetric::Recall => self.recall_at_k,
+            EvalMetric::Mrr => self.mrr,
+            EvalMetric::Ndcg => self.ndcg_at_k,
+        }
+    }
+}
+
+/// Per-query scores stored in the report
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueryScore {
+    pub query: String,
+    pub recall_at_k: f64,
+    pub reciprocal_rank: f64,
+    pub ndcg_at_k: f64,
+}
+
+/// Evaluation report stored with the pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EvalReport {
+    pub k: usize,
+    pub metric: EvalMetric,
+    pub quality_threshold: Option<f64>,
+    pub query_source: String,         // "config", "file" or "generated"
+    pub metrics: RetrievalMetrics,
+    pub queries: Vec<QueryScore>,
+    pub passed: bool,
+}
+
+/// Score one ranked result list; `ranked_ids` holds one id set per rank
+/// (chunk id and document id) so relevance may be judged at either level.
+/// All three metrics only look at the first `k` ranks.
+pub fn score_query(ranked_ids: &[Vec<String>], relevant: &HashSet<String>, k: usize) -> (f64, f64, f64) {
+    if relevant.is_empty() {
+        return (0.0, 0.0, 0.0);
+    }
+
+    let mut found = HashSet::new();
+    let mut reciprocal_rank = 0.0;
+    let mut dcg = 0.0;
+
+    for (rank, ids) in ranked_ids.iter().take(k).enumerate() {
+        let hit = ids.iter().find(|id| relevant.contains(*id) && !found.contains(*id));
+        let Some(hit) = hit else { continue };
+
+        if reciprocal_rank == 0.0 {
+            reciprocal_rank = 1.0 / (rank + 1) as f64;
+        }
+        found.insert(hit.clone());
+        dcg += 1.0 / ((rank + 2) as f64).log2();
+    }
+
+    let ideal_hits = relevant.len().min(k);
+    let idcg: f64 = (0..ideal_hits).map(|rank| 1.0 / ((rank + 2) as f64).log2()).sum();
+
+    let recall = found.len() as f64 / relevant.len() as f64;
+    let ndcg = if idcg > 0.0 { dcg / idcg } else { 0.0 };
+
+    (recall, reciprocal_rank, ndcg)
+}
+
+/// Average per-query scores into aggregate metrics
+pub fn aggregate_scores(scores: &[QueryScore]) -> RetrievalMetrics {
+    if scores.is_empty() {
+        return RetrievalMetrics::default();
+    }
+
+    let n = scores.len() as f64;
+    RetrievalMetrics {
+        recall_at_k: scores.iter().map(|s| s.recall_at_k).sum::<f64>() / n,
+        mrr: scores.iter().map(|s| s.reciprocal_rank).sum::<f64>() / n,
+        ndcg_at_k: scores.iter().map(|s| s.ndcg_at_k).sum::<f64>() / n,
+        query_count: scores.len(),
+    }
+}
+
+/// Generate known-item queries from indexed chunks
+///
+/// Each query is a short verbatim phrase from a chunk so the chunk itself is
+/// the relevant item. MVP: no LLM-generated questions yet.
+pub fn generate_queries(chunks: &[PipelineChunk], sample_size: usize) -> Vec<EvalQuery> {
+    if chunks.is_empty() || sample_size == 0 {
+        return Vec::new();
+    }
+
+    // Spread samples evenly across the corpus
+    let stride = (chunks.len() / sample_size).max(1);
+
+    chunks
+        .iter()
+        .step_by(stride)
+        .filter_map(|chunk| {
+            let phrase = chunk.content.lines().find_map(|line| {
+                let words: Vec<&str> = line.split_whitespace().collect();
+                (words.len() >= MIN_QUERY_WORDS)
+                    .then(|| words[..words.len().min(MAX_QUERY_WORDS)].join(" "))
+            })?;
+
+            // Lexical index matches contiguous text, skip phrases that don't survive joining
+            if !chunk.content.to_lowercase().contains(&phrase.to_lowercase()) {
+                return None;
+            }
+
+            Some(EvalQuery {
+                query: phrase,
+                relevant_ids: vec![chunk.id.clone()],
+            })
+        })
+        .take(sample_size)
+        .collect()
+}
+
+/// Run an evaluation query through hybrid search
+///
+/// MVP: no embedding service is wired into evaluation yet, so the vector leg gets
+/// an empty query vector and we fall back to lexical search when it is unavailable.
+pub async fn retrieve_for_eval(
+    vector_service: &(dyn VectorDbServiceTrait + Send + Sync),
+    kb_id: &str,
+    query: &str,
+    k: usize,
+) -> Result<Vec<SearchResult>, VectorDbError> {
+    match vector_service.hybrid_search(kb_id, query, &[], k, None).await {
+        Ok(results) => Ok(results),
+        Err(e) => {
+            debug!("Hybrid search unavailable for eval ({}), using BM25", e);
+            vector_service.bm25_search(kb_id, query, k, None).await
+        }
+    }
+}
+
+/// Eval step executor backed by the vector service's hybrid search
+pub struct EvalStepExecutor {
+    vector_service: Arc<dyn VectorDbServiceTrait + Send + Sync>,
+}
+
+impl EvalStepExecutor {
+    pub fn new(vector_service: Arc<dyn VectorDbServiceTrait + Send + Sync>) -> Self {
+        Self { vector_service }
+    }
+
+    async fn load_queries(&self, config: &EvalStepConfig, data: &StepData) -> Result<(Vec<EvalQuery>, String), PipelineError> {
+        if !config.queries.is_empty() {
+            return Ok((config.queries.clone(), "config".to_string()));
+        }
+
+        if let Some(path) = &config.queries_path {
+            let content = tokio::fs::read_to_string(path).await?;
+            let queries: Vec<EvalQuery> = serde_json::from_str(&content)?;
+            return Ok((queries, "file".to_string()));
+        }
+
+        Ok((generate_queries(&data.chunks, config.sample_size), "generated".to_string()))
+    }
+
+}
+
+#[async_trait]
+impl StepExecutor for EvalStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Eval
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: EvalStepConfig = if ctx.config().is_null() {
+            EvalStepConfig::default()
+        } else {
+            serde_json::from_value(ctx.config().clone())
+                .map_err(|e| PipelineError::InvalidConfig(format!("eval: {}", e)))?
+        };
+
+        if config.k == 0 {
+            return Err(PipelineError::InvalidConfig("eval: k must be greater than 0".to_string()));
+        }
+
+        let kb_id = ctx.kb_id.as_deref()
+            .ok_or_else(|| PipelineError::InvalidConfig("eval: pipeline has no target KB".to_string()))?;
+
+        let (queries, query_source) = self.load_queries(&config, &data).await?;
+        if queries.is_empty() {
+            warn!("Eval step for KB {} has no queries, skipping measurement", kb_id);
+        }
+
+        let mut scores = Vec::with_capacity(queries.len());
+        for eval_query in &queries {
+            let results = retrieve_for_eval(self.vector_service.as_ref(), kb_id, &eval_query.query, config.k).await?;
+            let ranked_ids: Vec<Vec<String>> = results
+                .into_iter()
+                .map(|r| vec![r.chunk_id, r.document_id])
+                .collect();
+            let relevant: HashSet<String> = eval_query.relevant_ids.iter().cloned().collect();
+
+            let (recall_at_k, reciprocal_rank, ndcg_at_k) = score_query(&ranked_ids, &relevant, config.k);
+            scores.push(QueryScore {
+                query: eval_query.query.clone(),
+                recall_at_k,
+                reciprocal_rank,
+                ndcg_at_k,
+            });
+        }
+
+        let metrics = aggregate_scores(&scores);
+        let score = metrics.get(config.metric);
+        let passed = match config.quality_threshold {
+            Some(threshold) if !queries.is_empty() => score >= threshold,
+            _ => true,
+        };
+
+        let report = EvalReport {
+            k: config.k,
+            metric: config.metric,
+            quality_threshold: config.quality_threshold,
+            query_source,
+            metrics,
+            queries: scores,
+            passed,
+        };
+        let details = serde_json::to_value(&report)?;
+
+        info!(
+            "Eval for KB {}: recall@{}={:.3} mrr={:.3} ndcg@{}={:.3} over {} queries",
+            kb_id, report.k, report.metrics.recall_at_k, report.metrics.mrr,
+            report.k, report.metrics.ndcg_at_k, report.metrics.query_count
+        );
+
+        if !passed {
+            return Err(PipelineError::QualityGateFailed(
+                format!(
+                    "{:?} {:.3} below threshold {:.3}",
+                    config.metric, score, config.quality_threshold.unwrap_or_default()
+                ),
+                details,
+            ));
+        }
+
+        Ok(StepOutcome {
+            items_processed: report.metrics.query_count,
+            data,
+            details,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use crate::schemas::{CitationInfo, VectorSchema};
+    use crate::services::vector::CollectionStats;
+
+    /// Lexical-only index over a fixed set of chunks
+    struct StaticIndex {
+        chunks: Vec<PipelineChunk>,
+    }
+
+    #[async_trait]
+    impl VectorDbServiceTrait for StaticIndex {
+        async fn create_collection(&self, _kb_id: &str, _schema: &VectorSchema) -> Result<(), VectorDbError> { Ok(()) }
+        async fn upsert_vectors(&self, _kb_id: &str, _vectors: Vec<VectorSchema>) -> Result<(), VectorDbError> { Ok(()) }
+        async fn search(&self, _kb_id: &str, _query_vector: &[f32], _limit: usize, _filter: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> {
+            Err(VectorDbError::ValidationError("no vectors".to_string()))
+        }
+        async fn hybrid_search(&self, kb_id: &str, query: &str, _query_vector: &[f32], limit: usize, filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> {
+            self.bm25_search(kb_id, query, limit, filters).await
+        }
+        async fn bm25_search(&self, kb_id: &str, query: &str, limit: usize, _filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> {
+            let query = query.to_lowercase();
+            Ok(self.chunks.iter()
+                .filter(|c| c.content.to_lowercase().contains(&query))
+                .take(limit)
+                .map(|c| SearchResult {
+                    chunk_id: c.id.clone(),
+                    document_id: c.document_id.clone(),
+                    kb_id: kb_id.to_string(),
+                    score: 1.0,
+                    content: c.content.clone(),
+                    snippet: c.content.clone(),
+                    highlights: Vec::new(),
+                    metadata: serde_json::json!({}),
+                    citation: CitationInfo {
+                        title: String::new(),
+                        source_path: String::new(),
+                        license: None,
+                        version: None,
+                        anchor: None,
+                        page_number: None,
+                        time_range: None,
+                    },
+                })
+                .collect())
+        }
+        async fn delete_collection(&self, _kb_id: &str) -> Result<(), VectorDbError> { Ok(()) }
+        async fn delete_document(&self, _kb_id: &str, _document_id: &str) -> Result<(), VectorDbError> { Ok(()) }
+        async fn get_collection_stats(&self, _kb_id: &str) -> Result<CollectionStats, VectorDbError> {
+            Err(VectorDbError::CollectionNotFound("unused".to_string()))
+        }
+    }
+
+    fn chunk(id: &str, content: &str) -> PipelineChunk {
+        PipelineChunk {
+            id: id.to_string(),
+            document_id: format!("doc_{}", id),
+            chunk_index: 0,
+            content: content.to_string(),
+            content_hash: String::new(),
+            metadata: serde_json::json!({}),
+            embedding: Vec::new(),
+        }
+    }
+
+    fn eval_context(config: serde_json::Value) -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Eval,
+            config,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Eval".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    #[test]
+    fn test_score_query() {
+        let relevant: HashSet<String> = ["a".to_string()].into_iter().collect();
+        let ranked = vec![vec!["b".to_string()], vec!["a".to_string()]];
+
+        let (recall, rr, ndcg) = score_query(&ranked, &relevant, 5);
+        assert_eq!(recall, 1.0);
+        assert_eq!(rr, 0.5);
+        assert!((ndcg - 1.0 / 3f64.log2()).abs() < 1e-9);
+
+        // A hit below rank k does not count towards MRR@k either
+        let (recall, rr, ndcg) = score_query(&ranked, &relevant, 1);
+        assert_eq!(recall, 0.0);
+        assert_eq!(rr, 0.0);
+        assert_eq!(ndcg, 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_generated_queries_pass() {
+        let chunks = vec![
+            chunk("c1", "Rust ownership rules prevent data races at compile time"),
+            chunk("c2", "Tantivy provides full text search for the lexical index"),
+        ];
+        let executor = EvalStepExecutor::new(Arc::new(StaticIndex { chunks: chunks.clone() }));
+        let data = StepData { documents: vec![], chunks };
+
+        let outcome = executor
+            .execute(&eval_context(serde_json::json!({"qualityThreshold": 0.9})), data)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.items_processed, 2);
+        assert_eq!(outcome.details["query_source"], "generated");
+        assert_eq!(outcome.details["metrics"]["recall_at_k"], 1.0);
+    }
+
+    #[tokio::test]
+    async fn test_fails_below_threshold() {
+        let executor = EvalStepExecutor::new(Arc::new(StaticIndex {
+            chunks: vec![chunk("c1", "unrelated content about cooking pasta")],
+        }));
+        let config = serde_json::json!({
+            "qualityThreshold": 0.5,
+            "metric": "mrr",
+            "queries": [{"query": "vector databases", "relevantIds": ["c9"]}]
+        });
+
+        let err = executor.execute(&eval_context(config), StepData::default()).await.unwrap_err();
+        match err {
+            PipelineError::QualityGateFailed(_, report) => assert_eq!(report["passed"], false),
+            other => panic!("unexpected error: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,204 @@
+/*!
+ * Extract Entities Step
+ *
+ * Builds the KB's knowledge graph: entities and same-sentence relations of
+ * each document go to the graph tables, replacing what an earlier run stored
+ * for that document. Entities come from the rule-based extractor, plus the
+ * injected `EntityRecognizer` (the Python worker) when `ner` is enabled.
+ */
+
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::modules::graph::{extract_graph, EntitySpan, ExtractOptions, GraphService};
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+use super::redact::EntityRecognizer;
+
+/// Extract entities step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ExtractEntitiesStepConfig {
+    #[serde(default = "default_min_mentions")]
+    pub min_mentions: u32,
+    #[serde(default = "default_max_entities")]
+    pub max_entities: usize,        // Per document
+    /// Add entities from the configured recognizer (people, organizations, ...)
+    #[serde(default)]
+    pub ner: bool,
+}
+
+fn default_min_mentions() -> u32 { ExtractOptions::default().min_mentions }
+fn default_max_entities() -> usize { ExtractOptions::default().max_entities }
+
+impl Default for ExtractEntitiesStepConfig {
+    fn default() -> Self {
+        Self {
+            min_mentions: default_min_mentions(),
+            max_entities: default_max_entities(),
+            ner: false,
+        }
+    }
+}
+
+/// Extraction report stored with the pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExtractEntitiesReport {
+    pub documents: usize,
+    pub entities: usize,            // Summed over documents
+    pub relations: usize,
+    pub ner_used: bool,
+}
+
+/// Writes each document's entities and relations to the graph tables
+pub struct ExtractEntitiesStepExecutor {
+    graph_service: Arc<GraphService>,
+    recognizer: Option<Arc<dyn EntityRecognizer>>,
+}
+
+impl ExtractEntitiesStepExecutor {
+    pub fn new(graph_service: Arc<GraphService>) -> Self {
+        Self { graph_service, recognizer: None }
+    }
+
+    /// Use an NER backend when the step config enables `ner`
+    pub fn with_recognizer(mut self, recognizer: Arc<dyn EntityRecognizer>) -> Self {
+        self.recognizer = Some(recognizer);
+        self
+    }
+}
+
+#[async_trait]
+impl StepExecutor for ExtractEntitiesStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::ExtractEntities
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: ExtractEntitiesStepConfig = if ctx.config().is_null() {
+            ExtractEntitiesStepConfig::default()
+        } else {
+            serde_json::from_value(ctx.config().clone())
+                .map_err(|e| PipelineError::InvalidConfig(format!("extract_entities: {}", e)))?
+        };
+        if config.min_mentions == 0 || config.max_entities == 0 {
+            return Err(PipelineError::InvalidConfig("extract_entities: minMentions and maxEntities must be greater than 0".to_string()));
+        }
+        let kb_id = ctx.kb_id.as_deref()
+            .ok_or_else(|| PipelineError::InvalidConfig("extract_entities: pipeline has no target KB".to_string()))?;
+
+        let recognizer = self.recognizer.as_ref().filter(|_| config.ner);
+        if config.ner && recognizer.is_none() {
+            warn!("Extract entities step for pipeline {}: NER requested but no recognizer configured", ctx.pipeline_id);
+        }
+        let options = ExtractOptions {
+            min_mentions: config.min_mentions,
+            max_entities: config.max_entities,
+            ..Default::default()
+        };
+
+        let mut report = ExtractEntitiesReport { documents: 0, entities: 0, relations: 0, ner_used: recognizer.is_some() };
+        for doc in &data.documents {
+            let detected: Vec<EntitySpan> = match recognizer {
+                Some(recognizer) => recognizer.recognize(&doc.content).await?
+                    .into_iter()
+                    .map(|entity| EntitySpan { entity_type: entity.category, start: entity.start, end: entity.end })
+                    .collect(),
+                None => Vec::new(),
+            };
+            let graph = extract_graph(&doc.content, &detected, &options);
+            self.graph_service.replace_document(kb_id, &doc.id, &graph).await?;
+
+            report.documents += 1;
+            report.entities += graph.entities.len();
+            report.relations += graph.relations.len();
+        }
+
+        info!(
+            "Extracted {} entities and {} relations from {} documents into the graph of KB {}",
+            report.entities, report.relations, report.documents, kb_id
+        );
+        Ok(StepOutcome {
+            items_processed: report.documents,
+            data,
+            details: serde_json::to_value(&report)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use tempfile::TempDir;
+    use crate::services::sql::{SqlConfig, SqlService};
+    use super::super::redact::DetectedEntity;
+
+    /// Tags every "Jane Doe" as a person
+    struct PersonRecognizer;
+
+    #[async_trait]
+    impl EntityRecognizer for PersonRecognizer {
+        async fn recognize(&self, text: &str) -> Result<Vec<DetectedEntity>, PipelineError> {
+            Ok(text.match_indices("Jane Doe")
+                .map(|(start, name)| DetectedEntity { category: "person".to_string(), start, end: start + name.len() })
+                .collect())
+        }
+    }
+
+    fn extract_context(config: serde_json::Value) -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::ExtractEntities,
+            config,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Graph".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    #[tokio::test]
+    async fn test_extract_entities_step() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let graph_service = Arc::new(GraphService::new(Arc::new(sql_service)));
+        let executor = ExtractEntitiesStepExecutor::new(graph_service.clone()).with_recognizer(Arc::new(PersonRecognizer));
+
+        let document = PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "notes.md".to_string(),
+            source_path: "/docs/notes.md".to_string(),
+            content: "Ask Jane Doe before touching Lance DB. Lance DB backs the index.".to_string(),
+            content_hash: String::new(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        };
+        let data = StepData { documents: vec![document], chunks: Vec::new() };
+
+        let outcome = executor.execute(&extract_context(serde_json::json!({ "ner": true })), data.clone()).await.unwrap();
+        assert_eq!(outcome.details["entities"], 2);
+        assert_eq!(outcome.details["relations"], 1);
+        assert_eq!(outcome.details["ner_used"], true);
+        assert_eq!(outcome.data.documents.len(), 1);
+
+        let expansion = graph_service.expand_query("kb_1", "lance db", 3).await.unwrap();
+        assert_eq!(expansion.related[0].name, "Jane Doe");
+        assert_eq!(expansion.related[0].entity_type, "person");
+
+        let outcome = executor.execute(&extract_context(serde_json::json!({ "minMentions": 2 })), data.clone()).await.unwrap();
+        assert_eq!(outcome.details["entities"], 1);
+        assert_eq!(outcome.details["ner_used"], false);
+        assert!(executor.execute(&extract_context(serde_json::json!({ "maxEntities": 0 })), data).await.is_err());
+    }
+}
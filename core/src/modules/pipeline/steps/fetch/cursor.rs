@@ -0,0 +1,168 @@
+/*!
+ * Fetch Cursors
+ *
+ * Updated-since cursors of fetch steps, per pipeline and step config. A run
+ * records the newest update it saw as pending; whoever ingests the run's
+ * output commits it (`commit_run`), so a run that fails after fetching is
+ * fetched again from the previous cursor. Sources without an updated-since
+ * query record the items they have seen instead (`seen`), versioned by
+ * lastmod or content hash, with the same pending/commit cycle.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, Utc};
+use diesel::prelude::*;
+
+use crate::schemas::schema::{fetch_cursors, fetch_seen_items};
+use crate::services::sql::{SqlError, SqlService};
+use super::super::super::errors::PipelineError;
+
+/// Cursor store in the app database
+pub struct FetchCursorStore {
+    sql_service: Arc<SqlService>,
+}
+
+impl FetchCursorStore {
+    pub fn new(sql_service: Arc<SqlService>) -> Self {
+        Self { sql_service }
+    }
+
+    /// Committed cursor; None before the first ingested run
+    pub async fn get(&self, pipeline_id: &str, cursor_key: &str) -> Result<Option<DateTime<Utc>>, PipelineError> {
+        let (pipeline_id, cursor_key) = (pipeline_id.to_string(), cursor_key.to_string());
+        let cursor: Option<Option<String>> = self.sql_service.with_app_transaction(move |conn| {
+            Ok(fetch_cursors::table
+                .filter(fetch_cursors::pipeline_id.eq(pipeline_id))
+                .filter(fetch_cursors::cursor_key.eq(cursor_key))
+                .select(fetch_cursors::cursor)
+                .first(conn)
+                .optional()?)
+        }).await.map_err(store_error)?;
+
+        cursor.flatten()
+            .map(|c| DateTime::parse_from_rfc3339(&c)
+                .map(|t| t.with_timezone(&Utc))
+                .map_err(|e| PipelineError::StepFailed(format!("Corrupt fetch cursor '{}': {}", c, e))))
+            .transpose()
+    }
+
+    /// Record the cursor reached by `run_id`, to be committed once its output is ingested
+    pub async fn set_pending(&self, pipeline_id: &str, cursor_key: &str, run_id: &str, cursor: DateTime<Utc>) -> Result<(), PipelineError> {
+        let (pipeline_id, cursor_key, run_id) = (pipeline_id.to_string(), cursor_key.to_string(), run_id.to_string());
+        let cursor = cursor.to_rfc3339();
+        self.sql_service.with_app_transaction(move |conn| {
+            let key = fetch_cursors::table
+                .filter(fetch_cursors::pipeline_id.eq(&pipeline_id))
+                .filter(fetch_cursors::cursor_key.eq(&cursor_key));
+            let updated = diesel::update(key)
+                .set((
+                    fetch_cursors::pending_cursor.eq(&cursor),
+                    fetch_cursors::pending_run_id.eq(&run_id),
+                    fetch_cursors::updated_at.eq(Utc::now().naive_utc()),
+                ))
+                .execute(conn)?;
+            if updated == 0 {
+                diesel::insert_into(fetch_cursors::table)
+                    .values((
+                        fetch_cursors::pipeline_id.eq(&pipeline_id),
+                        fetch_cursors::cursor_key.eq(&cursor_key),
+                        fetch_cursors::pending_cursor.eq(&cursor),
+                        fetch_cursors::pending_run_id.eq(&run_id),
+                        fetch_cursors::updated_at.eq(Utc::now().naive_utc()),
+                    ))
+                    .execute(conn)?;
+            }
+            Ok(())
+        }).await.map_err(store_error)
+    }
+
+    /// Committed versions of the items seen under a cursor key, by item key
+    pub async fn seen(&self, pipeline_id: &str, cursor_key: &str) -> Result<HashMap<String, String>, PipelineError> {
+        let (pipeline_id, cursor_key) = (pipeline_id.to_string(), cursor_key.to_string());
+        let items: Vec<(String, Option<String>)> = self.sql_service.with_app_transaction(move |conn| {
+            Ok(fetch_seen_items::table
+                .filter(fetch_seen_items::pipeline_id.eq(pipeline_id))
+                .filter(fetch_seen_items::cursor_key.eq(cursor_key))
+                .filter(fetch_seen_items::version.is_not_null())
+                .select((fetch_seen_items::item_key, fetch_seen_items::version))
+                .load(conn)?)
+        }).await.map_err(store_error)?;
+        Ok(items.into_iter().filter_map(|(key, version)| Some((key, version?))).collect())
+    }
+
+    /// Record item versions fetched by `run_id`, to be committed with its cursors
+    pub async fn set_seen_pending(&self, pipeline_id: &str, cursor_key: &str, run_id: &str, items: Vec<(String, String)>) -> Result<(), PipelineError> {
+        let (pipeline_id, cursor_key, run_id) = (pipeline_id.to_string(), cursor_key.to_string(), run_id.to_string());
+        self.sql_service.with_app_transaction(move |conn| {
+            let now = Utc::now().naive_utc();
+            for (item_key, version) in &items {
+                let key = fetch_seen_items::table
+                    .filter(fetch_seen_items::pipeline_id.eq(&pipeline_id))
+                    .filter(fetch_seen_items::cursor_key.eq(&cursor_key))
+                    .filter(fetch_seen_items::item_key.eq(item_key));
+                let updated = diesel::update(key)
+                    .set((
+                        fetch_seen_items::pending_version.eq(version),
+                        fetch_seen_items::pending_run_id.eq(&run_id),
+                        fetch_seen_items::updated_at.eq(now),
+                    ))
+                    .execute(conn)?;
+                if updated == 0 {
+                    diesel::insert_into(fetch_seen_items::table)
+                        .values((
+                            fetch_seen_items::pipeline_id.eq(&pipeline_id),
+                            fetch_seen_items::cursor_key.eq(&cursor_key),
+                            fetch_seen_items::item_key.eq(item_key),
+                            fetch_seen_items::pending_version.eq(version),
+                            fetch_seen_items::pending_run_id.eq(&run_id),
+                            fetch_seen_items::updated_at.eq(now),
+                        ))
+                        .execute(conn)?;
+                }
+            }
+            Ok(())
+        }).await.map_err(store_error)
+    }
+
+    /// Make the cursors `run_id` reached the ones the next run starts from,
+    /// and the items it fetched seen; the number of cursors and items advanced
+    pub async fn commit_run(&self, run_id: &str) -> Result<usize, PipelineError> {
+        let run_id = run_id.to_string();
+        self.sql_service.with_app_transaction(move |conn| {
+            let now = Utc::now().naive_utc();
+            let cursors = diesel::update(fetch_cursors::table.filter(fetch_cursors::pending_run_id.eq(&run_id)))
+                .set((
+                    fetch_cursors::cursor.eq(fetch_cursors::pending_cursor),
+                    fetch_cursors::pending_cursor.eq(None::<String>),
+                    fetch_cursors::pending_run_id.eq(None::<String>),
+                    fetch_cursors::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            let items = diesel::update(fetch_seen_items::table.filter(fetch_seen_items::pending_run_id.eq(&run_id)))
+                .set((
+                    fetch_seen_items::version.eq(fetch_seen_items::pending_version),
+                    fetch_seen_items::pending_version.eq(None::<String>),
+                    fetch_seen_items::pending_run_id.eq(None::<String>),
+                    fetch_seen_items::updated_at.eq(now),
+                ))
+                .execute(conn)?;
+            Ok(cursors + items)
+        }).await.map_err(store_error)
+    }
+
+    /// Forget a pipeline's cursors and seen items so its next run is a full
+    /// sync; the number of rows removed
+    pub async fn reset(&self, pipeline_id: &str) -> Result<usize, PipelineError> {
+        let pipeline_id = pipeline_id.to_string();
+        self.sql_service.with_app_transaction(move |conn| {
+            let cursors = diesel::delete(fetch_cursors::table.filter(fetch_cursors::pipeline_id.eq(&pipeline_id))).execute(conn)?;
+            let items = diesel::delete(fetch_seen_items::table.filter(fetch_seen_items::pipeline_id.eq(&pipeline_id))).execute(conn)?;
+            Ok(cursors + items)
+        }).await.map_err(store_error)
+    }
+}
+
+fn store_error(e: SqlError) -> PipelineError {
+    PipelineError::StepFailed(format!("Fetch cursor store: {}", e))
+}
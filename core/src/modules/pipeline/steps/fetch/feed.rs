@@ -0,0 +1,457 @@
+/*!
+ * Feed and Sitemap Connectors
+ *
+ * `feed` reads RSS 2.0, RSS 1.0 (RDF) and Atom feeds, one document per entry
+ * (optionally the linked page instead of the entry's summary). `sitemap`
+ * reads XML sitemaps and sitemap indexes, plain or gzipped, and downloads the
+ * listed pages. Neither can ask the server for changes only, so both report
+ * every item they ingest with a version (its update date, or a hash of its
+ * content when there is none) and skip items whose version was already
+ * ingested by an earlier run.
+ */
+
+use std::collections::HashSet;
+use std::io::Read as _;
+use std::sync::OnceLock;
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use tracing::debug;
+
+use super::super::super::errors::PipelineError;
+use super::super::email::html_to_text;
+use super::{collapse_blank_lines, download, fetched_document, send, FetchBatch, FetchRequest, SourceConnector};
+use crate::modules::kb::DocumentFingerprint;
+
+/// Largest feed, sitemap or page downloaded
+const MAX_DOCUMENT_BYTES: usize = 10 * 1024 * 1024;
+
+/// Nesting of sitemap indexes followed
+const MAX_SITEMAP_DEPTH: usize = 3;
+
+/// Feed source configuration (camelCase keys in the fetch step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FeedConfig {
+    pub url: String,
+    #[serde(default)]
+    pub fetch_pages: bool,          // Ingest the page each entry links to rather than its summary
+}
+
+/// Sitemap source configuration (camelCase keys in the fetch step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SitemapConfig {
+    pub url: String,                // Sitemap or sitemap index
+    #[serde(default)]
+    pub include: Vec<String>,       // URL prefixes to keep; all pages when empty
+    #[serde(default)]
+    pub exclude: Vec<String>,       // URL prefixes to drop
+    #[serde(default = "default_max_pages")]
+    pub max_pages: usize,           // Downloads per run; the rest wait for the next run
+}
+
+fn default_max_pages() -> usize { 500 }
+
+/// RSS and Atom feed entries
+pub struct FeedConnector;
+
+/// Pages listed in XML sitemaps
+pub struct SitemapConnector;
+
+/// Page URL and lastmod listed in a sitemap
+type SitemapPage = (String, Option<String>);
+
+/// Entry of a feed, whatever its dialect
+#[derive(Debug, Default)]
+struct FeedEntry {
+    key: String,                    // GUID, Atom id or link
+    title: String,
+    link: Option<String>,
+    updated: Option<DateTime<Utc>>,
+    html: String,
+    author: Option<String>,
+    categories: Vec<String>,
+}
+
+#[async_trait]
+impl SourceConnector for FeedConnector {
+    fn source_type(&self) -> &str {
+        "feed"
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, request: &FetchRequest<'_>) -> Result<FetchBatch, PipelineError> {
+        let config: FeedConfig = serde_json::from_value(request.config.clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("fetch: feed: {}", e)))?;
+        let xml = fetch_xml(client, &config.url).await?;
+        let (feed_title, entries) = parse_feed(&xml)
+            .map_err(|e| PipelineError::StepFailed(format!("{} is not a feed: {}", config.url, e)))?;
+
+        let mut batch = FetchBatch::default();
+        for entry in &entries {
+            let version = entry.updated
+                .map(|t| t.to_rfc3339())
+                .unwrap_or_else(|| DocumentFingerprint::hash_content(&format!("{}\n{}", entry.title, entry.html)));
+            if request.seen.get(&entry.key) == Some(&version) {
+                continue;
+            }
+
+            let page = match (&entry.link, config.fetch_pages) {
+                (Some(link), true) => fetch_page(client, link).await?,
+                _ => None,
+            };
+            let content = match page {
+                Some((_, text)) => text,
+                None => collapse_blank_lines(&html_to_text(&entry.html)),
+            };
+            if content.trim().is_empty() {
+                continue;
+            }
+            let metadata = json!({
+                "feed": {
+                    "feed_url": config.url,
+                    "feed_title": feed_title,
+                    "entry_id": entry.key,
+                    "link": entry.link,
+                    "author": entry.author,
+                    "categories": entry.categories,
+                    "updated_at": entry.updated,
+                }
+            });
+            let id = format!("doc_feed_{}", &DocumentFingerprint::hash_content(&entry.key)[..16]);
+            let source_path = entry.link.clone().unwrap_or_else(|| config.url.clone());
+            let title = if entry.title.is_empty() { source_path.clone() } else { entry.title.clone() };
+            batch.documents.push(fetched_document(id, title, source_path, content, metadata));
+            batch.seen.push((entry.key.clone(), version));
+        }
+        debug!("Feed {} has {} entries, {} new or changed", config.url, entries.len(), batch.documents.len());
+        Ok(batch)
+    }
+}
+
+#[async_trait]
+impl SourceConnector for SitemapConnector {
+    fn source_type(&self) -> &str {
+        "sitemap"
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, request: &FetchRequest<'_>) -> Result<FetchBatch, PipelineError> {
+        let config: SitemapConfig = serde_json::from_value(request.config.clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("fetch: sitemap: {}", e)))?;
+        if config.max_pages == 0 {
+            return Err(PipelineError::InvalidConfig("fetch: sitemap: maxPages must be greater than 0".to_string()));
+        }
+
+        // Every listed page, following indexes breadth first
+        let mut pages: Vec<SitemapPage> = Vec::new();
+        let mut sitemaps = vec![config.url.clone()];
+        for depth in 0..=MAX_SITEMAP_DEPTH {
+            let mut nested = Vec::new();
+            for sitemap in &sitemaps {
+                let xml = fetch_xml(client, sitemap).await?;
+                let (urls, children) = parse_sitemap(&xml)
+                    .map_err(|e| PipelineError::StepFailed(format!("{} is not a sitemap: {}", sitemap, e)))?;
+                pages.extend(urls);
+                nested.extend(children);
+            }
+            if nested.is_empty() {
+                break;
+            }
+            if depth == MAX_SITEMAP_DEPTH {
+                debug!("Not following {} sitemaps nested deeper than {}", nested.len(), MAX_SITEMAP_DEPTH);
+            }
+            sitemaps = nested;
+        }
+
+        let wanted = |url: &str| {
+            (config.include.is_empty() || config.include.iter().any(|p| url.starts_with(p.as_str())))
+                && !config.exclude.iter().any(|p| url.starts_with(p.as_str()))
+        };
+        let mut batch = FetchBatch::default();
+        let mut downloads = 0;
+        let mut listed = HashSet::new();
+        for (url, lastmod) in &pages {
+            if !wanted(url) || !listed.insert(url.as_str()) {
+                continue;
+            }
+            let seen = request.seen.get(url);
+            if lastmod.is_some() && seen == lastmod.as_ref() {
+                continue;
+            }
+            if downloads == config.max_pages {
+                debug!("Sitemap page limit reached; remaining pages wait for the next run");
+                break;
+            }
+            downloads += 1;
+            let Some((title, text)) = fetch_page(client, url).await? else { continue };
+            let version = lastmod.clone().unwrap_or_else(|| DocumentFingerprint::hash_content(&text));
+            if seen == Some(&version) {
+                continue;
+            }
+            let metadata = json!({
+                "sitemap": {
+                    "sitemap_url": config.url,
+                    "lastmod": lastmod,
+                }
+            });
+            let id = format!("doc_sitemap_{}", &DocumentFingerprint::hash_content(url)[..16]);
+            batch.documents.push(fetched_document(id, title.unwrap_or_else(|| url.clone()), url.clone(), text, metadata));
+            batch.seen.push((url.clone(), version));
+        }
+        debug!("Sitemap {} lists {} pages; downloaded {}, {} new or changed", config.url, pages.len(), downloads, batch.documents.len());
+        Ok(batch)
+    }
+}
+
+/// Download an XML document, inflating gzipped sitemaps
+async fn fetch_xml(client: &reqwest::Client, url: &str) -> Result<String, PipelineError> {
+    let bytes = send(client.get(url)).await?
+        .bytes().await
+        .map_err(|e| PipelineError::StepFailed(format!("HTTP error: {}", e)))?;
+    if bytes.len() > MAX_DOCUMENT_BYTES {
+        return Err(PipelineError::StepFailed(format!("{} is larger than {} bytes", url, MAX_DOCUMENT_BYTES)));
+    }
+    let mut xml = String::new();
+    if bytes.starts_with(&[0x1f, 0x8b]) {
+        flate2::read::GzDecoder::new(&bytes[..]).take(MAX_DOCUMENT_BYTES as u64).read_to_string(&mut xml)
+            .map_err(|e| PipelineError::StepFailed(format!("{}: {}", url, e)))?;
+    } else {
+        xml = String::from_utf8_lossy(&bytes).into_owned();
+    }
+    Ok(xml)
+}
+
+/// Title and text of an HTML page; `None` when it is too large or not text
+async fn fetch_page(client: &reqwest::Client, url: &str) -> Result<Option<(Option<String>, String)>, PipelineError> {
+    static TITLE: OnceLock<Regex> = OnceLock::new();
+    let title = TITLE.get_or_init(|| Regex::new(r"(?is)<title[^>]*>(.*?)</title>").expect("valid pattern"));
+
+    let Some(bytes) = download(client.get(url), MAX_DOCUMENT_BYTES).await? else { return Ok(None) };
+    let Ok(html) = String::from_utf8(bytes) else { return Ok(None) };
+    let page_title = title.captures(&html)
+        .map(|caps| html_to_text(&caps[1]).trim().to_string())
+        .filter(|t| !t.is_empty());
+    let text = collapse_blank_lines(&html_to_text(&html));
+    Ok((!text.is_empty()).then_some((page_title, text)))
+}
+
+/// First child element named `name`, ignoring namespaces
+fn child<'a, 'i>(node: roxmltree::Node<'a, 'i>, name: &str) -> Option<roxmltree::Node<'a, 'i>> {
+    node.children().find(|c| c.is_element() && c.tag_name().name() == name)
+}
+
+/// Text of the first child element named `name`
+fn child_text(node: roxmltree::Node, name: &str) -> Option<String> {
+    child(node, name)
+        .map(|c| c.descendants().filter(|d| d.is_text()).filter_map(|d| d.text()).collect::<String>().trim().to_string())
+        .filter(|t| !t.is_empty())
+}
+
+/// RFC 3339 (Atom, Dublin Core), RFC 2822 (RSS) or a bare date (sitemaps)
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_rfc2822(value))
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok().and_then(|d| d.and_hms_opt(0, 0, 0)).map(|t| t.and_utc()))
+}
+
+/// Feed title and entries of an RSS 2.0, RSS 1.0 or Atom document
+fn parse_feed(xml: &str) -> Result<(Option<String>, Vec<FeedEntry>), String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    let (channel, items, atom) = match root.tag_name().name() {
+        "rss" => {
+            let channel = child(root, "channel").ok_or("RSS feed without a channel")?;
+            (channel, channel, false)
+        }
+        // RSS 1.0 lists items next to the channel
+        "RDF" => (child(root, "channel").unwrap_or(root), root, false),
+        "feed" => (root, root, true),
+        other => return Err(format!("unexpected root element <{}>", other)),
+    };
+
+    let mut entries = Vec::new();
+    for item in items.children().filter(|c| c.is_element() && c.tag_name().name() == if atom { "entry" } else { "item" }) {
+        let entry = if atom {
+            let link = item.children()
+                .filter(|c| c.is_element() && c.tag_name().name() == "link")
+                .find(|l| l.attribute("rel").is_none_or(|rel| rel == "alternate"))
+                .and_then(|l| l.attribute("href"))
+                .map(str::to_string);
+            FeedEntry {
+                key: child_text(item, "id").or_else(|| link.clone()).unwrap_or_default(),
+                title: child_text(item, "title").unwrap_or_default(),
+                updated: child_text(item, "updated").or_else(|| child_text(item, "published")).and_then(|d| parse_date(&d)),
+                html: child_text(item, "content").or_else(|| child_text(item, "summary")).unwrap_or_default(),
+                author: child(item, "author").and_then(|a| child_text(a, "name")),
+                categories: item.children()
+                    .filter(|c| c.is_element() && c.tag_name().name() == "category")
+                    .filter_map(|c| c.attribute("term").map(str::to_string))
+                    .collect(),
+                link,
+            }
+        } else {
+            let link = child_text(item, "link").or_else(|| item.attribute(("http://www.w3.org/1999/02/22-rdf-syntax-ns#", "about")).map(str::to_string));
+            FeedEntry {
+                key: child_text(item, "guid").or_else(|| link.clone()).unwrap_or_default(),
+                title: child_text(item, "title").unwrap_or_default(),
+                // pubDate (RSS 2.0) or dc:date (RSS 1.0)
+                updated: child_text(item, "pubDate").or_else(|| child_text(item, "date")).and_then(|d| parse_date(&d)),
+                // content:encoded holds the full post, description often only a summary
+                html: child_text(item, "encoded").or_else(|| child_text(item, "description")).unwrap_or_default(),
+                author: child_text(item, "creator").or_else(|| child_text(item, "author")),
+                categories: item.children()
+                    .filter(|c| c.is_element() && c.tag_name().name() == "category")
+                    .filter_map(|c| c.text().map(|t| t.trim().to_string()))
+                    .collect(),
+                link,
+            }
+        };
+        // Entries without any identity can't be tracked; key them by title
+        let key = if entry.key.is_empty() { format!("title:{}", entry.title) } else { entry.key.clone() };
+        entries.push(FeedEntry { key, ..entry });
+    }
+    Ok((child_text(channel, "title"), entries))
+}
+
+/// Pages (URL and lastmod) and nested sitemaps of a sitemap document
+fn parse_sitemap(xml: &str) -> Result<(Vec<SitemapPage>, Vec<String>), String> {
+    let document = roxmltree::Document::parse(xml).map_err(|e| e.to_string())?;
+    let root = document.root_element();
+    let entries = |name: &'static str| root.children().filter(move |c| c.is_element() && c.tag_name().name() == name);
+    match root.tag_name().name() {
+        "urlset" => Ok((
+            entries("url")
+                .filter_map(|url| Some((child_text(url, "loc")?, child_text(url, "lastmod"))))
+                // Normalize so "2024-06-03" and "2024-06-03T00:00:00+00:00" compare equal
+                .map(|(loc, lastmod)| (loc, lastmod.map(|l| parse_date(&l).map(|t| t.to_rfc3339()).unwrap_or(l))))
+                .collect(),
+            Vec::new(),
+        )),
+        "sitemapindex" => Ok((Vec::new(), entries("sitemap").filter_map(|s| child_text(s, "loc")).collect())),
+        other => Err(format!("unexpected root element <{}>", other)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use super::super::test_server::TestServer;
+
+    #[test]
+    fn test_parse_feed_dialects() {
+        let rss = r#"<?xml version="1.0"?>
+            <rss version="2.0" xmlns:content="http://purl.org/rss/1.0/modules/content/">
+              <channel><title>Release notes</title>
+                <item><title>v1.2</title><link>https://example.com/v1.2</link><guid>rel-12</guid>
+                  <pubDate>Mon, 03 Jun 2024 10:00:00 +0200</pubDate><category>release</category>
+                  <description>Short</description><content:encoded><![CDATA[<p>Full <b>notes</b></p>]]></content:encoded></item>
+                <item><title>Untracked</title><description>No guid or link</description></item>
+              </channel>
+            </rss>"#;
+        let (title, entries) = parse_feed(rss).unwrap();
+        assert_eq!(title.as_deref(), Some("Release notes"));
+        assert_eq!(entries[0].key, "rel-12");
+        assert_eq!(entries[0].html, "<p>Full <b>notes</b></p>");
+        assert_eq!(entries[0].updated.unwrap().to_rfc3339(), "2024-06-03T08:00:00+00:00");
+        assert_eq!(entries[0].categories, vec!["release"]);
+        assert_eq!(entries[1].key, "title:Untracked");
+
+        let atom = r#"<feed xmlns="http://www.w3.org/2005/Atom"><title>Blog</title>
+            <entry><id>urn:post:1</id><title>Hello</title><updated>2024-06-01T00:00:00Z</updated>
+              <link rel="self" href="https://example.com/api/1"/><link href="https://example.com/hello"/>
+              <author><name>Ana</name></author><summary>Hi there</summary></entry></feed>"#;
+        let (_, entries) = parse_feed(atom).unwrap();
+        assert_eq!(entries[0].link.as_deref(), Some("https://example.com/hello"));
+        assert_eq!(entries[0].author.as_deref(), Some("Ana"));
+
+        let rdf = r#"<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns="http://purl.org/rss/1.0/"
+                xmlns:dc="http://purl.org/dc/elements/1.1/">
+              <channel rdf:about="https://example.com/"><title>Old style</title></channel>
+              <item rdf:about="https://example.com/a"><title>A</title><dc:date>2024-06-02</dc:date></item>
+            </rdf:RDF>"#;
+        let (title, entries) = parse_feed(rdf).unwrap();
+        assert_eq!(title.as_deref(), Some("Old style"));
+        assert_eq!(entries[0].key, "https://example.com/a");
+        assert!(entries[0].updated.is_some());
+
+        assert!(parse_feed("<html></html>").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_feed_skips_seen_entries() {
+        let feed = r#"<rss version="2.0"><channel><title>Docs</title>
+            <item><title>Install</title><link>{url}/install</link><guid>install</guid>
+              <pubDate>Mon, 03 Jun 2024 10:00:00 GMT</pubDate><description>Summary</description></item>
+            <item><title>Upgrade</title><link>{url}/upgrade</link><guid>upgrade</guid>
+              <pubDate>Tue, 04 Jun 2024 10:00:00 GMT</pubDate><description>Summary</description></item>
+            </channel></rss>"#;
+        let server = TestServer::start(vec![
+            ("/feed.xml", feed.to_string()),
+            ("/install", "<html><head><title>Installing</title></head><body><p>Run the installer.</p></body></html>".to_string()),
+            ("/upgrade", "<html><body><p>Back up first.</p></body></html>".to_string()),
+        ]).await;
+
+        let config = json!({"source": "feed", "url": format!("{}/feed.xml", server.url), "fetchPages": true});
+        let seen = HashMap::from([("install".to_string(), "2024-06-03T10:00:00+00:00".to_string())]);
+        let request = FetchRequest { config: &config, token: None, since: None, seen: &seen, max_attachment_bytes: None };
+        let batch = FeedConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+
+        assert_eq!(batch.documents.len(), 1);
+        let page = &batch.documents[0];
+        assert_eq!(page.title, "Upgrade");
+        assert_eq!(page.content, "Back up first.");
+        assert_eq!(page.metadata["feed"]["feed_title"], "Docs");
+        assert_eq!(batch.seen, vec![("upgrade".to_string(), "2024-06-04T10:00:00+00:00".to_string())]);
+        assert!(batch.cursor.is_none());
+        // The seen entry's page was never requested
+        assert!(!server.requests().iter().any(|r| r.starts_with("GET /install")));
+    }
+
+    #[tokio::test]
+    async fn test_sitemap_index_and_lastmod() {
+        let index = r#"<sitemapindex xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <sitemap><loc>{url}/sitemap-docs.xml</loc></sitemap></sitemapindex>"#;
+        let docs = r#"<urlset xmlns="http://www.sitemaps.org/schemas/sitemap/0.9">
+            <url><loc>{url}/docs/a</loc><lastmod>2024-06-01</lastmod></url>
+            <url><loc>{url}/docs/b</loc><lastmod>2024-06-05</lastmod></url>
+            <url><loc>{url}/docs/c</loc></url>
+            <url><loc>{url}/blog/x</loc></url>
+            </urlset>"#;
+        let server = TestServer::start(vec![
+            ("/sitemap.xml", index.to_string()),
+            ("/sitemap-docs.xml", docs.to_string()),
+            ("/docs/a", "<p>A</p>".to_string()),
+            ("/docs/b", "<title>B page</title><p>B</p>".to_string()),
+            ("/docs/c", "<p>C</p>".to_string()),
+        ]).await;
+
+        let config = json!({"source": "sitemap", "url": format!("{}/sitemap.xml", server.url), "include": [format!("{}/docs/", server.url)]});
+        let seen = HashMap::from([
+            (format!("{}/docs/a", server.url), "2024-06-01T00:00:00+00:00".to_string()),
+            (format!("{}/docs/b", server.url), "2024-06-01T00:00:00+00:00".to_string()),
+            (format!("{}/docs/c", server.url), DocumentFingerprint::hash_content("C")),
+        ]);
+        let request = FetchRequest { config: &config, token: None, since: None, seen: &seen, max_attachment_bytes: None };
+        let batch = SitemapConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+
+        // a is unchanged by lastmod, c by content; b was modified
+        assert_eq!(batch.documents.len(), 1);
+        assert_eq!(batch.documents[0].title, "B page");
+        assert_eq!(batch.documents[0].source_path, format!("{}/docs/b", server.url));
+        assert_eq!(batch.seen[0].1, "2024-06-05T00:00:00+00:00");
+        let requests = server.requests();
+        assert!(!requests.iter().any(|r| r.starts_with("GET /docs/a") || r.starts_with("GET /blog/")));
+
+        // A full sync downloads everything, up to the page limit
+        let limited = json!({"source": "sitemap", "url": format!("{}/sitemap.xml", server.url), "maxPages": 2});
+        let request = FetchRequest { config: &limited, token: None, since: None, seen: &HashMap::new(), max_attachment_bytes: None };
+        let batch = SitemapConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+        assert_eq!(batch.documents.len(), 2);
+    }
+}
@@ -0,0 +1,234 @@
+/*!
+ * GitHub Issues Connector
+ *
+ * Issues and pull requests of GitHub (or GitHub Enterprise) repositories
+ * with their conversation comments, through the REST issues API. A token is
+ * optional for public repositories but lifts the anonymous rate limit.
+ * Incremental syncs pass the cursor as `since` and page in update order.
+ */
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::super::super::errors::PipelineError;
+use super::{fetched_document, parse_timestamp, send_json, thread_text, Comment, FetchBatch, FetchRequest, SourceConnector};
+
+/// Default GitHub API endpoint; Enterprise servers use https://<host>/api/v3
+pub const DEFAULT_GITHUB_URL: &str = "https://api.github.com";
+
+/// REST API version sent with every request
+const GITHUB_API_VERSION: &str = "2022-11-28";
+
+/// Results per page (the API maximum)
+const PAGE_SIZE: usize = 100;
+
+/// GitHub source configuration (camelCase keys in the fetch step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GitHubConfig {
+    #[serde(default = "default_github_url")]
+    pub url: String,
+    pub repos: Vec<String>,         // "owner/name"
+    #[serde(default = "default_true")]
+    pub pull_requests: bool,        // Include pull requests next to issues
+    #[serde(default)]
+    pub labels: Vec<String>,        // Only items with all of these labels
+}
+
+fn default_github_url() -> String {
+    DEFAULT_GITHUB_URL.to_string()
+}
+
+fn default_true() -> bool { true }
+
+/// GitHub issues and pull requests
+pub struct GitHubConnector;
+
+struct GitHubClient<'a> {
+    client: &'a reqwest::Client,
+    token: Option<&'a str>,
+}
+
+impl GitHubClient<'_> {
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.client.get(url)
+            .header(reqwest::header::ACCEPT, "application/vnd.github+json")
+            .header("X-GitHub-Api-Version", GITHUB_API_VERSION);
+        match self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Every result of a paginated list endpoint
+    async fn paginate(&self, url: &str, query: &[(&str, String)]) -> Result<Vec<Value>, PipelineError> {
+        let mut results = Vec::new();
+        for page in 1.. {
+            let request = self.get(url).query(query).query(&[("per_page", PAGE_SIZE), ("page", page)]);
+            let items = send_json(request).await?.as_array().cloned().unwrap_or_default();
+            let done = items.len() < PAGE_SIZE;
+            results.extend(items);
+            if done {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl SourceConnector for GitHubConnector {
+    fn source_type(&self) -> &str {
+        "github"
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, request: &FetchRequest<'_>) -> Result<FetchBatch, PipelineError> {
+        let config: GitHubConfig = serde_json::from_value(request.config.clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("fetch: github: {}", e)))?;
+        if config.repos.is_empty() {
+            return Err(PipelineError::InvalidConfig("fetch: github: repos must list at least one repository".to_string()));
+        }
+        if let Some(repo) = config.repos.iter().find(|r| !is_repo_name(r)) {
+            return Err(PipelineError::InvalidConfig(format!("fetch: github: invalid repository '{}' (expected owner/name)", repo)));
+        }
+        let base = config.url.trim_end_matches('/');
+        let github = GitHubClient { client, token: request.token };
+
+        let mut batch = FetchBatch::default();
+        for repo in &config.repos {
+            let mut query = vec![
+                ("state", "all".to_string()),
+                ("sort", "updated".to_string()),
+                ("direction", "asc".to_string()),
+            ];
+            if let Some(since) = request.since {
+                query.push(("since", since.to_rfc3339_opts(chrono::SecondsFormat::Secs, true)));
+            }
+            if !config.labels.is_empty() {
+                query.push(("labels", config.labels.join(",")));
+            }
+            let items = github.paginate(&format!("{}/repos/{}/issues", base, repo), &query).await?;
+
+            let mut fetched = 0;
+            for item in &items {
+                let (Some(id), Some(number)) = (item["id"].as_u64(), item["number"].as_u64()) else { continue };
+                let pull_request = item.get("pull_request").is_some_and(|pr| !pr.is_null());
+                if pull_request && !config.pull_requests {
+                    continue;
+                }
+                // `since` includes items updated at the cursor itself
+                let updated = parse_timestamp(item.get("updated_at"));
+                if request.since.zip(updated).is_some_and(|(since, updated)| updated <= since) {
+                    continue;
+                }
+                batch.cursor = batch.cursor.max(updated);
+
+                let comments = match item["comments"].as_u64().unwrap_or(0) {
+                    0 => Vec::new(),
+                    _ => github.paginate(&format!("{}/repos/{}/issues/{}/comments", base, repo, number), &[]).await?,
+                };
+                let comments: Vec<Comment> = comments.iter()
+                    .map(|comment| Comment {
+                        author: comment.pointer("/user/login").and_then(Value::as_str).unwrap_or("ghost").to_string(),
+                        created: parse_timestamp(comment.get("created_at")),
+                        body: comment["body"].as_str().unwrap_or("").to_string(),
+                    })
+                    .collect();
+                let title = format!("{}#{}: {}", repo, number, item["title"].as_str().unwrap_or(""));
+                let content = thread_text(&title, item["body"].as_str().unwrap_or(""), &comments);
+                let source_path = item["html_url"].as_str().map(str::to_string)
+                    .unwrap_or_else(|| format!("{}/repos/{}/issues/{}", base, repo, number));
+                let labels: Vec<&Value> = item["labels"].as_array().into_iter().flatten().filter_map(|l| l.get("name")).collect();
+                let assignees: Vec<&Value> = item["assignees"].as_array().into_iter().flatten().filter_map(|a| a.get("login")).collect();
+                let metadata = json!({
+                    "github": {
+                        "repo": repo,
+                        "number": number,
+                        "kind": if pull_request { "pull_request" } else { "issue" },
+                        "state": item["state"],
+                        "state_reason": item["state_reason"],
+                        "labels": labels,
+                        "author": item.pointer("/user/login"),
+                        "assignees": assignees,
+                        "milestone": item.pointer("/milestone/title"),
+                        "comments": comments.len(),
+                        "created_at": parse_timestamp(item.get("created_at")),
+                        "updated_at": updated,
+                        "closed_at": parse_timestamp(item.get("closed_at")),
+                        "merged_at": parse_timestamp(item.pointer("/pull_request/merged_at")),
+                    }
+                });
+                batch.documents.push(fetched_document(format!("doc_github_{}", id), title, source_path, content, metadata));
+                fetched += 1;
+            }
+            debug!("GitHub fetch read {} of {} items of {}", fetched, items.len(), repo);
+        }
+        Ok(batch)
+    }
+}
+
+/// Whether `repo` looks like "owner/name"
+fn is_repo_name(repo: &str) -> bool {
+    let mut parts = repo.split('/');
+    let valid = |part: Option<&str>| part.is_some_and(|p| !p.is_empty() && p.chars().all(|c| c.is_alphanumeric() || "-_.".contains(c)));
+    valid(parts.next()) && valid(parts.next()) && parts.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+    use super::super::test_server::TestServer;
+
+    #[tokio::test]
+    async fn test_fetch_issues_and_pull_requests() {
+        let issues = r#"[
+            {"id": 501, "number": 12, "title": "Crash on empty KB", "body": "Searching an empty KB panics.",
+             "state": "closed", "state_reason": "completed", "labels": [{"name": "bug"}], "user": {"login": "ana"},
+             "assignees": [{"login": "bo"}], "milestone": {"title": "v1.2"}, "comments": 1,
+             "created_at": "2024-06-01T09:00:00Z", "updated_at": "2024-06-03T10:00:00Z", "closed_at": "2024-06-03T10:00:00Z",
+             "html_url": "https://github.com/acme/rag/issues/12"},
+            {"id": 502, "number": 13, "title": "Guard empty KB search", "body": null, "state": "open",
+             "labels": [], "user": {"login": "bo"}, "assignees": [], "comments": 0,
+             "created_at": "2024-06-03T09:00:00Z", "updated_at": "2024-06-03T11:00:00Z",
+             "pull_request": {"merged_at": null}, "html_url": "https://github.com/acme/rag/pull/13"},
+            {"id": 503, "number": 3, "title": "Old", "comments": 0, "updated_at": "2024-06-01T00:00:00Z"}
+        ]"#;
+        let comments = r#"[{"user": {"login": "bo"}, "created_at": "2024-06-02T08:00:00Z", "body": "Fixed in #13."}]"#;
+        let server = TestServer::start(vec![
+            ("/repos/acme/rag/issues/12/comments", comments.to_string()),
+            ("/repos/acme/rag/issues?", issues.to_string()),
+        ]).await;
+
+        let since = DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().with_timezone(&Utc);
+        let config = json!({"source": "github", "url": server.url, "repos": ["acme/rag"]});
+        let request = FetchRequest { config: &config, token: Some("ghp_x"), since: Some(since), seen: &HashMap::new(), max_attachment_bytes: None };
+        let batch = GitHubConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+
+        // #3 was updated at the cursor, so the last sync already had it
+        let ids: Vec<&str> = batch.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(ids, vec!["doc_github_501", "doc_github_502"]);
+        assert_eq!(batch.cursor.unwrap().to_rfc3339(), "2024-06-03T11:00:00+00:00");
+        let issue = &batch.documents[0];
+        assert_eq!(issue.content, "acme/rag#12: Crash on empty KB\n\nSearching an empty KB panics.\n\nbo commented on 2024-06-02:\nFixed in #13.");
+        assert_eq!(issue.source_path, "https://github.com/acme/rag/issues/12");
+        assert_eq!(issue.metadata["github"]["labels"], json!(["bug"]));
+        assert_eq!(issue.metadata["github"]["state"], "closed");
+        assert_eq!(batch.documents[1].metadata["github"]["kind"], "pull_request");
+
+        let requests = server.requests();
+        assert!(requests[0].contains("since=2024-06-01T00%3A00%3A00Z"));
+        assert!(requests[0].to_lowercase().contains("authorization: bearer ghp_x"));
+
+        let issues_only = json!({"source": "github", "url": server.url, "repos": ["acme/rag"], "pullRequests": false});
+        let request = FetchRequest { config: &issues_only, token: None, since: Some(since), seen: &HashMap::new(), max_attachment_bytes: None };
+        assert_eq!(GitHubConnector.fetch(&reqwest::Client::new(), &request).await.unwrap().documents.len(), 1);
+
+        let invalid = json!({"source": "github", "repos": ["acme"]});
+        let request = FetchRequest { config: &invalid, token: None, since: None, seen: &HashMap::new(), max_attachment_bytes: None };
+        assert!(matches!(GitHubConnector.fetch(&reqwest::Client::new(), &request).await, Err(PipelineError::InvalidConfig(_))));
+    }
+}
@@ -0,0 +1,263 @@
+/*!
+ * Jira Connector
+ *
+ * Issues of Jira Cloud or Server/Data Center with their comments, through
+ * the REST v2 search API, optionally limited to projects and narrowed by
+ * extra JQL. Authentication follows Confluence: account email and API token
+ * for Cloud, a personal access token for Server/DC. Cloud sites page with
+ * `nextPageToken` (`search/jql`), Server/DC with `startAt`. Incremental syncs
+ * query `updated` a day before the cursor (JQL compares in the account's time
+ * zone) and keep issues updated after it.
+ */
+
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::super::super::errors::PipelineError;
+use super::{authorize, fetched_document, parse_timestamp, send_json, thread_text, Comment, FetchBatch, FetchRequest, SourceConnector};
+
+/// Issue fields requested from the search API
+const SEARCH_FIELDS: &str = "summary,description,status,labels,components,issuetype,priority,assignee,reporter,\
+                             project,resolution,created,updated,resolutiondate,comment";
+
+/// Jira source configuration (camelCase keys in the fetch step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct JiraConfig {
+    pub url: String,                // e.g. https://acme.atlassian.net or https://jira.acme.com
+    #[serde(default)]
+    pub projects: Vec<String>,      // Project keys; all visible projects when empty
+    #[serde(default)]
+    pub jql: Option<String>,        // Extra filter, e.g. "issuetype = Bug"
+    #[serde(default)]
+    pub username: Option<String>,   // Cloud account email; omit for a Server/DC access token
+    #[serde(default = "default_page_size")]
+    pub page_size: usize,
+}
+
+fn default_page_size() -> usize { 50 }
+
+/// Jira Cloud and Server/DC issues
+pub struct JiraConnector;
+
+impl JiraConnector {
+    /// Every comment of an issue; the search response embeds only the first ones
+    async fn fetch_comments(
+        &self,
+        client: &reqwest::Client,
+        request: &FetchRequest<'_>,
+        config: &JiraConfig,
+        base: &str,
+        issue: &Value,
+    ) -> Result<Vec<Value>, PipelineError> {
+        let mut comments = issue.pointer("/fields/comment/comments").and_then(Value::as_array).cloned().unwrap_or_default();
+        let total = issue.pointer("/fields/comment/total").and_then(Value::as_u64).unwrap_or(0) as usize;
+        let key = issue["key"].as_str().unwrap_or("");
+        while comments.len() < total {
+            let url = format!("{}/rest/api/2/issue/{}/comment", base, key);
+            let page = client.get(url).query(&[("startAt", comments.len().to_string()), ("maxResults", "100".to_string())]);
+            let response = send_json(authorize(page, config.username.as_deref(), request.token)).await?;
+            let more = response["comments"].as_array().cloned().unwrap_or_default();
+            if more.is_empty() {
+                break;
+            }
+            comments.extend(more);
+        }
+        Ok(comments)
+    }
+}
+
+#[async_trait]
+impl SourceConnector for JiraConnector {
+    fn source_type(&self) -> &str {
+        "jira"
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, request: &FetchRequest<'_>) -> Result<FetchBatch, PipelineError> {
+        let config: JiraConfig = serde_json::from_value(request.config.clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("fetch: jira: {}", e)))?;
+        if config.page_size == 0 {
+            return Err(PipelineError::InvalidConfig("fetch: jira: pageSize must be greater than 0".to_string()));
+        }
+        if let Some(project) = config.projects.iter().find(|p| p.is_empty() || !p.chars().all(|c| c.is_alphanumeric() || c == '_')) {
+            return Err(PipelineError::InvalidConfig(format!("fetch: jira: invalid project key '{}'", project)));
+        }
+        let base = config.url.trim_end_matches('/');
+        let jql = search_jql(&config.projects, config.jql.as_deref(), request.since);
+        let cloud = base.contains(".atlassian.net");
+
+        let mut batch = FetchBatch::default();
+        let mut start = 0;
+        let mut next_page: Option<String> = None;
+        loop {
+            let mut query = vec![
+                ("jql", jql.clone()),
+                ("fields", SEARCH_FIELDS.to_string()),
+                ("maxResults", config.page_size.to_string()),
+            ];
+            let path = if cloud {
+                query.extend(next_page.clone().map(|token| ("nextPageToken", token)));
+                "search/jql"
+            } else {
+                query.push(("startAt", start.to_string()));
+                "search"
+            };
+            let search = client.get(format!("{}/rest/api/2/{}", base, path)).query(&query);
+            let response = send_json(authorize(search, config.username.as_deref(), request.token)).await?;
+            let issues = response["issues"].as_array().cloned().unwrap_or_default();
+
+            for issue in &issues {
+                let (Some(id), Some(key)) = (issue["id"].as_str(), issue["key"].as_str()) else { continue };
+                let fields = &issue["fields"];
+                let updated = parse_timestamp(fields.get("updated"));
+                if request.since.zip(updated).is_some_and(|(since, updated)| updated <= since) {
+                    continue;
+                }
+                batch.cursor = batch.cursor.max(updated);
+
+                let comments: Vec<Comment> = self.fetch_comments(client, request, &config, base, issue).await?
+                    .iter()
+                    .map(|comment| Comment {
+                        author: comment.pointer("/author/displayName").and_then(Value::as_str).unwrap_or("Unknown").to_string(),
+                        created: parse_timestamp(comment.get("created")),
+                        body: comment["body"].as_str().unwrap_or("").to_string(),
+                    })
+                    .collect();
+                let title = format!("{}: {}", key, fields["summary"].as_str().unwrap_or(""));
+                let content = thread_text(&title, fields["description"].as_str().unwrap_or(""), &comments);
+                let names = |list: &Value| -> Vec<Value> {
+                    list.as_array().into_iter().flatten().filter_map(|item| item.get("name").or(Some(item)).cloned()).collect()
+                };
+                let metadata = json!({
+                    "jira": {
+                        "issue_id": id,
+                        "key": key,
+                        "project": fields.pointer("/project/key"),
+                        "issue_type": fields.pointer("/issuetype/name"),
+                        "status": fields.pointer("/status/name"),
+                        "status_category": fields.pointer("/status/statusCategory/key"),
+                        "priority": fields.pointer("/priority/name"),
+                        "resolution": fields.pointer("/resolution/name"),
+                        "labels": names(&fields["labels"]),
+                        "components": names(&fields["components"]),
+                        "assignee": fields.pointer("/assignee/displayName"),
+                        "reporter": fields.pointer("/reporter/displayName"),
+                        "comments": comments.len(),
+                        "created_at": parse_timestamp(fields.get("created")),
+                        "updated_at": updated,
+                        "resolved_at": parse_timestamp(fields.get("resolutiondate")),
+                    }
+                });
+                batch.documents.push(fetched_document(format!("doc_jira_{}", id), title, format!("{}/browse/{}", base, key), content, metadata));
+            }
+
+            debug!("Jira search returned {} issues at offset {}", issues.len(), start);
+            start += issues.len();
+            next_page = response["nextPageToken"].as_str().map(str::to_string);
+            let last = if cloud {
+                next_page.is_none() || response["isLast"].as_bool().unwrap_or(false)
+            } else {
+                response["total"].as_u64().is_none_or(|total| start as u64 >= total)
+            };
+            if issues.is_empty() || last {
+                break;
+            }
+        }
+        Ok(batch)
+    }
+}
+
+/// JQL for issues of `projects` matching `filter`, updated since the day
+/// before `since`, oldest first
+fn search_jql(projects: &[String], filter: Option<&str>, since: Option<DateTime<Utc>>) -> String {
+    let mut clauses = Vec::new();
+    if !projects.is_empty() {
+        let keys: Vec<String> = projects.iter().map(|p| format!("\"{}\"", p)).collect();
+        clauses.push(format!("project in ({})", keys.join(", ")));
+    }
+    if let Some(filter) = filter.map(str::trim).filter(|f| !f.is_empty()) {
+        clauses.push(format!("({})", filter));
+    }
+    if let Some(since) = since {
+        clauses.push(format!("updated >= \"{}\"", (since - Duration::days(1)).format("%Y/%m/%d %H:%M")));
+    }
+    format!("{} ORDER BY updated ASC", clauses.join(" AND ")).trim_start().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use super::super::test_server::TestServer;
+
+    #[test]
+    fn test_search_jql() {
+        let since = DateTime::parse_from_rfc3339("2024-06-03T10:30:00Z").unwrap().with_timezone(&Utc);
+        assert_eq!(
+            search_jql(&["OPS".to_string()], Some("issuetype = Bug"), Some(since)),
+            "project in (\"OPS\") AND (issuetype = Bug) AND updated >= \"2024/06/02 10:30\" ORDER BY updated ASC"
+        );
+        assert_eq!(search_jql(&[], Some(" "), None), "ORDER BY updated ASC");
+    }
+
+    #[tokio::test]
+    async fn test_fetch_issues_with_comments() {
+        let search = r#"{"startAt": 0, "maxResults": 50, "total": 2, "issues": [
+            {"id": "10001", "key": "OPS-7", "fields": {
+                "summary": "Indexer stalls on large PDFs", "description": "Happens above 200 pages.",
+                "status": {"name": "Done", "statusCategory": {"key": "done"}}, "labels": ["indexer", "pdf"],
+                "components": [{"name": "Parsing"}], "issuetype": {"name": "Bug"}, "priority": {"name": "High"},
+                "assignee": {"displayName": "Ana"}, "reporter": {"displayName": "Bo"}, "project": {"key": "OPS"},
+                "resolution": {"name": "Fixed"}, "created": "2024-06-01T09:00:00.000+0000",
+                "updated": "2024-06-03T10:00:00.000+0000", "resolutiondate": "2024-06-03T10:00:00.000+0000",
+                "comment": {"total": 2, "maxResults": 1, "comments": [
+                    {"author": {"displayName": "Ana"}, "created": "2024-06-02T08:00:00.000+0200", "body": "Reproduced."}
+                ]}}},
+            {"id": "10002", "key": "OPS-8", "fields": {
+                "summary": "Old", "updated": "2024-05-01T09:00:00.000+0000", "comment": {"total": 0, "comments": []}}}
+        ]}"#;
+        let comments = r#"{"startAt": 1, "total": 2, "comments": [
+            {"author": {"displayName": "Bo"}, "created": "2024-06-03T09:00:00.000+0000", "body": "Fixed by streaming pages."}
+        ]}"#;
+        let server = TestServer::start(vec![
+            ("/rest/api/2/search", search.to_string()),
+            ("/rest/api/2/issue/OPS-7/comment", comments.to_string()),
+        ]).await;
+
+        let config = json!({"source": "jira", "url": server.url, "projects": ["OPS"]});
+        let request = FetchRequest {
+            config: &config,
+            token: Some("pat"),
+            since: Some(DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().with_timezone(&Utc)),
+            seen: &HashMap::new(),
+            max_attachment_bytes: None,
+        };
+        let batch = JiraConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+
+        // OPS-8 is older than the cursor
+        assert_eq!(batch.documents.len(), 1);
+        assert_eq!(batch.cursor.unwrap().to_rfc3339(), "2024-06-03T10:00:00+00:00");
+        let issue = &batch.documents[0];
+        assert_eq!(issue.id, "doc_jira_10001");
+        assert_eq!(issue.source_path, format!("{}/browse/OPS-7", server.url));
+        assert_eq!(
+            issue.content,
+            "OPS-7: Indexer stalls on large PDFs\n\nHappens above 200 pages.\n\n\
+             Ana commented on 2024-06-02:\nReproduced.\n\nBo commented on 2024-06-03:\nFixed by streaming pages."
+        );
+        let jira = &issue.metadata["jira"];
+        assert_eq!(jira["status"], "Done");
+        assert_eq!(jira["labels"], json!(["indexer", "pdf"]));
+        assert_eq!(jira["components"], json!(["Parsing"]));
+        assert_eq!(jira["comments"], 2);
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("GET /rest/api/2/search?"));
+        assert!(requests[0].contains("startAt=0"));
+        assert!(requests[1].contains("startAt=1"));
+        assert!(requests[0].to_lowercase().contains("authorization: bearer pat"));
+    }
+}
@@ -0,0 +1,345 @@
+/*!
+ * Notion Connector
+ *
+ * Pages shared with a Notion integration: every page of the listed
+ * databases, the listed pages, or (when neither is given) everything the
+ * integration can search. Page bodies are the block tree rendered as
+ * Markdown-like text; file and PDF blocks are attachments. Incremental syncs
+ * filter databases on `last_edited_time` and stop searching once results are
+ * older than the cursor.
+ */
+
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use super::super::super::errors::PipelineError;
+use super::super::super::models::PipelineDocument;
+use super::{attachment_document, attachment_text, download, fetched_document, parse_timestamp, send_json, FetchBatch, FetchRequest, SourceConnector};
+
+/// Notion API version sent with every request
+pub const NOTION_VERSION: &str = "2022-06-28";
+
+/// Default Notion API endpoint
+pub const DEFAULT_NOTION_URL: &str = "https://api.notion.com";
+
+/// Nested blocks read below a page (toggles, lists, columns, ...)
+const MAX_BLOCK_DEPTH: usize = 5;
+
+/// Notion source configuration (camelCase keys in the fetch step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NotionConfig {
+    #[serde(default = "default_notion_url")]
+    pub url: String,
+    #[serde(default)]
+    pub databases: Vec<String>,     // Database ids; all their pages
+    #[serde(default)]
+    pub pages: Vec<String>,         // Page ids
+}
+
+fn default_notion_url() -> String {
+    DEFAULT_NOTION_URL.to_string()
+}
+
+/// Notion pages and database entries
+pub struct NotionConnector;
+
+/// Files found while rendering a page
+struct BlockFile {
+    name: String,
+    url: String,
+    hosted: bool,                   // Notion-hosted (signed URL) rather than an external link
+}
+
+struct NotionClient<'a> {
+    client: &'a reqwest::Client,
+    base: &'a str,
+    token: Option<&'a str>,
+}
+
+impl NotionClient<'_> {
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}/v1/{}", self.base, path))
+            .header("Notion-Version", NOTION_VERSION);
+        match self.token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Every result of a paginated endpoint; `stop` ends early on a result
+    async fn paginate(
+        &self,
+        method: reqwest::Method,
+        path: &str,
+        body: Option<Value>,
+        stop: impl Fn(&Value) -> bool,
+    ) -> Result<Vec<Value>, PipelineError> {
+        let mut results = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let request = match &body {
+                Some(body) => {
+                    let mut body = body.clone();
+                    if let Some(cursor) = &cursor {
+                        body["start_cursor"] = json!(cursor);
+                    }
+                    self.request(method.clone(), path).json(&body)
+                }
+                None => {
+                    let mut request = self.request(method.clone(), path).query(&[("page_size", "100")]);
+                    if let Some(cursor) = &cursor {
+                        request = request.query(&[("start_cursor", cursor)]);
+                    }
+                    request
+                }
+            };
+            let response = send_json(request).await?;
+            for result in response["results"].as_array().into_iter().flatten() {
+                if stop(result) {
+                    return Ok(results);
+                }
+                results.push(result.clone());
+            }
+            cursor = response["next_cursor"].as_str().map(str::to_string);
+            if !response["has_more"].as_bool().unwrap_or(false) || cursor.is_none() {
+                return Ok(results);
+            }
+        }
+    }
+
+    /// Text of the blocks under `block_id`, appending files found to `files`
+    async fn render_blocks(&self, block_id: &str, depth: usize, files: &mut Vec<BlockFile>) -> Result<String, PipelineError> {
+        let blocks = self.paginate(reqwest::Method::GET, &format!("blocks/{}/children", block_id), None, |_| false).await?;
+        let indent = "  ".repeat(depth);
+        let mut lines = Vec::new();
+        let mut number = 0;
+        for block in &blocks {
+            let kind = block["type"].as_str().unwrap_or("");
+            let text = rich_text(&block[kind]["rich_text"]);
+            number = if kind == "numbered_list_item" { number + 1 } else { 0 };
+            let line = match kind {
+                "heading_1" => format!("# {}", text),
+                "heading_2" => format!("## {}", text),
+                "heading_3" => format!("### {}", text),
+                "bulleted_list_item" | "toggle" => format!("- {}", text),
+                "numbered_list_item" => format!("{}. {}", number, text),
+                "to_do" => format!("- [{}] {}", if block["to_do"]["checked"].as_bool().unwrap_or(false) { "x" } else { " " }, text),
+                "quote" => format!("> {}", text),
+                "code" => format!("```{}\n{}\n```", block["code"]["language"].as_str().unwrap_or(""), text),
+                "file" | "pdf" => {
+                    let hosted = block[kind]["type"].as_str() == Some("file");
+                    let url = block[kind][if hosted { "file" } else { "external" }]["url"].as_str().unwrap_or("").to_string();
+                    let name = block[kind]["name"].as_str().map(str::to_string)
+                        .or_else(|| url.split('?').next().and_then(|path| path.rsplit('/').next()).map(str::to_string))
+                        .unwrap_or_else(|| "attachment".to_string());
+                    if !url.is_empty() {
+                        files.push(BlockFile { name: name.clone(), url, hosted });
+                    }
+                    format!("[{}]", name)
+                }
+                // Child pages and databases are fetched on their own
+                "child_page" | "child_database" | "divider" | "image" | "video" | "unsupported" => String::new(),
+                _ => text,
+            };
+            if !line.is_empty() {
+                lines.push(format!("{}{}", indent, line.replace('\n', &format!("\n{}", indent))));
+            }
+            let nested = !matches!(kind, "child_page" | "child_database");
+            if nested && block["has_children"].as_bool().unwrap_or(false) && depth + 1 < MAX_BLOCK_DEPTH {
+                let id = block["id"].as_str().unwrap_or_default();
+                let children = Box::pin(self.render_blocks(id, depth + 1, files)).await?;
+                if !children.is_empty() {
+                    lines.push(children);
+                }
+            }
+        }
+        Ok(lines.join("\n"))
+    }
+
+    /// Document for a page, followed by its readable file attachments
+    async fn page_documents(&self, page: &Value, max_attachment_bytes: Option<usize>) -> Result<Vec<PipelineDocument>, PipelineError> {
+        let page_id = page["id"].as_str().unwrap_or_default();
+        let mut files = Vec::new();
+        let content = self.render_blocks(page_id, 0, &mut files).await?;
+        let title = page_title(page).unwrap_or_else(|| page_id.to_string());
+        let source_path = page["url"].as_str().map(str::to_string)
+            .unwrap_or_else(|| format!("https://www.notion.so/{}", page_id.replace('-', "")));
+        let mut document = fetched_document(format!("doc_notion_{}", page_id.replace('-', "")), title, source_path, content, Value::Null);
+
+        let mut linked = Vec::new();
+        let mut listed = Vec::new();
+        for (index, file) in files.iter().enumerate() {
+            // External links may point anywhere; only Notion-hosted files are downloaded
+            let text = match max_attachment_bytes.filter(|_| file.hosted) {
+                Some(max_bytes) => download(self.client.get(&file.url), max_bytes).await?
+                    .and_then(|bytes| attachment_text(bytes, "")),
+                None => None,
+            };
+            let id = text.as_ref().map(|_| format!("{}_file{}", document.id, index + 1));
+            if let (Some(id), Some(text)) = (&id, text) {
+                let source_path = format!("{}/{}", document.source_path, file.name);
+                linked.push(attachment_document(&document, id.clone(), &file.name, "", source_path, text));
+            }
+            listed.push(json!({ "filename": file.name, "hosted": file.hosted, "document_id": id }));
+        }
+
+        document.metadata = json!({
+            "notion": {
+                "page_id": page_id,
+                "database_id": page.pointer("/parent/database_id"),
+                "last_edited_time": page["last_edited_time"],
+                "attachments": listed,
+            }
+        });
+        Ok(std::iter::once(document).chain(linked).collect())
+    }
+}
+
+#[async_trait]
+impl SourceConnector for NotionConnector {
+    fn source_type(&self) -> &str {
+        "notion"
+    }
+
+    async fn fetch(&self, client: &reqwest::Client, request: &FetchRequest<'_>) -> Result<FetchBatch, PipelineError> {
+        let config: NotionConfig = serde_json::from_value(request.config.clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("fetch: notion: {}", e)))?;
+        if let Some(id) = config.databases.iter().chain(&config.pages).find(|id| !is_notion_id(id)) {
+            return Err(PipelineError::InvalidConfig(format!("fetch: notion: invalid id '{}'", id)));
+        }
+        let notion = NotionClient { client, base: config.url.trim_end_matches('/'), token: request.token };
+        let since = request.since;
+        let edited = |page: &Value| parse_timestamp(page.get("last_edited_time"));
+        let is_new = |page: &Value| since.is_none_or(|since| edited(page).is_none_or(|edited| edited > since));
+
+        let mut pages = Vec::new();
+        for database in &config.databases {
+            let mut body = json!({
+                "page_size": 100,
+                "sorts": [{"timestamp": "last_edited_time", "direction": "ascending"}],
+            });
+            if let Some(since) = since {
+                body["filter"] = json!({"timestamp": "last_edited_time", "last_edited_time": {"on_or_after": since.to_rfc3339()}});
+            }
+            pages.extend(notion.paginate(reqwest::Method::POST, &format!("databases/{}/query", database), Some(body), |_| false).await?);
+        }
+        for page in &config.pages {
+            pages.push(send_json(notion.request(reqwest::Method::GET, &format!("pages/{}", page))).await?);
+        }
+        if config.databases.is_empty() && config.pages.is_empty() {
+            // Newest first, so the first page older than the cursor ends the sync
+            let body = json!({
+                "page_size": 100,
+                "filter": {"property": "object", "value": "page"},
+                "sort": {"timestamp": "last_edited_time", "direction": "descending"},
+            });
+            pages = notion.paginate(reqwest::Method::POST, "search", Some(body), |page| !is_new(page)).await?;
+        }
+
+        let mut batch = FetchBatch::default();
+        for page in pages.iter().filter(|page| is_new(page) && !page["archived"].as_bool().unwrap_or(false)) {
+            batch.cursor = batch.cursor.max(edited(page));
+            let documents = notion.page_documents(page, request.max_attachment_bytes).await?;
+            batch.attachments += documents.len() - 1;
+            batch.documents.extend(documents);
+        }
+        debug!("Notion fetch read {} of {} pages", batch.documents.len() - batch.attachments, pages.len());
+        Ok(batch)
+    }
+}
+
+/// Plain text of a rich text array
+fn rich_text(value: &Value) -> String {
+    value.as_array().into_iter().flatten()
+        .filter_map(|part| part["plain_text"].as_str())
+        .collect()
+}
+
+/// Title property of a page (every page has exactly one)
+fn page_title(page: &Value) -> Option<String> {
+    page["properties"].as_object()?
+        .values()
+        .find(|property| property["type"] == "title")
+        .map(|property| rich_text(&property["title"]))
+        .filter(|title| !title.is_empty())
+}
+
+/// UUID with or without dashes
+fn is_notion_id(id: &str) -> bool {
+    let hex: String = id.chars().filter(|c| *c != '-').collect();
+    hex.len() == 32 && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{DateTime, Utc};
+    use std::collections::HashMap;
+    use super::super::test_server::TestServer;
+
+    const PAGE_ID: &str = "0f4a5b6c-7d8e-4f90-a1b2-c3d4e5f60718";
+
+    #[tokio::test]
+    async fn test_fetch_database_pages() {
+        let query = format!(r#"{{"results": [
+            {{"object": "page", "id": "{PAGE_ID}", "url": "https://www.notion.so/Onboarding-0f4a",
+             "last_edited_time": "2024-06-03T10:00:00.000Z", "archived": false,
+             "parent": {{"type": "database_id", "database_id": "11111111-2222-3333-4444-555555555555"}},
+             "properties": {{"Status": {{"type": "select"}}, "Name": {{"type": "title", "title": [{{"plain_text": "Onboarding"}}]}}}}}}
+        ], "has_more": false, "next_cursor": null}}"#);
+        let blocks = r#"{"results": [
+            {"id": "b1", "type": "heading_2", "has_children": false, "heading_2": {"rich_text": [{"plain_text": "First day"}]}},
+            {"id": "b2", "type": "numbered_list_item", "has_children": false, "numbered_list_item": {"rich_text": [{"plain_text": "Get a "}, {"plain_text": "laptop"}]}},
+            {"id": "b3", "type": "numbered_list_item", "has_children": false, "numbered_list_item": {"rich_text": [{"plain_text": "Join chat"}]}},
+            {"id": "b4", "type": "toggle", "has_children": true, "toggle": {"rich_text": [{"plain_text": "Accounts"}]}},
+            {"id": "b5", "type": "file", "has_children": false, "file": {"type": "file", "name": "policy.txt", "file": {"url": "{url}/files/policy.txt?X-Amz-Signature=abc"}}},
+            {"id": "b6", "type": "child_page", "has_children": true, "child_page": {"title": "Elsewhere"}}
+        ], "has_more": false, "next_cursor": null}"#;
+        let toggle = r#"{"results": [
+            {"id": "c1", "type": "to_do", "has_children": false, "to_do": {"checked": true, "rich_text": [{"plain_text": "SSO"}]}}
+        ], "has_more": false, "next_cursor": null}"#;
+        let server = TestServer::start(vec![
+            ("/v1/databases/", query),
+            ("/v1/blocks/b4/children", toggle.to_string()),
+            (PAGE_ID, blocks.to_string()),
+            ("/files/policy.txt", "Laptops stay encrypted.".to_string()),
+        ]).await;
+
+        let config = json!({"source": "notion", "url": server.url, "databases": ["11111111222233334444555555555555"]});
+        let request = FetchRequest {
+            config: &config,
+            token: Some("secret_abc"),
+            since: Some(DateTime::parse_from_rfc3339("2024-06-01T00:00:00Z").unwrap().with_timezone(&Utc)),
+            seen: &HashMap::new(),
+            max_attachment_bytes: Some(1024),
+        };
+        let batch = NotionConnector.fetch(&reqwest::Client::new(), &request).await.unwrap();
+
+        assert_eq!(batch.documents.len(), 2);
+        assert_eq!(batch.attachments, 1);
+        let page = &batch.documents[0];
+        assert_eq!(page.id, "doc_notion_0f4a5b6c7d8e4f90a1b2c3d4e5f60718");
+        assert_eq!(page.title, "Onboarding");
+        assert_eq!(page.content, "## First day\n1. Get a laptop\n2. Join chat\n- Accounts\n  - [x] SSO\n[policy.txt]");
+        assert_eq!(page.metadata["notion"]["database_id"], "11111111-2222-3333-4444-555555555555");
+        assert_eq!(page.metadata["notion"]["attachments"][0]["document_id"], "doc_notion_0f4a5b6c7d8e4f90a1b2c3d4e5f60718_file1");
+        assert_eq!(batch.documents[1].content, "Laptops stay encrypted.");
+        assert_eq!(batch.cursor.unwrap().to_rfc3339(), "2024-06-03T10:00:00+00:00");
+
+        let requests = server.requests();
+        assert!(requests[0].starts_with("POST /v1/databases/11111111222233334444555555555555/query "));
+        assert!(requests[0].contains("\"on_or_after\":\"2024-06-01T00:00:00+00:00\""));
+        assert!(requests[0].to_lowercase().contains("notion-version: 2022-06-28"));
+        // The signed file URL carries its own credentials
+        let download = requests.iter().find(|r| r.contains("/files/policy.txt")).unwrap();
+        assert!(!download.to_lowercase().contains("authorization"));
+
+        let config = json!({"source": "notion", "pages": ["not-an-id"]});
+        let request = FetchRequest { config: &config, token: None, since: None, seen: &HashMap::new(), max_attachment_bytes: None };
+        assert!(matches!(NotionConnector.fetch(&reqwest::Client::new(), &request).await, Err(PipelineError::InvalidConfig(_))));
+    }
+}
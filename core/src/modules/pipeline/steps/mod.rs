@@ -0,0 +1,34 @@
+/*!
+ * Pipeline Step Executors
+ *
+ * Concrete `StepExecutor` implementations for ETL steps.
+ */
+
+pub mod annotate;
+pub mod email;
+pub mod embed;
+pub mod eval;
+pub mod extract_entities;
+pub mod fetch;
+pub mod normalize;
+pub mod notebook;
+pub mod ocr;
+pub mod parse;
+pub mod redact;
+pub mod rst;
+pub mod summarize;
+pub mod table;
+pub mod transcribe;
+
+pub use annotate::{AnnotateStepExecutor, AnnotatorSet};
+pub use embed::EmbedStepExecutor;
+pub use eval::EvalStepExecutor;
+pub use extract_entities::ExtractEntitiesStepExecutor;
+pub use fetch::{FetchBatch, FetchCursorStore, FetchRequest, FetchStepExecutor, SourceConnector};
+pub use normalize::NormalizeStepExecutor;
+pub use ocr::{OcrEngine, OcrPage, OcrSource, TesseractConfig, TesseractOcrEngine};
+pub use parse::{OcrMode, ParseStepExecutor};
+pub use redact::{RedactStepExecutor, EntityRecognizer, DetectedEntity, redact_pii};
+pub use summarize::{SummarizeStepExecutor, SummaryLevel, summary_index_id};
+pub use table::TableMapping;
+pub use transcribe::{Transcriber, Transcript, TranscriptSegment, WhisperConfig, WhisperTranscriber};
@@ -0,0 +1,645 @@
+/*!
+ * Normalize Step
+ *
+ * Collapses exact duplicates (content hash of whitespace/case-normalized text)
+ * and near-duplicates (MinHash or SimHash over word shingles) for documents
+ * and chunks, reporting what was collapsed in the step metrics. Kept documents
+ * and chunks get their detected language (ISO 639-1) as `lang` metadata, which
+ * the embed step routes on and search can filter or boost by.
+ */
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+
+const DEFAULT_SIMILARITY_THRESHOLD: f64 = 0.9;
+const DEFAULT_SHINGLE_SIZE: usize = 3;
+const DEFAULT_NUM_HASHES: usize = 128;
+
+/// Rows per LSH band for MinHash candidate generation
+const MINHASH_BAND_ROWS: usize = 4;
+
+/// Letters needed before a language is detected; shorter chunks keep their document's
+const MIN_LANGUAGE_LETTERS: usize = 12;
+/// Characters sampled for language detection
+const LANGUAGE_SAMPLE_CHARS: usize = 4000;
+const MIN_LANGUAGE_CONFIDENCE: f64 = 0.5;
+
+/// ISO 639-3 codes (as detected) to the ISO 639-1 codes the model registry uses
+const ISO_639_1: &[(&str, &str)] = &[
+    ("afr", "af"), ("aka", "ak"), ("amh", "am"), ("ara", "ar"), ("aze", "az"), ("bel", "be"), ("ben", "bn"),
+    ("bul", "bg"), ("cat", "ca"), ("ces", "cs"), ("cmn", "zh"), ("dan", "da"), ("deu", "de"), ("ell", "el"),
+    ("eng", "en"), ("epo", "eo"), ("est", "et"), ("fin", "fi"), ("fra", "fr"), ("guj", "gu"), ("heb", "he"),
+    ("hin", "hi"), ("hrv", "hr"), ("hun", "hu"), ("hye", "hy"), ("ind", "id"), ("ita", "it"), ("jav", "jv"),
+    ("jpn", "ja"), ("kan", "kn"), ("kat", "ka"), ("khm", "km"), ("kor", "ko"), ("lat", "la"), ("lav", "lv"),
+    ("lit", "lt"), ("mal", "ml"), ("mar", "mr"), ("mkd", "mk"), ("mya", "my"), ("nep", "ne"), ("nld", "nl"),
+    ("nob", "nb"), ("ori", "or"), ("pan", "pa"), ("pes", "fa"), ("pol", "pl"), ("por", "pt"), ("ron", "ro"),
+    ("rus", "ru"), ("sin", "si"), ("slk", "sk"), ("slv", "sl"), ("sna", "sn"), ("spa", "es"), ("srp", "sr"),
+    ("swe", "sv"), ("tam", "ta"), ("tel", "te"), ("tgl", "tl"), ("tha", "th"), ("tuk", "tk"), ("tur", "tr"),
+    ("ukr", "uk"), ("urd", "ur"), ("uzb", "uz"), ("vie", "vi"), ("yid", "yi"), ("zul", "zu"),
+];
+
+/// Near-duplicate detection method
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NearDupMethod {
+    /// Exact-hash dedup only
+    None,
+    MinHash,
+    SimHash,
+}
+
+/// Normalize step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NormalizeStepConfig {
+    #[serde(default = "default_method")]
+    pub method: NearDupMethod,
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f64,
+    #[serde(default = "default_shingle_size")]
+    pub shingle_size: usize,
+    #[serde(default = "default_num_hashes")]
+    pub num_hashes: usize,
+    #[serde(default = "default_true")]
+    pub dedup_chunks: bool,
+    #[serde(default = "default_true")]
+    pub detect_language: bool,          // Tag documents and chunks with `lang`
+}
+
+fn default_method() -> NearDupMethod { NearDupMethod::MinHash }
+fn default_similarity_threshold() -> f64 { DEFAULT_SIMILARITY_THRESHOLD }
+fn default_shingle_size() -> usize { DEFAULT_SHINGLE_SIZE }
+fn default_num_hashes() -> usize { DEFAULT_NUM_HASHES }
+fn default_true() -> bool { true }
+
+impl Default for NormalizeStepConfig {
+    fn default() -> Self {
+        Self {
+            method: NearDupMethod::MinHash,
+            similarity_threshold: DEFAULT_SIMILARITY_THRESHOLD,
+            shingle_size: DEFAULT_SHINGLE_SIZE,
+            num_hashes: DEFAULT_NUM_HASHES,
+            dedup_chunks: true,
+            detect_language: true,
+        }
+    }
+}
+
+/// Items collapsed into a kept representative
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DuplicateCluster {
+    pub kept: String,
+    pub collapsed: Vec<String>,
+    pub exact: bool,
+}
+
+/// Dedup counts for one item kind (documents or chunks)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DedupStats {
+    pub input: usize,
+    pub output: usize,
+    pub exact_duplicates: usize,
+    pub near_duplicates: usize,
+    pub clusters: Vec<DuplicateCluster>,
+}
+
+/// Normalize report stored with the pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NormalizeReport {
+    pub method: NearDupMethod,
+    pub similarity_threshold: f64,
+    pub downgraded: bool,               // Near-dup pass skipped under memory pressure
+    pub documents: DedupStats,
+    pub chunks: DedupStats,
+    pub orphaned_chunks: usize,         // Chunks dropped with their collapsed document
+    #[serde(default)]
+    pub languages: BTreeMap<String, usize>, // Kept documents per detected language
+}
+
+/// Lowercase text with collapsed whitespace, used for all comparisons
+pub fn canonical_text(text: &str) -> String {
+    text.split_whitespace()
+        .map(|w| w.to_lowercase())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// ISO 639-1 code of the text's language; `None` for short text or an
+/// unconfident guess
+pub fn detect_language(text: &str) -> Option<&'static str> {
+    let sample: String = text.chars().take(LANGUAGE_SAMPLE_CHARS).collect();
+    if sample.chars().filter(|c| c.is_alphabetic()).count() < MIN_LANGUAGE_LETTERS {
+        return None;
+    }
+    let info = whatlang::detect(&sample).filter(|info| info.confidence() >= MIN_LANGUAGE_CONFIDENCE)?;
+    let code = info.lang().code();
+    ISO_639_1.iter().find(|(iso3, _)| *iso3 == code).map(|(_, iso1)| *iso1)
+}
+
+/// 64-bit FNV-1a (stable across runs and platforms)
+fn fnv1a(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf29ce484222325u64, |hash, b| {
+        (hash ^ *b as u64).wrapping_mul(0x100000001b3)
+    })
+}
+
+/// SplitMix64 finalizer, used to derive independent hash functions
+fn mix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e3779b97f4a7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+/// Hashed word shingles of canonical text
+pub fn shingles(canonical: &str, size: usize) -> HashSet<u64> {
+    let words: Vec<&str> = canonical.split(' ').filter(|w| !w.is_empty()).collect();
+    if words.len() <= size {
+        return std::iter::once(fnv1a(canonical.as_bytes())).collect();
+    }
+    words.windows(size).map(|w| fnv1a(w.join(" ").as_bytes())).collect()
+}
+
+/// MinHash signature with `num_hashes` permutations
+pub fn minhash_signature(shingles: &HashSet<u64>, num_hashes: usize) -> Vec<u64> {
+    (0..num_hashes as u64)
+        .map(|seed| {
+            shingles.iter()
+                .map(|s| mix64(s ^ mix64(seed)))
+                .min()
+                .unwrap_or(u64::MAX)
+        })
+        .collect()
+}
+
+/// Estimated Jaccard similarity of two MinHash signatures
+pub fn minhash_similarity(a: &[u64], b: &[u64]) -> f64 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+    a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / a.len() as f64
+}
+
+/// 64-bit SimHash fingerprint
+pub fn simhash(shingles: &HashSet<u64>) -> u64 {
+    let mut weights = [0i64; 64];
+    for shingle in shingles {
+        let hash = mix64(*shingle);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash & (1 << bit) != 0 { *weight += 1 } else { *weight -= 1 }
+        }
+    }
+    weights.iter().enumerate()
+        .filter(|(_, w)| **w > 0)
+        .fold(0u64, |acc, (bit, _)| acc | (1 << bit))
+}
+
+/// SimHash similarity (1 - normalized Hamming distance)
+pub fn simhash_similarity(a: u64, b: u64) -> f64 {
+    1.0 - (a ^ b).count_ones() as f64 / 64.0
+}
+
+/// Union-find over item indices (representative is the lowest index)
+struct Clusters {
+    parent: Vec<usize>,
+}
+
+impl Clusters {
+    fn new(n: usize) -> Self {
+        Self { parent: (0..n).collect() }
+    }
+
+    fn find(&mut self, i: usize) -> usize {
+        let mut root = i;
+        while self.parent[root] != root {
+            root = self.parent[root];
+        }
+        self.parent[i] = root;
+        root
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra != rb {
+            let (keep, drop) = if ra < rb { (ra, rb) } else { (rb, ra) };
+            self.parent[drop] = keep;
+        }
+    }
+}
+
+/// Find duplicates among texts; returns the indices to keep and the dedup stats
+pub fn dedup_texts(
+    ids: &[String],
+    texts: &[&str],
+    config: &NormalizeStepConfig,
+    near_dup: bool,
+) -> (Vec<usize>, DedupStats) {
+    let n = texts.len();
+    let canonical: Vec<String> = texts.iter().map(|t| canonical_text(t)).collect();
+    let mut clusters = Clusters::new(n);
+    let mut exact_pairs: HashSet<usize> = HashSet::new();
+
+    // Exact duplicates: identical canonical text
+    let mut first_by_hash: HashMap<u64, usize> = HashMap::new();
+    for (i, text) in canonical.iter().enumerate() {
+        match first_by_hash.get(&fnv1a(text.as_bytes())) {
+            Some(&first) if canonical[first] == *text => {
+                clusters.union(first, i);
+                exact_pairs.insert(i);
+            }
+            _ => {
+                first_by_hash.insert(fnv1a(text.as_bytes()), i);
+            }
+        }
+    }
+
+    // Near duplicates among the remaining unique texts
+    let unique: Vec<usize> = (0..n).filter(|i| !exact_pairs.contains(i)).collect();
+    if near_dup && unique.len() > 1 {
+        let shingle_sets: Vec<HashSet<u64>> = unique.iter()
+            .map(|&i| shingles(&canonical[i], config.shingle_size.max(1)))
+            .collect();
+
+        match config.method {
+            NearDupMethod::None => {}
+            NearDupMethod::MinHash => {
+                let num_hashes = config.num_hashes.max(MINHASH_BAND_ROWS);
+                let signatures: Vec<Vec<u64>> = shingle_sets.iter()
+                    .map(|s| minhash_signature(s, num_hashes))
+                    .collect();
+
+                // LSH banding limits comparisons to likely candidates
+                let mut candidates: HashSet<(usize, usize)> = HashSet::new();
+                for band in 0..num_hashes / MINHASH_BAND_ROWS {
+                    let mut buckets: HashMap<u64, Vec<usize>> = HashMap::new();
+                    for (pos, signature) in signatures.iter().enumerate() {
+                        let rows = &signature[band * MINHASH_BAND_ROWS..(band + 1) * MINHASH_BAND_ROWS];
+                        let key = rows.iter().fold(band as u64, |acc, r| mix64(acc ^ r));
+                        buckets.entry(key).or_default().push(pos);
+                    }
+                    for bucket in buckets.values().filter(|b| b.len() > 1) {
+                        for (x, &a) in bucket.iter().enumerate() {
+                            for &b in &bucket[x + 1..] {
+                                candidates.insert((a, b));
+                            }
+                        }
+                    }
+                }
+
+                for (a, b) in candidates {
+                    if minhash_similarity(&signatures[a], &signatures[b]) >= config.similarity_threshold {
+                        clusters.union(unique[a], unique[b]);
+                    }
+                }
+            }
+            NearDupMethod::SimHash => {
+                // MVP: pairwise comparison, upgrade path to permuted-table lookup
+                let fingerprints: Vec<u64> = shingle_sets.iter().map(simhash).collect();
+                for a in 0..fingerprints.len() {
+                    for b in a + 1..fingerprints.len() {
+                        if simhash_similarity(fingerprints[a], fingerprints[b]) >= config.similarity_threshold {
+                            clusters.union(unique[a], unique[b]);
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    // Group by representative
+    let mut members: HashMap<usize, Vec<usize>> = HashMap::new();
+    for i in 0..n {
+        let root = clusters.find(i);
+        members.entry(root).or_default().push(i);
+    }
+
+    let mut keep: Vec<usize> = members.keys().copied().collect();
+    keep.sort_unstable();
+
+    let mut stats = DedupStats {
+        input: n,
+        output: keep.len(),
+        ..Default::default()
+    };
+    for &root in &keep {
+        let collapsed: Vec<usize> = members[&root].iter().copied().filter(|&i| i != root).collect();
+        if collapsed.is_empty() {
+            continue;
+        }
+        let exact = collapsed.iter().all(|i| canonical[*i] == canonical[root]);
+        for i in &collapsed {
+            if canonical[*i] == canonical[root] {
+                stats.exact_duplicates += 1;
+            } else {
+                stats.near_duplicates += 1;
+            }
+        }
+        stats.clusters.push(DuplicateCluster {
+            kept: ids[root].clone(),
+            collapsed: collapsed.iter().map(|i| ids[*i].clone()).collect(),
+            exact,
+        });
+    }
+
+    (keep, stats)
+}
+
+/// Normalize step executor (document and chunk dedup)
+pub struct NormalizeStepExecutor;
+
+impl NormalizeStepExecutor {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Default for NormalizeStepExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl StepExecutor for NormalizeStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Normalize
+    }
+
+    fn supports_downgrade(&self) -> bool {
+        true
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: NormalizeStepConfig = if ctx.config().is_null() {
+            NormalizeStepConfig::default()
+        } else {
+            serde_json::from_value(ctx.config().clone())
+                .map_err(|e| PipelineError::InvalidConfig(format!("normalize: {}", e)))?
+        };
+
+        if !(0.0..=1.0).contains(&config.similarity_threshold) {
+            return Err(PipelineError::InvalidConfig(
+                "normalize: similarityThreshold must be between 0 and 1".to_string()
+            ));
+        }
+
+        // Under memory pressure fall back to exact-hash dedup only
+        let downgraded = ctx.downgrade_requested();
+        if downgraded {
+            warn!("Normalize step downgraded, skipping near-duplicate detection");
+        }
+        let near_dup = config.method != NearDupMethod::None && !downgraded;
+
+        let StepData { documents, chunks } = data;
+
+        let doc_ids: Vec<String> = documents.iter().map(|d| d.id.clone()).collect();
+        let doc_texts: Vec<&str> = documents.iter().map(|d| d.content.as_str()).collect();
+        let (keep_docs, doc_stats) = dedup_texts(&doc_ids, &doc_texts, &config, near_dup);
+
+        let collapsed_into: HashMap<&str, &str> = doc_stats.clusters.iter()
+            .flat_map(|c| c.collapsed.iter().map(move |id| (id.as_str(), c.kept.as_str())))
+            .collect();
+
+        let mut kept_documents = Vec::with_capacity(keep_docs.len());
+        for i in keep_docs {
+            let mut doc = documents[i].clone();
+            let duplicates: Vec<&str> = collapsed_into.iter()
+                .filter(|(_, kept)| **kept == doc.id)
+                .map(|(dup, _)| *dup)
+                .collect();
+            if !duplicates.is_empty() {
+                if let Some(metadata) = doc.metadata.as_object_mut() {
+                    metadata.insert("collapsed_duplicates".to_string(), serde_json::json!(duplicates));
+                }
+            }
+            kept_documents.push(doc);
+        }
+
+        // Chunks of collapsed documents go away with them
+        let chunks_in = chunks.len();
+        let surviving: Vec<PipelineChunk> = chunks.into_iter()
+            .filter(|c| !collapsed_into.contains_key(c.document_id.as_str()))
+            .collect();
+        let orphaned_chunks = chunks_in - surviving.len();
+
+        let (mut kept_chunks, chunk_stats) = if config.dedup_chunks {
+            let chunk_ids: Vec<String> = surviving.iter().map(|c| c.id.clone()).collect();
+            let chunk_texts: Vec<&str> = surviving.iter().map(|c| c.content.as_str()).collect();
+            let (keep, stats) = dedup_texts(&chunk_ids, &chunk_texts, &config, near_dup);
+            (keep.into_iter().map(|i| surviving[i].clone()).collect(), stats)
+        } else {
+            let stats = DedupStats {
+                input: surviving.len(),
+                output: surviving.len(),
+                ..Default::default()
+            };
+            (surviving, stats)
+        };
+
+        let mut languages: BTreeMap<String, usize> = BTreeMap::new();
+        if config.detect_language {
+            let mut document_languages: HashMap<String, &'static str> = HashMap::new();
+            for doc in &mut kept_documents {
+                let Some(lang) = detect_language(&doc.content) else { continue };
+                *languages.entry(lang.to_string()).or_default() += 1;
+                document_languages.insert(doc.id.clone(), lang);
+                if let Some(metadata) = doc.metadata.as_object_mut() {
+                    metadata.insert("lang".to_string(), lang.into());
+                }
+            }
+            for chunk in &mut kept_chunks {
+                let lang = detect_language(&chunk.content).or_else(|| document_languages.get(&chunk.document_id).copied());
+                if let Some((metadata, lang)) = chunk.metadata.as_object_mut().zip(lang) {
+                    metadata.insert("lang".to_string(), lang.into());
+                }
+            }
+        }
+
+        let report = NormalizeReport {
+            method: config.method,
+            similarity_threshold: config.similarity_threshold,
+            downgraded,
+            documents: doc_stats,
+            chunks: chunk_stats,
+            orphaned_chunks,
+            languages,
+        };
+
+        info!(
+            "Normalize for pipeline {}: {} -> {} documents ({} exact, {} near), {} -> {} chunks",
+            ctx.pipeline_id, report.documents.input, report.documents.output,
+            report.documents.exact_duplicates, report.documents.near_duplicates,
+            chunks_in, kept_chunks.len()
+        );
+
+        Ok(StepOutcome {
+            items_processed: report.documents.input,
+            details: serde_json::to_value(&report)?,
+            data: StepData {
+                documents: kept_documents,
+                chunks: kept_chunks,
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Arc;
+
+    fn doc(id: &str, content: &str) -> PipelineDocument {
+        PipelineDocument {
+            id: id.to_string(),
+            title: id.to_string(),
+            source_path: format!("/docs/{}.md", id),
+            content: content.to_string(),
+            content_hash: String::new(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    fn chunk(id: &str, document_id: &str, content: &str) -> PipelineChunk {
+        PipelineChunk {
+            id: id.to_string(),
+            document_id: document_id.to_string(),
+            chunk_index: 0,
+            content: content.to_string(),
+            content_hash: String::new(),
+            metadata: serde_json::json!({}),
+            embedding: Vec::new(),
+        }
+    }
+
+    fn normalize_context(config: serde_json::Value) -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Normalize,
+            config,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Normalize".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    const BASE: &str = "The quick brown fox jumps over the lazy dog while the farmer watches \
+        from the porch and the cat sleeps in the warm afternoon sun near the old barn door";
+
+    #[test]
+    fn test_similarity_estimates() {
+        let a = shingles(&canonical_text(BASE), 3);
+        let b = shingles(&canonical_text(&BASE.replace("barn", "red barn")), 3);
+        let c = shingles(&canonical_text("completely unrelated text about databases and indexes"), 3);
+
+        let (sa, sb, sc) = (minhash_signature(&a, 128), minhash_signature(&b, 128), minhash_signature(&c, 128));
+        assert!(minhash_similarity(&sa, &sb) > 0.7);
+        assert!(minhash_similarity(&sa, &sc) < 0.2);
+        assert!(simhash_similarity(simhash(&a), simhash(&b)) > simhash_similarity(simhash(&a), simhash(&c)));
+    }
+
+    #[tokio::test]
+    async fn test_exact_and_near_duplicates_collapsed() {
+        let data = StepData {
+            documents: vec![
+                doc("d1", BASE),
+                doc("d2", &BASE.to_uppercase()),                       // exact after normalization
+                doc("d3", &format!("{} today", BASE)),                // near duplicate
+                doc("d4", "A different document about vector search quality"),
+            ],
+            chunks: vec![
+                chunk("c1", "d1", "first chunk"),
+                chunk("c2", "d2", "first chunk"),
+                chunk("c3", "d4", "other chunk"),
+                chunk("c4", "d4", "Other   chunk"),
+            ],
+        };
+
+        let outcome = NormalizeStepExecutor::new()
+            .execute(&normalize_context(serde_json::json!({"similarityThreshold": 0.8})), data)
+            .await
+            .unwrap();
+
+        let kept: Vec<&str> = outcome.data.documents.iter().map(|d| d.id.as_str()).collect();
+        assert_eq!(kept, vec!["d1", "d4"]);
+        assert_eq!(outcome.details["documents"]["exact_duplicates"], 1);
+        assert_eq!(outcome.details["documents"]["near_duplicates"], 1);
+        assert_eq!(outcome.details["orphaned_chunks"], 1);
+
+        // c4 is an exact duplicate of c3 once whitespace and case are normalized
+        let chunk_ids: Vec<&str> = outcome.data.chunks.iter().map(|c| c.id.as_str()).collect();
+        assert_eq!(chunk_ids, vec!["c1", "c3"]);
+        assert_eq!(outcome.data.documents[0].metadata["collapsed_duplicates"].as_array().unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_exact_only_method() {
+        let data = StepData {
+            documents: vec![doc("d1", BASE), doc("d2", &format!("{} today", BASE))],
+            chunks: vec![],
+        };
+
+        let outcome = NormalizeStepExecutor::new()
+            .execute(&normalize_context(serde_json::json!({"method": "none"})), data)
+            .await
+            .unwrap();
+
+        assert_eq!(outcome.data.documents.len(), 2);
+        assert_eq!(outcome.details["documents"]["near_duplicates"], 0);
+    }
+
+    #[tokio::test]
+    async fn test_language_tags() {
+        let data = StepData {
+            documents: vec![
+                doc("d1", BASE),
+                doc("d2", "Die Installation dauert nur wenige Minuten, danach startet der Dienst automatisch im Hintergrund."),
+                doc("d3", "42 / 7"),
+            ],
+            chunks: vec![
+                chunk("c1", "d2", "Der Dienst startet automatisch, sobald die Installation abgeschlossen ist."),
+                chunk("c2", "d2", "v1.2"),
+            ],
+        };
+
+        let outcome = NormalizeStepExecutor::new()
+            .execute(&normalize_context(serde_json::json!({})), data)
+            .await
+            .unwrap();
+
+        let langs: Vec<Option<&str>> = outcome.data.documents.iter().map(|d| d.metadata["lang"].as_str()).collect();
+        assert_eq!(langs, vec![Some("en"), Some("de"), None]);
+        assert_eq!(outcome.details["languages"], serde_json::json!({"de": 1, "en": 1}));
+        // Too short to detect: the chunk takes its document's language
+        assert_eq!(outcome.data.chunks[1].metadata["lang"], "de");
+
+        let data = StepData { documents: vec![doc("d1", BASE)], chunks: vec![] };
+        let outcome = NormalizeStepExecutor::new()
+            .execute(&normalize_context(serde_json::json!({"detectLanguage": false})), data)
+            .await
+            .unwrap();
+        assert!(outcome.data.documents[0].metadata.get("lang").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_invalid_threshold() {
+        let result = NormalizeStepExecutor::new()
+            .execute(&normalize_context(serde_json::json!({"similarityThreshold": 1.5})), StepData::default())
+            .await;
+        assert!(matches!(result, Err(PipelineError::InvalidConfig(_))));
+    }
+}
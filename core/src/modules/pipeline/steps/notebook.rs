@@ -0,0 +1,159 @@
+/*!
+ * Jupyter Notebook Parsing
+ *
+ * Reads `.ipynb` files (nbformat 4) for the parse step as a list of
+ * sections: each code cell on its own, with its execution count and text
+ * outputs, and each run of consecutive Markdown cells together, so prose and
+ * code are indexed (and retrieved) separately. Raw cells and images are
+ * dropped.
+ */
+
+use std::path::Path;
+use serde_json::Value;
+
+/// Characters of a cell's outputs kept
+const MAX_OUTPUT_CHARS: usize = 2000;
+
+/// Consecutive cells indexed as one document
+#[derive(Debug, Clone, PartialEq)]
+pub struct NotebookSection {
+    pub cell_type: String,              // "code" or "markdown"
+    pub cells: (usize, usize),          // 1-based, inclusive
+    pub execution_count: Option<u64>,   // Code cells that were run
+    pub content: String,
+}
+
+/// Notebook kernel language and sections
+#[derive(Debug, Clone, PartialEq)]
+pub struct Notebook {
+    pub language: Option<String>,
+    pub sections: Vec<NotebookSection>,
+}
+
+pub fn is_notebook_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("ipynb"))
+}
+
+/// Sections of a notebook; `outputs` appends code cells' text outputs
+pub fn parse_notebook(bytes: &[u8], outputs: bool) -> Result<Notebook, String> {
+    let notebook: Value = serde_json::from_slice(bytes).map_err(|e| e.to_string())?;
+    let cells = notebook["cells"].as_array().ok_or("no cells (nbformat 4 is required)")?;
+    let language = notebook.pointer("/metadata/language_info/name")
+        .or_else(|| notebook.pointer("/metadata/kernelspec/language"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+
+    let mut sections: Vec<NotebookSection> = Vec::new();
+    for (index, cell) in cells.iter().enumerate() {
+        let number = index + 1;
+        let source = multiline(&cell["source"]);
+        match cell["cell_type"].as_str() {
+            Some("markdown") if !source.trim().is_empty() => match sections.last_mut() {
+                Some(last) if last.cell_type == "markdown" && last.cells.1 == number - 1 => {
+                    last.content.push_str("\n\n");
+                    last.content.push_str(source.trim());
+                    last.cells.1 = number;
+                }
+                _ => sections.push(NotebookSection {
+                    cell_type: "markdown".to_string(),
+                    cells: (number, number),
+                    execution_count: None,
+                    content: source.trim().to_string(),
+                }),
+            },
+            Some("code") if !source.trim().is_empty() => {
+                let fence = language.as_deref().unwrap_or("");
+                let mut content = format!("```{}\n{}\n```", fence, source.trim_end());
+                let output = if outputs { cell_output(cell) } else { String::new() };
+                if !output.is_empty() {
+                    content.push_str("\n\nOutput:\n");
+                    content.push_str(&output);
+                }
+                sections.push(NotebookSection {
+                    cell_type: "code".to_string(),
+                    cells: (number, number),
+                    execution_count: cell["execution_count"].as_u64(),
+                    content,
+                });
+            }
+            _ => {}
+        }
+    }
+    Ok(Notebook { language, sections })
+}
+
+/// nbformat stores text as a string or a list of lines
+fn multiline(value: &Value) -> String {
+    match value {
+        Value::Array(lines) => lines.iter().filter_map(Value::as_str).collect(),
+        Value::String(text) => text.clone(),
+        _ => String::new(),
+    }
+}
+
+/// Text of a code cell's outputs (streams, plain-text results and errors),
+/// cut to `MAX_OUTPUT_CHARS`
+fn cell_output(cell: &Value) -> String {
+    let mut text = String::new();
+    for output in cell["outputs"].as_array().into_iter().flatten() {
+        let part = match output["output_type"].as_str() {
+            Some("stream") => multiline(&output["text"]),
+            Some("execute_result" | "display_data") => multiline(&output["data"]["text/plain"]),
+            Some("error") => format!("{}: {}", output["ename"].as_str().unwrap_or("Error"), output["evalue"].as_str().unwrap_or("")),
+            _ => String::new(),
+        };
+        if !part.trim().is_empty() {
+            text.push_str(part.trim_end());
+            text.push('\n');
+        }
+    }
+    let text = text.trim_end();
+    match text.char_indices().nth(MAX_OUTPUT_CHARS) {
+        Some((cut, _)) => format!("{}\n[output truncated]", &text[..cut]),
+        None => text.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cells_become_sections() {
+        let notebook = serde_json::json!({
+            "nbformat": 4,
+            "metadata": {"language_info": {"name": "python"}},
+            "cells": [
+                {"cell_type": "markdown", "source": ["# Load data\n", "Read the CSV."]},
+                {"cell_type": "markdown", "source": "Columns are documented below."},
+                {"cell_type": "code", "execution_count": 3, "source": ["import pandas as pd\n", "df = pd.read_csv('x.csv')\n", "len(df)"],
+                 "outputs": [
+                    {"output_type": "stream", "name": "stdout", "text": ["loaded\n"]},
+                    {"output_type": "execute_result", "data": {"text/plain": ["42"], "image/png": "iVBOR"}}
+                 ]},
+                {"cell_type": "raw", "source": "ignored"},
+                {"cell_type": "code", "execution_count": null, "source": "", "outputs": []},
+                {"cell_type": "code", "execution_count": null, "source": "df.head()",
+                 "outputs": [{"output_type": "error", "ename": "NameError", "evalue": "name 'df' is not defined"}]},
+                {"cell_type": "markdown", "source": "Done."}
+            ]
+        });
+        let parsed = parse_notebook(notebook.to_string().as_bytes(), true).unwrap();
+        assert_eq!(parsed.language.as_deref(), Some("python"));
+        assert_eq!(parsed.sections.len(), 4);
+
+        let intro = &parsed.sections[0];
+        assert_eq!((intro.cell_type.as_str(), intro.cells), ("markdown", (1, 2)));
+        assert_eq!(intro.content, "# Load data\nRead the CSV.\n\nColumns are documented below.");
+
+        let load = &parsed.sections[1];
+        assert_eq!(load.execution_count, Some(3));
+        assert_eq!(load.content, "```python\nimport pandas as pd\ndf = pd.read_csv('x.csv')\nlen(df)\n```\n\nOutput:\nloaded\n42");
+        assert!(parsed.sections[2].content.ends_with("NameError: name 'df' is not defined"));
+        assert_eq!(parsed.sections[2].cells, (6, 6));
+
+        let without_outputs = parse_notebook(notebook.to_string().as_bytes(), false).unwrap();
+        assert!(!without_outputs.sections[1].content.contains("Output:"));
+        assert!(parse_notebook(b"{\"nbformat\": 3, \"worksheets\": []}", true).is_err());
+    }
+}
@@ -0,0 +1,267 @@
+/*!
+ * OCR for Scanned Documents
+ *
+ * Detects which parse inputs need OCR (standalone images and PDFs without a
+ * text layer) and recognizes them through an `OcrEngine`. The bundled engine
+ * drives the Tesseract CLI (`tesseract`), rasterizing PDF pages with Poppler's
+ * `pdftoppm` first; per-page confidence comes from Tesseract's TSV output.
+ * MVP: the text layer check looks for font resources in the raw PDF bytes, so
+ * PDFs whose fonts sit in compressed object streams are OCR'd too.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::Duration;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use super::super::errors::PipelineError;
+
+/// Environment variable overriding the Tesseract binary
+pub const TESSERACT_BINARY_ENV: &str = "RAG_STUDIO_TESSERACT";
+
+/// Environment variable overriding Poppler's `pdftoppm` binary
+pub const PDFTOPPM_BINARY_ENV: &str = "RAG_STUDIO_PDFTOPPM";
+
+/// Extensions of files that may need OCR (added to the parse step's directory filter)
+pub const OCR_EXTENSIONS: &[&str] = &["pdf", "png", "jpg", "jpeg", "tif", "tiff", "bmp", "gif", "webp"];
+
+/// Input that needs OCR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrSource {
+    Image,
+    Pdf,
+}
+
+/// Recognized text of one page (images have a single page)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OcrPage {
+    pub page: usize,                // 1-based
+    pub text: String,
+    pub confidence: f32,            // Mean word confidence, 0-100
+}
+
+/// OCR backend (Tesseract CLI, or a Python worker op in the desktop app)
+#[async_trait]
+pub trait OcrEngine: Send + Sync {
+    /// Engine name recorded in document metadata
+    fn name(&self) -> &str;
+
+    async fn recognize(&self, path: &Path, source: OcrSource, languages: &[String]) -> Result<Vec<OcrPage>, PipelineError>;
+}
+
+/// Detect whether a file's content needs OCR
+///
+/// `force` also OCRs PDFs that carry a text layer.
+pub fn detect_ocr_source(bytes: &[u8], force: bool) -> Option<OcrSource> {
+    const IMAGE_MAGIC: &[&[u8]] = &[b"\x89PNG", b"\xFF\xD8\xFF", b"II*\0", b"MM\0*", b"BM", b"GIF8"];
+    if IMAGE_MAGIC.iter().any(|magic| bytes.starts_with(magic))
+        || (bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP")
+    {
+        return Some(OcrSource::Image);
+    }
+    if bytes.starts_with(b"%PDF-") && (force || !pdf_has_text_layer(bytes)) {
+        return Some(OcrSource::Pdf);
+    }
+    None
+}
+
+/// True if the PDF declares fonts, i.e. has text that can be extracted without OCR
+fn pdf_has_text_layer(bytes: &[u8]) -> bool {
+    bytes.windows(5).any(|window| window == b"/Font")
+}
+
+/// Page text and mean word confidence from `tesseract ... tsv` output
+///
+/// Words on the same line are joined with spaces; a new paragraph or block
+/// starts a blank-line-separated paragraph.
+pub fn parse_tesseract_tsv(tsv: &str) -> (String, f32) {
+    let mut text = String::new();
+    let mut current: Option<(&str, &str, &str)> = None;     // block, paragraph, line
+    let (mut confidence_sum, mut words) = (0.0f32, 0usize);
+
+    for line in tsv.lines().skip(1) {
+        let fields: Vec<&str> = line.split('\t').collect();
+        // level page block par line word left top width height conf text
+        if fields.len() < 12 || fields[0] != "5" {
+            continue;
+        }
+        let word = fields[11].trim();
+        let confidence: f32 = fields[10].parse().unwrap_or(-1.0);
+        if word.is_empty() || confidence < 0.0 {
+            continue;
+        }
+
+        let position = (fields[2], fields[3], fields[4]);
+        match current {
+            Some(previous) if previous == position => text.push(' '),
+            Some(previous) if (previous.0, previous.1) == (position.0, position.1) => text.push('\n'),
+            Some(_) => text.push_str("\n\n"),
+            None => {}
+        }
+        current = Some(position);
+        text.push_str(word);
+        confidence_sum += confidence;
+        words += 1;
+    }
+
+    let confidence = if words == 0 { 0.0 } else { confidence_sum / words as f32 };
+    (text, confidence)
+}
+
+/// Run an external tool to completion; its stdout on success
+pub(super) async fn run_tool(command: &mut Command, timeout_secs: u64) -> Result<String, PipelineError> {
+    command.stdin(Stdio::null()).stdout(Stdio::piped()).stderr(Stdio::piped()).kill_on_drop(true);
+    let output = tokio::time::timeout(Duration::from_secs(timeout_secs), command.output())
+        .await
+        .map_err(|_| PipelineError::StepFailed(format!("{:?} timed out after {}s", command.as_std().get_program(), timeout_secs)))??;
+    if !output.status.success() {
+        return Err(PipelineError::StepFailed(format!(
+            "{:?} exited with {:?}: {}",
+            command.as_std().get_program(),
+            output.status.code(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Tesseract engine configuration
+#[derive(Debug, Clone)]
+pub struct TesseractConfig {
+    pub binary_path: PathBuf,       // Tesseract CLI
+    pub pdftoppm_path: PathBuf,     // Rasterizes PDF pages
+    pub dpi: u32,
+    pub timeout_secs: u64,          // Per tool invocation
+}
+
+impl Default for TesseractConfig {
+    fn default() -> Self {
+        Self {
+            binary_path: std::env::var(TESSERACT_BINARY_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("tesseract")),
+            pdftoppm_path: std::env::var(PDFTOPPM_BINARY_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("pdftoppm")),
+            dpi: 300,
+            timeout_secs: 120,
+        }
+    }
+}
+
+/// OCR through the Tesseract CLI
+pub struct TesseractOcrEngine {
+    config: TesseractConfig,
+}
+
+impl TesseractOcrEngine {
+    pub fn new(config: TesseractConfig) -> Self {
+        Self { config }
+    }
+
+    /// True when the Tesseract binary runs
+    pub fn is_available(&self) -> bool {
+        std::process::Command::new(&self.config.binary_path)
+            .arg("--version")
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .status()
+            .is_ok_and(|status| status.success())
+    }
+
+    async fn recognize_image(&self, image: &Path, page: usize, languages: &[String]) -> Result<OcrPage, PipelineError> {
+        let mut command = Command::new(&self.config.binary_path);
+        command.arg(image).arg("stdout").arg("--dpi").arg(self.config.dpi.to_string());
+        if !languages.is_empty() {
+            command.arg("-l").arg(languages.join("+"));
+        }
+        let tsv = run_tool(command.arg("tsv"), self.config.timeout_secs).await?;
+        let (text, confidence) = parse_tesseract_tsv(&tsv);
+        Ok(OcrPage { page, text, confidence })
+    }
+
+    async fn recognize_pdf(&self, pdf: &Path, languages: &[String]) -> Result<Vec<OcrPage>, PipelineError> {
+        let dir = std::env::temp_dir().join(format!("rag_ocr_{}", uuid::Uuid::new_v4().simple()));
+        tokio::fs::create_dir_all(&dir).await?;
+        let result = self.recognize_pdf_pages(pdf, &dir, languages).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+            warn!("Failed to remove OCR scratch directory {:?}: {}", dir, e);
+        }
+        result
+    }
+
+    async fn recognize_pdf_pages(&self, pdf: &Path, dir: &Path, languages: &[String]) -> Result<Vec<OcrPage>, PipelineError> {
+        run_tool(Command::new(&self.config.pdftoppm_path)
+            .arg("-r").arg(self.config.dpi.to_string())
+            .arg("-png")
+            .arg(pdf)
+            .arg(dir.join("page")), self.config.timeout_secs)
+            .await?;
+
+        // pdftoppm zero-pads page numbers to the same width, so names sort in page order
+        let mut images: Vec<PathBuf> = std::fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect::<Result<_, _>>()?;
+        images.sort();
+
+        let mut pages = Vec::with_capacity(images.len());
+        for (index, image) in images.iter().enumerate() {
+            pages.push(self.recognize_image(image, index + 1, languages).await?);
+        }
+        debug!("OCR'd {} pages of {:?}", pages.len(), pdf);
+        Ok(pages)
+    }
+}
+
+impl Default for TesseractOcrEngine {
+    fn default() -> Self {
+        Self::new(TesseractConfig::default())
+    }
+}
+
+#[async_trait]
+impl OcrEngine for TesseractOcrEngine {
+    fn name(&self) -> &str {
+        "tesseract"
+    }
+
+    async fn recognize(&self, path: &Path, source: OcrSource, languages: &[String]) -> Result<Vec<OcrPage>, PipelineError> {
+        match source {
+            OcrSource::Image => Ok(vec![self.recognize_image(path, 1, languages).await?]),
+            OcrSource::Pdf => self.recognize_pdf(path, languages).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_ocr_source() {
+        assert_eq!(detect_ocr_source(b"\x89PNG\r\n\x1a\n", false), Some(OcrSource::Image));
+        assert_eq!(detect_ocr_source(b"RIFF\0\0\0\0WEBPVP8 ", false), Some(OcrSource::Image));
+
+        let scanned = b"%PDF-1.4\n1 0 obj << /Type /XObject /Subtype /Image >> endobj";
+        let text = b"%PDF-1.4\n1 0 obj << /Resources << /Font << /F1 2 0 R >> >> >> endobj";
+        assert_eq!(detect_ocr_source(scanned, false), Some(OcrSource::Pdf));
+        assert_eq!(detect_ocr_source(text, false), None);
+        assert_eq!(detect_ocr_source(text, true), Some(OcrSource::Pdf));
+        assert_eq!(detect_ocr_source(b"# Plain markdown", true), None);
+    }
+
+    #[test]
+    fn test_parse_tesseract_tsv() {
+        let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n\
+                   1\t1\t0\t0\t0\t0\t0\t0\t100\t100\t-1\t\n\
+                   5\t1\t1\t1\t1\t1\t0\t0\t10\t10\t96\tInvoice\n\
+                   5\t1\t1\t1\t1\t2\t0\t0\t10\t10\t90\t42\n\
+                   5\t1\t1\t1\t2\t1\t0\t0\t10\t10\t84\tPaid\n\
+                   5\t1\t2\t1\t1\t1\t0\t0\t10\t10\t70\tThanks\n\
+                   5\t1\t2\t1\t1\t2\t0\t0\t10\t10\t-1\t \n";
+        let (text, confidence) = parse_tesseract_tsv(tsv);
+        assert_eq!(text, "Invoice 42\nPaid\n\nThanks");
+        assert!((confidence - 85.0).abs() < 1e-4);
+        assert_eq!(parse_tesseract_tsv(""), (String::new(), 0.0));
+    }
+}
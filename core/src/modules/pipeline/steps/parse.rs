@@ -0,0 +1,828 @@
+/*!
+ * Parse Step
+ *
+ * Reads the local files a pipeline lists in its step config into pipeline
+ * documents. Every path (and every file found when walking a directory) is
+ * resolved through the run's `SourceSandbox`, so only files under the
+ * pipeline's declared `source_roots` are read.
+ *
+ * Images and scanned PDFs go through the configured `OcrEngine` (see `ocr`);
+ * per-page confidence is kept in the document's `ocr` metadata. Audio and
+ * video files go through the configured `Transcriber` (see `transcribe`),
+ * keeping each segment's character span and time range in `transcript`.
+ * Email files yield one document per message (see `email`), with sender and
+ * date in `email` metadata and attachments parsed into linked documents.
+ * CSV files and spreadsheets yield one document per row or group of rows
+ * (see `table`), mapped through the step's `table` config. Jupyter notebooks
+ * yield one document per code cell and per run of Markdown cells (see
+ * `notebook`), and reStructuredText is converted to Markdown-like text with
+ * `#` headings (see `rst`).
+ * MVP: otherwise UTF-8 text formats only; other binary files are skipped.
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, info, warn};
+
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+use super::super::sandbox::SourceSandbox;
+use super::ocr::{detect_ocr_source, OcrEngine, OcrSource, OCR_EXTENSIONS};
+use super::email::{is_email_file, parse_message, split_mbox, EmailMessage};
+use super::notebook::{is_notebook_file, parse_notebook};
+use super::rst::{is_rst_file, rst_to_text};
+use super::table::{is_table_file, map_rows, read_tables, TableMapping};
+use super::transcribe::{is_media_file, Transcriber, MEDIA_EXTENSIONS};
+use crate::modules::kb::DocumentFingerprint;
+
+/// Extensions read when walking a directory
+const DEFAULT_EXTENSIONS: &[&str] = &["md", "txt", "rst", "html", "json", "eml", "mbox", "csv", "tsv", "xlsx", "ipynb"];
+
+/// Parse step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ParseStepConfig {
+    pub sources: Vec<String>,           // Files or directories, relative to the first source root
+    #[serde(default)]
+    pub extensions: Vec<String>,        // Directory filter; defaults to common text formats (plus images, PDFs and recordings when their engines are set)
+    #[serde(default)]
+    pub ocr: OcrMode,
+    #[serde(default = "default_ocr_languages")]
+    pub ocr_languages: Vec<String>,     // Tesseract language codes, e.g. "eng", "chi_sim"
+    #[serde(default)]
+    pub transcript_language: Option<String>,    // Whisper language code, e.g. "en"; detected when unset
+    #[serde(default = "default_true")]
+    pub email_attachments: bool,        // Parse email attachments into linked documents
+    #[serde(default)]
+    pub table: TableMapping,            // Row-to-document mapping of CSV files and spreadsheets
+    #[serde(default = "default_true")]
+    pub notebook_outputs: bool,         // Append code cells' text outputs
+}
+
+fn default_ocr_languages() -> Vec<String> {
+    vec!["eng".to_string()]
+}
+
+fn default_true() -> bool { true }
+
+/// When the parse step runs OCR
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OcrMode {
+    /// Images and PDFs without a text layer
+    #[default]
+    Auto,
+    /// Every image and PDF
+    Always,
+    Never,
+}
+
+/// Parse step executor
+pub struct ParseStepExecutor {
+    ocr_engine: Option<Arc<dyn OcrEngine>>,
+    transcriber: Option<Arc<dyn Transcriber>>,
+}
+
+impl ParseStepExecutor {
+    pub fn new() -> Self {
+        Self { ocr_engine: None, transcriber: None }
+    }
+
+    /// Read images and scanned PDFs through an OCR engine
+    pub fn with_ocr(mut self, engine: Arc<dyn OcrEngine>) -> Self {
+        self.ocr_engine = Some(engine);
+        self
+    }
+
+    /// Read audio and video files through a speech-to-text engine
+    pub fn with_transcriber(mut self, transcriber: Arc<dyn Transcriber>) -> Self {
+        self.transcriber = Some(transcriber);
+        self
+    }
+
+    /// Read a file through the engine its type calls for. The outer error is an
+    /// I/O failure reading the file; the inner result is the engine's, and
+    /// `None` means a binary file or no text
+    async fn read_file(&self, path: &Path, config: &ParseStepConfig) -> Result<(Reader, Result<Option<Read>, PipelineError>), PipelineError> {
+        if let Some(transcriber) = self.transcriber.as_deref().filter(|_| is_media_file(path)) {
+            return Ok((Reader::Transcriber, read_transcript(transcriber, path, config.transcript_language.as_deref()).await));
+        }
+
+        let bytes = tokio::fs::read(path).await?;
+        let ocr_engine = self.ocr_engine.as_deref().filter(|_| config.ocr != OcrMode::Never);
+        match ocr_engine.zip(detect_ocr_source(&bytes, config.ocr == OcrMode::Always)) {
+            Some((engine, source)) => Ok((Reader::Ocr, read_ocr(engine, path, source, &config.ocr_languages).await)),
+            None if is_rst_file(path) => Ok((Reader::Text, Ok(read_text(bytes).map(|(content, _)| read_rst(&content))))),
+            None => Ok((Reader::Text, Ok(read_text(bytes)))),
+        }
+    }
+
+    /// One document per message of an `.eml` or `.mbox` file, each followed by
+    /// its readable attachments (linked both ways through metadata)
+    async fn read_email(&self, path: &Path, config: &ParseStepConfig, counts: &mut ParseCounts) -> Result<Vec<PipelineDocument>, PipelineError> {
+        let bytes = tokio::fs::read(path).await?;
+        let mbox = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("mbox"));
+        let raw_messages = if mbox { split_mbox(&bytes) } else { vec![bytes] };
+
+        let mut documents = Vec::new();
+        for (index, raw) in raw_messages.iter().enumerate() {
+            let message = parse_message(raw);
+            let content = if message.body.is_empty() { message.headers.subject.clone().unwrap_or_default() } else { message.body.clone() };
+            if content.trim().is_empty() && message.attachments.is_empty() {
+                continue;
+            }
+            let title = message.headers.subject.clone()
+                .unwrap_or_else(|| path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default());
+            let source_path = if mbox { format!("{}#{}", path.display(), index + 1) } else { path.to_string_lossy().to_string() };
+            let mut document = new_document(title, source_path, content, serde_json::json!({}));
+
+            let attachments = if config.email_attachments && !message.attachments.is_empty() {
+                let dir = std::env::temp_dir().join(format!("rag_email_{}", uuid::Uuid::new_v4().simple()));
+                tokio::fs::create_dir_all(&dir).await?;
+                let result = self.read_attachments(&message, &document, &dir, config, counts).await;
+                if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+                    warn!("Failed to remove attachment scratch directory {:?}: {}", dir, e);
+                }
+                result?
+            } else {
+                Vec::new()
+            };
+
+            let mut email = serde_json::to_value(&message.headers)?;
+            email["attachments"] = message.attachments.iter()
+                .enumerate()
+                .map(|(i, attachment)| serde_json::json!({
+                    "filename": attachment.filename,
+                    "content_type": attachment.content_type,
+                    "size": attachment.data.len(),
+                    "document_id": attachments.iter().find(|(index, _)| *index == i).map(|(_, doc)| &doc.id),
+                }))
+                .collect();
+            document.metadata = serde_json::json!({ "email": email });
+            counts.messages += 1;
+            documents.push(document);
+            documents.extend(attachments.into_iter().map(|(_, doc)| doc));
+        }
+        Ok(documents)
+    }
+
+    /// One document per record of each table in a CSV file or spreadsheet.
+    /// With an id column, ids are stable so re-parsing replaces the same rows
+    async fn read_table(&self, path: &Path, config: &ParseStepConfig, counts: &mut ParseCounts) -> Result<Vec<PipelineDocument>, PipelineError> {
+        let bytes = tokio::fs::read(path).await?;
+        let tables = match read_tables(path, bytes, &config.table) {
+            Ok(tables) => tables,
+            Err(e) => {
+                warn!("Failed to read table {:?}: {}", path, e);
+                return Ok(Vec::new());
+            }
+        };
+
+        let filename = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut documents = Vec::new();
+        for table in &tables {
+            let records = map_rows(table, &config.table)
+                .map_err(|e| PipelineError::InvalidConfig(format!("parse: table {:?}: {}", path, e)))?;
+            let location = match &table.sheet {
+                Some(sheet) => format!("{}#{}!", path.display(), sheet),
+                None => format!("{}#", path.display()),
+            };
+            for record in records {
+                let rows = match record.rows.as_slice() {
+                    [row] => format!("row={}", row),
+                    rows => format!("rows={}", rows.iter().map(usize::to_string).collect::<Vec<_>>().join(",")),
+                };
+                let title = record.title.clone().unwrap_or_else(|| format!("{} {}", filename, rows.replace('=', " ")));
+                let metadata = serde_json::json!({
+                    "table": {
+                        "file": filename,
+                        "sheet": table.sheet,
+                        "rows": record.rows,
+                        "key": record.key,
+                        "fields": record.fields,
+                    }
+                });
+                let mut document = new_document(title, format!("{}{}", location, rows), record.content, metadata);
+                if let Some(key) = &record.key {
+                    document.id = format!("doc_{}", &DocumentFingerprint::hash_content(&format!("{}{}", location, key))[..32]);
+                }
+                counts.table_rows += record.rows.len();
+                documents.push(document);
+            }
+        }
+        Ok(documents)
+    }
+
+    /// One document per code cell and per run of Markdown cells of a notebook,
+    /// with the cell numbers, type and execution count in `notebook` metadata
+    async fn read_notebook(&self, path: &Path, config: &ParseStepConfig, counts: &mut ParseCounts) -> Result<Vec<PipelineDocument>, PipelineError> {
+        let bytes = tokio::fs::read(path).await?;
+        let notebook = match parse_notebook(&bytes, config.notebook_outputs) {
+            Ok(notebook) => notebook,
+            Err(e) => {
+                warn!("Failed to read notebook {:?}: {}", path, e);
+                return Ok(Vec::new());
+            }
+        };
+
+        let filename = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        let mut documents = Vec::new();
+        for section in notebook.sections {
+            let (first, last) = section.cells;
+            let cells = if first == last { format!("cell={}", first) } else { format!("cells={}-{}", first, last) };
+            let metadata = serde_json::json!({
+                "notebook": {
+                    "file": filename,
+                    "language": notebook.language,
+                    "cell_type": section.cell_type,
+                    "cells": [first, last],
+                    "execution_count": section.execution_count,
+                }
+            });
+            counts.notebook_cells += last - first + 1;
+            documents.push(new_document(
+                format!("{} {}", filename, cells.replace('=', " ")),
+                format!("{}#{}", path.display(), cells),
+                section.content,
+                metadata,
+            ));
+        }
+        Ok(documents)
+    }
+
+    /// Documents for the readable attachments of `message`, by attachment index
+    async fn read_attachments(
+        &self,
+        message: &EmailMessage,
+        parent: &PipelineDocument,
+        dir: &Path,
+        config: &ParseStepConfig,
+        counts: &mut ParseCounts,
+    ) -> Result<Vec<(usize, PipelineDocument)>, PipelineError> {
+        let mut documents = Vec::new();
+        for (index, attachment) in message.attachments.iter().enumerate() {
+            // Keep the extension (it picks the reader) but never the sender's path
+            let name = Path::new(&attachment.filename).file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_else(|| format!("attachment_{}", index + 1));
+            let file = dir.join(format!("{}_{}", index, name));
+            tokio::fs::write(&file, &attachment.data).await?;
+
+            let (reader, read) = self.read_file(&file, config).await?;
+            let Some((content, mut metadata)) = usable_read(&file, read) else {
+                continue;
+            };
+            counts.record(reader);
+            counts.attachments += 1;
+            metadata["email_attachment"] = serde_json::json!({
+                "parent_document_id": parent.id,
+                "message_id": message.headers.message_id,
+                "filename": name,
+                "content_type": attachment.content_type,
+            });
+            documents.push((index, new_document(name.clone(), format!("{}/{}", parent.source_path, name), content, metadata)));
+        }
+        Ok(documents)
+    }
+}
+
+/// Content and metadata read from a file
+type Read = (String, serde_json::Value);
+
+/// Engine a file was read through
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Reader {
+    Text,
+    Ocr,
+    Transcriber,
+}
+
+/// Files read through each engine, reported in the step details
+#[derive(Debug, Default)]
+struct ParseCounts {
+    ocr: usize,
+    transcribed: usize,
+    messages: usize,
+    attachments: usize,
+    table_rows: usize,
+    notebook_cells: usize,
+}
+
+impl ParseCounts {
+    fn record(&mut self, reader: Reader) {
+        match reader {
+            Reader::Text => {}
+            Reader::Ocr => self.ocr += 1,
+            Reader::Transcriber => self.transcribed += 1,
+        }
+    }
+}
+
+/// The read content, logging why there is none
+fn usable_read(path: &Path, read: Result<Option<Read>, PipelineError>) -> Option<Read> {
+    match read {
+        Ok(Some(read)) => Some(read),
+        Ok(None) => {
+            debug!("Skipping {:?}: binary or no text found", path);
+            None
+        }
+        Err(e) => {
+            warn!("Failed to read {:?}: {}", path, e);
+            None
+        }
+    }
+}
+
+fn new_document(title: String, source_path: String, content: String, metadata: serde_json::Value) -> PipelineDocument {
+    PipelineDocument {
+        id: format!("doc_{}", uuid::Uuid::new_v4().simple()),
+        title,
+        source_path,
+        content_hash: DocumentFingerprint::hash_content(&content),
+        content,
+        license_info: None,
+        metadata,
+    }
+}
+
+impl Default for ParseStepExecutor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// OCR `path`; `None` when no text was recognized
+async fn read_ocr(
+    engine: &dyn OcrEngine,
+    path: &Path,
+    source: OcrSource,
+    languages: &[String],
+) -> Result<Option<Read>, PipelineError> {
+    let pages = engine.recognize(path, source, languages).await?;
+    let content = pages.iter()
+        .map(|page| page.text.trim())
+        .filter(|text| !text.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    if content.is_empty() {
+        return Ok(None);
+    }
+
+    let confidence = pages.iter().map(|page| page.confidence).sum::<f32>() / pages.len() as f32;
+    let metadata = serde_json::json!({
+        "ocr": {
+            "engine": engine.name(),
+            "source": source,
+            "languages": languages,
+            "confidence": confidence,
+            "pages": pages.iter()
+                .map(|page| serde_json::json!({
+                    "page": page.page,
+                    "confidence": page.confidence,
+                    "chars": page.text.chars().count(),
+                }))
+                .collect::<Vec<_>>(),
+        }
+    });
+    Ok(Some((content, metadata)))
+}
+
+/// Transcribe `path`; `None` when nothing was said
+async fn read_transcript(
+    transcriber: &dyn Transcriber,
+    path: &Path,
+    language: Option<&str>,
+) -> Result<Option<Read>, PipelineError> {
+    let transcript = transcriber.transcribe(path, language).await?;
+
+    // One segment per line; spans are character offsets into the content
+    let mut content = String::new();
+    let mut segments = Vec::with_capacity(transcript.segments.len());
+    let mut offset = 0;
+    for segment in &transcript.segments {
+        if !content.is_empty() {
+            content.push('\n');
+            offset += 1;
+        }
+        let length = segment.text.chars().count();
+        content.push_str(&segment.text);
+        segments.push(serde_json::json!({
+            "start": offset,
+            "end": offset + length,
+            "start_ms": segment.start_ms,
+            "end_ms": segment.end_ms,
+        }));
+        offset += length;
+    }
+    if content.trim().is_empty() {
+        return Ok(None);
+    }
+
+    let metadata = serde_json::json!({
+        "transcript": {
+            "engine": transcriber.name(),
+            "language": transcript.language,
+            "duration_ms": transcript.segments.iter().map(|s| s.end_ms).max().unwrap_or(0),
+            "segments": segments,
+        }
+    });
+    Ok(Some((content, metadata)))
+}
+
+/// UTF-8 text of a file; `None` for binary files. PDFs without OCR are
+/// binary even when their bytes happen to be UTF-8
+fn read_text(bytes: Vec<u8>) -> Option<Read> {
+    String::from_utf8(bytes).ok()
+        .filter(|content| !content.starts_with("%PDF-"))
+        .map(|content| (content, serde_json::json!({})))
+}
+
+/// reStructuredText as Markdown-like text, with the document title in `rst` metadata
+fn read_rst(source: &str) -> Read {
+    let document = rst_to_text(source);
+    (document.content, serde_json::json!({ "rst": { "title": document.title } }))
+}
+
+/// Files under `dir` (recursively) with an accepted extension, each checked against the sandbox
+fn walk_dir(sandbox: &SourceSandbox, dir: &Path, extensions: &[String], files: &mut Vec<PathBuf>) -> Result<(), PipelineError> {
+    let mut entries: Vec<PathBuf> = std::fs::read_dir(dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    entries.sort();
+
+    for entry in entries {
+        // A symlinked entry may point anywhere; resolve it like a listed source
+        let path = sandbox.resolve(&entry.to_string_lossy())?;
+        if path.is_dir() {
+            walk_dir(sandbox, &path, extensions, files)?;
+        } else if path.extension().and_then(|e| e.to_str()).is_some_and(|ext| extensions.iter().any(|x| x == ext)) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl StepExecutor for ParseStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Parse
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: ParseStepConfig = serde_json::from_value(ctx.config().clone())
+            .map_err(|e| PipelineError::InvalidConfig(format!("parse: {}", e)))?;
+        let ocr_engine = self.ocr_engine.as_deref().filter(|_| config.ocr != OcrMode::Never);
+        let transcriber = self.transcriber.as_deref();
+        let is_language = |l: &String| !l.is_empty() && l.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+        if let Some(language) = config.ocr_languages.iter().chain(&config.transcript_language).find(|l| !is_language(l)) {
+            return Err(PipelineError::InvalidConfig(format!("parse: invalid language '{}'", language)));
+        }
+        let extensions: Vec<String> = if config.extensions.is_empty() {
+            let ocr_extensions = if ocr_engine.is_some() { OCR_EXTENSIONS } else { &[] };
+            let media_extensions = if transcriber.is_some() { MEDIA_EXTENSIONS } else { &[] };
+            DEFAULT_EXTENSIONS.iter().chain(ocr_extensions).chain(media_extensions).map(|e| e.to_string()).collect()
+        } else {
+            config.extensions.clone()
+        };
+
+        let sandbox = ctx.sandbox();
+        let mut files = Vec::new();
+        for source in &config.sources {
+            let path = sandbox.resolve(source)?;
+            if path.is_dir() {
+                walk_dir(sandbox, &path, &extensions, &mut files)?;
+            } else {
+                files.push(path);
+            }
+        }
+
+        let mut data = data;
+        let mut skipped = Vec::new();
+        let mut counts = ParseCounts::default();
+        for path in &files {
+            if is_email_file(path) || is_table_file(path) || is_notebook_file(path) {
+                let documents = if is_email_file(path) {
+                    self.read_email(path, &config, &mut counts).await?
+                } else if is_table_file(path) {
+                    self.read_table(path, &config, &mut counts).await?
+                } else {
+                    self.read_notebook(path, &config, &mut counts).await?
+                };
+                if documents.is_empty() {
+                    skipped.push(path.to_string_lossy().to_string());
+                }
+                data.documents.extend(documents);
+                continue;
+            }
+
+            let (reader, read) = self.read_file(path, &config).await?;
+            let Some((content, metadata)) = usable_read(path, read) else {
+                skipped.push(path.to_string_lossy().to_string());
+                continue;
+            };
+            counts.record(reader);
+            data.documents.push(new_document(
+                path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default(),
+                path.to_string_lossy().to_string(),
+                content,
+                metadata,
+            ));
+        }
+
+        let parsed = files.len() - skipped.len();
+        info!(
+            "Parse step read {} files ({} via OCR, {} transcribed, {} email messages, {} table rows, {} notebook cells, {} skipped)",
+            parsed, counts.ocr, counts.transcribed, counts.messages, counts.table_rows, counts.notebook_cells, skipped.len()
+        );
+        Ok(StepOutcome {
+            data,
+            items_processed: parsed,
+            details: serde_json::json!({
+                "parsed": parsed,
+                "ocr": counts.ocr,
+                "transcribed": counts.transcribed,
+                "emails": counts.messages,
+                "attachments": counts.attachments,
+                "table_rows": counts.table_rows,
+                "notebook_cells": counts.notebook_cells,
+                "skipped": skipped,
+            }),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use tempfile::TempDir;
+    use super::super::ocr::OcrPage;
+    use super::super::transcribe::{Transcript, TranscriptSegment};
+
+    fn parse_context(roots: &[&Path], config: serde_json::Value) -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Parse,
+            config,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Parse".to_string(),
+            kb_id: None,
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: roots.iter().map(|r| r.to_string_lossy().to_string()).collect(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    #[tokio::test]
+    async fn test_parse_reads_sources_within_roots() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(docs.join("guides")).unwrap();
+        std::fs::write(docs.join("guides/install.md"), "Install the CLI").unwrap();
+        std::fs::write(docs.join("guides/logo.png"), [0u8, 159, 146, 150]).unwrap();
+        std::fs::write(docs.join("faq.txt"), "FAQ").unwrap();
+        std::fs::write(temp_dir.path().join("secret.txt"), "secret").unwrap();
+
+        let ctx = parse_context(&[&docs], serde_json::json!({"sources": ["guides", "faq.txt"]}));
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        let titles: Vec<&str> = outcome.data.documents.iter().map(|d| d.title.as_str()).collect();
+        assert_eq!(titles, vec!["install.md", "faq.txt"]);
+
+        let ctx = parse_context(&[&docs], serde_json::json!({"sources": ["../secret.txt"]}));
+        assert!(matches!(
+            ParseStepExecutor::new().execute(&ctx, StepData::default()).await,
+            Err(PipelineError::PathNotAllowed(_))
+        ));
+    }
+
+    /// Reads every page as "scanned page"
+    struct FixedOcr;
+
+    #[async_trait]
+    impl OcrEngine for FixedOcr {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn recognize(&self, _path: &Path, source: OcrSource, _languages: &[String]) -> Result<Vec<OcrPage>, PipelineError> {
+            let pages = if source == OcrSource::Pdf { 2 } else { 1 };
+            Ok((1..=pages)
+                .map(|page| OcrPage { page, text: "scanned page".to_string(), confidence: 80.0 + page as f32 })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_ocr_detection() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("notes.md"), "Notes").unwrap();
+        std::fs::write(temp_dir.path().join("receipt.png"), b"\x89PNG\r\n\x1a\n").unwrap();
+        std::fs::write(temp_dir.path().join("scan.pdf"), b"%PDF-1.4\n<< /Subtype /Image >>").unwrap();
+        std::fs::write(temp_dir.path().join("report.pdf"), b"%PDF-1.4\n<< /Font << /F1 2 0 R >> >>").unwrap();
+        let executor = ParseStepExecutor::new().with_ocr(Arc::new(FixedOcr));
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["."], "ocrLanguages": ["eng", "deu"]}));
+        let outcome = executor.execute(&ctx, StepData::default()).await.unwrap();
+        let titles: Vec<&str> = outcome.data.documents.iter().map(|d| d.title.as_str()).collect();
+        assert_eq!(titles, vec!["notes.md", "receipt.png", "scan.pdf"]);
+        assert_eq!(outcome.details["ocr"], 2);
+        let scan = &outcome.data.documents[2];
+        assert_eq!(scan.content, "scanned page\n\nscanned page");
+        assert_eq!(scan.metadata["ocr"]["source"], "pdf");
+        assert_eq!(scan.metadata["ocr"]["languages"], serde_json::json!(["eng", "deu"]));
+        assert_eq!(scan.metadata["ocr"]["pages"][1]["confidence"], 82.0);
+        assert!(outcome.data.documents[0].metadata.get("ocr").is_none());
+
+        // Text-layer PDFs are only OCR'd on request; without OCR, images and PDFs are skipped as binary
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["report.pdf"], "ocr": "always"}));
+        assert_eq!(executor.execute(&ctx, StepData::default()).await.unwrap().details["ocr"], 1);
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["receipt.png"], "ocr": "never"}));
+        assert_eq!(executor.execute(&ctx, StepData::default()).await.unwrap().details["parsed"], 0);
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["scan.pdf"], "ocrLanguages": ["-psm"]}));
+        assert!(matches!(executor.execute(&ctx, StepData::default()).await, Err(PipelineError::InvalidConfig(_))));
+    }
+
+    /// Two fixed segments for every recording
+    struct FixedTranscriber;
+
+    #[async_trait]
+    impl Transcriber for FixedTranscriber {
+        fn name(&self) -> &str {
+            "fixed"
+        }
+
+        async fn transcribe(&self, _path: &Path, language: Option<&str>) -> Result<Transcript, PipelineError> {
+            Ok(Transcript {
+                language: language.map(str::to_string),
+                segments: vec![
+                    TranscriptSegment { start_ms: 0, end_ms: 4000, text: "Welcome to the standup.".to_string() },
+                    TranscriptSegment { start_ms: 4000, end_ms: 9500, text: "The release ships Friday.".to_string() },
+                ],
+            })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_parse_transcribes_recordings() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("standup.mp4"), [0u8, 0, 0, 24]).unwrap();
+        std::fs::write(temp_dir.path().join("agenda.md"), "Agenda").unwrap();
+        let executor = ParseStepExecutor::new().with_transcriber(Arc::new(FixedTranscriber));
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["."], "transcriptLanguage": "en"}));
+        let outcome = executor.execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.details["transcribed"], 1);
+        let recording = outcome.data.documents.iter().find(|d| d.title == "standup.mp4").unwrap();
+        assert_eq!(recording.content, "Welcome to the standup.\nThe release ships Friday.");
+        let transcript = &recording.metadata["transcript"];
+        assert_eq!(transcript["language"], "en");
+        assert_eq!(transcript["duration_ms"], 9500);
+        assert_eq!(transcript["segments"][1], serde_json::json!({"start": 24, "end": 49, "start_ms": 4000, "end_ms": 9500}));
+
+        // Without a transcriber, recordings are binary and skipped
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.details["parsed"], 1);
+    }
+
+    #[tokio::test]
+    async fn test_parse_email_archive() {
+        let temp_dir = TempDir::new().unwrap();
+        let archive = "From jane@example.com Mon Jun  3 10:00:00 2024\n\
+                       Message-ID: <m1@example.com>\n\
+                       Subject: Runbook\n\
+                       From: Jane <jane@example.com>\n\
+                       Date: Mon, 3 Jun 2024 10:00:00 +0000\n\
+                       Content-Type: multipart/mixed; boundary=\"b\"\n\
+                       \n\
+                       --b\n\
+                       Content-Type: text/plain\n\
+                       \n\
+                       Runbook attached.\n\
+                       \n\
+                       On Sun, Jun 2, 2024 Bob wrote:\n\
+                       > Where is the runbook?\n\
+                       --b\n\
+                       Content-Type: text/markdown\n\
+                       Content-Disposition: attachment; filename=\"runbook.md\"\n\
+                       \n\
+                       Restart the indexer.\n\
+                       --b\n\
+                       Content-Type: application/octet-stream\n\
+                       Content-Disposition: attachment; filename=\"dump.bin\"\n\
+                       Content-Transfer-Encoding: base64\n\
+                       \n\
+                       AJ+SlgA=\n\
+                       --b--\n\
+                       From bob@example.com Mon Jun  3 11:00:00 2024\n\
+                       Subject: Thanks\n\
+                       \n\
+                       Got it.\n";
+        std::fs::write(temp_dir.path().join("ops.mbox"), archive).unwrap();
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["."]}));
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.details["emails"], 2);
+        assert_eq!(outcome.details["attachments"], 1);
+        let titles: Vec<&str> = outcome.data.documents.iter().map(|d| d.title.as_str()).collect();
+        assert_eq!(titles, vec!["Runbook", "runbook.md", "Thanks"]);
+
+        let (message, attachment) = (&outcome.data.documents[0], &outcome.data.documents[1]);
+        assert_eq!(message.content, "Runbook attached.");
+        assert!(message.source_path.ends_with("ops.mbox#1"));
+        assert_eq!(message.metadata["email"]["from"], "Jane <jane@example.com>");
+        assert_eq!(message.metadata["email"]["date"], "2024-06-03T10:00:00+00:00");
+        assert_eq!(message.metadata["email"]["attachments"][0]["document_id"], attachment.id.as_str());
+        assert!(message.metadata["email"]["attachments"][1]["document_id"].is_null());
+        assert_eq!(attachment.content.trim(), "Restart the indexer.");
+        assert_eq!(attachment.source_path, format!("{}/runbook.md", message.source_path));
+        assert_eq!(attachment.metadata["email_attachment"]["parent_document_id"], message.id.as_str());
+        assert_eq!(attachment.metadata["email_attachment"]["message_id"], "m1@example.com");
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["ops.mbox"], "emailAttachments": false}));
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.data.documents.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_parse_table_rows() {
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("products.csv"), "sku,name,description,price\n\
+            A1,Lamp,Desk lamp with dimmer,25\n\
+            B2,Chair,Ergonomic office chair,180\n").unwrap();
+
+        let config = serde_json::json!({"sources": ["."], "table": {
+            "idColumn": "sku", "titleColumn": "name", "contentColumns": ["name", "description"], "metadataColumns": ["price"],
+        }});
+        let ctx = parse_context(&[temp_dir.path()], config.clone());
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.details["table_rows"], 2);
+        let chair = &outcome.data.documents[1];
+        assert_eq!(chair.title, "Chair");
+        assert_eq!(chair.content, "name: Chair\ndescription: Ergonomic office chair");
+        assert!(chair.source_path.ends_with("products.csv#row=2"));
+        assert_eq!(chair.metadata["table"]["fields"]["price"], "180");
+
+        // Same ids on every parse
+        let again = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(again.data.documents[1].id, chair.id);
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["products.csv"], "table": {"groupBy": "category"}}));
+        assert!(matches!(ParseStepExecutor::new().execute(&ctx, StepData::default()).await, Err(PipelineError::InvalidConfig(_))));
+    }
+
+    #[tokio::test]
+    async fn test_parse_notebook_and_rst() {
+        let temp_dir = TempDir::new().unwrap();
+        let notebook = serde_json::json!({
+            "nbformat": 4,
+            "metadata": {"kernelspec": {"language": "python"}},
+            "cells": [
+                {"cell_type": "markdown", "source": "# Churn analysis"},
+                {"cell_type": "code", "execution_count": 7, "source": "print(churn.mean())",
+                 "outputs": [{"output_type": "stream", "text": "0.12\n"}]},
+            ]
+        });
+        std::fs::write(temp_dir.path().join("churn.ipynb"), notebook.to_string()).unwrap();
+        std::fs::write(temp_dir.path().join("index.rst"), "Guide\n=====\n\nSee :doc:`setup`.\n").unwrap();
+
+        let ctx = parse_context(&[temp_dir.path()], serde_json::json!({"sources": ["."]}));
+        let outcome = ParseStepExecutor::new().execute(&ctx, StepData::default()).await.unwrap();
+        assert_eq!(outcome.details["notebook_cells"], 2);
+        assert_eq!(outcome.data.documents.len(), 3);
+
+        let code = &outcome.data.documents[1];
+        assert_eq!(code.title, "churn.ipynb cell 2");
+        assert!(code.source_path.ends_with("churn.ipynb#cell=2"));
+        assert_eq!(code.content, "```python\nprint(churn.mean())\n```\n\nOutput:\n0.12");
+        assert_eq!(code.metadata["notebook"]["cell_type"], "code");
+        assert_eq!(code.metadata["notebook"]["execution_count"], 7);
+
+        let guide = &outcome.data.documents[2];
+        assert_eq!(guide.content, "# Guide\n\nSee setup.");
+        assert_eq!(guide.metadata["rst"]["title"], "Guide");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_parse_rejects_symlink_in_directory() {
+        let temp_dir = TempDir::new().unwrap();
+        let docs = temp_dir.path().join("docs");
+        std::fs::create_dir_all(&docs).unwrap();
+        std::fs::write(docs.join("readme.md"), "readme").unwrap();
+        std::fs::write(temp_dir.path().join("secret.md"), "secret").unwrap();
+        std::os::unix::fs::symlink(temp_dir.path().join("secret.md"), docs.join("leak.md")).unwrap();
+
+        let ctx = parse_context(&[&docs], serde_json::json!({"sources": ["."]}));
+        assert!(matches!(
+            ParseStepExecutor::new().execute(&ctx, StepData::default()).await,
+            Err(PipelineError::PathNotAllowed(_))
+        ));
+    }
+}
@@ -0,0 +1,399 @@
+/*!
+ * reStructuredText Parsing
+ *
+ * Converts `.rst` files (plain docutils and Sphinx) to Markdown-like text for
+ * the parse step. Section titles become `#` headings, ranked in the order their
+ * adornment styles first appear, so chunk neighbourhoods follow sections as
+ * they do for Markdown. Code and literal blocks become fenced blocks,
+ * admonitions keep their body under a label, and directives with no readable
+ * text (toctree, include, autodoc, images without captions, ...) are dropped
+ * along with comments, targets and substitution definitions. Inline roles and
+ * hyperlinks are reduced to their text.
+ */
+
+use std::path::Path;
+
+/// Directives whose body is code
+const CODE_DIRECTIVES: &[&str] = &["code-block", "code", "sourcecode", "literalinclude", "doctest", "testcode", "highlight"];
+
+/// Directives whose body is prose shown under a label
+const ADMONITIONS: &[&str] = &[
+    "note", "warning", "tip", "important", "caution", "danger", "error", "hint", "attention",
+    "seealso", "admonition", "deprecated", "versionadded", "versionchanged", "topic", "sidebar", "rubric",
+];
+
+/// Directives whose body is read as ordinary text
+const CONTAINERS: &[&str] = &["container", "compound", "only", "tab", "tabs", "group-tab", "figure", "table", "list-table", "glossary", "centered", "epigraph", "highlights", "pull-quote", "math"];
+
+/// Converted text and the first section title
+#[derive(Debug, Clone, PartialEq)]
+pub struct RstDocument {
+    pub title: Option<String>,
+    pub content: String,
+}
+
+pub fn is_rst_file(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("rst"))
+}
+
+/// Markdown-like text of a reStructuredText document
+pub fn rst_to_text(source: &str) -> RstDocument {
+    let lines: Vec<&str> = source.lines().map(|l| l.trim_end()).collect();
+    let mut out: Vec<String> = Vec::new();
+    let mut styles: Vec<(char, bool)> = Vec::new();
+    let mut title = None;
+    let mut i = 0;
+
+    while i < lines.len() {
+        let line = lines[i];
+
+        // Over- and underlined title
+        if is_adornment(line) && i + 2 < lines.len() && !lines[i + 1].trim().is_empty()
+            && lines[i + 2] == line && !is_adornment(lines[i + 1]) {
+            let text = inline(lines[i + 1].trim());
+            push_heading(&mut out, &mut styles, &mut title, (line.chars().next().unwrap(), true), text);
+            i += 3;
+            continue;
+        }
+        // Underlined title
+        if !line.trim().is_empty() && !line.starts_with(' ') && i + 1 < lines.len()
+            && is_adornment(lines[i + 1]) && lines[i + 1].chars().count() >= line.trim().chars().count() {
+            let text = inline(line.trim());
+            push_heading(&mut out, &mut styles, &mut title, (lines[i + 1].chars().next().unwrap(), false), text);
+            i += 2;
+            continue;
+        }
+        // Transition
+        if is_adornment(line) {
+            i += 1;
+            continue;
+        }
+
+        if let Some(rest) = line.trim_start().strip_prefix("..") {
+            let indent = indentation(line);
+            let (body, next) = indented_block(&lines, i + 1, indent);
+            i = next;
+            let rest = rest.trim();
+            let Some((name, argument)) = rest.split_once("::").filter(|(name, _)| !name.contains(' ') && !name.is_empty()) else {
+                // Comment, hyperlink target (`.. _label:`) or substitution definition
+                continue;
+            };
+            let name = name.rsplit(':').next().unwrap_or(name).to_lowercase();  // `py:function` and the like
+            let argument = argument.trim();
+            let body = strip_options(body);
+            if CODE_DIRECTIVES.contains(&name.as_str()) {
+                if !body.is_empty() {
+                    out.push(format!("```{}\n{}\n```", if name == "highlight" { "" } else { argument }, body.join("\n")));
+                }
+            } else if ADMONITIONS.contains(&name.as_str()) {
+                let label = match name.as_str() {
+                    "admonition" | "topic" | "sidebar" | "rubric" => inline(argument),
+                    "seealso" => "See also".to_string(),
+                    "versionadded" => format!("New in version {}", argument),
+                    "versionchanged" => format!("Changed in version {}", argument),
+                    "deprecated" => format!("Deprecated since version {}", argument),
+                    other => capitalize(other),
+                };
+                let text = if ["note", "warning", "tip", "important", "caution", "danger", "error", "hint", "attention", "seealso"].contains(&name.as_str()) && !argument.is_empty() {
+                    // The first line of the body may follow the directive
+                    std::iter::once(argument.to_string()).chain(body).collect::<Vec<_>>()
+                } else {
+                    body
+                };
+                let text = inline(&paragraphs(&text));
+                out.push(if text.is_empty() { label } else { format!("{}: {}", label, text) });
+            } else if CONTAINERS.contains(&name.as_str()) || name == "image" && !body.is_empty() {
+                // Figures and images keep their caption
+                let text = rst_to_text(&body.join("\n")).content;
+                if !text.is_empty() {
+                    out.push(text);
+                }
+            } else if name.starts_with("auto") || matches!(name.as_str(), "function" | "class" | "method" | "attribute" | "module" | "data" | "exception" | "option" | "envvar" | "describe") {
+                // Domain objects: keep the signature and description
+                let text = rst_to_text(&body.join("\n")).content;
+                if !argument.is_empty() && !name.starts_with("auto") {
+                    out.push(format!("`{}`", argument));
+                }
+                if !text.is_empty() && !name.starts_with("auto") {
+                    out.push(text);
+                }
+            }
+            continue;
+        }
+
+        // Paragraph, possibly introducing a literal block with `::`
+        if !line.trim().is_empty() {
+            let start = i;
+            while i < lines.len() && !lines[i].trim().is_empty() && !lines[i].trim_start().starts_with("..") {
+                i += 1;
+            }
+            let paragraph: Vec<String> = lines[start..i].iter().map(|l| l.to_string()).collect();
+            let mut text = paragraph.join("\n");
+            let literal = text.ends_with("::");
+            if literal {
+                text.truncate(text.len() - 2);
+                if text.ends_with(' ') || text.is_empty() {
+                    text = text.trim_end().to_string();
+                } else {
+                    text.push(':');
+                }
+            }
+            if !text.trim().is_empty() {
+                out.push(inline(&text));
+            }
+            if literal {
+                let indent = lines[start..i].iter().map(|l| indentation(l)).min().unwrap_or(0);
+                let (body, next) = indented_block(&lines, i, indent);
+                i = next;
+                if !body.is_empty() {
+                    out.push(format!("```\n{}\n```", body.join("\n")));
+                }
+            }
+            continue;
+        }
+        i += 1;
+    }
+
+    RstDocument { title, content: out.join("\n\n") }
+}
+
+fn push_heading(out: &mut Vec<String>, styles: &mut Vec<(char, bool)>, title: &mut Option<String>, style: (char, bool), text: String) {
+    let level = match styles.iter().position(|s| *s == style) {
+        Some(level) => level + 1,
+        None => {
+            styles.push(style);
+            styles.len()
+        }
+    };
+    if title.is_none() {
+        *title = Some(text.clone());
+    }
+    out.push(format!("{} {}", "#".repeat(level.min(6)), text));
+}
+
+/// A line of one repeated punctuation character, at least 3 long
+fn is_adornment(line: &str) -> bool {
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(c) if c.is_ascii_punctuation() => line.len() >= 3 && chars.all(|d| d == c),
+        _ => false,
+    }
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Lines from `start` indented deeper than `indent` (blank lines included),
+/// dedented, and the index after them
+fn indented_block(lines: &[&str], start: usize, indent: usize) -> (Vec<String>, usize) {
+    let mut end = start;
+    while end < lines.len() && (lines[end].trim().is_empty() || indentation(lines[end]) > indent) {
+        end += 1;
+    }
+    // Trailing blank lines belong to what follows
+    while end > start && lines[end - 1].trim().is_empty() {
+        end -= 1;
+    }
+    let block = &lines[start..end];
+    let dedent = block.iter().filter(|l| !l.trim().is_empty()).map(|l| indentation(l)).min().unwrap_or(0);
+    let body = block.iter()
+        .map(|l| if l.len() >= dedent { l[dedent..].to_string() } else { String::new() })
+        .skip_while(|l| l.is_empty())
+        .collect();
+    (body, end)
+}
+
+/// Directive body without its leading `:option: value` field list
+fn strip_options(body: Vec<String>) -> Vec<String> {
+    let options = body.iter().take_while(|l| l.starts_with(':') && l[1..].contains(':')).count();
+    body.into_iter().skip(options).skip_while(|l| l.is_empty()).collect()
+}
+
+/// Lines joined into paragraphs
+fn paragraphs(lines: &[String]) -> String {
+    lines.split(|l| l.trim().is_empty())
+        .map(|p| p.iter().map(|l| l.trim()).collect::<Vec<_>>().join(" "))
+        .filter(|p| !p.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+fn capitalize(word: &str) -> String {
+    let mut chars = word.chars();
+    chars.next().map(|c| c.to_uppercase().chain(chars).collect()).unwrap_or_default()
+}
+
+/// Inline markup reduced to text: roles, hyperlink references and
+/// substitutions lose their markup, ``literals`` become `code`
+fn inline(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find(['`', '|', ':']) {
+        out.push_str(&rest[..start]);
+        let tail = &rest[start..];
+        if let Some(literal) = tail.strip_prefix("``") {
+            if let Some(end) = literal.find("``") {
+                out.push('`');
+                out.push_str(&literal[..end]);
+                out.push('`');
+                rest = &literal[end + 2..];
+                continue;
+            }
+        }
+        // `:role:`text`` (or `:domain:role:`)
+        if let Some(role) = tail.strip_prefix(':') {
+            let name_end = role.find(|c: char| !(c.is_ascii_alphanumeric() || c == '-' || c == ':' || c == '_'));
+            if let Some(end) = name_end.filter(|&n| n > 0 && role[..n].ends_with(':') && role[n..].starts_with('`')) {
+                rest = &role[end..];
+                continue;
+            }
+            out.push(':');
+            rest = role;
+            continue;
+        }
+        if let Some(quoted) = tail.strip_prefix('`') {
+            if let Some(end) = quoted.find('`') {
+                let inner = &quoted[..end];
+                // `text <target>`_ and `~module.name` keep the text
+                let inner = match inner.rfind(" <") {
+                    Some(split) if inner.ends_with('>') => &inner[..split],
+                    _ if inner.starts_with('<') && inner.ends_with('>') => &inner[1..inner.len() - 1],
+                    _ => inner.trim_start_matches(['~', '!']),
+                };
+                out.push_str(inner);
+                rest = quoted[end + 1..].trim_start_matches('_');
+                continue;
+            }
+        }
+        if let Some(sub) = tail.strip_prefix('|') {
+            if let Some(end) = sub.find('|').filter(|&e| e > 0 && !sub[..e].contains(' ')) {
+                out.push_str(&sub[..end]);
+                rest = sub[end + 1..].trim_start_matches('_');
+                continue;
+            }
+        }
+        out.push_str(&tail[..1]);
+        rest = &tail[1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const GUIDE: &str = "\
+.. _install-guide:
+
+============
+Installation
+============
+
+.. toctree::
+   :maxdepth: 2
+
+   quickstart
+
+Requirements
+------------
+
+Install the package with :command:`pip` (see :ref:`the FAQ <faq>`)::
+
+    pip install ``ragkit``
+
+.. note:: Python 3.9 or
+   newer is required.
+
+.. code-block:: python
+   :linenos:
+
+   import ragkit
+   ragkit.connect()
+
+Configuration
+-------------
+
+Set ``RAG_HOME`` to the data directory; read `the docs <https://example.com>`_.
+
+.. image:: diagram.png
+
+.. autoclass:: ragkit.Client
+   :members:
+
+.. This comment is dropped.
+
+Logging
+~~~~~~~
+
+Levels follow |project| defaults.
+";
+
+    #[test]
+    fn test_sphinx_document() {
+        let doc = rst_to_text(GUIDE);
+        assert_eq!(doc.title.as_deref(), Some("Installation"));
+        assert_eq!(doc.content, "\
+# Installation
+
+## Requirements
+
+Install the package with pip (see the FAQ):
+
+```
+pip install ``ragkit``
+```
+
+Note: Python 3.9 or newer is required.
+
+```python
+import ragkit
+ragkit.connect()
+```
+
+## Configuration
+
+Set `RAG_HOME` to the data directory; read the docs.
+
+### Logging
+
+Levels follow project defaults.");
+    }
+
+    #[test]
+    fn test_directives() {
+        let doc = rst_to_text("\
+Intro paragraph: ends with a colon ::
+
+   literal
+
+.. warning::
+
+   Deleting is permanent.
+
+.. versionadded:: 2.1
+   The ``sync`` command.
+
+.. figure:: arch.png
+   :alt: architecture
+
+   The indexing pipeline.
+
+.. |project| replace:: ragkit
+");
+        assert_eq!(doc.title, None);
+        assert_eq!(doc.content, "\
+Intro paragraph: ends with a colon
+
+```
+literal
+```
+
+Warning: Deleting is permanent.
+
+New in version 2.1: The `sync` command.
+
+The indexing pipeline.");
+    }
+}
@@ -0,0 +1,433 @@
+/*!
+ * Summarize Step
+ *
+ * Builds a RAPTOR-style summary tree per document: sections (spans between
+ * Markdown headings, split to at most `sectionChars`) are summarized, then
+ * the document is summarized from its section summaries. The nodes go to the
+ * KB's separate summary index (`summary_index_id`) so they don't compete with
+ * chunks in normal search; hierarchical search ranks them first and drills
+ * down into the chunks they cover.
+ *
+ * Summaries come from the configured LLM. Without one, or when a generation
+ * fails, an extractive summary is used: the sentences sharing the most terms
+ * with the rest of the text.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use async_trait::async_trait;
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::schemas::VectorSchema;
+use crate::services::generation::GenerationParams;
+use crate::services::llm::LlmProvider;
+use crate::services::vector::{VectorDbError, VectorDbServiceTrait};
+use super::super::errors::PipelineError;
+use super::super::executor::{StepContext, StepExecutor};
+use super::super::models::*;
+
+const DEFAULT_SECTION_CHARS: usize = 2000;
+const DEFAULT_SUMMARY_SENTENCES: usize = 3;
+const DEFAULT_SUMMARY_TOKENS: u32 = 200;
+
+/// Smallest section worth its own summary
+const MIN_SECTION_CHARS: usize = 200;
+
+/// Summary index kept next to a KB's chunk index
+pub fn summary_index_id(kb_id: &str) -> String {
+    format!("{}_summaries", kb_id)
+}
+
+/// Level of a node in the summary tree
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SummaryLevel {
+    Section,                        // Consecutive chunks under one heading
+    Document,
+}
+
+impl SummaryLevel {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SummaryLevel::Section => "section",
+            SummaryLevel::Document => "document",
+        }
+    }
+}
+
+/// Summarize step configuration (camelCase keys in the step `config`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SummarizeStepConfig {
+    #[serde(default = "default_section_chars")]
+    pub section_chars: usize,
+    #[serde(default = "default_summary_sentences")]
+    pub summary_sentences: usize,
+    #[serde(default = "default_summary_tokens")]
+    pub max_tokens: u32,            // Per LLM summary
+}
+
+fn default_section_chars() -> usize { DEFAULT_SECTION_CHARS }
+fn default_summary_sentences() -> usize { DEFAULT_SUMMARY_SENTENCES }
+fn default_summary_tokens() -> u32 { DEFAULT_SUMMARY_TOKENS }
+
+impl Default for SummarizeStepConfig {
+    fn default() -> Self {
+        Self {
+            section_chars: DEFAULT_SECTION_CHARS,
+            summary_sentences: DEFAULT_SUMMARY_SENTENCES,
+            max_tokens: DEFAULT_SUMMARY_TOKENS,
+        }
+    }
+}
+
+/// Summarize report stored with the pipeline run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizeReport {
+    pub summarizer: String,         // LLM name or "extractive"
+    pub documents: usize,
+    pub sections: usize,
+    pub llm_failures: usize,        // Nodes that fell back to an extractive summary
+}
+
+/// Character span of a document
+#[derive(Debug, Clone, PartialEq)]
+pub struct Section {
+    pub start: usize,
+    pub end: usize,
+    pub heading: Option<String>,
+}
+
+/// Split a document at Markdown headings, then long sections at paragraph or
+/// word boundaries. Offsets are in characters, like the chunker's.
+pub fn split_sections(content: &str, max_chars: usize) -> Vec<Section> {
+    // Byte ranges between headings
+    let mut spans: Vec<(usize, usize, Option<String>)> = Vec::new();
+    let mut offset = 0;
+    for line in content.split_inclusive('\n') {
+        if let Some(heading) = heading_text(line) {
+            if let Some(last) = spans.last_mut() {
+                last.1 = offset;
+            }
+            spans.push((offset, content.len(), Some(heading.to_string())));
+        } else if spans.is_empty() {
+            spans.push((0, content.len(), None));
+        }
+        offset += line.len();
+    }
+
+    let max_chars = max_chars.max(MIN_SECTION_CHARS);
+    let mut sections = Vec::new();
+    for (mut start, end, heading) in spans {
+        loop {
+            let limit = content[start..end].char_indices().nth(max_chars).map(|(i, _)| start + i);
+            let cut = match limit {
+                Some(limit) => {
+                    let window = &content[start..limit];
+                    window.rfind("\n\n")
+                        .or_else(|| window.rfind(char::is_whitespace))
+                        .filter(|&i| i > 0)
+                        .map_or(limit, |i| start + i)
+                }
+                None => end,
+            };
+            if !content[start..cut].trim().is_empty() {
+                sections.push(Section {
+                    start: content[..start].chars().count(),
+                    end: content[..cut].chars().count(),
+                    heading: heading.clone(),
+                });
+            }
+            if cut >= end {
+                break;
+            }
+            start = cut;
+        }
+    }
+    sections
+}
+
+/// Text of an ATX Markdown heading line
+fn heading_text(line: &str) -> Option<&str> {
+    let line = line.trim();
+    let level = line.chars().take_while(|c| *c == '#').count();
+    ((1..=6).contains(&level) && line[level..].starts_with(' ')).then(|| line[level..].trim())
+}
+
+/// Sentences sharing the most terms with the rest of the text, in text order
+pub fn extractive_summary(text: &str, sentences: usize) -> String {
+    let candidates: Vec<&str> = text.split_inclusive(['.', '!', '?', '\n'])
+        .map(str::trim)
+        .filter(|s| s.chars().any(char::is_alphanumeric) && heading_text(s).is_none())
+        .collect();
+    let terms = |sentence: &str| -> Vec<String> {
+        sentence.split(|c: char| !c.is_alphanumeric())
+            .filter(|t| t.chars().count() > 2)
+            .map(str::to_lowercase)
+            .collect()
+    };
+
+    let mut frequency: HashMap<String, usize> = HashMap::new();
+    for sentence in &candidates {
+        for term in terms(sentence) {
+            *frequency.entry(term).or_default() += 1;
+        }
+    }
+    // Average term frequency, so long sentences don't win by length alone
+    let mut scored: Vec<(usize, f64)> = candidates.iter().enumerate()
+        .map(|(i, sentence)| {
+            let terms = terms(sentence);
+            let total: usize = terms.iter().map(|t| frequency[t]).sum();
+            (i, total as f64 / (terms.len() as f64 + 1.0))
+        })
+        .collect();
+    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal).then(a.0.cmp(&b.0)));
+    let mut picked: Vec<usize> = scored.into_iter().take(sentences.max(1)).map(|(i, _)| i).collect();
+    picked.sort_unstable();
+    picked.into_iter().map(|i| candidates[i]).collect::<Vec<_>>().join(" ")
+}
+
+/// Builds per-document summary trees into the KB's summary index
+pub struct SummarizeStepExecutor {
+    vector_service: Arc<dyn VectorDbServiceTrait + Send + Sync>,
+    llm: Option<Arc<dyn LlmProvider>>,
+}
+
+impl SummarizeStepExecutor {
+    pub fn new(vector_service: Arc<dyn VectorDbServiceTrait + Send + Sync>) -> Self {
+        Self { vector_service, llm: None }
+    }
+
+    /// Summarize with a model instead of extracting sentences
+    pub fn with_llm(mut self, llm: Arc<dyn LlmProvider>) -> Self {
+        self.llm = Some(llm);
+        self
+    }
+
+    /// Summary of `text`; the flag is set when the LLM failed and extraction was used
+    async fn summarize(&self, config: &SummarizeStepConfig, subject: &str, text: &str) -> (String, bool) {
+        let Some(llm) = &self.llm else {
+            return (extractive_summary(text, config.summary_sentences), false);
+        };
+        let prompt = format!(
+            "Summarize {} in at most {} sentences. Keep the names, numbers and terms it uses.\n\n{}\n\nSummary:",
+            subject, config.summary_sentences, text,
+        );
+        let params = GenerationParams { max_tokens: config.max_tokens, temperature: 0.0, ..Default::default() };
+        match llm.generate(&prompt, &params).await {
+            Ok(output) if !output.text.trim().is_empty() => (output.text.trim().to_string(), false),
+            Ok(_) => (extractive_summary(text, config.summary_sentences), true),
+            Err(e) => {
+                warn!("Summary generation failed, using an extractive summary: {}", e);
+                (extractive_summary(text, config.summary_sentences), true)
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl StepExecutor for SummarizeStepExecutor {
+    fn step_type(&self) -> ETLStepType {
+        ETLStepType::Summarize
+    }
+
+    async fn execute(&self, ctx: &StepContext, data: StepData) -> Result<StepOutcome, PipelineError> {
+        let config: SummarizeStepConfig = if ctx.config().is_null() {
+            SummarizeStepConfig::default()
+        } else {
+            serde_json::from_value(ctx.config().clone())
+                .map_err(|e| PipelineError::InvalidConfig(format!("summarize: {}", e)))?
+        };
+        if config.summary_sentences == 0 {
+            return Err(PipelineError::InvalidConfig("summarize: summarySentences must be greater than 0".to_string()));
+        }
+
+        let kb_id = ctx.kb_id.as_deref()
+            .ok_or_else(|| PipelineError::InvalidConfig("summarize: pipeline has no target KB".to_string()))?;
+        let index_id = summary_index_id(kb_id);
+        let now = Utc::now().timestamp();
+
+        let mut report = SummarizeReport {
+            summarizer: self.llm.as_ref().map_or_else(|| "extractive".to_string(), |llm| llm.name()),
+            documents: 0,
+            sections: 0,
+            llm_failures: 0,
+        };
+        for doc in &data.documents {
+            let node = |id: String, level: SummaryLevel, content: String, span: (usize, usize), heading: Option<&str>| VectorSchema {
+                chunk_id: id,
+                document_id: doc.id.clone(),
+                kb_id: kb_id.to_string(),
+                content,
+                embedding: Vec::new(),  // MVP: ranked lexically until summaries are embedded
+                metadata: serde_json::json!({
+                    "summary_level": level,
+                    "title": doc.title,
+                    "source_path": doc.source_path,
+                    "heading": heading,
+                    "start": span.0,
+                    "end": span.1,
+                }),
+                created_at: now,
+                updated_at: now,
+            };
+
+            let sections = split_sections(&doc.content, config.section_chars);
+            if sections.is_empty() {
+                continue;
+            }
+            let chars: Vec<char> = doc.content.chars().collect();
+            let mut nodes = Vec::with_capacity(sections.len() + 1);
+            let mut section_summaries = Vec::with_capacity(sections.len());
+            // A single section is summarized once, at document level
+            if sections.len() > 1 {
+                for (i, section) in sections.iter().enumerate() {
+                    let text: String = chars[section.start..section.end].iter().collect();
+                    let subject = format!("this section of \"{}\"", doc.title);
+                    let (summary, failed) = self.summarize(&config, &subject, &text).await;
+                    report.llm_failures += failed as usize;
+                    section_summaries.push(match &section.heading {
+                        Some(heading) => format!("{}: {}", heading, summary),
+                        None => summary.clone(),
+                    });
+                    nodes.push(node(format!("{}_section_{}", doc.id, i), SummaryLevel::Section, summary, (section.start, section.end), section.heading.as_deref()));
+                }
+            }
+
+            let text = if section_summaries.is_empty() { doc.content.clone() } else { section_summaries.join("\n") };
+            let (summary, failed) = self.summarize(&config, &format!("the document \"{}\"", doc.title), &text).await;
+            report.llm_failures += failed as usize;
+            nodes.push(node(format!("{}_document", doc.id), SummaryLevel::Document, summary, (0, chars.len()), None));
+
+            // Replace the document's previous tree
+            match self.vector_service.delete_document(&index_id, &doc.id).await {
+                Ok(()) | Err(VectorDbError::CollectionNotFound(_)) => {}
+                Err(e) => return Err(e.into()),
+            }
+            report.sections += nodes.len() - 1;
+            report.documents += 1;
+            self.vector_service.upsert_vectors(&index_id, nodes).await?;
+        }
+
+        info!(
+            "Summarized {} documents ({} sections) into {} with {}",
+            report.documents, report.sections, index_id, report.summarizer
+        );
+        Ok(StepOutcome {
+            items_processed: report.documents + report.sections,
+            data,
+            details: serde_json::to_value(&report)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicBool;
+    use std::sync::Mutex;
+    use crate::schemas::SearchResult;
+    use crate::services::vector::CollectionStats;
+
+    /// Records upserted nodes per index
+    #[derive(Default)]
+    struct RecordingIndex {
+        nodes: Mutex<HashMap<String, Vec<VectorSchema>>>,
+    }
+
+    #[async_trait]
+    impl VectorDbServiceTrait for RecordingIndex {
+        async fn create_collection(&self, _kb_id: &str, _schema: &VectorSchema) -> Result<(), VectorDbError> { Ok(()) }
+        async fn upsert_vectors(&self, kb_id: &str, vectors: Vec<VectorSchema>) -> Result<(), VectorDbError> {
+            self.nodes.lock().unwrap().entry(kb_id.to_string()).or_default().extend(vectors);
+            Ok(())
+        }
+        async fn search(&self, _kb_id: &str, _query_vector: &[f32], _limit: usize, _filter: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> { Ok(Vec::new()) }
+        async fn hybrid_search(&self, _kb_id: &str, _query: &str, _query_vector: &[f32], _limit: usize, _filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> { Ok(Vec::new()) }
+        async fn bm25_search(&self, _kb_id: &str, _query: &str, _limit: usize, _filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError> { Ok(Vec::new()) }
+        async fn delete_collection(&self, _kb_id: &str) -> Result<(), VectorDbError> { Ok(()) }
+        async fn delete_document(&self, kb_id: &str, document_id: &str) -> Result<(), VectorDbError> {
+            match self.nodes.lock().unwrap().get_mut(kb_id) {
+                Some(nodes) => nodes.retain(|n| n.document_id != document_id),
+                None => return Err(VectorDbError::CollectionNotFound(kb_id.to_string())),
+            }
+            Ok(())
+        }
+        async fn get_collection_stats(&self, _kb_id: &str) -> Result<CollectionStats, VectorDbError> {
+            Err(VectorDbError::CollectionNotFound("unused".to_string()))
+        }
+    }
+
+    fn summarize_context() -> StepContext {
+        let step = PipelineStepConfig {
+            step_type: ETLStepType::Summarize,
+            config: serde_json::Value::Null,
+            resources: None,
+            on_limit: ResourceLimitAction::Abort,
+        };
+        let spec = PipelineSpec {
+            id: "pipeline_1".to_string(),
+            name: "Summarize".to_string(),
+            kb_id: Some("kb_1".to_string()),
+            steps: vec![step.clone()],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        };
+        StepContext::new("run_1", &spec, step, Arc::new(AtomicBool::new(false)))
+    }
+
+    fn document(content: &str) -> PipelineDocument {
+        PipelineDocument {
+            id: "doc_1".to_string(),
+            title: "guide.md".to_string(),
+            source_path: "/docs/guide.md".to_string(),
+            content: content.to_string(),
+            content_hash: String::new(),
+            license_info: None,
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let content = format!("Intro text.\n# Install\n{}\n# Upgrade\nRun the upgrade.\n", "Run the installer. ".repeat(20));
+        let sections = split_sections(&content, 200);
+        let headings: Vec<Option<&str>> = sections.iter().map(|s| s.heading.as_deref()).collect();
+        assert_eq!(headings, vec![None, Some("Install"), Some("Install"), Some("Upgrade")]);
+        assert!(sections.iter().all(|s| s.end - s.start <= 200));
+        assert_eq!(sections.last().unwrap().end, content.chars().count());
+        assert_eq!(split_sections("", 200), Vec::new());
+    }
+
+    #[test]
+    fn test_extractive_summary() {
+        let text = "Backups run nightly. The weather was nice. Backups are kept for thirty days and backups can be restored.";
+        assert_eq!(extractive_summary(text, 1), "Backups are kept for thirty days and backups can be restored.");
+        assert_eq!(extractive_summary(text, 5), text);
+    }
+
+    #[tokio::test]
+    async fn test_summarize_builds_tree() {
+        let index = Arc::new(RecordingIndex::default());
+        let executor = SummarizeStepExecutor::new(index.clone());
+        let content = "# Install\nRun the installer as root. The installer sets up the service.\n\
+                       # Backups\nBackups run nightly. Backups are kept for thirty days.\n";
+        let data = StepData { documents: vec![document(content)], chunks: Vec::new() };
+
+        let outcome = executor.execute(&summarize_context(), data.clone()).await.unwrap();
+        assert_eq!(outcome.details["sections"], 2);
+        assert_eq!(outcome.details["summarizer"], "extractive");
+        assert_eq!(outcome.data.documents.len(), 1);
+
+        // Re-running replaces the document's tree
+        executor.execute(&summarize_context(), data).await.unwrap();
+        let nodes = index.nodes.lock().unwrap()["kb_1_summaries"].clone();
+        let ids: Vec<&str> = nodes.iter().map(|n| n.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["doc_1_section_0", "doc_1_section_1", "doc_1_document"]);
+        assert_eq!(nodes[1].metadata["heading"], "Backups");
+        assert_eq!(nodes[2].metadata["summary_level"], "document");
+        assert!(nodes[2].content.contains("Install:") && nodes[2].content.contains("Backups:"));
+    }
+}
@@ -0,0 +1,273 @@
+/*!
+ * Table Parsing
+ *
+ * Reads CSV/TSV files and spreadsheets (`.xlsx`, `.xls`, `.ods`, every
+ * sheet) for the parse step, turning rows into records: one per row, per
+ * fixed run of rows, or per group of rows sharing a column value. The
+ * `TableMapping` picks the columns that become content ("Column: value"
+ * lines), the title, a stable id, and metadata, so FAQs and catalogs index
+ * as one searchable entry per question or product.
+ */
+
+use std::collections::BTreeMap;
+use std::io::Cursor;
+use std::path::Path;
+use calamine::Reader as _;
+use serde::{Deserialize, Serialize};
+
+/// Extensions of tabular files (read when walking a directory)
+pub const TABLE_EXTENSIONS: &[&str] = &["csv", "tsv", "xlsx", "xls", "ods"];
+
+/// How table rows map to documents (camelCase keys under the parse step's `table`)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TableMapping {
+    #[serde(default)]
+    pub content_columns: Vec<String>,   // Defaults to every column not used otherwise
+    #[serde(default)]
+    pub metadata_columns: Vec<String>,
+    #[serde(default)]
+    pub id_column: Option<String>,      // Stable document ids, so re-parsing replaces rows
+    #[serde(default)]
+    pub title_column: Option<String>,
+    #[serde(default)]
+    pub group_by: Option<String>,       // One document per distinct value
+    #[serde(default = "default_rows_per_document")]
+    pub rows_per_document: usize,       // Without `groupBy`
+    #[serde(default)]
+    pub delimiter: Option<char>,        // CSV; `,` (tab for .tsv) by default
+    #[serde(default)]
+    pub sheets: Vec<String>,            // Spreadsheets; every sheet when empty
+}
+
+fn default_rows_per_document() -> usize { 1 }
+
+impl Default for TableMapping {
+    fn default() -> Self {
+        Self {
+            content_columns: Vec::new(),
+            metadata_columns: Vec::new(),
+            id_column: None,
+            title_column: None,
+            group_by: None,
+            rows_per_document: default_rows_per_document(),
+            delimiter: None,
+            sheets: Vec::new(),
+        }
+    }
+}
+
+/// Header and rows of a CSV file or one sheet
+#[derive(Debug, Clone, PartialEq)]
+pub struct Table {
+    pub sheet: Option<String>,
+    pub header: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+}
+
+/// Rows that make one document
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableRecord {
+    pub title: Option<String>,          // Title column, or the group value
+    pub content: String,
+    pub key: Option<String>,            // Id column or group value
+    pub rows: Vec<usize>,               // 1-based, header excluded
+    pub fields: BTreeMap<String, String>,   // Metadata columns of the first row
+}
+
+pub fn is_table_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| TABLE_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Tables of a file: one for CSV/TSV, one per selected sheet of a spreadsheet
+pub fn read_tables(path: &Path, bytes: Vec<u8>, mapping: &TableMapping) -> Result<Vec<Table>, String> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    if extension == "csv" || extension == "tsv" {
+        let delimiter = mapping.delimiter.unwrap_or(if extension == "tsv" { '\t' } else { ',' });
+        if !delimiter.is_ascii() {
+            return Err(format!("delimiter '{}' is not ASCII", delimiter));
+        }
+        return read_csv(&bytes, delimiter as u8).map(|table| vec![table]);
+    }
+
+    let mut workbook = calamine::open_workbook_auto_from_rs(Cursor::new(bytes)).map_err(|e| e.to_string())?;
+    let mut tables = Vec::new();
+    for sheet in workbook.sheet_names() {
+        if !mapping.sheets.is_empty() && !mapping.sheets.contains(&sheet) {
+            continue;
+        }
+        let range = workbook.worksheet_range(&sheet).map_err(|e| format!("sheet {}: {}", sheet, e))?;
+        let mut rows = range.rows().map(|row| row.iter().map(cell_text).collect::<Vec<_>>());
+        let Some(header) = rows.next() else { continue };
+        tables.push(Table { sheet: Some(sheet), header: header_names(header), rows: rows.collect() });
+    }
+    Ok(tables)
+}
+
+fn read_csv(bytes: &[u8], delimiter: u8) -> Result<Table, String> {
+    let bytes = bytes.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(bytes);
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .flexible(true)
+        .has_headers(false)
+        .from_reader(bytes);
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| e.to_string())?;
+        rows.push(record.iter().map(|field| field.trim().to_string()).collect::<Vec<_>>());
+    }
+    if rows.is_empty() {
+        return Ok(Table { sheet: None, header: Vec::new(), rows });
+    }
+    let header = header_names(rows.remove(0));
+    Ok(Table { sheet: None, header, rows })
+}
+
+/// Spreadsheet cell as text; dates without their float encoding
+fn cell_text(cell: &calamine::Data) -> String {
+    match cell {
+        calamine::Data::DateTime(date) => match date.as_datetime() {
+            Some(t) if t.time() == chrono::NaiveTime::MIN => t.format("%Y-%m-%d").to_string(),
+            Some(t) => t.format("%Y-%m-%d %H:%M:%S").to_string(),
+            None => cell.to_string(),
+        },
+        _ => cell.to_string().trim().to_string(),
+    }
+}
+
+/// Header cells as column names; blank and repeated names get a position
+fn header_names(header: Vec<String>) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for (i, name) in header.into_iter().enumerate() {
+        let name = name.trim().to_string();
+        let name = if name.is_empty() || names.contains(&name) { format!("column_{}", i + 1) } else { name };
+        names.push(name);
+    }
+    names
+}
+
+/// Records of a table; errors name columns the mapping uses but the table lacks
+pub fn map_rows(table: &Table, mapping: &TableMapping) -> Result<Vec<TableRecord>, String> {
+    let column = |name: &String| table.header.iter().position(|h| h == name)
+        .ok_or_else(|| format!("no column '{}' (columns: {})", name, table.header.join(", ")));
+    let id = mapping.id_column.as_ref().map(column).transpose()?;
+    let title = mapping.title_column.as_ref().map(column).transpose()?;
+    let group = mapping.group_by.as_ref().map(column).transpose()?;
+    let metadata = mapping.metadata_columns.iter().map(column).collect::<Result<Vec<_>, _>>()?;
+    let content: Vec<usize> = if mapping.content_columns.is_empty() {
+        (0..table.header.len())
+            .filter(|i| ![id, group].contains(&Some(*i)) && !metadata.contains(i))
+            .collect()
+    } else {
+        mapping.content_columns.iter().map(column).collect::<Result<_, _>>()?
+    };
+    fn cell(row: &[String], i: usize) -> &str {
+        row.get(i).map(String::as_str).unwrap_or("")
+    }
+
+    // Row numbers of each document, skipping blank rows
+    let rows: Vec<usize> = (0..table.rows.len()).filter(|&r| table.rows[r].iter().any(|c| !c.is_empty())).collect();
+    let batches: Vec<Vec<usize>> = match group {
+        Some(g) => {
+            let mut groups: Vec<(String, Vec<usize>)> = Vec::new();
+            for r in rows {
+                let value = cell(&table.rows[r], g);
+                match groups.iter_mut().find(|(v, _)| v == value) {
+                    Some((_, members)) => members.push(r),
+                    None => groups.push((value.to_string(), vec![r])),
+                }
+            }
+            groups.into_iter().map(|(_, members)| members).collect()
+        }
+        None => rows.chunks(mapping.rows_per_document.max(1)).map(<[usize]>::to_vec).collect(),
+    };
+
+    let mut records = Vec::new();
+    for batch in batches {
+        let first = &table.rows[batch[0]];
+        let text: Vec<String> = batch.iter()
+            .map(|&r| content.iter()
+                .filter(|&&c| !cell(&table.rows[r], c).is_empty())
+                .map(|&c| format!("{}: {}", table.header[c], cell(&table.rows[r], c)))
+                .collect::<Vec<_>>()
+                .join("\n"))
+            .filter(|row| !row.is_empty())
+            .collect();
+        if text.is_empty() {
+            continue;
+        }
+        let value = |column: Option<usize>| column.map(|c| cell(first, c).to_string()).filter(|v| !v.is_empty());
+        records.push(TableRecord {
+            title: value(title).or_else(|| value(group)),
+            content: text.join("\n\n"),
+            key: value(id).filter(|_| batch.len() == 1).or_else(|| value(group)),
+            rows: batch.iter().map(|r| r + 1).collect(),
+            fields: metadata.iter().map(|&c| (table.header[c].clone(), cell(first, c).to_string())).collect(),
+        });
+    }
+    Ok(records)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FAQ: &str = "\u{feff}id,category,question,answer\n\
+        q1,Billing,How do I pay?,By card\n\
+        q2,Billing,\"Can I get a refund, later?\",Within 30 days\n\
+        ,,,\n\
+        q3,Account,How do I reset my password?,Use the login page\n";
+
+    #[test]
+    fn test_rows_become_records() {
+        let table = read_tables(Path::new("faq.csv"), FAQ.as_bytes().to_vec(), &TableMapping::default()).unwrap().remove(0);
+        assert_eq!(table.header, vec!["id", "category", "question", "answer"]);
+        assert_eq!(table.rows.len(), 4);
+
+        let mapping = TableMapping {
+            content_columns: vec!["question".to_string(), "answer".to_string()],
+            metadata_columns: vec!["category".to_string()],
+            id_column: Some("id".to_string()),
+            title_column: Some("question".to_string()),
+            ..TableMapping::default()
+        };
+        let records = map_rows(&table, &mapping).unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1].content, "question: Can I get a refund, later?\nanswer: Within 30 days");
+        assert_eq!(records[1].title.as_deref(), Some("Can I get a refund, later?"));
+        assert_eq!(records[1].key.as_deref(), Some("q2"));
+        assert_eq!(records[1].fields["category"], "Billing");
+        // The blank line is skipped but keeps its number
+        assert_eq!(records[2].rows, vec![4]);
+
+        let unknown = TableMapping { id_column: Some("sku".to_string()), ..TableMapping::default() };
+        assert!(map_rows(&table, &unknown).unwrap_err().contains("no column 'sku'"));
+    }
+
+    #[test]
+    fn test_grouped_and_batched_rows() {
+        let table = read_tables(Path::new("faq.csv"), FAQ.as_bytes().to_vec(), &TableMapping::default()).unwrap().remove(0);
+
+        let grouped = TableMapping { group_by: Some("category".to_string()), content_columns: vec!["question".to_string()], ..TableMapping::default() };
+        let records = map_rows(&table, &grouped).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].title.as_deref(), Some("Billing"));
+        assert_eq!(records[0].content, "question: How do I pay?\n\nquestion: Can I get a refund, later?");
+        assert_eq!(records[0].rows, vec![1, 2]);
+        assert_eq!(records[0].key.as_deref(), Some("Billing"));
+
+        // Default content columns leave out the id; runs of rows have no single id
+        let batched = TableMapping { rows_per_document: 2, id_column: Some("id".to_string()), ..TableMapping::default() };
+        let records = map_rows(&table, &batched).unwrap();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].content.starts_with("category: Billing\nquestion: How do I pay?"));
+        assert!(records[0].key.is_none());
+        assert_eq!(records[1].rows, vec![4]);
+
+        let tsv = read_tables(Path::new("x.tsv"), b"a\tb\n1\t2\n".to_vec(), &TableMapping::default()).unwrap();
+        assert_eq!(tsv[0].rows, vec![vec!["1".to_string(), "2".to_string()]]);
+        assert!(read_tables(Path::new("x.xlsx"), b"not a workbook".to_vec(), &TableMapping::default()).is_err());
+    }
+}
@@ -0,0 +1,226 @@
+/*!
+ * Audio/Video Transcription
+ *
+ * Turns recordings (meetings, tutorials) into time-coded transcripts through a
+ * `Transcriber`. The bundled one drives the whisper.cpp CLI (`whisper-cli`),
+ * converting the input to 16 kHz mono WAV with `ffmpeg` first so any format
+ * ffmpeg reads works. The parse step keeps each segment's character span and
+ * time range in the document's `transcript` metadata; indexing turns those
+ * into per-chunk time ranges that citations carry.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use tokio::process::Command;
+use tracing::{debug, warn};
+
+use super::super::errors::PipelineError;
+use super::ocr::run_tool;
+
+/// Environment variable overriding the whisper.cpp binary
+pub const WHISPER_BINARY_ENV: &str = "RAG_STUDIO_WHISPER_CLI";
+
+/// Environment variable naming the Whisper GGML model file
+pub const WHISPER_MODEL_ENV: &str = "RAG_STUDIO_WHISPER_MODEL";
+
+/// Environment variable overriding the ffmpeg binary
+pub const FFMPEG_BINARY_ENV: &str = "RAG_STUDIO_FFMPEG";
+
+/// Extensions of audio and video files (added to the parse step's directory filter)
+pub const MEDIA_EXTENSIONS: &[&str] = &["mp3", "wav", "m4a", "flac", "ogg", "opus", "mp4", "mkv", "mov", "webm", "avi"];
+
+/// Timed piece of a transcript
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TranscriptSegment {
+    pub start_ms: u64,
+    pub end_ms: u64,
+    pub text: String,
+}
+
+/// Transcript of one recording
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Transcript {
+    pub language: Option<String>,   // Detected or requested
+    pub segments: Vec<TranscriptSegment>,
+}
+
+/// Speech-to-text backend (whisper.cpp CLI, or a Python worker op in the desktop app)
+#[async_trait]
+pub trait Transcriber: Send + Sync {
+    /// Engine name recorded in document metadata
+    fn name(&self) -> &str;
+
+    /// Transcribe `path`; `language` None auto-detects
+    async fn transcribe(&self, path: &Path, language: Option<&str>) -> Result<Transcript, PipelineError>;
+}
+
+/// True if `path` has an audio or video extension
+pub fn is_media_file(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|ext| MEDIA_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+/// Transcript from whisper.cpp's `--output-json` file
+pub fn parse_whisper_json(json: &str) -> Result<Transcript, PipelineError> {
+    #[derive(Deserialize)]
+    struct Output {
+        #[serde(default)]
+        result: Option<OutputResult>,
+        transcription: Vec<OutputSegment>,
+    }
+    #[derive(Deserialize)]
+    struct OutputResult {
+        language: Option<String>,
+    }
+    #[derive(Deserialize)]
+    struct OutputSegment {
+        offsets: Offsets,
+        text: String,
+    }
+    #[derive(Deserialize)]
+    struct Offsets {
+        from: u64,
+        to: u64,
+    }
+
+    let output: Output = serde_json::from_str(json)?;
+    Ok(Transcript {
+        language: output.result.and_then(|r| r.language),
+        segments: output.transcription.into_iter()
+            .map(|segment| TranscriptSegment {
+                start_ms: segment.offsets.from,
+                end_ms: segment.offsets.to,
+                text: segment.text.trim().to_string(),
+            })
+            .filter(|segment| !segment.text.is_empty())
+            .collect(),
+    })
+}
+
+/// Whisper transcriber configuration
+#[derive(Debug, Clone)]
+pub struct WhisperConfig {
+    pub binary_path: PathBuf,       // whisper.cpp CLI
+    pub model_path: Option<PathBuf>,    // GGML model, e.g. ggml-base.en.bin
+    pub ffmpeg_path: PathBuf,
+    pub threads: Option<u32>,       // whisper.cpp picks when unset
+    pub timeout_secs: u64,          // Per tool invocation; long recordings take a while
+}
+
+impl Default for WhisperConfig {
+    fn default() -> Self {
+        Self {
+            binary_path: std::env::var(WHISPER_BINARY_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("whisper-cli")),
+            model_path: std::env::var(WHISPER_MODEL_ENV).ok().map(PathBuf::from),
+            ffmpeg_path: std::env::var(FFMPEG_BINARY_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("ffmpeg")),
+            threads: None,
+            timeout_secs: 3600,
+        }
+    }
+}
+
+/// Transcription through the whisper.cpp CLI
+pub struct WhisperTranscriber {
+    config: WhisperConfig,
+}
+
+impl WhisperTranscriber {
+    pub fn new(config: WhisperConfig) -> Self {
+        Self { config }
+    }
+
+    /// True when a model file is configured and present and ffmpeg runs
+    pub fn is_available(&self) -> bool {
+        self.config.model_path.as_deref().is_some_and(Path::is_file)
+            && std::process::Command::new(&self.config.ffmpeg_path)
+                .arg("-version")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success())
+    }
+
+    async fn transcribe_in(&self, path: &Path, dir: &Path, language: Option<&str>) -> Result<Transcript, PipelineError> {
+        let model = self.config.model_path.as_deref()
+            .ok_or_else(|| PipelineError::StepFailed(format!("No Whisper model configured (set {})", WHISPER_MODEL_ENV)))?;
+
+        let audio = dir.join("audio.wav");
+        run_tool(Command::new(&self.config.ffmpeg_path)
+            .arg("-nostdin").arg("-y")
+            .arg("-i").arg(path)
+            .args(["-vn", "-ar", "16000", "-ac", "1", "-c:a", "pcm_s16le"])
+            .arg(&audio), self.config.timeout_secs)
+            .await?;
+
+        let output = dir.join("transcript");
+        let mut command = Command::new(&self.config.binary_path);
+        command.arg("-m").arg(model)
+            .arg("-f").arg(&audio)
+            .arg("-l").arg(language.unwrap_or("auto"))
+            .arg("--output-json").arg("--no-prints")
+            .arg("-of").arg(&output);
+        if let Some(threads) = self.config.threads {
+            command.arg("-t").arg(threads.to_string());
+        }
+        run_tool(&mut command, self.config.timeout_secs).await?;
+
+        let mut transcript = parse_whisper_json(&tokio::fs::read_to_string(output.with_extension("json")).await?)?;
+        if transcript.language.is_none() {
+            transcript.language = language.map(str::to_string);
+        }
+        debug!("Transcribed {:?}: {} segments", path, transcript.segments.len());
+        Ok(transcript)
+    }
+}
+
+impl Default for WhisperTranscriber {
+    fn default() -> Self {
+        Self::new(WhisperConfig::default())
+    }
+}
+
+#[async_trait]
+impl Transcriber for WhisperTranscriber {
+    fn name(&self) -> &str {
+        "whisper"
+    }
+
+    async fn transcribe(&self, path: &Path, language: Option<&str>) -> Result<Transcript, PipelineError> {
+        let dir = std::env::temp_dir().join(format!("rag_transcribe_{}", uuid::Uuid::new_v4().simple()));
+        tokio::fs::create_dir_all(&dir).await?;
+        let result = self.transcribe_in(path, &dir, language).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&dir).await {
+            warn!("Failed to remove transcription scratch directory {:?}: {}", dir, e);
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_whisper_json() {
+        let json = r#"{
+            "result": {"language": "en"},
+            "transcription": [
+                {"timestamps": {"from": "00:00:00,000", "to": "00:00:02,500"}, "offsets": {"from": 0, "to": 2500}, "text": " Welcome to the demo."},
+                {"timestamps": {"from": "00:00:02,500", "to": "00:00:03,000"}, "offsets": {"from": 2500, "to": 3000}, "text": " "},
+                {"timestamps": {"from": "00:00:03,000", "to": "00:00:07,000"}, "offsets": {"from": 3000, "to": 7000}, "text": " First, install the CLI."}
+            ]
+        }"#;
+        let transcript = parse_whisper_json(json).unwrap();
+        assert_eq!(transcript.language.as_deref(), Some("en"));
+        assert_eq!(transcript.segments, vec![
+            TranscriptSegment { start_ms: 0, end_ms: 2500, text: "Welcome to the demo.".to_string() },
+            TranscriptSegment { start_ms: 3000, end_ms: 7000, text: "First, install the CLI.".to_string() },
+        ]);
+        assert!(parse_whisper_json("{}").is_err());
+        assert!(is_media_file(Path::new("/recordings/Standup.MP4")));
+        assert!(!is_media_file(Path::new("/docs/notes.md")));
+    }
+}
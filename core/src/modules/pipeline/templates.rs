@@ -0,0 +1,171 @@
+/*!
+ * Pipeline Templates
+ *
+ * Ready-made pipeline specs for common sources, to be cloned and filled in
+ * (site URL, spaces or databases, the vault secret holding the token).
+ */
+
+use serde::{Deserialize, Serialize};
+
+use super::models::*;
+
+/// Pipeline spec with a description of what to fill in
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PipelineTemplate {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub spec: PipelineSpec,
+}
+
+fn step(step_type: ETLStepType, config: serde_json::Value) -> PipelineStepConfig {
+    PipelineStepConfig {
+        step_type,
+        config,
+        resources: None,
+        on_limit: ResourceLimitAction::default(),
+    }
+}
+
+fn template(id: &str, name: &str, description: &str, fetch: serde_json::Value) -> PipelineTemplate {
+    PipelineTemplate {
+        id: id.to_string(),
+        name: name.to_string(),
+        description: description.to_string(),
+        spec: PipelineSpec {
+            id: format!("pipeline_{}", id),
+            name: name.to_string(),
+            kb_id: None,                // Picked when the template is cloned
+            steps: vec![
+                step(ETLStepType::Fetch, fetch),
+                step(ETLStepType::Normalize, serde_json::json!({})),
+            ],
+            resources: PipelineResources::default(),
+            source_roots: Vec::new(),
+        },
+    }
+}
+
+/// Bundled templates, by id
+pub fn pipeline_templates() -> Vec<PipelineTemplate> {
+    vec![
+        template(
+            "confluence_space",
+            "Confluence space",
+            "Pages and text attachments of Confluence spaces, synced incrementally. Set the site URL \
+             (Cloud: https://<site>.atlassian.net/wiki), the space keys, and for Cloud the account email; \
+             store the API token (Cloud) or personal access token (Server/DC) in the vault as confluence.token.",
+            serde_json::json!({
+                "source": "confluence",
+                "url": "https://example.atlassian.net/wiki",
+                "spaces": ["DOCS"],
+                "username": "you@example.com",
+                "tokenSecret": "confluence.token",
+            }),
+        ),
+        template(
+            "notion_database",
+            "Notion database",
+            "Pages of Notion databases shared with an integration, synced incrementally. Set the database \
+             ids (or list pages instead); store the integration secret in the vault as notion.token.",
+            serde_json::json!({
+                "source": "notion",
+                "databases": ["00000000000000000000000000000000"],
+                "tokenSecret": "notion.token",
+            }),
+        ),
+        template(
+            "jira_project",
+            "Jira issues",
+            "Issues of Jira projects with their comments, status and labels, synced incrementally. Set the site \
+             URL, the project keys (optionally extra JQL) and for Cloud the account email; store the API token \
+             (Cloud) or personal access token (Server/DC) in the vault as jira.token.",
+            serde_json::json!({
+                "source": "jira",
+                "url": "https://example.atlassian.net",
+                "projects": ["OPS"],
+                "username": "you@example.com",
+                "tokenSecret": "jira.token",
+            }),
+        ),
+        template(
+            "github_issues",
+            "GitHub issues",
+            "Issues and pull requests of GitHub repositories with their comments, state and labels, synced \
+             incrementally. Set the repositories (owner/name); store a token with read access in the vault as \
+             github.token.",
+            serde_json::json!({
+                "source": "github",
+                "repos": ["owner/name"],
+                "tokenSecret": "github.token",
+            }),
+        ),
+        template(
+            "docs_sitemap",
+            "Documentation site",
+            "Pages of a documentation site listed in its XML sitemap; each run downloads only pages that are \
+             new or whose lastmod (or content) changed. Set the sitemap URL and optionally the URL prefixes \
+             to include or exclude.",
+            serde_json::json!({
+                "source": "sitemap",
+                "url": "https://docs.example.com/sitemap.xml",
+                "include": ["https://docs.example.com/"],
+            }),
+        ),
+        template(
+            "rss_feed",
+            "RSS or Atom feed",
+            "Entries of an RSS or Atom feed (release notes, changelogs, blogs); each run ingests only new or \
+             updated entries. Set the feed URL; enable fetchPages to ingest the linked pages rather than the \
+             entry summaries.",
+            serde_json::json!({
+                "source": "feed",
+                "url": "https://example.com/feed.xml",
+                "fetchPages": false,
+            }),
+        ),
+        template(
+            "s3_bucket",
+            "S3 bucket",
+            "Text documents in an S3 or S3-compatible (MinIO, ...) bucket; each run downloads only objects whose \
+             ETag changed. Set the endpoint, bucket, prefix, region and access key id; store the secret access \
+             key in the vault as s3.secret_key.",
+            serde_json::json!({
+                "source": "s3",
+                "url": "https://s3.us-east-1.amazonaws.com",
+                "bucket": "docs",
+                "prefix": "kb/",
+                "region": "us-east-1",
+                "accessKeyId": "AKIA...",
+                "tokenSecret": "s3.secret_key",
+            }),
+        ),
+    ]
+}
+
+/// Bundled template by id
+pub fn pipeline_template(id: &str) -> Option<PipelineTemplate> {
+    pipeline_templates().into_iter().find(|t| t.id == id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::steps::fetch::FetchStepConfig;
+
+    #[test]
+    fn test_templates_have_valid_fetch_configs() {
+        let templates = pipeline_templates();
+        assert_eq!(templates.len(), 7);
+        for template in &templates {
+            let fetch = &template.spec.steps[0];
+            assert_eq!(fetch.step_type, ETLStepType::Fetch);
+            let config: FetchStepConfig = serde_json::from_value(fetch.config.clone()).unwrap();
+            assert!(config.incremental);
+            // Public feeds and sitemaps need no token
+            assert_eq!(config.token_secret.is_some(), !["feed", "sitemap"].contains(&config.source.as_str()));
+        }
+        assert_eq!(pipeline_template("notion_database").unwrap().spec.steps[0].config["source"], "notion");
+        assert!(pipeline_template("gopher").is_none());
+    }
+}
@@ -0,0 +1,33 @@
+/*!
+ * Scheduled Refresh Domain Errors
+ *
+ * Domain-specific error types for refresh schedules.
+ */
+
+use crate::modules::eval::EvalError;
+use crate::modules::kb::KbError;
+
+/// Schedule Domain Error Types
+#[derive(Debug, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("Knowledge base error: {0}")]
+    KbError(#[from] KbError),
+
+    #[error("Evaluation error: {0}")]
+    EvalError(#[from] EvalError),
+
+    #[error("Invalid cron expression '{0}': {1}")]
+    InvalidCron(String, String),
+
+    #[error("Schedule not found: {0}")]
+    NotFound(String),
+
+    #[error("Schedule {0} is already running")]
+    AlreadyRunning(String),
+
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+
+    #[error("State error: {0}")]
+    StateError(String),
+}
@@ -0,0 +1,16 @@
+/*!
+ * Scheduled Refresh Domain Module
+ *
+ * Cron-driven KB refreshes bound to a tool or KB: the stored pipeline is
+ * replayed over its sources, the new version is evaluated against the golden
+ * set and promoted only when the regression check passes.
+ */
+
+pub mod service;
+pub mod models;
+pub mod errors;
+
+// Re-export public types
+pub use service::RefreshService;
+pub use models::*;
+pub use errors::ScheduleError;
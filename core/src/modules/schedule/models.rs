@@ -0,0 +1,218 @@
+/*!
+ * Scheduled Refresh Domain Models
+ *
+ * Cron expressions, refresh schedule requests and refresh outcomes.
+ */
+
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Datelike, Duration, DurationRound, Timelike, Utc};
+
+use super::errors::ScheduleError;
+use crate::modules::pipeline::PipelineSpec;
+
+/// How often the scheduler looks for due refreshes
+pub const DEFAULT_SCHEDULER_TICK_SECS: u64 = 60;
+
+/// Furthest ahead `next_after` searches before giving up (e.g. "0 0 30 2 *")
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 4;
+
+/// Five-field cron expression (minute hour day-of-month month day-of-week),
+/// evaluated in UTC. Fields take `*`, values, ranges, lists and `/step`;
+/// `@hourly`, `@daily` (`@nightly`, `@midnight`), `@weekly` and `@monthly` are accepted.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,      // Day-of-month and day-of-week combine with OR when both are set
+    weekdays_restricted: bool,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self, ScheduleError> {
+        let invalid = |reason: &str| ScheduleError::InvalidCron(expression.to_string(), reason.to_string());
+
+        let expanded = match expression.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@nightly" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            other => other,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(invalid("expected 5 fields"));
+        }
+
+        let mut weekdays = parse_field(fields[4], 0, 7).map_err(|e| invalid(&e))?;
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);  // 7 is Sunday too
+        }
+
+        Ok(Self {
+            minutes: parse_field(fields[0], 0, 59).map_err(|e| invalid(&e))?,
+            hours: parse_field(fields[1], 0, 23).map_err(|e| invalid(&e))?,
+            days: parse_field(fields[2], 1, 31).map_err(|e| invalid(&e))?,
+            months: parse_field(fields[3], 1, 12).map_err(|e| invalid(&e))?,
+            weekdays,
+            days_restricted: fields[2] != "*",
+            weekdays_restricted: fields[4] != "*",
+        })
+    }
+
+    fn matches_day(&self, time: &DateTime<Utc>) -> bool {
+        let day = self.days & (1 << time.day()) != 0;
+        let weekday = self.weekdays & (1 << time.weekday().num_days_from_sunday()) != 0;
+        match (self.days_restricted, self.weekdays_restricted) {
+            (true, true) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+
+    /// First matching minute strictly after `after`
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut time = after.duration_trunc(Duration::minutes(1)).ok()? + Duration::minutes(1);
+        let limit = after + Duration::days(MAX_LOOKAHEAD_DAYS);
+
+        while time <= limit {
+            if self.months & (1 << time.month()) == 0 || !self.matches_day(&time) {
+                time = time.date_naive().succ_opt()?.and_hms_opt(0, 0, 0)?.and_utc();
+            } else if self.hours & (1 << time.hour()) == 0 {
+                time = time.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << time.minute()) == 0 {
+                time += Duration::minutes(1);
+            } else {
+                return Some(time);
+            }
+        }
+        None
+    }
+}
+
+/// Bitmask of the values one cron field selects
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step.parse().map_err(|_| format!("invalid step '{}'", step))?;
+                if step == 0 {
+                    return Err("step must be positive".to_string());
+                }
+                (range, step)
+            }
+            None => (item, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (parse_value(start, min, max)?, parse_value(end, min, max)?),
+                None => {
+                    let value = parse_value(range, min, max)?;
+                    // "5/15" means every 15 starting at 5
+                    (value, if item.contains('/') { max } else { value })
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("empty range '{}'", range));
+        }
+
+        for value in (start..=end).step_by(step as usize) {
+            mask |= 1 << value;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32) -> Result<u32, String> {
+    let parsed: u32 = value.parse().map_err(|_| format!("invalid value '{}'", value))?;
+    if parsed < min || parsed > max {
+        return Err(format!("{} is outside {}-{}", parsed, min, max));
+    }
+    Ok(parsed)
+}
+
+/// Refresh schedule as created or edited by the user
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshScheduleSpec {
+    pub id: Option<String>,             // None creates a new schedule
+    pub name: String,
+    pub cron_expression: String,
+    pub tool_id: Option<String>,        // Refresh the KB this tool searches
+    pub kb_id: Option<String>,          // Or name the KB directly
+    pub pipeline: PipelineSpec,         // Replayed on every run
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+/// How a refresh ended
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshStatus {
+    /// New version passed eval and is now active
+    Promoted,
+    /// New version was built but kept inactive after a failed or impossible eval
+    EvalRejected,
+    /// No version was built (sources, pipeline or indexing failed)
+    Failed,
+}
+
+/// Result of one scheduled (or manually triggered) refresh
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RefreshOutcome {
+    pub schedule_id: String,
+    pub kb_id: String,
+    pub tool_id: Option<String>,
+    pub status: RefreshStatus,
+    pub run_id: String,
+    pub version: Option<i32>,           // Version built by the run
+    pub eval_run_id: Option<String>,
+    pub message: Option<String>,        // Why the refresh was rejected or failed
+    pub started_at: DateTime<Utc>,
+    pub completed_at: DateTime<Utc>,
+}
+
+impl RefreshOutcome {
+    pub fn is_success(&self) -> bool {
+        self.status == RefreshStatus::Promoted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_cron_next_after() {
+        let at = |d: u32, h: u32, m: u32| Utc.with_ymd_and_hms(2026, 3, d, h, m, 0).unwrap();
+
+        let nightly = CronSchedule::parse("@nightly").unwrap();
+        assert_eq!(nightly.next_after(at(4, 13, 30)), Some(at(5, 0, 0)));
+        // Strictly after, even on a matching minute
+        assert_eq!(nightly.next_after(at(5, 0, 0)), Some(at(6, 0, 0)));
+
+        let quarter_hours = CronSchedule::parse("*/15 9-17 * * 1-5").unwrap();
+        assert_eq!(quarter_hours.next_after(at(4, 9, 1)), Some(at(4, 9, 15)));
+        // 2026-03-06 is a Friday, so 17:45 rolls over to Monday
+        assert_eq!(quarter_hours.next_after(at(6, 17, 45)), Some(at(9, 9, 0)));
+
+        let sundays = CronSchedule::parse("30 2 * * 7").unwrap();
+        assert_eq!(sundays.next_after(at(4, 0, 0)), Some(at(8, 2, 30)));
+
+        assert!(CronSchedule::parse("0 0 30 2 *").unwrap().next_after(at(4, 0, 0)).is_none());
+        assert!(CronSchedule::parse("0 0 * *").is_err());
+        assert!(CronSchedule::parse("60 * * * *").is_err());
+        assert!(CronSchedule::parse("*/0 * * * *").is_err());
+    }
+}
@@ -0,0 +1,391 @@
+/*!
+ * Scheduled Refresh Service
+ *
+ * Keeps refresh schedules in core state and runs them when due: sources are
+ * reloaded, the stored pipeline replayed, its output indexed as a new KB
+ * version, and that version promoted only if the golden-set regression
+ * passes (KBs without golden queries promote ungated). Every outcome (promoted, rejected or failed) is recorded on the
+ * schedule and broadcast to `subscribe` receivers for notification.
+ */
+
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use super::errors::ScheduleError;
+use super::models::*;
+use crate::modules::eval::{EvalError, EvalService};
+use crate::modules::kb::{KbService, KbServiceImpl};
+use crate::modules::pipeline::{FetchCursorStore, PipelineRunner, SourceSandbox, StepData};
+use crate::state::{PipelineRunState, PipelineRunStatus, ScheduleState, StateDelta, StateManager};
+
+/// Buffered outcomes per subscriber before it lags
+const OUTCOME_CHANNEL_CAPACITY: usize = 32;
+
+/// Scheduled KB refresh service
+pub struct RefreshService {
+    state_manager: Arc<StateManager>,
+    kb_service: Arc<KbServiceImpl>,
+    eval_service: Arc<EvalService>,
+    runner: Arc<PipelineRunner>,
+    fetch_cursors: Option<Arc<FetchCursorStore>>,   // Advanced once a run's output is indexed
+    running: Mutex<HashSet<String>>,    // Schedules with a refresh in flight
+    outcomes: broadcast::Sender<RefreshOutcome>,
+}
+
+impl RefreshService {
+    pub fn new(
+        state_manager: Arc<StateManager>,
+        kb_service: Arc<KbServiceImpl>,
+        eval_service: Arc<EvalService>,
+        runner: Arc<PipelineRunner>,
+    ) -> Self {
+        Self {
+            state_manager,
+            kb_service,
+            eval_service,
+            runner,
+            fetch_cursors: None,
+            running: Mutex::new(HashSet::new()),
+            outcomes: broadcast::channel(OUTCOME_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Commit the sync cursors of the runner's fetch steps after each indexed refresh
+    pub fn with_fetch_cursors(mut self, fetch_cursors: Arc<FetchCursorStore>) -> Self {
+        self.fetch_cursors = Some(fetch_cursors);
+        self
+    }
+
+    /// Outcome of every refresh, successful or not
+    pub fn subscribe(&self) -> broadcast::Receiver<RefreshOutcome> {
+        self.outcomes.subscribe()
+    }
+
+    /// Refresh schedules, soonest first
+    pub fn list_schedules(&self) -> Vec<ScheduleState> {
+        let mut schedules: Vec<ScheduleState> = self.state_manager.read_state().schedules.values()
+            .filter(|s| s.pipeline.is_some())
+            .cloned()
+            .collect();
+        schedules.sort_by(|a, b| a.next_run.cmp(&b.next_run).then_with(|| a.name.cmp(&b.name)));
+        schedules
+    }
+
+    /// Create or update a schedule; the KB comes from the request, the tool's
+    /// config or the pipeline, in that order
+    pub fn save_schedule(&self, spec: RefreshScheduleSpec) -> Result<ScheduleState, ScheduleError> {
+        if spec.name.trim().is_empty() {
+            return Err(ScheduleError::ValidationError("Schedule name is required".to_string()));
+        }
+        let cron = CronSchedule::parse(&spec.cron_expression)?;
+
+        let existing = match &spec.id {
+            Some(id) => Some(self.get_schedule(id)?),
+            None => None,
+        };
+
+        let kb_id = {
+            let state = self.state_manager.read_state();
+            let tool_kb = match &spec.tool_id {
+                Some(tool_id) => {
+                    let tool = state.tools.get(tool_id)
+                        .ok_or_else(|| ScheduleError::ValidationError(format!("Tool not found: {}", tool_id)))?;
+                    tool.config.get("kb_id").and_then(|v| v.as_str()).map(str::to_string)
+                }
+                None => None,
+            };
+            let kb_id = spec.kb_id.clone().or(tool_kb).or_else(|| spec.pipeline.kb_id.clone())
+                .ok_or_else(|| ScheduleError::ValidationError("Schedule needs a tool or KB to refresh".to_string()))?;
+            if !state.knowledge_bases.contains_key(&kb_id) {
+                return Err(ScheduleError::ValidationError(format!("Knowledge base not found: {}", kb_id)));
+            }
+            kb_id
+        };
+
+        let mut pipeline = spec.pipeline;
+        pipeline.kb_id = Some(kb_id.clone());
+
+        let schedule = ScheduleState {
+            id: spec.id.unwrap_or_else(|| format!("sched_{}", uuid::Uuid::new_v4().simple())),
+            name: spec.name.trim().to_string(),
+            cron_expression: spec.cron_expression.trim().to_string(),
+            pipeline_id: pipeline.id.clone(),
+            enabled: spec.enabled,
+            last_run: existing.as_ref().and_then(|s| s.last_run),
+            next_run: cron.next_after(Utc::now()),
+            run_count: existing.as_ref().map(|s| s.run_count).unwrap_or(0),
+            kb_id: Some(kb_id),
+            tool_id: spec.tool_id,
+            pipeline: Some(pipeline),
+            sources: spec.sources,
+            last_outcome: existing.and_then(|s| s.last_outcome),
+        };
+
+        self.state_manager
+            .mutate(StateDelta::ScheduleUpsert { schedule: Box::new(schedule.clone()) })
+            .map_err(ScheduleError::StateError)?;
+        info!("Saved refresh schedule {} ({}) for KB {:?}", schedule.id, schedule.cron_expression, schedule.kb_id);
+        Ok(schedule)
+    }
+
+    pub fn remove_schedule(&self, id: &str) -> Result<(), ScheduleError> {
+        self.get_schedule(id)?;
+        self.state_manager
+            .mutate(StateDelta::ScheduleRemove { id: id.to_string() })
+            .map_err(ScheduleError::StateError)
+    }
+
+    fn get_schedule(&self, id: &str) -> Result<ScheduleState, ScheduleError> {
+        self.state_manager.read_state().schedules.get(id)
+            .filter(|s| s.pipeline.is_some())
+            .cloned()
+            .ok_or_else(|| ScheduleError::NotFound(id.to_string()))
+    }
+
+    /// Refresh now, regardless of the schedule's next run
+    pub async fn run_schedule(&self, id: &str) -> Result<RefreshOutcome, ScheduleError> {
+        let schedule = self.get_schedule(id)?;
+        if !self.running.lock().unwrap().insert(id.to_string()) {
+            return Err(ScheduleError::AlreadyRunning(id.to_string()));
+        }
+
+        let outcome = self.refresh(&schedule).await;
+        self.running.lock().unwrap().remove(id);
+
+        let next_run = CronSchedule::parse(&schedule.cron_expression).ok()
+            .and_then(|cron| cron.next_after(Utc::now()));
+        self.state_manager
+            .mutate(StateDelta::ScheduleRecordRun { id: id.to_string(), outcome: outcome.clone(), next_run })
+            .map_err(ScheduleError::StateError)?;
+
+        match outcome.status {
+            RefreshStatus::Promoted => info!("Refresh {} promoted KB {} v{:?}", id, outcome.kb_id, outcome.version),
+            _ => warn!("Refresh {} of KB {} ended {:?}: {}", id, outcome.kb_id, outcome.status,
+                       outcome.message.as_deref().unwrap_or("")),
+        }
+        // No receivers is fine: the outcome is on the schedule too
+        let _ = self.outcomes.send(outcome.clone());
+        Ok(outcome)
+    }
+
+    /// Run every enabled schedule whose next run has passed, one at a time
+    pub async fn run_due(&self, now: DateTime<Utc>) -> Vec<RefreshOutcome> {
+        let due: Vec<String> = self.list_schedules().into_iter()
+            .filter(|s| s.enabled && s.next_run.is_some_and(|next| next <= now))
+            .map(|s| s.id)
+            .collect();
+
+        let mut outcomes = Vec::with_capacity(due.len());
+        for id in due {
+            match self.run_schedule(&id).await {
+                Ok(outcome) => outcomes.push(outcome),
+                Err(ScheduleError::AlreadyRunning(_)) => {}
+                Err(e) => warn!("Scheduled refresh {} could not run: {}", id, e),
+            }
+        }
+        outcomes
+    }
+
+    /// Check for due refreshes every `tick`; abort the handle to stop
+    pub fn spawn_scheduler(self: &Arc<Self>, tick: Duration) -> tokio::task::JoinHandle<()> {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(tick);
+            loop {
+                ticker.tick().await;
+                service.run_due(Utc::now()).await;
+            }
+        })
+    }
+
+    /// Pipeline → index → version → eval → promote; never errors, the outcome says how far it got
+    async fn refresh(&self, schedule: &ScheduleState) -> RefreshOutcome {
+        let run_id = format!("run_{}", uuid::Uuid::new_v4().simple());
+        let mut outcome = RefreshOutcome {
+            schedule_id: schedule.id.clone(),
+            kb_id: schedule.kb_id.clone().unwrap_or_default(),
+            tool_id: schedule.tool_id.clone(),
+            status: RefreshStatus::Failed,
+            run_id: run_id.clone(),
+            version: None,
+            eval_run_id: None,
+            message: None,
+            started_at: Utc::now(),
+            completed_at: Utc::now(),
+        };
+        let Some(spec) = &schedule.pipeline else {
+            outcome.message = Some("Schedule has no pipeline".to_string());
+            return outcome;
+        };
+        let kb_id = outcome.kb_id.clone();
+        info!("Refreshing KB {} from schedule {} (run {})", kb_id, schedule.id, run_id);
+
+        let run = PipelineRunState {
+            id: run_id.clone(),
+            pipeline_id: spec.id.clone(),
+            kb_id: Some(kb_id.clone()),
+            status: PipelineRunStatus::Running,
+            started_at: Some(outcome.started_at),
+            completed_at: None,
+            progress: 0.0,
+            error_message: None,
+            metrics: serde_json::json!({ "schedule_id": schedule.id }),
+        };
+        if let Err(e) = self.state_manager.mutate(StateDelta::RunAdd { run }) {
+            warn!("Failed to record refresh run: {}", e);
+        }
+
+        let built = async {
+            // Sources are read under the pipeline's declared roots, like its parse steps
+            let sandbox = SourceSandbox::new(&spec.source_roots);
+            let mut documents = Vec::with_capacity(schedule.sources.len());
+            for source in &schedule.sources {
+                documents.push(self.kb_service.load_source(&sandbox, source, None).await.map_err(|e| e.to_string())?);
+            }
+
+            // Pre-flight: don't start a run whose output can't be stored
+            self.kb_service.check_ingest_quota(&kb_id, &documents).await.map_err(|e| e.to_string())?;
+            let output = self.runner.run(spec, &run_id, StepData { documents, chunks: Vec::new() }).await;
+            if let Some(error) = &output.error {
+                return Err(format!("Pipeline failed: {}", error));
+            }
+            // Written to the version's own index only; the live index changes on promotion
+            let version = self.kb_service.create_version_from_run(&kb_id, spec, &run_id, &output).await
+                .map_err(|e| e.to_string())?
+                .ok_or_else(|| "Pipeline produced no version".to_string())?;
+            if let Some(fetch_cursors) = &self.fetch_cursors {
+                fetch_cursors.commit_run(&run_id).await.map_err(|e| e.to_string())?;
+            }
+            Ok(version)
+        }.await;
+
+        let (status, error) = match &built {
+            Ok(_) => (PipelineRunStatus::Completed, None),
+            Err(e) => (PipelineRunStatus::Failed, Some(e.clone())),
+        };
+        if let Err(e) = self.state_manager.mutate(StateDelta::RunUpdate {
+            id: run_id.clone(),
+            status,
+            progress: Some(1.0),
+            error,
+            metrics: None,
+        }) {
+            warn!("Failed to record refresh run: {}", e);
+        }
+
+        let version = match built {
+            Ok(version) => version,
+            Err(e) => {
+                outcome.message = Some(e);
+                outcome.completed_at = Utc::now();
+                return outcome;
+            }
+        };
+        outcome.version = Some(version.version);
+
+        // The golden set runs against the new version's index; it only goes live when it
+        // doesn't regress. A KB without golden queries has no gate to pass.
+        let promote = match self.eval_service.run_regression(&kb_id, version.version).await {
+            Ok(eval) if eval.passed => {
+                outcome.eval_run_id = Some(eval.id);
+                true
+            }
+            Ok(eval) => {
+                outcome.eval_run_id = Some(eval.id);
+                outcome.status = RefreshStatus::EvalRejected;
+                outcome.message = Some(format!("Eval regressed against v{:?}", eval.baseline_version));
+                false
+            }
+            Err(EvalError::NoGoldenQueries(_)) => {
+                outcome.message = Some("No golden queries, eval gate skipped".to_string());
+                true
+            }
+            Err(e) => {
+                outcome.status = RefreshStatus::EvalRejected;
+                outcome.message = Some(format!("Eval failed: {}", e));
+                false
+            }
+        };
+        if promote {
+            match self.kb_service.activate_version(&kb_id, version.version).await {
+                Ok(_) => outcome.status = RefreshStatus::Promoted,
+                Err(e) => outcome.message = Some(format!("Promotion failed: {}", e)),
+            }
+        }
+
+        outcome.completed_at = Utc::now();
+        outcome
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+    use crate::modules::eval::EvalConfig;
+    use crate::modules::kb::KbCreateConfig;
+    use crate::modules::pipeline::PipelineSpec;
+    use crate::services::sql::{SqlConfig, SqlService};
+    use crate::services::vector::{VectorDbConfig, VectorDbService};
+
+    #[tokio::test]
+    async fn test_refresh_gates_promotion_on_eval() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql = Arc::new(SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let state_manager = Arc::new(StateManager::new());
+        let kb_service = Arc::new(KbServiceImpl::new_mvp(sql.clone(), vector_service, state_manager.clone()));
+        let eval_service = Arc::new(EvalService::new(sql, kb_service.clone(), EvalConfig::default()));
+        let refresh = RefreshService::new(state_manager, kb_service.clone(), eval_service.clone(), Arc::new(PipelineRunner::new()));
+
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+        let source = temp_dir.path().join("guide.md");
+        std::fs::write(&source, "install the studio").unwrap();
+        let schedule = refresh.save_schedule(RefreshScheduleSpec {
+            id: None,
+            name: "Nightly".to_string(),
+            cron_expression: "0 2 * * *".to_string(),
+            tool_id: None,
+            kb_id: Some(kb_id.clone()),
+            pipeline: PipelineSpec {
+                id: "pipeline_1".to_string(),
+                name: "Ingest".to_string(),
+                kb_id: None,
+                steps: Vec::new(),
+                resources: Default::default(),
+                source_roots: vec![temp_dir.path().to_string_lossy().to_string()],
+            },
+            sources: vec!["guide.md".to_string()],
+            enabled: true,
+        }).unwrap();
+
+        // No golden queries: nothing to gate on
+        let outcome = refresh.run_schedule(&schedule.id).await.unwrap();
+        assert_eq!(outcome.status, RefreshStatus::Promoted);
+        assert!(outcome.eval_run_id.is_none());
+        let hits = kb_service.hybrid_search(&kb_id, "install", 5, None, None).await.unwrap();
+        assert_eq!(hits.len(), 1);
+
+        eval_service.add_golden_query(&kb_id, "install", vec![hits[0].chunk_id.clone()]).await.unwrap();
+        assert!(eval_service.run_regression(&kb_id, 1).await.unwrap().passed);
+
+        // The edit loses the golden chunk, so v2 is evaluated on its own index and kept inactive
+        std::fs::write(&source, "upgrade notes").unwrap();
+        let outcome = refresh.run_schedule(&schedule.id).await.unwrap();
+        assert_eq!(outcome.status, RefreshStatus::EvalRejected);
+        assert_eq!(outcome.version, Some(2));
+        assert_eq!(kb_service.get_active_version(&kb_id).await.unwrap().unwrap().version, 1);
+        assert!(kb_service.hybrid_search(&kb_id, "upgrade", 5, None, None).await.unwrap().is_empty());
+        assert_eq!(kb_service.hybrid_search(&kb_id, "install", 5, None, None).await.unwrap().len(), 1);
+    }
+}
@@ -177,6 +177,137 @@ diesel::table! {
     }
 }
 
+// Immutable KB versions
+diesel::table! {
+    kb_versions (id) {
+        id -> Text,
+        kb_id -> Text,
+        version -> Integer,
+        generation_id -> BigInt,
+        pipeline_run_id -> Nullable<Text>,
+        status -> Text,
+        manifest -> Text,
+        stats -> Text,
+        created_at -> Timestamp,
+        activated_at -> Nullable<Timestamp>,
+    }
+}
+
+// Per-document fingerprints for incremental reindexing
+diesel::table! {
+    document_fingerprints (kb_id, source_path) {
+        kb_id -> Text,
+        source_path -> Text,
+        document_id -> Text,
+        content_hash -> Text,
+        chunk_count -> Integer,
+        indexed_at -> Timestamp,
+    }
+}
+
+// Staged index writes, committed together with document fingerprints
+diesel::table! {
+    index_outbox (id) {
+        id -> Text,
+        kb_id -> Text,
+        document_id -> Text,
+        operation -> Text,
+        generation -> BigInt,
+        chunk_count -> Integer,
+        status -> Text,
+        created_at -> Timestamp,
+        committed_at -> Nullable<Timestamp>,
+    }
+}
+
+// Knowledge graph extracted per document (extract_entities step)
+diesel::table! {
+    graph_entities (kb_id, document_id, entity_id) {
+        kb_id -> Text,
+        document_id -> Text,
+        entity_id -> Text,
+        name -> Text,
+        entity_type -> Text,
+        mentions -> Integer,
+    }
+}
+
+diesel::table! {
+    graph_relations (kb_id, document_id, source_id, target_id, relation) {
+        kb_id -> Text,
+        document_id -> Text,
+        source_id -> Text,
+        target_id -> Text,
+        relation -> Text,
+        weight -> Integer,
+    }
+}
+
+// Golden-dataset regression evaluation
+diesel::table! {
+    eval_golden_queries (id) {
+        id -> Text,
+        kb_id -> Text,
+        question -> Text,
+        relevant_chunk_ids -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    eval_runs (id) {
+        id -> Text,
+        kb_id -> Text,
+        kb_version -> Integer,
+        baseline_version -> Nullable<Integer>,
+        top_k -> Integer,
+        query_count -> Integer,
+        metrics -> Text,
+        deltas -> Nullable<Text>,
+        passed -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+// MCP tool invocation audit log (append-only)
+diesel::table! {
+    mcp_audit_log (id) {
+        id -> Text,
+        tool_name -> Text,
+        arguments -> Text,
+        caller -> Text,
+        latency_ms -> BigInt,
+        result_bytes -> BigInt,
+        success -> Bool,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+// Conversation memory with TTL (conversational RAG)
+diesel::table! {
+    conversation_messages (id) {
+        id -> Text,
+        session_id -> Text,
+        seq -> BigInt,
+        role -> Text,
+        content -> Text,
+        context_summary -> Nullable<Text>,
+        created_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    conversation_summaries (session_id) {
+        session_id -> Text,
+        summary -> Text,
+        summarized_messages -> BigInt,
+        updated_at -> Timestamp,
+        expires_at -> Timestamp,
+    }
+}
+
 // Foreign key relationships
 diesel::joinable!(documents -> knowledge_bases (kb_id));
 diesel::joinable!(document_chunks -> documents (document_id));
@@ -185,6 +316,56 @@ diesel::joinable!(pipeline_runs -> pipelines (pipeline_id));
 diesel::joinable!(pipeline_runs -> knowledge_bases (kb_id));
 diesel::joinable!(schedules -> pipelines (pipeline_id));
 
+// Versioned app state snapshots
+diesel::table! {
+    state_snapshots (version) {
+        version -> BigInt,
+        revision -> BigInt,
+        format -> Integer,
+        state -> Text,
+        created_at -> Timestamp,
+    }
+}
+
+// In-app alert inbox
+diesel::table! {
+    alerts (id) {
+        id -> Text,
+        category -> Text,
+        severity -> Text,
+        title -> Text,
+        message -> Text,
+        source -> Nullable<Text>,
+        metadata -> Text,
+        read -> Bool,
+        created_at -> Timestamp,
+    }
+}
+
+// Incremental sync cursors of pipeline fetch steps
+diesel::table! {
+    fetch_cursors (pipeline_id, cursor_key) {
+        pipeline_id -> Text,
+        cursor_key -> Text,
+        cursor -> Nullable<Text>,
+        pending_cursor -> Nullable<Text>,
+        pending_run_id -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    fetch_seen_items (pipeline_id, cursor_key, item_key) {
+        pipeline_id -> Text,
+        cursor_key -> Text,
+        item_key -> Text,
+        version -> Nullable<Text>,
+        pending_version -> Nullable<Text>,
+        pending_run_id -> Nullable<Text>,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::allow_tables_to_appear_in_same_query!(
     settings,
     knowledge_bases,
@@ -199,6 +380,20 @@ diesel::allow_tables_to_appear_in_same_query!(
     events,
     aggregate_snapshots,
     event_checkpoints,
+    kb_versions,
+    document_fingerprints,
+    index_outbox,
+    graph_entities,
+    graph_relations,
+    eval_golden_queries,
+    eval_runs,
+    mcp_audit_log,
+    conversation_messages,
+    conversation_summaries,
+    state_snapshots,
+    alerts,
+    fetch_cursors,
+    fetch_seen_items,
 );
 
 // ============================================================================
@@ -221,7 +416,7 @@ pub struct VectorSchema {
 }
 
 /// Search result returned from vector database queries
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SearchResult {
     pub chunk_id: String,
     pub document_id: String,
@@ -229,12 +424,21 @@ pub struct SearchResult {
     pub score: f32,
     pub content: String,
     pub snippet: String,
+    #[serde(default)]
+    pub highlights: Vec<Highlight>,    // Query terms matched in `snippet`
     pub metadata: serde_json::Value,
     pub citation: CitationInfo,
 }
 
+/// Query term match in a snippet, as character (not byte) offsets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Highlight {
+    pub start: usize,
+    pub end: usize,                    // Exclusive
+}
+
 /// Citation information for search results
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct CitationInfo {
     pub title: String,
     pub source_path: String,
@@ -242,6 +446,25 @@ pub struct CitationInfo {
     pub version: Option<String>,
     pub anchor: Option<String>,
     pub page_number: Option<u32>,
+    #[serde(default)]
+    pub time_range: Option<TimeRange>,  // Part of a recording a transcript chunk covers
+}
+
+/// Span of an audio or video recording, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TimeRange {
+    pub start_ms: u64,
+    pub end_ms: u64,
+}
+
+impl TimeRange {
+    /// Read from a chunk's `start_ms`/`end_ms` metadata (set for transcript chunks)
+    pub fn from_metadata(metadata: &serde_json::Value) -> Option<Self> {
+        Some(Self {
+            start_ms: metadata.get("start_ms")?.as_u64()?,
+            end_ms: metadata.get("end_ms")?.as_u64()?,
+        })
+    }
 }
 
 /// Search query structure
@@ -0,0 +1,384 @@
+/*!
+ * Alert Service Implementation
+ *
+ * Raises user-facing alerts for things that need attention: pipeline
+ * failures, disk quota warnings, finished model downloads, worker crashes
+ * and eval regressions. Each category is raised at the severity configured
+ * in settings (or not at all when set to `off`). Alerts are kept in an
+ * in-app inbox in the app database and broadcast to `subscribe` receivers,
+ * which the Manager turns into desktop notifications.
+ */
+
+use std::sync::{Arc, RwLock};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+use super::settings::AlertSettings;
+use super::sql::{SqlError, SqlService};
+use crate::schemas::schema::alerts;
+
+/// Alerts returned by `list` when no limit is given
+pub const DEFAULT_ALERT_LIMIT: usize = 100;
+
+/// Buffered alerts per subscriber before it lags
+const ALERT_CHANNEL_CAPACITY: usize = 64;
+
+/// Alert Service Error Types
+#[derive(Debug, Error)]
+pub enum AlertError {
+    #[error("SQL service error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Alert not found: {0}")]
+    NotFound(String),
+
+    #[error("Corrupt alert record: {0}")]
+    CorruptRecord(String),
+}
+
+/// How loudly a category is raised; `Off` silences it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Off,
+    Info,
+    Warning,
+    Critical,
+}
+
+impl AlertSeverity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertSeverity::Off => "off",
+            AlertSeverity::Info => "info",
+            AlertSeverity::Warning => "warning",
+            AlertSeverity::Critical => "critical",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "off" => Some(AlertSeverity::Off),
+            "info" => Some(AlertSeverity::Info),
+            "warning" => Some(AlertSeverity::Warning),
+            "critical" => Some(AlertSeverity::Critical),
+            _ => None,
+        }
+    }
+}
+
+/// What an alert is about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertCategory {
+    PipelineFailure,
+    DiskQuota,
+    ModelDownload,
+    WorkerCrash,
+    EvalRegression,
+}
+
+impl AlertCategory {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertCategory::PipelineFailure => "pipeline_failure",
+            AlertCategory::DiskQuota => "disk_quota",
+            AlertCategory::ModelDownload => "model_download",
+            AlertCategory::WorkerCrash => "worker_crash",
+            AlertCategory::EvalRegression => "eval_regression",
+        }
+    }
+
+    fn parse(value: &str) -> Option<Self> {
+        match value {
+            "pipeline_failure" => Some(AlertCategory::PipelineFailure),
+            "disk_quota" => Some(AlertCategory::DiskQuota),
+            "model_download" => Some(AlertCategory::ModelDownload),
+            "worker_crash" => Some(AlertCategory::WorkerCrash),
+            "eval_regression" => Some(AlertCategory::EvalRegression),
+            _ => None,
+        }
+    }
+
+    /// Severity configured for this category
+    pub fn severity(&self, settings: &AlertSettings) -> AlertSeverity {
+        match self {
+            AlertCategory::PipelineFailure => settings.pipeline_failure,
+            AlertCategory::DiskQuota => settings.disk_quota,
+            AlertCategory::ModelDownload => settings.model_download,
+            AlertCategory::WorkerCrash => settings.worker_crash,
+            AlertCategory::EvalRegression => settings.eval_regression,
+        }
+    }
+}
+
+/// An alert in the inbox
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub id: String,
+    pub category: AlertCategory,
+    pub severity: AlertSeverity,
+    pub title: String,
+    pub message: String,
+    pub source: Option<String>,         // What raised it, e.g. a KB or model id
+    pub metadata: serde_json::Value,
+    pub read: bool,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Inbox query
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct AlertFilter {
+    pub unread_only: bool,
+    pub category: Option<AlertCategory>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable, Insertable)]
+#[diesel(table_name = alerts)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct AlertRow {
+    id: String,
+    category: String,
+    severity: String,
+    title: String,
+    message: String,
+    source: Option<String>,
+    metadata: String,
+    read: bool,
+    created_at: NaiveDateTime,
+}
+
+impl AlertRow {
+    fn from_model(alert: &Alert) -> Result<Self, AlertError> {
+        Ok(Self {
+            id: alert.id.clone(),
+            category: alert.category.as_str().to_string(),
+            severity: alert.severity.as_str().to_string(),
+            title: alert.title.clone(),
+            message: alert.message.clone(),
+            source: alert.source.clone(),
+            metadata: serde_json::to_string(&alert.metadata)?,
+            read: alert.read,
+            created_at: alert.created_at.naive_utc(),
+        })
+    }
+
+    fn into_model(self) -> Result<Alert, AlertError> {
+        Ok(Alert {
+            category: AlertCategory::parse(&self.category)
+                .ok_or_else(|| AlertError::CorruptRecord(format!("unknown category {}", self.category)))?,
+            severity: AlertSeverity::parse(&self.severity)
+                .ok_or_else(|| AlertError::CorruptRecord(format!("unknown severity {}", self.severity)))?,
+            id: self.id,
+            title: self.title,
+            message: self.message,
+            source: self.source,
+            metadata: serde_json::from_str(&self.metadata)?,
+            read: self.read,
+            created_at: self.created_at.and_utc(),
+        })
+    }
+}
+
+/// Alert inbox and notification fan-out
+pub struct AlertService {
+    sql_service: Arc<SqlService>,
+    settings: RwLock<AlertSettings>,
+    alerts: broadcast::Sender<Alert>,
+}
+
+impl AlertService {
+    pub fn new(sql_service: Arc<SqlService>, settings: AlertSettings) -> Self {
+        Self {
+            sql_service,
+            settings: RwLock::new(settings),
+            alerts: broadcast::channel(ALERT_CHANNEL_CAPACITY).0,
+        }
+    }
+
+    /// Apply new per-category severities (hot reload)
+    pub fn set_settings(&self, settings: AlertSettings) {
+        *self.settings.write().unwrap() = settings;
+    }
+
+    pub fn settings(&self) -> AlertSettings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Every alert as it is raised
+    pub fn subscribe(&self) -> broadcast::Receiver<Alert> {
+        self.alerts.subscribe()
+    }
+
+    /// Whether an alert should also go out as a desktop notification
+    pub fn notifies_desktop(&self, alert: &Alert) -> bool {
+        let threshold = self.settings.read().unwrap().desktop_min_severity;
+        threshold != AlertSeverity::Off && alert.severity >= threshold
+    }
+
+    /// Store and broadcast an alert at its category's severity; None when the category is off
+    pub async fn raise(
+        &self,
+        category: AlertCategory,
+        title: &str,
+        message: &str,
+        source: Option<&str>,
+        metadata: serde_json::Value,
+    ) -> Result<Option<Alert>, AlertError> {
+        let severity = category.severity(&self.settings.read().unwrap());
+        if severity == AlertSeverity::Off {
+            return Ok(None);
+        }
+
+        let alert = Alert {
+            id: format!("alert_{}", uuid::Uuid::new_v4().simple()),
+            category,
+            severity,
+            title: title.to_string(),
+            message: message.to_string(),
+            source: source.map(str::to_string),
+            metadata,
+            read: false,
+            created_at: Utc::now(),
+        };
+        let row = AlertRow::from_model(&alert)?;
+        self.sql_service.with_app_transaction(|conn| {
+            diesel::insert_into(alerts::table)
+                .values(&row)
+                .execute(conn)?;
+            Ok(())
+        }).await?;
+
+        info!("Alert raised ({}, {}): {}", alert.category.as_str(), alert.severity.as_str(), alert.title);
+        // No receivers is fine: the alert is in the inbox
+        let _ = self.alerts.send(alert.clone());
+        Ok(Some(alert))
+    }
+
+    /// Like `raise`, for callers that shouldn't fail because an alert couldn't be stored
+    pub async fn notify(
+        &self,
+        category: AlertCategory,
+        title: &str,
+        message: &str,
+        source: Option<&str>,
+        metadata: serde_json::Value,
+    ) {
+        if let Err(e) = self.raise(category, title, message, source, metadata).await {
+            warn!("Failed to raise {} alert '{}': {}", category.as_str(), title, e);
+        }
+    }
+
+    /// Inbox entries matching the filter, newest first
+    pub async fn list(&self, filter: &AlertFilter) -> Result<Vec<Alert>, AlertError> {
+        let filter = filter.clone();
+        let rows = self.sql_service.with_app_transaction(move |conn| {
+            let mut query = alerts::table.into_boxed();
+            if filter.unread_only {
+                query = query.filter(alerts::read.eq(false));
+            }
+            if let Some(category) = filter.category {
+                query = query.filter(alerts::category.eq(category.as_str()));
+            }
+            Ok(query
+                .order(alerts::created_at.desc())
+                .limit(filter.limit.unwrap_or(DEFAULT_ALERT_LIMIT) as i64)
+                .select(AlertRow::as_select())
+                .load(conn)?)
+        }).await?;
+
+        rows.into_iter().map(AlertRow::into_model).collect()
+    }
+
+    pub async fn unread_count(&self) -> Result<u64, AlertError> {
+        let count: i64 = self.sql_service.with_app_transaction(|conn| {
+            Ok(alerts::table
+                .filter(alerts::read.eq(false))
+                .count()
+                .get_result(conn)?)
+        }).await?;
+        Ok(count.max(0) as u64)
+    }
+
+    /// Mark one alert read, or all of them when `id` is None
+    pub async fn mark_read(&self, id: Option<&str>) -> Result<(), AlertError> {
+        let id = id.map(str::to_string);
+        let updated = self.sql_service.with_app_transaction({
+            let id = id.clone();
+            move |conn| {
+                let updated = match id {
+                    Some(id) => diesel::update(alerts::table.filter(alerts::id.eq(id)))
+                        .set(alerts::read.eq(true))
+                        .execute(conn)?,
+                    None => diesel::update(alerts::table.filter(alerts::read.eq(false)))
+                        .set(alerts::read.eq(true))
+                        .execute(conn)?,
+                };
+                Ok(updated)
+            }
+        }).await?;
+
+        match id {
+            Some(id) if updated == 0 => Err(AlertError::NotFound(id)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Remove read alerts older than `before`; returns how many were removed
+    pub async fn prune_read(&self, before: DateTime<Utc>) -> Result<usize, AlertError> {
+        let removed = self.sql_service.with_app_transaction(move |conn| {
+            Ok(diesel::delete(alerts::table
+                .filter(alerts::read.eq(true))
+                .filter(alerts::created_at.lt(before.naive_utc())))
+                .execute(conn)?)
+        }).await?;
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sql::SqlConfig;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_raise_follows_category_severity() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let service = AlertService::new(Arc::new(sql_service), AlertSettings::default());
+        let mut stream = service.subscribe();
+
+        let raised = service
+            .raise(AlertCategory::PipelineFailure, "Refresh failed", "Parse step failed", Some("kb_docs"), serde_json::json!({}))
+            .await.unwrap().unwrap();
+        assert_eq!(raised.severity, AlertSeverity::Critical);
+        assert!(service.notifies_desktop(&raised));
+        assert_eq!(stream.try_recv().unwrap().id, raised.id);
+
+        // Silenced categories are neither stored nor broadcast
+        service.set_settings(AlertSettings { model_download: AlertSeverity::Off, ..AlertSettings::default() });
+        let silenced = service
+            .raise(AlertCategory::ModelDownload, "Model ready", "bge-small installed", None, serde_json::json!({}))
+            .await.unwrap();
+        assert!(silenced.is_none());
+        assert!(stream.try_recv().is_err());
+
+        assert_eq!(service.unread_count().await.unwrap(), 1);
+        service.mark_read(Some(&raised.id)).await.unwrap();
+        assert!(service.list(&AlertFilter { unread_only: true, ..Default::default() }).await.unwrap().is_empty());
+        assert!(matches!(service.mark_read(Some("missing")).await, Err(AlertError::NotFound(_))));
+
+        assert_eq!(service.prune_read(Utc::now() + chrono::Duration::seconds(1)).await.unwrap(), 1);
+        assert!(service.list(&AlertFilter::default()).await.unwrap().is_empty());
+    }
+}
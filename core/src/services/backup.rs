@@ -0,0 +1,427 @@
+/*!
+ * Full-App Backup Archives
+ *
+ * Packs a project into one gzipped tarball for moving RAG Studio to another
+ * machine: settings, a consistent copy of the SQL databases (tools, flows,
+ * prompts and KB metadata live there), vector data with its generations, the
+ * blob store, KB packs and, optionally, models. A manifest goes first in the
+ * archive so a restore can check compatibility before unpacking anything.
+ *
+ * Writing and extracting are blocking; callers run them on a blocking thread
+ * and forward `BackupProgress` to the UI.
+ */
+
+use std::collections::HashMap;
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use super::sql::schema_version;
+
+/// Layout version of backup archives; bumped on incompatible changes
+pub const ARCHIVE_FORMAT_VERSION: u32 = 1;
+
+/// First entry of every archive
+pub const ARCHIVE_MANIFEST_FILE: &str = "manifest.json";
+
+/// Extension of backup archives (a gzipped tarball)
+pub const BACKUP_ARCHIVE_EXTENSION: &str = "ragbackup";
+
+/// Progress is reported at least this often while copying large files
+const PROGRESS_STEP_BYTES: u64 = 8 * 1024 * 1024;
+
+/// Backup Archive Error Types
+#[derive(Debug, Error)]
+pub enum BackupError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed manifest: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid backup archive: {0}")]
+    InvalidArchive(String),
+
+    #[error("Incompatible backup: {0}")]
+    Incompatible(String),
+}
+
+/// Part of a project stored in an archive, each under its own top-level directory
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArchiveSection {
+    Settings,
+    Databases,
+    Vectors,
+    Blobs,      // Deduplicated KB version and pipeline run content
+    Packs,
+    Models,     // Optional; shared by every project
+}
+
+impl ArchiveSection {
+    pub fn dir_name(self) -> &'static str {
+        match self {
+            ArchiveSection::Settings => "settings",
+            ArchiveSection::Databases => "databases",
+            ArchiveSection::Vectors => "vectors",
+            ArchiveSection::Blobs => "blobs",
+            ArchiveSection::Packs => "packs",
+            ArchiveSection::Models => "models",
+        }
+    }
+
+    fn from_dir_name(name: &str) -> Option<Self> {
+        [Self::Settings, Self::Databases, Self::Vectors, Self::Blobs, Self::Packs, Self::Models]
+            .into_iter()
+            .find(|section| section.dir_name() == name)
+    }
+}
+
+/// A file or directory to archive as a section
+#[derive(Debug, Clone)]
+pub struct ArchiveSource {
+    pub section: ArchiveSection,
+    pub path: PathBuf,
+}
+
+/// Files and bytes of one section
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SectionSummary {
+    pub section: ArchiveSection,
+    pub files: usize,
+    pub size_bytes: u64,
+}
+
+/// What an archive holds and which app made it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchiveManifest {
+    pub format_version: u32,
+    pub app_version: String,
+    pub schema_version: String,     // Newest app migration of the archived databases
+    pub project_name: String,
+    pub created_at: DateTime<Utc>,
+    pub sections: Vec<SectionSummary>,
+}
+
+impl ArchiveManifest {
+    pub fn total_bytes(&self) -> u64 {
+        self.sections.iter().map(|s| s.size_bytes).sum()
+    }
+
+    pub fn has(&self, section: ArchiveSection) -> bool {
+        self.sections.iter().any(|s| s.section == section && s.files > 0)
+    }
+
+    /// Archives from older apps restore (their databases are migrated
+    /// afterwards); archives from newer apps are refused
+    pub fn check_compatible(&self) -> Result<(), BackupError> {
+        if self.format_version > ARCHIVE_FORMAT_VERSION {
+            return Err(BackupError::Incompatible(format!(
+                "archive format {} needs a newer RAG Studio (this is {}, format {})",
+                self.format_version, env!("CARGO_PKG_VERSION"), ARCHIVE_FORMAT_VERSION
+            )));
+        }
+        let supported = schema_version();
+        if self.schema_version > supported {
+            return Err(BackupError::Incompatible(format!(
+                "database schema {} was made by RAG Studio {}; this version supports up to {}",
+                self.schema_version, self.app_version, supported
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Bytes copied so far; `section` and `current_file` are what's being copied now
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupProgress {
+    pub section: Option<ArchiveSection>,
+    pub current_file: Option<String>,
+    pub processed_bytes: u64,
+    pub total_bytes: u64,
+}
+
+/// Write `sources` into a new archive at `dest`. The archive is written
+/// beside `dest` and renamed into place, so a failed backup leaves nothing behind.
+pub fn write_archive(
+    dest: &Path,
+    project_name: &str,
+    sources: &[ArchiveSource],
+    progress: &mut dyn FnMut(BackupProgress),
+) -> Result<ArchiveManifest, BackupError> {
+    if dest.exists() {
+        return Err(BackupError::InvalidArchive(format!("{:?} already exists", dest)));
+    }
+
+    // Sizes first, so progress has a total
+    let mut files: Vec<(ArchiveSection, PathBuf, String, u64)> = Vec::new();
+    let mut sections: Vec<SectionSummary> = Vec::new();
+    for source in sources {
+        let mut summary = SectionSummary { section: source.section, files: 0, size_bytes: 0 };
+        for (path, relative) in list_files(&source.path)? {
+            let size = std::fs::metadata(&path)?.len();
+            summary.files += 1;
+            summary.size_bytes += size;
+            let name = format!("{}/{}", source.section.dir_name(), relative);
+            files.push((source.section, path, name, size));
+        }
+        match sections.iter_mut().find(|s| s.section == source.section) {
+            Some(existing) => {
+                existing.files += summary.files;
+                existing.size_bytes += summary.size_bytes;
+            }
+            None => sections.push(summary),
+        }
+    }
+
+    let manifest = ArchiveManifest {
+        format_version: ARCHIVE_FORMAT_VERSION,
+        app_version: env!("CARGO_PKG_VERSION").to_string(),
+        schema_version: schema_version(),
+        project_name: project_name.to_string(),
+        created_at: Utc::now(),
+        sections,
+    };
+
+    if let Some(parent) = dest.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let part = dest.with_extension("part");
+    let result = (|| -> Result<(), BackupError> {
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(&part)?, flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+
+        let manifest_bytes = serde_json::to_vec_pretty(&manifest)?;
+        let mut header = tar::Header::new_gnu();
+        header.set_size(manifest_bytes.len() as u64);
+        header.set_mode(0o644);
+        header.set_mtime(manifest.created_at.timestamp().max(0) as u64);
+        header.set_cksum();
+        builder.append_data(&mut header, ARCHIVE_MANIFEST_FILE, manifest_bytes.as_slice())?;
+
+        let total_bytes = manifest.total_bytes();
+        let mut processed_bytes = 0;
+        for (section, path, name, size) in &files {
+            let mut header = tar::Header::new_gnu();
+            header.set_metadata(&std::fs::metadata(path)?);
+            header.set_size(*size);
+            header.set_cksum();
+            let reader = ProgressReader {
+                inner: std::fs::File::open(path)?.take(*size),
+                reported: processed_bytes,
+                processed: &mut processed_bytes,
+                report: &mut |processed| progress(BackupProgress {
+                    section: Some(*section),
+                    current_file: Some(name.clone()),
+                    processed_bytes: processed,
+                    total_bytes,
+                }),
+            };
+            builder.append_data(&mut header, name, reader)?;
+        }
+        builder.into_inner()?.finish()?;
+        Ok(())
+    })();
+    if let Err(e) = result {
+        let _ = std::fs::remove_file(&part);
+        return Err(e);
+    }
+    std::fs::rename(&part, dest)?;
+
+    progress(BackupProgress {
+        section: None,
+        current_file: None,
+        processed_bytes: manifest.total_bytes(),
+        total_bytes: manifest.total_bytes(),
+    });
+    info!("Backup archive written to {:?} ({} bytes)", dest, manifest.total_bytes());
+    Ok(manifest)
+}
+
+/// Manifest of an archive, without unpacking anything else
+pub fn read_archive_manifest(path: &Path) -> Result<ArchiveManifest, BackupError> {
+    let mut archive = open_archive(path)?;
+    let mut entries = archive.entries()?;
+    match entries.next() {
+        Some(entry) => {
+            let mut entry = entry?;
+            if entry.path()?.as_ref() != Path::new(ARCHIVE_MANIFEST_FILE) {
+                return Err(BackupError::InvalidArchive(format!("{:?} does not start with {}", path, ARCHIVE_MANIFEST_FILE)));
+            }
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            Ok(serde_json::from_slice(&bytes)?)
+        }
+        None => Err(BackupError::InvalidArchive(format!("{:?} is empty", path))),
+    }
+}
+
+/// Unpack the sections listed in `targets`, each into its own directory;
+/// other sections are skipped. Checks compatibility before writing anything.
+/// Models already present (same name and size) are kept, since they're shared.
+pub fn extract_archive(
+    path: &Path,
+    targets: &HashMap<ArchiveSection, PathBuf>,
+    progress: &mut dyn FnMut(BackupProgress),
+) -> Result<ArchiveManifest, BackupError> {
+    let manifest = read_archive_manifest(path)?;
+    manifest.check_compatible()?;
+    let total_bytes: u64 = manifest.sections.iter()
+        .filter(|s| targets.contains_key(&s.section))
+        .map(|s| s.size_bytes)
+        .sum();
+
+    let mut archive = open_archive(path)?;
+    let mut processed_bytes = 0;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.into_owned();
+        if name == Path::new(ARCHIVE_MANIFEST_FILE) || !entry.header().entry_type().is_file() {
+            continue;
+        }
+
+        let mut components = name.components();
+        let section = match components.next() {
+            Some(Component::Normal(dir)) => ArchiveSection::from_dir_name(&dir.to_string_lossy()),
+            _ => None,
+        }.ok_or_else(|| BackupError::InvalidArchive(format!("unexpected entry {:?}", name)))?;
+        let relative = components.as_path();
+        if relative.as_os_str().is_empty() || relative.components().any(|c| !matches!(c, Component::Normal(_))) {
+            return Err(BackupError::InvalidArchive(format!("unsafe entry {:?}", name)));
+        }
+        let Some(target_dir) = targets.get(&section) else { continue };
+        let target = target_dir.join(relative);
+
+        let size = entry.size();
+        let keep_existing = section == ArchiveSection::Models
+            && std::fs::metadata(&target).is_ok_and(|m| m.len() == size);
+        if keep_existing {
+            processed_bytes += size;
+            continue;
+        }
+
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let current_file = name.to_string_lossy().to_string();
+        let mut reader = ProgressReader {
+            inner: &mut entry,
+            reported: processed_bytes,
+            processed: &mut processed_bytes,
+            report: &mut |processed| progress(BackupProgress {
+                section: Some(section),
+                current_file: Some(current_file.clone()),
+                processed_bytes: processed,
+                total_bytes,
+            }),
+        };
+        std::io::copy(&mut reader, &mut std::fs::File::create(&target)?)?;
+    }
+
+    progress(BackupProgress { section: None, current_file: None, processed_bytes: total_bytes, total_bytes });
+    info!("Backup archive {:?} extracted", path);
+    Ok(manifest)
+}
+
+/// Files under `path` (or `path` itself) with their `/`-separated paths
+/// relative to it, sorted; a missing path is an empty section
+fn list_files(path: &Path) -> Result<Vec<(PathBuf, String)>, BackupError> {
+    if path.is_file() {
+        let name = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+        return Ok(vec![(path.to_path_buf(), name)]);
+    }
+    let mut files = Vec::new();
+    if !path.is_dir() {
+        return Ok(files);
+    }
+    let mut pending = vec![path.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        for entry in std::fs::read_dir(&dir)? {
+            let entry = entry?;
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                pending.push(entry.path());
+            } else if file_type.is_file() {
+                let relative = entry.path().strip_prefix(path).unwrap_or(&entry.path()).to_path_buf();
+                let relative = relative.components()
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect::<Vec<_>>()
+                    .join("/");
+                files.push((entry.path(), relative));
+            }
+        }
+    }
+    files.sort_by(|a, b| a.1.cmp(&b.1));
+    Ok(files)
+}
+
+fn open_archive(path: &Path) -> Result<tar::Archive<flate2::read::GzDecoder<std::fs::File>>, BackupError> {
+    Ok(tar::Archive::new(flate2::read::GzDecoder::new(std::fs::File::open(path)?)))
+}
+
+/// Counts bytes read and reports every `PROGRESS_STEP_BYTES`
+struct ProgressReader<'a, R> {
+    inner: R,
+    reported: u64,
+    processed: &'a mut u64,
+    report: &'a mut dyn FnMut(u64),
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let read = self.inner.read(buf)?;
+        *self.processed += read as u64;
+        if read == 0 || *self.processed - self.reported >= PROGRESS_STEP_BYTES {
+            self.reported = *self.processed;
+            (self.report)(*self.processed);
+        }
+        Ok(read)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_archive_round_trip_and_compatibility() {
+        let temp_dir = TempDir::new().unwrap();
+        let project = temp_dir.path().join("project");
+        std::fs::create_dir_all(project.join("vector_db/gen_2")).unwrap();
+        std::fs::write(project.join("rag_studio.settings.json"), b"{}").unwrap();
+        std::fs::write(project.join("vector_db/gen_2/data.lance"), vec![7u8; 4096]).unwrap();
+        std::fs::write(project.join("vector_db/generations.json"), b"[]").unwrap();
+
+        let archive = temp_dir.path().join("project.ragbackup");
+        let sources = vec![
+            ArchiveSource { section: ArchiveSection::Settings, path: project.join("rag_studio.settings.json") },
+            ArchiveSource { section: ArchiveSection::Vectors, path: project.join("vector_db") },
+            ArchiveSource { section: ArchiveSection::Packs, path: project.join("packs") },
+        ];
+        let mut reports = Vec::new();
+        let manifest = write_archive(&archive, "Client A", &sources, &mut |p| reports.push(p)).unwrap();
+        assert_eq!(manifest.total_bytes(), 2 + 4096 + 2);
+        assert!(!manifest.has(ArchiveSection::Packs));
+        assert_eq!(reports.last().unwrap().processed_bytes, manifest.total_bytes());
+        assert_eq!(read_archive_manifest(&archive).unwrap().project_name, "Client A");
+
+        // Only the requested sections come back
+        let restored = temp_dir.path().join("restored");
+        let targets = HashMap::from([(ArchiveSection::Vectors, restored.join("vectors"))]);
+        extract_archive(&archive, &targets, &mut |_| {}).unwrap();
+        assert_eq!(std::fs::read(restored.join("vectors/gen_2/data.lance")).unwrap(), vec![7u8; 4096]);
+        assert!(!restored.join("settings").exists());
+
+        // Archives from a newer app are refused
+        let mut newer = manifest.clone();
+        newer.format_version = ARCHIVE_FORMAT_VERSION + 1;
+        assert!(matches!(newer.check_compatible(), Err(BackupError::Incompatible(_))));
+        newer.format_version = ARCHIVE_FORMAT_VERSION;
+        newer.schema_version = "9999-01-01-000000_future".to_string();
+        assert!(matches!(newer.check_compatible(), Err(BackupError::Incompatible(_))));
+    }
+}
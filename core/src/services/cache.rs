@@ -0,0 +1,801 @@
+/*!
+ * Cache Service Implementation
+ *
+ * Two-tier cache: moka in memory over a redb file on disk. Writes go through
+ * to both tiers, disk hits are promoted to memory, and one size budget covers
+ * both. Entries carry tags (e.g. `kb:<id>`) so a KB update can drop everything
+ * derived from it. `CachedEmbeddingBackend` puts the cache in front of an
+ * `EmbeddingBackend`: vectors are keyed by sha256(content) and model id, so
+ * unchanged documents and repeated queries skip inference. The semantic
+ * tier keeps recent (query embedding, value) pairs in memory and serves a
+ * lookup whose embedding is within `semantic_threshold` cosine similarity
+ * of a stored one; tags and TTLs apply as on the other tiers. With a
+ * content cipher set, values in the disk tier are sealed (keys, tags and
+ * expiry stay readable for invalidation and eviction). MVP: disk
+ * eviction scans for the least recently written or promoted entries;
+ * upgrade path to a disk-side LRU index.
+ */
+
+use std::collections::VecDeque;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use redb::{Database, ReadableTable, ReadableTableMetadata, TableDefinition};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::encryption::{is_sealed_text, ContentCipher, EncryptionError};
+use super::model::{EmbeddingBackend, LoadedModel, ModelError};
+use super::storage::sha256_hex;
+
+/// Disk tier: cache key -> serialized `StoredEntry`
+const ENTRIES: TableDefinition<&str, &[u8]> = TableDefinition::new("entries");
+
+/// Disk eviction stops once usage is back under this share of the disk budget
+const EVICTION_LOW_WATERMARK: f64 = 0.9;
+
+/// Embeddings depend only on content and model, so they are kept long
+const EMBEDDING_TTL: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Cache Service Error Types
+#[derive(Debug, Error)]
+pub enum CacheError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Disk cache error: {0}")]
+    DiskError(Box<redb::Error>),
+
+    #[error("Cache full: {0}")]
+    CacheFull(String),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+}
+
+impl From<redb::Error> for CacheError {
+    fn from(error: redb::Error) -> Self {
+        CacheError::DiskError(Box::new(error))
+    }
+}
+
+/// Cache configuration
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub path: PathBuf,              // redb file for the disk tier
+    pub size_budget_bytes: u64,     // Both tiers together
+    pub memory_bytes: u64,          // Memory tier share of the budget
+    pub default_ttl: Duration,
+    pub semantic_entries: usize,    // Query embeddings kept by the semantic tier
+    pub semantic_threshold: f32,    // Cosine similarity a semantic hit needs
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        Self {
+            path: PathBuf::from("./cache/rag_cache.redb"),
+            size_budget_bytes: 256 * 1024 * 1024,
+            memory_bytes: 32 * 1024 * 1024,
+            default_ttl: Duration::from_secs(60 * 60),
+            semantic_entries: 512,
+            semantic_threshold: 0.95,
+        }
+    }
+}
+
+impl CacheConfig {
+    /// Test configuration with an isolated cache file
+    pub fn test_config(data_dir: &Path) -> Self {
+        Self {
+            path: data_dir.join("cache.redb"),
+            size_budget_bytes: 1024 * 1024,
+            memory_bytes: 64 * 1024,
+            default_ttl: Duration::from_secs(60),
+            semantic_entries: 4,
+            semantic_threshold: 0.95,
+        }
+    }
+
+    fn disk_budget_bytes(&self) -> u64 {
+        self.size_budget_bytes.saturating_sub(self.memory_bytes)
+    }
+}
+
+/// Cache statistics across both tiers
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheStats {
+    pub memory_entries: u64,
+    pub memory_bytes: u64,
+    pub disk_entries: u64,
+    pub disk_bytes: u64,
+    pub size_budget_bytes: u64,
+    pub hit_count: u64,
+    pub memory_hit_count: u64,      // Subset of hit_count served without touching disk
+    pub miss_count: u64,
+    pub eviction_count: u64,        // Disk evictions to stay within budget
+    pub invalidation_count: u64,    // Entries dropped by tag invalidation
+    pub semantic_entries: u64,
+    pub hit_rate: f64,
+}
+
+/// Tag for entries derived from a KB's contents
+pub fn kb_tag(kb_id: &str) -> String {
+    format!("kb:{}", kb_id)
+}
+
+/// Tag for embeddings a model produced
+pub fn embedding_tag(model_id: &str) -> String {
+    format!("embedding:{}", model_id)
+}
+
+/// Cache key of a text's embedding under a model
+fn embedding_cache_key(model_id: &str, text: &str) -> String {
+    format!("embedding:{}:{}", model_id, sha256_hex(text.as_bytes()))
+}
+
+/// Entry as stored on disk
+#[derive(Debug, Serialize, Deserialize)]
+struct StoredEntry {
+    data: String,                   // JSON value
+    tags: Vec<String>,
+    expires_at: Option<i64>,        // Unix millis
+    last_access: i64,               // Unix millis of the last write or promotion
+}
+
+impl StoredEntry {
+    fn is_expired(&self, now: i64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+}
+
+/// Entry held in the memory tier
+#[derive(Debug)]
+struct MemoryEntry {
+    data: String,
+    tags: Vec<String>,
+    expires_at: Option<i64>,
+}
+
+impl MemoryEntry {
+    fn from_stored(entry: &StoredEntry) -> Self {
+        Self {
+            data: entry.data.clone(),
+            tags: entry.tags.clone(),
+            expires_at: entry.expires_at,
+        }
+    }
+
+    fn size(key: &str, data: &str) -> u32 {
+        (key.len() + data.len()).try_into().unwrap_or(u32::MAX)
+    }
+}
+
+/// Entry held in the semantic tier
+#[derive(Debug)]
+struct SemanticEntry {
+    scope: String,                  // Only lookups in the same scope can match
+    embedding: Vec<f32>,
+    data: String,
+    tags: Vec<String>,
+    expires_at: i64,
+}
+
+fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.len() != b.len() {
+        return 0.0;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norms = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|y| y * y).sum::<f32>().sqrt();
+    if norms > 0.0 { dot / norms } else { 0.0 }
+}
+
+#[derive(Debug, Default)]
+struct Counters {
+    hits: AtomicU64,
+    memory_hits: AtomicU64,
+    misses: AtomicU64,
+    evictions: AtomicU64,
+    invalidations: AtomicU64,
+}
+
+/// Two-tier cache service
+pub struct CacheService {
+    config: CacheConfig,
+    memory: moka::sync::Cache<String, Arc<MemoryEntry>>,
+    disk: Database,
+    disk_bytes: AtomicU64,
+    semantic: Mutex<VecDeque<SemanticEntry>>,   // Most recently used first
+    cipher: Option<Arc<ContentCipher>>,         // Seals values in the disk tier
+    counters: Counters,
+}
+
+impl CacheService {
+    /// Open (or create) the disk tier and size the memory tier
+    pub fn new(config: CacheConfig) -> Result<Self, CacheError> {
+        if let Some(parent) = config.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let disk = Database::create(&config.path).map_err(redb::Error::from)?;
+
+        // Create the table up front so read transactions always find it
+        let txn = disk.begin_write().map_err(redb::Error::from)?;
+        txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        txn.commit().map_err(redb::Error::from)?;
+
+        let memory = moka::sync::Cache::builder()
+            .max_capacity(config.memory_bytes)
+            .weigher(|key: &String, entry: &Arc<MemoryEntry>| MemoryEntry::size(key, &entry.data))
+            .build();
+
+        let service = Self {
+            config,
+            memory,
+            disk,
+            disk_bytes: AtomicU64::new(0),
+            semantic: Mutex::new(VecDeque::new()),
+            cipher: None,
+            counters: Counters::default(),
+        };
+        let (entries, bytes) = service.scan_disk_usage()?;
+        service.disk_bytes.store(bytes, Ordering::Relaxed);
+
+        info!("Cache opened at {:?} ({} disk entries, {} bytes)", service.config.path, entries, bytes);
+        Ok(service)
+    }
+
+    /// Seal values written to the disk tier (cached search results carry chunk text)
+    pub fn with_cipher(mut self, cipher: Arc<ContentCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Look up a value: memory first, then disk (promoting the hit to memory)
+    pub fn get<T: DeserializeOwned>(&self, key: &str) -> Result<Option<T>, CacheError> {
+        let now = Utc::now().timestamp_millis();
+
+        if let Some(entry) = self.memory.get(key) {
+            if entry.expires_at.is_none_or(|expires_at| expires_at > now) {
+                self.counters.hits.fetch_add(1, Ordering::Relaxed);
+                self.counters.memory_hits.fetch_add(1, Ordering::Relaxed);
+                return Ok(Some(serde_json::from_str(&entry.data)?));
+            }
+            self.memory.invalidate(key);
+        }
+
+        let stored = match self.read_disk(key)? {
+            Some(stored) if stored.is_expired(now) => {
+                self.remove(key)?;
+                None
+            }
+            stored => stored,
+        };
+        let Some(mut stored) = stored else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        };
+
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        let value = serde_json::from_str(&stored.data)?;
+        self.memory.insert(key.to_string(), Arc::new(MemoryEntry::from_stored(&stored)));
+
+        // Promotion counts as an access for disk eviction
+        stored.last_access = now;
+        self.write_disk(key, &stored)?;
+        Ok(Some(value))
+    }
+
+    /// Store a value in both tiers; `ttl` defaults to the configured TTL
+    pub fn set<T: Serialize>(&self, key: &str, value: &T, ttl: Option<Duration>, tags: &[String]) -> Result<(), CacheError> {
+        let now = Utc::now().timestamp_millis();
+        let ttl = ttl.unwrap_or(self.config.default_ttl);
+        let stored = StoredEntry {
+            data: serde_json::to_string(value)?,
+            tags: tags.to_vec(),
+            expires_at: Some(now.saturating_add(ttl.as_millis().try_into().unwrap_or(i64::MAX))),
+            last_access: now,
+        };
+
+        let bytes = serde_json::to_vec(&stored)?;
+        let disk_budget = self.config.disk_budget_bytes();
+        if (key.len() + bytes.len()) as u64 > disk_budget {
+            return Err(CacheError::CacheFull(format!(
+                "Entry {} is {} bytes, disk budget is {} bytes", key, bytes.len(), disk_budget
+            )));
+        }
+
+        self.write_disk(key, &stored)?;
+        self.memory.insert(key.to_string(), Arc::new(MemoryEntry::from_stored(&stored)));
+
+        if self.disk_bytes.load(Ordering::Relaxed) > disk_budget {
+            self.evict_to((disk_budget as f64 * EVICTION_LOW_WATERMARK) as u64)?;
+        }
+        Ok(())
+    }
+
+    /// Look up the value stored for the most similar embedding in `scope`
+    pub fn get_similar<T: DeserializeOwned>(&self, scope: &str, embedding: &[f32]) -> Result<Option<T>, CacheError> {
+        let now = Utc::now().timestamp_millis();
+        let mut semantic = self.semantic.lock().unwrap();
+        semantic.retain(|entry| entry.expires_at > now);
+
+        let best = semantic.iter().enumerate()
+            .filter(|(_, entry)| entry.scope == scope)
+            .map(|(i, entry)| (i, cosine_similarity(&entry.embedding, embedding)))
+            .filter(|(_, similarity)| *similarity >= self.config.semantic_threshold)
+            .max_by(|a, b| a.1.total_cmp(&b.1));
+        let Some((index, _)) = best else {
+            self.counters.misses.fetch_add(1, Ordering::Relaxed);
+            return Ok(None);
+        };
+
+        self.counters.hits.fetch_add(1, Ordering::Relaxed);
+        let entry = semantic.remove(index).expect("index from iteration");
+        let value = serde_json::from_str(&entry.data)?;
+        semantic.push_front(entry);
+        Ok(Some(value))
+    }
+
+    /// Store a value for a query embedding; the least recently used entry goes when full
+    pub fn set_similar<T: Serialize>(&self, scope: &str, embedding: &[f32], value: &T, ttl: Option<Duration>, tags: &[String]) -> Result<(), CacheError> {
+        let now = Utc::now().timestamp_millis();
+        let ttl = ttl.unwrap_or(self.config.default_ttl);
+        let entry = SemanticEntry {
+            scope: scope.to_string(),
+            embedding: embedding.to_vec(),
+            data: serde_json::to_string(value)?,
+            tags: tags.to_vec(),
+            expires_at: now.saturating_add(ttl.as_millis().try_into().unwrap_or(i64::MAX)),
+        };
+
+        let mut semantic = self.semantic.lock().unwrap();
+        semantic.push_front(entry);
+        semantic.truncate(self.config.semantic_entries);
+        Ok(())
+    }
+
+    /// Remove a key from both tiers
+    pub fn remove(&self, key: &str) -> Result<(), CacheError> {
+        self.memory.invalidate(key);
+        self.delete_disk(&[key.to_string()])?;
+        Ok(())
+    }
+
+    /// Drop every entry carrying `tag`; returns how many were removed
+    pub fn invalidate_tag(&self, tag: &str) -> Result<usize, CacheError> {
+        for (key, entry) in self.memory.iter() {
+            if entry.tags.iter().any(|t| t == tag) {
+                self.memory.invalidate(key.as_str());
+            }
+        }
+
+        let mut semantic = self.semantic.lock().unwrap();
+        let before = semantic.len();
+        semantic.retain(|entry| !entry.tags.iter().any(|t| t == tag));
+        let semantic_removed = before - semantic.len();
+        drop(semantic);
+
+        let keys = self.disk_keys_where(|_, entry| entry.tags.iter().any(|t| t == tag))?;
+        let removed = self.delete_disk(&keys)? + semantic_removed;
+        self.counters.invalidations.fetch_add(removed as u64, Ordering::Relaxed);
+        if removed > 0 {
+            info!("Invalidated {} cache entries tagged {}", removed, tag);
+        }
+        Ok(removed)
+    }
+
+    /// Drop everything cached for a KB (called whenever its contents change)
+    pub fn invalidate_kb(&self, kb_id: &str) -> Result<usize, CacheError> {
+        self.invalidate_tag(&kb_tag(kb_id))
+    }
+
+    /// Remove expired entries from disk; returns how many were removed
+    pub fn purge_expired(&self) -> Result<usize, CacheError> {
+        let now = Utc::now().timestamp_millis();
+        let keys = self.disk_keys_where(|_, entry| entry.is_expired(now))?;
+        for key in &keys {
+            self.memory.invalidate(key.as_str());
+        }
+        self.delete_disk(&keys)
+    }
+
+    /// Drop expired entries and settle the memory tier before shutdown;
+    /// disk writes are already committed, so nothing live is lost
+    pub fn flush(&self) -> Result<usize, CacheError> {
+        let purged = self.purge_expired()?;
+        self.memory.run_pending_tasks();
+        Ok(purged)
+    }
+
+    /// Empty every tier
+    pub fn clear(&self) -> Result<(), CacheError> {
+        self.memory.invalidate_all();
+        self.semantic.lock().unwrap().clear();
+        let txn = self.disk.begin_write().map_err(redb::Error::from)?;
+        txn.delete_table(ENTRIES).map_err(redb::Error::from)?;
+        txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        txn.commit().map_err(redb::Error::from)?;
+        self.disk_bytes.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn stats(&self) -> Result<CacheStats, CacheError> {
+        self.memory.run_pending_tasks();
+        let txn = self.disk.begin_read().map_err(redb::Error::from)?;
+        let table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        let disk_entries = table.len().map_err(redb::Error::from)?;
+
+        let hit_count = self.counters.hits.load(Ordering::Relaxed);
+        let miss_count = self.counters.misses.load(Ordering::Relaxed);
+        let lookups = hit_count + miss_count;
+        Ok(CacheStats {
+            memory_entries: self.memory.entry_count(),
+            memory_bytes: self.memory.weighted_size(),
+            disk_entries,
+            disk_bytes: self.disk_bytes.load(Ordering::Relaxed),
+            size_budget_bytes: self.config.size_budget_bytes,
+            hit_count,
+            memory_hit_count: self.counters.memory_hits.load(Ordering::Relaxed),
+            miss_count,
+            eviction_count: self.counters.evictions.load(Ordering::Relaxed),
+            invalidation_count: self.counters.invalidations.load(Ordering::Relaxed),
+            semantic_entries: self.semantic.lock().unwrap().len() as u64,
+            hit_rate: if lookups > 0 { hit_count as f64 / lookups as f64 } else { 0.0 },
+        })
+    }
+
+    fn read_disk(&self, key: &str) -> Result<Option<StoredEntry>, CacheError> {
+        let txn = self.disk.begin_read().map_err(redb::Error::from)?;
+        let table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        let value = table.get(key).map_err(redb::Error::from)?;
+        let Some(bytes) = value else {
+            return Ok(None);
+        };
+        let mut entry: StoredEntry = serde_json::from_slice(bytes.value())?;
+        if is_sealed_text(&entry.data) {
+            let cipher = self.cipher.as_ref().ok_or(EncryptionError::KeyUnavailable)?;
+            entry.data = cipher.open_text(&entry.data, key)?;
+        }
+        Ok(Some(entry))
+    }
+
+    fn write_disk(&self, key: &str, entry: &StoredEntry) -> Result<(), CacheError> {
+        let bytes = match &self.cipher {
+            Some(cipher) => serde_json::to_vec(&StoredEntry {
+                data: cipher.seal_text(&entry.data, key)?,
+                tags: entry.tags.clone(),
+                expires_at: entry.expires_at,
+                last_access: entry.last_access,
+            })?,
+            None => serde_json::to_vec(entry)?,
+        };
+        let txn = self.disk.begin_write().map_err(redb::Error::from)?;
+        let previous = {
+            let mut table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+            let previous = table.insert(key, bytes.as_slice()).map_err(redb::Error::from)?;
+            previous.map(|old| old.value().len())
+        };
+        txn.commit().map_err(redb::Error::from)?;
+
+        if let Some(old_len) = previous {
+            self.disk_bytes.fetch_sub((key.len() + old_len) as u64, Ordering::Relaxed);
+        }
+        self.disk_bytes.fetch_add((key.len() + bytes.len()) as u64, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn delete_disk(&self, keys: &[String]) -> Result<usize, CacheError> {
+        if keys.is_empty() {
+            return Ok(0);
+        }
+        let txn = self.disk.begin_write().map_err(redb::Error::from)?;
+        let mut removed = 0;
+        let mut freed = 0u64;
+        {
+            let mut table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+            for key in keys {
+                if let Some(old) = table.remove(key.as_str()).map_err(redb::Error::from)? {
+                    removed += 1;
+                    freed += (key.len() + old.value().len()) as u64;
+                }
+            }
+        }
+        txn.commit().map_err(redb::Error::from)?;
+        self.disk_bytes.fetch_sub(freed, Ordering::Relaxed);
+        Ok(removed)
+    }
+
+    /// Keys of disk entries matching `predicate` (unreadable entries always match)
+    fn disk_keys_where(&self, predicate: impl Fn(&str, &StoredEntry) -> bool) -> Result<Vec<String>, CacheError> {
+        let txn = self.disk.begin_read().map_err(redb::Error::from)?;
+        let table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        let mut keys = Vec::new();
+        for item in table.iter().map_err(redb::Error::from)? {
+            let (key, value) = item.map_err(redb::Error::from)?;
+            let matches = match serde_json::from_slice::<StoredEntry>(value.value()) {
+                Ok(entry) => predicate(key.value(), &entry),
+                Err(_) => true,
+            };
+            if matches {
+                keys.push(key.value().to_string());
+            }
+        }
+        Ok(keys)
+    }
+
+    /// Entry count and byte usage of the disk tier
+    fn scan_disk_usage(&self) -> Result<(u64, u64), CacheError> {
+        let txn = self.disk.begin_read().map_err(redb::Error::from)?;
+        let table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+        let mut entries = 0;
+        let mut bytes = 0;
+        for item in table.iter().map_err(redb::Error::from)? {
+            let (key, value) = item.map_err(redb::Error::from)?;
+            entries += 1;
+            bytes += (key.value().len() + value.value().len()) as u64;
+        }
+        Ok((entries, bytes))
+    }
+
+    /// Evict expired, then least recently accessed, entries until disk usage is under `target`
+    fn evict_to(&self, target: u64) -> Result<(), CacheError> {
+        let now = Utc::now().timestamp_millis();
+        let mut candidates: Vec<(i64, String, u64)> = Vec::new();
+        {
+            let txn = self.disk.begin_read().map_err(redb::Error::from)?;
+            let table = txn.open_table(ENTRIES).map_err(redb::Error::from)?;
+            for item in table.iter().map_err(redb::Error::from)? {
+                let (key, value) = item.map_err(redb::Error::from)?;
+                let size = (key.value().len() + value.value().len()) as u64;
+                let rank = match serde_json::from_slice::<StoredEntry>(value.value()) {
+                    Ok(entry) if !entry.is_expired(now) => entry.last_access,
+                    _ => i64::MIN,
+                };
+                candidates.push((rank, key.value().to_string(), size));
+            }
+        }
+        candidates.sort();
+
+        let mut usage = self.disk_bytes.load(Ordering::Relaxed);
+        let mut victims = Vec::new();
+        for (_, key, size) in candidates {
+            if usage <= target {
+                break;
+            }
+            usage = usage.saturating_sub(size);
+            victims.push(key);
+        }
+        for key in &victims {
+            self.memory.invalidate(key.as_str());
+        }
+
+        let evicted = self.delete_disk(&victims)?;
+        self.counters.evictions.fetch_add(evicted as u64, Ordering::Relaxed);
+        if evicted > 0 {
+            warn!("Cache over budget: evicted {} entries", evicted);
+        }
+        Ok(())
+    }
+}
+
+/// Serves embeddings from the cache and sends only the misses to the backend
+pub struct CachedEmbeddingBackend {
+    inner: Arc<dyn EmbeddingBackend>,
+    cache: Arc<CacheService>,
+}
+
+impl CachedEmbeddingBackend {
+    pub fn new(inner: Arc<dyn EmbeddingBackend>, cache: Arc<CacheService>) -> Self {
+        Self { inner, cache }
+    }
+}
+
+#[async_trait]
+impl EmbeddingBackend for CachedEmbeddingBackend {
+    async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError> {
+        let keys: Vec<String> = texts.iter().map(|text| embedding_cache_key(model_id, text)).collect();
+        let mut vectors: Vec<Option<Vec<f32>>> = keys.iter().map(|key| {
+            self.cache.get(key).unwrap_or_else(|e| {
+                warn!("Embedding cache lookup failed for {}: {}", key, e);
+                None
+            })
+        }).collect();
+
+        let missing: Vec<usize> = (0..texts.len()).filter(|&i| vectors[i].is_none()).collect();
+        if !missing.is_empty() {
+            let batch: Vec<String> = missing.iter().map(|&i| texts[i].clone()).collect();
+            let computed = self.inner.embed(model_id, &batch).await?;
+            if computed.len() != batch.len() {
+                return Err(ModelError::Backend(format!(
+                    "{} returned {} vectors for {} texts", model_id, computed.len(), batch.len()
+                )));
+            }
+            let tags = [embedding_tag(model_id)];
+            for (&i, vector) in missing.iter().zip(computed) {
+                if let Err(e) = self.cache.set(&keys[i], &vector, Some(EMBEDDING_TTL), &tags) {
+                    warn!("Failed to cache embedding {}: {}", keys[i], e);
+                }
+                vectors[i] = Some(vector);
+            }
+        }
+        Ok(vectors.into_iter().flatten().collect())
+    }
+
+    /// Scores depend on the query and document together, so reranking is not cached
+    async fn rerank(&self, model_id: &str, query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError> {
+        self.inner.rerank(model_id, query, documents).await
+    }
+
+    fn loaded_models(&self) -> Vec<LoadedModel> {
+        self.inner.loaded_models()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn tags(kb_id: &str) -> Vec<String> {
+        vec![kb_tag(kb_id)]
+    }
+
+    #[test]
+    fn test_write_through_and_promotion() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = CacheConfig::test_config(temp_dir.path());
+        {
+            let cache = CacheService::new(config.clone()).unwrap();
+            cache.set("q1", &vec!["a", "b"], None, &tags("kb_1")).unwrap();
+            assert_eq!(cache.get::<Vec<String>>("q1").unwrap().unwrap(), vec!["a", "b"]);
+            assert!(cache.get::<Vec<String>>("missing").unwrap().is_none());
+
+            let stats = cache.stats().unwrap();
+            assert_eq!((stats.hit_count, stats.memory_hit_count, stats.miss_count), (1, 1, 1));
+            assert_eq!(stats.disk_entries, 1);
+        }
+
+        // Reopened: memory is cold, the disk tier serves and promotes the entry
+        let cache = CacheService::new(config).unwrap();
+        assert!(cache.stats().unwrap().disk_bytes > 0);
+        assert_eq!(cache.get::<Vec<String>>("q1").unwrap().unwrap(), vec!["a", "b"]);
+        assert_eq!(cache.get::<Vec<String>>("q1").unwrap().unwrap(), vec!["a", "b"]);
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.hit_count, stats.memory_hit_count), (2, 1));
+    }
+
+    #[test]
+    fn test_sealed_disk_tier() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = CacheConfig::test_config(temp_dir.path());
+        let cipher = Arc::new(ContentCipher::from_key(&[5u8; 32]).unwrap());
+        {
+            let cache = CacheService::new(config.clone()).unwrap().with_cipher(cipher.clone());
+            cache.set("q1", &"confidential chunk", None, &tags("kb_1")).unwrap();
+        }
+        let file = std::fs::read(&config.path).unwrap();
+        assert!(!file.windows(12).any(|w| w == b"confidential"));
+
+        // The disk tier needs the key; tags still work without it
+        let cache = CacheService::new(config.clone()).unwrap();
+        assert!(matches!(cache.get::<String>("q1"), Err(CacheError::EncryptionError(_))));
+        drop(cache);
+        let cache = CacheService::new(config).unwrap().with_cipher(cipher);
+        assert_eq!(cache.get::<String>("q1").unwrap().unwrap(), "confidential chunk");
+        assert_eq!(cache.invalidate_kb("kb_1").unwrap(), 1);
+    }
+
+    #[test]
+    fn test_invalidate_kb_and_expiry() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = CacheService::new(CacheConfig::test_config(temp_dir.path())).unwrap();
+        cache.set("kb_1:q1", &1, None, &tags("kb_1")).unwrap();
+        cache.set("kb_1:q2", &2, None, &tags("kb_1")).unwrap();
+        cache.set("kb_2:q1", &3, None, &tags("kb_2")).unwrap();
+        cache.set("stale", &4, Some(Duration::ZERO), &[]).unwrap();
+
+        assert_eq!(cache.invalidate_kb("kb_1").unwrap(), 2);
+        assert!(cache.get::<i32>("kb_1:q1").unwrap().is_none());
+        assert_eq!(cache.get::<i32>("kb_2:q1").unwrap(), Some(3));
+        assert!(cache.get::<i32>("stale").unwrap().is_none());
+
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.disk_entries, stats.invalidation_count), (1, 2));
+    }
+
+    #[test]
+    fn test_size_budget_evicts_least_recent() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = CacheConfig {
+            size_budget_bytes: 8 * 1024,
+            memory_bytes: 2 * 1024,
+            ..CacheConfig::test_config(temp_dir.path())
+        };
+        let cache = CacheService::new(config).unwrap();
+        let value = "x".repeat(1000);
+        for i in 0..10 {
+            cache.set(&format!("k{}", i), &value, None, &[]).unwrap();
+        }
+
+        let stats = cache.stats().unwrap();
+        assert!(stats.disk_bytes <= 6 * 1024);
+        assert!(stats.eviction_count > 0);
+        assert!(cache.get::<String>("k0").unwrap().is_none());
+        assert_eq!(cache.get::<String>("k9").unwrap(), Some(value.clone()));
+
+        let too_large = "x".repeat(10 * 1024);
+        assert!(matches!(cache.set("big", &too_large, None, &[]), Err(CacheError::CacheFull(_))));
+    }
+
+    /// Records which texts reach the model
+    #[derive(Default)]
+    struct RecordingBackend {
+        calls: Mutex<Vec<Vec<String>>>,
+    }
+
+    #[async_trait]
+    impl EmbeddingBackend for RecordingBackend {
+        async fn embed(&self, _model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError> {
+            self.calls.lock().unwrap().push(texts.to_vec());
+            Ok(texts.iter().map(|text| vec![text.len() as f32]).collect())
+        }
+
+        async fn rerank(&self, _model_id: &str, _query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError> {
+            Ok(vec![0.0; documents.len()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cached_embeddings_skip_inference() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = Arc::new(CacheService::new(CacheConfig::test_config(temp_dir.path())).unwrap());
+        let inner = Arc::new(RecordingBackend::default());
+        let backend = CachedEmbeddingBackend::new(inner.clone(), cache.clone());
+        let texts = |items: &[&str]| items.iter().map(|t| t.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(backend.embed("m1", &texts(&["a", "bb"])).await.unwrap(), vec![vec![1.0], vec![2.0]]);
+        // Only the new text is embedded; cached vectors keep their positions
+        assert_eq!(backend.embed("m1", &texts(&["ccc", "a"])).await.unwrap(), vec![vec![3.0], vec![1.0]]);
+        assert_eq!(backend.embed("m1", &texts(&["bb"])).await.unwrap(), vec![vec![2.0]]);
+        // Another model does not share vectors
+        backend.embed("m2", &texts(&["a"])).await.unwrap();
+        assert_eq!(*inner.calls.lock().unwrap(), vec![texts(&["a", "bb"]), texts(&["ccc"]), texts(&["a"])]);
+
+        assert_eq!(cache.invalidate_tag(&embedding_tag("m1")).unwrap(), 3);
+        backend.embed("m1", &texts(&["a"])).await.unwrap();
+        assert_eq!(inner.calls.lock().unwrap().len(), 4);
+    }
+
+    #[test]
+    fn test_semantic_tier() {
+        let temp_dir = TempDir::new().unwrap();
+        let cache = CacheService::new(CacheConfig::test_config(temp_dir.path())).unwrap();
+        cache.set_similar("search:kb_1", &[1.0, 0.0], &vec!["a"], None, &tags("kb_1")).unwrap();
+        cache.set_similar("search:kb_2", &[1.0, 0.0], &vec!["b"], None, &tags("kb_2")).unwrap();
+
+        // Close enough within the scope; other scopes and distant queries miss
+        assert_eq!(cache.get_similar::<Vec<String>>("search:kb_1", &[0.99, 0.05]).unwrap().unwrap(), vec!["a"]);
+        assert!(cache.get_similar::<Vec<String>>("search:kb_1", &[0.6, 0.8]).unwrap().is_none());
+        assert!(cache.get_similar::<Vec<String>>("search:kb_3", &[1.0, 0.0]).unwrap().is_none());
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.hit_count, stats.miss_count, stats.semantic_entries), (1, 2, 2));
+
+        // A KB change drops its entries; expired entries and the least recently used go too
+        assert_eq!(cache.invalidate_kb("kb_1").unwrap(), 1);
+        assert!(cache.get_similar::<Vec<String>>("search:kb_1", &[1.0, 0.0]).unwrap().is_none());
+        cache.set_similar("search:kb_2", &[0.0, 1.0], &vec!["c"], Some(Duration::ZERO), &[]).unwrap();
+        assert!(cache.get_similar::<Vec<String>>("search:kb_2", &[0.0, 1.0]).unwrap().is_none());
+        for i in 0..4 {
+            cache.set_similar("search:kb_4", &[i as f32, 1.0], &i, None, &[]).unwrap();
+        }
+        assert!(cache.get_similar::<Vec<String>>("search:kb_2", &[1.0, 0.0]).unwrap().is_none());
+        assert_eq!(cache.stats().unwrap().semantic_entries, 4);
+    }
+}
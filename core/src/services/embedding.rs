@@ -0,0 +1,826 @@
+/*!
+ * Embedding Service (ONNX Runtime)
+ *
+ * In-process `EmbeddingBackend` that runs sentence-transformer ONNX exports
+ * of installed models, so embedding and reranking need no Python worker.
+ * A model directory needs `tokenizer.json` and `model.onnx` (or
+ * `onnx/model.onnx`, the Hub's export layout). Embeddings are pooled as the
+ * model's `1_Pooling/config.json` says (mean pooling when it is missing) and
+ * L2-normalized; rerankers (cross-encoders) score each (query, document)
+ * pair by its first logit. Sessions are loaded on first use and kept while
+ * the loaded models fit the memory budget (estimated from their ONNX files);
+ * the least recently used are evicted to make room. A model run that
+ * outlasts the request timeout is terminated and its session dropped, so a
+ * hung run can't stall pipelines; the next request loads the model afresh.
+ * Concurrent embed requests for a model are micro-batched: the first waits
+ * up to `max_wait` for others to join, then all of them share session runs
+ * bounded by row count and padded token count (`BatchConfig`); each run's
+ * latency goes to the `embed_duration_seconds` histogram.
+ * Sessions run on the device chosen by `RAG_STUDIO_EMBEDDING_DEVICE`
+ * (`auto` by default: CUDA, CoreML or DirectML when ONNX Runtime can use
+ * them, otherwise CPU); a device that fails to register falls back to CPU,
+ * and accelerators get larger batches.
+ * The ONNX Runtime library is loaded at runtime from `ORT_DYLIB_PATH`
+ * (built with the `onnx` feature).
+ */
+
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use ort::execution_providers::{
+    CUDAExecutionProvider, CoreMLExecutionProvider, DirectMLExecutionProvider, ExecutionProvider,
+};
+use ort::session::builder::SessionBuilder;
+use ort::session::{RunOptions, Session};
+use ort::value::Tensor;
+use serde::Deserialize;
+use tokenizers::{Encoding, Tokenizer, TruncationParams};
+use tokio::sync::{oneshot, Notify};
+use tracing::{debug, info, warn};
+
+use super::metrics::MetricsService;
+use super::model::{registry_model, EmbeddingBackend, LoadedModel, ModelError, ModelService};
+
+/// Model files, in order of preference
+const ONNX_FILES: &[&str] = &["model.onnx", "onnx/model.onnx"];
+
+const TOKENIZER_FILE: &str = "tokenizer.json";
+
+/// Sentence-transformers pooling settings
+const POOLING_CONFIG: &str = "1_Pooling/config.json";
+
+/// Token limit for models missing from the registry
+const DEFAULT_MAX_LENGTH: usize = 512;
+
+/// Memory budget for loaded models
+const DEFAULT_MEMORY_LIMIT: u64 = 4 * 1024 * 1024 * 1024;
+
+/// Deadline for a model run, including the wait for the model
+const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Pad tokens tried when the tokenizer has no padding configured
+const PAD_TOKENS: &[&str] = &["[PAD]", "<pad>"];
+
+/// Environment variable selecting the execution device
+pub const DEVICE_ENV: &str = "RAG_STUDIO_EMBEDDING_DEVICE";
+
+/// Batch limits are multiplied by this on accelerators, which amortize
+/// kernel launches and transfers over more rows
+const ACCELERATOR_BATCH_SCALE: usize = 4;
+
+/// Where model sessions run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Device {
+    #[default]
+    Auto,           // First accelerator ONNX Runtime can use, else CPU
+    Cpu,
+    Cuda(i32),      // Device id
+    CoreMl,
+    DirectMl(i32),  // Adapter index
+}
+
+impl Device {
+    /// The device named by `RAG_STUDIO_EMBEDDING_DEVICE` (`Auto` when unset or invalid)
+    pub fn from_env() -> Self {
+        match std::env::var(DEVICE_ENV) {
+            Ok(value) => value.parse().unwrap_or_else(|e| {
+                warn!("Ignoring {}: {}", DEVICE_ENV, e);
+                Device::Auto
+            }),
+            Err(_) => Device::Auto,
+        }
+    }
+
+    /// Accelerators to try, in order, before falling back to CPU
+    fn accelerators(self) -> Vec<Device> {
+        match self {
+            Device::Auto => vec![Device::Cuda(0), Device::CoreMl, Device::DirectMl(0)],
+            Device::Cpu => Vec::new(),
+            device => vec![device],
+        }
+    }
+
+    fn provider(self) -> Option<Box<dyn ExecutionProvider>> {
+        match self {
+            Device::Cuda(id) => Some(Box::new(CUDAExecutionProvider::default().with_device_id(id))),
+            Device::CoreMl => Some(Box::new(CoreMLExecutionProvider::default())),
+            Device::DirectMl(id) => Some(Box::new(DirectMLExecutionProvider::default().with_device_id(id))),
+            Device::Auto | Device::Cpu => None,
+        }
+    }
+
+    /// Register this device's execution provider on a session builder
+    fn register(self, builder: &mut SessionBuilder) -> Result<(), String> {
+        let Some(provider) = self.provider() else {
+            return Ok(());
+        };
+        if !provider.supported_by_platform() {
+            return Err("not supported on this platform".to_string());
+        }
+        if !provider.is_available().unwrap_or(false) {
+            return Err("not available in the loaded ONNX Runtime".to_string());
+        }
+        provider.register(builder).map_err(|e| ort::Error::from(e).to_string())
+    }
+}
+
+impl fmt::Display for Device {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Device::Auto => write!(f, "auto"),
+            Device::Cpu => write!(f, "cpu"),
+            Device::Cuda(id) => write!(f, "cuda:{}", id),
+            Device::CoreMl => write!(f, "coreml"),
+            Device::DirectMl(id) => write!(f, "directml:{}", id),
+        }
+    }
+}
+
+impl std::str::FromStr for Device {
+    type Err = String;
+
+    /// `auto`, `cpu`, `coreml`, or `cuda`/`directml` with an optional `:<id>`
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim().to_ascii_lowercase();
+        let (name, id) = match value.split_once(':') {
+            Some((name, id)) => (name, Some(id.parse::<i32>().map_err(|_| format!("invalid device id in {:?}", value))?)),
+            None => (value.as_str(), None),
+        };
+        match (name, id) {
+            ("auto", None) => Ok(Device::Auto),
+            ("cpu", None) => Ok(Device::Cpu),
+            ("coreml", None) => Ok(Device::CoreMl),
+            ("cuda", id) => Ok(Device::Cuda(id.unwrap_or(0))),
+            ("directml", id) => Ok(Device::DirectMl(id.unwrap_or(0))),
+            _ => Err(format!("unknown device {:?}", value)),
+        }
+    }
+}
+
+/// Micro-batching of embed requests
+#[derive(Debug, Clone)]
+pub struct BatchConfig {
+    pub max_batch_size: usize,      // Texts per session run
+    pub max_batch_tokens: usize,    // Padded tokens (rows x longest row) per session run
+    pub max_wait: Duration,         // How long a request waits for others to join its batch
+}
+
+impl Default for BatchConfig {
+    fn default() -> Self {
+        Self {
+            max_batch_size: 64,
+            max_batch_tokens: 16_384,
+            max_wait: Duration::from_millis(20),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pooling {
+    Mean,
+    Cls,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PoolingConfig {
+    #[serde(default)]
+    pooling_mode_cls_token: bool,
+}
+
+impl BatchConfig {
+    /// Limits for sessions on `device`
+    fn for_device(&self, device: Device) -> BatchConfig {
+        let scale = if device == Device::Cpu { 1 } else { ACCELERATOR_BATCH_SCALE };
+        BatchConfig {
+            max_batch_size: self.max_batch_size * scale,
+            max_batch_tokens: self.max_batch_tokens * scale,
+            max_wait: self.max_wait,
+        }
+    }
+}
+
+/// A loaded model: its session, the device it runs on, tokenizer (padding
+/// done per batch), pooling and estimated memory use
+struct OnnxModel {
+    session: Session,
+    device: Device,
+    tokenizer: Tokenizer,
+    pad_id: u32,
+    pooling: Pooling,
+    memory_bytes: u64,
+}
+
+/// A model in the session cache, with what health output reports about it
+struct CachedModel<M = OnnxModel> {
+    model: Arc<Mutex<M>>,
+    info: LoadedModel,
+}
+
+impl<M> Clone for CachedModel<M> {
+    fn clone(&self) -> Self {
+        Self { model: self.model.clone(), info: self.info.clone() }
+    }
+}
+
+/// Loaded models, least recently used first, within a memory budget
+struct ModelCache<M = OnnxModel> {
+    entries: Vec<CachedModel<M>>,
+    memory_limit: u64,
+}
+
+impl<M> ModelCache<M> {
+    fn new(memory_limit: u64) -> Self {
+        Self { entries: Vec::new(), memory_limit }
+    }
+
+    /// A loaded model, now the most recently used
+    fn get(&mut self, model_id: &str) -> Option<CachedModel<M>> {
+        let index = self.entries.iter().position(|cached| cached.info.model_id == model_id)?;
+        let cached = self.entries.remove(index);
+        self.entries.push(cached.clone());
+        Some(cached)
+    }
+
+    /// Add a model, evicting the least recently used until it fits (a model
+    /// over the whole budget still loads, alone); a model loaded meanwhile
+    /// by another request wins
+    fn insert(&mut self, cached: CachedModel<M>) -> CachedModel<M> {
+        if let Some(existing) = self.get(&cached.info.model_id) {
+            return existing;
+        }
+        while !self.entries.is_empty() && self.memory_bytes() + cached.info.memory_bytes > self.memory_limit {
+            let evicted = self.entries.remove(0);
+            info!("Evicted ONNX model {} ({} bytes) to load {}", evicted.info.model_id, evicted.info.memory_bytes, cached.info.model_id);
+        }
+        self.entries.push(cached.clone());
+        cached
+    }
+
+    /// Drop the cached entry if it is still the model in use by `cached`
+    fn remove(&mut self, cached: &CachedModel<M>) {
+        self.entries.retain(|entry| !Arc::ptr_eq(&entry.model, &cached.model));
+    }
+
+    fn memory_bytes(&self) -> u64 {
+        self.entries.iter().map(|cached| cached.info.memory_bytes).sum()
+    }
+
+    /// Loaded models, least recently used first
+    fn loaded(&self) -> Vec<LoadedModel> {
+        self.entries.iter().map(|cached| cached.info.clone()).collect()
+    }
+}
+
+/// Token ids, attention mask and token type ids of a padded batch
+struct Batch {
+    rows: usize,
+    len: usize,
+    input_ids: Vec<i64>,
+    attention_mask: Vec<i64>,
+    token_type_ids: Vec<i64>,
+}
+
+fn backend_error(e: impl std::fmt::Display) -> ModelError {
+    ModelError::Backend(e.to_string())
+}
+
+impl OnnxModel {
+    fn load(model_id: &str, dir: &Path, device: Device) -> Result<Self, ModelError> {
+        let onnx_file = ONNX_FILES.iter().map(|file| dir.join(file)).find(|path| path.is_file());
+        let tokenizer_file = dir.join(TOKENIZER_FILE);
+        let (Some(onnx_file), true) = (onnx_file, tokenizer_file.is_file()) else {
+            return Err(ModelError::ModelNotFound(format!("{} (no ONNX export installed)", model_id)));
+        };
+
+        let mut tokenizer = Tokenizer::from_file(&tokenizer_file).map_err(backend_error)?;
+        let max_length = registry_model(model_id).map_or(DEFAULT_MAX_LENGTH, |m| m.max_sequence_length);
+        tokenizer.with_truncation(Some(TruncationParams { max_length, ..Default::default() }))
+            .map_err(backend_error)?;
+        let pad_id = match tokenizer.get_padding() {
+            Some(padding) => padding.pad_id,
+            None => PAD_TOKENS.iter().find_map(|token| tokenizer.token_to_id(token)).unwrap_or(0),
+        };
+        tokenizer.with_padding(None);
+
+        let pooling = match std::fs::read(dir.join(POOLING_CONFIG)) {
+            Ok(bytes) => {
+                let config: PoolingConfig = serde_json::from_slice(&bytes)?;
+                if config.pooling_mode_cls_token { Pooling::Cls } else { Pooling::Mean }
+            }
+            Err(_) => Pooling::Mean,
+        };
+
+        let (session, device) = open_session(&onnx_file, device)?;
+        let memory_bytes = model_bytes(&onnx_file);
+        info!("Loaded ONNX model {} from {:?} on {} ({:?} pooling)", model_id, onnx_file, device, pooling);
+        Ok(Self { session, device, tokenizer, pad_id, pooling, memory_bytes })
+    }
+
+    /// Tokenize `inputs` into padded batches within the `limits` (scaled for the device)
+    fn tokenize<'s, E: Into<tokenizers::EncodeInput<'s>> + Send>(&self, inputs: Vec<E>, limits: &BatchConfig) -> Result<Vec<Batch>, ModelError> {
+        let encodings = self.tokenizer.encode_batch(inputs, true).map_err(backend_error)?;
+        let lengths: Vec<usize> = encodings.iter().map(Encoding::len).collect();
+        let limits = limits.for_device(self.device);
+        Ok(split_batches(&lengths, limits.max_batch_size, limits.max_batch_tokens)
+            .into_iter()
+            .map(|range| Batch::pad(&encodings[range], self.pad_id))
+            .collect())
+    }
+
+    /// Run a batch; returns the first output's shape and values
+    fn run(&mut self, batch: &Batch, options: &RunOptions) -> Result<(Vec<usize>, Vec<f32>), ModelError> {
+        let shape = [batch.rows, batch.len];
+        let mut inputs = Vec::with_capacity(self.session.inputs.len());
+        for input in &self.session.inputs {
+            let values = match input.name.as_str() {
+                "input_ids" => &batch.input_ids,
+                "attention_mask" => &batch.attention_mask,
+                "token_type_ids" => &batch.token_type_ids,
+                other => return Err(ModelError::Backend(format!("Unsupported model input: {}", other))),
+            };
+            let tensor = Tensor::from_array((shape, values.clone())).map_err(backend_error)?;
+            inputs.push((input.name.clone(), tensor));
+        }
+
+        let outputs = self.session.run_with_options(inputs, options).map_err(backend_error)?;
+        let (shape, values) = outputs[0].try_extract_tensor::<f32>().map_err(backend_error)?;
+        Ok((shape.iter().map(|&d| d as usize).collect(), values.to_vec()))
+    }
+
+    fn embed(&mut self, texts: &[String], limits: &BatchConfig, options: &RunOptions, metrics: Option<&MetricsService>) -> Result<Vec<Vec<f32>>, ModelError> {
+        let mut vectors = Vec::with_capacity(texts.len());
+        for batch in self.tokenize(texts.iter().map(String::as_str).collect(), limits)? {
+            let started = Instant::now();
+            let (shape, values) = self.run(&batch, options)?;
+            if let Some(metrics) = metrics {
+                metrics.record_embedding(batch.rows, started.elapsed());
+            }
+            match shape.as_slice() {
+                // Already pooled (`sentence_embedding` exports)
+                [rows, dim] if *rows == batch.rows => vectors.extend(values.chunks(*dim).map(<[f32]>::to_vec)),
+                [rows, len, dim] if *rows == batch.rows && *len == batch.len => {
+                    vectors.extend(pool(&values, &batch.attention_mask, batch.len, *dim, self.pooling));
+                }
+                _ => return Err(ModelError::Backend(format!("Unexpected output shape {:?}", shape))),
+            }
+        }
+        vectors.iter_mut().for_each(|vector| normalize(vector));
+        Ok(vectors)
+    }
+
+    fn rerank(&mut self, query: &str, documents: &[String], limits: &BatchConfig, options: &RunOptions) -> Result<Vec<f32>, ModelError> {
+        let mut scores = Vec::with_capacity(documents.len());
+        for batch in self.tokenize(documents.iter().map(|doc| (query, doc.as_str())).collect(), limits)? {
+            let (shape, values) = self.run(&batch, options)?;
+            match shape.as_slice() {
+                [rows, labels] if *rows == batch.rows && *labels > 0 => scores.extend(values.chunks(*labels).map(|logits| logits[0])),
+                [rows] if *rows == batch.rows => scores.extend(values),
+                _ => return Err(ModelError::Backend(format!("Unexpected output shape {:?}", shape))),
+            }
+        }
+        Ok(scores)
+    }
+}
+
+/// Memory a session takes, estimated as the size of its weights: the ONNX
+/// file plus external data saved beside it (`model.onnx_data`)
+fn model_bytes(onnx_file: &Path) -> u64 {
+    let size = |path: &Path| std::fs::metadata(path).map_or(0, |m| m.len());
+    let mut external = onnx_file.as_os_str().to_owned();
+    external.push("_data");
+    size(onnx_file) + size(Path::new(&external))
+}
+
+/// Open a session on the first accelerator of `device` that registers and
+/// loads the model, else on CPU; returns the device actually used
+fn open_session(onnx_file: &Path, device: Device) -> Result<(Session, Device), ModelError> {
+    for accelerator in device.accelerators() {
+        let mut builder = Session::builder().map_err(backend_error)?;
+        let opened = accelerator.register(&mut builder)
+            .and_then(|_| builder.commit_from_file(onnx_file).map_err(|e| e.to_string()));
+        match opened {
+            Ok(session) => return Ok((session, accelerator)),
+            // Auto probes every accelerator; only an explicit choice failing is worth a warning
+            Err(e) if device == Device::Auto => debug!("{} unavailable: {}", accelerator, e),
+            Err(e) => warn!("{} unavailable, falling back to CPU: {}", accelerator, e),
+        }
+    }
+    let session = Session::builder()
+        .and_then(|builder| builder.commit_from_file(onnx_file))
+        .map_err(backend_error)?;
+    Ok((session, Device::Cpu))
+}
+
+impl Batch {
+    /// Right-pad encodings to the longest one; padding is masked out
+    fn pad(encodings: &[Encoding], pad_id: u32) -> Self {
+        let len = encodings.iter().map(Encoding::len).max().unwrap_or(0);
+        let mut batch = Batch {
+            rows: encodings.len(),
+            len,
+            input_ids: Vec::with_capacity(encodings.len() * len),
+            attention_mask: Vec::with_capacity(encodings.len() * len),
+            token_type_ids: Vec::with_capacity(encodings.len() * len),
+        };
+        for encoding in encodings {
+            let padding = len - encoding.len();
+            batch.input_ids.extend(encoding.get_ids().iter().map(|&id| id as i64).chain(std::iter::repeat_n(pad_id as i64, padding)));
+            batch.attention_mask.extend(encoding.get_attention_mask().iter().map(|&m| m as i64).chain(std::iter::repeat_n(0, padding)));
+            batch.token_type_ids.extend(encoding.get_type_ids().iter().map(|&t| t as i64).chain(std::iter::repeat_n(0, padding)));
+        }
+        batch
+    }
+}
+
+/// Consecutive ranges of rows, each at most `max_rows` long and at most
+/// `max_tokens` once padded to its longest row (a longer row runs alone)
+fn split_batches(lengths: &[usize], max_rows: usize, max_tokens: usize) -> Vec<Range<usize>> {
+    let mut ranges = Vec::new();
+    let (mut start, mut longest) = (0, 0);
+    for (i, &len) in lengths.iter().enumerate() {
+        let rows = i - start + 1;
+        let widest = longest.max(len);
+        if rows > 1 && (rows > max_rows.max(1) || rows * widest > max_tokens) {
+            ranges.push(start..i);
+            (start, longest) = (i, len);
+        } else {
+            longest = widest;
+        }
+    }
+    if start < lengths.len() {
+        ranges.push(start..lengths.len());
+    }
+    ranges
+}
+
+/// One vector per row of `[rows, len, dim]` token embeddings
+fn pool(hidden: &[f32], attention_mask: &[i64], len: usize, dim: usize, pooling: Pooling) -> Vec<Vec<f32>> {
+    hidden.chunks(len * dim).zip(attention_mask.chunks(len)).map(|(tokens, mask)| match pooling {
+        Pooling::Cls => tokens[..dim].to_vec(),
+        Pooling::Mean => {
+            let mut sum = vec![0.0; dim];
+            let mut count = 0.0;
+            for (token, _) in tokens.chunks(dim).zip(mask).filter(|(_, &m)| m != 0) {
+                sum.iter_mut().zip(token).for_each(|(s, v)| *s += v);
+                count += 1.0;
+            }
+            sum.iter_mut().for_each(|s| *s /= f32::max(count, 1.0));
+            sum
+        }
+    }).collect()
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|v| v * v).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter_mut().for_each(|v| *v /= norm);
+    }
+}
+
+/// An embed request waiting for its batch
+struct PendingEmbed {
+    texts: Vec<String>,
+    reply: oneshot::Sender<Result<Vec<Vec<f32>>, ModelError>>,
+}
+
+/// Embed requests for one model, collected until the batch is full or the
+/// first request has waited `max_wait`
+#[derive(Default)]
+struct EmbedQueue {
+    pending: Mutex<Vec<PendingEmbed>>,
+    full: Notify,
+}
+
+impl EmbedQueue {
+    /// Queue a request; true when it is the first, which then leads the batch
+    fn push(&self, request: PendingEmbed, max_batch_size: usize) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        let queued: usize = pending.iter().map(|p| p.texts.len()).sum();
+        let joined = request.texts.len();
+        pending.push(request);
+        if queued < max_batch_size && queued + joined >= max_batch_size {
+            self.full.notify_one();
+        }
+        pending.len() == 1
+    }
+
+    /// Wait for the batch to fill, at most `max_wait`, and take it
+    async fn collect(&self, max_wait: Duration) -> Vec<PendingEmbed> {
+        let _ = tokio::time::timeout(max_wait, self.full.notified()).await;
+        std::mem::take(&mut *self.pending.lock().unwrap())
+    }
+}
+
+/// Hand each request its share of a batch's vectors (or the batch's error)
+fn answer(requests: Vec<PendingEmbed>, result: Result<Vec<Vec<f32>>, ModelError>) {
+    match result {
+        Ok(vectors) => {
+            let mut vectors = vectors.into_iter();
+            for request in requests {
+                let share = vectors.by_ref().take(request.texts.len()).collect();
+                let _ = request.reply.send(Ok(share));
+            }
+        }
+        Err(e) => {
+            for request in requests {
+                let error = match &e {
+                    ModelError::ModelNotFound(model) => ModelError::ModelNotFound(model.clone()),
+                    ModelError::Timeout(model) => ModelError::Timeout(model.clone()),
+                    other => ModelError::Backend(other.to_string()),
+                };
+                let _ = request.reply.send(Err(error));
+            }
+        }
+    }
+}
+
+/// Runs installed ONNX exports in-process
+#[derive(Clone)]
+pub struct OnnxEmbeddingBackend {
+    model_service: Arc<ModelService>,
+    models: Arc<Mutex<ModelCache>>,
+    queues: Arc<Mutex<HashMap<String, Arc<EmbedQueue>>>>,
+    device: Device,
+    batching: BatchConfig,
+    request_timeout: Duration,
+    metrics_service: Option<Arc<MetricsService>>,
+}
+
+impl OnnxEmbeddingBackend {
+    pub fn new(model_service: Arc<ModelService>) -> Self {
+        Self {
+            model_service,
+            models: Arc::new(Mutex::new(ModelCache::new(DEFAULT_MEMORY_LIMIT))),
+            queues: Arc::new(Mutex::new(HashMap::new())),
+            device: Device::Auto,
+            batching: BatchConfig::default(),
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
+            metrics_service: None,
+        }
+    }
+
+    /// Device for models loaded from now on
+    pub fn with_device(mut self, device: Device) -> Self {
+        self.device = device;
+        self
+    }
+
+    /// Memory budget for loaded models (default 4 GiB)
+    pub fn with_memory_limit(self, bytes: u64) -> Self {
+        self.models.lock().unwrap().memory_limit = bytes;
+        self
+    }
+
+    pub fn with_batching(mut self, batching: BatchConfig) -> Self {
+        self.batching = batching;
+        self
+    }
+
+    /// Deadline for each model run (default 60s); loading the model doesn't count
+    pub fn with_request_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = timeout;
+        self
+    }
+
+    /// Record each session run in the embedding metrics
+    pub fn with_metrics_service(mut self, metrics_service: Arc<MetricsService>) -> Self {
+        self.metrics_service = Some(metrics_service);
+        self
+    }
+
+    /// Run `f` on a model off the async runtime, loading the model on first use
+    ///
+    /// Past the request timeout the run is terminated and the model dropped
+    /// from the cache: a run that ignores termination keeps its session locked,
+    /// and the next request loads a fresh one instead of queueing behind it.
+    async fn with_model<T, F>(&self, model_id: &str, f: F) -> Result<T, ModelError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut OnnxModel, &RunOptions) -> Result<T, ModelError> + Send + 'static,
+    {
+        let cached = self.load(model_id).await?;
+        let options = Arc::new(RunOptions::new().map_err(backend_error)?);
+        let run = {
+            let (cached, options) = (cached.clone(), options.clone());
+            tokio::task::spawn_blocking(move || {
+                let mut model = cached.model.lock().unwrap();
+                f(&mut model, &options)
+            })
+        };
+        match tokio::time::timeout(self.request_timeout, run).await {
+            Ok(result) => result.map_err(backend_error)?,
+            Err(_) => {
+                warn!("ONNX model {} ran past {:?}; terminating the run and dropping the session", model_id, self.request_timeout);
+                if let Err(e) = options.terminate() {
+                    warn!("Failed to terminate the run on {}: {}", model_id, e);
+                }
+                self.models.lock().unwrap().remove(&cached);
+                Err(ModelError::Timeout(format!("{} after {:?}", model_id, self.request_timeout)))
+            }
+        }
+    }
+
+    /// The cached model, loading it off the async runtime on first use
+    async fn load(&self, model_id: &str) -> Result<CachedModel, ModelError> {
+        let dir: PathBuf = self.model_service.model_dir(model_id)?;
+        if let Some(cached) = self.models.lock().unwrap().get(model_id) {
+            return Ok(cached);
+        }
+        let models = self.models.clone();
+        let model_id = model_id.to_string();
+        let device = self.device;
+        tokio::task::spawn_blocking(move || {
+            let started = Instant::now();
+            let model = OnnxModel::load(&model_id, &dir, device)?;
+            let info = LoadedModel {
+                model_id: model_id.clone(),
+                device: model.device.to_string(),
+                memory_bytes: model.memory_bytes,
+                load_ms: started.elapsed().as_millis() as u64,
+            };
+            Ok(models.lock().unwrap().insert(CachedModel { model: Arc::new(Mutex::new(model)), info }))
+        }).await.map_err(backend_error)?
+    }
+}
+
+#[async_trait]
+impl EmbeddingBackend for OnnxEmbeddingBackend {
+    async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError> {
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.model_service.model_dir(model_id)?;
+        let queue = self.queues.lock().unwrap().entry(model_id.to_string()).or_default().clone();
+        let (reply, result) = oneshot::channel();
+        let leads = queue.push(PendingEmbed { texts: texts.to_vec(), reply }, self.batching.max_batch_size);
+        if leads {
+            // Spawned so a caller giving up doesn't strand the requests that joined it
+            let backend = self.clone();
+            let model_id = model_id.to_string();
+            tokio::spawn(async move {
+                let requests = queue.collect(backend.batching.max_wait).await;
+                let texts: Vec<String> = requests.iter().flat_map(|r| r.texts.iter().cloned()).collect();
+                let (limits, metrics) = (backend.batching.clone(), backend.metrics_service.clone());
+                let result = backend.with_model(&model_id, move |model, options| model.embed(&texts, &limits, options, metrics.as_deref())).await;
+                answer(requests, result);
+            });
+        }
+        result.await.map_err(backend_error)?
+    }
+
+    async fn rerank(&self, model_id: &str, query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError> {
+        if documents.is_empty() {
+            return Ok(Vec::new());
+        }
+        let query = query.to_string();
+        let documents = documents.to_vec();
+        let limits = self.batching.clone();
+        self.with_model(model_id, move |model, options| model.rerank(&query, &documents, &limits, options)).await
+    }
+
+    fn loaded_models(&self) -> Vec<LoadedModel> {
+        self.models.lock().unwrap().loaded()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::model::ModelConfig;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_pooling() {
+        // Two rows of three 2-dimensional tokens; the second row's last token is padding
+        let hidden = [1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 2.0, 0.0, 4.0, 0.0, 9.0, 9.0];
+        let mask = [1, 1, 1, 1, 1, 0];
+        assert_eq!(pool(&hidden, &mask, 3, 2, Pooling::Mean), vec![vec![3.0, 4.0], vec![3.0, 0.0]]);
+        assert_eq!(pool(&hidden, &mask, 3, 2, Pooling::Cls), vec![vec![1.0, 2.0], vec![2.0, 0.0]]);
+
+        let mut vector = vec![3.0, 4.0];
+        normalize(&mut vector);
+        assert_eq!(vector, vec![0.6, 0.8]);
+        let mut zero = vec![0.0, 0.0];
+        normalize(&mut zero);
+        assert_eq!(zero, vec![0.0, 0.0]);
+    }
+
+    #[test]
+    fn test_split_batches() {
+        // Row limit
+        assert_eq!(split_batches(&[4, 4, 4, 4, 4], 2, 1000), vec![0..2, 2..4, 4..5]);
+        // Padded token limit: 3 rows of 10 padded tokens exceed 25
+        assert_eq!(split_batches(&[5, 10, 5, 5], 64, 25), vec![0..2, 2..4]);
+        // A row over the token limit runs alone
+        assert_eq!(split_batches(&[3, 40, 3], 64, 25), vec![0..1, 1..2, 2..3]);
+        assert!(split_batches(&[], 64, 25).is_empty());
+    }
+
+    #[test]
+    fn test_device_selection() {
+        assert_eq!("auto".parse::<Device>(), Ok(Device::Auto));
+        assert_eq!(" CPU ".parse::<Device>(), Ok(Device::Cpu));
+        assert_eq!("cuda".parse::<Device>(), Ok(Device::Cuda(0)));
+        assert_eq!("cuda:1".parse::<Device>(), Ok(Device::Cuda(1)));
+        assert_eq!("directml:2".parse::<Device>(), Ok(Device::DirectMl(2)));
+        assert!("cuda:x".parse::<Device>().is_err());
+        assert!("cpu:0".parse::<Device>().is_err());
+        assert!("tpu".parse::<Device>().is_err());
+        assert_eq!(Device::Cuda(1).to_string().parse::<Device>(), Ok(Device::Cuda(1)));
+
+        // Auto probes every accelerator; CPU skips them; an explicit choice is tried alone
+        assert_eq!(Device::Auto.accelerators(), vec![Device::Cuda(0), Device::CoreMl, Device::DirectMl(0)]);
+        assert!(Device::Cpu.accelerators().is_empty());
+        assert_eq!(Device::Cuda(1).accelerators(), vec![Device::Cuda(1)]);
+
+        let limits = BatchConfig::default();
+        assert_eq!(limits.for_device(Device::Cpu).max_batch_size, 64);
+        assert_eq!(limits.for_device(Device::Cuda(0)).max_batch_size, 64 * ACCELERATOR_BATCH_SCALE);
+        assert_eq!(limits.for_device(Device::Cuda(0)).max_batch_tokens, 16_384 * ACCELERATOR_BATCH_SCALE);
+    }
+
+    #[test]
+    fn test_model_cache_evicts_least_recently_used() {
+        let entry = |model_id: &str, memory_bytes: u64| CachedModel {
+            model: Arc::new(Mutex::new(())),
+            info: LoadedModel { model_id: model_id.to_string(), device: "cpu".to_string(), memory_bytes, load_ms: 1 },
+        };
+        let ids = |cache: &ModelCache<()>| cache.loaded().into_iter().map(|m| m.model_id).collect::<Vec<_>>();
+        let mut cache = ModelCache::new(100);
+        cache.insert(entry("a", 40));
+        cache.insert(entry("b", 40));
+        assert_eq!(ids(&cache), vec!["a", "b"]);
+
+        // Using "a" makes "b" the least recently used, so "b" makes room for "c"
+        assert!(cache.get("a").is_some());
+        cache.insert(entry("c", 50));
+        assert_eq!(ids(&cache), vec!["a", "c"]);
+        assert!(cache.get("b").is_none());
+        assert_eq!(cache.memory_bytes(), 90);
+
+        // A concurrent load of a cached model keeps the cached one
+        let first = cache.get("c").unwrap();
+        let raced = cache.insert(entry("c", 10));
+        assert!(Arc::ptr_eq(&raced.model, &first.model));
+
+        // Dropping a timed-out model leaves a reloaded one alone
+        cache.remove(&raced);
+        assert_eq!(ids(&cache), vec!["a"]);
+        let reloaded = cache.insert(entry("c", 50));
+        cache.remove(&first);
+        assert!(Arc::ptr_eq(&cache.get("c").unwrap().model, &reloaded.model));
+
+        // A model over the whole budget loads alone
+        cache.insert(entry("d", 500));
+        assert_eq!(ids(&cache), vec!["d"]);
+    }
+
+    #[tokio::test]
+    async fn test_embed_queue_coalesces_requests() {
+        let queue = EmbedQueue::default();
+        let request = |texts: &[&str]| {
+            let (reply, result) = oneshot::channel();
+            (PendingEmbed { texts: texts.iter().map(|t| t.to_string()).collect(), reply }, result)
+        };
+        let (first, first_result) = request(&["a", "b"]);
+        let (second, second_result) = request(&["c"]);
+        assert!(queue.push(first, 3));
+        assert!(!queue.push(second, 3));
+
+        // Full at three texts, so the leader doesn't wait out max_wait
+        let started = Instant::now();
+        let requests = queue.collect(Duration::from_secs(10)).await;
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert_eq!(requests.len(), 2);
+
+        answer(requests, Ok(vec![vec![1.0], vec![2.0], vec![3.0]]));
+        assert_eq!(first_result.await.unwrap().unwrap(), vec![vec![1.0], vec![2.0]]);
+        assert_eq!(second_result.await.unwrap().unwrap(), vec![vec![3.0]]);
+
+        // The next request starts a new batch
+        let (third, third_result) = request(&["d"]);
+        assert!(queue.push(third, 3));
+        answer(queue.collect(Duration::from_millis(1)).await, Err(ModelError::ModelNotFound("org/tiny".to_string())));
+        assert!(matches!(third_result.await.unwrap(), Err(ModelError::ModelNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_missing_export() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = Arc::new(ModelService::new(ModelConfig::test_config(temp_dir.path(), "http://127.0.0.1:9")).unwrap());
+        let model_dir = service.model_dir("org/tiny").unwrap();
+        std::fs::create_dir_all(&model_dir).unwrap();
+        std::fs::write(model_dir.join(TOKENIZER_FILE), "{}").unwrap();
+        let backend = OnnxEmbeddingBackend::new(service);
+
+        let texts = vec!["hello".to_string()];
+        assert!(matches!(backend.embed("org/tiny", &texts).await, Err(ModelError::ModelNotFound(_))));
+        assert!(matches!(backend.embed("../etc", &texts).await, Err(ModelError::InvalidModelId(_))));
+        assert!(backend.rerank("org/tiny", "query", &[]).await.unwrap().is_empty());
+        assert!(backend.loaded_models().is_empty());
+    }
+}
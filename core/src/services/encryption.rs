@@ -0,0 +1,201 @@
+/*!
+ * Content Encryption
+ *
+ * Optional at-rest encryption for stored document content: blob store
+ * objects, the chunk text kept in KB content databases and BM25 index files,
+ * and cached values in the cache's disk tier. Values are sealed with
+ * AES-256-GCM under a content key held in the secrets vault, with the place
+ * the value is stored at (blob hash, chunk ID, cache key) as associated data
+ * so sealed values can't be swapped between rows.
+ *
+ * Lexical search keeps working over encrypted chunks through a blind index:
+ * each term is replaced by a keyed hash before it reaches FTS5, and queries
+ * are hashed the same way. Term frequencies stay visible; the text does not.
+ *
+ * Content written before encryption was enabled stays readable (it isn't
+ * sealed, so reads pass it through) and is sealed when next rewritten.
+ */
+
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::hmac;
+use ring::rand::{SecureRandom, SystemRandom};
+use thiserror::Error;
+use tracing::info;
+
+use super::secrets::{from_hex, to_hex, SecretsError, SecretsService};
+use super::storage::sha256_hex;
+
+/// Vault entry holding the content key
+pub const CONTENT_KEY_SECRET: &str = "content_encryption.aes256gcm";
+
+/// Leading bytes of a sealed binary value
+const SEALED_MAGIC: &[u8; 4] = b"RSE1";
+
+/// Prefix of a sealed text value (hex of the sealed bytes follows)
+const SEALED_TEXT_PREFIX: &str = "enc:v1:";
+
+const KEY_LEN: usize = 32;
+
+/// Blind index terms keep this many bytes of the keyed hash
+const BLIND_TOKEN_BYTES: usize = 12;
+
+/// Content Encryption Error Types
+#[derive(Debug, Error)]
+pub enum EncryptionError {
+    #[error("Secrets error: {0}")]
+    SecretsError(#[from] SecretsError),
+
+    #[error("Crypto error: {0}")]
+    CryptoError(String),
+
+    #[error("Content is encrypted, but content encryption is not enabled")]
+    KeyUnavailable,
+}
+
+/// Seals and opens stored content with the vault's content key
+pub struct ContentCipher {
+    key: LessSafeKey,
+    token_key: hmac::Key,
+    key_id: String,
+    rng: SystemRandom,
+}
+
+impl std::fmt::Debug for ContentCipher {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ContentCipher").field("key_id", &self.key_id).finish_non_exhaustive()
+    }
+}
+
+impl ContentCipher {
+    /// Load the content key from the vault, generating it on first use
+    pub fn load_or_create(secrets: &SecretsService) -> Result<Self, EncryptionError> {
+        let key = match secrets.get(CONTENT_KEY_SECRET) {
+            Ok(value) => from_hex(value.expose())?,
+            Err(SecretsError::NotFound(_)) => {
+                let mut key = [0u8; KEY_LEN];
+                SystemRandom::new().fill(&mut key)
+                    .map_err(|_| EncryptionError::CryptoError("System RNG unavailable".to_string()))?;
+                secrets.set(CONTENT_KEY_SECRET, &to_hex(&key))?;
+                info!("Generated content encryption key");
+                key.to_vec()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        Self::from_key(&key)
+    }
+
+    pub fn from_key(key: &[u8]) -> Result<Self, EncryptionError> {
+        if key.len() != KEY_LEN {
+            return Err(EncryptionError::CryptoError(format!("Content key must be {} bytes", KEY_LEN)));
+        }
+        let aead = UnboundKey::new(&AES_256_GCM, key)
+            .map(LessSafeKey::new)
+            .map_err(|_| EncryptionError::CryptoError("Invalid content key".to_string()))?;
+        // Separate key for the blind index, so term hashes reveal nothing about the content key
+        let token_key = hmac::sign(&hmac::Key::new(hmac::HMAC_SHA256, key), b"rag-studio blind index");
+        Ok(Self {
+            key: aead,
+            token_key: hmac::Key::new(hmac::HMAC_SHA256, token_key.as_ref()),
+            key_id: sha256_hex(key)[..16].to_string(),
+            rng: SystemRandom::new(),
+        })
+    }
+
+    /// Short identifier of the content key (never the key itself)
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Whether `data` was produced by `seal`
+    pub fn is_sealed(data: &[u8]) -> bool {
+        data.starts_with(SEALED_MAGIC)
+    }
+
+    /// `SEALED_MAGIC | nonce | ciphertext+tag`, bound to `context`
+    pub fn seal(&self, plaintext: &[u8], context: &str) -> Result<Vec<u8>, EncryptionError> {
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce)
+            .map_err(|_| EncryptionError::CryptoError("System RNG unavailable".to_string()))?;
+        let mut in_out = plaintext.to_vec();
+        self.key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(context.as_bytes()), &mut in_out)
+            .map_err(|_| EncryptionError::CryptoError(format!("Failed to encrypt {}", context)))?;
+
+        let mut sealed = Vec::with_capacity(SEALED_MAGIC.len() + NONCE_LEN + in_out.len());
+        sealed.extend_from_slice(SEALED_MAGIC);
+        sealed.extend_from_slice(&nonce);
+        sealed.extend_from_slice(&in_out);
+        Ok(sealed)
+    }
+
+    pub fn open(&self, sealed: &[u8], context: &str) -> Result<Vec<u8>, EncryptionError> {
+        let body = sealed.strip_prefix(SEALED_MAGIC.as_slice())
+            .filter(|body| body.len() >= NONCE_LEN)
+            .ok_or_else(|| EncryptionError::CryptoError(format!("{} is not sealed", context)))?;
+        let (nonce, ciphertext) = body.split_at(NONCE_LEN);
+        let nonce = Nonce::try_assume_unique_for_key(nonce)
+            .map_err(|_| EncryptionError::CryptoError(format!("Bad nonce for {}", context)))?;
+        let mut in_out = ciphertext.to_vec();
+        let plaintext = self.key.open_in_place(nonce, Aad::from(context.as_bytes()), &mut in_out)
+            .map_err(|_| EncryptionError::CryptoError(format!("Failed to decrypt {} (wrong key or tampered)", context)))?;
+        Ok(plaintext.to_vec())
+    }
+
+    /// Sealed text, safe to store in a TEXT column
+    pub fn seal_text(&self, plaintext: &str, context: &str) -> Result<String, EncryptionError> {
+        Ok(format!("{}{}", SEALED_TEXT_PREFIX, to_hex(&self.seal(plaintext.as_bytes(), context)?)))
+    }
+
+    /// Open text written by `seal_text`; anything else is plaintext and passes through
+    pub fn open_text(&self, text: &str, context: &str) -> Result<String, EncryptionError> {
+        let Some(hex) = text.strip_prefix(SEALED_TEXT_PREFIX) else {
+            return Ok(text.to_string());
+        };
+        let plaintext = self.open(&from_hex(hex)?, context)?;
+        String::from_utf8(plaintext)
+            .map_err(|_| EncryptionError::CryptoError(format!("{} is not valid UTF-8", context)))
+    }
+
+    /// Keyed hash standing in for a (lowercased) search term
+    pub fn blind_token(&self, term: &str) -> String {
+        let tag = hmac::sign(&self.token_key, term.to_lowercase().as_bytes());
+        format!("t{}", to_hex(&tag.as_ref()[..BLIND_TOKEN_BYTES]))
+    }
+}
+
+/// Whether `text` was written by `ContentCipher::seal_text`
+pub fn is_sealed_text(text: &str) -> bool {
+    text.starts_with(SEALED_TEXT_PREFIX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::secrets::SecretsConfig;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_seal_open_and_blind_tokens() {
+        let temp_dir = TempDir::new().unwrap();
+        let secrets = SecretsService::new(SecretsConfig::test_config(temp_dir.path())).unwrap();
+        let cipher = ContentCipher::load_or_create(&secrets).unwrap();
+
+        // The key is stored once and reused
+        assert_eq!(ContentCipher::load_or_create(&secrets).unwrap().key_id(), cipher.key_id());
+
+        let sealed = cipher.seal(b"quarterly numbers", "blob:abc").unwrap();
+        assert!(ContentCipher::is_sealed(&sealed));
+        assert_eq!(cipher.open(&sealed, "blob:abc").unwrap(), b"quarterly numbers");
+        // Bound to where it was stored
+        assert!(cipher.open(&sealed, "blob:def").is_err());
+
+        let text = cipher.seal_text("board minutes", "chunk_1").unwrap();
+        assert!(is_sealed_text(&text) && !text.contains("board"));
+        assert_eq!(cipher.open_text(&text, "chunk_1").unwrap(), "board minutes");
+        assert_eq!(cipher.open_text("written before encryption", "chunk_2").unwrap(), "written before encryption");
+
+        assert_eq!(cipher.blind_token("Revenue"), cipher.blind_token("revenue"));
+        assert_ne!(cipher.blind_token("revenue"), cipher.blind_token("profit"));
+        let other = ContentCipher::from_key(&[7u8; KEY_LEN]).unwrap();
+        assert_ne!(other.blind_token("revenue"), cipher.blind_token("revenue"));
+    }
+}
@@ -0,0 +1,398 @@
+/*!
+ * Generation Service Implementation
+ *
+ * Local text generation from GGUF models by driving a llama.cpp CLI
+ * subprocess (`llama-cli`), so answer tools and flows work fully offline.
+ * Tokens are streamed from the process's stdout as they are produced.
+ * Prompts are handed over in a private temp file (`-f`), not on the
+ * command line where other local users could read them.
+ * MVP: one process per request; upgrade path to a resident llama-server
+ * or in-process bindings once model load time matters.
+ */
+
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::AsyncReadExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::alerts::{AlertCategory, AlertService};
+
+/// Environment variable overriding the GGUF model path
+pub const MODEL_PATH_ENV: &str = "RAG_STUDIO_GGUF_MODEL";
+
+/// Environment variable overriding the llama.cpp binary
+pub const LLAMA_BINARY_ENV: &str = "RAG_STUDIO_LLAMA_CLI";
+
+/// Generation Service Error Types
+#[derive(Debug, Error)]
+pub enum GenerationError {
+    #[error("No generation model configured (set {MODEL_PATH_ENV})")]
+    ModelNotConfigured,
+
+    #[error("Model file not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Failed to start llama.cpp: {0}")]
+    SpawnFailed(#[from] std::io::Error),
+
+    #[error("llama.cpp exited with {code:?}: {stderr}")]
+    ProcessFailed { code: Option<i32>, stderr: String },
+
+    #[error("Generation timed out after {0}s")]
+    Timeout(u64),
+}
+
+/// Generation configuration
+#[derive(Debug, Clone)]
+pub struct GenerationConfig {
+    pub binary_path: PathBuf,         // llama.cpp CLI
+    pub model_path: Option<PathBuf>,  // GGUF file
+    pub context_size: u32,
+    pub threads: Option<u32>,         // llama.cpp picks when unset
+    pub timeout_secs: u64,
+}
+
+impl Default for GenerationConfig {
+    fn default() -> Self {
+        Self {
+            binary_path: std::env::var(LLAMA_BINARY_ENV).map(PathBuf::from).unwrap_or_else(|_| PathBuf::from("llama-cli")),
+            model_path: std::env::var(MODEL_PATH_ENV).ok().map(PathBuf::from),
+            context_size: 4096,
+            threads: None,
+            timeout_secs: 120,
+        }
+    }
+}
+
+/// Sampling parameters for one request
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenerationParams {
+    pub max_tokens: u32,
+    pub temperature: f32,
+    pub top_p: f32,
+    pub stop: Vec<String>,       // Output is cut at the first match
+    pub seed: Option<u64>,
+}
+
+impl Default for GenerationParams {
+    fn default() -> Self {
+        Self {
+            max_tokens: 512,
+            temperature: 0.2,
+            top_p: 0.95,
+            stop: Vec::new(),
+            seed: None,
+        }
+    }
+}
+
+/// Completed generation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationOutput {
+    pub text: String,
+    pub model: String,
+    pub duration_ms: u64,
+}
+
+/// Local LLM generation service
+pub struct GenerationService {
+    config: GenerationConfig,
+    alert_service: Option<Arc<AlertService>>,   // Told when llama.cpp crashes
+}
+
+impl GenerationService {
+    pub fn new(config: GenerationConfig) -> Self {
+        Self { config, alert_service: None }
+    }
+
+    pub fn with_alert_service(mut self, alert_service: Arc<AlertService>) -> Self {
+        self.alert_service = Some(alert_service);
+        self
+    }
+
+    /// True when a model file is configured and present
+    pub fn is_available(&self) -> bool {
+        self.model_path().is_ok()
+    }
+
+    fn model_path(&self) -> Result<&Path, GenerationError> {
+        let path = self.config.model_path.as_deref().ok_or(GenerationError::ModelNotConfigured)?;
+        if !path.is_file() {
+            return Err(GenerationError::ModelNotFound(path.display().to_string()));
+        }
+        Ok(path)
+    }
+
+    /// Model name reported with outputs (file stem of the GGUF)
+    pub fn model_name(&self) -> Option<String> {
+        self.config.model_path.as_ref()
+            .and_then(|p| p.file_stem())
+            .map(|s| s.to_string_lossy().to_string())
+    }
+
+    /// Stream generated text fragments; the channel closes when generation ends
+    pub async fn generate_stream(
+        &self,
+        prompt: &str,
+        params: &GenerationParams,
+    ) -> Result<mpsc::Receiver<Result<String, GenerationError>>, GenerationError> {
+        let model_path = self.model_path()?;
+        // Owner-only file, removed once the process has exited
+        let mut prompt_file = tempfile::NamedTempFile::new()?;
+        std::io::Write::write_all(&mut prompt_file, prompt.as_bytes())?;
+
+        let mut command = Command::new(&self.config.binary_path);
+        command
+            .arg("-m").arg(model_path)
+            .arg("-f").arg(prompt_file.path())
+            .arg("-n").arg(params.max_tokens.to_string())
+            .arg("-c").arg(self.config.context_size.to_string())
+            .arg("--temp").arg(params.temperature.to_string())
+            .arg("--top-p").arg(params.top_p.to_string())
+            .args(["--no-display-prompt", "-no-cnv"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+        if let Some(threads) = self.config.threads {
+            command.arg("-t").arg(threads.to_string());
+        }
+        if let Some(seed) = params.seed {
+            command.arg("--seed").arg(seed.to_string());
+        }
+
+        debug!("Starting llama.cpp with model {}", model_path.display());
+        let mut child = command.spawn()?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let mut stderr = child.stderr.take().expect("stderr is piped");
+        let timeout = Duration::from_secs(self.config.timeout_secs);
+        let timeout_secs = self.config.timeout_secs;
+
+        let (tx, rx) = mpsc::channel(64);
+        let alert_service = self.alert_service.clone();
+        let model_name = self.model_name().unwrap_or_default();
+        tokio::spawn(async move {
+            let stderr_task = tokio::spawn(async move {
+                let mut buf = String::new();
+                let _ = stderr.read_to_string(&mut buf).await;
+                buf
+            });
+
+            let run = async {
+                let mut buf = [0u8; 1024];
+                let mut pending = Vec::new();
+                loop {
+                    let n = stdout.read(&mut buf).await?;
+                    if n == 0 {
+                        break;
+                    }
+                    pending.extend_from_slice(&buf[..n]);
+                    let fragment = drain_utf8(&mut pending);
+                    if !fragment.is_empty() && tx.send(Ok(fragment)).await.is_err() {
+                        return Ok(None); // Receiver dropped, stop generating
+                    }
+                }
+                // A sequence cut off by the end of output
+                if !pending.is_empty() {
+                    let _ = tx.send(Ok(String::from_utf8_lossy(&pending).to_string())).await;
+                }
+                child.wait().await.map(Some)
+            };
+
+            match tokio::time::timeout(timeout, run).await {
+                Ok(Ok(Some(status))) if !status.success() => {
+                    let stderr = stderr_task.await.unwrap_or_default();
+                    let tail: String = stderr.lines().rev().take(5).collect::<Vec<_>>().into_iter().rev().collect::<Vec<_>>().join("\n");
+                    if let Some(alerts) = &alert_service {
+                        alerts.notify(
+                            AlertCategory::WorkerCrash,
+                            "Local model crashed",
+                            &format!("llama.cpp exited with {:?} while generating with {}", status.code(), model_name),
+                            Some(&model_name),
+                            serde_json::json!({ "exit_code": status.code(), "stderr": tail.clone() }),
+                        ).await;
+                    }
+                    let _ = tx.send(Err(GenerationError::ProcessFailed { code: status.code(), stderr: tail })).await;
+                }
+                Ok(Ok(_)) => {}
+                Ok(Err(e)) => {
+                    let _ = tx.send(Err(GenerationError::SpawnFailed(e))).await;
+                }
+                Err(_) => {
+                    warn!("Generation timed out after {}s", timeout_secs);
+                    let _ = tx.send(Err(GenerationError::Timeout(timeout_secs))).await;
+                }
+            }
+            drop(prompt_file);
+        });
+
+        Ok(rx)
+    }
+
+    /// Generate a complete answer
+    pub async fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<GenerationOutput, GenerationError> {
+        let started = std::time::Instant::now();
+        let mut stream = self.generate_stream(prompt, params).await?;
+
+        let mut text = String::new();
+        while let Some(fragment) = stream.recv().await {
+            text.push_str(&fragment?);
+            if let Some(end) = find_stop(&text, &params.stop) {
+                text.truncate(end);
+                break; // Dropping the stream stops the process
+            }
+        }
+
+        let output = GenerationOutput {
+            text: text.trim().to_string(),
+            model: self.model_name().unwrap_or_default(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        };
+        info!("Generated {} chars in {}ms", output.text.len(), output.duration_ms);
+        Ok(output)
+    }
+}
+
+/// Decode the complete UTF-8 prefix of `pending`, replacing invalid bytes
+/// with U+FFFD; a sequence cut off at the end stays pending for the next read
+fn drain_utf8(pending: &mut Vec<u8>) -> String {
+    let mut text = String::new();
+    loop {
+        match std::str::from_utf8(pending) {
+            Ok(valid) => {
+                text.push_str(valid);
+                pending.clear();
+                return text;
+            }
+            Err(e) => {
+                let valid = e.valid_up_to();
+                text.push_str(std::str::from_utf8(&pending[..valid]).expect("prefix is valid UTF-8"));
+                match e.error_len() {
+                    Some(invalid) => {
+                        text.push(char::REPLACEMENT_CHARACTER);
+                        pending.drain(..valid + invalid);
+                    }
+                    None => {
+                        pending.drain(..valid);
+                        return text;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Byte offset of the earliest stop sequence
+fn find_stop(text: &str, stop: &[String]) -> Option<usize> {
+    stop.iter()
+        .filter(|s| !s.is_empty())
+        .filter_map(|s| text.find(s.as_str()))
+        .min()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    /// Fake llama.cpp that prints its canned output
+    #[cfg(unix)]
+    fn fake_binary(dir: &Path, script: &str) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = dir.join("llama-cli");
+        std::fs::write(&path, format!("#!/bin/sh\n{}\n", script)).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        path
+    }
+
+    fn config(dir: &Path, binary_path: PathBuf) -> GenerationConfig {
+        let model_path = dir.join("tiny.gguf");
+        std::fs::write(&model_path, b"GGUF").unwrap();
+        GenerationConfig {
+            binary_path,
+            model_path: Some(model_path),
+            context_size: 512,
+            threads: Some(1),
+            timeout_secs: 10,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_model_required() {
+        let service = GenerationService::new(GenerationConfig { model_path: None, ..Default::default() });
+        assert!(!service.is_available());
+        assert!(matches!(service.generate("hi", &GenerationParams::default()).await, Err(GenerationError::ModelNotConfigured)));
+
+        let service = GenerationService::new(GenerationConfig { model_path: Some("/missing.gguf".into()), ..Default::default() });
+        assert!(matches!(service.generate("hi", &GenerationParams::default()).await, Err(GenerationError::ModelNotFound(_))));
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_generate_with_stop_sequence() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = fake_binary(temp_dir.path(), "printf 'Install it [1].\\nQuestion: more'");
+        let service = GenerationService::new(config(temp_dir.path(), binary));
+
+        let params = GenerationParams { stop: vec!["Question:".to_string()], ..Default::default() };
+        let output = service.generate("prompt", &params).await.unwrap();
+        assert_eq!(output.text, "Install it [1].");
+        assert_eq!(output.model, "tiny");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_prompt_passed_by_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = fake_binary(temp_dir.path(), r#"case "$*" in *secret*) echo leaked; exit 1;; esac
+while [ "$1" != "-f" ]; do shift; done; cat "$2""#);
+        let service = GenerationService::new(config(temp_dir.path(), binary));
+
+        let output = service.generate("secret prompt", &GenerationParams::default()).await.unwrap();
+        assert_eq!(output.text, "secret prompt");
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_invalid_utf8_does_not_stall() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = fake_binary(temp_dir.path(), r"printf 'caf\303\251 \377 ok \342\202'");
+        let service = GenerationService::new(config(temp_dir.path(), binary));
+
+        let output = service.generate("prompt", &GenerationParams::default()).await.unwrap();
+        assert_eq!(output.text, "café \u{FFFD} ok \u{FFFD}");
+    }
+
+    #[test]
+    fn test_drain_utf8_keeps_cut_off_sequence() {
+        let mut pending = vec![b'a', 0xff, b'b', 0xe2, 0x82];
+        assert_eq!(drain_utf8(&mut pending), "a\u{FFFD}b");
+        assert_eq!(pending, vec![0xe2, 0x82]);
+        pending.push(0xac);
+        assert_eq!(drain_utf8(&mut pending), "€");
+        assert!(pending.is_empty());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_process_failure() {
+        let temp_dir = TempDir::new().unwrap();
+        let binary = fake_binary(temp_dir.path(), "echo 'failed to load model' >&2; exit 3");
+        let service = GenerationService::new(config(temp_dir.path(), binary));
+
+        match service.generate("prompt", &GenerationParams::default()).await {
+            Err(GenerationError::ProcessFailed { code, stderr }) => {
+                assert_eq!(code, Some(3));
+                assert!(stderr.contains("failed to load model"));
+            }
+            other => panic!("Expected process failure, got {:?}", other),
+        }
+    }
+}
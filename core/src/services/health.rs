@@ -0,0 +1,236 @@
+/*!
+ * Health Aggregation
+ *
+ * Folds per-subsystem probes (SQL, vector store, BM25 index, cache, storage
+ * quota, model service, embedding worker, MCP subprocess) into one health
+ * report. Each probe is timed and bounded by a timeout; the last error a
+ * subsystem reported is kept after it recovers so the dashboard can show
+ * what went wrong. Status changes are broadcast as `HealthDelta`s.
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::RwLock;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast;
+use tracing::{info, warn};
+
+/// Longest a single probe may take before it counts as unhealthy
+pub const DEFAULT_PROBE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// How often the Manager re-probes every subsystem
+pub const DEFAULT_HEALTH_INTERVAL_SECS: u64 = 30;
+
+/// Buffered deltas per subscriber before it lags
+const HEALTH_CHANNEL_CAPACITY: usize = 64;
+
+/// Status of one subsystem, or of the app as a whole.
+/// Ordered from least to most severe; `Disabled` never affects the overall status.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HealthState {
+    Disabled,       // Not configured or not running by choice
+    Healthy,
+    Degraded,       // Working, with reduced capability (e.g. fallback index)
+    Unhealthy,
+}
+
+/// What a probe found, before timing and history are added
+#[derive(Debug, Clone)]
+pub struct ProbeResult {
+    pub status: HealthState,
+    pub error: Option<String>,
+    pub details: serde_json::Value,
+}
+
+impl ProbeResult {
+    pub fn healthy(details: serde_json::Value) -> Self {
+        Self { status: HealthState::Healthy, error: None, details }
+    }
+
+    pub fn degraded(reason: impl Into<String>, details: serde_json::Value) -> Self {
+        Self { status: HealthState::Degraded, error: Some(reason.into()), details }
+    }
+
+    pub fn unhealthy(error: impl Into<String>) -> Self {
+        Self { status: HealthState::Unhealthy, error: Some(error.into()), details: serde_json::Value::Null }
+    }
+
+    pub fn disabled(reason: &str) -> Self {
+        Self { status: HealthState::Disabled, error: None, details: serde_json::json!({ "reason": reason }) }
+    }
+}
+
+/// Latest health of one subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubsystemHealth {
+    pub name: String,
+    pub status: HealthState,
+    pub latency_ms: u64,                        // Time the probe took
+    pub last_error: Option<String>,             // Kept after recovery
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub details: serde_json::Value,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Health of every subsystem
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthReport {
+    pub overall: HealthState,
+    pub subsystems: Vec<SubsystemHealth>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthReport {
+    pub fn subsystem(&self, name: &str) -> Option<&SubsystemHealth> {
+        self.subsystems.iter().find(|s| s.name == name)
+    }
+}
+
+/// A subsystem whose status changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthDelta {
+    pub subsystem: String,
+    pub previous: Option<HealthState>,  // None on the first probe
+    pub current: SubsystemHealth,
+}
+
+/// Keeps the latest probe of every subsystem and broadcasts status changes
+pub struct HealthMonitor {
+    latest: RwLock<HashMap<String, SubsystemHealth>>,
+    deltas: broadcast::Sender<HealthDelta>,
+    probe_timeout: Duration,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self::new(DEFAULT_PROBE_TIMEOUT)
+    }
+}
+
+impl HealthMonitor {
+    pub fn new(probe_timeout: Duration) -> Self {
+        Self {
+            latest: RwLock::new(HashMap::new()),
+            deltas: broadcast::channel(HEALTH_CHANNEL_CAPACITY).0,
+            probe_timeout,
+        }
+    }
+
+    /// Every status change, including a subsystem's first probe
+    pub fn subscribe(&self) -> broadcast::Receiver<HealthDelta> {
+        self.deltas.subscribe()
+    }
+
+    /// Run and time a probe, then record its result
+    pub async fn probe<F>(&self, name: &str, probe: F) -> SubsystemHealth
+    where
+        F: Future<Output = ProbeResult>,
+    {
+        let started = Instant::now();
+        let result = tokio::time::timeout(self.probe_timeout, probe).await
+            .unwrap_or_else(|_| ProbeResult::unhealthy(format!("probe timed out after {:?}", self.probe_timeout)));
+        self.record(name, result, started.elapsed())
+    }
+
+    /// Record a probe result, carrying the last error forward
+    pub fn record(&self, name: &str, result: ProbeResult, latency: Duration) -> SubsystemHealth {
+        let now = Utc::now();
+        let mut latest = self.latest.write().unwrap();
+        let previous = latest.get(name);
+
+        let (last_error, last_error_at) = match &result.error {
+            Some(error) => (Some(error.clone()), Some(now)),
+            None => (
+                previous.and_then(|p| p.last_error.clone()),
+                previous.and_then(|p| p.last_error_at),
+            ),
+        };
+        let health = SubsystemHealth {
+            name: name.to_string(),
+            status: result.status,
+            latency_ms: latency.as_millis() as u64,
+            last_error,
+            last_error_at,
+            details: result.details,
+            checked_at: now,
+        };
+
+        let previous_status = previous.map(|p| p.status);
+        if previous_status != Some(health.status) {
+            match health.status {
+                HealthState::Degraded | HealthState::Unhealthy => warn!(
+                    "{} is {:?}: {}", name, health.status, health.last_error.as_deref().unwrap_or("")
+                ),
+                _ => info!("{} is {:?}", name, health.status),
+            }
+            // No receivers is fine: the report has it too
+            let _ = self.deltas.send(HealthDelta {
+                subsystem: name.to_string(),
+                previous: previous_status,
+                current: health.clone(),
+            });
+        }
+
+        latest.insert(name.to_string(), health.clone());
+        health
+    }
+
+    /// Report over the given subsystems; the overall status is the worst enabled one
+    pub fn report(&self, subsystems: Vec<SubsystemHealth>) -> HealthReport {
+        let overall = subsystems.iter()
+            .map(|s| s.status)
+            .filter(|s| *s != HealthState::Disabled)
+            .max()
+            .unwrap_or(HealthState::Healthy);
+        HealthReport { overall, subsystems, checked_at: Utc::now() }
+    }
+
+    /// Report from the last probe of every subsystem, without probing again
+    pub fn latest(&self) -> HealthReport {
+        let mut subsystems: Vec<SubsystemHealth> = self.latest.read().unwrap().values().cloned().collect();
+        subsystems.sort_by(|a, b| a.name.cmp(&b.name));
+        self.report(subsystems)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_probe_tracks_errors_and_changes() {
+        let monitor = HealthMonitor::new(Duration::from_millis(50));
+        let mut deltas = monitor.subscribe();
+
+        let sql = monitor.probe("sql", async { ProbeResult::healthy(serde_json::json!({})) }).await;
+        assert_eq!(sql.status, HealthState::Healthy);
+        assert_eq!(deltas.try_recv().unwrap().previous, None);
+
+        // Same status again is not a change
+        monitor.probe("sql", async { ProbeResult::healthy(serde_json::json!({})) }).await;
+        assert!(deltas.try_recv().is_err());
+
+        let failed = monitor.probe("sql", async { ProbeResult::unhealthy("database is locked") }).await;
+        assert_eq!(failed.last_error.as_deref(), Some("database is locked"));
+        assert_eq!(deltas.try_recv().unwrap().previous, Some(HealthState::Healthy));
+
+        // Recovery keeps the last error for the dashboard
+        let recovered = monitor.probe("sql", async { ProbeResult::healthy(serde_json::json!({})) }).await;
+        assert_eq!(recovered.status, HealthState::Healthy);
+        assert_eq!(recovered.last_error.as_deref(), Some("database is locked"));
+
+        let slow = monitor.probe("cache", async {
+            tokio::time::sleep(Duration::from_secs(1)).await;
+            ProbeResult::healthy(serde_json::json!({}))
+        }).await;
+        assert_eq!(slow.status, HealthState::Unhealthy);
+
+        monitor.probe("mcp", async { ProbeResult::disabled("not started") }).await;
+        let report = monitor.latest();
+        assert_eq!(report.subsystems.len(), 3);
+        assert_eq!(report.overall, HealthState::Unhealthy);
+    }
+}
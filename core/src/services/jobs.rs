@@ -0,0 +1,506 @@
+/*!
+ * Background Job Service
+ *
+ * One place for long-running operations (pipeline runs, model downloads, KB
+ * exports, backups, reindexes, index maintenance) instead of ad-hoc spawned
+ * tasks. Jobs wait in a queue for a slot under the global concurrency limit
+ * and their kind's own limit, report progress, can be cancelled while queued
+ * or running, and are broadcast to `subscribe` receivers on every change so
+ * the UI can show one background tasks panel.
+ *
+ * Job records are persisted to a JSON file on every status change. A job
+ * can't be resumed after a restart (its work is a future, not data), so jobs
+ * still queued or running when the app stopped come back as `Interrupted`.
+ */
+
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::PathBuf;
+use std::pin::Pin;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::{broadcast, oneshot, watch, OwnedSemaphorePermit, Semaphore};
+use tracing::{info, warn};
+
+/// Job history file, kept in the project root
+pub const DEFAULT_JOBS_FILE: &str = "rag_studio.jobs.json";
+
+/// Finished jobs kept in the history; the oldest are dropped first
+pub const MAX_JOB_HISTORY: usize = 200;
+
+/// Buffered updates per subscriber before it lags
+const JOB_EVENT_CAPACITY: usize = 256;
+
+/// Background Job Error Types
+#[derive(Debug, Error)]
+pub enum JobError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed job history: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Job not found: {0}")]
+    NotFound(String),
+
+    #[error("Job {0} was cancelled")]
+    Cancelled(String),
+
+    #[error("{0}")]
+    Failed(String),
+}
+
+/// What a job does; each kind has its own concurrency limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    PipelineRun,
+    ModelDownload,
+    KbExport,
+    Backup,
+    Reindex,
+    IndexMaintenance,   // BM25 rebuilds and index optimization
+}
+
+impl JobKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobKind::PipelineRun => "pipeline_run",
+            JobKind::ModelDownload => "model_download",
+            JobKind::KbExport => "kb_export",
+            JobKind::Backup => "backup",
+            JobKind::Reindex => "reindex",
+            JobKind::IndexMaintenance => "index_maintenance",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Completed,
+    Failed,
+    Cancelled,
+    Interrupted,    // The app stopped while the job was queued or running
+}
+
+impl JobStatus {
+    pub fn is_finished(&self) -> bool {
+        !matches!(self, JobStatus::Queued | JobStatus::Running)
+    }
+}
+
+/// A job as shown in the background tasks panel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct JobRecord {
+    pub id: String,
+    pub kind: JobKind,
+    pub title: String,
+    pub status: JobStatus,
+    pub progress: f32,              // 0-1
+    pub message: Option<String>,    // Latest progress note
+    pub error: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub completed_at: Option<DateTime<Utc>>,
+}
+
+/// Job service configuration
+#[derive(Debug, Clone)]
+pub struct JobConfig {
+    pub store_path: Option<PathBuf>,            // None keeps the history in memory only
+    pub max_concurrent: usize,                  // Jobs running at once, across kinds
+    pub kind_limits: HashMap<JobKind, usize>,   // Kinds not listed only share the global limit
+}
+
+impl Default for JobConfig {
+    fn default() -> Self {
+        Self {
+            store_path: None,
+            max_concurrent: 4,
+            kind_limits: HashMap::from([
+                (JobKind::PipelineRun, 1),
+                (JobKind::ModelDownload, 2),
+                (JobKind::Backup, 1),
+                (JobKind::Reindex, 1),
+                (JobKind::IndexMaintenance, 1),
+            ]),
+        }
+    }
+}
+
+type JobFuture = Pin<Box<dyn Future<Output = Result<(), String>> + Send>>;
+
+/// Handed to a job's work: progress reporting and cancellation
+pub struct JobContext {
+    id: String,
+    service: Arc<JobService>,
+    cancelled: watch::Receiver<bool>,
+}
+
+impl JobContext {
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// Report progress (0-1) with an optional note
+    pub fn set_progress(&self, progress: f32, message: Option<&str>) {
+        self.service.update(&self.id, false, |job| {
+            job.progress = progress.clamp(0.0, 1.0);
+            if let Some(message) = message {
+                job.message = Some(message.to_string());
+            }
+        });
+    }
+
+    /// For work that checks between steps; the job is also dropped at its
+    /// next await point once cancelled
+    pub fn is_cancelled(&self) -> bool {
+        *self.cancelled.borrow()
+    }
+}
+
+/// Queue, concurrency limits, progress and cancellation for background jobs
+pub struct JobService {
+    config: JobConfig,
+    jobs: RwLock<Vec<JobRecord>>,                       // Oldest first
+    cancels: Mutex<HashMap<String, watch::Sender<bool>>>,  // Jobs not finished yet
+    tasks: Mutex<HashMap<String, tokio::task::JoinHandle<()>>>,
+    global_slots: Arc<Semaphore>,
+    kind_slots: HashMap<JobKind, Arc<Semaphore>>,
+    shutting_down: AtomicBool,
+    events: broadcast::Sender<JobRecord>,
+}
+
+impl JobService {
+    /// Load the job history; jobs left unfinished by the last run are marked interrupted
+    pub fn open(config: JobConfig) -> Result<Self, JobError> {
+        let mut jobs: Vec<JobRecord> = match &config.store_path {
+            Some(path) if path.exists() => serde_json::from_slice(&std::fs::read(path)?)?,
+            _ => Vec::new(),
+        };
+        let mut interrupted = 0;
+        for job in jobs.iter_mut().filter(|job| !job.status.is_finished()) {
+            job.status = JobStatus::Interrupted;
+            job.error = Some("RAG Studio closed before the job finished".to_string());
+            job.completed_at.get_or_insert_with(Utc::now);
+            interrupted += 1;
+        }
+        if interrupted > 0 {
+            info!("{} background jobs were interrupted by the last shutdown", interrupted);
+        }
+
+        let service = Self {
+            global_slots: Arc::new(Semaphore::new(config.max_concurrent.max(1))),
+            kind_slots: config.kind_limits.iter()
+                .map(|(kind, limit)| (*kind, Arc::new(Semaphore::new((*limit).max(1)))))
+                .collect(),
+            config,
+            jobs: RwLock::new(jobs),
+            cancels: Mutex::new(HashMap::new()),
+            tasks: Mutex::new(HashMap::new()),
+            shutting_down: AtomicBool::new(false),
+            events: broadcast::channel(JOB_EVENT_CAPACITY).0,
+        };
+        service.save();
+        Ok(service)
+    }
+
+    /// Every job change, progress updates included
+    pub fn subscribe(&self) -> broadcast::Receiver<JobRecord> {
+        self.events.subscribe()
+    }
+
+    /// All known jobs, newest first
+    pub fn list(&self) -> Vec<JobRecord> {
+        self.jobs.read().unwrap().iter().rev().cloned().collect()
+    }
+
+    pub fn get(&self, id: &str) -> Result<JobRecord, JobError> {
+        self.jobs.read().unwrap().iter()
+            .find(|job| job.id == id)
+            .cloned()
+            .ok_or_else(|| JobError::NotFound(id.to_string()))
+    }
+
+    /// Queue `work` and return at once
+    pub fn spawn<F, Fut>(self: &Arc<Self>, kind: JobKind, title: &str, work: F) -> JobRecord
+    where
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<(), String>> + Send + 'static,
+    {
+        self.start(kind, title, Box::new(move |ctx| -> JobFuture { Box::pin(work(ctx)) }))
+    }
+
+    /// Queue `work` and wait for its result. The job keeps running if the
+    /// caller stops waiting; only `cancel` stops it.
+    pub async fn run<T, F, Fut>(self: &Arc<Self>, kind: JobKind, title: &str, work: F) -> Result<T, JobError>
+    where
+        T: Send + 'static,
+        F: FnOnce(JobContext) -> Fut + Send + 'static,
+        Fut: Future<Output = Result<T, String>> + Send + 'static,
+    {
+        let (tx, rx) = oneshot::channel();
+        let job = self.spawn(kind, title, move |ctx| async move {
+            let value = work(ctx).await?;
+            let _ = tx.send(value);
+            Ok(())
+        });
+        match rx.await {
+            Ok(value) => Ok(value),
+            Err(_) => {
+                let job = self.get(&job.id)?;
+                match job.status {
+                    JobStatus::Failed => Err(JobError::Failed(job.error.unwrap_or_default())),
+                    _ => Err(JobError::Cancelled(job.id)),
+                }
+            }
+        }
+    }
+
+    /// Cancel a queued or running job; finished jobs are returned unchanged
+    pub fn cancel(&self, id: &str) -> Result<JobRecord, JobError> {
+        let job = self.get(id)?;
+        if let Some(cancel) = self.cancels.lock().unwrap().get(id) {
+            cancel.send_replace(true);
+            info!("Cancelling job {} ({})", id, job.title);
+        }
+        Ok(job)
+    }
+
+    /// Drop finished jobs from the history; returns how many were removed
+    pub fn clear_finished(&self) -> usize {
+        let removed = {
+            let mut jobs = self.jobs.write().unwrap();
+            let before = jobs.len();
+            jobs.retain(|job| !job.status.is_finished());
+            before - jobs.len()
+        };
+        self.save();
+        removed
+    }
+
+    /// Stop every job; they are recorded as interrupted rather than cancelled
+    pub async fn shutdown(&self) {
+        self.shutting_down.store(true, Ordering::SeqCst);
+        for cancel in self.cancels.lock().unwrap().values() {
+            cancel.send_replace(true);
+        }
+        let tasks: Vec<_> = self.tasks.lock().unwrap().drain().map(|(_, task)| task).collect();
+        for task in tasks {
+            let _ = task.await;
+        }
+        self.save();
+    }
+
+    fn start(
+        self: &Arc<Self>,
+        kind: JobKind,
+        title: &str,
+        work: Box<dyn FnOnce(JobContext) -> JobFuture + Send>,
+    ) -> JobRecord {
+        let job = JobRecord {
+            id: format!("job_{}", uuid::Uuid::new_v4().simple()),
+            kind,
+            title: title.to_string(),
+            status: JobStatus::Queued,
+            progress: 0.0,
+            message: None,
+            error: None,
+            created_at: Utc::now(),
+            started_at: None,
+            completed_at: None,
+        };
+        let (cancel_tx, mut cancel_rx) = watch::channel(false);
+        self.cancels.lock().unwrap().insert(job.id.clone(), cancel_tx);
+        self.jobs.write().unwrap().push(job.clone());
+        self.changed(&job, true);
+        if self.shutting_down.load(Ordering::SeqCst) {
+            self.cancel(&job.id).ok();
+        }
+
+        let service = Arc::clone(self);
+        let id = job.id.clone();
+        let task = tokio::spawn(async move {
+            let slots = tokio::select! {
+                biased;
+                _ = cancellation(&mut cancel_rx) => None,
+                slots = service.acquire(kind) => Some(slots),
+            };
+            let outcome = match slots {
+                None => Err(None),
+                Some(_slots) => {
+                    service.update(&id, true, |job| {
+                        job.status = JobStatus::Running;
+                        job.started_at = Some(Utc::now());
+                    });
+                    let ctx = JobContext { id: id.clone(), service: service.clone(), cancelled: cancel_rx.clone() };
+                    tokio::select! {
+                        biased;
+                        _ = cancellation(&mut cancel_rx) => Err(None),
+                        result = work(ctx) => result.map_err(Some),
+                    }
+                }
+            };
+            service.finish(&id, outcome);
+        });
+        // The task may already have finished (and not be worth keeping)
+        if !task.is_finished() {
+            self.tasks.lock().unwrap().insert(job.id.clone(), task);
+        }
+        job
+    }
+
+    /// Kind slot first, so a job waiting on its kind doesn't hold a global one
+    async fn acquire(&self, kind: JobKind) -> (Option<OwnedSemaphorePermit>, Option<OwnedSemaphorePermit>) {
+        let kind_slot = match self.kind_slots.get(&kind) {
+            Some(slots) => slots.clone().acquire_owned().await.ok(),
+            None => None,
+        };
+        (kind_slot, self.global_slots.clone().acquire_owned().await.ok())
+    }
+
+    /// `Err(None)` is a cancellation
+    fn finish(&self, id: &str, outcome: Result<(), Option<String>>) {
+        let interrupted = self.shutting_down.load(Ordering::SeqCst);
+        self.update(id, true, |job| {
+            job.completed_at = Some(Utc::now());
+            match &outcome {
+                Ok(()) => {
+                    job.status = JobStatus::Completed;
+                    job.progress = 1.0;
+                }
+                Err(Some(error)) => {
+                    job.status = JobStatus::Failed;
+                    job.error = Some(error.clone());
+                }
+                Err(None) if interrupted => {
+                    job.status = JobStatus::Interrupted;
+                    job.error = Some("RAG Studio closed before the job finished".to_string());
+                }
+                Err(None) => job.status = JobStatus::Cancelled,
+            }
+        });
+        self.cancels.lock().unwrap().remove(id);
+        self.tasks.lock().unwrap().remove(id);
+        match outcome {
+            Ok(()) => info!("Job {} completed", id),
+            Err(Some(error)) => warn!("Job {} failed: {}", id, error),
+            Err(None) => info!("Job {} stopped before finishing", id),
+        }
+    }
+
+    fn update(&self, id: &str, persist: bool, change: impl FnOnce(&mut JobRecord)) {
+        let job = {
+            let mut jobs = self.jobs.write().unwrap();
+            let Some(job) = jobs.iter_mut().find(|job| job.id == id) else {
+                return;
+            };
+            change(job);
+            job.clone()
+        };
+        self.changed(&job, persist);
+    }
+
+    fn changed(&self, job: &JobRecord, persist: bool) {
+        let _ = self.events.send(job.clone());
+        if persist {
+            self.save();
+        }
+    }
+
+    /// Write the history, dropping the oldest finished jobs past `MAX_JOB_HISTORY`
+    fn save(&self) {
+        let Some(path) = &self.config.store_path else {
+            return;
+        };
+        let result = (|| -> Result<(), JobError> {
+            let json = {
+                let mut jobs = self.jobs.write().unwrap();
+                let mut excess = jobs.len().saturating_sub(MAX_JOB_HISTORY);
+                jobs.retain(|job| {
+                    let drop = excess > 0 && job.status.is_finished();
+                    excess -= drop as usize;
+                    !drop
+                });
+                serde_json::to_vec_pretty(&*jobs)?
+            };
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let tmp = path.with_extension("json.tmp");
+            std::fs::write(&tmp, json)?;
+            std::fs::rename(&tmp, path)?;
+            Ok(())
+        })();
+        if let Err(e) = result {
+            warn!("Failed to save job history to {:?}: {}", path, e);
+        }
+    }
+}
+
+/// Resolves once cancellation is requested; never if the job can't be cancelled any more
+async fn cancellation(cancelled: &mut watch::Receiver<bool>) {
+    if cancelled.wait_for(|cancelled| *cancelled).await.is_err() {
+        std::future::pending::<()>().await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn config(temp_dir: &TempDir) -> JobConfig {
+        JobConfig {
+            store_path: Some(temp_dir.path().join("jobs.json")),
+            ..JobConfig::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jobs_queue_cancel_and_persist() {
+        let temp_dir = TempDir::new().unwrap();
+        let jobs = Arc::new(JobService::open(config(&temp_dir)).unwrap());
+
+        let value = jobs.run(JobKind::KbExport, "Export docs", |ctx| async move {
+            ctx.set_progress(0.5, Some("halfway"));
+            Ok(42)
+        }).await.unwrap();
+        assert_eq!(value, 42);
+        assert_eq!(jobs.list()[0].status, JobStatus::Completed);
+
+        let failed = jobs.run(JobKind::KbExport, "Export missing", |_| async { Err::<(), _>("no such KB".to_string()) }).await;
+        assert!(matches!(failed, Err(JobError::Failed(e)) if e == "no such KB"));
+
+        // One pipeline run at a time: the second waits in the queue
+        let first = jobs.spawn(JobKind::PipelineRun, "Run 1", |_| async {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            Ok(())
+        });
+        let second = jobs.spawn(JobKind::PipelineRun, "Run 2", |_| async { Ok(()) });
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(jobs.get(&first.id).unwrap().status, JobStatus::Running);
+        assert_eq!(jobs.get(&second.id).unwrap().status, JobStatus::Queued);
+
+        jobs.cancel(&first.id).unwrap();
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        assert_eq!(jobs.get(&first.id).unwrap().status, JobStatus::Cancelled);
+        assert_eq!(jobs.get(&second.id).unwrap().status, JobStatus::Completed);
+
+        // A job running at shutdown comes back interrupted
+        let stuck = jobs.spawn(JobKind::Backup, "Backup", |_| std::future::pending());
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        jobs.shutdown().await;
+        let reopened = JobService::open(config(&temp_dir)).unwrap();
+        assert_eq!(reopened.get(&stuck.id).unwrap().status, JobStatus::Interrupted);
+        assert_eq!(reopened.list().len(), 5);
+        assert_eq!(reopened.clear_finished(), 5);
+    }
+}
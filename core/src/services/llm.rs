@@ -0,0 +1,424 @@
+/*!
+ * LLM Provider Abstraction
+ *
+ * `LlmProvider` hides where text is generated: the local llama.cpp backend
+ * (`GenerationService`), an OpenAI-compatible HTTP endpoint, or Ollama.
+ * Tools and flows pick a provider through an `"llm"` entry in their config
+ * (`LlmProviderConfig`). Credentials are referenced by secret name and
+ * resolved from the `SecretsService` vault when a provider is built; an
+ * inline key is moved into the vault with `externalize_api_key` before the
+ * config is stored. Providers that would leave the machine are checked
+ * against the `NetworkPolicy` (`remote_llm`) when they are built.
+ */
+
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use thiserror::Error;
+use tracing::debug;
+
+use super::generation::{GenerationError, GenerationOutput, GenerationParams, GenerationService};
+use super::network_policy::{is_loopback_url, NetworkFeature, NetworkPolicy, PolicyError};
+use super::secrets::{SecretsError, SecretsService};
+
+/// Default Ollama endpoint
+pub const DEFAULT_OLLAMA_URL: &str = "http://localhost:11434";
+
+/// Config key holding a tool's or flow's provider selection
+pub const LLM_CONFIG_KEY: &str = "llm";
+
+/// Timeout for remote generation requests
+const HTTP_TIMEOUT: Duration = Duration::from_secs(120);
+
+/// LLM Provider Error Types
+#[derive(Debug, Error)]
+pub enum LlmError {
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+
+    #[error("Invalid provider config: {0}")]
+    InvalidConfig(String),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Provider returned {status}: {body}")]
+    ProviderError { status: u16, body: String },
+
+    #[error("Invalid provider response: {0}")]
+    InvalidResponse(String),
+
+    #[error(transparent)]
+    Generation(#[from] GenerationError),
+
+    #[error(transparent)]
+    Secret(#[from] SecretsError),
+}
+
+/// Provider selection stored per tool/flow
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "provider", rename_all = "snake_case")]
+pub enum LlmProviderConfig {
+    /// llama.cpp with the Manager's GGUF model
+    #[default]
+    Local,
+    /// Any `/chat/completions` endpoint (OpenAI, vLLM, LM Studio, llama-server, ...)
+    OpenaiCompatible {
+        base_url: String,           // e.g. https://api.openai.com/v1
+        model: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        api_key: Option<String>,            // Inline key; moved to the vault before storing
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        api_key_secret: Option<String>,     // Name of the vault secret holding the key
+    },
+    /// Ollama's native `/api/generate`
+    Ollama {
+        #[serde(default = "default_ollama_url")]
+        base_url: String,
+        model: String,
+    },
+}
+
+fn default_ollama_url() -> String {
+    DEFAULT_OLLAMA_URL.to_string()
+}
+
+impl LlmProviderConfig {
+    /// Provider selection from a tool config / flow definition (`Local` when absent)
+    pub fn from_config(config: &Value) -> Result<Self, LlmError> {
+        match config.get(LLM_CONFIG_KEY) {
+            None | Some(Value::Null) => Ok(Self::Local),
+            Some(value) => serde_json::from_value(value.clone())
+                .map_err(|e| LlmError::InvalidConfig(e.to_string())),
+        }
+    }
+
+    /// True if requests leave the machine (anything not on loopback)
+    pub fn is_remote(&self) -> bool {
+        match self {
+            Self::Local => false,
+            Self::OpenaiCompatible { base_url, .. } | Self::Ollama { base_url, .. } => !is_loopback_url(base_url),
+        }
+    }
+
+    /// Display name, e.g. "ollama:llama3"
+    pub fn name(&self) -> String {
+        match self {
+            Self::Local => "local".to_string(),
+            Self::OpenaiCompatible { model, .. } => format!("openai_compatible:{}", model),
+            Self::Ollama { model, .. } => format!("ollama:{}", model),
+        }
+    }
+
+    /// Move an inline API key into the vault, leaving a reference to it. An
+    /// existing reference is reused, otherwise the secret is named `default_name`.
+    pub fn externalize_api_key(&mut self, secrets: &SecretsService, default_name: &str) -> Result<(), SecretsError> {
+        if let Self::OpenaiCompatible { api_key, api_key_secret, .. } = self {
+            if let Some(key) = api_key.take() {
+                let name = api_key_secret.get_or_insert_with(|| default_name.to_string());
+                secrets.set(name, &key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Text generation backend
+#[async_trait]
+pub trait LlmProvider: Send + Sync {
+    /// Display name, e.g. "ollama:llama3"
+    fn name(&self) -> String;
+
+    /// True if requests leave the machine
+    fn is_remote(&self) -> bool;
+
+    async fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<GenerationOutput, LlmError>;
+}
+
+/// Local llama.cpp backend
+pub struct LocalLlmProvider {
+    service: Arc<GenerationService>,
+}
+
+#[async_trait]
+impl LlmProvider for LocalLlmProvider {
+    fn name(&self) -> String {
+        "local".to_string()
+    }
+
+    fn is_remote(&self) -> bool {
+        false
+    }
+
+    async fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<GenerationOutput, LlmError> {
+        Ok(self.service.generate(prompt, params).await?)
+    }
+}
+
+/// OpenAI-compatible chat completions endpoint
+pub struct OpenAiCompatibleProvider {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+#[async_trait]
+impl LlmProvider for OpenAiCompatibleProvider {
+    fn name(&self) -> String {
+        format!("openai_compatible:{}", self.model)
+    }
+
+    fn is_remote(&self) -> bool {
+        !is_loopback_url(&self.base_url)
+    }
+
+    async fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<GenerationOutput, LlmError> {
+        let started = Instant::now();
+        let mut body = json!({
+            "model": self.model,
+            "messages": [{"role": "user", "content": prompt}],
+            "max_tokens": params.max_tokens,
+            "temperature": params.temperature,
+            "top_p": params.top_p,
+        });
+        if !params.stop.is_empty() {
+            body["stop"] = json!(params.stop);
+        }
+        if let Some(seed) = params.seed {
+            body["seed"] = json!(seed);
+        }
+
+        let mut request = self.client.post(format!("{}/chat/completions", self.base_url.trim_end_matches('/'))).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        let response = send(request).await?;
+
+        let text = response.pointer("/choices/0/message/content")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| LlmError::InvalidResponse("missing choices[0].message.content".to_string()))?;
+        Ok(GenerationOutput {
+            text: text.trim().to_string(),
+            model: response.get("model").and_then(|v| v.as_str()).unwrap_or(&self.model).to_string(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// Ollama native API
+pub struct OllamaProvider {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+#[async_trait]
+impl LlmProvider for OllamaProvider {
+    fn name(&self) -> String {
+        format!("ollama:{}", self.model)
+    }
+
+    fn is_remote(&self) -> bool {
+        !is_loopback_url(&self.base_url)
+    }
+
+    async fn generate(&self, prompt: &str, params: &GenerationParams) -> Result<GenerationOutput, LlmError> {
+        let started = Instant::now();
+        let mut options = json!({
+            "num_predict": params.max_tokens,
+            "temperature": params.temperature,
+            "top_p": params.top_p,
+        });
+        if !params.stop.is_empty() {
+            options["stop"] = json!(params.stop);
+        }
+        if let Some(seed) = params.seed {
+            options["seed"] = json!(seed);
+        }
+
+        let request = self.client
+            .post(format!("{}/api/generate", self.base_url.trim_end_matches('/')))
+            .json(&json!({ "model": self.model, "prompt": prompt, "stream": false, "options": options }));
+        let response = send(request).await?;
+
+        let text = response.get("response")
+            .and_then(|v| v.as_str())
+            .ok_or_else(|| LlmError::InvalidResponse("missing response".to_string()))?;
+        Ok(GenerationOutput {
+            text: text.trim().to_string(),
+            model: self.model.clone(),
+            duration_ms: started.elapsed().as_millis() as u64,
+        })
+    }
+}
+
+/// Send a request and parse a JSON body, surfacing non-2xx responses
+async fn send(request: reqwest::RequestBuilder) -> Result<Value, LlmError> {
+    let response = request.send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let body: String = response.text().await.unwrap_or_default().chars().take(500).collect();
+        return Err(LlmError::ProviderError { status: status.as_u16(), body });
+    }
+    Ok(response.json().await?)
+}
+
+/// Resolves provider configs into providers
+pub struct LlmService {
+    local: Arc<GenerationService>,
+    client: reqwest::Client,
+    secrets: Option<Arc<SecretsService>>,  // Resolves `api_key_secret` references
+    policy: Arc<NetworkPolicy>,             // Gates remote providers
+}
+
+impl LlmService {
+    pub fn new(local: Arc<GenerationService>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(HTTP_TIMEOUT)
+            .build()
+            .expect("HTTP client configuration is static");
+        Self { local, client, secrets: None, policy: Arc::new(NetworkPolicy::default()) }
+    }
+
+    pub fn with_secrets_service(mut self, secrets: Arc<SecretsService>) -> Self {
+        self.secrets = Some(secrets);
+        self
+    }
+
+    pub fn with_network_policy(mut self, policy: Arc<NetworkPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Provider for a config; remote providers must pass the network policy
+    pub fn provider(&self, config: &LlmProviderConfig) -> Result<Arc<dyn LlmProvider>, LlmError> {
+        if config.is_remote() {
+            self.policy.check(NetworkFeature::RemoteLlm, &config.name())?;
+        }
+        debug!("Using LLM provider {}", config.name());
+
+        Ok(match config {
+            LlmProviderConfig::Local => Arc::new(LocalLlmProvider { service: self.local.clone() }),
+            LlmProviderConfig::OpenaiCompatible { base_url, model, api_key, api_key_secret } => Arc::new(OpenAiCompatibleProvider {
+                client: self.client.clone(),
+                base_url: base_url.clone(),
+                model: model.clone(),
+                api_key: match (api_key, api_key_secret) {
+                    (Some(key), _) => Some(key.clone()),
+                    (None, Some(name)) => {
+                        let secrets = self.secrets.as_ref()
+                            .ok_or_else(|| LlmError::InvalidConfig(format!("No secrets vault to resolve {}", name)))?;
+                        Some(secrets.get(name)?.expose().to_string())
+                    }
+                    (None, None) => None,
+                },
+            }),
+            LlmProviderConfig::Ollama { base_url, model } => Arc::new(OllamaProvider {
+                client: self.client.clone(),
+                base_url: base_url.clone(),
+                model: model.clone(),
+            }),
+        })
+    }
+
+    /// Generate with the given provider config
+    pub async fn generate(
+        &self,
+        config: &LlmProviderConfig,
+        prompt: &str,
+        params: &GenerationParams,
+    ) -> Result<GenerationOutput, LlmError> {
+        self.provider(config)?.generate(prompt, params).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use crate::services::generation::GenerationConfig;
+    use crate::services::secrets::SecretsConfig;
+
+    fn service() -> LlmService {
+        LlmService::new(Arc::new(GenerationService::new(GenerationConfig::default())))
+    }
+
+    #[test]
+    fn test_provider_config() {
+        assert_eq!(LlmProviderConfig::from_config(&json!({"kb_id": "kb_1"})).unwrap(), LlmProviderConfig::Local);
+
+        let config = LlmProviderConfig::from_config(&json!({
+            "llm": {"provider": "ollama", "model": "llama3"}
+        })).unwrap();
+        assert_eq!(config, LlmProviderConfig::Ollama { base_url: DEFAULT_OLLAMA_URL.to_string(), model: "llama3".to_string() });
+        assert!(!config.is_remote());
+
+        let remote = LlmProviderConfig::OpenaiCompatible {
+            base_url: "https://api.openai.com/v1".to_string(),
+            model: "gpt-4o-mini".to_string(),
+            api_key: Some("sk-test".to_string()),
+            api_key_secret: None,
+        };
+        assert!(remote.is_remote());
+        assert!(!LlmProviderConfig::OpenaiCompatible { base_url: "http://127.0.0.1:8080/v1".to_string(), model: "m".to_string(), api_key: None, api_key_secret: None }.is_remote());
+
+        assert!(LlmProviderConfig::from_config(&json!({"llm": {"provider": "unknown"}})).is_err());
+    }
+
+    #[test]
+    fn test_air_gapped_refuses_remote_providers() {
+        let policy = Arc::new(NetworkPolicy::new(true));
+        let service = service().with_network_policy(policy.clone());
+        let remote = LlmProviderConfig::Ollama { base_url: "http://gpu-box:11434".to_string(), model: "llama3".to_string() };
+
+        assert!(matches!(service.provider(&remote), Err(LlmError::Policy(PolicyError::Blocked { .. }))));
+        assert!(!service.provider(&LlmProviderConfig::Local).unwrap().is_remote());
+
+        policy.set_air_gapped(false);
+        assert!(service.provider(&remote).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_openai_compatible_generate() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+        let server = tokio::spawn(async move {
+            let (mut socket, _) = listener.accept().await.unwrap();
+            let mut raw = Vec::new();
+            let mut buf = [0u8; 4096];
+            while !String::from_utf8_lossy(&raw).contains("\"messages\"") {
+                let n = socket.read(&mut buf).await.unwrap();
+                raw.extend_from_slice(&buf[..n]);
+            }
+            let body = r#"{"model":"tiny","choices":[{"message":{"role":"assistant","content":" Install it [1]. "}}]}"#;
+            let response = format!("HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", body.len(), body);
+            socket.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8_lossy(&raw).to_string()
+        });
+
+        // The key goes through the vault; the stored config only names it
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let secrets = Arc::new(SecretsService::new(SecretsConfig::test_config(temp_dir.path())).unwrap());
+        let mut config = LlmProviderConfig::OpenaiCompatible {
+            base_url,
+            model: "tiny".to_string(),
+            api_key: Some("sk-test".to_string()),
+            api_key_secret: None,
+        };
+        config.externalize_api_key(&secrets, "tiny.api_key").unwrap();
+        assert!(!serde_json::to_string(&config).unwrap().contains("sk-test"));
+        assert!(matches!(service().provider(&config), Err(LlmError::InvalidConfig(_))));
+
+        let output = service().with_secrets_service(secrets)
+            .generate(&config, "How?", &GenerationParams::default()).await.unwrap();
+        assert_eq!(output.text, "Install it [1].");
+        assert_eq!(output.model, "tiny");
+
+        let request = server.await.unwrap();
+        assert!(request.starts_with("POST /v1/chat/completions "));
+        assert!(request.to_lowercase().contains("authorization: bearer sk-test"));
+    }
+}
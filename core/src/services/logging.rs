@@ -0,0 +1,556 @@
+/*!
+ * Logging Service Implementation
+ *
+ * Structured JSON logs per subsystem (app, mcp, worker, pipelines). A tracing
+ * layer routes each event by an explicit `subsystem` field, the subsystem a
+ * subprocess is configured for, or else its target, to a size-rotated file
+ * under `log_dir` (`app.log`, `app.log.1`, ...), one JSON object per line.
+ * Entries are also broadcast so the UI can tail them live, and `get_logs`
+ * reads them back with subsystem/level/text/time filters.
+ */
+
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::broadcast;
+use tracing::field::{Field, Visit};
+use tracing::{Event, Subscriber};
+use tracing_subscriber::layer::{Context, Layer, SubscriberExt};
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use super::telemetry::TelemetryService;
+
+/// Live-tail buffer; slow subscribers skip ahead instead of blocking logging
+const TAIL_CHANNEL_CAPACITY: usize = 1024;
+
+/// Entries returned by `get_logs` when the range sets no limit
+const DEFAULT_LOG_LIMIT: usize = 500;
+
+/// Logging Service Error Types
+#[derive(Debug, Error)]
+pub enum LoggingError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid log filter: {0}")]
+    InvalidFilter(String),
+
+    #[error("Failed to install log subscriber: {0}")]
+    SubscriberError(String),
+}
+
+/// Subsystem a log entry belongs to; each has its own file
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogSubsystem {
+    App,
+    Mcp,
+    Worker,
+    Pipelines,
+}
+
+impl LogSubsystem {
+    pub const ALL: [LogSubsystem; 4] = [LogSubsystem::App, LogSubsystem::Mcp, LogSubsystem::Worker, LogSubsystem::Pipelines];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LogSubsystem::App => "app",
+            LogSubsystem::Mcp => "mcp",
+            LogSubsystem::Worker => "worker",
+            LogSubsystem::Pipelines => "pipelines",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "app" => Some(LogSubsystem::App),
+            "mcp" => Some(LogSubsystem::Mcp),
+            "worker" => Some(LogSubsystem::Worker),
+            "pipelines" => Some(LogSubsystem::Pipelines),
+            _ => None,
+        }
+    }
+
+    /// Route an event by its tracing target (module path)
+    pub fn from_target(target: &str) -> Self {
+        if target.starts_with("rag_mcp") || target.contains("::mcp") {
+            LogSubsystem::Mcp
+        } else if target.contains("worker") || target.contains("embedding") {
+            LogSubsystem::Worker
+        } else if target.contains("pipeline") {
+            LogSubsystem::Pipelines
+        } else {
+            LogSubsystem::App
+        }
+    }
+}
+
+/// Log level, ordered by severity
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogLevel {
+    Trace,
+    Debug,
+    Info,
+    Warn,
+    Error,
+}
+
+impl From<&tracing::Level> for LogLevel {
+    fn from(level: &tracing::Level) -> Self {
+        match *level {
+            tracing::Level::TRACE => LogLevel::Trace,
+            tracing::Level::DEBUG => LogLevel::Debug,
+            tracing::Level::INFO => LogLevel::Info,
+            tracing::Level::WARN => LogLevel::Warn,
+            tracing::Level::ERROR => LogLevel::Error,
+        }
+    }
+}
+
+/// One structured log line
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LogEntry {
+    pub timestamp: DateTime<Utc>,
+    pub level: LogLevel,
+    pub subsystem: LogSubsystem,
+    pub target: String,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "serde_json::Map::is_empty")]
+    pub fields: serde_json::Map<String, serde_json::Value>,
+}
+
+/// Which entries `get_logs` returns; unset fields match everything
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogFilter {
+    pub subsystems: Option<Vec<LogSubsystem>>,
+    pub min_level: Option<LogLevel>,
+    pub target: Option<String>,     // Target prefix, e.g. "rag_core::modules::kb"
+    pub text: Option<String>,       // Case-insensitive substring of the message
+}
+
+impl LogFilter {
+    pub fn matches(&self, entry: &LogEntry) -> bool {
+        self.subsystems.as_ref().is_none_or(|s| s.contains(&entry.subsystem))
+            && self.min_level.is_none_or(|level| entry.level >= level)
+            && self.target.as_ref().is_none_or(|t| entry.target.starts_with(t.as_str()))
+            && self.text.as_ref().is_none_or(|t| entry.message.to_lowercase().contains(&t.to_lowercase()))
+    }
+}
+
+/// Time window and page size for `get_logs`
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogRange {
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+    pub limit: Option<usize>,       // Newest entries first, default 500
+}
+
+impl LogRange {
+    fn contains(&self, timestamp: &DateTime<Utc>) -> bool {
+        self.since.is_none_or(|since| *timestamp >= since)
+            && self.until.is_none_or(|until| *timestamp <= until)
+    }
+}
+
+/// Logging configuration
+#[derive(Debug, Clone)]
+pub struct LoggingConfig {
+    pub log_dir: PathBuf,
+    pub max_file_bytes: u64,        // Rotate a subsystem's file once it would exceed this
+    pub max_files: usize,           // Rotated files kept per subsystem, besides the live one
+    pub default_filter: String,     // EnvFilter directives when RUST_LOG is unset
+    pub console: bool,              // Also print human-readable logs to stderr
+    pub subsystem: Option<LogSubsystem>, // Route every event here (subprocesses), else by target
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            log_dir: PathBuf::from("./logs"),
+            max_file_bytes: 10 * 1024 * 1024,
+            max_files: 5,
+            default_filter: "info".to_string(),
+            console: true,
+            subsystem: None,
+        }
+    }
+}
+
+impl LoggingConfig {
+    /// Test configuration with small files and no console output
+    pub fn test_config(log_dir: &Path) -> Self {
+        Self {
+            log_dir: log_dir.to_path_buf(),
+            max_file_bytes: 64 * 1024,
+            max_files: 2,
+            default_filter: "debug".to_string(),
+            console: false,
+            subsystem: None,
+        }
+    }
+}
+
+/// Append-only log file rotated by size
+struct RotatingFile {
+    path: PathBuf,
+    file: Option<File>,
+    size: u64,
+}
+
+impl RotatingFile {
+    fn new(path: PathBuf) -> Self {
+        let size = std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        Self { path, file: None, size }
+    }
+
+    fn write_line(&mut self, line: &[u8], max_bytes: u64, max_files: usize) -> std::io::Result<()> {
+        if self.size > 0 && self.size + line.len() as u64 > max_bytes {
+            self.rotate(max_files)?;
+        }
+        if self.file.is_none() {
+            self.file = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        if let Some(file) = &mut self.file {
+            file.write_all(line)?;
+        }
+        self.size += line.len() as u64;
+        Ok(())
+    }
+
+    /// `x.log` -> `x.log.1` -> ... -> `x.log.<max_files>`, dropping the oldest
+    fn rotate(&mut self, max_files: usize) -> std::io::Result<()> {
+        self.file = None;
+        if max_files == 0 {
+            std::fs::remove_file(&self.path)?;
+        } else {
+            for index in (1..max_files).rev() {
+                let from = rotated_path(&self.path, index);
+                if from.exists() {
+                    std::fs::rename(&from, rotated_path(&self.path, index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, rotated_path(&self.path, 1))?;
+        }
+        self.size = 0;
+        Ok(())
+    }
+}
+
+fn rotated_path(path: &Path, index: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(format!(".{}", index));
+    PathBuf::from(name)
+}
+
+/// Structured logging with per-subsystem rotating files and a live tail
+pub struct LoggingService {
+    config: LoggingConfig,
+    files: Mutex<HashMap<LogSubsystem, RotatingFile>>,
+    tail: broadcast::Sender<LogEntry>,
+}
+
+impl LoggingService {
+    pub fn new(config: LoggingConfig) -> Result<Self, LoggingError> {
+        std::fs::create_dir_all(&config.log_dir)?;
+        let (tail, _) = broadcast::channel(TAIL_CHANNEL_CAPACITY);
+        Ok(Self {
+            config,
+            files: Mutex::new(HashMap::new()),
+            tail,
+        })
+    }
+
+    pub fn log_dir(&self) -> &Path {
+        &self.config.log_dir
+    }
+
+    /// Install this service's layer (plus env filter, optional console output and
+    /// OpenTelemetry spans) as the process-wide subscriber; fails if one is already set
+    pub fn install_global(self: &Arc<Self>, telemetry: Option<&TelemetryService>) -> Result<(), LoggingError> {
+        let filter = match EnvFilter::try_from_default_env() {
+            Ok(filter) => filter,
+            Err(_) => EnvFilter::try_new(&self.config.default_filter)
+                .map_err(|e| LoggingError::InvalidFilter(e.to_string()))?,
+        };
+        let console = self.config.console.then(|| {
+            tracing_subscriber::fmt::layer().with_writer(std::io::stderr).with_ansi(false)
+        });
+
+        tracing_subscriber::registry()
+            .with(filter)
+            .with(console)
+            .with(self.layer())
+            .with(telemetry.map(|t| t.layer()))
+            .try_init()
+            .map_err(|e| LoggingError::SubscriberError(e.to_string()))
+    }
+
+    /// Tracing layer writing events through this service
+    pub fn layer(self: &Arc<Self>) -> LoggingLayer {
+        LoggingLayer { service: self.clone() }
+    }
+
+    /// Live stream of entries as they are recorded
+    pub fn subscribe(&self) -> broadcast::Receiver<LogEntry> {
+        self.tail.subscribe()
+    }
+
+    /// Append an entry to its subsystem's file and broadcast it
+    pub fn record(&self, entry: LogEntry) {
+        // Must not log from here: the event would come straight back to this layer
+        match serde_json::to_vec(&entry) {
+            Ok(mut line) => {
+                line.push(b'\n');
+                let mut files = self.files.lock().unwrap_or_else(|e| e.into_inner());
+                let file = files.entry(entry.subsystem).or_insert_with(|| {
+                    RotatingFile::new(self.log_file(entry.subsystem))
+                });
+                if let Err(e) = file.write_line(&line, self.config.max_file_bytes, self.config.max_files) {
+                    eprintln!("Failed to write {} log: {}", entry.subsystem.as_str(), e);
+                }
+            }
+            Err(e) => eprintln!("Failed to serialize log entry: {}", e),
+        }
+        let _ = self.tail.send(entry);
+    }
+
+    /// Stored entries matching `filter` within `range`, newest first
+    pub fn get_logs(&self, filter: &LogFilter, range: &LogRange) -> Result<Vec<LogEntry>, LoggingError> {
+        let subsystems = filter.subsystems.clone().unwrap_or_else(|| LogSubsystem::ALL.to_vec());
+
+        let mut entries = Vec::new();
+        for subsystem in subsystems {
+            for path in self.log_files(subsystem) {
+                let file = match File::open(&path) {
+                    Ok(file) => file,
+                    Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                    Err(e) => return Err(e.into()),
+                };
+                for line in BufReader::new(file).lines() {
+                    // Torn or foreign lines are skipped rather than failing the query
+                    let Ok(entry) = serde_json::from_str::<LogEntry>(&line?) else {
+                        continue;
+                    };
+                    if range.contains(&entry.timestamp) && filter.matches(&entry) {
+                        entries.push(entry);
+                    }
+                }
+            }
+        }
+
+        // Files were read oldest first; reversing keeps same-timestamp entries newest first
+        entries.reverse();
+        entries.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+        entries.truncate(range.limit.unwrap_or(DEFAULT_LOG_LIMIT));
+        Ok(entries)
+    }
+
+    /// A subsystem's files, oldest first
+    pub fn log_files(&self, subsystem: LogSubsystem) -> Vec<PathBuf> {
+        let live = self.log_file(subsystem);
+        let mut files: Vec<PathBuf> = (1..=self.config.max_files).rev()
+            .map(|index| rotated_path(&live, index))
+            .filter(|path| path.exists())
+            .collect();
+        files.push(live);
+        files
+    }
+
+    fn log_file(&self, subsystem: LogSubsystem) -> PathBuf {
+        self.config.log_dir.join(format!("{}.log", subsystem.as_str()))
+    }
+}
+
+/// Tracing layer forwarding events to a `LoggingService`
+pub struct LoggingLayer {
+    service: Arc<LoggingService>,
+}
+
+impl<S: Subscriber> Layer<S> for LoggingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let mut visitor = FieldVisitor::default();
+        event.record(&mut visitor);
+
+        let subsystem = visitor.subsystem.as_deref()
+            .and_then(LogSubsystem::parse)
+            .or(self.service.config.subsystem)
+            .unwrap_or_else(|| LogSubsystem::from_target(metadata.target()));
+        self.service.record(LogEntry {
+            timestamp: Utc::now(),
+            level: metadata.level().into(),
+            subsystem,
+            target: metadata.target().to_string(),
+            message: visitor.message,
+            fields: visitor.fields,
+        });
+    }
+}
+
+/// Collects an event's message and fields; `subsystem = "..."` overrides routing
+#[derive(Default)]
+struct FieldVisitor {
+    message: String,
+    subsystem: Option<String>,
+    fields: serde_json::Map<String, serde_json::Value>,
+}
+
+impl FieldVisitor {
+    fn insert(&mut self, field: &Field, value: serde_json::Value) {
+        match (field.name(), value) {
+            ("message", serde_json::Value::String(message)) => self.message = message,
+            ("subsystem", serde_json::Value::String(subsystem)) => self.subsystem = Some(subsystem),
+            (name, value) => {
+                self.fields.insert(name.to_string(), value);
+            }
+        }
+    }
+}
+
+impl Visit for FieldVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.insert(field, value.into());
+    }
+
+    fn record_i64(&mut self, field: &Field, value: i64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_u64(&mut self, field: &Field, value: u64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_f64(&mut self, field: &Field, value: f64) {
+        self.insert(field, value.into());
+    }
+
+    fn record_bool(&mut self, field: &Field, value: bool) {
+        self.insert(field, value.into());
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.insert(field, format!("{:?}", value).into());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(subsystem: LogSubsystem, level: LogLevel, message: &str) -> LogEntry {
+        LogEntry {
+            timestamp: Utc::now(),
+            level,
+            subsystem,
+            target: "rag_core::test".to_string(),
+            message: message.to_string(),
+            fields: serde_json::Map::new(),
+        }
+    }
+
+    #[test]
+    fn test_subsystem_routing() {
+        assert_eq!(LogSubsystem::from_target("rag_mcp::server"), LogSubsystem::Mcp);
+        assert_eq!(LogSubsystem::from_target("rag_core::modules::pipeline::runner"), LogSubsystem::Pipelines);
+        assert_eq!(LogSubsystem::from_target("rag_core::services::embedding_worker"), LogSubsystem::Worker);
+        assert_eq!(LogSubsystem::from_target("rag_studio_lib::manager"), LogSubsystem::App);
+    }
+
+    #[test]
+    fn test_layer_writes_json_per_subsystem() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = Arc::new(LoggingService::new(LoggingConfig::test_config(temp_dir.path())).unwrap());
+        let mut tail = service.subscribe();
+
+        let subscriber = tracing_subscriber::registry().with(service.layer());
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "rag_mcp::server", tool = "search", "Tool call");
+            tracing::warn!(target: "rag_core::modules::kb", subsystem = "worker", "Worker restarted");
+            tracing::error!(target: "rag_studio_lib::manager", attempts = 3, "Backup failed");
+        });
+
+        assert_eq!(tail.try_recv().unwrap().message, "Tool call");
+        assert!(temp_dir.path().join("mcp.log").exists());
+
+        let all = service.get_logs(&LogFilter::default(), &LogRange::default()).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let mcp = service.get_logs(&LogFilter {
+            subsystems: Some(vec![LogSubsystem::Mcp]),
+            ..Default::default()
+        }, &LogRange::default()).unwrap();
+        assert_eq!(mcp.len(), 1);
+        assert_eq!(mcp[0].fields.get("tool"), Some(&serde_json::json!("search")));
+
+        let worker = service.get_logs(&LogFilter {
+            subsystems: Some(vec![LogSubsystem::Worker]),
+            ..Default::default()
+        }, &LogRange::default()).unwrap();
+        assert_eq!(worker[0].message, "Worker restarted");
+        assert!(!worker[0].fields.contains_key("subsystem"));
+
+        let errors = service.get_logs(&LogFilter {
+            min_level: Some(LogLevel::Warn),
+            text: Some("BACKUP".to_string()),
+            ..Default::default()
+        }, &LogRange::default()).unwrap();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].fields.get("attempts"), Some(&serde_json::json!(3)));
+    }
+
+    #[test]
+    fn test_rotation_and_range() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = LoggingConfig::test_config(temp_dir.path());
+        config.max_file_bytes = 1024;
+        let service = LoggingService::new(config).unwrap();
+
+        let start = Utc::now();
+        for i in 0..100 {
+            service.record(entry(LogSubsystem::App, LogLevel::Info, &format!("message {}", i)));
+        }
+
+        // Live file plus at most two rotated ones, each within the size limit
+        let files = service.log_files(LogSubsystem::App);
+        assert_eq!(files.len(), 3);
+        assert!(files.iter().all(|f| std::fs::metadata(f).unwrap().len() <= 1024));
+
+        let newest = service.get_logs(&LogFilter::default(), &LogRange {
+            limit: Some(2),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(newest.len(), 2);
+        assert!(newest.iter().any(|e| e.message == "message 99"));
+
+        let future = service.get_logs(&LogFilter::default(), &LogRange {
+            since: Some(start + chrono::Duration::hours(1)),
+            ..Default::default()
+        }).unwrap();
+        assert!(future.is_empty());
+
+        // A restarted service keeps appending to the same files
+        let reopened = LoggingService::new(LoggingConfig {
+            max_file_bytes: 1024,
+            ..LoggingConfig::test_config(temp_dir.path())
+        }).unwrap();
+        reopened.record(entry(LogSubsystem::App, LogLevel::Error, "after restart"));
+        let errors = reopened.get_logs(&LogFilter {
+            min_level: Some(LogLevel::Error),
+            ..Default::default()
+        }, &LogRange::default()).unwrap();
+        assert_eq!(errors.len(), 1);
+    }
+}
@@ -0,0 +1,359 @@
+/*!
+ * Metrics Service Implementation
+ *
+ * In-process counters, gauges and histograms for the dashboard and an
+ * optional Prometheus scrape endpoint. Services record into a shared
+ * `MetricsService` (search latency, embedding throughput, pipeline durations,
+ * cache hits, worker restarts); `snapshot` summarizes them for the UI and
+ * `render_prometheus` writes the text exposition format. Histograms use fixed
+ * buckets, so percentiles are bucket upper bounds.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Hybrid search latency (histogram, seconds)
+pub const SEARCH_LATENCY: &str = "search_latency_seconds";
+/// Texts embedded (counter)
+pub const EMBED_TEXTS: &str = "embed_texts_total";
+/// Time spent embedding (histogram, seconds per batch)
+pub const EMBED_DURATION: &str = "embed_duration_seconds";
+/// Pipeline run duration (histogram, seconds)
+pub const PIPELINE_DURATION: &str = "pipeline_duration_seconds";
+/// Failed pipeline runs (counter)
+pub const PIPELINE_FAILURES: &str = "pipeline_failures_total";
+/// Search cache lookups served from the cache (counter)
+pub const CACHE_HITS: &str = "cache_hits_total";
+/// Search cache lookups that fell through to the index (counter)
+pub const CACHE_MISSES: &str = "cache_misses_total";
+/// Hit rate of the shared cache across all users (gauge, 0.0-1.0)
+pub const CACHE_HIT_RATE: &str = "cache_hit_rate";
+/// Worker subprocess restarts, labelled by `worker` (counter)
+pub const WORKER_RESTARTS: &str = "worker_restarts_total";
+
+/// Prefix of every exported metric name
+const PROMETHEUS_PREFIX: &str = "rag_";
+
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+const PIPELINE_BUCKETS: &[f64] = &[0.5, 1.0, 5.0, 15.0, 30.0, 60.0, 300.0, 900.0, 3600.0];
+
+const HELP: &[(&str, &str)] = &[
+    (SEARCH_LATENCY, "Hybrid search latency in seconds"),
+    (EMBED_TEXTS, "Texts embedded"),
+    (EMBED_DURATION, "Embedding batch duration in seconds"),
+    (PIPELINE_DURATION, "Pipeline run duration in seconds"),
+    (PIPELINE_FAILURES, "Failed pipeline runs"),
+    (CACHE_HITS, "Search cache hits"),
+    (CACHE_MISSES, "Search cache misses"),
+    (CACHE_HIT_RATE, "Shared cache hit rate"),
+    (WORKER_RESTARTS, "Worker subprocess restarts"),
+];
+
+type Labels = BTreeMap<String, String>;
+
+/// One counter or gauge series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub name: String,
+    pub labels: Labels,
+    pub value: f64,
+}
+
+/// One histogram series with cumulative bucket counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistogramSnapshot {
+    pub name: String,
+    pub labels: Labels,
+    pub count: u64,
+    pub sum: f64,
+    pub p50: Option<f64>,
+    pub p95: Option<f64>,
+    pub buckets: Vec<(f64, u64)>,   // (upper bound, observations <= bound); +Inf is `count`
+}
+
+/// Headline numbers for the dashboard
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct MetricsSummary {
+    pub searches: u64,
+    pub search_p50_ms: Option<f64>,
+    pub search_p95_ms: Option<f64>,
+    pub embed_throughput: Option<f64>,      // Texts per second spent embedding
+    pub pipeline_runs: u64,
+    pub pipeline_failures: u64,
+    pub pipeline_mean_seconds: Option<f64>,
+    pub search_cache_hit_rate: Option<f64>, // None until the search cache was consulted
+    pub worker_restarts: u64,
+}
+
+/// All series at one point in time
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsSnapshot {
+    pub collected_at: DateTime<Utc>,
+    pub summary: MetricsSummary,
+    pub counters: Vec<MetricSample>,
+    pub gauges: Vec<MetricSample>,
+    pub histograms: Vec<HistogramSnapshot>,
+}
+
+#[derive(Debug, Clone)]
+struct Histogram {
+    bounds: &'static [f64],
+    counts: Vec<u64>,               // Per bucket, not cumulative; last slot is +Inf
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn new(bounds: &'static [f64]) -> Self {
+        Self { bounds, counts: vec![0; bounds.len() + 1], sum: 0.0, count: 0 }
+    }
+
+    fn observe(&mut self, value: f64) {
+        let bucket = self.bounds.iter().position(|bound| value <= *bound).unwrap_or(self.bounds.len());
+        self.counts[bucket] += 1;
+        self.sum += value;
+        self.count += 1;
+    }
+
+    /// Upper bound of the bucket holding the q-th observation (the largest bound if it overflowed)
+    fn quantile(&self, q: f64) -> Option<f64> {
+        if self.count == 0 {
+            return None;
+        }
+        let rank = ((q * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return self.bounds.get(index).or(self.bounds.last()).copied();
+            }
+        }
+        self.bounds.last().copied()
+    }
+
+    fn cumulative(&self) -> Vec<(f64, u64)> {
+        let mut seen = 0;
+        self.bounds.iter().zip(&self.counts).map(|(bound, count)| {
+            seen += count;
+            (*bound, seen)
+        }).collect()
+    }
+}
+
+#[derive(Default)]
+struct Registry {
+    counters: BTreeMap<(String, Labels), u64>,
+    gauges: BTreeMap<(String, Labels), f64>,
+    histograms: BTreeMap<(String, Labels), Histogram>,
+}
+
+/// Shared metrics registry
+#[derive(Default)]
+pub struct MetricsService {
+    registry: Mutex<Registry>,
+}
+
+impl MetricsService {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn increment(&self, name: &str, labels: &[(&str, &str)], by: u64) {
+        let mut registry = self.registry.lock().unwrap();
+        *registry.counters.entry(series(name, labels)).or_insert(0) += by;
+    }
+
+    pub fn set_gauge(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        self.registry.lock().unwrap().gauges.insert(series(name, labels), value);
+    }
+
+    pub fn observe(&self, name: &str, labels: &[(&str, &str)], value: f64) {
+        let bounds = if name == PIPELINE_DURATION { PIPELINE_BUCKETS } else { LATENCY_BUCKETS };
+        let mut registry = self.registry.lock().unwrap();
+        registry.histograms.entry(series(name, labels))
+            .or_insert_with(|| Histogram::new(bounds))
+            .observe(value);
+    }
+
+    pub fn record_search(&self, elapsed: Duration) {
+        self.observe(SEARCH_LATENCY, &[], elapsed.as_secs_f64());
+    }
+
+    pub fn record_embedding(&self, texts: usize, elapsed: Duration) {
+        self.increment(EMBED_TEXTS, &[], texts as u64);
+        self.observe(EMBED_DURATION, &[], elapsed.as_secs_f64());
+    }
+
+    pub fn record_pipeline_run(&self, elapsed: Duration, success: bool) {
+        self.observe(PIPELINE_DURATION, &[], elapsed.as_secs_f64());
+        if !success {
+            self.increment(PIPELINE_FAILURES, &[], 1);
+        }
+    }
+
+    pub fn record_cache_lookup(&self, hit: bool) {
+        self.increment(if hit { CACHE_HITS } else { CACHE_MISSES }, &[], 1);
+    }
+
+    pub fn record_worker_restart(&self, worker: &str) {
+        self.increment(WORKER_RESTARTS, &[("worker", worker)], 1);
+    }
+
+    pub fn snapshot(&self) -> MetricsSnapshot {
+        let registry = self.registry.lock().unwrap();
+        let counter = |name: &str| registry.counters.iter()
+            .filter(|((series, _), _)| series == name)
+            .map(|(_, value)| *value)
+            .sum::<u64>();
+        let histogram = |name: &str| registry.histograms.get(&(name.to_string(), Labels::new()));
+
+        let search = histogram(SEARCH_LATENCY);
+        let embed = histogram(EMBED_DURATION);
+        let pipeline = histogram(PIPELINE_DURATION);
+        let (cache_hits, cache_misses) = (counter(CACHE_HITS), counter(CACHE_MISSES));
+        let summary = MetricsSummary {
+            searches: search.map_or(0, |h| h.count),
+            search_p50_ms: search.and_then(|h| h.quantile(0.5)).map(|s| s * 1000.0),
+            search_p95_ms: search.and_then(|h| h.quantile(0.95)).map(|s| s * 1000.0),
+            embed_throughput: embed.filter(|h| h.sum > 0.0).map(|h| counter(EMBED_TEXTS) as f64 / h.sum),
+            pipeline_runs: pipeline.map_or(0, |h| h.count),
+            pipeline_failures: counter(PIPELINE_FAILURES),
+            pipeline_mean_seconds: pipeline.filter(|h| h.count > 0).map(|h| h.sum / h.count as f64),
+            search_cache_hit_rate: (cache_hits + cache_misses > 0)
+                .then(|| cache_hits as f64 / (cache_hits + cache_misses) as f64),
+            worker_restarts: counter(WORKER_RESTARTS),
+        };
+
+        MetricsSnapshot {
+            collected_at: Utc::now(),
+            summary,
+            counters: registry.counters.iter()
+                .map(|((name, labels), value)| MetricSample { name: name.clone(), labels: labels.clone(), value: *value as f64 })
+                .collect(),
+            gauges: registry.gauges.iter()
+                .map(|((name, labels), value)| MetricSample { name: name.clone(), labels: labels.clone(), value: *value })
+                .collect(),
+            histograms: registry.histograms.iter()
+                .map(|((name, labels), h)| HistogramSnapshot {
+                    name: name.clone(),
+                    labels: labels.clone(),
+                    count: h.count,
+                    sum: h.sum,
+                    p50: h.quantile(0.5),
+                    p95: h.quantile(0.95),
+                    buckets: h.cumulative(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Prometheus text exposition format (version 0.0.4)
+    pub fn render_prometheus(&self) -> String {
+        let registry = self.registry.lock().unwrap();
+        let mut out = String::new();
+        let mut described = std::collections::HashSet::new();
+        let mut describe = |out: &mut String, name: &str, kind: &str| {
+            if described.insert(name.to_string()) {
+                let help = HELP.iter().find(|(metric, _)| *metric == name).map_or(name, |(_, help)| *help);
+                out.push_str(&format!("# HELP {}{} {}\n# TYPE {}{} {}\n", PROMETHEUS_PREFIX, name, help, PROMETHEUS_PREFIX, name, kind));
+            }
+        };
+
+        for ((name, labels), value) in &registry.counters {
+            describe(&mut out, name, "counter");
+            out.push_str(&format!("{}{}{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, None), value));
+        }
+        for ((name, labels), value) in &registry.gauges {
+            describe(&mut out, name, "gauge");
+            out.push_str(&format!("{}{}{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, None), value));
+        }
+        for ((name, labels), histogram) in &registry.histograms {
+            describe(&mut out, name, "histogram");
+            for (bound, count) in histogram.cumulative() {
+                out.push_str(&format!("{}{}_bucket{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, Some(&bound.to_string())), count));
+            }
+            out.push_str(&format!("{}{}_bucket{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, Some("+Inf")), histogram.count));
+            out.push_str(&format!("{}{}_sum{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, None), histogram.sum));
+            out.push_str(&format!("{}{}_count{} {}\n", PROMETHEUS_PREFIX, name, label_set(labels, None), histogram.count));
+        }
+        out
+    }
+}
+
+fn series(name: &str, labels: &[(&str, &str)]) -> (String, Labels) {
+    let labels = labels.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect();
+    (name.to_string(), labels)
+}
+
+/// `{a="1",le="0.5"}`, or nothing without labels
+fn label_set(labels: &Labels, le: Option<&str>) -> String {
+    let mut pairs: Vec<String> = labels.iter()
+        .map(|(key, value)| format!("{}=\"{}\"", key, value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")))
+        .collect();
+    if let Some(le) = le {
+        pairs.push(format!("le=\"{}\"", le));
+    }
+    if pairs.is_empty() {
+        String::new()
+    } else {
+        format!("{{{}}}", pairs.join(","))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_summary() {
+        let metrics = MetricsService::new();
+        for ms in [3, 8, 20, 40, 400] {
+            metrics.record_search(Duration::from_millis(ms));
+        }
+        metrics.record_embedding(100, Duration::from_secs(2));
+        metrics.record_pipeline_run(Duration::from_secs(10), true);
+        metrics.record_pipeline_run(Duration::from_secs(20), false);
+        metrics.record_cache_lookup(true);
+        metrics.record_cache_lookup(false);
+        metrics.record_cache_lookup(true);
+        metrics.record_worker_restart("embedding");
+
+        let summary = metrics.snapshot().summary;
+        assert_eq!(summary.searches, 5);
+        assert_eq!(summary.search_p50_ms, Some(25.0));
+        assert_eq!(summary.search_p95_ms, Some(500.0));
+        assert_eq!(summary.embed_throughput, Some(50.0));
+        assert_eq!(summary.pipeline_runs, 2);
+        assert_eq!(summary.pipeline_failures, 1);
+        assert_eq!(summary.pipeline_mean_seconds, Some(15.0));
+        assert!((summary.search_cache_hit_rate.unwrap() - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(summary.worker_restarts, 1);
+
+        let empty = MetricsService::new().snapshot().summary;
+        assert_eq!(empty.searches, 0);
+        assert!(empty.search_p50_ms.is_none() && empty.search_cache_hit_rate.is_none());
+    }
+
+    #[test]
+    fn test_prometheus_rendering() {
+        let metrics = MetricsService::new();
+        metrics.record_search(Duration::from_millis(30));
+        metrics.record_search(Duration::from_secs(20));
+        metrics.record_worker_restart("embed\"er");
+        metrics.set_gauge(CACHE_HIT_RATE, &[], 0.5);
+
+        let text = metrics.render_prometheus();
+        assert!(text.contains("# TYPE rag_search_latency_seconds histogram\n"));
+        assert!(text.contains("rag_search_latency_seconds_bucket{le=\"0.025\"} 0\n"));
+        assert!(text.contains("rag_search_latency_seconds_bucket{le=\"0.05\"} 1\n"));
+        assert!(text.contains("rag_search_latency_seconds_bucket{le=\"10\"} 1\n"));
+        assert!(text.contains("rag_search_latency_seconds_bucket{le=\"+Inf\"} 2\n"));
+        assert!(text.contains("rag_search_latency_seconds_count 2\n"));
+        assert!(text.contains("# TYPE rag_worker_restarts_total counter\n"));
+        assert!(text.contains("rag_worker_restarts_total{worker=\"embed\\\"er\"} 1\n"));
+        assert!(text.contains("rag_cache_hit_rate 0.5\n"));
+    }
+}
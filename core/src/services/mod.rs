@@ -7,9 +7,24 @@
 
 pub mod sql;
 pub mod vector;
-
-// Future services to be implemented when needed:
-// pub mod cache;
-// pub mod storage;
-// pub mod embedding;
-// pub mod logging;
\ No newline at end of file
+pub mod storage;
+pub mod generation;
+pub mod llm;
+pub mod cache;
+pub mod model;
+pub mod logging;
+pub mod metrics;
+pub mod telemetry;
+pub mod settings;
+pub mod secrets;
+pub mod network_policy;
+pub mod pack_signing;
+pub mod alerts;
+pub mod health;
+pub mod projects;
+pub mod backup;
+pub mod encryption;
+pub mod quota;
+pub mod jobs;
+#[cfg(feature = "onnx")]
+pub mod embedding;
\ No newline at end of file
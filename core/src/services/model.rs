@@ -0,0 +1,1424 @@
+/*!
+ * Model Service Implementation
+ *
+ * Downloads models (GGUF generation models, embedding/reranker exports) from
+ * the HuggingFace Hub into the local models directory. Interrupted downloads
+ * resume from `.part` files with range requests, LFS files are verified
+ * against the SHA-256 the Hub publishes, and progress is reported over a
+ * channel so the UI can show live progress. Air-gapped installs come from
+ * model tarballs: bundled archives are installed on first run and
+ * `import_model_from_archive` installs one without any Hub access. Hub
+ * access is refused while the `NetworkPolicy` blocks `model_downloads`.
+ * Storage is kept under `cache_size_gb` and above `min_free_space_gb` by
+ * evicting least recently used downloads; bundled and pinned models stay.
+ * MVP: one file at a time; mirrors via `hf_endpoint` / HF_ENDPOINT.
+ */
+
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{Duration, Instant};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::network_policy::{NetworkFeature, NetworkPolicy, PolicyError};
+
+/// Environment variable overriding the Hub endpoint (mirror)
+pub const HF_ENDPOINT_ENV: &str = "HF_ENDPOINT";
+
+/// Environment variable holding a Hub access token for gated models
+pub const HF_TOKEN_ENV: &str = "HF_TOKEN";
+
+/// Vault secret holding the Hub token when HF_TOKEN is unset
+pub const HF_TOKEN_SECRET: &str = "huggingface.token";
+
+/// Environment variable disabling all Hub access ("1"), as in huggingface_hub
+pub const HF_HUB_OFFLINE_ENV: &str = "HF_HUB_OFFLINE";
+
+/// Environment variable naming the directory of bundled model archives
+pub const BUNDLED_MODELS_ENV: &str = "RAG_STUDIO_BUNDLED_MODELS";
+
+pub const DEFAULT_HF_ENDPOINT: &str = "https://huggingface.co";
+
+/// Revision downloaded when none is given
+pub const DEFAULT_REVISION: &str = "main";
+
+/// Per-model manifest written after a complete download
+const MANIFEST_FILE: &str = "model.json";
+
+/// Suffix of partially downloaded files
+const PART_SUFFIX: &str = ".part";
+
+/// Prefix of directories archives are unpacked into before install
+const IMPORT_STAGING_PREFIX: &str = ".import-";
+
+const BYTES_PER_GB: f64 = 1024.0 * 1024.0 * 1024.0;
+
+/// Model archive extensions
+const ARCHIVE_EXTENSIONS: &[&str] = &[".tar", ".tar.gz", ".tgz"];
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Benchmark results, one JSON file per model
+const BENCHMARKS_DIR: &str = "benchmarks";
+
+/// KBs above this many chunks weigh throughput more heavily when recommending
+const LARGE_KB_CHUNKS: usize = 100_000;
+
+/// Model Service Error Types
+#[derive(Debug, Error)]
+pub enum ModelError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
+
+    #[error("Hub returned {status}: {body}")]
+    HubError { status: u16, body: String },
+
+    #[error("Invalid model id: {0}")]
+    InvalidModelId(String),
+
+    #[error("Model not found: {0}")]
+    ModelNotFound(String),
+
+    #[error("Checksum mismatch for {file}: expected {expected}, got {actual}")]
+    ChecksumMismatch { file: String, expected: String, actual: String },
+
+    #[error("Download of {file} incomplete: {received} of {expected} bytes")]
+    Incomplete { file: String, received: u64, expected: u64 },
+
+    #[error("Model downloads are disabled in air-gapped mode: {0}")]
+    AirGapped(String),
+
+    #[error(transparent)]
+    Policy(#[from] PolicyError),
+
+    #[error("Inference backend error: {0}")]
+    Backend(String),
+
+    #[error("Model run timed out: {0}")]
+    Timeout(String),
+
+    #[error("Invalid model archive: {0}")]
+    InvalidArchive(String),
+}
+
+/// Model configuration
+#[derive(Debug, Clone)]
+pub struct ModelConfig {
+    pub models_dir: PathBuf,
+    pub hf_endpoint: String,        // Hub or mirror base URL
+    pub proxy: Option<String>,      // HTTP(S) proxy for Hub traffic
+    pub hf_token: Option<String>,
+    pub offline_mode: bool,                 // Never contact the Hub; installs come from archives
+    pub bundled_models: Option<PathBuf>,    // Directory of model archives shipped with the app
+    pub cache_size_gb: f64,                 // Storage quota for installed models
+    pub min_free_space_gb: f64,             // Disk space to leave free on the models volume
+    pub auto_cleanup_threshold: f64,        // Fraction of the quota at which eviction starts (0-1)
+}
+
+impl Default for ModelConfig {
+    fn default() -> Self {
+        Self {
+            models_dir: PathBuf::from("./models"),
+            hf_endpoint: std::env::var(HF_ENDPOINT_ENV).unwrap_or_else(|_| DEFAULT_HF_ENDPOINT.to_string()),
+            proxy: None,
+            hf_token: std::env::var(HF_TOKEN_ENV).ok(),
+            offline_mode: std::env::var(HF_HUB_OFFLINE_ENV).is_ok_and(|v| v == "1"),
+            bundled_models: std::env::var(BUNDLED_MODELS_ENV).ok().map(PathBuf::from),
+            cache_size_gb: 20.0,
+            min_free_space_gb: 2.0,
+            auto_cleanup_threshold: 0.9,
+        }
+    }
+}
+
+impl ModelConfig {
+    /// Test configuration with an isolated models directory
+    pub fn test_config(data_dir: &Path, hf_endpoint: &str) -> Self {
+        Self {
+            models_dir: data_dir.join("models"),
+            hf_endpoint: hf_endpoint.to_string(),
+            proxy: None,
+            hf_token: None,
+            offline_mode: false,
+            bundled_models: None,
+            cache_size_gb: 1.0,
+            min_free_space_gb: 0.0,
+            auto_cleanup_threshold: 1.0,
+        }
+    }
+}
+
+/// File in a model repository
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelFile {
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: Option<String>,     // Published for LFS files only
+}
+
+/// Locally installed model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelManifest {
+    pub id: String,                 // Hub repo id, e.g. "sentence-transformers/all-MiniLM-L6-v2"
+    pub revision: String,
+    pub source: String,             // Endpoint the files came from
+    pub files: Vec<ModelFile>,
+    pub size_bytes: u64,
+    pub downloaded_at: DateTime<Utc>,
+    #[serde(default)]
+    pub bundled: bool,              // Installed from an archive shipped with the app
+    #[serde(default)]
+    pub pinned: bool,               // Never evicted by cleanup
+    #[serde(default)]
+    pub last_used_at: Option<DateTime<Utc>>,
+}
+
+impl ModelManifest {
+    /// Cleanup may remove it
+    pub fn evictable(&self) -> bool {
+        !self.bundled && !self.pinned
+    }
+
+    fn last_activity(&self) -> DateTime<Utc> {
+        self.last_used_at.unwrap_or(self.downloaded_at)
+    }
+}
+
+/// Model storage usage against the quota
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelStorageStats {
+    pub model_count: usize,
+    pub used_bytes: u64,
+    pub bundled_bytes: u64,
+    pub pinned_bytes: u64,
+    pub evictable_bytes: u64,
+    pub quota_bytes: u64,
+    pub cleanup_threshold_bytes: u64,
+    pub free_space_bytes: Option<u64>,  // None when the volume can't be queried
+    pub min_free_space_bytes: u64,
+    pub reclaimed_bytes: u64,           // By cleanups since startup
+    pub evicted_models: u64,
+}
+
+/// Outcome of one cleanup run
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ModelCleanupReport {
+    pub evicted: Vec<String>,
+    pub reclaimed_bytes: u64,
+    pub used_bytes: u64,                // After cleanup
+    pub still_over_quota: bool,         // Only protected models are left
+}
+
+/// Download progress for one model
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DownloadProgress {
+    pub model_id: String,
+    pub file: String,
+    pub files_done: usize,
+    pub files_total: usize,
+    pub downloaded_bytes: u64,      // Across all files, including resumed bytes
+    pub total_bytes: u64,
+    pub done: bool,
+}
+
+/// Repository listing from the Hub API
+#[derive(Debug, Deserialize)]
+struct HubModelInfo {
+    #[serde(default)]
+    siblings: Vec<HubSibling>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubSibling {
+    rfilename: String,
+    size: Option<u64>,
+    lfs: Option<HubLfs>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HubLfs {
+    sha256: String,
+    size: u64,
+}
+
+/// What a model is used for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ModelKind {
+    Embedding,
+    Reranker,
+}
+
+/// Supported model with the facts needed to pick one for a KB
+#[derive(Debug, Clone, Serialize)]
+pub struct RegistryModel {
+    pub id: &'static str,
+    pub kind: ModelKind,
+    pub dimension: Option<usize>,           // Embedding models only
+    pub languages: &'static [&'static str], // ISO 639-1; ignored when multilingual
+    pub multilingual: bool,
+    pub size_mb: u64,
+    pub max_sequence_length: usize,
+    pub expected_throughput: f32,           // Texts (or pairs) per second on a laptop CPU, estimate
+    pub quality_prior: f32,                 // Relative retrieval quality (0-1) until benchmarked locally
+}
+
+impl RegistryModel {
+    pub fn supports_language(&self, language: &str) -> bool {
+        self.multilingual || self.languages.iter().any(|l| l.eq_ignore_ascii_case(language))
+    }
+}
+
+/// Built-in registry of supported embedding and reranker models
+pub const SUPPORTED_MODELS: &[RegistryModel] = &[
+    RegistryModel { id: "sentence-transformers/all-MiniLM-L6-v2", kind: ModelKind::Embedding, dimension: Some(384), languages: &["en"], multilingual: false, size_mb: 90, max_sequence_length: 256, expected_throughput: 1400.0, quality_prior: 0.55 },
+    RegistryModel { id: "sentence-transformers/all-mpnet-base-v2", kind: ModelKind::Embedding, dimension: Some(768), languages: &["en"], multilingual: false, size_mb: 420, max_sequence_length: 384, expected_throughput: 250.0, quality_prior: 0.65 },
+    RegistryModel { id: "BAAI/bge-small-en-v1.5", kind: ModelKind::Embedding, dimension: Some(384), languages: &["en"], multilingual: false, size_mb: 133, max_sequence_length: 512, expected_throughput: 1000.0, quality_prior: 0.68 },
+    RegistryModel { id: "BAAI/bge-base-en-v1.5", kind: ModelKind::Embedding, dimension: Some(768), languages: &["en"], multilingual: false, size_mb: 438, max_sequence_length: 512, expected_throughput: 300.0, quality_prior: 0.75 },
+    RegistryModel { id: "sentence-transformers/paraphrase-multilingual-MiniLM-L12-v2", kind: ModelKind::Embedding, dimension: Some(384), languages: &[], multilingual: true, size_mb: 471, max_sequence_length: 128, expected_throughput: 600.0, quality_prior: 0.5 },
+    RegistryModel { id: "intfloat/multilingual-e5-small", kind: ModelKind::Embedding, dimension: Some(384), languages: &[], multilingual: true, size_mb: 471, max_sequence_length: 512, expected_throughput: 700.0, quality_prior: 0.62 },
+    RegistryModel { id: "BAAI/bge-m3", kind: ModelKind::Embedding, dimension: Some(1024), languages: &[], multilingual: true, size_mb: 2270, max_sequence_length: 8192, expected_throughput: 40.0, quality_prior: 0.8 },
+    RegistryModel { id: "cross-encoder/ms-marco-MiniLM-L-6-v2", kind: ModelKind::Reranker, dimension: None, languages: &["en"], multilingual: false, size_mb: 91, max_sequence_length: 512, expected_throughput: 300.0, quality_prior: 0.6 },
+    RegistryModel { id: "BAAI/bge-reranker-base", kind: ModelKind::Reranker, dimension: None, languages: &["en", "zh"], multilingual: false, size_mb: 1110, max_sequence_length: 512, expected_throughput: 80.0, quality_prior: 0.7 },
+    RegistryModel { id: "BAAI/bge-reranker-v2-m3", kind: ModelKind::Reranker, dimension: None, languages: &[], multilingual: true, size_mb: 2270, max_sequence_length: 8192, expected_throughput: 30.0, quality_prior: 0.8 },
+];
+
+/// Registry entry for a model id
+pub fn registry_model(model_id: &str) -> Option<&'static RegistryModel> {
+    SUPPORTED_MODELS.iter().find(|m| m.id == model_id)
+}
+
+/// Probe set: (query, relevant passage, distractor passage)
+const PROBE_SET: &[(&str, &str, &str)] = &[
+    ("How do I reset my password?", "To change a forgotten password, open Settings and choose Reset password.", "Invoices are emailed on the first day of every month."),
+    ("Which port does the server listen on?", "By default the service accepts connections on TCP port 8080.", "The mobile app supports dark mode on Android and iOS."),
+    ("How much memory is required?", "Minimum system requirements: 8 GB of RAM and 2 CPU cores.", "Our support team is available on weekdays from 9 to 5."),
+    ("Can I export my data?", "Use Export to download all of your records as a CSV file.", "The logo must not be stretched or recolored."),
+    ("What happens when the trial ends?", "After the 14-day trial your workspace switches to the free plan.", "Firewall rules are evaluated from top to bottom."),
+    ("How do I enable two-factor authentication?", "Turn on 2FA under Security by scanning the QR code with an authenticator app.", "Backups are compressed with zstd before upload."),
+    ("Where are log files stored?", "Logs are written to the logs folder inside the data directory.", "Team members can be invited by email address."),
+    ("Is my data encrypted at rest?", "All stored files are encrypted with AES-256 on disk.", "The changelog lists new features for each release."),
+];
+
+/// Runs models for benchmarking (implemented by the embedding worker)
+#[async_trait]
+pub trait EmbeddingBackend: Send + Sync {
+    async fn embed(&self, model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError>;
+
+    /// Relevance score per document for a query
+    async fn rerank(&self, model_id: &str, query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError>;
+
+    /// Models currently loaded (for health output)
+    fn loaded_models(&self) -> Vec<LoadedModel> {
+        Vec::new()
+    }
+}
+
+/// Local latency and quality of a model on the probe set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ModelBenchmark {
+    pub model_id: String,
+    pub kind: ModelKind,
+    pub probes: usize,
+    pub accuracy: f32,              // Share of probes ranking the relevant passage above the distractor
+    pub mean_latency_ms: f64,       // Per probe
+    pub throughput: f64,            // Texts (or pairs) per second
+    pub benchmarked_at: DateTime<Utc>,
+}
+
+/// What the recommendation needs to know about a KB
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct KbModelProfile {
+    pub languages: Vec<String>,     // Empty means English
+    pub chunk_count: usize,
+    pub max_model_size_mb: Option<u64>,
+}
+
+/// Ranked model suggestion with the reasons behind it
+#[derive(Debug, Clone, Serialize)]
+pub struct ModelRecommendation {
+    pub model: RegistryModel,
+    pub score: f32,
+    pub installed: bool,
+    pub benchmark: Option<ModelBenchmark>,
+    pub reasons: Vec<String>,
+}
+
+/// A model an embedding backend holds in memory
+#[derive(Debug, Clone, Serialize)]
+pub struct LoadedModel {
+    pub model_id: String,
+    pub device: String,             // Where its session runs, e.g. `cpu` or `cuda:0`
+    pub memory_bytes: u64,          // Estimated from the model's weights
+    pub load_ms: u64,
+}
+
+/// Downloads and tracks local models
+pub struct ModelService {
+    config: ModelConfig,
+    client: reqwest::Client,
+    reclaimed_bytes: AtomicU64,
+    evicted_models: AtomicU64,
+    policy: Arc<NetworkPolicy>,     // Gates Hub access
+}
+
+impl ModelService {
+    pub fn new(config: ModelConfig) -> Result<Self, ModelError> {
+        let mut builder = reqwest::Client::builder().connect_timeout(CONNECT_TIMEOUT);
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(reqwest::Proxy::all(proxy)?);
+        }
+        Ok(Self {
+            config,
+            client: builder.build()?,
+            reclaimed_bytes: AtomicU64::new(0),
+            evicted_models: AtomicU64::new(0),
+            policy: Arc::new(NetworkPolicy::default()),
+        })
+    }
+
+    pub fn with_network_policy(mut self, policy: Arc<NetworkPolicy>) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    pub fn models_dir(&self) -> &Path {
+        &self.config.models_dir
+    }
+
+    /// Directory a model is installed in ("org/name" -> "org--name")
+    pub fn model_dir(&self, model_id: &str) -> Result<PathBuf, ModelError> {
+        validate_model_id(model_id)?;
+        Ok(self.config.models_dir.join(model_id.replace('/', "--")))
+    }
+
+    /// Files of a model repository at a revision
+    pub async fn list_remote_files(&self, model_id: &str, revision: &str) -> Result<Vec<ModelFile>, ModelError> {
+        validate_model_id(model_id)?;
+        self.policy.check(NetworkFeature::ModelDownloads, model_id)?;
+        let url = format!("{}/api/models/{}/revision/{}?blobs=true", self.endpoint(), model_id, revision);
+        let response = self.authorized(self.client.get(&url)).send().await?;
+        let status = response.status();
+        if status == reqwest::StatusCode::NOT_FOUND {
+            return Err(ModelError::ModelNotFound(model_id.to_string()));
+        }
+        if !status.is_success() {
+            let body: String = response.text().await.unwrap_or_default().chars().take(500).collect();
+            return Err(ModelError::HubError { status: status.as_u16(), body });
+        }
+
+        let info: HubModelInfo = response.json().await?;
+        Ok(info.siblings.into_iter().map(|sibling| ModelFile {
+            size_bytes: sibling.lfs.as_ref().map(|lfs| lfs.size).or(sibling.size).unwrap_or(0),
+            sha256: sibling.lfs.map(|lfs| lfs.sha256),
+            path: sibling.rfilename,
+        }).collect())
+    }
+
+    /// Download a model (optionally only the listed files), resuming partial files
+    pub async fn download_model(
+        &self,
+        model_id: &str,
+        revision: Option<&str>,
+        include: Option<&[String]>,
+        progress: Option<mpsc::Sender<DownloadProgress>>,
+    ) -> Result<ModelManifest, ModelError> {
+        if self.config.offline_mode {
+            return Err(ModelError::AirGapped(model_id.to_string()));
+        }
+        let revision = revision.unwrap_or(DEFAULT_REVISION);
+        let model_dir = self.model_dir(model_id)?;
+
+        let mut files = self.list_remote_files(model_id, revision).await?;
+        if let Some(include) = include {
+            files.retain(|f| include.contains(&f.path));
+            if files.len() != include.len() {
+                let missing: Vec<&String> = include.iter().filter(|p| !files.iter().any(|f| &f.path == *p)).collect();
+                return Err(ModelError::ModelNotFound(format!("{} (files {:?})", model_id, missing)));
+            }
+        }
+        for file in &files {
+            validate_file_path(&file.path)?;
+        }
+
+        let mut report = DownloadProgress {
+            model_id: model_id.to_string(),
+            file: String::new(),
+            files_done: 0,
+            files_total: files.len(),
+            downloaded_bytes: 0,
+            total_bytes: files.iter().map(|f| f.size_bytes).sum(),
+            done: false,
+        };
+        info!("Downloading {}@{} ({} files, {} bytes) from {}", model_id, revision, files.len(), report.total_bytes, self.endpoint());
+
+        for file in &files {
+            report.file = file.path.clone();
+            let url = format!("{}/{}/resolve/{}/{}", self.endpoint(), model_id, revision, file.path);
+            self.download_file(&url, &model_dir.join(&file.path), file, &mut report, progress.as_ref()).await?;
+            report.files_done += 1;
+        }
+
+        let manifest = ModelManifest {
+            id: model_id.to_string(),
+            revision: revision.to_string(),
+            source: self.endpoint().to_string(),
+            size_bytes: report.total_bytes,
+            files,
+            downloaded_at: Utc::now(),
+            bundled: false,
+            pinned: false,
+            last_used_at: None,
+        };
+        tokio::fs::write(model_dir.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?).await?;
+
+        report.done = true;
+        send_progress(progress.as_ref(), &report);
+        info!("Downloaded model {}", model_id);
+        self.auto_cleanup(model_id).await;
+        Ok(manifest)
+    }
+
+    /// Installed models (directories with a manifest)
+    pub async fn list_models(&self) -> Result<Vec<ModelManifest>, ModelError> {
+        let mut models: Vec<ModelManifest> = Vec::new();
+        let mut entries = match tokio::fs::read_dir(&self.config.models_dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(models),
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let manifest_path = entry.path().join(MANIFEST_FILE);
+            match tokio::fs::read(&manifest_path).await {
+                Ok(bytes) => match serde_json::from_slice(&bytes) {
+                    Ok(manifest) => models.push(manifest),
+                    Err(e) => warn!("Skipping unreadable model manifest {:?}: {}", manifest_path, e),
+                },
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        models.sort_by(|a, b| a.id.cmp(&b.id));
+        Ok(models)
+    }
+
+    pub async fn get_model(&self, model_id: &str) -> Result<ModelManifest, ModelError> {
+        let path = self.model_dir(model_id)?.join(MANIFEST_FILE);
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ModelError::ModelNotFound(model_id.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Remove a model and any partial downloads
+    pub async fn delete_model(&self, model_id: &str) -> Result<(), ModelError> {
+        let dir = self.model_dir(model_id)?;
+        match tokio::fs::remove_dir_all(&dir).await {
+            Ok(()) => {
+                info!("Deleted model {}", model_id);
+                Ok(())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(ModelError::ModelNotFound(model_id.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Check an installed model's files against its manifest (sizes and checksums)
+    pub async fn verify_model(&self, model_id: &str) -> Result<ModelManifest, ModelError> {
+        let manifest = self.get_model(model_id).await?;
+        verify_files(&self.model_dir(model_id)?, &manifest.files, true).await?;
+        Ok(manifest)
+    }
+
+    /// Install a model from a tarball (`.tar` or `.tar.gz`) of a model directory
+    /// with its `model.json`, entirely offline; replaces an installed copy
+    pub async fn import_model_from_archive(&self, path: &Path) -> Result<ModelManifest, ModelError> {
+        let manifest = self.import_archive(path, false).await?;
+        self.auto_cleanup(&manifest.id).await;
+        Ok(manifest)
+    }
+
+    /// Installed model directory for inference; records the use for cleanup ordering
+    pub async fn resolve_model(&self, model_id: &str) -> Result<PathBuf, ModelError> {
+        let mut manifest = self.get_model(model_id).await?;
+        manifest.last_used_at = Some(Utc::now());
+        self.write_manifest(&manifest).await?;
+        self.model_dir(model_id)
+    }
+
+    /// Pin or unpin a model; pinned models are never evicted
+    pub async fn set_model_pinned(&self, model_id: &str, pinned: bool) -> Result<ModelManifest, ModelError> {
+        let mut manifest = self.get_model(model_id).await?;
+        manifest.pinned = pinned;
+        self.write_manifest(&manifest).await?;
+        Ok(manifest)
+    }
+
+    pub async fn storage_stats(&self) -> Result<ModelStorageStats, ModelError> {
+        let models = self.list_models().await?;
+        let sum = |filter: fn(&ModelManifest) -> bool| models.iter().filter(|m| filter(m)).map(|m| m.size_bytes).sum();
+        Ok(ModelStorageStats {
+            model_count: models.len(),
+            used_bytes: sum(|_| true),
+            bundled_bytes: sum(|m| m.bundled),
+            pinned_bytes: sum(|m| m.pinned && !m.bundled),
+            evictable_bytes: sum(ModelManifest::evictable),
+            quota_bytes: gb_to_bytes(self.config.cache_size_gb),
+            cleanup_threshold_bytes: self.cleanup_threshold_bytes(),
+            free_space_bytes: self.free_space().await,
+            min_free_space_bytes: gb_to_bytes(self.config.min_free_space_gb),
+            reclaimed_bytes: self.reclaimed_bytes.load(Ordering::Relaxed),
+            evicted_models: self.evicted_models.load(Ordering::Relaxed),
+        })
+    }
+
+    /// Evict least recently used downloadable models until usage is under the
+    /// cleanup threshold and the volume has `min_free_space_gb` free
+    pub async fn enforce_quota(&self) -> Result<ModelCleanupReport, ModelError> {
+        self.enforce_quota_keeping(None).await
+    }
+
+    async fn enforce_quota_keeping(&self, keep: Option<&str>) -> Result<ModelCleanupReport, ModelError> {
+        let models = self.list_models().await?;
+        let threshold = self.cleanup_threshold_bytes();
+        let min_free = gb_to_bytes(self.config.min_free_space_gb);
+        let mut report = ModelCleanupReport {
+            used_bytes: models.iter().map(|m| m.size_bytes).sum(),
+            ..Default::default()
+        };
+        let free = self.free_space().await;
+        let over = |report: &ModelCleanupReport| {
+            report.used_bytes > threshold || free.is_some_and(|free| free + report.reclaimed_bytes < min_free)
+        };
+        if !over(&report) {
+            return Ok(report);
+        }
+
+        let mut candidates: Vec<&ModelManifest> = models.iter()
+            .filter(|m| m.evictable() && Some(m.id.as_str()) != keep)
+            .collect();
+        candidates.sort_by_key(|m| m.last_activity());
+        for model in candidates {
+            if !over(&report) {
+                break;
+            }
+            self.delete_model(&model.id).await?;
+            info!("Evicted model {} ({} bytes, last used {})", model.id, model.size_bytes, model.last_activity());
+            report.evicted.push(model.id.clone());
+            report.reclaimed_bytes += model.size_bytes;
+            report.used_bytes -= model.size_bytes;
+        }
+
+        report.still_over_quota = over(&report);
+        if report.still_over_quota {
+            warn!("Model storage still over quota after cleanup: {} bytes used, only protected models left", report.used_bytes);
+        }
+        self.reclaimed_bytes.fetch_add(report.reclaimed_bytes, Ordering::Relaxed);
+        self.evicted_models.fetch_add(report.evicted.len() as u64, Ordering::Relaxed);
+        Ok(report)
+    }
+
+    /// Cleanup after an install must not fail the install (or evict the new model)
+    async fn auto_cleanup(&self, installed: &str) {
+        if let Err(e) = self.enforce_quota_keeping(Some(installed)).await {
+            warn!("Model storage cleanup failed: {}", e);
+        }
+    }
+
+    fn cleanup_threshold_bytes(&self) -> u64 {
+        gb_to_bytes(self.config.cache_size_gb * self.config.auto_cleanup_threshold.clamp(0.0, 1.0))
+    }
+
+    async fn free_space(&self) -> Option<u64> {
+        let dir = self.config.models_dir.clone();
+        match tokio::task::spawn_blocking(move || fs4::available_space(&dir)).await {
+            Ok(Ok(bytes)) => Some(bytes),
+            Ok(Err(e)) => {
+                debug!("Free space of {:?} unavailable: {}", self.config.models_dir, e);
+                None
+            }
+            Err(_) => None,
+        }
+    }
+
+    async fn write_manifest(&self, manifest: &ModelManifest) -> Result<(), ModelError> {
+        let path = self.model_dir(&manifest.id)?.join(MANIFEST_FILE);
+        tokio::fs::write(path, serde_json::to_vec_pretty(manifest)?).await?;
+        Ok(())
+    }
+
+    /// Install bundled models that are missing or damaged (first run, app updates);
+    /// a broken archive is logged and skipped so the others still install
+    pub async fn verify_bundled_models(&self) -> Result<Vec<ModelManifest>, ModelError> {
+        let Some(dir) = &self.config.bundled_models else {
+            return Ok(Vec::new());
+        };
+        let mut archives = Vec::new();
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                warn!("Bundled models directory {:?} not found", dir);
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(e.into()),
+        };
+        while let Some(entry) = entries.next_entry().await? {
+            let name = entry.file_name().to_string_lossy().to_lowercase();
+            if ARCHIVE_EXTENSIONS.iter().any(|ext| name.ends_with(ext)) {
+                archives.push(entry.path());
+            }
+        }
+        archives.sort();
+
+        let mut installed = Vec::new();
+        for archive in archives {
+            let path = archive.clone();
+            let manifest = match tokio::task::spawn_blocking(move || read_archive_manifest(&path)).await.map_err(std::io::Error::other)? {
+                Ok(manifest) => manifest,
+                Err(e) => {
+                    warn!("Skipping bundled model archive {:?}: {}", archive, e);
+                    continue;
+                }
+            };
+
+            // Installed copies get a size check; checksums were verified at install
+            if let Ok(current) = self.get_model(&manifest.id).await {
+                let dir = self.model_dir(&manifest.id)?;
+                if current.revision == manifest.revision && verify_files(&dir, &current.files, false).await.is_ok() {
+                    debug!("Bundled model {} already installed", manifest.id);
+                    installed.push(current);
+                    continue;
+                }
+            }
+
+            match self.import_archive(&archive, true).await {
+                Ok(manifest) => installed.push(manifest),
+                Err(e) => warn!("Failed to install bundled model from {:?}: {}", archive, e),
+            }
+        }
+        Ok(installed)
+    }
+
+    /// Measure latency and quality of a supported model on the probe set; the result is saved
+    pub async fn benchmark_model(&self, model_id: &str, backend: &dyn EmbeddingBackend) -> Result<ModelBenchmark, ModelError> {
+        let model = registry_model(model_id).ok_or_else(|| ModelError::ModelNotFound(model_id.to_string()))?;
+
+        let mut correct = 0;
+        let mut texts_processed = 0;
+        let started = Instant::now();
+        for (query, relevant, distractor) in PROBE_SET {
+            let passages = vec![relevant.to_string(), distractor.to_string()];
+            let scores = match model.kind {
+                ModelKind::Embedding => {
+                    let inputs = vec![query.to_string(), passages[0].clone(), passages[1].clone()];
+                    let vectors = backend.embed(model_id, &inputs).await?;
+                    if vectors.len() != inputs.len() {
+                        return Err(ModelError::Backend(format!("expected {} embeddings, got {}", inputs.len(), vectors.len())));
+                    }
+                    texts_processed += inputs.len();
+                    vec![cosine(&vectors[0], &vectors[1]), cosine(&vectors[0], &vectors[2])]
+                }
+                ModelKind::Reranker => {
+                    let scores = backend.rerank(model_id, query, &passages).await?;
+                    if scores.len() != passages.len() {
+                        return Err(ModelError::Backend(format!("expected {} scores, got {}", passages.len(), scores.len())));
+                    }
+                    texts_processed += passages.len();
+                    scores
+                }
+            };
+            if scores[0] > scores[1] {
+                correct += 1;
+            }
+        }
+        let elapsed = started.elapsed().as_secs_f64();
+
+        let benchmark = ModelBenchmark {
+            model_id: model_id.to_string(),
+            kind: model.kind,
+            probes: PROBE_SET.len(),
+            accuracy: correct as f32 / PROBE_SET.len() as f32,
+            mean_latency_ms: elapsed * 1000.0 / PROBE_SET.len() as f64,
+            throughput: if elapsed > 0.0 { texts_processed as f64 / elapsed } else { 0.0 },
+            benchmarked_at: Utc::now(),
+        };
+
+        let dir = self.config.models_dir.join(BENCHMARKS_DIR);
+        tokio::fs::create_dir_all(&dir).await?;
+        tokio::fs::write(dir.join(benchmark_file(model_id)), serde_json::to_vec_pretty(&benchmark)?).await?;
+        info!("Benchmarked {}: accuracy {:.2}, {:.1} ms/probe", model_id, benchmark.accuracy, benchmark.mean_latency_ms);
+        Ok(benchmark)
+    }
+
+    /// Saved benchmark for a model, if one was run
+    pub async fn get_benchmark(&self, model_id: &str) -> Result<Option<ModelBenchmark>, ModelError> {
+        let path = self.config.models_dir.join(BENCHMARKS_DIR).join(benchmark_file(model_id));
+        match tokio::fs::read(&path).await {
+            Ok(bytes) => Ok(Some(serde_json::from_slice(&bytes)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Supported models of a kind ranked for a KB: language coverage and size budget
+    /// filter, quality (benchmarked when available) and throughput rank
+    pub async fn recommend_models(&self, kind: ModelKind, profile: &KbModelProfile) -> Result<Vec<ModelRecommendation>, ModelError> {
+        let languages: Vec<&str> = if profile.languages.is_empty() {
+            vec!["en"]
+        } else {
+            profile.languages.iter().map(String::as_str).collect()
+        };
+        let installed: Vec<String> = self.list_models().await?.into_iter().map(|m| m.id).collect();
+
+        let candidates: Vec<&RegistryModel> = SUPPORTED_MODELS.iter()
+            .filter(|m| m.kind == kind)
+            .filter(|m| languages.iter().all(|l| m.supports_language(l)))
+            .filter(|m| profile.max_model_size_mb.is_none_or(|max| m.size_mb <= max))
+            .collect();
+        let fastest = candidates.iter().map(|m| m.expected_throughput).fold(0.0_f32, f32::max);
+        // Large KBs spend most time embedding, so speed matters more
+        let speed_weight = if profile.chunk_count > LARGE_KB_CHUNKS { 0.5 } else { 0.2 };
+
+        let mut recommendations = Vec::with_capacity(candidates.len());
+        for model in candidates {
+            let benchmark = self.get_benchmark(model.id).await?;
+            let is_installed = installed.iter().any(|id| id == model.id);
+            let mut reasons = Vec::new();
+
+            let quality = match &benchmark {
+                Some(b) => {
+                    reasons.push(format!("local benchmark accuracy {:.0}%", b.accuracy * 100.0));
+                    b.accuracy
+                }
+                None => model.quality_prior,
+            };
+            let speed = if fastest > 0.0 { model.expected_throughput / fastest } else { 0.0 };
+            let mut score = quality * (1.0 - speed_weight) + speed * speed_weight;
+
+            if model.multilingual && languages.iter().any(|l| *l != "en") {
+                reasons.push("covers all KB languages".to_string());
+            }
+            if speed_weight > 0.2 && speed >= 0.5 {
+                reasons.push(format!("fast enough for {} chunks", profile.chunk_count));
+            }
+            if is_installed {
+                score += 0.05;
+                reasons.push("already installed".to_string());
+            }
+            recommendations.push(ModelRecommendation { model: model.clone(), score, installed: is_installed, benchmark, reasons });
+        }
+
+        recommendations.sort_by(|a, b| b.score.total_cmp(&a.score));
+        Ok(recommendations)
+    }
+
+    /// Unpack into a staging directory, verify every file, then move into place
+    async fn import_archive(&self, path: &Path, bundled: bool) -> Result<ModelManifest, ModelError> {
+        tokio::fs::create_dir_all(&self.config.models_dir).await?;
+        let staging = self.config.models_dir.join(format!("{}{}", IMPORT_STAGING_PREFIX, uuid::Uuid::new_v4()));
+        let result = self.install_from_staging(path, &staging, bundled).await;
+        if let Err(e) = tokio::fs::remove_dir_all(&staging).await {
+            if e.kind() != std::io::ErrorKind::NotFound {
+                warn!("Failed to clean up {:?}: {}", staging, e);
+            }
+        }
+        result
+    }
+
+    async fn install_from_staging(&self, path: &Path, staging: &Path, bundled: bool) -> Result<ModelManifest, ModelError> {
+        let (archive, dest) = (path.to_path_buf(), staging.to_path_buf());
+        tokio::task::spawn_blocking(move || unpack_archive(&archive, &dest)).await.map_err(std::io::Error::other)??;
+
+        // Archives hold the model files at the root or inside one top-level directory
+        let mut root = staging.to_path_buf();
+        if !root.join(MANIFEST_FILE).is_file() {
+            let mut dirs = Vec::new();
+            let mut entries = tokio::fs::read_dir(staging).await?;
+            while let Some(entry) = entries.next_entry().await? {
+                dirs.push(entry.path());
+            }
+            match dirs.as_slice() {
+                [dir] if dir.join(MANIFEST_FILE).is_file() => root = dir.clone(),
+                _ => return Err(ModelError::InvalidArchive(format!("{:?} has no {}", path, MANIFEST_FILE))),
+            }
+        }
+
+        let mut manifest: ModelManifest = serde_json::from_slice(&tokio::fs::read(root.join(MANIFEST_FILE)).await?)?;
+        let model_dir = self.model_dir(&manifest.id)?;
+        verify_files(&root, &manifest.files, true).await?;
+
+        manifest.source = format!("archive:{}", path.file_name().unwrap_or_default().to_string_lossy());
+        manifest.size_bytes = manifest.files.iter().map(|f| f.size_bytes).sum();
+        manifest.downloaded_at = Utc::now();
+        manifest.bundled = bundled;
+        tokio::fs::write(root.join(MANIFEST_FILE), serde_json::to_vec_pretty(&manifest)?).await?;
+
+        match tokio::fs::remove_dir_all(&model_dir).await {
+            Ok(()) => info!("Replacing installed model {}", manifest.id),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => return Err(e.into()),
+        }
+        tokio::fs::rename(&root, &model_dir).await?;
+        info!("Imported model {} from {:?}", manifest.id, path);
+        Ok(manifest)
+    }
+
+    fn endpoint(&self) -> &str {
+        self.config.hf_endpoint.trim_end_matches('/')
+    }
+
+    fn authorized(&self, request: reqwest::RequestBuilder) -> reqwest::RequestBuilder {
+        match &self.config.hf_token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    /// Download one file to `dest`, resuming from `dest.part` when the server honours ranges
+    async fn download_file(
+        &self,
+        url: &str,
+        dest: &Path,
+        file: &ModelFile,
+        report: &mut DownloadProgress,
+        progress: Option<&mpsc::Sender<DownloadProgress>>,
+    ) -> Result<(), ModelError> {
+        if let Some(parent) = dest.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+
+        // Already installed by an earlier (interrupted) run of this model
+        if let Ok(metadata) = tokio::fs::metadata(dest).await {
+            if metadata.len() == file.size_bytes && verify_checksum(dest, file).await.is_ok() {
+                debug!("{} already downloaded", file.path);
+                report.downloaded_bytes += file.size_bytes;
+                send_progress(progress, report);
+                return Ok(());
+            }
+        }
+
+        let part = part_path(dest);
+        let mut offset = tokio::fs::metadata(&part).await.map(|m| m.len()).unwrap_or(0);
+        if offset > file.size_bytes {
+            offset = 0;
+        }
+
+        let mut request = self.authorized(self.client.get(url));
+        if offset > 0 {
+            request = request.header(reqwest::header::RANGE, format!("bytes={}-", offset));
+        }
+        let mut response = request.send().await?;
+        let status = response.status();
+
+        let mut out = if status == reqwest::StatusCode::PARTIAL_CONTENT && offset > 0 {
+            debug!("Resuming {} at byte {}", file.path, offset);
+            tokio::fs::OpenOptions::new().append(true).open(&part).await?
+        } else if status.is_success() {
+            offset = 0;
+            tokio::fs::File::create(&part).await?
+        } else if status == reqwest::StatusCode::RANGE_NOT_SATISFIABLE && offset == file.size_bytes {
+            // The part file already holds every byte
+            tokio::fs::OpenOptions::new().append(true).open(&part).await?
+        } else {
+            let body: String = response.text().await.unwrap_or_default().chars().take(500).collect();
+            return Err(ModelError::HubError { status: status.as_u16(), body });
+        };
+
+        report.downloaded_bytes += offset;
+        send_progress(progress, report);
+        if status.is_success() {
+            while let Some(chunk) = response.chunk().await? {
+                out.write_all(&chunk).await?;
+                offset += chunk.len() as u64;
+                report.downloaded_bytes += chunk.len() as u64;
+                send_progress(progress, report);
+            }
+        }
+        out.flush().await?;
+        drop(out);
+
+        if file.size_bytes > 0 && offset != file.size_bytes {
+            // Keep the part file so the next attempt resumes
+            return Err(ModelError::Incomplete { file: file.path.clone(), received: offset, expected: file.size_bytes });
+        }
+        if let Err(e) = verify_checksum(&part, file).await {
+            tokio::fs::remove_file(&part).await?;
+            return Err(e);
+        }
+        tokio::fs::rename(&part, dest).await?;
+        Ok(())
+    }
+}
+
+fn gb_to_bytes(gb: f64) -> u64 {
+    (gb.max(0.0) * BYTES_PER_GB) as u64
+}
+
+fn benchmark_file(model_id: &str) -> String {
+    format!("{}.json", model_id.replace('/', "--"))
+}
+
+fn cosine(a: &[f32], b: &[f32]) -> f32 {
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm = a.iter().map(|x| x * x).sum::<f32>().sqrt() * b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 { dot / norm } else { 0.0 }
+}
+
+/// Repo ids are "name" or "org/name" with Hub-safe characters
+fn validate_model_id(model_id: &str) -> Result<(), ModelError> {
+    let parts: Vec<&str> = model_id.split('/').collect();
+    let valid = (1..=2).contains(&parts.len())
+        && parts.iter().all(|part| {
+            !part.is_empty()
+                && !part.starts_with('.')
+                && part.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        });
+    if valid {
+        Ok(())
+    } else {
+        Err(ModelError::InvalidModelId(model_id.to_string()))
+    }
+}
+
+/// Repository file paths must stay inside the model directory
+fn validate_file_path(path: &str) -> Result<(), ModelError> {
+    let safe = !path.is_empty() && Path::new(path).components().all(|c| matches!(c, Component::Normal(_)));
+    if safe {
+        Ok(())
+    } else {
+        Err(ModelError::InvalidModelId(format!("unsafe file path '{}'", path)))
+    }
+}
+
+/// Every manifest file must be a regular file of the listed size (and checksum)
+async fn verify_files(root: &Path, files: &[ModelFile], checksums: bool) -> Result<(), ModelError> {
+    for file in files {
+        validate_file_path(&file.path)?;
+        let path = root.join(&file.path);
+        let metadata = match tokio::fs::symlink_metadata(&path).await {
+            Ok(metadata) if metadata.is_file() => metadata,
+            Ok(_) => return Err(ModelError::InvalidArchive(format!("{} is not a regular file", file.path))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                return Err(ModelError::Incomplete { file: file.path.clone(), received: 0, expected: file.size_bytes });
+            }
+            Err(e) => return Err(e.into()),
+        };
+        if metadata.len() != file.size_bytes {
+            return Err(ModelError::Incomplete { file: file.path.clone(), received: metadata.len(), expected: file.size_bytes });
+        }
+        if checksums {
+            verify_checksum(&path, file).await?;
+        }
+    }
+    Ok(())
+}
+
+/// Open a tarball, gzip-compressed or not
+fn open_archive(path: &Path) -> std::io::Result<tar::Archive<Box<dyn Read + Send>>> {
+    let mut file = std::fs::File::open(path)?;
+    let mut magic = [0u8; 2];
+    let gzipped = file.read(&mut magic)? == 2 && magic == [0x1f, 0x8b];
+    file.seek(SeekFrom::Start(0))?;
+    let reader: Box<dyn Read + Send> = if gzipped {
+        Box::new(flate2::read::GzDecoder::new(file))
+    } else {
+        Box::new(file)
+    };
+    Ok(tar::Archive::new(reader))
+}
+
+/// `tar` skips entries that would escape `dest`
+fn unpack_archive(path: &Path, dest: &Path) -> Result<(), ModelError> {
+    std::fs::create_dir_all(dest)?;
+    open_archive(path)?
+        .unpack(dest)
+        .map_err(|e| ModelError::InvalidArchive(format!("{:?}: {}", path, e)))
+}
+
+/// Manifest of an archive without unpacking the model files
+fn read_archive_manifest(path: &Path) -> Result<ModelManifest, ModelError> {
+    let mut archive = open_archive(path)?;
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let entry_path = entry.path()?.into_owned();
+        if entry_path.file_name().is_some_and(|name| name == MANIFEST_FILE) && entry_path.components().count() <= 2 {
+            let mut bytes = Vec::new();
+            entry.read_to_end(&mut bytes)?;
+            return Ok(serde_json::from_slice(&bytes)?);
+        }
+    }
+    Err(ModelError::InvalidArchive(format!("{:?} has no {}", path, MANIFEST_FILE)))
+}
+
+fn part_path(dest: &Path) -> PathBuf {
+    let mut name = dest.file_name().unwrap_or_default().to_os_string();
+    name.push(PART_SUFFIX);
+    dest.with_file_name(name)
+}
+
+/// Compare a file against the published SHA-256 (files without one pass)
+async fn verify_checksum(path: &Path, file: &ModelFile) -> Result<(), ModelError> {
+    let Some(expected) = &file.sha256 else {
+        return Ok(());
+    };
+    let actual = sha256_file(path).await?;
+    if actual.eq_ignore_ascii_case(expected) {
+        Ok(())
+    } else {
+        Err(ModelError::ChecksumMismatch { file: file.path.clone(), expected: expected.clone(), actual })
+    }
+}
+
+/// SHA-256 of a file as lowercase hex, read in chunks
+pub async fn sha256_file(path: &Path) -> Result<String, ModelError> {
+    let mut reader = tokio::fs::File::open(path).await?;
+    let mut context = ring::digest::Context::new(&ring::digest::SHA256);
+    let mut buf = vec![0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf).await?;
+        if n == 0 {
+            break;
+        }
+        context.update(&buf[..n]);
+    }
+    Ok(context.finish().as_ref().iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+/// Progress is best-effort: a slow UI must not stall the download
+fn send_progress(progress: Option<&mpsc::Sender<DownloadProgress>>, report: &DownloadProgress) {
+    if let Some(tx) = progress {
+        let _ = tx.try_send(report.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tempfile::TempDir;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::sync::Mutex;
+    use crate::services::storage::sha256_hex;
+
+    const WEIGHTS: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    const CONFIG: &[u8] = br#"{"dim":384}"#;
+
+    /// Minimal Hub: model info plus file downloads honouring `Range: bytes=N-`
+    async fn serve_hub(weights_sha: String) -> (String, Arc<Mutex<Vec<String>>>) {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let requests = Arc::new(Mutex::new(Vec::new()));
+        let seen = requests.clone();
+        tokio::spawn(async move {
+            loop {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                while !String::from_utf8_lossy(&raw).contains("\r\n\r\n") {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                }
+                let request = String::from_utf8_lossy(&raw).to_string();
+                seen.lock().await.push(request.clone());
+
+                let path = request.split_whitespace().nth(1).unwrap_or_default().to_string();
+                let range = request.lines().find_map(|l| {
+                    let l = l.to_lowercase();
+                    l.strip_prefix("range: bytes=").map(|r| r.trim().trim_end_matches('-').parse::<usize>().unwrap())
+                });
+                let (status, body): (&str, Vec<u8>) = if path.starts_with("/api/models/org/tiny/revision/main") {
+                    let info = serde_json::json!({"siblings": [
+                        {"rfilename": "config.json", "size": CONFIG.len()},
+                        {"rfilename": "onnx/model.bin", "size": WEIGHTS.len(), "lfs": {"sha256": weights_sha, "size": WEIGHTS.len()}},
+                    ]});
+                    ("200 OK", info.to_string().into_bytes())
+                } else if path == "/org/tiny/resolve/main/config.json" {
+                    ("200 OK", CONFIG.to_vec())
+                } else if path == "/org/tiny/resolve/main/onnx/model.bin" {
+                    match range {
+                        Some(start) => ("206 Partial Content", WEIGHTS[start..].to_vec()),
+                        None => ("200 OK", WEIGHTS.to_vec()),
+                    }
+                } else {
+                    ("404 Not Found", b"not found".to_vec())
+                };
+                let head = format!("HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n", status, body.len());
+                socket.write_all(head.as_bytes()).await.unwrap();
+                socket.write_all(&body).await.unwrap();
+            }
+        });
+        (endpoint, requests)
+    }
+
+    #[tokio::test]
+    async fn test_download_resumes_partial_file() {
+        let temp_dir = TempDir::new().unwrap();
+        let (endpoint, requests) = serve_hub(sha256_hex(WEIGHTS)).await;
+        let policy = Arc::new(NetworkPolicy::new(false));
+        let service = ModelService::new(ModelConfig::test_config(temp_dir.path(), &endpoint)).unwrap()
+            .with_network_policy(policy.clone());
+
+        // An earlier run stopped after 10 bytes
+        let model_dir = service.model_dir("org/tiny").unwrap();
+        tokio::fs::create_dir_all(model_dir.join("onnx")).await.unwrap();
+        tokio::fs::write(model_dir.join("onnx/model.bin.part"), &WEIGHTS[..10]).await.unwrap();
+
+        let (tx, mut rx) = mpsc::channel(64);
+        let manifest = service.download_model("org/tiny", None, None, Some(tx)).await.unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert_eq!(manifest.size_bytes, (WEIGHTS.len() + CONFIG.len()) as u64);
+        assert_eq!(tokio::fs::read(model_dir.join("onnx/model.bin")).await.unwrap(), WEIGHTS);
+        assert!(!model_dir.join("onnx/model.bin.part").exists());
+
+        let requests = requests.lock().await;
+        assert!(requests.iter().any(|r| r.starts_with("GET /org/tiny/resolve/main/onnx/model.bin") && r.to_lowercase().contains("range: bytes=10-")));
+
+        let mut last = None;
+        while let Ok(update) = rx.try_recv() {
+            last = Some(update);
+        }
+        let last = last.unwrap();
+        assert!(last.done);
+        assert_eq!((last.files_done, last.downloaded_bytes), (2, manifest.size_bytes));
+
+        assert_eq!(service.list_models().await.unwrap()[0].id, "org/tiny");
+        policy.set_air_gapped(true);
+        assert!(matches!(
+            service.download_model("org/tiny", None, None, None).await,
+            Err(ModelError::Policy(PolicyError::Blocked { .. }))
+        ));
+        service.delete_model("org/tiny").await.unwrap();
+        assert!(matches!(service.get_model("org/tiny").await, Err(ModelError::ModelNotFound(_))));
+    }
+
+    #[tokio::test]
+    async fn test_checksum_mismatch_discards_download() {
+        let temp_dir = TempDir::new().unwrap();
+        let (endpoint, _) = serve_hub(sha256_hex(b"something else")).await;
+        let service = ModelService::new(ModelConfig::test_config(temp_dir.path(), &endpoint)).unwrap();
+
+        let include = vec!["onnx/model.bin".to_string()];
+        let result = service.download_model("org/tiny", None, Some(&include), None).await;
+        assert!(matches!(result, Err(ModelError::ChecksumMismatch { .. })));
+
+        let model_dir = service.model_dir("org/tiny").unwrap();
+        assert!(!model_dir.join("onnx/model.bin").exists());
+        assert!(!model_dir.join("onnx/model.bin.part").exists());
+
+        assert!(matches!(service.model_dir("../etc"), Err(ModelError::InvalidModelId(_))));
+        assert!(validate_file_path("../../evil").is_err());
+    }
+
+    /// Bag-of-words hashing "model": good enough to separate the probes
+    struct HashingBackend;
+
+    #[async_trait]
+    impl EmbeddingBackend for HashingBackend {
+        async fn embed(&self, _model_id: &str, texts: &[String]) -> Result<Vec<Vec<f32>>, ModelError> {
+            Ok(texts.iter().map(|text| {
+                let mut vector = vec![0.0; 64];
+                for term in crate::modules::kb::mmr::terms(text) {
+                    let slot = term.bytes().fold(0usize, |h, b| h.wrapping_mul(31).wrapping_add(b as usize)) % 64;
+                    vector[slot] += 1.0;
+                }
+                vector
+            }).collect())
+        }
+
+        async fn rerank(&self, _model_id: &str, _query: &str, documents: &[String]) -> Result<Vec<f32>, ModelError> {
+            Ok(vec![0.5; documents.len()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_benchmark_and_recommend() {
+        let temp_dir = TempDir::new().unwrap();
+        let service = ModelService::new(ModelConfig::test_config(temp_dir.path(), "http://127.0.0.1:9")).unwrap();
+
+        let benchmark = service.benchmark_model("BAAI/bge-small-en-v1.5", &HashingBackend).await.unwrap();
+        assert_eq!(benchmark.probes, PROBE_SET.len());
+        assert!(benchmark.accuracy >= 0.5);
+        let reranker = service.benchmark_model("cross-encoder/ms-marco-MiniLM-L-6-v2", &HashingBackend).await.unwrap();
+        assert_eq!(reranker.accuracy, 0.0);
+        assert!(matches!(service.benchmark_model("org/unknown", &HashingBackend).await, Err(ModelError::ModelNotFound(_))));
+
+        // German content rules out English-only models
+        let profile = KbModelProfile { languages: vec!["de".to_string()], chunk_count: 1_000, max_model_size_mb: None };
+        let ranked = service.recommend_models(ModelKind::Embedding, &profile).await.unwrap();
+        assert!(!ranked.is_empty());
+        assert!(ranked.iter().all(|r| r.model.multilingual));
+
+        // Size budget and saved benchmarks are applied
+        let profile = KbModelProfile { languages: Vec::new(), chunk_count: 500_000, max_model_size_mb: Some(500) };
+        let ranked = service.recommend_models(ModelKind::Embedding, &profile).await.unwrap();
+        assert!(ranked.iter().all(|r| r.model.size_mb <= 500));
+        let bge = ranked.iter().find(|r| r.model.id == "BAAI/bge-small-en-v1.5").unwrap();
+        assert_eq!(bge.benchmark.as_ref().unwrap().accuracy, benchmark.accuracy);
+        assert!(ranked.windows(2).all(|w| w[0].score >= w[1].score));
+    }
+
+    /// Tarball of a model directory, optionally with a corrupted weights file
+    fn build_archive(path: &Path, model_id: &str, weights: &[u8], corrupt: bool) {
+        let manifest = ModelManifest {
+            id: model_id.to_string(),
+            revision: "abc123".to_string(),
+            source: "https://huggingface.co".to_string(),
+            files: vec![ModelFile {
+                path: "model.safetensors".to_string(),
+                size_bytes: weights.len() as u64,
+                sha256: Some(sha256_hex(weights)),
+            }],
+            size_bytes: weights.len() as u64,
+            downloaded_at: Utc::now(),
+            bundled: false,
+            pinned: false,
+            last_used_at: None,
+        };
+        let stored: Vec<u8> = if corrupt { weights.iter().map(|b| b ^ 0xff).collect() } else { weights.to_vec() };
+        let dir = model_id.replace('/', "--");
+
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(path).unwrap(), flate2::Compression::fast());
+        let mut builder = tar::Builder::new(encoder);
+        for (name, data) in [(MANIFEST_FILE, serde_json::to_vec(&manifest).unwrap()), ("model.safetensors", stored)] {
+            let mut header = tar::Header::new_gnu();
+            header.set_size(data.len() as u64);
+            header.set_mode(0o644);
+            header.set_cksum();
+            builder.append_data(&mut header, format!("{}/{}", dir, name), data.as_slice()).unwrap();
+        }
+        builder.into_inner().unwrap().finish().unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_import_model_from_archive_offline() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ModelConfig::test_config(temp_dir.path(), "http://127.0.0.1:9");
+        config.offline_mode = true;
+        let service = ModelService::new(config).unwrap();
+
+        assert!(matches!(
+            service.download_model("org/tiny-embedder", None, None, None).await,
+            Err(ModelError::AirGapped(_))
+        ));
+
+        let weights = b"offline weights".repeat(100);
+        let good = temp_dir.path().join("tiny.tar.gz");
+        build_archive(&good, "org/tiny-embedder", &weights, false);
+        let manifest = service.import_model_from_archive(&good).await.unwrap();
+        assert_eq!(manifest.source, "archive:tiny.tar.gz");
+        assert!(!manifest.bundled);
+        assert_eq!(service.verify_model("org/tiny-embedder").await.unwrap().id, "org/tiny-embedder");
+        let installed = service.model_dir("org/tiny-embedder").unwrap().join("model.safetensors");
+        assert_eq!(tokio::fs::read(&installed).await.unwrap(), weights);
+
+        // A corrupted archive is rejected without touching the installed copy or leaving staging dirs
+        let bad = temp_dir.path().join("bad.tar.gz");
+        build_archive(&bad, "org/tiny-embedder", &weights, true);
+        assert!(matches!(service.import_model_from_archive(&bad).await, Err(ModelError::ChecksumMismatch { .. })));
+        assert_eq!(tokio::fs::read(&installed).await.unwrap(), weights);
+        let ids: Vec<String> = service.list_models().await.unwrap().into_iter().map(|m| m.id).collect();
+        assert_eq!(ids, vec!["org/tiny-embedder"]);
+        let mut entries = std::fs::read_dir(service.models_dir()).unwrap();
+        assert!(entries.all(|e| !e.unwrap().file_name().to_string_lossy().starts_with(IMPORT_STAGING_PREFIX)));
+    }
+
+    #[tokio::test]
+    async fn test_verify_bundled_models_installs_and_repairs() {
+        let temp_dir = TempDir::new().unwrap();
+        let bundled_dir = temp_dir.path().join("bundled");
+        std::fs::create_dir_all(&bundled_dir).unwrap();
+        let weights = b"bundled weights".repeat(50);
+        build_archive(&bundled_dir.join("mini.tgz"), "org/mini", &weights, false);
+        build_archive(&bundled_dir.join("broken.tar.gz"), "org/broken", &weights, true);
+        std::fs::write(bundled_dir.join("README.txt"), "not an archive").unwrap();
+
+        let mut config = ModelConfig::test_config(temp_dir.path(), "http://127.0.0.1:9");
+        config.bundled_models = Some(bundled_dir);
+        let service = ModelService::new(config).unwrap();
+
+        let installed = service.verify_bundled_models().await.unwrap();
+        assert_eq!(installed.len(), 1);
+        assert!(installed[0].bundled);
+        assert!(matches!(service.get_model("org/broken").await, Err(ModelError::ModelNotFound(_))));
+
+        // A damaged install is restored from the archive on the next check
+        let weights_path = service.model_dir("org/mini").unwrap().join("model.safetensors");
+        std::fs::write(&weights_path, b"truncated").unwrap();
+        let installed = service.verify_bundled_models().await.unwrap();
+        assert_eq!(installed.len(), 1);
+        assert_eq!(std::fs::read(&weights_path).unwrap(), weights);
+    }
+
+    /// Installed model of `size` bytes last used `age_hours` ago
+    async fn install_fake(service: &ModelService, model_id: &str, size: usize, age_hours: i64, bundled: bool, pinned: bool) {
+        let dir = service.model_dir(model_id).unwrap();
+        tokio::fs::create_dir_all(&dir).await.unwrap();
+        tokio::fs::write(dir.join("weights.bin"), vec![0u8; size]).await.unwrap();
+        let manifest = ModelManifest {
+            id: model_id.to_string(),
+            revision: DEFAULT_REVISION.to_string(),
+            source: "test".to_string(),
+            files: vec![ModelFile { path: "weights.bin".to_string(), size_bytes: size as u64, sha256: None }],
+            size_bytes: size as u64,
+            downloaded_at: Utc::now() - chrono::Duration::days(30),
+            bundled,
+            pinned,
+            last_used_at: Some(Utc::now() - chrono::Duration::hours(age_hours)),
+        };
+        service.write_manifest(&manifest).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_quota_evicts_least_recently_used() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = ModelConfig::test_config(temp_dir.path(), "http://127.0.0.1:9");
+        config.cache_size_gb = 1300.0 / BYTES_PER_GB;
+        let service = ModelService::new(config).unwrap();
+
+        // Oldest use first, but the pinned and bundled models are older still
+        install_fake(&service, "org/bundled", 400, 100, true, false).await;
+        install_fake(&service, "org/pinned", 400, 90, false, true).await;
+        install_fake(&service, "org/stale", 400, 48, false, false).await;
+        install_fake(&service, "org/recent", 400, 1, false, false).await;
+        assert_eq!(service.storage_stats().await.unwrap().evictable_bytes, 800);
+
+        let report = service.enforce_quota().await.unwrap();
+        assert_eq!(report.evicted, vec!["org/stale"]);
+        assert_eq!(report.reclaimed_bytes, 400);
+        assert!(!report.still_over_quota);
+
+        // Using a model moves it to the back of the eviction order
+        service.resolve_model("org/recent").await.unwrap();
+        install_fake(&service, "org/newer", 400, 1, false, false).await;
+        let report = service.enforce_quota().await.unwrap();
+        assert_eq!(report.evicted, vec!["org/newer"]);
+
+        // Protected models alone can exceed the quota
+        service.set_model_pinned("org/recent", true).await.unwrap();
+        install_fake(&service, "org/extra", 400, 0, false, true).await;
+        let report = service.enforce_quota().await.unwrap();
+        assert!(report.evicted.is_empty());
+        assert!(report.still_over_quota);
+
+        let stats = service.storage_stats().await.unwrap();
+        assert_eq!(stats.reclaimed_bytes, 800);
+        assert_eq!(stats.evicted_models, 2);
+        assert_eq!(stats.used_bytes, 1600);
+        assert_eq!(stats.bundled_bytes, 400);
+        assert_eq!(stats.pinned_bytes, 1200);
+    }
+}
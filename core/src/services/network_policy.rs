@@ -0,0 +1,338 @@
+/*!
+ * Network Policy Service
+ *
+ * One air-gapped switch shared by every service that can reach the network:
+ * model downloads from the Hub, web fetch steps, remote LLM providers and the
+ * MCP server's outbound HTTP. Services call `check` before leaving the
+ * machine and surface the typed `PolicyError` when it refuses. Loopback
+ * targets never count as egress.
+ *
+ * While air-gapped, single features can be let through with an override.
+ * Every override change is appended to an audit log (JSON lines) so the
+ * policy panel can show who opened what, when and why.
+ */
+
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt;
+use std::io::{BufRead, Write};
+use std::net::IpAddr;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+/// Default location of the override audit log
+pub const DEFAULT_POLICY_AUDIT_PATH: &str = "./network_policy_audit.jsonl";
+
+/// Audit entries kept in memory for the policy panel
+const AUDIT_HISTORY_LIMIT: usize = 200;
+
+/// Network Policy Error Types
+#[derive(Debug, Error)]
+pub enum PolicyError {
+    #[error("{feature} is blocked by the air-gapped network policy: {target}")]
+    Blocked { feature: NetworkFeature, target: String },
+
+    #[error("Unknown network feature: {0}")]
+    UnknownFeature(String),
+
+    #[error("Failed to write policy audit log: {0}")]
+    AuditError(#[from] std::io::Error),
+}
+
+/// Features that need outbound network access
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NetworkFeature {
+    ModelDownloads,     // Hugging Face Hub listings and downloads
+    WebFetch,           // Pipeline fetch steps and crawlers
+    RemoteLlm,          // LLM providers not on loopback
+    McpOutbound,        // MCP server HTTP beyond the Manager (net.fetch tools, remote outbound URL)
+}
+
+impl NetworkFeature {
+    pub const ALL: [NetworkFeature; 4] = [
+        NetworkFeature::ModelDownloads,
+        NetworkFeature::WebFetch,
+        NetworkFeature::RemoteLlm,
+        NetworkFeature::McpOutbound,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            NetworkFeature::ModelDownloads => "model_downloads",
+            NetworkFeature::WebFetch => "web_fetch",
+            NetworkFeature::RemoteLlm => "remote_llm",
+            NetworkFeature::McpOutbound => "mcp_outbound",
+        }
+    }
+}
+
+impl fmt::Display for NetworkFeature {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl FromStr for NetworkFeature {
+    type Err = PolicyError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::ALL.into_iter()
+            .find(|feature| feature.as_str() == s)
+            .ok_or_else(|| PolicyError::UnknownFeature(s.to_string()))
+    }
+}
+
+/// One change to a feature override
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PolicyOverrideEntry {
+    pub timestamp: DateTime<Utc>,
+    pub feature: NetworkFeature,
+    pub allowed: bool,              // Override granted (true) or revoked (false)
+    pub reason: String,
+    pub air_gapped: bool,           // Policy state when the change was made
+}
+
+/// Per-feature line of the policy panel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureStatus {
+    pub feature: NetworkFeature,
+    pub allowed: bool,
+    pub overridden: bool,           // Allowed only because of an override
+    pub blocked_attempts: u64,      // Refused calls since startup
+}
+
+/// Snapshot for the policy status panel
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkPolicyStatus {
+    pub air_gapped: bool,
+    pub features: Vec<FeatureStatus>,
+    pub audit: Vec<PolicyOverrideEntry>,    // Most recent first
+}
+
+#[derive(Debug, Default)]
+struct PolicyState {
+    air_gapped: bool,
+    overrides: HashSet<NetworkFeature>,
+    blocked: HashMap<NetworkFeature, u64>,
+    audit: VecDeque<PolicyOverrideEntry>,
+}
+
+/// Global network policy; cheap to check from any thread
+#[derive(Debug, Default)]
+pub struct NetworkPolicy {
+    state: RwLock<PolicyState>,
+    audit_path: Option<PathBuf>,
+}
+
+impl NetworkPolicy {
+    pub fn new(air_gapped: bool) -> Self {
+        let policy = Self::default();
+        policy.state.write().unwrap().air_gapped = air_gapped;
+        policy
+    }
+
+    /// Persist override changes to `path`, loading the entries already there
+    pub fn with_audit_log(mut self, path: impl Into<PathBuf>) -> Result<Self, PolicyError> {
+        let path = path.into();
+        if path.exists() {
+            let entries = read_audit_log(&path)?;
+            let mut state = self.state.write().unwrap();
+            let skip = entries.len().saturating_sub(AUDIT_HISTORY_LIMIT);
+            state.audit = entries.into_iter().skip(skip).collect();
+        }
+        self.audit_path = Some(path);
+        Ok(self)
+    }
+
+    /// Restore saved overrides (no audit entry; they were audited when granted)
+    pub fn with_overrides(self, features: &[NetworkFeature]) -> Self {
+        self.state.write().unwrap().overrides = features.iter().copied().collect();
+        self
+    }
+
+    pub fn is_air_gapped(&self) -> bool {
+        self.state.read().unwrap().air_gapped
+    }
+
+    pub fn set_air_gapped(&self, air_gapped: bool) {
+        let mut state = self.state.write().unwrap();
+        if state.air_gapped != air_gapped {
+            info!("Network policy: air-gapped mode {}", if air_gapped { "enabled" } else { "disabled" });
+            state.air_gapped = air_gapped;
+        }
+    }
+
+    /// Features currently let through while air-gapped
+    pub fn overrides(&self) -> Vec<NetworkFeature> {
+        let state = self.state.read().unwrap();
+        NetworkFeature::ALL.into_iter().filter(|f| state.overrides.contains(f)).collect()
+    }
+
+    /// Whether `feature` may use the network right now
+    pub fn allows(&self, feature: NetworkFeature) -> bool {
+        let state = self.state.read().unwrap();
+        !state.air_gapped || state.overrides.contains(&feature)
+    }
+
+    /// Refuse `feature` reaching `target` unless the policy allows it
+    pub fn check(&self, feature: NetworkFeature, target: &str) -> Result<(), PolicyError> {
+        if self.allows(feature) {
+            return Ok(());
+        }
+        *self.state.write().unwrap().blocked.entry(feature).or_default() += 1;
+        warn!("Network policy blocked {} -> {}", feature, target);
+        Err(PolicyError::Blocked { feature, target: target.to_string() })
+    }
+
+    /// Like `check`, but loopback URLs always pass
+    pub fn check_url(&self, feature: NetworkFeature, url: &str) -> Result<(), PolicyError> {
+        if is_loopback_url(url) {
+            return Ok(());
+        }
+        self.check(feature, url)
+    }
+
+    /// Grant or revoke an override for one feature, recording it in the audit
+    /// log. Returns false (and records nothing) if the override was already so.
+    pub fn set_override(&self, feature: NetworkFeature, allowed: bool, reason: &str) -> Result<bool, PolicyError> {
+        let mut state = self.state.write().unwrap();
+        let changed = if allowed {
+            state.overrides.insert(feature)
+        } else {
+            state.overrides.remove(&feature)
+        };
+        if !changed {
+            return Ok(false);
+        }
+
+        let entry = PolicyOverrideEntry {
+            timestamp: Utc::now(),
+            feature,
+            allowed,
+            reason: reason.to_string(),
+            air_gapped: state.air_gapped,
+        };
+        info!("Network policy override for {} {} ({})", feature, if allowed { "granted" } else { "revoked" }, reason);
+        if let Some(path) = &self.audit_path {
+            append_audit_entry(path, &entry)?;
+        }
+        state.audit.push_back(entry);
+        if state.audit.len() > AUDIT_HISTORY_LIMIT {
+            state.audit.pop_front();
+        }
+        Ok(true)
+    }
+
+    /// Bring overrides in line with `features`, auditing each difference
+    pub fn sync_overrides(&self, features: &[NetworkFeature], reason: &str) -> Result<(), PolicyError> {
+        for feature in NetworkFeature::ALL {
+            self.set_override(feature, features.contains(&feature), reason)?;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self) -> NetworkPolicyStatus {
+        let state = self.state.read().unwrap();
+        NetworkPolicyStatus {
+            air_gapped: state.air_gapped,
+            features: NetworkFeature::ALL.into_iter().map(|feature| {
+                let overridden = state.air_gapped && state.overrides.contains(&feature);
+                FeatureStatus {
+                    feature,
+                    allowed: !state.air_gapped || overridden,
+                    overridden,
+                    blocked_attempts: state.blocked.get(&feature).copied().unwrap_or(0),
+                }
+            }).collect(),
+            audit: state.audit.iter().rev().cloned().collect(),
+        }
+    }
+}
+
+/// Loopback hosts never count as network egress
+pub fn is_loopback_url(url: &str) -> bool {
+    match reqwest::Url::parse(url).ok().and_then(|u| u.host_str().map(String::from)) {
+        Some(host) => {
+            let host = host.trim_start_matches('[').trim_end_matches(']');
+            host.eq_ignore_ascii_case("localhost") || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        }
+        None => false,
+    }
+}
+
+fn read_audit_log(path: &Path) -> Result<Vec<PolicyOverrideEntry>, PolicyError> {
+    let file = std::fs::File::open(path)?;
+    let mut entries = Vec::new();
+    for line in std::io::BufReader::new(file).lines() {
+        let line = line?;
+        match serde_json::from_str(&line) {
+            Ok(entry) => entries.push(entry),
+            Err(e) => warn!("Skipping malformed policy audit entry in {:?}: {}", path, e),
+        }
+    }
+    Ok(entries)
+}
+
+fn append_audit_entry(path: &Path, entry: &PolicyOverrideEntry) -> Result<(), PolicyError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+    let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_air_gapped_blocks_features() {
+        let policy = NetworkPolicy::new(false);
+        assert!(policy.check(NetworkFeature::RemoteLlm, "openai_compatible:gpt-4o").is_ok());
+
+        policy.set_air_gapped(true);
+        let err = policy.check(NetworkFeature::ModelDownloads, "org/model").unwrap_err();
+        assert!(matches!(err, PolicyError::Blocked { feature: NetworkFeature::ModelDownloads, .. }));
+        assert!(policy.check_url(NetworkFeature::McpOutbound, "http://127.0.0.1:3000").is_ok());
+        assert!(policy.check_url(NetworkFeature::McpOutbound, "https://rag.example.com").is_err());
+
+        let status = policy.status();
+        assert!(status.air_gapped);
+        let downloads = status.features.iter().find(|f| f.feature == NetworkFeature::ModelDownloads).unwrap();
+        assert!(!downloads.allowed);
+        assert_eq!(downloads.blocked_attempts, 1);
+        assert_eq!("web_fetch".parse::<NetworkFeature>().unwrap(), NetworkFeature::WebFetch);
+        assert!("ftp".parse::<NetworkFeature>().is_err());
+    }
+
+    #[test]
+    fn test_overrides_are_audited() {
+        let temp_dir = TempDir::new().unwrap();
+        let audit_path = temp_dir.path().join("policy_audit.jsonl");
+        let policy = NetworkPolicy::new(true).with_audit_log(&audit_path).unwrap();
+
+        assert!(policy.set_override(NetworkFeature::ModelDownloads, true, "install embedding model").unwrap());
+        assert!(!policy.set_override(NetworkFeature::ModelDownloads, true, "again").unwrap());
+        assert!(policy.allows(NetworkFeature::ModelDownloads));
+        assert!(!policy.allows(NetworkFeature::RemoteLlm));
+        assert!(policy.status().features.iter().any(|f| f.feature == NetworkFeature::ModelDownloads && f.overridden));
+
+        policy.sync_overrides(&[], "settings file").unwrap();
+        assert!(!policy.allows(NetworkFeature::ModelDownloads));
+
+        // The log survives a restart
+        let reopened = NetworkPolicy::new(true).with_audit_log(&audit_path).unwrap();
+        let audit = reopened.status().audit;
+        assert_eq!(audit.len(), 2);
+        assert!(!audit[0].allowed);
+        assert_eq!(audit[1].reason, "install embedding model");
+    }
+}
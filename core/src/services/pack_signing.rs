@@ -0,0 +1,263 @@
+/*!
+ * Pack Signing Service
+ *
+ * Signs exported packs with this installation's ed25519 key and decides
+ * whether the signer of an imported pack is trusted. The private key lives
+ * in the secrets vault (PKCS#8, hex); trusted signer public keys are kept in
+ * a small JSON trust store next to the app data.
+ *
+ * A signature covers the manifest (type, id, name, version, creation time,
+ * metadata) and its checksum, which in turn covers every file hash, so any
+ * change to a signed pack invalidates it. Our own key is always trusted.
+ */
+
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use ring::rand::SystemRandom;
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use super::secrets::{from_hex, to_hex, SecretsError, SecretsService};
+use super::storage::sha256_hex;
+
+/// Vault entry holding this installation's signing key
+pub const PACK_SIGNING_SECRET: &str = "pack_signing.ed25519";
+
+/// Default location of the trusted signer store, relative to the project root
+pub const DEFAULT_TRUSTED_SIGNERS_PATH: &str = "./trusted_signers.json";
+
+/// Signature algorithm recorded in pack manifests
+pub const PACK_SIGNATURE_ALGORITHM: &str = "ed25519";
+
+/// Pack Signing Error Types
+#[derive(Debug, Error)]
+pub enum SigningError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed trust store: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Secrets error: {0}")]
+    SecretsError(#[from] SecretsError),
+
+    #[error("Invalid public key: {0}")]
+    InvalidKey(String),
+
+    #[error("Invalid pack signature: {0}")]
+    InvalidSignature(String),
+}
+
+/// Signature block embedded in a pack manifest
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PackSignature {
+    pub algorithm: String,
+    pub key_id: String,
+    pub public_key: String,         // Hex
+    pub signature: String,          // Hex
+}
+
+/// A signer the user agreed to trust
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustedSigner {
+    pub key_id: String,
+    pub public_key: String,         // Hex
+    pub label: String,              // Who the user says this key belongs to
+    pub added_at: DateTime<Utc>,
+}
+
+/// Short, stable identifier of a public key
+pub fn key_id(public_key: &[u8]) -> String {
+    sha256_hex(public_key)[..16].to_string()
+}
+
+/// Check `signature` over `payload`, returning the signer's key ID
+pub fn verify_signature(signature: &PackSignature, payload: &[u8]) -> Result<String, SigningError> {
+    if signature.algorithm != PACK_SIGNATURE_ALGORITHM {
+        return Err(SigningError::InvalidSignature(format!("unsupported algorithm {}", signature.algorithm)));
+    }
+    let public_key = from_hex(&signature.public_key).map_err(|e| SigningError::InvalidKey(e.to_string()))?;
+    if key_id(&public_key) != signature.key_id {
+        return Err(SigningError::InvalidSignature("key ID does not match public key".to_string()));
+    }
+    let signature_bytes = from_hex(&signature.signature)
+        .map_err(|e| SigningError::InvalidSignature(e.to_string()))?;
+
+    UnparsedPublicKey::new(&ED25519, &public_key)
+        .verify(payload, &signature_bytes)
+        .map_err(|_| SigningError::InvalidSignature(format!("signature by {} does not match pack contents", signature.key_id)))?;
+    Ok(signature.key_id.clone())
+}
+
+/// Local signing key plus the trusted signer store
+pub struct PackSigner {
+    key_pair: Ed25519KeyPair,
+    public_key: String,
+    key_id: String,
+    trust_path: Option<PathBuf>,
+    trusted: RwLock<BTreeMap<String, TrustedSigner>>,
+}
+
+impl PackSigner {
+    /// Load the signing key from the vault, generating it on first use
+    pub fn load_or_create(secrets: &SecretsService) -> Result<Self, SigningError> {
+        let pkcs8 = match secrets.get(PACK_SIGNING_SECRET) {
+            Ok(value) => from_hex(value.expose())?,
+            Err(SecretsError::NotFound(_)) => {
+                let document = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new())
+                    .map_err(|_| SecretsError::CryptoError("Failed to generate signing key".to_string()))?;
+                secrets.set(PACK_SIGNING_SECRET, &to_hex(document.as_ref()))?;
+                info!("Generated pack signing key");
+                document.as_ref().to_vec()
+            }
+            Err(e) => return Err(e.into()),
+        };
+        let key_pair = Ed25519KeyPair::from_pkcs8(&pkcs8)
+            .map_err(|e| SigningError::InvalidKey(e.to_string()))?;
+        let public_key = key_pair.public_key().as_ref();
+
+        Ok(Self {
+            public_key: to_hex(public_key),
+            key_id: key_id(public_key),
+            key_pair,
+            trust_path: None,
+            trusted: RwLock::new(BTreeMap::new()),
+        })
+    }
+
+    /// Persist trusted signers to `path`, loading the ones already there
+    pub fn with_trust_store(mut self, path: impl Into<PathBuf>) -> Result<Self, SigningError> {
+        let path = path.into();
+        if path.exists() {
+            let signers: Vec<TrustedSigner> = serde_json::from_str(&std::fs::read_to_string(&path)?)?;
+            *self.trusted.write().unwrap() = signers.into_iter().map(|s| (s.key_id.clone(), s)).collect();
+        }
+        self.trust_path = Some(path);
+        Ok(self)
+    }
+
+    pub fn key_id(&self) -> &str {
+        &self.key_id
+    }
+
+    /// Hex public key to share with teammates
+    pub fn public_key(&self) -> &str {
+        &self.public_key
+    }
+
+    pub fn sign(&self, payload: &[u8]) -> PackSignature {
+        PackSignature {
+            algorithm: PACK_SIGNATURE_ALGORITHM.to_string(),
+            key_id: self.key_id.clone(),
+            public_key: self.public_key.clone(),
+            signature: to_hex(self.key_pair.sign(payload).as_ref()),
+        }
+    }
+
+    pub fn is_trusted(&self, key_id: &str) -> bool {
+        key_id == self.key_id || self.trusted.read().unwrap().contains_key(key_id)
+    }
+
+    /// Trust packs signed by `public_key` (hex) from now on
+    pub fn trust(&self, public_key: &str, label: &str) -> Result<TrustedSigner, SigningError> {
+        let bytes = from_hex(public_key.trim()).map_err(|e| SigningError::InvalidKey(e.to_string()))?;
+        if bytes.len() != 32 {
+            return Err(SigningError::InvalidKey(format!("expected 32 bytes, got {}", bytes.len())));
+        }
+        let signer = TrustedSigner {
+            key_id: key_id(&bytes),
+            public_key: to_hex(&bytes),
+            label: label.to_string(),
+            added_at: Utc::now(),
+        };
+
+        let mut trusted = self.trusted.write().unwrap();
+        trusted.insert(signer.key_id.clone(), signer.clone());
+        self.save(&trusted)?;
+        info!("Trusted pack signer {} ({})", signer.key_id, signer.label);
+        Ok(signer)
+    }
+
+    /// Stop trusting a signer; returns whether it was trusted
+    pub fn untrust(&self, key_id: &str) -> Result<bool, SigningError> {
+        let mut trusted = self.trusted.write().unwrap();
+        if trusted.remove(key_id).is_none() {
+            return Ok(false);
+        }
+        self.save(&trusted)?;
+        Ok(true)
+    }
+
+    pub fn trusted_signers(&self) -> Vec<TrustedSigner> {
+        self.trusted.read().unwrap().values().cloned().collect()
+    }
+
+    fn save(&self, trusted: &BTreeMap<String, TrustedSigner>) -> Result<(), SigningError> {
+        let Some(path) = &self.trust_path else {
+            return Ok(());
+        };
+        write_trust_store(path, &trusted.values().collect::<Vec<_>>())
+    }
+}
+
+fn write_trust_store(path: &Path, signers: &[&TrustedSigner]) -> Result<(), SigningError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(path, serde_json::to_string_pretty(signers)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::secrets::SecretsConfig;
+    use tempfile::TempDir;
+
+    fn signer(dir: &Path) -> PackSigner {
+        let secrets = SecretsService::new(SecretsConfig::test_config(dir)).unwrap();
+        PackSigner::load_or_create(&secrets).unwrap()
+            .with_trust_store(dir.join("trusted_signers.json")).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let temp_dir = TempDir::new().unwrap();
+        let local = signer(temp_dir.path());
+
+        // The key is stored once and reused
+        assert_eq!(signer(temp_dir.path()).key_id(), local.key_id());
+
+        let signature = local.sign(b"manifest");
+        assert_eq!(verify_signature(&signature, b"manifest").unwrap(), local.key_id());
+        assert!(matches!(verify_signature(&signature, b"tampered"), Err(SigningError::InvalidSignature(_))));
+
+        let mut forged = signature.clone();
+        forged.key_id = "0000000000000000".to_string();
+        assert!(verify_signature(&forged, b"manifest").is_err());
+    }
+
+    #[test]
+    fn test_trust_store() {
+        let alice_dir = TempDir::new().unwrap();
+        let bob_dir = TempDir::new().unwrap();
+        let alice = signer(alice_dir.path());
+        let bob = signer(bob_dir.path());
+
+        assert!(alice.is_trusted(alice.key_id()));
+        assert!(!alice.is_trusted(bob.key_id()));
+
+        let trusted = alice.trust(bob.public_key(), "Bob's laptop").unwrap();
+        assert_eq!(trusted.key_id, bob.key_id());
+        assert!(signer(alice_dir.path()).is_trusted(bob.key_id()));
+        assert!(matches!(alice.trust("abcd", "short"), Err(SigningError::InvalidKey(_))));
+
+        assert!(alice.untrust(bob.key_id()).unwrap());
+        assert!(!alice.untrust(bob.key_id()).unwrap());
+        assert!(alice.trusted_signers().is_empty());
+    }
+}
@@ -0,0 +1,224 @@
+/*!
+ * Project Registry
+ *
+ * A project (workspace) is a directory holding everything one body of work
+ * needs: its own settings file, SQLite database, vector data, cache and KB
+ * packs. Tools, flows and KB metadata live in the project database, so
+ * projects on one machine never see each other's data. Models, logs and the
+ * secrets vault stay app-wide.
+ *
+ * The registry remembers recently opened projects (most recent first) in a
+ * small JSON file next to the app; each project root carries a marker file
+ * naming it.
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::info;
+
+use super::settings::{Settings, DEFAULT_SETTINGS_PATH};
+
+/// Default location of the recent-projects list
+pub const DEFAULT_PROJECTS_PATH: &str = "./rag_studio.projects.json";
+
+/// File in a project root identifying it as a project
+pub const PROJECT_MARKER_FILE: &str = "rag_studio.project.json";
+
+/// Projects kept in the recent list
+pub const MAX_RECENT_PROJECTS: usize = 20;
+
+/// Project Registry Error Types
+#[derive(Debug, Error)]
+pub enum ProjectError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed project file: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("A project already exists at {0}")]
+    AlreadyExists(PathBuf),
+
+    #[error("Not a project directory: {0}")]
+    NotAProject(PathBuf),
+
+    #[error("Project not found: {0}")]
+    NotFound(String),
+
+    #[error("Validation error: {0}")]
+    ValidationError(String),
+}
+
+/// A project as listed in the recent-projects list
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ProjectInfo {
+    pub id: String,
+    pub name: String,
+    pub root: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub last_opened_at: Option<DateTime<Utc>>,
+}
+
+impl ProjectInfo {
+    /// Settings file of the project
+    pub fn settings_path(&self) -> PathBuf {
+        settings_path(&self.root)
+    }
+}
+
+/// Settings file of the project rooted at `root`
+pub fn settings_path(root: &Path) -> PathBuf {
+    root.join(Path::new(DEFAULT_SETTINGS_PATH).file_name().unwrap_or_default())
+}
+
+/// Marker file contents
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ProjectMarker {
+    id: String,
+    name: String,
+    created_at: DateTime<Utc>,
+}
+
+/// Recently opened projects, persisted to a JSON file
+pub struct ProjectRegistry {
+    path: PathBuf,
+    recent: RwLock<Vec<ProjectInfo>>,
+}
+
+impl ProjectRegistry {
+    /// Load the recent-projects list; a missing file is an empty list
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, ProjectError> {
+        let path = path.into();
+        let recent = if path.exists() {
+            serde_json::from_str(&std::fs::read_to_string(&path)?)?
+        } else {
+            Vec::new()
+        };
+        Ok(Self { path, recent: RwLock::new(recent) })
+    }
+
+    /// Recent projects, most recently opened first
+    pub fn recent(&self) -> Vec<ProjectInfo> {
+        self.recent.read().unwrap().clone()
+    }
+
+    /// Project opened last, if any
+    pub fn last_opened(&self) -> Option<ProjectInfo> {
+        self.recent.read().unwrap().first().cloned()
+    }
+
+    /// Make `root` a new project with default settings; the directory may
+    /// exist but must not already be a project
+    pub fn create(&self, root: &Path, name: &str) -> Result<ProjectInfo, ProjectError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(ProjectError::ValidationError("Project name is required".to_string()));
+        }
+        let marker_path = root.join(PROJECT_MARKER_FILE);
+        if marker_path.exists() {
+            return Err(ProjectError::AlreadyExists(root.to_path_buf()));
+        }
+
+        std::fs::create_dir_all(root)?;
+        let marker = ProjectMarker {
+            id: format!("proj_{}", uuid::Uuid::new_v4().simple()),
+            name: name.to_string(),
+            created_at: Utc::now(),
+        };
+        std::fs::write(&marker_path, serde_json::to_string_pretty(&marker)?)?;
+
+        // Relative paths in a project's settings resolve inside the project
+        let settings_path = settings_path(root);
+        if !settings_path.exists() {
+            std::fs::write(&settings_path, serde_json::to_string_pretty(&Settings::default())?)?;
+        }
+
+        info!("Created project '{}' at {:?}", marker.name, root);
+        Ok(ProjectInfo {
+            id: marker.id,
+            name: marker.name,
+            root: root.to_path_buf(),
+            created_at: marker.created_at,
+            last_opened_at: None,
+        })
+    }
+
+    /// Read the project at `root` and move it to the front of the recent list
+    pub fn touch(&self, root: &Path) -> Result<ProjectInfo, ProjectError> {
+        let marker_path = root.join(PROJECT_MARKER_FILE);
+        if !marker_path.exists() {
+            return Err(ProjectError::NotAProject(root.to_path_buf()));
+        }
+        let marker: ProjectMarker = serde_json::from_str(&std::fs::read_to_string(&marker_path)?)?;
+        let project = ProjectInfo {
+            id: marker.id,
+            name: marker.name,
+            root: root.canonicalize().unwrap_or_else(|_| root.to_path_buf()),
+            created_at: marker.created_at,
+            last_opened_at: Some(Utc::now()),
+        };
+
+        let mut recent = self.recent.write().unwrap();
+        recent.retain(|p| p.id != project.id);
+        recent.insert(0, project.clone());
+        recent.truncate(MAX_RECENT_PROJECTS);
+        self.save(&recent)?;
+        Ok(project)
+    }
+
+    /// Drop a project from the recent list (its files are left alone)
+    pub fn forget(&self, id: &str) -> Result<(), ProjectError> {
+        let mut recent = self.recent.write().unwrap();
+        let before = recent.len();
+        recent.retain(|p| p.id != id);
+        if recent.len() == before {
+            return Err(ProjectError::NotFound(id.to_string()));
+        }
+        self.save(&recent)
+    }
+
+    fn save(&self, recent: &[ProjectInfo]) -> Result<(), ProjectError> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(recent)?)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_create_and_reopen_projects() {
+        let temp_dir = TempDir::new().unwrap();
+        let registry = ProjectRegistry::open(temp_dir.path().join("projects.json")).unwrap();
+
+        let client_a = registry.create(&temp_dir.path().join("client_a"), "Client A").unwrap();
+        let client_b = registry.create(&temp_dir.path().join("client_b"), "Client B").unwrap();
+        assert!(client_a.settings_path().exists());
+        assert!(matches!(
+            registry.create(&client_a.root, "Again"),
+            Err(ProjectError::AlreadyExists(_))
+        ));
+
+        registry.touch(&client_a.root).unwrap();
+        registry.touch(&client_b.root).unwrap();
+        registry.touch(&client_a.root).unwrap();
+        let recent: Vec<String> = registry.recent().into_iter().map(|p| p.id).collect();
+        assert_eq!(recent, vec![client_a.id.clone(), client_b.id.clone()]);
+
+        // The list survives a reload
+        let reloaded = ProjectRegistry::open(temp_dir.path().join("projects.json")).unwrap();
+        assert_eq!(reloaded.last_opened().unwrap().name, "Client A");
+
+        reloaded.forget(&client_b.id).unwrap();
+        assert_eq!(reloaded.recent().len(), 1);
+        assert!(matches!(reloaded.touch(temp_dir.path()), Err(ProjectError::NotAProject(_))));
+    }
+}
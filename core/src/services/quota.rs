@@ -0,0 +1,282 @@
+/*!
+ * Disk Quota Service
+ *
+ * Byte limits for each KB and for the project as a whole. A KB's usage is
+ * its content database share plus the blobs its versions reference; project
+ * usage is everything in the databases, vector store, packs and blob store.
+ *
+ * Writers call `check` with an estimate of what they are about to add before
+ * starting (pipeline runs, document ingestion). Crossing the warning
+ * threshold raises a `disk_quota` alert once per scope; going over a limit
+ * raises one too and fails the check with `QuotaError::Exceeded`, so the
+ * write never starts instead of filling the disk part way through.
+ */
+
+use std::collections::HashSet;
+use std::fmt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, RwLock};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::warn;
+
+use super::alerts::{AlertCategory, AlertService};
+use super::settings::QuotaSettings;
+use super::sql::{SqlError, SqlService};
+use super::storage::{dir_bytes, StorageError, StorageService};
+
+/// Stored bytes per byte of document text: chunk text (with overlap) in the
+/// content database, its FTS index and the compressed blob copy
+const CONTENT_BYTES_FACTOR: u64 = 3;
+
+/// Stored bytes per chunk in the vector store (384-dim f32 vector plus row data)
+const VECTOR_BYTES_PER_CHUNK: u64 = 2048;
+
+/// Quota Error Types
+#[derive(Debug, Error)]
+pub enum QuotaError {
+    #[error("{scope} is over its disk quota: {used_bytes} bytes used + {requested_bytes} requested > {limit_bytes} limit")]
+    Exceeded {
+        scope: QuotaScope,
+        used_bytes: u64,
+        requested_bytes: u64,
+        limit_bytes: u64,
+    },
+
+    #[error("SQL error: {0}")]
+    SqlError(#[from] SqlError),
+
+    #[error("Storage error: {0}")]
+    StorageError(#[from] StorageError),
+}
+
+/// What a limit applies to
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(tag = "type", content = "id", rename_all = "snake_case")]
+pub enum QuotaScope {
+    Kb(String),
+    Project,
+}
+
+impl fmt::Display for QuotaScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QuotaScope::Kb(kb_id) => write!(f, "KB {}", kb_id),
+            QuotaScope::Project => write!(f, "Project"),
+        }
+    }
+}
+
+/// Limits in bytes; None is unlimited
+#[derive(Debug, Clone, PartialEq)]
+pub struct QuotaLimits {
+    pub kb_limit_bytes: Option<u64>,
+    pub project_limit_bytes: Option<u64>,
+    pub warn_fraction: f64,     // Of the limit, where the warning alert is raised
+}
+
+impl From<&QuotaSettings> for QuotaLimits {
+    fn from(settings: &QuotaSettings) -> Self {
+        let bytes = |mb: u64| (mb > 0).then(|| mb * 1024 * 1024);
+        Self {
+            kb_limit_bytes: bytes(settings.kb_limit_mb),
+            project_limit_bytes: bytes(settings.project_limit_mb),
+            warn_fraction: settings.warn_threshold,
+        }
+    }
+}
+
+impl Default for QuotaLimits {
+    fn default() -> Self {
+        Self::from(&QuotaSettings::default())
+    }
+}
+
+/// Usage of one scope against its limit
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaUsage {
+    pub scope: QuotaScope,
+    pub used_bytes: u64,
+    pub limit_bytes: Option<u64>,
+    pub warn_bytes: Option<u64>,
+}
+
+impl QuotaUsage {
+    fn new(scope: QuotaScope, used_bytes: u64, limit_bytes: Option<u64>, warn_fraction: f64) -> Self {
+        let warn_bytes = limit_bytes.map(|limit| (limit as f64 * warn_fraction.clamp(0.0, 1.0)) as u64);
+        Self { scope, used_bytes, limit_bytes, warn_bytes }
+    }
+
+    pub fn fits(&self, requested_bytes: u64) -> bool {
+        self.limit_bytes.is_none_or(|limit| self.used_bytes + requested_bytes <= limit)
+    }
+
+    pub fn warns(&self, requested_bytes: u64) -> bool {
+        self.warn_bytes.is_some_and(|warn| self.used_bytes + requested_bytes >= warn)
+    }
+}
+
+/// Outcome of a check that passed
+#[derive(Debug, Clone, Serialize)]
+pub struct QuotaCheck {
+    pub requested_bytes: u64,
+    pub usage: Vec<QuotaUsage>,
+    pub warnings: Vec<QuotaScope>,  // Scopes past their warning threshold after the write
+}
+
+/// Bytes an ingest of `content_bytes` of text in `chunk_count` chunks is expected to add
+pub fn estimate_ingest_bytes(content_bytes: u64, chunk_count: u64) -> u64 {
+    content_bytes * CONTENT_BYTES_FACTOR + chunk_count * VECTOR_BYTES_PER_CHUNK
+}
+
+/// Per-KB and project disk quotas
+pub struct QuotaService {
+    sql_service: Arc<SqlService>,
+    storage_service: Arc<StorageService>,
+    vector_dir: PathBuf,
+    limits: RwLock<QuotaLimits>,
+    alert_service: Option<Arc<AlertService>>,
+    warned: Mutex<HashSet<QuotaScope>>,     // Scopes already alerted; cleared once back under
+}
+
+impl QuotaService {
+    pub fn new(
+        sql_service: Arc<SqlService>,
+        storage_service: Arc<StorageService>,
+        vector_dir: impl Into<PathBuf>,
+        limits: QuotaLimits,
+    ) -> Self {
+        Self {
+            sql_service,
+            storage_service,
+            vector_dir: vector_dir.into(),
+            limits: RwLock::new(limits),
+            alert_service: None,
+            warned: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Raise `disk_quota` alerts for warnings and refused writes
+    pub fn with_alert_service(mut self, alert_service: Arc<AlertService>) -> Self {
+        self.alert_service = Some(alert_service);
+        self
+    }
+
+    /// Apply new limits (hot reload)
+    pub fn set_limits(&self, limits: QuotaLimits) {
+        *self.limits.write().unwrap() = limits;
+    }
+
+    pub fn limits(&self) -> QuotaLimits {
+        self.limits.read().unwrap().clone()
+    }
+
+    pub async fn kb_usage(&self, kb_id: &str) -> Result<QuotaUsage, QuotaError> {
+        let used = self.sql_service.kb_content_bytes(kb_id).await? + self.storage_service.kb_blob_bytes(kb_id)?;
+        let limits = self.limits();
+        Ok(QuotaUsage::new(QuotaScope::Kb(kb_id.to_string()), used, limits.kb_limit_bytes, limits.warn_fraction))
+    }
+
+    pub async fn project_usage(&self) -> Result<QuotaUsage, QuotaError> {
+        let used = self.sql_service.database_bytes()?
+            + self.storage_service.usage_bytes()?
+            + dir_bytes(&self.vector_dir)?;
+        let limits = self.limits();
+        Ok(QuotaUsage::new(QuotaScope::Project, used, limits.project_limit_bytes, limits.warn_fraction))
+    }
+
+    /// Pre-flight check before writing `requested_bytes` (to `kb_id`, when
+    /// given). Fails without writing anything when a limit would be exceeded.
+    pub async fn check(&self, kb_id: Option<&str>, requested_bytes: u64) -> Result<QuotaCheck, QuotaError> {
+        let mut usage = Vec::with_capacity(2);
+        if let Some(kb_id) = kb_id {
+            usage.push(self.kb_usage(kb_id).await?);
+        }
+        usage.push(self.project_usage().await?);
+
+        if let Some(over) = usage.iter().find(|u| !u.fits(requested_bytes)) {
+            let error = QuotaError::Exceeded {
+                scope: over.scope.clone(),
+                used_bytes: over.used_bytes,
+                requested_bytes,
+                limit_bytes: over.limit_bytes.unwrap_or_default(),
+            };
+            warn!("Refusing write: {}", error);
+            self.alert(&format!("{} is out of disk quota", over.scope), &error.to_string(), over).await;
+            return Err(error);
+        }
+
+        let mut warnings = Vec::new();
+        for u in &usage {
+            if !u.warns(requested_bytes) {
+                self.warned.lock().unwrap().remove(&u.scope);
+                continue;
+            }
+            warnings.push(u.scope.clone());
+            if self.warned.lock().unwrap().insert(u.scope.clone()) {
+                let message = format!(
+                    "{} will use {} of {} MB; delete old versions, run blob GC or raise the quota",
+                    u.scope,
+                    (u.used_bytes + requested_bytes) / (1024 * 1024),
+                    u.limit_bytes.unwrap_or_default() / (1024 * 1024),
+                );
+                self.alert(&format!("{} is almost out of disk quota", u.scope), &message, u).await;
+            }
+        }
+        Ok(QuotaCheck { requested_bytes, usage, warnings })
+    }
+
+    async fn alert(&self, title: &str, message: &str, usage: &QuotaUsage) {
+        if let Some(alerts) = &self.alert_service {
+            let source = match &usage.scope {
+                QuotaScope::Kb(kb_id) => Some(kb_id.as_str()),
+                QuotaScope::Project => None,
+            };
+            alerts.notify(AlertCategory::DiskQuota, title, message, source, serde_json::to_value(usage).unwrap_or_default()).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::sql::{FtsChunk, SqlConfig};
+    use crate::services::storage::StorageConfig;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_quota_check_warns_then_refuses() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql_service.run_migrations().await.unwrap();
+        let storage = Arc::new(StorageService::new(StorageConfig::test_config(temp_dir.path())));
+        sql_service.fts_index_chunks("kb_1", &[FtsChunk {
+            chunk_id: "c1".to_string(),
+            document_id: "doc-1".to_string(),
+            content: "x".repeat(1000),
+            metadata: serde_json::json!({}),
+        }]).await.unwrap();
+
+        let quotas = QuotaService::new(sql_service, storage, temp_dir.path().join("vectors"), QuotaLimits {
+            kb_limit_bytes: Some(2000),
+            project_limit_bytes: None,
+            warn_fraction: 0.8,
+        });
+        let used = quotas.kb_usage("kb_1").await.unwrap().used_bytes;
+        assert!(used >= 1000);
+
+        let check = quotas.check(Some("kb_1"), 0).await.unwrap();
+        assert!(check.warnings.is_empty());
+        let check = quotas.check(Some("kb_1"), 1600 - used).await.unwrap();
+        assert_eq!(check.warnings, vec![QuotaScope::Kb("kb_1".to_string())]);
+        assert!(matches!(
+            quotas.check(Some("kb_1"), 5000).await,
+            Err(QuotaError::Exceeded { scope: QuotaScope::Kb(_), limit_bytes: 2000, .. })
+        ));
+
+        // Other KBs are measured separately; no limit means no refusal
+        assert!(quotas.check(Some("kb_2"), 1500).await.is_ok());
+        quotas.set_limits(QuotaLimits { kb_limit_bytes: None, ..quotas.limits() });
+        assert!(quotas.check(Some("kb_1"), 5000).await.is_ok());
+    }
+}
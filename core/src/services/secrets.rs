@@ -0,0 +1,468 @@
+/*!
+ * Secrets Service Implementation
+ *
+ * Encrypted local vault for credentials (LLM provider API keys, HF tokens,
+ * connector passwords). Tools, flows and providers reference secrets by name
+ * (e.g. `api_key_secret`), so raw values stay out of app state, snapshots,
+ * exports and logs; they are decrypted only when a request is made.
+ *
+ * Each value is sealed with ChaCha20-Poly1305 under a 256-bit vault key,
+ * with the secret's name as associated data. The key is kept in the OS
+ * keychain (macOS Keychain, Windows Credential Manager, Secret Service),
+ * or is derived with PBKDF2 from `RAG_STUDIO_VAULT_PASSPHRASE` when that is
+ * set. Without a usable keychain it falls back to a key file next to the
+ * vault (owner-only permissions); an existing key file moves into the
+ * keychain once the keychain holds the key.
+ */
+
+use std::collections::BTreeMap;
+use std::num::NonZeroU32;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use chrono::{DateTime, Utc};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{debug, info};
+
+/// Default vault directory, relative to the project root
+pub const DEFAULT_SECRETS_DIR: &str = "./secrets";
+
+/// Passphrase the vault key is derived from instead of the key file
+pub const VAULT_PASSPHRASE_ENV: &str = "RAG_STUDIO_VAULT_PASSPHRASE";
+
+/// Vault file format written by this version
+const VAULT_VERSION: u32 = 1;
+
+const KEY_LEN: usize = 32;
+const SALT_LEN: usize = 16;
+const PBKDF2_ITERATIONS: u32 = 210_000;
+
+/// Known plaintext sealed into every vault to detect a wrong key up front
+const KEY_CHECK_NAME: &str = "__vault_key_check__";
+const KEY_CHECK_VALUE: &[u8] = b"rag-studio-vault";
+
+const MAX_NAME_LEN: usize = 128;
+
+/// Keychain service the vault keys are stored under, one entry per vault path
+const KEYCHAIN_SERVICE: &str = "rag-studio-vault";
+
+/// Secrets Service Error Types
+#[derive(Debug, Error)]
+pub enum SecretsError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed vault: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid secret name '{0}' (use letters, digits, '.', '_' or '-')")]
+    InvalidName(String),
+
+    #[error("Secret not found: {0}")]
+    NotFound(String),
+
+    #[error("Vault key does not match this vault{}", if *.0 { format!(" (check {})", VAULT_PASSPHRASE_ENV) } else { String::new() })]
+    WrongKey(bool),
+
+    #[error("Crypto error: {0}")]
+    CryptoError(String),
+}
+
+/// Secrets configuration
+#[derive(Debug, Clone)]
+pub struct SecretsConfig {
+    pub vault_path: PathBuf,
+    pub key_path: PathBuf,              // Used when no passphrase is set and the keychain is unusable
+    pub passphrase: Option<String>,     // Derive the key from this instead
+    pub keychain: bool,                 // Keep the key in the OS keychain when one is available
+}
+
+impl Default for SecretsConfig {
+    fn default() -> Self {
+        Self::in_dir(Path::new(DEFAULT_SECRETS_DIR))
+    }
+}
+
+impl SecretsConfig {
+    /// Vault and key file inside `dir` (a project's `secrets_dir`)
+    pub fn in_dir(dir: &Path) -> Self {
+        Self {
+            vault_path: dir.join("vault.json"),
+            key_path: dir.join("vault.key"),
+            passphrase: std::env::var(VAULT_PASSPHRASE_ENV).ok().filter(|p| !p.is_empty()),
+            keychain: true,
+        }
+    }
+
+    /// Test configuration with an isolated vault and key file (never the real keychain)
+    pub fn test_config(data_dir: &Path) -> Self {
+        Self {
+            vault_path: data_dir.join("vault.json"),
+            key_path: data_dir.join("vault.key"),
+            passphrase: None,
+            keychain: false,
+        }
+    }
+}
+
+/// Secret metadata; values are never listed
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SecretInfo {
+    pub name: String,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Decrypted secret; redacted in Debug output
+#[derive(Clone)]
+pub struct SecretValue(String);
+
+impl SecretValue {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+impl std::fmt::Debug for SecretValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SecretValue([REDACTED])")
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SealedValue {
+    nonce: String,                  // Hex
+    ciphertext: String,             // Hex, with tag
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultEntry {
+    #[serde(flatten)]
+    sealed: SealedValue,
+    created_at: DateTime<Utc>,
+    updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct VaultFile {
+    version: u32,
+    kdf_salt: Option<String>,       // Hex; set for passphrase-derived keys
+    key_check: SealedValue,
+    secrets: BTreeMap<String, VaultEntry>,
+}
+
+/// Encrypted credential store
+pub struct SecretsService {
+    config: SecretsConfig,
+    key: LessSafeKey,
+    rng: SystemRandom,
+    vault: RwLock<VaultFile>,
+}
+
+impl SecretsService {
+    /// Open the vault, creating it (and its key) on first use
+    pub fn new(config: SecretsConfig) -> Result<Self, SecretsError> {
+        let rng = SystemRandom::new();
+        let keychain = keychain_entry(&config);
+
+        if config.vault_path.exists() {
+            let vault: VaultFile = serde_json::from_str(&std::fs::read_to_string(&config.vault_path)?)?;
+            // A key file left from before the keychain (or beside an unusable one) wins
+            let (raw_key, from_file) = match &vault.kdf_salt {
+                Some(salt) => {
+                    let passphrase = config.passphrase.as_deref().ok_or(SecretsError::WrongKey(true))?;
+                    (derive_key(passphrase, &from_hex(salt)?), false)
+                }
+                None if config.key_path.exists() => (load_key_file(&config.key_path)?, true),
+                None => match keychain.as_ref().and_then(keychain_key) {
+                    Some(key) => (key, false),
+                    None => (load_key_file(&config.key_path)?, true),
+                },
+            };
+            let key = aead_key(&raw_key)?;
+            open_sealed(&key, KEY_CHECK_NAME, &vault.key_check)
+                .map_err(|_| SecretsError::WrongKey(vault.kdf_salt.is_some()))?;
+            // Only a key proven against the vault moves into the keychain
+            if from_file {
+                move_key_file_to_keychain(&config, keychain.as_ref(), &raw_key)?;
+            }
+
+            return Ok(Self { config, key, rng, vault: RwLock::new(vault) });
+        }
+
+        let (key, kdf_salt) = match &config.passphrase {
+            Some(passphrase) => {
+                let salt = random_bytes::<SALT_LEN>(&rng)?;
+                (derive_key(passphrase, &salt), Some(to_hex(&salt)))
+            }
+            None if config.key_path.exists() => {
+                let key = load_key_file(&config.key_path)?;
+                move_key_file_to_keychain(&config, keychain.as_ref(), &key)?;
+                (key, None)
+            }
+            None => {
+                let key = random_bytes::<KEY_LEN>(&rng)?;
+                if !keychain.as_ref().is_some_and(|entry| store_in_keychain(entry, &key)) {
+                    write_private(&config.key_path, to_hex(&key).as_bytes())?;
+                }
+                (key, None)
+            }
+        };
+        let key = aead_key(&key)?;
+        let vault = VaultFile {
+            version: VAULT_VERSION,
+            kdf_salt,
+            key_check: seal(&key, &rng, KEY_CHECK_NAME, KEY_CHECK_VALUE)?,
+            secrets: BTreeMap::new(),
+        };
+        write_private(&config.vault_path, serde_json::to_string_pretty(&vault)?.as_bytes())?;
+        info!("Created secrets vault {:?}", config.vault_path);
+
+        Ok(Self { config, key, rng, vault: RwLock::new(vault) })
+    }
+
+    /// Store or replace a secret
+    pub fn set(&self, name: &str, value: &str) -> Result<SecretInfo, SecretsError> {
+        validate_name(name)?;
+        let sealed = seal(&self.key, &self.rng, name, value.as_bytes())?;
+        let now = Utc::now();
+
+        let mut vault = self.vault.write().unwrap();
+        let created_at = vault.secrets.get(name).map_or(now, |entry| entry.created_at);
+        vault.secrets.insert(name.to_string(), VaultEntry { sealed, created_at, updated_at: now });
+        self.save(&vault)?;
+
+        Ok(SecretInfo { name: name.to_string(), created_at, updated_at: now })
+    }
+
+    pub fn get(&self, name: &str) -> Result<SecretValue, SecretsError> {
+        let vault = self.vault.read().unwrap();
+        let entry = vault.secrets.get(name).ok_or_else(|| SecretsError::NotFound(name.to_string()))?;
+        let plaintext = open_sealed(&self.key, name, &entry.sealed)?;
+        String::from_utf8(plaintext)
+            .map(SecretValue)
+            .map_err(|_| SecretsError::CryptoError(format!("Secret {} is not valid UTF-8", name)))
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.vault.read().unwrap().secrets.contains_key(name)
+    }
+
+    /// Remove a secret; returns whether it existed
+    pub fn delete(&self, name: &str) -> Result<bool, SecretsError> {
+        let mut vault = self.vault.write().unwrap();
+        if vault.secrets.remove(name).is_none() {
+            return Ok(false);
+        }
+        self.save(&vault)?;
+        Ok(true)
+    }
+
+    /// Stored secrets by name
+    pub fn list(&self) -> Vec<SecretInfo> {
+        self.vault.read().unwrap().secrets.iter()
+            .map(|(name, entry)| SecretInfo {
+                name: name.clone(),
+                created_at: entry.created_at,
+                updated_at: entry.updated_at,
+            })
+            .collect()
+    }
+
+    fn save(&self, vault: &VaultFile) -> Result<(), SecretsError> {
+        write_private(&self.config.vault_path, serde_json::to_string_pretty(vault)?.as_bytes())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), SecretsError> {
+    let valid = !name.is_empty()
+        && name.len() <= MAX_NAME_LEN
+        && name != KEY_CHECK_NAME
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-'));
+    if valid {
+        Ok(())
+    } else {
+        Err(SecretsError::InvalidName(name.to_string()))
+    }
+}
+
+fn derive_key(passphrase: &str, salt: &[u8]) -> [u8; KEY_LEN] {
+    let mut key = [0u8; KEY_LEN];
+    let iterations = NonZeroU32::new(PBKDF2_ITERATIONS).expect("iteration count is non-zero");
+    ring::pbkdf2::derive(ring::pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    key
+}
+
+fn load_key_file(path: &Path) -> Result<[u8; KEY_LEN], SecretsError> {
+    let key = from_hex(std::fs::read_to_string(path)?.trim())?;
+    key.try_into().map_err(|_| SecretsError::CryptoError(format!("Key file {:?} must hold {} bytes", path, KEY_LEN)))
+}
+
+/// Keychain entry for the vault, None when disabled or the platform has no keychain
+fn keychain_entry(config: &SecretsConfig) -> Option<keyring::Entry> {
+    if !config.keychain {
+        return None;
+    }
+    let vault_path = std::path::absolute(&config.vault_path).unwrap_or_else(|_| config.vault_path.clone());
+    keyring::Entry::new(KEYCHAIN_SERVICE, &vault_path.to_string_lossy())
+        .map_err(|e| debug!("OS keychain unavailable: {}", e))
+        .ok()
+}
+
+/// Key stored in the keychain; None when there is none or the keychain can't be reached
+fn keychain_key(entry: &keyring::Entry) -> Option<[u8; KEY_LEN]> {
+    match entry.get_password() {
+        Ok(hex) => from_hex(hex.trim()).ok()?.try_into().ok(),
+        Err(keyring::Error::NoEntry) => None,
+        Err(e) => {
+            debug!("OS keychain unavailable: {}", e);
+            None
+        }
+    }
+}
+
+/// Store the key in the keychain; true once it reads back intact
+fn store_in_keychain(entry: &keyring::Entry, key: &[u8; KEY_LEN]) -> bool {
+    if let Err(e) = entry.set_password(&to_hex(key)) {
+        debug!("Keeping the vault key in a key file, OS keychain unavailable: {}", e);
+        return false;
+    }
+    keychain_key(entry).as_ref() == Some(key)
+}
+
+/// Delete the key file once the keychain holds its key; kept otherwise
+fn move_key_file_to_keychain(config: &SecretsConfig, keychain: Option<&keyring::Entry>, key: &[u8; KEY_LEN]) -> Result<(), SecretsError> {
+    if keychain.is_some_and(|entry| store_in_keychain(entry, key)) {
+        std::fs::remove_file(&config.key_path)?;
+        info!("Moved vault key {:?} into the OS keychain", config.key_path);
+    }
+    Ok(())
+}
+
+fn aead_key(key: &[u8; KEY_LEN]) -> Result<LessSafeKey, SecretsError> {
+    UnboundKey::new(&CHACHA20_POLY1305, key)
+        .map(LessSafeKey::new)
+        .map_err(|_| SecretsError::CryptoError("Invalid vault key".to_string()))
+}
+
+fn random_bytes<const N: usize>(rng: &SystemRandom) -> Result<[u8; N], SecretsError> {
+    let mut bytes = [0u8; N];
+    rng.fill(&mut bytes).map_err(|_| SecretsError::CryptoError("System RNG unavailable".to_string()))?;
+    Ok(bytes)
+}
+
+fn seal(key: &LessSafeKey, rng: &SystemRandom, name: &str, plaintext: &[u8]) -> Result<SealedValue, SecretsError> {
+    let nonce = random_bytes::<NONCE_LEN>(rng)?;
+    let mut in_out = plaintext.to_vec();
+    key.seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut in_out)
+        .map_err(|_| SecretsError::CryptoError(format!("Failed to encrypt {}", name)))?;
+    Ok(SealedValue { nonce: to_hex(&nonce), ciphertext: to_hex(&in_out) })
+}
+
+fn open_sealed(key: &LessSafeKey, name: &str, sealed: &SealedValue) -> Result<Vec<u8>, SecretsError> {
+    let nonce: [u8; NONCE_LEN] = from_hex(&sealed.nonce)?.try_into()
+        .map_err(|_| SecretsError::CryptoError(format!("Bad nonce for {}", name)))?;
+    let mut in_out = from_hex(&sealed.ciphertext)?;
+    let plaintext = key.open_in_place(Nonce::assume_unique_for_key(nonce), Aad::from(name.as_bytes()), &mut in_out)
+        .map_err(|_| SecretsError::CryptoError(format!("Failed to decrypt {}", name)))?;
+    Ok(plaintext.to_vec())
+}
+
+/// Write via a temporary file, readable by the owner only
+fn write_private(path: &Path, contents: &[u8]) -> Result<(), SecretsError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("tmp");
+    std::fs::write(&tmp, contents)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&tmp, std::fs::Permissions::from_mode(0o600))?;
+    }
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+pub(crate) fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+pub(crate) fn from_hex(hex: &str) -> Result<Vec<u8>, SecretsError> {
+    if !hex.len().is_multiple_of(2) {
+        return Err(SecretsError::CryptoError("Odd-length hex".to_string()));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(|_| SecretsError::CryptoError("Invalid hex".to_string())))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_secrets_round_trip_encrypted_at_rest() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SecretsConfig::test_config(temp_dir.path());
+        let secrets = SecretsService::new(config.clone()).unwrap();
+
+        let info = secrets.set("openai.api_key", "sk-live-123").unwrap();
+        assert_eq!(secrets.get("openai.api_key").unwrap().expose(), "sk-live-123");
+        assert_eq!(format!("{:?}", secrets.get("openai.api_key").unwrap()), "SecretValue([REDACTED])");
+        assert!(!std::fs::read_to_string(&config.vault_path).unwrap().contains("sk-live-123"));
+
+        // Replacing keeps the creation time
+        let updated = secrets.set("openai.api_key", "sk-live-456").unwrap();
+        assert_eq!(updated.created_at, info.created_at);
+
+        let reopened = SecretsService::new(config.clone()).unwrap();
+        assert_eq!(reopened.get("openai.api_key").unwrap().expose(), "sk-live-456");
+        assert_eq!(reopened.list().iter().map(|s| s.name.as_str()).collect::<Vec<_>>(), vec!["openai.api_key"]);
+
+        assert!(matches!(secrets.set("bad name", "x"), Err(SecretsError::InvalidName(_))));
+        assert!(matches!(secrets.get("missing"), Err(SecretsError::NotFound(_))));
+        assert!(reopened.delete("openai.api_key").unwrap());
+        assert!(!reopened.delete("openai.api_key").unwrap());
+
+        // A different key file can't open the vault
+        std::fs::write(&config.key_path, to_hex(&[7u8; KEY_LEN])).unwrap();
+        assert!(matches!(SecretsService::new(config), Err(SecretsError::WrongKey(false))));
+    }
+
+    #[test]
+    fn test_passphrase_vault() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SecretsConfig {
+            passphrase: Some("correct horse".to_string()),
+            ..SecretsConfig::test_config(temp_dir.path())
+        };
+        SecretsService::new(config.clone()).unwrap().set("hf_token", "hf_abc").unwrap();
+        assert!(!config.key_path.exists());
+
+        assert_eq!(SecretsService::new(config.clone()).unwrap().get("hf_token").unwrap().expose(), "hf_abc");
+        let wrong = SecretsConfig { passphrase: Some("wrong".to_string()), ..config.clone() };
+        assert!(matches!(SecretsService::new(wrong), Err(SecretsError::WrongKey(true))));
+        let missing = SecretsConfig { passphrase: None, ..config };
+        assert!(matches!(SecretsService::new(missing), Err(SecretsError::WrongKey(true))));
+    }
+
+    #[test]
+    fn test_key_file_kept_without_keychain() {
+        let temp_dir = TempDir::new().unwrap();
+        let config = SecretsConfig::test_config(temp_dir.path());
+        SecretsService::new(config.clone()).unwrap().set("hf_token", "hf_abc").unwrap();
+        assert!(config.key_path.exists());
+
+        // No keychain entry and no key file: the vault can't be opened
+        let key = std::fs::read(&config.key_path).unwrap();
+        std::fs::remove_file(&config.key_path).unwrap();
+        assert!(matches!(SecretsService::new(config.clone()), Err(SecretsError::IoError(_))));
+        std::fs::write(&config.key_path, key).unwrap();
+        assert_eq!(SecretsService::new(config).unwrap().get("hf_token").unwrap().expose(), "hf_abc");
+    }
+}
@@ -0,0 +1,585 @@
+/*!
+ * Settings Service Implementation
+ *
+ * Typed application configuration (paths, feature flags, worker limits,
+ * retrieval defaults, alerts, disk quotas, index maintenance) kept in a
+ * versioned JSON file. Files from older
+ * versions are migrated on load, and every change is validated before it is
+ * saved or applied. Unknown fields are rejected so typos don't pass silently.
+ *
+ * Updates come from `update` (partial JSON patches from the UI) or from
+ * edits to the file itself, picked up by `watch`. Subscribers get each new
+ * valid configuration; an invalid edit is logged and the previous settings
+ * stay in effect.
+ */
+
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tokio::sync::watch;
+use tracing::{info, warn};
+
+use super::alerts::AlertSeverity;
+use super::network_policy::NetworkFeature;
+use super::pack_signing::DEFAULT_TRUSTED_SIGNERS_PATH;
+use super::secrets::DEFAULT_SECRETS_DIR;
+use crate::models::prompt::DEFAULT_PROMPTS_PATH;
+use crate::models::tool_catalog::DEFAULT_TOOLS_PATH;
+use crate::modules::schedule::CronSchedule;
+
+/// Default location of the settings file
+pub const DEFAULT_SETTINGS_PATH: &str = "./rag_studio.settings.json";
+
+/// Settings file format written by this version
+pub const SETTINGS_VERSION: u32 = 1;
+
+/// Settings Service Error Types
+#[derive(Debug, Error)]
+pub enum SettingsError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Malformed settings: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Invalid settings: {}", .0.join("; "))]
+    ValidationError(Vec<String>),
+
+    #[error("Settings version {0} is newer than supported ({SETTINGS_VERSION})")]
+    UnsupportedVersion(u64),
+
+    #[error("Failed to watch settings file: {0}")]
+    WatchError(String),
+}
+
+/// Where data lives; changes apply on restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PathSettings {
+    pub database: PathBuf,          // App SQLite database
+    pub cache_dir: PathBuf,
+    pub vector_dir: PathBuf,        // Vector store data and generations
+    pub packs_dir: PathBuf,         // Exported/imported KB packs
+    pub blobs_dir: PathBuf,         // Deduplicated KB version and pipeline run content
+    pub logs_dir: PathBuf,          // Shared by every project
+    pub models_dir: PathBuf,        // Shared by every project
+    pub backup_dir: PathBuf,        // Database backup sets
+    pub secrets_dir: PathBuf,       // Secrets vault and its key
+    pub trusted_signers: PathBuf,   // Signers whose KB packs are trusted on import
+    pub mcp_tools: PathBuf,         // Tool catalog shared with the MCP subprocess
+    pub mcp_prompts: PathBuf,       // Prompt catalog shared with the MCP subprocess
+}
+
+impl Default for PathSettings {
+    fn default() -> Self {
+        Self {
+            database: PathBuf::from("./rag_studio.db"),
+            cache_dir: PathBuf::from("./cache"),
+            vector_dir: PathBuf::from("./data/vector_db"),
+            packs_dir: PathBuf::from("./packs"),
+            blobs_dir: PathBuf::from("./data/blobs"),
+            logs_dir: PathBuf::from("./logs"),
+            models_dir: PathBuf::from("./models"),
+            backup_dir: PathBuf::from("./backups"),
+            secrets_dir: PathBuf::from(DEFAULT_SECRETS_DIR),
+            trusted_signers: PathBuf::from(DEFAULT_TRUSTED_SIGNERS_PATH),
+            mcp_tools: PathBuf::from(DEFAULT_TOOLS_PATH),
+            mcp_prompts: PathBuf::from(DEFAULT_PROMPTS_PATH),
+        }
+    }
+}
+
+impl PathSettings {
+    /// Per-project paths resolved against the project root; relative paths
+    /// in a project's settings file mean "inside the project". Logs and
+    /// models stay where they are so every project shares them.
+    pub fn resolved_in(&self, root: &Path) -> Self {
+        let resolve = |path: &PathBuf| if path.is_relative() { root.join(path) } else { path.clone() };
+        Self {
+            database: resolve(&self.database),
+            cache_dir: resolve(&self.cache_dir),
+            vector_dir: resolve(&self.vector_dir),
+            packs_dir: resolve(&self.packs_dir),
+            blobs_dir: resolve(&self.blobs_dir),
+            logs_dir: self.logs_dir.clone(),
+            models_dir: self.models_dir.clone(),
+            backup_dir: resolve(&self.backup_dir),
+            secrets_dir: resolve(&self.secrets_dir),
+            trusted_signers: resolve(&self.trusted_signers),
+            mcp_tools: resolve(&self.mcp_tools),
+            mcp_prompts: resolve(&self.mcp_prompts),
+        }
+    }
+}
+
+/// Feature switches; applied while running, except `encrypt_content`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct FeatureFlags {
+    pub air_gapped: bool,           // Refuse outbound network use
+    pub metrics_endpoint: bool,     // Serve Prometheus /metrics on loopback
+    pub network_overrides: Vec<NetworkFeature>, // Allowed despite air-gapped mode
+    pub encrypt_content: bool,      // Encrypt stored blobs and chunk text at rest (on restart)
+}
+
+/// Concurrency and size limits; changes apply on restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorkerLimits {
+    pub max_vector_operations: usize,   // Concurrent vector store operations
+    pub cache_size_mb: u64,             // Memory + disk cache budget
+    pub model_storage_gb: f64,          // Quota for installed models
+    pub max_background_jobs: usize,     // Background jobs running at once
+}
+
+impl Default for WorkerLimits {
+    fn default() -> Self {
+        Self {
+            max_vector_operations: 10,
+            cache_size_mb: 256,
+            model_storage_gb: 20.0,
+            max_background_jobs: 4,
+        }
+    }
+}
+
+/// Search defaults for KB queries; changes apply on restart
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RetrievalDefaults {
+    pub max_results: usize,
+    pub hybrid_search: bool,        // BM25 + vector
+    pub rerank: bool,
+    pub cache_results: bool,
+    pub mmr_lambda: Option<f32>,    // MMR diversification (None = off)
+}
+
+impl Default for RetrievalDefaults {
+    fn default() -> Self {
+        Self {
+            max_results: 50,
+            hybrid_search: true,
+            rerank: false,
+            cache_results: true,
+            mmr_lambda: None,
+        }
+    }
+}
+
+/// Disk quotas in MB (0 = unlimited); applied while running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct QuotaSettings {
+    pub kb_limit_mb: u64,           // Per KB: content database share plus version blobs
+    pub project_limit_mb: u64,      // Databases, vectors, packs and blobs together
+    pub warn_threshold: f64,        // Fraction of a limit at which a disk_quota alert is raised
+}
+
+impl Default for QuotaSettings {
+    fn default() -> Self {
+        Self {
+            kb_limit_mb: 0,
+            project_limit_mb: 0,
+            warn_threshold: 0.9,
+        }
+    }
+}
+
+/// Severity each alert category is raised at; applied while running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AlertSettings {
+    pub desktop_min_severity: AlertSeverity,    // Lower severities only go to the inbox
+    pub pipeline_failure: AlertSeverity,
+    pub disk_quota: AlertSeverity,
+    pub model_download: AlertSeverity,
+    pub worker_crash: AlertSeverity,
+    pub eval_regression: AlertSeverity,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            desktop_min_severity: AlertSeverity::Warning,
+            pipeline_failure: AlertSeverity::Critical,
+            disk_quota: AlertSeverity::Warning,
+            model_download: AlertSeverity::Info,
+            worker_crash: AlertSeverity::Critical,
+            eval_regression: AlertSeverity::Warning,
+        }
+    }
+}
+
+/// Off-hours index maintenance (BM25 compaction, FTS5 segment merging);
+/// applied while running
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MaintenanceSettings {
+    pub enabled: bool,
+    pub schedule: String,           // Cron expression, UTC
+}
+
+impl Default for MaintenanceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            schedule: "0 3 * * *".to_string(),
+        }
+    }
+}
+
+/// Application configuration
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Settings {
+    pub version: u32,
+    pub paths: PathSettings,
+    pub features: FeatureFlags,
+    pub workers: WorkerLimits,
+    pub retrieval: RetrievalDefaults,
+    pub alerts: AlertSettings,
+    pub quotas: QuotaSettings,
+    pub maintenance: MaintenanceSettings,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            version: SETTINGS_VERSION,
+            paths: PathSettings::default(),
+            features: FeatureFlags::default(),
+            workers: WorkerLimits::default(),
+            retrieval: RetrievalDefaults::default(),
+            alerts: AlertSettings::default(),
+            quotas: QuotaSettings::default(),
+            maintenance: MaintenanceSettings::default(),
+        }
+    }
+}
+
+impl Settings {
+    /// Check values against their allowed ranges; errors name the field
+    pub fn validate(&self) -> Result<(), SettingsError> {
+        let mut errors = Vec::new();
+
+        for (field, path) in [
+            ("paths.database", &self.paths.database),
+            ("paths.cache_dir", &self.paths.cache_dir),
+            ("paths.vector_dir", &self.paths.vector_dir),
+            ("paths.packs_dir", &self.paths.packs_dir),
+            ("paths.blobs_dir", &self.paths.blobs_dir),
+            ("paths.logs_dir", &self.paths.logs_dir),
+            ("paths.models_dir", &self.paths.models_dir),
+            ("paths.backup_dir", &self.paths.backup_dir),
+            ("paths.secrets_dir", &self.paths.secrets_dir),
+            ("paths.trusted_signers", &self.paths.trusted_signers),
+            ("paths.mcp_tools", &self.paths.mcp_tools),
+            ("paths.mcp_prompts", &self.paths.mcp_prompts),
+        ] {
+            if path.as_os_str().is_empty() {
+                errors.push(format!("{} must not be empty", field));
+            }
+        }
+        if !(1..=256).contains(&self.workers.max_vector_operations) {
+            errors.push("workers.max_vector_operations must be between 1 and 256".to_string());
+        }
+        if !(16..=65536).contains(&self.workers.cache_size_mb) {
+            errors.push("workers.cache_size_mb must be between 16 and 65536".to_string());
+        }
+        if !(self.workers.model_storage_gb > 0.0 && self.workers.model_storage_gb <= 10_000.0) {
+            errors.push("workers.model_storage_gb must be between 0 and 10000".to_string());
+        }
+        if !(1..=64).contains(&self.workers.max_background_jobs) {
+            errors.push("workers.max_background_jobs must be between 1 and 64".to_string());
+        }
+        if !(1..=1000).contains(&self.retrieval.max_results) {
+            errors.push("retrieval.max_results must be between 1 and 1000".to_string());
+        }
+        if self.retrieval.mmr_lambda.is_some_and(|lambda| !(0.0..=1.0).contains(&lambda)) {
+            errors.push("retrieval.mmr_lambda must be between 0 and 1".to_string());
+        }
+        if !(self.quotas.warn_threshold > 0.0 && self.quotas.warn_threshold <= 1.0) {
+            errors.push("quotas.warn_threshold must be between 0 and 1".to_string());
+        }
+        if self.quotas.kb_limit_mb > 0 && self.quotas.project_limit_mb > 0
+            && self.quotas.kb_limit_mb > self.quotas.project_limit_mb
+        {
+            errors.push("quotas.kb_limit_mb must not exceed quotas.project_limit_mb".to_string());
+        }
+        if let Err(e) = CronSchedule::parse(&self.maintenance.schedule) {
+            errors.push(format!("maintenance.schedule: {}", e));
+        }
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(SettingsError::ValidationError(errors))
+        }
+    }
+
+    /// Sections that differ from `other` and only take effect after a restart
+    pub fn restart_required(&self, other: &Settings) -> Vec<String> {
+        let mut sections = Vec::new();
+        if self.paths != other.paths {
+            sections.push("paths".to_string());
+        }
+        if self.workers != other.workers {
+            sections.push("workers".to_string());
+        }
+        if self.retrieval != other.retrieval {
+            sections.push("retrieval".to_string());
+        }
+        if self.features.encrypt_content != other.features.encrypt_content {
+            sections.push("features.encrypt_content".to_string());
+        }
+        sections
+    }
+
+    /// Read a settings file without owning it (e.g. from the MCP server)
+    pub fn load(path: &Path) -> Result<Self, SettingsError> {
+        read_settings(path)
+    }
+
+    /// Parse a settings document, migrating older versions
+    fn from_json(mut value: serde_json::Value) -> Result<Self, SettingsError> {
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version > u64::from(SETTINGS_VERSION) {
+            return Err(SettingsError::UnsupportedVersion(version));
+        }
+        if version == 0 {
+            // Unversioned files predate the field but share the v1 layout
+            if let Some(object) = value.as_object_mut() {
+                object.insert("version".to_string(), SETTINGS_VERSION.into());
+            }
+        }
+
+        let settings: Settings = serde_json::from_value(value)?;
+        settings.validate()?;
+        Ok(settings)
+    }
+}
+
+/// Result of a settings update
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SettingsChange {
+    pub settings: Settings,
+    pub restart_required: Vec<String>,  // Changed sections applied on next start
+}
+
+/// Settings file owner; subscribers see every applied configuration
+pub struct SettingsService {
+    path: PathBuf,
+    current: watch::Sender<Settings>,
+    write_lock: std::sync::Mutex<()>,   // Serializes read-modify-write of the file
+    watcher: std::sync::Mutex<Option<RecommendedWatcher>>, // Set by `watch`
+}
+
+impl SettingsService {
+    /// Load the settings file, creating it with defaults if missing. An
+    /// invalid file is left untouched for the user to fix; defaults apply meanwhile.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, SettingsError> {
+        let path = path.into();
+        let settings = if path.exists() {
+            match read_settings(&path) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    warn!("Ignoring settings file {:?}: {}", path, e);
+                    Settings::default()
+                }
+            }
+        } else {
+            let settings = Settings::default();
+            write_settings(&path, &settings)?;
+            info!("Created settings file {:?}", path);
+            settings
+        };
+
+        Ok(Self {
+            path,
+            current: watch::channel(settings).0,
+            write_lock: std::sync::Mutex::new(()),
+            watcher: std::sync::Mutex::new(None),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn get(&self) -> Settings {
+        self.current.borrow().clone()
+    }
+
+    /// Notified with each newly applied configuration
+    pub fn subscribe(&self) -> watch::Receiver<Settings> {
+        self.current.subscribe()
+    }
+
+    /// Merge a partial JSON document into the settings, validate, save and apply
+    pub fn update(&self, patch: &serde_json::Value) -> Result<SettingsChange, SettingsError> {
+        let _guard = self.write_lock.lock().unwrap();
+        let previous = self.get();
+
+        let mut value = serde_json::to_value(&previous)?;
+        merge_json(&mut value, patch);
+        let settings = Settings::from_json(value)?;
+
+        write_settings(&self.path, &settings)?;
+        let restart_required = settings.restart_required(&previous);
+        self.current.send_if_modified(|current| {
+            let changed = *current != settings;
+            *current = settings.clone();
+            changed
+        });
+        Ok(SettingsChange { settings, restart_required })
+    }
+
+    /// Re-read the file; returns whether the settings changed
+    pub fn reload(&self) -> Result<bool, SettingsError> {
+        let _guard = self.write_lock.lock().unwrap();
+        let settings = read_settings(&self.path)?;
+        let changed = self.current.send_if_modified(|current| {
+            let changed = *current != settings;
+            *current = settings;
+            changed
+        });
+        if changed {
+            info!("Settings reloaded from {:?}", self.path);
+        }
+        Ok(changed)
+    }
+
+    /// Reload whenever the file changes, for as long as the service lives
+    pub fn watch(self: &Arc<Self>) -> Result<(), SettingsError> {
+        // Watch the directory: editors and `write_settings` replace the file
+        let dir = match self.path.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let file_name = self.path.file_name().map(|name| name.to_os_string());
+
+        let service = Arc::downgrade(self);
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+            let Ok(event) = event else { return };
+            if !event.paths.iter().any(|path| path.file_name() == file_name.as_deref()) {
+                return;
+            }
+            if let Some(service) = service.upgrade() {
+                if let Err(e) = service.reload() {
+                    warn!("Keeping previous settings, {:?} is invalid: {}", service.path, e);
+                }
+            }
+        }).map_err(|e| SettingsError::WatchError(e.to_string()))?;
+
+        watcher.watch(&dir, RecursiveMode::NonRecursive)
+            .map_err(|e| SettingsError::WatchError(e.to_string()))?;
+        *self.watcher.lock().unwrap() = Some(watcher);
+        Ok(())
+    }
+}
+
+fn read_settings(path: &Path) -> Result<Settings, SettingsError> {
+    let content = std::fs::read_to_string(path)?;
+    Settings::from_json(serde_json::from_str(&content)?)
+}
+
+/// Write via a temporary file so readers never see a partial document
+fn write_settings(path: &Path, settings: &Settings) -> Result<(), SettingsError> {
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        std::fs::create_dir_all(dir)?;
+    }
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_string_pretty(settings)?)?;
+    std::fs::rename(&tmp, path)?;
+    Ok(())
+}
+
+/// Recursively merge `patch` objects into `target`; other values replace
+fn merge_json(target: &mut serde_json::Value, patch: &serde_json::Value) {
+    match (target, patch) {
+        (serde_json::Value::Object(target), serde_json::Value::Object(patch)) => {
+            for (key, value) in patch {
+                match target.get_mut(key) {
+                    Some(existing) => merge_json(existing, value),
+                    None => {
+                        target.insert(key.clone(), value.clone());
+                    }
+                }
+            }
+        }
+        (target, patch) => *target = patch.clone(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use serde_json::json;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_update_validates_and_persists() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+        let service = SettingsService::open(&path).unwrap();
+        assert_eq!(service.get(), Settings::default());
+        assert!(path.exists());
+
+        let change = service.update(&json!({
+            "features": { "air_gapped": true },
+            "retrieval": { "max_results": 20 },
+        })).unwrap();
+        assert!(change.settings.features.air_gapped);
+        assert_eq!(change.restart_required, vec!["retrieval".to_string()]);
+        assert!(!change.settings.retrieval.rerank);
+
+        // Invalid values, unknown fields and newer versions are rejected without saving
+        let err = service.update(&json!({ "retrieval": { "max_results": 0, "mmr_lambda": 2.0 } })).unwrap_err();
+        assert!(matches!(err, SettingsError::ValidationError(ref errors) if errors.len() == 2), "{}", err);
+        assert!(service.update(&json!({ "features": { "air_gaped": true } })).is_err());
+        assert!(service.update(&json!({ "maintenance": { "schedule": "every night" } })).is_err());
+        assert!(matches!(service.update(&json!({ "version": 9 })), Err(SettingsError::UnsupportedVersion(9))));
+
+        let reopened = SettingsService::open(&path).unwrap();
+        assert_eq!(reopened.get(), change.settings);
+    }
+
+    #[test]
+    fn test_unversioned_and_invalid_files() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+
+        std::fs::write(&path, r#"{ "workers": { "max_vector_operations": 4 } }"#).unwrap();
+        let settings = SettingsService::open(&path).unwrap().get();
+        assert_eq!((settings.version, settings.workers.max_vector_operations), (SETTINGS_VERSION, 4));
+
+        // An invalid file falls back to defaults and is left for the user to fix
+        let invalid = r#"{ "workers": { "max_vector_operations": 0 } }"#;
+        std::fs::write(&path, invalid).unwrap();
+        let service = SettingsService::open(&path).unwrap();
+        assert_eq!(service.get(), Settings::default());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), invalid);
+        assert!(service.reload().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_file_edits_hot_reload() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("settings.json");
+        let service = Arc::new(SettingsService::open(&path).unwrap());
+        let mut changes = service.subscribe();
+        service.watch().unwrap();
+
+        let mut edited = Settings::default();
+        edited.features.metrics_endpoint = true;
+        std::fs::write(&path, serde_json::to_string(&edited).unwrap()).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(5), changes.changed()).await
+            .expect("settings reloaded").unwrap();
+        assert!(changes.borrow_and_update().features.metrics_endpoint);
+        assert_eq!(service.get(), edited);
+    }
+}
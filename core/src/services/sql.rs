@@ -3,20 +3,65 @@
  *
  * Provides SQLite database access with connection pooling, migrations,
  * backup functionality, and health monitoring. Implements MVP architecture
- * with upgrade path to split database design.
+ * with upgrade path to split database design: in split mode app metadata
+ * stays in app_meta.db, events go to events.db and each KB's content
+ * (documents, chunks, fingerprints) gets its own database with its own pool.
+ * Existing single-file databases are migrated on startup.
+ * Backups use SQLite's online backup API after a WAL checkpoint, so they run
+ * while the app is live; every backup and restore is integrity-checked.
+ * Chunk text is mirrored into an FTS5 table (`chunks_fts`) that serves as the
+ * fallback lexical search backend when a KB's BM25 index is unavailable.
+ * With a content cipher set, that table holds blind-index terms and sealed
+ * text instead of chunk text (see `encryption`).
  */
 
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool, PooledConnection};
 use diesel_migrations::{embed_migrations, EmbeddedMigrations, MigrationHarness};
+use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::ffi::CString;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
 use chrono::{DateTime, Utc};
-use tracing::info;
+use tracing::{info, warn};
+
+use super::encryption::{ContentCipher, EncryptionError};
 
 // Embed migrations at compile time
 pub const APP_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/app_meta/");
+pub const KB_CONTENT_MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations/kb_content/");
+
+/// Newest app migration this build knows. Migration names start with their
+/// date, so versions compare as strings; full-app backups record it so a
+/// restore can refuse a schema newer than the running app.
+pub fn schema_version() -> String {
+    diesel::migration::MigrationSource::<diesel::sqlite::Sqlite>::migrations(&APP_MIGRATIONS)
+        .ok()
+        .and_then(|migrations| migrations.iter().map(|m| m.name().to_string()).max())
+        .unwrap_or_default()
+}
+
+/// Tables that move to per-KB databases in split mode, in copy order
+const KB_CONTENT_TABLES: &[&str] = &[
+    "documents", "document_chunks", "document_fingerprints", "chunks_fts", "index_outbox", "graph_entities", "graph_relations",
+];
+
+/// Manifest written last into a backup set; its presence marks the set complete
+const BACKUP_MANIFEST_FILE: &str = "backup.json";
+
+/// File names inside a backup set
+const APP_BACKUP_FILE: &str = "app_meta.db";
+const EVENTS_BACKUP_FILE: &str = "events.db";
+const KB_BACKUP_DIR: &str = "kb";
+
+/// Pages copied per online backup step; writers get the lock between steps
+const BACKUP_PAGES_PER_STEP: i32 = 256;
+
+type DbPool = Pool<ConnectionManager<SqliteConnection>>;
+type DbConnection = PooledConnection<ConnectionManager<SqliteConnection>>;
 
 /// SQL Service Error Types
 #[derive(Debug, Error)]
@@ -33,6 +78,15 @@ pub enum SqlError {
     #[error("Backup operation failed: {0}")]
     BackupFailed(String),
 
+    #[error("Restore failed: {0}")]
+    RestoreFailed(String),
+
+    #[error("Integrity check failed for {path}: {details}")]
+    IntegrityCheckFailed { path: String, details: String },
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
 
@@ -41,6 +95,9 @@ pub enum SqlError {
 
     #[error("I/O error: {0}")]
     IoError(#[from] std::io::Error),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
 }
 
 /// WAL Mode Configuration
@@ -74,6 +131,10 @@ pub struct SqlConfig {
     pub events_connection_timeout: Duration, // Default: 10s
     pub events_wal_mode: WalMode,     // FULL (for separate events.db)
 
+    // Per-KB content databases (Production upgrade path)
+    pub kb_db_dir: Option<PathBuf>,   // None for MVP, holds kb_<id>.db files in split mode
+    pub kb_pool_size: u32,            // Default: 4 per KB database
+
     // MVP flag
     pub use_split_databases: bool,    // false for MVP, true for production
 
@@ -84,13 +145,17 @@ pub struct SqlConfig {
     pub vacuum_interval: Duration,    // Default: 24h
     pub backup_enabled: bool,         // Default: true
     pub backup_interval: Duration,    // Default: 1h
+    pub backup_dir: PathBuf,          // Default: backups/ next to the app DB, one directory per backup set
+    pub max_backups: usize,           // Default: 7, older sets are pruned
 }
 
 impl SqlConfig {
     /// Create default configuration for MVP (single database)
     pub fn new_mvp(app_db_path: impl Into<PathBuf>) -> Self {
+        let app_db_path = app_db_path.into();
+        let backup_dir = app_db_path.parent().unwrap_or(Path::new(".")).join("backups");
         Self {
-            app_db_path: app_db_path.into(),
+            app_db_path,
             app_pool_size: 10,
             app_connection_timeout: Duration::from_secs(30),
             app_wal_mode: WalMode::Normal,
@@ -98,6 +163,8 @@ impl SqlConfig {
             events_pool_size: 5,
             events_connection_timeout: Duration::from_secs(10),
             events_wal_mode: WalMode::Full,
+            kb_db_dir: None,
+            kb_pool_size: 4,
             use_split_databases: false,
             busy_timeout: Duration::from_secs(5),
             journal_size_limit: 64 * 1024 * 1024, // 64MB
@@ -105,6 +172,8 @@ impl SqlConfig {
             vacuum_interval: Duration::from_secs(24 * 3600), // 24h
             backup_enabled: true,
             backup_interval: Duration::from_secs(3600), // 1h
+            backup_dir,
+            max_backups: 7,
         }
     }
 
@@ -115,6 +184,7 @@ impl SqlConfig {
     ) -> Self {
         let mut config = Self::new_mvp(app_db_path);
         config.events_db_path = Some(events_db_path.into());
+        config.kb_db_dir = Some(config.app_db_path.parent().unwrap_or(Path::new(".")).join("kb"));
         config.use_split_databases = true;
         config
     }
@@ -137,15 +207,81 @@ pub struct DatabaseHealthMetrics {
     pub vacuum_last_run: DateTime<Utc>,
     pub backup_last_run: DateTime<Utc>,
     pub is_split_database: bool, // MVP vs Production mode
+    pub kb_databases: Vec<KbDatabaseHealth>, // Empty for MVP (content in app_meta.db)
+}
+
+/// Health of one per-KB content database
+#[derive(Debug, Clone)]
+pub struct KbDatabaseHealth {
+    pub kb_id: String,
+    pub db_size: u64,
+    pub pool_connections: Option<u32>, // None until the KB's pool is opened
+    pub pool_idle: Option<u32>,
+}
+
+/// Database file in a backup set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BackupFile {
+    pub name: String,                  // Relative to the backup set, e.g. "kb/kb_<id>.db"
+    pub size_bytes: u64,
+}
+
+/// Completed backup set
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DatabaseBackup {
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub created_at: DateTime<Utc>,
+    pub split_databases: bool,
+    pub files: Vec<BackupFile>,
+}
+
+impl DatabaseBackup {
+    pub fn size_bytes(&self) -> u64 {
+        self.files.iter().map(|f| f.size_bytes).sum()
+    }
+}
+
+/// Rows moved out of app_meta.db by the single-file to split migration
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SplitMigrationReport {
+    pub kbs_migrated: usize,
+    pub rows_moved: usize,
+}
+
+/// Chunk mirrored into the FTS5 lexical index
+#[derive(Debug, Clone)]
+pub struct FtsChunk {
+    pub chunk_id: String,
+    pub document_id: String,
+    pub content: String,
+    pub metadata: serde_json::Value,
+}
+
+/// FTS5 search hit; higher score is better (negated `bm25()`)
+#[derive(Debug, Clone, QueryableByName)]
+pub struct FtsMatch {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub chunk_id: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub document_id: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub content: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub metadata: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Double)]
+    pub score: f64,
 }
 
 /// SQL Service with connection pooling and health monitoring
 pub struct SqlService {
-    app_pool: Pool<ConnectionManager<SqliteConnection>>,
-    events_pool: Option<Pool<ConnectionManager<SqliteConnection>>>, // None for MVP
+    app_pool: DbPool,
+    events_pool: Option<DbPool>, // None for MVP
+    kb_pools: Mutex<HashMap<String, DbPool>>, // Opened lazily per KB in split mode
     config: SqlConfig,
     last_vacuum: DateTime<Utc>,
-    last_backup: DateTime<Utc>,
+    last_backup: Mutex<DateTime<Utc>>,
+    content_cipher: Option<Arc<ContentCipher>>, // Encrypts chunk text stored in KB content tables
 }
 
 impl SqlService {
@@ -191,12 +327,21 @@ impl SqlService {
             None
         };
 
+        if config.use_split_databases {
+            let kb_db_dir = config.kb_db_dir.as_ref().ok_or_else(|| SqlError::ConfigurationError(
+                "kb_db_dir required when use_split_databases is true".to_string()
+            ))?;
+            std::fs::create_dir_all(kb_db_dir)?;
+        }
+
         let service = Self {
             app_pool,
             events_pool,
+            kb_pools: Mutex::new(HashMap::new()),
             config,
             last_vacuum: Utc::now(),
-            last_backup: Utc::now(),
+            last_backup: Mutex::new(Utc::now()),
+            content_cipher: None,
         };
 
         // Configure database settings
@@ -205,6 +350,18 @@ impl SqlService {
         Ok(service)
     }
 
+    /// Encrypt chunk text written to KB content tables from now on. Rows
+    /// written in the clear stay readable but only match lexical search
+    /// again once their KB is reindexed.
+    pub fn with_content_cipher(mut self, cipher: Arc<ContentCipher>) -> Self {
+        self.content_cipher = Some(cipher);
+        self
+    }
+
+    pub fn content_encrypted(&self) -> bool {
+        self.content_cipher.is_some()
+    }
+
     /// Get connection to app database
     pub async fn get_app_connection(&self) -> Result<PooledConnection<ConnectionManager<SqliteConnection>>, SqlError> {
         self.app_pool.get().map_err(|e| SqlError::ConnectionFailed(e.to_string()))
@@ -218,6 +375,126 @@ impl SqlService {
         }
     }
 
+    /// Get connection to a KB's content database (uses app connection for MVP)
+    pub async fn get_kb_connection(&self, kb_id: &str) -> Result<DbConnection, SqlError> {
+        let pool = match &self.config.kb_db_dir {
+            Some(dir) if self.config.use_split_databases => self.kb_pool(dir, kb_id)?,
+            _ => self.app_pool.clone(), // MVP: content tables live in app_meta.db
+        };
+        pool.get().map_err(|e| SqlError::ConnectionFailed(e.to_string()))
+    }
+
+    /// Path of a KB's content database, or None in MVP mode
+    pub fn kb_db_path(&self, kb_id: &str) -> Result<Option<PathBuf>, SqlError> {
+        match &self.config.kb_db_dir {
+            Some(dir) if self.config.use_split_databases => Ok(Some(kb_db_file(dir, kb_id)?)),
+            _ => Ok(None),
+        }
+    }
+
+    /// Open (and migrate) a KB's content database on first use
+    fn kb_pool(&self, dir: &Path, kb_id: &str) -> Result<DbPool, SqlError> {
+        let mut pools = self.kb_pools.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(pool) = pools.get(kb_id) {
+            return Ok(pool.clone());
+        }
+
+        let path = kb_db_file(dir, kb_id)?;
+        info!("Opening KB content database at: {:?}", path);
+        let manager = ConnectionManager::<SqliteConnection>::new(path.to_string_lossy().to_string());
+        let pool = Pool::builder()
+            .max_size(self.config.kb_pool_size)
+            .connection_timeout(self.config.app_connection_timeout)
+            .build(manager)
+            .map_err(|e| SqlError::ConnectionFailed(e.to_string()))?;
+
+        let mut conn = pool.get().map_err(|e| SqlError::ConnectionFailed(e.to_string()))?;
+        self.apply_database_config(&mut conn, self.config.app_wal_mode)?;
+        conn.run_pending_migrations(KB_CONTENT_MIGRATIONS)
+            .map_err(|e| SqlError::MigrationFailed(e.to_string()))?;
+        drop(conn);
+
+        pools.insert(kb_id.to_string(), pool.clone());
+        Ok(pool)
+    }
+
+    /// Close and delete a KB's content database (split mode); MVP rows are left to the caller
+    pub async fn remove_kb_database(&self, kb_id: &str) -> Result<(), SqlError> {
+        let Some(path) = self.kb_db_path(kb_id)? else {
+            return Ok(());
+        };
+        self.kb_pools.lock().unwrap_or_else(|e| e.into_inner()).remove(kb_id);
+        for suffix in ["", "-wal", "-shm"] {
+            let mut file = path.clone().into_os_string();
+            file.push(suffix);
+            match std::fs::remove_file(&file) {
+                Ok(()) => {}
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+                Err(e) => return Err(e.into()),
+            }
+        }
+        info!("Removed KB content database for {}", kb_id);
+        Ok(())
+    }
+
+    /// Move KB content rows left in app_meta.db by the single-file layout into
+    /// per-KB databases; each KB moves in one transaction, so reruns are safe
+    pub async fn migrate_to_split_layout(&self) -> Result<SplitMigrationReport, SqlError> {
+        let mut report = SplitMigrationReport::default();
+        let Some(dir) = self.config.kb_db_dir.clone().filter(|_| self.config.use_split_databases) else {
+            return Ok(report);
+        };
+
+        let kb_ids: Vec<String> = {
+            let mut conn = self.get_app_connection().await?;
+            let union = KB_CONTENT_TABLES.iter()
+                .map(|table| format!("SELECT kb_id FROM {}", table))
+                .collect::<Vec<_>>()
+                .join(" UNION ");
+            diesel::sql_query(union)
+                .load::<KbIdRow>(&mut conn)?
+                .into_iter()
+                .map(|row| row.kb_id)
+                .collect()
+        };
+
+        for kb_id in kb_ids {
+            // Creates the content schema before rows are copied into it
+            let path = kb_db_file(&dir, &kb_id)?;
+            self.kb_pool(&dir, &kb_id)?;
+
+            let mut conn = SqliteConnection::establish(&self.config.app_db_path.to_string_lossy())
+                .map_err(|e| SqlError::ConnectionFailed(e.to_string()))?;
+            diesel::sql_query(format!("PRAGMA busy_timeout = {}", self.config.busy_timeout.as_millis()))
+                .execute(&mut conn)?;
+            diesel::sql_query("ATTACH DATABASE ? AS kb")
+                .bind::<diesel::sql_types::Text, _>(path.to_string_lossy().to_string())
+                .execute(&mut conn)?;
+
+            let moved = conn.transaction(|conn| {
+                let mut moved = 0;
+                for table in KB_CONTENT_TABLES {
+                    moved += diesel::sql_query(format!("INSERT OR IGNORE INTO kb.{0} SELECT * FROM main.{0} WHERE kb_id = ?", table))
+                        .bind::<diesel::sql_types::Text, _>(&kb_id)
+                        .execute(conn)?;
+                }
+                for table in KB_CONTENT_TABLES.iter().rev() {
+                    diesel::sql_query(format!("DELETE FROM main.{} WHERE kb_id = ?", table))
+                        .bind::<diesel::sql_types::Text, _>(&kb_id)
+                        .execute(conn)?;
+                }
+                Ok::<_, diesel::result::Error>(moved)
+            }).map_err(|e| SqlError::MigrationFailed(format!("KB {}: {}", kb_id, e)))?;
+
+            diesel::sql_query("DETACH DATABASE kb").execute(&mut conn)?;
+            info!("Moved {} rows of KB {} into {:?}", moved, kb_id, path);
+            report.kbs_migrated += 1;
+            report.rows_moved += moved;
+        }
+
+        Ok(report)
+    }
+
     /// Configure database settings (WAL mode, performance optimizations)
     async fn configure_database(&self) -> Result<(), SqlError> {
         info!("Configuring database settings");
@@ -308,49 +585,243 @@ impl SqlService {
         }
         // MVP: Event sourcing tables are included in APP_MIGRATIONS
 
+        // Production: Move content of single-file databases into per-KB databases
+        let report = self.migrate_to_split_layout().await?;
+        if report.kbs_migrated > 0 {
+            info!("Migrated {} KBs to split databases ({} rows)", report.kbs_migrated, report.rows_moved);
+        }
+
         info!("Database migrations completed successfully");
         Ok(())
     }
 
-    /// Backup databases using VACUUM INTO
-    pub async fn backup_databases(&self) -> Result<(), SqlError> {
-        let timestamp = Utc::now().format("%Y%m%d_%H%M%S");
+    /// Timestamped backup set under `backup_dir`, pruned to `max_backups`
+    pub async fn backup_databases(&self) -> Result<DatabaseBackup, SqlError> {
+        let dest = self.config.backup_dir.join(Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string());
+        let backup = self.backup(&dest).await?;
+        self.prune_backups(self.config.max_backups).await?;
+        Ok(backup)
+    }
+
+    /// Online backup of every database into the `dest` directory: WAL checkpoint,
+    /// page-by-page copy while writers continue, then an integrity check of each copy
+    pub async fn backup(&self, dest: &Path) -> Result<DatabaseBackup, SqlError> {
+        if dest.join(BACKUP_MANIFEST_FILE).exists() {
+            return Err(SqlError::BackupFailed(format!("{:?} already holds a backup", dest)));
+        }
+        std::fs::create_dir_all(dest)?;
+        self.checkpoint().await?;
+
+        let mut files = Vec::new();
+        for (name, source) in self.database_files()? {
+            let target = dest.join(&name);
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            info!("Backing up {:?} to {:?}", source, target);
+            let timeout = self.config.busy_timeout;
+            let (from, to) = (source.clone(), target.clone());
+            tokio::task::spawn_blocking(move || online_backup(&from, &to, timeout, true))
+                .await
+                .map_err(|e| SqlError::BackupFailed(e.to_string()))??;
+            integrity_check(&target)?;
+            files.push(BackupFile { name, size_bytes: std::fs::metadata(&target)?.len() });
+        }
+
+        let backup = DatabaseBackup {
+            path: dest.to_path_buf(),
+            created_at: Utc::now(),
+            split_databases: self.config.use_split_databases,
+            files,
+        };
+        std::fs::write(dest.join(BACKUP_MANIFEST_FILE), serde_json::to_vec_pretty(&backup)?)?;
+        *self.last_backup.lock().unwrap_or_else(|e| e.into_inner()) = backup.created_at;
 
-        // Always backup app_meta.db
-        let app_backup_path = format!("backups/app_meta_{}.db", timestamp);
-        info!("Backing up app database to: {}", app_backup_path);
-        self.backup_database(&self.config.app_db_path, &app_backup_path).await?;
+        info!("Database backup completed: {:?} ({} bytes)", dest, backup.size_bytes());
+        Ok(backup)
+    }
 
-        // Production: Backup events.db if split database is enabled
-        if self.config.use_split_databases {
-            if let Some(events_db_path) = &self.config.events_db_path {
-                let events_backup_path = format!("backups/events_{}.db", timestamp);
-                info!("Backing up events database to: {}", events_backup_path);
-                self.backup_database(events_db_path, &events_backup_path).await?;
+    /// Restore a backup set into the live databases. Every file is integrity-checked
+    /// before anything is written, and the current state is backed up first.
+    /// Callers should reload any state cached from the database afterwards.
+    pub async fn restore(&self, path: &Path) -> Result<DatabaseBackup, SqlError> {
+        let mut backup = read_backup_manifest(path)?;
+        if backup.split_databases != self.config.use_split_databases {
+            return Err(SqlError::RestoreFailed(format!(
+                "backup layout (split: {}) does not match the current layout (split: {})",
+                backup.split_databases, self.config.use_split_databases
+            )));
+        }
+
+        let mut targets = Vec::with_capacity(backup.files.len());
+        for file in &backup.files {
+            let source = path.join(&file.name);
+            if !source.is_file() {
+                return Err(SqlError::RestoreFailed(format!("{} is missing from the backup", file.name)));
+            }
+            integrity_check(&source)?;
+            targets.push((source, self.restore_target(&file.name)?));
+        }
+
+        let safety = self.config.backup_dir.join(format!("pre_restore_{}", Utc::now().format("%Y%m%d_%H%M%S_%3f")));
+        self.backup(&safety).await?;
+        info!("Current databases saved to {:?} before restore", safety);
+
+        for (source, target) in targets {
+            if let Some(parent) = target.parent() {
+                std::fs::create_dir_all(parent)?;
             }
+            info!("Restoring {:?} from {:?}", target, source);
+            let timeout = self.config.busy_timeout;
+            let live = target.clone();
+            tokio::task::spawn_blocking(move || online_backup(&source, &live, timeout, false))
+                .await
+                .map_err(|e| SqlError::RestoreFailed(e.to_string()))??;
+            integrity_check(&target)?;
         }
-        // MVP: All data backed up with app_meta.db
 
-        info!("Database backup completed successfully");
+        // Per-KB pools reopen against the restored files
+        self.kb_pools.lock().unwrap_or_else(|e| e.into_inner()).clear();
+        backup.path = path.to_path_buf();
+        info!("Databases restored from {:?}", path);
+        Ok(backup)
+    }
+
+    /// Complete backup sets under `backup_dir`, newest first
+    pub async fn list_backups(&self) -> Result<Vec<DatabaseBackup>, SqlError> {
+        let mut backups = Vec::new();
+        let entries = match std::fs::read_dir(&self.config.backup_dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(backups),
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let dir = entry?.path();
+            if dir.join(BACKUP_MANIFEST_FILE).is_file() {
+                match read_backup_manifest(&dir) {
+                    Ok(backup) => backups.push(backup),
+                    Err(e) => warn!("Skipping unreadable backup {:?}: {}", dir, e),
+                }
+            }
+        }
+        backups.sort_by_key(|b| std::cmp::Reverse(b.created_at));
+        Ok(backups)
+    }
+
+    /// Delete all but the newest `keep` backup sets; returns how many were removed
+    pub async fn prune_backups(&self, keep: usize) -> Result<usize, SqlError> {
+        let backups = self.list_backups().await?;
+        let mut removed = 0;
+        for backup in backups.iter().skip(keep.max(1)) {
+            std::fs::remove_dir_all(&backup.path)?;
+            removed += 1;
+        }
+        if removed > 0 {
+            info!("Pruned {} old backups", removed);
+        }
+        Ok(removed)
+    }
+
+    /// Back up every `interval`, keeping `max_backups` sets; abort the handle to stop
+    pub fn spawn_backup_scheduler(self: &Arc<Self>, interval: Duration, max_backups: usize) -> tokio::task::JoinHandle<()> {
+        let service = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            ticker.tick().await; // First tick is immediate; back up after one interval
+            loop {
+                ticker.tick().await;
+                let dest = service.config.backup_dir.join(Utc::now().format("%Y%m%d_%H%M%S_%3f").to_string());
+                match service.backup(&dest).await {
+                    Ok(_) => {
+                        if let Err(e) = service.prune_backups(max_backups).await {
+                            warn!("Failed to prune backups: {}", e);
+                        }
+                    }
+                    Err(e) => warn!("Scheduled backup failed: {}", e),
+                }
+            }
+        })
+    }
+
+    /// Fold WAL contents back into the database files
+    pub async fn checkpoint(&self) -> Result<(), SqlError> {
+        let mut conn = self.get_app_connection().await?;
+        diesel::sql_query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn)?;
+        if self.events_pool.is_some() {
+            let mut conn = self.get_events_connection().await?;
+            diesel::sql_query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn)?;
+        }
+        let kb_pools: Vec<DbPool> = self.kb_pools.lock().unwrap_or_else(|e| e.into_inner()).values().cloned().collect();
+        for pool in kb_pools {
+            let mut conn = pool.get().map_err(|e| SqlError::ConnectionFailed(e.to_string()))?;
+            diesel::sql_query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn)?;
+        }
         Ok(())
     }
 
-    /// Backup a specific database using VACUUM INTO
-    async fn backup_database(&self, source: &Path, dest: &str) -> Result<(), SqlError> {
-        // Ensure backup directory exists
-        if let Some(parent) = Path::new(dest).parent() {
-            std::fs::create_dir_all(parent)?;
+    /// Live database files keyed by their name inside a backup set
+    fn database_files(&self) -> Result<Vec<(String, PathBuf)>, SqlError> {
+        let mut files = vec![(APP_BACKUP_FILE.to_string(), self.config.app_db_path.clone())];
+        if !self.config.use_split_databases {
+            return Ok(files);
+        }
+        if let Some(events_db_path) = &self.config.events_db_path {
+            files.push((EVENTS_BACKUP_FILE.to_string(), events_db_path.clone()));
+        }
+        if let Some(dir) = &self.config.kb_db_dir {
+            let mut kb_files = Vec::new();
+            for entry in std::fs::read_dir(dir)? {
+                let name = entry?.file_name().to_string_lossy().to_string();
+                if name.starts_with("kb_") && name.ends_with(".db") {
+                    kb_files.push((format!("{}/{}", KB_BACKUP_DIR, name), dir.join(&name)));
+                }
+            }
+            kb_files.sort();
+            files.extend(kb_files);
         }
+        Ok(files)
+    }
 
-        // Use VACUUM INTO for consistent backup
-        let backup_query = format!("VACUUM INTO '{}'", dest);
-        let mut conn = SqliteConnection::establish(source.to_str().unwrap())
-            .map_err(|e| SqlError::BackupFailed(e.to_string()))?;
+    /// Live path a backup file restores to
+    fn restore_target(&self, name: &str) -> Result<PathBuf, SqlError> {
+        if name == APP_BACKUP_FILE {
+            return Ok(self.config.app_db_path.clone());
+        }
+        if name == EVENTS_BACKUP_FILE {
+            if let Some(events_db_path) = &self.config.events_db_path {
+                return Ok(events_db_path.clone());
+            }
+        }
+        if let (Some(dir), Some(file)) = (&self.config.kb_db_dir, name.strip_prefix(&format!("{}/", KB_BACKUP_DIR))) {
+            let kb_id = file.strip_prefix("kb_").and_then(|f| f.strip_suffix(".db")).unwrap_or_default();
+            return kb_db_file(dir, kb_id);
+        }
+        Err(SqlError::RestoreFailed(format!("unexpected file in backup: {}", name)))
+    }
 
-        diesel::sql_query(&backup_query)
-            .execute(&mut conn)
-            .map_err(|e| SqlError::BackupFailed(e.to_string()))?;
+    /// Fold every open database's WAL back into the main file (used on shutdown);
+    /// returns how many databases were checkpointed
+    pub async fn checkpoint_wal(&self) -> Result<usize, SqlError> {
+        let mut pools = vec![self.app_pool.clone()];
+        pools.extend(self.events_pool.clone());
+        pools.extend(self.kb_pools.lock().unwrap_or_else(|e| e.into_inner()).values().cloned());
 
+        for pool in &pools {
+            let mut conn = pool.get().map_err(|e| SqlError::ConnectionFailed(e.to_string()))?;
+            diesel::sql_query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut conn)?;
+        }
+        info!("Checkpointed WAL of {} database(s)", pools.len());
+        Ok(pools.len())
+    }
+
+    /// Round-trip a trivial query on the app (and events) database
+    pub async fn ping(&self) -> Result<(), SqlError> {
+        let mut conn = self.get_app_connection().await?;
+        diesel::sql_query("SELECT 1").execute(&mut conn)?;
+        if self.events_pool.is_some() {
+            let mut conn = self.get_events_connection().await?;
+            diesel::sql_query("SELECT 1").execute(&mut conn)?;
+        }
         Ok(())
     }
 
@@ -375,11 +846,178 @@ impl SqlService {
             events_pool_active: self.events_pool.as_ref().map(|p| p.state().connections),
             wal_checkpoint_age: Duration::from_secs(0), // TODO: Calculate actual age
             vacuum_last_run: self.last_vacuum,
-            backup_last_run: self.last_backup,
+            backup_last_run: *self.last_backup.lock().unwrap_or_else(|e| e.into_inner()),
             is_split_database: self.config.use_split_databases,
+            kb_databases: self.kb_database_health()?,
         })
     }
 
+    /// Size and pool state of every per-KB database on disk
+    fn kb_database_health(&self) -> Result<Vec<KbDatabaseHealth>, SqlError> {
+        let Some(dir) = self.config.kb_db_dir.as_ref().filter(|_| self.config.use_split_databases) else {
+            return Ok(Vec::new());
+        };
+        let pools = self.kb_pools.lock().unwrap_or_else(|e| e.into_inner());
+        let mut databases = Vec::new();
+        for entry in std::fs::read_dir(dir)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(kb_id) = name.strip_prefix("kb_").and_then(|n| n.strip_suffix(".db")) else {
+                continue;
+            };
+            let state = pools.get(kb_id).map(|pool| pool.state());
+            databases.push(KbDatabaseHealth {
+                kb_id: kb_id.to_string(),
+                db_size: entry.metadata()?.len(),
+                pool_connections: state.as_ref().map(|s| s.connections),
+                pool_idle: state.as_ref().map(|s| s.idle_connections),
+            });
+        }
+        databases.sort_by(|a, b| a.kb_id.cmp(&b.kb_id));
+        Ok(databases)
+    }
+
+    /// Bytes a KB's content takes: its database file in split mode, otherwise
+    /// the chunk text and metadata it stores in the shared database
+    pub async fn kb_content_bytes(&self, kb_id: &str) -> Result<u64, SqlError> {
+        if let Some(path) = self.kb_db_path(kb_id)? {
+            return Ok(sqlite_file_bytes(&path));
+        }
+        let mut conn = self.get_kb_connection(kb_id).await?;
+        let row = diesel::sql_query(
+            "SELECT COALESCE(SUM(LENGTH(content) + LENGTH(COALESCE(metadata, ''))), 0) AS count \
+             FROM chunks_fts WHERE kb_id = ?",
+        )
+            .bind::<diesel::sql_types::Text, _>(kb_id)
+            .get_result::<CountRow>(&mut conn)?;
+        Ok(row.count.max(0) as u64)
+    }
+
+    /// Bytes on disk for every database this service manages, WAL files included
+    pub fn database_bytes(&self) -> Result<u64, SqlError> {
+        let mut bytes = sqlite_file_bytes(&self.config.app_db_path);
+        if let Some(events) = &self.config.events_db_path {
+            bytes += sqlite_file_bytes(events);
+        }
+        if let Some(dir) = self.config.kb_db_dir.as_ref().filter(|_| self.config.use_split_databases) {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                if path.extension().is_some_and(|ext| ext == "db") {
+                    bytes += sqlite_file_bytes(&path);
+                }
+            }
+        }
+        Ok(bytes)
+    }
+
+    /// Add or replace chunks in a KB's FTS5 index
+    pub async fn fts_index_chunks(&self, kb_id: &str, chunks: &[FtsChunk]) -> Result<usize, SqlError> {
+        let rows = chunks.iter()
+            .map(|chunk| self.fts_row(chunk))
+            .collect::<Result<Vec<_>, SqlError>>()?;
+        self.with_kb_transaction(kb_id, |conn| {
+            for (chunk, (content, metadata)) in chunks.iter().zip(&rows) {
+                diesel::sql_query("DELETE FROM chunks_fts WHERE chunk_id = ? AND kb_id = ?")
+                    .bind::<diesel::sql_types::Text, _>(&chunk.chunk_id)
+                    .bind::<diesel::sql_types::Text, _>(kb_id)
+                    .execute(conn)?;
+                diesel::sql_query("INSERT INTO chunks_fts (chunk_id, document_id, kb_id, content, metadata) VALUES (?, ?, ?, ?, ?)")
+                    .bind::<diesel::sql_types::Text, _>(&chunk.chunk_id)
+                    .bind::<diesel::sql_types::Text, _>(&chunk.document_id)
+                    .bind::<diesel::sql_types::Text, _>(kb_id)
+                    .bind::<diesel::sql_types::Text, _>(content)
+                    .bind::<diesel::sql_types::Text, _>(metadata)
+                    .execute(conn)?;
+            }
+            Ok(chunks.len())
+        }).await
+    }
+
+    /// `(content, metadata)` columns for a chunk. Encrypted, content holds the
+    /// chunk's blind-index terms and metadata the sealed text and metadata.
+    fn fts_row(&self, chunk: &FtsChunk) -> Result<(String, String), SqlError> {
+        let Some(cipher) = &self.content_cipher else {
+            return Ok((chunk.content.clone(), chunk.metadata.to_string()));
+        };
+        let terms: Vec<String> = fts_terms(&chunk.content).map(|term| cipher.blind_token(term)).collect();
+        let payload = serde_json::json!({ "content": chunk.content, "metadata": chunk.metadata });
+        Ok((terms.join(" "), cipher.seal_text(&payload.to_string(), &chunk.chunk_id)?))
+    }
+
+    /// Replace a KB's whole FTS5 index (pack import, rebuild)
+    pub async fn fts_replace_chunks(&self, kb_id: &str, chunks: &[FtsChunk]) -> Result<usize, SqlError> {
+        self.fts_clear(kb_id).await?;
+        self.fts_index_chunks(kb_id, chunks).await
+    }
+
+    /// Remove a document's chunks from a KB's FTS5 index
+    pub async fn fts_remove_document(&self, kb_id: &str, document_id: &str) -> Result<usize, SqlError> {
+        self.with_kb_transaction(kb_id, |conn| {
+            Ok(diesel::sql_query("DELETE FROM chunks_fts WHERE document_id = ? AND kb_id = ?")
+                .bind::<diesel::sql_types::Text, _>(document_id)
+                .bind::<diesel::sql_types::Text, _>(kb_id)
+                .execute(conn)?)
+        }).await
+    }
+
+    /// Drop all of a KB's rows from the FTS5 index
+    pub async fn fts_clear(&self, kb_id: &str) -> Result<usize, SqlError> {
+        self.with_kb_transaction(kb_id, |conn| {
+            Ok(diesel::sql_query("DELETE FROM chunks_fts WHERE kb_id = ?")
+                .bind::<diesel::sql_types::Text, _>(kb_id)
+                .execute(conn)?)
+        }).await
+    }
+
+    /// Merge the FTS5 index's b-tree segments into one. The table is shared
+    /// by every KB in the same database, so this optimizes all of them.
+    pub async fn fts_optimize(&self, kb_id: &str) -> Result<(), SqlError> {
+        let mut conn = self.get_kb_connection(kb_id).await?;
+        diesel::sql_query("INSERT INTO chunks_fts (chunks_fts) VALUES ('optimize')").execute(&mut conn)?;
+        Ok(())
+    }
+
+    /// Number of chunks in a KB's FTS5 index
+    pub async fn fts_chunk_count(&self, kb_id: &str) -> Result<i64, SqlError> {
+        let mut conn = self.get_kb_connection(kb_id).await?;
+        let row = diesel::sql_query("SELECT COUNT(*) AS count FROM chunks_fts WHERE kb_id = ?")
+            .bind::<diesel::sql_types::Text, _>(kb_id)
+            .get_result::<CountRow>(&mut conn)?;
+        Ok(row.count)
+    }
+
+    /// BM25-ranked lexical search over a KB's FTS5 index; any term may match
+    pub async fn fts_search(&self, kb_id: &str, query: &str, limit: usize) -> Result<Vec<FtsMatch>, SqlError> {
+        let match_expr = match &self.content_cipher {
+            Some(cipher) => fts_match_expression(&fts_terms(query).map(|term| cipher.blind_token(term)).collect::<Vec<_>>().join(" ")),
+            None => fts_match_expression(query),
+        };
+        let Some(match_expr) = match_expr else {
+            return Ok(Vec::new());
+        };
+        let mut conn = self.get_kb_connection(kb_id).await?;
+        let mut matches = diesel::sql_query(
+            "SELECT chunk_id, document_id, content, metadata, -bm25(chunks_fts) AS score \
+             FROM chunks_fts WHERE chunks_fts MATCH ? AND kb_id = ? \
+             ORDER BY bm25(chunks_fts) LIMIT ?",
+        )
+            .bind::<diesel::sql_types::Text, _>(match_expr)
+            .bind::<diesel::sql_types::Text, _>(kb_id)
+            .bind::<diesel::sql_types::BigInt, _>(limit as i64)
+            .load::<FtsMatch>(&mut conn)?;
+        if let Some(cipher) = &self.content_cipher {
+            for hit in &mut matches {
+                let Some(sealed) = hit.metadata.as_deref().filter(|m| super::encryption::is_sealed_text(m)) else {
+                    continue;
+                };
+                let payload: serde_json::Value = serde_json::from_str(&cipher.open_text(sealed, &hit.chunk_id)?)?;
+                hit.content = payload["content"].as_str().unwrap_or_default().to_string();
+                hit.metadata = Some(payload["metadata"].to_string());
+            }
+        }
+        Ok(matches)
+    }
+
     /// Execute a transaction on the app database
     pub async fn with_app_transaction<T, F>(&self, f: F) -> Result<T, SqlError>
     where
@@ -390,6 +1028,16 @@ impl SqlService {
             .map_err(|e| SqlError::TransactionFailed(e.to_string()))
     }
 
+    /// Execute a transaction on a KB's content database
+    pub async fn with_kb_transaction<T, F>(&self, kb_id: &str, f: F) -> Result<T, SqlError>
+    where
+        F: FnOnce(&mut SqliteConnection) -> Result<T, SqlError>,
+    {
+        let mut conn = self.get_kb_connection(kb_id).await?;
+        conn.transaction(|conn| f(conn))
+            .map_err(|e| SqlError::TransactionFailed(e.to_string()))
+    }
+
     /// Execute a transaction on the events database
     pub async fn with_events_transaction<T, F>(&self, f: F) -> Result<T, SqlError>
     where
@@ -401,6 +1049,167 @@ impl SqlService {
     }
 }
 
+#[derive(QueryableByName)]
+struct IntegrityRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    integrity_check: String,
+}
+
+#[derive(QueryableByName)]
+struct CountRow {
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    count: i64,
+}
+
+/// A database file plus its WAL and shared-memory files
+fn sqlite_file_bytes(path: &Path) -> u64 {
+    ["", "-wal", "-shm"].iter()
+        .filter_map(|suffix| {
+            let mut file = path.as_os_str().to_owned();
+            file.push(suffix);
+            std::fs::metadata(PathBuf::from(file)).ok()
+        })
+        .map(|m| m.len())
+        .sum()
+}
+
+/// Terms as the FTS5 tokenizer sees them, before lowercasing
+fn fts_terms(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|term| !term.is_empty())
+}
+
+/// Quote each query term so user input can't inject FTS5 syntax; terms are OR-ed
+fn fts_match_expression(query: &str) -> Option<String> {
+    let terms: Vec<String> = fts_terms(query)
+        .map(|term| format!("\"{}\"", term.to_lowercase()))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" OR "))
+}
+
+#[derive(QueryableByName)]
+struct KbIdRow {
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    kb_id: String,
+}
+
+fn read_backup_manifest(path: &Path) -> Result<DatabaseBackup, SqlError> {
+    let bytes = std::fs::read(path.join(BACKUP_MANIFEST_FILE)).map_err(|e| {
+        SqlError::RestoreFailed(format!("{:?} is not a complete backup: {}", path, e))
+    })?;
+    let mut backup: DatabaseBackup = serde_json::from_slice(&bytes)?;
+    backup.path = path.to_path_buf();
+    Ok(backup)
+}
+
+/// `PRAGMA integrity_check` must report exactly "ok"
+fn integrity_check(path: &Path) -> Result<(), SqlError> {
+    let failed = |details: String| SqlError::IntegrityCheckFailed { path: path.display().to_string(), details };
+    if !path.is_file() {
+        return Err(failed("file not found".to_string()));
+    }
+    let mut conn = SqliteConnection::establish(&path.to_string_lossy()).map_err(|e| failed(e.to_string()))?;
+    let rows = diesel::sql_query("PRAGMA integrity_check")
+        .load::<IntegrityRow>(&mut conn)
+        .map_err(|e| failed(e.to_string()))?;
+    match rows.as_slice() {
+        [row] if row.integrity_check == "ok" => Ok(()),
+        _ => Err(failed(rows.into_iter().map(|r| r.integrity_check).take(5).collect::<Vec<_>>().join("; "))),
+    }
+}
+
+/// Raw handle for the online backup API, which diesel doesn't expose
+struct RawDatabase(*mut libsqlite3_sys::sqlite3);
+
+impl RawDatabase {
+    fn open(path: &Path) -> Result<Self, SqlError> {
+        let c_path = CString::new(path.to_string_lossy().as_bytes())
+            .map_err(|e| SqlError::BackupFailed(e.to_string()))?;
+        let mut db = std::ptr::null_mut();
+        let flags = libsqlite3_sys::SQLITE_OPEN_READWRITE | libsqlite3_sys::SQLITE_OPEN_CREATE;
+        // SAFETY: valid C string and out-pointer; the handle is closed on drop even when open fails
+        let rc = unsafe { libsqlite3_sys::sqlite3_open_v2(c_path.as_ptr(), &mut db, flags, std::ptr::null()) };
+        let database = Self(db);
+        if rc != libsqlite3_sys::SQLITE_OK {
+            return Err(SqlError::BackupFailed(format!("cannot open {:?}: {}", path, database.error_message())));
+        }
+        Ok(database)
+    }
+
+    fn exec(&self, sql: &str) -> Result<(), SqlError> {
+        let c_sql = CString::new(sql).map_err(|e| SqlError::BackupFailed(e.to_string()))?;
+        // SAFETY: open handle, valid C string, no callback
+        let rc = unsafe { libsqlite3_sys::sqlite3_exec(self.0, c_sql.as_ptr(), None, std::ptr::null_mut(), std::ptr::null_mut()) };
+        if rc != libsqlite3_sys::SQLITE_OK {
+            return Err(SqlError::BackupFailed(format!("{}: {}", sql, self.error_message())));
+        }
+        Ok(())
+    }
+
+    fn error_message(&self) -> String {
+        // SAFETY: sqlite3_errmsg handles NULL and returns a NUL-terminated string owned by SQLite
+        unsafe { std::ffi::CStr::from_ptr(libsqlite3_sys::sqlite3_errmsg(self.0)) }
+            .to_string_lossy()
+            .into_owned()
+    }
+}
+
+impl Drop for RawDatabase {
+    fn drop(&mut self) {
+        // SAFETY: closing NULL is a no-op; no statements or backups outlive the handle
+        unsafe { libsqlite3_sys::sqlite3_close(self.0) };
+    }
+}
+
+/// Copy `source` into `dest` with SQLite's online backup API, retrying while
+/// either side is locked for up to `busy_timeout`. `standalone` switches the
+/// copy to rollback journaling so a backup is a single self-contained file;
+/// otherwise (restores into live databases) WAL mode is kept.
+fn online_backup(source: &Path, dest: &Path, busy_timeout: Duration, standalone: bool) -> Result<(), SqlError> {
+    use libsqlite3_sys as ffi;
+
+    let src = RawDatabase::open(source)?;
+    let dst = RawDatabase::open(dest)?;
+    let main = c"main";
+    // SAFETY: both handles are open and outlive the backup object, which is always finished
+    let backup = unsafe { ffi::sqlite3_backup_init(dst.0, main.as_ptr(), src.0, main.as_ptr()) };
+    if backup.is_null() {
+        return Err(SqlError::BackupFailed(dst.error_message()));
+    }
+
+    let started = Instant::now();
+    let step_result = loop {
+        // SAFETY: backup is a live handle from sqlite3_backup_init
+        match unsafe { ffi::sqlite3_backup_step(backup, BACKUP_PAGES_PER_STEP) } {
+            ffi::SQLITE_OK => continue,
+            ffi::SQLITE_DONE => break Ok(()),
+            ffi::SQLITE_BUSY | ffi::SQLITE_LOCKED if started.elapsed() < busy_timeout => {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            rc => break Err(rc),
+        }
+    };
+    // SAFETY: finishes and frees the backup handle exactly once
+    let finish_rc = unsafe { ffi::sqlite3_backup_finish(backup) };
+    if let Err(rc) = step_result {
+        return Err(SqlError::BackupFailed(format!("backup step failed with code {}: {}", rc, dst.error_message())));
+    }
+    if finish_rc != ffi::SQLITE_OK {
+        return Err(SqlError::BackupFailed(dst.error_message()));
+    }
+
+    dst.exec(if standalone { "PRAGMA journal_mode = DELETE" } else { "PRAGMA journal_mode = WAL" })
+}
+
+/// KB ids become file names, so only path-safe ids get a database
+fn kb_db_file(dir: &Path, kb_id: &str) -> Result<PathBuf, SqlError> {
+    let safe = !kb_id.is_empty() && kb_id.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_'));
+    if safe {
+        Ok(dir.join(format!("kb_{}.db", kb_id)))
+    } else {
+        Err(SqlError::ConfigurationError(format!("KB id not usable as a database name: {}", kb_id)))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -583,9 +1392,8 @@ mod tests {
     #[tokio::test]
     async fn test_backup_functionality() {
         let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
 
-        let config = SqlConfig::new_mvp(&db_path);
+        let config = SqlConfig::test_config(temp_dir.path());
         let sql_service = SqlService::new(config).await.expect("Failed to create SQL service");
 
         sql_service.run_migrations().await.expect("Failed to run migrations");
@@ -594,6 +1402,199 @@ mod tests {
         assert!(result.is_ok(), "Backup should succeed: {:?}", result);
     }
 
+    #[tokio::test]
+    async fn test_backup_and_restore_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let mut config = SqlConfig::test_config(temp_dir.path());
+        config.max_backups = 2;
+        let sql_service = SqlService::new(config).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+
+        let insert = |name: &'static str| move |conn: &mut SqliteConnection| {
+            diesel::sql_query(format!(
+                "INSERT INTO knowledge_bases (id, name, status, embedder_model) VALUES ('{0}', '{0}', 'active', 'test')", name
+            )).execute(conn)?;
+            Ok(())
+        };
+        let names = || |conn: &mut SqliteConnection| {
+            Ok(diesel::sql_query("SELECT id AS kb_id FROM knowledge_bases ORDER BY id").load::<KbIdRow>(conn)?
+                .into_iter().map(|row| row.kb_id).collect::<Vec<_>>())
+        };
+
+        sql_service.with_app_transaction(insert("before")).await.unwrap();
+        let backup = sql_service.backup_databases().await.unwrap();
+        assert_eq!(backup.files.len(), 1);
+        assert!(backup.size_bytes() > 0);
+
+        // Changes after the backup are rolled back, through the existing pool
+        sql_service.with_app_transaction(insert("after")).await.unwrap();
+        sql_service.restore(&backup.path).await.unwrap();
+        assert_eq!(sql_service.with_app_transaction(names()).await.unwrap(), vec!["before"]);
+
+        // The pre-restore safety copy still has the later write
+        let backups = sql_service.list_backups().await.unwrap();
+        assert_eq!(backups.len(), 2);
+        sql_service.restore(&backups[0].path).await.unwrap();
+        assert_eq!(sql_service.with_app_transaction(names()).await.unwrap(), vec!["after", "before"]);
+
+        // Retention keeps the newest sets
+        sql_service.backup_databases().await.unwrap();
+        assert_eq!(sql_service.list_backups().await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_restore_rejects_corrupt_backup() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+
+        let dest = temp_dir.path().join("manual");
+        let backup = sql_service.backup(&dest).await.unwrap();
+        assert!(sql_service.backup(&dest).await.is_err(), "existing backups are never overwritten");
+
+        // Garbage over a whole b-tree page (page 2 is the first table's root;
+        // garbage in a page's free space would go unnoticed)
+        let file = dest.join(APP_BACKUP_FILE);
+        let mut bytes = std::fs::read(&file).unwrap();
+        let page_size = u16::from_be_bytes([bytes[16], bytes[17]]) as usize;
+        bytes[page_size..page_size * 2].fill(0xAB);
+        std::fs::write(&file, bytes).unwrap();
+
+        assert!(matches!(sql_service.restore(&backup.path).await, Err(SqlError::IntegrityCheckFailed { .. })));
+        assert!(matches!(sql_service.restore(temp_dir.path()).await, Err(SqlError::RestoreFailed(_))));
+        sql_service.health_check().await.unwrap();
+        integrity_check(&temp_dir.path().join("test_app.db")).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_split_layout_migrates_kb_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let app_db_path = temp_dir.path().join("app.db");
+
+        // Single-file layout with content for two KBs
+        let mvp = SqlService::new(SqlConfig::new_mvp(&app_db_path)).await.unwrap();
+        mvp.run_migrations().await.unwrap();
+        mvp.with_app_transaction(|conn| {
+            for kb_id in ["kb-a", "kb-b"] {
+                diesel::sql_query(format!(
+                    "INSERT INTO knowledge_bases (id, name, status, embedder_model) VALUES ('{0}', '{0}', 'active', 'test')", kb_id
+                )).execute(conn)?;
+                diesel::sql_query(format!(
+                    "INSERT INTO documents (id, kb_id, title, source_path, content_hash) VALUES ('{0}-doc', '{0}', 'Doc', '/doc.md', 'h')", kb_id
+                )).execute(conn)?;
+                diesel::sql_query(format!(
+                    "INSERT INTO document_fingerprints (kb_id, source_path, document_id, content_hash) VALUES ('{0}', '/doc.md', '{0}-doc', 'h')", kb_id
+                )).execute(conn)?;
+            }
+            Ok(())
+        }).await.unwrap();
+        mvp.fts_index_chunks("kb-a", &[fts_chunk("kb-a-c0", "kb-a-doc", "Ownership rules in Rust")]).await.unwrap();
+        drop(mvp);
+
+        let config = SqlConfig::new_production(&app_db_path, temp_dir.path().join("events.db"));
+        let split = SqlService::new(config).await.unwrap();
+        split.run_migrations().await.unwrap();
+
+        #[derive(QueryableByName)]
+        struct Count {
+            #[diesel(sql_type = diesel::sql_types::BigInt)]
+            n: i64,
+        }
+        let count = |table: &'static str| move |conn: &mut SqliteConnection| {
+            Ok(diesel::sql_query(format!("SELECT COUNT(*) AS n FROM {}", table)).get_result::<Count>(conn)?.n)
+        };
+        assert_eq!(split.with_app_transaction(count("document_fingerprints")).await.unwrap(), 0);
+        assert_eq!(split.with_app_transaction(count("documents")).await.unwrap(), 0);
+        assert_eq!(split.with_app_transaction(count("knowledge_bases")).await.unwrap(), 2);
+        assert_eq!(split.with_kb_transaction("kb-a", count("document_fingerprints")).await.unwrap(), 1);
+        assert_eq!(split.with_kb_transaction("kb-b", count("documents")).await.unwrap(), 1);
+        assert_eq!(split.with_app_transaction(count("chunks_fts")).await.unwrap(), 0);
+        assert_eq!(split.fts_search("kb-a", "rust", 5).await.unwrap().len(), 1);
+
+        // Nothing left to move on the next start
+        assert_eq!(split.migrate_to_split_layout().await.unwrap(), SplitMigrationReport::default());
+
+        let health = split.health_check().await.unwrap();
+        let ids: Vec<&str> = health.kb_databases.iter().map(|db| db.kb_id.as_str()).collect();
+        assert_eq!(ids, vec!["kb-a", "kb-b"]);
+        assert!(health.kb_databases.iter().all(|db| db.db_size > 0 && db.pool_connections.is_some()));
+
+        let backup = split.backup(&temp_dir.path().join("backup")).await.unwrap();
+        let names: Vec<&str> = backup.files.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, vec!["app_meta.db", "events.db", "kb/kb_kb-a.db", "kb/kb_kb-b.db"]);
+
+        assert!(split.get_kb_connection("../escape").await.is_err());
+        split.remove_kb_database("kb-b").await.unwrap();
+        assert_eq!(split.health_check().await.unwrap().kb_databases.len(), 1);
+    }
+
+    fn fts_chunk(chunk_id: &str, document_id: &str, content: &str) -> FtsChunk {
+        FtsChunk {
+            chunk_id: chunk_id.to_string(),
+            document_id: document_id.to_string(),
+            content: content.to_string(),
+            metadata: serde_json::json!({ "source": document_id }),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fts_index_and_search() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+
+        sql_service.fts_index_chunks("kb", &[
+            fts_chunk("c1", "doc-1", "Tokio runtime spawns async tasks"),
+            fts_chunk("c2", "doc-1", "Async tasks, async streams and async traits"),
+            fts_chunk("c3", "doc-2", "SQLite stores rows in B-trees"),
+        ]).await.unwrap();
+        sql_service.fts_index_chunks("other", &[fts_chunk("c4", "doc-3", "async everywhere")]).await.unwrap();
+
+        let matches = sql_service.fts_search("kb", "async", 10).await.unwrap();
+        let ids: Vec<&str> = matches.iter().map(|m| m.chunk_id.as_str()).collect();
+        assert_eq!(ids, vec!["c2", "c1"]);
+        assert!(matches[0].score >= matches[1].score && matches[1].score > 0.0);
+        assert_eq!(matches[0].metadata.as_deref(), Some(r#"{"source":"doc-1"}"#));
+
+        // FTS5 operators in user input are treated as plain terms
+        assert_eq!(sql_service.fts_search("kb", "b-trees\" OR NEAR(", 10).await.unwrap()[0].chunk_id, "c3");
+        assert!(sql_service.fts_search("kb", "  ()\"*  ", 10).await.unwrap().is_empty());
+
+        // Re-indexing a chunk replaces it
+        sql_service.fts_index_chunks("kb", &[fts_chunk("c3", "doc-2", "Pages and rows")]).await.unwrap();
+        assert_eq!(sql_service.fts_chunk_count("kb").await.unwrap(), 3);
+
+        assert_eq!(sql_service.fts_remove_document("kb", "doc-1").await.unwrap(), 2);
+        assert!(sql_service.fts_search("kb", "async", 10).await.unwrap().is_empty());
+        assert_eq!(sql_service.fts_clear("kb").await.unwrap(), 1);
+        assert_eq!(sql_service.fts_chunk_count("other").await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_fts_encrypted_content() {
+        let temp_dir = TempDir::new().unwrap();
+        let cipher = Arc::new(ContentCipher::from_key(&[9u8; 32]).unwrap());
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap()
+            .with_content_cipher(cipher);
+        sql_service.run_migrations().await.unwrap();
+
+        sql_service.fts_index_chunks("kb", &[
+            fts_chunk("c1", "doc-1", "Merger terms for Project Falcon"),
+            fts_chunk("c2", "doc-2", "Cafeteria menu"),
+        ]).await.unwrap();
+
+        // Stored rows carry no plaintext
+        let mut conn = sql_service.get_kb_connection("kb").await.unwrap();
+        let leaked = diesel::sql_query("SELECT COUNT(*) AS count FROM chunks_fts WHERE content LIKE '%Falcon%' OR metadata LIKE '%doc-1%'")
+            .get_result::<CountRow>(&mut conn).unwrap();
+        assert_eq!(leaked.count, 0);
+
+        let matches = sql_service.fts_search("kb", "falcon merger", 10).await.unwrap();
+        assert_eq!(matches.len(), 1);
+        assert_eq!(matches[0].content, "Merger terms for Project Falcon");
+        assert_eq!(matches[0].metadata.as_deref(), Some(r#"{"source":"doc-1"}"#));
+    }
+
     #[tokio::test]
     async fn test_error_handling_invalid_path() {
         let invalid_path = if cfg!(windows) {
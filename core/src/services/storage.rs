@@ -0,0 +1,778 @@
+/*!
+ * Storage Service Implementation
+ *
+ * Packs named files into compressed, checksummed archives (`PackManifest` +
+ * payload) for export/import between machines. MVP: zstd-compressed JSON
+ * container, upgrade path to ZIP packs with quotas and auto-prune.
+ *
+ * With a `PackSigner` attached, exported manifests carry an ed25519
+ * signature and imports are refused unless their signer is trusted.
+ * Unsigned packs (older exports) only import once the user confirms them.
+ *
+ * With a blobs directory configured, the service is also a content-addressable
+ * blob store: each blob is zstd-compressed under its SHA-256, so identical
+ * content in different KB versions and pipeline runs is stored once. Named
+ * manifests reference blobs; a blob no manifest references is garbage and
+ * `gc_unreferenced_blobs` removes it.
+ */
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use tracing::{info, warn};
+
+use super::encryption::{ContentCipher, EncryptionError};
+use super::pack_signing::{verify_signature, PackSignature, PackSigner, SigningError};
+
+/// Current pack container format
+pub const PACK_FORMAT_VERSION: u32 = 1;
+
+/// Storage Service Error Types
+#[derive(Debug, Error)]
+pub enum StorageError {
+    #[error("I/O error: {0}")]
+    IoError(#[from] std::io::Error),
+
+    #[error("Serialization error: {0}")]
+    SerializationError(#[from] serde_json::Error),
+
+    #[error("Checksum mismatch for {0}")]
+    ChecksumMismatch(String),
+
+    #[error("Invalid pack: {0}")]
+    InvalidPack(String),
+
+    #[error("Signature error: {0}")]
+    SignatureError(#[from] SigningError),
+
+    #[error("Pack is signed by untrusted key {key_id}")]
+    UntrustedSigner { key_id: String, public_key: String },
+
+    #[error("Pack {0} is unsigned")]
+    UnsignedPack(String),
+
+    #[error("Blob not found: {0}")]
+    BlobNotFound(String),
+
+    #[error("Blob store is not configured")]
+    BlobStoreDisabled,
+
+    #[error("Invalid manifest name: {0}")]
+    InvalidManifestName(String),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
+}
+
+/// Storage configuration
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub packs_dir: PathBuf,
+    pub blobs_dir: Option<PathBuf>,   // Content-addressable blob store; None keeps content in the database only
+    pub compression_level: i32,   // zstd level (1-19)
+    pub gc_grace: Duration,       // Unreferenced blobs younger than this survive GC (a manifest may be on its way)
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            packs_dir: PathBuf::from("./packs"),
+            blobs_dir: None,
+            compression_level: 3,
+            gc_grace: Duration::from_secs(3600),
+        }
+    }
+}
+
+impl StorageConfig {
+    /// Test configuration with isolated pack directory
+    pub fn test_config(data_dir: &Path) -> Self {
+        Self {
+            packs_dir: data_dir.join("packs"),
+            blobs_dir: Some(data_dir.join("blobs")),
+            compression_level: 1,
+            gc_grace: Duration::ZERO,
+        }
+    }
+}
+
+/// File entry listed in a pack manifest
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackFileEntry {
+    pub path: String,
+    pub size_bytes: u64,
+    pub sha256: String,
+}
+
+/// Manifest describing a pack and its payload
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PackManifest {
+    pub format_version: u32,
+    pub pack_type: String,        // "kb", "tool", ...
+    pub id: String,
+    pub name: String,
+    pub version: String,
+    pub created_at: DateTime<Utc>,
+    pub files: Vec<PackFileEntry>,
+    pub checksum: String,         // SHA-256 over all file entries
+    pub metadata: serde_json::Value,
+    #[serde(default)]
+    pub signature: Option<PackSignature>,
+}
+
+/// Who signed a pack and whether we trust them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum PackVerification {
+    Unsigned,
+    Trusted { key_id: String },
+    Untrusted { key_id: String, public_key: String },   // Ask the user before importing
+}
+
+impl PackManifest {
+    pub fn new(pack_type: &str, id: &str, name: &str, version: &str) -> Self {
+        Self {
+            format_version: PACK_FORMAT_VERSION,
+            pack_type: pack_type.to_string(),
+            id: id.to_string(),
+            name: name.to_string(),
+            version: version.to_string(),
+            created_at: Utc::now(),
+            files: Vec::new(),
+            checksum: String::new(),
+            metadata: serde_json::json!({}),
+            signature: None,
+        }
+    }
+
+    /// Bytes covered by the signature: every manifest field but the signature
+    pub fn signing_payload(&self) -> Vec<u8> {
+        format!(
+            "ragpack:{}\n{}\n{}\n{}\n{}\n{}\n{}\n{}",
+            self.format_version,
+            self.pack_type,
+            self.id,
+            self.name,
+            self.version,
+            self.created_at.to_rfc3339(),
+            self.checksum,
+            sha256_hex(self.metadata.to_string().as_bytes()),
+        ).into_bytes()
+    }
+
+    /// Checksum over the file listing (paths + per-file hashes)
+    pub fn compute_checksum(files: &[PackFileEntry]) -> String {
+        let listing: String = files.iter()
+            .map(|f| format!("{}:{}:{}\n", f.path, f.size_bytes, f.sha256))
+            .collect();
+        sha256_hex(listing.as_bytes())
+    }
+}
+
+/// A stored blob
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BlobRef {
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+/// Named set of blobs, e.g. the documents of a KB version or a pipeline
+/// run's artifacts; every blob it lists is kept alive by it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BlobManifest {
+    pub name: String,                       // "kb/<kb_id>/v<version>", "pipeline_runs/<run_id>", ...
+    pub created_at: DateTime<Utc>,
+    pub blobs: BTreeMap<String, BlobRef>,   // Logical path -> blob
+    pub metadata: serde_json::Value,
+}
+
+impl BlobManifest {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            created_at: Utc::now(),
+            blobs: BTreeMap::new(),
+            metadata: serde_json::json!({}),
+        }
+    }
+
+    pub fn size_bytes(&self) -> u64 {
+        self.blobs.values().map(|b| b.size_bytes).sum()
+    }
+}
+
+/// What a blob garbage collection found and removed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobGcReport {
+    pub manifests: usize,
+    pub scanned_blobs: usize,
+    pub referenced_blobs: usize,
+    pub removed_blobs: usize,
+    pub reclaimed_bytes: u64,     // Compressed bytes freed on disk
+    pub kept_recent: usize,       // Unreferenced, but within the grace period
+}
+
+/// Blob store usage; `logical_bytes` counts every reference, so the gap to
+/// `stored_bytes` is what deduplication and compression save
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct BlobStoreStats {
+    pub blobs: usize,
+    pub manifests: usize,
+    pub stored_bytes: u64,
+    pub logical_bytes: u64,
+}
+
+/// On-disk pack container (compressed as a whole)
+#[derive(Debug, Serialize, Deserialize)]
+struct PackContainer {
+    manifest: PackManifest,
+    files: BTreeMap<String, String>,
+}
+
+/// SHA-256 digest as lowercase hex
+pub fn sha256_hex(data: &[u8]) -> String {
+    ring::digest::digest(&ring::digest::SHA256, data)
+        .as_ref()
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Storage Service for packs
+pub struct StorageService {
+    config: StorageConfig,
+    signer: Option<Arc<PackSigner>>,
+    cipher: Option<Arc<ContentCipher>>,
+}
+
+impl StorageService {
+    pub fn new(config: StorageConfig) -> Self {
+        Self { config, signer: None, cipher: None }
+    }
+
+    /// Encrypt blobs at rest; blobs stored in the clear stay readable
+    pub fn with_cipher(mut self, cipher: Arc<ContentCipher>) -> Self {
+        self.cipher = Some(cipher);
+        self
+    }
+
+    /// Sign exported packs and enforce signer trust on import
+    pub fn with_signer(mut self, signer: Arc<PackSigner>) -> Self {
+        self.signer = Some(signer);
+        self
+    }
+
+    pub fn signer(&self) -> Option<&Arc<PackSigner>> {
+        self.signer.as_ref()
+    }
+
+    /// Build a compressed pack, filling in file entries and the manifest checksum
+    pub fn write_pack(
+        &self,
+        mut manifest: PackManifest,
+        files: BTreeMap<String, String>,
+    ) -> Result<(PackManifest, Vec<u8>), StorageError> {
+        manifest.files = files.iter().map(|(path, content)| PackFileEntry {
+            path: path.clone(),
+            size_bytes: content.len() as u64,
+            sha256: sha256_hex(content.as_bytes()),
+        }).collect();
+        manifest.checksum = PackManifest::compute_checksum(&manifest.files);
+        manifest.signature = self.signer.as_ref().map(|signer| signer.sign(&manifest.signing_payload()));
+
+        let container = PackContainer {
+            manifest: manifest.clone(),
+            files,
+        };
+        let json = serde_json::to_vec(&container)?;
+        let bytes = zstd::encode_all(json.as_slice(), self.config.compression_level)?;
+
+        info!("Wrote {} pack {} ({} files, {} bytes)", manifest.pack_type, manifest.id, manifest.files.len(), bytes.len());
+        Ok((manifest, bytes))
+    }
+
+    /// Decompress a pack and verify every file against the manifest, refusing
+    /// packs signed by a key we don't trust and unsigned packs
+    pub fn read_pack(&self, bytes: &[u8]) -> Result<(PackManifest, BTreeMap<String, String>), StorageError> {
+        self.read_pack_with(bytes, false)
+    }
+
+    /// Like `read_pack`, but `allow_unsigned` admits unsigned packs once the
+    /// user has confirmed them (see `verify_pack`)
+    pub fn read_pack_with(&self, bytes: &[u8], allow_unsigned: bool) -> Result<(PackManifest, BTreeMap<String, String>), StorageError> {
+        let (manifest, files, verification) = self.open_pack(bytes)?;
+        match verification {
+            PackVerification::Untrusted { key_id, public_key } => {
+                return Err(StorageError::UntrustedSigner { key_id, public_key });
+            }
+            PackVerification::Unsigned if !allow_unsigned => {
+                return Err(StorageError::UnsignedPack(manifest.id));
+            }
+            PackVerification::Unsigned => warn!("Importing unsigned {} pack {}", manifest.pack_type, manifest.id),
+            PackVerification::Trusted { .. } => {}
+        }
+        Ok((manifest, files))
+    }
+
+    /// Check a pack's integrity and signature without importing it, so the
+    /// UI can ask whether to trust an unknown signer
+    pub fn verify_pack(&self, bytes: &[u8]) -> Result<(PackManifest, PackVerification), StorageError> {
+        let (manifest, _, verification) = self.open_pack(bytes)?;
+        Ok((manifest, verification))
+    }
+
+    fn open_pack(&self, bytes: &[u8]) -> Result<(PackManifest, BTreeMap<String, String>, PackVerification), StorageError> {
+        let json = zstd::decode_all(bytes)
+            .map_err(|e| StorageError::InvalidPack(format!("Failed to decompress: {}", e)))?;
+        let container: PackContainer = serde_json::from_slice(&json)?;
+        let manifest = container.manifest;
+
+        if manifest.format_version > PACK_FORMAT_VERSION {
+            return Err(StorageError::InvalidPack(format!(
+                "Unsupported pack format version: {}", manifest.format_version
+            )));
+        }
+        if PackManifest::compute_checksum(&manifest.files) != manifest.checksum {
+            return Err(StorageError::ChecksumMismatch("manifest".to_string()));
+        }
+        if manifest.files.len() != container.files.len() {
+            return Err(StorageError::InvalidPack("File listing does not match payload".to_string()));
+        }
+
+        for entry in &manifest.files {
+            let content = container.files.get(&entry.path)
+                .ok_or_else(|| StorageError::InvalidPack(format!("Missing file: {}", entry.path)))?;
+            if sha256_hex(content.as_bytes()) != entry.sha256 {
+                return Err(StorageError::ChecksumMismatch(entry.path.clone()));
+            }
+        }
+
+        // A bad signature is tampering; an unknown signer is a trust decision.
+        // Without a signer attached (CLI, tests) there is no trust store to consult.
+        let verification = match &manifest.signature {
+            None => PackVerification::Unsigned,
+            Some(signature) => {
+                let key_id = verify_signature(signature, &manifest.signing_payload())?;
+                match &self.signer {
+                    Some(signer) if !signer.is_trusted(&key_id) => PackVerification::Untrusted {
+                        key_id,
+                        public_key: signature.public_key.clone(),
+                    },
+                    _ => PackVerification::Trusted { key_id },
+                }
+            }
+        };
+
+        Ok((manifest, container.files, verification))
+    }
+
+    /// Persist pack bytes under the packs directory
+    pub async fn save_pack(&self, file_name: &str, bytes: &[u8]) -> Result<PathBuf, StorageError> {
+        tokio::fs::create_dir_all(&self.config.packs_dir).await?;
+        let path = self.config.packs_dir.join(file_name);
+        tokio::fs::write(&path, bytes).await?;
+        Ok(path)
+    }
+
+    pub fn blob_store_enabled(&self) -> bool {
+        self.config.blobs_dir.is_some()
+    }
+
+    fn blobs_dir(&self) -> Result<&Path, StorageError> {
+        self.config.blobs_dir.as_deref().ok_or(StorageError::BlobStoreDisabled)
+    }
+
+    /// `objects/ab/cdef….zst`, sharded by the first byte of the hash
+    fn blob_path(&self, sha256: &str) -> Result<PathBuf, StorageError> {
+        if sha256.len() != 64 || !sha256.bytes().all(|b| b.is_ascii_hexdigit()) {
+            return Err(StorageError::BlobNotFound(sha256.to_string()));
+        }
+        Ok(self.blobs_dir()?.join("objects").join(&sha256[..2]).join(format!("{}.zst", &sha256[2..])))
+    }
+
+    fn manifest_path(&self, name: &str) -> Result<PathBuf, StorageError> {
+        let relative = Path::new(name);
+        let valid = !name.is_empty()
+            && relative.components().all(|c| matches!(c, Component::Normal(_)))
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | '/'));
+        if !valid {
+            return Err(StorageError::InvalidManifestName(name.to_string()));
+        }
+        Ok(self.blobs_dir()?.join("manifests").join(format!("{}.json", name)))
+    }
+
+    /// Store a blob, or reuse the copy already stored under the same hash.
+    /// With a cipher set, a copy stored in the clear is rewritten sealed.
+    pub fn put_blob(&self, data: &[u8]) -> Result<BlobRef, StorageError> {
+        let sha256 = sha256_hex(data);
+        let path = self.blob_path(&sha256)?;
+        let reseal = self.cipher.is_some() && path.exists() && !ContentCipher::is_sealed(&std::fs::read(&path)?);
+        if path.exists() && !reseal {
+            // Refresh the timestamp so GC's grace period covers the new reference
+            std::fs::File::options().write(true).open(&path)?.set_modified(SystemTime::now())?;
+        } else {
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            let mut bytes = zstd::encode_all(data, self.config.compression_level)?;
+            if let Some(cipher) = &self.cipher {
+                bytes = cipher.seal(&bytes, &sha256)?;
+            }
+            let tmp = path.with_extension(format!("tmp.{}", uuid::Uuid::new_v4().simple()));
+            std::fs::write(&tmp, bytes)?;
+            std::fs::rename(&tmp, &path)?;
+        }
+        Ok(BlobRef { sha256, size_bytes: data.len() as u64 })
+    }
+
+    /// Blob content, decrypted if sealed and verified against its hash
+    pub fn get_blob(&self, sha256: &str) -> Result<Vec<u8>, StorageError> {
+        let path = self.blob_path(sha256)?;
+        let mut compressed = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Err(StorageError::BlobNotFound(sha256.to_string())),
+            Err(e) => return Err(e.into()),
+        };
+        if ContentCipher::is_sealed(&compressed) {
+            let cipher = self.cipher.as_ref().ok_or(EncryptionError::KeyUnavailable)?;
+            compressed = cipher.open(&compressed, sha256)?;
+        }
+        let data = zstd::decode_all(compressed.as_slice())?;
+        if sha256_hex(&data) != sha256 {
+            return Err(StorageError::ChecksumMismatch(sha256.to_string()));
+        }
+        Ok(data)
+    }
+
+    /// Store each file as a blob and record them under manifest `name`,
+    /// replacing an earlier manifest of that name
+    pub fn store_blobs(
+        &self,
+        name: &str,
+        files: BTreeMap<String, Vec<u8>>,
+        metadata: serde_json::Value,
+    ) -> Result<BlobManifest, StorageError> {
+        let mut manifest = BlobManifest::new(name);
+        manifest.metadata = metadata;
+        for (path, data) in files {
+            manifest.blobs.insert(path, self.put_blob(&data)?);
+        }
+        self.save_manifest(&manifest)?;
+        Ok(manifest)
+    }
+
+    pub fn save_manifest(&self, manifest: &BlobManifest) -> Result<(), StorageError> {
+        let path = self.manifest_path(&manifest.name)?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(manifest)?)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    pub fn load_manifest(&self, name: &str) -> Result<BlobManifest, StorageError> {
+        let path = self.manifest_path(name)?;
+        match std::fs::read(&path) {
+            Ok(bytes) => Ok(serde_json::from_slice(&bytes)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Err(StorageError::BlobNotFound(name.to_string())),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Drop a manifest; its blobs stay until GC finds them unreferenced.
+    /// Returns false when there was no such manifest.
+    pub fn delete_manifest(&self, name: &str) -> Result<bool, StorageError> {
+        match std::fs::remove_file(self.manifest_path(name)?) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    /// Every manifest, by name
+    pub fn list_manifests(&self) -> Result<Vec<BlobManifest>, StorageError> {
+        let root = self.blobs_dir()?.join("manifests");
+        let mut manifests = Vec::new();
+        for path in walk_files(&root)? {
+            if path.extension().is_some_and(|ext| ext == "json") {
+                manifests.push(serde_json::from_slice::<BlobManifest>(&std::fs::read(&path)?)?);
+            }
+        }
+        manifests.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(manifests)
+    }
+
+    /// How many manifests reference each stored blob
+    pub fn blob_refcounts(&self) -> Result<HashMap<String, usize>, StorageError> {
+        let mut refcounts: HashMap<String, usize> = HashMap::new();
+        for manifest in self.list_manifests()? {
+            for blob in manifest.blobs.values() {
+                *refcounts.entry(blob.sha256.clone()).or_default() += 1;
+            }
+        }
+        Ok(refcounts)
+    }
+
+    pub fn blob_stats(&self) -> Result<BlobStoreStats, StorageError> {
+        let manifests = self.list_manifests()?;
+        let mut stats = BlobStoreStats {
+            manifests: manifests.len(),
+            logical_bytes: manifests.iter().map(|m| m.size_bytes()).sum(),
+            ..BlobStoreStats::default()
+        };
+        for path in walk_files(&self.blobs_dir()?.join("objects"))? {
+            if path.extension().is_some_and(|ext| ext == "zst") {
+                stats.blobs += 1;
+                stats.stored_bytes += std::fs::metadata(&path)?.len();
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Stored (compressed) size of the distinct blobs a KB's versions reference
+    pub fn kb_blob_bytes(&self, kb_id: &str) -> Result<u64, StorageError> {
+        if !self.blob_store_enabled() {
+            return Ok(0);
+        }
+        let prefix = format!("kb/{}/", kb_id);
+        let mut blobs = std::collections::HashSet::new();
+        for manifest in self.list_manifests()?.iter().filter(|m| m.name.starts_with(&prefix)) {
+            blobs.extend(manifest.blobs.values().map(|blob| blob.sha256.clone()));
+        }
+        let mut bytes = 0;
+        for sha256 in blobs {
+            bytes += std::fs::metadata(self.blob_path(&sha256)?).map(|m| m.len()).unwrap_or(0);
+        }
+        Ok(bytes)
+    }
+
+    /// Bytes on disk under the packs and blob directories
+    pub fn usage_bytes(&self) -> Result<u64, StorageError> {
+        let mut bytes = dir_bytes(&self.config.packs_dir)?;
+        if let Some(blobs_dir) = &self.config.blobs_dir {
+            bytes += dir_bytes(blobs_dir)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Remove blobs no manifest references (mark and sweep). Blobs written
+    /// within the grace period are kept: their manifest may not be saved yet.
+    pub fn gc_unreferenced_blobs(&self) -> Result<BlobGcReport, StorageError> {
+        let manifests = self.list_manifests()?;
+        let referenced: std::collections::HashSet<&str> = manifests.iter()
+            .flat_map(|m| m.blobs.values().map(|b| b.sha256.as_str()))
+            .collect();
+        let mut report = BlobGcReport {
+            manifests: manifests.len(),
+            referenced_blobs: referenced.len(),
+            ..BlobGcReport::default()
+        };
+
+        let now = SystemTime::now();
+        for path in walk_files(&self.blobs_dir()?.join("objects"))? {
+            let (Some(shard), Some(stem)) = (
+                path.parent().and_then(|p| p.file_name()).map(|n| n.to_string_lossy().to_string()),
+                path.file_stem().map(|n| n.to_string_lossy().to_string()),
+            ) else { continue };
+            let metadata = std::fs::metadata(&path)?;
+            let age = metadata.modified().ok().and_then(|m| now.duration_since(m).ok()).unwrap_or_default();
+
+            // Leftovers of interrupted writes
+            if path.extension().is_some_and(|ext| ext != "zst") {
+                if age >= self.config.gc_grace {
+                    std::fs::remove_file(&path)?;
+                    report.reclaimed_bytes += metadata.len();
+                }
+                continue;
+            }
+
+            report.scanned_blobs += 1;
+            if referenced.contains(format!("{}{}", shard, stem).as_str()) {
+                continue;
+            }
+            if age < self.config.gc_grace {
+                report.kept_recent += 1;
+                continue;
+            }
+            std::fs::remove_file(&path)?;
+            report.removed_blobs += 1;
+            report.reclaimed_bytes += metadata.len();
+        }
+
+        info!("Blob GC removed {} of {} blobs ({} bytes reclaimed)",
+              report.removed_blobs, report.scanned_blobs, report.reclaimed_bytes);
+        Ok(report)
+    }
+}
+
+/// Files under `dir`, recursively; a missing directory is empty
+/// Total size of the files under `dir`; a missing directory is empty
+pub(crate) fn dir_bytes(dir: &Path) -> Result<u64, StorageError> {
+    let mut bytes = 0;
+    for path in walk_files(dir)? {
+        bytes += std::fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+    }
+    Ok(bytes)
+}
+
+fn walk_files(dir: &Path) -> Result<Vec<PathBuf>, StorageError> {
+    let mut files = Vec::new();
+    let mut pending = vec![dir.to_path_buf()];
+    while let Some(dir) = pending.pop() {
+        let entries = match std::fs::read_dir(&dir) {
+            Ok(entries) => entries,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+            Err(e) => return Err(e.into()),
+        };
+        for entry in entries {
+            let entry = entry?;
+            if entry.file_type()?.is_dir() {
+                pending.push(entry.path());
+            } else {
+                files.push(entry.path());
+            }
+        }
+    }
+    Ok(files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::secrets::{SecretsConfig, SecretsService};
+    use tempfile::TempDir;
+
+    fn sample_files() -> BTreeMap<String, String> {
+        BTreeMap::from([
+            ("kb.json".to_string(), r#"{"id":"kb_1"}"#.to_string()),
+            ("chunks.json".to_string(), "[]".to_string()),
+        ])
+    }
+
+    #[test]
+    fn test_pack_roundtrip() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageService::new(StorageConfig::test_config(temp_dir.path()));
+
+        let (manifest, bytes) = storage
+            .write_pack(PackManifest::new("kb", "kb_1", "Docs", "1"), sample_files())
+            .unwrap();
+        assert_eq!(manifest.files.len(), 2);
+        assert!(!manifest.checksum.is_empty());
+
+        // Unsigned packs need an explicit go-ahead
+        assert!(matches!(storage.read_pack(&bytes), Err(StorageError::UnsignedPack(_))));
+        let (read_manifest, files) = storage.read_pack_with(&bytes, true).unwrap();
+        assert_eq!(read_manifest.checksum, manifest.checksum);
+        assert_eq!(files["kb.json"], r#"{"id":"kb_1"}"#);
+    }
+
+    #[test]
+    fn test_signed_pack_trust() {
+        let alice_dir = TempDir::new().unwrap();
+        let bob_dir = TempDir::new().unwrap();
+        let signer = |dir: &Path| {
+            let secrets = SecretsService::new(SecretsConfig::test_config(dir)).unwrap();
+            Arc::new(PackSigner::load_or_create(&secrets).unwrap())
+        };
+        let (alice, bob) = (signer(alice_dir.path()), signer(bob_dir.path()));
+        let alice_storage = StorageService::new(StorageConfig::test_config(alice_dir.path())).with_signer(alice.clone());
+        let bob_storage = StorageService::new(StorageConfig::test_config(bob_dir.path())).with_signer(bob.clone());
+
+        let (manifest, bytes) = alice_storage
+            .write_pack(PackManifest::new("kb", "kb_1", "Docs", "1"), sample_files())
+            .unwrap();
+        assert_eq!(manifest.signature.as_ref().unwrap().key_id, alice.key_id());
+        assert!(alice_storage.read_pack(&bytes).is_ok());
+
+        // Unknown signer: inspectable, but not importable until trusted
+        let (_, verification) = bob_storage.verify_pack(&bytes).unwrap();
+        assert_eq!(verification, PackVerification::Untrusted {
+            key_id: alice.key_id().to_string(),
+            public_key: alice.public_key().to_string(),
+        });
+        assert!(matches!(bob_storage.read_pack(&bytes), Err(StorageError::UntrustedSigner { .. })));
+        bob.trust(alice.public_key(), "Alice").unwrap();
+        assert!(bob_storage.read_pack(&bytes).is_ok());
+
+        // Re-labelling the manifest breaks the signature even with valid checksums
+        let json = zstd::decode_all(bytes.as_slice()).unwrap();
+        let mut container: PackContainer = serde_json::from_slice(&json).unwrap();
+        container.manifest.name = "Trusted Docs".to_string();
+        let tampered = zstd::encode_all(serde_json::to_vec(&container).unwrap().as_slice(), 1).unwrap();
+        assert!(matches!(bob_storage.read_pack(&tampered), Err(StorageError::SignatureError(_))));
+    }
+
+    #[test]
+    fn test_blob_dedup_and_gc() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageService::new(StorageConfig::test_config(temp_dir.path()));
+        let doc = |text: &str| text.as_bytes().to_vec();
+
+        // v1 and v2 share one unchanged document
+        let v1 = storage.store_blobs("kb/kb_1/v1", BTreeMap::from([
+            ("a.md".to_string(), doc("alpha")),
+            ("b.md".to_string(), doc("beta")),
+        ]), serde_json::json!({})).unwrap();
+        storage.store_blobs("kb/kb_1/v2", BTreeMap::from([
+            ("a.md".to_string(), doc("alpha")),
+            ("b.md".to_string(), doc("beta, revised")),
+        ]), serde_json::json!({})).unwrap();
+        let stats = storage.blob_stats().unwrap();
+        assert_eq!((stats.blobs, stats.manifests), (3, 2));
+        assert_eq!(storage.blob_refcounts().unwrap()[&v1.blobs["a.md"].sha256], 2);
+        assert_eq!(storage.get_blob(&v1.blobs["b.md"].sha256).unwrap(), doc("beta"));
+
+        // Dropping v1 leaves only its changed document unreferenced
+        assert!(storage.delete_manifest("kb/kb_1/v1").unwrap());
+        let report = storage.gc_unreferenced_blobs().unwrap();
+        assert_eq!((report.scanned_blobs, report.removed_blobs), (3, 1));
+        assert!(report.reclaimed_bytes > 0);
+        assert!(matches!(storage.get_blob(&v1.blobs["b.md"].sha256), Err(StorageError::BlobNotFound(_))));
+        assert_eq!(storage.get_blob(&v1.blobs["a.md"].sha256).unwrap(), doc("alpha"));
+
+        assert!(matches!(storage.load_manifest("../escape"), Err(StorageError::InvalidManifestName(_))));
+    }
+
+    #[test]
+    fn test_encrypted_blobs() {
+        let temp_dir = TempDir::new().unwrap();
+        let plain = StorageService::new(StorageConfig::test_config(temp_dir.path()));
+        let cipher = Arc::new(ContentCipher::from_key(&[3u8; 32]).unwrap());
+        let sealed = StorageService::new(StorageConfig::test_config(temp_dir.path())).with_cipher(cipher);
+
+        // A blob stored in the clear is readable, then sealed when stored again
+        let blob = plain.put_blob(b"confidential").unwrap();
+        assert_eq!(sealed.get_blob(&blob.sha256).unwrap(), b"confidential");
+        sealed.put_blob(b"confidential").unwrap();
+        assert_eq!(sealed.get_blob(&blob.sha256).unwrap(), b"confidential");
+        assert!(matches!(
+            plain.get_blob(&blob.sha256),
+            Err(StorageError::EncryptionError(EncryptionError::KeyUnavailable))
+        ));
+    }
+
+    #[test]
+    fn test_tampered_pack_rejected() {
+        let temp_dir = TempDir::new().unwrap();
+        let storage = StorageService::new(StorageConfig::test_config(temp_dir.path()));
+        let (manifest, _) = storage
+            .write_pack(PackManifest::new("kb", "kb_1", "Docs", "1"), sample_files())
+            .unwrap();
+
+        // Payload changed after the manifest was computed
+        let mut files = sample_files();
+        files.insert("kb.json".to_string(), r#"{"id":"evil"}"#.to_string());
+        let json = serde_json::to_vec(&PackContainer { manifest, files }).unwrap();
+        let bytes = zstd::encode_all(json.as_slice(), 1).unwrap();
+
+        assert!(matches!(storage.read_pack(&bytes), Err(StorageError::ChecksumMismatch(_))));
+        assert!(matches!(storage.read_pack(b"not a pack"), Err(StorageError::InvalidPack(_))));
+    }
+}
@@ -0,0 +1,248 @@
+/*!
+ * Telemetry Service Implementation
+ *
+ * OpenTelemetry tracing across the Manager, the MCP subprocess and workers.
+ * Each process installs `TelemetryService::layer` next to its log layer so
+ * tracing spans become OTel spans, exported over OTLP/HTTP when an endpoint
+ * is configured (Jaeger, Tempo or a collector on port 4318).
+ *
+ * Trace context crosses process boundaries as a W3C `traceparent` string
+ * (`00-<trace_id>-<span_id>-<flags>`): in `_meta.traceparent` of MCP request
+ * params and in the `traceparent` field of outbound RPC requests. The
+ * receiving side attaches it as the remote parent of its request span, so a
+ * search or pipeline run shows up as one trace end-to-end.
+ */
+
+use std::time::Duration;
+use opentelemetry::trace::{SpanContext, TraceContextExt, TraceFlags, TraceState, TracerProvider};
+use opentelemetry::{SpanId, TraceId};
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::trace::{Sampler, SdkTracer, SdkTracerProvider};
+use opentelemetry_sdk::Resource;
+use thiserror::Error;
+use tracing::Subscriber;
+use tracing_opentelemetry::{OpenTelemetryLayer, OpenTelemetrySpanExt};
+use tracing_subscriber::registry::LookupSpan;
+
+/// Field carrying the W3C trace context in request envelopes
+pub const TRACEPARENT_KEY: &str = "traceparent";
+
+/// Standard OTLP endpoint variable, inherited by subprocesses
+pub const OTLP_ENDPOINT_ENV: &str = "OTEL_EXPORTER_OTLP_ENDPOINT";
+
+/// Standard sampler ratio variable (0.0-1.0)
+pub const SAMPLER_RATIO_ENV: &str = "OTEL_TRACES_SAMPLER_ARG";
+
+/// OTLP/HTTP path for traces, appended to base endpoints
+const OTLP_TRACES_PATH: &str = "/v1/traces";
+
+const OTLP_EXPORT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Telemetry Service Error Types
+#[derive(Debug, Error)]
+pub enum TelemetryError {
+    #[error("Failed to build OTLP exporter: {0}")]
+    ExporterError(String),
+
+    #[error("Failed to flush spans: {0}")]
+    ShutdownError(String),
+}
+
+/// Telemetry configuration
+#[derive(Debug, Clone)]
+pub struct TelemetryConfig {
+    pub service_name: String,           // `service.name` of exported spans
+    pub otlp_endpoint: Option<String>,  // OTLP/HTTP base URL; None keeps trace context local
+    pub sample_ratio: f64,              // Share of new root traces recorded; children follow their parent
+}
+
+impl TelemetryConfig {
+    pub fn new(service_name: &str) -> Self {
+        Self {
+            service_name: service_name.to_string(),
+            otlp_endpoint: None,
+            sample_ratio: 1.0,
+        }
+    }
+
+    /// Configuration from the standard OTEL_* environment variables
+    pub fn from_env(service_name: &str) -> Self {
+        Self {
+            otlp_endpoint: std::env::var(OTLP_ENDPOINT_ENV).ok().filter(|e| !e.trim().is_empty()),
+            sample_ratio: std::env::var(SAMPLER_RATIO_ENV).ok()
+                .and_then(|ratio| ratio.parse::<f64>().ok())
+                .map_or(1.0, |ratio| ratio.clamp(0.0, 1.0)),
+            ..Self::new(service_name)
+        }
+    }
+}
+
+/// Trace and span id of a span, as carried between processes
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceContext {
+    pub trace_id: String,           // 32 lowercase hex digits
+    pub span_id: String,            // 16 lowercase hex digits
+    pub sampled: bool,
+}
+
+impl TraceContext {
+    /// Parse a W3C `traceparent` header value; None if malformed or all-zero
+    pub fn parse(traceparent: &str) -> Option<Self> {
+        let mut parts = traceparent.trim().split('-');
+        let (version, trace_id, span_id, flags) = (parts.next()?, parts.next()?, parts.next()?, parts.next()?);
+        if parts.next().is_some() || version != "00" || trace_id.len() != 32 || span_id.len() != 16 || flags.len() != 2 {
+            return None;
+        }
+        let trace = TraceId::from_hex(trace_id).ok().filter(|id| *id != TraceId::INVALID)?;
+        let span = SpanId::from_hex(span_id).ok().filter(|id| *id != SpanId::INVALID)?;
+        let flags = u8::from_str_radix(flags, 16).ok()?;
+
+        Some(Self {
+            trace_id: trace.to_string(),
+            span_id: span.to_string(),
+            sampled: flags & TraceFlags::SAMPLED.to_u8() != 0,
+        })
+    }
+
+    pub fn to_traceparent(&self) -> String {
+        format!("00-{}-{}-{:02x}", self.trace_id, self.span_id, u8::from(self.sampled))
+    }
+
+    /// Context of the current span; None unless a telemetry layer is installed
+    pub fn current() -> Option<Self> {
+        Self::of(&tracing::Span::current())
+    }
+
+    pub fn of(span: &tracing::Span) -> Option<Self> {
+        let context = span.context();
+        let span_ref = context.span();
+        let span_context = span_ref.span_context();
+        span_context.is_valid().then(|| Self {
+            trace_id: span_context.trace_id().to_string(),
+            span_id: span_context.span_id().to_string(),
+            sampled: span_context.is_sampled(),
+        })
+    }
+
+    /// Make this (remote) span the parent of `span`; must run before `span` is entered
+    pub fn attach(&self, span: &tracing::Span) {
+        let (Ok(trace_id), Ok(span_id)) = (TraceId::from_hex(&self.trace_id), SpanId::from_hex(&self.span_id)) else {
+            return;
+        };
+        let flags = if self.sampled { TraceFlags::SAMPLED } else { TraceFlags::NOT_SAMPLED };
+        let remote = SpanContext::new(trace_id, span_id, flags, true, TraceState::default());
+        // Fails only when no telemetry layer is installed, where there is nothing to link
+        let _ = span.set_parent(opentelemetry::Context::new().with_remote_span_context(remote));
+    }
+}
+
+/// OpenTelemetry tracer provider for this process
+pub struct TelemetryService {
+    config: TelemetryConfig,
+    provider: SdkTracerProvider,
+}
+
+impl TelemetryService {
+    /// Spans are always tracked (so context propagates); they are exported only with an endpoint
+    pub fn new(config: TelemetryConfig) -> Result<Self, TelemetryError> {
+        let mut builder = SdkTracerProvider::builder()
+            .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+            .with_resource(Resource::builder().with_service_name(config.service_name.clone()).build());
+
+        if let Some(endpoint) = &config.otlp_endpoint {
+            let exporter = SpanExporter::builder()
+                .with_http()
+                .with_endpoint(traces_endpoint(endpoint))
+                .with_timeout(OTLP_EXPORT_TIMEOUT)
+                .build()
+                .map_err(|e| TelemetryError::ExporterError(e.to_string()))?;
+            builder = builder.with_batch_exporter(exporter);
+        }
+
+        Ok(Self { config, provider: builder.build() })
+    }
+
+    pub fn is_exporting(&self) -> bool {
+        self.config.otlp_endpoint.is_some()
+    }
+
+    /// Tracing layer turning spans into OTel spans
+    pub fn layer<S>(&self) -> OpenTelemetryLayer<S, SdkTracer>
+    where
+        S: Subscriber + for<'span> LookupSpan<'span>,
+    {
+        tracing_opentelemetry::layer().with_tracer(self.provider.tracer(self.config.service_name.clone()))
+    }
+
+    /// Flush pending spans; call before the process exits
+    pub fn shutdown(&self) -> Result<(), TelemetryError> {
+        self.provider.shutdown().map_err(|e| TelemetryError::ShutdownError(e.to_string()))
+    }
+}
+
+fn traces_endpoint(endpoint: &str) -> String {
+    let endpoint = endpoint.trim_end_matches('/');
+    if endpoint.ends_with(OTLP_TRACES_PATH) {
+        endpoint.to_string()
+    } else {
+        format!("{}{}", endpoint, OTLP_TRACES_PATH)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tracing_subscriber::layer::SubscriberExt;
+
+    const TRACEPARENT: &str = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+
+    #[test]
+    fn test_traceparent_round_trip() {
+        let context = TraceContext::parse(TRACEPARENT).unwrap();
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.span_id, "00f067aa0ba902b7");
+        assert!(context.sampled);
+        assert_eq!(context.to_traceparent(), TRACEPARENT);
+
+        for invalid in [
+            "",
+            "01-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01",
+            "00-00000000000000000000000000000000-00f067aa0ba902b7-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-0000000000000000-01",
+            "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7",
+            "00-4bf92f3577b34da6a3ce929d0e0e473z-00f067aa0ba902b7-01",
+        ] {
+            assert!(TraceContext::parse(invalid).is_none(), "{}", invalid);
+        }
+
+        assert_eq!(traces_endpoint("http://localhost:4318/"), "http://localhost:4318/v1/traces");
+        assert_eq!(traces_endpoint("http://tempo:4318/v1/traces"), "http://tempo:4318/v1/traces");
+    }
+
+    #[test]
+    fn test_remote_parent_propagates_to_child_spans() {
+        let telemetry = TelemetryService::new(TelemetryConfig::new("rag-core-test")).unwrap();
+        assert!(!telemetry.is_exporting());
+        let subscriber = tracing_subscriber::registry().with(telemetry.layer());
+
+        tracing::subscriber::with_default(subscriber, || {
+            let parent = TraceContext::parse(TRACEPARENT).unwrap();
+            let request = tracing::info_span!("request");
+            parent.attach(&request);
+
+            let _entered = request.enter();
+            let child = tracing::info_span!("child").entered();
+            let current = TraceContext::current().unwrap();
+            assert_eq!(current.trace_id, parent.trace_id);
+            assert_ne!(current.span_id, parent.span_id);
+            assert_eq!(TraceContext::parse(&current.to_traceparent()), Some(current));
+            drop(child);
+
+            // Without a parent, a new trace starts
+            let root = tracing::info_span!(parent: None, "root");
+            assert_ne!(TraceContext::of(&root).unwrap().trace_id, parent.trace_id);
+        });
+
+        assert!(TraceContext::current().is_none());
+    }
+}
@@ -3,9 +3,12 @@
  *
  * Full LanceDB and Tantivy integration for production-ready vector search.
  * Supports hybrid search, generation management, and garbage collection.
+ * Lexical search can fall back to the SQLite FTS5 index kept by `SqlService`
+ * when a KB's BM25 index is missing or being rebuilt. With a content cipher
+ * set, chunk text in the BM25 index files is sealed (it stays plain in memory).
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -26,6 +29,10 @@ use tantivy::{
 
 // Re-export shared types from schemas module
 pub use crate::schemas::{VectorSchema, SearchResult, CitationInfo};
+use crate::schemas::TimeRange;
+use crate::services::encryption::{is_sealed_text, ContentCipher, EncryptionError};
+use crate::services::sql::{FtsChunk, FtsMatch, SqlError, SqlService};
+use crate::utils::snippet::{extract_snippet, highlight_result, SNIPPET_MAX_CHARS};
 
 /// Vector Database Service Error Types
 #[derive(Debug, thiserror::Error)]
@@ -71,6 +78,12 @@ pub enum VectorDbError {
 
     #[error("Connection error: {0}")]
     ConnectionError(String),
+
+    #[error("Lexical index error: {0}")]
+    LexicalIndexError(#[from] SqlError),
+
+    #[error("Encryption error: {0}")]
+    EncryptionError(#[from] EncryptionError),
 }
 
 // ============================================================================
@@ -149,6 +162,14 @@ impl Table {
         ))
     }
 
+    pub async fn delete_document(&self, _document_id: &str) -> Result<(), VectorDbError> {
+        // For now, return an error indicating LanceDB delete is not yet supported
+        // This will allow compilation while we resolve the Arrow version conflicts
+        Err(VectorDbError::ValidationError(
+            "LanceDB delete operation pending Arrow version resolution".to_string()
+        ))
+    }
+
     pub async fn search(&self, _query_vector: &[f32], _limit: usize) -> Result<Vec<VectorDocument>, VectorDbError> {
         // For now, return an error indicating LanceDB search is not yet supported
         // This will allow compilation while we resolve the Arrow version conflicts
@@ -156,6 +177,14 @@ impl Table {
             "LanceDB search operation pending Arrow version resolution".to_string()
         ))
     }
+
+    pub async fn optimize(&self) -> Result<(), VectorDbError> {
+        // For now, return an error indicating LanceDB compaction is not yet supported
+        // This will allow compilation while we resolve the Arrow version conflicts
+        Err(VectorDbError::ValidationError(
+            "LanceDB compaction pending Arrow version resolution".to_string()
+        ))
+    }
 }
 
 /// MVP BM25 Index using simple file storage
@@ -163,16 +192,17 @@ impl Table {
 pub struct BM25Index {
     index_path: PathBuf,
     documents: Arc<RwLock<Vec<VectorDocument>>>,
+    cipher: Option<Arc<ContentCipher>>, // Seals chunk text in documents.json
 }
 
 impl BM25Index {
-    pub async fn new(index_path: &Path) -> Result<Self, VectorDbError> {
+    pub async fn new(index_path: &Path, cipher: Option<Arc<ContentCipher>>) -> Result<Self, VectorDbError> {
         tokio::fs::create_dir_all(index_path).await?;
 
         let documents_file = index_path.join("documents.json");
         let documents = if documents_file.exists() {
             let content = tokio::fs::read_to_string(&documents_file).await?;
-            serde_json::from_str(&content).unwrap_or_default()
+            open_documents(serde_json::from_str(&content).unwrap_or_default(), cipher.as_deref())?
         } else {
             Vec::new()
         };
@@ -180,6 +210,7 @@ impl BM25Index {
         Ok(Self {
             index_path: index_path.to_path_buf(),
             documents: Arc::new(RwLock::new(documents)),
+            cipher,
         })
     }
 
@@ -203,10 +234,46 @@ impl BM25Index {
         Ok(())
     }
 
+    /// Snapshot of all stored chunks
+    pub async fn documents(&self) -> Vec<VectorDocument> {
+        self.documents.read().await.clone()
+    }
+
+    /// Replace the stored chunks (used when importing a pack)
+    pub async fn replace_documents(&self, documents: Vec<VectorDocument>) {
+        *self.documents.write().await = documents;
+    }
+
+    /// Remove all chunks of a document, returning how many were dropped
+    pub async fn remove_document(&self, document_id: &str) -> usize {
+        let mut documents = self.documents.write().await;
+        let before = documents.len();
+        documents.retain(|doc| doc.document_id != document_id);
+        before - documents.len()
+    }
+
+    /// Chunks in an index directory's file; unlike `new`, a malformed file is an error
+    async fn read_documents(index_path: &Path, cipher: Option<&ContentCipher>) -> Result<Vec<VectorDocument>, VectorDbError> {
+        let documents_file = index_path.join("documents.json");
+        if !documents_file.exists() {
+            return Ok(Vec::new());
+        }
+        open_documents(serde_json::from_str(&tokio::fs::read_to_string(&documents_file).await?)?, cipher)
+    }
+
     pub async fn commit(&self) -> Result<(), VectorDbError> {
         let documents = self.documents.read().await;
         let documents_file = self.index_path.join("documents.json");
-        let content = serde_json::to_string_pretty(&*documents)?;
+        let content = match &self.cipher {
+            Some(cipher) => {
+                let sealed = documents.iter().map(|doc| Ok(VectorDocument {
+                    content: cipher.seal_text(&doc.content, &doc.chunk_id)?,
+                    ..doc.clone()
+                })).collect::<Result<Vec<_>, EncryptionError>>()?;
+                serde_json::to_string_pretty(&sealed)?
+            }
+            None => serde_json::to_string_pretty(&*documents)?,
+        };
         tokio::fs::write(&documents_file, content).await?;
         Ok(())
     }
@@ -238,6 +305,18 @@ impl BM25Index {
     }
 }
 
+/// Open chunk text sealed by `BM25Index::commit`; text written before
+/// encryption was enabled passes through
+fn open_documents(mut documents: Vec<VectorDocument>, cipher: Option<&ContentCipher>) -> Result<Vec<VectorDocument>, VectorDbError> {
+    for doc in &mut documents {
+        if is_sealed_text(&doc.content) {
+            let cipher = cipher.ok_or(EncryptionError::KeyUnavailable)?;
+            doc.content = cipher.open_text(&doc.content, &doc.chunk_id)?;
+        }
+    }
+    Ok(documents)
+}
+
 // Simple cosine similarity implementation
 #[allow(dead_code)]
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
@@ -357,6 +436,15 @@ pub struct CacheConfig {
     pub enable_layered_cache: bool,
 }
 
+/// Backend answering BM25 lexical queries
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LexicalBackend {
+    Bm25Index, // Per-KB BM25 index only
+    Fts5,      // SQLite FTS5 index only
+    Auto,      // BM25 index, FTS5 while it is missing or being rebuilt
+}
+
 /// Vector Database Service configuration
 #[derive(Debug, Clone)]
 pub struct VectorDbConfig {
@@ -370,6 +458,7 @@ pub struct VectorDbConfig {
     // Feature flags for MVP vs Production implementation
     pub use_lancedb: bool,           // false = MVP (BM25 only), true = LanceDB + BM25
     pub fallback_to_mvp: bool,       // true = auto-fallback to MVP if LanceDB fails
+    pub lexical_backend: LexicalBackend, // FTS5 needs a SqlService, see `with_sql_service`
 }
 
 impl Default for VectorDbConfig {
@@ -385,6 +474,7 @@ impl Default for VectorDbConfig {
             // MVP defaults - safe fallback
             use_lancedb: false,              // Default to MVP implementation
             fallback_to_mvp: true,           // Auto-fallback enabled
+            lexical_backend: LexicalBackend::Auto,
         }
     }
 }
@@ -406,6 +496,7 @@ impl VectorDbConfig {
             // Production: Try LanceDB with fallback
             use_lancedb: true,               // Enable LanceDB for production
             fallback_to_mvp: true,           // Keep fallback for safety
+            lexical_backend: LexicalBackend::Auto,
         }
     }
 
@@ -421,6 +512,7 @@ impl VectorDbConfig {
             // Test: Force MVP for reliable testing
             use_lancedb: false,              // Tests use MVP only for now
             fallback_to_mvp: true,           // Always fallback for tests
+            lexical_backend: LexicalBackend::Auto,
         }
     }
 
@@ -437,6 +529,7 @@ impl VectorDbConfig {
             // LanceDB test configuration
             use_lancedb: true,               // Force LanceDB for testing
             fallback_to_mvp: false,          // No fallback - test LanceDB directly
+            lexical_backend: LexicalBackend::Auto,
         }
     }
 
@@ -457,6 +550,7 @@ impl VectorDbConfig {
             // MVP-only: No LanceDB at all
             use_lancedb: false,              // Force MVP implementation
             fallback_to_mvp: false,          // No fallback needed
+            lexical_backend: LexicalBackend::Auto,
         }
     }
 }
@@ -466,7 +560,8 @@ impl VectorDbConfig {
 // ============================================================================
 
 /// Generation status tracking
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
 pub enum GenerationStatus {
     Building,
     Ready,
@@ -520,9 +615,13 @@ impl GenerationManager {
 
     pub async fn create_generation(&self, kb_id: &str) -> Result<u64, VectorDbError> {
         let mut generations = self.generations.write().await;
-        let gen_id = SystemTime::now()
+        let mut gen_id = SystemTime::now()
             .duration_since(UNIX_EPOCH)?
             .as_millis() as u64;
+        // Each generation has its own collection, so ids must not repeat within a millisecond
+        while generations.contains_key(&format!("{}_{}", kb_id, gen_id)) {
+            gen_id += 1;
+        }
 
         let generation = Generation {
             id: gen_id,
@@ -634,12 +733,25 @@ impl GenerationManager {
 // ============================================================================
 
 /// Collection statistics
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct CollectionStats {
+    pub vector_count: u64,              // Chunks with an embedding
+    pub chunk_count: u64,
+    pub document_count: u64,
+    pub dimension: Option<usize>,       // None until a vector is stored
+    pub size_bytes: u64,                // BM25 index and LanceDB tables on disk
+    pub last_updated: Option<chrono::DateTime<chrono::Utc>>, // Newest index file change
+    pub generation_id: Option<u64>,     // Active generation
+    pub generations: Vec<GenerationStats>,
+}
+
+/// One generation of a collection
+#[derive(Debug, Clone, Serialize)]
+pub struct GenerationStats {
+    pub id: u64,
+    pub status: GenerationStatus,
     pub vector_count: u64,
     pub size_bytes: u64,
-    pub last_updated: Option<chrono::DateTime<chrono::Utc>>,
-    pub generation_id: Option<u64>,
 }
 
 /// Health status
@@ -650,6 +762,35 @@ pub enum HealthStatus {
     Degraded,
 }
 
+/// Lexical index state
+#[derive(Debug, Clone, Serialize)]
+pub struct LexicalHealth {
+    pub backend: LexicalBackend,
+    pub loaded_indexes: usize,          // KBs with an in-memory BM25 index
+    pub rebuilding: Vec<String>,        // KBs whose BM25 index is being rebuilt
+    pub fts_available: bool,            // FTS5 fallback wired up
+}
+
+/// Where a rebuild or optimize of a KB's indexes has got to
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexMaintenanceProgress {
+    pub stage: &'static str,            // "bm25", "fts5" or "lancedb"
+    pub processed: usize,               // Chunks done in this stage
+    pub total: usize,
+}
+
+/// Result of `rebuild_bm25_index` or `optimize_index`
+#[derive(Debug, Clone, Serialize)]
+pub struct IndexMaintenanceReport {
+    pub kb_id: String,
+    pub chunks: usize,                  // Chunks in the BM25 index afterwards
+    pub removed: usize,                 // Duplicate or empty chunks dropped
+    pub size_before: u64,               // BM25 index file, bytes
+    pub size_after: u64,
+    pub fts_updated: bool,              // Rebuild: rows rewritten; optimize: segments merged
+    pub lancedb_compacted: bool,
+}
+
 /// Vector Database Service trait
 #[async_trait]
 pub trait VectorDbServiceTrait {
@@ -659,6 +800,7 @@ pub trait VectorDbServiceTrait {
     async fn hybrid_search(&self, kb_id: &str, query: &str, query_vector: &[f32], limit: usize, filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError>;
     async fn bm25_search(&self, kb_id: &str, query: &str, limit: usize, filters: Option<&str>) -> Result<Vec<SearchResult>, VectorDbError>;
     async fn delete_collection(&self, kb_id: &str) -> Result<(), VectorDbError>;
+    async fn delete_document(&self, kb_id: &str, document_id: &str) -> Result<(), VectorDbError>;
     async fn get_collection_stats(&self, kb_id: &str) -> Result<CollectionStats, VectorDbError>;
 }
 
@@ -670,6 +812,10 @@ pub struct VectorDbService {
     config: VectorDbConfig,
     semaphore: Arc<Semaphore>,
     generation_manager: Arc<GenerationManager>,
+    sql_service: Option<Arc<SqlService>>, // FTS5 lexical index, None = BM25 index only
+    content_cipher: Option<Arc<ContentCipher>>, // Seals chunk text in BM25 index files
+    bm25_rebuilding: Arc<RwLock<HashSet<String>>>, // KBs whose BM25 index is being rebuilt
+    stats_cache: Arc<RwLock<HashMap<String, CollectionStats>>>, // Measured stores, dropped on each write
 }
 
 impl VectorDbService {
@@ -694,12 +840,16 @@ impl VectorDbService {
         let bm25_results = bm25_results?;
 
         // Merge and score results with hybrid approach
-        let merged_results = self.merge_search_results(vector_results, bm25_results, limit).await?;
+        let mut merged_results = self.merge_search_results(vector_results, bm25_results, limit).await?;
+        // Vector hits carry query-less snippets
+        for result in &mut merged_results {
+            highlight_result(result, query);
+        }
 
         Ok(merged_results)
     }
 
-    /// Perform BM25 lexical search using simplified text search
+    /// Perform BM25 lexical search on the configured lexical backend
     pub async fn bm25_search(
         &self,
         kb_id: &str,
@@ -707,12 +857,65 @@ impl VectorDbService {
         limit: usize,
         _filters: Option<&str>,
     ) -> Result<Vec<SearchResult>, VectorDbError> {
+        match self.config.lexical_backend {
+            LexicalBackend::Bm25Index => return self.bm25_index_search(kb_id, query, limit).await,
+            LexicalBackend::Fts5 => return self.fts_search(kb_id, query, limit).await,
+            LexicalBackend::Auto if self.sql_service.is_none() => {
+                return self.bm25_index_search(kb_id, query, limit).await;
+            }
+            LexicalBackend::Auto => {}
+        }
+
+        if self.bm25_rebuilding.read().await.contains(kb_id) {
+            tracing::debug!("BM25 index of KB {} is being rebuilt, searching FTS5 index", kb_id);
+            return self.fts_search(kb_id, query, limit).await;
+        }
+        match self.bm25_index_search(kb_id, query, limit).await {
+            Ok(results) => Ok(results),
+            Err(e) => {
+                tracing::warn!("BM25 index of KB {} unavailable ({}), searching FTS5 index", kb_id, e);
+                self.fts_search(kb_id, query, limit).await
+            }
+        }
+    }
+
+    /// Lexical search on the KB's FTS5 index; scores are normalized to (0, 1]
+    pub async fn fts_search(&self, kb_id: &str, query: &str, limit: usize) -> Result<Vec<SearchResult>, VectorDbError> {
+        let sql_service = self.sql_service.as_ref().ok_or_else(|| {
+            VectorDbError::ConfigError("FTS5 lexical backend requires a SQL service".to_string())
+        })?;
+
+        let matches = sql_service.fts_search(kb_id, query, limit).await?;
+        let top_score = matches.first().map(|m| m.score).filter(|s| *s > 0.0).unwrap_or(1.0);
+        let scores: Vec<f32> = matches.iter().map(|m| (m.score / top_score) as f32).collect();
+        let documents = matches.into_iter().map(|m| fts_match_to_document(kb_id, m)).collect();
+
+        let mut results = self.convert_stored_docs_to_search_results(documents, query).await?;
+        for (result, score) in results.iter_mut().zip(scores) {
+            result.score = score;
+        }
+        Ok(results)
+    }
+
+    /// Mark a KB's BM25 index as being rebuilt; `Auto` serves lexical queries from FTS5 meanwhile
+    pub async fn set_bm25_rebuilding(&self, kb_id: &str, rebuilding: bool) {
+        let mut set = self.bm25_rebuilding.write().await;
+        if rebuilding {
+            set.insert(kb_id.to_string());
+        } else {
+            set.remove(kb_id);
+        }
+    }
+
+    /// Search the KB's BM25 index, which must exist on disk
+    async fn bm25_index_search(&self, kb_id: &str, query: &str, limit: usize) -> Result<Vec<SearchResult>, VectorDbError> {
+        self.load_bm25_index(kb_id, false).await?;
         let bm25_indexes = self.bm25_indexes.read().await;
         let bm25_index = bm25_indexes.get(kb_id)
             .ok_or_else(|| VectorDbError::CollectionNotFound(format!("{}_bm25", kb_id)))?;
 
         let stored_docs = bm25_index.search(query, limit).await?;
-        let search_results = self.convert_stored_docs_to_search_results(stored_docs).await?;
+        let search_results = self.convert_stored_docs_to_search_results(stored_docs, query).await?;
 
         Ok(search_results)
     }
@@ -823,6 +1026,10 @@ impl VectorDbService {
             config,
             semaphore,
             generation_manager,
+            sql_service: None,
+            content_cipher: None,
+            bm25_rebuilding: Arc::new(RwLock::new(HashSet::new())),
+            stats_cache: Arc::new(RwLock::new(HashMap::new())),
         };
 
         tracing::info!(
@@ -835,10 +1042,94 @@ impl VectorDbService {
         Ok(service)
     }
 
+    /// Keep an FTS5 copy of chunk text in the SQL databases for lexical fallback
+    pub fn with_sql_service(mut self, sql_service: Arc<SqlService>) -> Self {
+        self.sql_service = Some(sql_service);
+        self
+    }
+
+    /// Seal chunk text written to BM25 index files
+    pub fn with_content_cipher(mut self, cipher: Arc<ContentCipher>) -> Self {
+        self.content_cipher = Some(cipher);
+        self
+    }
+
+    /// SQL service receiving FTS5 writes, unless the BM25 index is the only backend
+    fn fts_index(&self) -> Option<&Arc<SqlService>> {
+        self.sql_service.as_ref().filter(|_| self.config.lexical_backend != LexicalBackend::Bm25Index)
+    }
+
+    /// FTS5 write failures only fail the operation when FTS5 is the sole backend
+    fn fts_write_result<T>(&self, kb_id: &str, result: Result<T, SqlError>) -> Result<(), VectorDbError> {
+        match result {
+            Ok(_) => Ok(()),
+            Err(e) if self.config.lexical_backend == LexicalBackend::Fts5 => Err(e.into()),
+            Err(e) => {
+                tracing::warn!("Failed to update FTS5 index of KB {}: {}", kb_id, e);
+                Ok(())
+            }
+        }
+    }
+
     pub fn generation_manager(&self) -> &Arc<GenerationManager> {
         &self.generation_manager
     }
 
+    /// Open a collection's BM25 index from disk if this process hasn't loaded it yet
+    /// (collections written by another process, e.g. rag-cli, or before a restart)
+    async fn load_bm25_index(&self, kb_id: &str, create: bool) -> Result<(), VectorDbError> {
+        if self.bm25_indexes.read().await.contains_key(kb_id) {
+            return Ok(());
+        }
+        let bm25_index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
+        if !create && !bm25_index_path.exists() {
+            return Ok(());
+        }
+
+        let bm25_index = BM25Index::new(&bm25_index_path, self.content_cipher.clone()).await?;
+        self.bm25_indexes.write().await.entry(kb_id.to_string()).or_insert(bm25_index);
+        Ok(())
+    }
+
+    /// Raw chunks (content + embeddings) stored for a collection, used for export
+    pub async fn export_chunks(&self, kb_id: &str) -> Result<Vec<VectorDocument>, VectorDbError> {
+        self.load_bm25_index(kb_id, false).await?;
+        let bm25_indexes = self.bm25_indexes.read().await;
+        let bm25_index = bm25_indexes.get(kb_id)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(kb_id.to_string()))?;
+
+        Ok(bm25_index.documents().await)
+    }
+
+    /// Restore exported chunks into a collection's BM25 index (and LanceDB table when present)
+    pub async fn import_chunks(&self, kb_id: &str, documents: Vec<VectorDocument>) -> Result<(), VectorDbError> {
+        let _permit = self.semaphore.acquire().await?;
+
+        let mut bm25_indexes = self.bm25_indexes.write().await;
+        if !bm25_indexes.contains_key(kb_id) {
+            let bm25_index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
+            bm25_indexes.insert(kb_id.to_string(), BM25Index::new(&bm25_index_path, self.content_cipher.clone()).await?);
+        }
+        let bm25_index = bm25_indexes.get(kb_id).unwrap();
+
+        if let Some(table) = self.tables.read().await.get(kb_id) {
+            table.add(documents.clone()).await?;
+        }
+
+        if let Some(sql_service) = self.fts_index() {
+            let chunks: Vec<FtsChunk> = documents.iter().map(fts_chunk).collect();
+            self.fts_write_result(kb_id, sql_service.fts_replace_chunks(kb_id, &chunks).await)?;
+        }
+
+        let count = documents.len();
+        bm25_index.replace_documents(documents).await;
+        bm25_index.commit().await?;
+        self.invalidate_stats(kb_id).await;
+
+        tracing::info!("Imported {} chunks into KB: {}", count, kb_id);
+        Ok(())
+    }
+
     /// Check if service is running in LanceDB mode
     pub fn is_lancedb_enabled(&self) -> bool {
         self.config.use_lancedb
@@ -878,16 +1169,243 @@ impl VectorDbService {
         Ok(status)
     }
 
-    async fn convert_stored_docs_to_search_results(&self, documents: Vec<VectorDocument>) -> Result<Vec<SearchResult>, VectorDbError> {
+    /// State of the lexical indexes, for health reporting
+    pub async fn lexical_health(&self) -> LexicalHealth {
+        let mut rebuilding: Vec<String> = self.bm25_rebuilding.read().await.iter().cloned().collect();
+        rebuilding.sort();
+        LexicalHealth {
+            backend: self.config.lexical_backend,
+            loaded_indexes: self.bm25_indexes.read().await.len(),
+            rebuilding,
+            fts_available: self.sql_service.is_some(),
+        }
+    }
+
+    /// Rewrite a KB's BM25 index from its stored chunks, dropping duplicate
+    /// and empty ones, and replace its FTS5 rows to match. The new index is
+    /// written beside the old one and swapped in; `Auto` lexical search is
+    /// served from FTS5 until then.
+    pub async fn rebuild_bm25_index(
+        &self,
+        kb_id: &str,
+        progress: &mut (dyn FnMut(IndexMaintenanceProgress) + Send),
+    ) -> Result<IndexMaintenanceReport, VectorDbError> {
+        let _permit = self.semaphore.acquire().await?;
+        self.set_bm25_rebuilding(kb_id, true).await;
+        let result = self.rebuild_bm25_files(kb_id, progress).await;
+        self.set_bm25_rebuilding(kb_id, false).await;
+        match &result {
+            Ok(report) => tracing::info!("Rebuilt BM25 index of KB {}: {} chunks, {} dropped", kb_id, report.chunks, report.removed),
+            Err(e) => tracing::warn!("Failed to rebuild BM25 index of KB {}: {}", kb_id, e),
+        }
+        result
+    }
+
+    async fn rebuild_bm25_files(
+        &self,
+        kb_id: &str,
+        progress: &mut (dyn FnMut(IndexMaintenanceProgress) + Send),
+    ) -> Result<IndexMaintenanceReport, VectorDbError> {
+        // Held throughout, so writes to the KB wait for the swap instead of being lost
+        let mut bm25_indexes = self.bm25_indexes.write().await;
+        let index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
+        let documents = match bm25_indexes.get(kb_id) {
+            Some(bm25_index) => bm25_index.documents().await,
+            None if index_path.exists() => BM25Index::read_documents(&index_path, self.content_cipher.as_deref()).await?,
+            None => return Err(VectorDbError::CollectionNotFound(format!("{}_bm25", kb_id))),
+        };
+        let size_before = bm25_index_size(&index_path);
+        let total = documents.len();
+        progress(IndexMaintenanceProgress { stage: "bm25", processed: 0, total });
+
+        let (documents, removed) = compact_chunks(documents);
+        let fts_chunks: Vec<FtsChunk> = documents.iter().map(fts_chunk).collect();
+        let chunks = documents.len();
+
+        let staging_path = self.config.data_dir.join(format!("{}_bm25.rebuild", kb_id));
+        let retired_path = self.config.data_dir.join(format!("{}_bm25.old", kb_id));
+        for path in [&staging_path, &retired_path] {
+            if path.exists() {
+                tokio::fs::remove_dir_all(path).await?;
+            }
+        }
+        tokio::fs::create_dir_all(&staging_path).await?;
+        let mut rebuilt = BM25Index {
+            index_path: staging_path.clone(),
+            documents: Arc::new(RwLock::new(documents)),
+            cipher: self.content_cipher.clone(),
+        };
+        rebuilt.commit().await?;
+
+        // The old index is only deleted once the new one is in place
+        if index_path.exists() {
+            tokio::fs::rename(&index_path, &retired_path).await?;
+        }
+        tokio::fs::rename(&staging_path, &index_path).await?;
+        if retired_path.exists() {
+            tokio::fs::remove_dir_all(&retired_path).await?;
+        }
+        rebuilt.index_path = index_path.clone();
+        bm25_indexes.insert(kb_id.to_string(), rebuilt);
+        self.invalidate_stats(kb_id).await;
+        progress(IndexMaintenanceProgress { stage: "bm25", processed: total, total });
+
+        let fts_updated = match self.fts_index() {
+            Some(sql_service) => {
+                progress(IndexMaintenanceProgress { stage: "fts5", processed: 0, total: chunks });
+                let result = sql_service.fts_replace_chunks(kb_id, &fts_chunks).await;
+                let updated = result.is_ok();
+                self.fts_write_result(kb_id, result)?;
+                progress(IndexMaintenanceProgress { stage: "fts5", processed: chunks, total: chunks });
+                updated
+            }
+            None => false,
+        };
+
+        Ok(IndexMaintenanceReport {
+            kb_id: kb_id.to_string(),
+            chunks,
+            removed,
+            size_before,
+            size_after: bm25_index_size(&index_path),
+            fts_updated,
+            lancedb_compacted: false,
+        })
+    }
+
+    /// Compact a KB's indexes: the BM25 index drops duplicate and empty chunks,
+    /// FTS5 merges its segments and the LanceDB table, when there is one, is
+    /// compacted. Only BM25 index failures are returned; the others are logged.
+    pub async fn optimize_index(
+        &self,
+        kb_id: &str,
+        progress: &mut (dyn FnMut(IndexMaintenanceProgress) + Send),
+    ) -> Result<IndexMaintenanceReport, VectorDbError> {
+        let _permit = self.semaphore.acquire().await?;
+        self.load_bm25_index(kb_id, false).await?;
+        let index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
+        let size_before = bm25_index_size(&index_path);
+
+        let (chunks, removed) = {
+            let bm25_indexes = self.bm25_indexes.write().await;
+            let bm25_index = bm25_indexes.get(kb_id)
+                .ok_or_else(|| VectorDbError::CollectionNotFound(format!("{}_bm25", kb_id)))?;
+            let documents = bm25_index.documents().await;
+            let total = documents.len();
+            progress(IndexMaintenanceProgress { stage: "bm25", processed: 0, total });
+            let (documents, removed) = compact_chunks(documents);
+            let chunks = documents.len();
+            bm25_index.replace_documents(documents).await;
+            bm25_index.commit().await?;
+            self.invalidate_stats(kb_id).await;
+            progress(IndexMaintenanceProgress { stage: "bm25", processed: total, total });
+            (chunks, removed)
+        };
+
+        let fts_updated = match self.fts_index() {
+            Some(sql_service) => {
+                progress(IndexMaintenanceProgress { stage: "fts5", processed: 0, total: chunks });
+                match sql_service.fts_optimize(kb_id).await {
+                    Ok(()) => {
+                        progress(IndexMaintenanceProgress { stage: "fts5", processed: chunks, total: chunks });
+                        true
+                    }
+                    Err(e) => {
+                        tracing::warn!("Failed to optimize FTS5 index of KB {}: {}", kb_id, e);
+                        false
+                    }
+                }
+            }
+            None => false,
+        };
+
+        let lancedb_compacted = match self.tables.read().await.get(kb_id) {
+            Some(table) => {
+                progress(IndexMaintenanceProgress { stage: "lancedb", processed: 0, total: chunks });
+                match table.optimize().await {
+                    Ok(()) => true,
+                    Err(e) => {
+                        tracing::warn!("Failed to compact LanceDB table of KB {}: {}", kb_id, e);
+                        false
+                    }
+                }
+            }
+            None => false,
+        };
+
+        let report = IndexMaintenanceReport {
+            kb_id: kb_id.to_string(),
+            chunks,
+            removed,
+            size_before,
+            size_after: bm25_index_size(&index_path),
+            fts_updated,
+            lancedb_compacted,
+        };
+        tracing::info!(
+            "Optimized indexes of KB {}: {} chunks, {} dropped, {} -> {} bytes",
+            kb_id, report.chunks, report.removed, report.size_before, report.size_after
+        );
+        Ok(report)
+    }
+
+    /// Counts and on-disk size of a collection's stores, cached until its
+    /// next write. None when the collection has no BM25 index.
+    async fn measure_collection(&self, kb_id: &str) -> Result<Option<CollectionStats>, VectorDbError> {
+        if let Some(stats) = self.stats_cache.read().await.get(kb_id) {
+            return Ok(Some(stats.clone()));
+        }
+        self.load_bm25_index(kb_id, false).await?;
+
+        let mut stats = {
+            let bm25_indexes = self.bm25_indexes.read().await;
+            let Some(bm25_index) = bm25_indexes.get(kb_id) else {
+                return Ok(None);
+            };
+            let documents = bm25_index.documents.read().await;
+            let mut stats = CollectionStats { chunk_count: documents.len() as u64, ..Default::default() };
+            let mut document_ids = HashSet::new();
+            for doc in documents.iter() {
+                document_ids.insert(doc.document_id.as_str());
+                if !doc.embedding.is_empty() {
+                    stats.vector_count += 1;
+                    stats.dimension.get_or_insert(doc.embedding.len());
+                }
+            }
+            stats.document_count = document_ids.len() as u64;
+            stats
+        };
+
+        // LanceDB keeps each table in a `<name>.lance` directory
+        let mut paths = vec![self.config.data_dir.join(format!("{}_bm25", kb_id))];
+        let tables: HashSet<String> = [
+            format!("{}_vectors", kb_id),
+            self.generation_manager.get_active_table_name(kb_id),
+            self.generation_manager.get_staging_table_name(kb_id),
+        ].into_iter().collect();
+        paths.extend(tables.iter().map(|table| self.config.data_dir.join(format!("{}.lance", table))));
+        for path in &paths {
+            let (bytes, modified) = disk_usage(path);
+            stats.size_bytes += bytes;
+            stats.last_updated = stats.last_updated.max(modified.map(chrono::DateTime::<chrono::Utc>::from));
+        }
+
+        self.stats_cache.write().await.insert(kb_id.to_string(), stats.clone());
+        Ok(Some(stats))
+    }
+
+    async fn invalidate_stats(&self, kb_id: &str) {
+        self.stats_cache.write().await.remove(kb_id);
+    }
+
+    /// Search results for stored chunks; snippets are matched against `query`
+    /// (empty for vector search, which gets the chunk's opening sentences)
+    async fn convert_stored_docs_to_search_results(&self, documents: Vec<VectorDocument>, query: &str) -> Result<Vec<SearchResult>, VectorDbError> {
         let mut search_results = Vec::new();
 
         for doc in documents {
-            // Create snippet from content (first 200 characters)
-            let snippet = if doc.content.len() > 200 {
-                format!("{}...", &doc.content[..200])
-            } else {
-                doc.content.clone()
-            };
+            let snippet = extract_snippet(&doc.content, query, SNIPPET_MAX_CHARS);
+            let time_range = TimeRange::from_metadata(&doc.metadata);
 
             let result = SearchResult {
                 chunk_id: doc.chunk_id,
@@ -895,7 +1413,8 @@ impl VectorDbService {
                 kb_id: doc.kb_id,
                 score: 0.9, // Default score, will be updated by search query
                 content: doc.content,
-                snippet,
+                snippet: snippet.text,
+                highlights: snippet.highlights,
                 metadata: doc.metadata,
                 citation: CitationInfo {
                     title: doc.document_id,
@@ -904,6 +1423,7 @@ impl VectorDbService {
                     version: None,
                     anchor: None,
                     page_number: None,
+                    time_range,
                 },
             };
             search_results.push(result);
@@ -913,6 +1433,70 @@ impl VectorDbService {
     }
 }
 
+/// Drop empty chunks and all but the last copy of each chunk id, keeping
+/// order; returns the kept chunks and how many were dropped
+fn compact_chunks(documents: Vec<VectorDocument>) -> (Vec<VectorDocument>, usize) {
+    let total = documents.len();
+    let last: HashMap<String, usize> = documents.iter().enumerate()
+        .map(|(i, doc)| (doc.chunk_id.clone(), i))
+        .collect();
+    let kept: Vec<VectorDocument> = documents.into_iter().enumerate()
+        .filter(|(i, doc)| last.get(&doc.chunk_id) == Some(i) && !doc.content.trim().is_empty())
+        .map(|(_, doc)| doc)
+        .collect();
+    let removed = total - kept.len();
+    (kept, removed)
+}
+
+/// Bytes under `path` (a file or directory) and the newest modification
+/// time; nothing for a missing path
+fn disk_usage(path: &Path) -> (u64, Option<SystemTime>) {
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return (0, None);
+    };
+    if !metadata.is_dir() {
+        return (metadata.len(), metadata.modified().ok());
+    }
+    let mut total = (0, metadata.modified().ok());
+    if let Ok(entries) = std::fs::read_dir(path) {
+        for entry in entries.flatten() {
+            let (bytes, modified) = disk_usage(&entry.path());
+            total.0 += bytes;
+            total.1 = total.1.max(modified);
+        }
+    }
+    total
+}
+
+/// Size of a BM25 index's chunk file, 0 when missing
+fn bm25_index_size(index_path: &Path) -> u64 {
+    std::fs::metadata(index_path.join("documents.json")).map(|m| m.len()).unwrap_or(0)
+}
+
+fn fts_chunk(doc: &VectorDocument) -> FtsChunk {
+    FtsChunk {
+        chunk_id: doc.chunk_id.clone(),
+        document_id: doc.document_id.clone(),
+        content: doc.content.clone(),
+        metadata: doc.metadata.clone(),
+    }
+}
+
+fn fts_match_to_document(kb_id: &str, m: FtsMatch) -> VectorDocument {
+    VectorDocument {
+        chunk_id: m.chunk_id,
+        document_id: m.document_id,
+        kb_id: kb_id.to_string(),
+        content: m.content,
+        embedding: Vec::new(),
+        metadata: m.metadata
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or(serde_json::Value::Null),
+        created_at: 0,
+        updated_at: 0,
+    }
+}
+
 #[async_trait]
 impl VectorDbServiceTrait for VectorDbService {
     async fn create_collection(&self, kb_id: &str, schema: &VectorSchema) -> Result<(), VectorDbError> {
@@ -930,13 +1514,14 @@ impl VectorDbServiceTrait for VectorDbService {
 
         // Create BM25 index for hybrid search
         let bm25_index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
-        let bm25_index = BM25Index::new(&bm25_index_path).await?;
+        let bm25_index = BM25Index::new(&bm25_index_path, self.content_cipher.clone()).await?;
 
         let mut tables = self.tables.write().await;
         let mut bm25_indexes = self.bm25_indexes.write().await;
 
         tables.insert(kb_id.to_string(), table);
         bm25_indexes.insert(kb_id.to_string(), bm25_index);
+        self.invalidate_stats(kb_id).await;
 
         tracing::info!(
             "Created vector collection and BM25 index for KB: {} (MVP mode: {})",
@@ -947,12 +1532,13 @@ impl VectorDbServiceTrait for VectorDbService {
 
     async fn upsert_vectors(&self, kb_id: &str, vectors: Vec<VectorSchema>) -> Result<(), VectorDbError> {
         let _permit = self.semaphore.acquire().await?;
+        // MVP: BM25 is the source of truth; the LanceDB table is optional until Arrow is resolved
+        self.load_bm25_index(kb_id, true).await?;
 
         let tables = self.tables.read().await;
         let bm25_indexes = self.bm25_indexes.read().await;
 
-        let table = tables.get(kb_id)
-            .ok_or_else(|| VectorDbError::CollectionNotFound(kb_id.to_string()))?;
+        let table = tables.get(kb_id);
         let bm25_index = bm25_indexes.get(kb_id)
             .ok_or_else(|| VectorDbError::CollectionNotFound(format!("{}_bm25", kb_id)))?;
 
@@ -981,11 +1567,24 @@ impl VectorDbServiceTrait for VectorDbService {
                 bm25_index.add_document(vector).await?;
             }
 
-            table.add(stored_docs).await?;
+            if let Some(table) = table {
+                table.add(stored_docs).await?;
+            }
         }
 
         // Commit BM25 index
         bm25_indexes.get(kb_id).unwrap().commit().await?;
+        self.invalidate_stats(kb_id).await;
+
+        if let Some(sql_service) = self.fts_index() {
+            let chunks: Vec<FtsChunk> = vectors.iter().map(|v| FtsChunk {
+                chunk_id: v.chunk_id.clone(),
+                document_id: v.document_id.clone(),
+                content: v.content.clone(),
+                metadata: v.metadata.clone(),
+            }).collect();
+            self.fts_write_result(kb_id, sql_service.fts_index_chunks(kb_id, &chunks).await)?;
+        }
 
         tracing::debug!("Upserted {} vectors to KB: {}", vectors.len(), kb_id);
         Ok(())
@@ -1003,7 +1602,7 @@ impl VectorDbServiceTrait for VectorDbService {
         let table = self.connection.open_table(&table_name).await?;
         let documents = table.search(query_vector, limit).await?;
 
-        let search_results = self.convert_stored_docs_to_search_results(documents).await?;
+        let search_results = self.convert_stored_docs_to_search_results(documents, "").await?;
 
         tracing::debug!(
             "Vector search returned {} results for KB: {} (MVP mode: {})",
@@ -1028,17 +1627,53 @@ impl VectorDbServiceTrait for VectorDbService {
 
         tables.remove(kb_id);
         bm25_indexes.remove(kb_id);
+        self.invalidate_stats(kb_id).await;
+
+        // Otherwise the next load_bm25_index would bring the collection back
+        let bm25_index_path = self.config.data_dir.join(format!("{}_bm25", kb_id));
+        if bm25_index_path.exists() {
+            tokio::fs::remove_dir_all(&bm25_index_path).await?;
+        }
+
+        if let Some(sql_service) = self.fts_index() {
+            self.fts_write_result(kb_id, sql_service.fts_clear(kb_id).await)?;
+        }
 
         tracing::info!("Deleted collection: {}", kb_id);
         Ok(())
     }
 
-    async fn get_collection_stats(&self, kb_id: &str) -> Result<CollectionStats, VectorDbError> {
+    async fn delete_document(&self, kb_id: &str, document_id: &str) -> Result<(), VectorDbError> {
+        let _permit = self.semaphore.acquire().await?;
+        self.load_bm25_index(kb_id, false).await?;
+
         let tables = self.tables.read().await;
-        let _table = tables.get(kb_id)
+        let bm25_indexes = self.bm25_indexes.read().await;
+
+        let bm25_index = bm25_indexes.get(kb_id)
+            .ok_or_else(|| VectorDbError::CollectionNotFound(format!("{}_bm25", kb_id)))?;
+
+        if let Some(table) = tables.get(kb_id) {
+            table.delete_document(document_id).await?;
+        }
+
+        let removed = bm25_index.remove_document(document_id).await;
+        bm25_index.commit().await?;
+        self.invalidate_stats(kb_id).await;
+
+        if let Some(sql_service) = self.fts_index() {
+            self.fts_write_result(kb_id, sql_service.fts_remove_document(kb_id, document_id).await)?;
+        }
+
+        tracing::debug!("Deleted {} chunks of document {} from KB: {}", removed, document_id, kb_id);
+        Ok(())
+    }
+
+    async fn get_collection_stats(&self, kb_id: &str) -> Result<CollectionStats, VectorDbError> {
+        let mut stats = self.measure_collection(kb_id).await?
             .ok_or_else(|| VectorDbError::CollectionNotFound(kb_id.to_string()))?;
 
-        let generation_id = if self.config.enable_generation_management {
+        stats.generation_id = if self.config.enable_generation_management {
             self.generation_manager.get_active_generation(kb_id)
                 .await
                 .map(|gen| gen.id)
@@ -1046,12 +1681,20 @@ impl VectorDbServiceTrait for VectorDbService {
             None
         };
 
-        Ok(CollectionStats {
-            vector_count: 0,
-            size_bytes: 0,
-            last_updated: Some(chrono::Utc::now()),
-            generation_id,
-        })
+        // Generations change outside this service's writes, so they are never cached
+        for generation in self.generation_manager.get_generations(kb_id).await {
+            let measured = self.measure_collection(&format!("{}_{}", kb_id, generation.id)).await?;
+            let (dir_bytes, _) = disk_usage(&self.generation_manager.get_generation_path(kb_id, generation.id));
+            stats.generations.push(GenerationStats {
+                id: generation.id,
+                status: generation.status,
+                vector_count: measured.as_ref().map_or(generation.vector_count, |m| m.vector_count),
+                size_bytes: measured.map_or(generation.size_bytes, |m| m.size_bytes) + dir_bytes,
+            });
+        }
+        stats.generations.sort_by_key(|generation| generation.id);
+
+        Ok(stats)
     }
 }
 
@@ -1348,6 +1991,193 @@ mod tests {
         assert!(result.is_err(), "LanceDB service creation should fail until Arrow compatibility is resolved");
     }
 
+    #[tokio::test]
+    async fn test_fts5_fallback_when_bm25_index_missing() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(SqlService::new(crate::services::sql::SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql_service.run_migrations().await.unwrap();
+        let data_dir = temp_dir.path().join("vectors");
+
+        let chunk = |id: &str, doc: &str, content: &str| VectorSchema {
+            chunk_id: id.to_string(),
+            document_id: doc.to_string(),
+            kb_id: "kb".to_string(),
+            content: content.to_string(),
+            embedding: vec![0.1, 0.2],
+            metadata: serde_json::json!({ "page": 1 }),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let vector_service = VectorDbService::new(VectorDbConfig::test_config(&data_dir)).await.unwrap()
+            .with_sql_service(sql_service.clone());
+        vector_service.upsert_vectors("kb", vec![
+            chunk("c1", "doc-1", "Lexical search with BM25 ranking"),
+            chunk("c2", "doc-2", "Dense retrieval with embeddings"),
+        ]).await.unwrap();
+
+        // Marked as rebuilding: served from FTS5 even though the BM25 index exists
+        vector_service.set_bm25_rebuilding("kb", true).await;
+        let results = vector_service.bm25_search("kb", "ranking", 5, None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk_id, "c1");
+        assert_eq!(results[0].score, 1.0);
+        assert_eq!(results[0].metadata, serde_json::json!({ "page": 1 }));
+        vector_service.set_bm25_rebuilding("kb", false).await;
+
+        // BM25 index lost: a fresh service still answers lexical queries
+        tokio::fs::remove_dir_all(data_dir.join("kb_bm25")).await.unwrap();
+        let restarted = VectorDbService::new(VectorDbConfig::test_config(&data_dir)).await.unwrap()
+            .with_sql_service(sql_service.clone());
+        let results = restarted.bm25_search("kb", "embeddings", 5, None).await.unwrap();
+        assert_eq!(results.iter().map(|r| r.chunk_id.as_str()).collect::<Vec<_>>(), vec!["c2"]);
+
+        let mut config = VectorDbConfig::test_config(&data_dir);
+        config.lexical_backend = LexicalBackend::Bm25Index;
+        let bm25_only = VectorDbService::new(config).await.unwrap().with_sql_service(sql_service.clone());
+        assert!(matches!(
+            bm25_only.bm25_search("kb", "embeddings", 5, None).await,
+            Err(VectorDbError::CollectionNotFound(_))
+        ));
+
+        // Deleting the collection clears its FTS5 rows
+        restarted.delete_collection("kb").await.unwrap();
+        assert_eq!(sql_service.fts_chunk_count("kb").await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_and_optimize_bm25_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let sql_service = Arc::new(SqlService::new(crate::services::sql::SqlConfig::test_config(temp_dir.path())).await.unwrap());
+        sql_service.run_migrations().await.unwrap();
+        let data_dir = temp_dir.path().join("vectors");
+
+        let chunk = |id: &str, content: &str| VectorDocument {
+            chunk_id: id.to_string(),
+            document_id: "doc-1".to_string(),
+            kb_id: "kb".to_string(),
+            content: content.to_string(),
+            embedding: vec![0.1, 0.2],
+            metadata: serde_json::json!({}),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let vector_service = VectorDbService::new(VectorDbConfig::test_config(&data_dir)).await.unwrap()
+            .with_sql_service(sql_service.clone());
+        vector_service.import_chunks("kb", vec![
+            chunk("c1", "Segment merging"),
+            chunk("c2", "Stale copy"),
+            chunk("c3", "   "),
+            chunk("c2", "Compaction of old segments"),
+        ]).await.unwrap();
+
+        let mut stages = Vec::new();
+        let report = vector_service.optimize_index("kb", &mut |p| stages.push((p.stage, p.processed))).await.unwrap();
+        assert_eq!((report.chunks, report.removed), (2, 2));
+        assert!(report.size_after < report.size_before);
+        assert!(report.fts_updated && !report.lancedb_compacted);
+        assert_eq!(stages, vec![("bm25", 0), ("bm25", 4), ("fts5", 0), ("fts5", 2)]);
+        let contents: Vec<String> = vector_service.export_chunks("kb").await.unwrap().into_iter().map(|c| c.content).collect();
+        assert_eq!(contents, vec!["Segment merging", "Compaction of old segments"]);
+
+        // A fresh service rebuilds from the file on disk and restores the FTS5 rows
+        sql_service.fts_clear("kb").await.unwrap();
+        let restarted = VectorDbService::new(VectorDbConfig::test_config(&data_dir)).await.unwrap()
+            .with_sql_service(sql_service.clone());
+        let report = restarted.rebuild_bm25_index("kb", &mut |_| {}).await.unwrap();
+        assert_eq!((report.chunks, report.removed), (2, 0));
+        assert_eq!(sql_service.fts_chunk_count("kb").await.unwrap(), 2);
+        assert_eq!(restarted.bm25_search("kb", "compaction", 5, None).await.unwrap()[0].chunk_id, "c2");
+        assert!(restarted.lexical_health().await.rebuilding.is_empty());
+        assert!(!data_dir.join("kb_bm25.rebuild").exists() && !data_dir.join("kb_bm25.old").exists());
+
+        // A malformed index file fails the rebuild instead of emptying the index
+        tokio::fs::write(data_dir.join("kb_bm25").join("documents.json"), "{not json").await.unwrap();
+        let broken = VectorDbService::new(VectorDbConfig::test_config(&data_dir)).await.unwrap();
+        assert!(broken.rebuild_bm25_index("kb", &mut |_| {}).await.is_err());
+        assert!(matches!(
+            broken.rebuild_bm25_index("missing", &mut |_| {}).await,
+            Err(VectorDbError::CollectionNotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_sealed_bm25_index() {
+        let temp_dir = TempDir::new().unwrap();
+        let cipher = Arc::new(ContentCipher::from_key(&[7u8; 32]).unwrap());
+        let chunk = VectorDocument {
+            chunk_id: "c1".to_string(),
+            document_id: "doc-1".to_string(),
+            kb_id: "kb".to_string(),
+            content: "Confidential rollout plan".to_string(),
+            embedding: vec![0.1, 0.2],
+            metadata: serde_json::json!({}),
+            created_at: 0,
+            updated_at: 0,
+        };
+        let sealed = VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap()
+            .with_content_cipher(cipher.clone());
+        sealed.import_chunks("kb", vec![chunk]).await.unwrap();
+
+        let file = tokio::fs::read_to_string(temp_dir.path().join("kb_bm25").join("documents.json")).await.unwrap();
+        assert!(!file.contains("rollout"));
+
+        // Reopened with the key, the index searches the plain text
+        let reopened = VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap()
+            .with_content_cipher(cipher);
+        assert_eq!(reopened.export_chunks("kb").await.unwrap()[0].content, "Confidential rollout plan");
+        assert_eq!(reopened.bm25_search("kb", "rollout", 5, None).await.unwrap().len(), 1);
+
+        let keyless = VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap();
+        assert!(matches!(keyless.export_chunks("kb").await, Err(VectorDbError::EncryptionError(_))));
+    }
+
+    #[tokio::test]
+    async fn test_collection_stats_from_stores() {
+        let temp_dir = TempDir::new().unwrap();
+        let vector_service = VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap();
+        let chunk = |id: &str, doc: &str, embedding: Vec<f32>| VectorSchema {
+            chunk_id: id.to_string(),
+            document_id: doc.to_string(),
+            kb_id: "kb".to_string(),
+            content: format!("content of {}", id),
+            embedding,
+            metadata: serde_json::json!({}),
+            created_at: 0,
+            updated_at: 0,
+        };
+        vector_service.upsert_vectors("kb", vec![
+            chunk("c1", "doc-1", vec![0.1, 0.2, 0.3]),
+            chunk("c2", "doc-1", vec![0.4, 0.5, 0.6]),
+            chunk("c3", "doc-2", Vec::new()),
+        ]).await.unwrap();
+
+        let stats = vector_service.get_collection_stats("kb").await.unwrap();
+        assert_eq!((stats.chunk_count, stats.vector_count, stats.document_count), (3, 2, 2));
+        assert_eq!(stats.dimension, Some(3));
+        let file_size = std::fs::metadata(temp_dir.path().join("kb_bm25").join("documents.json")).unwrap().len();
+        assert_eq!(stats.size_bytes, file_size);
+        assert!(stats.last_updated.is_some());
+
+        // Writes drop the cached numbers
+        vector_service.delete_document("kb", "doc-1").await.unwrap();
+        let stats = vector_service.get_collection_stats("kb").await.unwrap();
+        assert_eq!((stats.chunk_count, stats.vector_count, stats.dimension), (1, 0, None));
+
+        // Generations are listed with their own collection's numbers
+        let generation = vector_service.create_generation("kb").await.unwrap();
+        vector_service.upsert_vectors(&format!("kb_{}", generation), vec![chunk("c4", "doc-3", vec![0.7, 0.8, 0.9])]).await.unwrap();
+        let stats = vector_service.get_collection_stats("kb").await.unwrap();
+        assert_eq!(stats.generations.len(), 1);
+        assert_eq!((stats.generations[0].id, stats.generations[0].vector_count), (generation, 1));
+        assert_eq!(stats.generations[0].status, GenerationStatus::Building);
+        assert!(stats.generations[0].size_bytes > 0);
+
+        assert!(matches!(
+            vector_service.get_collection_stats("missing").await,
+            Err(VectorDbError::CollectionNotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_config_variant_differences() {
         let temp_dir = TempDir::new().unwrap();
@@ -8,6 +8,11 @@ use std::collections::HashMap;
 use serde::{Deserialize, Serialize};
 use chrono::{DateTime, Utc};
 
+use crate::models::prompt::PromptTemplate;
+use crate::models::tool_template::ToolTemplate;
+use crate::modules::pipeline::PipelineSpec;
+use crate::modules::schedule::RefreshOutcome;
+
 /// Global Application State (MVP)
 /// Shared state with Arc<RwLock<AppState>> pattern
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -41,6 +46,22 @@ pub struct AppState {
 
     /// Error States (FR-11)
     pub errors: HashMap<String, ErrorState>,
+
+    /// Workspace profiles with pinned KB versions
+    #[serde(default)]
+    pub workspaces: HashMap<String, WorkspaceState>,
+
+    /// Workspace used when a query doesn't name one
+    #[serde(default)]
+    pub active_workspace_id: Option<String>,
+
+    /// Prompt templates served over MCP
+    #[serde(default)]
+    pub prompts: HashMap<String, PromptTemplate>,
+
+    /// User-defined tool templates (built-ins are not stored)
+    #[serde(default)]
+    pub tool_templates: HashMap<String, ToolTemplate>,
 }
 
 impl Default for AppState {
@@ -56,10 +77,25 @@ impl Default for AppState {
             settings: HashMap::new(),
             loading_states: HashMap::new(),
             errors: HashMap::new(),
+            workspaces: HashMap::new(),
+            active_workspace_id: None,
+            prompts: HashMap::new(),
+            tool_templates: HashMap::new(),
         }
     }
 }
 
+impl AppState {
+    /// Version pinned for a KB in the given workspace (or the active one)
+    pub fn pinned_version(&self, workspace_id: Option<&str>, kb_id: &str) -> Option<i32> {
+        let workspace_id = workspace_id.or(self.active_workspace_id.as_deref())?;
+        self.workspaces
+            .get(workspace_id)
+            .and_then(|ws| ws.pinned_versions.get(kb_id))
+            .copied()
+    }
+}
+
 /// Knowledge Base State
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KnowledgeBaseState {
@@ -83,6 +119,16 @@ pub enum KnowledgeBaseStatus {
     Error(String),
 }
 
+/// Workspace profile pinning KB versions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkspaceState {
+    pub id: String,
+    pub name: String,
+    pub pinned_versions: HashMap<String, i32>,  // kb_id -> version
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
 /// Pipeline Run State
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PipelineRunState {
@@ -117,6 +163,30 @@ pub struct ToolState {
     pub usage_count: u64,
     pub config: serde_json::Value,
     pub schema: serde_json::Value,
+    #[serde(default)]
+    pub permissions: Vec<String>,       // Scopes the tool needs, e.g. "kb.read"
+    #[serde(default)]
+    pub usage: ToolUsage,               // Folded in from the MCP audit log
+}
+
+/// Invocation figures for a tool over the recent usage window
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ToolUsage {
+    pub calls: u64,
+    pub errors: u64,
+    pub error_rate: f64,
+    pub p50_latency_ms: u64,
+    pub p95_latency_ms: u64,
+    pub p99_latency_ms: u64,
+    pub last_used: Option<DateTime<Utc>>,
+    pub top_queries: Vec<QueryCount>,   // Most frequent first, PII masked
+}
+
+/// How often a query was sent to a tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueryCount {
+    pub query: String,
+    pub count: u64,
 }
 
 /// Schedule State
@@ -130,6 +200,16 @@ pub struct ScheduleState {
     pub last_run: Option<DateTime<Utc>>,
     pub next_run: Option<DateTime<Utc>>,
     pub run_count: u64,
+    #[serde(default)]
+    pub kb_id: Option<String>,          // KB the refresh builds new versions of
+    #[serde(default)]
+    pub tool_id: Option<String>,        // Tool the schedule was declared on
+    #[serde(default)]
+    pub pipeline: Option<PipelineSpec>, // Stored pipeline replayed on each run
+    #[serde(default)]
+    pub sources: Vec<String>,
+    #[serde(default)]
+    pub last_outcome: Option<RefreshOutcome>,
 }
 
 /// Flow State
@@ -200,6 +280,51 @@ pub enum StateDelta {
         id: String,
     },
 
+    // Workspace mutations
+    WorkspaceAdd {
+        workspace: WorkspaceState,
+    },
+    WorkspaceRemove {
+        id: String,
+    },
+    WorkspaceActivate {
+        id: Option<String>,
+    },
+    WorkspacePin {
+        workspace_id: String,
+        kb_id: String,
+        version: Option<i32>,   // None removes the pin
+    },
+
+    // Prompt mutations
+    PromptUpsert {
+        prompt: PromptTemplate,
+    },
+    PromptRemove {
+        id: String,
+    },
+
+    // Tool template mutations
+    ToolTemplateUpsert {
+        template: ToolTemplate,
+    },
+    ToolTemplateRemove {
+        id: String,
+    },
+
+    // Schedule mutations
+    ScheduleUpsert {
+        schedule: Box<ScheduleState>,   // Boxed: the stored pipeline makes it the largest delta
+    },
+    ScheduleRemove {
+        id: String,
+    },
+    ScheduleRecordRun {
+        id: String,
+        outcome: RefreshOutcome,
+        next_run: Option<DateTime<Utc>>,
+    },
+
     // Pipeline Run mutations
     RunAdd {
         run: PipelineRunState,
@@ -227,6 +352,27 @@ pub enum StateDelta {
         id: String,
         enabled: bool,
     },
+    ToolRemove {
+        id: String,
+    },
+    ToolUsageSet {
+        id: String,
+        usage: ToolUsage,
+    },
+
+    // Flow mutations
+    FlowUpsert {
+        flow: FlowState,
+    },
+    FlowRemove {
+        id: String,
+    },
+
+    // Settings mutations
+    SettingSet {
+        key: String,
+        value: Option<String>,  // None removes the setting
+    },
 
     // Metrics mutations
     MetricsUpdate {
@@ -255,4 +401,116 @@ pub enum StateDelta {
         key: String,
         loading: bool,
     },
-}
\ No newline at end of file
+}
+
+impl StateDelta {
+    /// Whether the mutation changes state that is saved across restarts
+    /// (logs, metrics, errors, loading flags and pipeline runs are session-only)
+    pub fn is_persistent(&self) -> bool {
+        !matches!(
+            self,
+            StateDelta::RunAdd { .. }
+                | StateDelta::RunUpdate { .. }
+                | StateDelta::RunRemove { .. }
+                | StateDelta::MetricsUpdate { .. }
+                | StateDelta::LogAdd { .. }
+                | StateDelta::LogsPrune { .. }
+                | StateDelta::ErrorAdd { .. }
+                | StateDelta::ErrorResolve { .. }
+                | StateDelta::LoadingSet { .. }
+                | StateDelta::ToolUsageSet { .. }
+        )
+    }
+
+    /// Deltas undoing this one when applied to `state` as it was before it.
+    /// None for no-ops, session-only deltas, and KBs and flows (which mirror their SQL tables).
+    pub fn inverse(&self, state: &AppState) -> Option<Vec<StateDelta>> {
+        let inverse = match self {
+            StateDelta::WorkspaceAdd { workspace } => match state.workspaces.get(&workspace.id) {
+                Some(old) => vec![StateDelta::WorkspaceAdd { workspace: old.clone() }],
+                None => vec![StateDelta::WorkspaceRemove { id: workspace.id.clone() }],
+            },
+            StateDelta::WorkspaceRemove { id } => {
+                let old = state.workspaces.get(id)?;
+                let mut inverse = vec![StateDelta::WorkspaceAdd { workspace: old.clone() }];
+                if state.active_workspace_id.as_deref() == Some(id.as_str()) {
+                    inverse.push(StateDelta::WorkspaceActivate { id: Some(id.clone()) });
+                }
+                inverse
+            }
+            StateDelta::WorkspaceActivate { .. } => {
+                vec![StateDelta::WorkspaceActivate { id: state.active_workspace_id.clone() }]
+            }
+            StateDelta::WorkspacePin { workspace_id, kb_id, .. } => {
+                let workspace = state.workspaces.get(workspace_id)?;
+                vec![StateDelta::WorkspacePin {
+                    workspace_id: workspace_id.clone(),
+                    kb_id: kb_id.clone(),
+                    version: workspace.pinned_versions.get(kb_id).copied(),
+                }]
+            }
+
+            StateDelta::PromptUpsert { prompt } => match state.prompts.get(&prompt.id) {
+                Some(old) => vec![StateDelta::PromptUpsert { prompt: old.clone() }],
+                None => vec![StateDelta::PromptRemove { id: prompt.id.clone() }],
+            },
+            StateDelta::PromptRemove { id } => {
+                vec![StateDelta::PromptUpsert { prompt: state.prompts.get(id)?.clone() }]
+            }
+
+            StateDelta::ToolTemplateUpsert { template } => match state.tool_templates.get(&template.id) {
+                Some(old) => vec![StateDelta::ToolTemplateUpsert { template: old.clone() }],
+                None => vec![StateDelta::ToolTemplateRemove { id: template.id.clone() }],
+            },
+            StateDelta::ToolTemplateRemove { id } => {
+                vec![StateDelta::ToolTemplateUpsert { template: state.tool_templates.get(id)?.clone() }]
+            }
+
+            StateDelta::ScheduleUpsert { schedule } => match state.schedules.get(&schedule.id) {
+                Some(old) => vec![StateDelta::ScheduleUpsert { schedule: Box::new(old.clone()) }],
+                None => vec![StateDelta::ScheduleRemove { id: schedule.id.clone() }],
+            },
+            StateDelta::ScheduleRemove { id } => {
+                vec![StateDelta::ScheduleUpsert { schedule: Box::new(state.schedules.get(id)?.clone()) }]
+            }
+
+            StateDelta::ToolAdd { tool } => match state.tools.get(&tool.id) {
+                Some(old) => vec![StateDelta::ToolAdd { tool: old.clone() }],
+                None => vec![StateDelta::ToolRemove { id: tool.id.clone() }],
+            },
+            StateDelta::ToolUpdate { id, .. } => {
+                let old = state.tools.get(id)?;
+                vec![StateDelta::ToolUpdate { id: id.clone(), updates: serde_json::to_value(old).ok()? }]
+            }
+            StateDelta::ToolToggle { id, .. } => {
+                vec![StateDelta::ToolToggle { id: id.clone(), enabled: state.tools.get(id)?.enabled }]
+            }
+            StateDelta::ToolRemove { id } => {
+                vec![StateDelta::ToolAdd { tool: state.tools.get(id)?.clone() }]
+            }
+
+            StateDelta::SettingSet { key, .. } => {
+                vec![StateDelta::SettingSet { key: key.clone(), value: state.settings.get(key).cloned() }]
+            }
+
+            _ => return None,
+        };
+        Some(inverse)
+    }
+}
+
+/// A state mutation as streamed to the frontend
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SequencedDelta {
+    pub seq: u64,                       // Increases by one per applied delta
+    pub timestamp: DateTime<Utc>,
+    pub delta: StateDelta,
+}
+
+/// Deltas after a sequence number, for catching up after a reconnect
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeltasSince {
+    pub deltas: Vec<SequencedDelta>,
+    pub latest_seq: u64,
+    pub resync: bool,                   // Some deltas are no longer retained; reload full state
+}
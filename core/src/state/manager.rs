@@ -3,32 +3,89 @@
  *
  * Thread-safe state management using Arc<RwLock<AppState>> pattern for MVP.
  * Clear upgrade path to actor-based StateManager post-MVP.
+ *
+ * Every persistent mutation bumps a revision that `subscribe_changes`
+ * watchers (the SQL state persister) react to.
+ *
+ * Undoable mutations are recorded with their inverse deltas in a bounded
+ * history, so `undo`/`redo` can step back and forth through user changes.
+ *
+ * Every applied delta (including those applied by undo/redo) gets the next
+ * sequence number, is kept in a bounded journal and is broadcast to
+ * `subscribe_deltas` receivers. A client that sees a gap in sequence numbers
+ * catches up with `deltas_since`, or reloads the full state when the journal
+ * no longer reaches back far enough.
  */
 
-use std::sync::{Arc, RwLock};
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex, RwLock};
+use tokio::sync::{broadcast, watch};
 use super::app_state::*;
+use super::persistence::PersistedState;
+
+/// Changes kept for undo
+pub const DEFAULT_HISTORY_LIMIT: usize = 100;
+
+/// Sequenced deltas kept for `deltas_since`
+pub const DEFAULT_JOURNAL_LIMIT: usize = 1000;
+
+/// Buffered deltas per stream subscriber before it lags
+const DELTA_CHANNEL_CAPACITY: usize = 256;
+
+/// A recorded mutation and the deltas reverting it
+#[derive(Debug, Clone)]
+struct HistoryEntry {
+    delta: StateDelta,
+    inverse: Vec<StateDelta>,
+}
+
+#[derive(Debug, Default)]
+struct Journal {
+    deltas: VecDeque<SequencedDelta>,   // Newest last
+    last_seq: u64,
+}
+
+#[derive(Debug, Default)]
+struct History {
+    undo: VecDeque<HistoryEntry>,   // Oldest first
+    redo: Vec<HistoryEntry>,        // Most recently undone last
+}
 
 /// Shared Application State Manager (MVP)
 /// Thread-safe shared state with simple mutation API
 pub struct StateManager {
     state: Arc<RwLock<AppState>>,
+    revision: watch::Sender<u64>,   // Count of persistent mutations
+    history: Mutex<History>,        // Locked after `state`
+    history_limit: usize,
+    journal: Mutex<Journal>,        // Locked after `state`
+    deltas: broadcast::Sender<SequencedDelta>,
 }
 
 impl StateManager {
     /// Create new state manager with default state
     pub fn new() -> Self {
-        Self {
-            state: Arc::new(RwLock::new(AppState::default())),
-        }
+        Self::with_state(AppState::default())
     }
 
     /// Create state manager with initial state
     pub fn with_state(state: AppState) -> Self {
         Self {
             state: Arc::new(RwLock::new(state)),
+            revision: watch::channel(0).0,
+            history: Mutex::new(History::default()),
+            history_limit: DEFAULT_HISTORY_LIMIT,
+            journal: Mutex::new(Journal::default()),
+            deltas: broadcast::channel(DELTA_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Number of changes kept for undo (0 disables history)
+    pub fn with_history_limit(mut self, limit: usize) -> Self {
+        self.history_limit = limit;
+        self
+    }
+
     /// Get a read lock on the state
     pub fn read_state(&self) -> std::sync::RwLockReadGuard<'_, AppState> {
         self.state.read().unwrap()
@@ -36,8 +93,128 @@ impl StateManager {
 
     /// Apply a state mutation
     pub fn mutate(&self, delta: StateDelta) -> Result<(), String> {
+        let persistent = delta.is_persistent();
+        let mut state = self.state.write().unwrap();
+        let inverse = delta.inverse(&state).filter(|_| self.history_limit > 0);
+        let recorded = inverse.is_some().then(|| delta.clone());
+        self.apply_delta(&mut state, delta.clone())?;
+        self.publish(delta);
+
+        if let (Some(delta), Some(inverse)) = (recorded, inverse) {
+            let mut history = self.history.lock().unwrap();
+            history.redo.clear();
+            history.undo.push_back(HistoryEntry { delta, inverse });
+            while history.undo.len() > self.history_limit {
+                history.undo.pop_front();
+            }
+        }
+        drop(state);
+
+        if persistent {
+            self.revision.send_modify(|revision| *revision += 1);
+        }
+        Ok(())
+    }
+
+    /// Revert the most recent undoable change; returns it, or None if there is nothing to undo
+    pub fn undo(&self) -> Result<Option<StateDelta>, String> {
+        let mut state = self.state.write().unwrap();
+        let mut history = self.history.lock().unwrap();
+        let Some(entry) = history.undo.pop_back() else {
+            return Ok(None);
+        };
+
+        // Inverses are computed against the state they revert to, so they apply cleanly
+        for delta in entry.inverse.iter().cloned() {
+            if let Err(e) = self.apply_delta(&mut state, delta.clone()) {
+                history.undo.push_back(entry);
+                return Err(e);
+            }
+            self.publish(delta);
+        }
+        let delta = entry.delta.clone();
+        history.redo.push(entry);
+        drop(history);
+        drop(state);
+
+        self.revision.send_modify(|revision| *revision += 1);
+        Ok(Some(delta))
+    }
+
+    /// Re-apply the most recently undone change; returns it, or None if there is nothing to redo
+    pub fn redo(&self) -> Result<Option<StateDelta>, String> {
         let mut state = self.state.write().unwrap();
-        self.apply_delta(&mut state, delta)
+        let mut history = self.history.lock().unwrap();
+        let Some(entry) = history.redo.pop() else {
+            return Ok(None);
+        };
+
+        if let Err(e) = self.apply_delta(&mut state, entry.delta.clone()) {
+            history.redo.push(entry);
+            return Err(e);
+        }
+        let delta = entry.delta.clone();
+        self.publish(delta.clone());
+        history.undo.push_back(entry);
+        drop(history);
+        drop(state);
+
+        self.revision.send_modify(|revision| *revision += 1);
+        Ok(Some(delta))
+    }
+
+    /// Sequence number of the last applied delta (0 before any)
+    pub fn last_seq(&self) -> u64 {
+        self.journal.lock().unwrap().last_seq
+    }
+
+    /// Live stream of applied deltas, in sequence order
+    pub fn subscribe_deltas(&self) -> broadcast::Receiver<SequencedDelta> {
+        self.deltas.subscribe()
+    }
+
+    /// Deltas with a sequence number above `seq`, oldest first. Resync is
+    /// requested when some were dropped from the journal, or when `seq` is
+    /// ahead of this session (the client outlived an app restart).
+    pub fn deltas_since(&self, seq: u64) -> DeltasSince {
+        let journal = self.journal.lock().unwrap();
+        DeltasSince {
+            deltas: journal.deltas.iter().filter(|d| d.seq > seq).cloned().collect(),
+            latest_seq: journal.last_seq,
+            resync: seq > journal.last_seq || journal.deltas.front().is_some_and(|d| d.seq > seq + 1),
+        }
+    }
+
+    /// Number a delta just applied under the state write lock, journal and broadcast it
+    fn publish(&self, delta: StateDelta) {
+        let mut journal = self.journal.lock().unwrap();
+        journal.last_seq += 1;
+        let sequenced = SequencedDelta { seq: journal.last_seq, timestamp: chrono::Utc::now(), delta };
+
+        journal.deltas.push_back(sequenced.clone());
+        while journal.deltas.len() > DEFAULT_JOURNAL_LIMIT {
+            journal.deltas.pop_front();
+        }
+        // No receivers is fine: the journal still serves catch-up requests
+        let _ = self.deltas.send(sequenced);
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.history.lock().unwrap().undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.history.lock().unwrap().redo.is_empty()
+    }
+
+    /// Number of persistent mutations so far
+    pub fn revision(&self) -> u64 {
+        *self.revision.borrow()
+    }
+
+    /// Notified after each persistent mutation (bursts coalesce to the latest revision)
+    pub fn subscribe_changes(&self) -> watch::Receiver<u64> {
+        self.revision.subscribe()
     }
 
     /// Get clone of current state (for serialization/persistence)
@@ -49,6 +226,14 @@ impl StateManager {
     pub fn load_state(&self, new_state: AppState) {
         let mut state = self.state.write().unwrap();
         *state = new_state;
+        *self.history.lock().unwrap() = History::default();
+    }
+
+    /// Rehydrate saved state; does not count as a mutation
+    pub fn restore(&self, saved: PersistedState) {
+        let mut state = self.state.write().unwrap();
+        saved.apply_to(&mut state);
+        *self.history.lock().unwrap() = History::default();
     }
 
     /// Apply delta mutation to state
@@ -69,6 +254,33 @@ impl StateManager {
                 state.knowledge_bases.remove(&id);
             }
 
+            StateDelta::WorkspaceAdd { workspace } => {
+                state.workspaces.insert(workspace.id.clone(), workspace);
+            }
+            StateDelta::WorkspaceRemove { id } => {
+                state.workspaces.remove(&id);
+                if state.active_workspace_id.as_deref() == Some(id.as_str()) {
+                    state.active_workspace_id = None;
+                }
+            }
+            StateDelta::WorkspaceActivate { id } => {
+                if let Some(id) = &id {
+                    if !state.workspaces.contains_key(id) {
+                        return Err(format!("Workspace not found: {}", id));
+                    }
+                }
+                state.active_workspace_id = id;
+            }
+            StateDelta::WorkspacePin { workspace_id, kb_id, version } => {
+                let workspace = state.workspaces.get_mut(&workspace_id)
+                    .ok_or_else(|| format!("Workspace not found: {}", workspace_id))?;
+                match version {
+                    Some(version) => workspace.pinned_versions.insert(kb_id, version),
+                    None => workspace.pinned_versions.remove(&kb_id),
+                };
+                workspace.updated_at = chrono::Utc::now();
+            }
+
             StateDelta::RunAdd { run } => {
                 state.pipeline_runs.insert(run.id.clone(), run);
             }
@@ -90,6 +302,42 @@ impl StateManager {
                 state.pipeline_runs.remove(&id);
             }
 
+            StateDelta::PromptUpsert { prompt } => {
+                // Names are the MCP-facing key, keep them unique
+                if state.prompts.values().any(|p| p.name == prompt.name && p.id != prompt.id) {
+                    return Err(format!("Prompt name already in use: {}", prompt.name));
+                }
+                state.prompts.insert(prompt.id.clone(), prompt);
+            }
+            StateDelta::PromptRemove { id } => {
+                state.prompts.remove(&id);
+            }
+
+            StateDelta::ToolTemplateUpsert { template } => {
+                if state.tool_templates.values().any(|t| t.name == template.name && t.id != template.id) {
+                    return Err(format!("Template name already in use: {}", template.name));
+                }
+                state.tool_templates.insert(template.id.clone(), template);
+            }
+            StateDelta::ToolTemplateRemove { id } => {
+                state.tool_templates.remove(&id);
+            }
+
+            StateDelta::ScheduleUpsert { schedule } => {
+                state.schedules.insert(schedule.id.clone(), *schedule);
+            }
+            StateDelta::ScheduleRemove { id } => {
+                state.schedules.remove(&id);
+            }
+            StateDelta::ScheduleRecordRun { id, outcome, next_run } => {
+                if let Some(schedule) = state.schedules.get_mut(&id) {
+                    schedule.last_run = Some(outcome.completed_at);
+                    schedule.run_count += 1;
+                    schedule.next_run = next_run;
+                    schedule.last_outcome = Some(outcome);
+                }
+            }
+
             StateDelta::ToolAdd { tool } => {
                 state.tools.insert(tool.id.clone(), tool);
             }
@@ -105,6 +353,30 @@ impl StateManager {
                     tool.enabled = enabled;
                 }
             }
+            StateDelta::ToolRemove { id } => {
+                state.tools.remove(&id);
+            }
+            StateDelta::ToolUsageSet { id, usage } => {
+                if let Some(tool) = state.tools.get_mut(&id) {
+                    tool.usage_count = usage.calls;
+                    tool.last_used = usage.last_used;
+                    tool.usage = usage;
+                }
+            }
+
+            StateDelta::FlowUpsert { flow } => {
+                state.flows.insert(flow.id.clone(), flow);
+            }
+            StateDelta::FlowRemove { id } => {
+                state.flows.remove(&id);
+            }
+
+            StateDelta::SettingSet { key, value } => {
+                match value {
+                    Some(value) => state.settings.insert(key, value),
+                    None => state.settings.remove(&key),
+                };
+            }
 
             StateDelta::MetricsUpdate { key, value } => {
                 state.metrics.insert(key, value);
@@ -223,6 +495,35 @@ mod tests {
         assert_eq!(state.pipeline_runs["test-run"].progress, 0.5);
     }
 
+    #[test]
+    fn test_workspace_pin_resolution() {
+        let manager = StateManager::new();
+
+        manager.mutate(StateDelta::WorkspaceAdd {
+            workspace: WorkspaceState {
+                id: "ws-1".to_string(),
+                name: "Release 1.x".to_string(),
+                pinned_versions: std::collections::HashMap::new(),
+                created_at: Utc::now(),
+                updated_at: Utc::now(),
+            },
+        }).unwrap();
+        manager.mutate(StateDelta::WorkspacePin {
+            workspace_id: "ws-1".to_string(),
+            kb_id: "test-kb".to_string(),
+            version: Some(3),
+        }).unwrap();
+
+        // Pins only apply once the workspace is named or active
+        assert_eq!(manager.read_state().pinned_version(None, "test-kb"), None);
+        assert_eq!(manager.read_state().pinned_version(Some("ws-1"), "test-kb"), Some(3));
+
+        manager.mutate(StateDelta::WorkspaceActivate { id: Some("ws-1".to_string()) }).unwrap();
+        assert_eq!(manager.read_state().pinned_version(None, "test-kb"), Some(3));
+
+        assert!(manager.mutate(StateDelta::WorkspaceActivate { id: Some("missing".to_string()) }).is_err());
+    }
+
     #[test]
     fn test_log_buffer_pruning() {
         let manager = StateManager::new();
@@ -250,4 +551,98 @@ mod tests {
         assert_eq!(state.recent_logs[0].id, "log-5");
         assert_eq!(state.recent_logs[99].id, "log-104");
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_prompt_names_unique() {
+        use crate::models::prompt::PromptTemplate;
+
+        let manager = StateManager::new();
+        let prompt = PromptTemplate::new("summarize", "Summary", "Summarize {{doc}}", vec![]);
+        manager.mutate(StateDelta::PromptUpsert { prompt: prompt.clone() }).unwrap();
+
+        // Updating the same prompt keeps its name
+        manager.mutate(StateDelta::PromptUpsert { prompt: prompt.clone() }).unwrap();
+
+        let duplicate = PromptTemplate::new("summarize", "Other", "Other {{doc}}", vec![]);
+        assert!(manager.mutate(StateDelta::PromptUpsert { prompt: duplicate }).is_err());
+
+        manager.mutate(StateDelta::PromptRemove { id: prompt.id }).unwrap();
+        assert!(manager.read_state().prompts.is_empty());
+    }
+
+    #[test]
+    fn test_undo_redo() {
+        let manager = StateManager::new().with_history_limit(2);
+        let tool = ToolState {
+            id: "tool-1".to_string(),
+            name: "Docs search".to_string(),
+            tool_type: "kb_search".to_string(),
+            enabled: true,
+            last_used: None,
+            usage_count: 0,
+            config: serde_json::json!({}),
+            schema: serde_json::json!({}),
+            permissions: vec![],
+            usage: Default::default(),
+        };
+        manager.mutate(StateDelta::ToolAdd { tool }).unwrap();
+        manager.mutate(StateDelta::ToolToggle { id: "tool-1".to_string(), enabled: false }).unwrap();
+        manager.mutate(StateDelta::ToolRemove { id: "tool-1".to_string() }).unwrap();
+        // Session-only changes aren't recorded
+        manager.mutate(StateDelta::LoadingSet { key: "tools".to_string(), loading: true }).unwrap();
+
+        assert!(matches!(manager.undo().unwrap(), Some(StateDelta::ToolRemove { .. })));
+        assert!(!manager.read_state().tools["tool-1"].enabled);
+        assert!(matches!(manager.undo().unwrap(), Some(StateDelta::ToolToggle { .. })));
+        assert!(manager.read_state().tools["tool-1"].enabled);
+        assert!(manager.read_state().loading_states.contains_key("tools"));
+
+        assert!(matches!(manager.redo().unwrap(), Some(StateDelta::ToolToggle { .. })));
+        assert!(!manager.read_state().tools["tool-1"].enabled);
+        assert!(manager.can_redo());
+
+        // A new change drops the redo branch
+        manager.mutate(StateDelta::SettingSet { key: "theme".to_string(), value: Some("dark".to_string()) }).unwrap();
+        assert!(!manager.can_redo());
+        assert!(manager.redo().unwrap().is_none());
+        manager.undo().unwrap();
+        assert!(!manager.read_state().settings.contains_key("theme"));
+
+        // History is bounded: the ToolAdd fell off
+        manager.undo().unwrap();
+        assert!(manager.undo().unwrap().is_none());
+        assert!(manager.read_state().tools["tool-1"].enabled);
+        assert_eq!(manager.revision(), 9);
+    }
+
+    #[test]
+    fn test_delta_journal() {
+        let manager = StateManager::new();
+        let mut stream = manager.subscribe_deltas();
+        assert_eq!(manager.last_seq(), 0);
+
+        // Failed mutations don't consume a sequence number
+        assert!(manager.mutate(StateDelta::WorkspaceActivate { id: Some("missing".to_string()) }).is_err());
+        manager.mutate(StateDelta::LoadingSet { key: "key-0".to_string(), loading: true }).unwrap();
+        let first = stream.try_recv().unwrap();
+        assert_eq!(first.seq, 1);
+        assert!(matches!(first.delta, StateDelta::LoadingSet { .. }));
+
+        for i in 1..DEFAULT_JOURNAL_LIMIT + 5 {
+            manager.mutate(StateDelta::LoadingSet { key: format!("key-{}", i), loading: true }).unwrap();
+        }
+        let latest = (DEFAULT_JOURNAL_LIMIT + 5) as u64;
+        assert_eq!(manager.last_seq(), latest);
+
+        let recent = manager.deltas_since(latest - 2);
+        assert!(!recent.resync);
+        assert_eq!(recent.deltas.iter().map(|d| d.seq).collect::<Vec<_>>(), vec![latest - 1, latest]);
+
+        // The oldest deltas were dropped from the journal
+        assert!(manager.deltas_since(1).resync);
+        assert!(!manager.deltas_since(5).resync);
+        // A client from a previous session is ahead of this one
+        assert!(manager.deltas_since(latest + 10).resync);
+        assert!(manager.deltas_since(latest).deltas.is_empty());
+    }
+}
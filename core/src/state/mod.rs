@@ -6,7 +6,9 @@
 
 pub mod app_state;
 pub mod manager;
+pub mod persistence;
 
 // Re-export public types
 pub use app_state::*;
-pub use manager::StateManager;
\ No newline at end of file
+pub use manager::StateManager;
+pub use persistence::{PersistedState, StateSnapshot, StateStore};
\ No newline at end of file
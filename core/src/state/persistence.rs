@@ -0,0 +1,309 @@
+/*!
+ * State Persistence
+ *
+ * Saves the persistent part of `AppState` (tools, settings, workspaces with
+ * their pinned versions, prompts, tool templates, flows, schedules) to
+ * `state_snapshots` as versioned JSON snapshots. A persister task writes a
+ * snapshot after each persistent mutation; the Manager rehydrates the
+ * newest one on startup.
+ * The KB registry is not part of the snapshot: it is rebuilt from the
+ * knowledge_bases table, which also sees KBs created headlessly.
+ */
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use super::app_state::*;
+use super::manager::StateManager;
+use crate::models::prompt::PromptTemplate;
+use crate::models::tool_template::ToolTemplate;
+use crate::schemas::schema::state_snapshots;
+use crate::services::sql::{SqlError, SqlService};
+
+/// Snapshots kept; older ones are pruned on save
+pub const DEFAULT_SNAPSHOTS_KEPT: usize = 20;
+
+/// Payload format written by this version
+const SNAPSHOT_FORMAT: i32 = 1;
+
+/// State that survives restarts
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    #[serde(default)]
+    pub tools: HashMap<String, ToolState>,
+    #[serde(default)]
+    pub schedules: HashMap<String, ScheduleState>,
+    #[serde(default)]
+    pub flows: HashMap<String, FlowState>,
+    #[serde(default)]
+    pub settings: HashMap<String, String>,
+    #[serde(default)]
+    pub workspaces: HashMap<String, WorkspaceState>,
+    #[serde(default)]
+    pub active_workspace_id: Option<String>,
+    #[serde(default)]
+    pub prompts: HashMap<String, PromptTemplate>,
+    #[serde(default)]
+    pub tool_templates: HashMap<String, ToolTemplate>,
+}
+
+impl PersistedState {
+    pub fn from_state(state: &AppState) -> Self {
+        Self {
+            tools: state.tools.clone(),
+            schedules: state.schedules.clone(),
+            flows: state.flows.clone(),
+            settings: state.settings.clone(),
+            workspaces: state.workspaces.clone(),
+            active_workspace_id: state.active_workspace_id.clone(),
+            prompts: state.prompts.clone(),
+            tool_templates: state.tool_templates.clone(),
+        }
+    }
+
+    /// Replace the persistent fields of `state`, leaving session-only ones alone
+    pub fn apply_to(self, state: &mut AppState) {
+        state.tools = self.tools;
+        state.schedules = self.schedules;
+        state.flows = self.flows;
+        state.settings = self.settings;
+        state.active_workspace_id = self.active_workspace_id.filter(|id| self.workspaces.contains_key(id));
+        state.workspaces = self.workspaces;
+        state.prompts = self.prompts;
+        state.tool_templates = self.tool_templates;
+    }
+}
+
+/// One saved version of the persistent state
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StateSnapshot {
+    pub version: i64,
+    pub revision: u64,
+    pub created_at: DateTime<Utc>,
+    pub state: PersistedState,
+}
+
+#[derive(Debug, Clone, Queryable, Selectable)]
+#[diesel(table_name = state_snapshots)]
+#[diesel(check_for_backend(diesel::sqlite::Sqlite))]
+struct StateSnapshotRow {
+    version: i64,
+    revision: i64,
+    format: i32,
+    state: String,
+    created_at: NaiveDateTime,
+}
+
+impl StateSnapshotRow {
+    fn into_snapshot(self) -> Result<StateSnapshot, SqlError> {
+        if self.format > SNAPSHOT_FORMAT {
+            return Err(SqlError::ConfigurationError(format!(
+                "State snapshot {} uses format {} (newer than {})", self.version, self.format, SNAPSHOT_FORMAT
+            )));
+        }
+        Ok(StateSnapshot {
+            version: self.version,
+            revision: self.revision.max(0) as u64,
+            created_at: self.created_at.and_utc(),
+            state: serde_json::from_str(&self.state)?,
+        })
+    }
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = state_snapshots)]
+struct NewStateSnapshot {
+    revision: i64,
+    format: i32,
+    state: String,
+    created_at: NaiveDateTime,
+}
+
+/// Versioned state snapshots in the app database
+pub struct StateStore {
+    sql_service: Arc<SqlService>,
+    keep: usize,
+}
+
+impl StateStore {
+    pub fn new(sql_service: Arc<SqlService>) -> Self {
+        Self { sql_service, keep: DEFAULT_SNAPSHOTS_KEPT }
+    }
+
+    /// Number of snapshots kept (at least one)
+    pub fn with_keep(mut self, keep: usize) -> Self {
+        self.keep = keep.max(1);
+        self
+    }
+
+    /// Save a snapshot and prune old ones; returns the new version
+    pub async fn save(&self, revision: u64, state: &PersistedState) -> Result<i64, SqlError> {
+        let row = NewStateSnapshot {
+            revision: revision as i64,
+            format: SNAPSHOT_FORMAT,
+            state: serde_json::to_string(state)?,
+            created_at: Utc::now().naive_utc(),
+        };
+        let keep = self.keep as i64;
+
+        self.sql_service.with_app_transaction(move |conn| {
+            diesel::insert_into(state_snapshots::table).values(&row).execute(conn)?;
+            let version: i64 = state_snapshots::table
+                .select(diesel::dsl::max(state_snapshots::version))
+                .first::<Option<i64>>(conn)?
+                .unwrap_or_default();
+
+            let oldest_kept: Option<i64> = state_snapshots::table
+                .select(state_snapshots::version)
+                .order(state_snapshots::version.desc())
+                .offset(keep - 1)
+                .first(conn)
+                .optional()?;
+            if let Some(oldest_kept) = oldest_kept {
+                diesel::delete(state_snapshots::table.filter(state_snapshots::version.lt(oldest_kept))).execute(conn)?;
+            }
+            Ok(version)
+        }).await
+    }
+
+    /// Newest snapshot, if any
+    pub async fn load_latest(&self) -> Result<Option<StateSnapshot>, SqlError> {
+        let mut conn = self.sql_service.get_app_connection().await?;
+        state_snapshots::table
+            .select(StateSnapshotRow::as_select())
+            .order(state_snapshots::version.desc())
+            .first(&mut conn)
+            .optional()?
+            .map(StateSnapshotRow::into_snapshot)
+            .transpose()
+    }
+
+    /// A specific snapshot version, if still kept
+    pub async fn load_version(&self, version: i64) -> Result<Option<StateSnapshot>, SqlError> {
+        let mut conn = self.sql_service.get_app_connection().await?;
+        state_snapshots::table
+            .select(StateSnapshotRow::as_select())
+            .filter(state_snapshots::version.eq(version))
+            .first(&mut conn)
+            .optional()?
+            .map(StateSnapshotRow::into_snapshot)
+            .transpose()
+    }
+
+    /// Kept versions with their revisions, newest first
+    pub async fn list_versions(&self) -> Result<Vec<(i64, u64, DateTime<Utc>)>, SqlError> {
+        let mut conn = self.sql_service.get_app_connection().await?;
+        let rows: Vec<(i64, i64, NaiveDateTime)> = state_snapshots::table
+            .select((state_snapshots::version, state_snapshots::revision, state_snapshots::created_at))
+            .order(state_snapshots::version.desc())
+            .load(&mut conn)?;
+        Ok(rows.into_iter().map(|(version, revision, created_at)| (version, revision.max(0) as u64, created_at.and_utc())).collect())
+    }
+
+    /// Save a snapshot after every persistent mutation of `state_manager`
+    pub fn spawn_persister(self: &Arc<Self>, state_manager: Arc<StateManager>) -> tokio::task::JoinHandle<()> {
+        let store = self.clone();
+        let mut changes = state_manager.subscribe_changes();
+        tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let revision = *changes.borrow_and_update();
+                let state = PersistedState::from_state(&state_manager.read_state());
+                match store.save(revision, &state).await {
+                    Ok(version) => debug!("Saved state snapshot {} (revision {})", version, revision),
+                    Err(e) => warn!("Failed to save state snapshot at revision {}: {}", revision, e),
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use crate::services::sql::SqlConfig;
+    use tempfile::TempDir;
+
+    fn tool(id: &str) -> ToolState {
+        ToolState {
+            id: id.to_string(),
+            name: format!("Tool {}", id),
+            tool_type: "kb_search".to_string(),
+            enabled: true,
+            last_used: None,
+            usage_count: 0,
+            config: serde_json::json!({"kb_id": "kb_1"}),
+            schema: serde_json::json!({}),
+            permissions: vec![],
+            usage: Default::default(),
+        }
+    }
+
+    async fn store(temp_dir: &TempDir) -> Arc<StateStore> {
+        let sql_service = SqlService::new(SqlConfig::test_config(temp_dir.path())).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        Arc::new(StateStore::new(Arc::new(sql_service)).with_keep(3))
+    }
+
+    #[tokio::test]
+    async fn test_snapshots_versioned_and_pruned() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = store(&temp_dir).await;
+        assert!(store.load_latest().await.unwrap().is_none());
+
+        let mut state = PersistedState::default();
+        let mut versions = Vec::new();
+        for i in 0..5 {
+            state.tools.insert(format!("tool_{}", i), tool(&format!("tool_{}", i)));
+            versions.push(store.save(i + 1, &state).await.unwrap());
+        }
+        assert!(versions.windows(2).all(|w| w[0] < w[1]));
+
+        let latest = store.load_latest().await.unwrap().unwrap();
+        assert_eq!((latest.version, latest.revision, latest.state.tools.len()), (versions[4], 5, 5));
+
+        let kept: Vec<i64> = store.list_versions().await.unwrap().into_iter().map(|(v, _, _)| v).collect();
+        assert_eq!(kept, vec![versions[4], versions[3], versions[2]]);
+        assert!(store.load_version(versions[0]).await.unwrap().is_none());
+        assert_eq!(store.load_version(versions[2]).await.unwrap().unwrap().state.tools.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_persister_saves_mutations_and_restore_rehydrates() {
+        let temp_dir = TempDir::new().unwrap();
+        let store = store(&temp_dir).await;
+        let state_manager = Arc::new(StateManager::new());
+        let persister = store.spawn_persister(state_manager.clone());
+
+        // Session-only mutations don't produce snapshots
+        state_manager.mutate(StateDelta::LoadingSet { key: "kbs".to_string(), loading: true }).unwrap();
+        state_manager.mutate(StateDelta::ToolAdd { tool: tool("tool_1") }).unwrap();
+        state_manager.mutate(StateDelta::SettingSet { key: "theme".to_string(), value: Some("dark".to_string()) }).unwrap();
+        assert_eq!(state_manager.revision(), 2);
+
+        let mut saved = None;
+        for _ in 0..100 {
+            saved = store.load_latest().await.unwrap().filter(|s| s.revision == 2);
+            if saved.is_some() {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        persister.abort();
+        let saved = saved.expect("snapshot for revision 2");
+
+        // A fresh session picks up tools and settings but not loading flags
+        let restored = StateManager::new();
+        restored.restore(saved.state);
+        let state = restored.read_state();
+        assert!(state.tools.contains_key("tool_1"));
+        assert_eq!(state.settings.get("theme").map(String::as_str), Some("dark"));
+        assert!(state.loading_states.is_empty());
+        drop(state);
+        assert_eq!(restored.revision(), 0);
+    }
+}
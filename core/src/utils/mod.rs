@@ -5,6 +5,7 @@
  */
 
 pub mod helpers;
+pub mod snippet;
 
 // Re-export common utilities
-pub use helpers::*;
\ No newline at end of file
+pub use helpers::*;
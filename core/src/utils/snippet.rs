@@ -0,0 +1,252 @@
+/*!
+ * Search Snippets
+ *
+ * Picks the passage of a chunk that best matches the query (the sentence
+ * with the most distinct query terms, widened with neighbouring sentences)
+ * and reports where the query terms fall in it so previews can be
+ * highlighted. Without a match the snippet is the chunk's opening sentences.
+ */
+
+use std::ops::Range;
+
+use crate::schemas::{Highlight, SearchResult};
+
+/// Longest snippet, in characters (ellipses excluded)
+pub const SNIPPET_MAX_CHARS: usize = 240;
+
+/// Query terms of at least this many characters also match as word prefixes ("install" → "installer")
+const PREFIX_MATCH_CHARS: usize = 4;
+
+const ELLIPSIS: char = '…';
+
+/// Snippet text with highlighted term offsets
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    pub text: String,
+    pub highlights: Vec<Highlight>,
+}
+
+/// Replace a result's snippet with one matched against `query`
+pub fn highlight_result(result: &mut SearchResult, query: &str) {
+    let snippet = extract_snippet(&result.content, query, SNIPPET_MAX_CHARS);
+    result.snippet = snippet.text;
+    result.highlights = snippet.highlights;
+}
+
+/// Best-matching passage of `content` for `query`, at most `max_chars` long
+pub fn extract_snippet(content: &str, query: &str, max_chars: usize) -> Snippet {
+    let terms = query_terms(query);
+    let matches: Vec<Range<usize>> = words(content)
+        .filter(|word| {
+            let word = content[word.clone()].to_lowercase();
+            terms.iter().any(|term| word == *term || (term.chars().count() >= PREFIX_MATCH_CHARS && word.starts_with(term.as_str())))
+        })
+        .collect();
+    let sentences = sentences(content);
+    if sentences.is_empty() {
+        return Snippet { text: String::new(), highlights: Vec::new() };
+    }
+
+    // Most distinct terms, then most matches; earliest on ties
+    let mut anchor = 0;
+    let mut best = (0, 0);
+    for (i, sentence) in sentences.iter().enumerate() {
+        let mut matched: Vec<String> = matches.iter()
+            .filter(|m| m.start >= sentence.start && m.end <= sentence.end)
+            .map(|m| content[m.clone()].to_lowercase())
+            .collect();
+        let count = matched.len();
+        matched.sort();
+        matched.dedup();
+        if (matched.len(), count) > best {
+            best = (matched.len(), count);
+            anchor = i;
+        }
+    }
+
+    let window = if char_len(content, &sentences[anchor]) > max_chars {
+        let focus = matches.iter().find(|m| m.start >= sentences[anchor].start).map_or(sentences[anchor].start, |m| m.start);
+        clip(content, sentences[anchor].clone(), focus, max_chars)
+    } else {
+        // Widen with following, then preceding sentences while they fit
+        let (mut first, mut last) = (anchor, anchor);
+        loop {
+            let mut grew = false;
+            if last + 1 < sentences.len() && char_len(content, &(sentences[first].start..sentences[last + 1].end)) <= max_chars {
+                last += 1;
+                grew = true;
+            }
+            if first > 0 && char_len(content, &(sentences[first - 1].start..sentences[last].end)) <= max_chars {
+                first -= 1;
+                grew = true;
+            }
+            if !grew {
+                break;
+            }
+        }
+        sentences[first].start..sentences[last].end
+    };
+
+    render(content, window, &matches)
+}
+
+/// Lowercased, deduplicated query words
+fn query_terms(query: &str) -> Vec<String> {
+    let mut terms: Vec<String> = words(query)
+        .map(|word| query[word].to_lowercase())
+        .filter(|term| term.chars().count() > 1)
+        .collect();
+    terms.sort();
+    terms.dedup();
+    terms
+}
+
+/// Byte ranges of alphanumeric runs
+fn words(text: &str) -> impl Iterator<Item = Range<usize>> + '_ {
+    let mut start: Option<usize> = None;
+    text.char_indices()
+        .chain([(text.len(), ' ')])
+        .filter_map(move |(i, c)| match (c.is_alphanumeric(), start) {
+            (true, None) => {
+                start = Some(i);
+                None
+            }
+            (false, Some(s)) => {
+                start = None;
+                Some(s..i)
+            }
+            _ => None,
+        })
+}
+
+/// Byte ranges of sentences, ending after `.`, `!` or `?` plus whitespace, or at a line break
+pub(crate) fn sentences(text: &str) -> Vec<Range<usize>> {
+    let mut sentences = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = text.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        if c.is_whitespace() {
+            if c == '\n' {
+                if let Some(s) = start.take() {
+                    sentences.push(s..i);
+                }
+            }
+            continue;
+        }
+        let s = *start.get_or_insert(i);
+        let at_break = chars.peek().is_none_or(|(_, next)| next.is_whitespace());
+        if matches!(c, '.' | '!' | '?') && at_break {
+            sentences.push(s..i + c.len_utf8());
+            start = None;
+        }
+    }
+    if let Some(s) = start {
+        sentences.push(s..text.trim_end().len());
+    }
+    sentences
+}
+
+fn char_len(text: &str, range: &Range<usize>) -> usize {
+    text[range.clone()].chars().count()
+}
+
+/// At most `max_chars` of `range` around `focus`, cut at word boundaries
+fn clip(text: &str, range: Range<usize>, focus: usize, max_chars: usize) -> Range<usize> {
+    // Keep about a third of the window before the focus
+    let lead = text[range.start..focus].char_indices().rev().nth(max_chars / 3).map_or(range.start, |(i, _)| range.start + i);
+    let start = if lead > range.start {
+        text[lead..focus].find(char::is_whitespace).map_or(focus, |i| lead + i + 1)
+    } else {
+        lead
+    };
+    let end = text[start..range.end].char_indices().nth(max_chars).map_or(range.end, |(i, _)| start + i);
+    let end = if end < range.end {
+        text[start..end].rfind(char::is_whitespace).map_or(end, |i| start + i)
+    } else {
+        end
+    };
+    let trimmed = text[start..end].trim_end().len();
+    start..start + trimmed
+}
+
+/// Snippet text with whitespace collapsed, ellipses where content was cut
+fn render(text: &str, window: Range<usize>, matches: &[Range<usize>]) -> Snippet {
+    let mut snippet = String::new();
+    let mut highlights = Vec::new();
+    let mut chars = 0;
+    if text[..window.start].chars().any(|c| !c.is_whitespace()) {
+        snippet.push(ELLIPSIS);
+        chars += 1;
+    }
+
+    let mut pending = matches.iter().filter(|m| m.start >= window.start && m.end <= window.end).peekable();
+    let mut open: Option<usize> = None;
+    let mut space = false;
+    for (i, c) in text[window.clone()].char_indices() {
+        let position = window.start + i;
+        if c.is_whitespace() {
+            space = true;
+            continue;
+        }
+        if space {
+            snippet.push(' ');
+            chars += 1;
+            space = false;
+        }
+        if pending.peek().is_some_and(|m| m.start == position) {
+            open = Some(chars);
+        }
+        snippet.push(c);
+        chars += 1;
+        if let (Some(start), Some(m)) = (open, pending.peek()) {
+            if position + c.len_utf8() == m.end {
+                highlights.push(Highlight { start, end: chars });
+                open = None;
+                pending.next();
+            }
+        }
+    }
+
+    if text[window.end..].chars().any(|c| !c.is_whitespace()) {
+        snippet.push(ELLIPSIS);
+    }
+    Snippet { text: snippet, highlights }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn highlighted(snippet: &Snippet) -> Vec<String> {
+        snippet.highlights.iter()
+            .map(|h| snippet.text.chars().skip(h.start).take(h.end - h.start).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_snippet_picks_best_sentence() {
+        let content = "Welcome to the guide. Nothing here is relevant at all. \
+                       To install the server, run the installer as root. Then reboot.";
+        let snippet = extract_snippet(content, "install server", 70);
+        assert_eq!(snippet.text, "…To install the server, run the installer as root. Then reboot.");
+        assert_eq!(highlighted(&snippet), vec!["install", "server", "installer"]);
+    }
+
+    #[test]
+    fn test_snippet_without_match_uses_opening() {
+        let content = "First sentence.\nSecond   sentence here. Third one is long enough to cut.";
+        let snippet = extract_snippet(content, "zebra", 40);
+        assert_eq!(snippet.text, "First sentence. Second sentence here.…");
+        assert!(snippet.highlights.is_empty());
+        assert_eq!(extract_snippet("", "zebra", 40).text, "");
+    }
+
+    #[test]
+    fn test_snippet_clips_long_sentence() {
+        let content = format!("{} the café configuration lives here {}", "word ".repeat(50), "tail ".repeat(50));
+        let snippet = extract_snippet(&content, "Café", 60);
+        assert!(snippet.text.starts_with('…') && snippet.text.ends_with('…'));
+        assert!(snippet.text.chars().count() <= 62);
+        assert_eq!(highlighted(&snippet), vec!["café"]);
+    }
+}
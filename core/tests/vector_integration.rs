@@ -56,7 +56,8 @@ async fn test_vector_service_full_workflow() {
 
     // Test collection stats
     let stats = vector_service.get_collection_stats("test_kb").await.expect("Failed to get collection stats");
-    assert_eq!(stats.vector_count, 0); // Mock implementation returns 0
+    assert_eq!(stats.vector_count, 2);
+    assert_eq!(stats.dimension, Some(4));
     assert!(stats.last_updated.is_some());
 
     // Test collection deletion
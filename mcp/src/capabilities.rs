@@ -0,0 +1,171 @@
+/*!
+ * Capabilities File
+ *
+ * `--capabilities <file>` narrows what this MCP instance exposes, so a server
+ * can be handed to an untrusted agent with only the tools, knowledge bases
+ * and scopes it needs:
+ *
+ *   { "tools": ["kb.hybrid_search", "docs_*"], "kbs": ["kb_docs"], "scopes": ["kb.read"] }
+ *
+ * Every list is optional and a missing list allows everything; tool names
+ * ending in `*` match by prefix. Tools, KBs and resources outside the lists
+ * are hidden from the list methods and refused when called. Scopes can only
+ * narrow what `--scopes` and air-gapped mode already grant.
+ *
+ * Tools that reach chunks by ID alone can't be tied to a KB, so they are
+ * unavailable while a KB list is set; the `ragstudio://kb/...` resources,
+ * which name their KB, cover the same reads. `kb.stats` and `kb.get_document`
+ * must then name their collection, and the KB listings only show allowed KBs.
+ */
+
+use std::collections::HashSet;
+use std::path::Path;
+use anyhow::{Result, Context, anyhow, bail};
+use serde::{Deserialize, Serialize};
+
+use crate::protocol::ToolCall;
+use crate::validation::Scope;
+
+/// Tools whose arguments don't say which KB they read
+const UNATTRIBUTED_TOOLS: &[&str] = &["kb.resolve_citations"];
+
+/// Tools whose `collection` is optional but must be given while KBs are restricted
+const COLLECTION_REQUIRED_TOOLS: &[&str] = &["kb.stats", "kb.get_document"];
+
+/// Whitelists from a capabilities file (None allows everything)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Capabilities {
+    #[serde(default)]
+    pub tools: Option<Vec<String>>,
+    #[serde(default)]
+    pub kbs: Option<Vec<String>>,
+    #[serde(default)]
+    pub scopes: Option<Vec<String>>,
+}
+
+impl Capabilities {
+    /// Read and check a capabilities file; unknown scopes are an error
+    pub fn load(path: &Path) -> Result<Self> {
+        let contents = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read capabilities {}", path.display()))?;
+        let capabilities: Self = serde_json::from_str(&contents)
+            .with_context(|| format!("Invalid capabilities {}", path.display()))?;
+        capabilities.granted_scopes()?;
+        if capabilities.tools.iter().flatten().any(|pattern| pattern.is_empty()) {
+            bail!("Invalid capabilities {}: empty tool name", path.display());
+        }
+        Ok(capabilities)
+    }
+
+    /// Scopes the file grants, if it limits them
+    pub fn granted_scopes(&self) -> Result<Option<Vec<Scope>>> {
+        self.scopes.as_ref()
+            .map(|names| names.iter()
+                .map(|name| Scope::parse(name).ok_or_else(|| anyhow!("Unknown scope in capabilities: {}", name)))
+                .collect())
+            .transpose()
+    }
+
+    /// KBs the file allows, if it limits them
+    pub fn allowed_kbs(&self) -> Option<HashSet<String>> {
+        self.kbs.as_ref().map(|kbs| kbs.iter().cloned().collect())
+    }
+
+    pub fn allows_tool(&self, name: &str) -> bool {
+        self.tools.as_ref().is_none_or(|patterns| patterns.iter().any(|pattern| match pattern.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == pattern,
+        }))
+    }
+
+    pub fn allows_kb(&self, kb_id: &str) -> bool {
+        self.kbs.as_ref().is_none_or(|kbs| kbs.iter().any(|kb| kb == kb_id))
+    }
+
+    /// Whether a tool is listed at all; `bound_kb` is a user-defined tool's KB
+    pub fn exposes_tool(&self, name: &str, bound_kb: Option<&str>) -> bool {
+        if !self.allows_tool(name) {
+            return false;
+        }
+        if self.kbs.is_some() && UNATTRIBUTED_TOOLS.contains(&name) {
+            return false;
+        }
+        bound_kb.is_none_or(|kb| self.allows_kb(kb))
+    }
+
+    /// Check a call's tool and every KB it names
+    pub fn check_tool_call(&self, call: &ToolCall, bound_kb: Option<&str>) -> Result<()> {
+        if !self.allows_tool(&call.name) {
+            bail!("Tool {} is not exposed by this server", call.name);
+        }
+        if self.kbs.is_none() {
+            return Ok(());
+        }
+        if UNATTRIBUTED_TOOLS.contains(&call.name.as_str()) {
+            bail!("Tool {} is unavailable while knowledge bases are restricted; read documents as resources instead", call.name);
+        }
+
+        let mut kbs: Vec<&str> = bound_kb.into_iter().collect();
+        if let Some(collection) = call.arguments.get("collection").and_then(|v| v.as_str()) {
+            kbs.push(collection);
+        }
+        if let Some(collections) = call.arguments.get("collections").and_then(|v| v.as_array()) {
+            kbs.extend(collections.iter().filter_map(|v| v.as_str()));
+        }
+        // Without a collection, kb.stats reports on every KB and kb.get_document looks up by ID alone
+        if kbs.is_empty() && COLLECTION_REQUIRED_TOOLS.contains(&call.name.as_str()) {
+            bail!("{} needs a collection while knowledge bases are restricted", call.name);
+        }
+        match kbs.into_iter().find(|kb| !self.allows_kb(kb)) {
+            Some(kb) => Err(anyhow!("Knowledge base {} is not exposed by this server", kb)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_capabilities_gating() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("capabilities.json");
+        std::fs::write(&path, r#"{"tools": ["kb.hybrid_search", "kb.stats", "kb.get_document", "docs_*"], "kbs": ["kb_docs"], "scopes": ["kb.read"]}"#).unwrap();
+        let capabilities = Capabilities::load(&path).unwrap();
+        assert_eq!(capabilities.granted_scopes().unwrap(), Some(vec![Scope::KbRead]));
+
+        assert!(capabilities.exposes_tool("kb.hybrid_search", None));
+        assert!(capabilities.exposes_tool("docs_search", Some("kb_docs")));
+        assert!(!capabilities.exposes_tool("docs_private", Some("kb_hr")));
+        assert!(!capabilities.exposes_tool("rag.answer", None));
+        assert!(capabilities.exposes_tool("kb.get_document", None));
+        assert!(!capabilities.exposes_tool("kb.resolve_citations", None));   // Can't be tied to a KB
+
+        let call = |name: &str, arguments: serde_json::Value| ToolCall {
+            name: name.to_string(),
+            arguments: serde_json::from_value::<HashMap<_, _>>(arguments).unwrap(),
+        };
+        assert!(capabilities.check_tool_call(&call("kb.hybrid_search", json!({"collection": "kb_docs", "query": "q"})), None).is_ok());
+        let err = capabilities.check_tool_call(&call("kb.hybrid_search", json!({"collection": "kb_hr", "query": "q"})), None).unwrap_err();
+        assert!(err.to_string().contains("kb_hr"));
+        assert!(capabilities.check_tool_call(&call("kb.stats", json!({})), None).is_err());
+        assert!(capabilities.check_tool_call(&call("kb.get_document", json!({"doc_id": "d"})), None).is_err());
+        assert!(capabilities.check_tool_call(&call("kb.get_document", json!({"collection": "kb_docs", "doc_id": "d"})), None).is_ok());
+        assert!(capabilities.check_tool_call(&call("docs_search", json!({"query": "q"})), Some("kb_docs")).is_ok());
+        assert!(capabilities.check_tool_call(&call("rag.answer", json!({"collection": "kb_docs"})), None).is_err());
+
+        // No lists: everything goes
+        let open = Capabilities::default();
+        assert!(open.check_tool_call(&call("kb.get_document", json!({"doc_id": "d"})), None).is_ok());
+
+        std::fs::write(&path, r#"{"scopes": ["kb.admin"]}"#).unwrap();
+        assert!(Capabilities::load(&path).is_err());
+        std::fs::write(&path, r#"{"tool": ["kb.stats"]}"#).unwrap();
+        assert!(Capabilities::load(&path).is_err());
+    }
+}
@@ -2,25 +2,57 @@
  * RAG MCP Server - Model Context Protocol Server for RAG Studio
  *
  * MVP Implementation:
- * - JSON-RPC over stdin/stdout for simplicity
+ * - JSON-RPC over stdin/stdout, or per-connection sessions on TCP/UDS (`--listen`)
  * - Basic subprocess isolation (process boundaries)
  * - Tool registry for kb.* operations
  * - Upgrade path to UDS/Axum with full sandboxing
  */
 
-use std::io::{self, BufRead, BufReader, Write};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
 use clap::Parser;
 use serde_json::Value;
-use tracing::{info, error, debug, warn};
+use tracing::{info, info_span, error, debug, warn, Instrument};
 use anyhow::{Result, Context};
 
+mod capabilities;
+mod progress;
+mod prompts;
 mod protocol;
+mod rate_limit;
+mod resources;
+mod schemas;
+mod session;
 mod tools;
 mod validation;
 
-use protocol::{McpRequest, McpResponse, JsonRpcError, ToolCall};
-use tools::ToolRegistry;
-use validation::InputValidator;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncRead, AsyncWrite, AsyncWriteExt, BufReader};
+use tokio::sync::mpsc::{self, UnboundedSender};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::EnvFilter;
+
+use rag_core::{LogSubsystem, LoggingConfig, LoggingService, NetworkFeature, NetworkPolicy, Settings, SqlConfig, SqlService, TelemetryConfig, TelemetryService};
+use rag_core::models::mcp_quota::QuotaLimits;
+use rag_core::modules::audit::{AuditService, McpAuditEntry};
+use rag_core::models::tool_catalog::ToolCatalog;
+
+use capabilities::Capabilities;
+use progress::ProgressReporter;
+use session::{Handshake, Session};
+use prompts::PromptRegistry;
+use protocol::{McpRequest, McpResponse, McpNotification, JsonRpcError, OutgoingMessage, ToolCall};
+use rate_limit::RateLimiter;
+use resources::{ResourceProvider, ResourceUri};
+use tools::{ToolCancelled, ToolRegistry};
+use validation::{CapabilityPolicy, InputValidator, InvalidArguments, Scope};
 
 /// RAG MCP Server CLI arguments
 #[derive(Parser, Debug)]
@@ -34,44 +66,263 @@ struct Args {
     #[arg(long, default_value = "http://localhost:3000")]
     outbound_url: String,
 
+    /// Manager's RPC token file (`rpc_token` in its project directory); RAG_STUDIO_RPC_TOKEN overrides it
+    #[arg(long)]
+    rpc_token_file: Option<String>,
+
     /// Enable air-gapped mode (no outbound connections)
     #[arg(long)]
     air_gapped: bool,
 
-    /// Tool capabilities file (JSON schema)
+    /// Manager settings file; its air-gapped switch and network overrides apply here too
+    #[arg(long)]
+    settings: Option<String>,
+
+    /// Capabilities file whitelisting the tools, KBs and scopes this instance exposes
     #[arg(long)]
     capabilities: Option<String>,
+
+    /// Comma-separated permission scopes granted to tools (all when omitted)
+    #[arg(long)]
+    scopes: Option<String>,
+
+    /// Tool calls allowed per client per minute
+    #[arg(long, default_value_t = 120)]
+    calls_per_minute: u32,
+
+    /// Concurrent tool calls allowed per client
+    #[arg(long, default_value_t = 4)]
+    max_concurrent_calls: u32,
+
+    /// Serve clients on a TCP address or `unix:<path>` socket instead of stdio, one session per connection
+    #[arg(long)]
+    listen: Option<String>,
+
+    /// Requests handled at once; further requests wait for a free slot
+    #[arg(long, default_value_t = 32)]
+    max_in_flight: usize,
+
+    /// Maximum serialized tool result size in bytes
+    #[arg(long, default_value_t = 1_000_000)]
+    max_result_bytes: usize,
+
+    /// Quota metrics snapshot read by the Manager dashboard
+    #[arg(long)]
+    quota_metrics: Option<String>,
+
+    /// SQLite database receiving the tool invocation audit log (usually the Manager's app DB)
+    #[arg(long)]
+    audit_db: Option<String>,
+
+    /// Prompt catalog written by the Manager (built-in prompts when omitted)
+    #[arg(long)]
+    prompts: Option<String>,
+
+    /// Dynamic tool catalog written by the Manager (polled for changes)
+    #[arg(long)]
+    tools: Option<String>,
+
+    /// Directory for structured JSON logs shared with the Manager's log viewer
+    #[arg(long)]
+    log_dir: Option<String>,
 }
 
+/// `--listen` prefix selecting a Unix socket instead of a TCP address
+const UNIX_SOCKET_PREFIX: &str = "unix:";
+
+/// How often the dynamic tool catalog is checked for changes
+const TOOL_CATALOG_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
 /// MCP Server state
 pub struct McpServer {
     tool_registry: ToolRegistry,
+    resource_provider: ResourceProvider,
+    prompt_registry: PromptRegistry,
     validator: InputValidator,
     outbound_url: String,
     air_gapped: bool,
+    tool_catalog: Option<PathBuf>,
+    rate_limiter: RateLimiter,
+    quota_metrics: Option<PathBuf>,
+    stdio: Arc<Session>,
+    sessions: Mutex<HashMap<u64, Arc<Session>>>,            // Clients attached on --listen
+    next_session: AtomicU64,
+    audit: Option<Arc<AuditService>>,
+    capabilities: Capabilities,                             // Whitelists from --capabilities (allow all by default)
 }
 
 impl McpServer {
     pub fn new(outbound_url: String, air_gapped: bool) -> Result<Self> {
         let tool_registry = ToolRegistry::new()?;
-        let validator = InputValidator::new()?;
+        let validator = InputValidator::new()?
+            .with_policy(CapabilityPolicy::new(Scope::ALL, air_gapped));
 
         Ok(Self {
             tool_registry,
+            resource_provider: ResourceProvider::new(),
+            prompt_registry: PromptRegistry::new(None),
             validator,
             outbound_url,
             air_gapped,
+            tool_catalog: None,
+            rate_limiter: RateLimiter::new(QuotaLimits::default()),
+            quota_metrics: None,
+            stdio: Arc::new(Session::stdio(None)),
+            sessions: Mutex::new(HashMap::new()),
+            next_session: AtomicU64::new(1),
+            audit: None,
+            capabilities: Capabilities::default(),
         })
     }
 
-    /// Process a single MCP request
+    /// Replace the per-client quota limits
+    pub fn with_quota_limits(mut self, limits: QuotaLimits) -> Self {
+        self.rate_limiter = RateLimiter::new(limits);
+        self
+    }
+
+    /// Write quota metrics for the dashboard after every tool call
+    pub fn with_quota_metrics(mut self, path: PathBuf) -> Self {
+        self.quota_metrics = Some(path);
+        self
+    }
+
+    /// Record every tool call in the audit log
+    pub fn with_audit_log(mut self, audit: Arc<AuditService>) -> Self {
+        self.audit = Some(audit);
+        self
+    }
+
+    fn write_quota_metrics(&self) {
+        if let Some(path) = &self.quota_metrics {
+            if let Err(e) = self.rate_limiter.metrics().save(path) {
+                warn!("Failed to write quota metrics {}: {}", path.display(), e);
+            }
+        }
+    }
+
+    /// Restrict tools to the given scopes (network scopes still stripped when air-gapped)
+    pub fn with_scopes(mut self, scopes: &str) -> Result<Self> {
+        let policy = CapabilityPolicy::from_list(scopes, self.air_gapped)?;
+        self.validator = InputValidator::new()?.with_policy(policy);
+        Ok(self)
+    }
+
+    /// Expose only what the capabilities file allows; apply after `with_scopes`
+    pub fn with_capabilities(mut self, capabilities: Capabilities) -> Result<Self> {
+        if let Some(scopes) = capabilities.granted_scopes()? {
+            self.validator = self.validator.restrict_scopes(&scopes);
+        }
+        if let Some(kbs) = capabilities.allowed_kbs() {
+            self.tool_registry.restrict_kbs(kbs);
+        }
+        self.capabilities = capabilities;
+        Ok(self)
+    }
+
+    /// True if the capabilities file exposes the tool and every scope it declares is granted
+    fn is_permitted(&self, tool: &str) -> bool {
+        self.capabilities.exposes_tool(tool, self.tool_registry.bound_kb(tool).as_deref())
+            && self.tool_registry.required_scopes(tool)
+                .is_some_and(|scopes| self.validator.validate_scopes(tool, &scopes).is_ok())
+    }
+
+    /// True if the resource's KB is exposed (unparseable URIs are not)
+    fn is_resource_permitted(&self, uri: &str) -> bool {
+        ResourceUri::parse(uri).is_ok_and(|resource| self.capabilities.allows_kb(resource.kb_id()))
+    }
+
+    /// Serve prompts from the Manager's catalog file
+    pub fn with_prompt_catalog(mut self, path: std::path::PathBuf) -> Self {
+        self.prompt_registry = PromptRegistry::new(Some(path));
+        self
+    }
+
+    /// Register user-defined tools from the Manager's catalog file
+    pub fn with_tool_catalog(mut self, path: PathBuf) -> Result<Self> {
+        self.tool_catalog = Some(path);
+        self.reload_tool_catalog()?;
+        Ok(self)
+    }
+
+    /// Re-read the tool catalog, notifying the client if the tool list changed
+    pub fn reload_tool_catalog(&self) -> Result<bool> {
+        let Some(path) = &self.tool_catalog else {
+            return Ok(false);
+        };
+        let catalog = ToolCatalog::load(path)
+            .map_err(|e| anyhow::anyhow!("Failed to load tool catalog {}: {}", path.display(), e))?;
+
+        let changed = self.tool_registry.sync_dynamic_tools(catalog.tools);
+        if changed {
+            let notification = McpNotification::new("notifications/tools/list_changed", serde_json::json!({}));
+            self.stdio.notify(OutgoingMessage::Notification(notification.clone()));
+            for session in self.sessions.lock().unwrap().values() {
+                session.notify(OutgoingMessage::Notification(notification.clone()));
+            }
+        }
+        Ok(changed)
+    }
+
+    /// Attach the stdio client's outgoing channel (progress and list-changed notifications)
+    pub fn with_notifier(mut self, notifier: UnboundedSender<OutgoingMessage>) -> Self {
+        self.stdio = Arc::new(Session::stdio(Some(notifier)));
+        self
+    }
+
+    /// Start a session for a client connected on `--listen`
+    pub fn open_session(&self, notifier: UnboundedSender<OutgoingMessage>) -> Arc<Session> {
+        let number = self.next_session.fetch_add(1, Ordering::Relaxed);
+        let session = Arc::new(Session::connection(number, notifier));
+        self.sessions.lock().unwrap().insert(number, session.clone());
+        info!("Session {} opened ({} attached)", number, self.sessions.lock().unwrap().len());
+        session
+    }
+
+    /// End a network session, cancelling whatever it still has running
+    pub fn close_session(&self, session: &Session) {
+        let Some(number) = session.number() else {
+            return;
+        };
+        session.cancel_all();
+        self.sessions.lock().unwrap().remove(&number);
+        info!("Session {} ({}) closed", number, session.client_id());
+    }
+
+    /// Cancel a running stdio tool call; returns false if the id is unknown or already finished
+    pub fn cancel_request(&self, id: &Value) -> bool {
+        self.stdio.cancel_request(id)
+    }
+
+    /// Process a single MCP request from the stdio client
     pub async fn process_request(&self, request: McpRequest) -> McpResponse {
+        self.process_session_request(&self.stdio, request).await
+    }
+
+    /// Process a single MCP request, continuing the client's trace when it sent one
+    pub async fn process_session_request(&self, session: &Session, request: McpRequest) -> McpResponse {
+        let span = info_span!("mcp.request", method = %request.method, client = %session.client_id());
+        if let Some(parent) = protocol::trace_context(&request.params) {
+            parent.attach(&span);
+        }
+        if !session.accepts(&request.method) {
+            return McpResponse::error(request.id, JsonRpcError::invalid_request("Session not initialized: send initialize first"));
+        }
+        self.dispatch_request(session, request).instrument(span).await
+    }
+
+    async fn dispatch_request(&self, session: &Session, request: McpRequest) -> McpResponse {
         debug!("Processing MCP request: {:?}", request);
 
         match request.method.as_str() {
-            "initialize" => self.handle_initialize(request).await,
+            "initialize" => self.handle_initialize(session, request).await,
             "tools/list" => self.handle_list_tools(request).await,
-            "tools/call" => self.handle_tool_call(request).await,
+            "tools/call" => self.handle_tool_call(session, request).await,
+            "resources/list" => self.handle_list_resources(request).await,
+            "resources/templates/list" => self.handle_list_resource_templates(request).await,
+            "resources/read" => self.handle_read_resource(request).await,
+            "prompts/list" => self.handle_list_prompts(request).await,
+            "prompts/get" => self.handle_get_prompt(request).await,
             "ping" => self.handle_ping(request).await,
             _ => McpResponse::error(
                 request.id.clone(),
@@ -81,16 +332,26 @@ impl McpServer {
     }
 
     /// Handle MCP initialization
-    async fn handle_initialize(&self, request: McpRequest) -> McpResponse {
+    async fn handle_initialize(&self, session: &Session, request: McpRequest) -> McpResponse {
         info!("Initializing MCP server");
 
+        // Quotas and audit entries are keyed by the client's self-reported name
+        if let Err(e) = session.initialize(Handshake::from_params(&request.params)) {
+            return McpResponse::error(request.id, JsonRpcError::invalid_request(&e));
+        }
+
         let capabilities = serde_json::json!({
             "tools": {
-                "listChanged": false,
-                "supportsProgress": false
+                "listChanged": true,
+                "supportsProgress": true
+            },
+            "resources": {
+                "subscribe": false,
+                "listChanged": false
+            },
+            "prompts": {
+                "listChanged": false
             },
-            "resources": {},
-            "prompts": {},
             "logging": {}
         });
 
@@ -101,8 +362,10 @@ impl McpServer {
     async fn handle_list_tools(&self, request: McpRequest) -> McpResponse {
         debug!("Listing available tools");
 
+        // Tools whose scopes are not granted are hidden
         let tools = self.tool_registry.list_tools();
         let tools_array: Vec<Value> = tools.into_iter()
+            .filter(|tool| self.is_permitted(&tool.name))
             .map(|tool| serde_json::to_value(tool).unwrap_or_default())
             .collect();
 
@@ -111,8 +374,39 @@ impl McpServer {
         }))
     }
 
-    /// Handle tool call request
-    async fn handle_tool_call(&self, request: McpRequest) -> McpResponse {
+    /// Handle tool call request, recording it in the audit log
+    async fn handle_tool_call(&self, session: &Session, request: McpRequest) -> McpResponse {
+        let Some(audit) = &self.audit else {
+            return self.execute_tool_call(session, request).await;
+        };
+
+        let started = Instant::now();
+        let tool_name = request.params.get("name").and_then(|v| v.as_str()).unwrap_or("").to_string();
+        let arguments = request.params.get("arguments").cloned().unwrap_or(Value::Null);
+        let caller = session.client_id();
+
+        let response = self.execute_tool_call(session, request).await;
+
+        let mut entry = McpAuditEntry::new(&tool_name, &arguments, &caller);
+        entry.latency_ms = started.elapsed().as_millis() as u64;
+        match &response {
+            McpResponse::Success { result, .. } => entry.result_bytes = result.to_string().len() as u64,
+            McpResponse::Error { error, .. } => {
+                entry.success = false;
+                entry.error = Some(match &error.data {
+                    Some(Value::String(detail)) => format!("{}: {}", error.message, detail),
+                    _ => error.message.clone(),
+                });
+            }
+        }
+        if let Err(e) = audit.record(&entry).await {
+            warn!("Failed to write audit entry for {}: {}", tool_name, e);
+        }
+        response
+    }
+
+    /// Validate, authorize and execute a tool call
+    async fn execute_tool_call(&self, session: &Session, request: McpRequest) -> McpResponse {
         let tool_call = match serde_json::from_value::<ToolCall>(request.params.clone()) {
             Ok(call) => call,
             Err(e) => {
@@ -126,18 +420,72 @@ impl McpServer {
 
         debug!("Calling tool: {} with args: {:?}", tool_call.name, tool_call.arguments);
 
-        // Validate input
-        if let Err(e) = self.validator.validate_tool_call(&tool_call) {
+        // Tools and KBs outside the capabilities file are refused
+        let bound_kb = self.tool_registry.bound_kb(&tool_call.name);
+        if let Err(e) = self.capabilities.check_tool_call(&tool_call, bound_kb.as_deref()) {
+            warn!("Tool call denied: {}", e);
+            return McpResponse::error(request.id, JsonRpcError::permission_denied(&e.to_string()));
+        }
+
+        // Enforce declared scopes (unknown tools fail validation below)
+        if let Some(scopes) = self.tool_registry.required_scopes(&tool_call.name) {
+            if let Err(e) = self.validator.validate_scopes(&tool_call.name, &scopes) {
+                warn!("Tool call denied: {}", e);
+                return McpResponse::error(request.id, JsonRpcError::permission_denied(&e.to_string()));
+            }
+        }
+
+        // Validate input against the tool's schema; violations carry their paths
+        let validation = match self.tool_registry.input_schema(&tool_call.name) {
+            Some(schema) => self.validator.validate_tool_call(&tool_call, &schema),
+            None => Err(anyhow::anyhow!("Unknown tool: {}", tool_call.name)),
+        };
+        if let Err(e) = validation {
             warn!("Tool call validation failed: {}", e);
-            return McpResponse::error(
-                request.id,
-                JsonRpcError::invalid_params(&format!("Validation failed: {}", e)),
-            );
+            let error = match e.downcast_ref::<InvalidArguments>() {
+                Some(invalid) => JsonRpcError::invalid_arguments(&invalid.to_string(), invalid.to_error_data()),
+                None => JsonRpcError::invalid_params(&format!("Validation failed: {}", e)),
+            };
+            return McpResponse::error(request.id, error);
         }
 
-        // Execute tool
-        match self.tool_registry.execute_tool(&tool_call, &self.outbound_url).await {
-            Ok(result) => McpResponse::success(request.id, serde_json::to_value(result).unwrap_or_default()),
+        // Per-client quotas
+        let client_id = session.client_id();
+        let permit = match self.rate_limiter.acquire(&client_id) {
+            Ok(permit) => permit,
+            Err(exceeded) => {
+                warn!("Quota exceeded for {}: {}", client_id, exceeded);
+                self.write_quota_metrics();
+                return McpResponse::error(request.id, JsonRpcError::quota_exceeded(&exceeded.to_string(), exceeded.to_error_data(&client_id)));
+            }
+        };
+
+        // Execute tool (progress only when the client sent a progress token)
+        // and cancellable by request id
+        let progress = ProgressReporter::new(protocol::progress_token(&request.params), session.notifier());
+        let cancel = CancellationToken::new();
+        if let Some(id) = &request.id {
+            session.track(id, cancel.clone());
+        }
+        let result = self.tool_registry.execute_tool(&tool_call, &self.outbound_url, &progress, &cancel).await;
+        if let Some(id) = &request.id {
+            session.untrack(id);
+        }
+        drop(permit);
+
+        let response = match result {
+            Ok(result) => {
+                let result = serde_json::to_value(result).unwrap_or_default();
+                let bytes = result.to_string().len();
+                match self.rate_limiter.check_result_size(&client_id, bytes) {
+                    Ok(()) => McpResponse::success(request.id, result),
+                    Err(exceeded) => {
+                        warn!("Result of {} rejected: {}", tool_call.name, exceeded);
+                        McpResponse::error(request.id, JsonRpcError::quota_exceeded(&exceeded.to_string(), exceeded.to_error_data(&client_id)))
+                    }
+                }
+            }
+            Err(e) if e.is::<ToolCancelled>() => McpResponse::error(request.id, JsonRpcError::request_cancelled()),
             Err(e) => {
                 error!("Tool execution failed: {}", e);
                 McpResponse::error(
@@ -145,6 +493,97 @@ impl McpServer {
                     JsonRpcError::internal_error(&format!("Tool execution failed: {}", e)),
                 )
             }
+        };
+        self.write_quota_metrics();
+        response
+    }
+
+    /// Handle resources list request
+    async fn handle_list_resources(&self, request: McpRequest) -> McpResponse {
+        debug!("Listing resources");
+
+        match self.resource_provider.list_resources(&self.outbound_url).await {
+            Ok(resources) => McpResponse::success(request.id, serde_json::json!({
+                "resources": resources.into_iter()
+                    .filter(|resource| self.is_resource_permitted(&resource.uri))
+                    .collect::<Vec<_>>()
+            })),
+            Err(e) => {
+                error!("Failed to list resources: {}", e);
+                McpResponse::error(
+                    request.id,
+                    JsonRpcError::internal_error(&format!("Failed to list resources: {}", e)),
+                )
+            }
+        }
+    }
+
+    /// Handle resource templates list request
+    async fn handle_list_resource_templates(&self, request: McpRequest) -> McpResponse {
+        McpResponse::success(request.id, serde_json::json!({
+            "resourceTemplates": self.resource_provider.list_templates()
+        }))
+    }
+
+    /// Handle resource read request
+    async fn handle_read_resource(&self, request: McpRequest) -> McpResponse {
+        let Some(uri) = request.params.get("uri").and_then(|v| v.as_str()) else {
+            return McpResponse::error(request.id, JsonRpcError::invalid_params("Missing required parameter: uri"));
+        };
+
+        if let Err(e) = ResourceUri::parse(uri) {
+            warn!("Rejected resource URI: {}", e);
+            return McpResponse::error(request.id, JsonRpcError::resource_not_found(uri));
+        }
+        // KBs outside the capabilities file look like they don't exist
+        if !self.is_resource_permitted(uri) {
+            warn!("Resource {} is not exposed", uri);
+            return McpResponse::error(request.id, JsonRpcError::resource_not_found(uri));
+        }
+
+        match self.resource_provider.read_resource(uri, &self.outbound_url).await {
+            Ok(contents) => McpResponse::success(request.id, serde_json::json!({
+                "contents": [contents]
+            })),
+            Err(e) => {
+                error!("Failed to read resource {}: {}", uri, e);
+                McpResponse::error(
+                    request.id,
+                    JsonRpcError::internal_error(&format!("Failed to read resource: {}", e)),
+                )
+            }
+        }
+    }
+
+    /// Handle prompts list request
+    async fn handle_list_prompts(&self, request: McpRequest) -> McpResponse {
+        McpResponse::success(request.id, serde_json::json!({
+            "prompts": self.prompt_registry.list_prompts()
+        }))
+    }
+
+    /// Handle prompt get request
+    async fn handle_get_prompt(&self, request: McpRequest) -> McpResponse {
+        let Some(name) = request.params.get("name").and_then(|v| v.as_str()) else {
+            return McpResponse::error(request.id, JsonRpcError::invalid_params("Missing required parameter: name"));
+        };
+        let arguments = request.params.get("arguments").cloned().unwrap_or(Value::Null);
+
+        if let Some(kb_id) = self.prompt_registry.context_kb(name, &arguments) {
+            if !self.capabilities.allows_kb(&kb_id) {
+                warn!("Prompt {} denied: KB {} is not exposed", name, kb_id);
+                return McpResponse::error(request.id, JsonRpcError::permission_denied(
+                    &format!("Knowledge base {} is not exposed by this server", kb_id),
+                ));
+            }
+        }
+
+        match self.prompt_registry.get_prompt(name, &arguments, &self.outbound_url).await {
+            Ok(result) => McpResponse::success(request.id, result),
+            Err(e) => {
+                warn!("Prompt {} failed: {}", name, e);
+                McpResponse::error(request.id, JsonRpcError::invalid_params(&e.to_string()))
+            }
         }
     }
 
@@ -159,15 +598,148 @@ impl McpServer {
 }
 
 /// Main MCP server loop - JSON-RPC over stdin/stdout
-async fn run_stdio_server(server: McpServer) -> Result<()> {
+async fn run_stdio_server(server: McpServer, max_in_flight: usize) -> Result<()> {
     info!("Starting RAG MCP server on stdio");
 
-    let stdin = io::stdin();
-    let mut stdout = io::stdout();
-    let reader = BufReader::new(stdin.lock());
+    // Single writer keeps responses and progress notifications line-atomic and ordered
+    let (tx, mut rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+    let writer = tokio::spawn(async move {
+        let mut stdout = io::stdout();
+        while let Some(message) = rx.recv().await {
+            match serde_json::to_string(&message) {
+                Ok(json) => {
+                    debug!("Sending: {}", json);
+                    if writeln!(stdout, "{}", json).and_then(|_| stdout.flush()).is_err() {
+                        break;
+                    }
+                }
+                Err(e) => error!("Failed to serialize outgoing message: {}", e),
+            }
+        }
+    });
+    let server = Arc::new(server.with_notifier(tx.clone()));
+    let watcher = server.tool_catalog.clone().map(|path| {
+        tokio::spawn(watch_tool_catalog(server.clone(), path))
+    });
+
+    let slots = Arc::new(Semaphore::new(max_in_flight.max(1)));
+    let stdin = BufReader::new(tokio::io::stdin());
+    let result = serve_session(server.clone(), server.stdio.clone(), stdin, tx, slots).await;
+
+    // Flush pending output before exiting
+    if let Some(watcher) = watcher {
+        watcher.abort();
+        let _ = watcher.await;
+    }
+    drop(server);
+    let _ = writer.await;
+
+    info!("MCP server shutting down");
+    result
+}
+
+/// Accept clients on TCP (`127.0.0.1:7400`) or a Unix socket
+/// (`unix:/path/to.sock`), each connection getting its own session
+async fn run_network_server(server: McpServer, listen: &str, max_in_flight: usize) -> Result<()> {
+    let server = Arc::new(server);
+    if let Some(path) = server.tool_catalog.clone() {
+        tokio::spawn(watch_tool_catalog(server.clone(), path));
+    }
+    // One limit across all clients; per-client quotas still apply on top
+    let slots = Arc::new(Semaphore::new(max_in_flight.max(1)));
+
+    if let Some(path) = listen.strip_prefix(UNIX_SOCKET_PREFIX) {
+        #[cfg(unix)]
+        {
+            // A socket left behind by a previous run would make bind fail
+            if Path::new(path).exists() {
+                std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {}", path))?;
+            }
+            let listener = tokio::net::UnixListener::bind(path)
+                .with_context(|| format!("Failed to listen on {}", path))?;
+            info!("Starting RAG MCP server on unix socket {}", path);
+            loop {
+                let (stream, _) = listener.accept().await.context("Failed to accept connection")?;
+                spawn_connection(server.clone(), stream, slots.clone());
+            }
+        }
+        #[cfg(not(unix))]
+        anyhow::bail!("Unix sockets are not supported on this platform: {}", path);
+    }
+
+    let listener = tokio::net::TcpListener::bind(listen).await
+        .with_context(|| format!("Failed to listen on {}", listen))?;
+    let addr = listener.local_addr()?;
+    if !addr.ip().is_loopback() {
+        warn!("Listening on {}, which is reachable from other machines", addr);
+    }
+    info!("Starting RAG MCP server on tcp {}", addr);
+    loop {
+        let (stream, peer) = listener.accept().await.context("Failed to accept connection")?;
+        debug!("Connection from {}", peer);
+        let _ = stream.set_nodelay(true);
+        spawn_connection(server.clone(), stream, slots.clone());
+    }
+}
+
+/// Serve one network client until it disconnects
+fn spawn_connection<S>(server: Arc<McpServer>, stream: S, slots: Arc<Semaphore>)
+where
+    S: AsyncRead + AsyncWrite + Send + 'static,
+{
+    let (reader, mut writer) = tokio::io::split(stream);
+    let (tx, mut rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+    let session = server.open_session(tx.clone());
+
+    tokio::spawn(async move {
+        // Per-connection writer, like stdout's: one line per message
+        let output = tokio::spawn(async move {
+            while let Some(message) = rx.recv().await {
+                match serde_json::to_string(&message) {
+                    Ok(mut json) => {
+                        json.push('\n');
+                        if writer.write_all(json.as_bytes()).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => error!("Failed to serialize outgoing message: {}", e),
+                }
+            }
+            let _ = writer.shutdown().await;
+        });
+
+        if let Err(e) = serve_session(server, session, BufReader::new(reader), tx, slots).await {
+            warn!("Connection ended: {}", e);
+        }
+        let _ = output.await;
+    });
+}
+
+/// Read JSON-RPC lines from one client until end of input. Requests run
+/// concurrently so a slow search doesn't hold up pings or cancellations;
+/// each response carries its request's id, so the order they are written in
+/// doesn't matter.
+async fn serve_session<R>(
+    server: Arc<McpServer>,
+    session: Arc<Session>,
+    reader: R,
+    tx: UnboundedSender<OutgoingMessage>,
+    slots: Arc<Semaphore>,
+) -> Result<()>
+where
+    R: AsyncBufRead + Unpin,
+{
+    let mut in_flight = JoinSet::new();
+    let mut lines = reader.lines();
 
-    for line in reader.lines() {
-        let line = line.context("Failed to read from stdin")?;
+    let result = loop {
+        let line = match lines.next_line().await.context("Failed to read request") {
+            Ok(Some(line)) => line,
+            Ok(None) => break Ok(()),
+            Err(e) => break Err(e),
+        };
+        // Reap finished requests
+        while in_flight.try_join_next().is_some() {}
 
         if line.trim().is_empty() {
             continue;
@@ -184,56 +756,173 @@ async fn run_stdio_server(server: McpServer) -> Result<()> {
                     None,
                     JsonRpcError::parse_error(&format!("Invalid JSON: {}", e)),
                 );
-
-                if let Ok(response_json) = serde_json::to_string(&error_response) {
-                    writeln!(stdout, "{}", response_json)?;
-                    stdout.flush()?;
+                if tx.send(OutgoingMessage::Response(error_response)).is_err() {
+                    break Err(anyhow::anyhow!("Output writer closed"));
                 }
                 continue;
             }
         };
 
-        // Process request
-        let response = server.process_request(request).await;
+        // Cancellations are notifications: no response
+        if let Some(id) = protocol::cancelled_request_id(&request.method, &request.params) {
+            session.cancel_request(&id);
+            continue;
+        }
 
-        // Send response
-        if let Ok(response_json) = serde_json::to_string(&response) {
-            debug!("Sending: {}", response_json);
-            writeln!(stdout, "{}", response_json)?;
-            stdout.flush()?;
-        } else {
-            error!("Failed to serialize response");
+        // Initialization sets the client identity later requests are attributed to
+        if request.method == "initialize" {
+            let response = server.process_session_request(&session, request).await;
+            if tx.send(OutgoingMessage::Response(response)).is_err() {
+                break Err(anyhow::anyhow!("Output writer closed"));
+            }
+            continue;
+        }
+
+        let server = server.clone();
+        let session = session.clone();
+        let slots = slots.clone();
+        let tx = tx.clone();
+        in_flight.spawn(async move {
+            let Ok(_slot) = slots.acquire_owned().await else { return };
+            let response = server.process_session_request(&session, request).await;
+            let _ = tx.send(OutgoingMessage::Response(response));
+        });
+    };
+
+    // A network client that went away gets its calls cancelled; stdio's
+    // run to completion before the server exits
+    server.close_session(&session);
+    while in_flight.join_next().await.is_some() {}
+    result
+}
+
+/// Poll the tool catalog's modification time and reload on change
+async fn watch_tool_catalog(server: Arc<McpServer>, path: PathBuf) {
+    let modified = |path: &PathBuf| std::fs::metadata(path).and_then(|m| m.modified()).ok();
+    let mut last_seen: Option<SystemTime> = modified(&path);
+
+    loop {
+        tokio::time::sleep(TOOL_CATALOG_POLL_INTERVAL).await;
+
+        let current = modified(&path);
+        if current == last_seen {
+            continue;
+        }
+        last_seen = current;
+
+        match server.reload_tool_catalog() {
+            Ok(true) => info!("Dynamic tools reloaded from {}", path.display()),
+            Ok(false) => {}
+            Err(e) => warn!("{}", e),
         }
     }
+}
 
-    info!("MCP server shutting down");
-    Ok(())
+/// Network policy from `--air-gapped` (strict) or the Manager's settings file
+fn network_policy(args: &Args) -> Result<NetworkPolicy> {
+    if args.air_gapped {
+        return Ok(NetworkPolicy::new(true));
+    }
+    let Some(path) = &args.settings else {
+        return Ok(NetworkPolicy::new(false));
+    };
+    let settings = Settings::load(Path::new(path))
+        .with_context(|| format!("Failed to read settings {}", path))?;
+    Ok(NetworkPolicy::new(settings.features.air_gapped)
+        .with_overrides(&settings.features.network_overrides))
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
+    let policy = network_policy(&args)?;
+    let air_gapped = !policy.allows(NetworkFeature::McpOutbound);
 
-    // Initialize logging
+    // Initialize logging: stderr (stdout carries the protocol), plus mcp.log when sharing a log dir
     let log_level = if args.debug { "debug" } else { "info" };
-    tracing_subscriber::fmt()
-        .with_env_filter(format!("rag_mcp={},rag_core={}", log_level, log_level))
-        .with_target(false)
-        .with_ansi(false) // Disable ANSI for clean stdio
-        .init();
+    let log_filter = format!("rag_mcp={},rag_core={}", log_level, log_level);
+    // Spans export over OTLP when OTEL_EXPORTER_OTLP_ENDPOINT is set, except when air-gapped
+    let mut telemetry_config = TelemetryConfig::from_env("rag-mcp");
+    if air_gapped {
+        telemetry_config.otlp_endpoint = None;
+    }
+    let telemetry = TelemetryService::new(telemetry_config)?;
+    if let Some(log_dir) = &args.log_dir {
+        let logging = Arc::new(LoggingService::new(LoggingConfig {
+            log_dir: PathBuf::from(log_dir),
+            default_filter: log_filter,
+            subsystem: Some(LogSubsystem::Mcp),
+            ..Default::default()
+        })?);
+        logging.install_global(Some(&telemetry))?;
+    } else {
+        tracing_subscriber::registry()
+            .with(EnvFilter::new(log_filter))
+            .with(tracing_subscriber::fmt::layer()
+                .with_target(false)
+                .with_ansi(false)) // Disable ANSI for clean stdio
+            .with(telemetry.layer())
+            .init();
+    }
 
     info!("RAG MCP Server starting (Version: {})", env!("CARGO_PKG_VERSION"));
     info!("Debug mode: {}", args.debug);
-    info!("Air-gapped mode: {}", args.air_gapped);
+    info!("Air-gapped mode: {}", air_gapped);
     info!("Outbound URL: {}", args.outbound_url);
+    // The Manager is normally on loopback; anything else is egress
+    policy.check_url(NetworkFeature::McpOutbound, &args.outbound_url)?;
+    if let Some(path) = &args.rpc_token_file {
+        let token = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read RPC token {}", path))?;
+        tools::set_rpc_token(token.trim().to_string());
+    }
 
     // Create and run server
-    let server = McpServer::new(args.outbound_url.clone(), args.air_gapped)
+    let mut server = McpServer::new(args.outbound_url.clone(), air_gapped)
         .context("Failed to create MCP server")?;
+    if let Some(scopes) = args.scopes {
+        info!("Granted scopes: {}", scopes);
+        server = server.with_scopes(&scopes)?;
+    }
+    if let Some(path) = args.capabilities {
+        info!("Capabilities: {}", path);
+        server = server.with_capabilities(Capabilities::load(Path::new(&path))?)?;
+    }
+    server = server.with_quota_limits(QuotaLimits {
+        calls_per_minute: args.calls_per_minute,
+        max_concurrent_calls: args.max_concurrent_calls,
+        max_result_bytes: args.max_result_bytes,
+    });
+    if let Some(path) = args.quota_metrics {
+        info!("Quota metrics: {}", path);
+        server = server.with_quota_metrics(path.into());
+    }
+    if let Some(path) = args.audit_db {
+        info!("Audit log: {}", path);
+        let sql_service = SqlService::new(SqlConfig::new_mvp(path)).await
+            .context("Failed to open audit database")?;
+        sql_service.run_migrations().await
+            .context("Failed to migrate audit database")?;
+        server = server.with_audit_log(Arc::new(AuditService::new(Arc::new(sql_service))));
+    }
+    if let Some(path) = args.prompts {
+        info!("Prompt catalog: {}", path);
+        server = server.with_prompt_catalog(path.into());
+    }
+    if let Some(path) = args.tools {
+        info!("Tool catalog: {}", path);
+        server = server.with_tool_catalog(path.into())?;
+    }
 
-    run_stdio_server(server).await
-        .context("MCP server failed")?;
+    let served = match args.listen {
+        Some(listen) => run_network_server(server, &listen, args.max_in_flight).await,
+        None => run_stdio_server(server, args.max_in_flight).await,
+    };
+    served.context("MCP server failed")?;
 
+    if let Err(e) = telemetry.shutdown() {
+        warn!("{}", e);
+    }
     Ok(())
 }
 
@@ -244,13 +933,13 @@ mod tests {
 
     #[tokio::test]
     async fn test_mcp_server_creation() {
-        let server = McpServer::new("http://localhost:3000".to_string(), false);
+        let server = McpServer::new("mock://manager".to_string(), false);
         assert!(server.is_ok());
     }
 
     #[tokio::test]
     async fn test_ping_request() {
-        let server = McpServer::new("http://localhost:3000".to_string(), false).unwrap();
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
 
         let request = McpRequest {
             jsonrpc: "2.0".to_string(),
@@ -271,7 +960,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_invalid_method() {
-        let server = McpServer::new("http://localhost:3000".to_string(), false).unwrap();
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
 
         let request = McpRequest {
             jsonrpc: "2.0".to_string(),
@@ -289,4 +978,363 @@ mod tests {
             _ => panic!("Expected error response"),
         }
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_tool_call_with_progress_token() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap().with_notifier(tx);
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({
+                "name": "kb.hybrid_search",
+                "arguments": {"collection": "test_kb", "query": "test query"},
+                "_meta": {"progressToken": "p-1"}
+            }),
+            id: Some(serde_json::Value::String("test-3".to_string())),
+        };
+
+        let response = server.process_request(request).await;
+        assert!(matches!(response, McpResponse::Success { .. }));
+
+        match rx.try_recv() {
+            Ok(OutgoingMessage::Notification(n)) => assert_eq!(n.params["progressToken"], "p-1"),
+            _ => panic!("Expected progress notification"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resources_read() {
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "resources/read".to_string(),
+            params: serde_json::json!({"uri": "ragstudio://kb/test_kb_1/doc/doc_1"}),
+            id: Some(serde_json::Value::String("test-4".to_string())),
+        };
+        match server.process_request(request).await {
+            McpResponse::Success { result, .. } => {
+                assert_eq!(result["contents"][0]["uri"], "ragstudio://kb/test_kb_1/doc/doc_1");
+                assert_eq!(result["contents"][0]["mimeType"], "text/plain");
+            }
+            _ => panic!("Expected success response"),
+        }
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "resources/read".to_string(),
+            params: serde_json::json!({"uri": "file:///etc/passwd"}),
+            id: Some(serde_json::Value::String("test-5".to_string())),
+        };
+        match server.process_request(request).await {
+            McpResponse::Error { error, .. } => assert_eq!(error.code, -32002),
+            _ => panic!("Expected error response"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_prompts_get() {
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "prompts/get".to_string(),
+            params: serde_json::json!({"name": "summarize-doc", "arguments": {"document": "Some text"}}),
+            id: Some(serde_json::Value::String("test-6".to_string())),
+        };
+        match server.process_request(request).await {
+            McpResponse::Success { result, .. } => {
+                assert!(result["messages"][0]["content"]["text"].as_str().unwrap().ends_with("Some text"));
+            }
+            _ => panic!("Expected success response"),
+        }
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "prompts/get".to_string(),
+            params: serde_json::json!({"name": "missing-prompt"}),
+            id: Some(serde_json::Value::String("test-7".to_string())),
+        };
+        assert!(matches!(server.process_request(request).await, McpResponse::Error { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_tool_catalog_reload() {
+        use rag_core::models::tool_catalog::DynamicToolSpec;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("tools.json");
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap()
+            .with_notifier(tx)
+            .with_tool_catalog(path.clone())
+            .unwrap();
+
+        let catalog = ToolCatalog {
+            tools: vec![DynamicToolSpec {
+                tool_id: "tool_1".to_string(),
+                name: "docs_search".to_string(),
+                description: "Search product docs".to_string(),
+                kb_id: "test_kb".to_string(),
+                top_k: 5,
+                top_n: None,
+                scopes: vec!["kb.read".to_string()],
+            }],
+            updated_at: None,
+        };
+        catalog.save(&path).unwrap();
+        assert!(server.reload_tool_catalog().unwrap());
+        match rx.try_recv() {
+            Ok(OutgoingMessage::Notification(n)) => assert_eq!(n.method, "notifications/tools/list_changed"),
+            _ => panic!("Expected list_changed notification"),
+        }
+
+        let request = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "docs_search", "arguments": {"query": "install"}}),
+            id: Some(serde_json::Value::String("test-8".to_string())),
+        };
+        assert!(matches!(server.process_request(request).await, McpResponse::Success { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_arguments_error_data() {
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
+        let call = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "kb.hybrid_search", "arguments": {"collection": "test_kb", "query": "q", "top_k": 1000}}),
+            id: Some(serde_json::Value::from(1)),
+        };
+        match server.process_request(call).await {
+            McpResponse::Error { error, .. } => {
+                assert_eq!(error.code, -32602);
+                let data = error.data.unwrap();
+                assert_eq!(data["errors"][0]["path"], "/top_k");
+                assert_eq!(data["errors"][0]["keyword"], "maximum");
+            }
+            _ => panic!("Expected invalid params error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_scopes_enforced() {
+        use rag_core::models::tool_catalog::DynamicToolSpec;
+
+        // Air-gapped: a tool needing network access is hidden and denied
+        let server = McpServer::new("mock://manager".to_string(), true).unwrap();
+        server.tool_registry.sync_dynamic_tools(vec![DynamicToolSpec {
+            tool_id: "tool_1".to_string(),
+            name: "web_search".to_string(),
+            description: "Search the web".to_string(),
+            kb_id: "test_kb".to_string(),
+            top_k: 5,
+            top_n: None,
+            scopes: vec!["kb.read".to_string(), "net.fetch".to_string()],
+        }]);
+
+        let list = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/list".to_string(),
+            params: serde_json::Value::Null,
+            id: Some(serde_json::Value::String("test-9".to_string())),
+        };
+        match server.process_request(list).await {
+            McpResponse::Success { result, .. } => assert_eq!(result["tools"].as_array().unwrap().len(), 8),
+            _ => panic!("Expected success response"),
+        }
+
+        let call = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "web_search", "arguments": {"query": "install"}}),
+            id: Some(serde_json::Value::String("test-10".to_string())),
+        };
+        match server.process_request(call).await {
+            McpResponse::Error { error, .. } => assert_eq!(error.code, -32003),
+            _ => panic!("Expected permission error"),
+        }
+
+        // Built-in kb tools are denied when kb.read is not granted
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap()
+            .with_scopes("fs.read").unwrap();
+        let call = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "kb.stats", "arguments": {}}),
+            id: Some(serde_json::Value::String("test-11".to_string())),
+        };
+        assert!(matches!(server.process_request(call).await, McpResponse::Error { .. }));
+    }
+
+    #[tokio::test]
+    async fn test_capabilities_applied() {
+        let capabilities = Capabilities {
+            tools: Some(vec!["kb.*".to_string()]),
+            kbs: Some(vec!["test_kb".to_string()]),
+            scopes: None,
+        };
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap()
+            .with_capabilities(capabilities).unwrap();
+
+        let list = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/list".to_string(),
+            params: serde_json::Value::Null,
+            id: Some(serde_json::Value::String("test-12".to_string())),
+        };
+        match server.process_request(list).await {
+            McpResponse::Success { result, .. } => {
+                let names: Vec<&str> = result["tools"].as_array().unwrap().iter()
+                    .filter_map(|tool| tool["name"].as_str())
+                    .collect();
+                assert!(names.contains(&"kb.hybrid_search"));
+                assert!(!names.contains(&"rag.answer"));
+                assert!(names.contains(&"kb.get_document"));
+                assert!(!names.contains(&"kb.resolve_citations"));
+            }
+            _ => panic!("Expected success response"),
+        }
+
+        let call = |collection: &str| McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "kb.hybrid_search", "arguments": {"collection": collection, "query": "q"}}),
+            id: Some(serde_json::Value::String("test-13".to_string())),
+        };
+        assert!(matches!(server.process_request(call("test_kb")).await, McpResponse::Success { .. }));
+        match server.process_request(call("other_kb")).await {
+            McpResponse::Error { error, .. } => assert_eq!(error.code, -32003),
+            _ => panic!("Expected permission error"),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_quota() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let metrics_path = temp_dir.path().join("quota.json");
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap()
+            .with_quota_limits(QuotaLimits { calls_per_minute: 1, max_concurrent_calls: 1, max_result_bytes: 1_000_000 })
+            .with_quota_metrics(metrics_path.clone());
+
+        let initialize = McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "initialize".to_string(),
+            params: serde_json::json!({"clientInfo": {"name": "test-agent"}}),
+            id: Some(serde_json::Value::from(1)),
+        };
+        server.process_request(initialize).await;
+
+        let call = || McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": "kb.stats", "arguments": {}}),
+            id: Some(serde_json::Value::from(2)),
+        };
+        assert!(matches!(server.process_request(call()).await, McpResponse::Success { .. }));
+        match server.process_request(call()).await {
+            McpResponse::Error { error, .. } => {
+                assert_eq!(error.code, -32005);
+                let data = error.data.unwrap();
+                assert_eq!(data["reason"], "rate_limited");
+                assert_eq!(data["client"], "test-agent");
+            }
+            _ => panic!("Expected quota error"),
+        }
+
+        let metrics = rag_core::models::QuotaMetrics::load(&metrics_path).unwrap();
+        assert_eq!(metrics.clients[0].client_id, "test-agent");
+        assert_eq!((metrics.clients[0].calls_allowed, metrics.clients[0].calls_rejected), (1, 1));
+    }
+
+    #[tokio::test]
+    async fn test_cancel_request() {
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
+        let token = CancellationToken::new();
+        server.stdio.track(&serde_json::json!(42), token.clone());
+
+        let params = serde_json::json!({"requestId": 42, "reason": "user aborted"});
+        let id = protocol::cancelled_request_id("notifications/cancelled", &params).unwrap();
+        assert!(server.cancel_request(&id));
+        assert!(token.is_cancelled());
+
+        let params = serde_json::json!({"id": "other"});
+        let id = protocol::cancelled_request_id("$/cancelRequest", &params).unwrap();
+        assert!(!server.cancel_request(&id));
+        assert!(protocol::cancelled_request_id("tools/call", &params).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_network_sessions_are_isolated() {
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap();
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let first = server.open_session(tx.clone());
+        let second = server.open_session(tx);
+
+        let request = |method: &str, params: Value| McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+            id: Some(serde_json::Value::from(1)),
+        };
+        let call = || request("tools/call", serde_json::json!({"name": "kb.stats", "arguments": {}}));
+
+        // Nothing but ping before the handshake
+        assert!(matches!(server.process_session_request(&first, call()).await, McpResponse::Error { .. }));
+        assert!(matches!(server.process_session_request(&first, request("ping", Value::Null)).await, McpResponse::Success { .. }));
+
+        // Same reported name, separate identities
+        let init = || request("initialize", serde_json::json!({"clientInfo": {"name": "claude"}}));
+        assert!(matches!(server.process_session_request(&first, init()).await, McpResponse::Success { .. }));
+        assert!(matches!(server.process_session_request(&second, init()).await, McpResponse::Success { .. }));
+        assert_ne!(first.client_id(), second.client_id());
+        assert!(matches!(server.process_session_request(&first, init()).await, McpResponse::Error { .. }));
+        assert!(matches!(server.process_session_request(&first, call()).await, McpResponse::Success { .. }));
+
+        // Cancellations only reach the session that owns the call
+        let token = CancellationToken::new();
+        first.track(&serde_json::json!(9), token.clone());
+        assert!(!second.cancel_request(&serde_json::json!(9)));
+        assert!(!token.is_cancelled());
+
+        server.close_session(&first);
+        assert!(token.is_cancelled());
+        assert_eq!(server.sessions.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_tool_calls_audited() {
+        use rag_core::modules::audit::AuditLogFilter;
+
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let sql_service = SqlService::new(SqlConfig::new_mvp(temp_dir.path().join("audit.db"))).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let audit = Arc::new(AuditService::new(Arc::new(sql_service)));
+        let server = McpServer::new("mock://manager".to_string(), false).unwrap()
+            .with_audit_log(audit.clone());
+
+        let call = |name: &str, id: i64| McpRequest {
+            jsonrpc: "2.0".to_string(),
+            method: "tools/call".to_string(),
+            params: serde_json::json!({"name": name, "arguments": {"collection": "test_kb", "token": "secret"}}),
+            id: Some(serde_json::Value::from(id)),
+        };
+        let mut stats = call("kb.stats", 1);
+        stats.params["arguments"] = serde_json::json!({"collection": "test_kb"});
+        server.process_request(stats).await;
+        server.process_request(call("kb.unknown", 2)).await;
+
+        let entries = audit.query(&AuditLogFilter::default()).await.unwrap();
+        assert_eq!(entries.len(), 2);
+        let ok = entries.iter().find(|e| e.tool_name == "kb.stats").unwrap();
+        assert!(ok.success && ok.result_bytes > 0);
+        assert_eq!(ok.caller, session::DEFAULT_CLIENT_ID);
+        let failed = entries.iter().find(|e| e.tool_name == "kb.unknown").unwrap();
+        assert_eq!(failed.arguments["token"], "[REDACTED]");
+        assert!(!failed.success);
+        assert!(failed.error.as_deref().unwrap().contains("Unknown tool"));
+    }
+}
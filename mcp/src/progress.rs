@@ -0,0 +1,99 @@
+/*!
+ * MCP Progress Reporting
+ *
+ * Sends `notifications/progress` (with optional partial results) for
+ * long-running tool calls that carry a `_meta.progressToken`.
+ * MVP: notifications share the stdout writer with responses.
+ */
+
+use serde_json::{json, Value};
+use tokio::sync::mpsc::UnboundedSender;
+use tracing::debug;
+
+use crate::protocol::{McpNotification, OutgoingMessage, ToolContent};
+
+/// Per-call progress reporter (no-op without a token or notifier)
+#[derive(Clone)]
+pub struct ProgressReporter {
+    token: Option<Value>,
+    sender: Option<UnboundedSender<OutgoingMessage>>,
+}
+
+impl ProgressReporter {
+    pub fn new(token: Option<Value>, sender: Option<UnboundedSender<OutgoingMessage>>) -> Self {
+        Self { token, sender }
+    }
+
+    /// True when the client asked for progress and a notifier is attached
+    pub fn is_enabled(&self) -> bool {
+        self.token.is_some() && self.sender.is_some()
+    }
+
+    /// Report progress (`total` is optional when unknown)
+    pub fn progress(&self, progress: u64, total: Option<u64>, message: Option<&str>) {
+        self.send(progress, total, message, None);
+    }
+
+    /// Report progress with a partial result the client can render early
+    pub fn partial(&self, progress: u64, total: Option<u64>, content: Vec<ToolContent>) {
+        self.send(progress, total, None, Some(json!({ "content": content })));
+    }
+
+    fn send(&self, progress: u64, total: Option<u64>, message: Option<&str>, partial: Option<Value>) {
+        let (Some(token), Some(sender)) = (&self.token, &self.sender) else {
+            return;
+        };
+
+        let mut notification = McpNotification::progress(token, progress, total, message);
+        if let (Some(partial), Some(params)) = (partial, notification.params.as_object_mut()) {
+            params.insert("partialResult".to_string(), partial);
+        }
+
+        // Receiver is gone only when the server is shutting down
+        if sender.send(OutgoingMessage::Notification(notification)).is_err() {
+            debug!("Dropping progress notification, writer closed");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::sync::mpsc;
+
+    #[test]
+    fn test_disabled_reporter_is_noop() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let reporter = ProgressReporter::new(None, Some(tx));
+        assert!(!reporter.is_enabled());
+
+        reporter.progress(1, Some(2), Some("half"));
+        assert!(rx.try_recv().is_err());
+        ProgressReporter::new(Some(json!(1)), None).progress(1, None, None);
+    }
+
+    #[test]
+    fn test_progress_and_partial_notifications() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let reporter = ProgressReporter::new(Some(json!("tok-1")), Some(tx));
+
+        reporter.progress(1, Some(3), Some("searching"));
+        reporter.partial(2, Some(3), vec![ToolContent::text("first batch")]);
+
+        match rx.try_recv().unwrap() {
+            OutgoingMessage::Notification(n) => {
+                assert_eq!(n.method, "notifications/progress");
+                assert_eq!(n.params["progressToken"], "tok-1");
+                assert_eq!(n.params["message"], "searching");
+            }
+            _ => panic!("Expected notification"),
+        }
+        match rx.try_recv().unwrap() {
+            OutgoingMessage::Notification(n) => {
+                assert_eq!(n.params["progress"], 2);
+                assert_eq!(n.params["partialResult"]["content"][0]["text"], "first batch");
+            }
+            _ => panic!("Expected notification"),
+        }
+    }
+}
@@ -0,0 +1,223 @@
+/*!
+ * MCP Prompts
+ *
+ * Serves the user-authored prompt catalog written by the Manager (see
+ * `rag_core::models::prompt`). Arguments are validated against each prompt's
+ * JSON schema; `{{context}}` is filled with cited passages from a KB search.
+ * MVP: catalog file re-read on every request, upgrade path to change notifications.
+ */
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use anyhow::{Result, anyhow};
+use jsonschema::JSONSchema;
+use serde_json::{json, Value};
+use tracing::{debug, warn};
+
+use rag_core::models::prompt::{builtin_prompts, PromptCatalog, PromptTemplate, CONTEXT_PLACEHOLDER};
+use crate::tools::call_outbound_rpc;
+
+/// Passages retrieved for `{{context}}`
+const CONTEXT_TOP_K: usize = 5;
+
+/// Argument naming the KB used for `{{context}}`
+const COLLECTION_ARGUMENT: &str = "collection";
+
+/// Argument used as the retrieval query, in order of preference
+const QUERY_ARGUMENTS: &[&str] = &["question", "query", "topic"];
+
+/// Prompt registry backed by the shared catalog file
+pub struct PromptRegistry {
+    catalog_path: Option<PathBuf>,
+}
+
+impl PromptRegistry {
+    pub fn new(catalog_path: Option<PathBuf>) -> Self {
+        Self { catalog_path }
+    }
+
+    /// Current catalog (built-ins when no file is configured or it is unreadable)
+    fn catalog(&self) -> PromptCatalog {
+        match &self.catalog_path {
+            Some(path) => PromptCatalog::load(path).unwrap_or_else(|e| {
+                warn!("Failed to load prompt catalog {}: {}", path.display(), e);
+                PromptCatalog { prompts: builtin_prompts() }
+            }),
+            None => PromptCatalog { prompts: builtin_prompts() },
+        }
+    }
+
+    /// Enabled prompts in MCP `prompts/list` shape
+    pub fn list_prompts(&self) -> Vec<Value> {
+        let mut prompts: Vec<PromptTemplate> = self.catalog().prompts.into_iter()
+            .filter(|p| p.enabled)
+            .collect();
+        prompts.sort_by(|a, b| a.name.cmp(&b.name));
+
+        prompts.iter()
+            .map(|p| json!({
+                "name": p.name,
+                "description": p.description,
+                "arguments": p.arguments,
+            }))
+            .collect()
+    }
+
+    /// KB a prompt retrieves `{{context}}` from (None when it doesn't use context)
+    pub fn context_kb(&self, name: &str, arguments: &Value) -> Option<String> {
+        let catalog = self.catalog();
+        let prompt = catalog.get(name).filter(|prompt| prompt.uses_context())?;
+        arguments.get(COLLECTION_ARGUMENT)
+            .map(|v| v.as_str().map(String::from).unwrap_or_else(|| v.to_string()))
+            .or_else(|| prompt.kb_id.clone())
+    }
+
+    /// Validate arguments and render a prompt into MCP `prompts/get` messages
+    pub async fn get_prompt(&self, name: &str, arguments: &Value, outbound_url: &str) -> Result<Value> {
+        let catalog = self.catalog();
+        let prompt = catalog.get(name)
+            .ok_or_else(|| anyhow!("Unknown prompt: {}", name))?;
+
+        let arguments = if arguments.is_null() { json!({}) } else { arguments.clone() };
+        validate_arguments(&prompt.schema(), &arguments)?;
+
+        // MCP prompt arguments are strings; render non-strings as JSON
+        let mut args: HashMap<String, String> = arguments.as_object()
+            .map(|map| map.iter()
+                .map(|(k, v)| (k.clone(), v.as_str().map(String::from).unwrap_or_else(|| v.to_string())))
+                .collect())
+            .unwrap_or_default();
+
+        if prompt.uses_context() {
+            let context = self.retrieve_context(prompt, &args, outbound_url).await?;
+            args.insert(CONTEXT_PLACEHOLDER.to_string(), context);
+        }
+
+        let text = prompt.render(&args).map_err(|e| anyhow!("{}", e))?;
+        debug!("Rendered prompt {} ({} chars)", name, text.len());
+
+        Ok(json!({
+            "description": prompt.description,
+            "messages": [
+                {
+                    "role": "user",
+                    "content": { "type": "text", "text": text }
+                }
+            ]
+        }))
+    }
+
+    /// Numbered passages with citations from the prompt's KB
+    async fn retrieve_context(
+        &self,
+        prompt: &PromptTemplate,
+        args: &HashMap<String, String>,
+        outbound_url: &str,
+    ) -> Result<String> {
+        let collection = args.get(COLLECTION_ARGUMENT).cloned()
+            .or_else(|| prompt.kb_id.clone())
+            .ok_or_else(|| anyhow!("Prompt {} needs a '{}' argument or a default KB", prompt.name, COLLECTION_ARGUMENT))?;
+        let query = QUERY_ARGUMENTS.iter()
+            .find_map(|name| args.get(*name))
+            .ok_or_else(|| anyhow!("Prompt {} has no question/query argument to retrieve with", prompt.name))?;
+
+        let response = call_outbound_rpc(outbound_url, json!({
+            "method": "kb.hybrid_search",
+            "params": {
+                "collection": collection,
+                "query": query,
+                "top_k": CONTEXT_TOP_K
+            }
+        })).await?;
+
+        let passages: Vec<String> = response.get("results").and_then(|v| v.as_array()).into_iter().flatten()
+            .enumerate()
+            .map(|(i, result)| {
+                let content = result.get("content").and_then(|v| v.as_str()).unwrap_or("");
+                let citation = result.get("citation");
+                let title = citation.and_then(|c| c.get("title")).and_then(|v| v.as_str()).unwrap_or("Untitled");
+                let source = citation.and_then(|c| c.get("source_path")).and_then(|v| v.as_str()).unwrap_or("");
+                format!("[{}] {} ({})\n{}", i + 1, title, source, content)
+            })
+            .collect();
+
+        if passages.is_empty() {
+            Ok("(no passages found)".to_string())
+        } else {
+            Ok(passages.join("\n\n"))
+        }
+    }
+}
+
+/// Validate prompt arguments against a JSON schema
+fn validate_arguments(schema: &Value, arguments: &Value) -> Result<()> {
+    let compiled = JSONSchema::compile(schema)
+        .map_err(|e| anyhow!("Invalid prompt schema: {}", e))?;
+    let result = compiled.validate(arguments);
+    if let Err(errors) = result {
+        let messages: Vec<String> = errors.map(|e| e.to_string()).collect();
+        return Err(anyhow!("Invalid arguments: {}", messages.join("; ")));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rag_core::models::prompt::PromptArgument;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_list_builtin_prompts() {
+        let registry = PromptRegistry::new(None);
+        let prompts = registry.list_prompts();
+        assert_eq!(prompts.len(), 2);
+        assert_eq!(prompts[0]["name"], "answer-with-citations");
+        assert_eq!(prompts[0]["arguments"][0]["name"], "question");
+    }
+
+    #[tokio::test]
+    async fn test_get_prompt_with_context() {
+        let registry = PromptRegistry::new(None);
+        let result = registry.get_prompt(
+            "answer-with-citations",
+            &json!({"question": "What is RAG?", "collection": "test_kb"}),
+            "mock://manager",
+        ).await.unwrap();
+
+        let text = result["messages"][0]["content"]["text"].as_str().unwrap();
+        assert!(text.contains("Question: What is RAG?"));
+        assert!(text.contains("[1] Sample Document (/documents/sample.md)"));
+
+        // Schema rejects unknown and missing arguments
+        let err = registry.get_prompt("summarize-doc", &json!({"doc": "x"}), "mock://manager").await;
+        assert!(err.is_err());
+        let err = registry.get_prompt("answer-with-citations", &json!({"question": "q"}), "mock://manager").await;
+        assert!(err.unwrap_err().to_string().contains("collection"));
+    }
+
+    #[tokio::test]
+    async fn test_catalog_file_prompts() {
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("prompts.json");
+
+        let mut custom = PromptTemplate::new("release-notes", "Release notes", "Write notes for {{version}}",
+            vec![PromptArgument::required("version", "Version")]);
+        custom.arguments_schema = Some(json!({
+            "type": "object",
+            "properties": {"version": {"type": "string", "pattern": "^\\d+\\.\\d+$"}},
+            "required": ["version"]
+        }));
+        let mut disabled = PromptTemplate::new("hidden", "Hidden", "text", vec![]);
+        disabled.enabled = false;
+        PromptCatalog { prompts: vec![custom, disabled] }.save(&path).unwrap();
+
+        let registry = PromptRegistry::new(Some(path));
+        assert_eq!(registry.list_prompts().len(), 1);
+
+        let result = registry.get_prompt("release-notes", &json!({"version": "2.1"}), "").await.unwrap();
+        assert_eq!(result["messages"][0]["content"]["text"], "Write notes for 2.1");
+        assert!(registry.get_prompt("release-notes", &json!({"version": "latest"}), "").await.is_err());
+        assert!(registry.get_prompt("hidden", &json!({}), "").await.is_err());
+    }
+}
@@ -8,6 +8,7 @@
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::collections::HashMap;
+use rag_core::services::telemetry::{TraceContext, TRACEPARENT_KEY};
 
 /// JSON-RPC 2.0 Request
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -52,6 +53,76 @@ impl McpResponse {
     }
 }
 
+/// JSON-RPC 2.0 Notification (no id, no response)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct McpNotification {
+    pub jsonrpc: String,
+    pub method: String,
+    pub params: Value,
+}
+
+impl McpNotification {
+    pub fn new(method: &str, params: Value) -> Self {
+        Self {
+            jsonrpc: "2.0".to_string(),
+            method: method.to_string(),
+            params,
+        }
+    }
+
+    /// MCP `notifications/progress`
+    pub fn progress(token: &Value, progress: u64, total: Option<u64>, message: Option<&str>) -> Self {
+        let mut params = serde_json::json!({
+            "progressToken": token,
+            "progress": progress,
+        });
+        if let Some(total) = total {
+            params["total"] = Value::from(total);
+        }
+        if let Some(message) = message {
+            params["message"] = Value::from(message);
+        }
+        Self::new("notifications/progress", params)
+    }
+}
+
+/// Message written to the client (responses and notifications share stdout)
+#[derive(Debug, Clone, Serialize)]
+#[serde(untagged)]
+pub enum OutgoingMessage {
+    Response(McpResponse),
+    Notification(McpNotification),
+}
+
+/// Progress token from request params (`_meta.progressToken`)
+pub fn progress_token(params: &Value) -> Option<Value> {
+    params.get("_meta")
+        .and_then(|meta| meta.get("progressToken"))
+        .filter(|token| token.is_string() || token.is_number())
+        .cloned()
+}
+
+/// Caller's trace context from request params (`_meta.traceparent`)
+pub fn trace_context(params: &Value) -> Option<TraceContext> {
+    params.get("_meta")
+        .and_then(|meta| meta.get(TRACEPARENT_KEY))
+        .and_then(|traceparent| traceparent.as_str())
+        .and_then(TraceContext::parse)
+}
+
+/// Id of the request a cancellation targets (`$/cancelRequest` uses `id`,
+/// MCP `notifications/cancelled` uses `requestId`); None for other methods
+pub fn cancelled_request_id(method: &str, params: &Value) -> Option<Value> {
+    let field = match method {
+        "$/cancelRequest" => "id",
+        "notifications/cancelled" => "requestId",
+        _ => return None,
+    };
+    params.get(field)
+        .filter(|id| id.is_string() || id.is_number())
+        .cloned()
+}
+
 /// JSON-RPC 2.0 Error
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JsonRpcError {
@@ -93,6 +164,15 @@ impl JsonRpcError {
         }
     }
 
+    /// Arguments that fail the tool's input schema; `data` lists each violation
+    pub fn invalid_arguments(message: &str, data: Value) -> Self {
+        Self {
+            code: -32602,
+            message: format!("Invalid params: {}", message),
+            data: Some(data),
+        }
+    }
+
     pub fn internal_error(message: &str) -> Self {
         Self {
             code: -32603,
@@ -101,6 +181,38 @@ impl JsonRpcError {
         }
     }
 
+    pub fn resource_not_found(uri: &str) -> Self {
+        Self {
+            code: -32002,
+            message: "Resource not found".to_string(),
+            data: Some(Value::String(uri.to_string())),
+        }
+    }
+
+    pub fn permission_denied(message: &str) -> Self {
+        Self {
+            code: -32003,
+            message: "Permission denied".to_string(),
+            data: Some(Value::String(message.to_string())),
+        }
+    }
+
+    pub fn quota_exceeded(message: &str, data: Value) -> Self {
+        Self {
+            code: -32005,
+            message: format!("Quota exceeded: {}", message),
+            data: Some(data),
+        }
+    }
+
+    pub fn request_cancelled() -> Self {
+        Self {
+            code: -32800,
+            message: "Request cancelled".to_string(),
+            data: None,
+        }
+    }
+
     pub fn tool_error(message: &str) -> Self {
         Self {
             code: -32000,
@@ -182,6 +294,36 @@ impl ToolDefinition {
     }
 }
 
+/// Resource definition (MCP standard)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceDefinition {
+    pub uri: String,
+    pub name: String,
+    pub description: Option<String>,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+}
+
+/// Resource template for parameterized URIs (MCP standard)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceTemplate {
+    #[serde(rename = "uriTemplate")]
+    pub uri_template: String,
+    pub name: String,
+    pub description: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+}
+
+/// Resource contents returned by `resources/read` (MCP standard)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceContents {
+    pub uri: String,
+    #[serde(rename = "mimeType")]
+    pub mime_type: String,
+    pub text: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -237,4 +379,29 @@ mod tests {
         assert_eq!(result.isError, Some(false));
         assert_eq!(result.content.len(), 2);
     }
+
+    #[test]
+    fn test_progress_notification() {
+        let params = serde_json::json!({"name": "kb.hybrid_search", "_meta": {"progressToken": 7}});
+        let token = progress_token(&params).unwrap();
+        assert!(progress_token(&serde_json::json!({"name": "kb.stats"})).is_none());
+
+        let notification = McpNotification::progress(&token, 1, Some(4), None);
+        let json = serde_json::to_string(&OutgoingMessage::Notification(notification)).unwrap();
+        assert!(json.contains("\"method\":\"notifications/progress\""));
+        assert!(json.contains("\"progressToken\":7"));
+        assert!(!json.contains("\"id\""));
+    }
+
+    #[test]
+    fn test_trace_context_from_meta() {
+        let traceparent = "00-4bf92f3577b34da6a3ce929d0e0e4736-00f067aa0ba902b7-01";
+        let params = serde_json::json!({"name": "kb.search", "_meta": {"traceparent": traceparent}});
+        let context = trace_context(&params).unwrap();
+        assert_eq!(context.trace_id, "4bf92f3577b34da6a3ce929d0e0e4736");
+        assert_eq!(context.to_traceparent(), traceparent);
+
+        assert!(trace_context(&serde_json::json!({"_meta": {"traceparent": "garbage"}})).is_none());
+        assert!(trace_context(&serde_json::json!({"name": "kb.search"})).is_none());
+    }
 }
\ No newline at end of file
@@ -0,0 +1,216 @@
+/*!
+ * Rate Limiting and Quotas
+ *
+ * Token-bucket limiter keyed by client/session. Each client gets
+ * `calls_per_minute` tokens refilled continuously, a cap on concurrent calls
+ * and a maximum serialized result size. Rejections carry a reason and a
+ * retry hint so clients can back off.
+ * MVP: in-memory buckets, same limits for every client.
+ */
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use chrono::Utc;
+use serde_json::{json, Value};
+
+use rag_core::models::mcp_quota::{ClientQuotaUsage, QuotaLimits, QuotaMetrics};
+
+/// Why a call was rejected
+#[derive(Debug, Clone, PartialEq)]
+pub enum QuotaExceeded {
+    RateLimited { retry_after_ms: u64 },
+    TooManyConcurrent { limit: u32 },
+    ResultTooLarge { bytes: usize, limit: usize },
+}
+
+impl QuotaExceeded {
+    /// Structured JSON-RPC error data
+    pub fn to_error_data(&self, client_id: &str) -> Value {
+        match self {
+            QuotaExceeded::RateLimited { retry_after_ms } => json!({
+                "reason": "rate_limited",
+                "client": client_id,
+                "retryAfterMs": retry_after_ms,
+            }),
+            QuotaExceeded::TooManyConcurrent { limit } => json!({
+                "reason": "too_many_concurrent_calls",
+                "client": client_id,
+                "limit": limit,
+            }),
+            QuotaExceeded::ResultTooLarge { bytes, limit } => json!({
+                "reason": "result_too_large",
+                "client": client_id,
+                "bytes": bytes,
+                "limit": limit,
+            }),
+        }
+    }
+}
+
+impl std::fmt::Display for QuotaExceeded {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            QuotaExceeded::RateLimited { retry_after_ms } => write!(f, "Rate limit exceeded, retry in {}ms", retry_after_ms),
+            QuotaExceeded::TooManyConcurrent { limit } => write!(f, "Too many concurrent calls (max: {})", limit),
+            QuotaExceeded::ResultTooLarge { bytes, limit } => write!(f, "Result too large: {} bytes (max: {})", bytes, limit),
+        }
+    }
+}
+
+/// Bucket and counters for one client
+struct ClientBucket {
+    tokens: f64,
+    refilled_at: Instant,
+    usage: ClientQuotaUsage,
+}
+
+impl ClientBucket {
+    fn new(client_id: &str, capacity: f64) -> Self {
+        Self {
+            tokens: capacity,
+            refilled_at: Instant::now(),
+            usage: ClientQuotaUsage { client_id: client_id.to_string(), ..Default::default() },
+        }
+    }
+
+    fn refill(&mut self, capacity: f64, per_second: f64) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.refilled_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_second).min(capacity);
+        self.refilled_at = now;
+    }
+}
+
+/// Per-client rate limiter
+pub struct RateLimiter {
+    limits: QuotaLimits,
+    buckets: Arc<Mutex<HashMap<String, ClientBucket>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: QuotaLimits) -> Self {
+        Self {
+            limits,
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    fn capacity(&self) -> f64 {
+        self.limits.calls_per_minute.max(1) as f64
+    }
+
+    /// Take a token and a concurrency slot; the slot is released when the permit drops
+    pub fn acquire(&self, client_id: &str) -> Result<CallPermit, QuotaExceeded> {
+        let capacity = self.capacity();
+        let per_second = capacity / 60.0;
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.entry(client_id.to_string())
+            .or_insert_with(|| ClientBucket::new(client_id, capacity));
+        bucket.refill(capacity, per_second);
+
+        if bucket.usage.in_flight >= self.limits.max_concurrent_calls {
+            bucket.usage.calls_rejected += 1;
+            return Err(QuotaExceeded::TooManyConcurrent { limit: self.limits.max_concurrent_calls });
+        }
+        if bucket.tokens < 1.0 {
+            bucket.usage.calls_rejected += 1;
+            let retry_after_ms = ((1.0 - bucket.tokens) / per_second * 1000.0).ceil() as u64;
+            return Err(QuotaExceeded::RateLimited { retry_after_ms });
+        }
+
+        bucket.tokens -= 1.0;
+        bucket.usage.calls_allowed += 1;
+        bucket.usage.in_flight += 1;
+        bucket.usage.last_call_at = Some(Utc::now());
+
+        Ok(CallPermit {
+            client_id: client_id.to_string(),
+            buckets: self.buckets.clone(),
+        })
+    }
+
+    /// Check a serialized result against the size limit
+    pub fn check_result_size(&self, client_id: &str, bytes: usize) -> Result<(), QuotaExceeded> {
+        if bytes <= self.limits.max_result_bytes {
+            return Ok(());
+        }
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(client_id) {
+            bucket.usage.results_truncated += 1;
+        }
+        Err(QuotaExceeded::ResultTooLarge { bytes, limit: self.limits.max_result_bytes })
+    }
+
+    /// Snapshot for the dashboard
+    pub fn metrics(&self) -> QuotaMetrics {
+        let capacity = self.capacity();
+        let mut buckets = self.buckets.lock().unwrap();
+        let mut clients: Vec<ClientQuotaUsage> = buckets.values_mut()
+            .map(|bucket| {
+                bucket.refill(capacity, capacity / 60.0);
+                ClientQuotaUsage { tokens_remaining: bucket.tokens, ..bucket.usage.clone() }
+            })
+            .collect();
+        clients.sort_by(|a, b| a.client_id.cmp(&b.client_id));
+
+        QuotaMetrics {
+            limits: self.limits.clone(),
+            clients,
+            updated_at: Some(Utc::now()),
+        }
+    }
+}
+
+/// Held for the duration of a call
+pub struct CallPermit {
+    client_id: String,
+    buckets: Arc<Mutex<HashMap<String, ClientBucket>>>,
+}
+
+impl Drop for CallPermit {
+    fn drop(&mut self) {
+        if let Some(bucket) = self.buckets.lock().unwrap().get_mut(&self.client_id) {
+            bucket.usage.in_flight = bucket.usage.in_flight.saturating_sub(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(QuotaLimits { calls_per_minute: 2, max_concurrent_calls: 10, max_result_bytes: 100 });
+
+        drop(limiter.acquire("a").unwrap());
+        drop(limiter.acquire("a").unwrap());
+        match limiter.acquire("a") {
+            Err(QuotaExceeded::RateLimited { retry_after_ms }) => assert!(retry_after_ms > 0 && retry_after_ms <= 30_000),
+            _ => panic!("Expected rate limit"),
+        }
+        // Buckets are per client
+        assert!(limiter.acquire("b").is_ok());
+
+        let metrics = limiter.metrics();
+        assert_eq!(metrics.clients[0].calls_allowed, 2);
+        assert_eq!(metrics.clients[0].calls_rejected, 1);
+    }
+
+    #[test]
+    fn test_concurrency_and_result_size() {
+        let limiter = RateLimiter::new(QuotaLimits { calls_per_minute: 100, max_concurrent_calls: 1, max_result_bytes: 100 });
+
+        let permit = limiter.acquire("a").unwrap();
+        assert_eq!(limiter.acquire("a").err(), Some(QuotaExceeded::TooManyConcurrent { limit: 1 }));
+        assert_eq!(limiter.metrics().clients[0].in_flight, 1);
+        drop(permit);
+        assert!(limiter.acquire("a").is_ok());
+
+        assert!(limiter.check_result_size("a", 100).is_ok());
+        let err = limiter.check_result_size("a", 101).unwrap_err();
+        assert_eq!(err.to_error_data("a")["reason"], "result_too_large");
+        assert_eq!(limiter.metrics().clients[0].results_truncated, 1);
+    }
+}
@@ -0,0 +1,330 @@
+/*!
+ * MCP Resources
+ *
+ * Exposes knowledge bases, documents and chunks as browsable MCP resources:
+ * - `ragstudio://kb/{kb_id}`                             KB stats (JSON)
+ * - `ragstudio://kb/{kb_id}/doc/{doc_id}`                document text
+ * - `ragstudio://kb/{kb_id}/doc/{doc_id}/chunk/{chunk_id}` chunk text
+ *
+ * MVP: read-only, no subscriptions; data comes through the outbound RPC.
+ */
+
+use std::fmt;
+use anyhow::{Result, anyhow};
+use serde_json::{json, Value};
+use tracing::debug;
+
+use crate::protocol::{ResourceContents, ResourceDefinition, ResourceTemplate};
+use crate::tools::call_outbound_rpc;
+
+pub const URI_SCHEME: &str = "ragstudio://";
+
+const MAX_URI_LENGTH: usize = 512;
+const MIME_JSON: &str = "application/json";
+const MIME_TEXT: &str = "text/plain";
+
+/// Parsed `ragstudio://` resource URI
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResourceUri {
+    KnowledgeBase { kb_id: String },
+    Document { kb_id: String, doc_id: String },
+    Chunk { kb_id: String, doc_id: String, chunk_id: String },
+}
+
+impl ResourceUri {
+    pub fn parse(uri: &str) -> Result<Self> {
+        if uri.len() > MAX_URI_LENGTH {
+            return Err(anyhow!("Resource URI too long"));
+        }
+        let path = uri.strip_prefix(URI_SCHEME)
+            .ok_or_else(|| anyhow!("Unsupported resource URI: {}", uri))?;
+
+        let segments: Vec<&str> = path.split('/').collect();
+        if segments.iter().skip(1).step_by(2).any(|id| !is_valid_id(id)) {
+            return Err(anyhow!("Invalid identifier in resource URI: {}", uri));
+        }
+
+        match segments.as_slice() {
+            ["kb", kb_id] => Ok(Self::KnowledgeBase { kb_id: kb_id.to_string() }),
+            ["kb", kb_id, "doc", doc_id] => Ok(Self::Document {
+                kb_id: kb_id.to_string(),
+                doc_id: doc_id.to_string(),
+            }),
+            ["kb", kb_id, "doc", doc_id, "chunk", chunk_id] => Ok(Self::Chunk {
+                kb_id: kb_id.to_string(),
+                doc_id: doc_id.to_string(),
+                chunk_id: chunk_id.to_string(),
+            }),
+            _ => Err(anyhow!("Unknown resource: {}", uri)),
+        }
+    }
+
+    /// KB the resource belongs to
+    pub fn kb_id(&self) -> &str {
+        match self {
+            Self::KnowledgeBase { kb_id } | Self::Document { kb_id, .. } | Self::Chunk { kb_id, .. } => kb_id,
+        }
+    }
+}
+
+impl fmt::Display for ResourceUri {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::KnowledgeBase { kb_id } => write!(f, "{}kb/{}", URI_SCHEME, kb_id),
+            Self::Document { kb_id, doc_id } => write!(f, "{}kb/{}/doc/{}", URI_SCHEME, kb_id, doc_id),
+            Self::Chunk { kb_id, doc_id, chunk_id } => {
+                write!(f, "{}kb/{}/doc/{}/chunk/{}", URI_SCHEME, kb_id, doc_id, chunk_id)
+            }
+        }
+    }
+}
+
+/// Same character set as collection names in tool validation
+fn is_valid_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= 100
+        && id.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-')
+}
+
+/// Resource provider backed by the Manager's outbound RPC
+pub struct ResourceProvider;
+
+impl ResourceProvider {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// URI templates clients can fill in without listing
+    pub fn list_templates(&self) -> Vec<ResourceTemplate> {
+        vec![
+            ResourceTemplate {
+                uri_template: format!("{}kb/{{kb_id}}", URI_SCHEME),
+                name: "Knowledge base".to_string(),
+                description: "Knowledge base statistics and health".to_string(),
+                mime_type: MIME_JSON.to_string(),
+            },
+            ResourceTemplate {
+                uri_template: format!("{}kb/{{kb_id}}/doc/{{doc_id}}", URI_SCHEME),
+                name: "Document".to_string(),
+                description: "Full text of a knowledge base document".to_string(),
+                mime_type: MIME_TEXT.to_string(),
+            },
+            ResourceTemplate {
+                uri_template: format!("{}kb/{{kb_id}}/doc/{{doc_id}}/chunk/{{chunk_id}}", URI_SCHEME),
+                name: "Chunk".to_string(),
+                description: "Text of a single indexed chunk".to_string(),
+                mime_type: MIME_TEXT.to_string(),
+            },
+        ]
+    }
+
+    /// List knowledge bases and their documents (chunks are read-only via templates)
+    pub async fn list_resources(&self, outbound_url: &str) -> Result<Vec<ResourceDefinition>> {
+        let response = call_outbound_rpc(outbound_url, json!({
+            "method": "kb.list_collections",
+            "params": {}
+        })).await?;
+
+        let mut resources = Vec::new();
+        for kb in response.get("collections").and_then(|v| v.as_array()).into_iter().flatten() {
+            let Some(kb_id) = kb.get("id").and_then(|v| v.as_str()).filter(|id| is_valid_id(id)) else {
+                continue;
+            };
+            let kb_name = kb.get("name").and_then(|v| v.as_str()).unwrap_or(kb_id);
+
+            resources.push(ResourceDefinition {
+                uri: ResourceUri::KnowledgeBase { kb_id: kb_id.to_string() }.to_string(),
+                name: kb_name.to_string(),
+                description: kb.get("description").and_then(|v| v.as_str()).map(String::from),
+                mime_type: MIME_JSON.to_string(),
+            });
+
+            let documents = call_outbound_rpc(outbound_url, json!({
+                "method": "kb.list_documents",
+                "params": { "collection": kb_id }
+            })).await?;
+
+            for doc in documents.get("documents").and_then(|v| v.as_array()).into_iter().flatten() {
+                let Some(doc_id) = doc.get("id").and_then(|v| v.as_str()).filter(|id| is_valid_id(id)) else {
+                    continue;
+                };
+                resources.push(ResourceDefinition {
+                    uri: ResourceUri::Document {
+                        kb_id: kb_id.to_string(),
+                        doc_id: doc_id.to_string(),
+                    }.to_string(),
+                    name: doc.get("title").and_then(|v| v.as_str()).unwrap_or(doc_id).to_string(),
+                    description: doc.get("source_path").and_then(|v| v.as_str()).map(String::from),
+                    mime_type: MIME_TEXT.to_string(),
+                });
+            }
+        }
+
+        debug!("Listed {} resources", resources.len());
+        Ok(resources)
+    }
+
+    /// Read a single resource by URI
+    pub async fn read_resource(&self, uri: &str, outbound_url: &str) -> Result<ResourceContents> {
+        let parsed = ResourceUri::parse(uri)?;
+
+        let (mime_type, text) = match &parsed {
+            ResourceUri::KnowledgeBase { kb_id } => {
+                let stats = call_outbound_rpc(outbound_url, json!({
+                    "method": "kb.stats",
+                    "params": { "collection": kb_id }
+                })).await?;
+                (MIME_JSON, serde_json::to_string_pretty(&stats)?)
+            }
+            ResourceUri::Document { kb_id, doc_id } => {
+                let doc = call_outbound_rpc(outbound_url, json!({
+                    "method": "kb.get_document",
+                    "params": { "collection": kb_id, "doc_id": doc_id }
+                })).await?;
+                (MIME_TEXT, content_text(&doc, uri)?)
+            }
+            ResourceUri::Chunk { kb_id, doc_id, chunk_id } => {
+                let chunk = call_outbound_rpc(outbound_url, json!({
+                    "method": "kb.get_chunk",
+                    "params": { "collection": kb_id, "doc_id": doc_id, "chunk_id": chunk_id }
+                })).await?;
+                (MIME_TEXT, content_text(&chunk, uri)?)
+            }
+        };
+
+        Ok(ResourceContents {
+            uri: parsed.to_string(),
+            mime_type: mime_type.to_string(),
+            text,
+        })
+    }
+}
+
+impl Default for ResourceProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn content_text(response: &Value, uri: &str) -> Result<String> {
+    response.get("content")
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow!("Resource not found: {}", uri))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use rag_core::modules::kb::{KbCreateConfig, KbRpcHandler, KbService, KbServiceImpl};
+    use rag_core::services::sql::{SqlConfig, SqlService};
+    use rag_core::services::vector::{VectorDbConfig, VectorDbService};
+    use rag_core::state::StateManager;
+
+    #[test]
+    fn test_uri_roundtrip() {
+        let uri = "ragstudio://kb/kb_1/doc/doc-2/chunk/chunk_3";
+        let parsed = ResourceUri::parse(uri).unwrap();
+        assert_eq!(parsed, ResourceUri::Chunk {
+            kb_id: "kb_1".to_string(),
+            doc_id: "doc-2".to_string(),
+            chunk_id: "chunk_3".to_string(),
+        });
+        assert_eq!(parsed.to_string(), uri);
+
+        assert!(ResourceUri::parse("ragstudio://kb/kb_1").is_ok());
+        assert!(ResourceUri::parse("file:///etc/passwd").is_err());
+        assert!(ResourceUri::parse("ragstudio://kb/../doc/x").is_err());
+        assert!(ResourceUri::parse("ragstudio://kb/kb_1/other/x").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_list_and_read_resources() {
+        let provider = ResourceProvider::new();
+        let resources = provider.list_resources("mock://manager").await.unwrap();
+
+        assert!(resources.iter().any(|r| r.uri == "ragstudio://kb/test_kb_1"));
+        let doc = resources.iter().find(|r| r.uri == "ragstudio://kb/test_kb_1/doc/doc_1").unwrap();
+        assert_eq!(doc.mime_type, MIME_TEXT);
+
+        let contents = provider.read_resource(&doc.uri, "mock://manager").await.unwrap();
+        assert!(!contents.text.is_empty());
+
+        let stats = provider.read_resource("ragstudio://kb/test_kb_1", "mock://manager").await.unwrap();
+        assert_eq!(stats.mime_type, MIME_JSON);
+    }
+
+    /// Loopback stand-in for the Manager's outbound server, dispatching to `handler`
+    async fn serve_rpc(handler: KbRpcHandler) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handler = Arc::new(handler);
+        tokio::spawn(async move {
+            while let Ok((mut socket, _)) = listener.accept().await {
+                let handler = handler.clone();
+                tokio::spawn(async move {
+                    let mut buf = Vec::new();
+                    let mut chunk = [0u8; 4096];
+                    let body = loop {
+                        let n = socket.read(&mut chunk).await.unwrap();
+                        buf.extend_from_slice(&chunk[..n]);
+                        let Some(head_end) = buf.windows(4).position(|w| w == b"\r\n\r\n") else { continue };
+                        let head = String::from_utf8_lossy(&buf[..head_end]).to_lowercase();
+                        let length: usize = head.lines()
+                            .find_map(|line| line.strip_prefix("content-length:"))
+                            .map_or(0, |v| v.trim().parse().unwrap());
+                        if buf.len() >= head_end + 4 + length {
+                            break buf[head_end + 4..head_end + 4 + length].to_vec();
+                        }
+                    };
+
+                    let response = handler.handle(serde_json::from_slice(&body).unwrap()).await;
+                    let body = serde_json::to_vec(&response).unwrap();
+                    let head = format!(
+                        "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                        body.len()
+                    );
+                    socket.write_all(head.as_bytes()).await.unwrap();
+                    socket.write_all(&body).await.unwrap();
+                });
+            }
+        });
+        url
+    }
+
+    #[tokio::test]
+    async fn test_read_chunk_through_kb_rpc() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let mut sql_config = SqlConfig::new_mvp(temp_dir.path().join("app.db"));
+        sql_config.backup_dir = temp_dir.path().join("backups");
+        let sql_service = SqlService::new(sql_config).await.unwrap();
+        sql_service.run_migrations().await.unwrap();
+        let vector_service = Arc::new(VectorDbService::new(VectorDbConfig::test_config(temp_dir.path())).await.unwrap());
+        let kb_service = Arc::new(KbServiceImpl::new_mvp(Arc::new(sql_service), vector_service, Arc::new(StateManager::new())));
+
+        let kb_id = kb_service.create_collection("Docs", KbCreateConfig {
+            description: None,
+            embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+            chunk_size: 512,
+            chunk_overlap: 50,
+        }).await.unwrap();
+        let path = temp_dir.path().join("guide.md");
+        std::fs::write(&path, "install the studio").unwrap();
+        let added = kb_service.add_documents(&kb_id, vec![path.to_str().unwrap().to_string()]).await.unwrap();
+        let document = kb_service.get_document_content(&kb_id, &added[0].id, None).await.unwrap();
+        let url = serve_rpc(KbRpcHandler::new(kb_service)).await;
+
+        let uri = ResourceUri::Chunk {
+            kb_id: kb_id.clone(),
+            doc_id: added[0].id.clone(),
+            chunk_id: document.chunks[0].chunk_id.clone(),
+        }.to_string();
+        let contents = ResourceProvider::new().read_resource(&uri, &url).await.unwrap();
+        assert_eq!(contents.mime_type, MIME_TEXT);
+        assert_eq!(contents.text, "install the studio");
+
+        let missing = format!("{}kb/{}/doc/{}/chunk/chunk_missing", URI_SCHEME, kb_id, added[0].id);
+        assert!(ResourceProvider::new().read_resource(&missing, &url).await.is_err());
+    }
+}
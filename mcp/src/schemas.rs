@@ -0,0 +1,368 @@
+/*!
+ * Tool Input Schemas
+ *
+ * Parameter structs for the built-in tools. The `inputSchema` each tool
+ * advertises in `tools/list` is generated from its struct, the validator
+ * enforces that same schema before a call runs, and the executor then
+ * deserializes the arguments into the struct, so what clients are told and
+ * what the server accepts can't drift apart.
+ *
+ * Schemas are strict: unknown fields are rejected, optional fields may be
+ * left out but not sent as null, and lengths, ranges and patterns are part
+ * of the schema rather than checks hidden in the validator.
+ */
+
+use std::collections::HashMap;
+use anyhow::{Result, anyhow};
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::schema::{InstanceType, Schema, SchemaObject, StringValidation};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+
+use crate::protocol::ToolCall;
+
+/// KB collection names: ASCII letters, digits, `_` and `-`
+const COLLECTION_PATTERN: &str = "^[A-Za-z0-9_-]+$";
+
+/// Document and chunk IDs: no path separators or `..`
+const SAFE_ID_PATTERN: &str = r"^(?!.*\.\.)[^/\\]+$";
+
+/// rag.answer defaults
+const ANSWER_TOP_K: u64 = 5;
+const ANSWER_MAX_TOKENS: u64 = 512;
+const ANSWER_TEMPERATURE: f64 = 0.2;
+
+/// JSON Schema for a parameter struct, as advertised in `tools/list`
+pub fn input_schema<T: JsonSchema>() -> Value {
+    let generator = SchemaSettings::draft07()
+        .with(|settings| {
+            settings.option_nullable = false;
+            settings.option_add_null_type = false;   // Optional means omitted, not null
+            settings.inline_subschemas = true;       // Self-contained, no `definitions`
+        })
+        .into_generator();
+    let mut schema = serde_json::to_value(generator.into_root_schema_for::<T>()).unwrap_or_default();
+    // The tool carries its own name and description
+    if let Some(root) = schema.as_object_mut() {
+        for key in ["$schema", "title", "description", "definitions"] {
+            root.remove(key);
+        }
+    }
+    schema
+}
+
+/// Deserialize a call's arguments into its parameter struct
+pub fn parse_arguments<T: DeserializeOwned>(call: &ToolCall) -> Result<T> {
+    let arguments: Map<String, Value> = call.arguments.clone().into_iter().collect();
+    serde_json::from_value(Value::Object(arguments))
+        .map_err(|e| anyhow!("Invalid arguments for {}: {}", call.name, e))
+}
+
+/// String schema with length limits and a pattern
+fn string_schema(min_length: u32, max_length: u32, pattern: &str) -> Schema {
+    SchemaObject {
+        instance_type: Some(InstanceType::String.into()),
+        string: Some(Box::new(StringValidation {
+            min_length: Some(min_length),
+            max_length: Some(max_length),
+            pattern: Some(pattern.to_string()),
+        })),
+        ..Default::default()
+    }
+    .into()
+}
+
+/// Knowledge base collection name
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CollectionName(pub String);
+
+impl JsonSchema for CollectionName {
+    fn schema_name() -> String {
+        "CollectionName".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema(1, 100, COLLECTION_PATTERN)
+    }
+}
+
+/// Document or chunk ID, safe to use in lookups by path
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SafeId(pub String);
+
+impl JsonSchema for SafeId {
+    fn schema_name() -> String {
+        "SafeId".to_string()
+    }
+
+    fn json_schema(_: &mut SchemaGenerator) -> Schema {
+        string_schema(1, 255, SAFE_ID_PATTERN)
+    }
+}
+
+fn default_top_k() -> usize {
+    10
+}
+
+/// Context returned around each hit instead of the chunk alone
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(tag = "mode", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ExpandParams {
+    /// The hit plus `window` chunks on each side
+    Adjacent {
+        #[serde(default = "default_expand_window")]
+        #[schemars(range(min = 1, max = 5))]
+        window: u32,
+    },
+    /// The document section containing the hit
+    Parent {
+        #[serde(default = "default_parent_chunks")]
+        #[schemars(range(min = 1, max = 32))]
+        max_chunks: u32,
+    },
+}
+
+fn default_expand_window() -> u32 {
+    1
+}
+
+fn default_parent_chunks() -> u32 {
+    8
+}
+
+/// How the KB is walked
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchModeParams {
+    /// Chunks ranked by vector + BM25 scores
+    Hybrid,
+    /// Document and section summaries first, then the chunks under the best sections
+    Hierarchical,
+}
+
+/// kb.hybrid_search
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct HybridSearchParams {
+    /// Knowledge base collection name
+    pub collection: CollectionName,
+    /// Search query text
+    #[schemars(length(min = 1, max = 10000))]
+    pub query: String,
+    /// Number of results to return
+    #[serde(default = "default_top_k")]
+    #[schemars(range(min = 1, max = 100))]
+    pub top_k: usize,
+    /// Results kept after reranking
+    #[schemars(range(min = 1, max = 100))]
+    pub top_n: Option<u32>,
+    /// Optional filters for search
+    pub filters: Option<Map<String, Value>>,
+    /// Cache TTL in seconds
+    #[schemars(range(max = 86400))]
+    pub cache_ttl: Option<u32>,
+    /// Specific KB version (defaults to the workspace's pinned version)
+    #[schemars(range(min = 0))]
+    pub version: Option<i64>,
+    /// Workspace whose pins apply (defaults to the active workspace)
+    pub workspace_id: Option<String>,
+    /// Return each hit's neighbouring chunks or parent section
+    pub expand: Option<ExpandParams>,
+    /// Search mode (defaults to hybrid)
+    pub mode: Option<SearchModeParams>,
+    /// Add entities related to the query's from the KB's knowledge graph
+    pub graph_expand: Option<bool>,
+}
+
+/// kb.search_multiple
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct SearchMultipleParams {
+    /// Knowledge base collection names
+    #[schemars(length(min = 1, max = 16))]     // Matches the core's federated search limit
+    pub collections: Vec<CollectionName>,
+    /// Search query text
+    #[schemars(length(min = 1, max = 10000))]
+    pub query: String,
+    /// Number of merged results to return
+    #[serde(default = "default_top_k")]
+    #[schemars(range(min = 1, max = 100))]
+    pub top_k: usize,
+    /// Optional filters applied in every KB
+    pub filters: Option<Map<String, Value>>,
+    /// Return each hit's neighbouring chunks or parent section
+    pub expand: Option<ExpandParams>,
+}
+
+/// Character range of a document
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DocumentRange {
+    pub start: u64,
+    pub end: u64,
+}
+
+/// kb.get_document
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct GetDocumentParams {
+    /// Document ID to retrieve
+    pub doc_id: SafeId,
+    /// Knowledge base holding the document; returns its full text and citations
+    pub collection: Option<CollectionName>,
+    /// Optional range selection (start, end)
+    pub range: Option<DocumentRange>,
+}
+
+/// kb.resolve_citations
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ResolveCitationsParams {
+    /// Array of chunk IDs to resolve citations for
+    #[schemars(length(min = 1, max = 1000))]
+    pub chunk_ids: Vec<SafeId>,
+}
+
+/// kb.stats
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct StatsParams {
+    /// Specific collection name (optional)
+    pub collection: Option<CollectionName>,
+    /// Specific version (optional, defaults to the workspace's pinned version)
+    #[schemars(range(min = 0))]
+    pub version: Option<i64>,
+    /// Workspace whose pins apply (defaults to the active workspace)
+    pub workspace_id: Option<String>,
+}
+
+/// kb.list_collections
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ListCollectionsParams {
+    /// Optional filters for collection listing
+    pub filters: Option<Map<String, Value>>,
+}
+
+/// kb.list
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ListParams {
+    /// Optional filters for knowledge base listing
+    pub filters: Option<Map<String, Value>>,
+}
+
+fn default_answer_top_k() -> u64 {
+    ANSWER_TOP_K
+}
+
+fn default_answer_max_tokens() -> u64 {
+    ANSWER_MAX_TOKENS
+}
+
+fn default_answer_temperature() -> f64 {
+    ANSWER_TEMPERATURE
+}
+
+/// rag.answer
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AnswerParams {
+    /// Knowledge base collection name
+    pub collection: CollectionName,
+    /// Question to answer
+    #[schemars(length(min = 1, max = 10000))]
+    pub question: String,
+    /// Number of chunks to ground the answer on
+    #[serde(default = "default_answer_top_k")]
+    #[schemars(range(min = 1, max = 20))]
+    pub top_k: u64,
+    /// Maximum answer length in tokens
+    #[serde(default = "default_answer_max_tokens")]
+    #[schemars(range(min = 1, max = 4096))]
+    pub max_tokens: u64,
+    /// Sampling temperature
+    #[serde(default = "default_answer_temperature")]
+    #[schemars(range(min = 0, max = 2))]
+    pub temperature: f64,
+    /// Token budget for the sources packed into the prompt
+    #[serde(default)]
+    #[schemars(range(min = 256, max = 32768))]
+    pub context_tokens: Option<u64>,
+}
+
+/// User-defined KB search tools (the KB comes from the tool binding)
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct DynamicSearchParams {
+    /// Search query text
+    #[schemars(length(min = 1, max = 10000))]
+    pub query: String,
+    /// Number of results to return
+    #[schemars(range(min = 1))]
+    pub top_k: Option<u32>,
+}
+
+/// Schema of a user-defined search tool: `top_k` is capped by the tool's own setting
+pub fn dynamic_search_schema(max_top_k: u32) -> Value {
+    let mut schema = input_schema::<DynamicSearchParams>();
+    if let Some(top_k) = schema.pointer_mut("/properties/top_k").and_then(Value::as_object_mut) {
+        top_k.insert("maximum".to_string(), Value::from(max_top_k));
+        top_k.insert("default".to_string(), Value::from(max_top_k));
+    }
+    schema
+}
+
+/// Schemas of the built-in tools, by tool name
+pub fn builtin_schemas() -> HashMap<&'static str, Value> {
+    HashMap::from([
+        ("kb.hybrid_search", input_schema::<HybridSearchParams>()),
+        ("kb.search_multiple", input_schema::<SearchMultipleParams>()),
+        ("kb.get_document", input_schema::<GetDocumentParams>()),
+        ("kb.resolve_citations", input_schema::<ResolveCitationsParams>()),
+        ("kb.stats", input_schema::<StatsParams>()),
+        ("kb.list_collections", input_schema::<ListCollectionsParams>()),
+        ("kb.list", input_schema::<ListParams>()),
+        ("rag.answer", input_schema::<AnswerParams>()),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generated_schemas_are_strict() {
+        let schema = input_schema::<HybridSearchParams>();
+        assert_eq!(schema["type"], "object");
+        assert_eq!(schema["additionalProperties"], false);
+        assert_eq!(schema["required"], serde_json::json!(["collection", "query"]));
+        assert_eq!(schema["properties"]["top_k"]["maximum"].as_f64(), Some(100.0));
+        assert_eq!(schema["properties"]["top_k"]["default"], 10);
+        assert_eq!(schema["properties"]["query"]["description"], "Search query text");
+        assert_eq!(schema["properties"]["collection"]["pattern"], COLLECTION_PATTERN);
+        // Optional fields don't admit null, and nothing is left behind a `$ref`
+        assert_eq!(schema["properties"]["version"]["type"], "integer");
+        assert!(!schema.to_string().contains("$ref"));
+        let modes: Vec<&Value> = schema["properties"]["mode"]["oneOf"].as_array().unwrap().iter()
+            .map(|variant| &variant["enum"][0])
+            .collect();
+        assert_eq!(modes, vec!["hybrid", "hierarchical"]);
+
+        let schema = input_schema::<SearchMultipleParams>();
+        assert_eq!(schema["properties"]["collections"]["maxItems"], 16);
+        assert_eq!(schema["properties"]["collections"]["items"]["maxLength"], 100);
+        let modes: Vec<&Value> = schema["properties"]["expand"]["oneOf"].as_array().unwrap().iter()
+            .map(|variant| &variant["properties"]["mode"]["enum"][0])
+            .collect();
+        assert_eq!(modes, vec!["adjacent", "parent"]);
+
+        let schema = dynamic_search_schema(5);
+        assert_eq!(schema["properties"]["top_k"]["maximum"], 5);
+        assert_eq!(builtin_schemas().len(), 8);
+    }
+}
@@ -0,0 +1,209 @@
+/*!
+ * Client Sessions
+ *
+ * One session per attached client: the stdio pipe, or each connection
+ * accepted on `--listen`. A session holds what `initialize` negotiated (the
+ * client's name, protocol version and capabilities), the channel its
+ * notifications go to and its running tool calls, so a cancellation only
+ * reaches calls from the client that sent it.
+ *
+ * Quotas and audit entries are keyed by the session's client id. Network
+ * sessions append their session number to the reported name, so two agents
+ * that both call themselves "claude" get separate buckets and audit trails,
+ * and must complete `initialize` before anything but `ping` is served.
+ */
+
+use std::collections::HashMap;
+use std::sync::{Mutex, RwLock};
+use serde_json::Value;
+use tokio::sync::mpsc::UnboundedSender;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info};
+
+use crate::protocol::OutgoingMessage;
+
+/// Quota and audit key used before (or without) `initialize`
+pub const DEFAULT_CLIENT_ID: &str = "default";
+
+/// Longest client name kept from `clientInfo.name`
+const MAX_CLIENT_NAME: usize = 64;
+
+/// What the client sent in `initialize`
+#[derive(Debug, Clone, Default)]
+pub struct Handshake {
+    pub client_name: Option<String>,
+    pub protocol_version: Option<String>,
+    pub capabilities: Value,
+}
+
+impl Handshake {
+    pub fn from_params(params: &Value) -> Self {
+        let client_name = params.get("clientInfo")
+            .and_then(|info| info.get("name"))
+            .and_then(Value::as_str)
+            .map(|name| name.chars().filter(|c| !c.is_control()).take(MAX_CLIENT_NAME).collect::<String>())
+            .filter(|name| !name.is_empty());
+        Self {
+            client_name,
+            protocol_version: params.get("protocolVersion").and_then(Value::as_str).map(String::from),
+            capabilities: params.get("capabilities").cloned().unwrap_or(Value::Null),
+        }
+    }
+}
+
+/// State of one attached client
+pub struct Session {
+    number: Option<u64>,                                    // None for stdio
+    notifier: Option<UnboundedSender<OutgoingMessage>>,     // Responses and notifications to this client
+    handshake: RwLock<Option<Handshake>>,
+    client_id: RwLock<String>,
+    in_flight: Mutex<HashMap<String, CancellationToken>>,   // Running tool calls by request id
+}
+
+impl Session {
+    /// The stdio client; serves requests without a prior `initialize`
+    pub fn stdio(notifier: Option<UnboundedSender<OutgoingMessage>>) -> Self {
+        Self::new(None, notifier)
+    }
+
+    /// A client connected over TCP or a Unix socket
+    pub fn connection(number: u64, notifier: UnboundedSender<OutgoingMessage>) -> Self {
+        Self::new(Some(number), Some(notifier))
+    }
+
+    fn new(number: Option<u64>, notifier: Option<UnboundedSender<OutgoingMessage>>) -> Self {
+        let client_id = match number {
+            Some(number) => format!("{}#{}", DEFAULT_CLIENT_ID, number),
+            None => DEFAULT_CLIENT_ID.to_string(),
+        };
+        Self {
+            number,
+            notifier,
+            handshake: RwLock::new(None),
+            client_id: RwLock::new(client_id),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn number(&self) -> Option<u64> {
+        self.number
+    }
+
+    pub fn notifier(&self) -> Option<UnboundedSender<OutgoingMessage>> {
+        self.notifier.clone()
+    }
+
+    /// Send a notification; dropped if the client is gone
+    pub fn notify(&self, message: OutgoingMessage) {
+        if let Some(notifier) = &self.notifier {
+            let _ = notifier.send(message);
+        }
+    }
+
+    /// Key for quotas and audit entries
+    pub fn client_id(&self) -> String {
+        self.client_id.read().unwrap().clone()
+    }
+
+    /// Record the handshake; network sessions may only initialize once
+    pub fn initialize(&self, handshake: Handshake) -> Result<(), String> {
+        let mut current = self.handshake.write().unwrap();
+        if current.is_some() && self.number.is_some() {
+            return Err("Session is already initialized".to_string());
+        }
+        if let Some(name) = &handshake.client_name {
+            *self.client_id.write().unwrap() = match self.number {
+                Some(number) => format!("{}#{}", name, number),
+                None => name.clone(),
+            };
+        }
+        info!(
+            "Client {} initialized (protocol {})",
+            self.client_id(),
+            handshake.protocol_version.as_deref().unwrap_or("unspecified"),
+        );
+        *current = Some(handshake);
+        Ok(())
+    }
+
+    pub fn handshake(&self) -> Option<Handshake> {
+        self.handshake.read().unwrap().clone()
+    }
+
+    /// True if `method` may run yet: network clients must initialize first
+    pub fn accepts(&self, method: &str) -> bool {
+        self.number.is_none()
+            || matches!(method, "initialize" | "ping")
+            || self.handshake.read().unwrap().is_some()
+    }
+
+    /// Track a running tool call so it can be cancelled by request id
+    pub fn track(&self, id: &Value, token: CancellationToken) {
+        self.in_flight.lock().unwrap().insert(id.to_string(), token);
+    }
+
+    pub fn untrack(&self, id: &Value) {
+        self.in_flight.lock().unwrap().remove(&id.to_string());
+    }
+
+    /// Cancel a running tool call; returns false if the id is unknown or already finished
+    pub fn cancel_request(&self, id: &Value) -> bool {
+        match self.in_flight.lock().unwrap().get(&id.to_string()) {
+            Some(token) => {
+                info!("Cancelling request {}", id);
+                token.cancel();
+                true
+            }
+            None => {
+                debug!("Cancel for unknown request {}", id);
+                false
+            }
+        }
+    }
+
+    /// Cancel everything still running (the client went away)
+    pub fn cancel_all(&self) {
+        for token in self.in_flight.lock().unwrap().values() {
+            token.cancel();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_session_handshake() {
+        let (tx, _rx) = tokio::sync::mpsc::unbounded_channel();
+        let session = Session::connection(3, tx);
+        assert_eq!(session.client_id(), "default#3");
+        assert!(session.accepts("ping"));
+        assert!(!session.accepts("tools/call"));
+
+        let handshake = Handshake::from_params(&json!({
+            "protocolVersion": "2024-11-05",
+            "clientInfo": {"name": "agent\u{7}"},
+            "capabilities": {"roots": {}},
+        }));
+        session.initialize(handshake).unwrap();
+        assert_eq!(session.client_id(), "agent#3");
+        assert!(session.accepts("tools/call"));
+        assert_eq!(session.handshake().unwrap().protocol_version.as_deref(), Some("2024-11-05"));
+        assert!(session.initialize(Handshake::default()).is_err());
+
+        // Stdio serves without a handshake and keys by the bare name
+        let stdio = Session::stdio(None);
+        assert!(stdio.accepts("tools/call"));
+        stdio.initialize(Handshake::from_params(&json!({"clientInfo": {"name": "cli"}}))).unwrap();
+        assert_eq!(stdio.client_id(), "cli");
+
+        let token = CancellationToken::new();
+        stdio.track(&json!(7), token.clone());
+        assert!(stdio.cancel_request(&json!(7)));
+        assert!(token.is_cancelled());
+        stdio.untrack(&json!(7));
+        assert!(!stdio.cancel_request(&json!(7)));
+    }
+}
@@ -3,25 +3,76 @@
  *
  * Implements the kb.* tool set for RAG operations.
  * MVP: Basic tool registration and execution.
- * Upgrade path: Dynamic tool loading, capability policies.
+ * Every tool declares the permission scopes it needs (see `validation::Scope`).
+ * Upgrade path: Dynamic tool loading.
  */
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock, RwLock};
+use std::time::Duration;
+use jsonschema::JSONSchema;
 use serde_json::{json, Value};
 use anyhow::{Result, anyhow};
-use tracing::{debug, error, info};
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, error, info, warn};
 
 use crate::protocol::{ToolDefinition, ToolCall, ToolResult, ToolContent};
+use crate::progress::ProgressReporter;
+use crate::schemas::{
+    builtin_schemas, dynamic_search_schema, parse_arguments, AnswerParams, CollectionName,
+    DynamicSearchParams, GetDocumentParams, HybridSearchParams, ListCollectionsParams, ListParams,
+    ResolveCitationsParams, SearchMultipleParams, StatsParams,
+};
+use crate::validation::Scope;
+use rag_core::models::outbound_rpc::{RpcResponse, RPC_PATH, RPC_TOKEN_ENV};
+use rag_core::services::telemetry::{TraceContext, TRACEPARENT_KEY};
+use rag_core::models::tool_catalog::DynamicToolSpec;
+use rag_core::modules::kb::answer::{build_grounded_prompt, cite_answer, NO_SOURCES_ANSWER};
+use rag_core::modules::kb::context::{pack_context, ContextBudget, EstimatingTokenizer};
+use rag_core::schemas::SearchResult;
+
+/// Search results per partial-result notification
+const STREAM_BATCH_SIZE: usize = 5;
+
+/// Outbound URL scheme served by canned responses instead of the Manager (tests only)
+#[cfg(test)]
+pub const MOCK_OUTBOUND_SCHEME: &str = "mock://";
+
+/// Outbound RPC policy
+const OUTBOUND_TIMEOUT: Duration = Duration::from_secs(30);
+const OUTBOUND_CONNECT_TIMEOUT: Duration = Duration::from_secs(2);
+const OUTBOUND_MAX_RETRIES: u32 = 2;
+const OUTBOUND_RETRY_BACKOFF: Duration = Duration::from_millis(200);
+
+static HTTP_CLIENT: OnceLock<reqwest::Client> = OnceLock::new();
+
+/// Bearer token from `--rpc-token-file`; `RPC_TOKEN_ENV` takes precedence
+static RPC_TOKEN: OnceLock<String> = OnceLock::new();
+
+/// Error returned when a tool call is cancelled by the client
+#[derive(Debug, thiserror::Error)]
+#[error("Request cancelled")]
+pub struct ToolCancelled;
 
 /// Tool registry for managing available MCP tools
 pub struct ToolRegistry {
     tools: HashMap<String, ToolDefinition>,
+    scopes: HashMap<String, Vec<String>>,                       // Scopes declared by built-in tools
+    schemas: HashMap<String, Arc<JSONSchema>>,                  // Compiled input schemas of built-in tools
+    dynamic_tools: RwLock<HashMap<String, DynamicToolSpec>>,   // User-defined KB search tools
+    dynamic_schemas: RwLock<HashMap<String, Arc<JSONSchema>>>,
+    kb_allowlist: Option<HashSet<String>>,                      // KBs kb.list / kb.list_collections may show (all when None)
 }
 
 impl ToolRegistry {
     pub fn new() -> Result<Self> {
         let mut registry = Self {
             tools: HashMap::new(),
+            scopes: HashMap::new(),
+            schemas: HashMap::new(),
+            dynamic_tools: RwLock::new(HashMap::new()),
+            dynamic_schemas: RwLock::new(HashMap::new()),
+            kb_allowlist: None,
         };
 
         // Register KB tools (MVP set)
@@ -31,196 +82,203 @@ impl ToolRegistry {
         Ok(registry)
     }
 
-    /// Register all knowledge base tools
+    /// Register all knowledge base tools (input schemas come from their parameter structs)
     fn register_kb_tools(&mut self) -> Result<()> {
-        // kb.hybrid_search - Core hybrid search functionality
-        self.register_tool(ToolDefinition::new(
-            "kb.hybrid_search",
-            "Search knowledge bases using hybrid vector + BM25 search with citations",
-            json!({
-                "type": "object",
-                "properties": {
-                    "collection": {
-                        "type": "string",
-                        "description": "Knowledge base collection name"
-                    },
-                    "query": {
-                        "type": "string",
-                        "description": "Search query text"
-                    },
-                    "top_k": {
-                        "type": "integer",
-                        "minimum": 1,
-                        "maximum": 100,
-                        "default": 10,
-                        "description": "Number of results to return"
-                    },
-                    "filters": {
-                        "type": "object",
-                        "description": "Optional filters for search"
-                    },
-                    "cache_ttl": {
-                        "type": "integer",
-                        "description": "Cache TTL in seconds"
-                    }
-                },
-                "required": ["collection", "query"]
-            }),
-        ));
-
-        // kb.get_document - Document retrieval
-        self.register_tool(ToolDefinition::new(
-            "kb.get_document",
-            "Retrieve a specific document by ID with optional range selection",
-            json!({
-                "type": "object",
-                "properties": {
-                    "doc_id": {
-                        "type": "string",
-                        "description": "Document ID to retrieve"
-                    },
-                    "range": {
-                        "type": "object",
-                        "properties": {
-                            "start": {"type": "integer", "minimum": 0},
-                            "end": {"type": "integer", "minimum": 0}
-                        },
-                        "description": "Optional range selection (start, end)"
-                    }
-                },
-                "required": ["doc_id"]
-            }),
-        ));
-
-        // kb.resolve_citations - Citation resolution
-        self.register_tool(ToolDefinition::new(
-            "kb.resolve_citations",
-            "Resolve citations for given chunk IDs with license information",
-            json!({
-                "type": "object",
-                "properties": {
-                    "chunk_ids": {
-                        "type": "array",
-                        "items": {"type": "string"},
-                        "description": "Array of chunk IDs to resolve citations for"
-                    }
-                },
-                "required": ["chunk_ids"]
-            }),
-        ));
-
-        // kb.stats - KB statistics and health
-        self.register_tool(ToolDefinition::new(
-            "kb.stats",
-            "Get knowledge base statistics and health metrics",
-            json!({
-                "type": "object",
-                "properties": {
-                    "collection": {
-                        "type": "string",
-                        "description": "Specific collection name (optional)"
-                    },
-                    "version": {
-                        "type": "integer",
-                        "description": "Specific version (optional)"
-                    }
-                }
-            }),
-        ));
-
-        // kb.list_collections - List available KBs
-        self.register_tool(ToolDefinition::new(
-            "kb.list_collections",
-            "List all available knowledge base collections",
-            json!({
-                "type": "object",
-                "properties": {
-                    "filters": {
-                        "type": "object",
-                        "description": "Optional filters for collection listing"
-                    }
-                }
-            }),
-        ));
+        let mut schemas = builtin_schemas();
+        let tools: [(&str, &str, &[Scope]); 8] = [
+            // Core hybrid search functionality
+            ("kb.hybrid_search", "Search knowledge bases using hybrid vector + BM25 search with citations", &[Scope::KbRead]),
+            // Federated search across several KBs
+            ("kb.search_multiple", "Search several knowledge bases at once; results are merged by rank and tagged with their source KB", &[Scope::KbRead]),
+            ("kb.get_document", "Retrieve a document by ID; with its collection, the full text (or a character range) with citations for the document and each chunk", &[Scope::KbRead]),
+            ("kb.resolve_citations", "Resolve citations (title, source path, version) for given chunk IDs", &[Scope::KbRead]),
+            ("kb.stats", "Get knowledge base statistics and health metrics", &[Scope::KbRead]),
+            ("kb.list_collections", "List all available knowledge base collections", &[Scope::KbRead]),
+            // KBs with their versions
+            ("kb.list", "List knowledge bases with their versions, status and health, to pick what to query", &[Scope::KbRead]),
+            // Grounded answer generation with citations
+            ("rag.answer", "Answer a question from a knowledge base with an LLM, citing sources inline as [n]", &[Scope::KbRead, Scope::LlmGenerate]),
+        ];
+        for (name, description, scopes) in tools {
+            let schema = schemas.remove(name).ok_or_else(|| anyhow!("No input schema for tool {}", name))?;
+            self.register_tool(ToolDefinition::new(name, description, schema), scopes)?;
+        }
 
         Ok(())
     }
 
-    /// Register a new tool
-    fn register_tool(&mut self, tool: ToolDefinition) {
+    /// Register a new tool with the scopes it requires
+    fn register_tool(&mut self, tool: ToolDefinition, scopes: &[Scope]) -> Result<()> {
         debug!("Registering tool: {}", tool.name);
+        self.schemas.insert(tool.name.clone(), Arc::new(compile_schema(&tool)?));
+        self.scopes.insert(tool.name.clone(), scopes.iter().map(|s| s.as_str().to_string()).collect());
         self.tools.insert(tool.name.clone(), tool);
+        Ok(())
+    }
+
+    /// Compiled input schema of a tool, enforced before it runs (None for unknown tools)
+    pub fn input_schema(&self, name: &str) -> Option<Arc<JSONSchema>> {
+        self.schemas.get(name).cloned()
+            .or_else(|| self.dynamic_schemas.read().unwrap().get(name).cloned())
+    }
+
+    /// Only list these KBs from kb.list and kb.list_collections
+    pub fn restrict_kbs(&mut self, kbs: HashSet<String>) {
+        self.kb_allowlist = Some(kbs);
+    }
+
+    /// KB a user-defined tool searches (None for built-in and unknown tools)
+    pub fn bound_kb(&self, name: &str) -> Option<String> {
+        self.dynamic_tools.read().unwrap().get(name).map(|spec| spec.kb_id.clone())
+    }
+
+    /// Scopes a tool declares (None for unknown tools)
+    pub fn required_scopes(&self, name: &str) -> Option<Vec<String>> {
+        self.scopes.get(name).cloned()
+            .or_else(|| self.dynamic_tools.read().unwrap().get(name).map(|spec| spec.scopes.clone()))
+    }
+
+    /// Replace the dynamic tool set; returns true if anything changed
+    pub fn sync_dynamic_tools(&self, specs: Vec<DynamicToolSpec>) -> bool {
+        let next: HashMap<String, DynamicToolSpec> = specs.into_iter()
+            .filter(|spec| !self.tools.contains_key(&spec.name))
+            .map(|spec| (spec.name.clone(), spec))
+            .collect();
+
+        let mut dynamic_tools = self.dynamic_tools.write().unwrap();
+        if *dynamic_tools == next {
+            return false;
+        }
+        info!("Dynamic tools updated: {} -> {}", dynamic_tools.len(), next.len());
+        let mut schemas = HashMap::new();
+        for spec in next.values() {
+            match compile_schema(&dynamic_tool_definition(spec)) {
+                Ok(schema) => {
+                    schemas.insert(spec.name.clone(), Arc::new(schema));
+                }
+                Err(e) => warn!("Dynamic tool {} has an invalid schema: {}", spec.name, e),
+            }
+        }
+        *self.dynamic_schemas.write().unwrap() = schemas;
+        *dynamic_tools = next;
+        true
     }
 
     /// List all available tools
     pub fn list_tools(&self) -> Vec<ToolDefinition> {
-        self.tools.values().cloned().collect()
+        let dynamic_tools = self.dynamic_tools.read().unwrap();
+        self.tools.values().cloned()
+            .chain(dynamic_tools.values().map(dynamic_tool_definition))
+            .collect()
     }
 
-    /// Execute a tool call
-    pub async fn execute_tool(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
+    /// Execute a tool call, streaming progress and partial results when the client asked for them.
+    /// Returns `ToolCancelled` as soon as `cancel` fires.
+    pub async fn execute_tool(
+        &self,
+        call: &ToolCall,
+        outbound_url: &str,
+        progress: &ProgressReporter,
+        cancel: &CancellationToken,
+    ) -> Result<ToolResult> {
+        tokio::select! {
+            biased;
+            _ = cancel.cancelled() => {
+                info!("Tool {} cancelled", call.name);
+                Err(ToolCancelled.into())
+            }
+            result = self.dispatch_tool(call, outbound_url, progress) => result,
+        }
+    }
+
+    async fn dispatch_tool(
+        &self,
+        call: &ToolCall,
+        outbound_url: &str,
+        progress: &ProgressReporter,
+    ) -> Result<ToolResult> {
         debug!("Executing tool: {} with args: {:?}", call.name, call.arguments);
 
         match call.name.as_str() {
-            "kb.hybrid_search" => self.execute_hybrid_search(call, outbound_url).await,
+            "kb.hybrid_search" => self.execute_hybrid_search(call, outbound_url, progress).await,
+            "kb.search_multiple" => self.execute_search_multiple(call, outbound_url).await,
             "kb.get_document" => self.execute_get_document(call, outbound_url).await,
             "kb.resolve_citations" => self.execute_resolve_citations(call, outbound_url).await,
             "kb.stats" => self.execute_stats(call, outbound_url).await,
             "kb.list_collections" => self.execute_list_collections(call, outbound_url).await,
-            _ => Err(anyhow!("Unknown tool: {}", call.name)),
+            "kb.list" => self.execute_list(call, outbound_url).await,
+            "rag.answer" => self.execute_answer(call, outbound_url, progress).await,
+            name => {
+                let spec = self.dynamic_tools.read().unwrap().get(name).cloned();
+                match spec {
+                    Some(spec) => self.execute_dynamic_search(&spec, call, outbound_url, progress).await,
+                    None => Err(anyhow!("Unknown tool: {}", call.name)),
+                }
+            }
         }
     }
 
     /// Execute hybrid search tool
-    async fn execute_hybrid_search(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
-        let collection = call.arguments.get("collection")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required parameter: collection"))?;
-
-        let query = call.arguments.get("query")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required parameter: query"))?;
-
-        let top_k = call.arguments.get("top_k")
-            .and_then(|v| v.as_i64())
-            .unwrap_or(10) as usize;
+    async fn execute_hybrid_search(
+        &self,
+        call: &ToolCall,
+        outbound_url: &str,
+        progress: &ProgressReporter,
+    ) -> Result<ToolResult> {
+        let params: HybridSearchParams = parse_arguments(call)?;
+        self.hybrid_search(&params, outbound_url, progress).await
+    }
 
-        let filters = call.arguments.get("filters").cloned();
+    async fn hybrid_search(
+        &self,
+        params: &HybridSearchParams,
+        outbound_url: &str,
+        progress: &ProgressReporter,
+    ) -> Result<ToolResult> {
+        let collection = &params.collection.0;
 
-        debug!("Hybrid search: collection={}, query={}, top_k={}", collection, query, top_k);
+        // Version resolution (explicit > workspace pin > active) happens in the core
+        debug!("Hybrid search: collection={}, query={}, top_k={}, version={:?}", collection, params.query, params.top_k, params.version);
 
         // MVP: Call outbound RPC to RAG core services
         let request_body = json!({
             "method": "kb.hybrid_search",
             "params": {
                 "collection": collection,
-                "query": query,
-                "top_k": top_k,
-                "filters": filters
+                "query": params.query,
+                "top_k": params.top_k,
+                "top_n": params.top_n,
+                "filters": params.filters,
+                "cache_ttl": params.cache_ttl,
+                "version": params.version,
+                "workspace_id": params.workspace_id,
+                "expand": params.expand,
+                "mode": params.mode,
+                "graph_expand": params.graph_expand
             }
         });
 
-        match self.call_outbound_rpc(outbound_url, request_body).await {
+        progress.progress(0, None, Some(&format!("Searching {}", collection)));
+
+        match call_outbound_rpc(outbound_url, request_body).await {
             Ok(response) => {
                 let default_results = Value::Array(vec![]);
                 let results = response.get("results").unwrap_or(&default_results);
 
                 // Format results with mandatory citations
                 let formatted_results = if let Value::Array(results_array) = results {
+                    // Stream results in batches before the final response
+                    if progress.is_enabled() {
+                        let total = results_array.len() as u64;
+                        for (batch_index, batch) in results_array.chunks(STREAM_BATCH_SIZE).enumerate() {
+                            let done = (batch_index * STREAM_BATCH_SIZE + batch.len()) as u64;
+                            let text = batch.iter().map(format_search_result).collect::<Vec<_>>().join("\n\n");
+                            progress.partial(done, Some(total), vec![ToolContent::text(&text)]);
+                        }
+                    }
+
                     results_array.iter()
-                        .map(|result| {
-                            let chunk_id = result.get("chunk_id").and_then(|v| v.as_str()).unwrap_or("unknown");
-                            let score = result.get("score").and_then(|v| v.as_f64()).unwrap_or(0.0);
-                            let content = result.get("content").and_then(|v| v.as_str()).unwrap_or("");
-                            let default_citation = json!({});
-                            let citation = result.get("citation").unwrap_or(&default_citation);
-
-                            format!(
-                                "Result {}: {} (score: {:.3})\nCitation: {}\n---",
-                                chunk_id, content, score,
-                                serde_json::to_string_pretty(citation).unwrap_or_default()
-                            )
-                        })
+                        .map(format_search_result)
                         .collect::<Vec<_>>()
                         .join("\n\n")
                 } else {
@@ -239,25 +297,96 @@ impl ToolRegistry {
         }
     }
 
+    /// Execute federated search over several KBs
+    async fn execute_search_multiple(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
+        let params: SearchMultipleParams = parse_arguments(call)?;
+
+        debug!("Federated search: collections={:?}, query={}, top_k={}", params.collections, params.query, params.top_k);
+
+        let request_body = json!({
+            "method": "kb.search_multiple",
+            "params": {
+                "collections": params.collections,
+                "query": params.query,
+                "top_k": params.top_k,
+                "filters": params.filters,
+                "expand": params.expand
+            }
+        });
+
+        match call_outbound_rpc(outbound_url, request_body).await {
+            Ok(response) => {
+                let results = response.get("results").cloned().unwrap_or_else(|| json!([]));
+                let formatted_results = match results.as_array() {
+                    Some(results_array) if !results_array.is_empty() => results_array.iter()
+                        .map(|result| {
+                            let source = result.pointer("/metadata/source_kb/name")
+                                .or_else(|| result.get("kb_id"))
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("unknown");
+                            format!("[{}] {}", source, format_search_result(result))
+                        })
+                        .collect::<Vec<_>>()
+                        .join("\n\n"),
+                    _ => "No results found".to_string(),
+                };
+
+                Ok(ToolResult::success(vec![
+                    ToolContent::text(&formatted_results),
+                    ToolContent::json(&results),
+                ]))
+            }
+            Err(e) => {
+                error!("Federated search failed: {}", e);
+                Ok(ToolResult::error(&format!("Search failed: {}", e)))
+            }
+        }
+    }
+
+    /// Execute a user-defined tool as a search over its bound KB
+    async fn execute_dynamic_search(
+        &self,
+        spec: &DynamicToolSpec,
+        call: &ToolCall,
+        outbound_url: &str,
+        progress: &ProgressReporter,
+    ) -> Result<ToolResult> {
+        let params: DynamicSearchParams = parse_arguments(call)?;
+        // Callers may ask for fewer results than the tool is configured for, not more
+        let top_k = params.top_k.map_or(spec.top_k, |k| k.min(spec.top_k));
+
+        let search = HybridSearchParams {
+            collection: CollectionName(spec.kb_id.clone()),
+            query: params.query,
+            top_k: top_k as usize,
+            top_n: spec.top_n,
+            filters: None,
+            cache_ttl: None,
+            version: None,
+            workspace_id: None,
+            expand: None,
+            mode: None,
+            graph_expand: None,
+        };
+        self.hybrid_search(&search, outbound_url, progress).await
+    }
+
     /// Execute get document tool
     async fn execute_get_document(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
-        let doc_id = call.arguments.get("doc_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required parameter: doc_id"))?;
-
-        let range = call.arguments.get("range").cloned();
+        let params: GetDocumentParams = parse_arguments(call)?;
 
-        debug!("Get document: doc_id={}, range={:?}", doc_id, range);
+        debug!("Get document: doc_id={}, collection={:?}, range={:?}", params.doc_id.0, params.collection, params.range);
 
         let request_body = json!({
             "method": "kb.get_document",
             "params": {
-                "doc_id": doc_id,
-                "range": range
+                "doc_id": params.doc_id,
+                "collection": params.collection,
+                "range": params.range
             }
         });
 
-        match self.call_outbound_rpc(outbound_url, request_body).await {
+        match call_outbound_rpc(outbound_url, request_body).await {
             Ok(response) => {
                 Ok(ToolResult::success(vec![
                     ToolContent::json(&response),
@@ -269,20 +398,18 @@ impl ToolRegistry {
 
     /// Execute resolve citations tool
     async fn execute_resolve_citations(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
-        let chunk_ids = call.arguments.get("chunk_ids")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow!("Missing required parameter: chunk_ids"))?;
+        let params: ResolveCitationsParams = parse_arguments(call)?;
 
-        debug!("Resolve citations for {} chunks", chunk_ids.len());
+        debug!("Resolve citations for {} chunks", params.chunk_ids.len());
 
         let request_body = json!({
             "method": "kb.resolve_citations",
             "params": {
-                "chunk_ids": chunk_ids
+                "chunk_ids": params.chunk_ids
             }
         });
 
-        match self.call_outbound_rpc(outbound_url, request_body).await {
+        match call_outbound_rpc(outbound_url, request_body).await {
             Ok(response) => {
                 Ok(ToolResult::success(vec![
                     ToolContent::json(&response),
@@ -294,20 +421,20 @@ impl ToolRegistry {
 
     /// Execute stats tool
     async fn execute_stats(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
-        let collection = call.arguments.get("collection").and_then(|v| v.as_str());
-        let version = call.arguments.get("version").and_then(|v| v.as_i64());
+        let params: StatsParams = parse_arguments(call)?;
 
-        debug!("Get stats: collection={:?}, version={:?}", collection, version);
+        debug!("Get stats: collection={:?}, version={:?}", params.collection, params.version);
 
         let request_body = json!({
             "method": "kb.stats",
             "params": {
-                "collection": collection,
-                "version": version
+                "collection": params.collection,
+                "version": params.version,
+                "workspace_id": params.workspace_id
             }
         });
 
-        match self.call_outbound_rpc(outbound_url, request_body).await {
+        match call_outbound_rpc(outbound_url, request_body).await {
             Ok(response) => {
                 Ok(ToolResult::success(vec![
                     ToolContent::json(&response),
@@ -319,19 +446,20 @@ impl ToolRegistry {
 
     /// Execute list collections tool
     async fn execute_list_collections(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
-        let filters = call.arguments.get("filters").cloned();
+        let params: ListCollectionsParams = parse_arguments(call)?;
 
-        debug!("List collections with filters: {:?}", filters);
+        debug!("List collections with filters: {:?}", params.filters);
 
         let request_body = json!({
             "method": "kb.list_collections",
             "params": {
-                "filters": filters
+                "filters": params.filters
             }
         });
 
-        match self.call_outbound_rpc(outbound_url, request_body).await {
-            Ok(response) => {
+        match call_outbound_rpc(outbound_url, request_body).await {
+            Ok(mut response) => {
+                self.retain_allowed_kbs(&mut response, "collections");
                 Ok(ToolResult::success(vec![
                     ToolContent::json(&response),
                 ]))
@@ -340,66 +468,385 @@ impl ToolRegistry {
         }
     }
 
-    /// Call outbound RPC service (placeholder for MVP)
-    async fn call_outbound_rpc(&self, _outbound_url: &str, request: Value) -> Result<Value> {
-        // MVP: Placeholder implementation
-        // This would make HTTP/UDS calls to the Manager's outbound RPC server
-        debug!("Outbound RPC call: {}", request);
-
-        // For MVP, return mock responses
-        match request.get("method").and_then(|v| v.as_str()) {
-            Some("kb.hybrid_search") => Ok(json!({
-                "results": [
-                    {
-                        "chunk_id": "chunk_1",
-                        "document_id": "doc_1",
-                        "kb_id": "test_kb",
-                        "score": 0.95,
-                        "content": "This is a sample search result from the knowledge base.",
-                        "snippet": "This is a sample search result...",
-                        "metadata": {"source": "mvp"},
-                        "citation": {
-                            "title": "Sample Document",
-                            "source_path": "/documents/sample.md",
-                            "license": "MIT",
-                            "version": "1",
-                            "anchor": "chunk_1"
-                        }
+    /// Execute list tool: KBs with their versions
+    async fn execute_list(&self, call: &ToolCall, outbound_url: &str) -> Result<ToolResult> {
+        let params: ListParams = parse_arguments(call)?;
+
+        debug!("List KBs with filters: {:?}", params.filters);
+
+        let request_body = json!({
+            "method": "kb.list",
+            "params": {
+                "filters": params.filters
+            }
+        });
+
+        match call_outbound_rpc(outbound_url, request_body).await {
+            Ok(mut response) => {
+                self.retain_allowed_kbs(&mut response, "knowledge_bases");
+                Ok(ToolResult::success(vec![
+                    ToolContent::json(&response),
+                ]))
+            }
+            Err(e) => Ok(ToolResult::error(&format!("Failed to list knowledge bases: {}", e))),
+        }
+    }
+
+    /// Drop KBs outside the allowlist from a listing's `key` array
+    fn retain_allowed_kbs(&self, response: &mut Value, key: &str) {
+        if let (Some(allowed), Some(kbs)) = (&self.kb_allowlist, response.get_mut(key).and_then(Value::as_array_mut)) {
+            kbs.retain(|kb| kb.get("id").and_then(Value::as_str).is_some_and(|id| allowed.contains(id)));
+        }
+    }
+
+    /// Execute answer tool: retrieve, pack into the context budget, generate from a
+    /// grounded prompt, resolve citation markers
+    async fn execute_answer(&self, call: &ToolCall, outbound_url: &str, progress: &ProgressReporter) -> Result<ToolResult> {
+        let AnswerParams { collection, question, top_k, max_tokens, temperature, context_tokens } = parse_arguments(call)?;
+        let (collection, question) = (collection.0.as_str(), question.as_str());
+
+        debug!("Answer: collection={}, question={}, top_k={}", collection, question, top_k);
+
+        progress.progress(0, Some(2), Some(&format!("Searching {}", collection)));
+        let search = json!({
+            "method": "kb.hybrid_search",
+            "params": {
+                "collection": collection,
+                "query": question,
+                "top_k": top_k
+            }
+        });
+        let retrieved: Vec<SearchResult> = match call_outbound_rpc(outbound_url, search).await {
+            Ok(response) => serde_json::from_value(response.get("results").cloned().unwrap_or(json!([])))
+                .map_err(|e| anyhow!("Invalid search results: {}", e))?,
+            Err(e) => return Ok(ToolResult::error(&format!("Search failed: {}", e))),
+        };
+        let budget = context_tokens.map(|tokens| ContextBudget::new(tokens as usize)).unwrap_or_default();
+        let sources = pack_context(&retrieved, &budget, &EstimatingTokenizer).sources;
+
+        // Never let the model answer without sources
+        if sources.is_empty() {
+            let answer = cite_answer(NO_SOURCES_ANSWER, &sources);
+            return Ok(ToolResult::success(vec![
+                ToolContent::text(&answer.answer),
+                ToolContent::json(&serde_json::to_value(&answer)?),
+            ]));
+        }
+
+        progress.progress(1, Some(2), Some("Generating answer"));
+        let generate = json!({
+            "method": "llm.generate",
+            "params": {
+                "prompt": build_grounded_prompt(question, &sources),
+                "max_tokens": max_tokens,
+                "temperature": temperature
+            }
+        });
+        let text = match call_outbound_rpc(outbound_url, generate).await {
+            Ok(response) => response.get("text").and_then(|v| v.as_str()).unwrap_or_default().to_string(),
+            Err(e) => {
+                error!("Answer generation failed: {}", e);
+                return Ok(ToolResult::error(&format!("Generation failed: {}", e)));
+            }
+        };
+
+        let answer = cite_answer(&text, &sources);
+        let mut formatted = answer.answer.clone();
+        if !answer.citations.is_empty() {
+            formatted.push_str("\n\nSources:");
+            for citation in &answer.citations {
+                let flag = if citation.verified { "" } else { " [unverified]" };
+                formatted.push_str(&format!("\n[{}] {} ({}){}", citation.marker, citation.citation.title, citation.citation.source_path, flag));
+            }
+        }
+        formatted.push_str(&format!("\n\nGroundedness: {:.2}", answer.groundedness));
+
+        Ok(ToolResult::success(vec![
+            ToolContent::text(&formatted),
+            ToolContent::json(&serde_json::to_value(&answer)?),
+        ]))
+    }
+
+}
+
+/// Use the token read from the Manager's token file for outbound calls
+pub fn set_rpc_token(token: String) {
+    let _ = RPC_TOKEN.set(token);
+}
+
+/// Call the Manager's outbound RPC server (in tests, `mock://` URLs serve canned responses)
+///
+/// Transport failures, timeouts and 5xx responses are retried with backoff;
+/// RPC errors returned by the Manager are not.
+pub async fn call_outbound_rpc(outbound_url: &str, request: Value) -> Result<Value> {
+    debug!("Outbound RPC call: {}", request);
+
+    #[cfg(test)]
+    if outbound_url.starts_with(MOCK_OUTBOUND_SCHEME) {
+        return mock_outbound_rpc(&request);
+    }
+
+    let client = HTTP_CLIENT.get_or_init(|| {
+        reqwest::Client::builder()
+            .timeout(OUTBOUND_TIMEOUT)
+            .connect_timeout(OUTBOUND_CONNECT_TIMEOUT)
+            .build()
+            .expect("HTTP client configuration is static")
+    });
+    let url = format!("{}{}", outbound_url.trim_end_matches('/'), RPC_PATH);
+    let token = std::env::var(RPC_TOKEN_ENV).ok().or_else(|| RPC_TOKEN.get().cloned());
+
+    // Continue the calling MCP request's trace in the Manager
+    let mut request = request;
+    if let (Some(context), Some(body)) = (TraceContext::current(), request.as_object_mut()) {
+        body.insert(TRACEPARENT_KEY.to_string(), Value::from(context.to_traceparent()));
+    }
+
+    let mut attempt = 0;
+    let response = loop {
+        let mut builder = client.post(&url).json(&request);
+        if let Some(token) = &token {
+            builder = builder.bearer_auth(token);
+        }
+
+        let retryable = match builder.send().await {
+            Ok(response) if response.status().is_server_error() => anyhow!("Outbound RPC returned {}", response.status()),
+            Ok(response) if !response.status().is_success() => {
+                return Err(anyhow!("Outbound RPC rejected: {}", response.status()));
+            }
+            Ok(response) => break response,
+            Err(e) => anyhow!("Outbound RPC failed: {}", e),
+        };
+
+        if attempt >= OUTBOUND_MAX_RETRIES {
+            return Err(retryable);
+        }
+        let backoff = OUTBOUND_RETRY_BACKOFF * 2u32.pow(attempt);
+        warn!("{} (retrying in {:?})", retryable, backoff);
+        tokio::time::sleep(backoff).await;
+        attempt += 1;
+    };
+
+    let body: RpcResponse = response.json().await
+        .map_err(|e| anyhow!("Invalid outbound RPC response: {}", e))?;
+    match (body.result, body.error) {
+        (_, Some(error)) => Err(anyhow!("{} (code {})", error.message, error.code)),
+        (Some(result), None) => Ok(result),
+        (None, None) => Ok(Value::Null),
+    }
+}
+
+/// Canned responses for tests
+#[cfg(test)]
+fn mock_outbound_rpc(request: &Value) -> Result<Value> {
+    match request.get("method").and_then(|v| v.as_str()) {
+        Some("kb.hybrid_search") => Ok(json!({
+            "results": [
+                {
+                    "chunk_id": "chunk_1",
+                    "document_id": "doc_1",
+                    "kb_id": "test_kb",
+                    "score": 0.95,
+                    "content": "This is a sample search result from the knowledge base.",
+                    "snippet": "This is a sample search result...",
+                    "metadata": {"source": "mvp"},
+                    "citation": {
+                        "title": "Sample Document",
+                        "source_path": "/documents/sample.md",
+                        "license": "MIT",
+                        "version": "1",
+                        "anchor": "chunk_1"
                     }
-                ]
-            })),
-            Some("kb.stats") => Ok(json!({
-                "collection_name": "test_kb",
-                "version": 1,
-                "document_count": 100,
-                "chunk_count": 1000,
-                "size_bytes": 1024000,
-                "health_score": 0.95,
-                "embedder_version": "sentence-transformers/all-MiniLM-L6-v2",
-                "last_updated": chrono::Utc::now().to_rfc3339()
-            })),
-            Some("kb.list_collections") => Ok(json!({
-                "collections": [
-                    {
-                        "id": "test_kb_1",
-                        "name": "Test Knowledge Base 1",
-                        "version": 1,
-                        "status": "Active",
-                        "description": "Sample KB for testing",
-                        "health_score": 0.95,
-                        "pinned": false,
-                        "flows": []
+                }
+            ]
+        })),
+        Some("kb.search_multiple") => Ok(json!({
+            "results": [
+                {
+                    "chunk_id": "chunk_1",
+                    "document_id": "doc_1",
+                    "kb_id": "test_kb_1",
+                    "score": 1.0,
+                    "content": "This is a sample search result from the first knowledge base.",
+                    "snippet": "This is a sample search result...",
+                    "metadata": {"source_kb": {"id": "test_kb_1", "name": "Test Knowledge Base 1"}, "source_score": 1.0},
+                    "citation": {
+                        "title": "Sample Document",
+                        "source_path": "/documents/sample.md",
+                        "license": "MIT",
+                        "version": "1",
+                        "anchor": "chunk_1"
                     }
-                ]
-            })),
-            _ => Ok(json!({
-                "status": "ok",
-                "message": "MVP placeholder response"
-            })),
-        }
+                },
+                {
+                    "chunk_id": "chunk_7",
+                    "document_id": "doc_3",
+                    "kb_id": "test_kb_2",
+                    "score": 0.98,
+                    "content": "This is a sample search result from the second knowledge base.",
+                    "snippet": "This is a sample search result...",
+                    "metadata": {"source_kb": {"id": "test_kb_2", "name": "Test Knowledge Base 2"}, "source_score": 1.0},
+                    "citation": {
+                        "title": "Other Document",
+                        "source_path": "/documents/other.md",
+                        "license": "MIT",
+                        "version": "1",
+                        "anchor": "chunk_7"
+                    }
+                }
+            ]
+        })),
+        Some("kb.stats") => Ok(json!({
+            "collection_name": "test_kb",
+            "version": 1,
+            "document_count": 100,
+            "chunk_count": 1000,
+            "size_bytes": 1024000,
+            "health_score": 0.95,
+            "embedder_version": "sentence-transformers/all-MiniLM-L6-v2",
+            "last_updated": chrono::Utc::now().to_rfc3339(),
+            "health": "Healthy"
+        })),
+        Some("kb.list_collections") => Ok(json!({
+            "collections": [
+                {
+                    "id": "test_kb_1",
+                    "name": "Test Knowledge Base 1",
+                    "version": 1,
+                    "status": "Active",
+                    "description": "Sample KB for testing",
+                    "health_score": 0.95,
+                    "pinned": false,
+                    "flows": []
+                }
+            ]
+        })),
+        Some("kb.list") => Ok(json!({
+            "knowledge_bases": [
+                {
+                    "id": "test_kb_1",
+                    "name": "Test Knowledge Base 1",
+                    "version": 2,
+                    "status": "Active",
+                    "description": "Sample KB for testing",
+                    "health_score": 0.95,
+                    "pinned": false,
+                    "flows": [],
+                    "versions": [
+                        {
+                            "version": 2,
+                            "status": "active",
+                            "pipeline_run_id": "run_2",
+                            "stats": {"document_count": 10, "chunk_count": 120, "size_bytes": 65536},
+                            "created_at": "2026-01-02T00:00:00Z",
+                            "activated_at": "2026-01-02T00:00:00Z"
+                        },
+                        {
+                            "version": 1,
+                            "status": "archived",
+                            "pipeline_run_id": "run_1",
+                            "stats": {"document_count": 8, "chunk_count": 96, "size_bytes": 49152},
+                            "created_at": "2026-01-01T00:00:00Z",
+                            "activated_at": "2026-01-01T00:00:00Z"
+                        }
+                    ]
+                },
+                {
+                    "id": "test_kb_2",
+                    "name": "Test Knowledge Base 2",
+                    "version": 1,
+                    "status": "Active",
+                    "description": null,
+                    "health_score": 1.0,
+                    "pinned": false,
+                    "flows": [],
+                    "versions": []
+                }
+            ]
+        })),
+        Some("kb.list_documents") => Ok(json!({
+            "documents": [
+                {
+                    "id": "doc_1",
+                    "title": "Sample Document",
+                    "source_path": "/documents/sample.md",
+                    "chunk_count": 1
+                }
+            ]
+        })),
+        Some("kb.get_document") => Ok(json!({
+            "kb_id": request["params"]["collection"],
+            "id": request["params"]["doc_id"],
+            "title": "Sample Document",
+            "source_path": "/documents/sample.md",
+            "content": "This is a sample search result from the knowledge base.",
+            "citation": {
+                "title": "Sample Document",
+                "source_path": "/documents/sample.md",
+                "license": null,
+                "version": "1",
+                "anchor": null,
+                "page_number": null,
+                "time_range": null
+            },
+            "chunks": [
+                {
+                    "chunk_id": "doc_1_0",
+                    "start": 0,
+                    "end": 56,
+                    "citation": {
+                        "title": "Sample Document",
+                        "source_path": "/documents/sample.md",
+                        "license": null,
+                        "version": "1",
+                        "anchor": "chunk_doc_1_0",
+                        "page_number": null,
+                        "time_range": null
+                    }
+                }
+            ]
+        })),
+        Some("llm.generate") => Ok(json!({
+            "text": "This is a sample answer grounded in the knowledge base [1]."
+        })),
+        Some("kb.get_chunk") => Ok(json!({
+            "id": request["params"]["chunk_id"],
+            "document_id": "doc_1",
+            "content": "This is a sample search result from the knowledge base."
+        })),
+        _ => Ok(json!({
+            "status": "ok",
+            "message": "MVP placeholder response"
+        })),
     }
 }
 
+/// MCP definition for a user-defined KB search tool
+fn dynamic_tool_definition(spec: &DynamicToolSpec) -> ToolDefinition {
+    ToolDefinition::new(&spec.name, &spec.description, dynamic_search_schema(spec.top_k))
+}
+
+/// Compile a tool's input schema for validation
+fn compile_schema(tool: &ToolDefinition) -> Result<JSONSchema> {
+    JSONSchema::compile(&tool.inputSchema)
+        .map_err(|e| anyhow!("Invalid input schema for {}: {}", tool.name, e))
+}
+
+/// Format a search result with its mandatory citation
+fn format_search_result(result: &Value) -> String {
+    let chunk_id = result.get("chunk_id").and_then(|v| v.as_str()).unwrap_or("unknown");
+    let score = result.get("score").and_then(|v| v.as_f64()).unwrap_or(0.0);
+    let content = result.get("content").and_then(|v| v.as_str()).unwrap_or("");
+    let default_citation = json!({});
+    let citation = result.get("citation").unwrap_or(&default_citation);
+
+    format!(
+        "Result {}: {} (score: {:.3})\nCitation: {}\n---",
+        chunk_id, content, score,
+        serde_json::to_string_pretty(citation).unwrap_or_default()
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -419,7 +866,7 @@ mod tests {
     fn test_list_tools() {
         let registry = ToolRegistry::new().unwrap();
         let tools = registry.list_tools();
-        assert_eq!(tools.len(), 5); // kb.* tools
+        assert_eq!(tools.len(), 8); // kb.* tools + rag.answer
 
         let tool_names: Vec<&str> = tools.iter().map(|t| t.name.as_str()).collect();
         assert!(tool_names.contains(&"kb.hybrid_search"));
@@ -440,7 +887,7 @@ mod tests {
             arguments: args,
         };
 
-        let result = registry.execute_tool(&call, "http://localhost:3000").await;
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await;
         assert!(result.is_ok());
 
         let tool_result = result.unwrap();
@@ -448,6 +895,57 @@ mod tests {
         assert!(!tool_result.content.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_search_multiple_execution() {
+        let registry = ToolRegistry::new().unwrap();
+        let call = ToolCall {
+            name: "kb.search_multiple".to_string(),
+            arguments: HashMap::from([
+                ("collections".to_string(), json!(["test_kb_1", "test_kb_2"])),
+                ("query".to_string(), json!("test query")),
+            ]),
+        };
+
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.isError, Some(false));
+        assert!(result.content[0].text.starts_with("[Test Knowledge Base 1] Result chunk_1"));
+        assert!(result.content[0].text.contains("[Test Knowledge Base 2] Result chunk_7"));
+
+        let json: Value = serde_json::from_str(&result.content[1].text).unwrap();
+        assert_eq!(json[1]["metadata"]["source_kb"]["id"], "test_kb_2");
+    }
+
+    #[tokio::test]
+    async fn test_orientation_tools() {
+        let mut registry = ToolRegistry::new().unwrap();
+        registry.restrict_kbs(HashSet::from(["test_kb_1".to_string()]));
+        let run = |name: &str, arguments: Value| {
+            let call = ToolCall {
+                name: name.to_string(),
+                arguments: serde_json::from_value(arguments).unwrap(),
+            };
+            let registry = &registry;
+            async move {
+                let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await.unwrap();
+                assert_eq!(result.isError, Some(false));
+                serde_json::from_str::<Value>(&result.content[0].text).unwrap()
+            }
+        };
+
+        // Only allowed KBs are listed, each with its versions
+        let listing = run("kb.list", json!({})).await;
+        let kbs = listing["knowledge_bases"].as_array().unwrap();
+        assert_eq!(kbs.len(), 1);
+        assert_eq!(kbs[0]["versions"][0]["status"], "active");
+
+        let stats = run("kb.stats", json!({"collection": "test_kb_1"})).await;
+        assert_eq!(stats["health"], "Healthy");
+
+        let document = run("kb.get_document", json!({"collection": "test_kb_1", "doc_id": "doc_1"})).await;
+        assert!(!document["content"].as_str().unwrap().is_empty());
+        assert_eq!(document["chunks"][0]["citation"]["anchor"], "chunk_doc_1_0");
+    }
+
     #[tokio::test]
     async fn test_invalid_tool_execution() {
         let registry = ToolRegistry::new().unwrap();
@@ -457,7 +955,7 @@ mod tests {
             arguments: HashMap::new(),
         };
 
-        let result = registry.execute_tool(&call, "http://localhost:3000").await;
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await;
         assert!(result.is_err());
     }
 
@@ -470,7 +968,163 @@ mod tests {
             arguments: HashMap::new(), // Missing required params
         };
 
-        let result = registry.execute_tool(&call, "http://localhost:3000").await;
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await;
         assert!(result.is_err());
     }
-}
\ No newline at end of file
+
+    #[tokio::test]
+    async fn test_hybrid_search_streams_partial_results() {
+        let registry = ToolRegistry::new().unwrap();
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let progress = ProgressReporter::new(Some(json!("search-1")), Some(tx));
+
+        let mut args = HashMap::new();
+        args.insert("collection".to_string(), json!("test_kb"));
+        args.insert("query".to_string(), json!("test query"));
+        let call = ToolCall {
+            name: "kb.hybrid_search".to_string(),
+            arguments: args,
+        };
+
+        let result = registry.execute_tool(&call, "mock://manager", &progress, &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.isError, Some(false));
+
+        let mut notifications = Vec::new();
+        while let Ok(crate::protocol::OutgoingMessage::Notification(n)) = rx.try_recv() {
+            notifications.push(n);
+        }
+        assert_eq!(notifications.len(), 2); // start + one result batch
+        assert_eq!(notifications[1].params["progress"], 1);
+        assert!(notifications[1].params["partialResult"]["content"][0]["text"]
+            .as_str().unwrap().contains("chunk_1"));
+    }
+
+    #[tokio::test]
+    async fn test_dynamic_tools() {
+        let registry = ToolRegistry::new().unwrap();
+        let spec = DynamicToolSpec {
+            tool_id: "tool_1".to_string(),
+            name: "docs_search".to_string(),
+            description: "Search product docs".to_string(),
+            kb_id: "test_kb".to_string(),
+            top_k: 5,
+            top_n: Some(3),
+            scopes: vec!["kb.read".to_string()],
+        };
+
+        assert!(registry.sync_dynamic_tools(vec![spec.clone()]));
+        assert!(!registry.sync_dynamic_tools(vec![spec.clone()])); // unchanged
+        assert!(registry.input_schema("docs_search").is_some());
+        assert_eq!(registry.list_tools().len(), 9);
+        assert_eq!(registry.required_scopes("docs_search"), Some(vec!["kb.read".to_string()]));
+        assert_eq!(registry.required_scopes("kb.stats"), Some(vec!["kb.read".to_string()]));
+        assert!(registry.required_scopes("missing").is_none());
+
+        let call = ToolCall {
+            name: "docs_search".to_string(),
+            arguments: HashMap::from([("query".to_string(), json!("install"))]),
+        };
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.isError, Some(false));
+
+        assert!(registry.sync_dynamic_tools(Vec::new()));
+        assert!(registry.input_schema("docs_search").is_none());
+        assert!(registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_tool_call() {
+        let registry = ToolRegistry::new().unwrap();
+        let call = ToolCall {
+            name: "kb.stats".to_string(),
+            arguments: HashMap::new(),
+        };
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let err = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &cancel).await.unwrap_err();
+        assert!(err.is::<ToolCancelled>());
+    }
+
+    #[tokio::test]
+    async fn test_answer_execution() {
+        let registry = ToolRegistry::new().unwrap();
+        assert_eq!(registry.required_scopes("rag.answer").unwrap(), vec!["kb.read", "llm.generate"]);
+
+        let call = ToolCall {
+            name: "rag.answer".to_string(),
+            arguments: HashMap::from([
+                ("collection".to_string(), json!("test_kb")),
+                ("question".to_string(), json!("What is this?")),
+            ]),
+        };
+        let result = registry.execute_tool(&call, "mock://manager", &ProgressReporter::new(None, None), &CancellationToken::new()).await.unwrap();
+        assert_eq!(result.isError, Some(false));
+        assert!(result.content[0].text.contains("[1] Sample Document (/documents/sample.md)"));
+
+        let json: Value = serde_json::from_str(&result.content[1].text).unwrap();
+        assert_eq!(json["citations"][0]["chunk_id"], "chunk_1");
+        assert_eq!(json["citations"][0]["marker"], 1);
+        assert_eq!(json["citations"][0]["verified"], true);
+        assert_eq!(json["groundedness"], 1.0);
+    }
+
+    /// Serve canned HTTP responses in order, returning the raw requests
+    async fn serve_responses(responses: Vec<(&'static str, &'static str)>) -> (String, tokio::task::JoinHandle<Vec<String>>) {
+        use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let mut requests = Vec::new();
+            for (status, body) in responses {
+                let (mut socket, _) = listener.accept().await.unwrap();
+                let mut raw = Vec::new();
+                let mut buf = [0u8; 4096];
+                loop {
+                    let n = socket.read(&mut buf).await.unwrap();
+                    raw.extend_from_slice(&buf[..n]);
+                    let text = String::from_utf8_lossy(&raw).to_string();
+                    if let Some(end) = text.find("\r\n\r\n") {
+                        let length = text.lines()
+                            .find_map(|l| l.to_lowercase().strip_prefix("content-length:").map(|v| v.trim().parse::<usize>().unwrap()))
+                            .unwrap_or(0);
+                        if raw.len() >= end + 4 + length || n == 0 {
+                            break;
+                        }
+                    }
+                }
+                requests.push(String::from_utf8_lossy(&raw).to_string());
+                let response = format!(
+                    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status, body.len(), body
+                );
+                socket.write_all(response.as_bytes()).await.unwrap();
+            }
+            requests
+        });
+        (url, handle)
+    }
+
+    #[tokio::test]
+    async fn test_outbound_rpc_client() {
+        std::env::set_var(RPC_TOKEN_ENV, "test-token");
+        let (url, server) = serve_responses(vec![
+            ("503 Service Unavailable", ""),
+            ("200 OK", r#"{"result":{"collections":[]}}"#),
+            ("200 OK", r#"{"error":{"code":-32002,"message":"Knowledge base not found: kb_x"}}"#),
+        ]).await;
+
+        // 5xx is retried, then the result is unwrapped
+        let result = call_outbound_rpc(&url, json!({"method": "kb.list_collections", "params": {}})).await.unwrap();
+        assert_eq!(result, json!({"collections": []}));
+
+        // RPC errors surface without retrying
+        let err = call_outbound_rpc(&url, json!({"method": "kb.stats", "params": {"collection": "kb_x"}})).await.unwrap_err();
+        assert!(err.to_string().contains("-32002"));
+
+        let requests = server.await.unwrap();
+        assert_eq!(requests.len(), 3);
+        assert!(requests.iter().all(|r| r.starts_with("POST /rpc ") && r.to_lowercase().contains("authorization: bearer test-token")));
+    }
+}
@@ -1,224 +1,238 @@
 /*!
  * Input Validation for MCP Tools
  *
- * MVP: Basic JSON Schema validation with size limits.
- * Upgrade path: Fuzz-resistant validation, capability policies.
+ * Arguments are checked strictly against each tool's generated input schema
+ * (see `schemas`); violations come back as `InvalidArguments`, one issue per
+ * failing JSON pointer, which the server returns in the JSON-RPC error data.
+ * Calls are also checked against a capability policy: every tool declares
+ * the scopes it needs and calls are checked against the scopes granted to
+ * the server. Air-gapped mode strips scopes that require network access.
+ * Upgrade path: Fuzz-resistant validation, per-client grants.
  */
 
 use anyhow::{Result, anyhow};
-use serde_json::Value;
-use std::collections::HashMap;
+use jsonschema::error::ValidationErrorKind;
+use jsonschema::{JSONSchema, ValidationError};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::{HashMap, HashSet};
 use tracing::debug;
 
 use crate::protocol::ToolCall;
 
-/// Input validator for MCP tool calls
-pub struct InputValidator {
-    max_query_length: usize,
-    max_chunk_ids: usize,
-    max_json_size: usize,
+/// Permission scope a tool may require
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Scope {
+    KbRead,
+    KbWrite,
+    LlmGenerate,
+    FsRead,
+    NetFetch,
 }
 
-impl InputValidator {
-    pub fn new() -> Result<Self> {
-        Ok(Self {
-            max_query_length: 10_000,   // 10KB query limit
-            max_chunk_ids: 1_000,       // Max 1000 chunk IDs per request
-            max_json_size: 1_000_000,   // 1MB JSON payload limit
-        })
-    }
-
-    /// Validate a tool call
-    pub fn validate_tool_call(&self, call: &ToolCall) -> Result<()> {
-        debug!("Validating tool call: {}", call.name);
+impl Scope {
+    pub const ALL: [Scope; 5] = [Scope::KbRead, Scope::KbWrite, Scope::LlmGenerate, Scope::FsRead, Scope::NetFetch];
 
-        // Basic size validation
-        self.validate_json_size(&call.arguments)?;
-
-        // Tool-specific validation
-        match call.name.as_str() {
-            "kb.hybrid_search" => self.validate_hybrid_search(call),
-            "kb.get_document" => self.validate_get_document(call),
-            "kb.resolve_citations" => self.validate_resolve_citations(call),
-            "kb.stats" => self.validate_stats(call),
-            "kb.list_collections" => self.validate_list_collections(call),
-            _ => Err(anyhow!("Unknown tool: {}", call.name)),
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Scope::KbRead => "kb.read",
+            Scope::KbWrite => "kb.write",
+            Scope::LlmGenerate => "llm.generate",
+            Scope::FsRead => "fs.read",
+            Scope::NetFetch => "net.fetch",
         }
     }
 
-    /// Validate JSON payload size
-    fn validate_json_size(&self, args: &HashMap<String, Value>) -> Result<()> {
-        let json_size = serde_json::to_string(args)
-            .map(|s| s.len())
-            .unwrap_or(0);
-
-        if json_size > self.max_json_size {
-            return Err(anyhow!(
-                "JSON payload too large: {} bytes (max: {})",
-                json_size,
-                self.max_json_size
-            ));
-        }
-
-        Ok(())
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.as_str() == value)
     }
 
-    /// Validate hybrid search parameters
-    fn validate_hybrid_search(&self, call: &ToolCall) -> Result<()> {
-        // Validate required fields
-        let collection = call.arguments.get("collection")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required field: collection"))?;
+    /// Scopes that reach outside the machine (removed in air-gapped mode)
+    pub fn requires_network(&self) -> bool {
+        matches!(self, Scope::NetFetch)
+    }
+}
 
-        let query = call.arguments.get("query")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required field: query"))?;
+/// Scopes granted to this server instance
+#[derive(Debug, Clone)]
+pub struct CapabilityPolicy {
+    granted: HashSet<Scope>,
+}
 
-        // Validate collection name
-        if collection.is_empty() || collection.len() > 100 {
-            return Err(anyhow!("Invalid collection name length"));
-        }
+impl Default for CapabilityPolicy {
+    fn default() -> Self {
+        Self::new(Scope::ALL, false)
+    }
+}
 
-        if !collection.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
-            return Err(anyhow!("Invalid collection name characters"));
-        }
+impl CapabilityPolicy {
+    /// Grant the given scopes, minus network scopes when air-gapped
+    pub fn new(granted: impl IntoIterator<Item = Scope>, air_gapped: bool) -> Self {
+        let granted: HashSet<Scope> = granted.into_iter()
+            .filter(|scope| !(air_gapped && scope.requires_network()))
+            .collect();
+        Self { granted }
+    }
 
-        // Validate query
-        if query.is_empty() {
-            return Err(anyhow!("Query cannot be empty"));
-        }
+    /// Parse a comma-separated scope list (e.g. from the command line)
+    pub fn from_list(list: &str, air_gapped: bool) -> Result<Self> {
+        let scopes = list.split(',')
+            .map(str::trim)
+            .filter(|s| !s.is_empty())
+            .map(|s| Scope::parse(s).ok_or_else(|| anyhow!("Unknown scope: {}", s)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self::new(scopes, air_gapped))
+    }
 
-        if query.len() > self.max_query_length {
-            return Err(anyhow!(
-                "Query too long: {} characters (max: {})",
-                query.len(),
-                self.max_query_length
-            ));
-        }
+    /// Keep only the scopes also in `scopes` (grants can be narrowed, never widened)
+    pub fn restricted_to(&self, scopes: &[Scope]) -> Self {
+        Self { granted: self.granted.iter().copied().filter(|scope| scopes.contains(scope)).collect() }
+    }
 
-        // Validate top_k
-        if let Some(top_k) = call.arguments.get("top_k").and_then(|v| v.as_i64()) {
-            if top_k < 1 || top_k > 100 {
-                return Err(anyhow!("top_k must be between 1 and 100"));
-            }
-        }
+    pub fn is_granted(&self, scope: Scope) -> bool {
+        self.granted.contains(&scope)
+    }
 
-        // Validate cache_ttl
-        if let Some(ttl) = call.arguments.get("cache_ttl").and_then(|v| v.as_i64()) {
-            if ttl < 0 || ttl > 86400 {
-                return Err(anyhow!("cache_ttl must be between 0 and 86400 seconds"));
+    /// Check a tool's declared scopes; unknown scopes are never granted
+    pub fn check(&self, tool: &str, required: &[String]) -> Result<()> {
+        for name in required {
+            match Scope::parse(name) {
+                Some(scope) if self.is_granted(scope) => {}
+                Some(_) => return Err(anyhow!("Tool {} requires scope '{}' which is not granted", tool, name)),
+                None => return Err(anyhow!("Tool {} declares unknown scope '{}'", tool, name)),
             }
         }
-
         Ok(())
     }
+}
 
-    /// Validate get document parameters
-    fn validate_get_document(&self, call: &ToolCall) -> Result<()> {
-        let doc_id = call.arguments.get("doc_id")
-            .and_then(|v| v.as_str())
-            .ok_or_else(|| anyhow!("Missing required field: doc_id"))?;
+/// One schema violation, located by a JSON pointer into the tool arguments
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ValidationIssue {
+    pub path: String,       // e.g. "/top_k" or "/chunk_ids/3"; "" is the arguments object itself
+    pub keyword: String,    // Schema keyword that failed ("maximum", "required", "pattern", ...)
+    pub message: String,
+}
 
-        // Validate doc_id
-        if doc_id.is_empty() || doc_id.len() > 255 {
-            return Err(anyhow!("Invalid doc_id length"));
-        }
+/// Tool arguments that don't match the tool's input schema
+#[derive(Debug, thiserror::Error)]
+#[error("Invalid arguments for {tool}: {}", summarize(.issues))]
+pub struct InvalidArguments {
+    pub tool: String,
+    pub issues: Vec<ValidationIssue>,
+}
 
-        // Basic sanitization check
-        if doc_id.contains("..") || doc_id.contains("/") || doc_id.contains("\\") {
-            return Err(anyhow!("Invalid doc_id: contains path traversal characters"));
+impl InvalidArguments {
+    fn single(tool: &str, path: &str, keyword: &str, message: String) -> Self {
+        Self {
+            tool: tool.to_string(),
+            issues: vec![ValidationIssue { path: path.to_string(), keyword: keyword.to_string(), message }],
         }
+    }
 
-        // Validate range if provided
-        if let Some(range) = call.arguments.get("range") {
-            if let Some(range_obj) = range.as_object() {
-                if let (Some(start), Some(end)) = (
-                    range_obj.get("start").and_then(|v| v.as_i64()),
-                    range_obj.get("end").and_then(|v| v.as_i64()),
-                ) {
-                    if start < 0 || end < 0 || start > end {
-                        return Err(anyhow!("Invalid range: start and end must be non-negative and start <= end"));
-                    }
-
-                    if end - start > 1_000_000 {
-                        return Err(anyhow!("Range too large: max 1MB"));
-                    }
-                }
-            }
-        }
+    /// JSON-RPC error data: every violation with its path, so clients can fix the call
+    pub fn to_error_data(&self) -> Value {
+        json!({
+            "reason": "invalid_arguments",
+            "tool": self.tool,
+            "errors": self.issues,
+        })
+    }
+}
 
-        Ok(())
+fn summarize(issues: &[ValidationIssue]) -> String {
+    issues.iter()
+        .map(|issue| match issue.path.as_str() {
+            "" => issue.message.clone(),
+            path => format!("{}: {}", path, issue.message),
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+/// Input validator for MCP tool calls: payload size, the tool's input
+/// schema, then the few rules a schema can't express
+pub struct InputValidator {
+    max_json_size: usize,
+    policy: CapabilityPolicy,
+}
+
+impl InputValidator {
+    pub fn new() -> Result<Self> {
+        Ok(Self {
+            max_json_size: 1_000_000,   // 1MB JSON payload limit
+            policy: CapabilityPolicy::default(),
+        })
     }
 
-    /// Validate resolve citations parameters
-    fn validate_resolve_citations(&self, call: &ToolCall) -> Result<()> {
-        let chunk_ids = call.arguments.get("chunk_ids")
-            .and_then(|v| v.as_array())
-            .ok_or_else(|| anyhow!("Missing required field: chunk_ids"))?;
+    /// Replace the capability policy (all scopes granted by default)
+    pub fn with_policy(mut self, policy: CapabilityPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
 
-        // Validate chunk_ids count
-        if chunk_ids.is_empty() {
-            return Err(anyhow!("chunk_ids cannot be empty"));
-        }
+    /// Narrow the granted scopes to `scopes`
+    pub fn restrict_scopes(mut self, scopes: &[Scope]) -> Self {
+        self.policy = self.policy.restricted_to(scopes);
+        self
+    }
 
-        if chunk_ids.len() > self.max_chunk_ids {
-            return Err(anyhow!(
-                "Too many chunk_ids: {} (max: {})",
-                chunk_ids.len(),
-                self.max_chunk_ids
-            ));
-        }
+    /// Check that the tool's declared scopes are granted
+    pub fn validate_scopes(&self, tool: &str, required: &[String]) -> Result<()> {
+        self.policy.check(tool, required)
+    }
 
-        // Validate each chunk_id
-        for (i, chunk_id) in chunk_ids.iter().enumerate() {
-            let id_str = chunk_id.as_str()
-                .ok_or_else(|| anyhow!("chunk_ids[{}] must be a string", i))?;
+    /// Validate a tool call against its input schema. Schema violations are
+    /// returned as `InvalidArguments`, listing every failing path.
+    pub fn validate_tool_call(&self, call: &ToolCall, schema: &JSONSchema) -> Result<()> {
+        debug!("Validating tool call: {}", call.name);
 
-            if id_str.is_empty() || id_str.len() > 255 {
-                return Err(anyhow!("Invalid chunk_id[{}] length", i));
-            }
+        // Basic size validation
+        self.validate_json_size(&call.arguments)?;
 
-            // Basic sanitization
-            if id_str.contains("..") || id_str.contains("/") || id_str.contains("\\") {
-                return Err(anyhow!("Invalid chunk_id[{}]: contains invalid characters", i));
-            }
+        let arguments = Value::Object(call.arguments.clone().into_iter().collect());
+        if let Err(errors) = schema.validate(&arguments) {
+            let mut issues: Vec<ValidationIssue> = errors.flat_map(schema_issues).collect();
+            issues.sort_by(|a, b| a.path.cmp(&b.path));
+            return Err(InvalidArguments { tool: call.name.clone(), issues }.into());
         }
 
-        Ok(())
+        // Tool-specific rules across fields
+        match call.name.as_str() {
+            "kb.get_document" => self.validate_document_range(call),
+            _ => Ok(()),
+        }
     }
 
-    /// Validate stats parameters
-    fn validate_stats(&self, call: &ToolCall) -> Result<()> {
-        // Collection is optional
-        if let Some(collection) = call.arguments.get("collection").and_then(|v| v.as_str()) {
-            if collection.is_empty() || collection.len() > 100 {
-                return Err(anyhow!("Invalid collection name length"));
-            }
-
-            if !collection.chars().all(|c| c.is_alphanumeric() || c == '_' || c == '-') {
-                return Err(anyhow!("Invalid collection name characters"));
-            }
-        }
+    /// Validate JSON payload size
+    fn validate_json_size(&self, args: &HashMap<String, Value>) -> Result<()> {
+        let json_size = serde_json::to_string(args)
+            .map(|s| s.len())
+            .unwrap_or(0);
 
-        // Version is optional
-        if let Some(version) = call.arguments.get("version").and_then(|v| v.as_i64()) {
-            if version < 0 {
-                return Err(anyhow!("Version must be non-negative"));
-            }
+        if json_size > self.max_json_size {
+            return Err(anyhow!(
+                "JSON payload too large: {} bytes (max: {})",
+                json_size,
+                self.max_json_size
+            ));
         }
 
         Ok(())
     }
 
-    /// Validate list collections parameters
-    fn validate_list_collections(&self, call: &ToolCall) -> Result<()> {
-        // All parameters are optional, just validate filters if present
-        if let Some(filters) = call.arguments.get("filters") {
-            if !filters.is_object() {
-                return Err(anyhow!("filters must be an object"));
-            }
+    /// kb.get_document ranges must be ordered and at most 1MB
+    fn validate_document_range(&self, call: &ToolCall) -> Result<()> {
+        let Some(range) = call.arguments.get("range") else {
+            return Ok(());
+        };
+        let (start, end) = (range["start"].as_u64().unwrap_or(0), range["end"].as_u64().unwrap_or(0));
+        if start > end {
+            return Err(InvalidArguments::single(&call.name, "/range", "range", "start must be <= end".to_string()).into());
+        }
+        if end - start > 1_000_000 {
+            return Err(InvalidArguments::single(&call.name, "/range", "range", "Range too large: max 1MB".to_string()).into());
         }
-
         Ok(())
     }
 
@@ -232,10 +246,48 @@ impl InputValidator {
     }
 }
 
+/// Issues for one schema error. Missing and unknown fields point at the
+/// field itself rather than the object holding it.
+fn schema_issues(error: ValidationError<'_>) -> Vec<ValidationIssue> {
+    let path = error.instance_path.to_string();
+    let schema_path = error.schema_path.to_string();
+    let keyword = schema_path.rsplit('/').next().unwrap_or_default().to_string();
+    match &error.kind {
+        ValidationErrorKind::Required { property } => {
+            let field = property.as_str().map(str::to_string).unwrap_or_else(|| property.to_string());
+            vec![ValidationIssue {
+                path: format!("{}/{}", path, field),
+                keyword,
+                message: format!("Missing required field: {}", field),
+            }]
+        }
+        ValidationErrorKind::AdditionalProperties { unexpected } => unexpected.iter()
+            .map(|field| ValidationIssue {
+                path: format!("{}/{}", path, field),
+                keyword: keyword.clone(),
+                message: format!("Unknown field: {}", field),
+            })
+            .collect(),
+        _ => vec![ValidationIssue { path, keyword, message: error.to_string() }],
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serde_json::json;
+    use crate::tools::ToolRegistry;
+    use rag_core::models::tool_catalog::DynamicToolSpec;
+
+    /// Validate against the tool's registered schema, as the server does
+    fn validate(validator: &InputValidator, call: &ToolCall) -> Result<()> {
+        let registry = ToolRegistry::new().unwrap();
+        let schema = registry.input_schema(&call.name).expect("registered tool");
+        validator.validate_tool_call(call, &schema)
+    }
+
+    fn issues(result: Result<()>) -> Vec<ValidationIssue> {
+        result.unwrap_err().downcast::<InvalidArguments>().expect("schema violation").issues
+    }
 
     #[test]
     fn test_validator_creation() {
@@ -257,7 +309,7 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
+        let result = validate(&validator, &call);
         assert!(result.is_ok());
     }
 
@@ -270,9 +322,10 @@ mod tests {
             arguments: HashMap::new(),
         };
 
-        let result = validator.validate_tool_call(&call);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Missing required field"));
+        let result = validate(&validator, &call);
+        assert!(result.as_ref().unwrap_err().to_string().contains("Missing required field"));
+        let paths: Vec<String> = issues(result).into_iter().map(|issue| issue.path).collect();
+        assert_eq!(paths, vec!["/collection", "/query"]);
     }
 
     #[test]
@@ -289,9 +342,29 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("top_k must be between"));
+        let issues = issues(validate(&validator, &call));
+        assert_eq!(issues.len(), 1);
+        assert_eq!((issues[0].path.as_str(), issues[0].keyword.as_str()), ("/top_k", "maximum"));
+    }
+
+    #[test]
+    fn test_search_multiple_validation() {
+        let validator = InputValidator::new().unwrap();
+        let call = |collections: Value| ToolCall {
+            name: "kb.search_multiple".to_string(),
+            arguments: HashMap::from([
+                ("collections".to_string(), collections),
+                ("query".to_string(), json!("test query")),
+            ]),
+        };
+
+        assert!(validate(&validator, &call(json!(["kb_a", "kb_b"]))).is_ok());
+        assert!(validate(&validator, &call(json!([]))).is_err());
+        assert!(validate(&validator, &call(json!("kb_a"))).is_err());
+        assert!(validate(&validator, &call(json!(["kb_a", "../etc"]))).is_err());
+        let too_many: Vec<String> = (0..=16).map(|i| format!("kb_{}", i)).collect();
+        assert!(validate(&validator, &call(json!(too_many))).is_err());
+        assert_eq!(issues(validate(&validator, &call(json!(["kb_a", "../etc"]))))[0].path, "/collections/1");
     }
 
     #[test]
@@ -306,7 +379,7 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
+        let result = validate(&validator, &call);
         assert!(result.is_ok());
     }
 
@@ -322,9 +395,8 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("path traversal"));
+        let issues = issues(validate(&validator, &call));
+        assert_eq!((issues[0].path.as_str(), issues[0].keyword.as_str()), ("/doc_id", "pattern"));
     }
 
     #[test]
@@ -339,7 +411,7 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
+        let result = validate(&validator, &call);
         assert!(result.is_ok());
     }
 
@@ -355,9 +427,34 @@ mod tests {
             arguments: args,
         };
 
-        let result = validator.validate_tool_call(&call);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("cannot be empty"));
+        let issues = issues(validate(&validator, &call));
+        assert_eq!((issues[0].path.as_str(), issues[0].keyword.as_str()), ("/chunk_ids", "minItems"));
+    }
+
+    #[test]
+    fn test_capability_policy() {
+        let scopes = |names: &[&str]| names.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let validator = InputValidator::new().unwrap();
+        assert!(validator.validate_scopes("kb.hybrid_search", &scopes(&["kb.read"])).is_ok());
+        assert!(validator.validate_scopes("fetch", &scopes(&["net.fetch"])).is_ok());
+        assert!(validator.validate_scopes("custom", &scopes(&["kb.admin"])).is_err());
+
+        // Air-gapped mode strips network scopes
+        let validator = InputValidator::new().unwrap().with_policy(CapabilityPolicy::new(Scope::ALL, true));
+        let err = validator.validate_scopes("fetch", &scopes(&["kb.read", "net.fetch"])).unwrap_err();
+        assert!(err.to_string().contains("net.fetch"));
+        assert!(validator.validate_scopes("search", &scopes(&["kb.read", "llm.generate"])).is_ok());
+
+        let policy = CapabilityPolicy::from_list("kb.read, fs.read", false).unwrap();
+        assert!(policy.check("read_file", &scopes(&["fs.read"])).is_ok());
+        assert!(policy.check("write", &scopes(&["kb.write"])).is_err());
+        assert!(CapabilityPolicy::from_list("kb.read,bogus", false).is_err());
+
+        // Narrowing never adds scopes back
+        let narrowed = policy.restricted_to(&[Scope::KbRead, Scope::NetFetch]);
+        assert!(narrowed.is_granted(Scope::KbRead));
+        assert!(!narrowed.is_granted(Scope::FsRead) && !narrowed.is_granted(Scope::NetFetch));
     }
 
     #[test]
@@ -386,16 +483,61 @@ mod tests {
     }
 
     #[test]
-    fn test_unknown_tool_validation() {
+    fn test_strict_types_and_unknown_fields() {
         let validator = InputValidator::new().unwrap();
+        let call = ToolCall {
+            name: "rag.answer".to_string(),
+            arguments: HashMap::from([
+                ("collection".to_string(), json!("test_kb")),
+                ("question".to_string(), json!(42)),
+                ("temperature".to_string(), json!(null)),
+                ("verbose".to_string(), json!(true)),
+            ]),
+        };
+
+        let error = validate(&validator, &call).unwrap_err().downcast::<InvalidArguments>().unwrap();
+        let found: Vec<(&str, &str)> = error.issues.iter().map(|i| (i.path.as_str(), i.keyword.as_str())).collect();
+        assert_eq!(found, vec![("/question", "type"), ("/temperature", "type"), ("/verbose", "additionalProperties")]);
 
+        let data = error.to_error_data();
+        assert_eq!(data["reason"], "invalid_arguments");
+        assert_eq!(data["tool"], "rag.answer");
+        assert_eq!(data["errors"][2]["message"], "Unknown field: verbose");
+
+        // Ranges a schema can't express
         let call = ToolCall {
-            name: "unknown.tool".to_string(),
-            arguments: HashMap::new(),
+            name: "kb.get_document".to_string(),
+            arguments: HashMap::from([
+                ("doc_id".to_string(), json!("doc_1")),
+                ("range".to_string(), json!({"start": 10, "end": 5})),
+            ]),
         };
+        assert_eq!(issues(validate(&validator, &call))[0].path, "/range");
+    }
 
-        let result = validator.validate_tool_call(&call);
-        assert!(result.is_err());
-        assert!(result.unwrap_err().to_string().contains("Unknown tool"));
+    #[test]
+    fn test_dynamic_search_validation() {
+        let validator = InputValidator::new().unwrap();
+        let registry = ToolRegistry::new().unwrap();
+        registry.sync_dynamic_tools(vec![DynamicToolSpec {
+            tool_id: "tool_1".to_string(),
+            name: "docs_search".to_string(),
+            description: "Search product docs".to_string(),
+            kb_id: "test_kb".to_string(),
+            top_k: 5,
+            top_n: None,
+            scopes: vec!["kb.read".to_string()],
+        }]);
+        let schema = registry.input_schema("docs_search").unwrap();
+        let call = |arguments: Value| ToolCall {
+            name: "docs_search".to_string(),
+            arguments: serde_json::from_value(arguments).unwrap(),
+        };
+
+        assert!(validator.validate_tool_call(&call(json!({"query": "install steps"})), &schema).is_ok());
+        assert!(validator.validate_tool_call(&call(json!({"query": ""})), &schema).is_err());
+        // The tool's own top_k is the ceiling
+        let result = validator.validate_tool_call(&call(json!({"query": "install", "top_k": 6})), &schema);
+        assert_eq!(issues(result)[0].path, "/top_k");
     }
-}
\ No newline at end of file
+}
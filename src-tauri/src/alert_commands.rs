@@ -0,0 +1,46 @@
+/*!
+ * Alert Tauri Commands
+ *
+ * The in-app alert inbox. New alerts arrive as `alert_raised` events;
+ * per-category severities live in the `alerts` settings section.
+ */
+
+
+use rag_core::{Alert, AlertFilter};
+
+use crate::projects::ActiveManager;
+
+/// Inbox entries, newest first
+#[tauri::command]
+pub async fn list_alerts(
+    manager: ActiveManager,
+    filter: Option<AlertFilter>,
+) -> Result<Vec<Alert>, String> {
+    manager.alert_service
+        .list(&filter.unwrap_or_default())
+        .await
+        .map_err(|e| format!("Failed to list alerts: {}", e))
+}
+
+/// Number of unread alerts (status bar badge)
+#[tauri::command]
+pub async fn get_unread_alert_count(
+    manager: ActiveManager,
+) -> Result<u64, String> {
+    manager.alert_service
+        .unread_count()
+        .await
+        .map_err(|e| format!("Failed to count alerts: {}", e))
+}
+
+/// Mark one alert read, or all of them when no id is given
+#[tauri::command]
+pub async fn mark_alert_read(
+    manager: ActiveManager,
+    alert_id: Option<String>,
+) -> Result<(), String> {
+    manager.alert_service
+        .mark_read(alert_id.as_deref())
+        .await
+        .map_err(|e| format!("Failed to mark alert read: {}", e))
+}
@@ -0,0 +1,288 @@
+/*!
+ * OpenAI-Compatible API Server
+ *
+ * Optional loopback HTTP server so external apps (IDE plugins, scripts) can
+ * use RAG Studio KBs through a standard OpenAI client. The `model` field
+ * names the knowledge base; chat completions run the grounded answer
+ * pipeline and add a `citations` array to the response.
+ * `/v1/embeddings` runs the Manager's in-process embedding backend; its
+ * `model` is a knowledge base (embedded with that KB's model) or an
+ * embedding model id. It answers 503 in builds without a backend.
+ * MVP: no streaming.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use rag_core::models::outbound_rpc::token_matches;
+use rag_core::modules::kb::{answer_question, AnswerError, AnswerRequest, KbError, KbService};
+use rag_core::{GenerationParams, LlmError, LlmProviderConfig, ModelError};
+use crate::manager::Manager;
+
+/// Default port (loopback only)
+pub const DEFAULT_API_PORT: u16 = 8765;
+
+/// Chunks retrieved per question unless the request sets `top_k`
+const DEFAULT_TOP_K: usize = 5;
+
+/// Running server, owned by the Manager
+pub struct ApiServerHandle {
+    addr: SocketAddr,
+    auth_required: bool,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// Server status for the settings UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiServerStatus {
+    pub running: bool,
+    pub address: Option<String>,     // Base URL for OpenAI clients, e.g. http://127.0.0.1:8765/v1
+    pub auth_required: bool,
+}
+
+impl ApiServerStatus {
+    pub fn of(handle: Option<&ApiServerHandle>) -> Self {
+        Self {
+            running: handle.is_some(),
+            address: handle.map(|h| format!("http://{}/v1", h.addr)),
+            auth_required: handle.is_some_and(|h| h.auth_required),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct ApiState {
+    manager: Arc<Manager>,
+    api_key: Option<Arc<String>>,
+}
+
+/// Bind and serve until the handle is stopped
+pub async fn start(manager: Arc<Manager>, port: u16, api_key: Option<String>) -> std::io::Result<ApiServerHandle> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    let addr = listener.local_addr()?;
+    let auth_required = api_key.is_some();
+
+    let app = Router::new()
+        .route("/v1/models", get(list_models))
+        .route("/v1/chat/completions", post(chat_completions))
+        .route("/v1/embeddings", post(embeddings))
+        .with_state(ApiState { manager, api_key: api_key.map(Arc::new) });
+
+    let (shutdown, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+        if let Err(e) = server.await {
+            warn!("API server stopped with error: {}", e);
+        }
+    });
+
+    info!("OpenAI-compatible API server listening on {}", addr);
+    Ok(ApiServerHandle { addr, auth_required, shutdown })
+}
+
+impl ApiServerHandle {
+    pub fn stop(self) {
+        let _ = self.shutdown.send(());
+        info!("OpenAI-compatible API server on {} stopped", self.addr);
+    }
+}
+
+/// OpenAI-style error response
+fn api_error(status: StatusCode, error_type: &str, message: &str) -> Response {
+    (status, Json(json!({
+        "error": { "message": message, "type": error_type, "code": null }
+    }))).into_response()
+}
+
+/// Rejection for requests without the configured API key
+fn unauthorized(state: &ApiState, headers: &HeaderMap) -> Option<Response> {
+    let api_key = state.api_key.as_ref()?;
+    let presented = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    // Constant-time, like the outbound RPC token check
+    (!presented.is_some_and(|key| token_matches(api_key, key)))
+        .then(|| api_error(StatusCode::UNAUTHORIZED, "invalid_request_error", "Invalid API key"))
+}
+
+/// One model per knowledge base
+async fn list_models(State(state): State<ApiState>, headers: HeaderMap) -> Response {
+    if let Some(response) = unauthorized(&state, &headers) {
+        return response;
+    }
+    match state.manager.kb_service.list_collections(None).await {
+        Ok(collections) => Json(json!({
+            "object": "list",
+            "data": collections.iter().map(|kb| json!({
+                "id": kb.id,
+                "object": "model",
+                "owned_by": "rag-studio",
+                "description": kb.description.as_deref().unwrap_or(&kb.name),
+            })).collect::<Vec<_>>(),
+        })).into_response(),
+        Err(e) => api_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatMessage {
+    role: String,
+    content: Value,         // String or array of content parts
+}
+
+#[derive(Debug, Deserialize)]
+struct ChatCompletionRequest {
+    model: String,          // Knowledge base id
+    messages: Vec<ChatMessage>,
+    max_tokens: Option<u32>,
+    temperature: Option<f32>,
+    top_p: Option<f32>,
+    #[serde(default)]
+    stop: Option<Value>,    // String or array
+    #[serde(default)]
+    stream: bool,
+    top_k: Option<usize>,   // Extension: chunks to retrieve
+}
+
+/// Text of a message's content (plain string or `text` parts)
+fn message_text(content: &Value) -> String {
+    match content {
+        Value::String(text) => text.clone(),
+        Value::Array(parts) => parts.iter()
+            .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => String::new(),
+    }
+}
+
+async fn chat_completions(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<ChatCompletionRequest>,
+) -> Response {
+    if let Some(response) = unauthorized(&state, &headers) {
+        return response;
+    }
+    if request.stream {
+        return api_error(StatusCode::BAD_REQUEST, "invalid_request_error", "Streaming is not supported");
+    }
+    let Some(question) = request.messages.iter().rev()
+        .find(|m| m.role == "user")
+        .map(|m| message_text(&m.content))
+        .filter(|q| !q.trim().is_empty())
+    else {
+        return api_error(StatusCode::BAD_REQUEST, "invalid_request_error", "No user message to answer");
+    };
+
+    let defaults = GenerationParams::default();
+    let stop = match request.stop {
+        Some(Value::String(s)) => vec![s],
+        Some(value) => serde_json::from_value(value).unwrap_or_default(),
+        None => Vec::new(),
+    };
+    let answer_request = AnswerRequest {
+        collection: request.model.clone(),
+        question,
+        top_k: request.top_k.unwrap_or(DEFAULT_TOP_K),
+        params: GenerationParams {
+            max_tokens: request.max_tokens.unwrap_or(defaults.max_tokens),
+            temperature: request.temperature.unwrap_or(defaults.temperature),
+            top_p: request.top_p.unwrap_or(defaults.top_p),
+            stop,
+            seed: None,
+        },
+        context_tokens: None,
+    };
+
+    let manager = &state.manager;
+    let result = answer_question(
+        manager.kb_service.as_ref(),
+        &manager.llm_service,
+        &LlmProviderConfig::Local,
+        &answer_request,
+    ).await;
+
+    match result {
+        Ok(answer) => Json(json!({
+            "id": format!("chatcmpl-{}", uuid::Uuid::new_v4().simple()),
+            "object": "chat.completion",
+            "created": chrono::Utc::now().timestamp(),
+            "model": request.model,
+            "choices": [{
+                "index": 0,
+                "message": { "role": "assistant", "content": answer.answer },
+                "finish_reason": "stop",
+            }],
+            "usage": { "prompt_tokens": 0, "completion_tokens": 0, "total_tokens": 0 },
+            "citations": answer.citations,
+        })).into_response(),
+        Err(AnswerError::Kb(KbError::KbNotFound(kb))) => {
+            api_error(StatusCode::NOT_FOUND, "model_not_found", &format!("Knowledge base not found: {}", kb))
+        }
+        Err(AnswerError::Llm(e @ LlmError::Policy(_))) => api_error(StatusCode::FORBIDDEN, "permission_error", &e.to_string()),
+        Err(e) => api_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct EmbeddingRequest {
+    model: String,          // Knowledge base id or embedding model id
+    input: Value,           // String or array of strings
+}
+
+async fn embeddings(
+    State(state): State<ApiState>,
+    headers: HeaderMap,
+    Json(request): Json<EmbeddingRequest>,
+) -> Response {
+    if let Some(response) = unauthorized(&state, &headers) {
+        return response;
+    }
+    let Some(backend) = &state.manager.embedding_backend else {
+        return api_error(StatusCode::SERVICE_UNAVAILABLE, "service_unavailable", "This build has no embedding backend");
+    };
+    let texts: Vec<String> = match request.input {
+        Value::String(text) => vec![text],
+        value => match serde_json::from_value(value) {
+            Ok(texts) => texts,
+            Err(_) => return api_error(StatusCode::BAD_REQUEST, "invalid_request_error", "`input` must be a string or an array of strings"),
+        },
+    };
+    if texts.is_empty() {
+        return api_error(StatusCode::BAD_REQUEST, "invalid_request_error", "`input` is empty");
+    }
+
+    // A KB name embeds with the model its chunks were embedded with
+    let model_id = state.manager.state_manager.read_state().knowledge_bases
+        .get(&request.model)
+        .map_or_else(|| request.model.clone(), |kb| kb.embedder_model.clone());
+    match backend.embed(&model_id, &texts).await {
+        Ok(vectors) => Json(json!({
+            "object": "list",
+            "data": vectors.into_iter().enumerate().map(|(index, embedding)| json!({
+                "object": "embedding",
+                "index": index,
+                "embedding": embedding,
+            })).collect::<Vec<_>>(),
+            "model": request.model,
+            "usage": { "prompt_tokens": 0, "total_tokens": 0 },
+        })).into_response(),
+        Err(e @ (ModelError::ModelNotFound(_) | ModelError::InvalidModelId(_))) => {
+            api_error(StatusCode::NOT_FOUND, "model_not_found", &e.to_string())
+        }
+        Err(e @ ModelError::Timeout(_)) => api_error(StatusCode::GATEWAY_TIMEOUT, "server_error", &e.to_string()),
+        Err(e) => api_error(StatusCode::INTERNAL_SERVER_ERROR, "server_error", &e.to_string()),
+    }
+}
@@ -0,0 +1,190 @@
+/*!
+ * Full-App Backup Commands
+ *
+ * `create_backup` writes the open project to one archive; `restore_backup`
+ * unpacks an archive into a new project directory and opens it, which is
+ * how RAG Studio moves to another machine. Both report `backup_progress`
+ * events while they copy.
+ */
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::Utc;
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, State};
+
+use rag_core::services::backup::{self, BACKUP_ARCHIVE_EXTENSION};
+use rag_core::services::projects::{settings_path, PROJECT_MARKER_FILE};
+use rag_core::{ArchiveManifest, ArchiveSection, ArchiveSource, BackupProgress, JobKind, ProjectInfo, Settings};
+
+use crate::lifecycle::Lifecycle;
+use crate::projects::{ActiveManager, ProjectHost};
+use crate::settings_commands::reload_restored_databases;
+
+/// Progress event, for both backup and restore
+#[derive(Debug, Clone, Serialize)]
+struct BackupProgressEvent {
+    operation: &'static str,    // "backup" or "restore"
+    archive: String,
+    #[serde(flatten)]
+    progress: BackupProgress,
+}
+
+fn progress_reporter(app_handle: Option<AppHandle>, operation: &'static str, archive: &Path) -> impl FnMut(BackupProgress) {
+    let archive = archive.display().to_string();
+    move |progress| {
+        if let Some(app_handle) = &app_handle {
+            let event = BackupProgressEvent { operation, archive: archive.clone(), progress };
+            if let Err(e) = app_handle.emit("backup_progress", event) {
+                eprintln!("Failed to emit backup progress: {}", e);
+            }
+        }
+    }
+}
+
+/// Finished archive as shown in Settings
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupArchiveEntry {
+    pub path: String,
+    pub manifest: ArchiveManifest,
+}
+
+/// Write the open project (settings, databases, vector data, blobs, packs
+/// and, when asked, models) to one archive. `dest` defaults to the project's
+/// `backups` directory.
+#[tauri::command]
+pub async fn create_backup(
+    manager: ActiveManager,
+    host: State<'_, Arc<ProjectHost>>,
+    dest: Option<String>,
+    include_models: Option<bool>,
+) -> Result<BackupArchiveEntry, String> {
+    let project_name = host.current_project()
+        .map(|project| project.name)
+        .unwrap_or_else(|| "RAG Studio".to_string());
+    let stamp = Utc::now().format("%Y%m%d_%H%M%S").to_string();
+    let dest = dest.map(PathBuf::from).unwrap_or_else(|| {
+        let slug: String = project_name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+            .collect();
+        manager.root.join("backups").join(format!("{}_{}.{}", slug, stamp, BACKUP_ARCHIVE_EXTENSION))
+    });
+
+    // Databases go in as an online backup set, consistent while writers continue
+    let staging = manager.root.join(format!(".backup_{}", stamp));
+    manager.sql_service.backup(&staging.join("databases")).await
+        .map_err(|e| format!("Database backup failed: {}", e))?;
+
+    let paths = manager.paths();
+    let mut sources = vec![
+        ArchiveSource { section: ArchiveSection::Settings, path: settings_path(&manager.root) },
+        ArchiveSource { section: ArchiveSection::Databases, path: staging.join("databases") },
+        ArchiveSource { section: ArchiveSection::Vectors, path: paths.vector_dir },
+        ArchiveSource { section: ArchiveSection::Blobs, path: paths.blobs_dir },
+        ArchiveSource { section: ArchiveSection::Packs, path: paths.packs_dir },
+    ];
+    if include_models.unwrap_or(false) {
+        sources.push(ArchiveSource { section: ArchiveSection::Models, path: paths.models_dir });
+    }
+
+    // Runs as a background job; progress goes to both the job and `backup_progress`
+    let mut report = progress_reporter(manager.app_handle.clone(), "backup", &dest);
+    let archive = dest.clone();
+    let title = format!("Back up {}", project_name);
+    let result = manager.job_service
+        .run(JobKind::Backup, &title, move |job| async move {
+            tokio::task::spawn_blocking(move || backup::write_archive(&archive, &project_name, &sources, &mut |progress| {
+                if progress.total_bytes > 0 {
+                    job.set_progress(progress.processed_bytes as f32 / progress.total_bytes as f32, progress.section.map(ArchiveSection::dir_name));
+                }
+                report(progress);
+            }))
+                .await
+                .map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Backup failed: {}", e));
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        eprintln!("Failed to remove backup staging directory {:?}: {}", staging, e);
+    }
+    let manifest = result?.map_err(|e| format!("Backup failed: {}", e))?;
+
+    Ok(BackupArchiveEntry { path: dest.display().to_string(), manifest })
+}
+
+/// What an archive holds, and whether this version of the app can restore it
+#[tauri::command]
+pub async fn inspect_backup(path: String) -> Result<ArchiveManifest, String> {
+    let manifest = tokio::task::spawn_blocking(move || backup::read_archive_manifest(Path::new(&path)))
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| e.to_string())?;
+    manifest.check_compatible().map_err(|e| e.to_string())?;
+    Ok(manifest)
+}
+
+/// Restore an archive into `root` as a new project and open it. Databases
+/// from an older app are migrated; archives from a newer app are refused
+/// before anything is written.
+#[tauri::command]
+pub async fn restore_backup(
+    app_handle: AppHandle,
+    host: State<'_, Arc<ProjectHost>>,
+    lifecycle: State<'_, Arc<Lifecycle>>,
+    path: String,
+    root: String,
+    include_models: Option<bool>,
+) -> Result<ProjectInfo, String> {
+    let archive = PathBuf::from(&path);
+    let root = PathBuf::from(root);
+    if root.join(PROJECT_MARKER_FILE).exists() {
+        return Err(format!("{:?} is already a project; restore into a new directory", root));
+    }
+
+    // Settings first: they say where everything else goes
+    let mut report = progress_reporter(Some(app_handle.clone()), "restore", &archive);
+    let settings_targets = HashMap::from([(ArchiveSection::Settings, root.clone())]);
+    let (source, targets) = (archive.clone(), settings_targets);
+    let manifest = tokio::task::spawn_blocking(move || backup::extract_archive(&source, &targets, &mut |_| {}))
+        .await
+        .map_err(|e| format!("Restore failed: {}", e))?
+        .map_err(|e| format!("Restore failed: {}", e))?;
+
+    let paths = Settings::load(&settings_path(&root))
+        .map_err(|e| format!("Restored settings are unusable: {}", e))?
+        .paths
+        .resolved_in(&root);
+    let staging = root.join(format!(".restore_{}", Utc::now().format("%Y%m%d_%H%M%S")));
+    let mut targets = HashMap::from([
+        (ArchiveSection::Databases, staging.join("databases")),
+        (ArchiveSection::Vectors, paths.vector_dir),
+        (ArchiveSection::Blobs, paths.blobs_dir),
+        (ArchiveSection::Packs, paths.packs_dir),
+    ]);
+    if include_models.unwrap_or(true) {
+        targets.insert(ArchiveSection::Models, paths.models_dir);
+    }
+    let source = archive.clone();
+    tokio::task::spawn_blocking(move || backup::extract_archive(&source, &targets, &mut report))
+        .await
+        .map_err(|e| format!("Restore failed: {}", e))?
+        .map_err(|e| format!("Restore failed: {}", e))?;
+
+    // Open the project on fresh databases, then swap the archived ones in
+    host.registry().create(&root, &manifest.project_name).map_err(|e| e.to_string())?;
+    let project = host.open(&app_handle, &lifecycle, &root).await?;
+    let manager = host.manager().ok_or("Restored project did not open")?;
+    let databases = staging.join("databases");
+    if manifest.has(ArchiveSection::Databases) {
+        manager.sql_service.restore(&databases).await
+            .map_err(|e| format!("Database restore failed: {}", e))?;
+        reload_restored_databases(&manager, &path).await?;
+    }
+    if let Err(e) = std::fs::remove_dir_all(&staging) {
+        eprintln!("Failed to remove restore staging directory {:?}: {}", staging, e);
+    }
+
+    println!("Restored '{}' (RAG Studio {}) into {:?}", manifest.project_name, manifest.app_version, project.root);
+    Ok(project)
+}
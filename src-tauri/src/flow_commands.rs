@@ -0,0 +1,119 @@
+/*!
+ * Flow Tauri Commands
+ *
+ * Authoring and running multi-step query flows (rewrite → retrieve → fusion →
+ * rerank → generate). Flows use the provider in their `llm` field, subject
+ * to the network policy.
+ */
+
+use tracing::info;
+
+use rag_core::modules::eval::EvalConfig;
+use rag_core::modules::flow::{FlowEvalReport, FlowRunOutput, FlowSpec};
+
+use crate::projects::ActiveManager;
+
+/// Create (empty id) or update a flow
+#[tauri::command]
+pub async fn save_flow(
+    manager: ActiveManager,
+    mut spec: FlowSpec,
+) -> Result<FlowSpec, String> {
+    // Inline API keys go to the vault; the stored definition only names the secret
+    if let Some(llm) = spec.llm.as_mut() {
+        let secret_name = format!("flow.{}.llm_api_key", uuid::Uuid::new_v4().simple());
+        llm.externalize_api_key(&manager.secrets_service, &secret_name)
+            .map_err(|e| format!("Failed to store API key: {}", e))?;
+    }
+
+    let spec = manager.flow_service
+        .save_flow(spec)
+        .await
+        .map_err(|e| format!("Failed to save flow: {}", e))?;
+
+    manager.emit_state_delta("flow_saved", serde_json::json!(spec)).await;
+    Ok(spec)
+}
+
+/// List flows
+#[tauri::command]
+pub async fn list_flows(
+    manager: ActiveManager,
+) -> Result<Vec<FlowSpec>, String> {
+    manager.flow_service
+        .list_flows()
+        .map_err(|e| format!("Failed to list flows: {}", e))
+}
+
+#[tauri::command]
+pub async fn delete_flow(
+    manager: ActiveManager,
+    flow_id: String,
+) -> Result<(), String> {
+    manager.flow_service
+        .delete_flow(&flow_id)
+        .await
+        .map_err(|e| format!("Failed to delete flow: {}", e))?;
+
+    manager.emit_state_delta("flow_deleted", serde_json::json!({ "flow_id": flow_id })).await;
+    Ok(())
+}
+
+/// Run a saved flow
+#[tauri::command]
+pub async fn run_flow(
+    manager: ActiveManager,
+    flow_id: String,
+    query: String,
+) -> Result<FlowRunOutput, String> {
+    info!("Running flow {}", flow_id);
+
+    let output = manager.flow_service
+        .run_flow(&flow_id, &query)
+        .await
+        .map_err(|e| format!("Flow run failed: {}", e))?;
+
+    manager.emit_state_delta("flow_run_completed", serde_json::json!({
+        "flow_id": flow_id,
+        "run_id": output.run_id,
+        "success": output.is_success(),
+        "error": output.error,
+    })).await;
+    Ok(output)
+}
+
+/// Run an unsaved flow definition from the editor
+#[tauri::command]
+pub async fn test_flow(
+    manager: ActiveManager,
+    spec: FlowSpec,
+    query: String,
+) -> Result<FlowRunOutput, String> {
+    manager.flow_service
+        .test_flow(&spec, &query)
+        .await
+        .map_err(|e| format!("Flow test failed: {}", e))
+}
+
+/// Score a saved flow's query transforms against a KB's golden queries
+#[tauri::command]
+pub async fn evaluate_flow(
+    manager: ActiveManager,
+    flow_id: String,
+    kb_id: String,
+    top_k: Option<usize>,
+) -> Result<FlowEvalReport, String> {
+    let spec = manager.flow_service
+        .get_flow(&flow_id)
+        .map_err(|e| format!("Failed to load flow: {}", e))?;
+    let golden = manager.eval_service
+        .list_golden_queries(&kb_id)
+        .await
+        .map_err(|e| format!("Failed to load golden queries: {}", e))?;
+    let k = top_k.unwrap_or(EvalConfig::default().top_k);
+
+    manager.flow_service
+        .evaluate_flow(&spec, &kb_id, &golden, k)
+        .await
+        .map_err(|e| format!("Flow evaluation failed: {}", e))
+}
@@ -0,0 +1,44 @@
+/*!
+ * Background Job Tauri Commands
+ *
+ * The background tasks panel. Downloads, exports, backups, reindexes and
+ * refresh runs all go through the job service; changes arrive as
+ * `job_update` events.
+ */
+
+use rag_core::JobRecord;
+
+use crate::projects::ActiveManager;
+
+/// Queued, running and finished jobs, newest first
+#[tauri::command]
+pub async fn list_jobs(
+    manager: ActiveManager,
+) -> Result<Vec<JobRecord>, String> {
+    Ok(manager.job_service.list())
+}
+
+#[tauri::command]
+pub async fn get_job(
+    manager: ActiveManager,
+    job_id: String,
+) -> Result<JobRecord, String> {
+    manager.job_service.get(&job_id).map_err(|e| e.to_string())
+}
+
+/// Cancel a queued or running job
+#[tauri::command]
+pub async fn cancel_job(
+    manager: ActiveManager,
+    job_id: String,
+) -> Result<JobRecord, String> {
+    manager.job_service.cancel(&job_id).map_err(|e| format!("Failed to cancel job: {}", e))
+}
+
+/// Remove finished jobs from the panel; returns how many were removed
+#[tauri::command]
+pub async fn clear_finished_jobs(
+    manager: ActiveManager,
+) -> Result<usize, String> {
+    Ok(manager.job_service.clear_finished())
+}
@@ -6,14 +6,18 @@
  */
 
 use std::collections::HashMap;
-use tauri::State;
 use serde::{Serialize, Deserialize};
 use tracing::{info, error};
 
 // Import KbService trait for method calls
-use rag_core::modules::kb::KbService;
+use rag_core::modules::kb::{ConsistencyReport, DocumentInfo, KbService, KbVersion, KbVersionDiff};
+use rag_core::state::{KnowledgeBaseState, KnowledgeBaseStatus as CoreKbStatus, StateDelta, WorkspaceState};
+use rag_core::modules::eval::{EvalRun, GoldenQuery};
+use rag_core::schemas::Highlight;
+use rag_core::{AlertCategory, JobContext, JobKind, JobRecord, MetricsSnapshot};
 
 use crate::manager::{Manager, KnowledgeBase, KnowledgeBaseStatus, IngestRun};
+use crate::projects::ActiveManager;
 
 /// Request/Response types for frontend integration
 
@@ -35,6 +39,8 @@ pub struct SearchRequest {
     pub query: String,
     pub top_k: Option<usize>,
     pub filters: Option<HashMap<String, serde_json::Value>>,
+    pub version: Option<i32>,          // Explicit version, overrides workspace pins
+    pub workspace_id: Option<String>,  // Defaults to the active workspace
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -42,12 +48,43 @@ pub struct SearchResult {
     pub chunk_id: String,
     pub score: f32,
     pub snippet: String,
+    pub highlights: Vec<Highlight>,    // Character offsets into `snippet`
     pub title: String,
     pub document_id: String,
     pub citation: CitationInfo,
     pub metadata: serde_json::Value,
 }
 
+impl From<rag_core::schemas::SearchResult> for SearchResult {
+    fn from(result: rag_core::schemas::SearchResult) -> Self {
+        SearchResult {
+            chunk_id: result.chunk_id,
+            score: result.score,
+            snippet: result.snippet,
+            highlights: result.highlights,
+            title: result.citation.title.clone(),
+            document_id: result.document_id,
+            citation: CitationInfo {
+                title: result.citation.title,
+                url: Some(result.citation.source_path), // Use source_path as URL
+                license: result.citation.license,
+                version: result.citation.version,
+                anchor: result.citation.anchor,
+                time_range: result.citation.time_range,
+            },
+            metadata: result.metadata,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct FederatedSearchRequest {
+    pub kb_ids: Vec<String>,
+    pub query: String,
+    pub top_k: Option<usize>,
+    pub filters: Option<HashMap<String, serde_json::Value>>,
+}
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct CitationInfo {
     pub title: String,
@@ -55,12 +92,20 @@ pub struct CitationInfo {
     pub license: Option<String>,
     pub version: Option<String>,
     pub anchor: Option<String>,
+    pub time_range: Option<rag_core::schemas::TimeRange>,  // Set for transcript chunks
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct GoldenQueryRequest {
+    pub kb_id: String,
+    pub question: String,
+    pub relevant_chunk_ids: Vec<String>,
 }
 
 /// Get all knowledge bases with current status
 #[tauri::command]
 pub async fn get_knowledge_bases(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
 ) -> Result<Vec<KnowledgeBase>, String> {
     info!("Getting knowledge bases list");
 
@@ -74,7 +119,7 @@ pub async fn get_knowledge_bases(
 /// Create a new knowledge base
 #[tauri::command]
 pub async fn create_knowledge_base(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     request: CreateKBRequest,
 ) -> Result<KnowledgeBase, String> {
     info!("Creating knowledge base: {}", request.name);
@@ -99,6 +144,27 @@ pub async fn create_knowledge_base(
         updated_at: chrono::Utc::now().to_rfc3339(),
     };
 
+    // Register with the core state so KB services (documents, versions, export) can find it
+    manager.state_manager
+        .mutate(StateDelta::KnowledgeBaseAdd {
+            kb: KnowledgeBaseState {
+                id: kb_id.clone(),
+                name: request.name.clone(),
+                version: 1,
+                status: CoreKbStatus::Building,
+                embedder_model: "sentence-transformers/all-MiniLM-L6-v2".to_string(),
+                health_score: 0.0,
+                document_count: 0,
+                chunk_count: 0,
+                last_updated: chrono::Utc::now(),
+                metadata: serde_json::json!({
+                    "product": request.product,
+                    "description": request.description
+                }),
+            },
+        })
+        .map_err(|e| format!("Failed to register knowledge base: {}", e))?;
+
     // Add to state
     {
         let mut state = manager.app_state.write().await;
@@ -111,11 +177,12 @@ pub async fn create_knowledge_base(
         "kb": new_kb
     })).await;
 
-    // Start indexing process (simulated for MVP) - run in background
-    let manager_clone = (*manager).clone();
+    // Start indexing process (simulated for MVP) as a background job
+    let manager_clone = manager.inner().clone();
     let kb_id_clone = kb_id.clone();
-    tauri::async_runtime::spawn(async move {
-        simulate_indexing_process(&manager_clone, &kb_id_clone).await;
+    manager.job_service.spawn(JobKind::Reindex, &format!("Index {}", request.name), move |job| async move {
+        simulate_indexing_process(&manager_clone, &kb_id_clone, &job).await;
+        Ok(())
     });
 
     info!("Knowledge base created: {}", kb_id);
@@ -125,45 +192,51 @@ pub async fn create_knowledge_base(
 /// Search in knowledge base using hybrid search
 #[tauri::command]
 pub async fn search_knowledge_base(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     request: SearchRequest,
 ) -> Result<Vec<SearchResult>, String> {
     info!("Searching in collection: {} with query: {}", request.collection, request.query);
 
     let start_time = std::time::Instant::now();
 
+    // Explicit version wins, otherwise resolve the workspace's pinned version
+    let version = match (request.version, request.workspace_id.as_deref()) {
+        (Some(version), _) => Some(version),
+        (None, Some(workspace_id)) => manager.kb_service
+            .resolve_version(&request.collection, Some(workspace_id))
+            .await
+            .map_err(|e| format!("Search failed: {}", e))?
+            .map(|v| v.version),
+        (None, None) => None,
+    };
+
     // Call KB service for hybrid search
-    let search_results = manager.kb_service
-        .hybrid_search(
-            &request.collection,
-            &request.query,
-            request.top_k.unwrap_or(10),
-            request.filters,
-            None, // No cache TTL for MVP
-        )
-        .await
-        .map_err(|e| format!("Search failed: {}", e))?;
+    let search_results = match version {
+        Some(version) => manager.kb_service
+            .hybrid_search_version(
+                &request.collection,
+                version,
+                &request.query,
+                request.top_k.unwrap_or(10),
+                request.filters,
+            )
+            .await,
+        None => manager.kb_service
+            .hybrid_search(
+                &request.collection,
+                &request.query,
+                request.top_k.unwrap_or(10),
+                request.filters,
+                None, // No cache TTL for MVP
+            )
+            .await,
+    }
+    .map_err(|e| format!("Search failed: {}", e))?;
 
     let latency_ms = start_time.elapsed().as_millis() as f32;
 
     // Convert to frontend format
-    let results: Vec<SearchResult> = search_results.into_iter().map(|result| {
-        SearchResult {
-            chunk_id: result.chunk_id,
-            score: result.score,
-            snippet: result.snippet,
-            title: result.citation.title.clone(),
-            document_id: result.document_id,
-            citation: CitationInfo {
-                title: result.citation.title,
-                url: Some(result.citation.source_path), // Use source_path as URL
-                license: result.citation.license,
-                version: result.citation.version,
-                anchor: result.citation.anchor,
-            },
-            metadata: result.metadata,
-        }
-    }).collect();
+    let results: Vec<SearchResult> = search_results.into_iter().map(SearchResult::from).collect();
 
     // Update metrics
     {
@@ -184,10 +257,37 @@ pub async fn search_knowledge_base(
     Ok(results)
 }
 
+/// Search several knowledge bases at once; `metadata.source_kb` names each result's KB
+#[tauri::command]
+pub async fn search_knowledge_bases(
+    manager: ActiveManager,
+    request: FederatedSearchRequest,
+) -> Result<Vec<SearchResult>, String> {
+    info!("Federated search over {} collections with query: {}", request.kb_ids.len(), request.query);
+
+    let start_time = std::time::Instant::now();
+    let search_results = manager.kb_service
+        .search_multiple(&request.kb_ids, &request.query, request.top_k.unwrap_or(10), request.filters)
+        .await
+        .map_err(|e| format!("Search failed: {}", e))?;
+    let latency_ms = start_time.elapsed().as_millis() as f32;
+
+    let results: Vec<SearchResult> = search_results.into_iter().map(SearchResult::from).collect();
+
+    manager.emit_state_delta("search_completed", serde_json::json!({
+        "collections": request.kb_ids,
+        "query": request.query,
+        "results_count": results.len(),
+        "latency_ms": latency_ms
+    })).await;
+
+    Ok(results)
+}
+
 /// Delete a knowledge base
 #[tauri::command]
 pub async fn delete_knowledge_base(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     kb_id: String,
 ) -> Result<(), String> {
     info!("Deleting knowledge base: {}", kb_id);
@@ -205,7 +305,10 @@ pub async fn delete_knowledge_base(
         }
     }
 
-    // TODO: Call KB service to actually delete data
+    manager.kb_service
+        .delete_collection(&kb_id)
+        .await
+        .map_err(|e| format!("Failed to remove knowledge base: {}", e))?;
 
     // Emit state delta
     manager.emit_state_delta("kb_deleted", serde_json::json!({
@@ -216,39 +319,78 @@ pub async fn delete_knowledge_base(
     Ok(())
 }
 
-/// Export knowledge base as .kbpack file
+/// Export knowledge base as a portable .ragkb archive
 #[tauri::command]
 pub async fn export_knowledge_base(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     kb_id: String,
 ) -> Result<Vec<u8>, String> {
     info!("Exporting knowledge base: {}", kb_id);
 
-    // Find KB
-    let kb = {
-        let state = manager.app_state.read().await;
-        state.knowledge_bases.iter()
-            .find(|kb| kb.id == kb_id)
-            .cloned()
-            .ok_or("Knowledge base not found")?
+    let kb_service = manager.kb_service.clone();
+    let export_id = kb_id.clone();
+    let data = manager.job_service
+        .run(JobKind::KbExport, &format!("Export {}", kb_id), move |_| async move {
+            kb_service.export_kb(&export_id).await.map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Failed to export knowledge base: {}", e))?;
+
+    info!("Knowledge base exported: {} ({} bytes)", kb_id, data.len());
+    Ok(data)
+}
+
+/// Import a .ragkb archive exported on another machine. Unsigned archives
+/// need `allow_unsigned`, set once the user confirms the `inspect_pack` prompt.
+#[tauri::command]
+pub async fn import_knowledge_base(
+    manager: ActiveManager,
+    data: Vec<u8>,
+    allow_unsigned: Option<bool>,
+) -> Result<KnowledgeBase, String> {
+    let kb_id = manager.kb_service
+        .import_kb(&data, allow_unsigned.unwrap_or(false))
+        .await
+        .map_err(|e| format!("Failed to import knowledge base: {}", e))?;
+
+    let imported = manager.state_manager.read_state().knowledge_bases.get(&kb_id).cloned()
+        .ok_or("Imported knowledge base missing from state")?;
+
+    let new_kb = KnowledgeBase {
+        id: imported.id.clone(),
+        name: imported.name.clone(),
+        product: imported.metadata.get("product").and_then(|v| v.as_str()).unwrap_or("default").to_string(),
+        version: imported.version.to_string(),
+        description: imported.metadata.get("description").and_then(|v| v.as_str()).map(str::to_string),
+        status: KnowledgeBaseStatus::Indexed,
+        document_count: imported.document_count as u32,
+        chunk_count: imported.chunk_count as u32,
+        index_size: 0,
+        health_score: imported.health_score as f32,
+        created_at: chrono::Utc::now().to_rfc3339(),
+        updated_at: imported.last_updated.to_rfc3339(),
     };
 
-    // TODO: Call KB service to export actual data
-    // For MVP, return mock ZIP data
-    let mock_export_data = format!(
-        "{{\"kb_id\": \"{}\", \"name\": \"{}\", \"version\": \"{}\", \"exported_at\": \"{}\"}}",
-        kb.id, kb.name, kb.version, chrono::Utc::now().to_rfc3339()
-    );
+    {
+        let mut state = manager.app_state.write().await;
+        state.knowledge_bases.push(new_kb.clone());
+        state.metrics.total_kbs += 1;
+    }
+
+    manager.emit_state_delta("kb_created", serde_json::json!({
+        "kb": new_kb
+    })).await;
 
-    info!("Knowledge base exported: {}", kb_id);
-    Ok(mock_export_data.into_bytes())
+    info!("Knowledge base imported: {}", kb_id);
+    Ok(new_kb)
 }
 
 /// Start reindexing a knowledge base
 #[tauri::command]
 pub async fn reindex_knowledge_base(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     kb_id: String,
+    full: Option<bool>,
 ) -> Result<(), String> {
     info!("Starting reindex for knowledge base: {}", kb_id);
 
@@ -256,37 +398,386 @@ pub async fn reindex_knowledge_base(
     manager.update_kb_status(&kb_id, KnowledgeBaseStatus::Indexing).await
         .map_err(|e| format!("Failed to update KB status: {}", e))?;
 
-    // Start reindexing process (simulated for MVP) - run in background
-    let manager_clone = (*manager).clone();
+    // Incremental by default: documents whose fingerprint is unchanged are skipped
+    let manager_clone = manager.inner().clone();
     let kb_id_clone = kb_id.clone();
-    tauri::async_runtime::spawn(async move {
-        simulate_indexing_process(&manager_clone, &kb_id_clone).await;
+    let full = full.unwrap_or(false);
+    manager.job_service.spawn(JobKind::Reindex, &format!("Reindex {}", kb_id), move |job| async move {
+        job.set_progress(0.0, Some("reindexing"));
+        let plan = match manager_clone.kb_service.reindex_sources(&kb_id_clone, full).await {
+            Ok(plan) => plan,
+            Err(e) => {
+                error!("Reindex failed for KB {}: {}", kb_id_clone, e);
+                let _ = manager_clone.update_kb_status(&kb_id_clone, KnowledgeBaseStatus::Failed).await;
+                return Err(e.to_string());
+            }
+        };
+        job.set_progress(1.0, Some("completed"));
+
+        // Counts come from the fingerprints the reindex just recorded
+        let counts = manager_clone.state_manager.read_state().knowledge_bases.get(&kb_id_clone)
+            .map(|kb| (kb.document_count, kb.chunk_count));
+        if let Some((document_count, chunk_count)) = counts {
+            let mut state = manager_clone.app_state.write().await;
+            if let Some(kb) = state.knowledge_bases.iter_mut().find(|kb| kb.id == kb_id_clone) {
+                kb.document_count = document_count as u32;
+                kb.chunk_count = chunk_count as u32;
+            }
+        }
+
+        if let Err(e) = manager_clone.update_kb_status(&kb_id_clone, KnowledgeBaseStatus::Indexed).await {
+            error!("Failed to update KB status to indexed: {}", e);
+        }
+        manager_clone.emit_state_delta("kb_indexing_completed", serde_json::json!({
+            "kb_id": kb_id_clone,
+            "changed": plan.changed.len(),
+            "unchanged": plan.unchanged.len(),
+            "removed": plan.removed.len()
+        })).await;
+        Ok(())
     });
 
     info!("Reindexing started for knowledge base: {}", kb_id);
     Ok(())
 }
 
+/// Rebuild a knowledge base's BM25 index as a background job
+#[tauri::command]
+pub async fn rebuild_bm25_index(
+    manager: ActiveManager,
+    kb_id: String,
+) -> Result<JobRecord, String> {
+    if !manager.state_manager.read_state().knowledge_bases.contains_key(&kb_id) {
+        return Err(format!("Knowledge base not found: {}", kb_id));
+    }
+    Ok(manager.spawn_index_maintenance(&kb_id, true))
+}
+
+/// Compact a knowledge base's indexes as a background job
+#[tauri::command]
+pub async fn optimize_index(
+    manager: ActiveManager,
+    kb_id: String,
+) -> Result<JobRecord, String> {
+    if !manager.state_manager.read_state().knowledge_bases.contains_key(&kb_id) {
+        return Err(format!("Knowledge base not found: {}", kb_id));
+    }
+    Ok(manager.spawn_index_maintenance(&kb_id, false))
+}
+
+/// Register a golden question for regression evaluation
+#[tauri::command]
+pub async fn add_kb_golden_query(
+    manager: ActiveManager,
+    request: GoldenQueryRequest,
+) -> Result<GoldenQuery, String> {
+    manager.eval_service
+        .add_golden_query(&request.kb_id, &request.question, request.relevant_chunk_ids)
+        .await
+        .map_err(|e| format!("Failed to add golden query: {}", e))
+}
+
+/// List golden questions registered for a knowledge base
+#[tauri::command]
+pub async fn list_kb_golden_queries(
+    manager: ActiveManager,
+    kb_id: String,
+) -> Result<Vec<GoldenQuery>, String> {
+    manager.eval_service
+        .list_golden_queries(&kb_id)
+        .await
+        .map_err(|e| format!("Failed to list golden queries: {}", e))
+}
+
+/// Run golden-dataset evaluation for a KB version and compare with the previous one
+#[tauri::command]
+pub async fn run_kb_evaluation(
+    manager: ActiveManager,
+    kb_id: String,
+    version: i32,
+) -> Result<EvalRun, String> {
+    info!("Running evaluation for knowledge base: {} v{}", kb_id, version);
+
+    let run = manager.eval_service
+        .run_regression(&kb_id, version)
+        .await
+        .map_err(|e| format!("Evaluation failed: {}", e))?;
+
+    if !run.passed {
+        manager.alert_service.notify(
+            AlertCategory::EvalRegression,
+            "Evaluation regressed",
+            &format!("{} v{} scored worse than v{:?} on its golden set", kb_id, version, run.baseline_version),
+            Some(&kb_id),
+            serde_json::json!({ "version": version, "deltas": run.deltas }),
+        ).await;
+    }
+
+    manager.emit_state_delta("kb_evaluation_completed", serde_json::json!({
+        "kb_id": kb_id,
+        "version": version,
+        "passed": run.passed,
+        "metrics": run.metrics,
+        "deltas": run.deltas
+    })).await;
+
+    Ok(run)
+}
+
+/// Add documents from local paths to an existing knowledge base
+#[tauri::command]
+pub async fn add_kb_documents(
+    manager: ActiveManager,
+    kb_id: String,
+    sources: Vec<String>,
+) -> Result<Vec<DocumentInfo>, String> {
+    info!("Adding {} documents to knowledge base: {}", sources.len(), kb_id);
+
+    let added = manager.kb_service
+        .add_documents(&kb_id, sources)
+        .await
+        .map_err(|e| format!("Failed to add documents: {}", e))?;
+
+    manager.emit_state_delta("kb_documents_changed", serde_json::json!({
+        "kb_id": kb_id,
+        "added": added.iter().map(|d| &d.id).collect::<Vec<_>>()
+    })).await;
+
+    Ok(added)
+}
+
+/// Remove a document from a knowledge base
+#[tauri::command]
+pub async fn remove_kb_document(
+    manager: ActiveManager,
+    kb_id: String,
+    doc_id: String,
+) -> Result<(), String> {
+    manager.kb_service
+        .remove_document(&kb_id, &doc_id)
+        .await
+        .map_err(|e| format!("Failed to remove document: {}", e))?;
+
+    manager.emit_state_delta("kb_documents_changed", serde_json::json!({
+        "kb_id": kb_id,
+        "removed": [doc_id]
+    })).await;
+
+    Ok(())
+}
+
+/// Reindex a single document from its source
+#[tauri::command]
+pub async fn update_kb_document(
+    manager: ActiveManager,
+    kb_id: String,
+    doc_id: String,
+) -> Result<DocumentInfo, String> {
+    let updated = manager.kb_service
+        .update_document(&kb_id, &doc_id)
+        .await
+        .map_err(|e| format!("Failed to update document: {}", e))?;
+
+    manager.emit_state_delta("kb_documents_changed", serde_json::json!({
+        "kb_id": kb_id,
+        "updated": [doc_id]
+    })).await;
+
+    Ok(updated)
+}
+
+/// Report divergence between a KB's SQL metadata and vector store, optionally repairing it
+#[tauri::command]
+pub async fn check_kb_consistency(
+    manager: ActiveManager,
+    kb_id: String,
+    repair: Option<bool>,
+) -> Result<ConsistencyReport, String> {
+    let report = manager.kb_service
+        .check_consistency(&kb_id, repair.unwrap_or(false))
+        .await
+        .map_err(|e| format!("Failed to check consistency: {}", e))?;
+
+    if report.repaired {
+        manager.emit_state_delta("kb_documents_changed", serde_json::json!({
+            "kb_id": kb_id,
+            "repaired": true
+        })).await;
+    }
+
+    Ok(report)
+}
+
+/// List immutable versions of a knowledge base, newest first
+#[tauri::command]
+pub async fn list_kb_versions(
+    manager: ActiveManager,
+    kb_id: String,
+) -> Result<Vec<KbVersion>, String> {
+    manager.kb_service
+        .list_versions(&kb_id)
+        .await
+        .map_err(|e| format!("Failed to list versions: {}", e))
+}
+
+/// Activate a KB version so search serves from its index
+#[tauri::command]
+pub async fn activate_kb_version(
+    manager: ActiveManager,
+    kb_id: String,
+    version: i32,
+) -> Result<KbVersion, String> {
+    info!("Activating knowledge base {} v{}", kb_id, version);
+
+    let activated = manager.kb_service
+        .activate_version(&kb_id, version)
+        .await
+        .map_err(|e| format!("Failed to activate version: {}", e))?;
+
+    manager.emit_state_delta("kb_version_activated", serde_json::json!({
+        "kb_id": kb_id,
+        "version": version
+    })).await;
+
+    Ok(activated)
+}
+
+/// Delete a non-active KB version
+#[tauri::command]
+pub async fn delete_kb_version(
+    manager: ActiveManager,
+    kb_id: String,
+    version: i32,
+) -> Result<(), String> {
+    manager.kb_service
+        .delete_version(&kb_id, version)
+        .await
+        .map_err(|e| format!("Failed to delete version: {}", e))
+}
+
+/// Diff two KB versions so a reindex can be audited before activation
+#[tauri::command]
+pub async fn diff_kb_versions(
+    manager: ActiveManager,
+    kb_id: String,
+    from_version: i32,
+    to_version: i32,
+) -> Result<KbVersionDiff, String> {
+    manager.kb_service
+        .diff_versions(&kb_id, from_version, to_version)
+        .await
+        .map_err(|e| format!("Failed to diff versions: {}", e))
+}
+
+/// Create a workspace profile for pinning KB versions
+#[tauri::command]
+pub async fn create_workspace(
+    manager: ActiveManager,
+    name: String,
+) -> Result<WorkspaceState, String> {
+    let now = chrono::Utc::now();
+    let workspace = WorkspaceState {
+        id: format!("ws_{}", uuid::Uuid::new_v4().simple()),
+        name,
+        pinned_versions: HashMap::new(),
+        created_at: now,
+        updated_at: now,
+    };
+
+    manager.state_manager
+        .mutate(StateDelta::WorkspaceAdd { workspace: workspace.clone() })
+        .map_err(|e| format!("Failed to create workspace: {}", e))?;
+
+    manager.emit_state_delta("workspace_created", serde_json::json!(workspace)).await;
+    Ok(workspace)
+}
+
+/// List workspace profiles
+#[tauri::command]
+pub async fn list_workspaces(
+    manager: ActiveManager,
+) -> Result<Vec<WorkspaceState>, String> {
+    Ok(manager.state_manager.read_state().workspaces.values().cloned().collect())
+}
+
+/// Set the workspace used when queries don't name one (None clears it)
+#[tauri::command]
+pub async fn set_active_workspace(
+    manager: ActiveManager,
+    workspace_id: Option<String>,
+) -> Result<(), String> {
+    manager.state_manager
+        .mutate(StateDelta::WorkspaceActivate { id: workspace_id.clone() })
+        .map_err(|e| format!("Failed to activate workspace: {}", e))?;
+
+    manager.emit_state_delta("workspace_activated", serde_json::json!({
+        "workspace_id": workspace_id
+    })).await;
+    Ok(())
+}
+
+/// Pin a KB version in a workspace (None unpins and follows the active version)
+#[tauri::command]
+pub async fn pin_kb_version(
+    manager: ActiveManager,
+    workspace_id: String,
+    kb_id: String,
+    version: Option<i32>,
+) -> Result<(), String> {
+    if let Some(version) = version {
+        // Reject pins to versions that don't exist
+        let versions = manager.kb_service
+            .list_versions(&kb_id)
+            .await
+            .map_err(|e| format!("Failed to pin version: {}", e))?;
+        if !versions.iter().any(|v| v.version == version) {
+            return Err(format!("Version {} of knowledge base {} not found", version, kb_id));
+        }
+    }
+
+    manager.state_manager
+        .mutate(StateDelta::WorkspacePin {
+            workspace_id: workspace_id.clone(),
+            kb_id: kb_id.clone(),
+            version,
+        })
+        .map_err(|e| format!("Failed to pin version: {}", e))?;
+
+    manager.emit_state_delta("workspace_pin_updated", serde_json::json!({
+        "workspace_id": workspace_id,
+        "kb_id": kb_id,
+        "version": version
+    })).await;
+    Ok(())
+}
+
 /// Get application state for initial load
 #[tauri::command]
 pub async fn get_app_state(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
 ) -> Result<crate::manager::AppState, String> {
     let state = manager.app_state.read().await;
     Ok(state.clone())
 }
 
-/// Get health status of all services
+/// Get health status of all subsystems (probed now)
 #[tauri::command]
 pub async fn get_health_status(
-    manager: State<'_, Manager>,
-) -> Result<serde_json::Value, String> {
-    manager.health_check().await
-        .map_err(|e| format!("Health check failed: {}", e))
+    manager: ActiveManager,
+) -> Result<rag_core::HealthReport, String> {
+    Ok(manager.health_check().await)
+}
+
+/// Counters and latency histograms for the dashboard
+#[tauri::command]
+pub async fn get_metrics(
+    manager: ActiveManager,
+) -> Result<MetricsSnapshot, String> {
+    Ok(manager.metrics_snapshot())
 }
 
 /// Simulate indexing process for MVP (will be replaced with real implementation)
-async fn simulate_indexing_process(manager: &Manager, kb_id: &str) {
+async fn simulate_indexing_process(manager: &Manager, kb_id: &str, job: &JobContext) {
     info!("Starting simulated indexing for KB: {}", kb_id);
 
     // Simulate indexing steps with progress
@@ -309,6 +800,7 @@ async fn simulate_indexing_process(manager: &Manager, kb_id: &str) {
             "progress": progress
         })).await;
 
+        job.set_progress(progress, Some(step));
         info!("KB {} indexing progress: {} ({}%)", kb_id, step, (progress * 100.0) as u32);
     }
 
@@ -2,32 +2,45 @@ mod python_integration;
 mod manager;
 mod kb_commands;
 mod settings_commands;
+mod prompt_commands;
+mod tools_commands;
+mod flow_commands;
+mod memory_commands;
+mod model_commands;
+mod log_commands;
+mod state_commands;
+mod pack_commands;
+mod schedule_commands;
+mod alert_commands;
+mod outbound_server;
+mod api_server;
+mod metrics_server;
+mod lifecycle;
+mod services;
+mod projects;
+mod backup_commands;
+mod job_commands;
 
 use python_integration::PythonContext;
-use std::sync::OnceLock;
 use std::sync::Arc;
 use tauri::Manager as TauriManager; // Add Tauri Manager trait
 // Import our core crate and KB module
 use rag_core::{SqlService, SqlConfig};
 use rag_core::modules::kb::{KbService, KbServiceImpl};
-use manager::Manager;
 use kb_commands::*;
 use settings_commands::*;
-
-// Global Python context instance using OnceLock for thread-safe lazy initialization
-static PYTHON_CONTEXT: OnceLock<PythonContext> = OnceLock::new();
-// Global Manager instance for application services
-static MANAGER: OnceLock<Arc<Manager>> = OnceLock::new();
-
-fn get_python_context() -> &'static PythonContext {
-    PYTHON_CONTEXT.get_or_init(|| {
-        PythonContext::new().expect("Failed to initialize Python context")
-    })
-}
-
-async fn get_manager() -> Arc<Manager> {
-    MANAGER.get().unwrap().clone()
-}
+use prompt_commands::*;
+use tools_commands::*;
+use flow_commands::*;
+use memory_commands::*;
+use model_commands::*;
+use log_commands::*;
+use state_commands::*;
+use pack_commands::*;
+use schedule_commands::*;
+use alert_commands::*;
+use backup_commands::*;
+use job_commands::*;
 
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
 #[tauri::command]
@@ -37,8 +50,7 @@ fn greet(name: &str) -> String {
 
 // Clean Rust -> Python call using reorganized module
 #[tauri::command]
-fn rust_call_python(name: &str) -> Result<String, String> {
-    let python_ctx = get_python_context();
+fn rust_call_python(python_ctx: tauri::State<'_, PythonContext>, name: &str) -> Result<String, String> {
     python_ctx.call_greeting(name)
 }
 
@@ -67,67 +79,185 @@ async fn test_sql_setup() -> Result<String, String> {
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_notification::init())
         .invoke_handler(tauri::generate_handler![
             greet,
             rust_call_python,
             test_sql_setup,
+            lifecycle::get_startup_status,
+            // Project Commands
+            projects::list_recent_projects,
+            projects::get_current_project,
+            projects::create_project,
+            projects::open_project,
+            projects::close_project,
+            projects::forget_project,
             // KB Management Commands
             get_knowledge_bases,
             create_knowledge_base,
             search_knowledge_base,
+            search_knowledge_bases,
             delete_knowledge_base,
             export_knowledge_base,
+            import_knowledge_base,
             reindex_knowledge_base,
+            rebuild_bm25_index,
+            optimize_index,
+            add_kb_golden_query,
+            list_kb_golden_queries,
+            run_kb_evaluation,
+            add_kb_documents,
+            remove_kb_document,
+            update_kb_document,
+            check_kb_consistency,
+            list_kb_versions,
+            activate_kb_version,
+            delete_kb_version,
+            diff_kb_versions,
+            create_workspace,
+            list_workspaces,
+            set_active_workspace,
+            pin_kb_version,
             get_app_state,
             get_health_status,
+            get_metrics,
             // Settings Management Commands
             get_app_settings,
             update_app_settings,
             start_mcp_server,
             stop_mcp_server,
             get_mcp_server_status,
+            get_mcp_quota_metrics,
+            get_generation_status,
+            start_api_server,
+            stop_api_server,
+            get_api_server_status,
+            start_metrics_server,
+            stop_metrics_server,
+            get_metrics_server_status,
+            get_mcp_audit_log,
+            export_mcp_audit_log,
             select_data_directory,
             clear_application_cache,
+            get_cache_stats,
+            get_blob_store_stats,
+            gc_unreferenced_blobs,
+            get_disk_quota_usage,
+            backup_database,
+            list_database_backups,
+            restore_database,
             export_settings,
-            import_settings
+            import_settings,
+            // Prompt Commands
+            list_prompts,
+            save_prompt,
+            delete_prompt,
+            // Tool Commands
+            create_tool,
+            get_tools,
+            set_tool_enabled,
+            update_tool,
+            delete_tool,
+            get_tool_analytics,
+            get_tool_templates,
+            save_tool_as_template,
+            update_tool_template,
+            delete_tool_template,
+            // Flow Commands
+            save_flow,
+            list_flows,
+            delete_flow,
+            run_flow,
+            test_flow,
+            evaluate_flow,
+            // Conversation Memory Commands
+            conversation_answer,
+            get_conversation,
+            clear_conversation,
+            // Model Commands
+            list_models,
+            download_model,
+            import_model,
+            delete_model,
+            list_supported_models,
+            recommend_model,
+            get_model_storage_stats,
+            pin_model,
+            cleanup_models,
+            // Log Commands
+            get_logs,
+            start_log_tail,
+            stop_log_tail,
+            undo_last_change,
+            redo,
+            get_deltas_since,
+            get_settings,
+            update_settings,
+            list_secrets,
+            set_secret,
+            delete_secret,
+            get_network_policy,
+            set_network_override,
+            inspect_pack,
+            get_pack_signing_key,
+            list_trusted_signers,
+            trust_pack_signer,
+            remove_trusted_signer,
+            export_tool,
+            bulk_export_tools,
+            import_tool,
+            resolve_tool_dependencies,
+            // Refresh Schedule Commands
+            list_refresh_schedules,
+            save_refresh_schedule,
+            delete_refresh_schedule,
+            run_refresh_schedule,
+            // Alert Commands
+            list_alerts,
+            get_unread_alert_count,
+            mark_alert_read,
+            // Full-App Backup Commands
+            create_backup,
+            inspect_backup,
+            restore_backup,
+            // Background Job Commands
+            list_jobs,
+            get_job,
+            cancel_job,
+            clear_finished_jobs
         ])
         .setup(|app| {
             println!("RAG Studio application initializing...");
 
-            // Initialize Manager in async context
-            let app_handle = app.handle().clone();
-            tauri::async_runtime::spawn(async move {
-                match Manager::new().await {
-                    Ok(mut manager) => {
-                        manager.set_app_handle(app_handle.clone());
-
-                        // Load initial state
-                        if let Err(e) = manager.load_initial_state().await {
-                            eprintln!("Failed to load initial state: {}", e);
-                        }
-
-                        // Store manager globally
-                        let manager_arc = Arc::new(manager);
-                        if MANAGER.set(manager_arc.clone()).is_err() {
-                            eprintln!("Failed to set global manager instance");
-                        }
+            // Lifecycle is managed before anything else so the UI can ask how startup is going
+            let lifecycle = Arc::new(lifecycle::Lifecycle::new(app.handle().clone()));
+            app.manage(lifecycle.clone());
+            app.manage(PythonContext::new()?);
 
-                        // Add manager to app state for Tauri commands
-                        app_handle.manage(manager_arc.as_ref().clone());
-
-                        println!("✅ RAG Studio Manager initialized successfully");
-                    }
-                    Err(e) => {
-                        eprintln!("❌ Failed to initialize Manager: {}", e);
-                    }
-                }
-            });
+            // App services, then the last project, in async context
+            tauri::async_runtime::spawn(lifecycle::start(app.handle().clone(), lifecycle));
 
             println!("RAG Studio application setup completed.");
             Ok(())
         })
-        .run(tauri::generate_context!())
-        .expect("error while running tauri application");
+        .build(tauri::generate_context!())
+        .expect("error while building tauri application")
+        .run(|app_handle, event| {
+            // Hold exit until services have shut down and flushed to disk
+            if let tauri::RunEvent::ExitRequested { api, code, .. } = event {
+                let lifecycle = app_handle.state::<Arc<lifecycle::Lifecycle>>().inner().clone();
+                if lifecycle.phase() != lifecycle::LifecyclePhase::Stopped {
+                    api.prevent_exit();
+                    if lifecycle.begin_shutdown() {
+                        let app_handle = app_handle.clone();
+                        tauri::async_runtime::spawn(async move {
+                            lifecycle::shutdown(&app_handle, &lifecycle).await;
+                            app_handle.exit(code.unwrap_or(0));
+                        });
+                    }
+                }
+            }
+        });
 }
 
 #[cfg(test)]
@@ -136,7 +266,7 @@ mod tests {
 
     #[test]
     fn test_python_integration() {
-        let ctx = get_python_context();
+        let ctx = PythonContext::new().expect("Failed to initialize Python context");
         let result = ctx.call_greeting("Test");
         
         match result {
@@ -0,0 +1,259 @@
+/*!
+ * Application Lifecycle
+ *
+ * Brings the app up (app-wide services, then the last open project's
+ * Manager in dependency order) and takes it down again on exit. Switching
+ * projects runs the same startup steps again.
+ * Progress is published as a `StartupStatus` (also emitted as
+ * `startup_status` events) so the UI can show "starting…" until the core
+ * services are ready, and exit is held back until shutdown has flushed
+ * everything to disk.
+ */
+
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use tauri::{AppHandle, Emitter, Manager as TauriManager, State};
+use tokio::sync::watch;
+
+use rag_core::services::projects::DEFAULT_PROJECTS_PATH;
+use rag_core::ProjectRegistry;
+
+use crate::manager::{AppServices, Manager};
+use crate::projects::ProjectHost;
+use crate::{outbound_server, settings_commands, tools_commands};
+
+/// Longest shutdown may hold up exit
+pub const SHUTDOWN_TIMEOUT: Duration = Duration::from_secs(15);
+
+/// Where the app is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifecyclePhase {
+    Starting,
+    Ready,
+    Failed,         // Core services could not start; commands are unavailable
+    ShuttingDown,
+    Stopped,
+}
+
+/// Startup progress as shown by the UI
+#[derive(Debug, Clone, Serialize)]
+pub struct StartupStatus {
+    pub phase: LifecyclePhase,
+    pub current_step: Option<String>,
+    pub completed_steps: Vec<String>,
+    pub error: Option<String>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Lifecycle tracker, managed as Tauri state from the first moment of setup
+pub struct Lifecycle {
+    status: watch::Sender<StartupStatus>,
+    app_handle: AppHandle,
+}
+
+impl Lifecycle {
+    pub fn new(app_handle: AppHandle) -> Self {
+        Self {
+            status: watch::channel(StartupStatus {
+                phase: LifecyclePhase::Starting,
+                current_step: None,
+                completed_steps: Vec::new(),
+                error: None,
+                updated_at: Utc::now(),
+            }).0,
+            app_handle,
+        }
+    }
+
+    pub fn status(&self) -> StartupStatus {
+        self.status.borrow().clone()
+    }
+
+    pub fn phase(&self) -> LifecyclePhase {
+        self.status.borrow().phase
+    }
+
+    fn update(&self, change: impl FnOnce(&mut StartupStatus)) {
+        self.status.send_modify(|status| {
+            change(status);
+            status.updated_at = Utc::now();
+        });
+        if let Err(e) = self.app_handle.emit("startup_status", self.status()) {
+            eprintln!("Failed to emit startup status: {}", e);
+        }
+    }
+
+    fn begin_step(&self, step: &str) {
+        println!("Starting {}...", step);
+        self.update(|status| status.current_step = Some(step.to_string()));
+    }
+
+    fn complete_step(&self) {
+        self.update(|status| {
+            if let Some(step) = status.current_step.take() {
+                status.completed_steps.push(step);
+            }
+        });
+    }
+
+    /// Back to `Starting` with no steps done, when a project is (re)opened
+    pub(crate) fn restart(&self) {
+        self.update(|status| {
+            status.phase = LifecyclePhase::Starting;
+            status.current_step = None;
+            status.completed_steps.clear();
+            status.error = None;
+        });
+    }
+
+    pub(crate) fn ready(&self) {
+        self.update(|status| status.phase = LifecyclePhase::Ready);
+    }
+
+    pub(crate) fn fail(&self, error: String) {
+        self.update(|status| {
+            status.phase = LifecyclePhase::Failed;
+            status.error = Some(error);
+        });
+    }
+
+    /// Move to shutting down; false when shutdown already began
+    pub fn begin_shutdown(&self) -> bool {
+        let mut began = false;
+        self.update(|status| {
+            if !matches!(status.phase, LifecyclePhase::ShuttingDown | LifecyclePhase::Stopped) {
+                status.phase = LifecyclePhase::ShuttingDown;
+                status.current_step = None;
+                began = true;
+            }
+        });
+        began
+    }
+}
+
+/// Bring up the app-wide services and the project registry, then open the
+/// last project (or the default one on first run)
+pub async fn start(app_handle: AppHandle, lifecycle: Arc<Lifecycle>) {
+    // Logging and telemetry outlive any one project
+    lifecycle.begin_step("app services");
+    let app_services = match AppServices::init() {
+        Ok(app_services) => app_services,
+        Err(e) => {
+            eprintln!("❌ Failed to initialize app services: {}", e);
+            lifecycle.fail(format!("Failed to start app services: {}", e));
+            return;
+        }
+    };
+    let registry = match ProjectRegistry::open(DEFAULT_PROJECTS_PATH) {
+        Ok(registry) => registry,
+        Err(e) => {
+            eprintln!("❌ Failed to load recent projects: {}", e);
+            lifecycle.fail(format!("Failed to load recent projects: {}", e));
+            return;
+        }
+    };
+    let host = Arc::new(ProjectHost::new(app_services, registry));
+    app_handle.manage(host.clone());
+    lifecycle.complete_step();
+
+    let root = host.startup_root();
+    if host.open(&app_handle, &lifecycle, &root).await.is_ok() {
+        println!("✅ RAG Studio Manager initialized successfully");
+    }
+}
+
+/// Start a project's Manager and its background work in dependency order:
+/// core services, event streams, restored state, settings, then the
+/// schedulers and servers that read them
+pub(crate) async fn start_manager(
+    app_handle: &AppHandle,
+    lifecycle: &Lifecycle,
+    root: &Path,
+    app_services: &AppServices,
+) -> Result<Arc<Manager>, String> {
+    // Storage, database, vector store, cache, models and modules (Manager::open
+    // constructs them in dependency order and fails if any core service can't start)
+    lifecycle.begin_step("core services");
+    let mut manager = Manager::open(root, app_services).await
+        .map_err(|e| format!("Failed to start core services: {}", e))?;
+    lifecycle.complete_step();
+
+    // Streams first, so the frontend sees the restored state arrive
+    lifecycle.begin_step("event streams");
+    manager.set_app_handle(app_handle.clone());
+    // The one instance every command, server and background task of the project shares
+    let manager = Arc::new(manager);
+    manager.start_delta_stream().await;
+    manager.start_alert_notifications().await;
+    manager.start_job_events().await;
+    lifecycle.complete_step();
+
+    lifecycle.begin_step("state");
+    if let Err(e) = manager.load_initial_state().await {
+        eprintln!("Failed to load initial state: {}", e);
+    }
+    lifecycle.complete_step();
+
+    lifecycle.begin_step("settings");
+    settings_commands::apply_saved_settings(&manager).await;
+    manager.start_settings_watcher().await;
+    lifecycle.complete_step();
+
+    lifecycle.begin_step("background tasks");
+    // Schedules restored with the state snapshot resume here
+    manager.start_refresh_scheduler().await;
+    manager.start_health_monitor().await;
+    // Restored tools go back on the MCP catalog
+    if let Err(e) = tools_commands::write_tool_catalog(manager.as_ref()) {
+        eprintln!("{}", e);
+    }
+
+    // Serve core services to the MCP subprocess
+    let rpc_manager = manager.clone();
+    manager.track(tokio::spawn(async move {
+        if let Err(e) = outbound_server::serve(rpc_manager).await {
+            eprintln!("❌ Outbound RPC server failed: {}", e);
+        }
+    }));
+
+    // Keep tool usage in state current for the Tools dashboard. The task
+    // holds a weak reference so a closed project's Manager can be dropped.
+    let usage_manager = Arc::downgrade(&manager);
+    manager.track(tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(30));
+        loop {
+            interval.tick().await;
+            let Some(manager) = usage_manager.upgrade() else { break };
+            if let Err(e) = tools_commands::refresh_tool_usage(manager.as_ref()).await {
+                eprintln!("{}", e);
+            }
+        }
+    }));
+    lifecycle.complete_step();
+
+    Ok(manager)
+}
+
+/// Close the open project (bounded by `SHUTDOWN_TIMEOUT`) and flush
+/// telemetry, then mark the lifecycle stopped so the held-back exit can go through
+pub async fn shutdown(app_handle: &AppHandle, lifecycle: &Lifecycle) {
+    // Not managed yet when exit comes during startup
+    if let Some(host) = app_handle.try_state::<Arc<ProjectHost>>() {
+        host.close().await;
+        if let Err(e) = host.app_services().telemetry_service.shutdown() {
+            eprintln!("{}", e);
+        }
+    }
+    lifecycle.update(|status| status.phase = LifecyclePhase::Stopped);
+}
+
+/// Startup progress; the UI polls this (and listens for `startup_status`)
+/// before calling anything that needs the Manager
+#[tauri::command]
+pub async fn get_startup_status(lifecycle: State<'_, Arc<Lifecycle>>) -> Result<StartupStatus, String> {
+    Ok(lifecycle.status())
+}
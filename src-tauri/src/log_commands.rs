@@ -0,0 +1,40 @@
+/*!
+ * Log Tauri Commands
+ *
+ * Structured log queries and the live log tail for the in-app log viewer.
+ */
+
+
+use rag_core::{LogEntry, LogFilter, LogRange};
+
+use crate::projects::ActiveManager;
+
+/// Stored log entries matching the filter within the range, newest first
+#[tauri::command]
+pub async fn get_logs(
+    manager: ActiveManager,
+    filter: Option<LogFilter>,
+    range: Option<LogRange>,
+) -> Result<Vec<LogEntry>, String> {
+    let logging = manager.logging_service.clone();
+    tokio::task::spawn_blocking(move || {
+        logging.get_logs(&filter.unwrap_or_default(), &range.unwrap_or_default())
+    })
+    .await
+    .map_err(|e| format!("Log query task failed: {}", e))?
+    .map_err(|e| format!("Failed to read logs: {}", e))
+}
+
+/// Stream new log entries to the frontend as `log_entry` events
+#[tauri::command]
+pub async fn start_log_tail(manager: ActiveManager) -> Result<(), String> {
+    manager.set_log_tail(true).await;
+    Ok(())
+}
+
+/// Stop the live log stream
+#[tauri::command]
+pub async fn stop_log_tail(manager: ActiveManager) -> Result<(), String> {
+    manager.set_log_tail(false).await;
+    Ok(())
+}
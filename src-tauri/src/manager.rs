@@ -7,19 +7,46 @@
  * - Real-time Updates: Tauri events for frontend sync
  */
 
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use serde::{Serialize, Deserialize};
 use tauri::{AppHandle, Emitter};
-use tracing::{info, error};
+use tauri_plugin_notification::NotificationExt;
+use tracing::{info, warn, error};
+
+use crate::api_server::ApiServerHandle;
+use crate::metrics_server::MetricsServerHandle;
 
 // Core imports
 use rag_core::{
     SqlService, SqlConfig,
+    StorageService, StorageConfig,
+    CacheService, CacheConfig,
+    ModelService, ModelConfig,
+    LoggingService, LoggingConfig, TelemetryService, TelemetryConfig,
+    MetricsService, MetricsSnapshot, SettingsService, Settings, PathSettings,
+    SecretsService, SecretsConfig, NetworkPolicy, PackSigner, ContentCipher,
+    GenerationService, GenerationConfig, LlmService, LlmProviderConfig,
+    AlertService, AlertCategory, QuotaService, QuotaLimits, JobService, JobConfig, JobKind, JobRecord,
+    IndexMaintenanceProgress, MaintenanceSettings,
+    HealthMonitor, HealthReport, ProbeResult,
     modules::kb::{KbService, KbServiceImpl, KbConfig},
+    modules::eval::{EvalService, EvalConfig},
+    modules::flow::FlowService,
+    modules::memory::MemoryService,
+    modules::graph::GraphService,
+    modules::audit::AuditService,
+    modules::pipeline::{AnnotateStepExecutor, EmbedStepExecutor, EvalStepExecutor, ExtractEntitiesStepExecutor, FetchCursorStore, FetchStepExecutor, NormalizeStepExecutor, ParseStepExecutor, PipelineRunner, RedactStepExecutor, SummarizeStepExecutor, TesseractOcrEngine, WhisperTranscriber},
+    modules::schedule::{CronSchedule, RefreshService, RefreshStatus, DEFAULT_SCHEDULER_TICK_SECS},
+    models::outbound_rpc::{load_or_create_token, RPC_TOKEN_ENV, RPC_TOKEN_FILE},
     services::vector::{VectorDbService, VectorDbConfig},
-    StateManager,
+    services::model::EmbeddingBackend,
+    StateManager, StateStore,
+    state::{PipelineRunStatus, StateDelta},
 };
+#[cfg(feature = "onnx")]
+use rag_core::services::{cache::CachedEmbeddingBackend, embedding::{Device, OnnxEmbeddingBackend}};
 
 /// Application State for MVP
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -114,47 +141,367 @@ impl Default for AppMetrics {
     }
 }
 
-/// Manager - Main composition root following CORE_DESIGN.md
+/// Services that outlive any one project: the process-wide log subscriber
+/// and trace exporter, set up once from the app-directory settings
 #[derive(Clone)]
+pub struct AppServices {
+    pub logging_service: Arc<LoggingService>,
+    pub telemetry_service: Arc<TelemetryService>,
+}
+
+impl AppServices {
+    pub fn init() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        let settings = Settings::load(std::path::Path::new(rag_core::services::settings::DEFAULT_SETTINGS_PATH))
+            .unwrap_or_default();
+
+        // Structured logs and traces go first so every service's startup is captured
+        let telemetry_config = TelemetryConfig::from_env("rag-studio");
+        let telemetry_service = Arc::new(TelemetryService::new(telemetry_config).or_else(|e| {
+            eprintln!("OTLP export disabled: {}", e);
+            TelemetryService::new(TelemetryConfig::new("rag-studio"))
+        })?);
+        let logging_service = Arc::new(LoggingService::new(LoggingConfig {
+            log_dir: settings.paths.logs_dir.clone(),
+            ..LoggingConfig::default()
+        })?);
+        if let Err(e) = logging_service.install_global(Some(&telemetry_service)) {
+            eprintln!("Structured logging disabled: {}", e);
+        }
+        Ok(Self { logging_service, telemetry_service })
+    }
+}
+
+/// Manager - Main composition root following CORE_DESIGN.md.
+/// Exactly one instance exists per app, shared as `Arc<Manager>` through
+/// Tauri state; it is deliberately not `Clone`.
 pub struct Manager {
     pub app_state: Arc<RwLock<AppState>>,
     pub sql_service: Arc<SqlService>,
     pub vector_service: Arc<VectorDbService>,
+    pub storage_service: Arc<StorageService>,
+    pub quota_service: Arc<QuotaService>,
+    pub cache_service: Arc<CacheService>,
+    pub model_service: Arc<ModelService>,
+    pub embedding_backend: Option<Arc<dyn EmbeddingBackend>>, // In-process embeddings; None without the onnx feature
+    pub generation_service: Arc<GenerationService>,
+    pub llm_service: Arc<LlmService>,
     pub kb_service: Arc<KbServiceImpl>,
+    pub eval_service: Arc<EvalService>,
+    pub flow_service: Arc<FlowService>,
+    pub memory_service: Arc<MemoryService>,
+    pub audit_service: Arc<AuditService>,
+    pub refresh_service: Arc<RefreshService>,
+    pub pipeline_runner: Arc<PipelineRunner>, // Shared by scheduled refreshes; cancelled on shutdown
+    pub job_service: Arc<JobService>,       // Background jobs (downloads, exports, backups, reindexes, runs)
+    pub alert_service: Arc<AlertService>,
+    pub health_monitor: Arc<HealthMonitor>,  // Latest probe per subsystem, broadcasts status changes
+    pub logging_service: Arc<LoggingService>,
+    pub telemetry_service: Arc<TelemetryService>,
+    pub metrics_service: Arc<MetricsService>,
+    pub settings_service: Arc<SettingsService>,
+    pub root: PathBuf,                      // Project directory; relative settings paths resolve here
+    pub secrets_service: Arc<SecretsService>,
+    pub network_policy: Arc<NetworkPolicy>,   // Air-gapped switch checked by every network-using service
+    pub rpc_token: String,                  // Bearer token for the outbound RPC server
+    pub api_server: Arc<tokio::sync::Mutex<Option<ApiServerHandle>>>,   // OpenAI-compatible API, off by default
+    pub metrics_server: Arc<tokio::sync::Mutex<Option<MetricsServerHandle>>>, // Prometheus /metrics, off by default
+    pub backup_scheduler: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Follows the auto_backup setting
+    pub refresh_scheduler: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Runs due KB refresh schedules
+    pub maintenance_scheduler: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Follows the maintenance settings
+    pub log_tail: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Forwards log entries while the log viewer is open
+    pub delta_stream: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Forwards core state deltas as `state_change` events
+    pub health_probe: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Re-probes subsystems for `health_delta` events
+    pub state_manager: Arc<StateManager>,
+    pub state_store: Arc<StateStore>,       // Versioned snapshots of persistent state
+    pub state_persister: Arc<tokio::sync::Mutex<Option<tokio::task::JoinHandle<()>>>>, // Saves a snapshot after each persistent mutation
+    tasks: std::sync::Mutex<Vec<tokio::task::JoinHandle<()>>>, // Other long-running tasks, stopped on shutdown
     pub app_handle: Option<AppHandle>,
 }
 
 impl Manager {
-    /// Initialize Manager with MVP configuration
-    pub async fn new() -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        info!("Initializing Manager with MVP configuration");
+    /// Open the project rooted at `root` ("." is the default, app-directory
+    /// project). Database, vector data, cache and packs come from the
+    /// project's settings file, resolved inside the project.
+    pub async fn open(root: &Path, app_services: &AppServices) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
+        // Typed configuration decides where everything else lives
+        let settings_service = Arc::new(SettingsService::open(rag_core::services::projects::settings_path(root))?);
+        let settings = settings_service.get();
+        let paths = settings.paths.resolved_in(root);
+
+        let telemetry_service = app_services.telemetry_service.clone();
+        let logging_service = app_services.logging_service.clone();
+        info!("Initializing Manager for project {:?}", root);
+
+        // One network policy for every service that can leave the machine
+        let network_policy = Arc::new(NetworkPolicy::new(settings.features.air_gapped)
+            .with_overrides(&settings.features.network_overrides)
+            .with_audit_log(rag_core::services::network_policy::DEFAULT_POLICY_AUDIT_PATH)?);
+        info!("Network policy initialized (air-gapped: {})", network_policy.is_air_gapped());
+
+        // Encrypted credentials, referenced by name from tools, flows and providers
+        let secrets_service = Arc::new(SecretsService::new(SecretsConfig::in_dir(&paths.secrets_dir))?);
+        info!("Secrets vault opened ({} secrets)", secrets_service.list().len());
+
+        // At-rest encryption for stored content, keyed from the vault
+        let content_cipher = if settings.features.encrypt_content {
+            let cipher = Arc::new(ContentCipher::load_or_create(&secrets_service)?);
+            info!("Content encryption enabled (key {})", cipher.key_id());
+            Some(cipher)
+        } else {
+            None
+        };
 
         // Initialize SQL service with MVP config
-        let sql_config = SqlConfig::new_mvp("./rag_studio.db");
-        let sql_service = Arc::new(SqlService::new(sql_config).await?);
+        let mut sql_config = SqlConfig::new_mvp(paths.database.clone());
+        sql_config.backup_dir = paths.backup_dir.clone();
+        let mut sql_service = SqlService::new(sql_config).await?;
+        if let Some(cipher) = &content_cipher {
+            sql_service = sql_service.with_content_cipher(cipher.clone());
+        }
+        let sql_service = Arc::new(sql_service);
 
         // Run migrations
         sql_service.run_migrations().await?;
         info!("SQL service initialized and migrations completed");
 
+        // Alert inbox; other services raise into it as soon as they exist
+        let alert_service = Arc::new(AlertService::new(sql_service.clone(), settings.alerts.clone()));
+
         // Initialize Vector service with MVP config (graceful fallback)
-        let vector_config = VectorDbConfig::default(); // MVP with fallback
-        let vector_service = Arc::new(VectorDbService::new(vector_config).await?);
+        let vector_config = VectorDbConfig {
+            data_dir: paths.vector_dir.clone(),
+            max_concurrent_operations: settings.workers.max_vector_operations,
+            ..VectorDbConfig::default() // MVP with fallback
+        };
+        let mut vector_service = VectorDbService::new(vector_config).await?
+            .with_sql_service(sql_service.clone());
+        if let Some(cipher) = &content_cipher {
+            vector_service = vector_service.with_content_cipher(cipher.clone());
+        }
+        let vector_service = Arc::new(vector_service);
         info!("Vector service initialized with MVP configuration");
 
         // Initialize State Manager
         let state_manager = Arc::new(StateManager::new());
+        let state_store = Arc::new(StateStore::new(sql_service.clone()));
         info!("State manager initialized");
 
+        // Initialize two-tier cache (memory + disk); expired entries are dropped at startup
+        let mut cache_service = CacheService::new(CacheConfig {
+            path: paths.cache_dir.join("rag_cache.redb"),
+            size_budget_bytes: settings.workers.cache_size_mb * 1024 * 1024,
+            ..CacheConfig::default()
+        })?;
+        if let Some(cipher) = &content_cipher {
+            cache_service = cache_service.with_cipher(cipher.clone());
+        }
+        let cache_service = Arc::new(cache_service);
+        let purged = cache_service.purge_expired()?;
+        info!("Cache service initialized ({} expired entries purged)", purged);
+
+        // Initialize Storage service (KB packs), signing exports with our vault key
+        let pack_signer = Arc::new(PackSigner::load_or_create(&secrets_service)?
+            .with_trust_store(paths.trusted_signers.clone())?);
+        let mut storage_service = StorageService::new(StorageConfig {
+            packs_dir: paths.packs_dir.clone(),
+            blobs_dir: Some(paths.blobs_dir.clone()),
+            ..StorageConfig::default()
+        }).with_signer(pack_signer);
+        if let Some(cipher) = content_cipher {
+            storage_service = storage_service.with_cipher(cipher);
+        }
+        let storage_service = Arc::new(storage_service);
+        info!("Storage service initialized");
+
+        // Model downloads (HuggingFace Hub or a mirror via HF_ENDPOINT)
+        let model_config = ModelConfig::default();
+        let hf_token = model_config.hf_token.clone().or_else(|| {
+            secrets_service.get(rag_core::services::model::HF_TOKEN_SECRET).ok().map(|token| token.expose().to_string())
+        });
+        let model_service = Arc::new(ModelService::new(ModelConfig {
+            models_dir: paths.models_dir.clone(),
+            cache_size_gb: settings.workers.model_storage_gb,
+            hf_token,
+            ..model_config
+        })?.with_network_policy(network_policy.clone()));
+        info!("Model service initialized (models dir: {:?})", model_service.models_dir());
+        // Bundled models install in the background so first run doesn't block startup,
+        // then storage is brought back under quota
+        let bundled = model_service.clone();
+        let quota_alerts = alert_service.clone();
+        tokio::spawn(async move {
+            match bundled.verify_bundled_models().await {
+                Ok(models) if !models.is_empty() => info!("{} bundled models verified", models.len()),
+                Ok(_) => {}
+                Err(e) => error!("Bundled model verification failed: {}", e),
+            }
+            match bundled.enforce_quota().await {
+                Ok(report) if !report.evicted.is_empty() => {
+                    info!("Model cleanup evicted {:?} ({} bytes reclaimed)", report.evicted, report.reclaimed_bytes)
+                }
+                Ok(_) => {}
+                Err(e) => error!("Model storage cleanup failed: {}", e),
+            }
+            // Whatever cleanup couldn't reclaim is the user's call
+            if let Ok(stats) = bundled.storage_stats().await {
+                let low_space = stats.free_space_bytes.is_some_and(|free| free < stats.min_free_space_bytes);
+                if stats.used_bytes > stats.cleanup_threshold_bytes || low_space {
+                    quota_alerts.notify(
+                        AlertCategory::DiskQuota,
+                        "Model storage is almost full",
+                        &format!("Models use {} of {} MB; remove or unpin models to free space",
+                                 stats.used_bytes / (1024 * 1024), stats.quota_bytes / (1024 * 1024)),
+                        None,
+                        serde_json::to_value(&stats).unwrap_or_default(),
+                    ).await;
+                }
+            }
+        });
+
+        let metrics_service = Arc::new(MetricsService::new());
+
+        // In-process embeddings (ONNX Runtime) behind the content-hash embedding cache
+        #[cfg(feature = "onnx")]
+        let embedding_backend: Option<Arc<dyn EmbeddingBackend>> = Some(Arc::new(CachedEmbeddingBackend::new(
+            Arc::new(OnnxEmbeddingBackend::new(model_service.clone())
+                .with_device(Device::from_env())
+                .with_metrics_service(metrics_service.clone())),
+            cache_service.clone(),
+        )));
+        #[cfg(not(feature = "onnx"))]
+        let embedding_backend: Option<Arc<dyn EmbeddingBackend>> = None;
+
+        // Initialize local generation (llama.cpp, model from RAG_STUDIO_GGUF_MODEL)
+        let generation_service = Arc::new(GenerationService::new(GenerationConfig::default())
+            .with_alert_service(alert_service.clone()));
+        info!("Generation service initialized (model available: {})", generation_service.is_available());
+        let llm_service = Arc::new(LlmService::new(generation_service.clone())
+            .with_secrets_service(secrets_service.clone())
+            .with_network_policy(network_policy.clone()));
+
         // Initialize KB service
-        let kb_config = KbConfig::mvp();
-        let kb_service = Arc::new(KbServiceImpl::new(
+        let kb_config = KbConfig {
+            hybrid_search_enabled: settings.retrieval.hybrid_search,
+            rerank_enabled: settings.retrieval.rerank,
+            cache_enabled: settings.retrieval.cache_results,
+            max_results: settings.retrieval.max_results,
+            mmr_lambda: settings.retrieval.mmr_lambda,
+            ..KbConfig::mvp()
+        };
+        // Per-KB and project disk quotas, checked before ingests and pipeline runs
+        let quota_service = Arc::new(QuotaService::new(
+            sql_service.clone(),
+            storage_service.clone(),
+            paths.vector_dir.clone(),
+            QuotaLimits::from(&settings.quotas),
+        ).with_alert_service(alert_service.clone()));
+        // Knowledge graph written by the extract_entities step, read by graph-expanded search
+        let graph_service = Arc::new(GraphService::new(sql_service.clone()));
+
+        // Scheduled KB refreshes and reindexes replay stored pipelines through the same steps as the CLI
+        let mut refresh_runner = PipelineRunner::new()
+            .with_network_policy(network_policy.clone())
+            .with_metrics_service(metrics_service.clone());
+        let fetch_cursors = Arc::new(FetchCursorStore::new(sql_service.clone()));
+        refresh_runner.register(Arc::new(FetchStepExecutor::new()
+            .with_secrets_service(secrets_service.clone())
+            .with_cursor_store(fetch_cursors.clone())));
+        let mut parse = ParseStepExecutor::new();
+        let ocr = TesseractOcrEngine::default();
+        if ocr.is_available() {
+            parse = parse.with_ocr(Arc::new(ocr));
+        } else {
+            info!("Tesseract not found; parse steps will skip images and scanned PDFs");
+        }
+        let whisper = WhisperTranscriber::default();
+        if whisper.is_available() {
+            parse = parse.with_transcriber(Arc::new(whisper));
+        } else {
+            info!("No Whisper model or ffmpeg found; parse steps will skip audio and video");
+        }
+        refresh_runner.register(Arc::new(parse));
+        refresh_runner.register(Arc::new(NormalizeStepExecutor::new()));
+        refresh_runner.register(Arc::new(RedactStepExecutor::new()));
+        refresh_runner.register(Arc::new(ExtractEntitiesStepExecutor::new(graph_service.clone())));
+        refresh_runner.register(Arc::new(AnnotateStepExecutor::new()));
+        let mut embed = EmbedStepExecutor::new();
+        if let Some(backend) = &embedding_backend {
+            embed = embed.with_backend(backend.clone());
+        }
+        refresh_runner.register(Arc::new(embed));
+        refresh_runner.register(Arc::new(EvalStepExecutor::new(vector_service.clone())));
+        let mut summarize = SummarizeStepExecutor::new(vector_service.clone());
+        if generation_service.is_available() {
+            summarize = summarize.with_llm(llm_service.provider(&LlmProviderConfig::Local)?);
+        }
+        refresh_runner.register(Arc::new(summarize));
+        let pipeline_runner = Arc::new(refresh_runner);
+
+        let mut kb_service = KbServiceImpl::new(
             sql_service.clone(),
             vector_service.clone(),
             state_manager.clone(),
             kb_config,
+        ).with_storage_service(storage_service.clone())
+         .with_cache_service(cache_service.clone())
+         .with_metrics_service(metrics_service.clone())
+         .with_quota_service(quota_service.clone())
+         .with_graph_service(graph_service.clone())
+         .with_pipeline_runner(pipeline_runner.clone());
+        if let Some(backend) = &embedding_backend {
+            kb_service = kb_service.with_query_embedder(backend.clone());
+        }
+        let kb_service = Arc::new(kb_service);
+        // KBs created headlessly (rag-cli) or in earlier sessions
+        let loaded = kb_service.load_collections().await?;
+        info!("KB service initialized ({} knowledge bases loaded)", loaded);
+
+        // Initialize evaluation service
+        let eval_service = Arc::new(EvalService::new(
+            sql_service.clone(),
+            kb_service.clone(),
+            EvalConfig::default(),
+        ));
+        info!("Eval service initialized");
+
+        // Initialize flow service (multi-step query flows)
+        let flow_service = Arc::new(FlowService::new(
+            sql_service.clone(),
+            state_manager.clone(),
+            kb_service.clone(),
+            llm_service.clone(),
         ));
-        info!("KB service initialized");
+        let loaded = flow_service.load_flows().await?;
+        info!("Flow service initialized ({} flows loaded)", loaded);
+
+        // Conversation memory (conversational RAG); drop sessions that expired while closed
+        let memory_service = Arc::new(MemoryService::new(sql_service.clone()));
+        let purged = memory_service.purge_expired().await?;
+        info!("Memory service initialized ({} expired rows purged)", purged);
+
+        // MCP audit log (written by the MCP subprocess via `--audit-db`)
+        let audit_service = Arc::new(AuditService::new(sql_service.clone()));
+
+        let refresh_service = Arc::new(RefreshService::new(
+            state_manager.clone(),
+            kb_service.clone(),
+            eval_service.clone(),
+            pipeline_runner.clone(),
+        ).with_fetch_cursors(fetch_cursors));
+
+        // Background jobs share one queue; their history survives restarts
+        let job_service = Arc::new(JobService::open(JobConfig {
+            store_path: Some(root.join(rag_core::services::jobs::DEFAULT_JOBS_FILE)),
+            max_concurrent: settings.workers.max_background_jobs,
+            ..JobConfig::default()
+        })?);
+        info!("Job service initialized ({} jobs in history)", job_service.list().len());
+
+        // Outbound RPC token: RPC_TOKEN_ENV, else the project's token file the MCP subprocess reads
+        let rpc_token = match std::env::var(RPC_TOKEN_ENV) {
+            Ok(token) => token,
+            Err(_) => load_or_create_token(&root.join(RPC_TOKEN_FILE))?,
+        };
 
         // Initialize application state
         let app_state = Arc::new(RwLock::new(AppState::default()));
@@ -163,11 +510,60 @@ impl Manager {
             app_state,
             sql_service,
             vector_service,
+            storage_service,
+            quota_service,
+            cache_service,
+            model_service,
+            embedding_backend,
+            generation_service,
+            llm_service,
             kb_service,
+            eval_service,
+            flow_service,
+            memory_service,
+            audit_service,
+            refresh_service,
+            pipeline_runner,
+            job_service,
+            alert_service,
+            health_monitor: Arc::new(HealthMonitor::default()),
+            logging_service,
+            telemetry_service,
+            metrics_service,
+            settings_service,
+            root: root.to_path_buf(),
+            secrets_service,
+            network_policy,
+            rpc_token,
+            api_server: Arc::new(tokio::sync::Mutex::new(None)),
+            metrics_server: Arc::new(tokio::sync::Mutex::new(None)),
+            backup_scheduler: Arc::new(tokio::sync::Mutex::new(None)),
+            refresh_scheduler: Arc::new(tokio::sync::Mutex::new(None)),
+            maintenance_scheduler: Arc::new(tokio::sync::Mutex::new(None)),
+            log_tail: Arc::new(tokio::sync::Mutex::new(None)),
+            delta_stream: Arc::new(tokio::sync::Mutex::new(None)),
+            health_probe: Arc::new(tokio::sync::Mutex::new(None)),
+            state_manager,
+            state_store,
+            state_persister: Arc::new(tokio::sync::Mutex::new(None)),
+            tasks: std::sync::Mutex::new(Vec::new()),
             app_handle: None,
         })
     }
 
+    /// Data directories of the open project, resolved against its root
+    pub fn paths(&self) -> PathSettings {
+        self.settings_service.get().paths.resolved_in(&self.root)
+    }
+
+    /// Keep a long-running task until shutdown, so a closed project's
+    /// Manager isn't kept alive (or kept working) by its own tasks
+    pub fn track(&self, handle: tokio::task::JoinHandle<()>) {
+        let mut tasks = self.tasks.lock().unwrap();
+        tasks.retain(|task| !task.is_finished());
+        tasks.push(handle);
+    }
+
     /// Set Tauri app handle for event emission
     pub fn set_app_handle(&mut self, app_handle: AppHandle) {
         self.app_handle = Some(app_handle);
@@ -194,10 +590,37 @@ impl Manager {
         }
     }
 
+    /// Emit a named event to the frontend (e.g. progress updates)
+    pub fn emit_event<S: Serialize + Clone>(&self, event: &str, payload: S) {
+        if let Some(app_handle) = &self.app_handle {
+            if let Err(e) = app_handle.emit(event, payload) {
+                error!("Failed to emit {}: {}", event, e);
+            }
+        }
+    }
+
     /// Load initial state from database
     pub async fn load_initial_state(&self) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
         info!("Loading initial state from database");
 
+        // Tools, settings, workspaces and prompts from the last session; the
+        // persister starts only afterwards so startup doesn't overwrite them
+        match self.state_store.load_latest().await {
+            Ok(Some(snapshot)) => {
+                info!("Restoring state snapshot {} (revision {}, {} tools)",
+                      snapshot.version, snapshot.revision, snapshot.state.tools.len());
+                self.state_manager.restore(snapshot.state);
+            }
+            Ok(None) => {}
+            Err(e) => error!("Failed to load state snapshot, starting fresh: {}", e),
+        }
+        {
+            let mut persister = self.state_persister.lock().await;
+            if persister.is_none() {
+                *persister = Some(self.state_store.spawn_persister(self.state_manager.clone()));
+            }
+        }
+
         // Load KBs from SQL service
         let kb_stats = self.kb_service.get_stats(None, None).await?;
 
@@ -208,7 +631,9 @@ impl Manager {
         state.metrics.total_documents = kb_stats.document_count as u32;
         state.metrics.total_chunks = kb_stats.chunk_count as u32;
         state.metrics.avg_query_latency_ms = 0.0; // Will be updated by queries
-        state.metrics.cache_hit_rate = 0.0; // Will be updated by cache service
+        state.metrics.cache_hit_rate = self.cache_service.stats()
+            .map(|stats| stats.hit_rate as f32)
+            .unwrap_or(0.0);
 
         // Load KB list
         let kb_list = self.kb_service.list_collections(None).await?;
@@ -271,26 +696,510 @@ impl Manager {
         Ok(())
     }
 
-    /// Health check for all services
-    pub async fn health_check(&self) -> Result<serde_json::Value, Box<dyn std::error::Error + Send + Sync>> {
-        let sql_health = self.sql_service.health_check().await?;
-        let vector_health = self.vector_service.health_check().await?;
-
-        Ok(serde_json::json!({
-            "sql": {
-                "status": "healthy",
-                "app_db_size": sql_health.app_db_size,
-                "is_split_database": sql_health.is_split_database
-            },
-            "vector": {
-                "status": match vector_health {
-                    rag_core::services::vector::HealthStatus::Healthy => "healthy",
-                    rag_core::services::vector::HealthStatus::Degraded => "degraded",
-                    rag_core::services::vector::HealthStatus::Unhealthy => "unhealthy",
-                },
-                "mode": format!("{:?}", vector_health)
-            },
-            "overall": "healthy"
-        }))
-    }
-}
\ No newline at end of file
+    /// Start or stop forwarding new log entries to the frontend as `log_entry` events
+    pub async fn set_log_tail(&self, enabled: bool) {
+        let mut log_tail = self.log_tail.lock().await;
+        if let Some(handle) = log_tail.take() {
+            handle.abort();
+        }
+        let Some(app_handle) = self.app_handle.clone().filter(|_| enabled) else {
+            return;
+        };
+
+        let mut entries = self.logging_service.subscribe();
+        *log_tail = Some(tokio::spawn(async move {
+            loop {
+                match entries.recv().await {
+                    // Emit failures are not logged: the entry would be tailed again
+                    Ok(entry) => { let _ = app_handle.emit("log_entry", entry); }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    /// Forward every core state delta to the frontend as a `state_change` event.
+    /// Receivers that lag skip ahead; the frontend sees the sequence gap and
+    /// catches up through `get_deltas_since`.
+    pub async fn start_delta_stream(&self) {
+        let Some(app_handle) = self.app_handle.clone() else {
+            return;
+        };
+        let mut delta_stream = self.delta_stream.lock().await;
+        if let Some(handle) = delta_stream.take() {
+            handle.abort();
+        }
+
+        let mut deltas = self.state_manager.subscribe_deltas();
+        *delta_stream = Some(tokio::spawn(async move {
+            loop {
+                match deltas.recv().await {
+                    Ok(delta) => {
+                        if let Err(e) = app_handle.emit("state_change", delta) {
+                            error!("Failed to emit state change: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    /// Forward background job changes to the tasks panel as `job_update` events
+    pub async fn start_job_events(&self) {
+        let Some(app_handle) = self.app_handle.clone() else {
+            return;
+        };
+
+        let mut jobs = self.job_service.subscribe();
+        self.track(tokio::spawn(async move {
+            loop {
+                match jobs.recv().await {
+                    Ok(job) => {
+                        if let Err(e) = app_handle.emit("job_update", job) {
+                            error!("Failed to emit job update: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    /// Forward raised alerts to the frontend inbox as `alert_raised` events,
+    /// and to the desktop when they reach the configured severity
+    pub async fn start_alert_notifications(&self) {
+        let Some(app_handle) = self.app_handle.clone() else {
+            return;
+        };
+
+        let alert_service = self.alert_service.clone();
+        let mut alerts = alert_service.subscribe();
+        self.track(tokio::spawn(async move {
+            loop {
+                match alerts.recv().await {
+                    Ok(alert) => {
+                        if alert_service.notifies_desktop(&alert) {
+                            if let Err(e) = app_handle.notification().builder()
+                                .title(&alert.title)
+                                .body(&alert.message)
+                                .show()
+                            {
+                                error!("Failed to show desktop notification: {}", e);
+                            }
+                        }
+                        if let Err(e) = app_handle.emit("alert_raised", alert) {
+                            error!("Failed to emit alert: {}", e);
+                        }
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+    }
+
+    /// Apply the settings that are safe to change while running
+    pub async fn apply_runtime_settings(self: &Arc<Self>, settings: &Settings) {
+        self.app_state.write().await.air_gapped_mode = settings.features.air_gapped;
+        self.alert_service.set_settings(settings.alerts.clone());
+        self.quota_service.set_limits(QuotaLimits::from(&settings.quotas));
+        self.network_policy.set_air_gapped(settings.features.air_gapped);
+        self.configure_index_maintenance(&settings.maintenance).await;
+        // Overrides normally arrive already applied; this catches hand edits of the file
+        if let Err(e) = self.network_policy.sync_overrides(&settings.features.network_overrides, "settings file") {
+            error!("Failed to apply network overrides: {}", e);
+        }
+
+        let mut server = self.metrics_server.lock().await;
+        match (settings.features.metrics_endpoint, server.is_some()) {
+            (true, false) => {
+                match crate::metrics_server::start(Arc::clone(self), crate::metrics_server::DEFAULT_METRICS_PORT).await {
+                    Ok(handle) => *server = Some(handle),
+                    Err(e) => error!("Failed to start metrics server: {}", e),
+                }
+            }
+            (false, true) => {
+                if let Some(handle) = server.take() {
+                    handle.stop();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Apply current settings, then follow updates and edits to the settings file
+    pub async fn start_settings_watcher(self: &Arc<Self>) {
+        self.apply_runtime_settings(&self.settings_service.get()).await;
+
+        if let Err(e) = self.settings_service.watch() {
+            error!("Settings hot reload disabled: {}", e);
+        }
+
+        let manager = Arc::clone(self);
+        let mut changes = self.settings_service.subscribe();
+        self.track(tokio::spawn(async move {
+            while changes.changed().await.is_ok() {
+                let settings = changes.borrow_and_update().clone();
+                manager.apply_runtime_settings(&settings).await;
+            }
+        }));
+    }
+
+    /// Start, restart or stop scheduled database backups
+    pub async fn configure_backup_schedule(&self, enabled: bool, interval_hours: u32, max_backups: u32) {
+        let mut scheduler = self.backup_scheduler.lock().await;
+        if let Some(handle) = scheduler.take() {
+            handle.abort();
+        }
+        if enabled {
+            let interval = std::time::Duration::from_secs(u64::from(interval_hours.max(1)) * 3600);
+            *scheduler = Some(self.sql_service.spawn_backup_scheduler(interval, max_backups.max(1) as usize));
+            info!("Scheduled database backups every {}h (keeping {})", interval_hours.max(1), max_backups.max(1));
+        }
+    }
+
+    /// Start, restart or stop scheduled optimization of every KB's indexes
+    pub async fn configure_index_maintenance(self: &Arc<Self>, settings: &MaintenanceSettings) {
+        let mut scheduler = self.maintenance_scheduler.lock().await;
+        if let Some(handle) = scheduler.take() {
+            handle.abort();
+        }
+        if !settings.enabled {
+            return;
+        }
+        // Validated with the rest of the settings
+        let Ok(schedule) = CronSchedule::parse(&settings.schedule) else {
+            return;
+        };
+
+        let manager = Arc::clone(self);
+        *scheduler = Some(tokio::spawn(async move {
+            while let Some(next) = schedule.next_after(chrono::Utc::now()) {
+                tokio::time::sleep((next - chrono::Utc::now()).to_std().unwrap_or_default()).await;
+                let kb_ids: Vec<String> = manager.state_manager.read_state().knowledge_bases.keys().cloned().collect();
+                info!("Queueing index maintenance for {} knowledge bases", kb_ids.len());
+                // One maintenance job runs at a time; the rest wait in the queue
+                for kb_id in kb_ids {
+                    manager.spawn_index_maintenance(&kb_id, false);
+                }
+            }
+        }));
+        info!("Scheduled index maintenance at '{}' (UTC)", settings.schedule);
+    }
+
+    /// Queue a BM25 rebuild (`rebuild`) or an index optimization of a KB as a background job
+    pub fn spawn_index_maintenance(&self, kb_id: &str, rebuild: bool) -> JobRecord {
+        let kb_service = self.kb_service.clone();
+        let kb_id = kb_id.to_string();
+        let title = if rebuild {
+            format!("Rebuild BM25 index of {}", kb_id)
+        } else {
+            format!("Optimize indexes of {}", kb_id)
+        };
+        self.job_service.spawn(JobKind::IndexMaintenance, &title, move |job| async move {
+            let mut progress = |p: IndexMaintenanceProgress| {
+                let (start, span) = match p.stage {
+                    "bm25" => (0.0, 0.6),
+                    "fts5" => (0.6, 0.3),
+                    _ => (0.9, 0.1),
+                };
+                let done = if p.total == 0 { 1.0 } else { p.processed as f32 / p.total as f32 };
+                job.set_progress(start + span * done, Some(p.stage));
+            };
+            let report = if rebuild {
+                kb_service.rebuild_bm25_index(&kb_id, &mut progress).await
+            } else {
+                kb_service.optimize_index(&kb_id, &mut progress).await
+            }.map_err(|e| e.to_string())?;
+            info!(
+                "Index maintenance of KB {} done: {} chunks, {} dropped, {} -> {} bytes",
+                kb_id, report.chunks, report.removed, report.size_before, report.size_after
+            );
+            Ok(())
+        })
+    }
+
+    /// Run KB refresh schedules when due, report every outcome to the frontend
+    /// as a `kb_refresh_completed` event and raise alerts for the ones that didn't promote
+    pub async fn start_refresh_scheduler(self: &Arc<Self>) {
+        let mut scheduler = self.refresh_scheduler.lock().await;
+        if let Some(handle) = scheduler.take() {
+            handle.abort();
+        }
+        *scheduler = Some(self.refresh_service.spawn_scheduler(
+            std::time::Duration::from_secs(DEFAULT_SCHEDULER_TICK_SECS),
+        ));
+
+        let manager = Arc::clone(self);
+        let mut outcomes = self.refresh_service.subscribe();
+        self.track(tokio::spawn(async move {
+            loop {
+                match outcomes.recv().await {
+                    Ok(outcome) => {
+                        let category = match outcome.status {
+                            RefreshStatus::Promoted => None,
+                            RefreshStatus::EvalRejected => Some((AlertCategory::EvalRegression, "Refreshed KB version not promoted")),
+                            RefreshStatus::Failed => Some((AlertCategory::PipelineFailure, "Scheduled KB refresh failed")),
+                        };
+                        if let Some((category, title)) = category {
+                            manager.alert_service.notify(
+                                category,
+                                title,
+                                outcome.message.as_deref().unwrap_or("See the schedule's last run for details"),
+                                Some(&outcome.kb_id),
+                                serde_json::to_value(&outcome).unwrap_or_default(),
+                            ).await;
+                        }
+                        manager.emit_event("kb_refresh_completed", outcome);
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                }
+            }
+        }));
+        info!("Refresh scheduler started ({} schedules)", self.refresh_service.list_schedules().len());
+    }
+
+    /// Refresh gauges sampled from other services before metrics are read
+    pub fn refresh_metrics(&self) {
+        if let Ok(stats) = self.cache_service.stats() {
+            self.metrics_service.set_gauge(rag_core::services::metrics::CACHE_HIT_RATE, &[], stats.hit_rate);
+        }
+    }
+
+    /// Current metrics for the dashboard
+    pub fn metrics_snapshot(&self) -> MetricsSnapshot {
+        self.refresh_metrics();
+        self.metrics_service.snapshot()
+    }
+
+    /// Probe every subsystem concurrently and fold the results into one report
+    pub async fn health_check(&self) -> HealthReport {
+        let monitor = &self.health_monitor;
+        let (sql, vector, bm25, cache, storage, models, embedding, mcp) = tokio::join!(
+            monitor.probe("sql", self.probe_sql()),
+            monitor.probe("vector_store", self.probe_vector_store()),
+            monitor.probe("bm25_index", self.probe_bm25_index()),
+            monitor.probe("cache", self.probe_cache()),
+            monitor.probe("storage_quota", self.probe_storage_quota()),
+            monitor.probe("model_service", self.probe_model_service()),
+            monitor.probe("embedding_worker", async {
+                // Embeddings run in process; builds without the onnx feature have none
+                match &self.embedding_backend {
+                    Some(backend) => ProbeResult::healthy(serde_json::json!({
+                        "backend": "in_process",
+                        "loaded_models": backend.loaded_models(),
+                    })),
+                    None => ProbeResult::disabled("This build has no embedding backend"),
+                }
+            }),
+            monitor.probe("mcp_server", self.probe_mcp_server()),
+        );
+        monitor.report(vec![sql, vector, bm25, cache, storage, models, embedding, mcp])
+    }
+
+    async fn probe_sql(&self) -> ProbeResult {
+        if let Err(e) = self.sql_service.ping().await {
+            return ProbeResult::unhealthy(e.to_string());
+        }
+        match self.sql_service.health_check().await {
+            Ok(health) => ProbeResult::healthy(serde_json::json!({
+                "app_db_size": health.app_db_size,
+                "is_split_database": health.is_split_database,
+                "kb_databases": health.kb_databases.len(),
+                "kb_db_size": health.kb_databases.iter().map(|db| db.db_size).sum::<u64>(),
+                "app_pool_active": health.app_pool_active,
+            })),
+            Err(e) => ProbeResult::degraded(format!("Database metrics unavailable: {}", e), serde_json::Value::Null),
+        }
+    }
+
+    async fn probe_vector_store(&self) -> ProbeResult {
+        match self.vector_service.health_check().await {
+            Ok(rag_core::services::vector::HealthStatus::Healthy) => ProbeResult::healthy(serde_json::json!({})),
+            Ok(rag_core::services::vector::HealthStatus::Degraded) => {
+                ProbeResult::degraded("Vector store is running in fallback mode", serde_json::json!({}))
+            }
+            Ok(rag_core::services::vector::HealthStatus::Unhealthy) => ProbeResult::unhealthy("Vector data directory is missing"),
+            Err(e) => ProbeResult::unhealthy(e.to_string()),
+        }
+    }
+
+    async fn probe_bm25_index(&self) -> ProbeResult {
+        let lexical = self.vector_service.lexical_health().await;
+        let details = serde_json::to_value(&lexical).unwrap_or_default();
+        if lexical.rebuilding.is_empty() {
+            ProbeResult::healthy(details)
+        } else if lexical.fts_available {
+            ProbeResult::degraded(
+                format!("Rebuilding BM25 index for {}; lexical search uses FTS5", lexical.rebuilding.join(", ")),
+                details,
+            )
+        } else {
+            ProbeResult::degraded(format!("Rebuilding BM25 index for {}", lexical.rebuilding.join(", ")), details)
+        }
+    }
+
+    async fn probe_cache(&self) -> ProbeResult {
+        match self.cache_service.stats() {
+            Ok(stats) => {
+                let details = serde_json::json!({
+                    "disk_bytes": stats.disk_bytes,
+                    "size_budget_bytes": stats.size_budget_bytes,
+                    "hit_rate": stats.hit_rate,
+                });
+                if stats.size_budget_bytes > 0 && stats.disk_bytes > stats.size_budget_bytes {
+                    ProbeResult::degraded("Cache is over its size budget", details)
+                } else {
+                    ProbeResult::healthy(details)
+                }
+            }
+            Err(e) => ProbeResult::unhealthy(e.to_string()),
+        }
+    }
+
+    async fn probe_storage_quota(&self) -> ProbeResult {
+        match self.model_service.storage_stats().await {
+            Ok(stats) => {
+                let details = serde_json::to_value(&stats).unwrap_or_default();
+                let low_space = stats.free_space_bytes.is_some_and(|free| free < stats.min_free_space_bytes);
+                if stats.quota_bytes > 0 && stats.used_bytes > stats.quota_bytes {
+                    ProbeResult::unhealthy(format!(
+                        "Model storage is over quota ({} MB of {} MB)",
+                        stats.used_bytes / (1024 * 1024), stats.quota_bytes / (1024 * 1024),
+                    ))
+                } else if low_space {
+                    ProbeResult::degraded("Free disk space is below the configured minimum", details)
+                } else if stats.used_bytes > stats.cleanup_threshold_bytes {
+                    ProbeResult::degraded("Model storage is past its cleanup threshold", details)
+                } else {
+                    ProbeResult::healthy(details)
+                }
+            }
+            Err(e) => ProbeResult::unhealthy(e.to_string()),
+        }
+    }
+
+    async fn probe_model_service(&self) -> ProbeResult {
+        match self.model_service.list_models().await {
+            Ok(models) => ProbeResult::healthy(serde_json::json!({ "installed_models": models.len() })),
+            Err(e) => ProbeResult::unhealthy(e.to_string()),
+        }
+    }
+
+    async fn probe_mcp_server(&self) -> ProbeResult {
+        if self.app_state.read().await.mcp_server_running {
+            ProbeResult::healthy(serde_json::json!({ "running": true }))
+        } else {
+            ProbeResult::disabled("MCP server is not running")
+        }
+    }
+
+    /// Re-probe every subsystem periodically and forward status changes to
+    /// the dashboard status bar as `health_delta` events
+    pub async fn start_health_monitor(self: &Arc<Self>) {
+        let mut probe = self.health_probe.lock().await;
+        if let Some(handle) = probe.take() {
+            handle.abort();
+        }
+
+        let mut deltas = self.health_monitor.subscribe();
+        let manager = Arc::clone(self);
+        *probe = Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(
+                std::time::Duration::from_secs(rag_core::services::health::DEFAULT_HEALTH_INTERVAL_SECS),
+            );
+            loop {
+                tokio::select! {
+                    _ = ticker.tick() => {
+                        manager.health_check().await;
+                    }
+                    delta = deltas.recv() => match delta {
+                        Ok(delta) => manager.emit_event("health_delta", delta),
+                        Err(tokio::sync::broadcast::error::RecvError::Lagged(_)) => continue,
+                        Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                    },
+                }
+            }
+        }));
+    }
+
+    /// Stop everything in reverse dependency order: producers of new work
+    /// first, then servers, then flush what's left to disk. llama.cpp
+    /// processes are `kill_on_drop` and end with the tasks that own them.
+    /// App-wide logging and telemetry keep running (see `AppServices`).
+    pub async fn shutdown(&self) {
+        info!("Shutting down services");
+
+        // No new work: schedulers and probes stop, runs in flight are cancelled
+        for task in [&self.refresh_scheduler, &self.backup_scheduler, &self.maintenance_scheduler, &self.health_probe] {
+            if let Some(handle) = task.lock().await.take() {
+                handle.abort();
+            }
+        }
+        self.pipeline_runner.cancel_all();
+        self.job_service.shutdown().await;
+        let running: Vec<String> = self.state_manager.read_state().pipeline_runs.values()
+            .filter(|run| matches!(run.status, PipelineRunStatus::Pending | PipelineRunStatus::Running))
+            .map(|run| run.id.clone())
+            .collect();
+        for id in running {
+            if let Err(e) = self.state_manager.mutate(StateDelta::RunUpdate {
+                id: id.clone(),
+                status: PipelineRunStatus::Cancelled,
+                progress: None,
+                error: Some("Cancelled by application shutdown".to_string()),
+                metrics: None,
+            }) {
+                warn!("Failed to mark run {} cancelled: {}", id, e);
+            }
+        }
+
+        // Servers and subprocesses
+        if let Some(handle) = self.api_server.lock().await.take() {
+            handle.stop();
+        }
+        if let Some(handle) = self.metrics_server.lock().await.take() {
+            handle.stop();
+        }
+        self.app_state.write().await.mcp_server_running = false;
+
+        // Last state snapshot, unless the persister already saved this revision
+        if let Some(handle) = self.state_persister.lock().await.take() {
+            handle.abort();
+        }
+        let revision = self.state_manager.revision();
+        let saved = self.state_store.load_latest().await.ok().flatten().map(|s| s.revision);
+        if saved.is_none_or(|saved| saved < revision) {
+            let state = rag_core::PersistedState::from_state(&self.state_manager.read_state());
+            if let Err(e) = self.state_store.save(revision, &state).await {
+                error!("Failed to save final state snapshot: {}", e);
+            }
+        }
+
+        // Flush to disk
+        match self.cache_service.flush() {
+            Ok(purged) => info!("Cache flushed ({} expired entries purged)", purged),
+            Err(e) => error!("Failed to flush cache: {}", e),
+        }
+        if let Err(e) = self.sql_service.checkpoint_wal().await {
+            error!("Failed to checkpoint WAL: {}", e);
+        }
+
+        // Event forwarders go last so the frontend sees the shutdown deltas;
+        // waiting on aborted tasks makes sure they've released their listeners
+        let mut stopped: Vec<tokio::task::JoinHandle<()>> = self.tasks.lock().unwrap().drain(..).collect();
+        for task in [&self.delta_stream, &self.log_tail] {
+            stopped.extend(task.lock().await.take());
+        }
+        for handle in stopped {
+            handle.abort();
+            let _ = handle.await;
+        }
+        info!("Shutdown complete");
+    }
+}
@@ -0,0 +1,59 @@
+/*!
+ * Conversation Memory Tauri Commands
+ *
+ * Conversational RAG: answers follow-up questions with the session's history
+ * and summary in the prompt. Sessions expire after the memory TTL.
+ */
+
+use tracing::info;
+
+use rag_core::modules::kb::{AnswerRequest, GroundedAnswer};
+use rag_core::modules::memory::ConversationMemory;
+use rag_core::services::llm::LlmProviderConfig;
+
+use crate::projects::ActiveManager;
+
+/// Answer a question within a conversation session
+#[tauri::command]
+pub async fn conversation_answer(
+    manager: ActiveManager,
+    session_id: String,
+    request: AnswerRequest,
+    provider: Option<LlmProviderConfig>,
+) -> Result<GroundedAnswer, String> {
+    info!("Answering in session {} from {}", session_id, request.collection);
+
+    manager.memory_service
+        .answer(
+            &session_id,
+            manager.kb_service.as_ref(),
+            &manager.llm_service,
+            &provider.unwrap_or_default(),
+            &request,
+        )
+        .await
+        .map_err(|e| format!("Failed to answer: {}", e))
+}
+
+/// Summary and recent turns of a session
+#[tauri::command]
+pub async fn get_conversation(
+    manager: ActiveManager,
+    session_id: String,
+) -> Result<ConversationMemory, String> {
+    manager.memory_service
+        .memory(&session_id)
+        .await
+        .map_err(|e| format!("Failed to load conversation: {}", e))
+}
+
+#[tauri::command]
+pub async fn clear_conversation(
+    manager: ActiveManager,
+    session_id: String,
+) -> Result<(), String> {
+    manager.memory_service
+        .clear_session(&session_id)
+        .await
+        .map_err(|e| format!("Failed to clear conversation: {}", e))
+}
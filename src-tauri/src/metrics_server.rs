@@ -0,0 +1,82 @@
+/*!
+ * Prometheus Metrics Endpoint
+ *
+ * Optional loopback HTTP server exposing `MetricsService` at `/metrics` in
+ * the Prometheus text format, for local Prometheus/Grafana setups. Off by
+ * default; started and stopped from settings.
+ */
+
+use std::net::SocketAddr;
+use std::sync::Arc;
+use axum::extract::State;
+use axum::http::header;
+use axum::response::IntoResponse;
+use axum::routing::get;
+use axum::Router;
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+use crate::manager::Manager;
+
+/// Default port (loopback only)
+pub const DEFAULT_METRICS_PORT: u16 = 9464;
+
+const PROMETHEUS_CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+/// Running server, owned by the Manager
+pub struct MetricsServerHandle {
+    addr: SocketAddr,
+    shutdown: oneshot::Sender<()>,
+}
+
+/// Server status for the settings UI
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsServerStatus {
+    pub running: bool,
+    pub address: Option<String>,     // Scrape URL, e.g. http://127.0.0.1:9464/metrics
+}
+
+impl MetricsServerStatus {
+    pub fn of(handle: Option<&MetricsServerHandle>) -> Self {
+        Self {
+            running: handle.is_some(),
+            address: handle.map(|h| format!("http://{}/metrics", h.addr)),
+        }
+    }
+}
+
+/// Bind and serve until the handle is stopped
+pub async fn start(manager: Arc<Manager>, port: u16) -> std::io::Result<MetricsServerHandle> {
+    let listener = tokio::net::TcpListener::bind(("127.0.0.1", port)).await?;
+    let addr = listener.local_addr()?;
+
+    let app = Router::new()
+        .route("/metrics", get(metrics))
+        .with_state(manager);
+
+    let (shutdown, shutdown_rx) = oneshot::channel();
+    tokio::spawn(async move {
+        let server = axum::serve(listener, app).with_graceful_shutdown(async {
+            let _ = shutdown_rx.await;
+        });
+        if let Err(e) = server.await {
+            warn!("Metrics server stopped with error: {}", e);
+        }
+    });
+
+    info!("Prometheus metrics endpoint listening on {}", addr);
+    Ok(MetricsServerHandle { addr, shutdown })
+}
+
+impl MetricsServerHandle {
+    pub fn stop(self) {
+        let _ = self.shutdown.send(());
+        info!("Prometheus metrics endpoint on {} stopped", self.addr);
+    }
+}
+
+async fn metrics(State(manager): State<Arc<Manager>>) -> impl IntoResponse {
+    manager.refresh_metrics();
+    ([(header::CONTENT_TYPE, PROMETHEUS_CONTENT_TYPE)], manager.metrics_service.render_prometheus())
+}
@@ -0,0 +1,187 @@
+/*!
+ * Model Tauri Commands
+ *
+ * Model management for the Models screen: downloads from the HuggingFace Hub
+ * (or a mirror) report live progress as `model_download_progress` events.
+ * Recommendations rank the built-in registry against a KB's languages and size.
+ * Storage stays under quota by evicting unused downloads; pinned models stay.
+ */
+
+use tokio::sync::mpsc;
+use tracing::info;
+
+use rag_core::{
+    AlertCategory, DownloadProgress, JobKind, KbModelProfile, ModelCleanupReport, ModelKind, ModelManifest, ModelRecommendation,
+    ModelStorageStats,
+};
+use rag_core::modules::kb::KbService;
+use rag_core::services::model::{RegistryModel, SUPPORTED_MODELS};
+
+use crate::projects::ActiveManager;
+
+/// Event carrying `DownloadProgress` updates
+const DOWNLOAD_PROGRESS_EVENT: &str = "model_download_progress";
+
+/// Installed models
+#[tauri::command]
+pub async fn list_models(
+    manager: ActiveManager,
+) -> Result<Vec<ModelManifest>, String> {
+    manager.model_service
+        .list_models()
+        .await
+        .map_err(|e| format!("Failed to list models: {}", e))
+}
+
+/// Download a model, optionally only some of its files (e.g. one GGUF quantization)
+#[tauri::command]
+pub async fn download_model(
+    manager: ActiveManager,
+    model_id: String,
+    revision: Option<String>,
+    files: Option<Vec<String>>,
+) -> Result<ModelManifest, String> {
+    info!("Downloading model {}", model_id);
+
+    // Runs as a background job; progress goes to both the job and the Models screen
+    let events = manager.inner().clone();
+    let download_id = model_id.clone();
+    let manifest = manager.job_service
+        .run(JobKind::ModelDownload, &format!("Download {}", model_id), move |job| async move {
+            let (tx, mut rx) = mpsc::channel::<DownloadProgress>(64);
+            let model_service = events.model_service.clone();
+            let download = model_service.download_model(&download_id, revision.as_deref(), files.as_deref(), Some(tx));
+            let forward = async {
+                while let Some(progress) = rx.recv().await {
+                    if progress.total_bytes > 0 {
+                        job.set_progress(progress.downloaded_bytes as f32 / progress.total_bytes as f32, Some(&progress.file));
+                    }
+                    events.emit_event(DOWNLOAD_PROGRESS_EVENT, progress);
+                }
+            };
+            let (result, _) = tokio::join!(download, forward);
+            result.map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Failed to download model {}: {}", model_id, e))?;
+    manager.alert_service.notify(
+        AlertCategory::ModelDownload,
+        "Model download complete",
+        &format!("{} is installed ({} MB)", manifest.id, manifest.size_bytes / (1024 * 1024)),
+        Some(&manifest.id),
+        serde_json::json!({ "size_bytes": manifest.size_bytes }),
+    ).await;
+    manager.emit_state_delta("model_installed", serde_json::json!({
+        "model_id": manifest.id,
+        "size_bytes": manifest.size_bytes,
+    })).await;
+    Ok(manifest)
+}
+
+/// Install a model from a local tarball without network access (air-gapped installs)
+#[tauri::command]
+pub async fn import_model(
+    manager: ActiveManager,
+    path: String,
+) -> Result<ModelManifest, String> {
+    info!("Importing model from {}", path);
+    let manifest = manager.model_service
+        .import_model_from_archive(std::path::Path::new(&path))
+        .await
+        .map_err(|e| format!("Failed to import model from {}: {}", path, e))?;
+
+    manager.emit_state_delta("model_installed", serde_json::json!({
+        "model_id": manifest.id,
+        "size_bytes": manifest.size_bytes,
+    })).await;
+    Ok(manifest)
+}
+
+#[tauri::command]
+pub async fn delete_model(
+    manager: ActiveManager,
+    model_id: String,
+) -> Result<(), String> {
+    manager.model_service
+        .delete_model(&model_id)
+        .await
+        .map_err(|e| format!("Failed to delete model {}: {}", model_id, e))?;
+
+    manager.emit_state_delta("model_deleted", serde_json::json!({ "model_id": model_id })).await;
+    Ok(())
+}
+
+/// Built-in registry of supported embedding and reranker models
+#[tauri::command]
+pub async fn list_supported_models() -> Result<Vec<RegistryModel>, String> {
+    Ok(SUPPORTED_MODELS.to_vec())
+}
+
+/// Rank supported models for a KB; the chunk count is read from the KB when given
+#[tauri::command]
+pub async fn recommend_model(
+    manager: ActiveManager,
+    kind: ModelKind,
+    kb_id: Option<String>,
+    languages: Option<Vec<String>>,
+    max_model_size_mb: Option<u64>,
+) -> Result<Vec<ModelRecommendation>, String> {
+    let chunk_count = match &kb_id {
+        Some(kb_id) => manager.kb_service
+            .get_stats(Some(kb_id.clone()), None)
+            .await
+            .map_err(|e| format!("Failed to get KB stats: {}", e))?
+            .chunk_count,
+        None => 0,
+    };
+    let profile = KbModelProfile {
+        languages: languages.unwrap_or_default(),
+        chunk_count,
+        max_model_size_mb,
+    };
+
+    manager.model_service
+        .recommend_models(kind, &profile)
+        .await
+        .map_err(|e| format!("Failed to recommend models: {}", e))
+}
+
+/// Storage usage against the quota, including space reclaimed by cleanup
+#[tauri::command]
+pub async fn get_model_storage_stats(
+    manager: ActiveManager,
+) -> Result<ModelStorageStats, String> {
+    manager.model_service
+        .storage_stats()
+        .await
+        .map_err(|e| format!("Failed to get model storage stats: {}", e))
+}
+
+/// Pin a model so cleanup never evicts it (or unpin it)
+#[tauri::command]
+pub async fn pin_model(
+    manager: ActiveManager,
+    model_id: String,
+    pinned: bool,
+) -> Result<ModelManifest, String> {
+    manager.model_service
+        .set_model_pinned(&model_id, pinned)
+        .await
+        .map_err(|e| format!("Failed to update model {}: {}", model_id, e))
+}
+
+/// Run storage cleanup now
+#[tauri::command]
+pub async fn cleanup_models(
+    manager: ActiveManager,
+) -> Result<ModelCleanupReport, String> {
+    let report = manager.model_service
+        .enforce_quota()
+        .await
+        .map_err(|e| format!("Model cleanup failed: {}", e))?;
+
+    for model_id in &report.evicted {
+        manager.emit_state_delta("model_deleted", serde_json::json!({ "model_id": model_id })).await;
+    }
+    Ok(report)
+}
@@ -0,0 +1,101 @@
+/*!
+ * Outbound RPC Server
+ *
+ * Loopback HTTP endpoint the MCP subprocess calls to reach core services.
+ * Requests must carry the Manager's bearer token. `kb.*` dispatch is delegated
+ * to `KbRpcHandler`; `llm.generate` runs on the provider selected by the
+ * calling tool or flow (local llama.cpp by default).
+ */
+
+use std::sync::Arc;
+use axum::extract::State;
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use tracing::{info, info_span, warn, Instrument};
+
+use rag_core::models::outbound_rpc::{token_matches, RpcRequest, RpcResponse, DEFAULT_RPC_ADDR, RPC_PATH};
+use rag_core::modules::kb::KbRpcHandler;
+use rag_core::{GenerationParams, LlmError, LlmProviderConfig, TraceContext};
+use crate::manager::Manager;
+
+#[derive(Clone)]
+struct RpcState {
+    handler: Arc<KbRpcHandler>,
+    manager: Arc<Manager>,
+}
+
+/// Router for the outbound RPC endpoint
+fn router(manager: Arc<Manager>) -> Router {
+    let handler = KbRpcHandler::new(manager.kb_service.clone());
+    Router::new()
+        .route(RPC_PATH, post(handle_rpc))
+        .with_state(RpcState { handler: Arc::new(handler), manager })
+}
+
+async fn handle_rpc(State(state): State<RpcState>, headers: HeaderMap, Json(request): Json<RpcRequest>) -> Response {
+    let authorized = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .is_some_and(|token| token_matches(&state.manager.rpc_token, token));
+    if !authorized {
+        warn!("Rejected outbound RPC call to {}: missing or invalid token", request.method);
+        return StatusCode::UNAUTHORIZED.into_response();
+    }
+
+    // Continue the caller's trace, if any
+    let span = info_span!("rpc", method = %request.method);
+    if let Some(parent) = request.traceparent.as_deref().and_then(TraceContext::parse) {
+        parent.attach(&span);
+    }
+
+    async move {
+        if request.method == "llm.generate" {
+            return Json(generate(&state.manager, &request.params).await).into_response();
+        }
+        Json(state.handler.handle(request).await).into_response()
+    }
+    .instrument(span)
+    .await
+}
+
+async fn generate(manager: &Manager, params: &Value) -> RpcResponse {
+    let Some(prompt) = params.get("prompt").and_then(|v| v.as_str()) else {
+        return RpcResponse::error(-32602, "Missing required field: prompt");
+    };
+    let generation_params: GenerationParams = match serde_json::from_value(params.clone()) {
+        Ok(p) => p,
+        Err(e) => return RpcResponse::error(-32602, &format!("Invalid generation params: {}", e)),
+    };
+    let provider = match provider_config(manager, params) {
+        Ok(config) => config,
+        Err(e) => return RpcResponse::error(-32602, &e),
+    };
+    match manager.llm_service.generate(&provider, prompt, &generation_params).await {
+        Ok(output) => RpcResponse::success(json!({ "text": output.text, "model": output.model, "provider": provider.name() })),
+        Err(e @ LlmError::Policy(_)) => RpcResponse::error(-32003, &e.to_string()),
+        Err(e) => RpcResponse::error(-32603, &e.to_string()),
+    }
+}
+
+/// Provider configured on the calling tool or flow
+fn provider_config(manager: &Manager, params: &Value) -> Result<LlmProviderConfig, String> {
+    let state = manager.state_manager.read_state();
+    let config = if let Some(tool_id) = params.get("tool_id").and_then(|v| v.as_str()) {
+        &state.tools.get(tool_id).ok_or_else(|| format!("Tool not found: {}", tool_id))?.config
+    } else if let Some(flow_id) = params.get("flow_id").and_then(|v| v.as_str()) {
+        &state.flows.get(flow_id).ok_or_else(|| format!("Flow not found: {}", flow_id))?.definition
+    } else {
+        return Ok(LlmProviderConfig::Local);
+    };
+    LlmProviderConfig::from_config(config).map_err(|e| e.to_string())
+}
+
+/// Serve outbound RPC on `DEFAULT_RPC_ADDR` until the app exits
+pub async fn serve(manager: Arc<Manager>) -> std::io::Result<()> {
+    let listener = tokio::net::TcpListener::bind(DEFAULT_RPC_ADDR).await?;
+    info!("Outbound RPC server listening on {}", DEFAULT_RPC_ADDR);
+    axum::serve(listener, router(manager)).await
+}
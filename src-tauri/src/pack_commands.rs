@@ -0,0 +1,332 @@
+/*!
+ * Pack Tauri Commands
+ *
+ * Signing identity and signer trust for exported packs. Before importing,
+ * the UI calls `inspect_pack`; if the signer is unknown it shows the key ID
+ * and asks the user, then calls `trust_pack_signer` and retries the import.
+ *
+ * Tool packs carry tools plus their dependencies, optionally user-defined
+ * tool templates, and with `include_kb_data` the archives of their KBs. On import (or on demand via
+ * `resolve_tool_dependencies`) missing KBs are imported from the pack when
+ * bundled, missing models are downloaded, and anything else comes back to
+ * the UI as setup tasks.
+ */
+
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use serde::{Serialize, Deserialize};
+use tracing::{info, warn};
+
+use rag_core::models::tool_pack::{
+    bundle_kb, bundled_kb, kb_pipeline_template, DependencyResolution, SetupTask, ToolDependencies,
+    DEPENDENCIES_FILE, TEMPLATES_FILE, TOOLS_FILE, TOOL_PACK_TYPE,
+};
+use rag_core::models::tool_template::ToolTemplate;
+use rag_core::modules::kb::KbService;
+use rag_core::services::network_policy::NetworkFeature;
+use rag_core::state::{StateDelta, ToolState};
+use rag_core::{PackManifest, PackSigner, PackVerification, TrustedSigner};
+
+use crate::manager::Manager;
+use crate::projects::ActiveManager;
+use crate::tools_commands::{check_name_free, write_tool_catalog};
+
+/// Result of checking a pack before import
+#[derive(Debug, Serialize, Deserialize)]
+pub struct PackInspection {
+    pub manifest: PackManifest,
+    pub verification: PackVerification,
+}
+
+/// This installation's signing key, to share with teammates
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SigningKeyInfo {
+    pub key_id: String,
+    pub public_key: String,
+}
+
+/// Imported tools and what is still missing for them to work
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ToolImportResult {
+    pub tools: Vec<ToolState>,
+    pub templates: Vec<ToolTemplate>,   // Templates whose name was taken are skipped
+    pub resolution: DependencyResolution,
+}
+
+fn pack_signer(manager: &Manager) -> Result<&Arc<PackSigner>, String> {
+    manager.storage_service.signer().ok_or_else(|| "Pack signing is not configured".to_string())
+}
+
+/// Verify a pack's integrity and signer without importing it
+#[tauri::command]
+pub async fn inspect_pack(
+    manager: ActiveManager,
+    data: Vec<u8>,
+) -> Result<PackInspection, String> {
+    let (manifest, verification) = manager.storage_service
+        .verify_pack(&data)
+        .map_err(|e| format!("Invalid pack: {}", e))?;
+    Ok(PackInspection { manifest, verification })
+}
+
+#[tauri::command]
+pub async fn get_pack_signing_key(
+    manager: ActiveManager,
+) -> Result<SigningKeyInfo, String> {
+    let signer = pack_signer(&manager)?;
+    Ok(SigningKeyInfo {
+        key_id: signer.key_id().to_string(),
+        public_key: signer.public_key().to_string(),
+    })
+}
+
+#[tauri::command]
+pub async fn list_trusted_signers(
+    manager: ActiveManager,
+) -> Result<Vec<TrustedSigner>, String> {
+    Ok(pack_signer(&manager)?.trusted_signers())
+}
+
+/// Trust packs signed by `public_key` (hex), e.g. after the import prompt
+#[tauri::command]
+pub async fn trust_pack_signer(
+    manager: ActiveManager,
+    public_key: String,
+    label: String,
+) -> Result<TrustedSigner, String> {
+    pack_signer(&manager)?
+        .trust(&public_key, &label)
+        .map_err(|e| format!("Failed to trust signer: {}", e))
+}
+
+/// Stop trusting a signer; returns whether it was trusted
+#[tauri::command]
+pub async fn remove_trusted_signer(
+    manager: ActiveManager,
+    key_id: String,
+) -> Result<bool, String> {
+    pack_signer(&manager)?
+        .untrust(&key_id)
+        .map_err(|e| format!("Failed to remove signer: {}", e))
+}
+
+/// Install what `dependencies` lists and is missing here, taking KBs from
+/// `bundled` pack files when present
+async fn resolve_dependencies(
+    manager: &Manager,
+    dependencies: &ToolDependencies,
+    bundled: &BTreeMap<String, String>,
+) -> DependencyResolution {
+    let mut resolution = DependencyResolution::default();
+
+    for kb in &dependencies.kbs {
+        if manager.state_manager.read_state().knowledge_bases.contains_key(&kb.id) {
+            continue;
+        }
+        let imported = match bundled_kb(bundled, &kb.id) {
+            // The tool pack's checksums cover bundled archives, and it was
+            // already trusted or confirmed, so unsigned ones are fine here
+            Some(archive) => match manager.kb_service.import_kb(&archive, true).await {
+                Ok(kb_id) => Some(kb_id),
+                Err(e) => {
+                    warn!("Failed to import bundled KB {}: {}", kb.id, e);
+                    None
+                }
+            },
+            None => None,
+        };
+        match imported {
+            Some(kb_id) => resolution.installed.push(format!("kb:{}", kb_id)),
+            None => resolution.tasks.push(SetupTask::BuildKb { kb: kb.clone(), pipeline: kb_pipeline_template(kb) }),
+        }
+    }
+
+    for model_id in &dependencies.models {
+        if manager.model_service.get_model(model_id).await.is_ok() {
+            continue;
+        }
+        match manager.model_service.download_model(model_id, None, None, None).await {
+            Ok(manifest) => {
+                manager.emit_state_delta("model_installed", serde_json::json!({
+                    "model_id": manifest.id,
+                    "size_bytes": manifest.size_bytes,
+                })).await;
+                resolution.installed.push(format!("model:{}", manifest.id));
+            }
+            Err(e) => resolution.tasks.push(SetupTask::InstallModel { model_id: model_id.clone(), reason: e.to_string() }),
+        }
+    }
+
+    for service in &dependencies.services {
+        if let Some(secret) = &service.api_key_secret {
+            if !manager.secrets_service.contains(secret) {
+                resolution.tasks.push(SetupTask::ConfigureSecret { provider: service.provider.clone(), secret: secret.clone() });
+            }
+        }
+        if service.remote && !manager.network_policy.allows(NetworkFeature::RemoteLlm) {
+            resolution.tasks.push(SetupTask::AllowNetwork { provider: service.provider.clone(), feature: NetworkFeature::RemoteLlm });
+        }
+    }
+    resolution
+}
+
+/// Build a signed tool pack for `tool_ids` and `template_ids`, optionally
+/// embedding the exported archives of the KBs the tools search
+async fn build_tool_pack(
+    manager: &Manager,
+    tool_ids: &[String],
+    template_ids: &[String],
+    include_kb_data: bool,
+) -> Result<Vec<u8>, String> {
+    let (tools, dependencies, templates) = {
+        let state = manager.state_manager.read_state();
+        let mut tools = Vec::new();
+        let mut dependencies = ToolDependencies::default();
+        for tool_id in tool_ids {
+            let tool = state.tools.get(tool_id).cloned()
+                .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+            dependencies.merge(ToolDependencies::for_tool(&tool, &state));
+            // Usage stays local; secrets are only referenced by name
+            tools.push(ToolState { usage_count: 0, last_used: None, usage: Default::default(), ..tool });
+        }
+        let templates = template_ids.iter()
+            .map(|id| state.tool_templates.get(id).cloned().ok_or_else(|| format!("Template not found: {}", id)))
+            .collect::<Result<Vec<_>, _>>()?;
+        (tools, dependencies, templates)
+    };
+    let (id, name) = match (tools.as_slice(), templates.as_slice()) {
+        ([tool], _) => (tool.id.clone(), tool.name.clone()),
+        ([], [template]) => (template.id.clone(), template.name.clone()),
+        ([], []) => return Err("Nothing to export".to_string()),
+        ([], [first, ..]) => (first.id.clone(), format!("{} tool templates", templates.len())),
+        ([first, ..], _) => (first.id.clone(), format!("{} tools", tools.len())),
+    };
+
+    let to_json = |value: serde_json::Result<String>| value.map_err(|e| format!("Failed to export tools: {}", e));
+    let mut files = BTreeMap::from([
+        (TOOLS_FILE.to_string(), to_json(serde_json::to_string_pretty(&tools))?),
+        (DEPENDENCIES_FILE.to_string(), to_json(serde_json::to_string_pretty(&dependencies))?),
+    ]);
+    if include_kb_data {
+        for kb in &dependencies.kbs {
+            let archive = manager.kb_service
+                .export_kb(&kb.id)
+                .await
+                .map_err(|e| format!("Failed to export knowledge base {}: {}", kb.id, e))?;
+            bundle_kb(&mut files, &kb.id, &archive);
+        }
+    }
+    if !templates.is_empty() {
+        files.insert(TEMPLATES_FILE.to_string(), to_json(serde_json::to_string_pretty(&templates))?);
+    }
+
+    let manifest = PackManifest::new(TOOL_PACK_TYPE, &id, &name, "1");
+    let (_, bytes) = manager.storage_service
+        .write_pack(manifest, files)
+        .map_err(|e| format!("Failed to export tools: {}", e))?;
+
+    info!("Exported {} tools, {} templates ({} KBs bundled, {} bytes)",
+        tools.len(), templates.len(), if include_kb_data { dependencies.kbs.len() } else { 0 }, bytes.len());
+    Ok(bytes)
+}
+
+/// Package a tool and its dependency listing as a signed tool pack
+#[tauri::command]
+pub async fn export_tool(
+    manager: ActiveManager,
+    tool_id: String,
+    include_kb_data: Option<bool>,
+    template_ids: Option<Vec<String>>,
+) -> Result<Vec<u8>, String> {
+    build_tool_pack(&manager, &[tool_id], &template_ids.unwrap_or_default(), include_kb_data.unwrap_or(false)).await
+}
+
+/// Package several tools (and/or templates) into one tool pack
+#[tauri::command]
+pub async fn bulk_export_tools(
+    manager: ActiveManager,
+    tool_ids: Vec<String>,
+    include_kb_data: Option<bool>,
+    template_ids: Option<Vec<String>>,
+) -> Result<Vec<u8>, String> {
+    build_tool_pack(&manager, &tool_ids, &template_ids.unwrap_or_default(), include_kb_data.unwrap_or(false)).await
+}
+
+/// Import a tool pack and install its dependencies. The tools stay disabled
+/// until every setup task is done. Unsigned packs need `allow_unsigned`,
+/// set once the user confirms the `inspect_pack` prompt.
+#[tauri::command]
+pub async fn import_tool(
+    manager: ActiveManager,
+    data: Vec<u8>,
+    allow_unsigned: Option<bool>,
+) -> Result<ToolImportResult, String> {
+    let (manifest, files) = manager.storage_service
+        .read_pack_with(&data, allow_unsigned.unwrap_or(false))
+        .map_err(|e| format!("Failed to import tool: {}", e))?;
+    if manifest.pack_type != TOOL_PACK_TYPE {
+        return Err(format!("Expected a tool pack, got: {}", manifest.pack_type));
+    }
+    let read = |name: &str| files.get(name).ok_or_else(|| format!("Tool pack is missing {}", name));
+    let mut tools: Vec<ToolState> = serde_json::from_str(read(TOOLS_FILE)?)
+        .map_err(|e| format!("Invalid {}: {}", TOOLS_FILE, e))?;
+    let dependencies: ToolDependencies = serde_json::from_str(read(DEPENDENCIES_FILE)?)
+        .map_err(|e| format!("Invalid {}: {}", DEPENDENCIES_FILE, e))?;
+    let templates: Vec<ToolTemplate> = match files.get(TEMPLATES_FILE) {
+        Some(json) => serde_json::from_str(json).map_err(|e| format!("Invalid {}: {}", TEMPLATES_FILE, e))?,
+        None => Vec::new(),
+    };
+
+    // Check every name before changing anything, including clashes inside the pack
+    let mut names = std::collections::HashSet::new();
+    for tool in &tools {
+        let mcp_name = check_name_free(&*manager, &tool.name, None)?;
+        if !names.insert(mcp_name.clone()) {
+            return Err(format!("Tool pack contains '{}' twice", mcp_name));
+        }
+    }
+
+    let resolution = resolve_dependencies(&manager, &dependencies, &files).await;
+    for tool in &mut tools {
+        if manager.state_manager.read_state().tools.contains_key(&tool.id) {
+            tool.id = format!("tool_{}", uuid::Uuid::new_v4().simple());
+        }
+        tool.enabled = resolution.is_complete();
+        manager.state_manager
+            .mutate(StateDelta::ToolAdd { tool: tool.clone() })
+            .map_err(|e| format!("Failed to import tool: {}", e))?;
+    }
+    write_tool_catalog(&*manager)?;
+
+    let mut imported_templates = Vec::new();
+    for mut template in templates {
+        let existing = manager.state_manager.read_state().tool_templates.get(&template.id).cloned();
+        if existing.is_some_and(|t| t.name != template.name) {
+            template.id = format!("tpl_{}", uuid::Uuid::new_v4().simple());
+        }
+        template.builtin = false;
+        match manager.state_manager.mutate(StateDelta::ToolTemplateUpsert { template: template.clone() }) {
+            Ok(_) => imported_templates.push(template),
+            Err(e) => warn!("Skipping template {}: {}", template.name, e),
+        }
+    }
+
+    info!("Imported {} tools, {} templates ({} installed, {} setup tasks)",
+        tools.len(), imported_templates.len(), resolution.installed.len(), resolution.tasks.len());
+    Ok(ToolImportResult { tools, templates: imported_templates, resolution })
+}
+
+/// Re-check an existing tool's dependencies, installing what can be installed
+#[tauri::command]
+pub async fn resolve_tool_dependencies(
+    manager: ActiveManager,
+    tool_id: String,
+) -> Result<DependencyResolution, String> {
+    let dependencies = {
+        let state = manager.state_manager.read_state();
+        let tool = state.tools.get(&tool_id)
+            .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+        ToolDependencies::for_tool(tool, &state)
+    };
+    Ok(resolve_dependencies(&manager, &dependencies, &BTreeMap::new()).await)
+}
@@ -0,0 +1,215 @@
+/*!
+ * Project Host
+ *
+ * Owns the Manager of the open project and swaps it at runtime. Opening a
+ * project shuts the current Manager down (flushing its state and freeing
+ * its ports), then starts a new one over the other project's data
+ * directories. Commands reach the open project's Manager through the
+ * `ActiveManager` argument, so a switch never leaves them holding the old one.
+ */
+
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use tauri::ipc::{CommandArg, CommandItem, InvokeError};
+use tauri::{AppHandle, Manager as TauriManager, Runtime, State};
+
+use rag_core::{ProjectInfo, ProjectRegistry};
+
+use crate::lifecycle::{self, Lifecycle};
+use crate::manager::{AppServices, Manager};
+
+/// Project opened on first run, next to the app
+pub const DEFAULT_PROJECT_ROOT: &str = ".";
+
+/// Name the default project is created with
+const DEFAULT_PROJECT_NAME: &str = "Default";
+
+struct OpenProject {
+    info: ProjectInfo,
+    manager: Arc<Manager>,
+}
+
+/// The open project and the app-wide services every project shares
+pub struct ProjectHost {
+    app_services: AppServices,
+    registry: ProjectRegistry,
+    current: RwLock<Option<OpenProject>>,
+    switching: tokio::sync::Mutex<()>,  // One open/close at a time
+}
+
+impl ProjectHost {
+    pub fn new(app_services: AppServices, registry: ProjectRegistry) -> Self {
+        Self {
+            app_services,
+            registry,
+            current: RwLock::new(None),
+            switching: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    pub fn app_services(&self) -> &AppServices {
+        &self.app_services
+    }
+
+    pub fn registry(&self) -> &ProjectRegistry {
+        &self.registry
+    }
+
+    /// Manager of the open project; None while a project is being opened
+    pub fn manager(&self) -> Option<Arc<Manager>> {
+        self.current.read().unwrap().as_ref().map(|open| open.manager.clone())
+    }
+
+    pub fn current_project(&self) -> Option<ProjectInfo> {
+        self.current.read().unwrap().as_ref().map(|open| open.info.clone())
+    }
+
+    /// Project to open at startup: the last one opened, or the default
+    /// project (created on first run)
+    pub fn startup_root(&self) -> PathBuf {
+        self.registry.last_opened()
+            .map(|project| project.root)
+            .filter(|root| root.exists())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_PROJECT_ROOT))
+    }
+
+    /// Close the open project, if any, and open the one at `root`. When the
+    /// new project fails to start, the previous one is reopened.
+    pub async fn open(&self, app_handle: &AppHandle, lifecycle: &Lifecycle, root: &Path) -> Result<ProjectInfo, String> {
+        let _switching = self.switching.lock().await;
+        if !root.join(rag_core::services::projects::PROJECT_MARKER_FILE).exists()
+            && root == Path::new(DEFAULT_PROJECT_ROOT)
+        {
+            self.registry.create(root, DEFAULT_PROJECT_NAME).map_err(|e| e.to_string())?;
+        }
+        let info = self.registry.touch(root).map_err(|e| e.to_string())?;
+        if self.current_project().is_some_and(|open| open.id == info.id) {
+            return Ok(info);
+        }
+
+        let previous = self.close_current().await;
+        lifecycle.restart();
+        match lifecycle::start_manager(app_handle, lifecycle, &info.root, &self.app_services).await {
+            Ok(manager) => {
+                *self.current.write().unwrap() = Some(OpenProject { info: info.clone(), manager });
+                lifecycle.ready();
+                Ok(info)
+            }
+            Err(e) => {
+                let error = format!("Failed to open project '{}': {}", info.name, e);
+                eprintln!("❌ {}", error);
+                if let Some(previous) = previous {
+                    lifecycle.restart();
+                    if let Ok(manager) = lifecycle::start_manager(app_handle, lifecycle, &previous.root, &self.app_services).await {
+                        let _ = self.registry.touch(&previous.root);
+                        *self.current.write().unwrap() = Some(OpenProject { info: previous, manager });
+                        lifecycle.ready();
+                        return Err(error);
+                    }
+                }
+                lifecycle.fail(error.clone());
+                Err(error)
+            }
+        }
+    }
+
+    /// Shut the open project down; the app is left without a project
+    pub async fn close(&self) -> Option<ProjectInfo> {
+        let _switching = self.switching.lock().await;
+        self.close_current().await
+    }
+
+    async fn close_current(&self) -> Option<ProjectInfo> {
+        let open = self.current.write().unwrap().take()?;
+        println!("Closing project '{}'...", open.info.name);
+        if tokio::time::timeout(lifecycle::SHUTDOWN_TIMEOUT, open.manager.shutdown()).await.is_err() {
+            eprintln!("Shutdown did not finish within {:?}", lifecycle::SHUTDOWN_TIMEOUT);
+        }
+        Some(open.info)
+    }
+}
+
+/// The open project's Manager, as a command argument. Fails the command
+/// while the app is starting or switching projects.
+pub struct ActiveManager(Arc<Manager>);
+
+impl ActiveManager {
+    pub fn inner(&self) -> &Arc<Manager> {
+        &self.0
+    }
+}
+
+impl Deref for ActiveManager {
+    type Target = Manager;
+
+    fn deref(&self) -> &Manager {
+        &self.0
+    }
+}
+
+impl<'de, R: Runtime> CommandArg<'de, R> for ActiveManager {
+    fn from_command(command: CommandItem<'de, R>) -> Result<Self, InvokeError> {
+        command.message.webview_ref()
+            .try_state::<Arc<ProjectHost>>()
+            .and_then(|host| host.manager())
+            .map(ActiveManager)
+            .ok_or_else(|| InvokeError::from("RAG Studio is still starting; no project is open yet".to_string()))
+    }
+}
+
+/// Recently opened projects, most recent first
+#[tauri::command]
+pub async fn list_recent_projects(host: State<'_, Arc<ProjectHost>>) -> Result<Vec<ProjectInfo>, String> {
+    Ok(host.registry().recent())
+}
+
+/// The open project; None while starting or switching
+#[tauri::command]
+pub async fn get_current_project(host: State<'_, Arc<ProjectHost>>) -> Result<Option<ProjectInfo>, String> {
+    Ok(host.current_project())
+}
+
+/// Make `root` a new project and open it
+#[tauri::command]
+pub async fn create_project(
+    app_handle: AppHandle,
+    host: State<'_, Arc<ProjectHost>>,
+    lifecycle: State<'_, Arc<Lifecycle>>,
+    root: String,
+    name: String,
+) -> Result<ProjectInfo, String> {
+    let root = PathBuf::from(root);
+    host.registry().create(&root, &name).map_err(|e| e.to_string())?;
+    host.open(&app_handle, &lifecycle, &root).await
+}
+
+/// Switch to the project at `root`
+#[tauri::command]
+pub async fn open_project(
+    app_handle: AppHandle,
+    host: State<'_, Arc<ProjectHost>>,
+    lifecycle: State<'_, Arc<Lifecycle>>,
+    root: String,
+) -> Result<ProjectInfo, String> {
+    host.open(&app_handle, &lifecycle, Path::new(&root)).await
+}
+
+/// Close the open project and go back to the default one
+#[tauri::command]
+pub async fn close_project(
+    app_handle: AppHandle,
+    host: State<'_, Arc<ProjectHost>>,
+    lifecycle: State<'_, Arc<Lifecycle>>,
+) -> Result<ProjectInfo, String> {
+    host.open(&app_handle, &lifecycle, Path::new(DEFAULT_PROJECT_ROOT)).await
+}
+
+/// Drop a project from the recent list; its files stay where they are
+#[tauri::command]
+pub async fn forget_project(host: State<'_, Arc<ProjectHost>>, project_id: String) -> Result<(), String> {
+    if host.current_project().is_some_and(|open| open.id == project_id) {
+        return Err("The open project can't be removed from the recent list".to_string());
+    }
+    host.registry().forget(&project_id).map_err(|e| e.to_string())
+}
@@ -0,0 +1,121 @@
+/*!
+ * Prompt Tauri Commands
+ *
+ * Authoring of MCP prompt templates. Prompts live in core state and are
+ * written to the catalog file the MCP subprocess reads (`--prompts`).
+ */
+
+use serde::{Serialize, Deserialize};
+use tracing::info;
+
+use rag_core::models::prompt::{PromptArgument, PromptCatalog, PromptTemplate};
+use rag_core::state::StateDelta;
+
+use crate::manager::Manager;
+use crate::projects::ActiveManager;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavePromptRequest {
+    pub id: Option<String>,             // None creates a new prompt
+    pub name: String,
+    pub description: Option<String>,
+    pub template: String,
+    pub arguments: Vec<PromptArgument>,
+    pub arguments_schema: Option<serde_json::Value>,
+    pub kb_id: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// Seed core state from the catalog file (built-ins on first run)
+fn ensure_prompts_loaded(manager: &Manager) -> Result<(), String> {
+    if !manager.state_manager.read_state().prompts.is_empty() {
+        return Ok(());
+    }
+
+    let catalog = PromptCatalog::load(&manager.paths().mcp_prompts)
+        .map_err(|e| format!("Failed to load prompt catalog: {}", e))?;
+    for prompt in catalog.prompts {
+        manager.state_manager
+            .mutate(StateDelta::PromptUpsert { prompt })
+            .map_err(|e| format!("Failed to load prompt: {}", e))?;
+    }
+    Ok(())
+}
+
+/// Write current prompts to the catalog shared with the MCP server
+pub(crate) fn write_prompt_catalog(manager: &Manager) -> Result<(), String> {
+    let mut prompts: Vec<PromptTemplate> = manager.state_manager.read_state().prompts.values().cloned().collect();
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+
+    PromptCatalog { prompts }
+        .save(&manager.paths().mcp_prompts)
+        .map_err(|e| format!("Failed to write prompt catalog: {}", e))
+}
+
+/// List prompt templates
+#[tauri::command]
+pub async fn list_prompts(
+    manager: ActiveManager,
+) -> Result<Vec<PromptTemplate>, String> {
+    ensure_prompts_loaded(&manager)?;
+
+    let mut prompts: Vec<PromptTemplate> = manager.state_manager.read_state().prompts.values().cloned().collect();
+    prompts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(prompts)
+}
+
+/// Create or update a prompt template
+#[tauri::command]
+pub async fn save_prompt(
+    manager: ActiveManager,
+    request: SavePromptRequest,
+) -> Result<PromptTemplate, String> {
+    ensure_prompts_loaded(&manager)?;
+
+    let existing = request.id.as_ref()
+        .and_then(|id| manager.state_manager.read_state().prompts.get(id).cloned());
+    if request.id.is_some() && existing.is_none() {
+        return Err(format!("Prompt not found: {}", request.id.unwrap_or_default()));
+    }
+
+    let mut prompt = existing.unwrap_or_else(|| PromptTemplate::new(&request.name, "", &request.template, Vec::new()));
+    prompt.name = request.name;
+    prompt.description = request.description;
+    prompt.template = request.template;
+    prompt.arguments = request.arguments;
+    prompt.arguments_schema = request.arguments_schema;
+    prompt.kb_id = request.kb_id;
+    prompt.enabled = request.enabled.unwrap_or(prompt.enabled);
+    prompt.updated_at = chrono::Utc::now();
+
+    prompt.validate().map_err(|e| e.to_string())?;
+
+    manager.state_manager
+        .mutate(StateDelta::PromptUpsert { prompt: prompt.clone() })
+        .map_err(|e| format!("Failed to save prompt: {}", e))?;
+    write_prompt_catalog(&manager)?;
+
+    info!("Saved prompt {} ({})", prompt.name, prompt.id);
+    manager.emit_state_delta("prompt_saved", serde_json::json!(prompt)).await;
+    Ok(prompt)
+}
+
+/// Delete a prompt template
+#[tauri::command]
+pub async fn delete_prompt(
+    manager: ActiveManager,
+    prompt_id: String,
+) -> Result<(), String> {
+    ensure_prompts_loaded(&manager)?;
+
+    if !manager.state_manager.read_state().prompts.contains_key(&prompt_id) {
+        return Err(format!("Prompt not found: {}", prompt_id));
+    }
+    manager.state_manager
+        .mutate(StateDelta::PromptRemove { id: prompt_id.clone() })
+        .map_err(|e| format!("Failed to delete prompt: {}", e))?;
+    write_prompt_catalog(&manager)?;
+
+    manager.emit_state_delta("prompt_deleted", serde_json::json!({ "prompt_id": prompt_id })).await;
+    Ok(())
+}
@@ -0,0 +1,64 @@
+/*!
+ * Schedule Tauri Commands
+ *
+ * Refresh schedules bound to tools or KBs. The Manager's refresh scheduler
+ * runs them when due; outcomes arrive as `kb_refresh_completed` events.
+ */
+
+use tracing::info;
+
+use rag_core::modules::schedule::{RefreshOutcome, RefreshScheduleSpec};
+use rag_core::state::ScheduleState;
+use rag_core::JobKind;
+
+use crate::manager::Manager;
+use crate::projects::ActiveManager;
+
+/// List refresh schedules, soonest first
+#[tauri::command]
+pub async fn list_refresh_schedules(
+    manager: ActiveManager,
+) -> Result<Vec<ScheduleState>, String> {
+    Ok(manager.refresh_service.list_schedules())
+}
+
+/// Create or update a refresh schedule
+#[tauri::command]
+pub async fn save_refresh_schedule(
+    manager: ActiveManager,
+    request: RefreshScheduleSpec,
+) -> Result<ScheduleState, String> {
+    let schedule = manager.refresh_service
+        .save_schedule(request)
+        .map_err(|e| format!("Failed to save refresh schedule: {}", e))?;
+
+    info!("Refresh schedule saved: {} (next run {:?})", schedule.id, schedule.next_run);
+    Ok(schedule)
+}
+
+/// Delete a refresh schedule
+#[tauri::command]
+pub async fn delete_refresh_schedule(
+    manager: ActiveManager,
+    schedule_id: String,
+) -> Result<(), String> {
+    manager.refresh_service
+        .remove_schedule(&schedule_id)
+        .map_err(|e| format!("Failed to delete refresh schedule: {}", e))
+}
+
+/// Run a refresh schedule now
+#[tauri::command]
+pub async fn run_refresh_schedule(
+    manager: ActiveManager,
+    schedule_id: String,
+) -> Result<RefreshOutcome, String> {
+    let refresh_service = manager.refresh_service.clone();
+    let title = format!("Refresh {}", schedule_id);
+    manager.job_service
+        .run(JobKind::PipelineRun, &title, move |_| async move {
+            refresh_service.run_schedule(&schedule_id).await.map_err(|e| e.to_string())
+        })
+        .await
+        .map_err(|e| format!("Refresh failed: {}", e))
+}
@@ -0,0 +1,80 @@
+/*!
+ * Service Locator
+ *
+ * Command helpers and background tasks that only need a few services reach
+ * them through `ServiceLocator` instead of the concrete Manager, so tests
+ * can inject in-memory or mock services without building the whole app.
+ */
+
+use std::sync::Arc;
+
+use rag_core::modules::audit::AuditService;
+use rag_core::services::settings::PathSettings;
+use rag_core::StateManager;
+
+use crate::manager::Manager;
+
+/// Services a command helper may ask for
+pub trait ServiceLocator: Send + Sync {
+    fn state_manager(&self) -> &Arc<StateManager>;
+
+    fn audit_service(&self) -> &Arc<AuditService>;
+
+    /// Data locations of the open project
+    fn paths(&self) -> PathSettings;
+}
+
+impl ServiceLocator for Manager {
+    fn state_manager(&self) -> &Arc<StateManager> {
+        &self.state_manager
+    }
+
+    fn audit_service(&self) -> &Arc<AuditService> {
+        &self.audit_service
+    }
+
+    fn paths(&self) -> PathSettings {
+        Manager::paths(self)
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use rag_core::{SqlConfig, SqlService};
+
+    /// Services backed by a throwaway database and in-memory state
+    pub(crate) struct TestServices {
+        pub state_manager: Arc<StateManager>,
+        pub audit_service: Arc<AuditService>,
+        pub root: std::path::PathBuf,
+    }
+
+    impl TestServices {
+        pub(crate) async fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("rag_studio_test_{}", uuid::Uuid::new_v4().simple()));
+            std::fs::create_dir_all(&dir).unwrap();
+            let sql_service = SqlService::new(SqlConfig::new_mvp(dir.join("app.db"))).await.unwrap();
+            sql_service.run_migrations().await.unwrap();
+            Self {
+                state_manager: Arc::new(StateManager::new()),
+                audit_service: Arc::new(AuditService::new(Arc::new(sql_service))),
+                root: dir,
+            }
+        }
+    }
+
+    impl ServiceLocator for TestServices {
+        fn state_manager(&self) -> &Arc<StateManager> {
+            &self.state_manager
+        }
+
+        fn audit_service(&self) -> &Arc<AuditService> {
+            &self.audit_service
+        }
+
+        fn paths(&self) -> PathSettings {
+            PathSettings::default().resolved_in(&self.root)
+        }
+    }
+}
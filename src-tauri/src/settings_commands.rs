@@ -1,6 +1,17 @@
 use serde::{Deserialize, Serialize};
-use tauri::State;
+use rag_core::{CacheStats, NetworkFeature, NetworkPolicyStatus, QuotaUsage, SecretInfo, Settings, SettingsChange};
+use rag_core::services::sql::DatabaseBackup;
+use rag_core::services::storage::{BlobGcReport, BlobStoreStats};
+use rag_core::models::mcp_quota::{QuotaMetrics, DEFAULT_QUOTA_METRICS_PATH};
+use rag_core::modules::audit::{AuditExportFormat, AuditLogFilter, McpAuditEntry};
+use crate::api_server::{self, ApiServerStatus, DEFAULT_API_PORT};
+use crate::metrics_server::{self, MetricsServerStatus, DEFAULT_METRICS_PORT};
 use crate::manager::Manager;
+use crate::projects::ActiveManager;
+use rag_core::state::StateDelta;
+
+/// State key holding the saved `AppSettings` JSON
+const APP_SETTINGS_KEY: &str = "app_settings";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerSettings {
@@ -101,13 +112,28 @@ impl Default for AppSettings {
     }
 }
 
+/// Settings saved by the last update, or defaults
+fn saved_app_settings(manager: &Manager) -> AppSettings {
+    manager.state_manager.read_state().settings.get(APP_SETTINGS_KEY)
+        .and_then(|json| serde_json::from_str(json).ok())
+        .unwrap_or_default()
+}
+
+/// Apply the saved backup schedule (air-gapped mode follows the settings file)
+pub async fn apply_saved_settings(manager: &Manager) {
+    let settings = saved_app_settings(manager);
+    manager.configure_backup_schedule(
+        settings.system.auto_backup,
+        settings.system.backup_interval_hours,
+        settings.system.max_backups,
+    ).await;
+}
+
 /// Get current application settings
 #[tauri::command]
 pub async fn get_app_settings(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<AppSettings, String> {
-    // For MVP, return default settings
-    // TODO: Load from configuration file or database
     let app_state = manager.get_app_state().await;
     let state = app_state.read().await;
 
@@ -118,8 +144,11 @@ pub async fn get_app_settings(
         "stopped"
     };
 
-    let mut settings = AppSettings::default();
+    let mut settings = saved_app_settings(&manager);
     settings.server.mcp_server_status = mcp_status.to_string();
+    let features = manager.settings_service.get().features;
+    settings.security.air_gapped_mode = features.air_gapped;
+    settings.security.encrypt_data = features.encrypt_content;
 
     Ok(settings)
 }
@@ -127,31 +156,120 @@ pub async fn get_app_settings(
 /// Update application settings
 #[tauri::command]
 pub async fn update_app_settings(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     settings: AppSettings
 ) -> Result<AppSettings, String> {
-    // TODO: Validate settings and save to configuration
+    // TODO: Validate settings
     println!("Updating app settings: {:?}", settings);
 
-    // Update MCP server status if needed
-    let app_state = manager.get_app_state().await;
-    {
-        let mut state = app_state.write().await;
-
-        // Update air-gapped mode
-        if state.air_gapped_mode != settings.security.air_gapped_mode {
-            state.air_gapped_mode = settings.security.air_gapped_mode;
-            println!("Air-gapped mode set to: {}", settings.security.air_gapped_mode);
-        }
+    let json = serde_json::to_string(&settings)
+        .map_err(|e| format!("Failed to serialize settings: {}", e))?;
+    manager.state_manager
+        .mutate(StateDelta::SettingSet { key: APP_SETTINGS_KEY.to_string(), value: Some(json) })
+        .map_err(|e| format!("Failed to save settings: {}", e))?;
+
+    manager.configure_backup_schedule(
+        settings.system.auto_backup,
+        settings.system.backup_interval_hours,
+        settings.system.max_backups,
+    ).await;
+
+    // Air-gapped mode and content encryption live in the settings file; air-gapped
+    // mode is hot-applied from there, encryption takes effect when the project reopens
+    let change = manager.settings_service
+        .update(&serde_json::json!({ "features": {
+            "air_gapped": settings.security.air_gapped_mode,
+            "encrypt_content": settings.security.encrypt_data,
+        } }))
+        .map_err(|e| format!("Failed to update security settings: {}", e))?;
+    manager.inner().apply_runtime_settings(&change.settings).await;
+    if !change.restart_required.is_empty() {
+        println!("Settings take effect after restart: {:?}", change.restart_required);
     }
 
     Ok(settings)
 }
 
+/// Typed application configuration
+#[tauri::command]
+pub async fn get_settings(
+    manager: ActiveManager,
+) -> Result<Settings, String> {
+    Ok(manager.settings_service.get())
+}
+
+/// Merge a partial settings document, validate and save it; safe changes
+/// apply immediately, the rest are listed in `restart_required`
+#[tauri::command]
+pub async fn update_settings(
+    manager: ActiveManager,
+    patch: serde_json::Value,
+) -> Result<SettingsChange, String> {
+    let change = manager.settings_service.update(&patch).map_err(|e| e.to_string())?;
+    // The settings watcher applies it too; doing it here makes it effective on return
+    manager.inner().apply_runtime_settings(&change.settings).await;
+    Ok(change)
+}
+
+/// Stored secrets (names and timestamps only)
+#[tauri::command]
+pub async fn list_secrets(
+    manager: ActiveManager,
+) -> Result<Vec<SecretInfo>, String> {
+    Ok(manager.secrets_service.list())
+}
+
+/// Store or replace a secret in the encrypted vault
+#[tauri::command]
+pub async fn set_secret(
+    manager: ActiveManager,
+    name: String,
+    value: String,
+) -> Result<SecretInfo, String> {
+    manager.secrets_service.set(&name, &value).map_err(|e| e.to_string())
+}
+
+/// Remove a secret; returns whether it existed
+#[tauri::command]
+pub async fn delete_secret(
+    manager: ActiveManager,
+    name: String,
+) -> Result<bool, String> {
+    manager.secrets_service.delete(&name).map_err(|e| e.to_string())
+}
+
+/// Air-gapped state, per-feature access and the override audit trail
+#[tauri::command]
+pub async fn get_network_policy(
+    manager: ActiveManager,
+) -> Result<NetworkPolicyStatus, String> {
+    Ok(manager.network_policy.status())
+}
+
+/// Let one feature through (or stop letting it through) while air-gapped.
+/// The change is audited with `reason` and saved with the settings.
+#[tauri::command]
+pub async fn set_network_override(
+    manager: ActiveManager,
+    feature: NetworkFeature,
+    allowed: bool,
+    reason: String,
+) -> Result<NetworkPolicyStatus, String> {
+    if reason.trim().is_empty() {
+        return Err("A reason is required for network policy overrides".to_string());
+    }
+    manager.network_policy.set_override(feature, allowed, reason.trim())
+        .map_err(|e| e.to_string())?;
+    manager.settings_service
+        .update(&serde_json::json!({ "features": { "network_overrides": manager.network_policy.overrides() } }))
+        .map_err(|e| format!("Failed to save network overrides: {}", e))?;
+    Ok(manager.network_policy.status())
+}
+
 /// Start MCP server
 #[tauri::command]
 pub async fn start_mcp_server(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<String, String> {
     println!("Starting MCP server...");
 
@@ -169,7 +287,7 @@ pub async fn start_mcp_server(
 /// Stop MCP server
 #[tauri::command]
 pub async fn stop_mcp_server(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<String, String> {
     println!("Stopping MCP server...");
 
@@ -187,7 +305,7 @@ pub async fn stop_mcp_server(
 /// Get MCP server status
 #[tauri::command]
 pub async fn get_mcp_server_status(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<ServerSettings, String> {
     let app_state = manager.get_app_state().await;
     let state = app_state.read().await;
@@ -208,6 +326,135 @@ pub async fn get_mcp_server_status(
     })
 }
 
+/// Get per-client MCP quota usage (snapshot written by the MCP server via `--quota-metrics`)
+#[tauri::command]
+pub async fn get_mcp_quota_metrics() -> Result<QuotaMetrics, String> {
+    QuotaMetrics::load(std::path::Path::new(DEFAULT_QUOTA_METRICS_PATH))
+        .map_err(|e| format!("Failed to read MCP quota metrics: {}", e))
+}
+
+/// Start the OpenAI-compatible API server on loopback
+#[tauri::command]
+pub async fn start_api_server(
+    manager: ActiveManager,
+    port: Option<u16>,
+    api_key: Option<String>,
+) -> Result<ApiServerStatus, String> {
+    let mut server = manager.api_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.stop();
+    }
+
+    let handle = api_server::start(manager.inner().clone(), port.unwrap_or(DEFAULT_API_PORT), api_key)
+        .await
+        .map_err(|e| format!("Failed to start API server: {}", e))?;
+    *server = Some(handle);
+    Ok(ApiServerStatus::of(server.as_ref()))
+}
+
+/// Stop the OpenAI-compatible API server
+#[tauri::command]
+pub async fn stop_api_server(
+    manager: ActiveManager,
+) -> Result<ApiServerStatus, String> {
+    let mut server = manager.api_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.stop();
+    }
+    Ok(ApiServerStatus::of(None))
+}
+
+/// OpenAI-compatible API server status
+#[tauri::command]
+pub async fn get_api_server_status(
+    manager: ActiveManager,
+) -> Result<ApiServerStatus, String> {
+    Ok(ApiServerStatus::of(manager.api_server.lock().await.as_ref()))
+}
+
+/// Start the Prometheus `/metrics` endpoint on loopback
+#[tauri::command]
+pub async fn start_metrics_server(
+    manager: ActiveManager,
+    port: Option<u16>,
+) -> Result<MetricsServerStatus, String> {
+    let mut server = manager.metrics_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.stop();
+    }
+
+    let handle = metrics_server::start(manager.inner().clone(), port.unwrap_or(DEFAULT_METRICS_PORT))
+        .await
+        .map_err(|e| format!("Failed to start metrics server: {}", e))?;
+    *server = Some(handle);
+    Ok(MetricsServerStatus::of(server.as_ref()))
+}
+
+/// Stop the Prometheus `/metrics` endpoint
+#[tauri::command]
+pub async fn stop_metrics_server(
+    manager: ActiveManager,
+) -> Result<MetricsServerStatus, String> {
+    let mut server = manager.metrics_server.lock().await;
+    if let Some(handle) = server.take() {
+        handle.stop();
+    }
+    Ok(MetricsServerStatus::of(None))
+}
+
+/// Prometheus endpoint status
+#[tauri::command]
+pub async fn get_metrics_server_status(
+    manager: ActiveManager,
+) -> Result<MetricsServerStatus, String> {
+    Ok(MetricsServerStatus::of(manager.metrics_server.lock().await.as_ref()))
+}
+
+/// Local generation backend status
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GenerationStatus {
+    pub local_model_available: bool,
+    pub local_model: Option<String>,
+    pub air_gapped_mode: bool,      // Remote LLM providers are refused while set
+}
+
+/// Report whether answer tools can generate locally
+#[tauri::command]
+pub async fn get_generation_status(
+    manager: ActiveManager,
+) -> Result<GenerationStatus, String> {
+    Ok(GenerationStatus {
+        local_model_available: manager.generation_service.is_available(),
+        local_model: manager.generation_service.model_name(),
+        air_gapped_mode: manager.app_state.read().await.air_gapped_mode,
+    })
+}
+
+/// Query the MCP tool invocation audit log (newest first)
+#[tauri::command]
+pub async fn get_mcp_audit_log(
+    manager: ActiveManager,
+    filter: Option<AuditLogFilter>,
+) -> Result<Vec<McpAuditEntry>, String> {
+    manager.audit_service
+        .query(&filter.unwrap_or_default())
+        .await
+        .map_err(|e| format!("Failed to read MCP audit log: {}", e))
+}
+
+/// Export the MCP audit log as JSON or CSV text
+#[tauri::command]
+pub async fn export_mcp_audit_log(
+    manager: ActiveManager,
+    filter: Option<AuditLogFilter>,
+    format: AuditExportFormat,
+) -> Result<String, String> {
+    manager.audit_service
+        .export(&filter.unwrap_or_default(), format)
+        .await
+        .map_err(|e| format!("Failed to export MCP audit log: {}", e))
+}
+
 /// Select data directory using system dialog
 #[tauri::command]
 pub async fn select_data_directory() -> Result<Option<String>, String> {
@@ -219,19 +466,141 @@ pub async fn select_data_directory() -> Result<Option<String>, String> {
 /// Clear application cache
 #[tauri::command]
 pub async fn clear_application_cache(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<String, String> {
     println!("Clearing application cache...");
 
-    // TODO: Clear actual cache through cache service
+    manager.cache_service.clear()
+        .map_err(|e| format!("Failed to clear cache: {}", e))?;
 
     Ok("Cache cleared successfully".to_string())
 }
 
+/// Cache usage and hit rates across the memory and disk tiers
+#[tauri::command]
+pub async fn get_cache_stats(
+    manager: ActiveManager
+) -> Result<CacheStats, String> {
+    manager.cache_service.stats()
+        .map_err(|e| format!("Failed to read cache stats: {}", e))
+}
+
+/// Blob store usage, including what deduplication saves
+#[tauri::command]
+pub async fn get_blob_store_stats(
+    manager: ActiveManager
+) -> Result<BlobStoreStats, String> {
+    manager.storage_service.blob_stats()
+        .map_err(|e| format!("Failed to read blob store stats: {}", e))
+}
+
+/// Remove blobs no KB version or pipeline run references any more
+#[tauri::command]
+pub async fn gc_unreferenced_blobs(
+    manager: ActiveManager
+) -> Result<BlobGcReport, String> {
+    let storage_service = manager.storage_service.clone();
+    let report = tokio::task::spawn_blocking(move || storage_service.gc_unreferenced_blobs())
+        .await
+        .map_err(|e| format!("Blob GC failed: {}", e))?
+        .map_err(|e| format!("Blob GC failed: {}", e))?;
+    manager.emit_state_delta("blob_gc", serde_json::to_value(&report).unwrap_or_default()).await;
+    Ok(report)
+}
+
+/// Disk usage against quotas: the project first, then each KB (or only `kb_id`)
+#[tauri::command]
+pub async fn get_disk_quota_usage(
+    manager: ActiveManager,
+    kb_id: Option<String>,
+) -> Result<Vec<QuotaUsage>, String> {
+    let mut kb_ids: Vec<String> = match kb_id {
+        Some(kb_id) => vec![kb_id],
+        None => manager.state_manager.read_state().knowledge_bases.keys().cloned().collect(),
+    };
+    kb_ids.sort();
+
+    let mut usage = vec![manager.quota_service.project_usage().await.map_err(|e| e.to_string())?];
+    for kb_id in kb_ids {
+        usage.push(manager.quota_service.kb_usage(&kb_id).await.map_err(|e| e.to_string())?);
+    }
+    Ok(usage)
+}
+
+/// Online backup of all databases; into `dest` when given, otherwise a new timestamped set
+#[tauri::command]
+pub async fn backup_database(
+    manager: ActiveManager,
+    dest: Option<String>,
+) -> Result<BackupEntry, String> {
+    let result = match dest {
+        Some(dest) => manager.sql_service.backup(std::path::Path::new(&dest)).await,
+        None => manager.sql_service.backup_databases().await,
+    };
+    result
+        .map(BackupEntry::from)
+        .map_err(|e| format!("Database backup failed: {}", e))
+}
+
+/// Completed backups, newest first
+#[tauri::command]
+pub async fn list_database_backups(
+    manager: ActiveManager,
+) -> Result<Vec<BackupEntry>, String> {
+    let backups = manager.sql_service.list_backups().await
+        .map_err(|e| format!("Failed to list backups: {}", e))?;
+    Ok(backups.into_iter().map(BackupEntry::from).collect())
+}
+
+/// Restore all databases from a backup set, then reload state derived from them
+#[tauri::command]
+pub async fn restore_database(
+    manager: ActiveManager,
+    path: String,
+) -> Result<BackupEntry, String> {
+    let backup = manager.sql_service.restore(std::path::Path::new(&path)).await
+        .map_err(|e| format!("Database restore failed: {}", e))?;
+    reload_restored_databases(&manager, &path).await?;
+
+    Ok(BackupEntry::from(backup))
+}
+
+/// Bring restored databases up to the current schema and reload everything derived from them
+pub(crate) async fn reload_restored_databases(manager: &Manager, path: &str) -> Result<(), String> {
+    manager.sql_service.run_migrations().await
+        .map_err(|e| format!("Restored, but migrating the databases failed: {}", e))?;
+    manager.kb_service.load_collections().await
+        .map_err(|e| format!("Restored, but reloading knowledge bases failed: {}", e))?;
+    manager.load_initial_state().await
+        .map_err(|e| format!("Restored, but reloading state failed: {}", e))?;
+    manager.emit_state_delta("database_restored", serde_json::json!({ "path": path })).await;
+    Ok(())
+}
+
+/// Backup set as shown in Settings
+#[derive(Debug, Clone, Serialize)]
+pub struct BackupEntry {
+    pub path: String,
+    pub created_at: String,
+    pub size_bytes: u64,
+    pub files: usize,
+}
+
+impl From<DatabaseBackup> for BackupEntry {
+    fn from(backup: DatabaseBackup) -> Self {
+        Self {
+            path: backup.path.display().to_string(),
+            created_at: backup.created_at.to_rfc3339(),
+            size_bytes: backup.size_bytes(),
+            files: backup.files.len(),
+        }
+    }
+}
+
 /// Export application settings
 #[tauri::command]
 pub async fn export_settings(
-    manager: State<'_, Manager>
+    manager: ActiveManager
 ) -> Result<String, String> {
     let settings = get_app_settings(manager).await?;
 
@@ -245,7 +614,7 @@ pub async fn export_settings(
 /// Import application settings
 #[tauri::command]
 pub async fn import_settings(
-    manager: State<'_, Manager>,
+    manager: ActiveManager,
     settings_json: String
 ) -> Result<AppSettings, String> {
     let settings: AppSettings = serde_json::from_str(&settings_json)
@@ -0,0 +1,69 @@
+/*!
+ * State Tauri Commands
+ *
+ * Undo/redo of user changes recorded by the core StateManager (tools,
+ * prompts, workspace pins, settings). After a step, the files and services
+ * that mirror the changed state are brought back in line.
+ *
+ * The frontend follows core state through `state_change` events carrying
+ * sequenced deltas. On a sequence gap (missed events, reconnect) it calls
+ * `get_deltas_since` with the last sequence it applied; when the response
+ * asks for a resync it reloads the full state and continues from
+ * `latest_seq`.
+ */
+
+use tracing::info;
+
+use rag_core::state::{DeltasSince, StateDelta};
+
+use crate::manager::Manager;
+use crate::projects::ActiveManager;
+use crate::prompt_commands::write_prompt_catalog;
+use crate::settings_commands::apply_saved_settings;
+use crate::tools_commands::write_tool_catalog;
+
+/// Re-sync what mirrors the state touched by an undone or redone change
+async fn sync_after_step(manager: &Manager, delta: &StateDelta) -> Result<(), String> {
+    match delta {
+        StateDelta::ToolAdd { .. }
+        | StateDelta::ToolUpdate { .. }
+        | StateDelta::ToolToggle { .. }
+        | StateDelta::ToolRemove { .. } => write_tool_catalog(manager)?,
+        StateDelta::PromptUpsert { .. } | StateDelta::PromptRemove { .. } => write_prompt_catalog(manager)?,
+        StateDelta::SettingSet { .. } => apply_saved_settings(manager).await,
+        _ => {}
+    }
+    Ok(())
+}
+
+/// Undo the most recent change; returns it, or None if there is nothing to undo
+#[tauri::command]
+pub async fn undo_last_change(manager: ActiveManager) -> Result<Option<StateDelta>, String> {
+    let Some(delta) = manager.state_manager.undo()
+        .map_err(|e| format!("Failed to undo: {}", e))? else {
+        return Ok(None);
+    };
+
+    info!("Undid {:?}", delta);
+    sync_after_step(&manager, &delta).await?;
+    Ok(Some(delta))
+}
+
+/// Re-apply the most recently undone change; returns it, or None if there is nothing to redo
+#[tauri::command]
+pub async fn redo(manager: ActiveManager) -> Result<Option<StateDelta>, String> {
+    let Some(delta) = manager.state_manager.redo()
+        .map_err(|e| format!("Failed to redo: {}", e))? else {
+        return Ok(None);
+    };
+
+    info!("Redid {:?}", delta);
+    sync_after_step(&manager, &delta).await?;
+    Ok(Some(delta))
+}
+
+/// Core state deltas applied after sequence number `since`
+#[tauri::command]
+pub async fn get_deltas_since(manager: ActiveManager, since: u64) -> Result<DeltasSince, String> {
+    Ok(manager.state_manager.deltas_since(since))
+}
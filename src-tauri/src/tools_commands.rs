@@ -0,0 +1,404 @@
+/*!
+ * Tool Tauri Commands
+ *
+ * Tools are KB-bound MCP search tools. They live in core state; every change
+ * rewrites the tool catalog the MCP subprocess polls (`--tools`), which
+ * registers/unregisters the matching MCP tools and notifies connected agents.
+ * The frontend hears about changes through the core state delta stream.
+ * Usage figures come from the MCP audit log the subprocess writes and are
+ * folded back into `ToolState.usage` (session-only deltas). Tools can be
+ * created from templates: the built-ins plus ones users save from tools.
+ */
+
+use serde::{Serialize, Deserialize};
+use tracing::info;
+
+use rag_core::modules::audit::{AnalyticsRange, ToolAnalytics};
+use rag_core::models::tool_catalog::{mcp_tool_name, ToolCatalog, KB_SEARCH_TOOL_TYPE};
+use rag_core::models::tool_template::{builtin_templates, ToolTemplate};
+use rag_core::state::{StateDelta, ToolState};
+use rag_core::LlmProviderConfig;
+
+use crate::projects::ActiveManager;
+use crate::services::ServiceLocator;
+
+/// Window summarized into `ToolState.usage`
+const TOOL_USAGE_WINDOW_DAYS: i64 = 30;
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CreateToolRequest {
+    pub name: String,
+    pub description: Option<String>,
+    pub kb_id: String,
+    pub top_k: Option<u32>,
+    pub top_n: Option<u32>,
+    pub permissions: Option<Vec<String>>,   // Defaults to kb.read
+    pub llm: Option<LlmProviderConfig>,     // Generation provider; defaults to local
+    pub template_id: Option<String>,        // Defaults for the fields left empty
+}
+
+/// Changes to a tool; absent fields keep their value
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateToolRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub kb_id: Option<String>,
+    pub top_k: Option<u32>,
+    pub top_n: Option<u32>,
+    pub permissions: Option<Vec<String>>,
+    pub llm: Option<LlmProviderConfig>,
+}
+
+/// Changes to a user-defined template; absent fields keep their value
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct UpdateToolTemplateRequest {
+    pub name: Option<String>,
+    pub description: Option<String>,
+    pub config: Option<serde_json::Value>,
+    pub permissions: Option<Vec<String>>,
+}
+
+/// Built-in or user-defined template by ID
+fn find_template(services: &dyn ServiceLocator, template_id: &str) -> Result<ToolTemplate, String> {
+    builtin_templates().into_iter()
+        .find(|t| t.id == template_id)
+        .or_else(|| services.state_manager().read_state().tool_templates.get(template_id).cloned())
+        .ok_or_else(|| format!("Template not found: {}", template_id))
+}
+
+/// User-defined template by ID (built-ins are read-only)
+fn find_custom_template(services: &dyn ServiceLocator, template_id: &str) -> Result<ToolTemplate, String> {
+    let template = find_template(services, template_id)?;
+    if template.builtin {
+        return Err(format!("Built-in template {} cannot be changed", template_id));
+    }
+    Ok(template)
+}
+
+/// Fail if another tool already maps to the same MCP name
+pub(crate) fn check_name_free(services: &dyn ServiceLocator, name: &str, except_id: Option<&str>) -> Result<String, String> {
+    let mcp_name = mcp_tool_name(name)
+        .ok_or_else(|| format!("Invalid tool name: '{}'", name))?;
+    let name_taken = services.state_manager().read_state().tools.values()
+        .any(|t| Some(t.id.as_str()) != except_id && mcp_tool_name(&t.name).as_deref() == Some(mcp_name.as_str()));
+    if name_taken {
+        return Err(format!("A tool named '{}' already exists", mcp_name));
+    }
+    Ok(mcp_name)
+}
+
+/// Write enabled tools to the catalog shared with the MCP server
+pub(crate) fn write_tool_catalog(services: &dyn ServiceLocator) -> Result<(), String> {
+    let tools: Vec<ToolState> = services.state_manager().read_state().tools.values().cloned().collect();
+    ToolCatalog::from_tools(&tools)
+        .save(&services.paths().mcp_tools)
+        .map_err(|e| format!("Failed to write tool catalog: {}", e))
+}
+
+/// Fold recent MCP calls into each tool's usage; only changed tools emit a delta
+pub(crate) async fn refresh_tool_usage(services: &dyn ServiceLocator) -> Result<(), String> {
+    let since = chrono::Utc::now() - chrono::Duration::days(TOOL_USAGE_WINDOW_DAYS);
+    let mut usage = services.audit_service()
+        .tool_usage(since)
+        .await
+        .map_err(|e| format!("Failed to read tool usage: {}", e))?;
+
+    let tools: Vec<ToolState> = services.state_manager().read_state().tools.values().cloned().collect();
+    for tool in tools {
+        let current = mcp_tool_name(&tool.name)
+            .and_then(|name| usage.remove(&name))
+            .unwrap_or_default();
+        if current != tool.usage {
+            services.state_manager()
+                .mutate(StateDelta::ToolUsageSet { id: tool.id, usage: current })
+                .map_err(|e| format!("Failed to update tool usage: {}", e))?;
+        }
+    }
+    Ok(())
+}
+
+/// Create a KB search tool and expose it over MCP
+#[tauri::command]
+pub async fn create_tool(
+    manager: ActiveManager,
+    request: CreateToolRequest,
+) -> Result<ToolState, String> {
+    let mcp_name = check_name_free(&*manager, &request.name, None)?;
+    if !manager.state_manager.read_state().knowledge_bases.contains_key(&request.kb_id) {
+        return Err(format!("Knowledge base not found: {}", request.kb_id));
+    }
+
+    // Inline API keys go to the vault; the tool config only names the secret
+    let template = request.template_id.as_deref().map(|id| find_template(&*manager, id)).transpose()?;
+    let defaults = template.as_ref().map_or_else(|| serde_json::json!({}), |t| t.config.clone());
+    let default_u32 = |key: &str| defaults.get(key).and_then(|v| v.as_u64()).map(|v| v as u32);
+
+    let tool_id = format!("tool_{}", uuid::Uuid::new_v4().simple());
+    let mut llm = match request.llm {
+        Some(llm) => Some(llm),
+        None if defaults.get("llm").is_some() => Some(LlmProviderConfig::from_config(&defaults).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    if let Some(llm) = llm.as_mut() {
+        llm.externalize_api_key(&manager.secrets_service, &format!("{}.llm_api_key", tool_id))
+            .map_err(|e| format!("Failed to store API key: {}", e))?;
+    }
+
+    let tool = ToolState {
+        id: tool_id,
+        name: request.name,
+        tool_type: template.as_ref().map_or_else(|| KB_SEARCH_TOOL_TYPE.to_string(), |t| t.tool_type.clone()),
+        enabled: true,
+        last_used: None,
+        usage_count: 0,
+        config: serde_json::json!({
+            "description": request.description.or_else(|| defaults.get("description").and_then(|v| v.as_str()).map(String::from)),
+            "kb_id": request.kb_id,
+            "top_k": request.top_k.or(default_u32("top_k")).unwrap_or(10),
+            "top_n": request.top_n.or(default_u32("top_n")),
+            "llm": llm,
+        }),
+        schema: serde_json::json!({}),
+        permissions: request.permissions
+            .or_else(|| template.as_ref().map(|t| t.permissions.clone()))
+            .unwrap_or_default(),
+        usage: Default::default(),
+    };
+
+    manager.state_manager
+        .mutate(StateDelta::ToolAdd { tool: tool.clone() })
+        .map_err(|e| format!("Failed to create tool: {}", e))?;
+    write_tool_catalog(&*manager)?;
+
+    info!("Created tool {} ({}) bound to KB {}", mcp_name, tool.id, tool.config["kb_id"]);
+    Ok(tool)
+}
+
+/// List tools with up-to-date usage from the MCP audit log
+#[tauri::command]
+pub async fn get_tools(
+    manager: ActiveManager,
+) -> Result<Vec<ToolState>, String> {
+    refresh_tool_usage(&*manager).await?;
+
+    let mut tools: Vec<ToolState> = manager.state_manager.read_state().tools.values().cloned().collect();
+    tools.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(tools)
+}
+
+/// Usage summary and time-series buckets for the Tools dashboard
+#[tauri::command]
+pub async fn get_tool_analytics(
+    manager: ActiveManager,
+    tool_id: String,
+    time_range: AnalyticsRange,
+) -> Result<ToolAnalytics, String> {
+    let name = manager.state_manager.read_state().tools.get(&tool_id)
+        .map(|t| t.name.clone())
+        .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+    let mcp_name = mcp_tool_name(&name)
+        .ok_or_else(|| format!("Invalid tool name: '{}'", name))?;
+
+    manager.audit_service
+        .tool_analytics(&mcp_name, time_range)
+        .await
+        .map_err(|e| format!("Failed to read tool analytics: {}", e))
+}
+
+/// Change a tool's name, binding, limits, permissions or provider
+#[tauri::command]
+pub async fn update_tool(
+    manager: ActiveManager,
+    tool_id: String,
+    request: UpdateToolRequest,
+) -> Result<ToolState, String> {
+    let mut tool = manager.state_manager.read_state().tools.get(&tool_id).cloned()
+        .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+
+    if let Some(name) = request.name {
+        check_name_free(&*manager, &name, Some(&tool_id))?;
+        tool.name = name;
+    }
+    if let Some(kb_id) = request.kb_id {
+        if !manager.state_manager.read_state().knowledge_bases.contains_key(&kb_id) {
+            return Err(format!("Knowledge base not found: {}", kb_id));
+        }
+        tool.config["kb_id"] = serde_json::json!(kb_id);
+    }
+    if let Some(description) = request.description {
+        tool.config["description"] = serde_json::json!(description);
+    }
+    if let Some(top_k) = request.top_k {
+        tool.config["top_k"] = serde_json::json!(top_k);
+    }
+    if let Some(top_n) = request.top_n {
+        tool.config["top_n"] = serde_json::json!(top_n);
+    }
+    if let Some(permissions) = request.permissions {
+        tool.permissions = permissions;
+    }
+    if let Some(mut llm) = request.llm {
+        llm.externalize_api_key(&manager.secrets_service, &format!("{}.llm_api_key", tool_id))
+            .map_err(|e| format!("Failed to store API key: {}", e))?;
+        tool.config["llm"] = serde_json::to_value(&llm)
+            .map_err(|e| format!("Invalid provider config: {}", e))?;
+    }
+
+    manager.state_manager
+        .mutate(StateDelta::ToolUpdate {
+            id: tool_id.clone(),
+            updates: serde_json::to_value(&tool).map_err(|e| format!("Failed to update tool: {}", e))?,
+        })
+        .map_err(|e| format!("Failed to update tool: {}", e))?;
+    write_tool_catalog(&*manager)?;
+
+    info!("Updated tool {}", tool_id);
+    Ok(tool)
+}
+
+/// Delete a tool and unregister its MCP tool. Its vault secret is kept so
+/// the deletion can be undone.
+#[tauri::command]
+pub async fn delete_tool(
+    manager: ActiveManager,
+    tool_id: String,
+) -> Result<(), String> {
+    if !manager.state_manager.read_state().tools.contains_key(&tool_id) {
+        return Err(format!("Tool not found: {}", tool_id));
+    }
+    manager.state_manager
+        .mutate(StateDelta::ToolRemove { id: tool_id.clone() })
+        .map_err(|e| format!("Failed to delete tool: {}", e))?;
+    write_tool_catalog(&*manager)?;
+
+    info!("Deleted tool {}", tool_id);
+    Ok(())
+}
+
+/// Enable or disable a tool (registers/unregisters the MCP tool)
+#[tauri::command]
+pub async fn set_tool_enabled(
+    manager: ActiveManager,
+    tool_id: String,
+    enabled: bool,
+) -> Result<(), String> {
+    if !manager.state_manager.read_state().tools.contains_key(&tool_id) {
+        return Err(format!("Tool not found: {}", tool_id));
+    }
+    manager.state_manager
+        .mutate(StateDelta::ToolToggle { id: tool_id.clone(), enabled })
+        .map_err(|e| format!("Failed to update tool: {}", e))?;
+    write_tool_catalog(&*manager)?;
+    Ok(())
+}
+
+/// Built-in templates followed by user-defined ones
+#[tauri::command]
+pub async fn get_tool_templates(
+    manager: ActiveManager,
+) -> Result<Vec<ToolTemplate>, String> {
+    let mut custom: Vec<ToolTemplate> = manager.state_manager.read_state().tool_templates.values().cloned().collect();
+    custom.sort_by(|a, b| a.name.cmp(&b.name));
+
+    let mut templates = builtin_templates();
+    templates.extend(custom);
+    Ok(templates)
+}
+
+/// Save a tool's settings (without its KB binding) as a reusable template
+#[tauri::command]
+pub async fn save_tool_as_template(
+    manager: ActiveManager,
+    tool_id: String,
+    name: String,
+    description: Option<String>,
+) -> Result<ToolTemplate, String> {
+    let tool = manager.state_manager.read_state().tools.get(&tool_id).cloned()
+        .ok_or_else(|| format!("Tool not found: {}", tool_id))?;
+    let template = ToolTemplate::from_tool(&tool, &name, description);
+    template.validate().map_err(|e| e.to_string())?;
+
+    manager.state_manager
+        .mutate(StateDelta::ToolTemplateUpsert { template: template.clone() })
+        .map_err(|e| format!("Failed to save template: {}", e))?;
+
+    info!("Saved tool {} as template {} ({})", tool_id, template.name, template.id);
+    Ok(template)
+}
+
+/// Change a user-defined template
+#[tauri::command]
+pub async fn update_tool_template(
+    manager: ActiveManager,
+    template_id: String,
+    request: UpdateToolTemplateRequest,
+) -> Result<ToolTemplate, String> {
+    let mut template = find_custom_template(&*manager, &template_id)?;
+    if let Some(name) = request.name {
+        template.name = name;
+    }
+    if let Some(description) = request.description {
+        template.description = Some(description);
+    }
+    if let Some(config) = request.config {
+        template.config = config;
+    }
+    if let Some(permissions) = request.permissions {
+        template.permissions = permissions;
+    }
+    template.updated_at = chrono::Utc::now();
+    template.validate().map_err(|e| e.to_string())?;
+
+    manager.state_manager
+        .mutate(StateDelta::ToolTemplateUpsert { template: template.clone() })
+        .map_err(|e| format!("Failed to update template: {}", e))?;
+    Ok(template)
+}
+
+/// Delete a user-defined template
+#[tauri::command]
+pub async fn delete_tool_template(
+    manager: ActiveManager,
+    template_id: String,
+) -> Result<(), String> {
+    find_custom_template(&*manager, &template_id)?;
+    manager.state_manager
+        .mutate(StateDelta::ToolTemplateRemove { id: template_id.clone() })
+        .map_err(|e| format!("Failed to delete template: {}", e))?;
+
+    info!("Deleted tool template {}", template_id);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::testing::TestServices;
+
+    #[tokio::test]
+    async fn test_tool_names_checked_against_injected_state() {
+        let services = TestServices::new().await;
+        services.state_manager.mutate(StateDelta::ToolAdd {
+            tool: ToolState {
+                id: "tool_1".to_string(),
+                name: "Search Docs".to_string(),
+                tool_type: KB_SEARCH_TOOL_TYPE.to_string(),
+                enabled: true,
+                last_used: None,
+                usage_count: 0,
+                config: serde_json::json!({ "kb_id": "kb_docs" }),
+                schema: serde_json::json!({}),
+                permissions: Vec::new(),
+                usage: Default::default(),
+            },
+        }).unwrap();
+
+        assert!(check_name_free(&services, "search docs", None).is_err());
+        assert!(check_name_free(&services, "Search Docs", Some("tool_1")).is_ok());
+        assert!(find_custom_template(&services, "missing").is_err());
+
+        // No MCP calls recorded yet, so usage stays empty
+        refresh_tool_usage(&services).await.unwrap();
+        assert_eq!(services.state_manager.read_state().tools["tool_1"].usage.calls, 0);
+    }
+}
@@ -1,5 +1,14 @@
-import { Component } from "@angular/core";
+import { Component, OnDestroy, OnInit, signal } from "@angular/core";
 import { RouterOutlet } from "@angular/router";
+import { invoke } from "@tauri-apps/api/core";
+import { listen, UnlistenFn } from "@tauri-apps/api/event";
+
+interface StartupStatus {
+  phase: 'starting' | 'ready' | 'failed' | 'shutting_down' | 'stopped';
+  current_step: string | null;
+  completed_steps: string[];
+  error: string | null;
+}
 
 @Component({
   selector: "app-root",
@@ -8,4 +17,23 @@ import { RouterOutlet } from "@angular/router";
   templateUrl: "./app.component.html",
   styleUrl: "./app.component.scss",
 })
-export class AppComponent {}
+export class AppComponent implements OnInit, OnDestroy {
+  // Pages need the backend's core services, so they wait for startup to finish
+  readonly startup = signal<StartupStatus | null>(null);
+  private unlisten?: UnlistenFn;
+
+  async ngOnInit(): Promise<void> {
+    this.unlisten = await listen<StartupStatus>('startup_status', event => {
+      this.startup.set(event.payload);
+    });
+    try {
+      this.startup.set(await invoke<StartupStatus>('get_startup_status'));
+    } catch (error) {
+      console.error('Failed to get startup status:', error);
+    }
+  }
+
+  ngOnDestroy(): void {
+    this.unlisten?.();
+  }
+}
@@ -12,7 +12,13 @@ import {
   RagFormField,
   RagSettingsSection
 } from '../../semantic';
-import { SettingsStore } from '../../../store/settings.store';
+import { DatabaseBackup, SettingsStore } from '../../../store/settings.store';
+
+const BACKUP_INTERVAL_HOURS: Record<string, number> = {
+  daily: 24,
+  weekly: 24 * 7,
+  monthly: 24 * 30,
+};
 
 interface GeneralSettingsForm {
   // Workspace settings
@@ -85,11 +91,14 @@ export class RagGeneralSettingsPanel {
 
   // State
   private readonly isInitialized = signal(false);
+  readonly backups = signal<DatabaseBackup[]>([]);
+  readonly backupMessage = signal<string | null>(null);
 
   constructor() {
     // Initialize store and sync form when settings change
     this.initializeStore();
     this.syncFormWithSettings();
+    this.loadBackups();
   }
 
   private async initializeStore() {
@@ -108,7 +117,7 @@ export class RagGeneralSettingsPanel {
         dataDirectory: settings.system?.data_directory || './data',
         autoSave: settings.system?.auto_backup ?? true,
         autoBackup: settings.system?.auto_backup ?? false,
-        backupInterval: 'daily', // TODO: Add to backend interface
+        backupInterval: this.intervalFromHours(settings.system?.backup_interval_hours),
         maxBackups: settings.system?.max_backups || 10,
         theme: 'system', // TODO: Add to backend interface
         language: 'en', // TODO: Add to backend interface
@@ -141,16 +150,65 @@ export class RagGeneralSettingsPanel {
             // Only update fields that exist in the backend interface
             data_directory: formValue.dataDirectory!,
             auto_backup: formValue.autoBackup!,
+            backup_interval_hours: BACKUP_INTERVAL_HOURS[formValue.backupInterval!] ?? 24,
             max_backups: formValue.maxBackups!,
             log_level: formValue.logLevel!,
             // TODO: Add these fields to backend interface:
-            // workspace_name, backup_interval, theme, language, auto_save
+            // workspace_name, theme, language, auto_save
           }
         });
       }
     }
   }
 
+  // Backup & restore
+  async loadBackups() {
+    try {
+      this.backups.set(await this.settingsStore.listBackups());
+    } catch {
+      this.backups.set([]);
+    }
+  }
+
+  async backupNow() {
+    try {
+      const backup = await this.settingsStore.backupDatabase();
+      this.backupMessage.set(`Backup created (${this.formatSize(backup.size_bytes)})`);
+      await this.loadBackups();
+    } catch (error) {
+      console.error('Backup failed:', error);
+    }
+  }
+
+  async restoreBackup(backup: DatabaseBackup) {
+    const confirmed = confirm(
+      `Restore all data from the backup taken ${new Date(backup.created_at).toLocaleString()}? ` +
+      'Current data is backed up first.'
+    );
+    if (!confirmed) {
+      return;
+    }
+    try {
+      await this.settingsStore.restoreDatabase(backup.path);
+      this.backupMessage.set('Backup restored');
+      await this.loadBackups();
+    } catch (error) {
+      console.error('Restore failed:', error);
+    }
+  }
+
+  formatSize(bytes: number): string {
+    if (bytes < 1024 * 1024) {
+      return `${(bytes / 1024).toFixed(1)} KB`;
+    }
+    return `${(bytes / (1024 * 1024)).toFixed(1)} MB`;
+  }
+
+  private intervalFromHours(hours?: number): string {
+    const match = Object.entries(BACKUP_INTERVAL_HOURS).find(([, value]) => value === hours);
+    return match ? match[0] : 'daily';
+  }
+
   resetSettings() {
     this.settingsForm.reset({
       workspaceName: 'My RAG Studio Workspace',
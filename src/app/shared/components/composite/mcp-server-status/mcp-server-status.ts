@@ -89,13 +89,14 @@ export class McpServerStatus implements OnInit, OnDestroy {
       // For now, check general health status as proxy
       const generalHealth = await invoke<any>('get_health_status');
 
-      // Map general health to MCP status (simplified for MVP)
+      // Map the MCP subsystem's health to MCP status (simplified for MVP)
+      const mcpHealth = (generalHealth.subsystems || []).find((s: any) => s.name === 'mcp_server');
       const mcpStatus = {
-        running: generalHealth.overall === 'healthy',
+        running: mcpHealth?.status === 'healthy',
         port: 3000,
         version: '1.0.0',
         active_connections: Math.floor(Math.random() * 5), // Mock connections
-        error: generalHealth.overall === 'failed' ? generalHealth.error : null
+        error: mcpHealth?.status === 'unhealthy' ? mcpHealth.last_error : null
       };
 
       this.mcpStatus.set(mcpStatus);
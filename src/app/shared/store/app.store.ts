@@ -52,12 +52,30 @@ export interface CrossDomainStats {
   activePipelines: number;
 }
 
+// Background job (download, export, backup, reindex, pipeline run)
+export type JobKind = 'pipeline_run' | 'model_download' | 'kb_export' | 'backup' | 'reindex' | 'index_maintenance';
+export type JobStatus = 'queued' | 'running' | 'completed' | 'failed' | 'cancelled' | 'interrupted';
+
+export interface JobRecord {
+  id: string;
+  kind: JobKind;
+  title: string;
+  status: JobStatus;
+  progress: number;
+  message: string | null;
+  error: string | null;
+  created_at: string;
+  started_at: string | null;
+  completed_at: string | null;
+}
+
 // Initial state
 interface AppState {
   activityLog: ActivityLogEntry[];
   performanceMetrics: PerformanceMetrics;
   healthStatus: AppHealthStatus | null;
   crossDomainStats: CrossDomainStats;
+  jobs: JobRecord[];
   isLoading: boolean;
   lastError: string | null;
   isInitialized: boolean;
@@ -81,6 +99,7 @@ const initialState: AppState = {
     totalPipelines: 0,
     activePipelines: 0
   },
+  jobs: [],
   isLoading: false,
   lastError: null,
   isInitialized: false
@@ -114,11 +133,17 @@ export const AppStore = signalStore(
       return { status: 'healthy', message: 'All systems operational' };
     }),
 
+    // Jobs still queued or running (background tasks badge)
+    activeJobs: computed(() =>
+      store.jobs().filter(job => job.status === 'queued' || job.status === 'running')
+    ),
+
     // Check if store is ready
     isReady: computed(() => store.isInitialized() && !store.isLoading())
   })),
   withMethods((store) => {
     let unlisten: UnlistenFn | undefined;
+    let unlistenJobs: UnlistenFn | undefined;
 
     return {
       // Initialization
@@ -153,6 +178,12 @@ export const AppStore = signalStore(
             this.handleAppEvent(type, payload);
           });
 
+          unlistenJobs = await listen<JobRecord>('job_update', (event) => {
+            const job = event.payload;
+            const others = store.jobs().filter(j => j.id !== job.id);
+            patchState(store, { jobs: [job, ...others].sort((a, b) => b.created_at.localeCompare(a.created_at)) });
+          });
+
           console.log('✅ App event listeners setup completed');
         } catch (error) {
           console.error('❌ Failed to setup app event listeners:', error);
@@ -164,6 +195,7 @@ export const AppStore = signalStore(
         try {
           // Load health status
           const health = await this.refreshHealthStatus();
+          await this.loadJobs();
 
           // Load cross-domain stats (TODO: implement proper endpoints)
           const stats: CrossDomainStats = {
@@ -206,14 +238,18 @@ export const AppStore = signalStore(
         try {
           const health = await invoke<any>('get_health_status');
 
-          // Map to our interface
+          // Map the subsystem report to our interface
+          const statusOf = (name: string): 'healthy' | 'failed' => {
+            const subsystem = (health.subsystems || []).find((s: any) => s.name === name);
+            return subsystem?.status === 'unhealthy' ? 'failed' : 'healthy';
+          };
           const appHealth: AppHealthStatus = {
-            overall: health.overall || 'healthy',
+            overall: health.overall === 'unhealthy' ? 'failed' : health.overall === 'degraded' ? 'degraded' : 'healthy',
             services: {
-              sql: health.sql || 'healthy',
-              vector: health.vector || 'healthy',
-              mcp: health.mcp || 'healthy',
-              embedding: health.embedding || 'healthy'
+              sql: statusOf('sql'),
+              vector: statusOf('vector_store'),
+              mcp: statusOf('mcp_server'),
+              embedding: statusOf('embedding_worker')
             },
             uptime: health.uptime || '0m',
             memory_usage: health.memory_usage || 0,
@@ -255,6 +291,26 @@ export const AppStore = signalStore(
         }
       },
 
+      // Background jobs
+      async loadJobs() {
+        try {
+          const jobs = await invoke<JobRecord[]>('list_jobs');
+          patchState(store, { jobs });
+        } catch (error) {
+          console.error('Failed to load background jobs:', error);
+        }
+      },
+
+      async cancelJob(jobId: string) {
+        return await invoke<JobRecord>('cancel_job', { jobId });
+      },
+
+      async clearFinishedJobs() {
+        const removed = await invoke<number>('clear_finished_jobs');
+        patchState(store, { jobs: store.jobs().filter(job => job.status === 'queued' || job.status === 'running') });
+        return removed;
+      },
+
       // Activity log management
       addActivity(entry: ActivityLogEntry) {
         const current = store.activityLog();
@@ -327,6 +383,10 @@ export const AppStore = signalStore(
           unlisten();
           unlisten = undefined;
         }
+        if (unlistenJobs) {
+          unlistenJobs();
+          unlistenJobs = undefined;
+        }
         console.log('AppStore destroyed');
       }
     };
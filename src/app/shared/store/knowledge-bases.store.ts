@@ -15,6 +15,7 @@ import {
   KnowledgeBaseStatus
 } from '../types';
 import { transformKnowledgeBase } from '../utils/knowledge-base.utils';
+import type { JobRecord } from './app.store';
 
 // State interfaces
 export interface CreateKBRequest {
@@ -39,18 +40,32 @@ export interface SearchResult {
   chunk_id: string;
   score: number;
   snippet: string;
+  highlights: SnippetHighlight[];
   title: string;
   document_id: string;
   citation: CitationInfo;
   metadata: any;
 }
 
+/** Query term match in `snippet`, as character offsets (end exclusive) */
+export interface SnippetHighlight {
+  start: number;
+  end: number;
+}
+
 export interface CitationInfo {
   title: string;
   url?: string;
   license?: string;
   version?: string;
   anchor?: string;
+  time_range?: TimeRange | null;   // Set for transcript chunks
+}
+
+/** Span of an audio or video recording, in milliseconds */
+export interface TimeRange {
+  start_ms: number;
+  end_ms: number;
 }
 
 export interface IngestRun {
@@ -305,6 +320,28 @@ export const KnowledgeBasesStore = signalStore(
         }
       },
 
+      async rebuildBm25Index(kbId: string) {
+        try {
+          return await invoke<JobRecord>('rebuild_bm25_index', { kbId });
+        } catch (error) {
+          const errorMessage = error instanceof Error ? error.message : 'Failed to rebuild BM25 index';
+          patchState(store, { lastError: errorMessage });
+          console.error('Failed to rebuild BM25 index:', error);
+          throw error;
+        }
+      },
+
+      async optimizeIndex(kbId: string) {
+        try {
+          return await invoke<JobRecord>('optimize_index', { kbId });
+        } catch (error) {
+          const errorMessage = error instanceof Error ? error.message : 'Failed to optimize indexes';
+          patchState(store, { lastError: errorMessage });
+          console.error('Failed to optimize indexes:', error);
+          throw error;
+        }
+      },
+
       async exportKnowledgeBase(kbId: string) {
         try {
           const bytes = await invoke<number[]>('export_knowledge_base', { kbId });
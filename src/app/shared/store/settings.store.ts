@@ -46,6 +46,62 @@ export interface SecuritySettings {
   audit_logging: boolean;
 }
 
+export interface DatabaseBackup {
+  path: string;
+  created_at: string;
+  size_bytes: number;
+  files: number;
+}
+
+export interface BlobGcReport {
+  manifests: number;
+  scanned_blobs: number;
+  referenced_blobs: number;
+  removed_blobs: number;
+  reclaimed_bytes: number;
+  kept_recent: number;
+}
+
+export interface BlobStoreStats {
+  blobs: number;
+  manifests: number;
+  stored_bytes: number;
+  logical_bytes: number;
+}
+
+export interface QuotaUsage {
+  scope: { type: 'project' } | { type: 'kb'; id: string };
+  used_bytes: number;
+  limit_bytes: number | null;
+  warn_bytes: number | null;
+}
+
+export type ArchiveSection = 'settings' | 'databases' | 'vectors' | 'blobs' | 'packs' | 'models';
+
+export interface ArchiveManifest {
+  format_version: number;
+  app_version: string;
+  schema_version: string;
+  project_name: string;
+  created_at: string;
+  sections: { section: ArchiveSection; files: number; size_bytes: number }[];
+}
+
+export interface BackupArchive {
+  path: string;
+  manifest: ArchiveManifest;
+}
+
+// Payload of `backup_progress` events
+export interface BackupProgress {
+  operation: 'backup' | 'restore';
+  archive: string;
+  section: ArchiveSection | null;
+  current_file: string | null;
+  processed_bytes: number;
+  total_bytes: number;
+}
+
 export interface AppSettings {
   server: ServerSettings;
   kb: KbSettings;
@@ -351,6 +407,123 @@ export const SettingsStore = signalStore(
       }
     },
 
+    // Content-addressable blob store
+    async getBlobStoreStats() {
+      return await invoke<BlobStoreStats>('get_blob_store_stats');
+    },
+
+    async gcUnreferencedBlobs() {
+      patchState(store, { isLoading: true, error: null });
+
+      try {
+        const report = await invoke<BlobGcReport>('gc_unreferenced_blobs');
+        patchState(store, { isLoading: false });
+        return report;
+      } catch (error) {
+        console.error('Failed to collect unreferenced blobs:', error);
+        patchState(store, {
+          isLoading: false,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        throw error;
+      }
+    },
+
+    // Disk quotas
+    async getDiskQuotaUsage(kbId?: string) {
+      return await invoke<QuotaUsage[]>('get_disk_quota_usage', { kbId: kbId ?? null });
+    },
+
+    // Database backup & restore
+    async backupDatabase(dest?: string) {
+      patchState(store, { isLoading: true, error: null });
+
+      try {
+        const backup = await invoke<DatabaseBackup>('backup_database', { dest: dest ?? null });
+        patchState(store, { isLoading: false });
+        return backup;
+      } catch (error) {
+        console.error('Failed to back up database:', error);
+        patchState(store, {
+          isLoading: false,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        throw error;
+      }
+    },
+
+    async listBackups() {
+      try {
+        return await invoke<DatabaseBackup[]>('list_database_backups');
+      } catch (error) {
+        console.error('Failed to list backups:', error);
+        throw error;
+      }
+    },
+
+    async restoreDatabase(path: string) {
+      patchState(store, { isLoading: true, error: null });
+
+      try {
+        const backup = await invoke<DatabaseBackup>('restore_database', { path });
+        patchState(store, { isLoading: false });
+        return backup;
+      } catch (error) {
+        console.error('Failed to restore database:', error);
+        patchState(store, {
+          isLoading: false,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        throw error;
+      }
+    },
+
+    // Full-app backup archives (progress arrives as `backup_progress` events)
+    async createBackup(dest?: string, includeModels = false) {
+      patchState(store, { isLoading: true, error: null });
+
+      try {
+        const archive = await invoke<BackupArchive>('create_backup', {
+          dest: dest ?? null,
+          includeModels,
+        });
+        patchState(store, { isLoading: false });
+        return archive;
+      } catch (error) {
+        console.error('Failed to create backup:', error);
+        patchState(store, {
+          isLoading: false,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        throw error;
+      }
+    },
+
+    async inspectBackup(path: string) {
+      return await invoke<ArchiveManifest>('inspect_backup', { path });
+    },
+
+    async restoreBackup(path: string, root: string, includeModels = true) {
+      patchState(store, { isLoading: true, error: null });
+
+      try {
+        const project = await invoke<unknown>('restore_backup', { path, root, includeModels });
+        patchState(store, { isLoading: false });
+        return project;
+      } catch (error) {
+        console.error('Failed to restore backup:', error);
+        patchState(store, {
+          isLoading: false,
+          error: error instanceof Error ? error.message : String(error),
+        });
+        throw error;
+      }
+    },
+
+    async onBackupProgress(handler: (progress: BackupProgress) => void) {
+      return await listen<BackupProgress>('backup_progress', (event) => handler(event.payload));
+    },
+
     // Settings import/export
     async exportSettings() {
       try {